@@ -5,4 +5,7 @@
 
 pub mod analysis;
 pub mod ocr;
+pub mod redaction;
+pub mod searchable_pdf;
 pub mod services;
+pub mod tables;
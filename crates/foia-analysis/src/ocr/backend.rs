@@ -15,6 +15,7 @@ use thiserror::Error;
 
 use foia::http_client::HttpClient;
 use foia::privacy::PrivacyConfig;
+use foia::repository::diesel_document::OcrRunSettings;
 
 use super::model_utils::build_ocr_result;
 use super::pdf_utils;
@@ -57,6 +58,23 @@ pub struct OcrResult {
     pub model: Option<String>,
     /// Processing time in milliseconds.
     pub processing_time_ms: u64,
+    /// OCR tool version, for backends that report one.
+    pub tool_version: Option<String>,
+    /// OCR language, for backends that take one.
+    pub language: Option<String>,
+    /// Resolution PDF pages were rendered at before OCR.
+    pub dpi: Option<u32>,
+}
+
+impl OcrResult {
+    /// Tool settings to record alongside the result.
+    pub fn run_settings(&self) -> OcrRunSettings {
+        OcrRunSettings {
+            tool_version: self.tool_version.clone(),
+            language: self.language.clone(),
+            dpi: self.dpi,
+        }
+    }
 }
 
 /// Available OCR backend types.
@@ -126,6 +144,15 @@ pub trait OcrBackend: Send + Sync {
     /// Core OCR: extract text from an image file.
     fn run_ocr(&self, image_path: &Path) -> Result<String, OcrError>;
 
+    /// Extract text along with a confidence score (0.0 - 1.0), for backends
+    /// that report one.
+    fn run_ocr_with_confidence(
+        &self,
+        image_path: &Path,
+    ) -> Result<(String, Option<f32>), OcrError> {
+        self.run_ocr(image_path).map(|text| (text, None))
+    }
+
     /// Whether this backend sends work to a remote API rather than running locally.
     /// Deferred backends can run concurrently with local stages in deep mode.
     fn is_deferred(&self) -> bool {
@@ -137,30 +164,51 @@ pub trait OcrBackend: Send + Sync {
         None
     }
 
+    /// Version of the underlying OCR tool, if it reports one.
+    fn tool_version(&self) -> Option<String> {
+        None
+    }
+
+    /// OCR language, for backends that take one.
+    fn language(&self) -> Option<String> {
+        None
+    }
+
     /// Run OCR on an image file, returning a timed result.
     fn ocr_image(&self, image_path: &Path) -> Result<OcrResult, OcrError> {
         let start = Instant::now();
-        let text = self.run_ocr(image_path)?;
-        Ok(build_ocr_result(
-            text,
-            self.backend_type(),
-            self.model_name(),
-            start,
-        ))
+        let (text, confidence) = self.run_ocr_with_confidence(image_path)?;
+        Ok(OcrResult {
+            confidence,
+            tool_version: self.tool_version(),
+            language: self.language(),
+            ..build_ocr_result(text, self.backend_type(), self.model_name(), start)
+        })
     }
 
     /// Run OCR on a specific page of a PDF file.
     fn ocr_pdf_page(&self, pdf_path: &Path, page: u32) -> Result<OcrResult, OcrError> {
+        self.ocr_pdf_page_at_dpi(pdf_path, page, pdf_utils::OCR_DPI)
+    }
+
+    /// Run OCR on a specific page of a PDF file rendered at `dpi`.
+    fn ocr_pdf_page_at_dpi(
+        &self,
+        pdf_path: &Path,
+        page: u32,
+        dpi: u32,
+    ) -> Result<OcrResult, OcrError> {
         let start = Instant::now();
         let temp_dir = TempDir::new()?;
-        let image_path = pdf_utils::pdf_page_to_image(pdf_path, page, temp_dir.path())?;
-        let text = self.run_ocr(&image_path)?;
-        Ok(build_ocr_result(
-            text,
-            self.backend_type(),
-            self.model_name(),
-            start,
-        ))
+        let image_path = pdf_utils::pdf_page_to_image(pdf_path, page, dpi, temp_dir.path())?;
+        let (text, confidence) = self.run_ocr_with_confidence(&image_path)?;
+        Ok(OcrResult {
+            confidence,
+            tool_version: self.tool_version(),
+            language: self.language(),
+            dpi: Some(dpi),
+            ..build_ocr_result(text, self.backend_type(), self.model_name(), start)
+        })
     }
 }
 
@@ -4,10 +4,14 @@
 
 use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
 use tempfile::TempDir;
 use thiserror::Error;
 
 use super::model_utils::check_binary;
+#[cfg(feature = "pdfium")]
+use super::pdf_engine::{pdf_engine, PdfEngine};
+use super::pdf_utils::output_with_timeout;
 
 /// Handle command output, extracting stdout on success or returning appropriate error.
 fn handle_cmd_output(
@@ -307,6 +311,13 @@ impl TextExtractor {
         file_path: &Path,
         expected_pages: u32,
     ) -> Result<Vec<String>, ExtractionError> {
+        #[cfg(feature = "pdfium")]
+        if pdf_engine() == PdfEngine::Pdfium {
+            let pages = super::pdfium::page_texts(file_path, 1, expected_pages.max(1))
+                .map_err(|e| ExtractionError::ExtractionFailed(e.to_string()))?;
+            return Ok(pages.into_iter().map(Option::unwrap_or_default).collect());
+        }
+
         let output = Command::new("pdftotext")
             .args(["-layout", "-enc", "UTF-8"])
             .arg(file_path)
@@ -346,12 +357,99 @@ impl TextExtractor {
         Ok(pages)
     }
 
+    /// Extract the text of pages `first` to `last` of a PDF, one entry per
+    /// page, killing pdftotext after `timeout`. The range is read in one
+    /// call; if that fails or doesn't split into the right number of pages,
+    /// pages are read one at a time and any that fail come back as `None`.
+    pub fn extract_pdf_page_range_texts(
+        &self,
+        file_path: &Path,
+        first: u32,
+        last: u32,
+        timeout: Duration,
+    ) -> Vec<Option<String>> {
+        let expected = (last + 1).saturating_sub(first) as usize;
+
+        #[cfg(feature = "pdfium")]
+        if pdf_engine() == PdfEngine::Pdfium {
+            return super::pdfium::page_texts(file_path, first, last)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("pdfium failed on pages {}-{}: {}", first, last, e);
+                    vec![None; expected]
+                });
+        }
+
+        match self.run_pdftotext_range(file_path, first, last, timeout) {
+            Ok(text) => {
+                let mut pages: Vec<&str> = text.split('\x0C').collect();
+                // pdftotext ends each page with a form-feed
+                if pages.last().is_some_and(|s| s.trim().is_empty()) {
+                    pages.pop();
+                }
+                if pages.len() == expected {
+                    return pages.into_iter().map(|p| Some(p.to_string())).collect();
+                }
+                tracing::debug!(
+                    "pdftotext produced {} pages for pages {}-{}, falling back to per-page",
+                    pages.len(),
+                    first,
+                    last
+                );
+            }
+            Err(e) => {
+                tracing::debug!("pdftotext failed on pages {}-{}: {}", first, last, e);
+            }
+        }
+
+        (first..=last)
+            .map(|page| {
+                self.run_pdftotext_range(file_path, page, page, timeout)
+                    .map_err(|e| tracing::warn!("pdftotext failed on page {}: {}", page, e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Run pdftotext on a page range, killing it after `timeout`.
+    fn run_pdftotext_range(
+        &self,
+        file_path: &Path,
+        first: u32,
+        last: u32,
+        timeout: Duration,
+    ) -> Result<String, ExtractionError> {
+        let output = output_with_timeout(
+            Command::new("pdftotext")
+                .args(["-layout", "-enc", "UTF-8"])
+                .args(["-f", &first.to_string(), "-l", &last.to_string()])
+                .arg(file_path)
+                .arg("-"),
+            timeout,
+        );
+        handle_cmd_output(
+            output,
+            "pdftotext (install poppler-utils)",
+            &format!("pdftotext failed on pages {}-{}", first, last),
+        )
+    }
+
     /// Run pdftotext on a single page of a PDF file.
     pub fn extract_pdf_page_text(
         &self,
         file_path: &Path,
         page: u32,
     ) -> Result<String, ExtractionError> {
+        #[cfg(feature = "pdfium")]
+        if pdf_engine() == PdfEngine::Pdfium {
+            return super::pdfium::page_texts(file_path, page, page)
+                .map_err(|e| ExtractionError::ExtractionFailed(e.to_string()))?
+                .pop()
+                .flatten()
+                .ok_or_else(|| {
+                    ExtractionError::ExtractionFailed(format!("pdfium failed on page {}", page))
+                });
+        }
+
         let page_str = page.to_string();
         let output = Command::new("pdftotext")
             .args(["-layout", "-enc", "UTF-8", "-f", &page_str, "-l", &page_str])
@@ -368,6 +466,11 @@ impl TextExtractor {
 
     /// Get the page count of a PDF.
     pub fn get_pdf_page_count(&self, file_path: &Path) -> Option<u32> {
+        #[cfg(feature = "pdfium")]
+        if pdf_engine() == PdfEngine::Pdfium {
+            return super::pdfium::page_count(file_path).ok();
+        }
+
         let output = Command::new("pdfinfo").arg(file_path).output().ok()?;
 
         if !output.status.success() {
@@ -684,6 +787,24 @@ mod tests {
         assert_eq!(bulk_pages[1].trim(), page2.trim(), "page 2 should match");
     }
 
+    #[test]
+    fn test_extract_pdf_page_range_texts() {
+        if !check_binary("pdflatex") || !check_binary("pdftotext") {
+            eprintln!("Skipping: pdflatex or pdftotext not available");
+            return;
+        }
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let pdf_path = create_test_pdf(dir.path(), &["First", "Second", "Third", "Fourth"]);
+
+        let extractor = TextExtractor::new();
+        let pages =
+            extractor.extract_pdf_page_range_texts(&pdf_path, 2, 3, Duration::from_secs(30));
+        assert_eq!(pages.len(), 2);
+        assert!(pages[0].as_deref().unwrap().contains("Second"));
+        assert!(pages[1].as_deref().unwrap().contains("Third"));
+    }
+
     #[test]
     fn test_extract_all_single_page() {
         if !check_binary("pdflatex") || !check_binary("pdftotext") {
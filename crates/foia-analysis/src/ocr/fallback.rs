@@ -171,9 +171,7 @@ impl OcrBackend for FallbackOcrBackend {
     }
 
     fn is_deferred(&self) -> bool {
-        self.backends
-            .first()
-            .map_or(false, |b| b.is_deferred())
+        self.backends.first().map_or(false, |b| b.is_deferred())
     }
 
     fn is_available(&self) -> bool {
@@ -206,6 +204,15 @@ impl OcrBackend for FallbackOcrBackend {
     fn ocr_pdf_page(&self, pdf_path: &Path, page: u32) -> Result<OcrResult, OcrError> {
         self.run_with_fallback(|backend| backend.ocr_pdf_page(pdf_path, page))
     }
+
+    fn ocr_pdf_page_at_dpi(
+        &self,
+        pdf_path: &Path,
+        page: u32,
+        dpi: u32,
+    ) -> Result<OcrResult, OcrError> {
+        self.run_with_fallback(|backend| backend.ocr_pdf_page_at_dpi(pdf_path, page, dpi))
+    }
 }
 
 #[cfg(test)]
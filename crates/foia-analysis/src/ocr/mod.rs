@@ -1,7 +1,7 @@
 //! OCR and text extraction module.
 //!
 //! Extracts text from documents using:
-//! - pdftotext (Poppler) for PDF text extraction
+//! - pdftotext (Poppler) for PDF text extraction, or pdfium (feature: pdfium)
 //! - Tesseract OCR for image-based PDFs and image files (default)
 //! - OCRS for pure-Rust OCR (feature: ocr-ocrs)
 //! - PaddleOCR for CNN-based OCR via ONNX (feature: ocr-paddle)
@@ -40,13 +40,18 @@ mod fallback;
 mod gemini;
 mod groq;
 mod model_utils;
+mod pdf_engine;
 mod pdf_utils;
+mod pool;
+mod quality;
 mod tesseract;
 
 #[cfg(feature = "ocr-ocrs")]
 mod ocrs_backend;
 #[cfg(feature = "ocr-paddle")]
 mod paddle_backend;
+#[cfg(feature = "pdfium")]
+mod pdfium;
 
 pub use archive::ArchiveExtractor;
 pub use email::EmailExtractor;
@@ -61,9 +66,19 @@ pub use deepseek::DeepSeekBackend;
 pub use fallback::FallbackOcrBackend;
 pub use gemini::GeminiBackend;
 pub use groq::GroqBackend;
+pub(crate) use model_utils::check_binary;
+pub use pdf_engine::{configure_pdf_engine, pdf_engine, PdfEngine};
+pub use pdf_utils::{
+    compute_file_hash, pdf_page_to_image, render_page, render_pages, RenderedPage, OCR_DPI,
+    REMEDIATION_DPI,
+};
+pub use pool::{OcrPool, PoolSlot};
+pub use quality::quality_score;
 pub use tesseract::TesseractBackend;
 
 #[cfg(feature = "ocr-ocrs")]
 pub use ocrs_backend::OcrsBackend;
 #[cfg(feature = "ocr-paddle")]
 pub use paddle_backend::PaddleBackend;
+#[cfg(feature = "pdfium")]
+pub use pdfium::is_available as pdfium_available;
@@ -211,6 +211,9 @@ pub fn build_ocr_result(
         backend,
         model,
         processing_time_ms: start.elapsed().as_millis() as u64,
+        tool_version: None,
+        language: None,
+        dpi: None,
     }
 }
 
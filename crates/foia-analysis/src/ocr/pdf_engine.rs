@@ -0,0 +1,79 @@
+//! Choice of PDF engine: Poppler's command-line tools or pdfium.
+//!
+//! Page counting, text extraction and page rendering go through the engine
+//! chosen here. The choice is made once per process, from
+//! `analysis.pdf` in the config.
+
+use std::process::Command;
+use std::sync::OnceLock;
+
+use foia::config::{PdfConfig, PdfEngineKind};
+
+/// Engine that reads and renders PDFs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfEngine {
+    /// pdfinfo, pdftotext and pdftoppm.
+    Poppler,
+    /// The pdfium library.
+    #[cfg(feature = "pdfium")]
+    Pdfium,
+}
+
+impl PdfEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Poppler => "poppler",
+            #[cfg(feature = "pdfium")]
+            Self::Pdfium => "pdfium",
+        }
+    }
+}
+
+static ENGINE: OnceLock<PdfEngine> = OnceLock::new();
+
+/// Choose this process's PDF engine from `config`, returning the engine in
+/// use. Only the first call (or first PDF operation) decides.
+pub fn configure_pdf_engine(config: &PdfConfig) -> PdfEngine {
+    *ENGINE.get_or_init(|| {
+        let engine = resolve(config);
+        tracing::debug!("PDF engine: {}", engine.as_str());
+        engine
+    })
+}
+
+/// The PDF engine in use, chosen from the default config if
+/// [`configure_pdf_engine`] hasn't been called.
+pub fn pdf_engine() -> PdfEngine {
+    configure_pdf_engine(&PdfConfig::default())
+}
+
+fn resolve(config: &PdfConfig) -> PdfEngine {
+    #[cfg(feature = "pdfium")]
+    super::pdfium::set_library_dir(config.pdfium_library.as_ref().map(Into::into));
+
+    match config.engine {
+        PdfEngineKind::Poppler => PdfEngine::Poppler,
+        PdfEngineKind::Pdfium => pdfium_engine().unwrap_or_else(|| {
+            tracing::warn!("pdfium is not available; falling back to Poppler");
+            PdfEngine::Poppler
+        }),
+        PdfEngineKind::Auto if poppler_installed() => PdfEngine::Poppler,
+        PdfEngineKind::Auto => pdfium_engine().unwrap_or(PdfEngine::Poppler),
+    }
+}
+
+#[cfg(feature = "pdfium")]
+fn pdfium_engine() -> Option<PdfEngine> {
+    super::pdfium::is_available().then_some(PdfEngine::Pdfium)
+}
+
+#[cfg(not(feature = "pdfium"))]
+fn pdfium_engine() -> Option<PdfEngine> {
+    None
+}
+
+/// Whether Poppler's tools can be run. Spawns pdfinfo directly rather than
+/// asking `which`, which Windows doesn't have.
+fn poppler_installed() -> bool {
+    Command::new("pdfinfo").arg("-v").output().is_ok()
+}
@@ -1,44 +1,332 @@
 //! Shared PDF-to-image conversion utilities for OCR backends.
 
+use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
 
 use sha2::{Digest, Sha256};
 
+use foia::config::ExtractionLimits;
+
 use super::backend::OcrError;
 use super::model_utils::PDFTOPPM_NOT_FOUND;
+#[cfg(feature = "pdfium")]
+use super::pdf_engine::{pdf_engine, PdfEngine};
+
+/// Resolution PDF pages are rendered at for OCR.
+pub const OCR_DPI: u32 = 300;
+
+/// Resolution poorly recognized pages are re-rendered at for a second try.
+pub const REMEDIATION_DPI: u32 = 600;
+
+/// Lowest resolution an oversized page is scaled down to.
+const MIN_DPI: u32 = 36;
+
+/// Points per inch in PDF page sizes.
+const POINTS_PER_INCH: f64 = 72.0;
 
-/// Convert a PDF page to an image using pdftoppm.
+/// A page rendered to an image.
+#[derive(Debug, Clone)]
+pub struct RenderedPage {
+    pub path: PathBuf,
+    /// Resolution actually rendered at, after any downscaling.
+    pub dpi: u32,
+}
+
+/// Convert a PDF page to a PNG image at `dpi` using pdftoppm.
 ///
-/// Uses 300 DPI PNG output for optimal OCR quality.
+/// [`OCR_DPI`] is a good default for OCR quality. Oversized pages are
+/// rendered within the default [`ExtractionLimits`].
 pub fn pdf_page_to_image(
     pdf_path: &Path,
     page: u32,
+    dpi: u32,
     output_dir: &Path,
 ) -> Result<PathBuf, OcrError> {
+    render_page(
+        pdf_path,
+        page,
+        dpi,
+        output_dir,
+        &ExtractionLimits::default(),
+    )
+    .map(|rendered| rendered.path)
+}
+
+/// Render a PDF page to a PNG image at up to `dpi`, within `limits`: the
+/// resolution is lowered for pages too large to render at `dpi`, pdftoppm
+/// is killed if it runs too long, and images over the temp space limit are
+/// deleted and refused.
+pub fn render_page(
+    pdf_path: &Path,
+    page: u32,
+    dpi: u32,
+    output_dir: &Path,
+    limits: &ExtractionLimits,
+) -> Result<RenderedPage, OcrError> {
+    let dpi = match page_size_points(pdf_path, page) {
+        Some((width, height)) => fit_dpi(width, height, dpi, limits.max_page_pixels()),
+        None => dpi,
+    };
+
+    // pdfium renders in-process, so the timeout doesn't apply
+    #[cfg(feature = "pdfium")]
+    if pdf_engine() == PdfEngine::Pdfium {
+        let output = output_dir.join(format!("page-{:04}.png", page));
+        super::pdfium::render_page(pdf_path, page, dpi, &output)?;
+        return rendered_image(output_dir, page, dpi, limits);
+    }
+
     let page_str = page.to_string();
     let output_prefix = output_dir.join("page");
 
-    let status = Command::new("pdftoppm")
-        .args(["-png", "-r", "300", "-f", &page_str, "-l", &page_str])
-        .arg(pdf_path)
-        .arg(&output_prefix)
-        .status();
+    let output = output_with_timeout(
+        Command::new("pdftoppm")
+            .args(["-png", "-r", &dpi.to_string()])
+            .args(["-f", &page_str, "-l", &page_str])
+            .arg(pdf_path)
+            .arg(&output_prefix),
+        limits.render_timeout(),
+    );
 
-    match status {
-        Ok(s) if s.success() => find_page_image(output_dir, page)
-            .ok_or_else(|| OcrError::OcrFailed(format!("No image generated for page {}", page))),
+    match output {
+        Ok(o) if o.status.success() => rendered_image(output_dir, page, dpi, limits),
         Ok(_) => Err(OcrError::OcrFailed(
             "pdftoppm failed to convert PDF page".to_string(),
         )),
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(OcrError::BackendNotAvailable(
             PDFTOPPM_NOT_FOUND.to_string(),
         )),
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            clear_page_images(output_dir);
+            Err(OcrError::OcrFailed(format!(
+                "Rendering page {} took over {}s",
+                page,
+                limits.render_timeout().as_secs()
+            )))
+        }
         Err(e) => Err(OcrError::Io(e)),
     }
 }
 
+/// Render pages `first..=last` of a PDF within `limits`, with one pdftoppm
+/// run per stretch of pages that fit at the same resolution. Results are in
+/// page order.
+///
+/// If a run fails or times out, its pages are rendered one at a time, so a
+/// single bad page doesn't fail the pages around it.
+pub fn render_pages(
+    pdf_path: &Path,
+    first: u32,
+    last: u32,
+    dpi: u32,
+    output_dir: &Path,
+    limits: &ExtractionLimits,
+) -> Vec<Result<RenderedPage, OcrError>> {
+    if last < first {
+        return Vec::new();
+    }
+    #[cfg(feature = "pdfium")]
+    if pdf_engine() == PdfEngine::Pdfium {
+        return (first..=last)
+            .map(|page| render_page(pdf_path, page, dpi, output_dir, limits))
+            .collect();
+    }
+    let sizes = page_sizes_points(pdf_path, first, last);
+    let dpis: Vec<u32> = (first..=last)
+        .map(|page| match sizes.get(&page) {
+            Some(&(width, height)) => fit_dpi(width, height, dpi, limits.max_page_pixels()),
+            None => dpi,
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(dpis.len());
+    let mut start = 0;
+    while start < dpis.len() {
+        let run_dpi = dpis[start];
+        let end = dpis[start..]
+            .iter()
+            .position(|d| *d != run_dpi)
+            .map_or(dpis.len(), |n| start + n);
+        let run_first = first + start as u32;
+        let run_last = first + end as u32 - 1;
+
+        if render_run(pdf_path, run_first, run_last, run_dpi, output_dir, limits) {
+            for page in run_first..=run_last {
+                results.push(rendered_image(output_dir, page, run_dpi, limits));
+            }
+        } else {
+            clear_page_images(output_dir);
+            for page in run_first..=run_last {
+                results.push(render_page(pdf_path, page, dpi, output_dir, limits));
+            }
+        }
+        start = end;
+    }
+    results
+}
+
+/// Render pages `first..=last` at `dpi` in one pdftoppm run, allowing the
+/// per-page timeout for each page. Returns whether pdftoppm succeeded.
+fn render_run(
+    pdf_path: &Path,
+    first: u32,
+    last: u32,
+    dpi: u32,
+    output_dir: &Path,
+    limits: &ExtractionLimits,
+) -> bool {
+    let timeout = limits.render_timeout() * (last - first + 1);
+    output_with_timeout(
+        Command::new("pdftoppm")
+            .args(["-png", "-r", &dpi.to_string()])
+            .args(["-f", &first.to_string(), "-l", &last.to_string()])
+            .arg(pdf_path)
+            .arg(output_dir.join("page")),
+        timeout,
+    )
+    .is_ok_and(|output| output.status.success())
+}
+
+/// The image pdftoppm wrote for `page`, if it is within the temp space limit.
+fn rendered_image(
+    output_dir: &Path,
+    page: u32,
+    dpi: u32,
+    limits: &ExtractionLimits,
+) -> Result<RenderedPage, OcrError> {
+    let path = find_page_image(output_dir, page)
+        .ok_or_else(|| OcrError::OcrFailed(format!("No image generated for page {}", page)))?;
+    let size = fs::metadata(&path).map_err(OcrError::Io)?.len();
+    if size > limits.max_temp_bytes() {
+        let _ = fs::remove_file(&path);
+        return Err(OcrError::OcrFailed(format!(
+            "Page {} image is {} MiB, over the {} MiB limit",
+            page,
+            size / (1024 * 1024),
+            limits.max_temp_mb
+        )));
+    }
+    Ok(RenderedPage { path, dpi })
+}
+
+/// Resolution to render a page of `width` x `height` points at: `dpi`, or
+/// lower if that would exceed `max_pixels`.
+pub fn fit_dpi(width: f64, height: f64, dpi: u32, max_pixels: u64) -> u32 {
+    let area_sq_in = (width / POINTS_PER_INCH) * (height / POINTS_PER_INCH);
+    if area_sq_in <= 0.0 {
+        return dpi;
+    }
+    let pixels = area_sq_in * f64::from(dpi) * f64::from(dpi);
+    if pixels <= max_pixels as f64 {
+        return dpi;
+    }
+    let fitted = (max_pixels as f64 / area_sq_in).sqrt().floor() as u32;
+    fitted.clamp(MIN_DPI.min(dpi), dpi)
+}
+
+/// Size of a PDF page in points, from pdfinfo.
+pub fn page_size_points(pdf_path: &Path, page: u32) -> Option<(f64, f64)> {
+    page_sizes_points(pdf_path, page, page).remove(&page)
+}
+
+/// Sizes of pages `first..=last` of a PDF in points, from one pdfinfo run.
+pub fn page_sizes_points(pdf_path: &Path, first: u32, last: u32) -> HashMap<u32, (f64, f64)> {
+    #[cfg(feature = "pdfium")]
+    if pdf_engine() == PdfEngine::Pdfium {
+        return super::pdfium::page_sizes(pdf_path, first, last);
+    }
+    let output = Command::new("pdfinfo")
+        .args(["-f", &first.to_string(), "-l", &last.to_string()])
+        .arg(pdf_path)
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            parse_page_sizes(&String::from_utf8_lossy(&output.stdout), first)
+        }
+        _ => HashMap::new(),
+    }
+}
+
+/// Collect the `Page N size: W x H pts` lines in pdfinfo output. Some
+/// Poppler versions print `Page size:` without the number; that size is
+/// taken to be `first`'s.
+fn parse_page_sizes(pdfinfo: &str, first: u32) -> HashMap<u32, (f64, f64)> {
+    pdfinfo
+        .lines()
+        .filter_map(|line| {
+            let (label, dims) = line.strip_prefix("Page")?.split_once("size:")?;
+            let label = label.trim();
+            let page = if label.is_empty() {
+                first
+            } else {
+                label.parse().ok()?
+            };
+            let mut parts = dims.split_whitespace();
+            let width = parts.next()?.parse().ok()?;
+            parts.next().filter(|x| *x == "x")?;
+            let height = parts.next()?.parse().ok()?;
+            Some((page, (width, height)))
+        })
+        .collect()
+}
+
+/// Run `cmd`, collecting its output, and kill it after `timeout`. A
+/// timeout is reported as [`std::io::ErrorKind::TimedOut`].
+pub(crate) fn output_with_timeout(cmd: &mut Command, timeout: Duration) -> std::io::Result<Output> {
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+    // Drain the pipes while waiting, so a chatty process can't block on a
+    // full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Remove partial page images left by a killed render.
+fn clear_page_images(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "png") {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
 /// Find the image file for a specific page number.
 ///
 /// pdftoppm names files like page-01.png, page-02.png, etc.
@@ -68,8 +356,47 @@ pub fn compute_file_hash(path: &Path) -> Result<String, OcrError> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ocr::check_binary;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_fit_dpi() {
+        // US Letter at 300 DPI is about 8.4 megapixels
+        assert_eq!(fit_dpi(612.0, 792.0, 300, 50_000_000), 300);
+        // A 100 x 100 inch poster is scaled down to fit
+        let dpi = fit_dpi(7200.0, 7200.0, 300, 50_000_000);
+        assert_eq!(dpi, 70);
+        assert!(100.0 * 100.0 * f64::from(dpi * dpi) <= 50_000_000.0);
+        // but never below the floor
+        assert_eq!(fit_dpi(72_000.0, 72_000.0, 300, 1_000_000), MIN_DPI);
+        assert_eq!(fit_dpi(0.0, 792.0, 300, 1), 300);
+    }
+
+    #[test]
+    fn test_parse_page_sizes() {
+        let info =
+            "Pages:          12\nPage    3 size: 612 x 792 pts (letter)\nPage    3 rot:  0\n\
+                    Page    4 size: 2448 x 3168 pts\n";
+        let sizes = parse_page_sizes(info, 3);
+        assert_eq!(sizes.get(&3), Some(&(612.0, 792.0)));
+        assert_eq!(sizes.get(&4), Some(&(2448.0, 3168.0)));
+        assert_eq!(sizes.get(&5), None);
+        assert_eq!(
+            parse_page_sizes("Page size:      595.276 x 841.89 pts (A4)\n", 1).get(&1),
+            Some(&(595.276, 841.89))
+        );
+    }
+
+    #[test]
+    fn test_output_with_timeout() {
+        if !check_binary("sleep") {
+            return;
+        }
+        let err = output_with_timeout(Command::new("sleep").arg("5"), Duration::from_millis(200))
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+
     #[test]
     fn test_find_page_image_not_found() {
         let temp = TempDir::new().unwrap();
@@ -0,0 +1,132 @@
+//! PDF reading and rendering through the pdfium library.
+//!
+//! pdfium is loaded at runtime from the configured directory, the
+//! directory of the running executable, or the system library path, so
+//! PDFs can be processed where Poppler's tools aren't installed (including
+//! Windows). Calls into pdfium are serialized by pdfium-render.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use pdfium_render::prelude::*;
+
+use super::backend::OcrError;
+
+static LIBRARY_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+static PDFIUM: OnceLock<Option<Pdfium>> = OnceLock::new();
+
+/// Look for the pdfium library in `dir` before the default locations.
+/// Only takes effect before pdfium is first used.
+pub fn set_library_dir(dir: Option<PathBuf>) {
+    let _ = LIBRARY_DIR.set(dir);
+}
+
+/// Whether the pdfium library could be loaded.
+pub fn is_available() -> bool {
+    pdfium().is_ok()
+}
+
+/// The loaded library, bound on first use.
+fn pdfium() -> Result<&'static Pdfium, OcrError> {
+    PDFIUM
+        .get_or_init(|| {
+            let configured = LIBRARY_DIR.get().cloned().flatten();
+            let exe_dir = std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf));
+            let bindings = configured
+                .into_iter()
+                .chain(exe_dir)
+                .find_map(|dir| {
+                    Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path(&dir)).ok()
+                })
+                .map_or_else(Pdfium::bind_to_system_library, Ok);
+            match bindings {
+                Ok(bindings) => Some(Pdfium::new(bindings)),
+                Err(e) => {
+                    tracing::debug!("pdfium library not loaded: {}", e);
+                    None
+                }
+            }
+        })
+        .as_ref()
+        .ok_or_else(|| {
+            OcrError::BackendNotAvailable(
+                "pdfium library not found (set analysis.pdf.pdfium_library)".to_string(),
+            )
+        })
+}
+
+fn load(pdf_path: &Path) -> Result<PdfDocument<'static>, OcrError> {
+    pdfium()?
+        .load_pdf_from_file(pdf_path, None)
+        .map_err(|e| OcrError::OcrFailed(format!("pdfium cannot open PDF: {}", e)))
+}
+
+/// pdfium's 0-based index for 1-based `page`.
+fn page_index(page: u32) -> Result<PdfPageIndex, OcrError> {
+    page.checked_sub(1)
+        .and_then(|index| PdfPageIndex::try_from(index).ok())
+        .ok_or_else(|| OcrError::OcrFailed(format!("No page {}", page)))
+}
+
+fn page_error(page: u32, e: PdfiumError) -> OcrError {
+    OcrError::OcrFailed(format!("pdfium failed on page {}: {}", page, e))
+}
+
+/// Number of pages in a PDF.
+pub fn page_count(pdf_path: &Path) -> Result<u32, OcrError> {
+    Ok(u32::from(load(pdf_path)?.pages().len()))
+}
+
+/// Text of pages `first..=last`, one entry per page; pages that can't be
+/// read come back as `None`.
+pub fn page_texts(pdf_path: &Path, first: u32, last: u32) -> Result<Vec<Option<String>>, OcrError> {
+    let document = load(pdf_path)?;
+    let pages = document.pages();
+    Ok((first..=last)
+        .map(|page| {
+            let text = page_index(page).and_then(|index| {
+                let pdf_page = pages.get(index).map_err(|e| page_error(page, e))?;
+                let text = pdf_page.text().map_err(|e| page_error(page, e))?;
+                Ok(text.all())
+            });
+            text.map_err(|e| tracing::warn!("{}", e)).ok()
+        })
+        .collect())
+}
+
+/// Sizes of pages `first..=last` in points.
+pub fn page_sizes(pdf_path: &Path, first: u32, last: u32) -> HashMap<u32, (f64, f64)> {
+    let Ok(document) = load(pdf_path) else {
+        return HashMap::new();
+    };
+    let pages = document.pages();
+    (first..=last)
+        .filter_map(|page| {
+            let pdf_page = pages.get(page_index(page).ok()?).ok()?;
+            let size = (
+                f64::from(pdf_page.width().value),
+                f64::from(pdf_page.height().value),
+            );
+            Some((page, size))
+        })
+        .collect()
+}
+
+/// Render `page` at `dpi` to a PNG at `output`.
+pub fn render_page(pdf_path: &Path, page: u32, dpi: u32, output: &Path) -> Result<(), OcrError> {
+    let document = load(pdf_path)?;
+    let pdf_page = document
+        .pages()
+        .get(page_index(page)?)
+        .map_err(|e| page_error(page, e))?;
+    let config = PdfRenderConfig::new().scale_page_by_factor(dpi as f32 / 72.0);
+    pdf_page
+        .render_with_config(&config)
+        .map_err(|e| page_error(page, e))?
+        .as_image()
+        .save(output)
+        .map_err(|e| OcrError::ImageError(e.to_string()))
+}
@@ -0,0 +1,163 @@
+//! Pool of slots for local OCR processes.
+//!
+//! Each slot owns a scratch directory that is emptied and reused between
+//! batches instead of creating a temporary directory per page. Checking a
+//! slot out blocks until one is free, so at most `size` batches of
+//! pdftoppm and Tesseract processes run at once.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+
+use tempfile::TempDir;
+
+use foia::config::OcrPoolConfig;
+
+/// A fixed number of reusable OCR slots.
+pub struct OcrPool {
+    state: Mutex<PoolState>,
+    freed: Condvar,
+    size: usize,
+    batch_pages: u32,
+}
+
+struct PoolState {
+    /// Scratch directories not checked out.
+    idle: Vec<TempDir>,
+    /// Slots created so far; directories are made on first use.
+    created: usize,
+}
+
+impl OcrPool {
+    /// Create a pool as configured.
+    pub fn new(config: &OcrPoolConfig) -> Self {
+        Self::with_size(config.workers(), config.batch_pages())
+    }
+
+    /// Create a pool of `size` slots taking `batch_pages` pages per batch.
+    pub fn with_size(size: usize, batch_pages: u32) -> Self {
+        Self {
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                created: 0,
+            }),
+            freed: Condvar::new(),
+            size: size.max(1),
+            batch_pages: batch_pages.max(1),
+        }
+    }
+
+    /// Number of slots.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Consecutive pages handed to one slot at a time.
+    pub fn batch_pages(&self) -> u32 {
+        self.batch_pages
+    }
+
+    /// Take a slot, waiting for one to be returned if all are in use.
+    pub fn checkout(&self) -> std::io::Result<PoolSlot<'_>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(dir) = state.idle.pop() {
+                return Ok(PoolSlot {
+                    dir: Some(dir),
+                    pool: self,
+                });
+            }
+            if state.created < self.size {
+                let dir = TempDir::with_prefix("foia-ocr-")?;
+                state.created += 1;
+                return Ok(PoolSlot {
+                    dir: Some(dir),
+                    pool: self,
+                });
+            }
+            state = self.freed.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn release(&self, dir: TempDir) {
+        clear_dir(dir.path());
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.idle.push(dir);
+        self.freed.notify_one();
+    }
+}
+
+/// A checked-out slot. Its scratch directory is emptied and the slot
+/// returned to the pool when dropped.
+pub struct PoolSlot<'a> {
+    dir: Option<TempDir>,
+    pool: &'a OcrPool,
+}
+
+impl PoolSlot<'_> {
+    /// Scratch directory for this slot's renders and Tesseract output.
+    pub fn path(&self) -> &Path {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.path())
+            .expect("slot directory is present until drop")
+    }
+}
+
+impl Drop for PoolSlot<'_> {
+    fn drop(&mut self) {
+        if let Some(dir) = self.dir.take() {
+            self.pool.release(dir);
+        }
+    }
+}
+
+/// Remove everything inside `dir`, keeping `dir` itself.
+fn clear_dir(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let _ = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_slots_are_reused_and_cleared() {
+        let pool = OcrPool::with_size(1, 4);
+        let first = {
+            let slot = pool.checkout().unwrap();
+            fs::write(slot.path().join("page-01.png"), b"png").unwrap();
+            slot.path().to_path_buf()
+        };
+        let slot = pool.checkout().unwrap();
+        assert_eq!(slot.path(), first);
+        assert_eq!(fs::read_dir(slot.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_checkout_waits_for_a_free_slot() {
+        let pool = Arc::new(OcrPool::with_size(1, 4));
+        let slot = pool.checkout().unwrap();
+
+        let waiter = std::thread::spawn({
+            let pool = pool.clone();
+            move || pool.checkout().map(|slot| slot.path().to_path_buf())
+        });
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+
+        let path = slot.path().to_path_buf();
+        drop(slot);
+        assert_eq!(waiter.join().unwrap().unwrap(), path);
+    }
+}
@@ -0,0 +1,111 @@
+//! OCR quality scoring.
+//!
+//! Scores combine how much of the text looks like real words with the
+//! backend's own confidence when it reports one (Tesseract does). Garbled
+//! OCR of a poor scan shows up as runs of symbols, mixed-case noise and
+//! consonant clusters, which pull the word score down even when the
+//! backend is confident.
+
+/// Fewer tokens than this is too little text to judge.
+const MIN_TOKENS: usize = 3;
+
+/// Longest token still considered a word.
+const MAX_WORD_LEN: usize = 30;
+
+/// Quality score (0.0 - 1.0) for OCR output, or `None` for blank pages.
+///
+/// Averages the word score with `confidence` when the backend gave one.
+pub fn quality_score(text: &str, confidence: Option<f32>) -> Option<f32> {
+    let text_score = text_score(text)?;
+    Some(match confidence {
+        Some(confidence) => (text_score + confidence.clamp(0.0, 1.0)) / 2.0,
+        None => text_score,
+    })
+}
+
+/// Fraction of whitespace-separated tokens that look like words or numbers.
+fn text_score(text: &str) -> Option<f32> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    if tokens.len() < MIN_TOKENS {
+        return None;
+    }
+    let wordlike = tokens.iter().filter(|t| is_wordlike(t)).count();
+    Some(wordlike as f32 / tokens.len() as f32)
+}
+
+fn is_wordlike(token: &str) -> bool {
+    // Surrounding punctuation is normal: "(b)(6)," "Sincerely,"
+    let core = token.trim_matches(|c: char| !c.is_alphanumeric());
+    if core.is_empty() {
+        return false;
+    }
+
+    // Numbers, dates, case and exemption numbers
+    if core.chars().any(|c| c.is_ascii_digit())
+        && core
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '/' | ':' | '(' | ')'))
+    {
+        return true;
+    }
+
+    // Mostly letters and digits, allowing "U.S.C", "don't", "552(b)(6"
+    let len = core.chars().count();
+    let wordish = core
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '.' | '\'' | '-' | '(' | ')'))
+        .count();
+    if len > MAX_WORD_LEN || wordish * 10 < len * 8 {
+        return false;
+    }
+
+    // Latin-script words need a vowel unless they're short abbreviations
+    if len > 4
+        && core.chars().all(|c| c.is_ascii_alphabetic())
+        && !core.chars().any(|c| "aeiouyAEIOUY".contains(c))
+    {
+        return false;
+    }
+
+    // Lowercase followed by uppercase more than once is recognition noise
+    // ("tHlS"), while "McDonald" and "iPhone" pass
+    let case_flips = core
+        .chars()
+        .zip(core.chars().skip(1))
+        .filter(|(a, b)| a.is_lowercase() && b.is_uppercase())
+        .count();
+    case_flips <= 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_text_scores_high() {
+        let text = "Pursuant to 5 U.S.C. 552(b)(6), the agency withheld records \
+                    dated 03/14/2019 regarding the McDonald request.";
+        let score = quality_score(text, None).unwrap();
+        assert!(score > 0.9, "score was {}", score);
+    }
+
+    #[test]
+    fn test_garbled_text_scores_low() {
+        let text = "~~ tHlS |||| ;:;: wrdfgh ^^^ lIlIlI %%$# qzxcvbn a";
+        let score = quality_score(text, None).unwrap();
+        assert!(score < 0.5, "score was {}", score);
+    }
+
+    #[test]
+    fn test_blends_confidence() {
+        let text = "the quick brown fox";
+        assert_eq!(quality_score(text, None), Some(1.0));
+        assert_eq!(quality_score(text, Some(0.5)), Some(0.75));
+    }
+
+    #[test]
+    fn test_blank_pages_are_unscored() {
+        assert_eq!(quality_score("", Some(0.9)), None);
+        assert_eq!(quality_score("  \n Page 3 \n", None), None);
+    }
+}
@@ -7,6 +7,9 @@
 
 use std::path::Path;
 use std::process::Command;
+use std::sync::OnceLock;
+
+use tempfile::TempDir;
 
 use super::backend::{BackendConfig, OcrBackend, OcrBackendType, OcrConfig, OcrError};
 use super::model_utils::{check_binary, check_pdftoppm_hint};
@@ -36,18 +39,29 @@ impl TesseractBackend {
         Self { config }
     }
 
-    /// Run Tesseract on an image file.
-    fn run_tesseract_impl(&self, image_path: &Path) -> Result<String, OcrError> {
+    /// Run Tesseract on an image file, returning the text and the mean
+    /// word confidence.
+    ///
+    /// Writes plain text and TSV in one pass; the TSV carries per-word
+    /// confidences.
+    fn run_tesseract_impl(&self, image_path: &Path) -> Result<(String, Option<f32>), OcrError> {
+        let temp_dir = TempDir::new()?;
+        let output_base = temp_dir.path().join("ocr");
         let output = Command::new("tesseract")
             .arg(image_path)
-            .arg("stdout")
+            .arg(&output_base)
             .args(["-l", &self.config.ocr.language])
+            .args(["txt", "tsv"])
             .output();
 
         match output {
             Ok(output) => {
                 if output.status.success() {
-                    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                    let text = std::fs::read_to_string(output_base.with_extension("txt"))?;
+                    let confidence = std::fs::read_to_string(output_base.with_extension("tsv"))
+                        .ok()
+                        .and_then(|tsv| mean_confidence(&tsv));
+                    Ok((text, confidence))
                 } else {
                     let stderr = String::from_utf8_lossy(&output.stderr);
                     Err(OcrError::OcrFailed(format!("tesseract failed: {}", stderr)))
@@ -61,6 +75,126 @@ impl TesseractBackend {
             Err(e) => Err(OcrError::Io(e)),
         }
     }
+
+    /// Run Tesseract once over several images, returning each image's text
+    /// and mean word confidence in order.
+    ///
+    /// The images are listed in a file under `scratch`, which also receives
+    /// the output. Tesseract ends each page's text with a form feed and
+    /// numbers the pages in its TSV.
+    pub fn run_ocr_batch(
+        &self,
+        images: &[&Path],
+        scratch: &Path,
+    ) -> Result<Vec<(String, Option<f32>)>, OcrError> {
+        if images.is_empty() {
+            return Ok(Vec::new());
+        }
+        let list_path = scratch.join("images.txt");
+        let list: Vec<String> = images
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        std::fs::write(&list_path, list.join("\n") + "\n")?;
+
+        let output_base = scratch.join("batch");
+        let output = Command::new("tesseract")
+            .arg(&list_path)
+            .arg(&output_base)
+            .args(["-l", &self.config.ocr.language])
+            .args(["txt", "tsv"])
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {}
+            Ok(output) => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(OcrError::OcrFailed(format!("tesseract failed: {}", stderr)));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(OcrError::BackendNotAvailable(
+                    "tesseract not found (install tesseract-ocr)".to_string(),
+                ))
+            }
+            Err(e) => return Err(OcrError::Io(e)),
+        }
+
+        let text = std::fs::read_to_string(output_base.with_extension("txt"))?;
+        let texts: Vec<&str> = text.split('\u{c}').collect();
+        // The last page's form feed leaves an empty tail
+        if texts.len() < images.len() {
+            return Err(OcrError::OcrFailed(format!(
+                "tesseract returned {} pages for {} images",
+                texts.len(),
+                images.len()
+            )));
+        }
+        let confidences = std::fs::read_to_string(output_base.with_extension("tsv"))
+            .map(|tsv| page_confidences(&tsv, images.len()))
+            .unwrap_or_else(|_| vec![None; images.len()]);
+
+        Ok(texts
+            .into_iter()
+            .zip(confidences)
+            .map(|(text, confidence)| (text.to_string(), confidence))
+            .collect())
+    }
+}
+
+/// Mean word confidence (0.0 - 1.0) from Tesseract TSV output.
+///
+/// Only word rows (level 5) with text count; layout rows carry `-1`.
+fn mean_confidence(tsv: &str) -> Option<f32> {
+    page_confidences(tsv, 1).pop().flatten()
+}
+
+/// Mean word confidence of each of `pages` pages in Tesseract TSV output,
+/// by its 1-based `page_num` column.
+fn page_confidences(tsv: &str, pages: usize) -> Vec<Option<f32>> {
+    let mut totals = vec![(0.0f32, 0u32); pages];
+    for line in tsv.lines().skip(1) {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() < 12 || cols[0] != "5" || cols[11].trim().is_empty() {
+            continue;
+        }
+        let Some(confidence) = cols[10].parse::<f32>().ok().filter(|c| *c >= 0.0) else {
+            continue;
+        };
+        let page = cols[1].parse::<usize>().unwrap_or(1);
+        if let Some((sum, count)) = page.checked_sub(1).and_then(|i| totals.get_mut(i)) {
+            *sum += confidence;
+            *count += 1;
+        }
+    }
+    totals
+        .into_iter()
+        .map(|(sum, count)| (count > 0).then(|| (sum / count as f32 / 100.0).clamp(0.0, 1.0)))
+        .collect()
+}
+
+/// Installed Tesseract version (e.g. `5.3.0`), read once per process.
+fn tesseract_version() -> Option<String> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            let output = Command::new("tesseract").arg("--version").output().ok()?;
+            // Older releases print the banner to stderr
+            let banner = if output.stdout.is_empty() {
+                output.stderr
+            } else {
+                output.stdout
+            };
+            parse_version(&String::from_utf8_lossy(&banner))
+        })
+        .clone()
+}
+
+/// Version from a `tesseract --version` banner (`tesseract 5.3.0` or
+/// `tesseract v5.0.0-alpha`).
+fn parse_version(banner: &str) -> Option<String> {
+    let first = banner.lines().next()?;
+    let version = first.strip_prefix("tesseract ")?.trim();
+    let version = version.strip_prefix('v').unwrap_or(version);
+    (!version.is_empty()).then(|| version.to_string())
 }
 
 impl Default for TesseractBackend {
@@ -88,7 +222,66 @@ impl OcrBackend for TesseractBackend {
         }
     }
 
+    fn tool_version(&self) -> Option<String> {
+        tesseract_version()
+    }
+
+    fn language(&self) -> Option<String> {
+        Some(self.config.ocr.language.clone())
+    }
+
     fn run_ocr(&self, image_path: &Path) -> Result<String, OcrError> {
+        self.run_tesseract_impl(image_path).map(|(text, _)| text)
+    }
+
+    fn run_ocr_with_confidence(
+        &self,
+        image_path: &Path,
+    ) -> Result<(String, Option<f32>), OcrError> {
         self.run_tesseract_impl(image_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("tesseract 5.3.0\n leptonica-1.82.0\n"),
+            Some("5.3.0".to_string())
+        );
+        assert_eq!(
+            parse_version("tesseract v5.0.0-alpha.20201127\n"),
+            Some("5.0.0-alpha.20201127".to_string())
+        );
+        assert_eq!(parse_version("something else"), None);
+    }
+
+    #[test]
+    fn test_mean_confidence() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t2550\t3300\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t100\t100\t80\t30\t96.5\tFreedom\n\
+                   5\t1\t1\t1\t1\t2\t190\t100\t40\t30\t83.5\tof\n\
+                   5\t1\t1\t1\t1\t3\t240\t100\t40\t30\t95\t \n";
+        let confidence = mean_confidence(tsv).unwrap();
+        assert!((confidence - 0.9).abs() < 1e-6);
+
+        assert_eq!(mean_confidence("level\tconf\n"), None);
+    }
+
+    #[test]
+    fn test_page_confidences() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   5\t1\t1\t1\t1\t1\t100\t100\t80\t30\t90\tFederal\n\
+                   5\t3\t1\t1\t1\t1\t100\t100\t80\t30\t70\tBureau\n\
+                   5\t3\t1\t1\t1\t2\t190\t100\t80\t30\t50\tof\n";
+        let confidences = page_confidences(tsv, 3);
+        assert_eq!(confidences.len(), 3);
+        assert!((confidences[0].unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(confidences[1], None);
+        assert!((confidences[2].unwrap() - 0.6).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,575 @@
+//! Redacted copies of PDFs.
+//!
+//! Produces a copy of a PDF with black boxes burned into regions a curator
+//! chose, and over personal information or given words found on its pages.
+//! Each page with something to cover is rendered with `pdftoppm`, the boxes
+//! painted into its pixels, and the page rebuilt from that image with a
+//! fresh Tesseract text layer, so nothing under a box survives in either
+//! the image or the text. Other pages are copied unchanged with
+//! `pdfseparate`, and the pages joined with `pdfunite`.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+
+use foia::models::scan_pii;
+use foia::utils::pdf_page_count;
+
+use crate::ocr::{check_binary, OCR_DPI};
+
+/// Resolution pages are redacted at.
+pub const REDACTION_DPI: u32 = OCR_DPI;
+
+/// Pixels added around each located word, so boxes cover ascenders,
+/// descenders and a little slack in Tesseract's word boxes.
+const PADDING: u32 = REDACTION_DPI / 50;
+
+/// Tools redaction shells out to.
+const REQUIRED_TOOLS: [&str; 5] = [
+    "pdfinfo",
+    "pdfseparate",
+    "pdfunite",
+    "pdftoppm",
+    "tesseract",
+];
+
+/// Names of required tools that aren't installed.
+pub fn missing_tools() -> Vec<&'static str> {
+    REQUIRED_TOOLS
+        .into_iter()
+        .filter(|tool| !check_binary(tool))
+        .collect()
+}
+
+/// A rectangle to black out, in fractions of the page's width and height
+/// from its top-left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRegion {
+    /// Page number (1-indexed).
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl RedactionRegion {
+    /// Parse `PAGE:X,Y,WIDTH,HEIGHT`, e.g. `3:0.1,0.25,0.5,0.05` for a strip
+    /// across the middle of page 3 starting a quarter of the way down.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid region '{}': expected PAGE:X,Y,WIDTH,HEIGHT", s);
+        let (page, rect) = s.split_once(':').ok_or_else(invalid)?;
+        let page: u32 = page.trim().parse().map_err(|_| invalid())?;
+        let values = rect
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        let [x, y, width, height] = values[..] else {
+            return Err(invalid());
+        };
+        let region = Self {
+            page,
+            x,
+            y,
+            width,
+            height,
+        };
+        let fraction = |v: f64| (0.0..=1.0).contains(&v);
+        if page == 0
+            || ![x, y, width, height].into_iter().all(fraction)
+            || width == 0.0
+            || height == 0.0
+        {
+            return Err(format!(
+                "Invalid region '{}': page starts at 1 and X, Y, WIDTH and HEIGHT are fractions of the page",
+                s
+            ));
+        }
+        Ok(region)
+    }
+
+    /// The region in pixels on a `width` x `height` rendering of its page.
+    fn to_rect(self, width: u32, height: u32) -> Rect {
+        let px = |f: f64, size: u32| ((f.clamp(0.0, 1.0) * size as f64).round() as u32).min(size);
+        Rect {
+            left: px(self.x, width),
+            top: px(self.y, height),
+            right: px(self.x + self.width, width),
+            bottom: px(self.y + self.height, height),
+        }
+    }
+}
+
+/// What to black out.
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPlan {
+    /// Regions chosen by a curator.
+    pub regions: Vec<RedactionRegion>,
+    /// Cover the personal information [`scan_pii`] finds.
+    pub pii: bool,
+    /// Words or phrases to cover wherever they appear, ignoring case.
+    pub terms: Vec<String>,
+    /// Pages to search for personal information and terms, usually those
+    /// whose extracted text mentions them; searching needs an extra OCR
+    /// pass, so other pages are only redacted if they have regions.
+    pub search_pages: BTreeSet<u32>,
+}
+
+impl RedactionPlan {
+    fn searches(&self, page: u32) -> bool {
+        (self.pii || !self.terms.is_empty()) && self.search_pages.contains(&page)
+    }
+}
+
+/// What was covered on one page of a redacted copy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactedPage {
+    pub page: u32,
+    /// Boxes burned in, including the curator's regions.
+    pub boxes: usize,
+    /// Personal information and terms located on the page.
+    pub found: usize,
+    /// The rebuilt page's OCR text, which is all that's left of it.
+    pub text: String,
+}
+
+/// A pixel rectangle, right and bottom exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+}
+
+impl Rect {
+    fn union(self, other: Rect) -> Rect {
+        Rect {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+
+    fn pad(self, by: u32) -> Rect {
+        Rect {
+            left: self.left.saturating_sub(by),
+            top: self.top.saturating_sub(by),
+            right: self.right.saturating_add(by),
+            bottom: self.bottom.saturating_add(by),
+        }
+    }
+}
+
+/// A binary PPM (P6) image, as `pdftoppm` writes by default.
+struct Pixmap {
+    width: u32,
+    height: u32,
+    /// Where pixel data starts, after the header.
+    offset: usize,
+    bytes: Vec<u8>,
+}
+
+impl Pixmap {
+    fn parse(bytes: Vec<u8>) -> Result<Self, String> {
+        let mut fields = Vec::with_capacity(4);
+        let mut pos = 0;
+        while fields.len() < 4 {
+            while bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+                pos += 1;
+            }
+            if bytes.get(pos) == Some(&b'#') {
+                while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                    pos += 1;
+                }
+                continue;
+            }
+            let start = pos;
+            while bytes.get(pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                pos += 1;
+            }
+            if start == pos {
+                return Err("Truncated PPM header".to_string());
+            }
+            fields.push(String::from_utf8_lossy(&bytes[start..pos]).into_owned());
+        }
+        if fields[0] != "P6" {
+            return Err(format!("Expected a P6 PPM image, got {}", fields[0]));
+        }
+        let number = |field: &str| {
+            field
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid PPM header field '{}'", field))
+        };
+        let (width, height) = (number(&fields[1])?, number(&fields[2])?);
+        if number(&fields[3])? != 255 {
+            return Err("Only 8-bit PPM images are supported".to_string());
+        }
+        // A single whitespace byte separates the header from the pixels
+        let offset = pos + 1;
+        if bytes.len() < offset + width as usize * height as usize * 3 {
+            return Err("Truncated PPM image".to_string());
+        }
+        Ok(Self {
+            width,
+            height,
+            offset,
+            bytes,
+        })
+    }
+
+    /// Paint `rect` black, clipped to the image.
+    fn fill(&mut self, rect: Rect) {
+        let right = rect.right.min(self.width) as usize;
+        let bottom = rect.bottom.min(self.height);
+        let left = (rect.left as usize).min(right);
+        for y in rect.top.min(bottom)..bottom {
+            let row = self.offset + y as usize * self.width as usize * 3;
+            self.bytes[row + left * 3..row + right * 3].fill(0);
+        }
+    }
+}
+
+/// A word Tesseract found on a page image.
+#[derive(Debug, Clone, PartialEq)]
+struct Word {
+    /// Block, paragraph and line number, the same for words on one line.
+    line: (u32, u32, u32),
+    rect: Rect,
+    text: String,
+}
+
+/// Words from Tesseract's TSV output, in reading order.
+fn parse_tsv(tsv: &str) -> Vec<Word> {
+    tsv.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.splitn(12, '\t').collect();
+            if cols.len() < 12 || cols[0] != "5" {
+                return None;
+            }
+            let text = cols[11].trim();
+            if text.is_empty() {
+                return None;
+            }
+            let n = |i: usize| cols[i].parse::<u32>().ok();
+            let (left, top, width, height) = (n(6)?, n(7)?, n(8)?, n(9)?);
+            Some(Word {
+                line: (n(2)?, n(3)?, n(4)?),
+                rect: Rect {
+                    left,
+                    top,
+                    right: left + width,
+                    bottom: top + height,
+                },
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Page text rebuilt from words, one line per line, and each word's byte
+/// range in it.
+fn words_text(words: &[Word]) -> (String, Vec<(usize, usize)>) {
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(words.len());
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            text.push(if words[i - 1].line == word.line {
+                ' '
+            } else {
+                '\n'
+            });
+        }
+        let start = text.len();
+        text.push_str(&word.text);
+        spans.push((start, text.len()));
+    }
+    (text, spans)
+}
+
+/// Byte ranges of personal information and of `terms` in `text`.
+fn sensitive_ranges(text: &str, pii: bool, terms: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    if pii {
+        ranges.extend(scan_pii(text).into_iter().map(|m| (m.start, m.end)));
+    }
+    // ASCII lowercasing keeps byte offsets; phrases may run across lines
+    let flat = text.to_ascii_lowercase().replace('\n', " ");
+    for term in terms {
+        let term = term
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_ascii_lowercase();
+        if term.is_empty() {
+            continue;
+        }
+        ranges.extend(flat.match_indices(&term).map(|(i, _)| (i, i + term.len())));
+    }
+    ranges
+}
+
+/// Boxes covering the words that overlap each range, one per line.
+fn cover(words: &[Word], spans: &[(usize, usize)], ranges: &[(usize, usize)]) -> Vec<Rect> {
+    let mut rects = Vec::new();
+    for &(start, end) in ranges {
+        let mut current: Option<((u32, u32, u32), Rect)> = None;
+        for (word, &(word_start, word_end)) in words.iter().zip(spans) {
+            if word_end <= start || word_start >= end {
+                continue;
+            }
+            match current.as_mut() {
+                Some((line, rect)) if *line == word.line => *rect = rect.union(word.rect),
+                _ => {
+                    if let Some((_, rect)) = current.replace((word.line, word.rect)) {
+                        rects.push(rect.pad(PADDING));
+                    }
+                }
+            }
+        }
+        if let Some((_, rect)) = current {
+            rects.push(rect.pad(PADDING));
+        }
+    }
+    rects
+}
+
+fn run(command: &mut Command, name: &str) -> Result<Vec<u8>, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", name, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Redact one page into `dir`, returning the rebuilt page's PDF, or `None`
+/// if there was nothing on it to cover.
+fn redact_page(
+    pdf_path: &Path,
+    page: u32,
+    plan: &RedactionPlan,
+    language: &str,
+    dir: &Path,
+) -> Result<Option<(PathBuf, RedactedPage)>, String> {
+    let dpi = REDACTION_DPI.to_string();
+    let number = page.to_string();
+    let prefix = dir.join(format!("render-{}", page));
+    run(
+        Command::new("pdftoppm")
+            .args(["-r", &dpi, "-f", &number, "-l", &number, "-singlefile"])
+            .arg(pdf_path)
+            .arg(&prefix),
+        "pdftoppm",
+    )?;
+    let image = prefix.with_extension("ppm");
+    let bytes = std::fs::read(&image).map_err(|e| format!("Page {}: {}", page, e))?;
+    let mut pixmap = Pixmap::parse(bytes).map_err(|e| format!("Page {}: {}", page, e))?;
+
+    let mut rects: Vec<Rect> = plan
+        .regions
+        .iter()
+        .filter(|region| region.page == page)
+        .map(|region| region.to_rect(pixmap.width, pixmap.height))
+        .collect();
+    let mut found = 0;
+    if plan.searches(page) {
+        let tsv = run(
+            Command::new("tesseract")
+                .arg(&image)
+                .arg("stdout")
+                .args(["-l", language, "--dpi", &dpi, "tsv"]),
+            "tesseract",
+        )?;
+        let words = parse_tsv(&String::from_utf8_lossy(&tsv));
+        let (text, spans) = words_text(&words);
+        let ranges = sensitive_ranges(&text, plan.pii, &plan.terms);
+        found = ranges.len();
+        rects.extend(cover(&words, &spans, &ranges));
+    }
+    if rects.is_empty() {
+        let _ = std::fs::remove_file(&image);
+        return Ok(None);
+    }
+
+    for rect in &rects {
+        pixmap.fill(*rect);
+    }
+    std::fs::write(&image, &pixmap.bytes).map_err(|e| format!("Page {}: {}", page, e))?;
+    let base = dir.join(format!("redacted-{}", page));
+    run(
+        Command::new("tesseract")
+            .arg(&image)
+            .arg(&base)
+            .args(["-l", language, "--dpi", &dpi, "pdf", "txt"]),
+        "tesseract",
+    )?;
+    let _ = std::fs::remove_file(&image);
+    let text = std::fs::read_to_string(base.with_extension("txt")).unwrap_or_default();
+
+    Ok(Some((
+        base.with_extension("pdf"),
+        RedactedPage {
+            page,
+            boxes: rects.len(),
+            found,
+            text: text.trim().to_string(),
+        },
+    )))
+}
+
+/// Write a redacted copy of `pdf_path` to `output` following `plan`, and
+/// report what was covered on each page. An empty report means nothing
+/// was found to cover and `output` wasn't written.
+///
+/// Blocks on external tools; call from a blocking context. `language` is
+/// the Tesseract language.
+pub fn redact_pdf(
+    pdf_path: &Path,
+    output: &Path,
+    plan: &RedactionPlan,
+    language: &str,
+) -> Result<Vec<RedactedPage>, String> {
+    let page_count = pdf_page_count(pdf_path)?;
+    if let Some(region) = plan.regions.iter().find(|r| r.page > page_count) {
+        return Err(format!(
+            "Region on page {}, but the PDF has {} pages",
+            region.page, page_count
+        ));
+    }
+    let dir = TempDir::new().map_err(|e| e.to_string())?;
+    run(
+        Command::new("pdfseparate")
+            .arg(pdf_path)
+            .arg(dir.path().join("page-%d.pdf")),
+        "pdfseparate",
+    )?;
+
+    let mut parts = Vec::with_capacity(page_count as usize);
+    let mut report = Vec::new();
+    for page in 1..=page_count {
+        let original = dir.path().join(format!("page-{}.pdf", page));
+        let touched = plan.regions.iter().any(|r| r.page == page) || plan.searches(page);
+        if !touched {
+            parts.push(original);
+            continue;
+        }
+        match redact_page(pdf_path, page, plan, language, dir.path())? {
+            Some((redacted, page_report)) => {
+                parts.push(redacted);
+                report.push(page_report);
+            }
+            None => parts.push(original),
+        }
+    }
+    if report.is_empty() {
+        return Ok(report);
+    }
+
+    if let [single] = parts.as_slice() {
+        std::fs::copy(single, output)
+            .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    } else {
+        run(
+            Command::new("pdfunite").args(&parts).arg(output),
+            "pdfunite",
+        )?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_region() {
+        let region = RedactionRegion::parse("3:0.1,0.25,0.5,0.05").unwrap();
+        assert_eq!(region.page, 3);
+        assert_eq!(
+            region.to_rect(1000, 2000),
+            Rect {
+                left: 100,
+                top: 500,
+                right: 600,
+                bottom: 600
+            }
+        );
+        assert!(RedactionRegion::parse("0:0.1,0.1,0.1,0.1").is_err());
+        assert!(RedactionRegion::parse("1:0.1,0.1,1.5,0.1").is_err());
+        assert!(RedactionRegion::parse("1:0.1,0.1,0.1").is_err());
+        assert!(RedactionRegion::parse("1:0.1,0.1,0,0.1").is_err());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut bytes = b"P6\n# pdftoppm\n4 2\n255\n".to_vec();
+        bytes.extend(std::iter::repeat(255).take(4 * 2 * 3));
+        let mut pixmap = Pixmap::parse(bytes).unwrap();
+        pixmap.fill(Rect {
+            left: 1,
+            top: 1,
+            right: 9,
+            bottom: 9,
+        });
+        let pixels = &pixmap.bytes[pixmap.offset..];
+        assert!(pixels[..15].iter().all(|&b| b == 255));
+        assert!(pixels[15..].iter().all(|&b| b == 0));
+        assert!(Pixmap::parse(b"P6\n4 2\n255\n".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_cover_pii_and_terms() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   5\t1\t1\t1\t1\t1\t10\t10\t40\t20\t95\tSSN\n\
+                   5\t1\t1\t1\t1\t2\t60\t10\t120\t20\t95\t219-09-9999\n\
+                   5\t1\t1\t1\t2\t1\t10\t40\t50\t20\t95\tJohn\n\
+                   5\t1\t1\t1\t3\t1\t10\t70\t60\t20\t95\tSmith,\n\
+                   5\t1\t1\t1\t3\t2\t80\t70\t40\t20\t95\tagent\n";
+        let words = parse_tsv(tsv);
+        assert_eq!(words.len(), 5);
+        let (text, spans) = words_text(&words);
+        assert_eq!(text, "SSN 219-09-9999\nJohn\nSmith, agent");
+
+        let ranges = sensitive_ranges(&text, true, &["john  SMITH".to_string()]);
+        let rects = cover(&words, &spans, &ranges);
+        assert_eq!(
+            rects,
+            vec![
+                Rect {
+                    left: 60,
+                    top: 10,
+                    right: 180,
+                    bottom: 30
+                }
+                .pad(PADDING),
+                Rect {
+                    left: 10,
+                    top: 40,
+                    right: 60,
+                    bottom: 60
+                }
+                .pad(PADDING),
+                Rect {
+                    left: 10,
+                    top: 70,
+                    right: 70,
+                    bottom: 90
+                }
+                .pad(PADDING),
+            ]
+        );
+        assert!(sensitive_ranges(&text, false, &[" ".to_string()]).is_empty());
+    }
+}
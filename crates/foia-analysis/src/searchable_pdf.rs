@@ -0,0 +1,124 @@
+//! Searchable PDF generation.
+//!
+//! Produces a copy of a PDF with an invisible OCR text layer over its page
+//! images, so it can be searched and copied from in any PDF reader. Uses
+//! `ocrmypdf` when installed, which keeps the original pages and only OCRs
+//! those without text. Otherwise each page is rendered, run through
+//! Tesseract's PDF renderer and the pages joined with `pdfunite`.
+
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+use crate::ocr::{check_binary, pdf_page_to_image, TextExtractor, OCR_DPI};
+
+/// Tool that produced a searchable PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchablePdfTool {
+    OcrMyPdf,
+    Tesseract,
+}
+
+impl SearchablePdfTool {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::OcrMyPdf => "ocrmypdf",
+            Self::Tesseract => "tesseract",
+        }
+    }
+
+    /// The best installed tool, if any.
+    pub fn detect() -> Option<Self> {
+        if check_binary("ocrmypdf") {
+            Some(Self::OcrMyPdf)
+        } else if ["tesseract", "pdftoppm", "pdfinfo", "pdfunite"]
+            .iter()
+            .all(|tool| check_binary(tool))
+        {
+            Some(Self::Tesseract)
+        } else {
+            None
+        }
+    }
+}
+
+/// Write a searchable copy of `pdf_path` to `output`.
+///
+/// Blocks on external tools; call from a blocking context. `language` is
+/// the Tesseract language.
+pub fn make_searchable_pdf(
+    tool: SearchablePdfTool,
+    pdf_path: &Path,
+    output: &Path,
+    language: &str,
+) -> Result<(), String> {
+    match tool {
+        SearchablePdfTool::OcrMyPdf => run_ocrmypdf(pdf_path, output, language),
+        SearchablePdfTool::Tesseract => run_tesseract(pdf_path, output, language),
+    }
+}
+
+fn run_ocrmypdf(pdf_path: &Path, output: &Path, language: &str) -> Result<(), String> {
+    let result = Command::new("ocrmypdf")
+        .args(["--skip-text", "--quiet", "--output-type", "pdf"])
+        .args(["-l", language])
+        .arg(pdf_path)
+        .arg(output)
+        .output()
+        .map_err(|e| format!("Failed to run ocrmypdf: {}", e))?;
+    if !result.status.success() {
+        return Err(format!(
+            "ocrmypdf failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn run_tesseract(pdf_path: &Path, output: &Path, language: &str) -> Result<(), String> {
+    let page_count = TextExtractor::new()
+        .get_pdf_page_count(pdf_path)
+        .ok_or_else(|| format!("Could not read page count of {}", pdf_path.display()))?;
+    let temp_dir = TempDir::new().map_err(|e| e.to_string())?;
+
+    let mut page_pdfs = Vec::with_capacity(page_count as usize);
+    for page in 1..=page_count {
+        let image = pdf_page_to_image(pdf_path, page, OCR_DPI, temp_dir.path())
+            .map_err(|e| format!("Page {}: {}", page, e))?;
+        let base = temp_dir.path().join(format!("ocr-{:05}", page));
+        let result = Command::new("tesseract")
+            .arg(&image)
+            .arg(&base)
+            .args(["-l", language, "--dpi", &OCR_DPI.to_string(), "pdf"])
+            .output()
+            .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+        if !result.status.success() {
+            return Err(format!(
+                "tesseract failed on page {}: {}",
+                page,
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+        let _ = std::fs::remove_file(&image);
+        page_pdfs.push(base.with_extension("pdf"));
+    }
+
+    if let [single] = page_pdfs.as_slice() {
+        return std::fs::copy(single, output)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to write {}: {}", output.display(), e));
+    }
+    let result = Command::new("pdfunite")
+        .args(&page_pdfs)
+        .arg(output)
+        .output()
+        .map_err(|e| format!("Failed to run pdfunite: {}", e))?;
+    if !result.status.success() {
+        return Err(format!(
+            "pdfunite failed: {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        ));
+    }
+    Ok(())
+}
@@ -8,18 +8,25 @@ pub mod stages;
 mod types;
 
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use tokio::sync::mpsc;
 
 use crate::analysis::AnalysisManager;
+use crate::ocr::OcrPool;
 use foia::repository::DieselDocumentRepository;
 use foia::work_queue::{ExecutionStrategy, PipelineEvent, PipelineRunner};
 
-pub use processing::{extract_document_text_per_page, ocr_document_page_with_config};
+pub use processing::{
+    extract_document_text_per_page, ocr_document_page_with_config, ocr_document_pages_with_config,
+    page_batches,
+};
 pub use stages::{OcrStage, TextExtractionStage};
 pub use types::{AnalysisEvent, AnalysisResult};
 
-use foia::config::OcrConfig;
+use foia::config::{ExtractionLimits, OcrConfig, OcrPoolConfig, PageImageConfig};
+use foia::models::PageOcrStatus;
+use foia::page_images::PageImageStore;
 
 /// Service for document analysis (MIME detection, text extraction, OCR).
 /// Default retry interval for failed analyses (hours).
@@ -29,6 +36,9 @@ pub struct AnalysisService {
     doc_repo: DieselDocumentRepository,
     analysis_manager: AnalysisManager,
     ocr_config: OcrConfig,
+    page_images: Option<PageImageStore>,
+    limits: ExtractionLimits,
+    pool: Arc<OcrPool>,
     documents_dir: PathBuf,
     retry_interval_hours: u32,
 }
@@ -41,6 +51,9 @@ impl AnalysisService {
             doc_repo,
             analysis_manager: AnalysisManager::with_defaults(),
             ocr_config: OcrConfig::default(),
+            page_images: None,
+            limits: ExtractionLimits::default(),
+            pool: Arc::new(OcrPool::new(&OcrPoolConfig::default())),
             documents_dir,
             retry_interval_hours: DEFAULT_RETRY_INTERVAL_HOURS,
         }
@@ -56,11 +69,34 @@ impl AnalysisService {
             doc_repo,
             analysis_manager: AnalysisManager::with_defaults(),
             ocr_config,
+            page_images: None,
+            limits: ExtractionLimits::default(),
+            pool: Arc::new(OcrPool::new(&OcrPoolConfig::default())),
             documents_dir,
             retry_interval_hours: DEFAULT_RETRY_INTERVAL_HOURS,
         }
     }
 
+    /// Render page images during OCR, unless `config` disables it.
+    pub fn with_page_images(mut self, config: &PageImageConfig) -> Self {
+        self.page_images = config
+            .enabled
+            .then(|| PageImageStore::new(&self.documents_dir, config));
+        self
+    }
+
+    /// Extract and render pages within `limits` instead of the defaults.
+    pub fn with_limits(mut self, limits: &ExtractionLimits) -> Self {
+        self.limits = limits.clone();
+        self
+    }
+
+    /// Size the local OCR process pool as configured.
+    pub fn with_pool(mut self, config: &OcrPoolConfig) -> Self {
+        self.pool = Arc::new(OcrPool::new(config));
+        self
+    }
+
     /// Set the retry interval for failed analyses.
     pub fn with_retry_interval(mut self, hours: u32) -> Self {
         self.retry_interval_hours = hours;
@@ -147,14 +183,18 @@ impl AnalysisService {
             mime_type,
             self.retry_interval_hours,
             workers,
-        );
+        )
+        .with_limits(self.limits.clone());
 
         let ocr_stage = OcrStage::new(
             self.doc_repo.clone(),
             self.ocr_config.clone(),
             self.documents_dir.clone(),
             workers,
-        );
+        )
+        .with_page_images(self.page_images.clone())
+        .with_limits(self.limits.clone())
+        .with_pool(self.pool.clone());
 
         let mut runner = PipelineRunner::new(effective_chunk, limit);
         runner.add_stage(Box::new(text_stage));
@@ -185,9 +225,7 @@ impl AnalysisService {
             .await
         {
             Ok(count) if count > 0 => {
-                tracing::info!(
-                    "Backfilled {count} analysis completion rows for '{analysis_type}'"
-                );
+                tracing::info!("Backfilled {count} analysis completion rows for '{analysis_type}'");
             }
             Ok(_) => {}
             Err(e) => {
@@ -291,10 +329,11 @@ impl AnalysisService {
         let doc_clone = doc.clone();
         let doc_id_owned = doc_id.to_string();
         let documents_dir = self.documents_dir.clone();
+        let limits = self.limits.clone();
 
         let pages = tokio::task::spawn_blocking(move || {
             let handle = tokio::runtime::Handle::current();
-            extract_document_text_per_page(&doc_clone, &doc_repo, &handle, &documents_dir)
+            extract_document_text_per_page(&doc_clone, &doc_repo, &handle, &documents_dir, &limits)
         })
         .await??;
 
@@ -310,6 +349,71 @@ impl AnalysisService {
 
         Ok(())
     }
+
+    /// Extract text from a document and OCR its pages with the configured
+    /// backends, without printing. Returns how many pages were OCR'd.
+    ///
+    /// Used by queue workers, which handle one document per task.
+    pub async fn ocr_document(&self, doc_id: &str) -> anyhow::Result<usize> {
+        let doc = self
+            .doc_repo
+            .get(doc_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Document not found: {}", doc_id))?;
+        let version_id = doc
+            .current_version()
+            .map(|v| v.id as i32)
+            .ok_or_else(|| anyhow::anyhow!("Document has no versions: {}", doc_id))?;
+
+        let doc_repo = self.doc_repo.clone();
+        let documents_dir = self.documents_dir.clone();
+        let limits = self.limits.clone();
+        tokio::task::spawn_blocking(move || {
+            let handle = tokio::runtime::Handle::current();
+            extract_document_text_per_page(&doc, &doc_repo, &handle, &documents_dir, &limits)
+        })
+        .await??;
+
+        let pages: Vec<_> = self
+            .doc_repo
+            .get_pages(doc_id, version_id)
+            .await?
+            .into_iter()
+            .filter(|page| {
+                matches!(
+                    page.ocr_status,
+                    PageOcrStatus::Pending | PageOcrStatus::TextExtracted
+                )
+            })
+            .collect();
+        let count = pages.len();
+        for batch in page_batches(pages, self.pool.batch_pages()) {
+            let doc_repo = self.doc_repo.clone();
+            let ocr_config = self.ocr_config.clone();
+            let page_images = self.page_images.clone();
+            let documents_dir = self.documents_dir.clone();
+            let limits = self.limits.clone();
+            let pool = self.pool.clone();
+            let results = tokio::task::spawn_blocking(move || {
+                let handle = tokio::runtime::Handle::current();
+                ocr_document_pages_with_config(
+                    &batch,
+                    &doc_repo,
+                    &handle,
+                    &ocr_config,
+                    page_images.as_ref(),
+                    &documents_dir,
+                    &limits,
+                    &pool,
+                )
+            })
+            .await?;
+            for result in results {
+                result?;
+            }
+        }
+        Ok(count)
+    }
 }
 
 /// Bridge generic `PipelineEvent`s to domain-specific `AnalysisEvent`s.
@@ -324,7 +428,10 @@ async fn bridge_pipeline_to_analysis_events(
 
     while let Some(event) = pipe_rx.recv().await {
         match event {
-            PipelineEvent::StageStarted { ref stage, total_items } => {
+            PipelineEvent::StageStarted {
+                ref stage,
+                total_items,
+            } => {
                 if stage == "Text extraction" {
                     let _ = event_tx
                         .send(AnalysisEvent::Phase1Started {
@@ -339,7 +446,11 @@ async fn bridge_pipeline_to_analysis_events(
                         .await;
                 }
             }
-            PipelineEvent::ItemStarted { ref stage, ref item_id, ref label } => {
+            PipelineEvent::ItemStarted {
+                ref stage,
+                ref item_id,
+                ref label,
+            } => {
                 if stage == "Text extraction" {
                     let _ = event_tx
                         .send(AnalysisEvent::DocumentStarted {
@@ -366,7 +477,11 @@ async fn bridge_pipeline_to_analysis_events(
                         .await;
                 }
             }
-            PipelineEvent::ItemCompleted { ref stage, ref item_id, ref detail } => {
+            PipelineEvent::ItemCompleted {
+                ref stage,
+                ref item_id,
+                ref detail,
+            } => {
                 if stage == "Text extraction" {
                     let pages = detail
                         .as_deref()
@@ -409,7 +524,10 @@ async fn bridge_pipeline_to_analysis_events(
                     }
                 }
             }
-            PipelineEvent::ItemSkipped { ref stage, ref item_id } => {
+            PipelineEvent::ItemSkipped {
+                ref stage,
+                ref item_id,
+            } => {
                 if stage == "Text extraction" {
                     result.phase1_skipped_missing += 1;
                     let _ = event_tx
@@ -438,7 +556,11 @@ async fn bridge_pipeline_to_analysis_events(
                         .await;
                 }
             }
-            PipelineEvent::ItemFailed { ref stage, ref item_id, ref error } => {
+            PipelineEvent::ItemFailed {
+                ref stage,
+                ref item_id,
+                ref error,
+            } => {
                 if stage == "Text extraction" {
                     result.phase1_failed += 1;
                     let _ = event_tx
@@ -467,7 +589,13 @@ async fn bridge_pipeline_to_analysis_events(
                         .await;
                 }
             }
-            PipelineEvent::StageCompleted { ref stage, succeeded, failed, skipped, .. } => {
+            PipelineEvent::StageCompleted {
+                ref stage,
+                succeeded,
+                failed,
+                skipped,
+                ..
+            } => {
                 if stage == "Text extraction" {
                     let _ = event_tx
                         .send(AnalysisEvent::Phase1Complete {
@@ -3,10 +3,19 @@
 use std::fs::File;
 use std::io::Read;
 
-use crate::ocr::{BackendConfig, FallbackOcrBackend, OcrBackend, TextExtractor};
-use foia::config::OcrConfig;
-use foia::models::{Document, DocumentPage, PageOcrStatus};
+use crate::ocr::{
+    compute_file_hash, quality_score, render_page, render_pages, BackendConfig, FallbackOcrBackend,
+    OcrBackend, OcrBackendType, OcrError, OcrPool, OcrResult, RenderedPage, TesseractBackend,
+    TextExtractor, OCR_DPI, REMEDIATION_DPI,
+};
+use crate::services::pdf_metadata::PDF_METADATA_ANALYSIS;
+use foia::config::{BackendEntry, ExtractionLimits, OcrConfig};
+use foia::metrics;
+use foia::models::{Document, DocumentPage, PageOcrStatus, POOR_OCR_QUALITY};
+use foia::page_images::PageImageStore;
+use foia::repository::diesel_document::OcrRunSettings;
 use foia::repository::DieselDocumentRepository;
+use foia::utils::{read_pdf_metadata, sniff_mime_type};
 
 use super::types::PageOcrResult;
 
@@ -14,10 +23,7 @@ use super::types::PageOcrResult;
 ///
 /// Returns `Some((detected_mime, old_mime))` if they differ meaningfully, `None` otherwise.
 /// Reads the first 8KB of the file for magic-byte detection.
-pub fn detect_mime_mismatch(
-    path: &std::path::Path,
-    stored_mime: &str,
-) -> Option<(String, String)> {
+pub fn detect_mime_mismatch(path: &std::path::Path, stored_mime: &str) -> Option<(String, String)> {
     let mut file = File::open(path).ok()?;
     let mut buffer = [0u8; 8192];
     let bytes_read = file.read(&mut buffer).ok()?;
@@ -26,41 +32,68 @@ pub fn detect_mime_mismatch(
         return None;
     }
 
-    let detected = infer::get(&buffer[..bytes_read])?;
-    let detected_mime = detected.mime_type();
-
+    let detected = sniff_mime_type(&buffer[..bytes_read], stored_mime)?;
     let stored_normalized = stored_mime
         .split(';')
         .next()
         .unwrap_or(stored_mime)
         .trim()
         .to_lowercase();
+    Some((detected.to_string(), stored_normalized))
+}
 
-    if detected_mime != stored_normalized {
-        if stored_normalized == "application/octet-stream"
-            || stored_normalized == "binary/octet-stream"
-        {
-            return Some((detected_mime.to_string(), stored_normalized));
-        }
-
-        let stored_base = stored_normalized.split('/').next().unwrap_or("");
-        let detected_base = detected_mime.split('/').next().unwrap_or("");
-
-        if stored_base != detected_base {
-            return Some((detected_mime.to_string(), stored_normalized));
+/// Read a PDF's embedded metadata and record it, with a `pdf_metadata`
+/// analysis result so `analyze-pdf-metadata` skips it. Failures are only
+/// logged; the backfill retries them.
+fn record_pdf_metadata(
+    doc: &Document,
+    version_id: i32,
+    file_path: &std::path::Path,
+    doc_repo: &DieselDocumentRepository,
+    handle: &tokio::runtime::Handle,
+) {
+    let metadata = match read_pdf_metadata(file_path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            tracing::debug!("No PDF metadata for document {}: {}", doc.id, e);
+            return;
         }
+    };
+    if let Err(e) = handle.block_on(doc_repo.set_pdf_metadata(&doc.id, &metadata)) {
+        tracing::warn!(
+            "Failed to store PDF metadata for document {}: {}",
+            doc.id,
+            e
+        );
+        return;
     }
-
-    None
+    let _ = handle.block_on(doc_repo.store_analysis_result_for_document(
+        &doc.id,
+        version_id,
+        PDF_METADATA_ANALYSIS,
+        "pdfinfo",
+        None,
+        metadata.producer.as_deref(),
+        None,
+        None,
+        None,
+        None,
+    ));
 }
 
 /// Extract text from a document per-page using pdftotext.
 /// This function runs in a blocking context and uses the runtime handle to call async methods.
+///
+/// PDF pages are extracted and saved `limits.page_batch` at a time, so a
+/// document with thousands of pages never has all its text in memory, and
+/// a run interrupted part way resumes after the last saved page. A page
+/// whose text can't be extracted is saved without it, for OCR to try.
 pub fn extract_document_text_per_page(
     doc: &Document,
     doc_repo: &DieselDocumentRepository,
     handle: &tokio::runtime::Handle,
     documents_dir: &std::path::Path,
+    limits: &ExtractionLimits,
 ) -> anyhow::Result<usize> {
     let extractor = TextExtractor::new();
 
@@ -122,9 +155,10 @@ pub fn extract_document_text_per_page(
         handle.block_on(doc_repo.set_version_page_count(version.id, page_count))?;
     }
 
-    // Skip if pages already exist for this version (text extraction already done)
+    // Skip if every page already exists for this version (text extraction
+    // already done); resume after the last saved page otherwise
     let existing_pages = handle.block_on(doc_repo.count_pages(&doc.id, version.id as i32))?;
-    if existing_pages > 0 {
+    if existing_pages >= page_count {
         tracing::debug!(
             "Document {} already has {} pages, skipping text extraction",
             doc.id,
@@ -133,38 +167,49 @@ pub fn extract_document_text_per_page(
         return Ok(0);
     }
 
-    // Extract all pages in a single pdftotext call, split on form-feed
-    let page_texts = extractor
-        .extract_all_pdf_page_texts(&file_path, page_count)
-        .unwrap_or_default();
-
-    let actual_pages = if page_texts.is_empty() {
-        page_count as usize
-    } else {
-        page_texts.len()
-    };
-
-    // Build all page records in memory
-    let mut pages = Vec::with_capacity(actual_pages);
-    for (i, pdf_text) in page_texts.iter().enumerate() {
-        let page_num = (i + 1) as u32;
-        let mut page = DocumentPage::new(doc.id.clone(), version.id, page_num);
-        page.pdf_text = Some(pdf_text.clone());
-        page.ocr_status = PageOcrStatus::TextExtracted;
-        pages.push(page);
+    // Embedded metadata is read once, before the first pages
+    if existing_pages == 0 {
+        record_pdf_metadata(doc, version.id as i32, &file_path, doc_repo, handle);
     }
 
-    // Bulk insert all pages at once
-    if !pages.is_empty() {
+    let batch = limits.page_batch.max(1);
+    let mut saved = 0;
+    let mut first = existing_pages + 1;
+    while first <= page_count {
+        // Pages saved so far are kept; the rest are picked up next run
+        if foia::shutdown::is_requested() {
+            break;
+        }
+        let last = first.saturating_add(batch - 1).min(page_count);
+        let texts = extractor.extract_pdf_page_range_texts(
+            &file_path,
+            first,
+            last,
+            limits.render_timeout(),
+        );
+        let pages: Vec<DocumentPage> = texts
+            .into_iter()
+            .zip(first..)
+            .map(|(pdf_text, page_num)| {
+                let mut page = DocumentPage::new(doc.id.clone(), version.id, page_num);
+                page.pdf_text = pdf_text;
+                page.ocr_status = PageOcrStatus::TextExtracted;
+                page
+            })
+            .collect();
+
         tracing::debug!(
-            "Saving {} pages to database for document {}",
-            pages.len(),
+            "Saving pages {}-{} to database for document {}",
+            first,
+            last,
             doc.id
         );
         handle.block_on(doc_repo.save_pages_batch(&pages))?;
+        saved += pages.len();
+        first = last + 1;
     }
 
-    Ok(pages.len())
+    Ok(saved)
 }
 
 /// Run OCR on a page and compare with existing text.
@@ -181,7 +226,15 @@ pub fn ocr_document_page(
     handle: &tokio::runtime::Handle,
     documents_dir: &std::path::Path,
 ) -> anyhow::Result<PageOcrResult> {
-    ocr_document_page_with_config(page, doc_repo, handle, &OcrConfig::default(), documents_dir)
+    ocr_document_page_with_config(
+        page,
+        doc_repo,
+        handle,
+        &OcrConfig::default(),
+        None,
+        documents_dir,
+        &ExtractionLimits::default(),
+    )
 }
 
 /// Run OCR on a page using configured backend entries.
@@ -193,38 +246,330 @@ pub fn ocr_document_page(
 /// Example config: `["tesseract", ["groq", "gemini"]]`
 /// - Runs tesseract, stores as "tesseract"
 /// - Runs groq (falls back to gemini if rate limited), stores as "groq" or "gemini"
+///
+/// With `page_images`, the page is also rendered for the document viewer.
+///
+/// The page is rendered once within `limits` and the image shared by every
+/// backend. A page that can't be rendered in time or space is marked failed.
 pub fn ocr_document_page_with_config(
     page: &DocumentPage,
     doc_repo: &DieselDocumentRepository,
     handle: &tokio::runtime::Handle,
     ocr_config: &OcrConfig,
+    page_images: Option<&PageImageStore>,
     documents_dir: &std::path::Path,
+    limits: &ExtractionLimits,
 ) -> anyhow::Result<PageOcrResult> {
-    let extractor = TextExtractor::new();
+    let source = PageSource::load(page, doc_repo, handle, documents_dir)?;
+
+    // Render once for deduplication and every backend
+    let temp_dir = tempfile::TempDir::new()?;
+    let rendered = render_page(
+        &source.file_path,
+        page.page_number,
+        OCR_DPI,
+        temp_dir.path(),
+        limits,
+    )
+    .map_err(|e| log_render_failure(page, &e))
+    .ok();
+    let image_hash = rendered
+        .as_ref()
+        .and_then(|r| compute_file_hash(&r.path).ok());
+
+    let chains = backend_chains(ocr_config);
+    ocr_rendered_page(
+        page,
+        &source,
+        rendered.as_ref(),
+        image_hash,
+        None,
+        &chains,
+        doc_repo,
+        handle,
+        page_images,
+        limits,
+    )
+}
 
-    // Get the document to find the file path
-    let doc = handle
-        .block_on(doc_repo.get(&page.document_id))?
-        .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+/// Run OCR on consecutive pages of one document version, as
+/// [`ocr_document_page_with_config`] does for each, using a slot from
+/// `pool`.
+///
+/// The pages are rendered by one pdftoppm run into the slot's scratch
+/// directory and, for entries led by Tesseract, recognized by one Tesseract
+/// run. Results are in page order.
+#[allow(clippy::too_many_arguments)]
+pub fn ocr_document_pages_with_config(
+    pages: &[DocumentPage],
+    doc_repo: &DieselDocumentRepository,
+    handle: &tokio::runtime::Handle,
+    ocr_config: &OcrConfig,
+    page_images: Option<&PageImageStore>,
+    documents_dir: &std::path::Path,
+    limits: &ExtractionLimits,
+    pool: &OcrPool,
+) -> Vec<anyhow::Result<PageOcrResult>> {
+    let (Some(first), Some(last)) = (pages.first(), pages.last()) else {
+        return Vec::new();
+    };
+    let fail_all = |e: &dyn std::fmt::Display| -> Vec<anyhow::Result<PageOcrResult>> {
+        pages
+            .iter()
+            .map(|_| Err(anyhow::anyhow!("{}", e)))
+            .collect()
+    };
+    let source = match PageSource::load(first, doc_repo, handle, documents_dir) {
+        Ok(source) => source,
+        Err(e) => return fail_all(&e),
+    };
+    let slot = match pool.checkout() {
+        Ok(slot) => slot,
+        Err(e) => return fail_all(&e),
+    };
 
-    let version = doc
-        .versions
+    let renders = render_pages(
+        &source.file_path,
+        first.page_number,
+        last.page_number,
+        OCR_DPI,
+        slot.path(),
+        limits,
+    );
+    let rendered: Vec<Option<RenderedPage>> = pages
         .iter()
-        .find(|v| v.id == page.version_id)
-        .ok_or_else(|| anyhow::anyhow!("Version not found"))?;
+        .map(|page| {
+            let index = page.page_number.checked_sub(first.page_number)? as usize;
+            match renders.get(index)? {
+                Ok(rendered) => Some(rendered.clone()),
+                Err(e) => {
+                    log_render_failure(page, e);
+                    None
+                }
+            }
+        })
+        .collect();
+    let image_hashes: Vec<Option<String>> = rendered
+        .iter()
+        .map(|r| r.as_ref().and_then(|r| compute_file_hash(&r.path).ok()))
+        .collect();
 
-    let file_path = version.resolve_path(documents_dir, &doc.source_url, &doc.title);
+    let mut tesseract_results = if ocr_config
+        .backends
+        .iter()
+        .any(|entry| entry.primary() == TESSERACT)
+    {
+        tesseract_batch(
+            &rendered,
+            &image_hashes,
+            ocr_config,
+            doc_repo,
+            handle,
+            slot.path(),
+        )
+    } else {
+        vec![None; pages.len()]
+    };
+
+    let chains = backend_chains(ocr_config);
+    pages
+        .iter()
+        .enumerate()
+        .map(|(i, page)| {
+            ocr_rendered_page(
+                page,
+                &source,
+                rendered[i].as_ref(),
+                image_hashes[i].clone(),
+                tesseract_results[i].take(),
+                &chains,
+                doc_repo,
+                handle,
+                page_images,
+                limits,
+            )
+        })
+        .collect()
+}
+
+/// Split `pages` into runs of at most `batch_pages` consecutive pages of
+/// one document version, for [`ocr_document_pages_with_config`].
+pub fn page_batches(mut pages: Vec<DocumentPage>, batch_pages: u32) -> Vec<Vec<DocumentPage>> {
+    pages.sort_by(|a, b| {
+        (&a.document_id, a.version_id, a.page_number).cmp(&(
+            &b.document_id,
+            b.version_id,
+            b.page_number,
+        ))
+    });
+    let mut batches: Vec<Vec<DocumentPage>> = Vec::new();
+    for page in pages {
+        match batches.last_mut() {
+            Some(batch)
+                if batch.len() < batch_pages.max(1) as usize
+                    && batch.last().is_some_and(|prev| {
+                        prev.document_id == page.document_id
+                            && prev.version_id == page.version_id
+                            && prev.page_number + 1 == page.page_number
+                    }) =>
+            {
+                batch.push(page)
+            }
+            _ => batches.push(vec![page]),
+        }
+    }
+    batches
+}
+
+/// Backend name of the Tesseract backend, whose pages are batched.
+const TESSERACT: &str = "tesseract";
+
+/// Where a page's document version is stored.
+struct PageSource {
+    file_path: std::path::PathBuf,
+    content_hash: String,
+}
+
+impl PageSource {
+    fn load(
+        page: &DocumentPage,
+        doc_repo: &DieselDocumentRepository,
+        handle: &tokio::runtime::Handle,
+        documents_dir: &std::path::Path,
+    ) -> anyhow::Result<Self> {
+        let doc = handle
+            .block_on(doc_repo.get(&page.document_id))?
+            .ok_or_else(|| anyhow::anyhow!("Document not found"))?;
+
+        let version = doc
+            .versions
+            .iter()
+            .find(|v| v.id == page.version_id)
+            .ok_or_else(|| anyhow::anyhow!("Version not found"))?;
+
+        Ok(Self {
+            file_path: version.resolve_path(documents_dir, &doc.source_url, &doc.title),
+            content_hash: version.content_hash.clone(),
+        })
+    }
+}
+
+fn log_render_failure(page: &DocumentPage, e: &OcrError) {
+    tracing::warn!(
+        "Cannot render page {} of {}: {}",
+        page.page_number,
+        page.document_id,
+        e
+    );
+}
+
+/// Each configured entry with its backend chain, built once and shared by
+/// the pages they're run on.
+fn backend_chains(ocr_config: &OcrConfig) -> Vec<(&BackendEntry, FallbackOcrBackend)> {
+    let backend_config = BackendConfig::with_config(crate::ocr::OcrConfig {
+        language: ocr_config.language.clone(),
+        ..Default::default()
+    });
+    ocr_config
+        .backends
+        .iter()
+        .map(|entry| {
+            let fallback =
+                FallbackOcrBackend::from_names(&entry.backends(), backend_config.clone());
+            (entry, fallback)
+        })
+        .collect()
+}
 
-    // Compute image hash once for deduplication across all backends
-    let image_hash = extractor
-        .get_pdf_page_hash(&file_path, page.page_number)
-        .ok();
+/// Recognize the rendered pages in one Tesseract run, skipping pages whose
+/// image already has a Tesseract result. Pages without a result here are
+/// left to the per-page path, including every page if the run fails.
+fn tesseract_batch(
+    rendered: &[Option<RenderedPage>],
+    image_hashes: &[Option<String>],
+    ocr_config: &OcrConfig,
+    doc_repo: &DieselDocumentRepository,
+    handle: &tokio::runtime::Handle,
+    scratch: &std::path::Path,
+) -> Vec<Option<OcrResult>> {
+    let mut results = vec![None; rendered.len()];
+    let backend = TesseractBackend::with_config(crate::ocr::OcrConfig {
+        language: ocr_config.language.clone(),
+        ..Default::default()
+    });
+    if !backend.is_available() {
+        return results;
+    }
+
+    let wanted: Vec<usize> = (0..rendered.len())
+        .filter(|&i| rendered[i].is_some())
+        .filter(|&i| {
+            image_hashes[i].as_ref().is_none_or(|hash| {
+                !matches!(
+                    handle.block_on(doc_repo.find_ocr_result_by_image_hash(hash, TESSERACT)),
+                    Ok(Some(_))
+                )
+            })
+        })
+        .collect();
+    if wanted.is_empty() {
+        return results;
+    }
+
+    let images: Vec<&std::path::Path> = wanted
+        .iter()
+        .filter_map(|&i| rendered[i].as_ref().map(|r| r.path.as_path()))
+        .collect();
+    let start = std::time::Instant::now();
+    let texts = match backend.run_ocr_batch(&images, scratch) {
+        Ok(texts) => texts,
+        Err(e) => {
+            tracing::debug!("Batched tesseract run failed, falling back per page: {}", e);
+            return results;
+        }
+    };
+    let per_page_ms = start.elapsed().as_millis() as u64 / wanted.len() as u64;
+
+    for (&i, (text, confidence)) in wanted.iter().zip(texts) {
+        results[i] = Some(OcrResult {
+            text,
+            confidence,
+            backend: OcrBackendType::Tesseract,
+            model: None,
+            processing_time_ms: per_page_ms,
+            tool_version: backend.tool_version(),
+            language: backend.language(),
+            dpi: rendered[i].as_ref().map(|r| r.dpi),
+        });
+    }
+    results
+}
+
+/// Run the configured backend `chains` on a rendered page and save the
+/// best result, finalizing the document once all its pages are done.
+/// `tesseract` is a result already produced for the page by a batched
+/// Tesseract run.
+#[allow(clippy::too_many_arguments)]
+fn ocr_rendered_page(
+    page: &DocumentPage,
+    source: &PageSource,
+    rendered: Option<&RenderedPage>,
+    image_hash: Option<String>,
+    mut tesseract: Option<OcrResult>,
+    chains: &[(&BackendEntry, FallbackOcrBackend)],
+    doc_repo: &DieselDocumentRepository,
+    handle: &tokio::runtime::Handle,
+    page_images: Option<&PageImageStore>,
+    limits: &ExtractionLimits,
+) -> anyhow::Result<PageOcrResult> {
+    let file_path = &source.file_path;
 
     let mut updated_page = page.clone();
     let mut improved = false;
     let mut any_succeeded = false;
     let mut best_text: Option<String> = None;
     let mut best_char_count = 0usize;
+    let mut best_quality: Option<f32> = None;
 
     let pdf_chars = page
         .pdf_text
@@ -233,7 +578,7 @@ pub fn ocr_document_page_with_config(
         .unwrap_or(0);
 
     // Process each backend entry
-    for entry in &ocr_config.backends {
+    for (entry, fallback) in chains {
         let backend_names: Vec<&str> = entry.backends();
 
         // Check for existing result from any backend in this entry
@@ -253,6 +598,9 @@ pub fn ocr_document_page_with_config(
             // Reuse existing result
             let ocr_text = existing_result.text.clone().unwrap_or_default();
             let ocr_chars = ocr_text.chars().filter(|c| !c.is_whitespace()).count();
+            let quality = existing_result
+                .quality_score
+                .or_else(|| quality_score(&ocr_text, existing_result.confidence));
 
             // Store reference for this page
             handle.block_on(doc_repo.store_page_ocr_result(
@@ -261,8 +609,10 @@ pub fn ocr_document_page_with_config(
                 existing_result.model.as_deref(),
                 Some(&ocr_text),
                 existing_result.confidence,
+                quality,
                 existing_result.processing_time_ms,
                 image_hash.as_deref(),
+                &OcrRunSettings::from(&existing_result),
             ))?;
 
             tracing::debug!(
@@ -275,13 +625,59 @@ pub fn ocr_document_page_with_config(
             if ocr_chars > best_char_count {
                 best_char_count = ocr_chars;
                 best_text = Some(ocr_text);
+                best_quality = quality;
             }
-        } else {
-            // Run OCR with this entry (single backend or fallback chain)
-            let fallback = FallbackOcrBackend::from_names(&backend_names, BackendConfig::default());
+        } else if let Some(rendered) = rendered {
+            // Run OCR with this entry (single backend or fallback chain),
+            // unless a batched Tesseract run already has
+            let batched = if entry.primary() == TESSERACT {
+                tesseract.take()
+            } else {
+                None
+            };
+
+            match batched.map_or_else(|| ocr_rendered(fallback, rendered), Ok) {
+                Ok(mut result) => {
+                    let mut quality = quality_score(&result.text, result.confidence);
+
+                    // Poorly recognized pages get one more local pass at a
+                    // higher resolution, keeping whichever scores better
+                    if quality.is_some_and(|q| q < POOR_OCR_QUALITY)
+                        && !result.backend.is_deferred()
+                        && result.dpi.is_some_and(|dpi| dpi < REMEDIATION_DPI)
+                    {
+                        let retry_dir = tempfile::TempDir::new()?;
+                        let retry = render_page(
+                            file_path,
+                            page.page_number,
+                            REMEDIATION_DPI,
+                            retry_dir.path(),
+                            limits,
+                        )
+                        .and_then(|retry| {
+                            // Oversized pages may not go any higher
+                            if retry.dpi > rendered.dpi {
+                                ocr_rendered(fallback, &retry)
+                            } else {
+                                Err(OcrError::OcrFailed("already at the size limit".to_string()))
+                            }
+                        });
+                        if let Ok(retry) = retry {
+                            let retry_quality = quality_score(&retry.text, retry.confidence);
+                            tracing::debug!(
+                                "Re-ran OCR for page {} at {} DPI: quality {:?} -> {:?}",
+                                page.page_number,
+                                REMEDIATION_DPI,
+                                quality,
+                                retry_quality
+                            );
+                            if retry_quality > quality {
+                                result = retry;
+                                quality = retry_quality;
+                            }
+                        }
+                    }
 
-            match fallback.ocr_pdf_page(&file_path, page.page_number) {
-                Ok(result) => {
                     let ocr_text = result.text;
                     let backend_name = result.backend.as_str();
                     let ocr_chars = ocr_text.chars().filter(|c| !c.is_whitespace()).count();
@@ -293,8 +689,10 @@ pub fn ocr_document_page_with_config(
                         result.model.as_deref(),
                         Some(&ocr_text),
                         result.confidence,
+                        quality,
                         Some(result.processing_time_ms as i32),
                         image_hash.as_deref(),
+                        &result.run_settings(),
                     ))?;
 
                     tracing::debug!(
@@ -308,6 +706,7 @@ pub fn ocr_document_page_with_config(
                     if ocr_chars > best_char_count {
                         best_char_count = ocr_chars;
                         best_text = Some(ocr_text);
+                        best_quality = quality;
                     }
                 }
                 Err(e) => {
@@ -327,6 +726,7 @@ pub fn ocr_document_page_with_config(
         improved = best_char_count > pdf_chars + (pdf_chars / 5);
         updated_page.ocr_text = Some(text.clone());
         updated_page.ocr_status = PageOcrStatus::OcrComplete;
+        updated_page.ocr_quality = best_quality;
         updated_page.final_text = if best_char_count > 0 {
             Some(text)
         } else {
@@ -335,14 +735,28 @@ pub fn ocr_document_page_with_config(
     } else if any_succeeded {
         // All results were empty
         updated_page.ocr_status = PageOcrStatus::OcrComplete;
+        updated_page.ocr_quality = None;
         updated_page.final_text = page.pdf_text.clone();
     } else {
         // All backends failed
         updated_page.ocr_status = PageOcrStatus::Failed;
+        updated_page.ocr_quality = None;
         updated_page.final_text = page.pdf_text.clone();
     }
 
     handle.block_on(doc_repo.save_page(&updated_page))?;
+    metrics::OCR_PAGES.inc(&[updated_page.ocr_status.as_str()]);
+
+    if let Some(store) = page_images {
+        if let Err(e) = store.get_or_render(file_path, &source.content_hash, page.page_number) {
+            tracing::debug!(
+                "Could not render page {} of {}: {}",
+                page.page_number,
+                page.document_id,
+                e
+            );
+        }
+    }
 
     // Check if all pages for this document are now complete
     let mut document_finalized = false;
@@ -379,3 +793,13 @@ pub fn ocr_document_page_with_config(
         document_finalized,
     })
 }
+
+/// OCR a rendered page image, recording the resolution it was rendered at.
+fn ocr_rendered(
+    backend: &FallbackOcrBackend,
+    rendered: &RenderedPage,
+) -> Result<OcrResult, OcrError> {
+    let mut result = backend.ocr_image(&rendered.path)?;
+    result.dpi = Some(rendered.dpi);
+    Ok(result)
+}
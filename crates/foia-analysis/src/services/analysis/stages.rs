@@ -7,18 +7,21 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use tokio::sync::{mpsc, Mutex};
 
-use foia::config::OcrConfig;
+use foia::config::{ExtractionLimits, OcrConfig, OcrPoolConfig};
+use foia::models::DocumentPage;
+use foia::page_images::PageImageStore;
 use foia::repository::DieselDocumentRepository;
 use foia::work_queue::db_analysis::DbAnalysisQueue;
 use foia::work_queue::{
-    ChunkResult, PipelineError, PipelineEvent, PipelineStage, WorkFilter, WorkQueue,
-    WorkQueueError,
+    ChunkResult, ClaimHeartbeat, PipelineError, PipelineEvent, PipelineStage, WorkFilter,
+    WorkQueue, WorkQueueError,
 };
 
-use crate::ocr::OcrBackendType;
 use super::processing::{
-    detect_mime_mismatch, extract_document_text_per_page, ocr_document_page_with_config,
+    detect_mime_mismatch, extract_document_text_per_page, ocr_document_pages_with_config,
+    page_batches,
 };
+use crate::ocr::{OcrBackendType, OcrPool};
 
 /// Text extraction stage (Phase 0 MIME check + Phase 1 extraction merged).
 ///
@@ -32,6 +35,7 @@ pub struct TextExtractionStage {
     documents_dir: PathBuf,
     filter: WorkFilter,
     workers: usize,
+    limits: ExtractionLimits,
     cursor: Mutex<Option<String>>,
 }
 
@@ -58,9 +62,16 @@ impl TextExtractionStage {
             documents_dir,
             filter,
             workers,
+            limits: ExtractionLimits::default(),
             cursor: Mutex::new(None),
         }
     }
+
+    /// Extract within `limits` instead of the defaults.
+    pub fn with_limits(mut self, limits: ExtractionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
 }
 
 #[async_trait]
@@ -113,13 +124,15 @@ impl PipelineStage for TextExtractionStage {
         let stage_name = self.name().to_string();
 
         for doc in &docs {
+            // Stop taking items on shutdown; claimed ones finish
+            if foia::shutdown::is_requested() {
+                break;
+            }
             // Inline MIME check (was Phase 0)
             if let Some(version) = doc.current_version() {
-                let path =
-                    version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
+                let path = version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
                 if path.exists() {
-                    if let Some((detected, _old)) =
-                        detect_mime_mismatch(&path, &version.mime_type)
+                    if let Some((detected, _old)) = detect_mime_mismatch(&path, &version.mime_type)
                     {
                         let _ = self
                             .doc_repo
@@ -157,16 +170,22 @@ impl PipelineStage for TextExtractionStage {
             };
             // Consume immediately — analysis pipeline manages its own result storage
             let _ = self.queue.complete(work_handle).await;
+            // Hold the claim for as long as extraction takes
+            let heartbeat = doc
+                .current_version()
+                .map(|v| ClaimHeartbeat::start(self.doc_repo.clone(), &doc.id, v.id as i32, "ocr"));
 
             let doc = doc.clone();
             let doc_repo = self.doc_repo.clone();
             let documents_dir = self.documents_dir.clone();
+            let limits = self.limits.clone();
             let succeeded = succeeded.clone();
             let failed = failed.clone();
             let event_tx = event_tx.clone();
             let stage_name = stage_name.clone();
 
             let handle = tokio::task::spawn_blocking(move || {
+                let _heartbeat = heartbeat;
                 let doc_id = doc.id.clone();
                 let title = doc.title.clone();
 
@@ -178,7 +197,13 @@ impl PipelineStage for TextExtractionStage {
 
                 let rt_handle = tokio::runtime::Handle::current();
 
-                match extract_document_text_per_page(&doc, &doc_repo, &rt_handle, &documents_dir) {
+                match extract_document_text_per_page(
+                    &doc,
+                    &doc_repo,
+                    &rt_handle,
+                    &documents_dir,
+                    &limits,
+                ) {
                     Ok(page_count) => {
                         succeeded.fetch_add(1, Ordering::Relaxed);
                         let _ = futures::executor::block_on(event_tx.send(
@@ -239,8 +264,11 @@ impl PipelineStage for TextExtractionStage {
 pub struct OcrStage {
     doc_repo: DieselDocumentRepository,
     ocr_config: OcrConfig,
+    page_images: Option<PageImageStore>,
     documents_dir: PathBuf,
     workers: usize,
+    limits: ExtractionLimits,
+    pool: Arc<OcrPool>,
     deferred: bool,
 }
 
@@ -258,8 +286,7 @@ impl OcrStage {
             .map(|entry| {
                 let names = entry.backends();
                 names.first().map_or(false, |name| {
-                    OcrBackendType::from_str(name)
-                        .map_or(false, |t| t.is_deferred())
+                    OcrBackendType::from_str(name).map_or(false, |t| t.is_deferred())
                 })
             })
             .unwrap_or(false);
@@ -267,11 +294,32 @@ impl OcrStage {
         Self {
             doc_repo,
             ocr_config,
+            page_images: None,
             documents_dir,
             workers,
+            limits: ExtractionLimits::default(),
+            pool: Arc::new(OcrPool::new(&OcrPoolConfig::default())),
             deferred,
         }
     }
+
+    /// Render pages within `limits` instead of the defaults.
+    pub fn with_limits(mut self, limits: ExtractionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run local OCR processes in `pool` instead of a default-sized one.
+    pub fn with_pool(mut self, pool: Arc<OcrPool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    /// Render page images for the viewer as pages are OCR'd.
+    pub fn with_page_images(mut self, store: Option<PageImageStore>) -> Self {
+        self.page_images = store;
+        self
+    }
 }
 
 #[async_trait]
@@ -315,13 +363,20 @@ impl PipelineStage for OcrStage {
         let failed = Arc::new(AtomicUsize::new(0));
         let skipped = Arc::new(AtomicUsize::new(0));
 
-        let mut handles = Vec::with_capacity(pages.len().min(self.workers));
+        let batches = page_batches(pages, self.pool.batch_pages());
+        let mut handles = Vec::with_capacity(batches.len().min(self.workers));
         let stage_name = self.name().to_string();
 
-        for page in pages {
+        for batch in batches {
+            if foia::shutdown::is_requested() {
+                break;
+            }
             let doc_repo = self.doc_repo.clone();
             let ocr_config = self.ocr_config.clone();
+            let page_images = self.page_images.clone();
             let documents_dir = self.documents_dir.clone();
+            let limits = self.limits.clone();
+            let pool = self.pool.clone();
             let succeeded = succeeded.clone();
             let failed = failed.clone();
             let skipped = skipped.clone();
@@ -329,52 +384,62 @@ impl PipelineStage for OcrStage {
             let stage_name = stage_name.clone();
 
             let handle = tokio::task::spawn_blocking(move || {
-                let item_id = format!("{}:p{}", page.document_id, page.page_number);
-
-                let _ = futures::executor::block_on(event_tx.send(PipelineEvent::ItemStarted {
-                    stage: stage_name.clone(),
-                    item_id: item_id.clone(),
-                    label: format!("page {}", page.page_number),
-                }));
+                let item_id =
+                    |page: &DocumentPage| format!("{}:p{}", page.document_id, page.page_number);
+
+                for page in &batch {
+                    let _ =
+                        futures::executor::block_on(event_tx.send(PipelineEvent::ItemStarted {
+                            stage: stage_name.clone(),
+                            item_id: item_id(page),
+                            label: format!("page {}", page.page_number),
+                        }));
+                }
 
                 let rt_handle = tokio::runtime::Handle::current();
-
-                match ocr_document_page_with_config(
-                    &page,
+                let results = ocr_document_pages_with_config(
+                    &batch,
                     &doc_repo,
                     &rt_handle,
                     &ocr_config,
+                    page_images.as_ref(),
                     &documents_dir,
-                ) {
-                    Ok(ocr_result) => {
-                        if ocr_result.improved {
-                            succeeded.fetch_add(1, Ordering::Relaxed);
-                        } else {
-                            skipped.fetch_add(1, Ordering::Relaxed);
+                    &limits,
+                    &pool,
+                );
+
+                for (page, result) in batch.iter().zip(results) {
+                    match result {
+                        Ok(ocr_result) => {
+                            if ocr_result.improved {
+                                succeeded.fetch_add(1, Ordering::Relaxed);
+                            } else {
+                                skipped.fetch_add(1, Ordering::Relaxed);
+                            }
+                            let detail = if ocr_result.document_finalized {
+                                Some("document finalized".to_string())
+                            } else {
+                                None
+                            };
+                            let _ = futures::executor::block_on(event_tx.send(
+                                PipelineEvent::ItemCompleted {
+                                    stage: stage_name.clone(),
+                                    item_id: item_id(page),
+                                    detail,
+                                },
+                            ));
+                        }
+                        Err(e) => {
+                            tracing::debug!("OCR failed for page {}: {}", page.page_number, e);
+                            failed.fetch_add(1, Ordering::Relaxed);
+                            let _ = futures::executor::block_on(event_tx.send(
+                                PipelineEvent::ItemFailed {
+                                    stage: stage_name.clone(),
+                                    item_id: item_id(page),
+                                    error: e.to_string(),
+                                },
+                            ));
                         }
-                        let detail = if ocr_result.document_finalized {
-                            Some("document finalized".to_string())
-                        } else {
-                            None
-                        };
-                        let _ = futures::executor::block_on(event_tx.send(
-                            PipelineEvent::ItemCompleted {
-                                stage: stage_name,
-                                item_id,
-                                detail,
-                            },
-                        ));
-                    }
-                    Err(e) => {
-                        tracing::debug!("OCR failed for page {}: {}", page.page_number, e);
-                        failed.fetch_add(1, Ordering::Relaxed);
-                        let _ = futures::executor::block_on(event_tx.send(
-                            PipelineEvent::ItemFailed {
-                                stage: stage_name,
-                                item_id,
-                                error: e.to_string(),
-                            },
-                        ));
                     }
                 }
             });
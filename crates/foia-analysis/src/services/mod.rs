@@ -1,4 +1,12 @@
 pub mod analysis;
+pub mod pdf_metadata;
+pub mod redaction;
+pub mod searchable_pdf;
+pub mod tables;
 
 #[allow(unused_imports)]
 pub use analysis::{AnalysisEvent, AnalysisResult, AnalysisService};
+pub use pdf_metadata::PdfMetadataService;
+pub use redaction::RedactionService;
+pub use searchable_pdf::SearchablePdfService;
+pub use tables::TableExtractionService;
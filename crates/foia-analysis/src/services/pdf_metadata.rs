@@ -0,0 +1,123 @@
+//! PDF metadata harvesting service.
+//!
+//! Finds PDFs without a `pdf_metadata` analysis result and records the
+//! author, software and dates embedded in them. New PDFs are read during
+//! text extraction; this backfills documents extracted before that.
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+use foia::models::{Document, PdfMetadata};
+use foia::repository::DieselDocumentRepository;
+use foia::utils::read_pdf_metadata;
+
+/// Analysis type recorded in `document_analysis_results`.
+pub const PDF_METADATA_ANALYSIS: &str = "pdf_metadata";
+
+const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// Hours to wait before retrying a failed read.
+const RETRY_INTERVAL_HOURS: u32 = 12;
+
+/// Service recording embedded PDF metadata.
+pub struct PdfMetadataService {
+    doc_repo: DieselDocumentRepository,
+    documents_dir: PathBuf,
+}
+
+impl PdfMetadataService {
+    pub fn new(doc_repo: DieselDocumentRepository, documents_dir: PathBuf) -> Self {
+        Self {
+            doc_repo,
+            documents_dir,
+        }
+    }
+
+    /// Count PDFs whose metadata hasn't been read.
+    pub async fn count_pending(&self, source_id: Option<&str>) -> anyhow::Result<u64> {
+        Ok(self
+            .doc_repo
+            .count_needing_analysis(
+                PDF_METADATA_ANALYSIS,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Next batch of PDFs needing their metadata read, after `after_id`.
+    pub async fn pending(
+        &self,
+        source_id: Option<&str>,
+        limit: usize,
+        after_id: Option<&str>,
+    ) -> anyhow::Result<Vec<Document>> {
+        Ok(self
+            .doc_repo
+            .get_needing_analysis(
+                PDF_METADATA_ANALYSIS,
+                limit,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                after_id,
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Read and store the metadata of a document's current version.
+    ///
+    /// Failures are recorded as a failed analysis result, to be retried
+    /// later, and returned as errors.
+    pub async fn process_document(&self, doc: &Document) -> anyhow::Result<PdfMetadata> {
+        let version = doc
+            .current_version()
+            .ok_or_else(|| anyhow::anyhow!("Document {} has no versions", doc.id))?;
+        let version_id = version.id as i32;
+
+        let pdf_path = version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
+        let started = Instant::now();
+        let read = tokio::task::spawn_blocking(move || read_pdf_metadata(&pdf_path)).await?;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let metadata = match read {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                self.doc_repo
+                    .store_analysis_result_for_document(
+                        &doc.id,
+                        version_id,
+                        PDF_METADATA_ANALYSIS,
+                        "pdfinfo",
+                        None,
+                        None,
+                        None,
+                        Some(elapsed_ms),
+                        Some(e.as_str()),
+                        None,
+                    )
+                    .await?;
+                return Err(anyhow::anyhow!(e));
+            }
+        };
+
+        self.doc_repo.set_pdf_metadata(&doc.id, &metadata).await?;
+        let summary = metadata.producer.clone();
+        self.doc_repo
+            .store_analysis_result_for_document(
+                &doc.id,
+                version_id,
+                PDF_METADATA_ANALYSIS,
+                "pdfinfo",
+                None,
+                summary.as_deref(),
+                None,
+                Some(elapsed_ms),
+                None,
+                None,
+            )
+            .await?;
+        Ok(metadata)
+    }
+}
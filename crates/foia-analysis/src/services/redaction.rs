@@ -0,0 +1,367 @@
+//! Redaction service.
+//!
+//! Stores a redacted copy of a document's current PDF as a
+//! [`ArtifactKind::RedactedPdf`] artifact, which public views and static
+//! publishing show in place of the original. The original file is kept
+//! as it was; the redacted pages' text is replaced with a correction
+//! holding what's left of them, so search and page text don't give away
+//! what the boxes cover.
+//!
+//! A page whose text mentions something that can't be found on its image
+//! would go out uncovered, so redacting fails unless such pages are
+//! explicitly allowed; their text is then masked instead.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use regex::Regex;
+
+use foia::artifacts::ArtifactStore;
+use foia::models::{scan_pii, ArtifactKind, DerivedArtifact, Document};
+use foia::repository::DieselDocumentRepository;
+
+use crate::redaction::{redact_pdf, RedactedPage, RedactionPlan, RedactionRegion};
+
+/// Analysis type recorded in `document_analysis_results`.
+pub const REDACTION_ANALYSIS: &str = "redaction";
+
+/// Artifact name of a version's redacted copy.
+pub const REDACTED_PDF_NAME: &str = "redacted.pdf";
+
+const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// Stands in for masked text on pages that couldn't be covered.
+const REDACTED_TEXT: &str = "[REDACTED]";
+
+/// What a redaction covered.
+#[derive(Debug, Clone)]
+pub struct RedactionOutcome {
+    /// Pages rebuilt with boxes burned in.
+    pub pages: Vec<RedactedPage>,
+    /// Pages whose stored text has personal information or a term that
+    /// couldn't be located on the page image, published with only their
+    /// text masked.
+    pub missed: Vec<u32>,
+}
+
+/// Service producing redacted copies of PDFs.
+pub struct RedactionService {
+    doc_repo: DieselDocumentRepository,
+    store: ArtifactStore,
+    documents_dir: PathBuf,
+    language: String,
+}
+
+impl RedactionService {
+    /// Create a service; `language` is the Tesseract language.
+    pub fn new(doc_repo: DieselDocumentRepository, documents_dir: PathBuf, language: &str) -> Self {
+        Self {
+            doc_repo,
+            store: ArtifactStore::new(&documents_dir),
+            documents_dir,
+            language: language.to_string(),
+        }
+    }
+
+    /// Redact the current version of `doc`, blacking out `regions`, the
+    /// personal information found on its pages if `pii` is set, and every
+    /// occurrence of `terms`. Each run starts from the original; a new copy
+    /// replaces any earlier one.
+    ///
+    /// Fails if some page's text mentions something that couldn't be found
+    /// on its image, unless `allow_missed`. Returns `None` if nothing was
+    /// found to cover.
+    pub async fn redact(
+        &self,
+        doc: &Document,
+        regions: Vec<RedactionRegion>,
+        pii: bool,
+        terms: Vec<String>,
+        allow_missed: bool,
+    ) -> anyhow::Result<Option<RedactionOutcome>> {
+        let version = doc
+            .current_version()
+            .ok_or_else(|| anyhow::anyhow!("Document {} has no versions", doc.id))?;
+        if version.mime_type != PDF_MIME_TYPE {
+            anyhow::bail!(
+                "Document {} is {}; only PDFs can be redacted",
+                doc.id,
+                version.mime_type
+            );
+        }
+        let version_id = version.id as i32;
+
+        // An earlier copy stays public until this one is ready, so read its
+        // pages' text as it was before that copy replaced it
+        let earlier = match self.redacted_copy(doc).await? {
+            Some(artifact) => redacted_pages(&artifact),
+            None => Vec::new(),
+        };
+
+        // Only pages whose text mentions something are searched again
+        let needles: Vec<String> = terms.iter().map(|t| normalize(t)).collect();
+        let mut search_pages = BTreeSet::new();
+        let mut texts = BTreeMap::new();
+        for mut page in self.doc_repo.get_pages(&doc.id, version_id).await? {
+            if earlier.contains(&page.page_number) {
+                page.compute_final_text();
+            }
+            let text = page
+                .final_text
+                .or(page.ocr_text)
+                .or(page.pdf_text)
+                .unwrap_or_default();
+            let flat = normalize(&text);
+            if (pii && !scan_pii(&text).is_empty())
+                || needles.iter().any(|t| !t.is_empty() && flat.contains(t))
+            {
+                search_pages.insert(page.page_number);
+            }
+            texts.insert(page.page_number, text);
+        }
+        let plan = RedactionPlan {
+            regions,
+            pii,
+            terms,
+            search_pages,
+        };
+
+        let pdf_path = version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
+        let language = self.language.clone();
+        let plan_for_task = plan.clone();
+        let started = Instant::now();
+        let (content, pages) = tokio::task::spawn_blocking(move || {
+            let temp_dir = tempfile::TempDir::new().map_err(|e| e.to_string())?;
+            let output = temp_dir.path().join(REDACTED_PDF_NAME);
+            let pages = redact_pdf(&pdf_path, &output, &plan_for_task, &language)?;
+            let content = if pages.is_empty() {
+                Vec::new()
+            } else {
+                std::fs::read(&output).map_err(|e| e.to_string())?
+            };
+            Ok::<_, String>((content, pages))
+        })
+        .await?
+        .map_err(|e| anyhow::anyhow!(e))?;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let missed: Vec<u32> = plan
+            .search_pages
+            .iter()
+            .copied()
+            .filter(|n| !pages.iter().any(|p| p.page == *n && p.found > 0))
+            .collect();
+        if !missed.is_empty() && !allow_missed {
+            let list: Vec<String> = missed.iter().map(|p| p.to_string()).collect();
+            anyhow::bail!(
+                "The text of {} {} of {} mentions something that couldn't be found on the page image, \
+                 which would be published uncovered; cover it with a region (--region) or allow \
+                 missed pages (--allow-missed)",
+                if missed.len() == 1 { "page" } else { "pages" },
+                list.join(", "),
+                doc.id
+            );
+        }
+        if pages.is_empty() {
+            return Ok(None);
+        }
+
+        self.remove(doc).await?;
+        let store = self.store.clone();
+        let (doc_id, source) = (doc.id.clone(), version.clone());
+        let mut artifact = tokio::task::spawn_blocking(move || {
+            store.write(
+                &doc_id,
+                &source,
+                ArtifactKind::RedactedPdf,
+                REDACTED_PDF_NAME,
+                PDF_MIME_TYPE,
+                &content,
+            )
+        })
+        .await?
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+        // Text the boxes missed is masked, on rebuilt and missed pages alike
+        let mut corrected: BTreeMap<u32, String> = missed
+            .iter()
+            .map(|n| (*n, texts.get(n).cloned().unwrap_or_default()))
+            .collect();
+        for page in &pages {
+            corrected.insert(page.page, page.text.clone());
+        }
+        for (page, text) in &corrected {
+            self.doc_repo
+                .correct_page_text(
+                    &doc.id,
+                    version_id,
+                    *page as i32,
+                    &mask_text(text, pii, &plan.terms),
+                )
+                .await?;
+        }
+        if doc.extracted_text.is_some() {
+            let mut updated = doc.clone();
+            updated.extracted_text = self
+                .doc_repo
+                .get_combined_page_text(&doc.id, version_id)
+                .await?;
+            self.doc_repo.save(&updated).await?;
+        }
+
+        let metadata = serde_json::json!({
+            "file_path": artifact.file_path,
+            "file_size": artifact.file_size,
+            "regions": plan.regions,
+            "pii": plan.pii,
+            "terms": plan.terms.len(),
+            "pages": pages
+                .iter()
+                .map(|p| serde_json::json!({ "page": p.page, "boxes": p.boxes, "found": p.found }))
+                .collect::<Vec<_>>(),
+            "missed": missed,
+        });
+        let boxes: usize = pages.iter().map(|p| p.boxes).sum();
+        let summary = format!(
+            "{} {} on {} {}",
+            boxes,
+            if boxes == 1 { "box" } else { "boxes" },
+            pages.len(),
+            if pages.len() == 1 { "page" } else { "pages" }
+        );
+        let result_id = self
+            .doc_repo
+            .store_analysis_result_for_document(
+                &doc.id,
+                version_id,
+                REDACTION_ANALYSIS,
+                "tesseract",
+                None,
+                Some(&summary),
+                None,
+                Some(elapsed_ms),
+                None,
+                Some(&metadata),
+            )
+            .await?;
+        artifact.analysis_result_id = Some(result_id);
+        artifact.metadata = Some(serde_json::json!({
+            "pages": corrected.keys().collect::<Vec<_>>(),
+        }));
+        self.doc_repo.save_artifact(&artifact).await?;
+        Ok(Some(RedactionOutcome { pages, missed }))
+    }
+
+    /// Remove the redacted copy of `doc`'s current version and restore its
+    /// pages' text, so the original is public again. Returns false if there
+    /// was no redacted copy.
+    pub async fn remove(&self, doc: &Document) -> anyhow::Result<bool> {
+        let Some(version) = doc.current_version() else {
+            return Ok(false);
+        };
+        let Some(artifact) = self.redacted_copy(doc).await? else {
+            return Ok(false);
+        };
+        for page in redacted_pages(&artifact) {
+            self.doc_repo
+                .revert_page_correction(&doc.id, version.id as i32, page as i32)
+                .await?;
+        }
+        if doc.extracted_text.is_some() {
+            let mut updated = doc.clone();
+            updated.extracted_text = self
+                .doc_repo
+                .get_combined_page_text(&doc.id, version.id as i32)
+                .await?;
+            self.doc_repo.save(&updated).await?;
+        }
+        self.doc_repo.delete_artifacts(&[artifact.id]).await?;
+        let _ = std::fs::remove_file(self.store.resolve(&artifact.file_path));
+        Ok(true)
+    }
+
+    /// The redacted copy of `doc`'s current version, if any.
+    async fn redacted_copy(&self, doc: &Document) -> anyhow::Result<Option<DerivedArtifact>> {
+        let Some(version) = doc.current_version() else {
+            return Ok(None);
+        };
+        Ok(self
+            .doc_repo
+            .find_artifact(version.id, ArtifactKind::RedactedPdf, REDACTED_PDF_NAME)
+            .await?)
+    }
+}
+
+/// Pages whose text a redacted copy replaced.
+fn redacted_pages(artifact: &DerivedArtifact) -> Vec<u32> {
+    artifact
+        .metadata
+        .as_ref()
+        .and_then(|m| m.get("pages"))
+        .and_then(|p| serde_json::from_value(p.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// `text` with the personal information in it (if `pii`) and every
+/// occurrence of `terms` replaced by a placeholder.
+fn mask_text(text: &str, pii: bool, terms: &[String]) -> String {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    if pii {
+        spans.extend(scan_pii(text).into_iter().map(|m| (m.start, m.end)));
+    }
+    for term in terms {
+        let words: Vec<String> = term.split_whitespace().map(regex::escape).collect();
+        if words.is_empty() {
+            continue;
+        }
+        if let Ok(re) = Regex::new(&format!("(?i){}", words.join(r"\s+"))) {
+            spans.extend(re.find_iter(text).map(|m| (m.start(), m.end())));
+        }
+    }
+    spans.sort_unstable();
+
+    let mut masked = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (start, end) in spans {
+        if start < pos {
+            pos = pos.max(end);
+            continue;
+        }
+        masked.push_str(&text[pos..start]);
+        masked.push_str(REDACTED_TEXT);
+        pos = end;
+    }
+    masked.push_str(&text[pos..]);
+    masked
+}
+
+/// Lowercase `text` with runs of whitespace collapsed to single spaces.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_text() {
+        let terms = vec!["operation  mongoose".to_string(), "Mongoose".to_string()];
+        assert_eq!(
+            mask_text("Operation\nMongoose met MONGOOSE.", false, &terms),
+            "[REDACTED] met [REDACTED]."
+        );
+        assert_eq!(
+            mask_text("SSN 219-09-9999 on file", true, &[]),
+            "SSN [REDACTED] on file"
+        );
+        assert_eq!(
+            mask_text("SSN 219-09-9999 on file", false, &[]),
+            "SSN 219-09-9999 on file"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Searchable PDF service.
+//!
+//! Finds PDFs without a `searchable_pdf` analysis result, or whose
+//! searchable PDF went stale after re-OCR, and stores a copy with an OCR
+//! text layer as a [`ArtifactKind::SearchablePdf`] artifact.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use foia::artifacts::ArtifactStore;
+use foia::models::{ArtifactKind, Document};
+use foia::repository::DieselDocumentRepository;
+use foia::work_queue::ClaimHeartbeat;
+
+use crate::searchable_pdf::{make_searchable_pdf, SearchablePdfTool};
+
+/// Analysis type recorded in `document_analysis_results`.
+pub const SEARCHABLE_PDF_ANALYSIS: &str = "searchable_pdf";
+
+/// Artifact name of a version's searchable PDF.
+pub const SEARCHABLE_PDF_NAME: &str = "searchable.pdf";
+
+const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// Hours to wait before retrying a failed conversion.
+const RETRY_INTERVAL_HOURS: u32 = 12;
+
+/// Service generating searchable PDFs as derived artifacts.
+pub struct SearchablePdfService {
+    doc_repo: DieselDocumentRepository,
+    store: ArtifactStore,
+    documents_dir: PathBuf,
+    language: String,
+    tool: SearchablePdfTool,
+}
+
+impl SearchablePdfService {
+    /// Create a service using `tool`; `language` is the Tesseract language.
+    pub fn new(
+        doc_repo: DieselDocumentRepository,
+        documents_dir: PathBuf,
+        language: &str,
+        tool: SearchablePdfTool,
+    ) -> Self {
+        Self {
+            doc_repo,
+            store: ArtifactStore::new(&documents_dir),
+            documents_dir,
+            language: language.to_string(),
+            tool,
+        }
+    }
+
+    /// Count PDFs without a searchable copy.
+    pub async fn count_pending(&self, source_id: Option<&str>) -> anyhow::Result<u64> {
+        Ok(self
+            .doc_repo
+            .count_needing_analysis(
+                SEARCHABLE_PDF_ANALYSIS,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Next batch of PDFs without a searchable copy, after `after_id`.
+    pub async fn pending(
+        &self,
+        source_id: Option<&str>,
+        limit: usize,
+        after_id: Option<&str>,
+    ) -> anyhow::Result<Vec<Document>> {
+        Ok(self
+            .doc_repo
+            .get_needing_analysis(
+                SEARCHABLE_PDF_ANALYSIS,
+                limit,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                after_id,
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Documents whose current searchable PDF is stale.
+    pub async fn stale(&self, source_id: Option<&str>) -> anyhow::Result<Vec<Document>> {
+        let ids: HashSet<String> = self
+            .doc_repo
+            .get_stale_artifacts(chrono::Utc::now())
+            .await?
+            .into_iter()
+            .filter(|a| a.kind == ArtifactKind::SearchablePdf)
+            .map(|a| a.document_id)
+            .collect();
+        let ids: Vec<String> = ids.into_iter().collect();
+        let docs = self.doc_repo.get_batch(&ids).await?;
+        Ok(docs
+            .into_iter()
+            .filter(|doc| source_id.is_none_or(|s| doc.source_id == s))
+            .collect())
+    }
+
+    /// Generate and store the searchable PDF of a document's current version.
+    ///
+    /// Failures are recorded as a failed analysis result, to be retried
+    /// later, and returned as errors.
+    pub async fn process_document(&self, doc: &Document) -> anyhow::Result<()> {
+        let version = doc
+            .current_version()
+            .ok_or_else(|| anyhow::anyhow!("Document {} has no versions", doc.id))?;
+        let version_id = version.id as i32;
+        self.doc_repo
+            .claim_analysis(&doc.id, version_id, SEARCHABLE_PDF_ANALYSIS)
+            .await?;
+        let _heartbeat = ClaimHeartbeat::start(
+            self.doc_repo.clone(),
+            &doc.id,
+            version_id,
+            SEARCHABLE_PDF_ANALYSIS,
+        );
+
+        let pdf_path = version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
+        let (tool, language) = (self.tool, self.language.clone());
+        let (store, doc_id, source) = (self.store.clone(), doc.id.clone(), version.clone());
+        let started = Instant::now();
+        let stored = tokio::task::spawn_blocking(move || {
+            let temp_dir = tempfile::TempDir::new().map_err(|e| e.to_string())?;
+            let output = temp_dir.path().join(SEARCHABLE_PDF_NAME);
+            make_searchable_pdf(tool, &pdf_path, &output, &language)?;
+            let content = std::fs::read(&output).map_err(|e| e.to_string())?;
+            store.write(
+                &doc_id,
+                &source,
+                ArtifactKind::SearchablePdf,
+                SEARCHABLE_PDF_NAME,
+                PDF_MIME_TYPE,
+                &content,
+            )
+        })
+        .await?;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let mut artifact = match stored {
+            Ok(artifact) => artifact,
+            Err(e) => {
+                self.doc_repo
+                    .store_analysis_result_for_document(
+                        &doc.id,
+                        version_id,
+                        SEARCHABLE_PDF_ANALYSIS,
+                        tool.as_str(),
+                        None,
+                        None,
+                        None,
+                        Some(elapsed_ms),
+                        Some(e.as_str()),
+                        None,
+                    )
+                    .await?;
+                return Err(anyhow::anyhow!(e));
+            }
+        };
+
+        let metadata = serde_json::json!({
+            "file_path": artifact.file_path,
+            "file_size": artifact.file_size,
+        });
+        let result_id = self
+            .doc_repo
+            .store_analysis_result_for_document(
+                &doc.id,
+                version_id,
+                SEARCHABLE_PDF_ANALYSIS,
+                tool.as_str(),
+                None,
+                None,
+                None,
+                Some(elapsed_ms),
+                None,
+                Some(&metadata),
+            )
+            .await?;
+        artifact.analysis_result_id = Some(result_id);
+        artifact.metadata = Some(serde_json::json!({ "tool": tool.as_str() }));
+        self.doc_repo.save_artifact(&artifact).await?;
+        Ok(())
+    }
+}
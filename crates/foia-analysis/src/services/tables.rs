@@ -0,0 +1,155 @@
+//! Table extraction service.
+//!
+//! Finds PDFs without a `tables` analysis result, extracts their tables and
+//! stores each as a CSV virtual file under [`TABLES_PREFIX`], replacing any
+//! from an earlier run. Documents without tables still get a result row so
+//! they aren't scanned again.
+
+use std::path::PathBuf;
+use std::time::Instant;
+
+use foia::models::{Document, VirtualFile, TABLES_PREFIX};
+use foia::repository::DieselDocumentRepository;
+use foia::work_queue::ClaimHeartbeat;
+
+use crate::tables::extract_pdf_tables;
+
+/// Analysis type recorded in `document_analysis_results`.
+pub const TABLES_ANALYSIS: &str = "tables";
+
+const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// Hours to wait before retrying a failed extraction.
+const RETRY_INTERVAL_HOURS: u32 = 12;
+
+/// Service extracting tables from PDFs into CSV virtual files.
+pub struct TableExtractionService {
+    doc_repo: DieselDocumentRepository,
+    documents_dir: PathBuf,
+    language: String,
+}
+
+impl TableExtractionService {
+    /// Create a service; `language` is the Tesseract language for scans.
+    pub fn new(doc_repo: DieselDocumentRepository, documents_dir: PathBuf, language: &str) -> Self {
+        Self {
+            doc_repo,
+            documents_dir,
+            language: language.to_string(),
+        }
+    }
+
+    /// Count PDFs whose tables haven't been extracted.
+    pub async fn count_pending(&self, source_id: Option<&str>) -> anyhow::Result<u64> {
+        Ok(self
+            .doc_repo
+            .count_needing_analysis(
+                TABLES_ANALYSIS,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Next batch of PDFs needing extraction, after `after_id`.
+    pub async fn pending(
+        &self,
+        source_id: Option<&str>,
+        limit: usize,
+        after_id: Option<&str>,
+    ) -> anyhow::Result<Vec<Document>> {
+        Ok(self
+            .doc_repo
+            .get_needing_analysis(
+                TABLES_ANALYSIS,
+                limit,
+                source_id,
+                Some(PDF_MIME_TYPE),
+                after_id,
+                RETRY_INTERVAL_HOURS,
+            )
+            .await?)
+    }
+
+    /// Extract and store the tables of a document's current version.
+    ///
+    /// Returns the number of tables found. Failures are recorded as a failed
+    /// analysis result, to be retried later, and returned as errors.
+    pub async fn process_document(&self, doc: &Document) -> anyhow::Result<usize> {
+        let version = doc
+            .current_version()
+            .ok_or_else(|| anyhow::anyhow!("Document {} has no versions", doc.id))?;
+        let version_id = version.id as i32;
+        self.doc_repo
+            .claim_analysis(&doc.id, version_id, TABLES_ANALYSIS)
+            .await?;
+        let _heartbeat =
+            ClaimHeartbeat::start(self.doc_repo.clone(), &doc.id, version_id, TABLES_ANALYSIS);
+
+        let pdf_path = version.resolve_path(&self.documents_dir, &doc.source_url, &doc.title);
+        let language = self.language.clone();
+        let started = Instant::now();
+        let extracted =
+            tokio::task::spawn_blocking(move || extract_pdf_tables(&pdf_path, &language)).await?;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+
+        let tables = match extracted {
+            Ok(tables) => tables,
+            Err(e) => {
+                self.doc_repo
+                    .store_analysis_result_for_document(
+                        &doc.id,
+                        version_id,
+                        TABLES_ANALYSIS,
+                        "builtin",
+                        None,
+                        None,
+                        None,
+                        Some(elapsed_ms),
+                        Some(e.as_str()),
+                        None,
+                    )
+                    .await?;
+                return Err(anyhow::anyhow!(e));
+            }
+        };
+
+        let files: Vec<VirtualFile> = tables
+            .iter()
+            .map(|table| {
+                VirtualFile::extracted_table(
+                    doc.id.clone(),
+                    version.id,
+                    table.page,
+                    table.index,
+                    table.to_csv(),
+                )
+            })
+            .collect();
+        self.doc_repo
+            .replace_virtual_files(&doc.id, version_id, TABLES_PREFIX, &files)
+            .await?;
+
+        let mut pages: Vec<u32> = tables.iter().map(|t| t.page).collect();
+        pages.dedup();
+        let summary = format!("{} tables", tables.len());
+        let metadata = serde_json::json!({ "tables": tables.len(), "pages": pages });
+        self.doc_repo
+            .store_analysis_result_for_document(
+                &doc.id,
+                version_id,
+                TABLES_ANALYSIS,
+                "builtin",
+                None,
+                Some(summary.as_str()),
+                None,
+                Some(elapsed_ms),
+                None,
+                Some(&metadata),
+            )
+            .await?;
+
+        Ok(tables.len())
+    }
+}
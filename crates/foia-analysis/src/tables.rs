@@ -0,0 +1,330 @@
+//! Table detection and extraction from PDF pages.
+//!
+//! Tables are found in layout-preserving page text: `pdftotext -layout` for
+//! pages with a text layer, and Tesseract with inter-word spacing preserved
+//! for scans. Lines whose text splits into two or more cells at runs of
+//! spaces form a block; a block becomes a table when it has enough rows,
+//! with columns taken from where the cells of its typical rows line up.
+
+use std::path::Path;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+use crate::ocr::{pdf_page_to_image, TextExtractor, OCR_DPI};
+
+/// Spaces that separate two cells; single spaces separate words.
+const MIN_GAP: usize = 2;
+
+/// Rows a block needs to count as a table.
+const MIN_ROWS: usize = 3;
+
+/// Columns a block needs to count as a table.
+const MIN_COLUMNS: usize = 2;
+
+/// Pages with fewer words than this in their text layer are OCR'd.
+const MIN_PAGE_WORDS: usize = 5;
+
+/// A table found on a PDF page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedTable {
+    /// Page number (1-based).
+    pub page: u32,
+    /// Position of the table on its page (1-based).
+    pub index: usize,
+    /// Cell text by row; every row has the same number of columns.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ExtractedTable {
+    /// Number of columns.
+    pub fn columns(&self) -> usize {
+        self.rows.first().map_or(0, Vec::len)
+    }
+
+    /// The table as CSV, quoting cells that need it.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Extract the tables on every page of a PDF.
+///
+/// Blocks on `pdftotext`, `pdftoppm` and `tesseract`; call from a blocking
+/// context. `language` is passed to Tesseract for scanned pages.
+pub fn extract_pdf_tables(pdf_path: &Path, language: &str) -> Result<Vec<ExtractedTable>, String> {
+    let extractor = TextExtractor::new();
+    let page_count = extractor
+        .get_pdf_page_count(pdf_path)
+        .ok_or_else(|| format!("Could not read page count of {}", pdf_path.display()))?;
+    let pages = extractor
+        .extract_all_pdf_page_texts(pdf_path, page_count)
+        .map_err(|e| e.to_string())?;
+
+    let mut tables = Vec::new();
+    for page in 1..=page_count {
+        let text = pages.get(page as usize - 1).map_or("", String::as_str);
+        let text = if text.split_whitespace().count() >= MIN_PAGE_WORDS {
+            text.to_string()
+        } else {
+            match ocr_page_layout(pdf_path, page, language) {
+                Ok(text) => text,
+                Err(e) => {
+                    tracing::warn!("Skipping tables on page {}: {}", page, e);
+                    continue;
+                }
+            }
+        };
+
+        tables.extend(
+            detect_tables(&text)
+                .into_iter()
+                .enumerate()
+                .map(|(i, rows)| ExtractedTable {
+                    page,
+                    index: i + 1,
+                    rows,
+                }),
+        );
+    }
+    Ok(tables)
+}
+
+/// OCR a scanned page keeping the spacing between words, so columns stay
+/// separated by runs of spaces as they are in `pdftotext -layout` output.
+fn ocr_page_layout(pdf_path: &Path, page: u32, language: &str) -> Result<String, String> {
+    let temp_dir = TempDir::new().map_err(|e| e.to_string())?;
+    let image =
+        pdf_page_to_image(pdf_path, page, OCR_DPI, temp_dir.path()).map_err(|e| e.to_string())?;
+
+    let output = Command::new("tesseract")
+        .arg(&image)
+        .arg("stdout")
+        .args(["-l", language, "--psm", "6"])
+        .args(["-c", "preserve_interword_spaces=1"])
+        .output()
+        .map_err(|e| format!("Failed to run tesseract: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A run of text on a line and the character columns it spans.
+#[derive(Debug)]
+struct Cell {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Find tables in layout-preserving text, returning each as rows of cells.
+///
+/// A single blank line inside a block is allowed (spaced-out rows); a
+/// second one, or a line of plain prose, ends it.
+pub fn detect_tables(text: &str) -> Vec<Vec<Vec<String>>> {
+    let mut tables = Vec::new();
+    let mut block: Vec<Vec<Cell>> = Vec::new();
+    let mut after_blank = false;
+
+    for line in text.lines() {
+        let cells = split_cells(line);
+        if cells.len() >= MIN_COLUMNS {
+            block.push(cells);
+            after_blank = false;
+        } else if line.trim().is_empty() && !block.is_empty() && !after_blank {
+            after_blank = true;
+        } else {
+            tables.extend(build_table(&block));
+            block.clear();
+            after_blank = false;
+        }
+    }
+    tables.extend(build_table(&block));
+    tables
+}
+
+/// Split a line into cells at runs of [`MIN_GAP`] or more spaces.
+fn split_cells(line: &str) -> Vec<Cell> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut cells = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i;
+        while i < chars.len() {
+            if chars[i].is_whitespace() {
+                let gap = chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+                if gap >= MIN_GAP || i + gap == chars.len() {
+                    break;
+                }
+                i += gap;
+            } else {
+                i += 1;
+                end = i;
+            }
+        }
+        cells.push(Cell {
+            start,
+            end,
+            text: chars[start..end].iter().collect(),
+        });
+    }
+    cells
+}
+
+/// Lay a block of split lines out on a common set of columns.
+///
+/// Columns are the merged character spans of cells in rows with at least
+/// the median cell count, so a wide header or a row with empty cells
+/// doesn't decide the layout. Every cell then goes to the column it
+/// overlaps most, or the nearest one.
+fn build_table(block: &[Vec<Cell>]) -> Option<Vec<Vec<String>>> {
+    if block.len() < MIN_ROWS {
+        return None;
+    }
+
+    let mut counts: Vec<usize> = block.iter().map(Vec::len).collect();
+    counts.sort_unstable();
+    let median = counts[counts.len() / 2];
+
+    let mut spans: Vec<(usize, usize)> = block
+        .iter()
+        .filter(|row| row.len() >= median)
+        .flatten()
+        .map(|cell| (cell.start, cell.end))
+        .collect();
+    spans.sort_unstable();
+
+    let mut columns: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in spans {
+        match columns.last_mut() {
+            Some(last) if start < last.1 => last.1 = last.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    if columns.len() < MIN_COLUMNS {
+        return None;
+    }
+
+    let rows = block
+        .iter()
+        .map(|row| {
+            let mut cells = vec![String::new(); columns.len()];
+            for cell in row {
+                let column = nearest_column(&columns, cell);
+                if !cells[column].is_empty() {
+                    cells[column].push(' ');
+                }
+                cells[column].push_str(&cell.text);
+            }
+            cells
+        })
+        .collect();
+    Some(rows)
+}
+
+fn nearest_column(columns: &[(usize, usize)], cell: &Cell) -> usize {
+    columns
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, &(start, end))| {
+            end.min(cell.end) as isize - start.max(cell.start) as isize
+        })
+        .map_or(0, |(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: &str = "\
+Department of Public Works
+Fiscal Year 2021 Budget Summary
+
+    Program                 FY2020        FY2021      Change
+    Road Maintenance     1,204,000     1,310,500     106,500
+    Snow Removal           480,250       455,000     -25,250
+
+    Fleet Services       2,010,000     2,115,750     105,750
+
+Approved by the council on June 3, 2020.
+";
+
+    #[test]
+    fn test_detects_aligned_columns() {
+        let tables = detect_tables(BUDGET);
+        assert_eq!(tables.len(), 1);
+        let table = &tables[0];
+        assert_eq!(table.len(), 4);
+        assert_eq!(table[0], ["Program", "FY2020", "FY2021", "Change"]);
+        assert_eq!(table[2], ["Snow Removal", "480,250", "455,000", "-25,250"]);
+        assert_eq!(table[3][0], "Fleet Services");
+    }
+
+    #[test]
+    fn test_missing_cells_stay_in_their_column() {
+        let text = "\
+Name            Badge     Unit
+J. Smith        4411      Patrol
+R. Jones                  Traffic
+K. Lee          5120      Patrol
+";
+        let tables = detect_tables(text);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0][2], ["R. Jones", "", "Traffic"]);
+    }
+
+    #[test]
+    fn test_ignores_prose_and_short_blocks() {
+        let text = "\
+Dear requester,
+
+This letter responds to your request dated March 4.  We located
+two pages.  They are enclosed.
+
+Sincerely,
+";
+        assert!(detect_tables(text).is_empty());
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let table = ExtractedTable {
+            page: 1,
+            index: 1,
+            rows: vec![
+                vec!["Vendor".into(), "Amount".into()],
+                vec!["Smith, Jones & Co".into(), "1,200".into()],
+                vec!["The \"Best\" Supply".into(), "75".into()],
+            ],
+        };
+        assert_eq!(table.columns(), 2);
+        assert_eq!(
+            table.to_csv(),
+            "Vendor,Amount\n\"Smith, Jones & Co\",\"1,200\"\n\"The \"\"Best\"\" Supply\",75\n"
+        );
+    }
+}
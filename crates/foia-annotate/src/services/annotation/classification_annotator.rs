@@ -0,0 +1,87 @@
+//! Record type classification annotator — wraps `LlmClient::classify_record_type()`
+//! behind the `Annotator` trait.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use foia::llm::{LlmClient, LlmConfig, UsageTracker};
+use foia::models::Document;
+use foia::repository::DieselDocumentRepository;
+
+use super::annotator::{get_document_text, Annotator};
+use super::types::{AnnotationError, AnnotationOutput};
+
+/// Annotator that assigns each document a controlled-vocabulary record type
+/// (correspondence, contract, incident report, ...) via an LLM service.
+///
+/// The result is stored in the `record_type` / `record_type_confidence`
+/// columns, separate from free-form tags.
+pub struct ClassificationAnnotator {
+    llm_client: LlmClient,
+    config: LlmConfig,
+}
+
+impl ClassificationAnnotator {
+    pub fn new(config: LlmConfig) -> Self {
+        let llm_client = LlmClient::new(config.clone());
+        Self { llm_client, config }
+    }
+
+    /// Count LLM calls against a usage tracker shared by the run.
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.llm_client = self.llm_client.with_usage_tracker(tracker);
+        self
+    }
+}
+
+#[async_trait]
+impl Annotator for ClassificationAnnotator {
+    fn annotation_type(&self) -> &str {
+        "record_type"
+    }
+
+    fn display_name(&self) -> &str {
+        "Record Type Classification"
+    }
+
+    fn is_deferred(&self) -> bool {
+        true
+    }
+
+    async fn is_available(&self) -> bool {
+        self.llm_client.is_available().await
+    }
+
+    fn availability_hint(&self) -> String {
+        self.config.availability_hint()
+    }
+
+    async fn annotate(
+        &self,
+        doc: &Document,
+        doc_repo: &DieselDocumentRepository,
+    ) -> Result<AnnotationOutput, AnnotationError> {
+        let text = match get_document_text(doc, doc_repo).await {
+            Ok(t) => t,
+            Err(output) => return Ok(output),
+        };
+
+        let result = self
+            .llm_client
+            .classify_record_type(&text, &doc.title)
+            .await?;
+
+        doc_repo
+            .update_record_type(&doc.id, result.record_type.as_str(), result.confidence)
+            .await
+            .map_err(|e| AnnotationError::Database(e.to_string()))?;
+
+        let data = serde_json::json!({
+            "record_type": result.record_type.as_str(),
+            "confidence": result.confidence,
+        });
+
+        Ok(AnnotationOutput::Data(data.to_string()))
+    }
+}
@@ -1,9 +1,15 @@
 //! LLM summarization annotator — wraps `LlmClient::summarize()` behind the `Annotator` trait.
+//!
+//! Archives and emails are summarized member by member: each virtual file
+//! with extracted text gets its own synopsis and tags, and the container's
+//! synopsis is an overview rolled up from those.
+
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
-use foia::llm::{LlmClient, LlmConfig};
-use foia::models::{Document, DocumentStatus};
+use foia::llm::{LlmClient, LlmConfig, LlmError, SummarizeResult, UsageTracker};
+use foia::models::{Document, DocumentStatus, VirtualFile};
 use foia::repository::DieselDocumentRepository;
 
 use super::annotator::{get_document_text, Annotator};
@@ -24,10 +30,76 @@ impl LlmAnnotator {
         Self { llm_client, config }
     }
 
+    /// Count LLM calls against a usage tracker shared by the run.
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.llm_client = self.llm_client.with_usage_tracker(tracker);
+        self
+    }
+
     /// Get the underlying LLM config (for display in CLI).
     pub fn llm_config(&self) -> &LlmConfig {
         &self.config
     }
+
+    /// Summarize the archive members that don't have a synopsis yet, then
+    /// the archive itself from all member synopses.
+    ///
+    /// Returns `None` when the document has no members with text, so it is
+    /// summarized from its own pages instead. Member summaries are saved as
+    /// they complete; an interrupted run resumes with the rest.
+    async fn summarize_members(
+        &self,
+        doc: &Document,
+        doc_repo: &DieselDocumentRepository,
+    ) -> Result<Option<(SummarizeResult, Vec<VirtualFile>)>, AnnotationError> {
+        let version_id = match doc.current_version() {
+            Some(v) => v.id,
+            None => return Ok(None),
+        };
+        let mut members: Vec<VirtualFile> = doc_repo
+            .get_virtual_files(&doc.id, version_id as i32)
+            .await
+            .map_err(|e| AnnotationError::Database(e.to_string()))?
+            .into_iter()
+            .filter(|vf| vf.synopsis.is_some() || vf.needs_summary())
+            .collect();
+        if members.is_empty() {
+            return Ok(None);
+        }
+        members.sort_by(|a, b| a.archive_path.cmp(&b.archive_path));
+
+        for member in members.iter_mut().filter(|m| m.needs_summary()) {
+            let text = member.extracted_text.as_deref().unwrap_or_default();
+            let title = format!("{} / {}", doc.title, member.archive_path);
+            let result = match self.llm_client.summarize(text, &title).await {
+                Ok(result) => result,
+                // One unusable member shouldn't hold back the whole archive
+                Err(e @ (LlmError::Parse(_) | LlmError::Validation(_))) => {
+                    tracing::warn!("Skipping archive member {}: {}", title, e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            doc_repo
+                .update_virtual_file_summary(&member.id, &result.synopsis, &result.tags)
+                .await
+                .map_err(|e| AnnotationError::Database(format!("Save failed: {}", e)))?;
+            member.synopsis = Some(result.synopsis);
+            member.tags = result.tags;
+        }
+
+        members.retain(|m| m.synopsis.is_some());
+        if members.is_empty() {
+            return Err(AnnotationError::Failed(
+                "No archive member could be summarized".to_string(),
+            ));
+        }
+        let result = self
+            .llm_client
+            .summarize_archive(&doc.title, &members)
+            .await?;
+        Ok(Some((result, members)))
+    }
 }
 
 #[async_trait]
@@ -57,32 +129,36 @@ impl Annotator for LlmAnnotator {
         doc: &Document,
         doc_repo: &DieselDocumentRepository,
     ) -> Result<AnnotationOutput, AnnotationError> {
-        let text = match get_document_text(doc, doc_repo).await {
-            Ok(t) => t,
-            Err(output) => return Ok(output),
+        let (result, members) = match self.summarize_members(doc, doc_repo).await? {
+            Some((result, members)) => (result, members.len()),
+            None => {
+                let text = match get_document_text(doc, doc_repo).await {
+                    Ok(t) => t,
+                    Err(output) => return Ok(output),
+                };
+                (self.llm_client.summarize(&text, &doc.title).await?, 0)
+            }
         };
 
-        let result = self
-            .llm_client
-            .summarize(&text, &doc.title)
-            .await
-            .map_err(|e| AnnotationError::Failed(e.to_string()))?;
-
-        // Update document with synopsis, tags, and status
+        // Update document with synopsis and tags, then move it to analyzed
         let mut updated_doc = doc.clone();
         updated_doc.synopsis = Some(result.synopsis.clone());
         updated_doc.tags = result.tags.clone();
-        updated_doc.status = DocumentStatus::Indexed;
         updated_doc.updated_at = chrono::Utc::now();
 
         doc_repo
             .save(&updated_doc)
             .await
             .map_err(|e| AnnotationError::Database(format!("Save failed: {}", e)))?;
+        doc_repo
+            .transition_status(&doc.id, DocumentStatus::Indexed)
+            .await
+            .map_err(|e| AnnotationError::Database(format!("Save failed: {}", e)))?;
 
         let data = serde_json::json!({
             "synopsis_len": result.synopsis.len(),
             "tag_count": result.tags.len(),
+            "member_count": members,
         });
 
         Ok(AnnotationOutput::Data(data.to_string()))
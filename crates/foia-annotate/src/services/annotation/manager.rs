@@ -4,6 +4,7 @@ use std::sync::Arc;
 
 use tokio::sync::mpsc;
 
+use foia::models::Document;
 use foia::repository::DieselDocumentRepository;
 use foia::work_queue::db_annotation::DbAnnotationQueue;
 use foia::work_queue::{
@@ -45,6 +46,18 @@ impl AnnotationManager {
         Ok(queue.count(&filter).await?)
     }
 
+    /// Documents that still need the given annotation, up to `limit`.
+    pub async fn pending(
+        &self,
+        annotator: &dyn Annotator,
+        source_id: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Document>> {
+        let queue = DbAnnotationQueue::new(self.doc_repo.clone());
+        let filter = Self::build_filter(annotator, source_id);
+        Ok(queue.fetch_batch(&filter, limit, None).await?)
+    }
+
     /// Run a batch of annotations, emitting events for progress tracking.
     ///
     /// The caller owns the event receiver and decides how to present progress
@@ -0,0 +1,86 @@
+//! Structured metadata extraction annotator — wraps `LlmClient::extract_metadata()`
+//! behind the `Annotator` trait.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use foia::llm::{LlmClient, LlmConfig, UsageTracker};
+use foia::models::Document;
+use foia::repository::DieselDocumentRepository;
+
+use super::annotator::{get_document_text, Annotator};
+use super::types::{AnnotationError, AnnotationOutput};
+
+/// Annotator that extracts the document date, originating agency/office,
+/// FOIA case number, and classification markings via an LLM service.
+///
+/// Results are stored in typed columns on `documents` so they can be
+/// filtered and sorted; `document_date` drives the timeline.
+pub struct MetadataAnnotator {
+    llm_client: LlmClient,
+    config: LlmConfig,
+}
+
+impl MetadataAnnotator {
+    pub fn new(config: LlmConfig) -> Self {
+        let llm_client = LlmClient::new(config.clone());
+        Self { llm_client, config }
+    }
+
+    /// Count LLM calls against a usage tracker shared by the run.
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.llm_client = self.llm_client.with_usage_tracker(tracker);
+        self
+    }
+}
+
+#[async_trait]
+impl Annotator for MetadataAnnotator {
+    fn annotation_type(&self) -> &str {
+        "metadata_extraction"
+    }
+
+    fn display_name(&self) -> &str {
+        "Metadata Extraction"
+    }
+
+    fn is_deferred(&self) -> bool {
+        true
+    }
+
+    async fn is_available(&self) -> bool {
+        self.llm_client.is_available().await
+    }
+
+    fn availability_hint(&self) -> String {
+        self.config.availability_hint()
+    }
+
+    async fn annotate(
+        &self,
+        doc: &Document,
+        doc_repo: &DieselDocumentRepository,
+    ) -> Result<AnnotationOutput, AnnotationError> {
+        let text = match get_document_text(doc, doc_repo).await {
+            Ok(t) => t,
+            Err(output) => return Ok(output),
+        };
+
+        let extracted = self.llm_client.extract_metadata(&text, &doc.title).await?;
+
+        doc_repo
+            .update_extracted_metadata(&doc.id, &extracted)
+            .await
+            .map_err(|e| AnnotationError::Database(e.to_string()))?;
+
+        if extracted.is_empty() {
+            return Ok(AnnotationOutput::NoResult);
+        }
+
+        let data = serde_json::to_string(&extracted)
+            .map_err(|e| AnnotationError::Failed(e.to_string()))?;
+
+        Ok(AnnotationOutput::Data(data))
+    }
+}
@@ -5,18 +5,22 @@
 //! that works with any annotator.
 
 mod annotator;
+mod classification_annotator;
 mod date_annotator;
 mod llm_annotator;
 mod manager;
+mod metadata_annotator;
 mod ner_annotator;
 pub mod stage;
 mod types;
 mod url_annotator;
 
 pub use annotator::{get_document_text, Annotator};
+pub use classification_annotator::ClassificationAnnotator;
 pub use date_annotator::DateAnnotator;
 pub use llm_annotator::LlmAnnotator;
 pub use manager::AnnotationManager;
+pub use metadata_annotator::MetadataAnnotator;
 pub use ner_annotator::NerAnnotator;
 pub use types::{AnnotationError, AnnotationEvent, AnnotationOutput, BatchAnnotationResult};
 pub use stage::AnnotationStage;
@@ -143,6 +143,14 @@ impl Annotator for NerAnnotator {
             .save_document_entities(&entity_rows)
             .await
             .map_err(|e| AnnotationError::Database(e.to_string()))?;
+        doc_repo
+            .refresh_document_location(doc)
+            .await
+            .map_err(|e| AnnotationError::Database(e.to_string()))?;
+        doc_repo
+            .refresh_document_relations(doc)
+            .await
+            .map_err(|e| AnnotationError::Database(e.to_string()))?;
 
         Ok(())
     }
@@ -13,7 +13,7 @@ use foia::work_queue::{
 };
 
 use super::annotator::Annotator;
-use super::types::AnnotationOutput;
+use super::types::{AnnotationError, AnnotationOutput};
 
 /// Annotation pipeline stage — runs a single `Annotator` against documents.
 pub struct AnnotationStage {
@@ -89,6 +89,10 @@ impl PipelineStage for AnnotationStage {
         let stage_name = self.name().to_string();
 
         for doc in &docs {
+            // Stop taking items on shutdown; claimed ones finish
+            if foia::shutdown::is_requested() {
+                break;
+            }
             // Claim the document
             let work_handle = match self.queue.claim(doc, &self.filter).await {
                 Ok(h) => h,
@@ -186,6 +190,15 @@ impl PipelineStage for AnnotationStage {
                         .await;
                     skipped += 1;
                 }
+                Err(AnnotationError::BudgetExceeded(reason)) => {
+                    // Leave the document unannotated so it is picked up
+                    // again once there is budget, and stop the run.
+                    let _ = self.queue.fail(work_handle, &reason, true).await;
+                    return Err(PipelineError::Other(anyhow::anyhow!(
+                        "LLM budget exceeded: {}",
+                        reason
+                    )));
+                }
                 Err(e) => {
                     let _ = self
                         .doc_repo
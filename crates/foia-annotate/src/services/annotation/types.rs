@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use foia::llm::LlmError;
+
 /// Events emitted during annotation processing.
 /// Used by the CLI to drive progress bars and status messages.
 /// Fields are populated when events are created, even if consumers don't read all of them.
@@ -73,4 +75,18 @@ pub enum AnnotationError {
 
     #[error("Database error: {0}")]
     Database(String),
+
+    /// The LLM budget is spent; the run should stop rather than fail
+    /// every remaining document.
+    #[error("LLM budget exceeded: {0}")]
+    BudgetExceeded(String),
+}
+
+impl From<LlmError> for AnnotationError {
+    fn from(e: LlmError) -> Self {
+        match e {
+            LlmError::BudgetExceeded(reason) => Self::BudgetExceeded(reason),
+            e => Self::Failed(e.to_string()),
+        }
+    }
 }
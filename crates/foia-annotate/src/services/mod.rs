@@ -5,7 +5,8 @@ pub mod ner;
 #[allow(unused_imports)]
 pub use annotation::{
     AnnotationError, AnnotationEvent, AnnotationManager, AnnotationOutput, Annotator,
-    BatchAnnotationResult, DateAnnotator, LlmAnnotator, NerAnnotator, UrlAnnotator,
+    BatchAnnotationResult, ClassificationAnnotator, DateAnnotator, LlmAnnotator, MetadataAnnotator,
+    NerAnnotator, UrlAnnotator,
 };
 #[allow(unused_imports)]
 pub use date_detection::{detect_date, DateConfidence, DateEstimate, DateSource};
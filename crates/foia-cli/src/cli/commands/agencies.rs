@@ -0,0 +1,294 @@
+//! Agency registry commands.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use console::style;
+
+use foia::config::Settings;
+use foia::models::{tag_slug, Agency, AgencyLevel};
+use foia::repository::diesel_document::BrowseParams;
+use foia::services::agency_list::{self, AgencyListFormat};
+
+use super::helpers::truncate;
+
+/// Fields for a new agency.
+pub struct NewAgencyArgs {
+    pub name: String,
+    pub id: Option<String>,
+    pub abbreviation: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub level: Option<String>,
+    pub parent: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub portals: Vec<String>,
+    pub website: Option<String>,
+}
+
+fn parse_level(s: &str) -> anyhow::Result<AgencyLevel> {
+    AgencyLevel::from_str(s).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unknown level '{}' (expected federal, state, county, municipal, tribal or other)",
+            s
+        )
+    })
+}
+
+/// List agencies with the number of sources linked to each.
+pub async fn cmd_agencies_list(settings: &Settings, level: Option<&str>) -> anyhow::Result<()> {
+    let level = level.map(parse_level).transpose()?;
+    let repos = settings.repositories()?;
+    let agencies = repos.agencies.list(level).await?;
+
+    if agencies.is_empty() {
+        println!(
+            "{} No agencies. Add one with 'foia agencies add' or 'foia agencies import'.",
+            style("!").yellow()
+        );
+        return Ok(());
+    }
+    let counts = repos.agencies.source_counts().await?;
+
+    println!(
+        "{:<16}  {:<40}  {:<10}  {:<12}  {:>7}",
+        "ID", "Name", "Level", "Parent", "Sources"
+    );
+    for agency in &agencies {
+        println!(
+            "{:<16}  {:<40}  {:<10}  {:<12}  {:>7}",
+            truncate(&agency.id, 16),
+            truncate(&agency.name, 40),
+            agency.level.as_str(),
+            truncate(agency.parent_id.as_deref().unwrap_or("-"), 12),
+            counts.get(&agency.id).copied().unwrap_or(0)
+        );
+    }
+    Ok(())
+}
+
+/// Show one agency with its contacts, components and sources.
+pub async fn cmd_agencies_show(settings: &Settings, id: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let agency = repos
+        .agencies
+        .get(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Agency '{}' not found", id))?;
+
+    println!("{}", style(&agency.name).bold());
+    println!("  ID:           {}", agency.id);
+    if let Some(abbreviation) = &agency.abbreviation {
+        println!("  Abbreviation: {}", abbreviation);
+    }
+    println!("  Level:        {}", agency.level.as_str());
+    println!("  Jurisdiction: {}", agency.jurisdiction);
+    if let Some(parent) = &agency.parent_id {
+        println!("  Parent:       {}", parent);
+    }
+    println!("  Tag:          {}", agency.tag());
+    if let Some(email) = &agency.foia_email {
+        println!("  FOIA email:   {}", email);
+    }
+    if let Some(phone) = &agency.foia_phone {
+        println!("  FOIA phone:   {}", phone);
+    }
+    if let Some(address) = &agency.foia_address {
+        println!("  FOIA address: {}", address);
+    }
+    for url in &agency.portal_urls {
+        println!("  Portal:       {}", url);
+    }
+    if let Some(website) = &agency.website {
+        println!("  Website:      {}", website);
+    }
+
+    let children = repos.agencies.children(&agency.id).await?;
+    if !children.is_empty() {
+        println!("\n{} ({})", style("Components").bold(), children.len());
+        for child in &children {
+            println!("  {:<16}  {}", child.id, child.name);
+        }
+    }
+
+    let source_ids = repos.agencies.source_ids(&agency.id).await?;
+    println!("\n{} ({})", style("Sources").bold(), source_ids.len());
+    for source_id in &source_ids {
+        let docs = repos.documents.count_by_source(source_id).await?;
+        println!("  {:<24}  {} documents", source_id, docs);
+    }
+    Ok(())
+}
+
+/// Add an agency, or replace the one with the same ID.
+pub async fn cmd_agencies_add(settings: &Settings, args: NewAgencyArgs) -> anyhow::Result<()> {
+    let mut agency = Agency::new(args.name, args.abbreviation);
+    if let Some(id) = args.id {
+        agency.id = tag_slug(&id);
+    }
+    if agency.id.is_empty() {
+        anyhow::bail!("No usable ID for '{}'; pass --id", agency.name);
+    }
+    if let Some(level) = args.level.as_deref() {
+        agency.level = parse_level(level)?;
+    }
+    if let Some(jurisdiction) = args.jurisdiction {
+        agency.jurisdiction = jurisdiction;
+    }
+    agency.parent_id = args.parent;
+    agency.foia_email = args.email;
+    agency.foia_phone = args.phone;
+    agency.foia_address = args.address;
+    agency.portal_urls = args.portals;
+    agency.website = args.website;
+
+    let repos = settings.repositories()?;
+    if let Some(parent) = &agency.parent_id {
+        if repos.agencies.get(parent).await?.is_none() {
+            anyhow::bail!("Parent agency '{}' not found", parent);
+        }
+    }
+    if let Some(existing) = repos.agencies.get(&agency.id).await? {
+        agency.created_at = existing.created_at;
+    }
+    repos.agencies.save(&agency).await?;
+    println!(
+        "{} Saved {} ({})",
+        style("✓").green(),
+        agency.name,
+        style(agency.tag()).yellow()
+    );
+    Ok(())
+}
+
+/// Import agencies from a CSV, JSON or FOIA.gov agency list.
+pub async fn cmd_agencies_import(
+    settings: &Settings,
+    file: &Path,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file.display(), e))?;
+    let agencies = agency_list::parse(&content, AgencyListFormat::from_path(file))?;
+
+    if dry_run {
+        for agency in &agencies {
+            println!(
+                "  {:<16}  {:<40}  {}",
+                truncate(&agency.id, 16),
+                truncate(&agency.name, 40),
+                agency.parent_id.as_deref().unwrap_or("")
+            );
+        }
+        println!(
+            "\n{} {} agencies would be imported",
+            style("→").cyan(),
+            agencies.len()
+        );
+        return Ok(());
+    }
+
+    let repos = settings.repositories()?;
+    let (mut added, mut updated) = (0, 0);
+    for mut agency in agencies {
+        match repos.agencies.get(&agency.id).await? {
+            Some(existing) => {
+                agency.created_at = existing.created_at;
+                updated += 1;
+            }
+            None => added += 1,
+        }
+        repos.agencies.save(&agency).await?;
+    }
+    println!(
+        "{} Imported {} agencies ({} new, {} updated)",
+        style("✓").green(),
+        added + updated,
+        added,
+        updated
+    );
+    Ok(())
+}
+
+/// Link a source to an agency and tag its documents with the agency.
+pub async fn cmd_agencies_link(
+    settings: &Settings,
+    source_id: &str,
+    agency_id: &str,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let agency = repos
+        .agencies
+        .get(agency_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Agency '{}' not found", agency_id))?;
+    if !repos
+        .agencies
+        .link_source(source_id, Some(&agency.id))
+        .await?
+    {
+        anyhow::bail!("Source '{}' not found", source_id);
+    }
+
+    let params = BrowseParams {
+        source_id: Some(source_id),
+        ..Default::default()
+    };
+    let tagged = repos
+        .documents
+        .add_tag_matching(&agency.tag(), &params)
+        .await?;
+    println!(
+        "{} Linked {} to {}; tagged {} documents {}",
+        style("✓").green(),
+        source_id,
+        agency.name,
+        tagged,
+        style(agency.tag()).yellow()
+    );
+    Ok(())
+}
+
+/// Unlink a source from its agency. Existing tags are kept.
+pub async fn cmd_agencies_unlink(settings: &Settings, source_id: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    if !repos.agencies.link_source(source_id, None).await? {
+        anyhow::bail!("Source '{}' not found", source_id);
+    }
+    println!("{} Unlinked {}", style("✓").green(), source_id);
+    Ok(())
+}
+
+/// Delete an agency; its sources are unlinked and documents keep their tags.
+pub async fn cmd_agencies_delete(
+    settings: &Settings,
+    id: &str,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let agency = repos
+        .agencies
+        .get(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Agency '{}' not found", id))?;
+
+    if !confirm {
+        println!(
+            "Delete agency {} ({})? Its sources are unlinked; documents keep their tags.",
+            agency.id, agency.name
+        );
+        print!("\nProceed? [y/N] ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("{} Cancelled", style("!").yellow());
+            return Ok(());
+        }
+    }
+
+    repos.agencies.delete(&agency.id).await?;
+    println!("{} Deleted agency {}", style("✓").green(), agency.id);
+    Ok(())
+}
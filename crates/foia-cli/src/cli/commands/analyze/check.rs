@@ -26,6 +26,25 @@ pub async fn cmd_analyze_check() -> anyhow::Result<()> {
         println!("  {:<15} {}", tool, status);
     }
 
+    // pdfium can stand in for the Poppler tools
+    #[cfg(feature = "pdfium")]
+    {
+        let pdfium_status = if foia_analysis::ocr::pdfium_available() {
+            style("✓ found").green()
+        } else {
+            style("✗ library not found").red()
+        };
+        println!("  {:<15} {}", "pdfium", pdfium_status);
+    }
+    #[cfg(not(feature = "pdfium"))]
+    {
+        println!(
+            "  {:<15} {}",
+            "pdfium",
+            style("not compiled (enable pdfium feature)").dim()
+        );
+    }
+
     // Check new backends
     println!("\n{}", style("OCR Backends:").cyan());
 
@@ -133,8 +152,9 @@ pub async fn cmd_analyze_check() -> anyhow::Result<()> {
             "{} Some tools are missing. Install them for full OCR support:",
             style("!").yellow()
         );
-        println!("  - pdftotext, pdftoppm, pdfinfo: poppler-utils package");
+        println!("  - pdftotext, pdftoppm, pdfinfo: poppler-utils package (or pdfium)");
         println!("  - tesseract: tesseract-ocr package");
+        println!("Run `foia doctor --install` to install them.");
     }
 
     Ok(())
@@ -2,8 +2,22 @@
 
 mod check;
 mod compare;
+mod pdf_metadata;
+mod pii;
 mod process;
+mod redactions;
+mod reprocess;
+mod searchable_pdf;
+mod tables;
+mod unlock;
 
 pub use check::cmd_analyze_check;
 pub use compare::cmd_analyze_compare;
+pub use pdf_metadata::cmd_analyze_pdf_metadata;
+pub use pii::cmd_analyze_pii;
 pub use process::cmd_analyze;
+pub use redactions::cmd_analyze_redactions;
+pub use reprocess::cmd_analyze_reprocess;
+pub use searchable_pdf::cmd_analyze_searchable_pdf;
+pub use tables::cmd_analyze_tables;
+pub use unlock::cmd_analyze_unlock;
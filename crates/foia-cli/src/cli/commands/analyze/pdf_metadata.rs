@@ -0,0 +1,93 @@
+//! PDF metadata harvesting command.
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use foia::config::Settings;
+use foia_analysis::services::PdfMetadataService;
+
+/// Documents fetched per query.
+const BATCH_SIZE: usize = 100;
+
+/// Record the metadata embedded in PDFs and report the most common
+/// producers.
+pub async fn cmd_analyze_pdf_metadata(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+    let service = PdfMetadataService::new(doc_repo.clone(), settings.documents_dir.clone());
+
+    let pending = service.count_pending(source_id).await?;
+    let total = if limit > 0 {
+        pending.min(limit as u64)
+    } else {
+        pending
+    };
+
+    if total > 0 {
+        println!("{} Reading metadata from {} PDFs", style("→").cyan(), total);
+        let progress = ProgressBar::new(total);
+        progress.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+                .unwrap()
+                .progress_chars("█▓░"),
+        );
+
+        let (mut processed, mut found, mut failed) = (0u64, 0u64, 0u64);
+        let mut cursor: Option<String> = None;
+        'outer: loop {
+            let batch = service
+                .pending(source_id, BATCH_SIZE, cursor.as_deref())
+                .await?;
+            if batch.is_empty() {
+                break;
+            }
+            cursor = batch.last().map(|doc| doc.id.clone());
+
+            for doc in &batch {
+                if processed >= total {
+                    break 'outer;
+                }
+                progress.set_message(doc.title.clone());
+                match service.process_document(doc).await {
+                    Ok(metadata) if !metadata.is_empty() => found += 1,
+                    Ok(_) => {}
+                    Err(e) => {
+                        failed += 1;
+                        progress.suspend(|| {
+                            eprintln!("{} {}: {}", style("✗").red(), doc.id, e);
+                        });
+                    }
+                }
+                processed += 1;
+                progress.inc(1);
+            }
+        }
+        progress.finish_and_clear();
+
+        println!(
+            "{} Found metadata in {} of {} PDFs ({} failed)",
+            style("✓").green(),
+            found,
+            processed,
+            failed
+        );
+    } else {
+        println!("{} No PDFs need their metadata read", style("!").yellow());
+    }
+
+    let producers = doc_repo.get_pdf_producer_counts(source_id, 10).await?;
+    if producers.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!("{:<40} {:>10}", "Producer", "Documents");
+    for producer in producers {
+        println!("{:<40} {:>10}", producer.value, producer.documents);
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! Personal information scan command.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Instant;
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use foia::config::Settings;
+use foia::models::{
+    count_pii, describe_pii, pii_score, scan_pii, FlagReason, FlagStatus, MAX_FLAG_NOTE_LEN,
+};
+
+/// Analysis type the scan's results are stored under.
+const ANALYSIS_TYPE: &str = "pii";
+
+/// Start of the note on flags the scan opens, which tells them apart from
+/// readers' reports so a dismissed one isn't reopened by the next run.
+const FLAG_NOTE_PREFIX: &str = "PII scan";
+
+/// Findings kept, masked, in a result's metadata.
+const MAX_RECORDED_MATCHES: usize = 50;
+
+/// Scan documents' text for SSNs, dates of birth, phone numbers and street
+/// addresses, record each document's score and flag those at or above
+/// `threshold` for review.
+pub async fn cmd_analyze_pii(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+    threshold: u32,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+    let flags = repos.flags;
+
+    let mut doc_ids = doc_repo.get_document_ids_with_text(source_id).await?;
+    if limit > 0 {
+        doc_ids.truncate(limit);
+    }
+    if doc_ids.is_empty() {
+        println!("{} No documents have extracted text", style("!").yellow());
+        return Ok(());
+    }
+
+    let progress = ProgressBar::new(doc_ids.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    let (mut with_pii, mut over_threshold, mut flagged) = (0u64, 0u64, 0u64);
+    for id in &doc_ids {
+        progress.set_message(id.clone());
+        let Some(version_id) = doc_repo.get_current_version_id(id).await? else {
+            progress.inc(1);
+            continue;
+        };
+        let version_id = version_id as i32;
+        let started = Instant::now();
+
+        let mut matches = Vec::new();
+        let mut pages = BTreeSet::new();
+        for page in doc_repo.get_pages(id, version_id).await? {
+            let text = page
+                .final_text
+                .as_deref()
+                .or(page.ocr_text.as_deref())
+                .or(page.pdf_text.as_deref())
+                .unwrap_or_default();
+            for m in scan_pii(text) {
+                pages.insert(page.page_number);
+                matches.push((page.page_number, m));
+            }
+        }
+
+        let found: Vec<_> = matches.iter().map(|(_, m)| m.clone()).collect();
+        let counts = count_pii(&found);
+        let score = pii_score(&found);
+        let summary = describe_pii(&counts);
+        let metadata = serde_json::json!({
+            "score": score,
+            "counts": counts
+                .iter()
+                .map(|(kind, n)| (kind.as_str(), *n))
+                .collect::<BTreeMap<_, _>>(),
+            "pages": pages,
+            "matches": matches
+                .iter()
+                .take(MAX_RECORDED_MATCHES)
+                .map(|(page, m)| serde_json::json!({
+                    "kind": m.kind.as_str(),
+                    "page": page,
+                    "text": m.masked(),
+                }))
+                .collect::<Vec<_>>(),
+        });
+        doc_repo
+            .store_analysis_result_for_document(
+                id,
+                version_id,
+                ANALYSIS_TYPE,
+                "regex",
+                None,
+                Some(&summary),
+                Some(score as f32 / 100.0),
+                Some(started.elapsed().as_millis() as u64),
+                None,
+                Some(&metadata),
+            )
+            .await?;
+
+        if !found.is_empty() {
+            with_pii += 1;
+        }
+        if score >= threshold && !found.is_empty() {
+            over_threshold += 1;
+            let already = flags.for_document(id).await?.iter().any(|flag| {
+                flag.status == FlagStatus::Open
+                    || (flag.reason == FlagReason::Pii && flag.note.starts_with(FLAG_NOTE_PREFIX))
+            });
+            if !already {
+                let page_list = pages
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let mut note = format!(
+                    "{} (score {}): {} on {} {}",
+                    FLAG_NOTE_PREFIX,
+                    score,
+                    summary,
+                    if pages.len() == 1 { "page" } else { "pages" },
+                    page_list
+                );
+                if note.chars().count() > MAX_FLAG_NOTE_LEN {
+                    note = note.chars().take(MAX_FLAG_NOTE_LEN).collect();
+                }
+                flags
+                    .create(id, FlagReason::Pii, pages.first().copied(), &note)
+                    .await?;
+                flagged += 1;
+            }
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    println!(
+        "{} {} of {} documents contain likely personal information",
+        style("✓").green(),
+        with_pii,
+        doc_ids.len()
+    );
+    println!(
+        "  {} scored {} or more; {} newly flagged for review",
+        over_threshold, threshold, flagged
+    );
+    if flagged > 0 {
+        println!("  Review them at /flags in the web UI");
+    }
+    Ok(())
+}
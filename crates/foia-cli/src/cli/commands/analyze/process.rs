@@ -7,9 +7,9 @@ use indicatif::{ProgressBar, ProgressStyle};
 
 use foia::config::{Config, Settings};
 use foia::work_queue::ExecutionStrategy;
-use foia_analysis::ocr::TextExtractor;
+use foia_analysis::ocr::{configure_pdf_engine, PdfEngine, TextExtractor};
 
-use crate::cli::commands::daemon::{ConfigWatcher, DaemonAction, ReloadMode};
+use crate::cli::commands::daemon::{idle, ConfigWatcher, DaemonAction, ReloadMode};
 
 /// Analyze documents: detect MIME types, extract text, and run OCR.
 #[allow(clippy::too_many_arguments)]
@@ -36,11 +36,17 @@ pub async fn cmd_analyze(
     use foia_analysis::services::{AnalysisEvent, AnalysisService};
     use tokio::sync::mpsc;
 
+    foia::shutdown::install();
+
     // Load config early so we can check the right backends
     let config = Config::load().await;
 
-    // Phase 1: Check PDF processing tools (always required)
-    let pdf_tools = TextExtractor::check_pdf_tools();
+    // Phase 1: Check PDF processing tools (required unless pdfium is used)
+    let pdf_tools = if configure_pdf_engine(&config.analysis.pdf) == PdfEngine::Poppler {
+        TextExtractor::check_pdf_tools()
+    } else {
+        Vec::new()
+    };
     let missing_pdf: Vec<_> = pdf_tools.iter().filter(|(_, avail)| !avail).collect();
 
     if !missing_pdf.is_empty() {
@@ -49,7 +55,7 @@ pub async fn cmd_analyze(
             println!("  - {}", tool);
         }
         println!();
-        println!("Install poppler-utils, then run: foia ocr-check");
+        println!("Install poppler-utils (or use a build with pdfium), then run: foia ocr-check");
         return Err(anyhow::anyhow!(
             "Missing required PDF tools. Run 'foia ocr-check' for install instructions."
         ));
@@ -93,7 +99,8 @@ pub async fn cmd_analyze(
         reload,
         config_history,
         scraper_configs,
-        config.hash(),
+        &config,
+        &[],
     )
     .await;
 
@@ -102,6 +109,9 @@ pub async fn cmd_analyze(
         config.analysis.ocr.clone(),
         settings.documents_dir.clone(),
     )
+    .with_page_images(&config.analysis.page_images)
+    .with_limits(&config.analysis.limits)
+    .with_pool(&config.analysis.pool)
     .with_retry_interval(retry_interval);
 
     // If specific doc_id provided, process just that document (no daemon mode)
@@ -132,7 +142,9 @@ pub async fn cmd_analyze(
                     style("→").dim(),
                     interval
                 );
-                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+                if !idle(interval).await {
+                    return Ok(());
+                }
                 continue;
             } else {
                 println!("{} No documents need OCR processing", style("!").yellow());
@@ -376,7 +388,9 @@ pub async fn cmd_analyze(
 
         // Run service
         let _result = service
-            .process(source_id, &methods, workers, limit, mime_type, chunk_size, strategy, event_tx)
+            .process(
+                source_id, &methods, workers, limit, mime_type, chunk_size, strategy, event_tx,
+            )
             .await?;
 
         // Wait for event handler to finish
@@ -389,10 +403,17 @@ pub async fn cmd_analyze(
         }
 
         match config_watcher.sleep_or_reload(interval, "continuing").await {
-            DaemonAction::Exit => return Ok(()),
+            DaemonAction::Exit => break,
             DaemonAction::Continue | DaemonAction::Reload => {}
         }
     }
 
+    if foia::shutdown::is_requested() {
+        println!(
+            "{} Stopped on shutdown request after finishing in-flight documents",
+            style("!").yellow()
+        );
+    }
+
     Ok(())
 }
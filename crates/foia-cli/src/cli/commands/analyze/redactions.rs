@@ -0,0 +1,66 @@
+//! Redaction marking command.
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use foia::config::Settings;
+
+/// Mark redactions in documents' text and report the exemptions they cite.
+pub async fn cmd_analyze_redactions(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+
+    let mut doc_ids = doc_repo.get_document_ids_with_text(source_id).await?;
+    if limit > 0 {
+        doc_ids.truncate(limit);
+    }
+    if doc_ids.is_empty() {
+        println!("{} No documents have extracted text", style("!").yellow());
+        return Ok(());
+    }
+
+    let progress = ProgressBar::new(doc_ids.len() as u64);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    let mut with_exemptions = 0u64;
+    for id in &doc_ids {
+        progress.set_message(id.clone());
+        if !doc_repo.mark_current_redactions(id).await?.is_empty() {
+            with_exemptions += 1;
+        }
+        progress.inc(1);
+    }
+    progress.finish_and_clear();
+
+    println!(
+        "{} {} of {} documents cite exemptions",
+        style("✓").green(),
+        with_exemptions,
+        doc_ids.len()
+    );
+
+    let stats = doc_repo.get_exemption_stats(source_id).await?;
+    if stats.is_empty() {
+        return Ok(());
+    }
+    println!();
+    println!(
+        "{:<12} {:>10} {:>10}",
+        "Exemption", "Documents", "Citations"
+    );
+    for stat in stats {
+        println!(
+            "{:<12} {:>10} {:>10}",
+            stat.code, stat.documents, stat.citations
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Selective OCR reprocessing by recorded tool settings.
+
+use std::collections::BTreeMap;
+
+use console::style;
+
+use foia::config::Settings;
+use foia::services::ocr_reprocess::OcrRunFilter;
+
+/// Queue pages for OCR again whose results match a settings filter.
+pub async fn cmd_analyze_reprocess(
+    settings: &Settings,
+    source_id: Option<&str>,
+    filter: &str,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let filter = OcrRunFilter::parse(filter).map_err(|e| anyhow::anyhow!(e))?;
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+
+    let matching: Vec<_> = doc_repo
+        .get_ocr_runs(source_id)
+        .await?
+        .into_iter()
+        .filter(|run| filter.matches(run))
+        .collect();
+
+    if matching.is_empty() {
+        println!("{} No OCR results match", style("!").yellow());
+        return Ok(());
+    }
+
+    let mut groups: BTreeMap<String, usize> = BTreeMap::new();
+    for run in &matching {
+        let s = &run.settings;
+        let key = format!(
+            "{}{}  version={}  language={}  dpi={}",
+            run.backend,
+            run.model
+                .as_deref()
+                .map(|m| format!(" ({})", m))
+                .unwrap_or_default(),
+            s.tool_version.as_deref().unwrap_or("none"),
+            s.language.as_deref().unwrap_or("none"),
+            s.dpi.map(|d| d.to_string()).as_deref().unwrap_or("none"),
+        );
+        *groups.entry(key).or_default() += 1;
+    }
+
+    println!("\n{}", style("Matching OCR results").bold());
+    println!("{}", "-".repeat(50));
+    for (key, count) in &groups {
+        println!("  {:>6}  {}", count, key);
+    }
+
+    if dry_run {
+        let mut pages: Vec<i64> = matching.iter().map(|r| r.page_id).collect();
+        pages.sort_unstable();
+        pages.dedup();
+        println!(
+            "\n{} Dry run: {} results on {} pages would be reprocessed",
+            style("→").cyan(),
+            matching.len(),
+            pages.len()
+        );
+        return Ok(());
+    }
+
+    let pages = doc_repo.requeue_ocr_results(&matching).await?;
+    println!(
+        "\n{} Removed {} results and queued {} pages for OCR",
+        style("✓").green(),
+        matching.len(),
+        pages
+    );
+    println!("  Run {} to reprocess them", style("foia analyze").cyan());
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Searchable PDF command.
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use foia::config::{Config, Settings};
+use foia::models::Document;
+use foia_analysis::searchable_pdf::SearchablePdfTool;
+use foia_analysis::services::SearchablePdfService;
+
+/// Documents fetched per query.
+const BATCH_SIZE: usize = 100;
+
+/// Generate searchable copies of PDFs, and regenerate stale ones.
+pub async fn cmd_analyze_searchable_pdf(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let tool = SearchablePdfTool::detect().ok_or_else(|| {
+        anyhow::anyhow!(
+            "No searchable PDF tool found. Install ocrmypdf, or tesseract and poppler-utils."
+        )
+    })?;
+
+    let config = Config::load().await;
+    let repos = settings.repositories()?;
+    let service = SearchablePdfService::new(
+        repos.documents,
+        settings.documents_dir.clone(),
+        &config.analysis.ocr.language,
+        tool,
+    );
+
+    let stale = service.stale(source_id).await?;
+    let pending = service.count_pending(source_id).await? + stale.len() as u64;
+    if pending == 0 {
+        println!("{} No PDFs need a searchable copy", style("!").yellow());
+        return Ok(());
+    }
+    let total = if limit > 0 {
+        pending.min(limit as u64)
+    } else {
+        pending
+    };
+
+    println!(
+        "{} Making {} PDFs searchable with {}",
+        style("→").cyan(),
+        total,
+        tool.as_str()
+    );
+    let progress = ProgressBar::new(total);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+
+    let (mut processed, mut failed) = (0u64, 0u64);
+    for doc in stale {
+        if processed >= total {
+            break;
+        }
+        if !process(&service, &progress, &doc).await {
+            failed += 1;
+        }
+        processed += 1;
+    }
+
+    let mut cursor: Option<String> = None;
+    'outer: while processed < total {
+        let batch = service
+            .pending(source_id, BATCH_SIZE, cursor.as_deref())
+            .await?;
+        if batch.is_empty() {
+            break;
+        }
+        cursor = batch.last().map(|doc| doc.id.clone());
+
+        for doc in batch {
+            if processed >= total {
+                break 'outer;
+            }
+            if !process(&service, &progress, &doc).await {
+                failed += 1;
+            }
+            processed += 1;
+        }
+    }
+    progress.finish_and_clear();
+
+    println!(
+        "{} Made {} of {} PDFs searchable ({} failed)",
+        style("✓").green(),
+        processed - failed,
+        processed,
+        failed
+    );
+    Ok(())
+}
+
+/// Process one document, reporting failures; returns whether it succeeded.
+async fn process(service: &SearchablePdfService, progress: &ProgressBar, doc: &Document) -> bool {
+    progress.set_message(doc.title.clone());
+    let result = service.process_document(doc).await;
+    if let Err(e) = &result {
+        progress.suspend(|| {
+            eprintln!("{} {}: {}", style("✗").red(), doc.id, e);
+        });
+    }
+    progress.inc(1);
+    result.is_ok()
+}
@@ -0,0 +1,107 @@
+//! Table extraction command.
+
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use foia::config::{Config, Settings};
+use foia_analysis::ocr::TextExtractor;
+use foia_analysis::services::TableExtractionService;
+
+/// Documents fetched per query.
+const BATCH_SIZE: usize = 100;
+
+/// Extract tables from PDFs into CSV files attached to each document.
+pub async fn cmd_analyze_tables(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let missing: Vec<_> = TextExtractor::check_pdf_tools()
+        .into_iter()
+        .filter(|(_, available)| !available)
+        .map(|(tool, _)| tool)
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Missing required PDF tools: {}. Install poppler-utils.",
+            missing.join(", ")
+        ));
+    }
+
+    let config = Config::load().await;
+    let repos = settings.repositories()?;
+    let service = TableExtractionService::new(
+        repos.documents,
+        settings.documents_dir.clone(),
+        &config.analysis.ocr.language,
+    );
+
+    let pending = service.count_pending(source_id).await?;
+    if pending == 0 {
+        println!("{} No PDFs need table extraction", style("!").yellow());
+        return Ok(());
+    }
+    let total = if limit > 0 {
+        pending.min(limit as u64)
+    } else {
+        pending
+    };
+
+    println!(
+        "{} Extracting tables from {} PDFs",
+        style("→").cyan(),
+        total
+    );
+    let progress = ProgressBar::new(total);
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+
+    let (mut processed, mut with_tables, mut tables, mut failed) = (0u64, 0u64, 0usize, 0u64);
+    let mut cursor: Option<String> = None;
+    'outer: loop {
+        let batch = service
+            .pending(source_id, BATCH_SIZE, cursor.as_deref())
+            .await?;
+        if batch.is_empty() {
+            break;
+        }
+        cursor = batch.last().map(|doc| doc.id.clone());
+
+        for doc in &batch {
+            if processed >= total {
+                break 'outer;
+            }
+            progress.set_message(doc.title.clone());
+            match service.process_document(doc).await {
+                Ok(0) => {}
+                Ok(found) => {
+                    with_tables += 1;
+                    tables += found;
+                }
+                Err(e) => {
+                    failed += 1;
+                    progress.suspend(|| {
+                        eprintln!("{} {}: {}", style("✗").red(), doc.id, e);
+                    });
+                }
+            }
+            processed += 1;
+            progress.inc(1);
+        }
+    }
+    progress.finish_and_clear();
+
+    println!(
+        "{} Found {} tables in {} of {} PDFs ({} failed)",
+        style("✓").green(),
+        tables,
+        with_tables,
+        processed,
+        failed
+    );
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Release analysis claims held by dead workers.
+
+use console::style;
+
+use foia::config::Settings;
+
+/// Delete pending analysis claims so their documents can be picked up again.
+///
+/// Claims lapse on their own once the lease passes; this is for when a
+/// worker is known to be gone and waiting out the lease is not wanted.
+pub async fn cmd_analyze_unlock(
+    settings: &Settings,
+    source_id: Option<&str>,
+    analysis_type: Option<&str>,
+    stale_only: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let released = repos
+        .documents
+        .release_analysis_claims(analysis_type, source_id, stale_only)
+        .await?;
+
+    if released == 0 {
+        println!("{} No analysis claims to release", style("!").yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{} Released {} {}analysis claims",
+        style("✓").green(),
+        released,
+        if stale_only { "expired " } else { "" }
+    );
+    if !stale_only {
+        println!(
+            "  {} Workers still running on these documents will finish them, \
+             but another worker may start them too",
+            style("→").dim()
+        );
+    }
+    Ok(())
+}
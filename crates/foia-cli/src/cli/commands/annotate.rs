@@ -7,13 +7,16 @@ use indicatif::{ProgressBar, ProgressStyle};
 use tokio::sync::mpsc;
 
 use foia::config::{Config, Settings};
+use foia::llm::{LlmConfig, UsageTracker};
 use foia::work_queue::ExecutionStrategy;
 use foia_annotate::services::annotation::{
-    AnnotationEvent, AnnotationManager, Annotator, DateAnnotator, LlmAnnotator, NerAnnotator,
+    AnnotationEvent, AnnotationManager, Annotator, ClassificationAnnotator, DateAnnotator,
+    LlmAnnotator, MetadataAnnotator, NerAnnotator,
 };
 
-use super::daemon::{ConfigWatcher, DaemonAction, ReloadMode};
+use super::daemon::{idle, ConfigWatcher, DaemonAction, ReloadMode};
 use super::helpers::truncate;
+use super::llm::{print_run_usage, usage_tracker};
 
 /// Spawn a task that drives a progress bar from annotation events.
 ///
@@ -112,6 +115,7 @@ pub async fn cmd_annotate(
     reload: ReloadMode,
     strategy: ExecutionStrategy,
 ) -> anyhow::Result<()> {
+    foia::shutdown::install();
     let repos = settings.repositories()?;
     let manager = AnnotationManager::new(repos.documents.clone());
 
@@ -125,7 +129,8 @@ pub async fn cmd_annotate(
         reload,
         config_history,
         scraper_configs,
-        config.hash(),
+        &config,
+        &["llm"],
     )
     .await;
     let mut llm_config = config.llm.clone();
@@ -168,6 +173,8 @@ pub async fn cmd_annotate(
     if let Some(id) = doc_id {
         println!("{} Processing single document: {}", style("→").cyan(), id);
         let (event_tx, _event_rx) = mpsc::channel::<AnnotationEvent>(100);
+        let annotator =
+            annotator.with_usage_tracker(usage_tracker(&repos.llm_usage, &llm_config).await?);
         return manager.process_single(&annotator, id, event_tx).await;
     }
 
@@ -216,7 +223,9 @@ pub async fn cmd_annotate(
                     style("→").dim(),
                     interval
                 );
-                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+                if !idle(interval).await {
+                    return Ok(());
+                }
                 continue;
             } else {
                 println!("{} No documents need annotation", style("!").yellow());
@@ -242,25 +251,36 @@ pub async fn cmd_annotate(
         let (event_tx, event_rx) = mpsc::channel::<AnnotationEvent>(100);
         let event_handler = spawn_progress_handler(event_rx, "Annotation");
 
-        let annotator_arc: Arc<dyn Annotator> = Arc::new(LlmAnnotator::new(llm_config.clone()));
-        let _result = manager
+        let tracker = usage_tracker(&repos.llm_usage, &llm_config).await?;
+        let annotator_arc: Arc<dyn Annotator> =
+            Arc::new(LlmAnnotator::new(llm_config.clone()).with_usage_tracker(tracker.clone()));
+        let result = manager
             .run_batch(annotator_arc, source_id, limit, chunk_size, strategy, event_tx)
-            .await?;
+            .await;
 
         if let Err(e) = event_handler.await {
             tracing::warn!("Event handler task failed: {}", e);
         }
+        print_run_usage(&tracker);
+        result?;
 
         if !daemon {
             break;
         }
 
         match config_watcher.sleep_or_reload(interval, "reloading").await {
-            DaemonAction::Exit => return Ok(()),
+            DaemonAction::Exit => break,
             DaemonAction::Continue | DaemonAction::Reload => {}
         }
     }
 
+    if foia::shutdown::is_requested() {
+        println!(
+            "{} Stopped on shutdown request after finishing in-flight documents",
+            style("!").yellow()
+        );
+    }
+
     Ok(())
 }
 
@@ -375,6 +395,111 @@ pub async fn cmd_extract_entities(
     Ok(())
 }
 
+/// Run an LLM annotator over the documents that need it, with a progress
+/// bar and a token usage summary. `pending` completes "No documents need",
+/// `action` comes before "up to N documents" and `label` names the bar.
+async fn run_llm_annotator<A: Annotator + 'static>(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+    build: impl FnOnce(LlmConfig, Arc<UsageTracker>) -> A,
+    pending: &str,
+    action: &str,
+    label: &str,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let config = Config::load().await;
+
+    if !config.llm.enabled() {
+        println!(
+            "{} LLM annotation is disabled in configuration",
+            style("!").yellow()
+        );
+        println!("  Set llm.enabled = true in your foia.json config");
+        return Ok(());
+    }
+
+    let tracker = usage_tracker(&repos.llm_usage, &config.llm).await?;
+    let annotator = build(config.llm.clone(), tracker.clone());
+    if !annotator.is_available().await {
+        println!("{} {}", style("✗").red(), annotator.availability_hint());
+        return Ok(());
+    }
+
+    let manager = AnnotationManager::new(repos.documents);
+    let total_count = manager.count_needing(&annotator, source_id).await?;
+
+    if total_count == 0 {
+        println!("{} No documents need {}", style("!").yellow(), pending);
+        return Ok(());
+    }
+
+    let effective_limit = if limit > 0 {
+        limit
+    } else {
+        total_count as usize
+    };
+
+    println!(
+        "{} {} up to {} documents",
+        style("→").cyan(),
+        action,
+        effective_limit
+    );
+
+    let (event_tx, event_rx) = mpsc::channel::<AnnotationEvent>(100);
+    let event_handler = spawn_progress_handler(event_rx, label);
+
+    let annotator_arc: Arc<dyn Annotator> = Arc::new(annotator);
+    let result = manager
+        .run_batch(annotator_arc, source_id, limit, None, ExecutionStrategy::Wide, event_tx)
+        .await;
+
+    if let Err(e) = event_handler.await {
+        tracing::warn!("Event handler task failed: {}", e);
+    }
+    print_run_usage(&tracker);
+    result?;
+
+    Ok(())
+}
+
+/// Classify documents into controlled-vocabulary record types.
+pub async fn cmd_classify_records(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    run_llm_annotator(
+        settings,
+        source_id,
+        limit,
+        |llm, tracker| ClassificationAnnotator::new(llm).with_usage_tracker(tracker),
+        "record type classification",
+        "Classifying",
+        "Classification",
+    )
+    .await
+}
+
+/// Extract structured metadata fields from documents using LLM.
+pub async fn cmd_extract_metadata(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    run_llm_annotator(
+        settings,
+        source_id,
+        limit,
+        |llm, tracker| MetadataAnnotator::new(llm).with_usage_tracker(tracker),
+        "metadata extraction",
+        "Extracting metadata from",
+        "Metadata",
+    )
+    .await
+}
+
 /// Reset annotations for documents, allowing them to be re-annotated.
 pub async fn cmd_annotate_reset(
     settings: &Settings,
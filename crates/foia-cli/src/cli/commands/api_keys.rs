@@ -0,0 +1,102 @@
+//! JSON API key commands: issuing, listing and revoking keys.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use console::style;
+
+use foia::config::{Config, Settings};
+
+use super::helpers::truncate;
+
+/// Issue a key and print it; it can't be shown again. An `admin` key may
+/// also manage keys through the API.
+pub async fn cmd_api_key_create(
+    settings: &Settings,
+    name: &str,
+    rate_limit: Option<u32>,
+    admin: bool,
+) -> anyhow::Result<()> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        anyhow::bail!("Key name must be 1-100 characters");
+    }
+    let repos = settings.repositories()?;
+    if repos.api_keys.get_by_name(name).await?.is_some() {
+        anyhow::bail!("A key named '{}' already exists", name);
+    }
+    let issued = repos.api_keys.issue(name, rate_limit, admin).await?;
+    let limit = rate_limit.unwrap_or(Config::load().await.server.api_rate_limit());
+    println!(
+        "{} Issued {}'{}' ({} requests/min)",
+        style("✓").green(),
+        if admin { "admin key " } else { "" },
+        name,
+        limit.max(1)
+    );
+    println!("\n  {}\n", style(&issued.key).bold());
+    println!(
+        "  {} Store it now; only a hash of it is kept",
+        style("→").dim()
+    );
+    Ok(())
+}
+
+/// List keys with their use over the last 30 days.
+pub async fn cmd_api_key_list(settings: &Settings) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let keys = repos.api_keys.list().await?;
+    if keys.is_empty() {
+        println!("No API keys; issue one with `foia api-key create <name>`");
+        return Ok(());
+    }
+    let default_limit = Config::load().await.server.api_rate_limit();
+    let usage: HashMap<i32, _> = repos
+        .api_keys
+        .usage_since(Utc::now() - Duration::days(29))
+        .await?
+        .into_iter()
+        .map(|u| (u.key_id, u))
+        .collect();
+
+    println!(
+        "{:<24}  {:<14}  {:<6}  {:>7}  {:>10}  {:>8}  {:<16}  Status",
+        "Name", "Key", "Role", "Req/min", "30d reqs", "Refused", "Last used"
+    );
+    for key in &keys {
+        let used = usage.get(&key.id).cloned().unwrap_or_default();
+        let last_used = key
+            .last_used_at
+            .as_deref()
+            .map(|t| t.get(..16).unwrap_or(t).replace('T', " "))
+            .unwrap_or_else(|| "never".to_string());
+        println!(
+            "{:<24}  {:<14}  {:<6}  {:>7}  {:>10}  {:>8}  {:<16}  {}",
+            truncate(&key.name, 24),
+            format!("{}…", key.prefix),
+            key.role,
+            key.rate_limit
+                .map_or(default_limit, |limit| limit.max(1) as u32),
+            used.requests,
+            used.rejected,
+            last_used,
+            if key.revoked_at.is_some() {
+                style("revoked").red().to_string()
+            } else {
+                style("active").green().to_string()
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Revoke a key; requests made with it are refused from then on.
+pub async fn cmd_api_key_revoke(settings: &Settings, name: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    if repos.api_keys.revoke(name).await? {
+        println!("{} Revoked '{}'", style("✓").green(), name);
+    } else {
+        anyhow::bail!("No active API key named '{}'", name);
+    }
+    Ok(())
+}
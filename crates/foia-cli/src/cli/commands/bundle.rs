@@ -0,0 +1,163 @@
+//! Portable bundle commands.
+
+use std::path::Path;
+
+use console::style;
+
+use foia::config::Settings;
+use foia::services::bundle::{self, BundleManifest};
+use foia::services::torrent::{self, TorrentOptions};
+
+use super::helpers::format_bytes;
+
+fn print_manifest(manifest: &BundleManifest) {
+    let bytes: u64 = manifest.files.iter().map(|f| f.size).sum();
+    println!("  Sources:   {}", manifest.sources.join(", "));
+    println!("  Documents: {}", manifest.documents);
+    println!("  Versions:  {}", manifest.versions);
+    println!(
+        "  Files:     {} ({})",
+        manifest.files.len(),
+        format_bytes(bytes)
+    );
+    println!(
+        "  Schema:    {} (format {}, foia {})",
+        manifest.schema_version, manifest.format_version, manifest.foia_version
+    );
+    println!("  Created:   {}", manifest.created_at.to_rfc3339());
+}
+
+/// Export sources with their documents and files as a bundle.
+pub async fn cmd_bundle_export(
+    settings: &Settings,
+    output: &Path,
+    source_ids: Vec<String>,
+    all: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let source_ids = if all {
+        repos
+            .sources
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|s| s.id)
+            .collect()
+    } else if source_ids.is_empty() {
+        anyhow::bail!("Name at least one source, or pass --all");
+    } else {
+        source_ids
+    };
+
+    let manifest = bundle::export_bundle(
+        &repos.documents,
+        &repos.sources,
+        &source_ids,
+        &settings.documents_dir,
+        output,
+    )
+    .await?;
+    print_manifest(&manifest);
+    if !manifest.missing_files.is_empty() {
+        println!(
+            "{} {} files were missing and are not in the bundle",
+            style("!").yellow(),
+            manifest.missing_files.len()
+        );
+    }
+    println!(
+        "{} Wrote bundle to {}",
+        style("✓").green(),
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Import a bundle into this instance.
+pub async fn cmd_bundle_import(settings: &Settings, path: &Path) -> anyhow::Result<()> {
+    let manifest = bundle::read_manifest(path)?;
+    print_manifest(&manifest);
+
+    let repos = settings.repositories()?;
+    let result = bundle::import_bundle(
+        &repos.documents,
+        &repos.sources,
+        &settings.documents_dir,
+        path,
+    )
+    .await?;
+
+    println!(
+        "{} Imported {} documents ({} merged into existing), {} versions, {} files, {} sources",
+        style("✓").green(),
+        result.documents_added,
+        result.documents_merged,
+        result.versions_added,
+        result.files_written,
+        result.sources_added
+    );
+    if result.ids_remapped > 0 {
+        println!(
+            "  {} documents were given new IDs because theirs were taken",
+            result.ids_remapped
+        );
+    }
+
+    Ok(())
+}
+
+/// Show what a bundle contains.
+pub fn cmd_bundle_inspect(path: &Path) -> anyhow::Result<()> {
+    let manifest = bundle::read_manifest(path)?;
+    println!("{}", style(path.display()).bold());
+    print_manifest(&manifest);
+    if !manifest.missing_files.is_empty() {
+        println!(
+            "  {} files were missing when the bundle was written",
+            manifest.missing_files.len()
+        );
+    }
+    Ok(())
+}
+
+/// Bundle a source, create a torrent of it and record the magnet link.
+pub async fn cmd_bundle_torrent(
+    settings: &Settings,
+    source_id: &str,
+    output: &Path,
+    trackers: Vec<String>,
+    web_seeds: Vec<String>,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let options = TorrentOptions {
+        trackers,
+        web_seeds,
+        comment: None,
+    };
+    let published = torrent::publish_torrent(
+        &repos.documents,
+        &repos.sources,
+        source_id,
+        &settings.documents_dir,
+        output,
+        &options,
+    )
+    .await?;
+
+    println!(
+        "  Content:   {} ({} files, {})",
+        published.content_dir.display(),
+        published.torrent.files,
+        format_bytes(published.torrent.size)
+    );
+    println!("  Torrent:   {}", published.torrent_path.display());
+    println!("  Info hash: {}", published.torrent.info_hash);
+    println!(
+        "{} Seed the content directory to share it:",
+        style("✓").green()
+    );
+    println!("  {}", published.distribution.magnet);
+
+    Ok(())
+}
@@ -1,11 +1,19 @@
 //! Configuration management commands.
 
+use std::collections::BTreeMap;
+use std::io::{self, Write};
 use std::path::Path;
 
 use console::style;
+use serde_json::Value;
 
 use crate::cli::icons::{error, success};
+use foia::config::reload::{self, snapshot_data};
 use foia::config::{Config, ScraperConfig, Settings};
+use foia::repository::{DieselConfigHistoryEntry, DieselConfigHistoryRepository};
+
+/// Length of the snapshot IDs shown by `config history`.
+const SHORT_ID_LEN: usize = 8;
 
 /// Migrate a config file into the database.
 pub async fn cmd_config_transfer(settings: &Settings, file: Option<&Path>) -> anyhow::Result<()> {
@@ -143,6 +151,239 @@ pub async fn cmd_config_set(settings: &Settings, setting: &str, value: &str) ->
     Ok(())
 }
 
+/// A setting that differs between two configs: path, old value, new value.
+type SettingChange = (String, Option<Value>, Option<Value>);
+
+/// Flatten a JSON config into dot-separated setting paths. Arrays are
+/// compared whole.
+fn flatten_settings(value: &Value, prefix: &str, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten_settings(value, &path, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+/// Settings added, removed or changed between two configs.
+fn diff_settings(old: &Value, new: &Value) -> Vec<SettingChange> {
+    let (mut old_settings, mut new_settings) = (BTreeMap::new(), BTreeMap::new());
+    flatten_settings(old, "", &mut old_settings);
+    flatten_settings(new, "", &mut new_settings);
+
+    let mut paths: Vec<&String> = old_settings.keys().chain(new_settings.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter(|path| old_settings.get(*path) != new_settings.get(*path))
+        .map(|path| {
+            (
+                path.clone(),
+                old_settings.get(path).cloned(),
+                new_settings.get(path).cloned(),
+            )
+        })
+        .collect()
+}
+
+fn print_diff(changes: &[SettingChange]) {
+    for (path, old, new) in changes {
+        if let Some(old) = old {
+            println!("  {}", style(format!("- {}: {}", path, old)).red());
+        }
+        if let Some(new) = new {
+            println!("  {}", style(format!("+ {}: {}", path, new)).green());
+        }
+    }
+}
+
+fn short_id(uuid: &str) -> &str {
+    &uuid[..uuid.len().min(SHORT_ID_LEN)]
+}
+
+/// Find the snapshot whose ID starts with `id`.
+async fn find_snapshot(
+    history: &DieselConfigHistoryRepository,
+    id: &str,
+) -> anyhow::Result<DieselConfigHistoryEntry> {
+    let mut matches = history.find_by_uuid_prefix(id).await?;
+    match matches.len() {
+        0 => anyhow::bail!(
+            "No config snapshot matches '{}'. Run 'config history' to list them.",
+            id
+        ),
+        1 => Ok(matches.remove(0)),
+        n => anyhow::bail!("'{}' matches {} snapshots; give more of the ID", id, n),
+    }
+}
+
+fn snapshot_settings(entry: &DieselConfigHistoryEntry) -> anyhow::Result<Value> {
+    serde_json::from_str(&entry.data).map_err(|e| {
+        anyhow::anyhow!(
+            "Snapshot {} is not valid JSON: {}",
+            short_id(&entry.uuid),
+            e
+        )
+    })
+}
+
+/// The active config as it would be stored in the history.
+fn active_settings(config: &Config) -> Value {
+    serde_json::from_str(&snapshot_data(config)).unwrap_or(Value::Null)
+}
+
+/// List stored config snapshots, most recent first.
+pub async fn cmd_config_history(settings: &Settings, config: &Config) -> anyhow::Result<()> {
+    let history = settings.repositories()?.config_history;
+    let entries = history.get_all().await?;
+    if entries.is_empty() {
+        println!("{} No config history", style("!").yellow());
+        return Ok(());
+    }
+
+    let active_hash = config.hash();
+    for (i, entry) in entries.iter().enumerate() {
+        let changes = match entries.get(i + 1) {
+            Some(older) => {
+                let changed = diff_settings(
+                    &snapshot_settings(older).unwrap_or(Value::Null),
+                    &snapshot_settings(entry).unwrap_or(Value::Null),
+                )
+                .len();
+                format!("{} settings changed", changed)
+            }
+            None => "oldest".to_string(),
+        };
+        println!(
+            "{}  {}  {}{}",
+            style(short_id(&entry.uuid)).cyan(),
+            entry.created_at.format("%Y-%m-%d %H:%M"),
+            changes,
+            if entry.hash == active_hash {
+                style("  (active)").green().to_string()
+            } else {
+                String::new()
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Show how snapshot `id` differs from snapshot `other`, or from the active
+/// config when `other` is not given.
+pub async fn cmd_config_diff(
+    settings: &Settings,
+    config: &Config,
+    id: &str,
+    other: Option<&str>,
+) -> anyhow::Result<()> {
+    let history = settings.repositories()?.config_history;
+    let entry = find_snapshot(&history, id).await?;
+    let (label, new) = match other {
+        Some(other) => {
+            let other = find_snapshot(&history, other).await?;
+            (
+                short_id(&other.uuid).to_string(),
+                snapshot_settings(&other)?,
+            )
+        }
+        None => ("active config".to_string(), active_settings(config)),
+    };
+
+    let changes = diff_settings(&snapshot_settings(&entry)?, &new);
+    if changes.is_empty() {
+        println!("{} No differences", success());
+        return Ok(());
+    }
+    println!("{} → {}:", short_id(&entry.uuid), label);
+    print_diff(&changes);
+    Ok(())
+}
+
+/// Restore snapshot `id` to the active config file.
+pub async fn cmd_config_rollback(
+    settings: &Settings,
+    config: &Config,
+    id: &str,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let Some(path) = config.source_path.as_deref() else {
+        anyhow::bail!("No config file is loaded; use --config to choose the file to restore");
+    };
+    let history = settings.repositories()?.config_history;
+    let entry = find_snapshot(&history, id).await?;
+    let restored: Config = serde_json::from_str(&entry.data).map_err(|e| {
+        anyhow::anyhow!(
+            "Snapshot {} is not a valid config: {}",
+            short_id(&entry.uuid),
+            e
+        )
+    })?;
+    if let Err(errors) = reload::validate(&restored) {
+        anyhow::bail!(
+            "Snapshot {} is not a valid config: {}",
+            short_id(&entry.uuid),
+            errors.join("; ")
+        );
+    }
+
+    let changes = diff_settings(&active_settings(config), &snapshot_settings(&entry)?);
+    if changes.is_empty() {
+        println!(
+            "{} {} already matches snapshot {}",
+            success(),
+            path.display(),
+            short_id(&entry.uuid)
+        );
+        return Ok(());
+    }
+
+    if !confirm {
+        println!(
+            "Restore snapshot {} from {} to {}:",
+            short_id(&entry.uuid),
+            entry.created_at.format("%Y-%m-%d %H:%M"),
+            path.display()
+        );
+        print_diff(&changes);
+        println!("The file is rewritten; comments in it are not kept.");
+        print!("\nProceed? [y/N] ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("{} Cancelled", style("!").yellow());
+            return Ok(());
+        }
+    }
+
+    // Keep the config being replaced so the rollback can itself be undone
+    reload::snapshot(&history, config).await;
+    let contents = restored
+        .to_file_contents(path)
+        .map_err(anyhow::Error::msg)?;
+    tokio::fs::write(path, contents).await?;
+
+    eprintln!(
+        "{} Restored snapshot {} ({} settings changed)",
+        success(),
+        short_id(&entry.uuid),
+        changes.len()
+    );
+    eprintln!("  {} {}", style("→").dim(), path.display());
+    Ok(())
+}
+
 /// Navigate a JSON value by dot-separated path.
 fn navigate_json<'a>(
     value: &'a serde_json::Value,
@@ -313,6 +554,36 @@ mod tests {
         assert_eq!(value, json!({"new": "data"}));
     }
 
+    #[test]
+    fn test_diff_settings() {
+        let old = json!({
+            "request_delay_ms": 500,
+            "llm": {"model": "a", "max_tokens": 100},
+            "via": {"https://a.gov": "https://cache/a"}
+        });
+        let new = json!({
+            "llm": {"model": "b", "max_tokens": 100},
+            "via": {},
+            "user_agent": "Test/1.0"
+        });
+        let changes = diff_settings(&old, &new);
+        let paths: Vec<&str> = changes.iter().map(|(p, _, _)| p.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "llm.model",
+                "request_delay_ms",
+                "user_agent",
+                "via",
+                "via.https://a.gov"
+            ]
+        );
+        assert_eq!(changes[0].1, Some(json!("a")));
+        assert_eq!(changes[0].2, Some(json!("b")));
+        assert_eq!(changes[1].2, None);
+        assert!(diff_settings(&old, &old).is_empty());
+    }
+
     #[test]
     fn test_set_json_value_complex_object() {
         let mut value = json!({});
@@ -0,0 +1,345 @@
+//! Curation commands: merging duplicate documents, splitting bundled PDFs,
+//! retitling documents named after their files and redacting PDFs for
+//! publication.
+
+use std::io::{self, Write};
+
+use console::style;
+
+use foia::config::{Config, Settings};
+use foia::llm::LlmClient;
+use foia::repository::diesel_document::{MERGE_ACTION, RETITLE_ACTION};
+use foia::services::{curation, title_inference};
+use foia_analysis::redaction::{missing_tools, RedactionRegion};
+use foia_analysis::services::RedactionService;
+
+use super::helpers::truncate;
+use super::llm::{print_run_usage, usage_tracker};
+
+/// Documents read per batch when looking for junk titles.
+const RETITLE_BATCH_SIZE: usize = 500;
+
+/// Who is making the change, for the curation log.
+fn actor() -> Option<String> {
+    std::env::var("USER").ok().filter(|u| !u.is_empty())
+}
+
+fn confirm_prompt() -> anyhow::Result<bool> {
+    print!("\nProceed? [y/N] ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        Ok(true)
+    } else {
+        println!("{} Cancelled", style("!").yellow());
+        Ok(false)
+    }
+}
+
+/// Merge documents that are the same record into `target`.
+pub async fn cmd_curate_merge(
+    settings: &Settings,
+    target: &str,
+    sources: &[String],
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+
+    if !confirm {
+        let Some(doc) = doc_repo.get(target).await? else {
+            anyhow::bail!("Document not found: {}", target);
+        };
+        println!("Merge into {} ({}):", doc.id, truncate(&doc.title, 60));
+        for id in sources {
+            match doc_repo.get(id).await? {
+                Some(source) => println!(
+                    "  {} {} ({}, {} versions)",
+                    style("←").cyan(),
+                    source.id,
+                    truncate(&source.title, 60),
+                    source.versions.len()
+                ),
+                None => anyhow::bail!("Document not found: {}", id),
+            }
+        }
+        println!("Merged documents are deleted; their versions move to the target.");
+        if !confirm_prompt()? {
+            return Ok(());
+        }
+    }
+
+    let merged = curation::merge_documents(&doc_repo, target, sources, actor().as_deref()).await?;
+    println!(
+        "{} Merged {} documents into {} ({} versions)",
+        style("✓").green(),
+        sources.len(),
+        merged.id,
+        merged.versions.len()
+    );
+    Ok(())
+}
+
+/// Split a PDF into child documents starting at each page in `at`.
+pub async fn cmd_curate_split(
+    settings: &Settings,
+    doc_id: &str,
+    at: &[u32],
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+
+    if !confirm {
+        let Some(doc) = doc_repo.get(doc_id).await? else {
+            anyhow::bail!("Document not found: {}", doc_id);
+        };
+        let pages: Vec<String> = at.iter().map(|p| p.to_string()).collect();
+        println!(
+            "Split {} ({}) into {} documents starting new parts at pages {}.",
+            doc.id,
+            truncate(&doc.title, 60),
+            at.len() + 1,
+            pages.join(", ")
+        );
+        println!("The original document is kept.");
+        if !confirm_prompt()? {
+            return Ok(());
+        }
+    }
+
+    let children = curation::split_document(
+        &doc_repo,
+        &settings.documents_dir,
+        doc_id,
+        at,
+        actor().as_deref(),
+    )
+    .await?;
+    for child in &children {
+        println!("  {} {}  {}", style("→").cyan(), child.id, child.title);
+    }
+    println!(
+        "{} Split into {} documents",
+        style("✓").green(),
+        children.len()
+    );
+    Ok(())
+}
+
+/// Find better titles for documents titled after their files, show them
+/// and apply them.
+pub async fn cmd_curate_retitle(
+    settings: &Settings,
+    source_id: Option<&str>,
+    use_llm: bool,
+    dry_run: bool,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+
+    let mut tracker = None;
+    let llm = if use_llm {
+        let config = Config::load().await;
+        if !config.llm.enabled() {
+            anyhow::bail!("LLM is disabled in configuration; set llm.enabled = true");
+        }
+        let usage = usage_tracker(&repos.llm_usage, &config.llm).await?;
+        let client = LlmClient::new(config.llm.clone()).with_usage_tracker(usage.clone());
+        if !client.is_available().await {
+            anyhow::bail!("{}", config.llm.availability_hint());
+        }
+        tracker = Some(usage);
+        Some(client)
+    } else {
+        None
+    };
+
+    let found = title_inference::find_retitles(
+        &repos.documents,
+        &settings.documents_dir,
+        source_id,
+        llm.as_ref(),
+        RETITLE_BATCH_SIZE,
+    )
+    .await?;
+    if let Some(tracker) = &tracker {
+        print_run_usage(tracker);
+    }
+
+    for retitle in &found.retitled {
+        println!(
+            "  {}  {} {} {}  ({})",
+            retitle.document_id,
+            truncate(&retitle.from, 40),
+            style("→").cyan(),
+            truncate(&retitle.to, 60),
+            retitle.source.as_str()
+        );
+    }
+    println!(
+        "{} of {} documents have filename titles; {} can be retitled, {} have nothing better",
+        found.junk,
+        found.checked,
+        found.retitled.len(),
+        found.unresolved
+    );
+    if dry_run || found.retitled.is_empty() {
+        return Ok(());
+    }
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    let changed =
+        title_inference::apply_retitles(&repos.documents, &found.retitled, actor().as_deref())
+            .await?;
+    println!("{} Retitled {} documents", style("✓").green(), changed);
+    Ok(())
+}
+
+/// Black out `regions`, personal information and `terms` in a copy of a
+/// PDF that is published in place of the original, or with `remove`,
+/// drop the copy.
+pub async fn cmd_curate_redact(
+    settings: &Settings,
+    doc_id: &str,
+    regions: &[String],
+    pii: bool,
+    terms: &[String],
+    allow_missed: bool,
+    remove: bool,
+) -> anyhow::Result<()> {
+    let regions = regions
+        .iter()
+        .map(|r| RedactionRegion::parse(r))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!(e))?;
+    if !remove && regions.is_empty() && !pii && terms.is_empty() {
+        anyhow::bail!("Nothing to redact; pass --region, --pii or --term");
+    }
+    let missing = missing_tools();
+    if !remove && !missing.is_empty() {
+        anyhow::bail!(
+            "Redaction needs {}; install tesseract and poppler-utils",
+            missing.join(", ")
+        );
+    }
+
+    let config = Config::load().await;
+    let doc_repo = settings.repositories()?.documents;
+    let Some(doc) = doc_repo.get(doc_id).await? else {
+        anyhow::bail!("Document not found: {}", doc_id);
+    };
+    let service = RedactionService::new(
+        doc_repo,
+        settings.documents_dir.clone(),
+        &config.analysis.ocr.language,
+    );
+
+    if remove {
+        if service.remove(&doc).await? {
+            println!(
+                "{} Removed the redacted copy of {}; the original is public again",
+                style("✓").green(),
+                doc.id
+            );
+        } else {
+            println!("{} {} has no redacted copy", style("!").yellow(), doc.id);
+        }
+        return Ok(());
+    }
+
+    let Some(outcome) = service
+        .redact(&doc, regions, pii, terms.to_vec(), allow_missed)
+        .await?
+    else {
+        println!(
+            "{} Nothing to redact found in {}; no copy was made",
+            style("!").yellow(),
+            doc.id
+        );
+        return Ok(());
+    };
+    for page in &outcome.pages {
+        println!(
+            "  {} page {}: {} {}",
+            style("■").cyan(),
+            page.page,
+            page.boxes,
+            if page.boxes == 1 { "box" } else { "boxes" }
+        );
+    }
+    println!(
+        "{} Redacted {} {} of {} ({})",
+        style("✓").green(),
+        outcome.pages.len(),
+        if outcome.pages.len() == 1 {
+            "page"
+        } else {
+            "pages"
+        },
+        doc.id,
+        truncate(&doc.title, 60)
+    );
+    if !outcome.missed.is_empty() {
+        let pages: Vec<String> = outcome.missed.iter().map(|p| p.to_string()).collect();
+        println!(
+            "{} The text of {} {} mentions something that couldn't be found on the page image; only its text was masked, so check it and add --region if needed",
+            style("!").yellow(),
+            if pages.len() == 1 { "page" } else { "pages" },
+            pages.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Show the curation log, optionally for one document.
+pub async fn cmd_curate_log(
+    settings: &Settings,
+    doc_id: Option<&str>,
+    limit: u32,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+    let entries = doc_repo.get_curation_log(doc_id, limit).await?;
+    if entries.is_empty() {
+        println!("{} No curation history", style("!").yellow());
+        return Ok(());
+    }
+
+    for entry in entries {
+        if entry.action == RETITLE_ACTION {
+            println!(
+                "{}  {:<7}  {} {:?} {} {:?}{}",
+                entry.created_at.format("%Y-%m-%d %H:%M"),
+                entry.action,
+                entry.document_id,
+                entry.details["from"].as_str().unwrap_or_default(),
+                style("→").cyan(),
+                entry.details["to"].as_str().unwrap_or_default(),
+                entry
+                    .actor
+                    .map(|a| format!("  (by {})", a))
+                    .unwrap_or_default()
+            );
+            continue;
+        }
+        let verb = if entry.action == MERGE_ACTION {
+            "merged into"
+        } else {
+            "split from"
+        };
+        println!(
+            "{}  {:<7}  {} {} {}{}",
+            entry.created_at.format("%Y-%m-%d %H:%M"),
+            entry.action,
+            entry.related_ids.join(", "),
+            verb,
+            entry.document_id,
+            entry
+                .actor
+                .map(|a| format!("  (by {})", a))
+                .unwrap_or_default()
+        );
+    }
+    Ok(())
+}
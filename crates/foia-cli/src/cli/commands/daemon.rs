@@ -1,8 +1,8 @@
 //! Shared daemon loop infrastructure for config watching and sleep/reload.
 
 use console::style;
-use tokio::sync::mpsc;
 
+use foia::config::{Config, ConfigReloader, LiveConfig};
 use foia::repository::{DieselConfigHistoryRepository, DieselScraperConfigRepository};
 
 /// Reload mode for daemon operation.
@@ -13,7 +13,8 @@ pub enum ReloadMode {
     NextRun,
     /// Exit process when config file changes (for process manager restart)
     StopProcess,
-    /// Watch config file and reload immediately when it changes
+    /// Watch config file, apply what can change mid-run immediately and
+    /// reload the rest when it changes
     Inplace,
 }
 
@@ -21,7 +22,8 @@ pub enum ReloadMode {
 pub enum DaemonAction {
     /// Continue to the next daemon cycle (timer expired, no config change).
     Continue,
-    /// Exit the process (config changed in stop-process mode).
+    /// Exit the process (config changed in stop-process mode, or shutdown
+    /// was requested).
     Exit,
     /// Config changed in-place; the caller should reload.
     Reload,
@@ -29,7 +31,7 @@ pub enum DaemonAction {
 
 /// Manages file- and DB-based config watching for daemon loops.
 pub struct ConfigWatcher {
-    watcher: Option<mpsc::Receiver<prefer::Config>>,
+    live: Option<LiveConfig>,
     config_history: DieselConfigHistoryRepository,
     scraper_configs: DieselScraperConfigRepository,
     current_hash: String,
@@ -40,18 +42,25 @@ pub struct ConfigWatcher {
 impl ConfigWatcher {
     /// Create a new config watcher.
     ///
-    /// If `daemon` is true and the reload mode requires watching, a file watcher
-    /// is set up via `prefer::watch`. Falls back to DB polling when no config
-    /// file is available.
+    /// If `daemon` is true and the reload mode requires watching, the config
+    /// file is watched by a [`ConfigReloader`], which validates edits and
+    /// records them in the configuration history. `hot` names the settings
+    /// the caller applies mid-run through [`ConfigWatcher::live`]. Falls
+    /// back to DB polling when no config file is available.
     pub async fn new(
         daemon: bool,
         reload: ReloadMode,
         config_history: DieselConfigHistoryRepository,
         scraper_configs: DieselScraperConfigRepository,
-        initial_hash: String,
+        config: &Config,
+        hot: &'static [&'static str],
     ) -> Self {
-        let watcher = if daemon && matches!(reload, ReloadMode::StopProcess | ReloadMode::Inplace) {
-            prefer::watch("foia").await.ok()
+        let initial_hash = config.hash();
+        let live = if daemon
+            && config.source_path.is_some()
+            && matches!(reload, ReloadMode::StopProcess | ReloadMode::Inplace)
+        {
+            Some(ConfigReloader::spawn(config.clone(), config_history.clone(), hot).await)
         } else {
             None
         };
@@ -66,7 +75,7 @@ impl ConfigWatcher {
         };
 
         Self {
-            watcher,
+            live,
             config_history,
             scraper_configs,
             current_hash,
@@ -75,6 +84,13 @@ impl ConfigWatcher {
         }
     }
 
+    /// Config updates to apply mid-run, in [`ReloadMode::Inplace`] only.
+    pub fn live(&self) -> Option<LiveConfig> {
+        self.live
+            .clone()
+            .filter(|_| self.reload == ReloadMode::Inplace)
+    }
+
     /// Update the stored config hash (used when the caller reloads config at
     /// the top of its loop).
     pub fn update_hash(&mut self, hash: String) {
@@ -84,19 +100,30 @@ impl ConfigWatcher {
     /// Sleep for `interval` seconds, watching for config changes.
     ///
     /// `inplace_label` is the verb shown in log output when an in-place reload
-    /// triggers (e.g. "reloading" or "continuing").
+    /// triggers (e.g. "reloading" or "continuing"). A shutdown request ends
+    /// the sleep with [`DaemonAction::Exit`].
     pub async fn sleep_or_reload(&mut self, interval: u64, inplace_label: &str) -> DaemonAction {
+        if foia::shutdown::is_requested() {
+            return DaemonAction::Exit;
+        }
+        tokio::select! {
+            action = self.wait_for_change(interval, inplace_label) => action,
+            _ = foia::shutdown::requested() => DaemonAction::Exit,
+        }
+    }
+
+    async fn wait_for_change(&mut self, interval: u64, inplace_label: &str) -> DaemonAction {
         println!(
             "{} Sleeping for {}s before next check...",
             style("→").dim(),
             interval
         );
 
-        if let Some(ref mut watcher) = self.watcher {
+        if let Some(ref mut live) = self.live {
             tokio::select! {
                 _ = tokio::time::sleep(std::time::Duration::from_secs(interval)) => {}
-                result = watcher.recv() => {
-                    if result.is_some() {
+                result = live.changed() => {
+                    if result.is_ok() {
                         match self.reload {
                             ReloadMode::StopProcess => {
                                 println!(
@@ -167,3 +194,12 @@ impl ConfigWatcher {
         DaemonAction::Continue
     }
 }
+
+/// Sleep for `interval` seconds while idle in a daemon loop. Returns false,
+/// early, if shutdown is requested.
+pub async fn idle(interval: u64) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(std::time::Duration::from_secs(interval)) => true,
+        _ = foia::shutdown::requested() => false,
+    }
+}
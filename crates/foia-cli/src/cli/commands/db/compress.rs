@@ -0,0 +1,52 @@
+//! Page text compression backfill command.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::repository::diesel_document::PageTextCompression;
+use foia::utils::format_size;
+
+/// Compress the page text of pages stored before compression.
+pub async fn cmd_db_compress_text(
+    settings: &Settings,
+    dry_run: bool,
+    batch_size: usize,
+) -> anyhow::Result<()> {
+    let databases = super::document_databases(settings)?;
+    let mut total = PageTextCompression::default();
+    for (name, docs) in &databases {
+        if databases.len() > 1 {
+            println!("{} Compressing page text in {}", style("→").cyan(), name);
+        }
+        let report = docs.compress_page_text(batch_size, dry_run).await?;
+        total.pages += report.pages;
+        total.compressed += report.compressed;
+        total.bytes_before += report.bytes_before;
+        total.bytes_after += report.bytes_after;
+    }
+
+    if total.compressed == 0 {
+        println!("{} All page text is already compressed", style("✓").green());
+        return Ok(());
+    }
+    println!(
+        "{} {} {} of {} pages: {} → {}",
+        style("✓").green(),
+        if dry_run {
+            "Would compress"
+        } else {
+            "Compressed"
+        },
+        total.compressed,
+        total.pages,
+        format_size(total.bytes_before),
+        format_size(total.bytes_after)
+    );
+    if !dry_run && !settings.is_postgres() {
+        println!(
+            "  {} Run VACUUM on the database to return the space to the filesystem",
+            style("→").dim()
+        );
+    }
+    Ok(())
+}
@@ -41,8 +41,9 @@ impl KeepStrategy {
 
 /// Deduplicate documents by content hash.
 ///
-/// Finds documents with identical content (same content_hash) and merges them,
-/// keeping one document and updating all references to point to the keeper.
+/// Finds documents with identical content (same BLAKE3 hash, or SHA-256 for
+/// versions not yet backfilled) and merges them, keeping one document and
+/// updating all references to point to the keeper.
 pub async fn cmd_db_dedup(
     settings: &Settings,
     dry_run: bool,
@@ -82,22 +83,24 @@ pub async fn cmd_db_dedup(
     let group_query = if same_source {
         // Group by content_hash AND source_id
         r#"
-        SELECT dv.content_hash, COUNT(DISTINCT d.id) as doc_count
+        SELECT COALESCE(dv.content_hash_blake3, dv.content_hash) AS content_hash,
+               COUNT(DISTINCT d.id) as doc_count
         FROM document_versions dv
         JOIN documents d ON d.id = dv.document_id
         WHERE dv.content_hash IS NOT NULL AND dv.content_hash != ''
-        GROUP BY dv.content_hash, d.source_id
+        GROUP BY COALESCE(dv.content_hash_blake3, dv.content_hash), d.source_id
         HAVING COUNT(DISTINCT d.id) > 1
         ORDER BY doc_count DESC
         "#
     } else {
         // Group by content_hash only (cross-source)
         r#"
-        SELECT dv.content_hash, COUNT(DISTINCT d.id) as doc_count
+        SELECT COALESCE(dv.content_hash_blake3, dv.content_hash) AS content_hash,
+               COUNT(DISTINCT d.id) as doc_count
         FROM document_versions dv
         JOIN documents d ON d.id = dv.document_id
         WHERE dv.content_hash IS NOT NULL AND dv.content_hash != ''
-        GROUP BY dv.content_hash
+        GROUP BY COALESCE(dv.content_hash_blake3, dv.content_hash)
         HAVING COUNT(DISTINCT d.id) > 1
         ORDER BY doc_count DESC
         "#
@@ -170,7 +173,7 @@ pub async fn cmd_db_dedup(
                     SELECT d.id, d.source_id, d.created_at, d.extracted_text, d.synopsis, d.tags
                     FROM documents d
                     JOIN document_versions dv ON dv.document_id = d.id
-                    WHERE dv.content_hash = $1
+                    WHERE COALESCE(dv.content_hash_blake3, dv.content_hash) = $1
                     ORDER BY d.created_at ASC
                     "#,
                 )
@@ -231,7 +234,7 @@ pub async fn cmd_db_dedup(
 
             for (keeper_id, dup_ids) in &updates_by_keeper {
                 // Update analysis_results
-                let updated: usize = foia::with_conn!(pool, conn, {
+                let updated: usize = foia::with_write_conn!(pool, conn, {
                     diesel::update(
                         document_analysis_results::table
                             .filter(document_analysis_results::document_id.eq_any(dup_ids)),
@@ -243,7 +246,7 @@ pub async fn cmd_db_dedup(
                 total_refs_updated += updated as u64;
 
                 // Update annotations
-                let updated: usize = foia::with_conn!(pool, conn, {
+                let updated: usize = foia::with_write_conn!(pool, conn, {
                     diesel::update(
                         document_annotations::table
                             .filter(document_annotations::document_id.eq_any(dup_ids)),
@@ -258,7 +261,7 @@ pub async fn cmd_db_dedup(
             // Batch delete in order respecting foreign keys
 
             // 1. document_pages
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(
                     document_pages::table
                         .filter(document_pages::document_id.eq_any(&batch_deletes)),
@@ -268,7 +271,7 @@ pub async fn cmd_db_dedup(
             })?;
 
             // 2. virtual_files
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(
                     virtual_files::table.filter(virtual_files::document_id.eq_any(&batch_deletes)),
                 )
@@ -277,7 +280,7 @@ pub async fn cmd_db_dedup(
             })?;
 
             // 3. document_versions
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(
                     document_versions::table
                         .filter(document_versions::document_id.eq_any(&batch_deletes)),
@@ -287,7 +290,7 @@ pub async fn cmd_db_dedup(
             })?;
 
             // 4. document_analysis_results (any remaining)
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(
                     document_analysis_results::table
                         .filter(document_analysis_results::document_id.eq_any(&batch_deletes)),
@@ -297,7 +300,7 @@ pub async fn cmd_db_dedup(
             })?;
 
             // 5. document_annotations (any remaining)
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(
                     document_annotations::table
                         .filter(document_annotations::document_id.eq_any(&batch_deletes)),
@@ -307,7 +310,7 @@ pub async fn cmd_db_dedup(
             })?;
 
             // 6. documents
-            foia::with_conn!(pool, conn, {
+            foia::with_write_conn!(pool, conn, {
                 diesel::delete(documents::table.filter(documents::id.eq_any(&batch_deletes)))
                     .execute(&mut conn)
                     .await
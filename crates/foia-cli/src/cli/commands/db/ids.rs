@@ -0,0 +1,46 @@
+//! Stable document ID migration command.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::services::stable_ids::migrate_to_stable_ids;
+
+/// Move documents to stable IDs derived from their source and canonical
+/// URL, merging documents that turn out to share one.
+pub async fn cmd_db_migrate_ids(settings: &Settings, dry_run: bool) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    println!(
+        "{} Moving documents to stable IDs{}",
+        style("→").cyan(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+
+    let report = migrate_to_stable_ids(&repos.documents, dry_run).await?;
+    if report.is_noop() {
+        println!(
+            "{} All {} documents already have stable IDs",
+            style("✓").green(),
+            report.documents
+        );
+        return Ok(());
+    }
+
+    let (renamed, merged) = if dry_run {
+        ("Would move", "would merge")
+    } else {
+        ("Moved", "merged")
+    };
+    println!(
+        "{} {} {} of {} documents to stable IDs and {} {} duplicates",
+        style("✓").green(),
+        renamed,
+        report.renamed,
+        report.documents,
+        merged,
+        report.merged
+    );
+    if !dry_run {
+        println!("  Old IDs redirect to the new ones in the web UI and API");
+    }
+    Ok(())
+}
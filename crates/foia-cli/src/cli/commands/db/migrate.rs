@@ -3,9 +3,10 @@
 use console::style;
 
 use foia::config::{Settings, SourcesConfig};
-use foia::repository::migrations;
 use foia::repository::util::redact_url_password;
 use foia::repository::Repositories;
+use foia::repository::{migrations, shards};
+use foia::services::hash_backfill::backfill_hashes;
 
 /// Expected schema version (should match storage_meta.format_version).
 const EXPECTED_SCHEMA_VERSION: &str = "15";
@@ -52,15 +53,34 @@ pub async fn cmd_migrate(settings: &Settings, check: bool, force: bool) -> anyho
         } else {
             println!("\n{} Schema is up to date.", style("✓").green());
         }
+        if schema_exists {
+            backfill_blake3_hashes(settings, &repos, true).await;
+        }
         return Ok(());
     }
 
+    // Shards are created and migrated even when the main schema is current,
+    // so a shard added to the config gets its database
+    let shard_databases = settings.shard_databases();
+    if !shard_databases.is_empty() {
+        println!(
+            "\n{} Migrating {} shard databases...",
+            style("→").cyan(),
+            shard_databases.len()
+        );
+        if let Err(e) = shards::prepare_shards(&shard_databases).await {
+            eprintln!("{} Shard migration failed: {}", style("✗").red(), e);
+            return Err(anyhow::anyhow!("Shard migration failed: {}", e));
+        }
+    }
+
     // Run migrations
     if !needs_migration && !force {
         println!(
             "\n{} Schema is already up to date. Use --force to re-run.",
             style("✓").green()
         );
+        backfill_blake3_hashes(settings, &repos, false).await;
         return Ok(());
     }
 
@@ -87,9 +107,66 @@ pub async fn cmd_migrate(settings: &Settings, check: bool, force: bool) -> anyho
     // Post-migration: seed scraper_configs from configuration_history
     migrate_config_history_to_scraper_configs(&repos).await;
 
+    // Post-migration: BLAKE3 hashes for versions stored with only SHA-256
+    backfill_blake3_hashes(settings, &repos, false).await;
+
     Ok(())
 }
 
+/// Record BLAKE3 hashes for versions stored when content was hashed with
+/// SHA-256, so deduplication stops falling back to SHA-256 for them.
+///
+/// Runs even when the schema is current, since the column may have been
+/// added by an earlier migrate that predates this step. Versions whose file
+/// is gone keep only their SHA-256 and are retried on the next run.
+async fn backfill_blake3_hashes(settings: &Settings, repos: &Repositories, check: bool) {
+    let pending = match repos.documents.count_versions_missing_blake3().await {
+        Ok(0) => return,
+        Ok(n) => n,
+        Err(e) => {
+            tracing::warn!("Failed to count versions without BLAKE3 hashes: {}", e);
+            return;
+        }
+    };
+    if !check {
+        println!(
+            "\n{} Hashing {} versions stored before BLAKE3...",
+            style("→").cyan(),
+            pending
+        );
+    }
+
+    let report = match backfill_hashes(&repos.documents, &settings.documents_dir, 1000, check).await
+    {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::warn!("Failed to backfill BLAKE3 hashes: {}", e);
+            return;
+        }
+    };
+    if check {
+        println!(
+            "{} {} versions need BLAKE3 hashes. Run 'foia db migrate' to hash them.",
+            style("!").yellow(),
+            report.hashed
+        );
+    } else {
+        println!(
+            "{} Hashed {} of {} versions",
+            style("✓").green(),
+            report.hashed,
+            report.versions
+        );
+    }
+    if report.missing > 0 {
+        println!(
+            "{} {} versions have no readable file and keep only their SHA-256",
+            style("!").yellow(),
+            report.missing
+        );
+    }
+}
+
 /// Migrate data from configuration_history into scraper_configs.
 ///
 /// If scraper_configs is empty and configuration_history has data,
@@ -1,11 +1,47 @@
 //! Database management commands.
 
+use foia::config::Settings;
+use foia::repository::diesel_context::DieselDbContext;
+use foia::repository::DieselDocumentRepository;
+
+mod compress;
 mod copy;
 mod dedup;
+mod ids;
 mod migrate;
+mod prune;
 mod remap;
+mod search;
+mod split;
 
+pub use compress::cmd_db_compress_text;
 pub use copy::cmd_db_copy;
 pub use dedup::cmd_db_dedup;
+pub use ids::cmd_db_migrate_ids;
 pub use migrate::cmd_migrate;
+pub use prune::cmd_db_prune_artifacts;
 pub use remap::cmd_db_remap_categories;
+pub use search::cmd_db_check_search;
+pub use split::cmd_db_split;
+
+/// Document repositories of every database holding pages, by name: the
+/// main database, and each shard that exists of a sharded archive.
+fn document_databases(
+    settings: &Settings,
+) -> anyhow::Result<Vec<(String, DieselDocumentRepository)>> {
+    if !settings.is_sharded() {
+        return Ok(vec![(
+            "main".to_string(),
+            settings.repositories()?.documents,
+        )]);
+    }
+    let main = DieselDbContext::from_url(&settings.database_url(), settings.no_tls)?;
+    let mut databases = vec![("main".to_string(), main.documents())];
+    for shard in settings.shard_databases() {
+        if shard.path.exists() {
+            let ctx = DieselDbContext::from_sqlite_path(&shard.path)?;
+            databases.push((shard.name, ctx.documents()));
+        }
+    }
+    Ok(databases)
+}
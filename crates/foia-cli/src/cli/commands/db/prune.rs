@@ -0,0 +1,33 @@
+//! Derived artifact pruning command.
+
+use console::style;
+
+use foia::artifacts::{prune_artifacts, ArtifactStore};
+use foia::config::Settings;
+
+/// Delete long-stale derived artifacts and unreferenced artifact files.
+pub async fn cmd_db_prune_artifacts(
+    settings: &Settings,
+    older_than_days: u32,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let store = ArtifactStore::new(&settings.documents_dir);
+    let report = prune_artifacts(
+        &repos.documents,
+        &store,
+        chrono::Duration::days(older_than_days as i64),
+        dry_run,
+    )
+    .await?;
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    println!(
+        "{} {} {} stale artifacts and {} unreferenced files",
+        style("✓").green(),
+        verb,
+        report.records,
+        report.files
+    );
+    Ok(())
+}
@@ -123,7 +123,7 @@ pub async fn cmd_db_remap_categories(
                     continue;
                 }
 
-                foia::with_conn!(pool, conn, {
+                foia::with_write_conn!(pool, conn, {
                     diesel::update(documents::table)
                         .filter(documents::id.eq_any(&doc_ids))
                         .set(documents::category_id.eq(Some(&category)))
@@ -0,0 +1,62 @@
+//! Search index consistency command.
+
+use console::style;
+
+use foia::config::Settings;
+
+/// Check the page search index against the pages it covers, and with
+/// `repair`, fix what's off.
+pub async fn cmd_db_check_search(settings: &Settings, repair: bool) -> anyhow::Result<()> {
+    let mut consistent = true;
+    for (name, docs) in super::document_databases(settings)? {
+        let check = docs.check_search_index().await?;
+        if check.is_consistent() {
+            println!(
+                "{} Search index of {} covers all {} pages",
+                style("✓").green(),
+                name,
+                check.pages
+            );
+            continue;
+        }
+
+        println!(
+            "{} Search index of {} is out of date:",
+            style("!").yellow(),
+            name
+        );
+        if check.damaged {
+            println!("  {} index is damaged or missing", style("→").dim());
+        }
+        if check.missing > 0 {
+            println!(
+                "  {} {} of {} pages not indexed",
+                style("→").dim(),
+                check.missing,
+                check.pages
+            );
+        }
+        if check.orphaned > 0 {
+            println!(
+                "  {} {} entries for deleted pages",
+                style("→").dim(),
+                check.orphaned
+            );
+        }
+
+        if repair {
+            docs.repair_search_index(&check).await?;
+            println!("  {} Repaired", style("✓").green());
+        } else {
+            consistent = false;
+        }
+    }
+
+    if !consistent {
+        println!(
+            "{} Run 'foia db check-search --repair' to fix",
+            style("→").cyan()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Shard split command.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::repository::shards;
+
+/// Move the documents and crawl state of sharded sources out of the main
+/// database into their shards.
+pub async fn cmd_db_split(settings: &Settings, shard_name: Option<&str>) -> anyhow::Result<()> {
+    if !settings.is_sharded() {
+        println!(
+            "{} No shards configured; add a 'shards' list to the config first",
+            style("!").yellow()
+        );
+        return Ok(());
+    }
+
+    let shard_databases: Vec<_> = settings
+        .shard_databases()
+        .into_iter()
+        .filter(|shard| shard_name.is_none_or(|name| name == shard.name))
+        .collect();
+    if shard_databases.is_empty() {
+        anyhow::bail!("Unknown shard '{}'", shard_name.unwrap_or_default());
+    }
+    shards::prepare_shards(&shard_databases).await?;
+
+    let main = shards::sqlite_path(&settings.database_url());
+    for shard in shard_databases {
+        println!(
+            "{} Moving {} into shard {}",
+            style("→").cyan(),
+            shard.sources.join(", "),
+            style(&shard.name).bold()
+        );
+        let main = main.clone();
+        let moved =
+            tokio::task::spawn_blocking(move || shards::split_shard(&main, &shard)).await??;
+        if moved.is_empty() {
+            println!("  {} Nothing left to move", style("→").dim());
+        }
+        for (table, rows) in moved {
+            println!("  {} {} rows from {}", style("✓").green(), rows, table);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,507 @@
+//! Check for the external tools foia runs and install missing ones.
+//!
+//! Tools are found by running them rather than with `which`, which Windows
+//! doesn't have. Installation goes through the platform's package manager;
+//! Tesseract language data that no package provides is downloaded into
+//! Tesseract's tessdata directory.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+use console::style;
+
+use foia::config::Config;
+
+/// Where Tesseract language data is downloaded from when no package has it.
+const TESSDATA_URL: &str = "https://github.com/tesseract-ocr/tessdata_fast/raw/main";
+
+/// Where the UB-Mannheim installer puts Tesseract on Windows.
+const WINDOWS_TESSERACT: &str = r"C:\Program Files\Tesseract-OCR\tesseract.exe";
+
+/// Package providing one or more tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Package {
+    Poppler,
+    Tesseract,
+}
+
+/// An external program and the argument that makes it print its version.
+struct Tool {
+    program: &'static str,
+    version_arg: &'static str,
+    package: Package,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        program: "pdfinfo",
+        version_arg: "-v",
+        package: Package::Poppler,
+    },
+    Tool {
+        program: "pdftotext",
+        version_arg: "-v",
+        package: Package::Poppler,
+    },
+    Tool {
+        program: "pdftoppm",
+        version_arg: "-v",
+        package: Package::Poppler,
+    },
+    Tool {
+        program: "tesseract",
+        version_arg: "--version",
+        package: Package::Tesseract,
+    },
+];
+
+/// Package managers that doctor knows how to drive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackageManager {
+    Apt,
+    Dnf,
+    Pacman,
+    Brew,
+    Winget,
+    Choco,
+}
+
+impl PackageManager {
+    /// The first package manager for this platform that runs.
+    fn detect() -> Option<Self> {
+        let candidates: &[Self] = if cfg!(windows) {
+            &[Self::Winget, Self::Choco]
+        } else if cfg!(target_os = "macos") {
+            &[Self::Brew]
+        } else {
+            &[Self::Apt, Self::Dnf, Self::Pacman, Self::Brew]
+        };
+        candidates
+            .iter()
+            .copied()
+            .find(|pm| version(pm.program(), "--version").is_some())
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Apt => "apt-get",
+            Self::Dnf => "dnf",
+            Self::Pacman => "pacman",
+            Self::Brew => "brew",
+            Self::Winget => "winget",
+            Self::Choco => "choco",
+        }
+    }
+
+    fn package_name(self, package: Package) -> &'static str {
+        match (self, package) {
+            (Self::Apt | Self::Dnf, Package::Poppler) => "poppler-utils",
+            (Self::Pacman | Self::Brew | Self::Choco, Package::Poppler) => "poppler",
+            (Self::Winget, Package::Poppler) => "oschwartz10612.Poppler",
+            (Self::Apt, Package::Tesseract) => "tesseract-ocr",
+            (Self::Winget, Package::Tesseract) => "UB-Mannheim.TesseractOCR",
+            (_, Package::Tesseract) => "tesseract",
+        }
+    }
+
+    /// Package with Tesseract data for `language`, if this package manager
+    /// has one.
+    fn language_package(self, language: &str) -> Option<String> {
+        match self {
+            Self::Apt => Some(format!(
+                "tesseract-ocr-{}",
+                language.to_lowercase().replace('_', "-")
+            )),
+            Self::Dnf => Some(format!("tesseract-langpack-{}", language)),
+            Self::Pacman => Some(format!("tesseract-data-{}", language)),
+            // One package with every language
+            Self::Brew => Some("tesseract-lang".to_string()),
+            Self::Winget | Self::Choco => None,
+        }
+    }
+
+    /// Commands that install `packages`.
+    fn install_commands(self, packages: &[String]) -> Vec<Vec<String>> {
+        let prefix: &[&str] = match self {
+            Self::Apt => &["sudo", "apt-get", "install", "-y"],
+            Self::Dnf => &["sudo", "dnf", "install", "-y"],
+            Self::Pacman => &["sudo", "pacman", "-S", "--needed", "--noconfirm"],
+            Self::Brew => &["brew", "install"],
+            Self::Choco => &["choco", "install", "-y"],
+            // winget installs one package per call
+            Self::Winget => {
+                return packages
+                    .iter()
+                    .map(|id| {
+                        argv(&[
+                            "winget",
+                            "install",
+                            "--exact",
+                            "--id",
+                            id,
+                            "--accept-package-agreements",
+                            "--accept-source-agreements",
+                        ])
+                    })
+                    .collect();
+            }
+        };
+        let mut command = argv(prefix);
+        command.extend(packages.iter().cloned());
+        vec![command]
+    }
+}
+
+fn argv(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// First line `program` prints for `arg`, or `None` if it can't be run.
+/// Poppler prints its version on stderr, and older releases exit non-zero.
+fn version(program: &str, arg: &str) -> Option<String> {
+    let output = Command::new(program).arg(arg).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// State of the tools after probing.
+struct Report {
+    /// Version line per tool, in [`TOOLS`] order.
+    versions: Vec<Option<String>>,
+    /// Tesseract installed outside PATH (Windows installer default).
+    tesseract_off_path: Option<PathBuf>,
+    /// tessdata directory and installed languages, if Tesseract runs.
+    languages: Option<(Option<PathBuf>, Vec<String>)>,
+}
+
+impl Report {
+    fn probe() -> Self {
+        let versions: Vec<_> = TOOLS
+            .iter()
+            .map(|tool| version(tool.program, tool.version_arg))
+            .collect();
+        let tesseract = TOOLS
+            .iter()
+            .zip(&versions)
+            .find(|(tool, _)| tool.package == Package::Tesseract)
+            .and_then(|(tool, found)| found.as_ref().map(|_| tool.program));
+        let tesseract_off_path = (cfg!(windows) && tesseract.is_none())
+            .then(|| PathBuf::from(WINDOWS_TESSERACT))
+            .filter(|path| path.exists());
+        let languages = tesseract.and_then(|program| {
+            let output = Command::new(program).arg("--list-langs").output().ok()?;
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Some(parse_list_langs(&text))
+        });
+        Self {
+            versions,
+            tesseract_off_path,
+            languages,
+        }
+    }
+
+    fn missing_packages(&self) -> Vec<Package> {
+        let mut packages = Vec::new();
+        for (tool, found) in TOOLS.iter().zip(&self.versions) {
+            if found.is_none() && !packages.contains(&tool.package) {
+                packages.push(tool.package);
+            }
+        }
+        packages
+    }
+
+    fn missing_languages<'a>(&self, wanted: &[&'a str]) -> Vec<&'a str> {
+        match &self.languages {
+            Some((_, installed)) => wanted
+                .iter()
+                .copied()
+                .filter(|lang| !installed.iter().any(|have| have == lang))
+                .collect(),
+            // Can't tell without Tesseract; installing it comes first
+            None => Vec::new(),
+        }
+    }
+
+    fn tessdata_dir(&self) -> Option<&PathBuf> {
+        self.languages.as_ref().and_then(|(dir, _)| dir.as_ref())
+    }
+
+    fn print(&self, wanted: &[&str]) {
+        println!("\n{}", style("External Tools").bold());
+        println!("{}", "-".repeat(50));
+        for (tool, found) in TOOLS.iter().zip(&self.versions) {
+            match found {
+                Some(line) => println!(
+                    "  {:<12} {} {}",
+                    tool.program,
+                    style("✓").green(),
+                    style(line).dim()
+                ),
+                None => println!("  {:<12} {}", tool.program, style("✗ not found").red()),
+            }
+        }
+        if let Some(path) = &self.tesseract_off_path {
+            println!(
+                "  {:<12} {} installed at {} but not on PATH",
+                "",
+                style("!").yellow(),
+                path.display()
+            );
+        }
+
+        println!("\n{}", style("Tesseract Languages").bold());
+        println!("{}", "-".repeat(50));
+        if self.languages.is_none() {
+            println!("  {}", style("Tesseract is not installed").dim());
+            return;
+        }
+        let missing = self.missing_languages(wanted);
+        for lang in wanted {
+            let status = if missing.contains(lang) {
+                style("✗ not installed").red()
+            } else {
+                style("✓ installed").green()
+            };
+            println!("  {:<12} {}", lang, status);
+        }
+    }
+}
+
+/// Parse `tesseract --list-langs` into the tessdata directory and the
+/// installed languages.
+fn parse_list_langs(output: &str) -> (Option<PathBuf>, Vec<String>) {
+    let mut dir = None;
+    let mut languages = Vec::new();
+    for line in output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if line.starts_with("List of available languages") {
+            dir = line.split('"').nth(1).map(PathBuf::from);
+        } else if !line.contains(' ') {
+            languages.push(line.to_string());
+        }
+    }
+    (dir, languages)
+}
+
+/// Commands that install the missing packages and languages.
+fn install_plan(
+    report: &Report,
+    pm: Option<PackageManager>,
+    wanted: &[&str],
+) -> (Vec<Vec<String>>, Vec<String>) {
+    let mut commands = Vec::new();
+    let mut notes = Vec::new();
+
+    let mut packages: Vec<String> = Vec::new();
+    for package in report.missing_packages() {
+        if package == Package::Tesseract && report.tesseract_off_path.is_some() {
+            notes.push(format!(
+                "Add {} to PATH instead of reinstalling Tesseract",
+                PathBuf::from(WINDOWS_TESSERACT)
+                    .parent()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_default()
+            ));
+            continue;
+        }
+        match pm {
+            Some(pm) => packages.push(pm.package_name(package).to_string()),
+            None => notes.push(format!(
+                "No supported package manager found; install {} manually",
+                match package {
+                    Package::Poppler => "Poppler (pdfinfo, pdftotext, pdftoppm)",
+                    Package::Tesseract => "Tesseract",
+                }
+            )),
+        }
+    }
+    if report.languages.is_none() && !wanted.is_empty() && pm.is_some() {
+        notes.push("Run doctor again after Tesseract is installed to check languages".to_string());
+    }
+
+    let mut downloads = Vec::new();
+    for lang in report.missing_languages(wanted) {
+        match pm.and_then(|pm| pm.language_package(lang)) {
+            Some(package) if !packages.contains(&package) => packages.push(package),
+            Some(_) => {}
+            None => downloads.push(lang),
+        }
+    }
+    if let Some(pm) = pm {
+        if !packages.is_empty() {
+            commands.extend(pm.install_commands(&packages));
+        }
+    }
+
+    if !downloads.is_empty() {
+        match report.tessdata_dir() {
+            Some(dir) => {
+                for lang in downloads {
+                    let dest = dir.join(format!("{}.traineddata", lang));
+                    commands.push(vec![
+                        "curl".to_string(),
+                        "-fL".to_string(),
+                        "-o".to_string(),
+                        dest.display().to_string(),
+                        format!("{}/{}.traineddata", TESSDATA_URL, lang),
+                    ]);
+                }
+            }
+            None => notes.push(format!(
+                "Download {} from {} into Tesseract's tessdata directory",
+                downloads
+                    .iter()
+                    .map(|lang| format!("{}.traineddata", lang))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                TESSDATA_URL
+            )),
+        }
+    }
+
+    (commands, notes)
+}
+
+/// Render a command for copying into a shell.
+fn display_command(command: &[String]) -> String {
+    command
+        .iter()
+        .map(|arg| {
+            if arg.contains(' ') {
+                format!("\"{}\"", arg)
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn confirm_prompt() -> anyhow::Result<bool> {
+    print!("\nRun these commands? [y/N] ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        Ok(true)
+    } else {
+        println!("{} Cancelled", style("!").yellow());
+        Ok(false)
+    }
+}
+
+/// Check external tools and, with `install`, install the missing ones.
+pub async fn cmd_doctor(config: &Config, install: bool, confirm: bool) -> anyhow::Result<()> {
+    let wanted: Vec<&str> = config
+        .analysis
+        .ocr
+        .language
+        .split('+')
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty())
+        .collect();
+
+    let report = Report::probe();
+    report.print(&wanted);
+
+    let pm = PackageManager::detect();
+    let (commands, notes) = install_plan(&report, pm, &wanted);
+    if commands.is_empty() && notes.is_empty() {
+        println!("\n{} All tools are installed", style("✓").green());
+        return Ok(());
+    }
+
+    println!();
+    if let Some(pm) = pm {
+        println!("Package manager: {}", pm.program());
+    }
+    if !commands.is_empty() {
+        println!("{}", style("To install:").cyan());
+        for command in &commands {
+            println!("  {}", display_command(command));
+        }
+    }
+    for note in &notes {
+        println!("  {} {}", style("!").yellow(), note);
+    }
+
+    if !install {
+        if !commands.is_empty() {
+            println!(
+                "\nRun {} to install them",
+                style("foia doctor --install").bold()
+            );
+        }
+        return Ok(());
+    }
+    if commands.is_empty() {
+        anyhow::bail!("Nothing can be installed automatically on this system");
+    }
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    for command in &commands {
+        println!("\n{} {}", style("→").cyan(), display_command(command));
+        let status = Command::new(&command[0]).args(&command[1..]).status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => println!("{} Command failed ({})", style("✗").red(), status),
+            Err(e) => println!("{} Could not run {}: {}", style("✗").red(), command[0], e),
+        }
+    }
+
+    // Verify what the installers actually left behind
+    let after = Report::probe();
+    after.print(&wanted);
+    let missing_tools = after.missing_packages();
+    let missing_languages = after.missing_languages(&wanted);
+    if missing_tools.is_empty() && missing_languages.is_empty() && after.languages.is_some() {
+        println!("\n{} All tools are installed", style("✓").green());
+        Ok(())
+    } else {
+        if cfg!(windows) {
+            println!(
+                "\n{} Open a new terminal if the installer changed PATH",
+                style("!").yellow()
+            );
+        }
+        anyhow::bail!("Some tools are still missing")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_langs() {
+        let output = "List of available languages in \"/usr/share/tesseract-ocr/5/tessdata/\" (3):\neng\nosd\nchi_sim\n";
+        let (dir, languages) = parse_list_langs(output);
+        assert_eq!(
+            dir,
+            Some(PathBuf::from("/usr/share/tesseract-ocr/5/tessdata/"))
+        );
+        assert_eq!(languages, vec!["eng", "osd", "chi_sim"]);
+    }
+
+    #[test]
+    fn test_language_packages() {
+        assert_eq!(
+            PackageManager::Apt.language_package("chi_sim").as_deref(),
+            Some("tesseract-ocr-chi-sim")
+        );
+        assert_eq!(
+            PackageManager::Pacman.language_package("fra").as_deref(),
+            Some("tesseract-data-fra")
+        );
+        assert_eq!(PackageManager::Winget.language_package("fra"), None);
+    }
+}
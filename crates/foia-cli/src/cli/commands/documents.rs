@@ -8,6 +8,8 @@ use indicatif::{ProgressBar, ProgressStyle};
 use foia::config::Settings;
 use foia::models::Document;
 use foia::repository::DieselDocumentRepository;
+use foia::services::metadata_export::{self, ExportFormat};
+use foia::services::provenance;
 
 use super::helpers::{format_bytes, mime_short, truncate};
 
@@ -352,7 +354,10 @@ pub async fn cmd_ls(
     // Get documents based on filters
     let documents: Vec<Document> = if let Some(tag_name) = tag {
         // Filter by tag
-        doc_repo.get_by_tag(tag_name, source_id).await?
+        doc_repo
+            .get_by_tag(tag_name, source_id, None, limit)
+            .await?
+            .items
     } else if let Some(type_name) = type_filter {
         // Filter by type
         doc_repo
@@ -360,7 +365,7 @@ pub async fn cmd_ls(
             .await?
     } else if let Some(sid) = source_id {
         // Filter by source
-        doc_repo.get_by_source(sid).await?
+        doc_repo.get_by_source(sid, None, limit).await?.items
     } else {
         // Get all
         doc_repo.get_all().await?
@@ -616,6 +621,73 @@ pub async fn cmd_read(settings: &Settings, doc_id: &str, text_only: bool) -> any
     Ok(())
 }
 
+/// Export a document's provenance record as JSON.
+pub async fn cmd_provenance(
+    settings: &Settings,
+    doc_id: &str,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let Some(record) =
+        provenance::provenance_record(&repos.documents, &repos.crawl, doc_id).await?
+    else {
+        eprintln!("Document not found: {}", doc_id);
+        std::process::exit(1);
+    };
+
+    let json = serde_json::to_string_pretty(&record)?;
+    match output {
+        Some(path) => {
+            std::fs::write(path, json)?;
+            eprintln!(
+                "{} Wrote provenance for {} to {}",
+                style("✓").green(),
+                doc_id,
+                path.display()
+            );
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
+
+/// Export document metadata as analysis tables.
+pub async fn cmd_export_metadata(
+    settings: &Settings,
+    format: &str,
+    output: &Path,
+    source_id: Option<&str>,
+) -> anyhow::Result<()> {
+    let Some(format) = ExportFormat::from_str(format) else {
+        anyhow::bail!("Unknown format '{}' (expected csv or parquet)", format);
+    };
+    let repos = settings.repositories()?;
+    if let Some(source_id) = source_id {
+        if repos.sources.get(source_id).await?.is_none() {
+            anyhow::bail!("Source '{}' not found", source_id);
+        }
+    }
+
+    let tables =
+        metadata_export::export_metadata(&repos.documents, source_id, output, format).await?;
+    for table in &tables {
+        println!(
+            "  {:<10} {:>10} rows  {}",
+            table.name,
+            table.rows,
+            table.path.display()
+        );
+    }
+    println!(
+        "{} Exported metadata to {}",
+        style("✓").green(),
+        output.display()
+    );
+
+    Ok(())
+}
+
 /// Search documents by content or metadata.
 pub async fn cmd_search(
     settings: &Settings,
@@ -630,7 +702,16 @@ pub async fn cmd_search(
 
     // Get all documents and filter
     let documents: Vec<Document> = if let Some(sid) = source_id {
-        doc_repo.get_by_source(sid).await?
+        let mut documents = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = doc_repo.get_by_source(sid, cursor.as_ref(), 500).await?;
+            documents.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break documents,
+            }
+        }
     } else {
         doc_repo.get_all().await?
     };
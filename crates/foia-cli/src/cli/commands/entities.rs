@@ -1,4 +1,4 @@
-//! Entity search and backfill commands.
+//! Entity search, backfill, geocoding and document linking commands.
 
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -168,7 +168,11 @@ pub async fn cmd_backfill_entities(
             })
             .collect();
 
-        match doc_repo.save_document_entities(&entity_rows).await {
+        let saved = match doc_repo.save_document_entities(&entity_rows).await {
+            Ok(()) => doc_repo.refresh_document_location(&doc).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        match saved {
             Ok(()) => succeeded += 1,
             Err(e) => {
                 pb.println(format!(
@@ -196,6 +200,122 @@ pub async fn cmd_backfill_entities(
     Ok(())
 }
 
+/// Geocode location entities that have no coordinates yet, then place
+/// documents on the map at the location each mentions most.
+pub async fn cmd_geocode(settings: &Settings, source_id: Option<&str>) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+
+    #[cfg(feature = "gis")]
+    {
+        let names = doc_repo.ungeocoded_location_names().await?;
+        let mut resolved = 0usize;
+        for name in &names {
+            if let Some((lat, lon)) = geolookup::lookup(name) {
+                doc_repo.set_location_coordinates(name, lat, lon).await?;
+                resolved += 1;
+            }
+        }
+        println!(
+            "{} Geocoded {} of {} unresolved locations",
+            style("✓").green(),
+            resolved,
+            names.len()
+        );
+    }
+    #[cfg(not(feature = "gis"))]
+    println!(
+        "{} Built without the gis feature; placing documents from existing coordinates only",
+        style("!").yellow()
+    );
+
+    let doc_ids = doc_repo.get_geocoded_document_ids(source_id).await?;
+    if doc_ids.is_empty() {
+        println!(
+            "{} No documents have geocoded locations",
+            style("!").yellow()
+        );
+        println!("  Documents need location entities (run extract-entities first)");
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new(doc_ids.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    let mut placed = 0usize;
+    for id in &doc_ids {
+        if let Some(doc) = doc_repo.get(id).await? {
+            if doc_repo.refresh_document_location(&doc).await?.is_some() {
+                placed += 1;
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    println!(
+        "{} Placed {} documents on the map",
+        style("✓").green(),
+        placed
+    );
+    Ok(())
+}
+
+/// Detect relationships between documents from their text and file
+/// number entities, replacing those found on earlier runs.
+pub async fn cmd_link_documents(
+    settings: &Settings,
+    source_id: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let doc_repo = repos.documents;
+
+    let mut doc_ids = doc_repo.get_document_ids_with_text(source_id).await?;
+    if limit > 0 {
+        doc_ids.truncate(limit);
+    }
+    if doc_ids.is_empty() {
+        println!("{} No documents have extracted text", style("!").yellow());
+        println!("  Run OCR first so references can be found");
+        return Ok(());
+    }
+
+    let pb = ProgressBar::new(doc_ids.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {wide_msg}")
+            .unwrap()
+            .progress_chars("█▓░"),
+    );
+    let mut linked = 0usize;
+    let mut relations = 0usize;
+    for id in &doc_ids {
+        if let Some(doc) = doc_repo.get(id).await? {
+            let found = doc_repo.refresh_document_relations(&doc).await?;
+            if !found.is_empty() {
+                linked += 1;
+                relations += found.len();
+            }
+        }
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    println!(
+        "{} Found {} relations from {} of {} documents",
+        style("✓").green(),
+        relations,
+        linked,
+        doc_ids.len()
+    );
+    Ok(())
+}
+
 /// Search documents by entity filters from the CLI.
 pub async fn cmd_search_entities(
     settings: &Settings,
@@ -230,8 +350,9 @@ pub async fn cmd_search_entities(
             .map_err(|_| anyhow::anyhow!("Invalid radius in --near"))?;
 
         let doc_ids = doc_repo
-            .search_near_location(lat, lon, radius_km, limit, 0)
-            .await?;
+            .search_near_location(lat, lon, radius_km, None, limit)
+            .await?
+            .items;
 
         println!(
             "{} Found {} documents near ({}, {}) within {}km",
@@ -264,8 +385,9 @@ pub async fn cmd_search_entities(
 
     let count = doc_repo.count_by_entities(&filters, source_id).await?;
     let doc_ids = doc_repo
-        .search_by_entities(&filters, source_id, limit, 0)
-        .await?;
+        .search_by_entities(&filters, source_id, None, limit)
+        .await?
+        .items;
 
     let type_label = entity_type.unwrap_or("any type");
     println!(
@@ -0,0 +1,85 @@
+//! Failure triage commands.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::services::failures::{self, ErrorClass, FailureGroup, FailureKind, TREND_DAYS};
+use foia::utils::sparkline;
+
+fn print_groups(heading: &str, groups: &[FailureGroup]) {
+    let total: u64 = groups.iter().map(|g| g.count).sum();
+    println!("\n{} ({})", style(heading).bold(), total);
+    if groups.is_empty() {
+        println!("  {}", style("none").dim());
+        return;
+    }
+    println!(
+        "  {:<14} {:>8}  {:<width$}  Example",
+        "Class",
+        "Count",
+        format!("Last {}d", TREND_DAYS),
+        width = TREND_DAYS
+    );
+    for g in groups {
+        let mut sample = g.sample.replace('\n', " ");
+        if sample.chars().count() > 60 {
+            sample = sample.chars().take(57).collect::<String>() + "...";
+        }
+        println!(
+            "  {:<14} {:>8}  {}  {}",
+            g.class.as_str(),
+            g.count,
+            sparkline(&g.daily),
+            style(sample).dim()
+        );
+    }
+}
+
+/// Show failed crawl URLs and analysis results grouped by error class.
+pub async fn cmd_failures_list(settings: &Settings, source_id: Option<&str>) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let report = failures::failure_report(&repos.crawl, &repos.documents, source_id).await?;
+
+    print_groups("Crawl failures", &report.crawl);
+    print_groups("Analysis failures", &report.analysis);
+    println!(
+        "\n{} Retry a class with `foia failures retry <CLASS>`",
+        style("→").cyan()
+    );
+    Ok(())
+}
+
+/// Retry every failure in one error class.
+pub async fn cmd_failures_retry(
+    settings: &Settings,
+    class: &str,
+    kind: Option<&str>,
+    source_id: Option<&str>,
+) -> anyhow::Result<()> {
+    let Some(class) = ErrorClass::from_str(class) else {
+        let names: Vec<&str> = ErrorClass::ALL.iter().map(|c| c.as_str()).collect();
+        anyhow::bail!(
+            "Unknown class '{}' (expected one of: {})",
+            class,
+            names.join(", ")
+        );
+    };
+    let kind = kind
+        .map(|k| {
+            FailureKind::from_str(k)
+                .ok_or_else(|| anyhow::anyhow!("Unknown kind '{}' (expected crawl or analysis)", k))
+        })
+        .transpose()?;
+
+    let repos = settings.repositories()?;
+    let count =
+        failures::retry_class(&repos.crawl, &repos.documents, class, kind, source_id).await?;
+    println!(
+        "{} Re-queued {} {} failure{}",
+        style("✓").green(),
+        count,
+        class.label(),
+        if count == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Fetch command: queue and download a list of known document URLs.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use console::style;
+
+use foia::config::Settings;
+use foia::privacy::PrivacyConfig;
+use foia::services::url_list::{self, UrlListFormat};
+
+use super::scrape;
+
+/// Queue the URLs listed in a CSV or JSONL file and download them through
+/// the normal pipeline. Listed titles and dates are applied to the
+/// documents; URLs already queued are left alone. Downloading drains each
+/// listed source's whole pending queue.
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_fetch(
+    settings: &Settings,
+    file: &Path,
+    source_id: Option<&str>,
+    skip_invalid: bool,
+    queue_only: bool,
+    workers: usize,
+    progress: bool,
+    privacy_config: &PrivacyConfig,
+) -> anyhow::Result<()> {
+    settings.ensure_directories()?;
+    let content = std::fs::read_to_string(file)?;
+    let rows = url_list::parse(&content, UrlListFormat::from_path(file))?;
+
+    let mut entries = Vec::new();
+    let mut invalid = 0usize;
+    for row in rows {
+        match row {
+            Ok(entry) => entries.push(entry),
+            Err(e) if skip_invalid => {
+                println!("{} {}", style("!").yellow(), e);
+                invalid += 1;
+            }
+            Err(e) => anyhow::bail!("{} (use --skip-invalid to skip bad rows)", e),
+        }
+    }
+
+    let repos = settings.repositories()?;
+    let mut sources = BTreeSet::new();
+    for entry in &entries {
+        match entry.source_id.as_deref().or(source_id) {
+            Some(id) => {
+                sources.insert(id.to_string());
+            }
+            None => anyhow::bail!(
+                "Line {}: no source column and no --source given",
+                entry.line
+            ),
+        }
+    }
+    for id in &sources {
+        if repos.sources.get(id).await?.is_none() {
+            anyhow::bail!("Source '{}' not found", id);
+        }
+    }
+
+    let mut added = 0usize;
+    let mut known = 0usize;
+    for entry in &entries {
+        let crawl_url = entry.to_crawl_url(source_id.unwrap_or_default());
+        if repos.crawl.add_url(&crawl_url).await? {
+            added += 1;
+        } else {
+            known += 1;
+        }
+    }
+    println!(
+        "{} Queued {} URLs ({} already known, {} invalid)",
+        style("✓").green(),
+        added,
+        known,
+        invalid
+    );
+
+    if queue_only || added == 0 {
+        return Ok(());
+    }
+    for id in &sources {
+        scrape::cmd_download(settings, Some(id), workers, 0, progress, 4, privacy_config).await?;
+    }
+    Ok(())
+}
@@ -316,6 +316,7 @@ pub async fn cmd_import_stdin(
                 metadata: serde_json::json!({}),
                 created_at: Utc::now(),
                 last_scraped: None,
+                agency_id: None,
             };
             source_repo.save(&new_source).await?;
             new_source
@@ -354,9 +355,9 @@ pub async fn cmd_import_stdin(
     version.dedup_index = dedup_index;
 
     // Check for existing document at this URL
-    let existing = doc_repo.get_by_url(url).await?;
+    let existing = doc_repo.find_by_source_url(&source.id, url).await?;
 
-    let (doc_id, is_new) = if let Some(mut doc) = existing.into_iter().next() {
+    let (doc_id, is_new) = if let Some(mut doc) = existing {
         let added = doc.add_version(version);
         if added {
             doc_repo.save_with_versions(&doc).await?;
@@ -365,7 +366,7 @@ pub async fn cmd_import_stdin(
     } else {
         let title = original_filename.unwrap_or_else(|| "Imported document".to_string());
         let doc = Document::new(
-            uuid::Uuid::new_v4().to_string(),
+            Document::stable_id(&source.id, url),
             source.id.clone(),
             title,
             url.to_string(),
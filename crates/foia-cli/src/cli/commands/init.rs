@@ -4,7 +4,7 @@ use console::style;
 
 use foia::config::{Config, Settings};
 use foia::models::{Source, SourceType};
-use foia::repository::migrations;
+use foia::repository::{migrations, shards};
 
 /// Initialize the data directory and database.
 pub async fn cmd_init(settings: &Settings) -> anyhow::Result<()> {
@@ -13,6 +13,7 @@ pub async fn cmd_init(settings: &Settings) -> anyhow::Result<()> {
     // Run database migrations
     println!("{} Running migrations...", style("→").cyan());
     migrations::run_migrations(&settings.database_url(), settings.no_tls).await?;
+    shards::prepare_shards(&settings.shard_databases()).await?;
 
     let repos = settings.repositories()?;
     let source_repo = repos.sources;
@@ -35,6 +36,11 @@ pub async fn cmd_init(settings: &Settings) -> anyhow::Result<()> {
             println!("  {} Added source: {}", style("✓").green(), source.name);
         }
 
+        // Link it to the agency its config names
+        if let Some(agency) = &scraper_config.agency {
+            repos.agencies.link_source(source_id, Some(agency)).await?;
+        }
+
         // Store scraper config in scraper_configs table
         repos
             .scraper_configs
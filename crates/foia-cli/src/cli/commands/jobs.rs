@@ -0,0 +1,113 @@
+//! Background job commands.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::models::{Job, JobStatus};
+
+fn status_style(status: JobStatus) -> console::StyledObject<&'static str> {
+    let s = style(status.as_str());
+    match status {
+        JobStatus::Queued => s.dim(),
+        JobStatus::Running => s.cyan(),
+        JobStatus::Completed => s.green(),
+        JobStatus::Failed => s.red(),
+        JobStatus::Cancelled => s.yellow(),
+    }
+}
+
+fn progress_str(job: &Job) -> String {
+    match (job.total, job.percent()) {
+        (Some(total), Some(percent)) => format!("{}/{} ({}%)", job.progress, total, percent),
+        _ => job.progress.to_string(),
+    }
+}
+
+/// List recent background jobs.
+pub async fn cmd_jobs_list(
+    settings: &Settings,
+    status: Option<&str>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let status = status
+        .map(|s| {
+            JobStatus::from_str(s).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown status '{}' (expected queued, running, completed, failed or cancelled)",
+                    s
+                )
+            })
+        })
+        .transpose()?;
+    let repos = settings.repositories()?;
+    let jobs = repos.jobs.list(status, limit).await?;
+
+    if jobs.is_empty() {
+        println!("{} No jobs", style("!").yellow());
+        return Ok(());
+    }
+    println!(
+        "{:<36}  {:<16}  {:<10}  {:<16}  Started",
+        "ID", "Kind", "Status", "Progress"
+    );
+    for job in &jobs {
+        println!(
+            "{:<36}  {:<16}  {:<10}  {:<16}  {}",
+            job.id,
+            job.kind.as_str(),
+            status_style(job.status),
+            progress_str(job),
+            job.created_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
+/// Show one job in full.
+pub async fn cmd_jobs_show(settings: &Settings, id: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let job = repos
+        .jobs
+        .get(id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Job '{}' not found", id))?;
+
+    println!("{}", style(format!("Job {}", job.id)).bold());
+    println!("  Kind:       {}", job.kind.label());
+    println!("  Status:     {}", status_style(job.status));
+    println!("  Parameters: {}", job.params);
+    println!("  Progress:   {}", progress_str(&job));
+    if let Some(message) = &job.message {
+        println!("  Message:    {}", message);
+    }
+    if let Some(error) = &job.error {
+        println!("  Error:      {}", style(error).red());
+    }
+    if let Some(result) = &job.result {
+        println!("  Result:     {}", result);
+    }
+    println!("  Created:    {}", job.created_at.to_rfc3339());
+    if let Some(started) = job.started_at {
+        println!("  Started:    {}", started.to_rfc3339());
+    }
+    if let Some(finished) = job.finished_at {
+        println!("  Finished:   {}", finished.to_rfc3339());
+    }
+    if job.cancel_requested && !job.status.is_finished() {
+        println!("  {}", style("Cancellation requested").yellow());
+    }
+    Ok(())
+}
+
+/// Ask a job to stop.
+pub async fn cmd_jobs_cancel(settings: &Settings, id: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    if !repos.jobs.request_cancel(id).await? {
+        anyhow::bail!("Job '{}' not found or already finished", id);
+    }
+    println!(
+        "{} Cancellation requested; the job stops after its current item",
+        style("✓").green()
+    );
+    Ok(())
+}
@@ -1,9 +1,198 @@
 //! LLM-related commands.
 
+use std::sync::Arc;
+
+use chrono::{Months, Utc};
 use console::style;
 
 use foia::config::{Config, Settings};
-use foia::llm::LlmClient;
+use foia::llm::{BudgetLimits, LlmClient, LlmConfig, UsageTracker};
+use foia::models::{month_start, UsageTotals};
+use foia::repository::DieselLlmUsageRepository;
+use foia::services::ask;
+
+/// Usage tracker for one run, starting from this month's recorded usage.
+pub async fn usage_tracker(
+    llm_usage: &DieselLlmUsageRepository,
+    llm_config: &LlmConfig,
+) -> anyhow::Result<Arc<UsageTracker>> {
+    let tracker =
+        UsageTracker::with_repository(llm_config.budget().clone(), llm_usage.clone()).await?;
+    Ok(Arc::new(tracker))
+}
+
+/// Print what a run spent on LLM calls.
+pub fn print_run_usage(tracker: &UsageTracker) {
+    let run = tracker.run_totals();
+    if run.requests > 0 {
+        println!(
+            "  LLM usage: {} requests, {} tokens, {}",
+            run.requests,
+            run.tokens(),
+            format_cost(&run)
+        );
+    }
+}
+
+fn format_cost(totals: &UsageTotals) -> String {
+    if totals.requests > 0 && totals.unpriced_requests == totals.requests {
+        "cost unknown".to_string()
+    } else if totals.unpriced_requests > 0 {
+        format!(
+            "${:.4} + {} unpriced",
+            totals.cost_usd, totals.unpriced_requests
+        )
+    } else {
+        format!("${:.4}", totals.cost_usd)
+    }
+}
+
+/// Print a set of limits, with how much of each `used` has consumed.
+fn print_limits(label: &str, limits: &BudgetLimits, used: Option<&UsageTotals>) {
+    if limits.is_empty() {
+        println!("{:<20} none", label);
+        return;
+    }
+    let zero = UsageTotals::default();
+    let totals = used.unwrap_or(&zero);
+    let mut parts = Vec::new();
+    if let Some(max) = limits.requests {
+        parts.push(match used {
+            Some(_) => format!("{}/{} requests", totals.requests, max),
+            None => format!("{} requests", max),
+        });
+    }
+    if let Some(max) = limits.tokens {
+        parts.push(match used {
+            Some(_) => format!("{}/{} tokens", totals.tokens(), max),
+            None => format!("{} tokens", max),
+        });
+    }
+    if let Some(max) = limits.cost_usd {
+        parts.push(match used {
+            Some(_) => format!("${:.2}/${:.2}", totals.cost_usd, max),
+            None => format!("${:.2}", max),
+        });
+    }
+    let status = match (used, limits.exceeded(totals)) {
+        (None, _) => String::new(),
+        (Some(_), Some(_)) => format!(" ({})", style("exceeded").red()),
+        (Some(_), None) => format!(" ({})", style("ok").green()),
+    };
+    println!("{:<20} {}{}", label, parts.join(", "), status);
+}
+
+/// Report LLM usage by month and model, with this month's budget status.
+pub async fn cmd_llm_usage(settings: &Settings, months: u32) -> anyhow::Result<()> {
+    let config = Config::load().await;
+    let repos = settings.repositories()?;
+    let this_month = month_start(Utc::now());
+    let since = this_month
+        .checked_sub_months(Months::new(months.max(1) - 1))
+        .unwrap_or(this_month);
+    let rows = repos.llm_usage.summary(since).await?;
+
+    println!("\n{}", style("LLM Usage").bold());
+    println!("{}", "-".repeat(88));
+    if rows.is_empty() {
+        println!("  No LLM calls recorded since {}", since.format("%Y-%m"));
+    } else {
+        println!(
+            "{:<8} {:<8} {:<36} {:>9} {:>12} {:>12}",
+            "Month", "Provider", "Model", "Requests", "Tokens", "Cost"
+        );
+        for row in &rows {
+            println!(
+                "{:<8} {:<8} {:<36} {:>9} {:>12} {:>12}",
+                row.month,
+                row.provider,
+                super::helpers::truncate(&row.model, 36),
+                row.totals.requests,
+                row.totals.tokens(),
+                format_cost(&row.totals)
+            );
+        }
+    }
+
+    let month = repos.llm_usage.totals_since(this_month).await?;
+    let budget = config.llm.budget();
+    println!("\n{}", style("Budget").bold());
+    println!("{}", "-".repeat(40));
+    println!(
+        "{:<20} {} requests, {} tokens, {}",
+        "This month:",
+        month.requests,
+        month.tokens(),
+        format_cost(&month)
+    );
+    print_limits("Monthly limit:", &budget.monthly, Some(&month));
+    print_limits("Per-run limit:", &budget.run, None);
+    if let Some(price) = budget.price(config.llm.provider(), config.llm.model()) {
+        println!(
+            "{:<20} ${:.2} in / ${:.2} out per 1M tokens ({})",
+            "Current price:",
+            price.input,
+            price.output,
+            config.llm.model()
+        );
+    } else {
+        println!(
+            "{:<20} unknown for {}; set llm.budget.prices to estimate cost",
+            "Current price:",
+            config.llm.model()
+        );
+    }
+
+    Ok(())
+}
+
+/// Answer a question from the archive, citing the pages used.
+pub async fn cmd_ask(
+    settings: &Settings,
+    question: &str,
+    source_id: Option<&str>,
+    pages: usize,
+) -> anyhow::Result<()> {
+    let config = Config::load().await;
+    if !config.llm.enabled() {
+        println!("{} LLM is disabled in configuration", style("!").yellow());
+        println!("  Set llm.enabled = true in your foia.json config");
+        return Ok(());
+    }
+
+    let repos = settings.repositories()?;
+    let tracker = usage_tracker(&repos.llm_usage, &config.llm).await?;
+    let client = LlmClient::new(config.llm.clone()).with_usage_tracker(tracker.clone());
+    if !client.is_available().await {
+        println!("{} {}", style("✗").red(), config.llm.availability_hint());
+        return Ok(());
+    }
+
+    let answer = ask::ask(&repos.documents, &client, question, source_id, pages).await?;
+
+    println!("\n{}", answer.answer);
+    if !answer.citations.is_empty() {
+        println!("\n{}", style("Sources").bold());
+        println!("{}", "-".repeat(40));
+        for citation in &answer.citations {
+            println!(
+                "  {} p.{}  {}",
+                citation.document_id,
+                citation.page_number,
+                super::helpers::truncate(&citation.title, 60)
+            );
+        }
+    } else if answer.pages_consulted > 0 {
+        println!(
+            "\n{} The answer cites none of the {} pages consulted",
+            style("!").yellow(),
+            answer.pages_consulted
+        );
+    }
+    print_run_usage(&tracker);
+
+    Ok(())
+}
 
 /// List available LLM models.
 pub async fn cmd_llm_models(_settings: &Settings) -> anyhow::Result<()> {
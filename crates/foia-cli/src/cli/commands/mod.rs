@@ -2,30 +2,51 @@
 //!
 //! This module contains the CLI parser and dispatches to command-specific modules.
 
+mod agencies;
 mod analyze;
 mod annotate;
+mod api_keys;
+mod bundle;
 mod config_cmd;
+mod curate;
 mod daemon;
 mod db;
 mod discover;
+mod doctor;
 mod documents;
 mod entities;
+mod failures;
+mod fetch;
 mod helpers;
 mod import;
 mod init;
+mod jobs;
 mod llm;
+mod queue;
 #[cfg(feature = "gis")]
 mod regions;
+mod reports;
+mod requests;
 mod scrape;
+mod scraper;
 mod serve;
 mod source;
 mod state;
+mod tags;
+mod text_pdf;
+mod top;
+mod urls;
+mod views;
+mod wayback;
+mod worker;
+mod workflow;
 
 use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
-use foia::config::{load_settings_with_options, LoadOptions};
+use foia::config::{load_settings_with_options, LoadOptions, DEFAULT_WORKSPACE};
+use foia::metrics::{self, MetricsPusher};
 use foia::work_queue::ExecutionStrategy;
 
 // Re-export ReloadMode for use by other modules
@@ -34,7 +55,7 @@ pub use daemon::ReloadMode;
 /// Backend type for rate limiting storage.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
 pub enum RateLimitBackendType {
-    /// In-memory (single process, not persisted)
+    /// In-memory (single process, saved to the database on exit)
     Memory,
     /// Database via Diesel (SQLite or PostgreSQL, persisted, multi-process)
     #[default]
@@ -62,6 +83,10 @@ pub struct Cli {
     #[arg(long, global = true)]
     cwd: bool,
 
+    /// Workspace to use (a named data directory from the config file)
+    #[arg(long, global = true, env = "FOIA_WORKSPACE")]
+    workspace: Option<String>,
+
     /// Enable verbose logging
     #[arg(short, long, global = true)]
     pub verbose: bool,
@@ -128,6 +153,31 @@ enum Commands {
         /// Show detailed progress for each file
         #[arg(short = 'P', long)]
         progress: bool,
+        /// Concurrent range requests per large file (1 = sequential)
+        #[arg(long, default_value = "4")]
+        chunks: usize,
+    },
+
+    /// Queue and download URLs listed in a CSV or JSONL file
+    Fetch {
+        /// CSV (with a `url` header) or JSONL file; optional title, date and source columns
+        #[arg(long, value_name = "FILE")]
+        from_file: PathBuf,
+        /// Source for rows without a source column
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Skip rows with a missing or invalid URL or date instead of failing
+        #[arg(long)]
+        skip_invalid: bool,
+        /// Only add the URLs to the queue; download later with `download`
+        #[arg(long)]
+        queue_only: bool,
+        /// Number of download workers
+        #[arg(short, long, default_value = "4")]
+        workers: usize,
+        /// Show detailed progress for each file
+        #[arg(short = 'P', long)]
+        progress: bool,
     },
 
     /// Manage crawl state
@@ -136,6 +186,86 @@ enum Commands {
         command: StateCommands,
     },
 
+    /// Inspect and edit the crawl URL queue
+    Urls {
+        #[command(subcommand)]
+        command: UrlCommands,
+    },
+
+    /// Group failed crawl URLs and analysis results by error class
+    Failures {
+        #[command(subcommand)]
+        command: FailureCommands,
+    },
+
+    /// Show and cancel background jobs started from the web UI or API
+    Jobs {
+        #[command(subcommand)]
+        command: JobCommands,
+    },
+
+    /// Manage the agency registry and link sources to agencies
+    Agencies {
+        #[command(subcommand)]
+        command: AgencyCommands,
+    },
+
+    /// Track FOIA requests and link the documents they produced
+    Requests {
+        #[command(subcommand)]
+        command: RequestCommands,
+    },
+
+    /// List saved browse views and subscribe addresses to new matches
+    Views {
+        #[command(subcommand)]
+        command: ViewCommands,
+    },
+
+    /// Issue, list and revoke keys for the JSON API
+    ApiKey {
+        #[command(subcommand)]
+        command: ApiKeyCommands,
+    },
+
+    /// Email crawl summaries, broken-scraper alerts and saved-view digests;
+    /// write crawl politeness reports
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
+    /// Publish tasks to the worker queue and inspect it
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommands,
+    },
+
+    /// Run queued download, OCR and summarize tasks until interrupted
+    Worker {
+        /// Queues to take tasks from, comma-separated (default: all)
+        #[arg(short, long, value_delimiter = ',')]
+        queues: Vec<String>,
+        /// Concurrent downloads within a download task
+        #[arg(short, long, default_value = "4")]
+        workers: usize,
+        /// Seconds to wait when every queue is empty
+        #[arg(long, default_value = "5")]
+        poll: u64,
+    },
+
+    /// Review and ingest Wayback Machine captures of dead URLs
+    Wayback {
+        #[command(subcommand)]
+        command: WaybackCommands,
+    },
+
+    /// Develop scrapers against recorded HTTP fixtures
+    Scraper {
+        #[command(subcommand)]
+        command: ScraperCommands,
+    },
+
     /// Configuration management
     Config {
         #[command(subcommand)]
@@ -174,8 +304,17 @@ enum Commands {
         #[arg(short = 'r', long, value_enum, num_args = 0..=1, default_value = "next-run", default_missing_value = "inplace", require_equals = true)]
         reload: ReloadMode,
         /// Rate limit backend: memory, database (default), or redis
-        #[arg(long, value_enum, default_value = "database", env = "RATE_LIMIT_BACKEND")]
+        #[arg(
+            long,
+            value_enum,
+            default_value = "database",
+            env = "RATE_LIMIT_BACKEND"
+        )]
         rate_limit_backend: RateLimitBackendType,
+        /// Preview only: run discovery and HEAD requests, report new URLs and
+        /// estimated download size, and write nothing
+        #[arg(long, conflicts_with = "daemon")]
+        dry_run: bool,
     },
 
     /// Show system status
@@ -201,6 +340,13 @@ enum Commands {
         json: bool,
     },
 
+    /// Live view of crawl throughput, rate limits, downloads, OCR backlog and errors
+    Top {
+        /// Refresh interval in seconds
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+
     /// Analyze documents: detect content types, extract text, and run OCR
     Analyze {
         /// Source ID (optional, processes all sources if not specified)
@@ -250,6 +396,16 @@ enum Commands {
     /// Check if required analysis tools (OCR, etc.) are installed
     AnalyzeCheck,
 
+    /// Check external tools (Poppler, Tesseract, language packs) and install missing ones
+    Doctor {
+        /// Install missing tools with the system package manager
+        #[arg(long)]
+        install: bool,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+
     /// Compare OCR backends on an image or PDF
     AnalyzeCompare {
         /// Image file or PDF to OCR
@@ -265,6 +421,79 @@ enum Commands {
         deepseek_path: Option<std::path::PathBuf>,
     },
 
+    /// Re-run OCR on pages whose results match a settings filter
+    AnalyzeReprocess {
+        /// Source ID (optional, matches all sources if not specified)
+        source_id: Option<String>,
+        /// Filter on recorded settings, e.g. 'backend=tesseract AND version<5'
+        /// (fields: backend, model, version, language, dpi, quality)
+        #[arg(long = "where", value_name = "FILTER")]
+        filter: String,
+        /// Show what would be reprocessed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Extract tables from PDFs to CSV files attached to each document
+    AnalyzeTables {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Maximum number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Mark redactions in document text and count the FOIA exemptions cited
+    AnalyzeRedactions {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Maximum number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Scan document text for personal information and flag documents for review
+    AnalyzePii {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Maximum number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+        /// Flag documents scoring at least this much (0-100)
+        #[arg(long, default_value = "50")]
+        threshold: u32,
+    },
+
+    /// Record the author, software and dates embedded in PDFs
+    AnalyzePdfMetadata {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Maximum number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Make PDFs searchable by embedding an OCR text layer
+    AnalyzeSearchablePdf {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Maximum number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Release documents claimed for analysis by workers that died
+    AnalyzeUnlock {
+        /// Source ID (optional, releases claims in all sources if not specified)
+        source_id: Option<String>,
+        /// Analysis type to release (e.g. ocr, tables); all types if not specified
+        #[arg(short = 't', long = "type")]
+        analysis_type: Option<String>,
+        /// Only release claims whose lease has already expired
+        #[arg(long)]
+        stale: bool,
+    },
+
     /// Start web server to browse documents (as Tor hidden service by default)
     Serve {
         /// Address to bind to: PORT, HOST, or HOST:PORT (default: 127.0.0.1:3030)
@@ -283,6 +512,29 @@ enum Commands {
         /// (requires allow_potentially_insecure_circuits in config)
         #[arg(long)]
         use_arti: bool,
+
+        /// Read-only public mode: no mutating endpoints or crawl pages,
+        /// credentials stripped from URLs, per-client rate limiting
+        #[arg(long)]
+        public: bool,
+    },
+
+    /// Publish a read-only snapshot of the archive
+    Publish {
+        /// Render a static HTML site into this directory
+        #[arg(long = "static", value_name = "DIR")]
+        static_dir: PathBuf,
+        /// Only publish documents from this source
+        source_id: Option<String>,
+        /// Site name shown in the header and page titles
+        #[arg(long, default_value = "foia")]
+        title: String,
+        /// Don't copy document files (metadata and text only)
+        #[arg(long)]
+        no_files: bool,
+        /// Publish documents with open flags too
+        #[arg(long)]
+        include_flagged: bool,
     },
 
     /// Refresh metadata for existing documents (server date, original filename)
@@ -361,6 +613,24 @@ enum Commands {
         limit: usize,
     },
 
+    /// Classify documents into record types (correspondence, contract, invoice, ...) using LLM
+    ClassifyRecords {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Limit number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
+    /// Extract document date, originating agency/office, case number, and classification markings using LLM
+    ExtractMetadata {
+        /// Source ID (optional, processes all sources if not specified)
+        source_id: Option<String>,
+        /// Limit number of documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
     /// Backfill the document_entities table from existing NER annotations
     BackfillEntities {
         /// Source ID (optional, processes all sources if not specified)
@@ -370,6 +640,22 @@ enum Commands {
         limit: usize,
     },
 
+    /// Geocode location entities and place documents on the map
+    Geocode {
+        /// Only place documents from this source (geocoding covers all)
+        source_id: Option<String>,
+    },
+
+    /// Detect attachments, replies and shared case numbers between documents
+    LinkDocuments {
+        /// Only link documents from this source (targets may be in any)
+        source_id: Option<String>,
+
+        /// Maximum documents to process (0 = unlimited)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+    },
+
     /// Search documents by extracted entities
     SearchEntities {
         /// Entity text to search for
@@ -391,6 +677,24 @@ enum Commands {
     /// List available LLM models
     LlmModels,
 
+    /// LLM usage and budgets
+    Llm {
+        #[command(subcommand)]
+        command: LlmCommands,
+    },
+
+    /// Answer a question from the archive using LLM, citing documents and pages
+    Ask {
+        /// The question to answer
+        question: String,
+        /// Only use documents from this source
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Number of pages given to the LLM as context
+        #[arg(short, long, default_value = "8")]
+        pages: usize,
+    },
+
     /// Extract contents from container files (zip archives, emails) as virtual files
     Archive {
         /// Source ID (optional, processes all sources if not specified)
@@ -428,6 +732,24 @@ enum Commands {
         doc_id: String,
     },
 
+    /// Manage document tags in bulk
+    Tags {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+
+    /// Merge duplicate documents or split bundled PDFs
+    Curate {
+        #[command(subcommand)]
+        command: CurateCommands,
+    },
+
+    /// Show per-status counts or move documents through the status workflow
+    Workflow {
+        #[command(subcommand)]
+        command: WorkflowCommands,
+    },
+
     /// Output document content to stdout
     Read {
         /// Document ID
@@ -437,6 +759,33 @@ enum Commands {
         text: bool,
     },
 
+    /// Export a document's provenance record as JSON
+    Provenance {
+        /// Document ID
+        doc_id: String,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Export document metadata as tables for data analysis
+    ExportMetadata {
+        /// Output format (csv, parquet)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+        /// Directory to write the tables to
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Only export documents from this source
+        source_id: Option<String>,
+    },
+
+    /// Move sources between instances as a single portable archive
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommands,
+    },
+
     /// Search documents by content or metadata
     Search {
         /// Search query
@@ -497,57 +846,760 @@ enum Commands {
 }
 
 #[derive(Subcommand)]
-enum SourceCommands {
-    /// List configured sources
-    List,
-    /// Rename a source (updates all associated documents)
-    Rename {
-        /// Current source ID
-        old_id: String,
-        /// New source ID
-        new_id: String,
-        /// Skip confirmation prompt
+enum SourceCommands {
+    /// List configured sources
+    List,
+    /// Rename a source (updates all associated documents)
+    Rename {
+        /// Current source ID
+        old_id: String,
+        /// New source ID
+        new_id: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum WorkflowCommands {
+    /// Show document counts per status and the allowed moves
+    Status {
+        /// Only documents from this source
+        #[arg(short, long)]
+        source: Option<String>,
+    },
+    /// Move documents to another status, as the workflow allows
+    Move {
+        /// Status to move to, e.g. reviewed or published
+        to: String,
+        /// Documents to move (default: every document in --from)
+        ids: Vec<String>,
+        /// Move every document with this status
+        #[arg(long)]
+        from: Option<String>,
+        /// With --from, only documents from this source
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum CurateCommands {
+    /// Merge documents that are the same record into one
+    Merge {
+        /// Document to keep
+        target: String,
+        /// Documents to merge into it (deleted afterwards)
+        #[arg(required = true)]
+        sources: Vec<String>,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Split a PDF into child documents at page boundaries
+    Split {
+        /// Document to split (kept as is)
+        doc_id: String,
+        /// Pages that start a new document, e.g. --at 5,12
+        #[arg(long, required = true, value_delimiter = ',')]
+        at: Vec<u32>,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Replace filename titles like "document.pdf (143)" with better ones
+    ///
+    /// Looks in the Content-Disposition filename, the PDF's metadata and
+    /// the first page's heading, then optionally asks the LLM.
+    Retitle {
+        /// Only documents from this source
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Ask the LLM when nothing else gives a title
+        #[arg(long)]
+        llm: bool,
+        /// Show the new titles without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Publish a copy of a PDF with regions or personal information blacked out
+    ///
+    /// The original is kept; public views and static sites show the
+    /// redacted copy instead.
+    Redact {
+        /// Document to redact
+        doc_id: String,
+        /// Region to black out as PAGE:X,Y,WIDTH,HEIGHT in fractions of the
+        /// page from its top-left corner, e.g. 2:0.1,0.4,0.8,0.05 (repeatable)
+        #[arg(long = "region", value_name = "REGION")]
+        regions: Vec<String>,
+        /// Black out SSNs, dates of birth, phone numbers and addresses
+        #[arg(long)]
+        pii: bool,
+        /// Black out every occurrence of a word or phrase (repeatable)
+        #[arg(long = "term", value_name = "TEXT")]
+        terms: Vec<String>,
+        /// Publish even if some page's text mentions something that can't be
+        /// found on its image; only that page's text is masked
+        #[arg(long)]
+        allow_missed: bool,
+        /// Remove the redacted copy and publish the original again
+        #[arg(long, conflicts_with_all = ["regions", "pii", "terms", "allow_missed"])]
+        remove: bool,
+    },
+    /// Show merges, splits and retitles, newest first
+    Log {
+        /// Only entries involving this document
+        doc_id: Option<String>,
+        /// Maximum entries to show
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommands {
+    /// List all tags with document counts
+    List,
+    /// Rename a tag across all documents
+    Rename {
+        /// Current tag
+        old: String,
+        /// New tag (merged if documents already have it)
+        new: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Merge one or more tags into another
+    Merge {
+        /// Tags to merge away
+        #[arg(required = true)]
+        from: Vec<String>,
+        /// Tag to merge into
+        #[arg(long)]
+        into: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Remove a tag from all documents
+    Delete {
+        /// Tag to delete
+        tag: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Add a tag to all documents matching a filter
+    Add {
+        /// Tag to add
+        tag: String,
+        #[command(flatten)]
+        filter: TagFilterArgs,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Remove a tag from all documents matching a filter
+    Remove {
+        /// Tag to remove
+        tag: String,
+        #[command(flatten)]
+        filter: TagFilterArgs,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+/// Document filter shared by `tags add` and `tags remove`.
+#[derive(clap::Args)]
+struct TagFilterArgs {
+    /// Only documents from this source
+    #[arg(short, long)]
+    source: Option<String>,
+    /// Only documents that already have this tag (repeatable)
+    #[arg(long = "has-tag")]
+    has_tags: Vec<String>,
+    /// Only documents of this record type (repeatable)
+    #[arg(long = "record-type")]
+    record_types: Vec<String>,
+    /// Only documents whose title or synopsis contains this text
+    #[arg(short, long)]
+    query: Option<String>,
+}
+
+impl From<TagFilterArgs> for tags::TagFilter {
+    fn from(args: TagFilterArgs) -> Self {
+        Self {
+            source: args.source,
+            tags: args.has_tags,
+            record_types: args.record_types,
+            query: args.query,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Migrate a config file into the database
+    Transfer {
+        /// Path to config file (default: auto-discover)
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+    },
+    /// Get a config value
+    Get {
+        /// Setting path (e.g., "user_agent", "scrapers.my-source.name")
+        setting: String,
+    },
+    /// Set a config value
+    Set {
+        /// Setting path (e.g., "user_agent", "request_timeout")
+        setting: String,
+        /// Value to set (JSON for complex types)
+        value: String,
+    },
+    /// List stored config file snapshots
+    History,
+    /// Show how a snapshot differs from another or from the active config
+    Diff {
+        /// Snapshot ID (or a prefix of it)
+        id: String,
+        /// Snapshot to compare against (default: the active config)
+        other: Option<String>,
+    },
+    /// Restore a snapshot to the active config file
+    Rollback {
+        /// Snapshot ID (or a prefix of it)
+        id: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StateCommands {
+    /// Show crawl status
+    Status {
+        /// Source ID (optional, shows all if not specified)
+        source_id: Option<String>,
+    },
+    /// Clear crawl state for a source
+    Clear {
+        /// Source ID
+        source_id: String,
+        /// Confirm clearing
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum UrlCommands {
+    /// List a source's queued URLs
+    List {
+        /// Source ID
+        source_id: String,
+        /// Status filter: pending, failed, skipped, fetched, or all
+        #[arg(short, long, default_value = "pending")]
+        status: String,
+        /// Maximum URLs to show
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+    },
+    /// Re-queue URLs, resetting their retry count
+    Retry {
+        /// Source ID
+        source_id: String,
+        /// URLs to re-queue
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Remove URLs from the queue (marked skipped so discovery won't re-add them)
+    Cancel {
+        /// Source ID
+        source_id: String,
+        /// URLs to cancel
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+    /// Re-queue all failed and exhausted URLs
+    RetryFailed {
+        /// Source ID (optional, all sources if not specified)
+        source_id: Option<String>,
+    },
+    /// Add seed URLs to a source's queue
+    Add {
+        /// Source ID
+        source_id: String,
+        /// URLs to add
+        #[arg(required = true)]
+        urls: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FailureCommands {
+    /// Show failure counts and 14-day trends per error class
+    List {
+        /// Only this source
+        #[arg(short, long)]
+        source: Option<String>,
+    },
+    /// Re-queue every failure in an error class (e.g. dns, timeout, not_found)
+    Retry {
+        /// Error class, as shown by `failures list`
+        class: String,
+        /// Only crawl or analysis failures (default: both)
+        #[arg(short, long)]
+        kind: Option<String>,
+        /// Only this source
+        #[arg(short, long)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum BundleCommands {
+    /// Write sources with their documents and files to a bundle
+    Export {
+        /// Bundle file to write (a tar archive)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Source IDs to include
+        source_ids: Vec<String>,
+        /// Include every source
+        #[arg(short, long, conflicts_with = "source_ids")]
+        all: bool,
+    },
+    /// Import a bundle, verifying every file against its manifest
+    Import {
+        /// Bundle file
+        path: PathBuf,
+    },
+    /// Show a bundle's manifest
+    Inspect {
+        /// Bundle file
+        path: PathBuf,
+    },
+    /// Bundle a source and create a torrent of it for peer-to-peer download
+    Torrent {
+        /// Source ID
+        source_id: String,
+        /// Directory to write the bundle and .torrent file to
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Tracker announce URL (repeatable; without one, peers use the DHT)
+        #[arg(long = "tracker")]
+        trackers: Vec<String>,
+        /// URL where the bundle directory is also served over HTTP (repeatable)
+        #[arg(long = "web-seed")]
+        web_seeds: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum LlmCommands {
+    /// Report LLM token usage and estimated cost by month and model
+    Usage {
+        /// Number of months to report, including the current one
+        #[arg(short, long, default_value = "3")]
+        months: u32,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobCommands {
+    /// List recent jobs, newest first
+    List {
+        /// Only jobs in this status (queued, running, completed, failed, cancelled)
+        #[arg(short, long)]
+        status: Option<String>,
+        /// Maximum jobs to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Show a job's parameters, progress and result
+    Show {
+        /// Job ID
+        id: String,
+    },
+    /// Ask a running or queued job to stop
+    Cancel {
+        /// Job ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AgencyCommands {
+    /// List agencies and how many sources link to each
+    List {
+        /// Only agencies at this level (federal, state, county, municipal, tribal, other)
+        #[arg(short, long)]
+        level: Option<String>,
+    },
+    /// Show an agency's contacts, components and sources
+    Show {
+        /// Agency ID
+        id: String,
+    },
+    /// Add an agency, or replace the one with the same ID
+    Add {
+        /// Full name
+        name: String,
+        /// ID used in `agency:` tags (default: the abbreviation or name, slugged)
+        #[arg(long)]
+        id: Option<String>,
+        #[arg(short, long)]
+        abbreviation: Option<String>,
+        /// Whose records laws apply, e.g. US or US-CA (default: US)
+        #[arg(short, long)]
+        jurisdiction: Option<String>,
+        /// federal, state, county, municipal, tribal or other (default: federal)
+        #[arg(short, long)]
+        level: Option<String>,
+        /// ID of the agency this is a component of
+        #[arg(short, long)]
+        parent: Option<String>,
+        /// FOIA office email
+        #[arg(long)]
+        email: Option<String>,
+        /// FOIA office phone
+        #[arg(long)]
+        phone: Option<String>,
+        /// FOIA office mailing address
+        #[arg(long)]
+        address: Option<String>,
+        /// Request portal or reading room URL (repeatable)
+        #[arg(long = "portal")]
+        portals: Vec<String>,
+        #[arg(long)]
+        website: Option<String>,
+    },
+    /// Import agencies from a CSV, JSON or FOIA.gov agency_components file
+    Import {
+        /// Agency list file
+        file: PathBuf,
+        /// List the agencies without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Link a source to an agency and tag its documents `agency:<id>`
+    Link {
+        /// Source ID
+        source_id: String,
+        /// Agency ID
+        agency_id: String,
+    },
+    /// Unlink a source from its agency; document tags are kept
+    Unlink {
+        /// Source ID
+        source_id: String,
+    },
+    /// Delete an agency; its sources are unlinked
+    Delete {
+        /// Agency ID
+        id: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum RequestCommands {
+    /// Record a request filed with an agency
+    Add {
+        /// Agency the request was sent to
+        #[arg(short, long)]
+        agency: String,
+        /// Short description of what was requested
+        #[arg(short = 'S', long)]
+        subject: String,
+        /// Filing date, YYYY-MM-DD (default: today)
+        #[arg(short, long)]
+        filed: Option<String>,
+        /// Agency tracking or case number
+        #[arg(short, long)]
+        tracking: Option<String>,
+        /// Source the responsive documents land in
+        #[arg(short, long)]
+        source: Option<String>,
+        /// Whose deadline rules apply (default: requests.default_jurisdiction)
+        #[arg(short, long)]
+        jurisdiction: Option<String>,
+        /// Full request text
+        #[arg(long, conflicts_with = "text_file")]
+        text: Option<String>,
+        /// Read the full request text from a file
+        #[arg(long)]
+        text_file: Option<String>,
+    },
+    /// List requests with their due dates
+    List {
+        /// Only requests in this status (filed, acknowledged, fulfilled, appealed)
+        #[arg(short, long)]
+        status: Option<String>,
+        /// Only requests past their response deadline
+        #[arg(long)]
+        overdue: bool,
+    },
+    /// Show a request, its deadline and the documents it produced
+    Show {
+        /// Request ID or ID prefix
+        id: String,
+    },
+    /// Change a request's status or details
+    Update {
+        /// Request ID or ID prefix
+        id: String,
+        /// New status (filed, acknowledged, fulfilled, appealed)
+        #[arg(long)]
+        status: Option<String>,
+        /// Date of the status change, YYYY-MM-DD (default: today)
+        #[arg(long)]
+        date: Option<String>,
+        #[arg(long)]
+        agency: Option<String>,
+        #[arg(long)]
+        subject: Option<String>,
+        /// Agency tracking or case number (empty to clear)
+        #[arg(long)]
+        tracking: Option<String>,
+        /// Source the responsive documents land in (empty to clear)
+        #[arg(long)]
+        source: Option<String>,
+        /// Whose deadline rules apply (empty for the default)
+        #[arg(long)]
+        jurisdiction: Option<String>,
+        /// Free-form notes (empty to clear)
+        #[arg(long)]
+        notes: Option<String>,
+    },
+    /// Link received documents to a request
+    Link {
+        /// Request ID or ID prefix
+        id: String,
+        /// Document IDs
+        #[arg(required = true)]
+        doc_ids: Vec<String>,
+    },
+    /// Unlink documents from a request
+    Unlink {
+        /// Request ID or ID prefix
+        id: String,
+        /// Document IDs
+        #[arg(required = true)]
+        doc_ids: Vec<String>,
+    },
+    /// Delete a request; its documents are kept
+    Delete {
+        /// Request ID or ID prefix
+        id: String,
+        /// Skip confirmation prompt
+        #[arg(long)]
+        confirm: bool,
+    },
+    /// Send reminders for overdue requests to the configured webhook or email
+    Remind {
+        /// List the reminders that are due without sending them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ViewCommands {
+    /// List saved views and who is subscribed to them
+    List,
+    /// Email an address whenever new documents match a view
+    Subscribe {
+        /// Saved view name
+        view: String,
+        /// Address to alert
+        email: String,
+    },
+    /// Stop alerting an address about a view
+    Unsubscribe {
+        /// Saved view name
+        view: String,
+        /// Subscribed address
+        email: String,
+    },
+    /// Send pending alerts now (scrape sends them after each crawl)
+    Alert {
+        /// Print the alerts instead of sending them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommands {
+    /// Issue a key and print it (shown only once)
+    Create {
+        /// Who or what the key is for
+        name: String,
+        /// Requests per minute (default: server.api_rate_limit)
+        #[arg(long)]
+        rate_limit: Option<u32>,
+        /// Let the key issue, list and revoke keys
+        #[arg(long)]
+        admin: bool,
+    },
+    /// List keys with their use over the last 30 days
+    List,
+    /// Revoke a key; requests made with it are refused
+    Revoke {
+        /// Key name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportCommands {
+    /// Send a test email to check the email settings
+    Test {
+        /// Recipient (default: email.to); repeat for several
+        #[arg(long)]
+        to: Vec<String>,
+    },
+    /// New documents, requests and failures per source
+    CrawlSummary {
+        /// Hours to cover
+        #[arg(long, default_value = "24")]
+        hours: u32,
+        /// Recipient (default: email.to); repeat for several
+        #[arg(long)]
+        to: Vec<String>,
+        /// Print the email instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Sources still being crawled with no successful request in a week
+    ScraperAlerts {
+        /// Recipient (default: email.to); repeat for several
+        #[arg(long)]
+        to: Vec<String>,
+        /// Print the email instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// New documents matching each saved view
+    Digest {
+        /// Days to cover
+        #[arg(long, default_value = "7")]
+        days: u32,
+        /// Recipient (default: email.to); repeat for several
+        #[arg(long)]
+        to: Vec<String>,
+        /// Print the email instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Requests, pacing, robots.txt and user agent per source, to show a
+    /// site how it was crawled
+    Politeness {
+        /// Only this source
+        source_id: Option<String>,
+        /// Days to cover
+        #[arg(long, default_value = "30")]
+        days: u32,
+        /// Output format (markdown, pdf)
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+        /// Write to this file instead of stdout (required for pdf)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum QueueCommands {
+    /// Publish tasks: download batches of pending URLs, or OCR/summarize
+    /// documents that still need it
+    Push {
+        /// download, ocr or summarize
+        kind: String,
+        /// Only this source
+        source_id: Option<String>,
+        /// Publish a task for this document only (ocr, summarize)
         #[arg(long)]
-        confirm: bool,
+        doc: Option<String>,
+        /// Maximum URLs or documents to queue (0 = all)
+        #[arg(short, long, default_value = "0")]
+        limit: usize,
+        /// URLs per download task
+        #[arg(long, default_value = "100")]
+        batch: usize,
+    },
+    /// Show ready, in-flight and dead tasks per queue
+    Status,
+    /// Requeue tasks that failed too many times
+    Retry {
+        /// download, ocr or summarize
+        kind: String,
     },
 }
 
 #[derive(Subcommand)]
-enum ConfigCommands {
-    /// Migrate a config file into the database
-    Transfer {
-        /// Path to config file (default: auto-discover)
+enum WaybackCommands {
+    /// List captures found for URLs that returned 404 or 410
+    List {
+        /// Only this source
         #[arg(short, long)]
-        file: Option<PathBuf>,
+        source: Option<String>,
+        /// offered, queued, ingested, dismissed or all
+        #[arg(long, default_value = "offered")]
+        state: String,
     },
-    /// Get a config value
-    Get {
-        /// Setting path (e.g., "user_agent", "scrapers.my-source.name")
-        setting: String,
+    /// Queue captures for download as new versions of their documents
+    Ingest {
+        /// Capture IDs, as shown by `wayback list`
+        ids: Vec<i32>,
+        /// Ingest every offered capture
+        #[arg(long)]
+        all: bool,
+        /// With --all, only this source
+        #[arg(short, long)]
+        source: Option<String>,
     },
-    /// Set a config value
-    Set {
-        /// Setting path (e.g., "user_agent", "request_timeout")
-        setting: String,
-        /// Value to set (JSON for complex types)
-        value: String,
+    /// Decline captures so they are no longer offered
+    Dismiss {
+        /// Capture IDs
+        #[arg(required = true)]
+        ids: Vec<i32>,
     },
 }
 
 #[derive(Subcommand)]
-enum StateCommands {
-    /// Show crawl status
-    Status {
-        /// Source ID (optional, shows all if not specified)
-        source_id: Option<String>,
+enum ScraperCommands {
+    /// Run discovery live, recording every response as a fixture
+    Record {
+        /// Source ID
+        source_id: String,
+        /// Fixture directory (default: <data_dir>/fixtures/<source_id>)
+        #[arg(long)]
+        fixtures: Option<PathBuf>,
     },
-    /// Clear crawl state for a source
-    Clear {
+    /// Replay discovery offline and check it finds the recorded documents
+    Test {
         /// Source ID
         source_id: String,
-        /// Confirm clearing
+        /// Fixture directory (default: <data_dir>/fixtures/<source_id>)
         #[arg(long)]
-        confirm: bool,
+        fixtures: Option<PathBuf>,
     },
 }
 
@@ -827,6 +1879,50 @@ enum DbCommands {
         batch_size: usize,
     },
 
+    /// Move documents to stable IDs derived from their source and URL,
+    /// merging duplicates; old IDs keep redirecting
+    MigrateIds {
+        /// Only show what would change, don't actually change it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Compress the text of pages stored before page text compression
+    CompressText {
+        /// Only measure how much space compression would save
+        #[arg(long)]
+        dry_run: bool,
+        /// Pages to read per batch
+        #[arg(long, default_value = "1000")]
+        batch_size: usize,
+    },
+
+    /// Check the page search index against the pages table
+    CheckSearch {
+        /// Index missing pages and drop stale entries, or rebuild the index
+        /// if it's damaged
+        #[arg(long)]
+        repair: bool,
+    },
+
+    /// Delete stale derived artifacts and files nothing references
+    PruneArtifacts {
+        /// Only delete artifacts stale for at least this many days
+        #[arg(long, default_value = "7")]
+        older_than_days: u32,
+        /// Only show what would be deleted, don't actually delete
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Move sharded sources' documents and crawl state from the main
+    /// database into their shards
+    Split {
+        /// Only split this shard
+        #[arg(long)]
+        shard: Option<String>,
+    },
+
     /// Load region boundary data (countries, US states) for spatial queries
     #[cfg(feature = "gis")]
     LoadRegions {
@@ -845,12 +1941,22 @@ pub async fn run() -> anyhow::Result<()> {
         use_cwd: cli.cwd,
         data: cli.data,
     };
-    let (mut settings, mut config) = load_settings_with_options(options).await;
+    let (mut base_settings, mut config) = load_settings_with_options(options).await;
 
     if cli.no_tls {
-        settings.no_tls = true;
+        base_settings.no_tls = true;
     }
 
+    // Switch to the selected workspace's data directory and database
+    let base_dir = config.resolve_base_dir(cli.cwd);
+    let workspace = cli
+        .workspace
+        .clone()
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+    let settings = config
+        .workspace_settings(&workspace, &base_settings, &base_dir)
+        .map_err(anyhow::Error::msg)?;
+
     // Apply CLI privacy overrides
     config.privacy = config.privacy.with_cli_overrides(
         cli.direct,
@@ -867,10 +1973,41 @@ pub async fn run() -> anyhow::Result<()> {
         cli.command,
         Commands::Init
             | Commands::Source { .. }
+            | Commands::Tags { .. }
+            | Commands::Curate { .. }
+            | Commands::Workflow { .. }
+            | Commands::Urls { .. }
+            | Commands::Failures { .. }
+            | Commands::Jobs { .. }
+            | Commands::Agencies { .. }
+            | Commands::Requests { .. }
+            | Commands::Views { .. }
+            | Commands::ApiKey { .. }
+            | Commands::Report { .. }
+            | Commands::Queue { .. }
+            | Commands::Wayback { .. }
+            | Commands::Scraper {
+                command: ScraperCommands::Test { .. }
+            }
             | Commands::Config { .. }
             | Commands::Serve { .. }
             | Commands::BackfillEntities { .. }
+            | Commands::Geocode { .. }
+            | Commands::LinkDocuments { .. }
             | Commands::SearchEntities { .. }
+            | Commands::AnalyzeReprocess { .. }
+            | Commands::AnalyzeTables { .. }
+            | Commands::AnalyzeRedactions { .. }
+            | Commands::AnalyzePii { .. }
+            | Commands::AnalyzePdfMetadata { .. }
+            | Commands::AnalyzeSearchablePdf { .. }
+            | Commands::AnalyzeUnlock { .. }
+            | Commands::Top { .. }
+            | Commands::ExportMetadata { .. }
+            | Commands::Bundle { .. }
+            | Commands::Llm { .. }
+            | Commands::Publish { .. }
+            | Commands::Doctor { .. }
     );
     if needs_tor {
         if let Err(e) = config.privacy.check_tor_availability() {
@@ -883,7 +2020,10 @@ pub async fn run() -> anyhow::Result<()> {
         config.privacy.enforce_security_warning().await;
     }
 
-    match cli.command {
+    // Push this run's metrics if a Pushgateway is configured
+    let pusher = MetricsPusher::start(&config.metrics);
+
+    let result = match cli.command {
         Commands::Init => init::cmd_init(&settings).await,
         Commands::Source { command } => match command {
             SourceCommands::List => source::cmd_source_list(&settings).await,
@@ -901,6 +2041,7 @@ pub async fn run() -> anyhow::Result<()> {
             workers,
             limit,
             progress,
+            chunks,
         } => {
             scrape::cmd_download(
                 &settings,
@@ -908,6 +2049,27 @@ pub async fn run() -> anyhow::Result<()> {
                 workers,
                 limit,
                 progress,
+                chunks,
+                &config.privacy,
+            )
+            .await
+        }
+        Commands::Fetch {
+            from_file,
+            source,
+            skip_invalid,
+            queue_only,
+            workers,
+            progress,
+        } => {
+            fetch::cmd_fetch(
+                &settings,
+                &from_file,
+                source.as_deref(),
+                skip_invalid,
+                queue_only,
+                workers,
+                progress,
                 &config.privacy,
             )
             .await
@@ -920,6 +2082,251 @@ pub async fn run() -> anyhow::Result<()> {
                 state::cmd_crawl_clear(&settings, &source_id, confirm).await
             }
         },
+        Commands::Failures { command } => match command {
+            FailureCommands::List { source } => {
+                failures::cmd_failures_list(&settings, source.as_deref()).await
+            }
+            FailureCommands::Retry {
+                class,
+                kind,
+                source,
+            } => {
+                failures::cmd_failures_retry(&settings, &class, kind.as_deref(), source.as_deref())
+                    .await
+            }
+        },
+        Commands::Jobs { command } => match command {
+            JobCommands::List { status, limit } => {
+                jobs::cmd_jobs_list(&settings, status.as_deref(), limit).await
+            }
+            JobCommands::Show { id } => jobs::cmd_jobs_show(&settings, &id).await,
+            JobCommands::Cancel { id } => jobs::cmd_jobs_cancel(&settings, &id).await,
+        },
+        Commands::Agencies { command } => match command {
+            AgencyCommands::List { level } => {
+                agencies::cmd_agencies_list(&settings, level.as_deref()).await
+            }
+            AgencyCommands::Show { id } => agencies::cmd_agencies_show(&settings, &id).await,
+            AgencyCommands::Add {
+                name,
+                id,
+                abbreviation,
+                jurisdiction,
+                level,
+                parent,
+                email,
+                phone,
+                address,
+                portals,
+                website,
+            } => {
+                let args = agencies::NewAgencyArgs {
+                    name,
+                    id,
+                    abbreviation,
+                    jurisdiction,
+                    level,
+                    parent,
+                    email,
+                    phone,
+                    address,
+                    portals,
+                    website,
+                };
+                agencies::cmd_agencies_add(&settings, args).await
+            }
+            AgencyCommands::Import { file, dry_run } => {
+                agencies::cmd_agencies_import(&settings, &file, dry_run).await
+            }
+            AgencyCommands::Link {
+                source_id,
+                agency_id,
+            } => agencies::cmd_agencies_link(&settings, &source_id, &agency_id).await,
+            AgencyCommands::Unlink { source_id } => {
+                agencies::cmd_agencies_unlink(&settings, &source_id).await
+            }
+            AgencyCommands::Delete { id, confirm } => {
+                agencies::cmd_agencies_delete(&settings, &id, confirm).await
+            }
+        },
+        Commands::Requests { command } => match command {
+            RequestCommands::Add {
+                agency,
+                subject,
+                filed,
+                tracking,
+                source,
+                jurisdiction,
+                text,
+                text_file,
+            } => {
+                let args = requests::NewRequestArgs {
+                    agency,
+                    subject,
+                    filed,
+                    tracking,
+                    source,
+                    jurisdiction,
+                    text,
+                    text_file,
+                };
+                requests::cmd_requests_add(&settings, args).await
+            }
+            RequestCommands::List { status, overdue } => {
+                requests::cmd_requests_list(&settings, status.as_deref(), overdue).await
+            }
+            RequestCommands::Show { id } => requests::cmd_requests_show(&settings, &id).await,
+            RequestCommands::Update {
+                id,
+                status,
+                date,
+                agency,
+                subject,
+                tracking,
+                source,
+                jurisdiction,
+                notes,
+            } => {
+                let args = requests::UpdateRequestArgs {
+                    status,
+                    date,
+                    agency,
+                    subject,
+                    tracking,
+                    source,
+                    jurisdiction,
+                    notes,
+                };
+                requests::cmd_requests_update(&settings, &id, args).await
+            }
+            RequestCommands::Link { id, doc_ids } => {
+                requests::cmd_requests_link(&settings, &id, &doc_ids).await
+            }
+            RequestCommands::Unlink { id, doc_ids } => {
+                requests::cmd_requests_unlink(&settings, &id, &doc_ids).await
+            }
+            RequestCommands::Delete { id, confirm } => {
+                requests::cmd_requests_delete(&settings, &id, confirm).await
+            }
+            RequestCommands::Remind { dry_run } => {
+                requests::cmd_requests_remind(&settings, dry_run).await
+            }
+        },
+        Commands::Views { command } => match command {
+            ViewCommands::List => views::cmd_views_list(&settings).await,
+            ViewCommands::Subscribe { view, email } => {
+                views::cmd_views_subscribe(&settings, &view, &email).await
+            }
+            ViewCommands::Unsubscribe { view, email } => {
+                views::cmd_views_unsubscribe(&settings, &view, &email).await
+            }
+            ViewCommands::Alert { dry_run } => views::cmd_views_alert(&settings, dry_run).await,
+        },
+        Commands::ApiKey { command } => match command {
+            ApiKeyCommands::Create {
+                name,
+                rate_limit,
+                admin,
+            } => api_keys::cmd_api_key_create(&settings, &name, rate_limit, admin).await,
+            ApiKeyCommands::List => api_keys::cmd_api_key_list(&settings).await,
+            ApiKeyCommands::Revoke { name } => api_keys::cmd_api_key_revoke(&settings, &name).await,
+        },
+        Commands::Report { command } => match command {
+            ReportCommands::Test { to } => reports::cmd_report_test(&settings, to).await,
+            ReportCommands::CrawlSummary { hours, to, dry_run } => {
+                reports::cmd_report_crawl_summary(&settings, hours, to, dry_run).await
+            }
+            ReportCommands::ScraperAlerts { to, dry_run } => {
+                reports::cmd_report_scraper_alerts(&settings, to, dry_run).await
+            }
+            ReportCommands::Digest { days, to, dry_run } => {
+                reports::cmd_report_digest(&settings, days, to, dry_run).await
+            }
+            ReportCommands::Politeness {
+                source_id,
+                days,
+                format,
+                output,
+            } => {
+                reports::cmd_report_politeness(
+                    &settings,
+                    source_id.as_deref(),
+                    days,
+                    &format,
+                    output.as_deref(),
+                )
+                .await
+            }
+        },
+        Commands::Queue { command } => match command {
+            QueueCommands::Push {
+                kind,
+                source_id,
+                doc,
+                limit,
+                batch,
+            } => {
+                queue::cmd_queue_push(
+                    &settings,
+                    &kind,
+                    source_id.as_deref(),
+                    doc.as_deref(),
+                    limit,
+                    batch,
+                )
+                .await
+            }
+            QueueCommands::Status => queue::cmd_queue_status(&settings).await,
+            QueueCommands::Retry { kind } => queue::cmd_queue_retry(&settings, &kind).await,
+        },
+        Commands::Worker {
+            queues,
+            workers,
+            poll,
+        } => worker::cmd_worker(&settings, &queues, workers, poll, &config.privacy).await,
+        Commands::Wayback { command } => match command {
+            WaybackCommands::List { source, state } => {
+                wayback::cmd_wayback_list(&settings, source.as_deref(), &state).await
+            }
+            WaybackCommands::Ingest { ids, all, source } => {
+                wayback::cmd_wayback_ingest(&settings, &ids, all, source.as_deref()).await
+            }
+            WaybackCommands::Dismiss { ids } => wayback::cmd_wayback_dismiss(&settings, &ids).await,
+        },
+        Commands::Scraper { command } => match command {
+            ScraperCommands::Record {
+                source_id,
+                fixtures,
+            } => {
+                scraper::cmd_scraper_record(&settings, &config, &source_id, fixtures.as_deref())
+                    .await
+            }
+            ScraperCommands::Test {
+                source_id,
+                fixtures,
+            } => {
+                scraper::cmd_scraper_test(&settings, &config, &source_id, fixtures.as_deref()).await
+            }
+        },
+        Commands::Urls { command } => match command {
+            UrlCommands::List {
+                source_id,
+                status,
+                limit,
+            } => urls::cmd_urls_list(&settings, &source_id, &status, limit).await,
+            UrlCommands::Retry { source_id, urls } => {
+                urls::cmd_urls_retry(&settings, &source_id, &urls).await
+            }
+            UrlCommands::Cancel { source_id, urls } => {
+                urls::cmd_urls_cancel(&settings, &source_id, &urls).await
+            }
+            UrlCommands::RetryFailed { source_id } => {
+                urls::cmd_urls_retry_failed(&settings, source_id.as_deref()).await
+            }
+            UrlCommands::Add { source_id, urls } => {
+                urls::cmd_urls_add(&settings, &source_id, &urls).await
+            }
+        },
         Commands::Config { command } => match command {
             ConfigCommands::Transfer { file } => {
                 config_cmd::cmd_config_transfer(&settings, file.as_deref()).await
@@ -930,6 +2337,13 @@ pub async fn run() -> anyhow::Result<()> {
             ConfigCommands::Set { setting, value } => {
                 config_cmd::cmd_config_set(&settings, &setting, &value).await
             }
+            ConfigCommands::History => config_cmd::cmd_config_history(&settings, &config).await,
+            ConfigCommands::Diff { id, other } => {
+                config_cmd::cmd_config_diff(&settings, &config, &id, other.as_deref()).await
+            }
+            ConfigCommands::Rollback { id, confirm } => {
+                config_cmd::cmd_config_rollback(&settings, &config, &id, confirm).await
+            }
         },
         Commands::Db { command } => match command {
             DbCommands::Migrate { check, force } => db::cmd_migrate(&settings, check, force).await,
@@ -968,6 +2382,17 @@ pub async fn run() -> anyhow::Result<()> {
                 same_source,
                 batch_size,
             } => db::cmd_db_dedup(&settings, dry_run, &keep, same_source, batch_size).await,
+            DbCommands::MigrateIds { dry_run } => db::cmd_db_migrate_ids(&settings, dry_run).await,
+            DbCommands::CompressText {
+                dry_run,
+                batch_size,
+            } => db::cmd_db_compress_text(&settings, dry_run, batch_size).await,
+            DbCommands::CheckSearch { repair } => db::cmd_db_check_search(&settings, repair).await,
+            DbCommands::PruneArtifacts {
+                older_than_days,
+                dry_run,
+            } => db::cmd_db_prune_artifacts(&settings, older_than_days, dry_run).await,
+            DbCommands::Split { shard } => db::cmd_db_split(&settings, shard.as_deref()).await,
             #[cfg(feature = "gis")]
             DbCommands::LoadRegions { file } => {
                 regions::cmd_load_regions(&settings, file.as_deref()).await
@@ -983,6 +2408,7 @@ pub async fn run() -> anyhow::Result<()> {
             interval,
             reload,
             rate_limit_backend,
+            dry_run,
         } => {
             scrape::cmd_scrape(
                 &settings,
@@ -995,6 +2421,7 @@ pub async fn run() -> anyhow::Result<()> {
                 interval,
                 reload,
                 rate_limit_backend,
+                dry_run,
                 &config.privacy,
             )
             .await
@@ -1006,6 +2433,7 @@ pub async fn run() -> anyhow::Result<()> {
             interval,
             json,
         } => scrape::cmd_status(&settings, url, source_id, live, interval, json).await,
+        Commands::Top { interval } => top::cmd_top(&settings, interval).await,
         Commands::Analyze {
             source_id,
             doc_id,
@@ -1045,25 +2473,84 @@ pub async fn run() -> anyhow::Result<()> {
             .await
         }
         Commands::AnalyzeCheck => analyze::cmd_analyze_check().await,
+        Commands::Doctor { install, confirm } => {
+            doctor::cmd_doctor(&config, install, confirm).await
+        }
         Commands::AnalyzeCompare {
             file,
             pages,
             backends,
             deepseek_path,
         } => analyze::cmd_analyze_compare(&file, pages.as_deref(), &backends, deepseek_path).await,
+        Commands::AnalyzeReprocess {
+            source_id,
+            filter,
+            dry_run,
+        } => {
+            analyze::cmd_analyze_reprocess(&settings, source_id.as_deref(), &filter, dry_run).await
+        }
+        Commands::AnalyzeTables { source_id, limit } => {
+            analyze::cmd_analyze_tables(&settings, source_id.as_deref(), limit).await
+        }
+        Commands::AnalyzeRedactions { source_id, limit } => {
+            analyze::cmd_analyze_redactions(&settings, source_id.as_deref(), limit).await
+        }
+        Commands::AnalyzePii {
+            source_id,
+            limit,
+            threshold,
+        } => analyze::cmd_analyze_pii(&settings, source_id.as_deref(), limit, threshold).await,
+        Commands::AnalyzePdfMetadata { source_id, limit } => {
+            analyze::cmd_analyze_pdf_metadata(&settings, source_id.as_deref(), limit).await
+        }
+        Commands::AnalyzeSearchablePdf { source_id, limit } => {
+            analyze::cmd_analyze_searchable_pdf(&settings, source_id.as_deref(), limit).await
+        }
+        Commands::AnalyzeUnlock {
+            source_id,
+            analysis_type,
+            stale,
+        } => {
+            analyze::cmd_analyze_unlock(
+                &settings,
+                source_id.as_deref(),
+                analysis_type.as_deref(),
+                stale,
+            )
+            .await
+        }
+        Commands::Publish {
+            static_dir,
+            source_id,
+            title,
+            no_files,
+            include_flagged,
+        } => {
+            let options = foia_server::PublishOptions {
+                source_id,
+                title,
+                include_files: !no_files,
+                include_flagged,
+            };
+            serve::cmd_publish_static(&settings, &static_dir, &options).await
+        }
         Commands::Serve {
             bind,
             no_migrate,
             no_hidden_service,
             use_arti,
+            public,
         } => {
             serve::cmd_serve(
-                &settings,
+                &base_settings,
                 &config,
+                &base_dir,
+                &workspace,
                 &bind,
                 no_migrate,
                 no_hidden_service,
                 use_arti,
+                public,
             )
             .await
         }
@@ -1130,9 +2617,21 @@ pub async fn run() -> anyhow::Result<()> {
         Commands::ExtractEntities { source_id, limit } => {
             annotate::cmd_extract_entities(&settings, source_id.as_deref(), limit).await
         }
+        Commands::ClassifyRecords { source_id, limit } => {
+            annotate::cmd_classify_records(&settings, source_id.as_deref(), limit).await
+        }
+        Commands::ExtractMetadata { source_id, limit } => {
+            annotate::cmd_extract_metadata(&settings, source_id.as_deref(), limit).await
+        }
         Commands::BackfillEntities { source_id, limit } => {
             entities::cmd_backfill_entities(&settings, source_id.as_deref(), limit).await
         }
+        Commands::Geocode { source_id } => {
+            entities::cmd_geocode(&settings, source_id.as_deref()).await
+        }
+        Commands::LinkDocuments { source_id, limit } => {
+            entities::cmd_link_documents(&settings, source_id.as_deref(), limit).await
+        }
         Commands::SearchEntities {
             query,
             entity_type,
@@ -1151,6 +2650,14 @@ pub async fn run() -> anyhow::Result<()> {
             .await
         }
         Commands::LlmModels => llm::cmd_llm_models(&settings).await,
+        Commands::Llm { command } => match command {
+            LlmCommands::Usage { months } => llm::cmd_llm_usage(&settings, months).await,
+        },
+        Commands::Ask {
+            question,
+            source,
+            pages,
+        } => llm::cmd_ask(&settings, &question, source.as_deref(), pages).await,
         Commands::Archive {
             source_id,
             limit,
@@ -1174,7 +2681,124 @@ pub async fn run() -> anyhow::Result<()> {
             .await
         }
         Commands::Info { doc_id } => documents::cmd_info(&settings, &doc_id).await,
+        Commands::Tags { command } => match command {
+            TagCommands::List => tags::cmd_tags_list(&settings).await,
+            TagCommands::Rename { old, new, confirm } => {
+                tags::cmd_tags_merge(&settings, &[old], &new, confirm).await
+            }
+            TagCommands::Merge {
+                from,
+                into,
+                confirm,
+            } => tags::cmd_tags_merge(&settings, &from, &into, confirm).await,
+            TagCommands::Delete { tag, confirm } => {
+                tags::cmd_tags_delete(&settings, &tag, confirm).await
+            }
+            TagCommands::Add {
+                tag,
+                filter,
+                confirm,
+            } => tags::cmd_tags_apply(&settings, &tag, &filter.into(), false, confirm).await,
+            TagCommands::Remove {
+                tag,
+                filter,
+                confirm,
+            } => tags::cmd_tags_apply(&settings, &tag, &filter.into(), true, confirm).await,
+        },
+        Commands::Curate { command } => match command {
+            CurateCommands::Merge {
+                target,
+                sources,
+                confirm,
+            } => curate::cmd_curate_merge(&settings, &target, &sources, confirm).await,
+            CurateCommands::Split {
+                doc_id,
+                at,
+                confirm,
+            } => curate::cmd_curate_split(&settings, &doc_id, &at, confirm).await,
+            CurateCommands::Retitle {
+                source,
+                llm,
+                dry_run,
+                confirm,
+            } => {
+                curate::cmd_curate_retitle(&settings, source.as_deref(), llm, dry_run, confirm)
+                    .await
+            }
+            CurateCommands::Redact {
+                doc_id,
+                regions,
+                pii,
+                terms,
+                allow_missed,
+                remove,
+            } => {
+                curate::cmd_curate_redact(
+                    &settings,
+                    &doc_id,
+                    &regions,
+                    pii,
+                    &terms,
+                    allow_missed,
+                    remove,
+                )
+                .await
+            }
+            CurateCommands::Log { doc_id, limit } => {
+                curate::cmd_curate_log(&settings, doc_id.as_deref(), limit).await
+            }
+        },
+        Commands::Workflow { command } => match command {
+            WorkflowCommands::Status { source } => {
+                workflow::cmd_workflow_status(&settings, source.as_deref()).await
+            }
+            WorkflowCommands::Move {
+                to,
+                ids,
+                from,
+                source,
+                confirm,
+            } => {
+                workflow::cmd_workflow_move(
+                    &settings,
+                    &to,
+                    from.as_deref(),
+                    source.as_deref(),
+                    &ids,
+                    confirm,
+                )
+                .await
+            }
+        },
         Commands::Read { doc_id, text } => documents::cmd_read(&settings, &doc_id, text).await,
+        Commands::Provenance { doc_id, output } => {
+            documents::cmd_provenance(&settings, &doc_id, output.as_deref()).await
+        }
+        Commands::ExportMetadata {
+            format,
+            output,
+            source_id,
+        } => {
+            documents::cmd_export_metadata(&settings, &format, &output, source_id.as_deref()).await
+        }
+        Commands::Bundle { command } => match command {
+            BundleCommands::Export {
+                output,
+                source_ids,
+                all,
+            } => bundle::cmd_bundle_export(&settings, &output, source_ids, all).await,
+            BundleCommands::Import { path } => bundle::cmd_bundle_import(&settings, &path).await,
+            BundleCommands::Inspect { path } => bundle::cmd_bundle_inspect(&path),
+            BundleCommands::Torrent {
+                source_id,
+                output,
+                trackers,
+                web_seeds,
+            } => {
+                bundle::cmd_bundle_torrent(&settings, &source_id, &output, trackers, web_seeds)
+                    .await
+            }
+        },
         Commands::Search {
             query,
             source,
@@ -1347,5 +2971,13 @@ pub async fn run() -> anyhow::Result<()> {
             )
             .await
         }
+    };
+
+    if let Some(pusher) = pusher {
+        if let Ok(repos) = settings.repositories() {
+            metrics::refresh_queue_depths(&repos.crawl).await;
+        }
+        pusher.finish().await;
     }
+    result
 }
@@ -0,0 +1,174 @@
+//! Worker queue commands: publish tasks and inspect the broker.
+
+use console::style;
+
+use foia::config::{Config, Settings};
+use foia::work_queue::broker::{self, Broker, Task, TaskKind};
+use foia_annotate::services::{AnnotationManager, LlmAnnotator};
+
+use super::scrape::get_pending_count;
+
+/// Documents fetched per query when publishing document tasks.
+const BATCH_SIZE: usize = 500;
+
+/// Retry interval for failed OCR, as in `foia analyze`.
+const OCR_RETRY_HOURS: u32 = 12;
+
+pub(crate) fn parse_kind(kind: &str) -> anyhow::Result<TaskKind> {
+    TaskKind::from_str(kind).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unknown queue '{}' (expected download, ocr or summarize)",
+            kind
+        )
+    })
+}
+
+/// Connect to the broker configured by `broker_url`.
+pub(crate) async fn connect(settings: &Settings) -> anyhow::Result<Box<dyn Broker>> {
+    let repos = settings.repositories()?;
+    Ok(broker::connect(settings.broker_url.as_deref(), repos.broker).await?)
+}
+
+/// Publish tasks for workers.
+///
+/// Download tasks split a source's pending URLs into batches of `batch`;
+/// OCR and summarize tasks are one per document still needing it, or just
+/// `doc_id`.
+pub async fn cmd_queue_push(
+    settings: &Settings,
+    kind: &str,
+    source_id: Option<&str>,
+    doc_id: Option<&str>,
+    limit: usize,
+    batch: usize,
+) -> anyhow::Result<()> {
+    let kind = parse_kind(kind)?;
+    let broker = connect(settings).await?;
+    let repos = settings.repositories()?;
+    let limit = if limit > 0 { limit } else { usize::MAX };
+
+    let mut tasks = Vec::new();
+    match (kind, doc_id) {
+        (TaskKind::Download, Some(_)) => {
+            anyhow::bail!("Download tasks are per source; use the SOURCE argument")
+        }
+        (TaskKind::Download, None) => {
+            let pending = get_pending_count(&repos.crawl, source_id).await? as usize;
+            let batch = batch.max(1);
+            let mut remaining = pending.min(limit);
+            while remaining > 0 {
+                tasks.push(Task::download(source_id, batch.min(remaining)));
+                remaining = remaining.saturating_sub(batch);
+            }
+        }
+        (TaskKind::Ocr, Some(id)) => tasks.push(Task::ocr(id)),
+        (TaskKind::Summarize, Some(id)) => tasks.push(Task::summarize(id)),
+        (TaskKind::Ocr, None) => {
+            let mut cursor: Option<String> = None;
+            while tasks.len() < limit {
+                let docs = repos
+                    .documents
+                    .get_needing_analysis(
+                        "ocr",
+                        BATCH_SIZE.min(limit - tasks.len()),
+                        source_id,
+                        None,
+                        cursor.as_deref(),
+                        OCR_RETRY_HOURS,
+                    )
+                    .await?;
+                let Some(last) = docs.last() else {
+                    break;
+                };
+                cursor = Some(last.id.clone());
+                tasks.extend(docs.iter().map(|doc| Task::ocr(&doc.id)));
+            }
+        }
+        (TaskKind::Summarize, None) => {
+            let config = Config::load().await;
+            let annotator = LlmAnnotator::new(config.llm.clone());
+            let manager = AnnotationManager::new(repos.documents.clone());
+            let docs = manager
+                .pending(&annotator, source_id, limit.min(i64::MAX as usize))
+                .await?;
+            tasks.extend(docs.iter().map(|doc| Task::summarize(&doc.id)));
+        }
+    }
+
+    if tasks.is_empty() {
+        println!(
+            "{} Nothing to queue for {}",
+            style("!").yellow(),
+            kind.as_str()
+        );
+        return Ok(());
+    }
+
+    let (mut published, mut duplicates) = (0usize, 0usize);
+    for task in &tasks {
+        if broker.publish(task).await? {
+            published += 1;
+        } else {
+            duplicates += 1;
+        }
+    }
+    println!(
+        "{} Queued {} {} tasks on {}",
+        style("✓").green(),
+        published,
+        kind.as_str(),
+        broker.name()
+    );
+    if duplicates > 0 {
+        println!(
+            "  {} {} already waiting in the queue",
+            style("→").dim(),
+            duplicates
+        );
+    }
+    Ok(())
+}
+
+/// Show how many tasks are waiting, in flight and dead per queue.
+pub async fn cmd_queue_status(settings: &Settings) -> anyhow::Result<()> {
+    let broker = connect(settings).await?;
+    println!("{} Broker: {}", style("→").cyan(), broker.name());
+    println!(
+        "{:<16}  {:>8}  {:>10}  {:>8}",
+        "Queue", "Ready", "In flight", "Dead"
+    );
+    for kind in TaskKind::ALL {
+        let depth = broker.depth(kind).await?;
+        let in_flight = depth
+            .in_flight
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let dead = if depth.dead > 0 {
+            style(depth.dead.to_string()).red()
+        } else {
+            style(depth.dead.to_string())
+        };
+        println!(
+            "{:<16}  {:>8}  {:>10}  {:>8}",
+            kind.queue(),
+            depth.ready,
+            in_flight,
+            dead
+        );
+    }
+    Ok(())
+}
+
+/// Requeue tasks that used up their attempts.
+pub async fn cmd_queue_retry(settings: &Settings, kind: &str) -> anyhow::Result<()> {
+    let kind = parse_kind(kind)?;
+    let broker = connect(settings).await?;
+    let moved = broker.retry_dead(kind).await?;
+    println!(
+        "{} Requeued {} dead {} tasks",
+        style("✓").green(),
+        moved,
+        kind.as_str()
+    );
+    Ok(())
+}
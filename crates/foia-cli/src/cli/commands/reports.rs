@@ -0,0 +1,180 @@
+//! Emailed report commands, meant to run from cron, and crawl politeness
+//! reports.
+
+use std::path::Path;
+
+use chrono::{Duration, Utc};
+use console::style;
+
+use foia::config::{Config, EmailConfig, Settings};
+use foia::privacy::PrivacyConfig;
+use foia::services::email::{Email, Mailer};
+use foia::services::{politeness_report, reports};
+
+use super::text_pdf::text_to_pdf;
+
+/// Print `email`, or send it to `to` (default: `email.to` in the config).
+async fn deliver(
+    config: &EmailConfig,
+    privacy: &PrivacyConfig,
+    to: Vec<String>,
+    email: Email,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let to = if to.is_empty() { config.to.clone() } else { to };
+    if dry_run {
+        println!("To: {}", to.join(", "));
+        println!("Subject: {}\n", email.subject);
+        println!("{}", email.body);
+        println!("{} Dry run, nothing sent", style("!").yellow());
+        return Ok(());
+    }
+    if to.is_empty() {
+        anyhow::bail!("No recipients; set email.to in the config or pass --to");
+    }
+
+    let mailer = Mailer::from_config(config, privacy)?;
+    mailer.send(&to, &email).await?;
+    println!(
+        "{} Sent \"{}\" to {}",
+        style("✓").green(),
+        email.subject,
+        to.join(", ")
+    );
+    Ok(())
+}
+
+/// Send a test email to check the email settings.
+pub async fn cmd_report_test(settings: &Settings, to: Vec<String>) -> anyhow::Result<()> {
+    let config = Config::load().await.email;
+    let transport = match &config.smtp_host {
+        Some(host) => format!("SMTP server {}:{}", host, config.smtp_port()),
+        None => config.sendmail().to_string(),
+    };
+    let email = Email::new(
+        "foia test email",
+        format!(
+            "Email from foia is working.\n\nSent from {} through {}.\n",
+            config.from(),
+            transport
+        ),
+    );
+    deliver(&config, &settings.privacy, to, email, false).await
+}
+
+/// Email new documents and crawl errors per source over the last `hours`.
+pub async fn cmd_report_crawl_summary(
+    settings: &Settings,
+    hours: u32,
+    to: Vec<String>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let config = Config::load().await.email;
+    let repos = settings.repositories()?;
+    let now = Utc::now();
+    let since = now - Duration::hours(hours.max(1) as i64);
+
+    let activity = reports::crawl_activity(&repos, since).await?;
+    let email = reports::crawl_summary_email(&activity, since, now);
+    deliver(&config, &settings.privacy, to, email, dry_run).await
+}
+
+/// Email an alert about sources whose requests keep failing. Sends nothing
+/// when every source is healthy.
+pub async fn cmd_report_scraper_alerts(
+    settings: &Settings,
+    to: Vec<String>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let config = Config::load().await.email;
+    let repos = settings.repositories()?;
+
+    let broken = reports::broken_sources(&repos, Utc::now()).await?;
+    match reports::scraper_alert_email(&broken, &config) {
+        Some(email) => deliver(&config, &settings.privacy, to, email, dry_run).await,
+        None => {
+            println!("{} All scrapers are healthy", style("✓").green());
+            Ok(())
+        }
+    }
+}
+
+/// Email documents added in the last `days` that match saved views. Sends
+/// nothing when there are none.
+pub async fn cmd_report_digest(
+    settings: &Settings,
+    days: u32,
+    to: Vec<String>,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let config = Config::load().await.email;
+    let repos = settings.repositories()?;
+    let now = Utc::now();
+    let since = now - Duration::days(days.max(1) as i64);
+
+    let matches = reports::saved_view_matches(&repos, since).await?;
+    match reports::digest_email(&matches, since, now, &config) {
+        Some(email) => deliver(&config, &settings.privacy, to, email, dry_run).await,
+        None => {
+            println!(
+                "{} No new documents match saved views in the last {} days",
+                style("✓").green(),
+                days
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Write a politeness report covering the last `days`, as Markdown or PDF.
+pub async fn cmd_report_politeness(
+    settings: &Settings,
+    source_id: Option<&str>,
+    days: u32,
+    format: &str,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let format = format.to_ascii_lowercase();
+    if !matches!(format.as_str(), "markdown" | "md" | "pdf") {
+        anyhow::bail!("Unknown format '{}'; use markdown or pdf", format);
+    }
+    if format == "pdf" && output.is_none() {
+        anyhow::bail!("PDF reports need --output");
+    }
+
+    let config = Config::load().await;
+    let repos = settings.repositories()?;
+    let until = Utc::now();
+    let since = until - Duration::days(days.max(1) as i64);
+    let report = politeness_report::politeness_report(
+        &repos,
+        &config,
+        std::time::Duration::from_millis(settings.request_delay_ms),
+        source_id,
+        since,
+        until,
+    )
+    .await?;
+    if let (Some(id), true) = (source_id, report.sources.is_empty()) {
+        anyhow::bail!("Unknown source: {}", id);
+    }
+
+    let content = if format == "pdf" {
+        text_to_pdf(&report.to_markdown())
+    } else {
+        report.to_markdown().into_bytes()
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            println!(
+                "{} Wrote politeness report for {} sources to {}",
+                style("✓").green(),
+                report.sources.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", String::from_utf8_lossy(&content)),
+    }
+    Ok(())
+}
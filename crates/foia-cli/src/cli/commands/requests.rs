@@ -0,0 +1,451 @@
+//! FOIA request tracking commands.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{NaiveDate, Utc};
+use console::style;
+
+use foia::config::{Config, RequestsConfig, Settings};
+use foia::models::{DeadlineRule, FoiaRequest, FoiaRequestStatus};
+use foia::repository::DieselFoiaRequestRepository;
+use foia::services::request_reminders;
+
+use super::helpers::truncate;
+
+/// Fields for a new request.
+pub struct NewRequestArgs {
+    pub agency: String,
+    pub subject: String,
+    pub filed: Option<String>,
+    pub tracking: Option<String>,
+    pub source: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub text: Option<String>,
+    pub text_file: Option<String>,
+}
+
+/// Changes to an existing request; unset fields are left alone.
+pub struct UpdateRequestArgs {
+    pub status: Option<String>,
+    pub date: Option<String>,
+    pub agency: Option<String>,
+    pub subject: Option<String>,
+    pub tracking: Option<String>,
+    pub source: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub notes: Option<String>,
+}
+
+fn parse_status(s: &str) -> anyhow::Result<FoiaRequestStatus> {
+    FoiaRequestStatus::from_str(s).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Unknown status '{}' (expected filed, acknowledged, fulfilled or appealed)",
+            s
+        )
+    })
+}
+
+fn parse_date(s: Option<&str>) -> anyhow::Result<NaiveDate> {
+    match s {
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| anyhow::anyhow!("Invalid date '{}' (expected YYYY-MM-DD)", s)),
+        None => Ok(Utc::now().date_naive()),
+    }
+}
+
+fn status_style(status: FoiaRequestStatus) -> console::StyledObject<&'static str> {
+    let s = style(status.as_str());
+    match status {
+        FoiaRequestStatus::Filed => s.dim(),
+        FoiaRequestStatus::Acknowledged => s.cyan(),
+        FoiaRequestStatus::Fulfilled => s.green(),
+        FoiaRequestStatus::Appealed => s.yellow(),
+    }
+}
+
+fn check_jurisdiction(config: &RequestsConfig, name: &str) -> anyhow::Result<()> {
+    if !config.has_jurisdiction(name) {
+        anyhow::bail!(
+            "Unknown jurisdiction '{}'; add it under requests.jurisdictions in the config",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Due date with the days left, red when overdue.
+fn due_str(request: &FoiaRequest, rule: &DeadlineRule, today: NaiveDate) -> String {
+    match (request.due_on(rule), request.days_until_due(rule, today)) {
+        (Some(due), Some(days)) if days < 0 => style(format!("{} ({}d overdue)", due, -days))
+            .red()
+            .to_string(),
+        (Some(due), Some(days)) => format!("{} ({}d left)", due, days),
+        _ => "-".to_string(),
+    }
+}
+
+/// Find a request by ID or unique ID prefix.
+async fn resolve(repo: &DieselFoiaRequestRepository, id: &str) -> anyhow::Result<FoiaRequest> {
+    if let Some(request) = repo.get(id).await? {
+        return Ok(request);
+    }
+    let mut matches: Vec<FoiaRequest> = repo
+        .list(None)
+        .await?
+        .into_iter()
+        .filter(|r| r.id.starts_with(id))
+        .collect();
+    match matches.len() {
+        0 => anyhow::bail!("Request '{}' not found", id),
+        1 => Ok(matches.remove(0)),
+        n => anyhow::bail!("'{}' matches {} requests; give more of the ID", id, n),
+    }
+}
+
+/// Record a newly filed request.
+pub async fn cmd_requests_add(settings: &Settings, args: NewRequestArgs) -> anyhow::Result<()> {
+    let config = Config::load().await.requests;
+    if let Some(jurisdiction) = &args.jurisdiction {
+        check_jurisdiction(&config, jurisdiction)?;
+    }
+    let filed_on = parse_date(args.filed.as_deref())?;
+    let mut request = FoiaRequest::new(args.agency, args.subject, filed_on);
+    request.tracking_number = args.tracking;
+    request.source_id = args.source;
+    request.jurisdiction = args.jurisdiction;
+    request.request_text = match (args.text, args.text_file) {
+        (_, Some(path)) => std::fs::read_to_string(Path::new(&path))
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?,
+        (Some(text), None) => text,
+        (None, None) => String::new(),
+    };
+
+    let repos = settings.repositories()?;
+    repos.foia_requests.create(&request).await?;
+    println!(
+        "{} Recorded request {} to {}, due {}",
+        style("✓").green(),
+        style(&request.id).bold(),
+        request.agency,
+        due_str(
+            &request,
+            &config.rule(request.jurisdiction.as_deref()),
+            Utc::now().date_naive()
+        )
+    );
+    Ok(())
+}
+
+/// List tracked requests.
+pub async fn cmd_requests_list(
+    settings: &Settings,
+    status: Option<&str>,
+    overdue: bool,
+) -> anyhow::Result<()> {
+    let status = status.map(parse_status).transpose()?;
+    let config = Config::load().await.requests;
+    let repos = settings.repositories()?;
+    let today = Utc::now().date_naive();
+    let requests: Vec<(FoiaRequest, DeadlineRule)> = repos
+        .foia_requests
+        .list(status)
+        .await?
+        .into_iter()
+        .map(|r| {
+            let rule = config.rule(r.jurisdiction.as_deref());
+            (r, rule)
+        })
+        .filter(|(r, rule)| !overdue || r.is_overdue(rule, today))
+        .collect();
+
+    if requests.is_empty() {
+        println!("{} No requests", style("!").yellow());
+        return Ok(());
+    }
+    let counts = repos.foia_requests.document_counts().await?;
+
+    println!(
+        "{:<8}  {:<16}  {:<30}  {:<12}  {:<10}  {:>4}  Due",
+        "ID", "Agency", "Subject", "Filed", "Status", "Docs"
+    );
+    for (r, rule) in &requests {
+        println!(
+            "{:<8}  {:<16}  {:<30}  {:<12}  {:<10}  {:>4}  {}",
+            &r.id[..8],
+            truncate(&r.agency, 16),
+            truncate(&r.subject, 30),
+            r.filed_on,
+            status_style(r.status),
+            counts.get(&r.id).copied().unwrap_or(0),
+            due_str(r, rule, today)
+        );
+    }
+    Ok(())
+}
+
+/// Show one request in full, with its linked documents.
+pub async fn cmd_requests_show(settings: &Settings, id: &str) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let request = resolve(&repos.foia_requests, id).await?;
+    let config = Config::load().await.requests;
+    let rule = config.rule(request.jurisdiction.as_deref());
+    let today = Utc::now().date_naive();
+
+    println!("{}", style(format!("Request {}", request.id)).bold());
+    println!("  Agency:       {}", request.agency);
+    println!("  Subject:      {}", request.subject);
+    if let Some(tracking) = &request.tracking_number {
+        println!("  Tracking #:   {}", tracking);
+    }
+    if let Some(source) = &request.source_id {
+        println!("  Source:       {}", source);
+    }
+    println!(
+        "  Jurisdiction: {}",
+        request
+            .jurisdiction
+            .as_deref()
+            .unwrap_or_else(|| config.default_jurisdiction())
+    );
+    println!("  Status:       {}", status_style(request.status));
+    println!("  Filed:        {}", request.filed_on);
+    if let Some(date) = request.acknowledged_on {
+        println!("  Acknowledged: {}", date);
+    }
+    if let Some(date) = request.appealed_on {
+        println!("  Appealed:     {}", date);
+    }
+    if let Some(date) = request.fulfilled_on {
+        println!("  Fulfilled:    {}", date);
+    }
+    println!("  Due:          {}", due_str(&request, &rule, today));
+    if let Some(date) = request.reminded_on {
+        println!("  Reminded:     {}", date);
+    }
+    if let Some(notes) = &request.notes {
+        println!("  Notes:        {}", notes);
+    }
+    if !request.request_text.is_empty() {
+        println!("\n{}", request.request_text);
+    }
+
+    let doc_ids = repos.foia_requests.document_ids(&request.id).await?;
+    println!(
+        "\n{} ({})",
+        style("Documents received").bold(),
+        doc_ids.len()
+    );
+    for doc_id in &doc_ids {
+        match repos.documents.get(doc_id).await? {
+            Some(doc) => println!("  {}  {}", doc_id, doc.title),
+            None => println!("  {}  {}", doc_id, style("(missing)").dim()),
+        }
+    }
+    Ok(())
+}
+
+/// Change a request's status or details.
+pub async fn cmd_requests_update(
+    settings: &Settings,
+    id: &str,
+    args: UpdateRequestArgs,
+) -> anyhow::Result<()> {
+    let config = Config::load().await.requests;
+    let repos = settings.repositories()?;
+    let mut request = resolve(&repos.foia_requests, id).await?;
+
+    if let Some(status) = args.status.as_deref() {
+        let status = parse_status(status)?;
+        request.set_status(status, parse_date(args.date.as_deref())?);
+    } else if args.date.is_some() {
+        anyhow::bail!("--date needs --status");
+    }
+    if let Some(agency) = args.agency {
+        request.agency = agency;
+    }
+    if let Some(subject) = args.subject {
+        request.subject = subject;
+    }
+    if let Some(tracking) = args.tracking {
+        request.tracking_number = Some(tracking).filter(|t| !t.is_empty());
+    }
+    if let Some(source) = args.source {
+        request.source_id = Some(source).filter(|s| !s.is_empty());
+    }
+    if let Some(jurisdiction) = args.jurisdiction {
+        if !jurisdiction.is_empty() {
+            check_jurisdiction(&config, &jurisdiction)?;
+        }
+        request.jurisdiction = Some(jurisdiction).filter(|j| !j.is_empty());
+    }
+    if let Some(notes) = args.notes {
+        request.notes = Some(notes).filter(|n| !n.is_empty());
+    }
+
+    repos.foia_requests.update(&request).await?;
+    println!(
+        "{} Request {} is {}, due {}",
+        style("✓").green(),
+        &request.id[..8],
+        status_style(request.status),
+        due_str(
+            &request,
+            &config.rule(request.jurisdiction.as_deref()),
+            Utc::now().date_naive()
+        )
+    );
+    Ok(())
+}
+
+/// Link received documents to a request.
+pub async fn cmd_requests_link(
+    settings: &Settings,
+    id: &str,
+    doc_ids: &[String],
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let request = resolve(&repos.foia_requests, id).await?;
+
+    let mut linked = 0;
+    for doc_id in doc_ids {
+        if repos.documents.get(doc_id).await?.is_none() {
+            println!("{} Document '{}' not found", style("!").yellow(), doc_id);
+            continue;
+        }
+        if repos
+            .foia_requests
+            .link_document(&request.id, doc_id)
+            .await?
+        {
+            linked += 1;
+        }
+    }
+    println!(
+        "{} Linked {} documents to {}",
+        style("✓").green(),
+        linked,
+        &request.id[..8]
+    );
+    Ok(())
+}
+
+/// Unlink documents from a request.
+pub async fn cmd_requests_unlink(
+    settings: &Settings,
+    id: &str,
+    doc_ids: &[String],
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let request = resolve(&repos.foia_requests, id).await?;
+
+    let mut unlinked = 0;
+    for doc_id in doc_ids {
+        if repos
+            .foia_requests
+            .unlink_document(&request.id, doc_id)
+            .await?
+        {
+            unlinked += 1;
+        }
+    }
+    println!(
+        "{} Unlinked {} documents from {}",
+        style("✓").green(),
+        unlinked,
+        &request.id[..8]
+    );
+    Ok(())
+}
+
+/// Delete a request. Its documents are kept.
+pub async fn cmd_requests_delete(
+    settings: &Settings,
+    id: &str,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let request = resolve(&repos.foia_requests, id).await?;
+
+    if !confirm {
+        println!(
+            "Delete request {} to {} ({})? Linked documents are kept.",
+            &request.id[..8],
+            request.agency,
+            request.subject
+        );
+        print!("\nProceed? [y/N] ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("{} Cancelled", style("!").yellow());
+            return Ok(());
+        }
+    }
+
+    repos.foia_requests.delete(&request.id).await?;
+    println!(
+        "{} Deleted request {}",
+        style("✓").green(),
+        &request.id[..8]
+    );
+    Ok(())
+}
+
+/// Send reminders for requests past their deadline. Meant to run from cron.
+pub async fn cmd_requests_remind(settings: &Settings, dry_run: bool) -> anyhow::Result<()> {
+    let Config {
+        requests: config,
+        email,
+        ..
+    } = Config::load().await;
+    if !dry_run && !config.reminders.is_enabled() {
+        anyhow::bail!("No reminder channel configured; set requests.reminders.webhook or .email");
+    }
+    let repos = settings.repositories()?;
+    let today = Utc::now().date_naive();
+    let requests = repos.foia_requests.list(None).await?;
+    let overdue = request_reminders::reminders_due(requests, &config, today);
+
+    if overdue.is_empty() {
+        println!("{} No reminders due", style("✓").green());
+        return Ok(());
+    }
+    for item in &overdue {
+        println!(
+            "  {:<8}  {:<16}  {:<30}  due {} ({}d overdue)",
+            &item.request.id[..8],
+            truncate(&item.request.agency, 16),
+            truncate(&item.request.subject, 30),
+            item.due_on,
+            item.days_overdue
+        );
+    }
+    if dry_run {
+        println!(
+            "{} {} reminders due (dry run, nothing sent)",
+            style("!").yellow(),
+            overdue.len()
+        );
+        return Ok(());
+    }
+
+    if let Err(errors) =
+        request_reminders::send_reminders(&config.reminders, &email, &settings.privacy, &overdue)
+            .await
+    {
+        for e in &errors {
+            eprintln!("{} {}", style("✗").red(), e);
+        }
+        anyhow::bail!("Failed to send reminders");
+    }
+    let ids: Vec<String> = overdue.into_iter().map(|o| o.request.id).collect();
+    repos.foia_requests.mark_reminded(&ids, today).await?;
+    println!(
+        "{} Sent reminders for {} overdue requests",
+        style("✓").green(),
+        ids.len()
+    );
+    Ok(())
+}
@@ -1,13 +1,18 @@
 //! Download pending documents command.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use console::style;
 
-use foia::config::{Config, Settings};
+use foia::config::{Config, DownloadPipelineConfig, Politeness, Settings};
+use foia::models::{agency_tag, Source};
 use foia::privacy::PrivacyConfig;
-use foia::repository::DieselCrawlRepository;
+use foia::repository::{DieselCrawlRepository, Repositories};
+use foia_scrape::services::download::{DownloadConfig, DEFAULT_LARGE_FILE_THRESHOLD};
+
+use crate::cli::commands::queue::connect;
 
 /// Download pending documents from the queue.
 pub async fn cmd_download(
@@ -16,15 +21,76 @@ pub async fn cmd_download(
     workers: usize,
     limit: usize,
     show_progress: bool,
+    chunks: usize,
+    privacy_config: &PrivacyConfig,
+) -> anyhow::Result<()> {
+    settings.ensure_directories()?;
+    foia::shutdown::install();
+
+    // Each shard has its own queue; work through the sources one at a time
+    if source_id.is_none() && settings.is_sharded() {
+        let stats = settings.repositories()?.crawl.get_all_stats().await?;
+        let mut pending: Vec<&String> = stats
+            .iter()
+            .filter(|(_, s)| s.urls_pending > 0)
+            .map(|(id, _)| id)
+            .collect();
+        pending.sort();
+        for sid in pending {
+            if foia::shutdown::is_requested() {
+                break;
+            }
+            println!("{} {}", style("→").cyan(), style(sid).bold());
+            let repos = settings.repositories_for_source(sid)?;
+            download_from(
+                settings,
+                repos,
+                Some(sid.as_str()),
+                workers,
+                limit,
+                show_progress,
+                chunks,
+                privacy_config,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let repos = match source_id {
+        Some(sid) => settings.repositories_for_source(sid)?,
+        None => settings.repositories()?,
+    };
+    download_from(
+        settings,
+        repos,
+        source_id,
+        workers,
+        limit,
+        show_progress,
+        chunks,
+        privacy_config,
+    )
+    .await
+}
+
+/// Download pending documents using `repos`' queue.
+#[allow(clippy::too_many_arguments)]
+async fn download_from(
+    settings: &Settings,
+    repos: Repositories,
+    source_id: Option<&str>,
+    workers: usize,
+    limit: usize,
+    show_progress: bool,
+    chunks: usize,
     privacy_config: &PrivacyConfig,
 ) -> anyhow::Result<()> {
     use crate::cli::progress::DownloadProgress;
-    use foia_scrape::services::download::{DownloadConfig, DownloadEvent, DownloadService};
+    use foia_scrape::services::download::{DownloadEvent, DownloadService};
     use tokio::sync::mpsc;
 
-    settings.ensure_directories()?;
-
-    let repos = settings.repositories()?;
+    let sources = repos.sources.get_all().await?;
 
     // Check for pending work
     let initial_pending = get_pending_count(&repos.crawl, source_id).await?;
@@ -51,22 +117,17 @@ pub async fn cmd_download(
         initial_pending
     );
 
-    // Load config for via mappings
+    // Load config for via mappings and per-source proxies, sessions, throttles,
+    // published checksums, wayback fallback, politeness and agency tags
     let config = Config::load().await;
-
-    // Create service
-    let service = DownloadService::new(
+    let mut service = DownloadService::new(
         doc_repo,
         crawl_repo,
-        DownloadConfig {
-            documents_dir: settings.documents_dir.clone(),
-            request_timeout: Duration::from_secs(settings.request_timeout),
-            request_delay: Duration::from_millis(settings.request_delay_ms),
-            privacy: privacy_config.clone(),
-            via: config.via,
-            via_mode: config.via_mode,
-        },
+        download_config(settings, &config, privacy_config, chunks, &sources),
     );
+    if config.download.enqueue_ocr {
+        service = service.with_ocr_queue(Arc::from(connect(settings).await?));
+    }
 
     // Event channel for progress updates
     let (event_tx, mut event_rx) = mpsc::channel::<DownloadEvent>(100);
@@ -83,17 +144,22 @@ pub async fn cmd_download(
     let event_handler = tokio::spawn(async move {
         let mut downloaded = 0usize;
         let mut skipped = 0usize;
+        // URL each fetch worker is on; a download can finish in a later
+        // stage after its worker has moved on to the next
+        let mut current: HashMap<usize, String> = HashMap::new();
 
         while let Some(event) = event_rx.recv().await {
-            match event {
+            let (worker_id, url, success) = match &event {
                 DownloadEvent::Started {
                     worker_id,
+                    url,
                     filename,
-                    ..
                 } => {
+                    current.insert(*worker_id, url.clone());
                     if let Some(ref progress) = progress_clone {
-                        progress.start_download(worker_id, &filename, None).await;
+                        progress.start_download(*worker_id, filename, None).await;
                     }
+                    continue;
                 }
                 DownloadEvent::Progress {
                     worker_id,
@@ -102,54 +168,51 @@ pub async fn cmd_download(
                 } => {
                     if let Some(ref progress) = progress_clone {
                         if let Some(t) = total {
-                            progress.start_download(worker_id, "", Some(t)).await;
+                            progress.start_download(*worker_id, "", Some(*t)).await;
                         }
-                        progress.update_progress(worker_id, bytes).await;
+                        progress.update_progress(*worker_id, *bytes).await;
                     }
+                    continue;
                 }
-                DownloadEvent::Completed { worker_id, .. } => {
+                // Count deduplicated files as successful downloads
+                DownloadEvent::Completed { worker_id, url, .. }
+                | DownloadEvent::Deduplicated { worker_id, url, .. } => {
                     downloaded += 1;
-                    if let Some(ref progress) = progress_clone {
-                        progress.set_summary(downloaded, skipped);
-                        progress.finish_download(worker_id, true).await;
-                    }
-                }
-                DownloadEvent::Deduplicated { worker_id, .. } => {
-                    // Count deduplicated files as successful downloads
-                    downloaded += 1;
-                    if let Some(ref progress) = progress_clone {
-                        progress.set_summary(downloaded, skipped);
-                        progress.finish_download(worker_id, true).await;
-                    }
+                    (*worker_id, url, true)
                 }
-                DownloadEvent::Unchanged { worker_id, .. } => {
+                DownloadEvent::Unchanged { worker_id, url }
+                | DownloadEvent::Skipped { worker_id, url, .. } => {
                     skipped += 1;
-                    if let Some(ref progress) = progress_clone {
-                        progress.set_summary(downloaded, skipped);
-                        progress.finish_download(worker_id, true).await;
-                    }
+                    (*worker_id, url, true)
                 }
                 DownloadEvent::Failed {
                     worker_id,
                     url,
                     error,
                 } => {
-                    if let Some(ref progress) = progress_clone {
-                        progress.println(&format!(
-                            "{} Failed to download {}: {}",
-                            console::style("✗").red(),
-                            url,
-                            error
-                        ));
-                        progress.finish_download(worker_id, false).await;
-                    } else {
-                        eprintln!(
-                            "{} Failed to download {}: {}",
-                            console::style("✗").red(),
-                            url,
-                            error
-                        );
+                    let message = format!(
+                        "{} Failed to download {}: {}",
+                        console::style("✗").red(),
+                        url,
+                        error
+                    );
+                    match progress_clone {
+                        Some(ref progress) => progress.println(&message),
+                        None => eprintln!("{}", message),
                     }
+                    (*worker_id, url, false)
+                }
+            };
+
+            if let Some(ref progress) = progress_clone {
+                if success {
+                    progress.set_summary(downloaded, skipped);
+                }
+                if current.get(&worker_id) == Some(url) {
+                    current.remove(&worker_id);
+                    progress.finish_download(worker_id, success).await;
+                } else {
+                    progress.record_finished(success);
                 }
             }
         }
@@ -186,6 +249,14 @@ pub async fn cmd_download(
         );
     }
 
+    if result.known_files > 0 {
+        println!(
+            "  {} {} known files not stored",
+            style("→").dim(),
+            result.known_files
+        );
+    }
+
     if result.remaining > 0 {
         println!(
             "  {} {} URLs still pending",
@@ -194,11 +265,18 @@ pub async fn cmd_download(
         );
     }
 
+    if result.interrupted {
+        println!(
+            "  {} Stopped on shutdown request after finishing in-flight downloads",
+            style("!").yellow()
+        );
+    }
+
     Ok(())
 }
 
 /// Get pending document count for a source or all sources.
-async fn get_pending_count(
+pub(crate) async fn get_pending_count(
     crawl_repo: &DieselCrawlRepository,
     source_id: Option<&str>,
 ) -> anyhow::Result<u64> {
@@ -210,3 +288,121 @@ async fn get_pending_count(
         Ok(all_stats.values().map(|s| s.urls_pending).sum())
     }
 }
+
+/// `agency:` tags for each source linked to an agency, by `foia agency
+/// link` or by its config's `agency`.
+fn source_agency_tags(config: &Config, sources: &[Source]) -> HashMap<String, Vec<String>> {
+    let mut agencies: HashMap<&str, &str> = config
+        .scrapers
+        .iter()
+        .filter_map(|(id, scraper)| Some((id.as_str(), scraper.agency.as_deref()?)))
+        .collect();
+    for source in sources {
+        if let Some(agency) = source.agency_id.as_deref() {
+            agencies.insert(&source.id, agency);
+        }
+    }
+    agencies
+        .into_iter()
+        .map(|(source, agency)| (source.to_string(), vec![agency_tag(agency)]))
+        .collect()
+}
+
+/// Download settings from `config`, shared with queue workers.
+pub(crate) fn download_config(
+    settings: &Settings,
+    config: &Config,
+    privacy_config: &PrivacyConfig,
+    chunks: usize,
+    sources: &[Source],
+) -> DownloadConfig {
+    // Sources paced by a profile, their own delay, or robots.txt
+    let source_politeness = config
+        .scrapers
+        .iter()
+        .filter_map(|(id, scraper)| {
+            let mut scraper = scraper.clone();
+            config.apply_politeness_defaults(&mut scraper);
+            if scraper.politeness.is_none()
+                && scraper.respect_robots.is_none()
+                && scraper.request_delay_ms.is_none()
+            {
+                return None;
+            }
+            let delay = Duration::from_millis(settings.request_delay_ms);
+            Some((id.clone(), Politeness::resolve(&scraper, delay)))
+        })
+        .collect();
+
+    DownloadConfig {
+        documents_dir: settings.documents_dir.clone(),
+        request_timeout: Duration::from_secs(settings.request_timeout),
+        request_delay: Duration::from_millis(settings.request_delay_ms),
+        privacy: privacy_config.clone(),
+        via: config.via.clone(),
+        via_mode: config.via_mode,
+        source_proxies: config
+            .scrapers
+            .iter()
+            .filter(|(_, scraper)| scraper.privacy.proxy.is_enabled())
+            .map(|(id, scraper)| (id.clone(), scraper.privacy.proxy.clone()))
+            .collect(),
+        source_sessions: config
+            .scrapers
+            .iter()
+            .filter_map(|(id, scraper)| {
+                let session = scraper.session.clone()?;
+                Some((
+                    id.clone(),
+                    session.with_default_cookie_file(&settings.data_dir, id),
+                ))
+            })
+            .collect(),
+        throttle: config.throttle.clone(),
+        source_throttles: config
+            .scrapers
+            .iter()
+            .filter_map(|(id, scraper)| Some((id.clone(), scraper.throttle.clone()?)))
+            .collect(),
+        source_checksums: config
+            .scrapers
+            .iter()
+            .filter_map(|(id, scraper)| Some((id.clone(), scraper.checksums.clone()?)))
+            .collect(),
+        source_wayback: config
+            .scrapers
+            .iter()
+            .filter_map(|(id, scraper)| Some((id.clone(), scraper.wayback.clone()?)))
+            .collect(),
+        source_politeness,
+        large_file_threshold: DEFAULT_LARGE_FILE_THRESHOLD,
+        parallel_chunks: chunks.max(1),
+        trusted_content_type: config
+            .scrapers
+            .iter()
+            .filter(|(_, scraper)| scraper.fetch.trust_content_type)
+            .map(|(id, _)| id.clone())
+            .collect(),
+        source_tags: source_agency_tags(config, sources),
+        pipeline: download_pipeline(config),
+    }
+}
+
+/// The download pipeline settings, with hash list paths resolved against
+/// the config file's directory.
+fn download_pipeline(config: &Config) -> DownloadPipelineConfig {
+    let mut pipeline = config.download.clone();
+    let base_dir = config.resolve_base_dir(false);
+    let known_files = &mut pipeline.known_files;
+    for path in known_files
+        .skip
+        .iter_mut()
+        .chain(known_files.keep.iter_mut())
+    {
+        *path = config
+            .resolve_path(path, &base_dir)
+            .to_string_lossy()
+            .into_owned();
+    }
+    pipeline
+}
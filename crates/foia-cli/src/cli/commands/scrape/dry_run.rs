@@ -0,0 +1,131 @@
+//! Scrape dry run: preview what a crawl would fetch.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use console::style;
+
+use foia::config::{Config, Settings, DEFAULT_REFRESH_TTL_DAYS};
+use foia::models::{Source, SourceType};
+use foia::privacy::PrivacyConfig;
+use foia::utils::format_size;
+use foia_scrape::{ConfigurableScraper, RateLimiter, ScrapePlan};
+
+/// Run discovery for one source and print what a scrape would download.
+///
+/// Only listing pages and HEAD requests are fetched; nothing is queued,
+/// downloaded or saved.
+pub(super) async fn cmd_scrape_dry_run(
+    settings: &Settings,
+    source_id: &str,
+    workers: usize,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    privacy_config: &PrivacyConfig,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let Some(mut scraper_config) = repos.scraper_configs.get(source_id).await? else {
+        println!(
+            "{} No scraper configured for '{}'",
+            style("✗").red(),
+            source_id
+        );
+        return Ok(());
+    };
+    let config = Config::load().await;
+
+    // Same session and throttle settings a real scrape would use
+    scraper_config.session = scraper_config
+        .session
+        .take()
+        .map(|s| s.with_default_cookie_file(&settings.data_dir, source_id));
+    let throttle = scraper_config
+        .throttle
+        .take()
+        .unwrap_or_default()
+        .or(&config.throttle);
+    scraper_config.throttle = (!throttle.is_default()).then_some(throttle);
+    config.apply_politeness_defaults(&mut scraper_config);
+
+    let source = match repos.sources.get(source_id).await? {
+        Some(s) => s,
+        None => Source::new(
+            source_id.to_string(),
+            SourceType::Custom,
+            scraper_config.name_or(source_id),
+            scraper_config.base_url_or(""),
+        ),
+    };
+    let refresh_ttl_days = scraper_config
+        .refresh_ttl_days
+        .or(config.default_refresh_ttl_days)
+        .unwrap_or(DEFAULT_REFRESH_TTL_DAYS);
+
+    // No crawl repository: discovery must not queue URLs or log requests
+    let scraper = ConfigurableScraper::with_rate_limiter_and_privacy(
+        source,
+        scraper_config.clone(),
+        None,
+        Duration::from_millis(settings.request_delay_ms),
+        refresh_ttl_days,
+        rate_limiter.as_ref().map(|r| (**r).clone()),
+        Some(privacy_config),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to create scraper: {}", e))?;
+    let scraper = if !scraper_config.via.is_empty() {
+        let via_mode = scraper_config.via_mode.unwrap_or_default();
+        scraper.with_via_config(scraper_config.via.clone(), via_mode)
+    } else {
+        scraper
+    };
+
+    println!(
+        "{} Planning {} (discovery and HEAD requests only)...",
+        style("→").cyan(),
+        source_id
+    );
+    let plan = scraper.plan(Some(&repos.crawl), workers).await;
+    let backlog = repos.crawl.get_crawl_state(source_id).await?.urls_pending;
+
+    print_plan(source_id, &plan, backlog);
+    Ok(())
+}
+
+fn print_plan(source_id: &str, plan: &ScrapePlan, backlog: u64) {
+    println!("\n{} {}", style("Dry run:").bold(), source_id);
+    println!("  Discovered:          {}", plan.discovered);
+    println!("  New URLs:            {}", plan.new_urls);
+    println!("  Queued, not fetched: {}", plan.queued);
+    println!("  Changed upstream:    {}", plan.changed);
+    println!(
+        "  Skipped (cached):    {} fresh, {} unchanged (304)",
+        plan.fresh, plan.unchanged
+    );
+    if plan.skipped > 0 {
+        println!("  Skipped (excluded):  {}", plan.skipped);
+    }
+    if plan.unreachable > 0 {
+        println!(
+            "  {} HEAD failed:         {}",
+            style("!").yellow(),
+            plan.unreachable
+        );
+    }
+
+    let mut size = format!("~{}", format_size(plan.estimated_bytes));
+    if plan.unknown_size > 0 {
+        size.push_str(&format!(" (+{} of unknown size)", plan.unknown_size));
+    }
+    println!(
+        "  {} Would download {} documents, {}",
+        style("→").cyan(),
+        plan.would_download(),
+        size
+    );
+    if backlog > plan.queued {
+        println!(
+            "  {} {} URLs already queued from earlier crawls",
+            style("→").dim(),
+            backlog
+        );
+    }
+}
@@ -97,7 +97,7 @@ pub async fn process_get_response_for_refresh(
     };
 
     let new_hash = DocumentVersion::compute_hash(&content);
-    let content_changed = new_hash != current_version.content_hash;
+    let content_changed = !current_version.holds_content(&new_hash, &content);
 
     if content_changed {
         let updated = match save_new_version(
@@ -3,12 +3,14 @@
 //! Split into submodules:
 //! - `helpers.rs`: Helper functions for document processing
 //! - `scrape_cmd.rs`: Main scrape command
+//! - `dry_run.rs`: Scrape preview without downloading
 //! - `download.rs`: Download pending documents
 //! - `status.rs`: Show system status
 //! - `refresh.rs`: Refresh document metadata
 
 mod discovery;
 mod download;
+mod dry_run;
 mod helpers;
 mod refresh;
 mod scrape_cmd;
@@ -16,6 +18,7 @@ mod single_source;
 mod status;
 
 pub use download::cmd_download;
+pub(crate) use download::{download_config, get_pending_count};
 pub use refresh::cmd_refresh;
 pub use scrape_cmd::cmd_scrape;
 pub use status::cmd_status;
@@ -91,15 +91,67 @@ pub async fn cmd_refresh(
     limit: usize,
     force: bool,
     privacy_config: &PrivacyConfig,
+) -> anyhow::Result<()> {
+    // Documents in a shard are updated through it, one source at a time
+    if source_id.is_none() && settings.is_sharded() {
+        for source in settings.repositories()?.sources.get_all().await? {
+            let repos = settings.repositories_for_source(&source.id)?;
+            refresh_from(
+                settings,
+                repos.documents,
+                Some(source.id.as_str()),
+                workers,
+                limit,
+                force,
+                privacy_config,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let repos = match source_id {
+        Some(sid) => settings.repositories_for_source(sid)?,
+        None => settings.repositories()?,
+    };
+    refresh_from(
+        settings,
+        repos.documents,
+        source_id,
+        workers,
+        limit,
+        force,
+        privacy_config,
+    )
+    .await
+}
+
+/// Refresh metadata for the documents in `doc_repo`.
+async fn refresh_from(
+    settings: &Settings,
+    doc_repo: DieselDocumentRepository,
+    source_id: Option<&str>,
+    workers: usize,
+    limit: usize,
+    force: bool,
+    privacy_config: &PrivacyConfig,
 ) -> anyhow::Result<()> {
     use tokio::sync::Semaphore;
 
-    let repos = settings.repositories()?;
-    let doc_repo = Arc::new(repos.documents);
+    let doc_repo = Arc::new(doc_repo);
 
     // Get documents that need metadata refresh
     let documents = if let Some(sid) = source_id {
-        doc_repo.get_by_source(sid).await?
+        let mut documents = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = doc_repo.get_by_source(sid, cursor.as_ref(), 500).await?;
+            documents.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break documents,
+            }
+        }
     } else {
         doc_repo.get_all().await?
     };
@@ -11,8 +11,10 @@ use foia::config::{Config, Settings};
 use foia::models::{ScraperStats, ServiceStatus};
 use foia::privacy::PrivacyConfig;
 use foia::repository::DieselServiceStatusRepository;
+use foia::services::saved_search_alerts;
 use foia_scrape::{DieselRateLimitBackend, InMemoryRateLimitBackend, RateLimiter};
 
+use super::dry_run::cmd_scrape_dry_run;
 use super::single_source::cmd_scrape_single_tui;
 
 /// Update service heartbeat if interval has elapsed.
@@ -44,6 +46,32 @@ pub(super) async fn maybe_update_heartbeat(
     }
 }
 
+/// Tell saved view subscribers about documents this crawl brought in.
+/// Failures are reported but never fail the crawl.
+async fn send_saved_view_alerts(settings: &Settings, config: &Config) {
+    let repos = match settings.repositories() {
+        Ok(repos) => repos,
+        Err(e) => {
+            tracing::warn!("Skipping saved view alerts: {}", e);
+            return;
+        }
+    };
+    match saved_search_alerts::send_alerts(&repos, &config.email, &settings.privacy).await {
+        Ok(0) => {}
+        Ok(sent) => println!(
+            "{} Sent {} saved view alert{}",
+            style("✓").green(),
+            sent,
+            if sent == 1 { "" } else { "s" }
+        ),
+        Err(errors) => {
+            for e in errors {
+                tracing::warn!("Saved view alert failed: {}", e);
+            }
+        }
+    }
+}
+
 /// Scrape documents from one or more sources.
 #[allow(clippy::too_many_arguments)]
 pub async fn cmd_scrape(
@@ -57,8 +85,11 @@ pub async fn cmd_scrape(
     interval: u64,
     reload: ReloadMode,
     rate_limit_backend_type: RateLimitBackendType,
+    dry_run: bool,
     privacy_config: &PrivacyConfig,
 ) -> anyhow::Result<()> {
+    foia::shutdown::install();
+
     // Create rate limiter with selected backend
     let base_delay_ms = settings.request_delay_ms;
     let rate_limiter = match rate_limit_backend_type {
@@ -99,7 +130,8 @@ pub async fn cmd_scrape(
         reload,
         config_history,
         scraper_configs.clone(),
-        config.hash(),
+        &config,
+        &["request_delay_ms"],
     )
     .await;
 
@@ -118,6 +150,20 @@ pub async fn cmd_scrape(
         source_ids.to_vec()
     };
 
+    if dry_run {
+        for source_id in &sources_to_scrape {
+            cmd_scrape_dry_run(
+                settings,
+                source_id,
+                workers,
+                Some(rate_limiter.clone()),
+                privacy_config,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
     if daemon {
         println!(
             "{} Running in daemon mode (interval: {}s, reload: {:?})",
@@ -189,6 +235,7 @@ pub async fn cmd_scrape(
                 tui_guard.is_active(),
                 Some(rate_limiter.clone()),
                 privacy_config,
+                config_watcher.live(),
             )
             .await;
 
@@ -226,6 +273,7 @@ pub async fn cmd_scrape(
                 let tui_active = tui_guard.is_active();
                 let rate_limiter_clone = rate_limiter.clone();
                 let privacy_config_clone = privacy_config.clone();
+                let live_config = config_watcher.live();
                 let handle = tokio::spawn(async move {
                     cmd_scrape_single_tui(
                         &settings,
@@ -237,6 +285,7 @@ pub async fn cmd_scrape(
                         tui_active,
                         Some(rate_limiter_clone),
                         &privacy_config_clone,
+                        live_config,
                     )
                     .await
                 });
@@ -286,18 +335,37 @@ pub async fn cmd_scrape(
             crate::cli::tui::set_status(0, &format!("{} Scraping complete", style("✓").green()));
 
         // TUI cleanup happens automatically when tui_guard is dropped
-        // Note: Rate limit state is persisted automatically by the Diesel backend
         drop(tui_guard);
 
-        if !daemon {
+        send_saved_view_alerts(settings, &config).await;
+
+        if !daemon || foia::shutdown::is_requested() {
             break;
         }
 
         match config_watcher.sleep_or_reload(interval, "reloading").await {
-            DaemonAction::Exit => return Ok(()),
+            DaemonAction::Exit => break,
             DaemonAction::Continue | DaemonAction::Reload => {}
         }
     }
 
+    // The Diesel and Redis backends persist as they go; in-memory backoff
+    // is saved so `foia status` and later database-backed runs see it
+    if rate_limit_backend_type == RateLimitBackendType::Memory {
+        let repos = settings.repositories()?;
+        let store = DieselRateLimitBackend::new(repos.pool().clone(), base_delay_ms);
+        match rate_limiter.save_rate_limit_state(&store).await {
+            Ok(saved) => tracing::debug!("Saved rate limit state for {} domains", saved),
+            Err(e) => tracing::warn!("Failed to save rate limit state: {}", e),
+        }
+    }
+
+    if foia::shutdown::is_requested() {
+        println!(
+            "{} Stopped on shutdown request; in-flight documents were saved",
+            style("!").yellow()
+        );
+    }
+
     Ok(())
 }
@@ -5,7 +5,7 @@ use std::time::Duration;
 
 use console::style;
 
-use foia::config::{Config, Settings, DEFAULT_REFRESH_TTL_DAYS};
+use foia::config::{Config, LiveConfig, Settings, DEFAULT_REFRESH_TTL_DAYS};
 use foia::llm::LlmClient;
 use foia::models::{ScraperStats, ServiceStatus, Source, SourceType};
 use foia::privacy::PrivacyConfig;
@@ -13,6 +13,23 @@ use foia_scrape::{ConfigurableScraper, RateLimiter};
 
 use super::scrape_cmd::maybe_update_heartbeat;
 
+/// Wait for the next config reload and return the global request delay it
+/// sets. Never resolves without a live config.
+async fn next_request_delay(live_config: &mut Option<LiveConfig>) -> Option<Duration> {
+    let Some(rx) = live_config else {
+        return std::future::pending().await;
+    };
+    if rx.changed().await.is_err() {
+        *live_config = None;
+        return None;
+    }
+    let delay_ms = rx
+        .borrow_and_update()
+        .request_delay_ms
+        .unwrap_or(Settings::default().request_delay_ms);
+    Some(Duration::from_millis(delay_ms))
+}
+
 /// Scrape a single source with TUI status updates.
 #[allow(clippy::too_many_arguments)]
 pub(super) async fn cmd_scrape_single_tui(
@@ -25,6 +42,7 @@ pub(super) async fn cmd_scrape_single_tui(
     tui_active: bool,
     rate_limiter: Option<Arc<RateLimiter>>,
     privacy_config: &PrivacyConfig,
+    mut live_config: Option<LiveConfig>,
 ) -> anyhow::Result<()> {
     settings.ensure_directories()?;
 
@@ -44,7 +62,7 @@ pub(super) async fn cmd_scrape_single_tui(
     };
 
     // Load scraper config from database (server config)
-    let repos = settings.repositories()?;
+    let repos = settings.repositories_for_source(source_id)?;
     let mut scraper_config = match repos.scraper_configs.get(source_id).await? {
         Some(c) => c,
         None => {
@@ -135,7 +153,7 @@ pub(super) async fn cmd_scrape_single_tui(
     }
 
     // Auto-register source if not in database
-    let source = match source_repo.get(source_id).await? {
+    let mut source = match source_repo.get(source_id).await? {
         Some(s) => s,
         None => {
             let new_source = Source::new(
@@ -149,6 +167,12 @@ pub(super) async fn cmd_scrape_single_tui(
         }
     };
 
+    // Link the source to the agency its config names
+    if scraper_config.agency.is_some() && source.agency_id != scraper_config.agency {
+        source.agency_id = scraper_config.agency.clone();
+        source_repo.save(&source).await?;
+    }
+
     // Check crawl state and update config hash
     {
         let config_hash = {
@@ -179,6 +203,21 @@ pub(super) async fn cmd_scrape_single_tui(
         tracing::warn!("Failed to register service status: {}", e);
     }
 
+    // Keep session cookies under the data directory unless configured otherwise
+    scraper_config.session = scraper_config
+        .session
+        .take()
+        .map(|s| s.with_default_cookie_file(&settings.data_dir, source_id));
+
+    // Fall back to the global bandwidth cap and schedule
+    let throttle = scraper_config
+        .throttle
+        .take()
+        .unwrap_or_default()
+        .or(&config.throttle);
+    scraper_config.throttle = (!throttle.is_default()).then_some(throttle);
+    config.apply_politeness_defaults(&mut scraper_config);
+
     // Create scraper and start streaming
     let refresh_ttl_days = scraper_config
         .refresh_ttl_days
@@ -218,13 +257,37 @@ pub(super) async fn cmd_scrape_single_tui(
     };
     let mut rx = stream.receiver;
 
+    let mut request_delay = Duration::from_millis(settings.request_delay_ms);
     let mut count = 0u64;
     let mut new_this_session = 0u64;
     let mut errors_this_session = 0u64;
     let mut last_heartbeat = std::time::Instant::now();
     let heartbeat_interval = std::time::Duration::from_secs(15);
 
-    while let Some(result) = rx.recv().await {
+    loop {
+        // On shutdown, stop before taking the next result; dropping the
+        // receiver stops the crawler from sending more
+        let result = tokio::select! {
+            biased;
+            _ = foia::shutdown::requested() => None,
+            Some(delay) = next_request_delay(&mut live_config) => {
+                if delay != request_delay {
+                    request_delay = delay;
+                    scraper.apply_request_delay(delay).await;
+                    log_msg(&format!(
+                        "{} {} request delay now {}ms",
+                        style("↻").cyan(),
+                        source_id,
+                        delay.as_millis()
+                    ));
+                }
+                continue;
+            }
+            result = rx.recv() => result,
+        };
+        let Some(result) = result else {
+            break;
+        };
         if result.not_modified {
             count += 1;
             update_status(&format!("{} {} processed", source_id, count));
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::io::{stdout, Stdout};
 use std::time::Duration;
 
-use chrono::Local;
+use chrono::{DateTime, Local, Utc};
 use console::style;
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use crossterm::terminal::{
@@ -16,7 +16,10 @@ use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table};
 
 use foia::config::Settings;
 use foia::models::{DocumentStatus, ServiceStatus};
+use foia::rate_limit::RateLimiter;
+use foia::repository::diesel_crawl::DomainRateLimit;
 use foia::repository::util::redact_url_password;
+use foia::utils::format_size;
 
 /// Show overall system status.
 pub async fn cmd_status(
@@ -50,9 +53,9 @@ pub async fn cmd_status(
     }
 
     if live {
-        run_live_status(settings, interval).await
+        run_live_status(settings, source_id.as_deref(), interval).await
     } else {
-        display_status_simple(settings).await
+        display_status_simple(settings, source_id.as_deref()).await
     }
 }
 
@@ -194,8 +197,8 @@ fn display_api_status(data: &serde_json::Value, url: &str) {
 }
 
 /// Display status as JSON from local database.
-async fn display_status_json(settings: &Settings, _source_id: Option<&str>) -> anyhow::Result<()> {
-    let data = fetch_status_data(settings).await?;
+async fn display_status_json(settings: &Settings, source_id: Option<&str>) -> anyhow::Result<()> {
+    let data = fetch_status_data(settings, source_id).await?;
 
     let json = serde_json::json!({
         "documents": {
@@ -207,10 +210,25 @@ async fn display_status_json(settings: &Settings, _source_id: Option<&str>) -> a
         },
         "sources": data.sources.iter().map(|s| serde_json::json!({
             "source_id": s.id,
-            "total": s.total,
-            "pending": s.pending,
-            "downloaded": s.downloaded,
-            "ocr_done": s.ocr_done,
+            "urls": {
+                "pending": s.urls_pending,
+                "fetched": s.urls_fetched,
+                "failed": s.urls_failed,
+            },
+            "documents": {
+                "total": s.total,
+                "by_status": s.by_status,
+            },
+            "pages_needing_ocr": s.pages_needing_ocr,
+            "needing_summarization": s.ocr_done,
+            "disk_bytes": s.disk_bytes,
+            "last_crawl": s.last_crawl.map(|t| t.to_rfc3339()),
+            "rate_limit": s.rate_limit.as_ref().map(|r| serde_json::json!({
+                "domain": r.domain,
+                "delay_ms": r.current_delay_ms,
+                "in_backoff": r.in_backoff,
+                "rate_limit_hits": r.rate_limit_hits,
+            })),
         })).collect::<Vec<_>>(),
         "database": data.database_url,
         "data_dir": data.data_dir,
@@ -237,53 +255,103 @@ struct SourceStats {
     total: u64,
     pending: u64,
     downloaded: u64,
+    /// OCR done but not yet summarized.
     ocr_done: u64,
+    by_status: HashMap<String, u64>,
+    urls_pending: u64,
+    urls_fetched: u64,
+    urls_failed: u64,
+    pages_needing_ocr: u64,
+    disk_bytes: u64,
+    last_crawl: Option<DateTime<Utc>>,
+    /// Persisted rate-limit state for the source's domain, if any.
+    rate_limit: Option<DomainRateLimit>,
 }
 
-/// Fetch all status data from the database.
-async fn fetch_status_data(settings: &Settings) -> anyhow::Result<StatusData> {
+/// Fetch all status data from the database, for every source or just
+/// `source_id`.
+async fn fetch_status_data(
+    settings: &Settings,
+    source_id: Option<&str>,
+) -> anyhow::Result<StatusData> {
     let repos = settings.repositories()?;
     let doc_repo = repos.documents;
     let source_repo = repos.sources;
     let crawl_repo = repos.crawl;
     let service_repo = repos.service_status;
 
-    let sources_list = source_repo.get_all().await?;
-    let total_docs = doc_repo.count().await?;
-    let status_counts = doc_repo.count_all_by_status().await?;
-    let pending_downloads = crawl_repo.count_pending_downloads().await.unwrap_or(0) as u64;
+    let mut sources_list = source_repo.get_all().await?;
+    if let Some(sid) = source_id {
+        sources_list.retain(|source| source.id == sid);
+        if sources_list.is_empty() {
+            anyhow::bail!("Source '{}' not found", sid);
+        }
+    }
     let source_counts = doc_repo.get_all_source_counts().await?;
-    let source_status_counts = doc_repo.get_source_status_counts().await?;
+    let mut source_status_counts = doc_repo.get_source_status_counts().await?;
+    let pages_needing_ocr = doc_repo.get_source_pages_needing_ocr().await?;
+    let disk_bytes = doc_repo.get_source_storage_bytes().await?;
+    let crawl_stats = crawl_repo.get_all_stats().await?;
+    let last_requests = crawl_repo.get_last_request_times().await?;
+    let rate_limits = crawl_repo
+        .get_domain_rate_limits()
+        .await
+        .unwrap_or_default();
     let services = service_repo.get_all().await.unwrap_or_default();
 
-    // Only include sources that have at least one document
+    // Only include sources that have documents or crawl URLs
     let sources: Vec<SourceStats> = sources_list
         .iter()
         .filter_map(|source| {
             let total = source_counts.get(&source.id).copied().unwrap_or(0);
-            if total == 0 {
+            let crawl = crawl_stats.get(&source.id).cloned().unwrap_or_default();
+            if total == 0 && crawl.urls_discovered == 0 {
                 return None;
             }
-            let statuses = source_status_counts.get(&source.id);
+            let by_status = source_status_counts.remove(&source.id).unwrap_or_default();
+            let count =
+                |status: DocumentStatus| by_status.get(status.as_str()).copied().unwrap_or(0);
+            let domain = RateLimiter::extract_domain(&source.base_url);
             Some(SourceStats {
                 id: source.id.clone(),
                 total,
-                pending: statuses
-                    .and_then(|s| s.get(DocumentStatus::Pending.as_str()))
-                    .copied()
-                    .unwrap_or(0),
-                downloaded: statuses
-                    .and_then(|s| s.get(DocumentStatus::Downloaded.as_str()))
+                pending: count(DocumentStatus::Pending),
+                downloaded: count(DocumentStatus::Downloaded),
+                ocr_done: count(DocumentStatus::OcrComplete),
+                urls_pending: crawl.urls_pending,
+                urls_fetched: crawl.urls_fetched,
+                urls_failed: crawl.urls_failed,
+                pages_needing_ocr: pages_needing_ocr.get(&source.id).copied().unwrap_or(0),
+                disk_bytes: disk_bytes.get(&source.id).copied().unwrap_or(0),
+                last_crawl: last_requests
+                    .get(&source.id)
                     .copied()
-                    .unwrap_or(0),
-                ocr_done: statuses
-                    .and_then(|s| s.get(DocumentStatus::OcrComplete.as_str()))
-                    .copied()
-                    .unwrap_or(0),
+                    .max(source.last_scraped),
+                rate_limit: rate_limits
+                    .iter()
+                    .find(|r| Some(&r.domain) == domain.as_ref())
+                    .cloned(),
+                by_status,
             })
         })
         .collect();
 
+    let (total_docs, status_counts, pending_downloads) = match source_id {
+        Some(_) => {
+            let source = sources.first();
+            (
+                source.map(|s| s.total).unwrap_or(0),
+                source.map(|s| s.by_status.clone()).unwrap_or_default(),
+                source.map(|s| s.urls_pending).unwrap_or(0),
+            )
+        }
+        None => (
+            doc_repo.count().await?,
+            doc_repo.count_all_by_status().await?,
+            crawl_repo.count_pending_downloads().await.unwrap_or(0) as u64,
+        ),
+    };
+
     Ok(StatusData {
         database_url: redact_url_password(&settings.database_url()),
         data_dir: settings.data_dir.display().to_string(),
@@ -297,8 +365,8 @@ async fn fetch_status_data(settings: &Settings) -> anyhow::Result<StatusData> {
 }
 
 /// Display status once (non-TUI mode).
-async fn display_status_simple(settings: &Settings) -> anyhow::Result<()> {
-    let data = fetch_status_data(settings).await?;
+async fn display_status_simple(settings: &Settings, source_id: Option<&str>) -> anyhow::Result<()> {
+    let data = fetch_status_data(settings, source_id).await?;
     let separator = "─".repeat(70);
 
     println!();
@@ -316,17 +384,11 @@ async fn display_status_simple(settings: &Settings) -> anyhow::Result<()> {
     println!("{}", style("DOCUMENTS").cyan().bold());
     println!("  {:<20} {:>10}", "Total:", format_number(data.total_docs));
 
-    for status in [
-        DocumentStatus::Pending,
-        DocumentStatus::Downloaded,
-        DocumentStatus::OcrComplete,
-        DocumentStatus::Indexed,
-        DocumentStatus::Failed,
-    ] {
+    for status in DocumentStatus::ALL {
         if let Some(&count) = data.status_counts.get(status.as_str()) {
             println!(
                 "  {:<20} {:>10}",
-                format!("{}:", status.as_str()),
+                format!("{}:", status.label()),
                 format_number(count)
             );
         }
@@ -389,22 +451,66 @@ async fn display_status_simple(settings: &Settings) -> anyhow::Result<()> {
 
     if !data.sources.is_empty() {
         println!(
-            "{:<26} {:>10} {:>10} {:>10} {:>10}",
+            "{:<26} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
             style("SOURCES").cyan().bold(),
-            "Total",
+            "Docs",
             "Pending",
             "Downloaded",
-            "OCR Done"
+            "OCR Pages",
+            "Summarize",
+            "Disk"
         );
-
         for source in &data.sources {
             println!(
-                "  {:<24} {:>10} {:>10} {:>10} {:>10}",
+                "  {:<24} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
                 truncate_string(&source.id, 24),
                 format_number(source.total),
                 format_number(source.pending),
                 format_number(source.downloaded),
+                format_number(source.pages_needing_ocr),
                 format_number(source.ocr_done),
+                format_size(source.disk_bytes),
+            );
+        }
+        println!();
+
+        println!(
+            "{:<26} {:>10} {:>10} {:>10} {:>18}  {}",
+            style("CRAWL").cyan().bold(),
+            "Pending",
+            "Fetched",
+            "Failed",
+            "Last Crawl",
+            "Rate Limit"
+        );
+        for source in &data.sources {
+            let last_crawl = source
+                .last_crawl
+                .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "never".to_string());
+            let rate_limit = match &source.rate_limit {
+                Some(r) if r.in_backoff => style(format!(
+                    "backoff {:.1}s ({} hits)",
+                    r.current_delay_ms as f64 / 1000.0,
+                    r.rate_limit_hits
+                ))
+                .red(),
+                Some(r) => style(format!("{:.1}s", r.current_delay_ms as f64 / 1000.0)),
+                None => style("-".to_string()).dim(),
+            };
+            let failed = if source.urls_failed > 0 {
+                style(format_number(source.urls_failed)).red()
+            } else {
+                style(format_number(source.urls_failed))
+            };
+            println!(
+                "  {:<24} {:>10} {:>10} {:>10} {:>18}  {}",
+                truncate_string(&source.id, 24),
+                format_number(source.urls_pending),
+                format_number(source.urls_fetched),
+                failed,
+                last_crawl,
+                rate_limit,
             );
         }
     }
@@ -415,16 +521,20 @@ async fn display_status_simple(settings: &Settings) -> anyhow::Result<()> {
 }
 
 /// Run status display in live TUI mode.
-async fn run_live_status(settings: &Settings, interval: u64) -> anyhow::Result<()> {
+async fn run_live_status(
+    settings: &Settings,
+    source_id: Option<&str>,
+    interval: u64,
+) -> anyhow::Result<()> {
     // Fetch initial data before entering TUI mode
-    let mut data = fetch_status_data(settings).await?;
+    let mut data = fetch_status_data(settings, source_id).await?;
 
     // Setup terminal
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
 
-    let result = run_tui_loop(&mut terminal, settings, &mut data, interval).await;
+    let result = run_tui_loop(&mut terminal, settings, source_id, &mut data, interval).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -437,6 +547,7 @@ async fn run_live_status(settings: &Settings, interval: u64) -> anyhow::Result<(
 async fn run_tui_loop(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     settings: &Settings,
+    source_id: Option<&str>,
     data: &mut StatusData,
     interval: u64,
 ) -> anyhow::Result<()> {
@@ -480,7 +591,7 @@ async fn run_tui_loop(
         }
 
         // Fetch new data (keep old data visible during fetch)
-        if let Ok(new_data) = fetch_status_data(settings).await {
+        if let Ok(new_data) = fetch_status_data(settings, source_id).await {
             *data = new_data;
         }
     }
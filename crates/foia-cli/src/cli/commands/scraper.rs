@@ -0,0 +1,180 @@
+//! Scraper development commands: record fixtures and test against them.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use console::style;
+
+use foia::config::{Config, Settings, DEFAULT_REFRESH_TTL_DAYS};
+use foia::models::{Source, SourceType};
+use foia::privacy::PrivacyConfig;
+use foia_scrape::fixtures::{default_fixture_dir, DiscoveryExpectation, FixtureMode, FixtureStore};
+use foia_scrape::ConfigurableScraper;
+
+/// Run discovery live, saving every response and the discovered URLs.
+pub async fn cmd_scraper_record(
+    settings: &Settings,
+    config: &Config,
+    source_id: &str,
+    fixtures: Option<&Path>,
+) -> anyhow::Result<()> {
+    let dir = fixture_dir(settings, source_id, fixtures);
+    let store = Arc::new(FixtureStore::new(&dir, FixtureMode::Record).map_err(anyhow::Error::msg)?);
+    let scraper = build_scraper(
+        settings,
+        config,
+        source_id,
+        Some(&config.privacy),
+        store.clone(),
+    )
+    .await?;
+
+    println!(
+        "{} Recording discovery for {} into {}",
+        style("→").cyan(),
+        source_id,
+        dir.display()
+    );
+    let discovered = scraper.discover().await;
+    let expected = DiscoveryExpectation::new(source_id, discovered);
+    expected.save(&dir)?;
+
+    println!(
+        "{} Recorded {} responses, {} documents discovered",
+        style("✓").green(),
+        store.recorded(),
+        expected.documents.len()
+    );
+    println!(
+        "  {} Check {} and run 'foia scraper test {}'",
+        style("→").dim(),
+        dir.join(foia_scrape::fixtures::EXPECTED_FILE).display(),
+        source_id
+    );
+    Ok(())
+}
+
+/// Replay discovery from fixtures and compare it to the expected URLs.
+pub async fn cmd_scraper_test(
+    settings: &Settings,
+    config: &Config,
+    source_id: &str,
+    fixtures: Option<&Path>,
+) -> anyhow::Result<()> {
+    let dir = fixture_dir(settings, source_id, fixtures);
+    let expected = DiscoveryExpectation::load(&dir)?;
+    let store = Arc::new(FixtureStore::new(&dir, FixtureMode::Replay).map_err(anyhow::Error::msg)?);
+    let scraper = build_scraper(settings, config, source_id, None, store.clone()).await?;
+
+    let discovered = scraper.discover().await;
+    let diff = expected.compare(&discovered);
+
+    let misses = store.misses();
+    if !misses.is_empty() {
+        println!(
+            "{} {} request{} had no fixture (re-record if the scraper config changed):",
+            style("!").yellow(),
+            misses.len(),
+            if misses.len() == 1 { "" } else { "s" }
+        );
+        for miss in &misses {
+            println!("    {}", miss);
+        }
+    }
+    for url in &diff.missing {
+        println!("  {} {}", style("-").red(), url);
+    }
+    for url in &diff.unexpected {
+        println!("  {} {}", style("+").yellow(), url);
+    }
+
+    if !diff.is_empty() {
+        anyhow::bail!(
+            "{}: {} expected document{} not discovered, {} unexpected",
+            source_id,
+            diff.missing.len(),
+            if diff.missing.len() == 1 { "" } else { "s" },
+            diff.unexpected.len()
+        );
+    }
+    println!(
+        "{} {}: discovered all {} expected documents",
+        style("✓").green(),
+        source_id,
+        expected.documents.len()
+    );
+    Ok(())
+}
+
+fn fixture_dir(settings: &Settings, source_id: &str, fixtures: Option<&Path>) -> PathBuf {
+    fixtures
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_fixture_dir(&settings.data_dir, source_id))
+}
+
+/// Build a source's scraper with no crawl repository, so discovery neither
+/// queues URLs nor logs requests. Without `privacy` the scraper is built
+/// for replay: it never reaches the network, so Tor and the source's
+/// proxies are left out.
+async fn build_scraper(
+    settings: &Settings,
+    config: &Config,
+    source_id: &str,
+    privacy: Option<&PrivacyConfig>,
+    store: Arc<FixtureStore>,
+) -> anyhow::Result<ConfigurableScraper> {
+    let repos = settings.repositories()?;
+    let Some(mut scraper_config) = repos.scraper_configs.get(source_id).await? else {
+        anyhow::bail!("No scraper configured for '{}'", source_id);
+    };
+    scraper_config.session = scraper_config
+        .session
+        .take()
+        .map(|s| s.with_default_cookie_file(&settings.data_dir, source_id));
+    config.apply_politeness_defaults(&mut scraper_config);
+    let offline;
+    let privacy = match privacy {
+        Some(privacy) => privacy,
+        None => {
+            scraper_config.privacy = Default::default();
+            offline = PrivacyConfig {
+                direct: true,
+                ..config.privacy.clone()
+            };
+            &offline
+        }
+    };
+
+    let source = match repos.sources.get(source_id).await? {
+        Some(s) => s,
+        None => Source::new(
+            source_id.to_string(),
+            SourceType::Custom,
+            scraper_config.name_or(source_id),
+            scraper_config.base_url_or(""),
+        ),
+    };
+    let refresh_ttl_days = scraper_config
+        .refresh_ttl_days
+        .or(config.default_refresh_ttl_days)
+        .unwrap_or(DEFAULT_REFRESH_TTL_DAYS);
+
+    let scraper = ConfigurableScraper::with_rate_limiter_and_privacy(
+        source,
+        scraper_config.clone(),
+        None,
+        Duration::from_millis(settings.request_delay_ms),
+        refresh_ttl_days,
+        None,
+        Some(privacy),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to create scraper: {}", e))?;
+    let scraper = if !scraper_config.via.is_empty() {
+        let via_mode = scraper_config.via_mode.unwrap_or_default();
+        scraper.with_via_config(scraper_config.via.clone(), via_mode)
+    } else {
+        scraper
+    };
+    Ok(scraper.with_fixtures(store))
+}
@@ -1,59 +1,52 @@
-//! Web server command.
+//! Web server and static site commands.
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
 
 use console::style;
 
-use foia::config::{Config, Settings};
+use foia::config::{Config, ServerConfig, Settings};
 use foia::privacy::{CTorHiddenService, HiddenServiceProvider};
-use foia::repository::migrations;
+use foia::repository::{migrations, shards};
+use foia_server::{PublishOptions, Workspace};
+
+use super::helpers::format_bytes;
 
 /// Start the web server.
+///
+/// Every configured workspace is mounted; `active` is the one served to
+/// clients that have not switched. The hidden service keys live under the
+/// base data directory, as they belong to the server rather than a workspace.
+/// `public` turns on read-only public mode on top of the `[server]` config.
+#[allow(clippy::too_many_arguments)]
 pub async fn cmd_serve(
     settings: &Settings,
     config: &Config,
+    base_dir: &Path,
+    active: &str,
     bind: &str,
     no_migrate: bool,
     no_hidden_service: bool,
     use_arti: bool,
+    public: bool,
 ) -> anyhow::Result<()> {
+    foia::shutdown::install();
     let (host, port) = parse_bind_address(bind)?;
 
-    let repos = settings.repositories()?;
-
-    if no_migrate {
-        // Check schema version but don't migrate
-        match repos.schema_version().await {
-            Ok(Some(version)) => {
-                println!(
-                    "  {} Database schema version: {}",
-                    style("→").cyan(),
-                    version
-                );
-            }
-            Ok(None) => {
-                eprintln!(
-                    "{} Database not initialized. Run 'foia db migrate' first.",
-                    style("!").yellow()
-                );
-                return Err(anyhow::anyhow!("Database not initialized"));
-            }
-            Err(e) => {
-                eprintln!("  {} Failed to check schema: {}", style("!").yellow(), e);
-            }
-        }
-    } else {
-        // Run database migrations
-        println!("{} Running database migrations...", style("→").cyan(),);
-        match migrations::run_migrations(&settings.database_url(), settings.no_tls).await {
-            Ok(()) => {
-                println!("  {} Database ready", style("✓").green(),);
-            }
-            Err(e) => {
-                eprintln!("  {} Migration failed: {}", style("✗").red(), e);
-                return Err(anyhow::anyhow!("Database migration failed: {}", e));
-            }
+    let mut server = config.server.clone();
+    if public {
+        server.public = Some(true);
+    }
+    let workspaces = mounted_workspaces(settings, config, base_dir)?;
+    for workspace in &workspaces {
+        if workspaces.len() > 1 {
+            println!(
+                "{} Workspace {}",
+                style("→").cyan(),
+                style(&workspace.id).bold()
+            );
         }
+        prepare_database(&workspace.settings, no_migrate).await?;
     }
 
     // Determine hidden service configuration
@@ -72,6 +65,32 @@ pub async fn cmd_serve(
         return Err(anyhow::anyhow!("Invalid hidden service configuration"));
     }
 
+    // Reachable from other machines, API writes always need a key
+    let local_only =
+        !hs_config.is_enabled() && host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    if !local_only {
+        if server.require_api_keys == Some(false) {
+            eprintln!(
+                "{} Ignoring server.require_api_keys = false: the server is reachable from other machines",
+                style("!").yellow()
+            );
+        }
+        server = server.exposed();
+    }
+    if server.is_public() {
+        println!(
+            "{} Public mode: read-only, {} requests/min per client",
+            style("→").cyan(),
+            server.public_rate_limit()
+        );
+    } else if server.requires_api_keys() {
+        println!(
+            "{} API writes require a key (foia api-key create), {} requests/min per key by default",
+            style("→").cyan(),
+            server.api_rate_limit()
+        );
+    }
+
     // Show security warning for non-secure configurations
     hs_config
         .enforce_security_warning(config.privacy.warning_delay)
@@ -87,15 +106,24 @@ pub async fn cmd_serve(
             port
         );
         println!("  Press Ctrl+C to stop");
-        return foia_server::serve(settings, &host, port).await;
+        return foia_server::serve_workspaces(&workspaces, active, &host, port, &server).await;
     }
 
     match hs_config.provider {
         HiddenServiceProvider::CTor => {
-            start_with_ctor(settings, config, &hs_config, &host, port).await
+            start_with_ctor(
+                settings,
+                &workspaces,
+                active,
+                &hs_config,
+                &host,
+                port,
+                &server,
+            )
+            .await
         }
         HiddenServiceProvider::Arti => {
-            start_with_arti(settings, config, &hs_config, &host, port).await
+            start_with_arti(&workspaces, active, &hs_config, &host, port, &server).await
         }
         HiddenServiceProvider::None => {
             unreachable!("already handled by is_enabled() check")
@@ -103,13 +131,83 @@ pub async fn cmd_serve(
     }
 }
 
+/// Settings for every configured workspace, default first.
+fn mounted_workspaces(
+    settings: &Settings,
+    config: &Config,
+    base_dir: &Path,
+) -> anyhow::Result<Vec<Workspace>> {
+    config
+        .workspace_ids()
+        .into_iter()
+        .map(|id| {
+            let settings = config
+                .workspace_settings(&id, settings, base_dir)
+                .map_err(anyhow::Error::msg)?;
+            let name = config
+                .workspaces
+                .get(&id)
+                .map(|ws| ws.name_or(&id).to_string())
+                .unwrap_or_else(|| id.clone());
+            Ok(Workspace { id, name, settings })
+        })
+        .collect()
+}
+
+/// Migrate a workspace database, or with `no_migrate` just check it exists.
+async fn prepare_database(settings: &Settings, no_migrate: bool) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+
+    if no_migrate {
+        // Check schema version but don't migrate
+        match repos.schema_version().await {
+            Ok(Some(version)) => {
+                println!(
+                    "  {} Database schema version: {}",
+                    style("→").cyan(),
+                    version
+                );
+            }
+            Ok(None) => {
+                eprintln!(
+                    "{} Database not initialized. Run 'foia db migrate' first.",
+                    style("!").yellow()
+                );
+                return Err(anyhow::anyhow!("Database not initialized"));
+            }
+            Err(e) => {
+                eprintln!("  {} Failed to check schema: {}", style("!").yellow(), e);
+            }
+        }
+    } else {
+        // Run database migrations
+        println!("{} Running database migrations...", style("→").cyan(),);
+        let migrated = async {
+            migrations::run_migrations(&settings.database_url(), settings.no_tls).await?;
+            shards::prepare_shards(&settings.shard_databases()).await
+        };
+        match migrated.await {
+            Ok(()) => {
+                println!("  {} Database ready", style("✓").green(),);
+            }
+            Err(e) => {
+                eprintln!("  {} Migration failed: {}", style("✗").red(), e);
+                return Err(anyhow::anyhow!("Database migration failed: {}", e));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Start server with C-Tor hidden service.
 async fn start_with_ctor(
     settings: &Settings,
-    _config: &Config,
+    workspaces: &[Workspace],
+    active: &str,
     hs_config: &foia::privacy::HiddenServiceConfig,
     host: &str,
     port: u16,
+    server: &ServerConfig,
 ) -> anyhow::Result<()> {
     // Check if C-Tor is available
     if !CTorHiddenService::is_available(hs_config) {
@@ -176,7 +274,7 @@ async fn start_with_ctor(
     println!();
 
     // Start the actual server
-    let result = foia_server::serve(settings, host, port).await;
+    let result = foia_server::serve_workspaces(workspaces, active, host, port, server).await;
 
     // Shutdown hidden service when server stops
     hs.shutdown();
@@ -186,11 +284,12 @@ async fn start_with_ctor(
 
 /// Start server with Arti hidden service (experimental).
 async fn start_with_arti(
-    settings: &Settings,
-    _config: &Config,
+    workspaces: &[Workspace],
+    active: &str,
     _hs_config: &foia::privacy::HiddenServiceConfig,
     host: &str,
     port: u16,
+    server: &ServerConfig,
 ) -> anyhow::Result<()> {
     // Arti hidden service support is experimental and not yet implemented
     // For now, we just show a message and fall back to clearnet
@@ -211,7 +310,60 @@ async fn start_with_arti(
         port
     );
     println!("  Press Ctrl+C to stop");
-    foia_server::serve(settings, host, port).await
+    foia_server::serve_workspaces(workspaces, active, host, port, server).await
+}
+
+/// Render the archive as a static HTML site in `out`.
+pub async fn cmd_publish_static(
+    settings: &Settings,
+    out: &Path,
+    options: &PublishOptions,
+) -> anyhow::Result<()> {
+    if !settings.database_exists() {
+        anyhow::bail!("System not initialized. Run 'foia init' first.");
+    }
+    println!(
+        "{} Publishing static site to {}",
+        style("→").cyan(),
+        out.display()
+    );
+    let summary = foia_server::publish_static(settings, out, options).await?;
+
+    println!(
+        "{} Published {} documents ({} pages)",
+        style("✓").green(),
+        summary.documents,
+        summary.pages
+    );
+    if options.include_files {
+        println!(
+            "  Copied {} files ({})",
+            summary.files,
+            format_bytes(summary.file_bytes)
+        );
+        if summary.redacted > 0 {
+            println!(
+                "  {} documents were published as their redacted copy",
+                summary.redacted
+            );
+        }
+    }
+    if summary.withheld > 0 {
+        println!(
+            "{} {} documents with open flags were left out (see /flags, or pass --include-flagged)",
+            style("!").yellow(),
+            summary.withheld
+        );
+    }
+    if summary.missing_files > 0 {
+        println!(
+            "{} {} files were missing from disk and are not linked",
+            style("!").yellow(),
+            summary.missing_files
+        );
+    }
+    println!("  Open {}", out.join("index.html").display());
+    Ok(())
 }
 
 /// Parse a bind address that can be:
@@ -144,7 +144,7 @@ pub async fn cmd_crawl(settings: &Settings, source_id: &str, _limit: usize) -> a
 
     // Load scraper config from database (server config)
     let repos = settings.repositories()?;
-    let scraper_config = match repos.scraper_configs.get(source_id).await? {
+    let mut scraper_config = match repos.scraper_configs.get(source_id).await? {
         Some(c) => c,
         None => {
             println!(
@@ -216,6 +216,7 @@ pub async fn cmd_crawl(settings: &Settings, source_id: &str, _limit: usize) -> a
     }
 
     // Create scraper for discovery
+    config.apply_politeness_defaults(&mut scraper_config);
     let refresh_ttl_days = scraper_config
         .refresh_ttl_days
         .or(config.default_refresh_ttl_days)
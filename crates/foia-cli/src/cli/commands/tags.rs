@@ -0,0 +1,185 @@
+//! Tag maintenance commands.
+
+use std::io::{self, Write};
+
+use console::style;
+
+use foia::config::Settings;
+use foia::repository::diesel_document::BrowseParams;
+
+/// Document filter for bulk tag add/remove.
+pub struct TagFilter {
+    pub source: Option<String>,
+    pub tags: Vec<String>,
+    pub record_types: Vec<String>,
+    pub query: Option<String>,
+}
+
+impl TagFilter {
+    fn is_empty(&self) -> bool {
+        self.source.is_none()
+            && self.tags.is_empty()
+            && self.record_types.is_empty()
+            && self.query.is_none()
+    }
+
+    fn params(&self) -> BrowseParams<'_> {
+        BrowseParams {
+            source_id: self.source.as_deref(),
+            tags: &self.tags,
+            record_types: &self.record_types,
+            search_query: self.query.as_deref(),
+            ..Default::default()
+        }
+    }
+}
+
+fn confirm_prompt() -> anyhow::Result<bool> {
+    print!("\nProceed? [y/N] ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        Ok(true)
+    } else {
+        println!("{} Cancelled", style("!").yellow());
+        Ok(false)
+    }
+}
+
+/// List all tags with document counts.
+pub async fn cmd_tags_list(settings: &Settings) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+    let mut tags = doc_repo.get_tag_counts(None).await?;
+
+    if tags.is_empty() {
+        println!("{} No tags found", style("!").yellow());
+        return Ok(());
+    }
+
+    tags.sort_by(|a, b| a.0.cmp(&b.0));
+    println!("\n{:<50} Documents", "Tag");
+    println!("{}", "-".repeat(60));
+    for (tag, count) in tags {
+        println!("{:<50} {}", tag, count);
+    }
+
+    Ok(())
+}
+
+/// Rename a tag, or merge several tags into one.
+pub async fn cmd_tags_merge(
+    settings: &Settings,
+    from: &[String],
+    into: &str,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+
+    let mut total = 0;
+    for tag in from {
+        let count = doc_repo.count_by_tag(tag).await?;
+        println!(
+            "{} '{}' → '{}' ({} documents)",
+            style("→").cyan(),
+            style(tag).yellow(),
+            style(into).green(),
+            count
+        );
+        total += count;
+    }
+
+    if total == 0 {
+        println!("{} No documents carry those tags", style("!").yellow());
+        return Ok(());
+    }
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    let updated = doc_repo.merge_tags(from, into).await?;
+    println!(
+        "\n{} Retagged {} documents as '{}'",
+        style("✓").green(),
+        updated,
+        into
+    );
+
+    Ok(())
+}
+
+/// Remove a tag from every document.
+pub async fn cmd_tags_delete(settings: &Settings, tag: &str, confirm: bool) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+
+    let count = doc_repo.count_by_tag(tag).await?;
+    if count == 0 {
+        println!("{} No documents tagged '{}'", style("!").yellow(), tag);
+        return Ok(());
+    }
+
+    println!(
+        "{} Delete tag '{}' from {} documents",
+        style("→").cyan(),
+        style(tag).yellow(),
+        count
+    );
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    let updated = doc_repo.delete_tag(tag).await?;
+    println!(
+        "\n{} Removed '{}' from {} documents",
+        style("✓").green(),
+        tag,
+        updated
+    );
+
+    Ok(())
+}
+
+/// Add or remove a tag on every document matching a filter.
+pub async fn cmd_tags_apply(
+    settings: &Settings,
+    tag: &str,
+    filter: &TagFilter,
+    remove: bool,
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+
+    if filter.is_empty() && !confirm {
+        println!(
+            "{} No filter given; this will affect every document.",
+            style("!").yellow()
+        );
+    }
+
+    let params = filter.params();
+    let matching = doc_repo.browse_count(&params).await?;
+    println!(
+        "{} {} '{}' {} {} matching documents",
+        style("→").cyan(),
+        if remove { "Remove" } else { "Add" },
+        style(tag).yellow(),
+        if remove { "from" } else { "to" },
+        matching
+    );
+
+    if matching == 0 {
+        return Ok(());
+    }
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    let updated = if remove {
+        doc_repo.remove_tag_matching(tag, &params).await?
+    } else {
+        doc_repo.add_tag_matching(tag, &params).await?
+    };
+    println!("\n{} Updated {} documents", style("✓").green(), updated);
+
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Plain text as a PDF, for reports meant to be printed or attached.
+//!
+//! Lines are set in 10pt Courier on US Letter pages, wrapped at the right
+//! margin. Courier is one of the standard fonts every reader has, so nothing
+//! is embedded; characters outside Latin-1 print as `?`.
+
+/// Points per inch; page geometry is in points.
+const PAGE_WIDTH: u32 = 612;
+const PAGE_HEIGHT: u32 = 792;
+const MARGIN: u32 = 54;
+const FONT_SIZE: u32 = 10;
+const LEADING: u32 = 12;
+/// Courier glyphs are 0.6em wide.
+const CHARS_PER_LINE: usize = ((PAGE_WIDTH - 2 * MARGIN) * 10 / (FONT_SIZE * 6)) as usize;
+const LINES_PER_PAGE: usize = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+
+/// Split `line` into pieces that fit the page, breaking at spaces where it
+/// can.
+fn wrap(line: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest: Vec<char> = line.trim_end().chars().collect();
+    while rest.len() > CHARS_PER_LINE {
+        let cut = rest[..=CHARS_PER_LINE]
+            .iter()
+            .rposition(|c| *c == ' ')
+            .filter(|&i| i > 0)
+            .unwrap_or(CHARS_PER_LINE);
+        pieces.push(rest[..cut].iter().collect());
+        let skip = if rest[cut] == ' ' { cut + 1 } else { cut };
+        rest.drain(..skip);
+    }
+    pieces.push(rest.into_iter().collect());
+    pieces
+}
+
+/// `line` as a PDF string literal in WinAnsi (Latin-1) encoding.
+fn pdf_string(line: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push(b'\\');
+                out.push(c as u8);
+            }
+            ' '..='~' => out.push(c as u8),
+            '\u{a0}'..='\u{ff}' => out.extend(format!("\\{:03o}", c as u32).bytes()),
+            '\t' => out.extend(b"    "),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+/// Render `text` as a PDF document.
+pub fn text_to_pdf(text: &str) -> Vec<u8> {
+    let lines: Vec<String> = text.lines().flat_map(wrap).collect();
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(LINES_PER_PAGE).collect()
+    };
+
+    // Objects 1-3 are the catalog, page tree and font; each page then
+    // takes two, the page and its content stream
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + 2 * i).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                id + 1
+            )
+            .into_bytes(),
+        );
+        let mut content = format!(
+            "BT /F1 {} Tf {} TL {} {} Td\n",
+            FONT_SIZE,
+            LEADING,
+            MARGIN,
+            PAGE_HEIGHT - MARGIN - FONT_SIZE
+        )
+        .into_bytes();
+        for line in page.iter() {
+            content.extend(pdf_string(line));
+            content.extend(b" Tj T*\n");
+        }
+        content.extend(b"ET");
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("short line  "), vec!["short line"]);
+        let long = format!("{} {}", "a".repeat(60), "b".repeat(60));
+        assert_eq!(wrap(&long), vec!["a".repeat(60), "b".repeat(60)]);
+        let unbroken = "c".repeat(CHARS_PER_LINE + 5);
+        assert_eq!(wrap(&unbroken)[1], "c".repeat(5));
+    }
+
+    #[test]
+    fn test_text_to_pdf() {
+        assert_eq!(pdf_string("f(x) é"), b"(f\\(x\\) \\351)".to_vec());
+
+        let text = (0..LINES_PER_PAGE + 1)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let pdf = text_to_pdf(&text);
+        let raw = String::from_utf8_lossy(&pdf);
+        assert!(raw.starts_with("%PDF-1.4"));
+        assert!(raw.contains("/Count 2"));
+
+        // Each xref entry points at its object
+        let xref = raw.rfind("xref\n").unwrap();
+        for (i, entry) in raw[xref..].lines().skip(3).take(7).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(raw[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
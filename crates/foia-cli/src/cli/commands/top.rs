@@ -0,0 +1,289 @@
+//! Live monitoring view for long-running crawls.
+
+use std::collections::VecDeque;
+use std::io::{stdout, Stdout};
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use crossterm::ExecutableCommand;
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Sparkline, Table};
+
+use foia::config::Settings;
+use foia::models::{CrawlRequest, CrawlUrl};
+use foia::repository::diesel_crawl::{DomainRateLimit, RequestLogFilter};
+use foia::repository::Repositories;
+use foia::utils::format_size;
+
+use super::helpers::truncate;
+
+/// Retry interval for failed OCR, as in `foia analyze`.
+const OCR_RETRY_HOURS: u32 = 12;
+
+/// Throughput samples kept for the sparkline.
+const HISTORY_LEN: usize = 120;
+
+/// Rows shown in each list.
+const LIST_LIMIT: u32 = 20;
+
+/// One refresh worth of monitoring data.
+struct TopData {
+    requests_per_min: u64,
+    bytes_per_min: u64,
+    pending_downloads: u64,
+    ocr_documents: u64,
+    ocr_pages: u64,
+    domains: Vec<DomainRateLimit>,
+    fetching: Vec<CrawlUrl>,
+    errors: Vec<CrawlRequest>,
+    last_updated: String,
+}
+
+async fn fetch_top_data(repos: &Repositories) -> anyhow::Result<TopData> {
+    let (requests_per_min, bytes_per_min) = repos
+        .crawl
+        .get_request_throughput(Utc::now() - chrono::Duration::minutes(1))
+        .await?;
+    let errors = repos
+        .crawl
+        .list_requests(
+            &RequestLogFilter {
+                errors_only: true,
+                ..Default::default()
+            },
+            LIST_LIMIT,
+        )
+        .await?;
+
+    Ok(TopData {
+        requests_per_min,
+        bytes_per_min,
+        pending_downloads: repos.crawl.count_pending_downloads().await? as u64,
+        ocr_documents: repos
+            .documents
+            .count_needing_analysis("ocr", None, None, OCR_RETRY_HOURS)
+            .await?,
+        ocr_pages: repos.documents.count_pages_needing_ocr().await?,
+        domains: repos.crawl.get_domain_rate_limits().await?,
+        fetching: repos.crawl.get_fetching_urls(LIST_LIMIT).await?,
+        errors,
+        last_updated: Local::now().format("%H:%M:%S").to_string(),
+    })
+}
+
+/// Show crawl throughput, rate limits, downloads, OCR backlog and errors,
+/// refreshing every `interval` seconds until 'q' is pressed.
+pub async fn cmd_top(settings: &Settings, interval: u64) -> anyhow::Result<()> {
+    if !settings.database_exists() {
+        anyhow::bail!("System not initialized. Run 'foia init' first.");
+    }
+    let repos = settings.repositories()?;
+    let mut data = fetch_top_data(&repos).await?;
+    let mut history = VecDeque::with_capacity(HISTORY_LEN);
+    history.push_back(data.requests_per_min);
+
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+    let result = run_top_loop(&mut terminal, &repos, &mut data, &mut history, interval).await;
+
+    disable_raw_mode()?;
+    stdout().execute(LeaveAlternateScreen)?;
+
+    result
+}
+
+async fn run_top_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    repos: &Repositories,
+    data: &mut TopData,
+    history: &mut VecDeque<u64>,
+    interval: u64,
+) -> anyhow::Result<()> {
+    let refresh_duration = Duration::from_secs(interval.max(1));
+    let poll_duration = Duration::from_millis(100);
+
+    loop {
+        terminal.draw(|frame| draw_top(frame, data, history))?;
+
+        let deadline = tokio::time::Instant::now() + refresh_duration;
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            if event::poll(remaining.min(poll_duration))? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        match key.code {
+                            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                            KeyCode::Char('c')
+                                if key.modifiers.contains(event::KeyModifiers::CONTROL) =>
+                            {
+                                return Ok(())
+                            }
+                            KeyCode::Char('r') => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        // Keep showing the last data if a refresh fails
+        if let Ok(new_data) = fetch_top_data(repos).await {
+            *data = new_data;
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(data.requests_per_min);
+        }
+    }
+}
+
+fn section(title: &str) -> Block<'_> {
+    Block::default()
+        .title(title)
+        .title_style(Style::default().fg(Color::Cyan).bold())
+        .borders(Borders::TOP)
+}
+
+fn header_row<'a>(titles: &[&'a str]) -> Row<'a> {
+    Row::new(
+        titles
+            .iter()
+            .map(|h| Cell::from(*h).style(Style::default().bold())),
+    )
+}
+
+fn draw_top(frame: &mut Frame, data: &TopData, history: &VecDeque<u64>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),  // Header
+            Constraint::Length(6),  // Throughput and queues
+            Constraint::Min(8),     // Domains and downloads
+            Constraint::Length(12), // Errors
+            Constraint::Length(1),  // Footer
+        ])
+        .split(frame.area());
+
+    let header = Paragraph::new(format!("foia top    updated {}", data.last_updated))
+        .style(Style::default().bold());
+    frame.render_widget(header, chunks[0]);
+
+    // Throughput and queues
+    let top = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+    let sparkline = Sparkline::default()
+        .block(section(" THROUGHPUT "))
+        .data(history.iter().copied())
+        .style(Style::default().fg(Color::Green));
+    let throughput = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(top[0]);
+    frame.render_widget(sparkline, throughput[0]);
+    frame.render_widget(
+        Paragraph::new(format!(
+            "  {} requests/min, {}/min",
+            data.requests_per_min,
+            format_size(data.bytes_per_min)
+        )),
+        throughput[1],
+    );
+
+    let queues = Paragraph::new(format!(
+        "  Download queue: {:>10}\n  OCR documents:  {:>10}\n  OCR pages:      {:>10}",
+        data.pending_downloads, data.ocr_documents, data.ocr_pages,
+    ))
+    .block(section(" QUEUES "));
+    frame.render_widget(queues, top[1]);
+
+    // Per-domain delays and active downloads
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .split(chunks[2]);
+
+    let domain_rows = data.domains.iter().map(|d| {
+        let state = if d.in_backoff {
+            Cell::from("backoff").style(Style::default().fg(Color::Red))
+        } else {
+            Cell::from("ok").style(Style::default().fg(Color::Green))
+        };
+        Row::new([
+            Cell::from(truncate(&d.domain, 28)),
+            Cell::from(format!("{:.1}s", d.current_delay_ms as f64 / 1000.0)),
+            state,
+            Cell::from(d.rate_limit_hits.to_string()),
+        ])
+    });
+    let domains = Table::new(
+        domain_rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(6),
+        ],
+    )
+    .header(header_row(&["Domain", "Delay", "State", "429s"]))
+    .block(section(" DOMAINS "));
+    frame.render_widget(domains, middle[0]);
+
+    let fetching_rows = data.fetching.iter().map(|url| {
+        Row::new([
+            Cell::from(truncate(&url.source_id, 16)),
+            Cell::from(truncate(&url.url, 80)),
+        ])
+    });
+    let fetching = Table::new(fetching_rows, [Constraint::Length(16), Constraint::Min(20)])
+        .header(header_row(&["Source", "URL"]))
+        .block(section(" DOWNLOADING "));
+    frame.render_widget(fetching, middle[1]);
+
+    // Recent errors
+    let error_rows = data.errors.iter().map(|request| {
+        let status = request
+            .response_status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let detail = request.error.as_deref().unwrap_or(&request.url);
+        Row::new([
+            Cell::from(
+                request
+                    .request_at
+                    .with_timezone(&Local)
+                    .format("%H:%M:%S")
+                    .to_string(),
+            ),
+            Cell::from(status).style(Style::default().fg(Color::Red)),
+            Cell::from(truncate(&request.source_id, 16)),
+            Cell::from(truncate(detail, 100)),
+        ])
+    });
+    let errors = Table::new(
+        error_rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(5),
+            Constraint::Length(16),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header_row(&["Time", "Code", "Source", "Error"]))
+    .block(section(" RECENT ERRORS "));
+    frame.render_widget(errors, chunks[3]);
+
+    let footer = Paragraph::new("Press 'q' to quit, 'r' to refresh")
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(footer, chunks[4]);
+}
@@ -0,0 +1,140 @@
+//! Crawl queue (frontier) management commands.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::models::{CrawlUrl, DiscoveryMethod, UrlStatus};
+
+/// List a source's crawl URLs, optionally filtered by status.
+pub async fn cmd_urls_list(
+    settings: &Settings,
+    source_id: &str,
+    status: &str,
+    limit: u32,
+) -> anyhow::Result<()> {
+    let Some(statuses) = UrlStatus::parse_filter(status) else {
+        anyhow::bail!(
+            "Unknown status '{}' (expected pending, failed, skipped, fetched or all)",
+            status
+        );
+    };
+    let crawl_repo = settings.repositories()?.crawl;
+    let urls = crawl_repo
+        .list_urls(source_id, &statuses, None, limit)
+        .await?
+        .items;
+
+    if urls.is_empty() {
+        println!(
+            "{} No {} URLs for source '{}'",
+            style("!").yellow(),
+            status,
+            source_id
+        );
+        return Ok(());
+    }
+
+    println!("\n{:<10} {:>7}  URL", "Status", "Retries");
+    println!("{}", "-".repeat(80));
+    for u in &urls {
+        println!("{:<10} {:>7}  {}", u.status.as_str(), u.retry_count, u.url);
+        if let Some(ref error) = u.last_error {
+            println!("{:<19} {}", "", style(error).dim());
+        }
+    }
+    if urls.len() == limit as usize {
+        println!(
+            "\n{} Showing first {} (use --limit to see more)",
+            style("→").cyan(),
+            limit
+        );
+    }
+
+    Ok(())
+}
+
+/// Re-queue individual URLs, clearing their retry state.
+pub async fn cmd_urls_retry(
+    settings: &Settings,
+    source_id: &str,
+    urls: &[String],
+) -> anyhow::Result<()> {
+    let crawl_repo = settings.repositories()?.crawl;
+    for url in urls {
+        if crawl_repo.retry_url(source_id, url).await? {
+            println!("{} Re-queued {}", style("✓").green(), url);
+        } else {
+            println!("{} Not in queue: {}", style("!").yellow(), url);
+        }
+    }
+    Ok(())
+}
+
+/// Take individual URLs out of the queue.
+pub async fn cmd_urls_cancel(
+    settings: &Settings,
+    source_id: &str,
+    urls: &[String],
+) -> anyhow::Result<()> {
+    let crawl_repo = settings.repositories()?.crawl;
+    for url in urls {
+        if crawl_repo.cancel_url(source_id, url).await? {
+            println!("{} Skipped {}", style("✓").green(), url);
+        } else {
+            println!("{} Not in queue: {}", style("!").yellow(), url);
+        }
+    }
+    Ok(())
+}
+
+/// Re-queue every failed URL, for one source or all of them.
+pub async fn cmd_urls_retry_failed(
+    settings: &Settings,
+    source_id: Option<&str>,
+) -> anyhow::Result<()> {
+    let crawl_repo = settings.repositories()?.crawl;
+    let count = crawl_repo.reset_failed_urls(source_id).await?;
+    println!(
+        "{} Re-queued {} failed URL{}",
+        style("✓").green(),
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
+
+/// Add seed URLs to a source's queue.
+pub async fn cmd_urls_add(
+    settings: &Settings,
+    source_id: &str,
+    urls: &[String],
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    if repos.sources.get(source_id).await?.is_none() {
+        anyhow::bail!("Source '{}' not found", source_id);
+    }
+
+    for url in urls {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            println!("{} Not an http(s) URL: {}", style("!").yellow(), url);
+            continue;
+        }
+        let crawl_url = CrawlUrl::new(
+            url.clone(),
+            source_id.to_string(),
+            DiscoveryMethod::Seed,
+            None,
+            0,
+        );
+        if repos.crawl.add_url(&crawl_url).await? {
+            println!("{} Added {}", style("✓").green(), url);
+        } else {
+            println!(
+                "{} Already known: {} (use `urls retry` to re-queue)",
+                style("!").yellow(),
+                url
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Saved view commands: listing views and managing alert subscriptions.
+
+use chrono::Utc;
+use console::style;
+
+use foia::config::{Config, Settings};
+use foia::services::saved_search_alerts;
+
+use super::helpers::truncate;
+
+/// List saved views with their subscribers.
+pub async fn cmd_views_list(settings: &Settings) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let views = repos.saved_views.list().await?;
+    if views.is_empty() {
+        println!("No saved views; save one from the browse page");
+        return Ok(());
+    }
+    let subscriptions = repos.saved_views.list_subscriptions().await?;
+
+    println!("{:<24}  {:<40}  Subscribers", "Name", "Filters");
+    for view in &views {
+        let subscribers: Vec<&str> = subscriptions
+            .iter()
+            .filter(|s| s.view_name == view.name)
+            .map(|s| s.email.as_str())
+            .collect();
+        println!(
+            "{:<24}  {:<40}  {}",
+            truncate(&view.name, 24),
+            truncate(&view.query, 40),
+            subscribers.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Alert `email` about new documents matching a view.
+pub async fn cmd_views_subscribe(
+    settings: &Settings,
+    view: &str,
+    email: &str,
+) -> anyhow::Result<()> {
+    if !email.contains('@') {
+        anyhow::bail!("'{}' is not an email address", email);
+    }
+    let repos = settings.repositories()?;
+    if repos.saved_views.get(view).await?.is_none() {
+        anyhow::bail!("No saved view named '{}'", view);
+    }
+    if repos.saved_views.subscribe(view, email).await? {
+        println!(
+            "{} {} will be alerted about new documents matching '{}'",
+            style("✓").green(),
+            email,
+            view
+        );
+    } else {
+        println!("{} is already subscribed to '{}'", email, view);
+    }
+    Ok(())
+}
+
+/// Stop alerting `email` about a view.
+pub async fn cmd_views_unsubscribe(
+    settings: &Settings,
+    view: &str,
+    email: &str,
+) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    if repos.saved_views.unsubscribe(view, email).await? {
+        println!(
+            "{} Unsubscribed {} from '{}'",
+            style("✓").green(),
+            email,
+            view
+        );
+    } else {
+        println!("{} is not subscribed to '{}'", email, view);
+    }
+    Ok(())
+}
+
+/// Email subscribers about documents newly matching their views. `scrape`
+/// does this after each crawl; this runs it on demand.
+pub async fn cmd_views_alert(settings: &Settings, dry_run: bool) -> anyhow::Result<()> {
+    let config = Config::load().await.email;
+    let repos = settings.repositories()?;
+
+    if dry_run {
+        let alerts = saved_search_alerts::pending_alerts(&repos, Utc::now()).await?;
+        if alerts.is_empty() {
+            println!("{} No new matches to alert about", style("✓").green());
+            return Ok(());
+        }
+        for alert in &alerts {
+            let email = saved_search_alerts::alert_email(alert, &config);
+            println!("To: {}", alert.email);
+            println!("Subject: {}\n", email.subject);
+            println!("{}", email.body);
+        }
+        println!(
+            "{} {} alerts pending (dry run, nothing sent)",
+            style("!").yellow(),
+            alerts.len()
+        );
+        return Ok(());
+    }
+
+    match saved_search_alerts::send_alerts(&repos, &config, &settings.privacy).await {
+        Ok(0) => println!("{} No new matches to alert about", style("✓").green()),
+        Ok(sent) => println!("{} Sent {} alerts", style("✓").green(), sent),
+        Err(errors) => {
+            for e in &errors {
+                eprintln!("{} {}", style("✗").red(), e);
+            }
+            anyhow::bail!("Failed to send some alerts");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Wayback Machine fallback commands.
+
+use console::style;
+
+use foia::config::Settings;
+use foia::models::FallbackState;
+use foia_scrape::services::wayback;
+
+/// List wayback captures found for dead URLs.
+pub async fn cmd_wayback_list(
+    settings: &Settings,
+    source_id: Option<&str>,
+    state: &str,
+) -> anyhow::Result<()> {
+    let state = match state {
+        "all" => None,
+        s => Some(FallbackState::from_str(s).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown state '{}' (expected offered, queued, ingested, dismissed or all)",
+                s
+            )
+        })?),
+    };
+
+    let repos = settings.repositories()?;
+    let snapshots = repos
+        .crawl
+        .list_fallback_snapshots(source_id, state)
+        .await?;
+    if snapshots.is_empty() {
+        println!("{} No wayback captures", style("!").yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{:>6}  {:<10} {:<16} {:<9} Original URL",
+        "ID", "Captured", "Source", "State"
+    );
+    for s in &snapshots {
+        println!(
+            "{:>6}  {:<10} {:<16} {:<9} {}",
+            s.id,
+            s.captured_at.get(..10).unwrap_or(&s.captured_at),
+            s.fallback_source().unwrap_or_default(),
+            s.fallback_state().map(|st| st.as_str()).unwrap_or("-"),
+            s.original_url
+        );
+    }
+    println!(
+        "\n{} Ingest with `foia wayback ingest <ID>...` or `foia wayback ingest --all`",
+        style("→").cyan()
+    );
+    Ok(())
+}
+
+/// Queue offered captures for download as new versions of their documents.
+pub async fn cmd_wayback_ingest(
+    settings: &Settings,
+    ids: &[i32],
+    all: bool,
+    source_id: Option<&str>,
+) -> anyhow::Result<()> {
+    if ids.is_empty() && !all {
+        anyhow::bail!("Give capture IDs or --all");
+    }
+
+    let repos = settings.repositories()?;
+    let snapshots = if all {
+        repos
+            .crawl
+            .list_fallback_snapshots(source_id, Some(FallbackState::Offered))
+            .await?
+    } else {
+        let mut snapshots = Vec::with_capacity(ids.len());
+        for &id in ids {
+            match repos.crawl.get_archive_snapshot(id).await? {
+                Some(s) => snapshots.push(s),
+                None => println!("{} No capture with ID {}", style("!").yellow(), id),
+            }
+        }
+        snapshots
+    };
+
+    let mut queued = 0;
+    for snapshot in &snapshots {
+        if wayback::ingest_fallback(&repos.crawl, snapshot).await? {
+            queued += 1;
+        } else {
+            println!(
+                "{} Capture {} is not a fallback offer",
+                style("!").yellow(),
+                snapshot.id
+            );
+        }
+    }
+    println!(
+        "{} Queued {} capture{} for download",
+        style("✓").green(),
+        queued,
+        if queued == 1 { "" } else { "s" }
+    );
+    if queued > 0 {
+        println!("  {} Run 'foia download' to fetch them", style("→").dim());
+    }
+    Ok(())
+}
+
+/// Decline captures so they are no longer offered.
+pub async fn cmd_wayback_dismiss(settings: &Settings, ids: &[i32]) -> anyhow::Result<()> {
+    let repos = settings.repositories()?;
+    let mut dismissed = 0;
+    for &id in ids {
+        if repos
+            .crawl
+            .set_fallback_state(id, FallbackState::Dismissed)
+            .await?
+        {
+            dismissed += 1;
+        } else {
+            println!("{} No capture with ID {}", style("!").yellow(), id);
+        }
+    }
+    println!(
+        "{} Dismissed {} capture{}",
+        style("✓").green(),
+        dismissed,
+        if dismissed == 1 { "" } else { "s" }
+    );
+    Ok(())
+}
@@ -0,0 +1,230 @@
+//! Queue worker: receive tasks from the broker and run them.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use console::style;
+use tokio::sync::mpsc;
+
+use foia::config::{Config, Settings};
+use foia::privacy::PrivacyConfig;
+use foia::shutdown;
+use foia::work_queue::broker::{Broker, Task, TaskKind, MAX_ATTEMPTS};
+use foia_analysis::services::AnalysisService;
+use foia_annotate::services::{AnnotationEvent, AnnotationManager, Annotator, LlmAnnotator};
+use foia_scrape::services::download::{DownloadEvent, DownloadService};
+
+use super::llm::usage_tracker;
+use super::queue::{connect, parse_kind};
+use super::scrape::download_config;
+
+/// Services that carry out each kind of task.
+struct Runners {
+    analysis: AnalysisService,
+    annotations: AnnotationManager,
+    annotator: LlmAnnotator,
+    downloads: DownloadService,
+    download_workers: usize,
+}
+
+impl Runners {
+    /// Run a task, returning a one-line summary for the log.
+    async fn run(&self, task: &Task) -> anyhow::Result<String> {
+        match task.kind {
+            TaskKind::Download => {
+                // Events are for progress bars; the worker only logs results
+                let (event_tx, mut event_rx) = mpsc::channel::<DownloadEvent>(100);
+                let drain = tokio::spawn(async move { while event_rx.recv().await.is_some() {} });
+                let result = self
+                    .downloads
+                    .download(
+                        task.source_id.as_deref(),
+                        self.download_workers,
+                        task.limit,
+                        event_tx,
+                    )
+                    .await;
+                let _ = drain.await;
+                let result = result?;
+                // The unfinished URLs are still pending; redeliver the task
+                // so another worker picks them up
+                if result.interrupted {
+                    anyhow::bail!("interrupted by shutdown");
+                }
+                Ok(format!(
+                    "{} downloaded, {} unchanged, {} failed",
+                    result.downloaded + result.deduplicated,
+                    result.skipped,
+                    result.failed
+                ))
+            }
+            TaskKind::Ocr => {
+                let id = document_id(task)?;
+                let pages = self.analysis.ocr_document(id).await?;
+                Ok(format!("{} pages OCR'd", pages))
+            }
+            TaskKind::Summarize => {
+                let id = document_id(task)?;
+                let (event_tx, _event_rx) = mpsc::channel::<AnnotationEvent>(100);
+                self.annotations
+                    .process_single(&self.annotator, id, event_tx)
+                    .await?;
+                Ok("summarized".to_string())
+            }
+        }
+    }
+}
+
+fn document_id(task: &Task) -> anyhow::Result<&str> {
+    task.document_id
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("{} task has no document", task.kind.as_str()))
+}
+
+/// Process tasks from the broker until interrupted.
+///
+/// Takes one task at a time, cycling through `queues` so that a long
+/// backlog in one does not starve the others. The first SIGINT/SIGTERM
+/// finishes the current task and exits; a second exits immediately,
+/// leaving the task to be redelivered.
+pub async fn cmd_worker(
+    settings: &Settings,
+    queues: &[String],
+    workers: usize,
+    poll: u64,
+    privacy_config: &PrivacyConfig,
+) -> anyhow::Result<()> {
+    let kinds = if queues.is_empty() {
+        TaskKind::ALL.to_vec()
+    } else {
+        queues
+            .iter()
+            .map(|q| parse_kind(q.trim()))
+            .collect::<anyhow::Result<Vec<_>>>()?
+    };
+
+    settings.ensure_directories()?;
+    let config = Config::load().await;
+    foia_analysis::ocr::configure_pdf_engine(&config.analysis.pdf);
+    let broker: Arc<dyn Broker> = Arc::from(connect(settings).await?);
+    let repos = settings.repositories()?;
+
+    let mut downloads = DownloadService::new(
+        Arc::new(repos.documents.clone()),
+        Arc::new(repos.crawl.clone()),
+        download_config(
+            settings,
+            &config,
+            privacy_config,
+            4,
+            &repos.sources.get_all().await?,
+        ),
+    );
+    if config.download.enqueue_ocr {
+        downloads = downloads.with_ocr_queue(broker.clone());
+    }
+
+    let mut annotator = LlmAnnotator::new(config.llm.clone());
+    if kinds.contains(&TaskKind::Summarize) {
+        if !config.llm.enabled() {
+            anyhow::bail!("LLM annotation is disabled; set llm.enabled = true or drop summarize from --queues");
+        }
+        if !annotator.is_available().await {
+            anyhow::bail!("{}", annotator.llm_config().availability_hint());
+        }
+        // The worker's lifetime is one run for the per-run budget
+        annotator =
+            annotator.with_usage_tracker(usage_tracker(&repos.llm_usage, &config.llm).await?);
+    }
+
+    let runners = Runners {
+        analysis: AnalysisService::with_ocr_config(
+            repos.documents.clone(),
+            config.analysis.ocr.clone(),
+            settings.documents_dir.clone(),
+        )
+        .with_page_images(&config.analysis.page_images)
+        .with_limits(&config.analysis.limits)
+        .with_pool(&config.analysis.pool),
+        annotations: AnnotationManager::new(repos.documents.clone()),
+        annotator,
+        downloads,
+        download_workers: workers.max(1),
+    };
+
+    shutdown::install();
+
+    let names: Vec<_> = kinds.iter().map(|k| k.as_str()).collect();
+    println!(
+        "{} Worker started on {} ({})",
+        style("→").cyan(),
+        broker.name(),
+        names.join(", ")
+    );
+
+    while !shutdown::is_requested() {
+        let mut idle = true;
+        for &kind in &kinds {
+            if shutdown::is_requested() {
+                break;
+            }
+            let handle = match broker.receive(kind).await {
+                Ok(Some(handle)) => handle,
+                Ok(None) => continue,
+                Err(e) => {
+                    tracing::warn!("Failed to receive from {}: {}", kind.queue(), e);
+                    continue;
+                }
+            };
+            idle = false;
+
+            let task = handle.item.clone();
+            let label = task
+                .document_id
+                .as_deref()
+                .or(task.source_id.as_deref())
+                .unwrap_or("all sources");
+            match runners.run(&task).await {
+                Ok(summary) => {
+                    println!(
+                        "{} {} {}: {}",
+                        style("✓").green(),
+                        kind.as_str(),
+                        label,
+                        summary
+                    );
+                    broker.ack(handle).await?;
+                }
+                Err(e) => {
+                    let error = e.to_string();
+                    let outcome = if task.attempts < MAX_ATTEMPTS {
+                        "will retry"
+                    } else {
+                        "giving up"
+                    };
+                    println!(
+                        "{} {} {} (attempt {}/{}, {}): {}",
+                        style("✗").red(),
+                        kind.as_str(),
+                        label,
+                        task.attempts,
+                        MAX_ATTEMPTS,
+                        outcome,
+                        error
+                    );
+                    broker.nack(handle, &error, true).await?;
+                }
+            }
+        }
+
+        if idle {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(poll)) => {}
+                _ = shutdown::requested() => {}
+            }
+        }
+    }
+
+    println!("{} Worker stopped", style("✓").green());
+    Ok(())
+}
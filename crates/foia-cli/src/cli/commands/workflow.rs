@@ -0,0 +1,146 @@
+//! Document status workflow commands.
+
+use std::io::{self, Write};
+
+use console::style;
+
+use foia::config::Settings;
+use foia::models::DocumentStatus;
+
+fn confirm_prompt() -> anyhow::Result<bool> {
+    print!("\nProceed? [y/N] ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if input.trim().eq_ignore_ascii_case("y") {
+        Ok(true)
+    } else {
+        println!("{} Cancelled", style("!").yellow());
+        Ok(false)
+    }
+}
+
+fn parse_status(name: &str) -> anyhow::Result<DocumentStatus> {
+    DocumentStatus::from_str(name).ok_or_else(|| {
+        let names: Vec<&str> = DocumentStatus::ALL.iter().map(|s| s.label()).collect();
+        anyhow::anyhow!(
+            "Unknown status '{}' (expected one of: {})",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+fn labels(statuses: &[DocumentStatus]) -> String {
+    if statuses.is_empty() {
+        return "-".to_string();
+    }
+    statuses
+        .iter()
+        .map(|s| s.label())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Show document counts per status and where each status may move.
+pub async fn cmd_workflow_status(
+    settings: &Settings,
+    source_id: Option<&str>,
+) -> anyhow::Result<()> {
+    let doc_repo = settings.repositories()?.documents;
+    let counts = doc_repo.count_by_status(source_id).await?;
+    let workflow = doc_repo.workflow();
+
+    println!("\n{:<14} {:>10}  Moves to", "Status", "Documents");
+    println!("{}", "-".repeat(60));
+    for status in DocumentStatus::ALL {
+        let count = counts.get(status.as_str()).copied().unwrap_or(0);
+        println!(
+            "{:<14} {:>10}  {}",
+            status.label(),
+            count,
+            labels(&workflow.next(status))
+        );
+    }
+
+    Ok(())
+}
+
+/// Move documents to another status, either every document in `from` or
+/// the listed documents. Moves the workflow doesn't allow are skipped.
+pub async fn cmd_workflow_move(
+    settings: &Settings,
+    to: &str,
+    from: Option<&str>,
+    source_id: Option<&str>,
+    ids: &[String],
+    confirm: bool,
+) -> anyhow::Result<()> {
+    let to = parse_status(to)?;
+    let doc_repo = settings.repositories()?.documents;
+
+    if !ids.is_empty() {
+        let moved = doc_repo.transition_documents(ids, to).await?;
+        println!(
+            "{} Moved {} of {} documents to {}",
+            style("✓").green(),
+            moved,
+            ids.len(),
+            to.label()
+        );
+        if (moved as usize) < ids.len() {
+            println!(
+                "  {} Others are missing, already {}, or not allowed to move there",
+                style("!").yellow(),
+                to.label()
+            );
+        }
+        return Ok(());
+    }
+
+    let Some(from) = from else {
+        anyhow::bail!("Give document IDs or --from <status>");
+    };
+    let from = parse_status(from)?;
+    if !doc_repo.workflow().allows(from, to) {
+        anyhow::bail!(
+            "The workflow doesn't allow {} → {} ({} may move to: {})",
+            from.label(),
+            to.label(),
+            from.label(),
+            labels(&doc_repo.workflow().next(from))
+        );
+    }
+
+    let count = doc_repo
+        .count_by_status(source_id)
+        .await?
+        .get(from.as_str())
+        .copied()
+        .unwrap_or(0);
+    if count == 0 {
+        println!("{} No {} documents", style("!").yellow(), from.label());
+        return Ok(());
+    }
+
+    println!(
+        "{} Move {} documents from {} to {}",
+        style("→").cyan(),
+        count,
+        style(from.label()).yellow(),
+        style(to.label()).green()
+    );
+    if !confirm && !confirm_prompt()? {
+        return Ok(());
+    }
+
+    let moved = doc_repo.transition_all(from, to, source_id).await?;
+    println!(
+        "\n{} Moved {} documents to {}",
+        style("✓").green(),
+        moved,
+        to.label()
+    );
+
+    Ok(())
+}
@@ -168,7 +168,12 @@ impl DownloadProgress {
             slot.bar.set_position(0);
         }
 
-        // Update summary
+        self.record_finished(success);
+    }
+
+    /// Count a finished download without touching its worker's slot, for
+    /// downloads finishing after their worker has moved on to the next.
+    pub fn record_finished(&self, success: bool) {
         if success {
             self.summary_bar.inc(1);
         }
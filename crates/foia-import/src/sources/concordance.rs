@@ -636,14 +636,14 @@ impl ImportSource for ConcordanceImportSource {
             version.dedup_index = dedup_index;
 
             let save_result: anyhow::Result<bool> = {
-                let existing = doc_repo.get_by_url(&url).await?;
-                if let Some(mut doc) = existing.into_iter().next() {
+                let existing = doc_repo.find_by_source_url(source_id, &url).await?;
+                if let Some(mut doc) = existing {
                     if doc.add_version(version) {
                         doc_repo.save_with_versions(&doc).await?;
                     }
                 } else {
                     let mut doc = Document::new(
-                        uuid::Uuid::new_v4().to_string(),
+                        Document::stable_id(source_id, &url),
                         source_id.to_string(),
                         title,
                         url.clone(),
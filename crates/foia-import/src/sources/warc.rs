@@ -338,6 +338,8 @@ impl WarcImportSource {
                     metadata: serde_json::json!({}),
                     original_filename: None,
                     server_date: None,
+                    declared_mime_type: None,
+                    tags: Vec::new(),
                 };
 
                 match save_document_async(&doc_repo, content, &input, &source_id, documents_dir)
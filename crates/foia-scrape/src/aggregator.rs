@@ -0,0 +1,389 @@
+//! FOIA aggregator site support.
+//!
+//! Aggregators like governmentattic.org republish FOIA responses obtained
+//! by others. Their listing pages describe each document next to its link:
+//! who asked for it, when it was requested and released, and which agency
+//! it came from. Capturing that with the document keeps its provenance when
+//! the same release also turns up from the agency directly.
+//!
+//! Provides functionality to:
+//! - Look up aggregator presets and apply config overrides to them
+//! - Find document links on a listing page along with their descriptions
+//! - Read requester and dates from a description into document metadata
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::config::AggregatorConfig;
+
+/// Description labels and the metadata field each fills.
+const FIELD_LABELS: &[(&str, &str)] = &[
+    ("requested by", "requester"),
+    ("requester", "requester"),
+    ("requestor", "requester"),
+    ("requested date", "request_date"),
+    ("request date", "request_date"),
+    ("date of request", "request_date"),
+    ("released date", "response_date"),
+    ("release date", "response_date"),
+    ("response date", "response_date"),
+    ("date released", "response_date"),
+    ("posted date", "posted_date"),
+    ("date posted", "posted_date"),
+    ("source of document", "original_source"),
+];
+
+/// Date fields, normalized to `YYYY-MM-DD` when they parse.
+const DATE_FIELDS: &[&str] = &["request_date", "response_date", "posted_date"];
+
+/// Date formats seen on aggregator listings.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d",
+    "%d-%B-%Y",
+    "%d-%b-%Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%d %B %Y",
+    "%m/%d/%Y",
+];
+
+/// An aggregator site's layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregator {
+    /// Recorded as `aggregator` in each document's metadata, if set.
+    pub name: Option<String>,
+    /// Site root, e.g. `https://www.governmentattic.org`.
+    pub base_url: Option<String>,
+    /// Listing pages to start from.
+    pub start_paths: Vec<String>,
+    /// Regex matching listing pages to follow; none means start pages only.
+    pub page_pattern: Option<String>,
+    /// Regex matching links to documents.
+    pub document_pattern: String,
+    /// CSS selector for the element describing one document.
+    pub entry_selector: String,
+    /// Requester to record when a listing doesn't name one.
+    pub requester: Option<String>,
+}
+
+impl Aggregator {
+    /// governmentattic.org. Documents live under `/docs/` (or a numbered
+    /// `/NNdocs/`), each described in the paragraph holding its link.
+    /// Everything on the site was requested by Government Attic itself.
+    pub fn governmentattic() -> Self {
+        Self {
+            name: Some("governmentattic".to_string()),
+            base_url: Some("https://www.governmentattic.org".to_string()),
+            start_paths: vec!["/".to_string()],
+            page_pattern: Some(
+                r"^https?://(www\.)?governmentattic\.org/[^/?#]+\.html$".to_string(),
+            ),
+            document_pattern: r"(?i)^https?://(www\.)?governmentattic\.org/\d*docs/.+\.pdf$"
+                .to_string(),
+            entry_selector: "p, li, td".to_string(),
+            requester: Some("Government Attic".to_string()),
+        }
+    }
+
+    /// Defaults for an aggregator described entirely by config.
+    pub fn generic() -> Self {
+        Self {
+            name: None,
+            base_url: None,
+            start_paths: Vec::new(),
+            page_pattern: None,
+            document_pattern: r"(?i)\.pdf$".to_string(),
+            entry_selector: "p, li, tr".to_string(),
+            requester: None,
+        }
+    }
+
+    /// The preset for a discovery type, if it names an aggregator.
+    pub fn preset(discovery_type: &str) -> Option<Self> {
+        match discovery_type {
+            "governmentattic" => Some(Self::governmentattic()),
+            "aggregator" => Some(Self::generic()),
+            _ => None,
+        }
+    }
+
+    /// Apply config overrides. Unset fields keep the preset's value.
+    pub fn with_config(mut self, config: &AggregatorConfig) -> Self {
+        if let Some(pattern) = &config.page_pattern {
+            self.page_pattern = Some(pattern.clone()).filter(|p| !p.is_empty());
+        }
+        if let Some(pattern) = &config.document_pattern {
+            self.document_pattern = pattern.clone();
+        }
+        if let Some(selector) = &config.entry_selector {
+            self.entry_selector = selector.clone();
+        }
+        if let Some(requester) = &config.requester {
+            self.requester = Some(requester.clone()).filter(|r| !r.is_empty());
+        }
+        self
+    }
+
+    /// Compile the aggregator's link patterns.
+    pub fn patterns(&self) -> Result<AggregatorPatterns, regex::Error> {
+        Ok(AggregatorPatterns {
+            page: self.page_pattern.as_deref().map(Regex::new).transpose()?,
+            document: Regex::new(&self.document_pattern)?,
+        })
+    }
+}
+
+/// An aggregator's compiled link patterns.
+#[derive(Debug, Clone)]
+pub struct AggregatorPatterns {
+    page: Option<Regex>,
+    document: Regex,
+}
+
+/// A document linked from a listing, with what the listing says about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatorEntry {
+    pub url: String,
+    pub metadata: Map<String, Value>,
+}
+
+/// What one listing page links to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AggregatorListing {
+    pub entries: Vec<AggregatorEntry>,
+    /// Further listing pages.
+    pub pages: Vec<String>,
+}
+
+/// A date as `YYYY-MM-DD`, if it's in a known format.
+pub fn normalize_date(value: &str) -> Option<String> {
+    let value = value.trim().trim_end_matches('.');
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Read labelled fields (`Requested date: ...`) from a document's
+/// description. Each value runs to the end of its line.
+pub fn parse_description(text: &str) -> Map<String, Value> {
+    let labels = FIELD_LABELS
+        .iter()
+        .map(|(label, _)| regex::escape(label))
+        .collect::<Vec<_>>()
+        .join("|");
+    let Ok(re) = Regex::new(&format!(r"(?i)\b({})\s*:", labels)) else {
+        return Map::new();
+    };
+
+    let matches: Vec<_> = re.captures_iter(text).collect();
+    let mut fields = Map::new();
+    for (i, caps) in matches.iter().enumerate() {
+        let label = caps[1].to_lowercase();
+        let Some((_, field)) = FIELD_LABELS.iter().find(|(l, _)| *l == label) else {
+            continue;
+        };
+        let start = caps.get(0).map_or(0, |m| m.end());
+        let end = matches
+            .get(i + 1)
+            .and_then(|next| next.get(0))
+            .map_or(text.len(), |m| m.start());
+        let Some(value) = text[start..end]
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+        else {
+            continue;
+        };
+        let value = if DATE_FIELDS.contains(field) {
+            normalize_date(value).unwrap_or_else(|| value.to_string())
+        } else {
+            value.to_string()
+        };
+        fields
+            .entry(field.to_string())
+            .or_insert(Value::String(value));
+    }
+    fields
+}
+
+/// The text of an element, one line per text node.
+fn element_text(element: &ElementRef) -> String {
+    element
+        .text()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find documents and further listing pages on a listing page.
+pub fn parse_listing(
+    html: &str,
+    page_url: &Url,
+    aggregator: &Aggregator,
+    patterns: &AggregatorPatterns,
+) -> AggregatorListing {
+    let document = Html::parse_document(html);
+    let mut listing = AggregatorListing::default();
+    let (Ok(anchors), Ok(entry_selector)) = (
+        Selector::parse("a[href]"),
+        Selector::parse(&aggregator.entry_selector),
+    ) else {
+        return listing;
+    };
+
+    let mut seen = HashSet::new();
+    seen.insert(page_url.to_string());
+    for a in document.select(&anchors) {
+        let Some(href) = a.value().attr("href") else {
+            continue;
+        };
+        if href.starts_with('#') || href.starts_with("javascript:") || href.starts_with("mailto:") {
+            continue;
+        }
+        let Ok(mut url) = page_url.join(href) else {
+            continue;
+        };
+        url.set_fragment(None);
+        let link = url.to_string();
+        if !seen.insert(link.clone()) {
+            continue;
+        }
+
+        if patterns.document.is_match(&link) {
+            let mut metadata = a
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .find(|el| entry_selector.matches(el))
+                .map(|entry| parse_description(&element_text(&entry)))
+                .unwrap_or_default();
+            if let Some(requester) = &aggregator.requester {
+                metadata
+                    .entry("requester")
+                    .or_insert_with(|| Value::String(requester.clone()));
+            }
+            if let Some(name) = &aggregator.name {
+                metadata.insert("aggregator".to_string(), Value::String(name.clone()));
+            }
+            listing.entries.push(AggregatorEntry {
+                url: link,
+                metadata,
+            });
+        } else if patterns.page.as_ref().is_some_and(|re| re.is_match(&link)) {
+            listing.pages.push(link);
+        }
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_date() {
+        assert_eq!(
+            normalize_date("20-January-2019").as_deref(),
+            Some("2019-01-20")
+        );
+        assert_eq!(
+            normalize_date("March 5, 2020").as_deref(),
+            Some("2020-03-05")
+        );
+        assert_eq!(normalize_date("04/01/2021").as_deref(), Some("2021-04-01"));
+        assert_eq!(normalize_date("sometime in 2019"), None);
+    }
+
+    #[test]
+    fn test_parse_description() {
+        let text = "FBI Manual of Investigative Operations, 2019\nRequested date:\n20-January-2019\nRelease date: 15-March-2020\nSource of document: FOIA Request\nFederal Bureau of Investigation";
+        assert_eq!(
+            Value::Object(parse_description(text)),
+            json!({
+                "request_date": "2019-01-20",
+                "response_date": "2020-03-15",
+                "original_source": "FOIA Request",
+            })
+        );
+        assert!(parse_description("No labels here").is_empty());
+    }
+
+    #[test]
+    fn test_parse_governmentattic_listing() {
+        let aggregator = Aggregator::governmentattic();
+        let patterns = aggregator.patterns().unwrap();
+        let page = Url::parse("https://www.governmentattic.org/DocumentsFBI.html").unwrap();
+        let html = r#"<html><body>
+            <a href="index.html">Home</a>
+            <a href="DocumentsCIA.html">CIA</a>
+            <p><a href="47docs/FBI_MIOG_2019.pdf">FBI Manual of Investigative Operations</a>, 2019<br>
+              <b>Requested date:</b> 20-January-2019<br>
+              <b>Release date:</b> 15-March-2020<br>
+              <b>Posted date:</b> 06-April-2020<br>
+              Source of document: FOIA Request</p>
+            <p><a href="docs/FBI_Other.pdf">Other</a></p>
+        </body></html>"#;
+        let listing = parse_listing(html, &page, &aggregator, &patterns);
+        assert_eq!(
+            listing.pages,
+            vec![
+                "https://www.governmentattic.org/index.html",
+                "https://www.governmentattic.org/DocumentsCIA.html",
+            ]
+        );
+        assert_eq!(listing.entries.len(), 2);
+        assert_eq!(
+            listing.entries[0].url,
+            "https://www.governmentattic.org/47docs/FBI_MIOG_2019.pdf"
+        );
+        assert_eq!(
+            Value::Object(listing.entries[0].metadata.clone()),
+            json!({
+                "aggregator": "governmentattic",
+                "requester": "Government Attic",
+                "request_date": "2019-01-20",
+                "response_date": "2020-03-15",
+                "posted_date": "2020-04-06",
+                "original_source": "FOIA Request",
+            })
+        );
+        assert_eq!(
+            Value::Object(listing.entries[1].metadata.clone()),
+            json!({"aggregator": "governmentattic", "requester": "Government Attic"})
+        );
+    }
+
+    #[test]
+    fn test_generic_overrides() {
+        let aggregator = Aggregator::preset("aggregator")
+            .unwrap()
+            .with_config(&AggregatorConfig {
+                document_pattern: Some(r"/files/".to_string()),
+                requester: Some("Jane Doe".to_string()),
+                ..Default::default()
+            });
+        let patterns = aggregator.patterns().unwrap();
+        let page = Url::parse("https://example.org/releases").unwrap();
+        let html = r#"<ul>
+            <li><a href="/files/1">Release</a> Requester: John Roe</li>
+            <li><a href="/files/2">Release</a></li>
+            <li><a href="/about">About</a></li>
+        </ul>"#;
+        let listing = parse_listing(html, &page, &aggregator, &patterns);
+        assert!(listing.pages.is_empty());
+        let requesters: Vec<_> = listing
+            .entries
+            .iter()
+            .map(|e| e.metadata["requester"].clone())
+            .collect();
+        assert_eq!(requesters, vec![json!("John Roe"), json!("Jane Doe")]);
+    }
+}
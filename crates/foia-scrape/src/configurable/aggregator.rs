@@ -0,0 +1,146 @@
+//! Aggregator discovery: listing pages and the documents they describe.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use tracing::{debug, info, warn};
+use url::Url;
+
+use super::ConfigurableScraper;
+use crate::aggregator::{parse_listing, Aggregator};
+use crate::config::ScraperConfig;
+use crate::HttpClient;
+use foia::models::{CrawlUrl, DiscoveryMethod};
+use foia::repository::DieselCrawlRepository;
+
+/// Listing page levels followed when `max_depth` isn't set.
+const DEFAULT_MAX_DEPTH: u32 = 2;
+
+impl ConfigurableScraper {
+    /// Streaming discovery for a FOIA aggregator site (`governmentattic`,
+    /// or `aggregator` for one described by config).
+    ///
+    /// Follows the site's listing pages and queues the documents they link
+    /// to, recording each listing's description of a document (requester,
+    /// request and response dates) as metadata for it.
+    pub(crate) async fn discover_aggregator_streaming(
+        config: &ScraperConfig,
+        client: &HttpClient,
+        source_id: &str,
+        crawl_repo: &Option<Arc<DieselCrawlRepository>>,
+        url_tx: &tokio::sync::mpsc::Sender<String>,
+    ) {
+        let Some(mut aggregator) = Aggregator::preset(&config.discovery.discovery_type) else {
+            return;
+        };
+        if let Some(overrides) = &config.discovery.aggregator {
+            aggregator = aggregator.with_config(overrides);
+        }
+        if let Some(base_url) = config
+            .discovery
+            .base_url
+            .as_ref()
+            .or(config.base_url.as_ref())
+        {
+            aggregator.base_url = Some(base_url.clone());
+        }
+        if !config.discovery.start_paths.is_empty() {
+            aggregator.start_paths = config.discovery.start_paths.clone();
+        }
+        let Some(base) = aggregator
+            .base_url
+            .as_deref()
+            .and_then(|u| Url::parse(u).ok())
+        else {
+            warn!("[{}] Aggregator discovery needs a base_url", source_id);
+            return;
+        };
+        let patterns = match aggregator.patterns() {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                warn!("[{}] Invalid aggregator pattern: {}", source_id, e);
+                return;
+            }
+        };
+        let max_depth = config.discovery.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        info!("Starting aggregator discovery from {}", base);
+
+        let start_paths = if aggregator.start_paths.is_empty() {
+            vec![String::new()]
+        } else {
+            aggregator.start_paths.clone()
+        };
+        let mut queue: VecDeque<(String, u32)> = start_paths
+            .iter()
+            .filter_map(|path| base.join(path).ok())
+            .map(|url| (url.to_string(), 0))
+            .collect();
+        let mut visited = HashSet::new();
+        let mut queued_docs = HashSet::new();
+
+        while let Some((page_url, depth)) = queue.pop_front() {
+            if !visited.insert(page_url.clone()) || !client.page_in_scope(&page_url, depth) {
+                continue;
+            }
+
+            let response = match client.get(&page_url, None, None).await {
+                Ok(r) if r.is_success() => r,
+                Ok(r) => {
+                    warn!("Aggregator page failed (HTTP {}) - {}", r.status, page_url);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Aggregator page error: {} - {}", e, page_url);
+                    continue;
+                }
+            };
+            let Ok(html) = response.text().await else {
+                continue;
+            };
+            let Ok(parsed_url) = Url::parse(&page_url) else {
+                continue;
+            };
+            let listing = parse_listing(&html, &parsed_url, &aggregator, &patterns);
+            debug!(
+                "Aggregator page {}: {} documents, {} pages",
+                page_url,
+                listing.entries.len(),
+                listing.pages.len()
+            );
+
+            for entry in listing.entries {
+                if !queued_docs.insert(entry.url.clone())
+                    || !client.in_scope(&entry.url, Some(depth + 1))
+                {
+                    continue;
+                }
+                if let Some(repo) = crawl_repo {
+                    let crawl_url = CrawlUrl::new(
+                        entry.url.clone(),
+                        source_id.to_string(),
+                        DiscoveryMethod::HtmlLink,
+                        Some(page_url.clone()),
+                        depth + 1,
+                    )
+                    .with_metadata(entry.metadata);
+                    let _ = repo.add_url(&crawl_url).await;
+                }
+                if url_tx.send(entry.url).await.is_err() {
+                    return; // Receiver dropped
+                }
+            }
+
+            if depth < max_depth {
+                queue.extend(listing.pages.into_iter().map(|url| (url, depth + 1)));
+            }
+        }
+
+        info!(
+            "[{}] Aggregator discovery complete: {} pages, {} documents",
+            source_id,
+            visited.len(),
+            queued_docs.len()
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! CourtListener discovery: dockets, docket entries, RECAP filings.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use tracing::{debug, info, warn};
+
+use super::ConfigurableScraper;
+use crate::config::ScraperConfig;
+use crate::courtlistener::{
+    auth_headers, docket_entries_url, docket_id, docket_url, next_page, parse_docket,
+    parse_entries, parse_search, search_url, TOKEN_ENV,
+};
+use crate::HttpClient;
+use foia::models::{CrawlUrl, DiscoveryMethod};
+use foia::repository::DieselCrawlRepository;
+
+/// Pages read per search or docket when `max_pages` isn't set.
+const DEFAULT_MAX_PAGES: u32 = 50;
+
+/// Tag given to every filing.
+const FILING_TAG: &str = "type:court-filing";
+
+/// Fetch an API endpoint. `Err` carries why the request failed.
+async fn get_json(
+    client: &HttpClient,
+    url: &str,
+    headers: &HashMap<String, String>,
+) -> Result<serde_json::Value, String> {
+    let response = match client.get_with_headers(url, headers.clone()).await {
+        Ok(r) if r.is_success() => r,
+        Ok(r) => return Err(format!("HTTP {}", r.status.as_u16())),
+        Err(e) => return Err(e.to_string()),
+    };
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+impl ConfigurableScraper {
+    /// Streaming discovery for court records in CourtListener's RECAP
+    /// archive.
+    ///
+    /// Collects the configured dockets and every docket the configured
+    /// searches match, then pages through each docket's entries and queues
+    /// the filings RECAP holds, with the case and entry as metadata.
+    pub(crate) async fn discover_courtlistener_streaming(
+        config: &ScraperConfig,
+        client: &HttpClient,
+        source_id: &str,
+        crawl_repo: &Option<Arc<DieselCrawlRepository>>,
+        url_tx: &tokio::sync::mpsc::Sender<String>,
+    ) {
+        let courtlistener = config.discovery.courtlistener.clone().unwrap_or_default();
+        let max_pages = courtlistener.max_pages.unwrap_or(DEFAULT_MAX_PAGES);
+        let headers = auth_headers();
+        if headers.is_empty() {
+            warn!(
+                "[{}] {} is not set; CourtListener only serves docket entries to authenticated requests",
+                source_id, TOKEN_ENV
+            );
+        }
+
+        let mut dockets: Vec<String> = Vec::new();
+        for value in &courtlistener.dockets {
+            match docket_id(value) {
+                Some(id) => dockets.push(id),
+                None => warn!("[{}] Not a CourtListener docket: {}", source_id, value),
+            }
+        }
+
+        for query in &courtlistener.queries {
+            let mut url = Some(search_url(query, courtlistener.court.as_deref()));
+            let mut pages = 0;
+            while let Some(page_url) = url.take() {
+                pages += 1;
+                let data = match get_json(client, &page_url, &headers).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!("[{}] CourtListener search failed: {}", source_id, e);
+                        break;
+                    }
+                };
+                dockets.extend(parse_search(&data));
+                if pages < max_pages {
+                    url = next_page(&data);
+                }
+            }
+        }
+
+        let mut seen = HashSet::new();
+        dockets.retain(|id| seen.insert(id.clone()));
+        if dockets.is_empty() {
+            warn!(
+                "[{}] CourtListener discovery needs dockets or queries",
+                source_id
+            );
+            return;
+        }
+
+        info!(
+            "Starting CourtListener discovery for {} dockets",
+            dockets.len()
+        );
+
+        let tags = vec![FILING_TAG.to_string()];
+        let mut total_urls = 0;
+        for id in &dockets {
+            let docket = match get_json(client, &docket_url(id), &headers).await {
+                Ok(data) => parse_docket(&data),
+                Err(e) => {
+                    warn!("[{}] Could not load docket {}: {}", source_id, id, e);
+                    continue;
+                }
+            };
+            let Some(docket) = docket else {
+                continue;
+            };
+
+            let mut url = Some(docket_entries_url(id));
+            let mut pages = 0;
+            while let Some(page_url) = url.take() {
+                pages += 1;
+                let data = match get_json(client, &page_url, &headers).await {
+                    Ok(data) => data,
+                    Err(e) => {
+                        warn!(
+                            "[{}] Could not load entries for docket {}: {}",
+                            source_id, id, e
+                        );
+                        break;
+                    }
+                };
+                for filing in parse_entries(&data, &docket) {
+                    if let Some(repo) = crawl_repo {
+                        let crawl_url = CrawlUrl::new(
+                            filing.url.clone(),
+                            source_id.to_string(),
+                            DiscoveryMethod::ApiNested,
+                            Some(docket.url.clone()),
+                            1,
+                        )
+                        .with_tags(&tags)
+                        .with_metadata(filing.metadata);
+                        let _ = repo.add_url(&crawl_url).await;
+                    }
+                    if url_tx.send(filing.url).await.is_err() {
+                        return; // Receiver dropped
+                    }
+                    total_urls += 1;
+                }
+                if pages < max_pages {
+                    url = next_page(&data);
+                }
+            }
+            debug!(
+                "Docket {} ({}): {} filings so far",
+                id,
+                docket.case_name.as_deref().unwrap_or("unnamed"),
+                total_urls
+            );
+        }
+
+        info!(
+            "[{}] CourtListener discovery complete: {} dockets, {} filings",
+            source_id,
+            dockets.len(),
+            total_urls
+        );
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 
 use super::ConfigurableScraper;
 use crate::config::ScraperConfig;
-use crate::HttpClient;
+use crate::{HttpClient, ScraperResult};
 #[cfg(feature = "browser")]
 use foia::browser::BrowserEngineConfig;
 use foia::repository::DieselCrawlRepository;
@@ -18,6 +18,7 @@ impl ConfigurableScraper {
         source_id: &str,
         crawl_repo: &Option<Arc<DieselCrawlRepository>>,
         url_tx: &tokio::sync::mpsc::Sender<String>,
+        listing_tx: &Option<tokio::sync::mpsc::Sender<ScraperResult>>,
         browser_config: &Option<BrowserEngineConfig>,
     ) {
         match config.discovery.discovery_type.as_str() {
@@ -28,6 +29,7 @@ impl ConfigurableScraper {
                     source_id,
                     crawl_repo,
                     url_tx,
+                    listing_tx,
                     browser_config,
                 )
                 .await;
@@ -42,6 +44,29 @@ impl ConfigurableScraper {
                 Self::discover_api_cursor_streaming(config, client, source_id, crawl_repo, url_tx)
                     .await;
             }
+            "govqa" => {
+                Self::discover_govqa_streaming(config, client, source_id, crawl_repo, url_tx).await;
+            }
+            "nextrequest" => {
+                Self::discover_nextrequest_streaming(config, client, source_id, crawl_repo, url_tx)
+                    .await;
+            }
+            "fbi_vault" | "doj_foia_library" => {
+                Self::discover_reading_room_streaming(
+                    config, client, source_id, crawl_repo, url_tx,
+                )
+                .await;
+            }
+            "governmentattic" | "aggregator" => {
+                Self::discover_aggregator_streaming(config, client, source_id, crawl_repo, url_tx)
+                    .await;
+            }
+            "courtlistener" => {
+                Self::discover_courtlistener_streaming(
+                    config, client, source_id, crawl_repo, url_tx,
+                )
+                .await;
+            }
             _ => {}
         }
     }
@@ -54,11 +79,12 @@ impl ConfigurableScraper {
         source_id: &str,
         crawl_repo: &Option<Arc<DieselCrawlRepository>>,
         url_tx: &tokio::sync::mpsc::Sender<String>,
+        listing_tx: &Option<tokio::sync::mpsc::Sender<ScraperResult>>,
     ) {
         match config.discovery.discovery_type.as_str() {
             "html_crawl" => {
                 Self::discover_html_crawl_streaming_no_browser(
-                    config, client, source_id, crawl_repo, url_tx,
+                    config, client, source_id, crawl_repo, url_tx, listing_tx,
                 )
                 .await;
             }
@@ -72,6 +98,29 @@ impl ConfigurableScraper {
                 Self::discover_api_cursor_streaming(config, client, source_id, crawl_repo, url_tx)
                     .await;
             }
+            "govqa" => {
+                Self::discover_govqa_streaming(config, client, source_id, crawl_repo, url_tx).await;
+            }
+            "nextrequest" => {
+                Self::discover_nextrequest_streaming(config, client, source_id, crawl_repo, url_tx)
+                    .await;
+            }
+            "fbi_vault" | "doj_foia_library" => {
+                Self::discover_reading_room_streaming(
+                    config, client, source_id, crawl_repo, url_tx,
+                )
+                .await;
+            }
+            "governmentattic" | "aggregator" => {
+                Self::discover_aggregator_streaming(config, client, source_id, crawl_repo, url_tx)
+                    .await;
+            }
+            "courtlistener" => {
+                Self::discover_courtlistener_streaming(
+                    config, client, source_id, crawl_repo, url_tx,
+                )
+                .await;
+            }
             _ => {}
         }
     }
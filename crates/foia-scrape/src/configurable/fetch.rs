@@ -90,6 +90,8 @@ impl ConfigurableScraper {
             server_date,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         };
 
         // Update metadata
@@ -163,6 +165,8 @@ impl ConfigurableScraper {
             server_date: None,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         })
     }
 
@@ -225,6 +229,8 @@ impl ConfigurableScraper {
             server_date: None,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         })
     }
 
@@ -336,6 +342,8 @@ impl ConfigurableScraper {
             server_date,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         })
     }
 }
@@ -0,0 +1,182 @@
+//! GovQA portal discovery: archive pages, request details, attachments.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use regex::Regex;
+use tracing::{debug, info, warn};
+use url::Url;
+
+use super::ConfigurableScraper;
+use crate::config::{GovQaConfig, ScraperConfig};
+use crate::govqa::{
+    attachment_links, detail_links, next_page_postback, strip_session, Postback,
+    DEFAULT_ARCHIVE_PATH, DEFAULT_ATTACHMENT_PATTERN, DEFAULT_DETAIL_PATTERN,
+};
+use crate::{HttpClient, HttpResponse};
+use foia::models::{CrawlUrl, DiscoveryMethod};
+use foia::repository::DieselCrawlRepository;
+
+/// Archive pages read per archive when `max_pages` isn't set.
+const DEFAULT_MAX_PAGES: u32 = 500;
+
+/// Read a successful response as text, along with the URL it came from.
+async fn page_text(
+    response: Result<HttpResponse, reqwest::Error>,
+    requested: &str,
+) -> Option<(String, Url)> {
+    let response = match response {
+        Ok(r) if r.is_success() => r,
+        Ok(r) => {
+            warn!("GovQA page failed (HTTP {}) - {}", r.status, requested);
+            return None;
+        }
+        Err(e) => {
+            warn!("GovQA page error: {} - {}", e, requested);
+            return None;
+        }
+    };
+    let url = response
+        .url
+        .as_deref()
+        .and_then(|u| Url::parse(u).ok())
+        .or_else(|| Url::parse(requested).ok())?;
+    let text = response.text().await.ok()?;
+    Some((text, url))
+}
+
+impl ConfigurableScraper {
+    /// Streaming discovery for a GovQA public records portal.
+    ///
+    /// Opens a session on the portal, walks each archive page by page,
+    /// opens every request's details and queues its attachments. Attachment
+    /// URLs are queued without the session token; fetching one starts a new
+    /// session on the portal.
+    pub(crate) async fn discover_govqa_streaming(
+        config: &ScraperConfig,
+        client: &HttpClient,
+        source_id: &str,
+        crawl_repo: &Option<Arc<DieselCrawlRepository>>,
+        url_tx: &tokio::sync::mpsc::Sender<String>,
+    ) {
+        let govqa = config.discovery.govqa.clone().unwrap_or_default();
+        let Some(portal_url) = govqa
+            .portal_url
+            .clone()
+            .or_else(|| config.base_url.clone())
+            .or_else(|| config.discovery.base_url.clone())
+        else {
+            warn!(
+                "[{}] GovQA discovery needs a portal_url or base_url",
+                source_id
+            );
+            return;
+        };
+        let (detail_re, attachment_re) = match patterns(&govqa) {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                warn!("[{}] Invalid GovQA pattern: {}", source_id, e);
+                return;
+            }
+        };
+
+        // The portal redirects into a fresh session; later pages are
+        // resolved against the session URL.
+        let Some((_, session_url)) =
+            page_text(client.get(&portal_url, None, None).await, &portal_url).await
+        else {
+            tracing::error!("[{}] Could not open GovQA portal {}", source_id, portal_url);
+            return;
+        };
+        info!(
+            "Starting GovQA discovery from {}",
+            strip_session(session_url.as_str())
+        );
+
+        let archive_paths = if govqa.archive_paths.is_empty() {
+            vec![DEFAULT_ARCHIVE_PATH.to_string()]
+        } else {
+            govqa.archive_paths.clone()
+        };
+        let max_pages = govqa.max_pages.unwrap_or(DEFAULT_MAX_PAGES);
+
+        let mut seen_requests = HashSet::new();
+        let mut total_urls = 0;
+        for path in &archive_paths {
+            let Ok(archive_url) = session_url.join(path) else {
+                warn!("[{}] Invalid GovQA archive path: {}", source_id, path);
+                continue;
+            };
+
+            let mut next: Option<Postback> = None;
+            for page in 1..=max_pages {
+                let requested = next
+                    .as_ref()
+                    .map(|p| p.action.clone())
+                    .unwrap_or_else(|| archive_url.to_string());
+                let response = match &next {
+                    Some(postback) => client.post(&postback.action, &postback.fields).await,
+                    None => client.get(&requested, None, None).await,
+                };
+                let Some((html, page_url)) = page_text(response, &requested).await else {
+                    break;
+                };
+
+                let details = detail_links(&html, &page_url, &detail_re);
+                debug!("GovQA archive page {}: {} requests", page, details.len());
+                for detail_url in details {
+                    if !seen_requests.insert(strip_session(&detail_url)) {
+                        continue;
+                    }
+                    let Some((detail_html, detail_page)) =
+                        page_text(client.get(&detail_url, None, None).await, &detail_url).await
+                    else {
+                        continue;
+                    };
+                    let parent = strip_session(&detail_url);
+                    for url in attachment_links(&detail_html, &detail_page, &attachment_re) {
+                        if let Some(repo) = crawl_repo {
+                            let crawl_url = CrawlUrl::new(
+                                url.clone(),
+                                source_id.to_string(),
+                                DiscoveryMethod::HtmlLink,
+                                Some(parent.clone()),
+                                2,
+                            );
+                            let _ = repo.add_url(&crawl_url).await;
+                        }
+                        if url_tx.send(url).await.is_err() {
+                            return; // Receiver dropped
+                        }
+                        total_urls += 1;
+                    }
+                }
+
+                next = next_page_postback(&html, &page_url);
+                if next.is_none() {
+                    break;
+                }
+            }
+        }
+
+        info!(
+            "[{}] GovQA discovery complete: {} requests, {} attachments",
+            source_id,
+            seen_requests.len(),
+            total_urls
+        );
+    }
+}
+
+/// Detail and attachment link patterns, with stock GovQA defaults.
+fn patterns(govqa: &GovQaConfig) -> Result<(Regex, Regex), regex::Error> {
+    let detail = govqa
+        .detail_pattern
+        .as_deref()
+        .unwrap_or(DEFAULT_DETAIL_PATTERN);
+    let attachment = govqa
+        .attachment_pattern
+        .as_deref()
+        .unwrap_or(DEFAULT_ATTACHMENT_PATTERN);
+    Ok((Regex::new(detail)?, Regex::new(attachment)?))
+}
@@ -15,7 +15,8 @@ use crate::google_drive::{
     extract_file_id, file_download_url, is_google_drive_file_url, is_google_drive_folder_url,
     DriveFolder,
 };
-use crate::HttpClient;
+use crate::links;
+use crate::{HttpClient, ScraperResult};
 #[cfg(feature = "browser")]
 use foia::browser::BrowserEngineConfig;
 #[cfg(feature = "browser")]
@@ -113,9 +114,10 @@ impl CrawlerConfig {
     }
 }
 
-/// Initialize the BFS frontier with seed URLs.
+/// Initialize the BFS frontier with seed URLs that are in scope.
 fn seed_frontier(
     config: &ScraperConfig,
+    client: &HttpClient,
     base_url: &str,
     visited: &mut HashSet<String>,
 ) -> VecDeque<(String, u32)> {
@@ -130,7 +132,7 @@ fn seed_frontier(
 
     for start_path in start_paths {
         let start_url = resolve_url(base_url, &start_path);
-        if visited.insert(start_url.clone()) {
+        if visited.insert(start_url.clone()) && client.page_in_scope(&start_url, 0) {
             frontier.push_back((start_url, 0));
         }
     }
@@ -141,7 +143,7 @@ fn seed_frontier(
             let encoded_query = urlencoding::encode(query);
             let search_path = template.replace("{query}", &encoded_query);
             let search_url = resolve_url(base_url, &search_path);
-            if visited.insert(search_url.clone()) {
+            if visited.insert(search_url.clone()) && client.page_in_scope(&search_url, 0) {
                 frontier.push_back((search_url, 0));
             }
         }
@@ -234,7 +236,8 @@ fn convert_google_drive_file_url(url: String) -> String {
     url
 }
 
-/// Send discovered document URLs to the channel and crawl repository.
+/// Send a discovered document URL to the channel and crawl repository.
+/// Returns whether it was new and in scope.
 #[allow(clippy::too_many_arguments)]
 async fn send_document_url(
     url: String,
@@ -242,12 +245,13 @@ async fn send_document_url(
     parent_url: &str,
     depth: u32,
     discovery_method: DiscoveryMethod,
+    client: &HttpClient,
     crawl_repo: &Option<Arc<DieselCrawlRepository>>,
     url_tx: &tokio::sync::mpsc::Sender<String>,
     visited: &mut HashSet<String>,
-) -> Result<(), ()> {
-    if !visited.insert(url.clone()) {
-        return Ok(());
+) -> Result<bool, ()> {
+    if !visited.insert(url.clone()) || !client.in_scope(&url, Some(depth + 1)) {
+        return Ok(false);
     }
 
     if let Some(repo) = crawl_repo {
@@ -264,7 +268,7 @@ async fn send_document_url(
     if url_tx.send(url).await.is_err() {
         return Err(());
     }
-    Ok(())
+    Ok(true)
 }
 
 /// Process Google Drive folder URLs, returning (gdrive_doc_urls, filtered_page_urls).
@@ -291,6 +295,19 @@ async fn process_google_drive_folders(
     (gdrive_doc_urls, filtered_page_urls)
 }
 
+/// Pass a crawled listing page on to be saved, when archiving listings.
+async fn archive_listing(
+    listing_tx: &Option<tokio::sync::mpsc::Sender<ScraperResult>>,
+    url: &str,
+    html: &str,
+) {
+    if let Some(tx) = listing_tx {
+        let _ = tx
+            .send(ScraperResult::listing_page(url.to_string(), html))
+            .await;
+    }
+}
+
 /// Close browser fetcher if present.
 #[cfg(feature = "browser")]
 async fn close_browser(browser_fetcher: &mut Option<BrowserFetcher>) {
@@ -347,10 +364,10 @@ impl ConfigurableScraper {
         source_id: &str,
         crawl_repo: &Option<Arc<DieselCrawlRepository>>,
         url_tx: &tokio::sync::mpsc::Sender<String>,
+        listing_tx: &Option<tokio::sync::mpsc::Sender<ScraperResult>>,
         browser_config: &Option<BrowserEngineConfig>,
     ) {
         let crawler_config = CrawlerConfig::from_scraper_config(config);
-        let page_link_selector = "a".to_string();
 
         // Create browser fetcher if configured
         let mut browser_fetcher = browser_config
@@ -359,7 +376,7 @@ impl ConfigurableScraper {
 
         // BFS frontier and visited set
         let mut visited: HashSet<String> = HashSet::new();
-        let mut frontier = seed_frontier(config, &crawler_config.base_url, &mut visited);
+        let mut frontier = seed_frontier(config, client, &crawler_config.base_url, &mut visited);
 
         info!(
             "Starting recursive HTML crawl discovery with {} seed URLs",
@@ -403,6 +420,7 @@ impl ConfigurableScraper {
                 Some(html) => html,
                 None => continue,
             };
+            archive_listing(listing_tx, &current_url, &html).await;
 
             pages_crawled += 1;
             if pages_crawled.is_multiple_of(100) {
@@ -418,10 +436,8 @@ impl ConfigurableScraper {
             let (doc_urls, page_urls) = extract_links_from_html(
                 &html,
                 &current_url,
-                &crawler_config.base_url,
                 &crawler_config.allowed_domain,
                 &crawler_config.document_patterns,
-                &page_link_selector,
             );
 
             // Process Google Drive folders and filter them from page URLs
@@ -437,52 +453,58 @@ impl ConfigurableScraper {
             // Send document URLs to download queue
             for full_url in doc_urls {
                 debug!("Found document: {}", full_url);
-                if send_document_url(
+                match send_document_url(
                     full_url,
                     source_id,
                     &current_url,
                     depth,
                     DiscoveryMethod::HtmlLink,
+                    client,
                     crawl_repo,
                     url_tx,
                     &mut visited,
                 )
                 .await
-                .is_err()
                 {
-                    info!("Discovery complete: receiver dropped");
-                    close_browser(&mut browser_fetcher).await;
-                    return;
+                    Ok(true) => docs_found += 1,
+                    Ok(false) => {}
+                    Err(()) => {
+                        info!("Discovery complete: receiver dropped");
+                        close_browser(&mut browser_fetcher).await;
+                        return;
+                    }
                 }
-                docs_found += 1;
             }
 
             // Send Google Drive files to download queue
             for full_url in gdrive_doc_urls {
                 debug!("Found Google Drive document: {}", full_url);
-                if send_document_url(
+                match send_document_url(
                     full_url,
                     source_id,
                     &current_url,
                     depth,
                     DiscoveryMethod::GoogleDriveFolder,
+                    client,
                     crawl_repo,
                     url_tx,
                     &mut visited,
                 )
                 .await
-                .is_err()
                 {
-                    info!("Discovery complete: receiver dropped");
-                    close_browser(&mut browser_fetcher).await;
-                    return;
+                    Ok(true) => docs_found += 1,
+                    Ok(false) => {}
+                    Err(()) => {
+                        info!("Discovery complete: receiver dropped");
+                        close_browser(&mut browser_fetcher).await;
+                        return;
+                    }
                 }
-                docs_found += 1;
             }
 
             // Add page URLs to frontier
             for page_url in page_urls {
-                if visited.insert(page_url.clone()) {
+                if visited.insert(page_url.clone()) && client.page_in_scope(&page_url, depth + 1) {
                     frontier.push_back((page_url, depth + 1));
                 }
             }
@@ -509,6 +531,7 @@ impl ConfigurableScraper {
         _source_id: &str,
         _crawl_repo: &Option<Arc<DieselCrawlRepository>>,
         url_tx: &tokio::sync::mpsc::Sender<String>,
+        listing_tx: &Option<tokio::sync::mpsc::Sender<ScraperResult>>,
     ) {
         let default_base = String::new();
         let base_url = config
@@ -524,6 +547,7 @@ impl ConfigurableScraper {
                 Ok(html) => html,
                 Err(_) => continue,
             };
+            archive_listing(listing_tx, &start_url, &html).await;
 
             let found_urls = {
                 let document = Html::parse_document(&html);
@@ -551,6 +575,9 @@ impl ConfigurableScraper {
             };
 
             for full_url in found_urls {
+                if !client.in_scope(&full_url, Some(1)) {
+                    continue;
+                }
                 if url_tx.send(full_url).await.is_err() {
                     return;
                 }
@@ -743,71 +770,25 @@ impl ConfigurableScraper {
 fn extract_links_from_html(
     html: &str,
     current_url: &str,
-    base_url: &str,
     allowed_domain: &str,
     document_patterns: &[Regex],
-    page_link_selector: &str,
 ) -> (Vec<String>, Vec<String>) {
-    let document = Html::parse_document(html);
     let mut doc_urls: Vec<String> = Vec::new();
     let mut page_urls: Vec<String> = Vec::new();
 
-    let selector = match Selector::parse(page_link_selector) {
-        Ok(s) => s,
-        Err(_) => return (doc_urls, page_urls),
+    let Ok(current) = Url::parse(current_url) else {
+        return (doc_urls, page_urls);
     };
+    let current_host = current.host_str().unwrap_or("");
 
-    for element in document.select(&selector) {
-        let href = match element.value().attr("href") {
-            Some(h) => h,
-            None => continue,
-        };
-
-        // Skip empty, javascript, mailto, tel links
-        if href.is_empty()
-            || href.starts_with('#')
-            || href.starts_with("javascript:")
-            || href.starts_with("mailto:")
-            || href.starts_with("tel:")
-        {
-            continue;
-        }
-
-        // Resolve URL
-        let full_url = if href.starts_with("http://") || href.starts_with("https://") {
-            href.to_string()
-        } else if href.starts_with('/') {
-            if let Ok(parsed) = Url::parse(current_url) {
-                format!(
-                    "{}://{}{}",
-                    parsed.scheme(),
-                    parsed.host_str().unwrap_or(""),
-                    href
-                )
-            } else {
-                format!("{}{}", base_url, href)
-            }
-        } else if href.starts_with("//") {
-            format!("https:{}", href)
-        } else if let Ok(base) = Url::parse(current_url) {
-            base.join(href).map(|u| u.to_string()).unwrap_or_default()
-        } else {
-            continue;
-        };
-
-        if full_url.is_empty() {
-            continue;
-        }
+    for link in links::extract_html(html, &current) {
+        let full_url = link.url;
 
         // Check if link should be followed
         let url_host = full_url
             .parse::<Url>()
             .map(|u| u.host_str().unwrap_or("").to_string())
             .unwrap_or_default();
-        let current_host = current_url
-            .parse::<Url>()
-            .map(|u| u.host_str().unwrap_or("").to_string())
-            .unwrap_or_default();
 
         let is_allowed_domain = allowed_domain.is_empty() || url_host.ends_with(allowed_domain);
         let is_same_host = url_host == current_host;
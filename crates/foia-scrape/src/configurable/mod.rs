@@ -14,24 +14,36 @@ use super::config::{ScraperConfig, ViaMode};
 use super::HttpClient;
 #[cfg(feature = "browser")]
 use foia::config::BrowserEngineConfig;
+use foia::config::Politeness;
+use foia::http_client::FixtureStore;
 use foia::models::Source;
 #[allow(unused_imports)]
 use foia::privacy::PrivacyConfig;
 use foia::rate_limit::RateLimiter;
 use foia::repository::DieselCrawlRepository;
 
+mod aggregator;
 mod api;
+mod courtlistener;
 mod discovery;
 mod extract;
 mod fetch;
+mod govqa;
 mod html_crawl;
+mod nextrequest;
+mod plan;
+mod reading_room;
 mod stream;
 
+pub use plan::ScrapePlan;
+
 /// Configurable scraper driven by JSON configuration.
 pub struct ConfigurableScraper {
     pub(crate) source: Source,
     pub(crate) config: ScraperConfig,
     pub(crate) client: HttpClient,
+    /// Pacing resolved from the source's politeness profile and overrides.
+    pub(crate) politeness: Politeness,
     pub(crate) crawl_repo: Option<Arc<DieselCrawlRepository>>,
     /// Refresh TTL in days - URLs older than this will be re-checked.
     pub(crate) refresh_ttl_days: u64,
@@ -87,6 +99,10 @@ impl ConfigurableScraper {
     /// The effective privacy config is determined by:
     /// 1. Starting with the global privacy config
     /// 2. Applying per-source overrides from scraper config's `privacy` field
+    /// 3. Routing through the source's own proxies instead, if it has any
+    ///
+    /// `request_delay` is the global delay; the source's politeness profile
+    /// and `request_delay_ms` take precedence over it.
     ///
     /// # Errors
     /// Returns an error if Tor mode is requested but Tor is not available.
@@ -102,13 +118,26 @@ impl ConfigurableScraper {
         // Apply per-source privacy overrides to global config
         let effective_privacy = privacy_config.map(|global| config.privacy.apply_to(global));
 
-        let mut builder = HttpClient::builder(&source.id, Duration::from_secs(30), request_delay);
-        if let Some(ua) = config.user_agent.as_deref() {
-            builder = builder.user_agent(ua);
-        }
+        let politeness = Politeness::resolve(&config, request_delay);
+        let mut builder = HttpClient::builder(
+            &source.id,
+            Duration::from_secs(30),
+            politeness.request_delay,
+        )
+        .politeness(&politeness);
         if let Some(privacy) = effective_privacy.as_ref() {
             builder = builder.privacy(privacy);
         }
+        builder = builder.proxy(&config.privacy.proxy);
+        if let Some(session) = config.session.as_ref() {
+            builder = builder.session(session);
+        }
+        if let Some(throttle) = config.throttle.as_ref() {
+            builder = builder.throttle(throttle);
+        }
+        if let Some(scope) = config.scope.as_ref() {
+            builder = builder.scope(scope);
+        }
         if let Some(limiter) = rate_limiter {
             builder = builder.rate_limiter(limiter);
         }
@@ -129,6 +158,7 @@ impl ConfigurableScraper {
             source,
             config,
             client,
+            politeness,
             crawl_repo,
             refresh_ttl_days,
             #[cfg(feature = "browser")]
@@ -148,6 +178,35 @@ impl ConfigurableScraper {
         }
     }
 
+    /// Re-pace a running crawl for a new global request delay, as when the
+    /// config file changes mid-crawl. The source's own `request_delay_ms`
+    /// and politeness profile still take precedence.
+    pub async fn apply_request_delay(&self, request_delay: Duration) {
+        let politeness = Politeness::resolve(&self.config, request_delay);
+        let domains: Vec<String> = self
+            .config
+            .base_url
+            .as_deref()
+            .and_then(RateLimiter::extract_domain)
+            .into_iter()
+            .collect();
+        self.client
+            .rate_limiter()
+            .apply_config(politeness.rate_limit, &domains)
+            .await;
+    }
+
+    /// Pacing this scraper crawls its source with.
+    pub fn politeness(&self) -> &Politeness {
+        &self.politeness
+    }
+
+    /// Record responses into, or replay them from, a fixture store.
+    pub fn with_fixtures(mut self, fixtures: Arc<FixtureStore>) -> Self {
+        self.client = self.client.with_fixtures(fixtures);
+        self
+    }
+
     /// Configure URL rewriting for caching proxies with mode.
     ///
     /// The via mappings allow routing requests through a CDN (like Cloudflare)
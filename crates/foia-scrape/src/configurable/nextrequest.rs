@@ -0,0 +1,148 @@
+//! NextRequest portal discovery: public requests and their documents.
+
+use std::sync::Arc;
+
+use tracing::{debug, info, warn};
+
+use super::ConfigurableScraper;
+use crate::config::ScraperConfig;
+use crate::nextrequest::{
+    instance_root, parse_documents, parse_requests, request_documents_url, request_page_url,
+    requests_url, total_count,
+};
+use crate::HttpClient;
+use foia::models::{CrawlUrl, DiscoveryMethod};
+use foia::repository::DieselCrawlRepository;
+
+/// Request list pages read when `max_pages` isn't set.
+const DEFAULT_MAX_PAGES: u32 = 1000;
+
+/// Document pages read per request.
+const MAX_DOCUMENT_PAGES: u32 = 100;
+
+/// Fetch a JSON endpoint. `Err` carries why discovery should stop.
+async fn get_json(client: &HttpClient, url: &str) -> Result<serde_json::Value, String> {
+    let response = match client.get(url, None, None).await {
+        Ok(r) if r.is_success() => r,
+        Ok(r) if r.is_rate_limited() => {
+            return Err(format!("Rate limited (HTTP {})", r.status.as_u16()))
+        }
+        Ok(r) => return Err(format!("HTTP {}", r.status.as_u16())),
+        Err(e) => return Err(e.to_string()),
+    };
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid JSON: {}", e))
+}
+
+impl ConfigurableScraper {
+    /// Streaming discovery for a NextRequest public records portal.
+    ///
+    /// Pages through the instance's public requests and queues the
+    /// documents released for each.
+    pub(crate) async fn discover_nextrequest_streaming(
+        config: &ScraperConfig,
+        client: &HttpClient,
+        source_id: &str,
+        crawl_repo: &Option<Arc<DieselCrawlRepository>>,
+        url_tx: &tokio::sync::mpsc::Sender<String>,
+    ) {
+        let nextrequest = config.discovery.nextrequest.clone().unwrap_or_default();
+        let Some(root) = nextrequest
+            .instance_url
+            .as_deref()
+            .or(config.base_url.as_deref())
+            .or(config.discovery.base_url.as_deref())
+            .and_then(instance_root)
+        else {
+            warn!(
+                "[{}] NextRequest discovery needs an instance_url or base_url",
+                source_id
+            );
+            return;
+        };
+        let max_pages = nextrequest.max_pages.unwrap_or(DEFAULT_MAX_PAGES);
+
+        info!("Starting NextRequest discovery from {}", root);
+
+        let mut requests_seen = 0u64;
+        let mut total_urls = 0;
+        let mut last_error: Option<String> = None;
+        for page in 1..=max_pages {
+            let data = match get_json(client, &requests_url(&root, page)).await {
+                Ok(data) => data,
+                Err(e) => {
+                    last_error = Some(e);
+                    break;
+                }
+            };
+            let request_ids = parse_requests(&data);
+            if request_ids.is_empty() {
+                break;
+            }
+            requests_seen += request_ids.len() as u64;
+
+            for request_id in &request_ids {
+                let parent = request_page_url(&root, request_id);
+                let mut request_documents = 0u64;
+                for doc_page in 1..=MAX_DOCUMENT_PAGES {
+                    let url = request_documents_url(&root, request_id, doc_page);
+                    let data = match get_json(client, &url).await {
+                        Ok(data) => data,
+                        Err(e) => {
+                            debug!("No documents for request {}: {}", request_id, e);
+                            break;
+                        }
+                    };
+                    let documents = parse_documents(&data, &root);
+                    if documents.is_empty() {
+                        break;
+                    }
+                    for document in documents {
+                        if let Some(repo) = crawl_repo {
+                            let crawl_url = CrawlUrl::new(
+                                document.url.clone(),
+                                source_id.to_string(),
+                                DiscoveryMethod::ApiNested,
+                                Some(parent.clone()),
+                                2,
+                            );
+                            let _ = repo.add_url(&crawl_url).await;
+                        }
+                        if url_tx.send(document.url).await.is_err() {
+                            return; // Receiver dropped
+                        }
+                        request_documents += 1;
+                        total_urls += 1;
+                    }
+                    if total_count(&data).is_none_or(|total| request_documents >= total) {
+                        break;
+                    }
+                }
+            }
+
+            debug!(
+                "NextRequest page {}: {} requests (total: {} documents)",
+                page,
+                request_ids.len(),
+                total_urls
+            );
+            if total_count(&data).is_some_and(|total| requests_seen >= total) {
+                break;
+            }
+        }
+
+        if let Some(err) = last_error {
+            tracing::error!(
+                "[{}] Discovery stopped after {} documents: {}",
+                source_id,
+                total_urls,
+                err
+            );
+        } else {
+            info!(
+                "[{}] NextRequest discovery complete: {} requests, {} documents",
+                source_id, requests_seen, total_urls
+            );
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Dry-run planning: discovery and HEAD requests only.
+//!
+//! Runs the source's discovery without queueing anything, then checks each
+//! URL against the crawl queue and with a HEAD request to estimate what a
+//! real scrape would download. No documents are fetched or written.
+
+use std::collections::HashSet;
+
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use tracing::debug;
+
+use super::ConfigurableScraper;
+use crate::HttpClient;
+use foia::models::{CrawlUrl, UrlStatus};
+use foia::repository::DieselCrawlRepository;
+
+/// What a scrape would do, gathered without downloading anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ScrapePlan {
+    /// Unique URLs found by discovery.
+    pub discovered: u64,
+    /// URLs not yet in the crawl queue.
+    pub new_urls: u64,
+    /// Known URLs still waiting to be fetched (or retried).
+    pub queued: u64,
+    /// Fetched within the refresh TTL, so not requested at all.
+    pub fresh: u64,
+    /// Due for refresh but unchanged upstream (304 to a conditional request).
+    pub unchanged: u64,
+    /// Due for refresh and changed upstream, so downloaded again.
+    pub changed: u64,
+    /// Skipped, out of retries, or disallowed by robots.txt; not fetched.
+    pub skipped: u64,
+    /// HEAD request failed; the download may still succeed.
+    pub unreachable: u64,
+    /// Sum of `Content-Length` for URLs that would be downloaded.
+    pub estimated_bytes: u64,
+    /// URLs that would be downloaded but reported no `Content-Length`.
+    pub unknown_size: u64,
+}
+
+impl ScrapePlan {
+    /// URLs a real scrape would download.
+    pub fn would_download(&self) -> u64 {
+        self.new_urls + self.queued + self.changed
+    }
+
+    fn record(&mut self, outcome: PlanOutcome) {
+        let size = match outcome {
+            PlanOutcome::New(size) => {
+                self.new_urls += 1;
+                size
+            }
+            PlanOutcome::Queued(size) => {
+                self.queued += 1;
+                size
+            }
+            PlanOutcome::Changed(size) => {
+                self.changed += 1;
+                size
+            }
+            PlanOutcome::Fresh => {
+                self.fresh += 1;
+                return;
+            }
+            PlanOutcome::Unchanged => {
+                self.unchanged += 1;
+                return;
+            }
+            PlanOutcome::Skipped => {
+                self.skipped += 1;
+                return;
+            }
+            PlanOutcome::Unreachable => {
+                self.unreachable += 1;
+                return;
+            }
+        };
+        match size {
+            Some(bytes) => self.estimated_bytes += bytes,
+            None => self.unknown_size += 1,
+        }
+    }
+}
+
+/// How one discovered URL would be handled; downloads carry their size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlanOutcome {
+    New(Option<u64>),
+    Queued(Option<u64>),
+    Changed(Option<u64>),
+    Fresh,
+    Unchanged,
+    Skipped,
+    Unreachable,
+}
+
+/// Decide from the crawl queue alone, or say which HEAD request is needed.
+enum Check {
+    Done(PlanOutcome),
+    /// HEAD the URL; `conditional` carries the stored ETag/Last-Modified.
+    Head {
+        known: Option<CrawlUrl>,
+        conditional: bool,
+    },
+}
+
+fn classify(known: Option<CrawlUrl>, cutoff: chrono::DateTime<chrono::Utc>) -> Check {
+    let Some(crawl_url) = known else {
+        return Check::Head {
+            known: None,
+            conditional: false,
+        };
+    };
+    match crawl_url.status {
+        UrlStatus::Skipped | UrlStatus::Exhausted => Check::Done(PlanOutcome::Skipped),
+        UrlStatus::Fetched if crawl_url.fetched_at.is_some_and(|at| at >= cutoff) => {
+            Check::Done(PlanOutcome::Fresh)
+        }
+        UrlStatus::Fetched => {
+            let conditional = crawl_url.etag.is_some() || crawl_url.last_modified.is_some();
+            Check::Head {
+                known: Some(crawl_url),
+                conditional,
+            }
+        }
+        UrlStatus::Discovered | UrlStatus::Fetching | UrlStatus::Failed => Check::Head {
+            known: Some(crawl_url),
+            conditional: false,
+        },
+    }
+}
+
+async fn head_outcome(
+    client: &HttpClient,
+    url: &str,
+    known: Option<CrawlUrl>,
+    conditional: bool,
+) -> PlanOutcome {
+    let (etag, last_modified) = match (&known, conditional) {
+        (Some(k), true) => (k.etag.as_deref(), k.last_modified.as_deref()),
+        _ => (None, None),
+    };
+    let response = match client.head(url, etag, last_modified).await {
+        Ok(r) => r,
+        Err(e) => {
+            debug!("HEAD {} failed: {}", url, e);
+            return PlanOutcome::Unreachable;
+        }
+    };
+    if response.is_robots_disallowed() {
+        return PlanOutcome::Skipped;
+    }
+    if response.is_not_modified() {
+        return PlanOutcome::Unchanged;
+    }
+    if !response.is_success() {
+        debug!("HEAD {} returned {}", url, response.status);
+        return PlanOutcome::Unreachable;
+    }
+    let size = response.content_length();
+    match known.map(|k| k.status) {
+        None => PlanOutcome::New(size),
+        Some(UrlStatus::Fetched) => PlanOutcome::Changed(size),
+        Some(_) => PlanOutcome::Queued(size),
+    }
+}
+
+impl ConfigurableScraper {
+    /// Preview a scrape: run discovery and HEAD each URL, writing nothing.
+    ///
+    /// `crawl_repo` is only read, to tell new URLs from known ones. Build the
+    /// scraper itself without a crawl repository so discovery does not queue
+    /// URLs or log requests.
+    pub async fn plan(
+        &self,
+        crawl_repo: Option<&DieselCrawlRepository>,
+        concurrency: usize,
+    ) -> ScrapePlan {
+        let (url_tx, mut url_rx) = tokio::sync::mpsc::channel::<String>(500);
+
+        let config = self.config.clone();
+        let client = self.client.clone();
+        let source_id = self.source.id.clone();
+        #[cfg(feature = "browser")]
+        let browser_config = self.browser_config.clone();
+        let discovery = tokio::spawn(async move {
+            #[cfg(feature = "browser")]
+            Self::discover_streaming(
+                &config,
+                &client,
+                &source_id,
+                &None,
+                &url_tx,
+                &browser_config,
+            )
+            .await;
+            #[cfg(not(feature = "browser"))]
+            Self::discover_streaming(&config, &client, &source_id, &None, &url_tx).await;
+        });
+
+        let mut urls = Vec::new();
+        let mut seen = HashSet::new();
+        while let Some(url) = url_rx.recv().await {
+            if seen.insert(url.clone()) {
+                urls.push(url);
+            }
+        }
+        let _ = discovery.await;
+
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(self.refresh_ttl_days as i64);
+        let source_id = self.source.id.as_str();
+        let client = &self.client;
+
+        let mut plan = ScrapePlan {
+            discovered: urls.len() as u64,
+            ..Default::default()
+        };
+        let mut outcomes = stream::iter(urls)
+            .map(|url| async move {
+                let known = match crawl_repo {
+                    Some(repo) => repo.get_url(source_id, &url).await.ok().flatten(),
+                    None => None,
+                };
+                match classify(known, cutoff) {
+                    Check::Done(outcome) => outcome,
+                    Check::Head { known, conditional } => {
+                        head_outcome(client, &url, known, conditional).await
+                    }
+                }
+            })
+            .buffer_unordered(self.politeness.workers(concurrency));
+        while let Some(outcome) = outcomes.next().await {
+            plan.record(outcome);
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use foia::models::DiscoveryMethod;
+
+    fn crawl_url(status: UrlStatus, days_ago: i64, etag: Option<&str>) -> CrawlUrl {
+        let mut url = CrawlUrl::new(
+            "https://agency.gov/a.pdf".into(),
+            "agency".into(),
+            DiscoveryMethod::HtmlLink,
+            None,
+            0,
+        );
+        url.status = status;
+        url.fetched_at = Some(chrono::Utc::now() - chrono::Duration::days(days_ago));
+        url.etag = etag.map(str::to_string);
+        url
+    }
+
+    #[test]
+    fn test_classify_uses_queue_state() {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(30);
+
+        assert!(matches!(
+            classify(None, cutoff),
+            Check::Head {
+                known: None,
+                conditional: false
+            }
+        ));
+        assert!(matches!(
+            classify(Some(crawl_url(UrlStatus::Fetched, 1, None)), cutoff),
+            Check::Done(PlanOutcome::Fresh)
+        ));
+        assert!(matches!(
+            classify(
+                Some(crawl_url(UrlStatus::Fetched, 60, Some("\"v1\""))),
+                cutoff
+            ),
+            Check::Head {
+                conditional: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            classify(Some(crawl_url(UrlStatus::Exhausted, 1, None)), cutoff),
+            Check::Done(PlanOutcome::Skipped)
+        ));
+        assert!(matches!(
+            classify(Some(crawl_url(UrlStatus::Failed, 1, None)), cutoff),
+            Check::Head {
+                conditional: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_plan_totals() {
+        let mut plan = ScrapePlan::default();
+        plan.record(PlanOutcome::New(Some(1000)));
+        plan.record(PlanOutcome::New(None));
+        plan.record(PlanOutcome::Changed(Some(500)));
+        plan.record(PlanOutcome::Unchanged);
+        plan.record(PlanOutcome::Fresh);
+
+        assert_eq!(plan.would_download(), 3);
+        assert_eq!(plan.estimated_bytes, 1500);
+        assert_eq!(plan.unknown_size, 1);
+        assert_eq!(plan.unchanged, 1);
+        assert_eq!(plan.fresh, 1);
+    }
+}
@@ -0,0 +1,162 @@
+//! Reading room discovery: index pages, subject folders, documents.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use tracing::{debug, info, warn};
+use url::Url;
+
+use super::ConfigurableScraper;
+use crate::config::ScraperConfig;
+use crate::reading_room::{parse_listing, ReadingRoom};
+use crate::HttpClient;
+use foia::models::{CrawlUrl, DiscoveryMethod};
+use foia::repository::DieselCrawlRepository;
+
+/// Folder levels followed below an index when `max_depth` isn't set.
+const DEFAULT_MAX_DEPTH: u32 = 3;
+
+/// Listing pages read in total, as a guard against pagination loops.
+const MAX_PAGES: usize = 10_000;
+
+impl ConfigurableScraper {
+    /// Streaming discovery for an agency reading room (`fbi_vault`,
+    /// `doj_foia_library`).
+    ///
+    /// Walks the room's index into its subject folders, following each
+    /// listing's pagination, and queues the documents filed in them. Every
+    /// document is tagged with the room's agency and the subject folders it
+    /// was found under.
+    pub(crate) async fn discover_reading_room_streaming(
+        config: &ScraperConfig,
+        client: &HttpClient,
+        source_id: &str,
+        crawl_repo: &Option<Arc<DieselCrawlRepository>>,
+        url_tx: &tokio::sync::mpsc::Sender<String>,
+    ) {
+        let Some(mut room) = ReadingRoom::preset(&config.discovery.discovery_type) else {
+            return;
+        };
+        if let Some(overrides) = &config.discovery.reading_room {
+            room = room.with_config(overrides);
+        }
+        if let Some(base_url) = config
+            .discovery
+            .base_url
+            .as_ref()
+            .or(config.base_url.as_ref())
+        {
+            room.base_url = base_url.trim_end_matches('/').to_string();
+        }
+        if !config.discovery.start_paths.is_empty() {
+            room.index_paths = config.discovery.start_paths.clone();
+        }
+        let patterns = match room.patterns() {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                warn!("[{}] Invalid reading room pattern: {}", source_id, e);
+                return;
+            }
+        };
+        let Ok(base) = Url::parse(&room.base_url) else {
+            warn!(
+                "[{}] Invalid reading room base URL: {}",
+                source_id, room.base_url
+            );
+            return;
+        };
+        let max_depth = config.discovery.max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+
+        info!("Starting reading room discovery from {}", room.base_url);
+
+        let base_tags: Vec<String> = room.agency_tag.iter().cloned().collect();
+        let mut queue: VecDeque<(String, Vec<String>, u32)> = room
+            .index_paths
+            .iter()
+            .filter_map(|path| base.join(path).ok())
+            .map(|url| (url.to_string(), base_tags.clone(), 0))
+            .collect();
+        let mut visited = HashSet::new();
+        let mut queued_docs = HashSet::new();
+        let mut folders = 0;
+
+        while let Some((page_url, tags, depth)) = queue.pop_front() {
+            if visited.len() >= MAX_PAGES {
+                warn!("[{}] Stopping after {} listing pages", source_id, MAX_PAGES);
+                break;
+            }
+            if !visited.insert(page_url.clone()) || !client.page_in_scope(&page_url, depth) {
+                continue;
+            }
+
+            let response = match client.get(&page_url, None, None).await {
+                Ok(r) if r.is_success() => r,
+                Ok(r) => {
+                    warn!(
+                        "Reading room page failed (HTTP {}) - {}",
+                        r.status, page_url
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Reading room page error: {} - {}", e, page_url);
+                    continue;
+                }
+            };
+            let Ok(html) = response.text().await else {
+                continue;
+            };
+            let Ok(parsed_url) = Url::parse(&page_url) else {
+                continue;
+            };
+            let listing = parse_listing(&html, &parsed_url, &room, &patterns);
+            debug!(
+                "Reading room page {}: {} folders, {} documents",
+                page_url,
+                listing.folders.len(),
+                listing.documents.len()
+            );
+
+            for url in listing.documents {
+                if !queued_docs.insert(url.clone()) || !client.in_scope(&url, Some(depth + 1)) {
+                    continue;
+                }
+                if let Some(repo) = crawl_repo {
+                    let crawl_url = CrawlUrl::new(
+                        url.clone(),
+                        source_id.to_string(),
+                        DiscoveryMethod::HtmlLink,
+                        Some(page_url.clone()),
+                        depth + 1,
+                    )
+                    .with_tags(&tags);
+                    let _ = repo.add_url(&crawl_url).await;
+                }
+                if url_tx.send(url).await.is_err() {
+                    return; // Receiver dropped
+                }
+            }
+
+            if let Some(next) = listing.next_page {
+                queue.push_back((next, tags.clone(), depth));
+            }
+            if depth < max_depth {
+                for folder in listing.folders {
+                    let mut folder_tags = tags.clone();
+                    if !folder_tags.contains(&folder.tag) {
+                        folder_tags.push(folder.tag);
+                    }
+                    folders += 1;
+                    queue.push_back((folder.url, folder_tags, depth + 1));
+                }
+            }
+        }
+
+        info!(
+            "[{}] Reading room discovery complete: {} folders, {} documents",
+            source_id,
+            folders,
+            queued_docs.len()
+        );
+    }
+}
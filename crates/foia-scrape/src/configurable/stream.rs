@@ -6,13 +6,33 @@ use tracing::debug;
 #[cfg(feature = "browser")]
 use super::fetch::FetchError;
 use super::ConfigurableScraper;
-use crate::{ScrapeStream, ScraperResult};
+use crate::{HttpClient, ScrapeStream, ScraperResult};
 #[cfg(feature = "browser")]
 use foia::browser::BrowserFetcher;
+use foia::http_client::ROBOTS_SKIP_REASON;
+use foia::models::agency_tag;
+use foia::services::quarantine;
 
 /// Default number of concurrent downloads.
 pub const DEFAULT_CONCURRENCY: usize = 4;
 
+/// Log how many URLs the crawl scope kept out, by reason.
+fn log_scope_exclusions(source_id: &str, client: &HttpClient) {
+    let counts: Vec<String> = client
+        .scope_exclusions()
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(reason, count)| format!("{} {}", count, reason.as_str()))
+        .collect();
+    if !counts.is_empty() {
+        tracing::info!(
+            "[{}] Excluded by crawl scope: {}",
+            source_id,
+            counts.join(", ")
+        );
+    }
+}
+
 impl ConfigurableScraper {
     /// Scrape documents from the source (legacy batch interface).
     pub async fn scrape(&self) -> Vec<ScraperResult> {
@@ -48,11 +68,20 @@ impl ConfigurableScraper {
 
         // Spawn download workers
         let workers = self
-            .spawn_download_workers(concurrency, url_rx, result_tx.clone())
+            .spawn_download_workers(
+                self.politeness.workers(concurrency),
+                url_rx,
+                result_tx.clone(),
+            )
             .await;
 
-        // Spawn discovery task
-        let discovery_handle = self.spawn_discovery_task(url_tx).await;
+        // Spawn discovery task; archived listing pages skip the workers
+        let listing_tx = self
+            .config
+            .discovery
+            .archive_listings
+            .then(|| result_tx.clone());
+        let discovery_handle = self.spawn_discovery_task(url_tx, listing_tx).await;
 
         // Spawn coordinator to clean up when done
         tokio::spawn(async move {
@@ -142,10 +171,19 @@ impl ConfigurableScraper {
             .clone()
             .or_else(|| self.config.discovery.base_url.clone());
 
+        let trust_content_type = self.config.fetch.trust_content_type;
+        let agency_tag = self
+            .source
+            .agency_id
+            .as_deref()
+            .or(self.config.agency.as_deref())
+            .map(agency_tag);
+
         for _ in 0..count {
             let url_rx = url_rx.clone();
             let result_tx = result_tx.clone();
             let client = self.client.clone();
+            let agency_tag = agency_tag.clone();
             #[cfg(feature = "browser")]
             let browser_config = browser_config.clone();
             #[cfg(feature = "browser")]
@@ -172,6 +210,17 @@ impl ConfigurableScraper {
                         continue;
                     }
 
+                    if !client.robots_allows(&url).await {
+                        client.mark_skipped(&url, ROBOTS_SKIP_REASON).await;
+                        continue;
+                    }
+
+                    // Also catches URLs queued before the scope was narrowed
+                    if !client.in_scope(&url, None) {
+                        client.mark_skipped(&url, "outside crawl scope").await;
+                        continue;
+                    }
+
                     client.mark_fetching(&url).await;
 
                     #[cfg(feature = "browser")]
@@ -208,7 +257,25 @@ impl ConfigurableScraper {
                     let fetch_result = Self::fetch_url(&client, &url).await;
 
                     match fetch_result {
-                        Some(result) => {
+                        Some(mut result) => {
+                            if !trust_content_type {
+                                result.correct_mime_type();
+                            }
+                            if let Some(page) = result.content.as_deref().and_then(|c| {
+                                quarantine::detect_error_page(c, &result.mime_type, &url)
+                            }) {
+                                debug!("Quarantined {}: {}", url, page.as_str());
+                                client.mark_failed(&url, &page.error_message()).await;
+                                continue;
+                            }
+                            if let Some(crawl_url) = client.crawl_url(&url).await {
+                                result.apply_discovery(&crawl_url);
+                            }
+                            if let Some(tag) = &agency_tag {
+                                if !result.tags.contains(tag) {
+                                    result.tags.push(tag.clone());
+                                }
+                            }
                             client
                                 .mark_fetched(
                                     &url,
@@ -244,6 +311,7 @@ impl ConfigurableScraper {
     pub(crate) async fn spawn_discovery_task(
         &self,
         url_tx: tokio::sync::mpsc::Sender<String>,
+        listing_tx: Option<tokio::sync::mpsc::Sender<ScraperResult>>,
     ) -> tokio::task::JoinHandle<()> {
         let source_id = self.source.id.clone();
         let config = self.config.clone();
@@ -314,11 +382,22 @@ impl ConfigurableScraper {
                 &source_id,
                 &crawl_repo,
                 &url_tx,
+                &listing_tx,
                 &browser_config,
             )
             .await;
             #[cfg(not(feature = "browser"))]
-            Self::discover_streaming(&config, &client, &source_id, &crawl_repo, &url_tx).await;
+            Self::discover_streaming(
+                &config,
+                &client,
+                &source_id,
+                &crawl_repo,
+                &url_tx,
+                &listing_tx,
+            )
+            .await;
+
+            log_scope_exclusions(&source_id, &client);
         })
     }
 
@@ -0,0 +1,305 @@
+//! CourtListener (RECAP) court records support.
+//!
+//! CourtListener's RECAP archive holds PACER dockets and the filings
+//! people have bought from PACER. FOIA lawsuits are where agencies file
+//! declarations and Vaughn indexes describing what they withheld, so a
+//! case's docket is often as useful as the records it releases.
+//!
+//! Provides functionality to:
+//! - Build search, docket and docket entry API URLs
+//! - Read dockets, entries and search results from API responses
+//! - Map available filings to download URLs with docket metadata
+
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+/// REST API root.
+pub const API_ROOT: &str = "https://www.courtlistener.com/api/rest/v4";
+
+/// Site root, for docket pages.
+pub const SITE_ROOT: &str = "https://www.courtlistener.com";
+
+/// Where RECAP filings are stored, joined with a filing's `filepath_local`.
+pub const STORAGE_ROOT: &str = "https://storage.courtlistener.com";
+
+/// Environment variable holding the API token. Docket entries need one;
+/// search works without.
+pub const TOKEN_ENV: &str = "COURTLISTENER_API_TOKEN";
+
+/// A docket's identifying details, copied onto each of its filings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Docket {
+    pub id: String,
+    pub case_name: Option<String>,
+    pub docket_number: Option<String>,
+    pub court: Option<String>,
+    /// Docket page on CourtListener.
+    pub url: String,
+}
+
+/// A filing with a downloadable PDF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filing {
+    pub url: String,
+    pub metadata: Map<String, Value>,
+}
+
+/// Request headers carrying the API token, if one is set.
+pub fn auth_headers() -> HashMap<String, String> {
+    std::env::var(TOKEN_ENV)
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .map(|token| {
+            HashMap::from([(
+                "Authorization".to_string(),
+                format!("Token {}", token.trim()),
+            )])
+        })
+        .unwrap_or_default()
+}
+
+/// The docket ID in a configured docket: a bare ID or a docket page URL
+/// (`https://www.courtlistener.com/docket/12345/case-name/`).
+pub fn docket_id(value: &str) -> Option<String> {
+    let value = value.trim();
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        return Some(value.to_string());
+    }
+    let re = Regex::new(r"courtlistener\.com/docket/(\d+)").ok()?;
+    re.captures(value).map(|c| c[1].to_string())
+}
+
+/// A docket's details.
+pub fn docket_url(id: &str) -> String {
+    format!("{}/dockets/{}/", API_ROOT, id)
+}
+
+/// A docket's entries, oldest first.
+pub fn docket_entries_url(id: &str) -> String {
+    format!(
+        "{}/docket-entries/?docket={}&order_by=entry_number",
+        API_ROOT, id
+    )
+}
+
+/// A RECAP search, optionally limited to one court.
+pub fn search_url(query: &str, court: Option<&str>) -> String {
+    let mut url = format!(
+        "{}/search/?type=r&q={}",
+        API_ROOT,
+        urlencoding::encode(query)
+    );
+    if let Some(court) = court {
+        url.push_str("&court=");
+        url.push_str(&urlencoding::encode(court));
+    }
+    url
+}
+
+/// The URL of the next page of a paged response, if there is one.
+pub fn next_page(data: &Value) -> Option<String> {
+    data.get("next")
+        .and_then(Value::as_str)
+        .filter(|next| !next.is_empty())
+        .map(str::to_string)
+}
+
+fn string_field(value: &Value, fields: &[&str]) -> Option<String> {
+    fields.iter().find_map(|field| match value.get(*field)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+fn absolute(path: &str) -> String {
+    if path.starts_with("http") {
+        path.to_string()
+    } else {
+        format!("{}{}", SITE_ROOT, path)
+    }
+}
+
+/// A docket from the dockets endpoint.
+pub fn parse_docket(data: &Value) -> Option<Docket> {
+    let id = string_field(data, &["id"])?;
+    Some(Docket {
+        url: string_field(data, &["absolute_url"])
+            .map(|u| absolute(&u))
+            .unwrap_or_else(|| format!("{}/docket/{}/", SITE_ROOT, id)),
+        case_name: string_field(data, &["case_name", "case_name_short"]),
+        docket_number: string_field(data, &["docket_number"]),
+        court: string_field(data, &["court_id"]),
+        id,
+    })
+}
+
+/// Docket IDs in a page of RECAP search results.
+pub fn parse_search(data: &Value) -> Vec<String> {
+    let mut ids: Vec<String> = data
+        .get("results")
+        .and_then(Value::as_array)
+        .map(|results| {
+            results
+                .iter()
+                .filter_map(|r| string_field(r, &["docket_id"]))
+                .collect()
+        })
+        .unwrap_or_default();
+    ids.dedup();
+    ids
+}
+
+/// Available filings in a page of docket entries. Filings RECAP doesn't
+/// hold yet have no file and are skipped.
+pub fn parse_entries(data: &Value, docket: &Docket) -> Vec<Filing> {
+    let Some(entries) = data.get("results").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut filings = Vec::new();
+    for entry in entries {
+        let Some(documents) = entry.get("recap_documents").and_then(Value::as_array) else {
+            continue;
+        };
+        for document in documents {
+            if document.get("is_available").and_then(Value::as_bool) == Some(false) {
+                continue;
+            }
+            let Some(path) = string_field(document, &["filepath_local"]) else {
+                continue;
+            };
+
+            let mut metadata = Map::new();
+            metadata.insert("courtlistener_docket_id".into(), json!(docket.id));
+            metadata.insert("docket_url".into(), json!(docket.url));
+            let fields = [
+                ("case_name", docket.case_name.clone()),
+                ("docket_number", docket.docket_number.clone()),
+                ("court", docket.court.clone()),
+                ("entry_number", string_field(entry, &["entry_number"])),
+                ("date_filed", string_field(entry, &["date_filed"])),
+                (
+                    "description",
+                    string_field(document, &["description"])
+                        .or_else(|| string_field(entry, &["description"])),
+                ),
+                (
+                    "document_number",
+                    string_field(document, &["document_number"]),
+                ),
+                (
+                    "attachment_number",
+                    string_field(document, &["attachment_number"]),
+                ),
+                ("page_count", string_field(document, &["page_count"])),
+            ];
+            for (key, value) in fields
+                .into_iter()
+                .filter_map(|(key, value)| Some((key, value?)))
+            {
+                metadata.insert(key.into(), json!(value));
+            }
+
+            filings.push(Filing {
+                url: format!("{}/{}", STORAGE_ROOT, path.trim_start_matches('/')),
+                metadata,
+            });
+        }
+    }
+    filings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(docket_id("4214664").as_deref(), Some("4214664"));
+        assert_eq!(
+            docket_id("https://www.courtlistener.com/docket/4214664/aclu-v-fbi/").as_deref(),
+            Some("4214664")
+        );
+        assert_eq!(docket_id("aclu v fbi"), None);
+        assert_eq!(
+            search_url("FOIA Vaughn index", Some("dcd")),
+            "https://www.courtlistener.com/api/rest/v4/search/?type=r&q=FOIA%20Vaughn%20index&court=dcd"
+        );
+        assert_eq!(
+            docket_entries_url("42"),
+            "https://www.courtlistener.com/api/rest/v4/docket-entries/?docket=42&order_by=entry_number"
+        );
+    }
+
+    #[test]
+    fn test_parse_docket_and_entries() {
+        let docket = parse_docket(&json!({
+            "id": 4214664,
+            "absolute_url": "/docket/4214664/aclu-v-fbi/",
+            "case_name": "ACLU v. FBI",
+            "docket_number": "1:17-cv-01234",
+            "court_id": "dcd"
+        }))
+        .unwrap();
+        assert_eq!(
+            docket.url,
+            "https://www.courtlistener.com/docket/4214664/aclu-v-fbi/"
+        );
+
+        let entries = json!({
+            "next": "https://www.courtlistener.com/api/rest/v4/docket-entries/?cursor=abc",
+            "results": [{
+                "entry_number": 15,
+                "date_filed": "2018-03-02",
+                "description": "DECLARATION of David M. Hardy",
+                "recap_documents": [
+                    {"document_number": "15", "attachment_number": null, "is_available": true,
+                     "filepath_local": "recap/gov.uscourts.dcd.187000/gov.uscourts.dcd.187000.15.0.pdf",
+                     "page_count": 40, "description": ""},
+                    {"document_number": "15", "attachment_number": 1, "is_available": false,
+                     "filepath_local": ""}
+                ]
+            }]
+        });
+        assert_eq!(
+            next_page(&entries).as_deref(),
+            Some("https://www.courtlistener.com/api/rest/v4/docket-entries/?cursor=abc")
+        );
+        let filings = parse_entries(&entries, &docket);
+        assert_eq!(filings.len(), 1);
+        assert_eq!(
+            filings[0].url,
+            "https://storage.courtlistener.com/recap/gov.uscourts.dcd.187000/gov.uscourts.dcd.187000.15.0.pdf"
+        );
+        assert_eq!(
+            Value::Object(filings[0].metadata.clone()),
+            json!({
+                "courtlistener_docket_id": "4214664",
+                "docket_url": "https://www.courtlistener.com/docket/4214664/aclu-v-fbi/",
+                "case_name": "ACLU v. FBI",
+                "docket_number": "1:17-cv-01234",
+                "court": "dcd",
+                "entry_number": "15",
+                "date_filed": "2018-03-02",
+                "description": "DECLARATION of David M. Hardy",
+                "document_number": "15",
+                "page_count": "40",
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_search() {
+        let data = json!({"results": [
+            {"docket_id": 1, "caseName": "A"},
+            {"docket_id": 1, "caseName": "A"},
+            {"docket_id": 2, "caseName": "B"},
+            {"caseName": "No docket"}
+        ]});
+        assert_eq!(parse_search(&data), vec!["1", "2"]);
+        assert_eq!(next_page(&data), None);
+    }
+}
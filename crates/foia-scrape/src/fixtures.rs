@@ -0,0 +1,112 @@
+//! Scraper test harness: expected discovery results for recorded fixtures.
+//!
+//! `foia scraper record` runs a source's discovery live, saving every
+//! response to a fixture directory (see [`FixtureStore`]) along with the
+//! document URLs it found in `expected.json`. `foia scraper test` replays
+//! discovery from the fixtures and compares the result to that file.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use foia::http_client::{FixtureMode, FixtureStore};
+
+/// File in a fixture directory listing the expected document URLs.
+pub const EXPECTED_FILE: &str = "expected.json";
+
+/// Default fixture directory for a source.
+pub fn default_fixture_dir(data_dir: &Path, source_id: &str) -> PathBuf {
+    data_dir.join("fixtures").join(source_id)
+}
+
+/// Document URLs a source's discovery should find in its fixtures.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveryExpectation {
+    pub source_id: String,
+    pub recorded_at: DateTime<Utc>,
+    /// Sorted, deduplicated document URLs.
+    pub documents: Vec<String>,
+}
+
+/// Difference between expected and discovered document URLs.
+#[derive(Debug, Default, PartialEq)]
+pub struct DiscoveryDiff {
+    /// Expected but not discovered.
+    pub missing: Vec<String>,
+    /// Discovered but not expected.
+    pub unexpected: Vec<String>,
+}
+
+impl DiscoveryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl DiscoveryExpectation {
+    pub fn new(source_id: &str, documents: Vec<String>) -> Self {
+        Self {
+            source_id: source_id.to_string(),
+            recorded_at: Utc::now(),
+            documents: documents
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Load `expected.json` from a fixture directory.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(EXPECTED_FILE);
+        let json = std::fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    /// Write `expected.json` into a fixture directory.
+    pub fn save(&self, dir: &Path) -> anyhow::Result<()> {
+        std::fs::write(dir.join(EXPECTED_FILE), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Compare discovered URLs against the expected ones.
+    pub fn compare(&self, discovered: &[String]) -> DiscoveryDiff {
+        let expected: BTreeSet<&String> = self.documents.iter().collect();
+        let found: BTreeSet<&String> = discovered.iter().collect();
+        DiscoveryDiff {
+            missing: expected.difference(&found).map(|u| u.to_string()).collect(),
+            unexpected: found.difference(&expected).map(|u| u.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(list: &[&str]) -> Vec<String> {
+        list.iter().map(|u| u.to_string()).collect()
+    }
+
+    #[test]
+    fn test_expectation_sorts_and_dedups() {
+        let expected = DiscoveryExpectation::new("agency", urls(&["b.pdf", "a.pdf", "b.pdf"]));
+        assert_eq!(expected.documents, urls(&["a.pdf", "b.pdf"]));
+    }
+
+    #[test]
+    fn test_compare() {
+        let expected = DiscoveryExpectation::new("agency", urls(&["a.pdf", "b.pdf"]));
+        assert!(expected
+            .compare(&urls(&["b.pdf", "a.pdf", "a.pdf"]))
+            .is_empty());
+
+        let diff = expected.compare(&urls(&["a.pdf", "c.pdf"]));
+        assert_eq!(diff.missing, urls(&["b.pdf"]));
+        assert_eq!(diff.unexpected, urls(&["c.pdf"]));
+        assert!(!diff.is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! GovQA public records portal support.
+//!
+//! GovQA hosts public records portals for hundreds of cities and counties.
+//! Each is an ASP.NET application that keeps its session in the URL path
+//! (`/WEBAPP/_rs/(S(token))/RequestArchive.aspx`), pages its archive grid
+//! through form postbacks and serves attachments from session-bound links.
+//!
+//! Provides functionality to:
+//! - Strip session tokens so the same attachment keeps one URL across runs
+//! - Find request detail links and attachment links on portal pages
+//! - Build the postback that loads an archive's next page
+
+use std::collections::HashSet;
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use url::Url;
+
+use foia::utils::has_document_extension;
+
+/// Archive page listing released requests on a stock install.
+pub const DEFAULT_ARCHIVE_PATH: &str = "RequestArchive.aspx";
+
+/// Links from an archive page to a request's details on a stock install.
+pub const DEFAULT_DETAIL_PATTERN: &str = r"(?i)RequestArchiveDetails\.aspx\?rid=\d+";
+
+/// Attachment download links on a stock install.
+pub const DEFAULT_ATTACHMENT_PATTERN: &str =
+    r"(?i)(GetAttachment|DownloadAttachment|RequestAttachment|FileDownload)";
+
+/// Pager link text that means "next page".
+const NEXT_LABELS: &[&str] = &["next", "next >", "next page", ">", "»", "›"];
+
+fn session_regex() -> Regex {
+    Regex::new(r"/\(S\(([A-Za-z0-9]+)\)\)").expect("valid session regex")
+}
+
+/// The session token embedded in a portal URL, if any.
+pub fn session_token(url: &str) -> Option<String> {
+    session_regex().captures(url).map(|c| c[1].to_string())
+}
+
+/// Remove the session segment from a portal URL.
+///
+/// Session tokens change every visit; without one the portal starts a new
+/// session and redirects to the same page.
+pub fn strip_session(url: &str) -> String {
+    session_regex().replace(url, "").into_owned()
+}
+
+fn links(document: &Html, page_url: &Url) -> Vec<String> {
+    let Ok(selector) = Selector::parse("a[href]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter_map(|a| a.value().attr("href"))
+        .filter(|href| !href.starts_with("javascript:") && !href.starts_with('#'))
+        .filter_map(|href| page_url.join(href).ok())
+        .map(|url| url.to_string())
+        .collect()
+}
+
+/// Links to request details on an archive page, in page order. These keep
+/// the session so they can be fetched within it.
+pub fn detail_links(html: &str, page_url: &Url, pattern: &Regex) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let mut seen = HashSet::new();
+    links(&document, page_url)
+        .into_iter()
+        .filter(|url| pattern.is_match(url))
+        .filter(|url| seen.insert(strip_session(url)))
+        .collect()
+}
+
+/// Attachment links on a request's details, with the session stripped.
+/// Links to files with a document extension count even when `pattern`
+/// doesn't match them.
+pub fn attachment_links(html: &str, page_url: &Url, pattern: &Regex) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let mut seen = HashSet::new();
+    links(&document, page_url)
+        .into_iter()
+        .filter(|url| pattern.is_match(url) || has_document_extension(url))
+        .map(|url| strip_session(&url))
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// A form postback that loads another page of an archive grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Postback {
+    /// Where the form posts to.
+    pub action: String,
+    /// Hidden form state plus the event that selects the next page.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Parse `__doPostBack('target','argument')` into its target and argument.
+fn parse_do_postback(script: &str) -> Option<(String, String)> {
+    let re = Regex::new(r#"__doPostBack\(\s*['"]([^'"]*)['"]\s*,\s*['"]([^'"]*)['"]\s*\)"#).ok()?;
+    let caps = re.captures(script)?;
+    Some((caps[1].to_string(), caps[2].to_string()))
+}
+
+/// The postback behind an archive page's "next page" link, if it has one.
+pub fn next_page_postback(html: &str, page_url: &Url) -> Option<Postback> {
+    let document = Html::parse_document(html);
+
+    let pager = Selector::parse("a[href*=\"__doPostBack\"]").ok()?;
+    let (target, argument) = document
+        .select(&pager)
+        .find(|a| {
+            let text = a.text().collect::<String>().trim().to_lowercase();
+            let title = a.value().attr("title").unwrap_or("").to_lowercase();
+            NEXT_LABELS.contains(&text.as_str()) || title.contains("next")
+        })
+        .and_then(|a| parse_do_postback(a.value().attr("href")?))?;
+
+    let form = Selector::parse("form").ok()?;
+    let form = document.select(&form).next()?;
+    let action = form
+        .value()
+        .attr("action")
+        .and_then(|a| page_url.join(a).ok())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| page_url.to_string());
+
+    let hidden = Selector::parse("input[type=hidden][name]").ok()?;
+    let mut fields: Vec<(String, String)> = form
+        .select(&hidden)
+        .filter_map(|input| {
+            let name = input.value().attr("name")?;
+            let value = input.value().attr("value").unwrap_or("");
+            Some((name.to_string(), value.to_string()))
+        })
+        .filter(|(name, _)| name != "__EVENTTARGET" && name != "__EVENTARGUMENT")
+        .collect();
+    fields.push(("__EVENTTARGET".to_string(), target));
+    fields.push(("__EVENTARGUMENT".to_string(), argument));
+
+    Some(Postback { action, fields })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_tokens() {
+        let url = "https://cityofx.govqa.us/WEBAPP/_rs/(S(abc123xyz))/RequestArchive.aspx";
+        assert_eq!(session_token(url).as_deref(), Some("abc123xyz"));
+        assert_eq!(
+            strip_session(url),
+            "https://cityofx.govqa.us/WEBAPP/_rs/RequestArchive.aspx"
+        );
+        assert_eq!(strip_session("https://a.gov/x.pdf"), "https://a.gov/x.pdf");
+        assert_eq!(session_token("https://a.gov/x.pdf"), None);
+    }
+
+    #[test]
+    fn test_archive_and_attachment_links() {
+        let page =
+            Url::parse("https://cityofx.govqa.us/WEBAPP/_rs/(S(abc))/RequestArchive.aspx").unwrap();
+        let archive = r#"<html><body>
+            <a href="RequestArchiveDetails.aspx?rid=101">R000101</a>
+            <a href="RequestArchiveDetails.aspx?rid=101">again</a>
+            <a href="RequestArchiveDetails.aspx?rid=102">R000102</a>
+            <a href="SupportHome.aspx">Home</a>
+        </body></html>"#;
+        let detail = Regex::new(DEFAULT_DETAIL_PATTERN).unwrap();
+        assert_eq!(
+            detail_links(archive, &page, &detail),
+            vec![
+                "https://cityofx.govqa.us/WEBAPP/_rs/(S(abc))/RequestArchiveDetails.aspx?rid=101",
+                "https://cityofx.govqa.us/WEBAPP/_rs/(S(abc))/RequestArchiveDetails.aspx?rid=102",
+            ]
+        );
+
+        let details = r#"<html><body>
+            <a href="GetAttachment.aspx?aid=9&rid=101">Response letter</a>
+            <a href="/files/records.pdf">records.pdf</a>
+            <a href="javascript:void(0)">Print</a>
+            <a href="RequestArchive.aspx">Back</a>
+        </body></html>"#;
+        let attachment = Regex::new(DEFAULT_ATTACHMENT_PATTERN).unwrap();
+        assert_eq!(
+            attachment_links(details, &page, &attachment),
+            vec![
+                "https://cityofx.govqa.us/WEBAPP/_rs/GetAttachment.aspx?aid=9&rid=101",
+                "https://cityofx.govqa.us/files/records.pdf",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_page_postback() {
+        let page =
+            Url::parse("https://cityofx.govqa.us/WEBAPP/_rs/(S(abc))/RequestArchive.aspx").unwrap();
+        let html = r#"<html><body>
+            <form method="post" action="./RequestArchive.aspx">
+              <input type="hidden" name="__VIEWSTATE" value="vs==" />
+              <input type="hidden" name="__EVENTTARGET" value="" />
+              <a href="javascript:__doPostBack('grid$pager','Page$1')">1</a>
+              <a href="javascript:__doPostBack('grid$pager','Page$Next')">Next</a>
+            </form>
+        </body></html>"#;
+        let postback = next_page_postback(html, &page).unwrap();
+        assert_eq!(postback.action, page.to_string());
+        assert_eq!(
+            postback.fields,
+            vec![
+                ("__VIEWSTATE".to_string(), "vs==".to_string()),
+                ("__EVENTTARGET".to_string(), "grid$pager".to_string()),
+                ("__EVENTARGUMENT".to_string(), "Page$Next".to_string()),
+            ]
+        );
+
+        let last = r#"<form><a href="javascript:__doPostBack('grid$pager','Page$1')">1</a></form>"#;
+        assert_eq!(next_page_postback(last, &page), None);
+    }
+}
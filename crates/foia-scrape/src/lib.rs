@@ -2,12 +2,19 @@
 
 #![allow(dead_code)]
 
+pub mod aggregator;
 pub mod archive;
 pub mod cdx;
 pub mod config;
 pub mod configurable;
+pub mod courtlistener;
 pub mod discovery;
+pub mod fixtures;
 pub mod google_drive;
+pub mod govqa;
+pub mod links;
+pub mod nextrequest;
+pub mod reading_room;
 pub mod services;
 #[allow(unused_imports)]
 pub use archive::{ArchiveError, ArchiveRegistry, ArchiveSource, SnapshotInfo, WaybackSource};
@@ -15,7 +22,7 @@ pub use archive::{ArchiveError, ArchiveRegistry, ArchiveSource, SnapshotInfo, Wa
 pub use config::ScraperConfig;
 #[allow(unused_imports)]
 pub use config::ViaMode;
-pub use configurable::ConfigurableScraper;
+pub use configurable::{ConfigurableScraper, ScrapePlan};
 #[cfg(feature = "browser")]
 pub use foia::browser::BrowserFetcher;
 #[cfg(feature = "browser")]
@@ -77,6 +84,10 @@ pub struct ScraperResult {
     pub archive_snapshot_id: Option<i32>,
     /// When the archive captured this content (for provenance).
     pub archive_captured_at: Option<DateTime<Utc>>,
+    /// Content-Type the server declared, when the content showed it was wrong.
+    pub declared_mime_type: Option<String>,
+    /// Tags assigned during discovery (e.g. a reading room's subject folder).
+    pub tags: Vec<String>,
 }
 
 impl ScraperResult {
@@ -96,6 +107,8 @@ impl ScraperResult {
             server_date: None,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         }
     }
 
@@ -115,6 +128,8 @@ impl ScraperResult {
             server_date: None,
             archive_snapshot_id: None,
             archive_captured_at: None,
+            declared_mime_type: None,
+            tags: Vec::new(),
         }
     }
 
@@ -143,8 +158,57 @@ impl ScraperResult {
             server_date: Some(captured_at), // Use archive capture time as server date
             archive_snapshot_id: Some(snapshot_id),
             archive_captured_at: Some(captured_at),
+            declared_mime_type: None,
+            tags: Vec::new(),
         }
     }
+
+    /// Replace a declared MIME type that the content contradicts with the
+    /// detected one, remembering what the server said.
+    pub fn correct_mime_type(&mut self) {
+        let Some(content) = &self.content else {
+            return;
+        };
+        if let Some(detected) = foia::utils::sniff_mime_type(content, &self.mime_type) {
+            let declared = std::mem::replace(&mut self.mime_type, detected.to_string());
+            self.declared_mime_type = Some(declared);
+        }
+    }
+
+    /// Apply what discovery recorded about the URL: its tags, and metadata
+    /// found alongside the link. Metadata from the fetch itself wins.
+    pub fn apply_discovery(&mut self, crawl_url: &CrawlUrl) {
+        self.tags = crawl_url.discovery_tags();
+        if let serde_json::Value::Object(metadata) = &mut self.metadata {
+            for (key, value) in crawl_url.discovery_metadata() {
+                metadata.entry(key).or_insert(value);
+            }
+        }
+    }
+
+    /// Create a result for a crawled listing page being archived.
+    ///
+    /// Titled from the page's `<title>`, falling back to the URL.
+    pub fn listing_page(url: String, html: &str) -> Self {
+        let title = scraper::Selector::parse("title")
+            .ok()
+            .and_then(|selector| {
+                scraper::Html::parse_document(html)
+                    .select(&selector)
+                    .next()
+                    .map(|el| el.text().collect::<String>().trim().to_string())
+            })
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| extract_title_from_url(&url));
+        let mut result = Self::new(
+            url,
+            title,
+            html.as_bytes().to_vec(),
+            "text/html".to_string(),
+        );
+        result.metadata = serde_json::json!({"listing_page": true});
+        result
+    }
 }
 
 impl From<&ScraperResult> for DocumentInput {
@@ -156,6 +220,8 @@ impl From<&ScraperResult> for DocumentInput {
             metadata: result.metadata.clone(),
             original_filename: result.original_filename.clone(),
             server_date: result.server_date,
+            declared_mime_type: result.declared_mime_type.clone(),
+            tags: result.tags.clone(),
         }
     }
 }
@@ -168,14 +234,35 @@ pub async fn save_scraped_document_async(
     source_id: &str,
     documents_dir: &Path,
 ) -> anyhow::Result<bool> {
-    foia::storage::save_document_async(
+    // Without validators a re-rendered page looks new on every fetch; keep
+    // the current version unless its fingerprint changed
+    if result.etag.is_none() && result.last_modified.is_none() {
+        if let Some(doc) = doc_repo.find_by_source_url(source_id, &result.url).await? {
+            if foia::storage::fingerprint_matches(&doc, documents_dir, content, &result.mime_type)
+                .await
+            {
+                foia::metrics::DOCUMENTS_DOWNLOADED.inc(&[source_id, "unchanged"]);
+                return Ok(false);
+            }
+        }
+    }
+
+    let saved = foia::storage::save_document_async(
         doc_repo,
         content,
         &DocumentInput::from(result),
         source_id,
         documents_dir,
     )
-    .await
+    .await;
+    let outcome = if saved.is_ok() {
+        "downloaded"
+    } else {
+        "failed"
+    };
+    foia::metrics::BYTES_DOWNLOADED.inc_by(&[source_id], content.len() as f64);
+    foia::metrics::DOCUMENTS_DOWNLOADED.inc(&[source_id, outcome]);
+    saved
 }
 
 pub use foia::utils::extract_title_from_url;
@@ -0,0 +1,360 @@
+//! Link extraction from HTML, JavaScript and JSON responses.
+//!
+//! Each extractor finds one kind of link and resolves it against the URL
+//! of the response it came from. [`extract`] runs the extractors that fit a
+//! response's content type. The declarative crawler uses these, and custom
+//! scrapers can too rather than matching links with their own regexes.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde_json::Value;
+use url::Url;
+
+/// Navigation in an onclick handler: `window.open('...')`,
+/// `location.href = '...'`, `location.assign('...')` and the like.
+static ONCLICK_TARGET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?:window\.open|location\.(?:assign|replace)|location(?:\.href)?\s*=)\s*\(?\s*['"]([^'"]+)['"]"#,
+    )
+    .expect("valid regex")
+});
+
+/// A quoted string ending in `.pdf`, optionally with a query string.
+static QUOTED_PDF: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)['"]([^'"\s<>]+\.pdf(?:\?[^'"\s<>]*)?)['"]"#).expect("valid regex")
+});
+
+/// How a link was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// `<a href>` or `<area href>`.
+    Anchor,
+    /// `<meta http-equiv="refresh">`.
+    MetaRefresh,
+    /// An element's `onclick` handler.
+    OnClick,
+    /// A PDF path in a script.
+    Script,
+    /// A URL-like field in JSON.
+    Json,
+}
+
+/// A link found in a response, resolved to an absolute URL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub url: String,
+    pub kind: LinkKind,
+    /// Anchor text, when there is any.
+    pub text: Option<String>,
+}
+
+impl Link {
+    fn new(url: String, kind: LinkKind) -> Self {
+        Self {
+            url,
+            kind,
+            text: None,
+        }
+    }
+}
+
+/// Resolve `href` against `base`, without its fragment. Returns `None` for
+/// links that can't be fetched: fragments alone, `javascript:`, `mailto:`,
+/// `tel:`, `data:` and any other non-HTTP scheme.
+pub fn resolve(base: &Url, href: &str) -> Option<String> {
+    let href = href.trim();
+    if href.is_empty() || href.starts_with('#') {
+        return None;
+    }
+    let mut url = base.join(href).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_fragment(None);
+    Some(url.to_string())
+}
+
+/// Every link in an HTML page: anchors, meta refresh, onclick handlers and
+/// PDFs named in inline scripts, each URL once in page order.
+pub fn extract_html(html: &str, base: &Url) -> Vec<Link> {
+    let document = Html::parse_document(html);
+    dedup(
+        anchors(&document, base)
+            .into_iter()
+            .chain(meta_refresh(&document, base))
+            .chain(onclick(&document, base))
+            .chain(inline_scripts(&document, base)),
+    )
+}
+
+/// Links in a response of any type, chosen by `content_type`: JSON fields
+/// for JSON, PDF strings for JavaScript, and the HTML extractors otherwise.
+pub fn extract(body: &str, content_type: &str, base: &Url) -> Vec<Link> {
+    let content_type = content_type.to_ascii_lowercase();
+    if content_type.contains("json") {
+        match serde_json::from_str::<Value>(body) {
+            Ok(value) => dedup(json_links(&value, base)),
+            Err(_) => Vec::new(),
+        }
+    } else if content_type.contains("javascript") || content_type.contains("ecmascript") {
+        dedup(script_links(body, base))
+    } else {
+        extract_html(body, base)
+    }
+}
+
+/// `<a href>` and `<area href>` links, with their text.
+pub fn anchors(document: &Html, base: &Url) -> Vec<Link> {
+    let Ok(selector) = Selector::parse("a[href], area[href]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter_map(|a| {
+            let url = resolve(base, a.value().attr("href")?)?;
+            let text = a.text().collect::<String>();
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            Some(Link {
+                url,
+                kind: LinkKind::Anchor,
+                text: (!text.is_empty()).then_some(text),
+            })
+        })
+        .collect()
+}
+
+/// Targets of `<meta http-equiv="refresh" content="5; url=...">`.
+pub fn meta_refresh(document: &Html, base: &Url) -> Vec<Link> {
+    let Ok(selector) = Selector::parse("meta[http-equiv][content]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter(|meta| {
+            meta.value()
+                .attr("http-equiv")
+                .is_some_and(|v| v.eq_ignore_ascii_case("refresh"))
+        })
+        .filter_map(|meta| {
+            let content = meta.value().attr("content")?;
+            let (_, target) = content.split_once(';')?;
+            let target = target.trim();
+            let target = target
+                .get(..4)
+                .filter(|prefix| prefix.eq_ignore_ascii_case("url="))
+                .map_or(target, |_| &target[4..]);
+            let target = target.trim().trim_matches(|c| c == '\'' || c == '"');
+            resolve(base, target).map(|url| Link::new(url, LinkKind::MetaRefresh))
+        })
+        .collect()
+}
+
+/// URLs opened or navigated to by `onclick` handlers.
+pub fn onclick(document: &Html, base: &Url) -> Vec<Link> {
+    let Ok(selector) = Selector::parse("[onclick]") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .filter_map(|el| el.value().attr("onclick"))
+        .flat_map(|handler| ONCLICK_TARGET.captures_iter(handler))
+        .filter_map(|caps| resolve(base, &caps[1]))
+        .map(|url| Link::new(url, LinkKind::OnClick))
+        .collect()
+}
+
+/// PDFs named in the page's inline `<script>` elements.
+pub fn inline_scripts(document: &Html, base: &Url) -> Vec<Link> {
+    let Ok(selector) = Selector::parse("script:not([src])") else {
+        return Vec::new();
+    };
+    document
+        .select(&selector)
+        .flat_map(|script| script_links(&script.text().collect::<String>(), base))
+        .collect()
+}
+
+/// PDF paths in quoted JavaScript strings.
+pub fn script_links(js: &str, base: &Url) -> Vec<Link> {
+    QUOTED_PDF
+        .captures_iter(js)
+        .filter_map(|caps| resolve(base, &caps[1].replace("\\/", "/")))
+        .map(|url| Link::new(url, LinkKind::Script))
+        .collect()
+}
+
+/// String values of URL-like JSON fields (`url`, `href`, `link`, `src` and
+/// names ending in those, such as `download_url` or `pdfLink`), at any
+/// depth.
+pub fn json_links(value: &Value, base: &Url) -> Vec<Link> {
+    let mut links = Vec::new();
+    collect_json(value, base, &mut links);
+    links
+}
+
+fn collect_json(value: &Value, base: &Url, links: &mut Vec<Link>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    Value::String(s) if is_link_field(key) => {
+                        if let Some(url) = resolve(base, s) {
+                            links.push(Link::new(url, LinkKind::Json));
+                        }
+                    }
+                    _ => collect_json(value, base, links),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_json(item, base, links);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_link_field(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key == "src"
+        || ["url", "uri", "href", "link"]
+            .iter()
+            .any(|s| key.ends_with(s))
+}
+
+/// Keep the first link for each URL.
+fn dedup(links: impl IntoIterator<Item = Link>) -> Vec<Link> {
+    let mut seen = HashSet::new();
+    links
+        .into_iter()
+        .filter(|link| seen.insert(link.url.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn base() -> Url {
+        Url::parse("https://agency.gov/foia/reading-room/").unwrap()
+    }
+
+    fn urls(links: &[Link]) -> Vec<&str> {
+        links.iter().map(|l| l.url.as_str()).collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let base = base();
+        assert_eq!(
+            resolve(&base, "doc.pdf#page=2").as_deref(),
+            Some("https://agency.gov/foia/reading-room/doc.pdf")
+        );
+        assert_eq!(
+            resolve(&base, "//cdn.agency.gov/a.pdf").as_deref(),
+            Some("https://cdn.agency.gov/a.pdf")
+        );
+        assert_eq!(resolve(&base, "#top"), None);
+        assert_eq!(resolve(&base, "javascript:void(0)"), None);
+        assert_eq!(resolve(&base, "mailto:foia@agency.gov"), None);
+        assert_eq!(resolve(&base, "tel:555-0100"), None);
+    }
+
+    #[test]
+    fn test_anchors() {
+        let html = r##"<a href="/files/a.pdf"> Annual
+            report </a><a href="#">skip</a><map><area href="b.pdf"></map>"##;
+        let links = anchors(&Html::parse_document(html), &base());
+        assert_eq!(
+            urls(&links),
+            vec![
+                "https://agency.gov/files/a.pdf",
+                "https://agency.gov/foia/reading-room/b.pdf"
+            ]
+        );
+        assert_eq!(links[0].text.as_deref(), Some("Annual report"));
+        assert_eq!(links[1].text, None);
+    }
+
+    #[test]
+    fn test_meta_refresh() {
+        let html =
+            r#"<head><meta http-equiv="Refresh" content="0; URL='/moved/index.html'"></head>"#;
+        let links = meta_refresh(&Html::parse_document(html), &base());
+        assert_eq!(urls(&links), vec!["https://agency.gov/moved/index.html"]);
+        assert_eq!(links[0].kind, LinkKind::MetaRefresh);
+    }
+
+    #[test]
+    fn test_onclick() {
+        let html = r#"
+            <button onclick="window.open('/docs/1.pdf', '_blank')">Open</button>
+            <li onclick="location.href = 'case/42'">Case 42</li>
+            <div onclick="toggle()"></div>"#;
+        let links = onclick(&Html::parse_document(html), &base());
+        assert_eq!(
+            urls(&links),
+            vec![
+                "https://agency.gov/docs/1.pdf",
+                "https://agency.gov/foia/reading-room/case/42"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_script_links() {
+        let js = r#"var files = ["\/uploads\/memo.PDF", 'scan.pdf?v=2']; load("data.json");"#;
+        let links = script_links(js, &base());
+        assert_eq!(
+            urls(&links),
+            vec![
+                "https://agency.gov/uploads/memo.PDF",
+                "https://agency.gov/foia/reading-room/scan.pdf?v=2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_links() {
+        let value = json!({
+            "results": [
+                {"title": "Memo", "download_url": "/api/files/1.pdf"},
+                {"title": "Letter", "pdfLink": "https://cdn.agency.gov/2.pdf", "id": 2}
+            ],
+            "next": "?page=2",
+            "meta": {"href": "https://agency.gov/about"}
+        });
+        let links = json_links(&value, &base());
+        let mut found = urls(&links);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                "https://agency.gov/about",
+                "https://agency.gov/api/files/1.pdf",
+                "https://cdn.agency.gov/2.pdf"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_by_content_type() {
+        let html = r#"<a href="a.pdf">A</a><script>var f = "a.pdf"; var g = "b.pdf";</script>"#;
+        let links = extract(html, "text/html; charset=utf-8", &base());
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].kind, LinkKind::Anchor);
+        assert_eq!(links[1].kind, LinkKind::Script);
+
+        let json = r#"{"url": "/x.pdf"}"#;
+        assert_eq!(
+            urls(&extract(json, "application/json", &base())),
+            vec!["https://agency.gov/x.pdf"]
+        );
+        assert!(extract("not json", "application/json", &base()).is_empty());
+    }
+}
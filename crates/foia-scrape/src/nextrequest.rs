@@ -0,0 +1,183 @@
+//! NextRequest public records portal support.
+//!
+//! NextRequest hosts records portals for cities, counties and state
+//! agencies, one instance per agency (`https://cityofx.nextrequest.com`).
+//! The portal's own pages are built from JSON endpoints that list public
+//! requests and the documents released for each, paged by `page_number`.
+//!
+//! Provides functionality to:
+//! - Build the request list and request document URLs for an instance
+//! - Read request IDs and released documents from their JSON responses
+
+use serde_json::Value;
+use url::Url;
+
+/// Endpoint listing public requests, newest first.
+const REQUESTS_ENDPOINT: &str = "/client/requests";
+
+/// Endpoint listing the documents released for one request.
+const REQUEST_DOCUMENTS_ENDPOINT: &str = "/client/request_documents";
+
+/// Fields that may hold a document's download URL, in order of preference.
+const DOCUMENT_URL_FIELDS: &[&str] = &["asset_url", "download_url", "document_url", "url"];
+
+/// A document released for a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NextRequestDocument {
+    /// Download URL.
+    pub url: String,
+    /// Title shown on the portal.
+    pub title: Option<String>,
+}
+
+/// The instance root (`scheme://host`) for any URL on it.
+pub fn instance_root(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    Some(format!("{}://{}", url.scheme(), url.host_str()?))
+}
+
+/// Page `page` of an instance's public requests.
+pub fn requests_url(root: &str, page: u32) -> String {
+    format!("{}{}?page_number={}", root, REQUESTS_ENDPOINT, page)
+}
+
+/// Page `page` of the documents released for request `request_id`.
+pub fn request_documents_url(root: &str, request_id: &str, page: u32) -> String {
+    format!(
+        "{}{}?request_id={}&page_number={}",
+        root,
+        REQUEST_DOCUMENTS_ENDPOINT,
+        urlencoding::encode(request_id),
+        page
+    )
+}
+
+/// The public page for request `request_id`.
+pub fn request_page_url(root: &str, request_id: &str) -> String {
+    format!("{}/requests/{}", root, request_id)
+}
+
+fn id_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Total number of items a paged response reports, if it does.
+pub fn total_count(data: &Value) -> Option<u64> {
+    data.get("total_count")
+        .or_else(|| data.get("total"))
+        .and_then(Value::as_u64)
+}
+
+/// Request IDs on one page of the request list.
+pub fn parse_requests(data: &Value) -> Vec<String> {
+    data.get("requests")
+        .and_then(Value::as_array)
+        .map(|requests| {
+            requests
+                .iter()
+                .filter_map(|r| r.get("id").and_then(id_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Released documents on one page of a request's documents. Documents
+/// without a download URL are linked through the portal's download route.
+pub fn parse_documents(data: &Value, root: &str) -> Vec<NextRequestDocument> {
+    let Ok(base) = Url::parse(root) else {
+        return Vec::new();
+    };
+    let Some(documents) = data.get("documents").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    documents
+        .iter()
+        .filter_map(|doc| {
+            let url = DOCUMENT_URL_FIELDS
+                .iter()
+                .find_map(|field| doc.get(*field).and_then(Value::as_str))
+                .filter(|u| !u.is_empty())
+                .and_then(|u| base.join(u).ok())
+                .map(|u| u.to_string())
+                .or_else(|| {
+                    let id = doc.get("id").and_then(id_string)?;
+                    Some(format!("{}/documents/{}/download", root, id))
+                })?;
+            let title = ["title", "file_name", "name"]
+                .iter()
+                .find_map(|field| doc.get(*field).and_then(Value::as_str))
+                .map(|t| t.to_string());
+            Some(NextRequestDocument { url, title })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ROOT: &str = "https://cityofx.nextrequest.com";
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            instance_root("https://cityofx.nextrequest.com/requests?page=2").as_deref(),
+            Some(ROOT)
+        );
+        assert_eq!(
+            requests_url(ROOT, 3),
+            "https://cityofx.nextrequest.com/client/requests?page_number=3"
+        );
+        assert_eq!(
+            request_documents_url(ROOT, "21-104", 1),
+            "https://cityofx.nextrequest.com/client/request_documents?request_id=21-104&page_number=1"
+        );
+        assert_eq!(
+            request_page_url(ROOT, "21-104"),
+            "https://cityofx.nextrequest.com/requests/21-104"
+        );
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        let requests = json!({
+            "total_count": 2,
+            "requests": [{"id": "21-104", "request_state": "Closed"}, {"id": 105}]
+        });
+        assert_eq!(parse_requests(&requests), vec!["21-104", "105"]);
+        assert_eq!(total_count(&requests), Some(2));
+
+        let documents = json!({
+            "total_count": 3,
+            "documents": [
+                {"id": 1, "title": "Response.pdf", "asset_url": "https://files.nextrequest.com/a/1.pdf"},
+                {"id": 2, "file_name": "Emails.pdf", "url": "/documents/2/view"},
+                {"id": 3}
+            ]
+        });
+        assert_eq!(
+            parse_documents(&documents, ROOT),
+            vec![
+                NextRequestDocument {
+                    url: "https://files.nextrequest.com/a/1.pdf".to_string(),
+                    title: Some("Response.pdf".to_string()),
+                },
+                NextRequestDocument {
+                    url: "https://cityofx.nextrequest.com/documents/2/view".to_string(),
+                    title: Some("Emails.pdf".to_string()),
+                },
+                NextRequestDocument {
+                    url: "https://cityofx.nextrequest.com/documents/3/download".to_string(),
+                    title: None,
+                },
+            ]
+        );
+        assert!(parse_documents(&json!({}), ROOT).is_empty());
+    }
+}
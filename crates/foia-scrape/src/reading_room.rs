@@ -0,0 +1,355 @@
+//! Agency FOIA reading room support.
+//!
+//! Reading rooms like the FBI Vault and the DOJ FOIA library file released
+//! records under subject folders: an index links to folders, folders link
+//! to documents (and sometimes to sub-folders), and long folders are split
+//! across numbered listing pages. Each supported reading room is a preset
+//! describing where its listings live and what its links look like.
+//!
+//! Provides functionality to:
+//! - Look up reading room presets and apply config overrides to them
+//! - Split a listing page into subject folders, documents and a next page
+//! - Turn a subject folder into a tag for the documents filed under it
+
+use std::collections::HashSet;
+
+use regex::Regex;
+use scraper::{Html, Selector};
+use url::Url;
+
+use crate::config::ReadingRoomConfig;
+use foia::models::tag_slug;
+
+/// Namespace for tags taken from subject folders.
+const FOLDER_TAG_NAMESPACE: &str = "topic";
+
+/// A reading room's layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingRoom {
+    /// Site root, e.g. `https://vault.fbi.gov`.
+    pub base_url: String,
+    /// Index pages linking to the subject folders.
+    pub index_paths: Vec<String>,
+    /// CSS selector for the part of a page holding the listing.
+    pub content_selector: String,
+    /// Regex matching links to subject folders.
+    pub folder_pattern: String,
+    /// Regex matching links to documents.
+    pub document_pattern: String,
+    /// Rewrite applied to matched document links (pattern, replacement),
+    /// for rooms that link to a viewer rather than the file.
+    pub download_rewrite: Option<(String, String)>,
+    /// CSS selectors for a listing's "next page" link.
+    pub next_selectors: Vec<String>,
+    /// Tag given to every document.
+    pub agency_tag: Option<String>,
+}
+
+impl ReadingRoom {
+    /// The FBI Vault (`vault.fbi.gov`). Subjects are top-level folders whose
+    /// documents link to a viewer page; the file is served from
+    /// `at_download/file` next to it.
+    pub fn fbi_vault() -> Self {
+        Self {
+            base_url: "https://vault.fbi.gov".to_string(),
+            index_paths: vec!["/alphabetical-index".to_string()],
+            content_selector: "#content-core".to_string(),
+            folder_pattern: r"^https://vault\.fbi\.gov/[^/?#]+/?$".to_string(),
+            document_pattern: r"^https://vault\.fbi\.gov/.+/view$".to_string(),
+            download_rewrite: Some((r"/view$".to_string(), "/at_download/file".to_string())),
+            next_selectors: vec![".listingBar .next a".to_string()],
+            agency_tag: Some("agency:fbi".to_string()),
+        }
+    }
+
+    /// The DOJ FOIA library (`justice.gov/oip/foia-library`). The index links
+    /// to each component's library, which files records under subject pages.
+    pub fn doj_foia_library() -> Self {
+        Self {
+            base_url: "https://www.justice.gov".to_string(),
+            index_paths: vec!["/oip/foia-library".to_string()],
+            content_selector: "main".to_string(),
+            folder_pattern: r"^https://www\.justice\.gov/[^/?#]+/(foia-library|foia-reading-room|frequently-requested-records)(/[^?#]*)?$".to_string(),
+            document_pattern: r"(?i)(/dl(\?inline=?)?$|\.pdf$)".to_string(),
+            download_rewrite: None,
+            next_selectors: vec![
+                "li.pager__item--next a".to_string(),
+                "li.pager-next a".to_string(),
+            ],
+            agency_tag: Some("agency:doj".to_string()),
+        }
+    }
+
+    /// The preset for a discovery type, if it names a reading room.
+    pub fn preset(discovery_type: &str) -> Option<Self> {
+        match discovery_type {
+            "fbi_vault" => Some(Self::fbi_vault()),
+            "doj_foia_library" => Some(Self::doj_foia_library()),
+            _ => None,
+        }
+    }
+
+    /// Apply config overrides. Unset fields keep the preset's value.
+    pub fn with_config(mut self, config: &ReadingRoomConfig) -> Self {
+        if let Some(selector) = &config.content_selector {
+            self.content_selector = selector.clone();
+        }
+        if let Some(pattern) = &config.folder_pattern {
+            self.folder_pattern = pattern.clone();
+        }
+        if let Some(pattern) = &config.document_pattern {
+            self.document_pattern = pattern.clone();
+        }
+        if !config.next_selectors.is_empty() {
+            self.next_selectors = config.next_selectors.clone();
+        }
+        if let Some(tag) = &config.agency_tag {
+            self.agency_tag = Some(tag.clone()).filter(|t| !t.is_empty());
+        }
+        self
+    }
+
+    /// Compile the room's link patterns.
+    pub fn patterns(&self) -> Result<ReadingRoomPatterns, regex::Error> {
+        let rewrite = match &self.download_rewrite {
+            Some((pattern, replacement)) => Some((Regex::new(pattern)?, replacement.clone())),
+            None => None,
+        };
+        Ok(ReadingRoomPatterns {
+            folder: Regex::new(&self.folder_pattern)?,
+            document: Regex::new(&self.document_pattern)?,
+            rewrite,
+        })
+    }
+}
+
+/// A reading room's compiled link patterns.
+#[derive(Debug, Clone)]
+pub struct ReadingRoomPatterns {
+    folder: Regex,
+    document: Regex,
+    rewrite: Option<(Regex, String)>,
+}
+
+/// A subject folder linked from a listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Folder {
+    pub url: String,
+    /// Tag for documents filed under the folder.
+    pub tag: String,
+}
+
+/// What one listing page links to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Listing {
+    pub folders: Vec<Folder>,
+    pub documents: Vec<String>,
+    pub next_page: Option<String>,
+}
+
+/// Tag for a subject folder, from its link text or, failing that, the
+/// last segment of its path.
+pub fn folder_tag(text: &str, url: &Url) -> Option<String> {
+    let slug = tag_slug(text);
+    let slug = if slug.is_empty() {
+        let segment = url
+            .path_segments()
+            .and_then(|mut segments| segments.rfind(|s| !s.is_empty()))
+            .map(|s| {
+                urlencoding::decode(s)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        tag_slug(&segment.replace(['-', '_'], " "))
+    } else {
+        slug
+    };
+    (!slug.is_empty()).then(|| format!("{}:{}", FOLDER_TAG_NAMESPACE, slug))
+}
+
+/// Split a listing page into folders, documents and the next page.
+///
+/// Only links inside the room's content area count, so site navigation
+/// doesn't pull in unrelated sections.
+pub fn parse_listing(
+    html: &str,
+    page_url: &Url,
+    room: &ReadingRoom,
+    patterns: &ReadingRoomPatterns,
+) -> Listing {
+    let document = Html::parse_document(html);
+    let mut listing = Listing::default();
+
+    let next_page = room
+        .next_selectors
+        .iter()
+        .filter_map(|s| Selector::parse(s).ok())
+        .find_map(|selector| {
+            let href = document.select(&selector).next()?.value().attr("href")?;
+            page_url.join(href).ok()
+        });
+    listing.next_page = next_page.map(|url| url.to_string());
+
+    let (Ok(content), Ok(anchors)) = (
+        Selector::parse(&room.content_selector),
+        Selector::parse("a[href]"),
+    ) else {
+        return listing;
+    };
+
+    let mut seen = HashSet::new();
+    seen.insert(page_url.to_string());
+    if let Some(next) = &listing.next_page {
+        seen.insert(next.clone());
+    }
+
+    for area in document.select(&content) {
+        for a in area.select(&anchors) {
+            let Some(href) = a.value().attr("href") else {
+                continue;
+            };
+            if href.starts_with('#') || href.starts_with("javascript:") {
+                continue;
+            }
+            let Ok(mut url) = page_url.join(href) else {
+                continue;
+            };
+            url.set_fragment(None);
+            let link = url.to_string();
+
+            if patterns.document.is_match(&link) {
+                let link = match &patterns.rewrite {
+                    Some((re, replacement)) => re.replace(&link, replacement.as_str()).into_owned(),
+                    None => link,
+                };
+                if seen.insert(link.clone()) {
+                    listing.documents.push(link);
+                }
+            } else if patterns.folder.is_match(&link) && seen.insert(link.clone()) {
+                let text = a.text().collect::<String>();
+                if let Some(tag) = folder_tag(&text, &url) {
+                    listing.folders.push(Folder { url: link, tag });
+                }
+            }
+        }
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_and_overrides() {
+        assert_eq!(
+            ReadingRoom::preset("fbi_vault"),
+            Some(ReadingRoom::fbi_vault())
+        );
+        assert_eq!(ReadingRoom::preset("html_crawl"), None);
+
+        let room = ReadingRoom::doj_foia_library().with_config(&ReadingRoomConfig {
+            content_selector: Some("#main-content".to_string()),
+            agency_tag: Some(String::new()),
+            ..Default::default()
+        });
+        assert_eq!(room.content_selector, "#main-content");
+        assert_eq!(room.agency_tag, None);
+        assert!(room.patterns().is_ok());
+    }
+
+    #[test]
+    fn test_folder_tag() {
+        let url = Url::parse("https://vault.fbi.gov/al-capone").unwrap();
+        assert_eq!(
+            folder_tag(" Al Capone ", &url).as_deref(),
+            Some("topic:al-capone")
+        );
+        assert_eq!(folder_tag("", &url).as_deref(), Some("topic:al-capone"));
+    }
+
+    #[test]
+    fn test_parse_fbi_listing() {
+        let room = ReadingRoom::fbi_vault();
+        let patterns = room.patterns().unwrap();
+        let page = Url::parse("https://vault.fbi.gov/alphabetical-index").unwrap();
+        let index = r#"<html><body>
+            <nav><a href="/recently-added">Recently Added</a></nav>
+            <div id="content-core">
+              <a href="/al-capone">Al Capone</a>
+              <a href="https://vault.fbi.gov/amelia-earhart/">Amelia Earhart</a>
+              <a href="/al-capone">Al Capone</a>
+              <div class="listingBar"><span class="next"><a href="?b_start:int=20">Next 20 items</a></span></div>
+            </div>
+        </body></html>"#;
+        let listing = parse_listing(index, &page, &room, &patterns);
+        assert_eq!(
+            listing.folders,
+            vec![
+                Folder {
+                    url: "https://vault.fbi.gov/al-capone".to_string(),
+                    tag: "topic:al-capone".to_string(),
+                },
+                Folder {
+                    url: "https://vault.fbi.gov/amelia-earhart/".to_string(),
+                    tag: "topic:amelia-earhart".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            listing.next_page.as_deref(),
+            Some("https://vault.fbi.gov/alphabetical-index?b_start:int=20")
+        );
+
+        let page = Url::parse("https://vault.fbi.gov/al-capone").unwrap();
+        let folder = r#"<div id="content-core">
+            <a href="/al-capone/al-capone-part-01-of-13/view">Al Capone Part 01 of 13</a>
+            <a href="/al-capone/al-capone-part-02-of-13/view">Al Capone Part 02 of 13</a>
+        </div>"#;
+        let listing = parse_listing(folder, &page, &room, &patterns);
+        assert!(listing.folders.is_empty());
+        assert_eq!(
+            listing.documents,
+            vec![
+                "https://vault.fbi.gov/al-capone/al-capone-part-01-of-13/at_download/file",
+                "https://vault.fbi.gov/al-capone/al-capone-part-02-of-13/at_download/file",
+            ]
+        );
+        assert_eq!(listing.next_page, None);
+    }
+
+    #[test]
+    fn test_parse_doj_listing() {
+        let room = ReadingRoom::doj_foia_library();
+        let patterns = room.patterns().unwrap();
+        let page = Url::parse("https://www.justice.gov/nsd/foia-library").unwrap();
+        let html = r#"<main>
+            <a href="/nsd/foia-library/frequently-requested-records">Frequently Requested Records</a>
+            <a href="/media/1234/dl?inline">FISA Annual Report 2022</a>
+            <a href="/d9/2023-01/report.pdf">Report</a>
+            <ul><li class="pager__item--next"><a href="?page=1">Next</a></li></ul>
+        </main>"#;
+        let listing = parse_listing(html, &page, &room, &patterns);
+        assert_eq!(
+            listing.folders,
+            vec![Folder {
+                url: "https://www.justice.gov/nsd/foia-library/frequently-requested-records"
+                    .to_string(),
+                tag: "topic:frequently-requested-records".to_string(),
+            }]
+        );
+        assert_eq!(
+            listing.documents,
+            vec![
+                "https://www.justice.gov/media/1234/dl?inline",
+                "https://www.justice.gov/d9/2023-01/report.pdf",
+            ]
+        );
+        assert_eq!(
+            listing.next_page.as_deref(),
+            Some("https://www.justice.gov/nsd/foia-library?page=1")
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! Verification against checksums published by the source.
+//!
+//! Manifests are fetched once per run and cached; sidecars are fetched per
+//! document. Both accept GNU coreutils lines (`<hex>  name`), BSD tagged
+//! lines (`SHA256 (name) = <hex>`) and, for sidecars, a bare digest. When
+//! the source signs its checksums, the detached signature is checked with
+//! `gpg` against the configured keyring.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+use crate::HttpClient;
+use foia::config::ChecksumConfig;
+use foia::models::{ChecksumAlgorithm, ChecksumVerification, DocumentVersion};
+
+/// Digests parsed from a checksum file, keyed by file name.
+///
+/// A bare digest (as in many sidecars) is stored under the empty name.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChecksumManifest {
+    entries: HashMap<String, (ChecksumAlgorithm, String)>,
+}
+
+impl ChecksumManifest {
+    /// Parse a manifest, skipping blank lines, comments and unknown lines.
+    pub fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(parse_line)
+            .collect();
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Published digest for `name`, falling back to a bare digest.
+    pub fn lookup(&self, name: &str) -> Option<&(ChecksumAlgorithm, String)> {
+        self.entries.get(name).or_else(|| self.entries.get(""))
+    }
+}
+
+/// Parse one manifest line into `(file name, (algorithm, digest))`.
+fn parse_line(line: &str) -> Option<(String, (ChecksumAlgorithm, String))> {
+    // BSD tagged form: `SHA256 (report.pdf) = abc...`
+    if let Some((tag, rest)) = line.split_once(" (") {
+        if let Some((name, digest)) = rest.rsplit_once(") = ") {
+            let algorithm = ChecksumAlgorithm::from_str(tag.trim())?;
+            let digest = digest.trim();
+            if is_hex_digest(digest, algorithm) {
+                return Some((file_name(name), (algorithm, digest.to_ascii_lowercase())));
+            }
+            return None;
+        }
+    }
+
+    // GNU form: `abc...  report.pdf` (`*` marks binary mode), or a bare digest
+    let (digest, name) = match line.split_once(char::is_whitespace) {
+        Some((digest, name)) => (digest, name.trim_start().trim_start_matches('*')),
+        None => (line, ""),
+    };
+    let algorithm = ChecksumAlgorithm::from_hex_len(digest.len())?;
+    if !is_hex_digest(digest, algorithm) {
+        return None;
+    }
+    Some((file_name(name), (algorithm, digest.to_ascii_lowercase())))
+}
+
+fn is_hex_digest(digest: &str, algorithm: ChecksumAlgorithm) -> bool {
+    ChecksumAlgorithm::from_hex_len(digest.len()) == Some(algorithm)
+        && digest.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Last path component, so `./releases/a.pdf` matches `a.pdf`.
+fn file_name(path: &str) -> String {
+    path.trim()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Names a downloaded file may be listed under: the URL's last path segment
+/// (decoded) and the server-provided filename.
+fn candidate_names(url: &str, filename: Option<&str>) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(parsed) = url::Url::parse(url) {
+        if let Some(segment) = parsed.path_segments().and_then(|mut s| s.next_back()) {
+            if !segment.is_empty() {
+                let decoded = urlencoding::decode(segment)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_else(|_| segment.to_string());
+                names.push(decoded);
+            }
+        }
+    }
+    if let Some(name) = filename {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// A fetched checksum file and whether its signature verified.
+struct FetchedManifest {
+    url: String,
+    manifest: ChecksumManifest,
+    signature_valid: Option<bool>,
+}
+
+/// Checks downloads from one source against its published checksums.
+pub struct ChecksumVerifier {
+    config: ChecksumConfig,
+    /// Scratch directory for signature files handed to `gpg`.
+    scratch_dir: PathBuf,
+    /// Manifests fetched so far this run; `None` records a failed fetch.
+    manifests: Mutex<HashMap<String, Option<Arc<FetchedManifest>>>>,
+}
+
+impl ChecksumVerifier {
+    pub fn new(config: ChecksumConfig, scratch_dir: PathBuf) -> Self {
+        Self {
+            config,
+            scratch_dir,
+            manifests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Verify a download against the first published checksum that lists it.
+    ///
+    /// `content` is the body when it was buffered; otherwise `file` is the
+    /// staged file on disk. Returns `None` if no published checksum covers
+    /// the file.
+    pub async fn verify(
+        &self,
+        client: &HttpClient,
+        url: &str,
+        filename: Option<&str>,
+        content: &[u8],
+        file: Option<&Path>,
+    ) -> Option<ChecksumVerification> {
+        let names = candidate_names(url, filename);
+
+        let mut published = None;
+        for manifest_url in &self.config.manifests {
+            let Some(fetched) = self.manifest(client, manifest_url).await else {
+                continue;
+            };
+            if let Some(entry) = names
+                .iter()
+                .find_map(|name| fetched.manifest.entries.get(name))
+            {
+                published = Some((entry.clone(), fetched.clone()));
+                break;
+            }
+        }
+        if published.is_none() {
+            for suffix in &self.config.sidecars {
+                let sidecar_url = format!("{}{}", url, suffix);
+                let Some(fetched) = self.fetch(client, &sidecar_url).await else {
+                    continue;
+                };
+                if let Some(entry) = names.iter().find_map(|name| fetched.manifest.lookup(name)) {
+                    published = Some((entry.clone(), Arc::new(fetched)));
+                    break;
+                }
+            }
+        }
+        let ((algorithm, expected), fetched) = published?;
+
+        let actual = match file {
+            Some(path) => {
+                let path = path.to_path_buf();
+                let hashed = tokio::task::spawn_blocking(move || {
+                    algorithm.hash_reader(std::fs::File::open(path)?)
+                })
+                .await;
+                match hashed {
+                    Ok(Ok(digest)) => digest,
+                    Ok(Err(e)) => {
+                        warn!("Failed to hash {} for checksum check: {}", url, e);
+                        return None;
+                    }
+                    Err(e) => {
+                        warn!("Checksum task for {} failed: {}", url, e);
+                        return None;
+                    }
+                }
+            }
+            None => algorithm.hash_bytes(content),
+        };
+
+        let verification = ChecksumVerification::new(
+            algorithm,
+            &expected,
+            &actual,
+            &fetched.url,
+            fetched.signature_valid,
+        );
+        if verification.is_verified() {
+            debug!("{} matches {} in {}", url, algorithm.as_str(), fetched.url);
+        } else {
+            warn!(
+                "{} failed checksum verification against {} ({})",
+                url,
+                fetched.url,
+                verification.status.as_str()
+            );
+        }
+        Some(verification)
+    }
+
+    /// Cached manifest, fetching it on first use.
+    async fn manifest(&self, client: &HttpClient, url: &str) -> Option<Arc<FetchedManifest>> {
+        let mut manifests = self.manifests.lock().await;
+        if let Some(cached) = manifests.get(url) {
+            return cached.clone();
+        }
+        let fetched = self.fetch(client, url).await.map(Arc::new);
+        manifests.insert(url.to_string(), fetched.clone());
+        fetched
+    }
+
+    /// Fetch and parse a checksum file, checking its signature if configured.
+    async fn fetch(&self, client: &HttpClient, url: &str) -> Option<FetchedManifest> {
+        let text = fetch_text(client, url).await?;
+        let manifest = ChecksumManifest::parse(&text);
+        if manifest.is_empty() {
+            debug!("No checksums found in {}", url);
+            return None;
+        }
+
+        let signature_valid = match self.config.signature_url(url) {
+            Some(signature_url) => Some(self.check_signature(client, &text, &signature_url).await),
+            None => None,
+        };
+
+        Some(FetchedManifest {
+            url: url.to_string(),
+            manifest,
+            signature_valid,
+        })
+    }
+
+    /// Check a detached signature over `text` with `gpg --verify`.
+    ///
+    /// A missing signature counts as invalid: the source said it signs.
+    async fn check_signature(&self, client: &HttpClient, text: &str, signature_url: &str) -> bool {
+        let signature = match client.get(signature_url, None, None).await {
+            Ok(response) if response.is_success() => match response.bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    warn!("Failed to read signature {}: {}", signature_url, e);
+                    return false;
+                }
+            },
+            Ok(response) => {
+                warn!(
+                    "Signature {} returned HTTP {}",
+                    signature_url, response.status
+                );
+                return false;
+            }
+            Err(e) => {
+                warn!("Failed to fetch signature {}: {}", signature_url, e);
+                return false;
+            }
+        };
+
+        let key = &DocumentVersion::compute_hash(signature_url.as_bytes())[..32];
+        let signature_path = self.scratch_dir.join(format!("{}.sig", key));
+        let data_path = self.scratch_dir.join(format!("{}.sums", key));
+        let written = async {
+            tokio::fs::create_dir_all(&self.scratch_dir).await?;
+            tokio::fs::write(&signature_path, &signature).await?;
+            tokio::fs::write(&data_path, text).await
+        }
+        .await;
+        if let Err(e) = written {
+            warn!("Failed to stage signature {}: {}", signature_url, e);
+            return false;
+        }
+
+        let mut cmd = Command::new("gpg");
+        cmd.args(["--batch", "--status-fd", "1"]);
+        if let Some(keyring) = &self.config.keyring {
+            cmd.arg("--no-default-keyring")
+                .arg("--keyring")
+                .arg(keyring);
+        }
+        cmd.arg("--verify").arg(&signature_path).arg(&data_path);
+        let output = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await;
+
+        let _ = tokio::fs::remove_file(&signature_path).await;
+        let _ = tokio::fs::remove_file(&data_path).await;
+
+        match output {
+            Ok(output) if output.status.success() => {
+                let status = String::from_utf8_lossy(&output.stdout);
+                status.contains("[GNUPG:] GOODSIG") && status.contains("[GNUPG:] VALIDSIG")
+            }
+            Ok(output) => {
+                warn!(
+                    "Signature {} did not verify: {}",
+                    signature_url,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                false
+            }
+            Err(e) => {
+                warn!("Failed to run gpg for {}: {}", signature_url, e);
+                false
+            }
+        }
+    }
+}
+
+async fn fetch_text(client: &HttpClient, url: &str) -> Option<String> {
+    match client.get(url, None, None).await {
+        Ok(response) if response.is_success() => match response.text().await {
+            Ok(text) => Some(text),
+            Err(e) => {
+                warn!("Failed to read checksums from {}: {}", url, e);
+                None
+            }
+        },
+        Ok(response) => {
+            debug!("Checksums at {} returned HTTP {}", url, response.status);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to fetch checksums from {}: {}", url, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+    const MD5: &str = "b1946ac92492d2347c6235b4d2611184";
+
+    #[test]
+    fn test_parse_gnu_and_bsd_lines() {
+        let text = format!(
+            "# release 2024-03\n{sha}  report.pdf\n{md5} *./scans/letter.pdf\n\nSHA256 (memo final.pdf) = {upper}\nnot a checksum line\n",
+            sha = SHA256,
+            md5 = MD5,
+            upper = SHA256.to_ascii_uppercase(),
+        );
+        let manifest = ChecksumManifest::parse(&text);
+        assert_eq!(
+            manifest.lookup("report.pdf"),
+            Some(&(ChecksumAlgorithm::Sha256, SHA256.to_string()))
+        );
+        assert_eq!(
+            manifest.lookup("letter.pdf"),
+            Some(&(ChecksumAlgorithm::Md5, MD5.to_string()))
+        );
+        assert_eq!(
+            manifest.lookup("memo final.pdf"),
+            Some(&(ChecksumAlgorithm::Sha256, SHA256.to_string()))
+        );
+        assert_eq!(manifest.lookup("other.pdf"), None);
+    }
+
+    #[test]
+    fn test_bare_sidecar_digest_matches_any_name() {
+        let manifest = ChecksumManifest::parse(&format!("{}\n", MD5));
+        assert_eq!(
+            manifest.lookup("anything.pdf"),
+            Some(&(ChecksumAlgorithm::Md5, MD5.to_string()))
+        );
+        assert!(ChecksumManifest::parse("deadbeef  short.pdf").is_empty());
+    }
+
+    #[test]
+    fn test_candidate_names() {
+        assert_eq!(
+            candidate_names(
+                "https://agency.gov/files/Annual%20Report.pdf?dl=1",
+                Some("annual.pdf")
+            ),
+            vec!["Annual Report.pdf".to_string(), "annual.pdf".to_string()]
+        );
+        assert_eq!(
+            candidate_names("https://agency.gov/files/a.pdf", Some("a.pdf")),
+            vec!["a.pdf".to_string()]
+        );
+    }
+}
@@ -2,34 +2,44 @@
 //!
 //! Handles downloading pending documents from the crawl queue.
 //! Separated from UI concerns - emits events for progress tracking.
+//! Downloads run as a staged pipeline; see [`pipeline`].
 
+mod checksums;
+mod pipeline;
+mod resumable;
 mod types;
 mod youtube_download;
 
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
 
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Mutex, Semaphore};
 use tracing::warn;
 
-use crate::services::youtube;
-use crate::{extract_title_from_url, HttpClient};
-use foia::models::{DocumentVersion, UrlStatus};
-use foia::repository::{extract_filename_parts, DieselCrawlRepository, DieselDocumentRepository};
-use foia::storage::compute_storage_path_with_dedup;
-
-use types::{
-    handle_download_failure, handle_unchanged, save_or_update_document, send_failure_event,
-};
+use crate::services::wayback::SavePageNow;
+use crate::HttpClient;
+use checksums::ChecksumVerifier;
+use foia::repository::{DieselCrawlRepository, DieselDocumentRepository};
+use foia::services::known_files::KnownFiles;
+use foia::shutdown;
+use foia::utils::sniff_mime_type;
+use foia::work_queue::broker::Broker;
+use pipeline::Pipeline;
+
+pub use resumable::DEFAULT_LARGE_FILE_THRESHOLD;
+use resumable::PARTIAL_DIR;
 pub use types::{DownloadConfig, DownloadEvent, DownloadResult};
-use youtube_download::download_youtube_video;
+
+/// Bytes read from the start of a large file to detect its type.
+const SNIFF_BYTES: usize = 8192;
 
 /// Service for downloading documents from the crawl queue.
 pub struct DownloadService {
     doc_repo: Arc<DieselDocumentRepository>,
     crawl_repo: Arc<DieselCrawlRepository>,
     config: DownloadConfig,
+    ocr_queue: Option<Arc<dyn Broker>>,
 }
 
 impl DownloadService {
@@ -43,13 +53,20 @@ impl DownloadService {
             doc_repo,
             crawl_repo,
             config,
+            ocr_queue: None,
         }
     }
 
+    /// Publish an OCR task to `broker` for each new document version.
+    pub fn with_ocr_queue(mut self, broker: Arc<dyn Broker>) -> Self {
+        self.ocr_queue = Some(broker);
+        self
+    }
+
     /// Download pending documents.
     ///
-    /// Returns a channel receiver for progress events and spawns worker tasks.
-    /// Call `await` on the returned future to get the final result.
+    /// Sends progress events to `event_tx` and spawns `workers` fetch tasks,
+    /// plus the hash and store tasks configured in `pipeline`.
     pub async fn download(
         &self,
         source_id: Option<&str>,
@@ -61,334 +78,236 @@ impl DownloadService {
         let deduplicated = Arc::new(AtomicUsize::new(0));
         let skipped = Arc::new(AtomicUsize::new(0));
         let failed = Arc::new(AtomicUsize::new(0));
+        let known_skipped = Arc::new(AtomicUsize::new(0));
+        let known_files =
+            KnownFiles::load(&self.config.pipeline.known_files).map_err(|e| anyhow::anyhow!(e))?;
+
+        // The global bandwidth cap is one bucket shared by every client
+        let global_bandwidth = self
+            .config
+            .throttle
+            .bandwidth_limiter()
+            .map_err(|e| anyhow::anyhow!("Invalid throttle bandwidth: {}", e))?;
+        let global_schedule = self
+            .config
+            .throttle
+            .schedule()
+            .map_err(|e| anyhow::anyhow!("Invalid throttle schedule: {}", e))?;
+        let mut source_schedules = HashMap::new();
+        for (source, throttle) in &self.config.source_throttles {
+            if let Some(schedule) = throttle
+                .schedule()
+                .map_err(|e| anyhow::anyhow!("Invalid schedule for {}: {}", source, e))?
+            {
+                source_schedules.insert(source.clone(), schedule);
+            }
+        }
 
-        let mut handles = Vec::with_capacity(workers);
-
-        for worker_id in 0..workers {
-            let crawl_repo = self.crawl_repo.clone();
-            let doc_repo = self.doc_repo.clone();
-            let documents_dir = self.config.documents_dir.clone();
-            let timeout = self.config.request_timeout;
-            let delay = self.config.request_delay;
-            let privacy = self.config.privacy.clone();
-            let via = self.config.via.clone();
-            let via_mode = self.config.via_mode;
-            let source_id = source_id.map(|s| s.to_string());
-            let downloaded = downloaded.clone();
-            let deduplicated = deduplicated.clone();
-            let skipped = skipped.clone();
-            let failed = failed.clone();
-            let event_tx = event_tx.clone();
-
-            let handle = tokio::spawn(async move {
-                let client = match HttpClient::builder("download", timeout, delay)
-                    .privacy(&privacy)
-                    .build()
-                {
-                    Ok(c) => c,
-                    Err(e) => {
-                        tracing::error!("Failed to create HTTP client: {}", e);
-                        return;
-                    }
-                };
-
-                // Apply via mappings for caching proxy support
-                let client = if !via.is_empty() {
-                    client.with_via_config(via, via_mode)
-                } else {
-                    client
-                };
-
-                loop {
-                    // Check limit
-                    if let Some(max) = limit {
-                        if downloaded.load(Ordering::Relaxed) >= max {
-                            break;
-                        }
-                    }
-
-                    // Claim a URL to process
-                    let crawl_url = match crawl_repo.claim_pending_url(source_id.as_deref()).await {
-                        Ok(Some(url)) => url,
-                        Ok(None) => {
-                            tokio::time::sleep(Duration::from_millis(100)).await;
-                            match crawl_repo.claim_pending_url(source_id.as_deref()).await {
-                                Ok(Some(url)) => url,
-                                _ => break,
-                            }
-                        }
-                        Err(_) => {
-                            tokio::time::sleep(Duration::from_millis(500)).await;
-                            continue;
-                        }
-                    };
-
-                    let url = crawl_url.url.clone();
-                    let filename = extract_title_from_url(&url);
-
-                    let _ = event_tx
-                        .send(DownloadEvent::Started {
-                            worker_id,
-                            url: url.clone(),
-                            filename: filename.clone(),
-                        })
-                        .await;
-
-                    // Handle YouTube URLs specially
-                    if youtube::is_youtube_url(&url) {
-                        let proxy_url = privacy.effective_proxy_url();
-                        let yt_result = download_youtube_video(
-                            &url,
-                            &crawl_url,
-                            &documents_dir,
-                            &doc_repo,
-                            &crawl_repo,
-                            worker_id,
-                            &event_tx,
-                            &downloaded,
-                            &failed,
-                            proxy_url.as_deref(),
-                        )
-                        .await;
-
-                        if yt_result {
-                            continue;
-                        }
-                        // If YouTube download failed, continue to try regular HTTP
-                    }
-
-                    // Fetch the URL
-                    let response = match client
-                        .get(
-                            &url,
-                            crawl_url.etag.as_deref(),
-                            crawl_url.last_modified.as_deref(),
-                        )
-                        .await
-                    {
-                        Ok(r) => r,
-                        Err(e) => {
-                            handle_download_failure(
-                                &crawl_url,
-                                &crawl_repo,
-                                &failed,
-                                &event_tx,
-                                worker_id,
-                                &e.to_string(),
-                                true,
-                            )
-                            .await;
-                            continue;
-                        }
-                    };
-
-                    if response.is_not_modified() {
-                        handle_unchanged(&crawl_url, &crawl_repo, &skipped, &event_tx, worker_id)
-                            .await;
-                        continue;
-                    }
-
-                    if !response.is_success() {
-                        handle_download_failure(
-                            &crawl_url,
-                            &crawl_repo,
-                            &failed,
-                            &event_tx,
-                            worker_id,
-                            &format!("HTTP {}", response.status),
-                            true,
-                        )
-                        .await;
-                        continue;
-                    }
-
-                    // Extract metadata before consuming response
-                    let disposition_filename = response.content_disposition_filename();
-                    let title = disposition_filename
-                        .clone()
-                        .unwrap_or_else(|| extract_title_from_url(&url));
-                    let mime_type = response
-                        .content_type()
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "application/octet-stream".to_string());
-                    let etag = response.etag().map(|s| s.to_string());
-                    let last_modified = response.last_modified().map(|s| s.to_string());
-                    let server_date = last_modified.as_ref().and_then(|lm| {
-                        chrono::DateTime::parse_from_rfc2822(lm)
-                            .ok()
-                            .map(|dt| dt.with_timezone(&chrono::Utc))
-                    });
-
-                    let content = match response.bytes().await {
-                        Ok(b) => b,
-                        Err(e) => {
-                            handle_download_failure(
-                                &crawl_url,
-                                &crawl_repo,
-                                &failed,
-                                &event_tx,
-                                worker_id,
-                                &e.to_string(),
-                                false,
-                            )
-                            .await;
-                            continue;
-                        }
-                    };
-
-                    let _ = event_tx
-                        .send(DownloadEvent::Progress {
-                            worker_id,
-                            bytes: content.len() as u64,
-                            total: Some(content.len() as u64),
-                        })
-                        .await;
-
-                    // Compute dual hashes for deduplication
-                    let hashes = DocumentVersion::compute_dual_hashes(&content);
-                    let file_size = content.len() as i64;
-
-                    // Check for existing file with same content
-                    let (dedup_index, was_deduplicated) = match doc_repo
-                        .find_existing_file(&hashes.sha256, &hashes.blake3, file_size)
-                        .await
-                    {
-                        Ok(Some(existing_path)) => {
-                            // File already exists, reuse it
-                            deduplicated.fetch_add(1, Ordering::Relaxed);
-                            let _ = event_tx
-                                .send(DownloadEvent::Deduplicated {
-                                    worker_id,
-                                    url: url.clone(),
-                                    existing_path,
-                                })
-                                .await;
-                            (None, true)
-                        }
-                        Ok(None) | Err(_) => {
-                            // No duplicate or dedup check failed - write new file
-                            let (basename, extension) =
-                                extract_filename_parts(&url, &title, &mime_type);
-                            let (relative_path, dedup_idx) = compute_storage_path_with_dedup(
-                                &documents_dir,
-                                &hashes.sha256,
-                                &basename,
-                                &extension,
-                                &content,
-                            );
-                            let new_path = documents_dir.join(&relative_path);
-
-                            let Some(parent) = new_path.parent() else {
-                                send_failure_event(
-                                    &url,
-                                    &failed,
-                                    &event_tx,
-                                    worker_id,
-                                    "storage path has no parent directory",
-                                )
-                                .await;
-                                continue;
-                            };
-                            if let Err(e) = tokio::fs::create_dir_all(parent).await {
-                                send_failure_event(
-                                    &url,
-                                    &failed,
-                                    &event_tx,
-                                    worker_id,
-                                    &e.to_string(),
-                                )
-                                .await;
-                                continue;
-                            }
-
-                            if let Err(e) = tokio::fs::write(&new_path, &content).await {
-                                send_failure_event(
-                                    &url,
-                                    &failed,
-                                    &event_tx,
-                                    worker_id,
-                                    &e.to_string(),
-                                )
-                                .await;
-                                continue;
-                            }
-                            (dedup_idx, false)
-                        }
-                    };
-
-                    let mut version = DocumentVersion::with_precomputed_hashes(
-                        hashes.clone(),
-                        file_size as u64,
-                        mime_type.clone(),
-                        Some(url.clone()),
-                        disposition_filename,
-                        server_date,
-                    );
-                    version.dedup_index = dedup_index;
-
-                    // Save or update document
-                    let new_document = match save_or_update_document(
-                        &doc_repo,
-                        &url,
-                        &crawl_url.source_id,
-                        title,
-                        version,
-                        serde_json::json!({}),
-                        "crawl",
-                    )
-                    .await
-                    {
-                        Ok(new_doc) => new_doc,
-                        Err(e) => {
-                            handle_download_failure(
-                                &crawl_url,
-                                &crawl_repo,
-                                &failed,
-                                &event_tx,
-                                worker_id,
-                                &format!("Failed to save document: {}", e),
-                                false,
-                            )
-                            .await;
-                            continue;
-                        }
-                    };
-
-                    // Mark URL as fetched
-                    let mut fetched_url = crawl_url.clone();
-                    fetched_url.status = UrlStatus::Fetched;
-                    fetched_url.fetched_at = Some(chrono::Utc::now());
-                    fetched_url.etag = etag;
-                    fetched_url.last_modified = last_modified;
-                    fetched_url.content_hash = Some(hashes.sha256.clone());
-                    if let Err(e) = crawl_repo.update_url(&fetched_url).await {
-                        warn!("Failed to update crawl URL status for {}: {}", url, e);
-                    }
+        // Sources with their own proxies, login session, throttle or politeness
+        // share one client across workers so proxy health, per-proxy rate
+        // limits, the session, the bandwidth cap and robots.txt are tracked
+        // in one place
+        let mut source_clients = HashMap::new();
+        let sources: HashSet<&String> = self
+            .config
+            .source_proxies
+            .keys()
+            .chain(self.config.source_sessions.keys())
+            .chain(self.config.source_throttles.keys())
+            .chain(self.config.source_politeness.keys())
+            .collect();
+        for source in sources {
+            let mut builder = HttpClient::builder(
+                "download",
+                self.config.request_timeout,
+                self.config.request_delay,
+            )
+            .privacy(&self.config.privacy);
+            if let Some(proxy) = self.config.source_proxies.get(source) {
+                builder = builder.proxy(proxy);
+            }
+            if let Some(session) = self.config.source_sessions.get(source) {
+                builder = builder.session(session);
+            }
+            if let Some(throttle) = self.config.source_throttles.get(source) {
+                builder = builder.throttle(throttle);
+            }
+            if let Some(politeness) = self.config.source_politeness.get(source) {
+                builder = builder.politeness(politeness);
+            }
+            if let Some(limiter) = &global_bandwidth {
+                builder = builder.bandwidth_limiter(limiter.clone());
+            }
+            let client = builder
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to create HTTP client for {}: {}", source, e))?
+                .with_via_config(self.config.via.clone(), self.config.via_mode);
+            source_clients.insert(source.clone(), client);
+        }
 
-                    // Only count as downloaded if we actually wrote a new file
-                    if !was_deduplicated {
-                        downloaded.fetch_add(1, Ordering::Relaxed);
-                        let _ = event_tx
-                            .send(DownloadEvent::Completed {
-                                worker_id,
-                                url,
-                                new_document,
-                            })
-                            .await;
+        // Sources whose profile caps concurrency hold a permit per download
+        let source_permits: HashMap<String, Arc<Semaphore>> = self
+            .config
+            .source_politeness
+            .iter()
+            .filter_map(|(source, politeness)| {
+                let cap = politeness.concurrency?;
+                Some((source.clone(), Arc::new(Semaphore::new(cap.max(1)))))
+            })
+            .collect();
+
+        // Manifests are cached per verifier, so they are fetched once a run
+        let source_checksums: HashMap<String, ChecksumVerifier> = self
+            .config
+            .source_checksums
+            .iter()
+            .filter(|(_, config)| config.is_enabled())
+            .map(|(source, config)| {
+                let verifier = ChecksumVerifier::new(
+                    config.clone(),
+                    self.config.documents_dir.join(PARTIAL_DIR),
+                );
+                (source.clone(), verifier)
+            })
+            .collect();
+
+        // Save Page Now submissions go through one background task so the
+        // archive's rate limit never holds up a download worker
+        let mut save_clients = HashMap::new();
+        for (source, wayback) in &self.config.source_wayback {
+            if wayback.save_new_urls {
+                let auth = wayback
+                    .authorization()
+                    .map_err(|e| anyhow::anyhow!("Invalid wayback config for {}: {}", source, e))?;
+                let client = SavePageNow::new(&self.config.privacy, auth)
+                    .map_err(|e| anyhow::anyhow!("Failed to create Save Page Now client: {}", e))?;
+                save_clients.insert(source.clone(), client);
+            }
+        }
+        let (save_tx, mut save_rx) = mpsc::channel::<(String, String)>(1000);
+        let save_task = tokio::spawn(async move {
+            while let Some((source, url)) = save_rx.recv().await {
+                if let Some(client) = save_clients.get(&source) {
+                    if let Err(e) = client.submit(&url).await {
+                        warn!("Save Page Now failed for {}: {}", url, e);
                     }
                 }
-            });
+            }
+        });
+
+        // Hashing with SHA-256 as well is only needed until every version
+        // has a BLAKE3 hash
+        let legacy_hashes = self
+            .doc_repo
+            .get_versions_missing_blake3(0, 1)
+            .await
+            .map(|versions| !versions.is_empty())
+            .unwrap_or(true);
+
+        // Stages hand items on over bounded channels, so the slowest stage
+        // sets the pace instead of bodies piling up in memory
+        let pipeline_config = &self.config.pipeline;
+        let capacity = pipeline_config.channel_capacity();
+        let (fetched_tx, fetched_rx) = mpsc::channel(capacity);
+        let (hashed_tx, hashed_rx) = mpsc::channel(capacity);
+        let (ocr_tx, enqueue_task) = match &self.ocr_queue {
+            Some(broker) => {
+                let (tx, rx) = mpsc::channel(capacity);
+                let task = tokio::spawn(pipeline::enqueue_worker(broker.clone(), rx));
+                (Some(tx), Some(task))
+            }
+            None => (None, None),
+        };
 
-            handles.push(handle);
+        let pipeline = Arc::new(Pipeline {
+            doc_repo: self.doc_repo.clone(),
+            crawl_repo: self.crawl_repo.clone(),
+            documents_dir: self.config.documents_dir.clone(),
+            request_timeout: self.config.request_timeout,
+            request_delay: self.config.request_delay,
+            privacy: self.config.privacy.clone(),
+            via: self.config.via.clone(),
+            via_mode: self.config.via_mode,
+            global_bandwidth,
+            global_schedule,
+            source_schedules,
+            source_clients,
+            source_permits,
+            source_checksums,
+            source_wayback: self.config.source_wayback.clone(),
+            source_proxies: self.config.source_proxies.clone(),
+            trusted_content_type: self.config.trusted_content_type.clone(),
+            source_tags: self.config.source_tags.clone(),
+            known_files: Arc::new(known_files),
+            legacy_hashes,
+            large_file_threshold: self.config.large_file_threshold,
+            parallel_chunks: self.config.parallel_chunks,
+            source_id: source_id.map(|s| s.to_string()),
+            limit,
+            downloaded: downloaded.clone(),
+            deduplicated: deduplicated.clone(),
+            skipped: skipped.clone(),
+            failed: failed.clone(),
+            known_skipped: known_skipped.clone(),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            event_tx,
+            save_tx,
+            ocr_tx,
+        });
+
+        let fetch_handles: Vec<_> = (0..workers)
+            .map(|worker_id| {
+                tokio::spawn(pipeline::fetch_worker(
+                    pipeline.clone(),
+                    worker_id,
+                    fetched_tx.clone(),
+                ))
+            })
+            .collect();
+        let fetched_rx = Arc::new(Mutex::new(fetched_rx));
+        let hash_handles: Vec<_> = (0..pipeline_config.hash_workers())
+            .map(|_| {
+                tokio::spawn(pipeline::hash_worker(
+                    pipeline.clone(),
+                    fetched_rx.clone(),
+                    hashed_tx.clone(),
+                ))
+            })
+            .collect();
+        let hashed_rx = Arc::new(Mutex::new(hashed_rx));
+        let store_handles: Vec<_> = (0..pipeline_config.store_workers())
+            .map(|_| tokio::spawn(pipeline::store_worker(pipeline.clone(), hashed_rx.clone())))
+            .collect();
+
+        // Each stage ends once every sender into it is gone: the stage
+        // before it has finished and these originals are dropped
+        drop(fetched_tx);
+        drop(hashed_tx);
+        for (stage, handles) in [
+            ("fetch", fetch_handles),
+            ("hash", hash_handles),
+            ("store", store_handles),
+        ] {
+            for handle in handles {
+                if let Err(e) = handle.await {
+                    tracing::error!("Download {} worker panicked: {}", stage, e);
+                }
+            }
         }
 
-        // Wait for all workers
-        for handle in handles {
-            if let Err(e) = handle.await {
-                tracing::error!("Download worker panicked: {}", e);
+        // The pipeline holds the OCR and Save Page Now senders
+        drop(pipeline);
+        if let Some(task) = enqueue_task {
+            if let Err(e) = task.await {
+                tracing::error!("OCR enqueue task panicked: {}", e);
             }
         }
 
+        // Let queued Save Page Now submissions finish
+        if let Err(e) = save_task.await {
+            tracing::error!("Save Page Now task panicked: {}", e);
+        }
+
         // Get remaining count
         let remaining = if let Some(sid) = source_id {
             self.crawl_repo.get_crawl_state(sid).await?.urls_pending
@@ -401,7 +320,35 @@ impl DownloadService {
             deduplicated: deduplicated.load(Ordering::Relaxed),
             skipped: skipped.load(Ordering::Relaxed),
             failed: failed.load(Ordering::Relaxed),
+            known_files: known_skipped.load(Ordering::Relaxed),
             remaining,
+            interrupted: shutdown::is_requested(),
         })
     }
 }
+
+/// Sniff the type of a file staged on disk from its first few kilobytes.
+async fn sniff_file(path: &std::path::Path, declared: &str) -> Option<&'static str> {
+    use tokio::io::AsyncReadExt;
+
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    let file = tokio::fs::File::open(path).await.ok()?;
+    file.take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .await
+        .ok()?;
+    sniff_mime_type(&head, declared)
+}
+
+/// Write `content` beside `path` and rename it into place, so an
+/// interrupted write never leaves a truncated document at `path`.
+async fn write_atomically(path: &std::path::Path, content: &[u8]) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = std::path::PathBuf::from(tmp);
+    if let Err(e) = tokio::fs::write(&tmp, content).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&tmp, path).await
+}
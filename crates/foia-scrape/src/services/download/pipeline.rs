@@ -0,0 +1,1053 @@
+//! Download pipeline stages.
+//!
+//! Each URL passes through four stages, each run by its own pool of
+//! tasks and connected to the next by a bounded channel:
+//!
+//! 1. fetch: claim a URL and read its body, streaming large files to disk
+//! 2. hash: hash the content, drop files on known-file hash lists, detect
+//!    its type, reject error pages and unchanged re-renders, and check
+//!    published checksums
+//! 3. store: write the file, save the document and mark the URL fetched
+//! 4. enqueue: publish an OCR task for the document, when configured
+//!
+//! A full channel makes the stage before it wait, so a slow disk or
+//! database holds fetching back instead of piling bodies up in memory.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tracing::warn;
+
+use super::checksums::ChecksumVerifier;
+use super::resumable::{download_large, PARTIAL_DIR};
+use super::types::{
+    document_tags, fingerprint_unchanged, handle_download_failure, handle_quarantined,
+    handle_skipped, handle_unchanged, save_or_update_document, send_failure_event, DownloadEvent,
+};
+use super::youtube_download::download_youtube_video;
+use super::{sniff_file, write_atomically};
+use crate::config::ViaMode;
+use crate::services::wayback;
+use crate::services::youtube;
+use crate::{extract_title_from_url, HttpClient};
+use foia::config::WaybackConfig;
+use foia::http_client::ROBOTS_SKIP_REASON;
+use foia::metrics;
+use foia::models::{ChecksumVerification, CrawlUrl, DocumentVersion, FallbackState, UrlStatus};
+use foia::privacy::{PrivacyConfig, ProxyConfig};
+use foia::rate_limit::{BandwidthLimiter, Schedule};
+use foia::repository::{extract_filename_parts, DieselCrawlRepository, DieselDocumentRepository};
+use foia::services::known_files::KnownFiles;
+use foia::services::{quarantine, title_inference, url_list};
+use foia::shutdown;
+use foia::storage::compute_storage_path_with_dedup;
+use foia::utils::sniff_mime_type;
+use foia::work_queue::broker::{Broker, Task};
+
+/// A channel end shared by every task of a stage.
+pub(super) type SharedReceiver<T> = Arc<Mutex<mpsc::Receiver<T>>>;
+
+/// Take the next item for a stage, or `None` once the stage before it is done.
+async fn next<T>(rx: &SharedReceiver<T>) -> Option<T> {
+    rx.lock().await.recv().await
+}
+
+/// Everything the stages share for one run.
+pub(super) struct Pipeline {
+    pub doc_repo: Arc<DieselDocumentRepository>,
+    pub crawl_repo: Arc<DieselCrawlRepository>,
+    pub documents_dir: PathBuf,
+    pub request_timeout: Duration,
+    pub request_delay: Duration,
+    pub privacy: PrivacyConfig,
+    pub via: HashMap<String, String>,
+    pub via_mode: ViaMode,
+    pub global_bandwidth: Option<Arc<BandwidthLimiter>>,
+    pub global_schedule: Option<Schedule>,
+    pub source_schedules: HashMap<String, Schedule>,
+    /// Clients shared by every worker, for sources that need one.
+    pub source_clients: HashMap<String, HttpClient>,
+    pub source_permits: HashMap<String, Arc<Semaphore>>,
+    pub source_checksums: HashMap<String, ChecksumVerifier>,
+    pub source_wayback: HashMap<String, WaybackConfig>,
+    pub source_proxies: HashMap<String, ProxyConfig>,
+    pub trusted_content_type: HashSet<String>,
+    pub source_tags: HashMap<String, Vec<String>>,
+    /// Hash lists of files not worth storing.
+    pub known_files: Arc<KnownFiles>,
+    /// Whether versions without a BLAKE3 hash remain, so content must also
+    /// be hashed with SHA-256 to recognise them.
+    pub legacy_hashes: bool,
+    pub large_file_threshold: u64,
+    pub parallel_chunks: usize,
+    pub source_id: Option<String>,
+    pub limit: Option<usize>,
+    pub downloaded: Arc<AtomicUsize>,
+    pub deduplicated: Arc<AtomicUsize>,
+    pub skipped: Arc<AtomicUsize>,
+    pub failed: Arc<AtomicUsize>,
+    /// URLs skipped for matching a known-file hash list.
+    pub known_skipped: Arc<AtomicUsize>,
+    /// URLs fetched but not yet through the pipeline.
+    pub in_flight: Arc<AtomicUsize>,
+    pub event_tx: mpsc::Sender<DownloadEvent>,
+    /// Save Page Now submissions, as (source, URL).
+    pub save_tx: mpsc::Sender<(String, String)>,
+    /// Saved documents to publish OCR tasks for.
+    pub ocr_tx: Option<mpsc::Sender<String>>,
+}
+
+impl Pipeline {
+    /// A client for sources without their own, one per worker so request
+    /// pacing is per worker.
+    fn default_client(&self) -> Result<HttpClient, String> {
+        let mut builder = HttpClient::builder("download", self.request_timeout, self.request_delay)
+            .privacy(&self.privacy);
+        if let Some(limiter) = &self.global_bandwidth {
+            builder = builder.bandwidth_limiter(limiter.clone());
+        }
+        let client = builder.build().map_err(|e| e.to_string())?;
+        // Apply via mappings for caching proxy support
+        Ok(if self.via.is_empty() {
+            client
+        } else {
+            client.with_via_config(self.via.clone(), self.via_mode)
+        })
+    }
+
+    fn client_for<'a>(&'a self, source_id: &str, default: &'a HttpClient) -> &'a HttpClient {
+        self.source_clients.get(source_id).unwrap_or(default)
+    }
+
+    /// Whether enough has been fetched to reach the limit if it all
+    /// succeeds, and whether the limit has been reached.
+    fn at_limit(&self) -> (bool, bool) {
+        let Some(max) = self.limit else {
+            return (false, false);
+        };
+        let done = self.downloaded.load(Ordering::Relaxed);
+        let pending = done + self.in_flight.load(Ordering::Relaxed);
+        (pending >= max, done >= max)
+    }
+}
+
+/// Counts a URL as in flight until the pipeline is done with it.
+pub(super) struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn new(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self(counter.clone())
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A fetched body, on its way to the hash stage.
+pub(super) struct Fetched {
+    _in_flight: InFlight,
+    worker_id: usize,
+    crawl_url: CrawlUrl,
+    url: String,
+    /// URL the document is saved under; differs for wayback captures.
+    document_url: String,
+    /// Wayback snapshot ID and the URL it replaces.
+    fallback: Option<(i32, String)>,
+    tags: Vec<String>,
+    title: String,
+    mime_type: String,
+    disposition_filename: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    server_date: Option<DateTime<Utc>>,
+    /// Body in memory; empty for large files.
+    content: Vec<u8>,
+    /// Large file streamed to disk, with its hash.
+    staged: Option<(PathBuf, String)>,
+    file_size: i64,
+}
+
+/// Hashed and inspected content, on its way to the store stage.
+pub(super) struct Hashed {
+    fetched: Fetched,
+    hash: String,
+    sha256: Option<String>,
+    declared_mime_type: Option<String>,
+    checksum: Option<ChecksumVerification>,
+}
+
+/// Fetch stage: claim URLs and read their bodies until the queue is
+/// empty, the limit is reached or shutdown is requested.
+pub(super) async fn fetch_worker(
+    pipeline: Arc<Pipeline>,
+    worker_id: usize,
+    out: mpsc::Sender<Fetched>,
+) {
+    let default_client = match pipeline.default_client() {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to create HTTP client: {}", e);
+            return;
+        }
+    };
+    let crawl_repo = &pipeline.crawl_repo;
+    let event_tx = &pipeline.event_tx;
+    let source_id = pipeline.source_id.as_deref();
+
+    loop {
+        // On shutdown, finish the current download but claim no more
+        if shutdown::is_requested() {
+            break;
+        }
+
+        // Stop at the limit; while downloads in flight might still fail,
+        // wait for them rather than claim more
+        match pipeline.at_limit() {
+            (_, true) => break,
+            (true, false) => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            _ => {}
+        }
+
+        // Outside the global window nothing is fetched; a single
+        // requested source waits for its own window
+        if let Some(schedule) = &pipeline.global_schedule {
+            schedule.wait_until_open("Downloads").await;
+        }
+        if let Some(sid) = source_id {
+            if let Some(schedule) = pipeline.source_schedules.get(sid) {
+                schedule.wait_until_open(sid).await;
+            }
+        }
+
+        // Other sources outside their window stay in the queue
+        let closed: Vec<String> = pipeline
+            .source_schedules
+            .iter()
+            .filter(|(_, schedule)| !schedule.is_open())
+            .map(|(source, _)| source.clone())
+            .collect();
+
+        // Claim a URL to process
+        let crawl_url = match crawl_repo
+            .claim_pending_url_excluding(source_id, &closed)
+            .await
+        {
+            Ok(Some(url)) => url,
+            Ok(None) => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                match crawl_repo
+                    .claim_pending_url_excluding(source_id, &closed)
+                    .await
+                {
+                    Ok(Some(url)) => url,
+                    // Downloads in flight can still fail and fall short of
+                    // the limit, but the queue is empty either way
+                    _ => break,
+                }
+            }
+            Err(_) => {
+                tokio::time::sleep(Duration::from_millis(500)).await;
+                continue;
+            }
+        };
+
+        let url = crawl_url.url.clone();
+        let tags = document_tags(&crawl_url, &pipeline.source_tags);
+        let client = pipeline.client_for(&crawl_url.source_id, &default_client);
+        let filename = extract_title_from_url(&url);
+        let _permit = match pipeline.source_permits.get(&crawl_url.source_id) {
+            Some(permits) => permits.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+
+        let _ = event_tx
+            .send(DownloadEvent::Started {
+                worker_id,
+                url: url.clone(),
+                filename: filename.clone(),
+            })
+            .await;
+
+        // Handle YouTube URLs specially
+        if youtube::is_youtube_url(&url) {
+            let proxy_url = pipeline
+                .source_proxies
+                .get(&crawl_url.source_id)
+                .and_then(|p| p.urls.first().cloned())
+                .or_else(|| pipeline.privacy.effective_proxy_url());
+            let yt_result = download_youtube_video(
+                &url,
+                &crawl_url,
+                &pipeline.documents_dir,
+                &pipeline.doc_repo,
+                crawl_repo,
+                worker_id,
+                event_tx,
+                &pipeline.downloaded,
+                &pipeline.failed,
+                proxy_url.as_deref(),
+                &tags,
+            )
+            .await;
+
+            if yt_result {
+                continue;
+            }
+            // If YouTube download failed, continue to try regular HTTP
+        }
+
+        // Fetch the URL
+        let response = match client
+            .get(
+                &url,
+                crawl_url.etag.as_deref(),
+                crawl_url.last_modified.as_deref(),
+            )
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                handle_download_failure(
+                    &crawl_url,
+                    crawl_repo,
+                    &pipeline.failed,
+                    event_tx,
+                    worker_id,
+                    &e.to_string(),
+                    true,
+                )
+                .await;
+                continue;
+            }
+        };
+
+        if response.is_not_modified() {
+            handle_unchanged(
+                &crawl_url,
+                crawl_repo,
+                &pipeline.skipped,
+                event_tx,
+                worker_id,
+            )
+            .await;
+            continue;
+        }
+
+        if response.is_robots_disallowed() {
+            handle_skipped(
+                &crawl_url,
+                crawl_repo,
+                &pipeline.skipped,
+                event_tx,
+                worker_id,
+                ROBOTS_SKIP_REASON,
+            )
+            .await;
+            continue;
+        }
+
+        if !response.is_success() {
+            // A document that is gone gets one wayback lookup, on its
+            // first failure
+            let status = response.status.as_u16();
+            let wayback_config = pipeline
+                .source_wayback
+                .get(&crawl_url.source_id)
+                .filter(|w| w.fallback);
+            if let Some(config) = wayback_config {
+                if wayback::is_gone(status)
+                    && crawl_url.retry_count == 0
+                    && wayback::fallback_target(&crawl_url).is_none()
+                {
+                    if let Err(e) = wayback::offer_fallback(
+                        crawl_repo,
+                        &crawl_url,
+                        &pipeline.privacy,
+                        config.auto_ingest,
+                    )
+                    .await
+                    {
+                        warn!("Wayback lookup failed for {}: {}", url, e);
+                    }
+                }
+            }
+            handle_download_failure(
+                &crawl_url,
+                crawl_repo,
+                &pipeline.failed,
+                event_tx,
+                worker_id,
+                &format!("HTTP {}", response.status),
+                true,
+            )
+            .await;
+            continue;
+        }
+
+        // Wayback captures are saved against the URL they replace
+        let fallback = wayback::fallback_target(&crawl_url);
+        let document_url = fallback
+            .as_ref()
+            .map_or(url.clone(), |(_, original)| original.clone());
+
+        // Extract metadata before consuming response
+        let disposition_filename = response.content_disposition_filename();
+        let title = title_inference::download_title(
+            url_list::listed_title(&crawl_url),
+            disposition_filename.as_deref(),
+            &document_url,
+        );
+        let mime_type = response
+            .content_type()
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let etag = response.etag().map(|s| s.to_string());
+        let last_modified = response.last_modified().map(|s| s.to_string());
+        let server_date = last_modified.as_ref().and_then(|lm| {
+            chrono::DateTime::parse_from_rfc2822(lm)
+                .ok()
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+        });
+
+        // Large files are streamed to a resumable partial file and hashed
+        // from disk instead of buffered in memory
+        let large_total = response
+            .content_length()
+            .filter(|len| *len >= pipeline.large_file_threshold);
+        let (content, staged, file_size) = if let Some(total) = large_total {
+            let report = |bytes: u64| {
+                let _ = event_tx.try_send(DownloadEvent::Progress {
+                    worker_id,
+                    bytes,
+                    total: Some(total),
+                });
+            };
+            match download_large(
+                client,
+                &url,
+                response,
+                total,
+                &pipeline.documents_dir.join(PARTIAL_DIR),
+                pipeline.parallel_chunks,
+                &report,
+            )
+            .await
+            {
+                Ok(file) => (Vec::new(), Some((file.path, file.hash)), file.size as i64),
+                Err(e) => {
+                    handle_download_failure(
+                        &crawl_url,
+                        crawl_repo,
+                        &pipeline.failed,
+                        event_tx,
+                        worker_id,
+                        &e,
+                        false,
+                    )
+                    .await;
+                    continue;
+                }
+            }
+        } else {
+            let content = match response.bytes().await {
+                Ok(b) => b,
+                Err(e) => {
+                    handle_download_failure(
+                        &crawl_url,
+                        crawl_repo,
+                        &pipeline.failed,
+                        event_tx,
+                        worker_id,
+                        &e.to_string(),
+                        false,
+                    )
+                    .await;
+                    continue;
+                }
+            };
+
+            let _ = event_tx
+                .send(DownloadEvent::Progress {
+                    worker_id,
+                    bytes: content.len() as u64,
+                    total: Some(content.len() as u64),
+                })
+                .await;
+
+            let file_size = content.len() as i64;
+            (content, None, file_size)
+        };
+        metrics::BYTES_DOWNLOADED.inc_by(&[&crawl_url.source_id], file_size as f64);
+
+        let fetched = Fetched {
+            _in_flight: InFlight::new(&pipeline.in_flight),
+            worker_id,
+            crawl_url,
+            url,
+            document_url,
+            fallback,
+            tags,
+            title,
+            mime_type,
+            disposition_filename,
+            etag,
+            last_modified,
+            server_date,
+            content,
+            staged,
+            file_size,
+        };
+        // Waits here while the hash stage is full
+        if out.send(fetched).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Hash stage: hash and inspect fetched content.
+pub(super) async fn hash_worker(
+    pipeline: Arc<Pipeline>,
+    input: SharedReceiver<Fetched>,
+    out: mpsc::Sender<Hashed>,
+) {
+    // Checksum manifests are fetched with the source's client
+    let default_client = match pipeline.default_client() {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::error!("Failed to create HTTP client: {}", e);
+            return;
+        }
+    };
+
+    while let Some(fetched) = next(&input).await {
+        if let Some(hashed) = hash_and_inspect(&pipeline, &default_client, fetched).await {
+            if out.send(hashed).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// Hash content and decide whether it's worth storing. Returns `None`
+/// once the URL has been dealt with here.
+async fn hash_and_inspect(
+    pipeline: &Pipeline,
+    default_client: &HttpClient,
+    mut fetched: Fetched,
+) -> Option<Hashed> {
+    let crawl_url = &fetched.crawl_url;
+    let crawl_repo = &pipeline.crawl_repo;
+    let event_tx = &pipeline.event_tx;
+    let worker_id = fetched.worker_id;
+
+    let hash = match &fetched.staged {
+        Some((_, hash)) => hash.clone(),
+        None => {
+            // Hashing is CPU-bound; keep it off the async workers
+            let content = std::mem::take(&mut fetched.content);
+            match tokio::task::spawn_blocking(move || {
+                let hash = DocumentVersion::compute_hash(&content);
+                (content, hash)
+            })
+            .await
+            {
+                Ok((content, hash)) => {
+                    fetched.content = content;
+                    hash
+                }
+                Err(e) => {
+                    send_failure_event(
+                        &fetched.url,
+                        &pipeline.failed,
+                        event_tx,
+                        worker_id,
+                        &e.to_string(),
+                    )
+                    .await;
+                    return None;
+                }
+            }
+        }
+    };
+
+    // Logos, separator pages and blank forms on a skip list aren't stored
+    if !pipeline.known_files.is_empty() {
+        let known_files = pipeline.known_files.clone();
+        let staged = fetched.staged.as_ref().map(|(path, _)| path.clone());
+        let content = std::mem::take(&mut fetched.content);
+        let blake3 = hash.clone();
+        let matched = tokio::task::spawn_blocking(move || {
+            let matched = match &staged {
+                Some(path) => known_files
+                    .match_file(path, &blake3)
+                    .unwrap_or_else(|e| {
+                        warn!(
+                            "Failed to check {} against hash lists: {}",
+                            path.display(),
+                            e
+                        );
+                        None
+                    })
+                    .map(str::to_string),
+                None => known_files
+                    .match_content(&content, &blake3)
+                    .map(str::to_string),
+            };
+            (content, matched)
+        })
+        .await;
+        match matched {
+            Ok((content, matched)) => {
+                fetched.content = content;
+                if let Some(list) = matched {
+                    if let Some((staged, _)) = &fetched.staged {
+                        let _ = tokio::fs::remove_file(staged).await;
+                    }
+                    handle_skipped(
+                        crawl_url,
+                        crawl_repo,
+                        &pipeline.known_skipped,
+                        event_tx,
+                        worker_id,
+                        &format!("known file ({})", list),
+                    )
+                    .await;
+                    return None;
+                }
+            }
+            Err(e) => {
+                send_failure_event(
+                    &fetched.url,
+                    &pipeline.failed,
+                    event_tx,
+                    worker_id,
+                    &e.to_string(),
+                )
+                .await;
+                return None;
+            }
+        }
+    }
+
+    // Servers mislabel files (PDFs as text/html); unless the source is
+    // trusted, the content's magic bytes win
+    let declared_mime_type = if pipeline.trusted_content_type.contains(&crawl_url.source_id) {
+        None
+    } else {
+        let detected = match &fetched.staged {
+            Some((staged, _)) => sniff_file(staged, &fetched.mime_type).await,
+            None => sniff_mime_type(&fetched.content, &fetched.mime_type),
+        };
+        detected.map(|d| std::mem::replace(&mut fetched.mime_type, d.to_string()))
+    };
+
+    // "Access Denied" and login pages served in place of the document are
+    // retried later rather than saved
+    if fetched.staged.is_none() {
+        if let Some(page) = quarantine::detect_error_page(
+            &fetched.content,
+            &fetched.mime_type,
+            &fetched.document_url,
+        ) {
+            handle_quarantined(
+                crawl_url,
+                crawl_repo,
+                &pipeline.failed,
+                event_tx,
+                worker_id,
+                page,
+            )
+            .await;
+            return None;
+        }
+    }
+
+    // Pages served without validators re-render on every fetch; only a
+    // change in their fingerprint is a new version
+    if fetched.staged.is_none()
+        && fetched.fallback.is_none()
+        && fetched.etag.is_none()
+        && fetched.last_modified.is_none()
+        && crawl_url.fetched_at.is_some()
+        && fingerprint_unchanged(
+            &pipeline.doc_repo,
+            &pipeline.documents_dir,
+            &crawl_url.source_id,
+            &fetched.document_url,
+            &fetched.content,
+            &fetched.mime_type,
+        )
+        .await
+    {
+        handle_unchanged(
+            crawl_url,
+            crawl_repo,
+            &pipeline.skipped,
+            event_tx,
+            worker_id,
+        )
+        .await;
+        return None;
+    }
+
+    // Versions stored before BLAKE3 and not yet backfilled by `db migrate`
+    // are only recognised by SHA-256
+    let sha256 = if pipeline.legacy_hashes {
+        let staged = fetched.staged.as_ref().map(|(path, _)| path.clone());
+        let content = std::mem::take(&mut fetched.content);
+        match tokio::task::spawn_blocking(move || {
+            let sha256 = match &staged {
+                Some(path) => std::fs::File::open(path)
+                    .and_then(DocumentVersion::compute_hash_sha256_reader)
+                    .map_err(|e| warn!("Failed to hash {}: {}", path.display(), e))
+                    .ok(),
+                None => Some(DocumentVersion::compute_hash_sha256(&content)),
+            };
+            (content, sha256)
+        })
+        .await
+        {
+            Ok((content, sha256)) => {
+                fetched.content = content;
+                sha256
+            }
+            Err(e) => {
+                send_failure_event(
+                    &fetched.url,
+                    &pipeline.failed,
+                    event_tx,
+                    worker_id,
+                    &e.to_string(),
+                )
+                .await;
+                return None;
+            }
+        }
+    } else {
+        None
+    };
+
+    // Check against checksums the source publishes, if any
+    let checksum = match pipeline.source_checksums.get(&crawl_url.source_id) {
+        Some(verifier) => {
+            let client = pipeline.client_for(&crawl_url.source_id, default_client);
+            verifier
+                .verify(
+                    client,
+                    &fetched.url,
+                    fetched.disposition_filename.as_deref(),
+                    &fetched.content,
+                    fetched.staged.as_ref().map(|(path, _)| path.as_path()),
+                )
+                .await
+        }
+        None => None,
+    };
+
+    Some(Hashed {
+        fetched,
+        hash,
+        sha256,
+        declared_mime_type,
+        checksum,
+    })
+}
+
+/// Store stage: write files and save documents.
+pub(super) async fn store_worker(pipeline: Arc<Pipeline>, input: SharedReceiver<Hashed>) {
+    while let Some(hashed) = next(&input).await {
+        store(&pipeline, hashed).await;
+    }
+}
+
+/// Write a file into storage, unless identical content is already there,
+/// and save its document.
+async fn store(pipeline: &Pipeline, hashed: Hashed) {
+    let Hashed {
+        fetched,
+        hash,
+        sha256,
+        declared_mime_type,
+        checksum,
+    } = hashed;
+    let Fetched {
+        _in_flight,
+        worker_id,
+        crawl_url,
+        url,
+        document_url,
+        fallback,
+        tags,
+        mut title,
+        mime_type,
+        disposition_filename,
+        etag,
+        last_modified,
+        server_date,
+        content,
+        staged,
+        file_size,
+    } = fetched;
+    let mut file_path = None;
+    let staged_file = staged.map(|(path, _)| path);
+    let documents_dir = &pipeline.documents_dir;
+    let crawl_repo = &pipeline.crawl_repo;
+    let event_tx = &pipeline.event_tx;
+
+    // Check for existing file with same content
+    let (dedup_index, was_deduplicated) = match pipeline
+        .doc_repo
+        .find_existing_file(&hash, sha256.as_deref(), file_size)
+        .await
+    {
+        Ok(Some(existing_path)) => {
+            // File already exists, reuse it
+            if let Some(staged) = &staged_file {
+                let _ = tokio::fs::remove_file(staged).await;
+            }
+            pipeline.deduplicated.fetch_add(1, Ordering::Relaxed);
+            metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "deduplicated"]);
+            let _ = event_tx
+                .send(DownloadEvent::Deduplicated {
+                    worker_id,
+                    url: url.clone(),
+                    existing_path,
+                })
+                .await;
+            (None, true)
+        }
+        Ok(None) | Err(_) => {
+            // No duplicate or dedup check failed - write new file
+            let (basename, extension) = extract_filename_parts(&url, &title, &mime_type);
+            let (relative_path, dedup_idx) = compute_storage_path_with_dedup(
+                documents_dir,
+                &hash,
+                &basename,
+                &extension,
+                &content,
+            );
+            let new_path = documents_dir.join(&relative_path);
+
+            let Some(parent) = new_path.parent() else {
+                send_failure_event(
+                    &url,
+                    &pipeline.failed,
+                    event_tx,
+                    worker_id,
+                    "storage path has no parent directory",
+                )
+                .await;
+                return;
+            };
+            if let Err(e) = tokio::fs::create_dir_all(parent).await {
+                send_failure_event(&url, &pipeline.failed, event_tx, worker_id, &e.to_string())
+                    .await;
+                return;
+            }
+
+            let stored = match &staged_file {
+                Some(staged) => tokio::fs::rename(staged, &new_path).await,
+                None => write_atomically(&new_path, &content).await,
+            };
+            if let Err(e) = stored {
+                send_failure_event(&url, &pipeline.failed, event_tx, worker_id, &e.to_string())
+                    .await;
+                return;
+            }
+
+            // A PDF's own title beats a junk filename. The file is
+            // already named after the old one, so its path is pinned
+            if mime_type == "application/pdf" && title_inference::is_junk_title(&title) {
+                let path = new_path.clone();
+                if let Ok(Some(better)) =
+                    tokio::task::spawn_blocking(move || title_inference::pdf_metadata_title(&path))
+                        .await
+                {
+                    title = better;
+                    file_path = Some(relative_path);
+                }
+            }
+            (dedup_idx, false)
+        }
+    };
+
+    let mut version = DocumentVersion::with_precomputed_hash(
+        hash.clone(),
+        file_size as u64,
+        mime_type,
+        Some(url.clone()),
+        disposition_filename,
+        server_date,
+    );
+    version.dedup_index = dedup_index;
+    version.file_path = file_path;
+    version.checksum = checksum;
+    version.declared_mime_type = declared_mime_type;
+    version.computed_sha256 = sha256;
+    version.archive_snapshot_id = fallback.as_ref().map(|(id, _)| *id);
+    let (mut metadata, discovery_method) = match &fallback {
+        Some(_) => (serde_json::json!({"from_archive": true}), "wayback"),
+        None => (serde_json::json!({}), "crawl"),
+    };
+    if let serde_json::Value::Object(metadata) = &mut metadata {
+        for (key, value) in crawl_url.discovery_metadata() {
+            metadata.entry(key).or_insert(value);
+        }
+    }
+    if let Some(date) = url_list::listed_date(&crawl_url) {
+        metadata["estimated_date"] = serde_json::json!({
+            "date": date.to_rfc3339(),
+            "confidence": "high",
+            "source": "url_list",
+        });
+    }
+
+    // Save or update document
+    let saved = match save_or_update_document(
+        &pipeline.doc_repo,
+        &document_url,
+        &crawl_url.source_id,
+        title,
+        version,
+        metadata,
+        discovery_method,
+        &tags,
+    )
+    .await
+    {
+        Ok(saved) => saved,
+        Err(e) => {
+            handle_download_failure(
+                &crawl_url,
+                crawl_repo,
+                &pipeline.failed,
+                event_tx,
+                worker_id,
+                &format!("Failed to save document: {}", e),
+                false,
+            )
+            .await;
+            return;
+        }
+    };
+
+    // Mark URL as fetched
+    let mut fetched_url = crawl_url.clone();
+    fetched_url.status = UrlStatus::Fetched;
+    fetched_url.fetched_at = Some(chrono::Utc::now());
+    fetched_url.etag = etag;
+    fetched_url.last_modified = last_modified;
+    fetched_url.content_hash = Some(hash);
+    if let Err(e) = crawl_repo.update_url(&fetched_url).await {
+        warn!("Failed to update crawl URL status for {}: {}", url, e);
+    }
+
+    match &fallback {
+        Some((snapshot_id, _)) => {
+            if let Err(e) = crawl_repo
+                .set_fallback_state(*snapshot_id, FallbackState::Ingested)
+                .await
+            {
+                warn!("Failed to mark wayback capture {}: {}", snapshot_id, e);
+            }
+        }
+        None if crawl_url.fetched_at.is_none()
+            && pipeline
+                .source_wayback
+                .get(&crawl_url.source_id)
+                .is_some_and(|w| w.save_new_urls) =>
+        {
+            let _ = pipeline
+                .save_tx
+                .try_send((crawl_url.source_id.clone(), url.clone()));
+        }
+        None => {}
+    }
+
+    if saved.new_version {
+        if let Some(ocr_tx) = &pipeline.ocr_tx {
+            let _ = ocr_tx.send(saved.id).await;
+        }
+    }
+
+    // Only count as downloaded if we actually wrote a new file
+    if !was_deduplicated {
+        pipeline.downloaded.fetch_add(1, Ordering::Relaxed);
+        metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "downloaded"]);
+        let _ = event_tx
+            .send(DownloadEvent::Completed {
+                worker_id,
+                url,
+                new_document: saved.created,
+            })
+            .await;
+    }
+}
+
+/// Enqueue stage: publish an OCR task for each saved document.
+pub(super) async fn enqueue_worker(broker: Arc<dyn Broker>, mut input: mpsc::Receiver<String>) {
+    while let Some(document_id) = input.recv().await {
+        if let Err(e) = broker.publish(&Task::ocr(&document_id)).await {
+            warn!("Failed to queue OCR for {}: {}", document_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shared_receiver_splits_items() {
+        let (tx, rx) = mpsc::channel::<usize>(2);
+        let rx: SharedReceiver<usize> = Arc::new(Mutex::new(rx));
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let rx = rx.clone();
+                tokio::spawn(async move {
+                    let mut seen = Vec::new();
+                    while let Some(item) = next(&rx).await {
+                        seen.push(item);
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for item in 0..20 {
+            tx.send(item).await.unwrap();
+        }
+        drop(tx);
+
+        let mut all = Vec::new();
+        for worker in workers {
+            all.extend(worker.await.unwrap());
+        }
+        all.sort();
+        assert_eq!(all, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_in_flight_counts_until_dropped() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let first = InFlight::new(&counter);
+        let second = InFlight::new(&counter);
+        assert_eq!(counter.load(Ordering::Relaxed), 2);
+        drop(first);
+        assert_eq!(counter.load(Ordering::Relaxed), 1);
+        drop(second);
+        assert_eq!(counter.load(Ordering::Relaxed), 0);
+    }
+}
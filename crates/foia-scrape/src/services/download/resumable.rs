@@ -0,0 +1,568 @@
+//! Resumable and parallel downloads for large files.
+//!
+//! Large bodies are streamed to `<documents_dir>/.partial/<key>.part` with a
+//! `<key>.json` sidecar recording how much of each byte range is on disk.
+//! A dropped connection resumes with a `Range` request instead of starting
+//! over, and servers that accept ranges get the file as several concurrent
+//! chunks. `If-Range` guards every resume so a file that changed upstream is
+//! never stitched together from two versions. The finished file is hashed
+//! from disk and checked against its length and any digest the server sent.
+
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
+
+use crate::{HttpClient, HttpResponse};
+use foia::models::{ChecksumAlgorithm, DocumentVersion};
+
+/// Directory under the documents dir holding in-progress downloads.
+pub const PARTIAL_DIR: &str = ".partial";
+
+/// Responses at least this large are streamed to disk and made resumable.
+pub const DEFAULT_LARGE_FILE_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Smallest range worth its own connection.
+const MIN_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Bytes written to a chunk between sidecar checkpoints.
+const CHECKPOINT_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Bytes written between progress callbacks.
+const PROGRESS_BYTES: u64 = 1024 * 1024;
+
+/// Attempts per chunk before giving up and leaving the partial file for the
+/// next run.
+const CHUNK_ATTEMPTS: u32 = 3;
+
+/// One byte range of the file, fetched over its own connection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkState {
+    /// First byte of the range.
+    pub start: u64,
+    /// One past the last byte of the range.
+    pub end: u64,
+    /// Bytes of this range already on disk.
+    pub done: u64,
+}
+
+impl ChunkState {
+    fn next_byte(&self) -> u64 {
+        self.start + self.done
+    }
+
+    fn is_complete(&self) -> bool {
+        self.next_byte() >= self.end
+    }
+
+    /// `Range` header value for the bytes still missing.
+    fn range_header(&self) -> String {
+        format!("bytes={}-{}", self.next_byte(), self.end - 1)
+    }
+}
+
+/// Bookkeeping for a partially downloaded file, saved next to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartialState {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Full length of the file.
+    pub total: u64,
+    pub chunks: Vec<ChunkState>,
+}
+
+impl PartialState {
+    /// Start a fresh download split into at most `chunks` ranges.
+    pub fn new(
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        total: u64,
+        chunks: usize,
+    ) -> Self {
+        let count = (total / MIN_CHUNK_SIZE).clamp(1, chunks.max(1) as u64);
+        let size = total.div_ceil(count);
+        let chunks = (0..count)
+            .map(|i| ChunkState {
+                start: i * size,
+                end: ((i + 1) * size).min(total),
+                done: 0,
+            })
+            .filter(|c| c.start < c.end)
+            .collect();
+        Self {
+            url: url.to_string(),
+            etag,
+            last_modified,
+            total,
+            chunks,
+        }
+    }
+
+    /// Bytes on disk across all ranges.
+    pub fn bytes_done(&self) -> u64 {
+        self.chunks.iter().map(|c| c.done).sum()
+    }
+
+    /// Whether every range is on disk.
+    pub fn is_complete(&self) -> bool {
+        self.chunks.iter().all(ChunkState::is_complete)
+    }
+
+    /// Whether a saved download still describes the file the server has now.
+    ///
+    /// Needs a validator: without one a changed file can't be detected.
+    pub fn matches(&self, etag: Option<&str>, last_modified: Option<&str>, total: u64) -> bool {
+        self.if_range().is_some()
+            && self.total == total
+            && self.etag.as_deref() == etag
+            && self.last_modified.as_deref() == last_modified
+    }
+
+    /// Validator for `If-Range`. Weak ETags aren't allowed there.
+    fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|e| !e.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+/// A finished large download, hashed and ready to move into storage.
+pub struct LargeFile {
+    /// Completed file under the partial directory.
+    pub path: PathBuf,
+    /// BLAKE3 of the file.
+    pub hash: String,
+    pub size: u64,
+}
+
+/// Why a chunk stopped.
+enum ChunkError {
+    /// The server ignored the range or the file changed; start over.
+    Changed(String),
+    /// Network or disk failure; what's on disk can be resumed later.
+    Failed(String),
+}
+
+/// Partial file and sidecar for one URL.
+struct PartialDownload {
+    part_path: PathBuf,
+    state_path: PathBuf,
+    state: tokio::sync::Mutex<PartialState>,
+}
+
+impl PartialDownload {
+    /// Resume a saved download of the same file, or start a new one.
+    async fn open(dir: &Path, fresh: PartialState, resumable: bool) -> std::io::Result<Self> {
+        tokio::fs::create_dir_all(dir).await?;
+        // SHA-256 so partial files saved before the switch to BLAKE3 resume
+        let key = DocumentVersion::compute_hash_sha256(fresh.url.as_bytes());
+        let part_path = dir.join(format!("{}.part", &key[..32]));
+        let state_path = dir.join(format!("{}.json", &key[..32]));
+
+        let saved = tokio::fs::read_to_string(&state_path)
+            .await
+            .ok()
+            .and_then(|s| serde_json::from_str::<PartialState>(&s).ok())
+            .filter(|s| {
+                resumable
+                    && s.url == fresh.url
+                    && s.matches(
+                        fresh.etag.as_deref(),
+                        fresh.last_modified.as_deref(),
+                        fresh.total,
+                    )
+            });
+        let part_len = tokio::fs::metadata(&part_path).await.map(|m| m.len()).ok();
+
+        let state = match saved {
+            Some(saved) if part_len == Some(saved.total) => {
+                info!(
+                    "Resuming {} at {} of {} bytes",
+                    saved.url,
+                    saved.bytes_done(),
+                    saved.total
+                );
+                saved
+            }
+            _ => {
+                let file = tokio::fs::File::create(&part_path).await?;
+                file.set_len(fresh.total).await?;
+                fresh
+            }
+        };
+
+        let download = Self {
+            part_path,
+            state_path,
+            state: tokio::sync::Mutex::new(state),
+        };
+        if resumable {
+            download.save(&*download.state.lock().await).await?;
+        }
+        Ok(download)
+    }
+
+    async fn save(&self, state: &PartialState) -> std::io::Result<()> {
+        let json = serde_json::to_string(state)?;
+        tokio::fs::write(&self.state_path, json).await
+    }
+
+    async fn chunk(&self, index: usize) -> ChunkState {
+        self.state.lock().await.chunks[index].clone()
+    }
+
+    /// Record how much of a range is on disk. Data must be synced first.
+    async fn checkpoint(&self, index: usize, done: u64) {
+        let mut state = self.state.lock().await;
+        state.chunks[index].done = done;
+        if let Err(e) = self.save(&state).await {
+            warn!(
+                "Failed to save download state {}: {}",
+                self.state_path.display(),
+                e
+            );
+        }
+    }
+
+    async fn discard(&self) {
+        let _ = tokio::fs::remove_file(&self.part_path).await;
+        let _ = tokio::fs::remove_file(&self.state_path).await;
+    }
+}
+
+/// Download a large file to disk, resuming and parallelizing where the
+/// server allows it.
+///
+/// `response` is the already-open 200 response for the whole file; it fills
+/// the first range of a fresh download. `progress` is called with the total
+/// bytes on disk as they arrive.
+pub async fn download_large(
+    client: &HttpClient,
+    url: &str,
+    response: HttpResponse,
+    total: u64,
+    partial_dir: &Path,
+    parallel_chunks: usize,
+    progress: &(dyn Fn(u64) + Sync),
+) -> Result<LargeFile, String> {
+    let resumable = response.accepts_ranges();
+    let expected_sha256 = response.sha256_digest();
+    let fresh = PartialState::new(
+        url,
+        response.etag().map(str::to_string),
+        response.last_modified().map(str::to_string),
+        total,
+        if resumable { parallel_chunks } else { 1 },
+    );
+
+    let download = PartialDownload::open(partial_dir, fresh, resumable)
+        .await
+        .map_err(|e| format!("Failed to create partial file: {}", e))?;
+    let state = download.state.lock().await.clone();
+    let done = AtomicU64::new(state.bytes_done());
+    progress(state.bytes_done());
+
+    // The open response starts at byte 0, so only a fresh download can use it
+    let mut initial = (state.bytes_done() == 0).then_some(response);
+    let attempts = if resumable { CHUNK_ATTEMPTS } else { 1 };
+    debug!(
+        "Downloading {} ({} bytes) in {} range(s)",
+        url,
+        total,
+        state.chunks.len()
+    );
+
+    let report = |bytes: u64| progress(done.fetch_add(bytes, Ordering::Relaxed) + bytes);
+    let mut fetches = Vec::new();
+    for (index, chunk) in state.chunks.iter().enumerate() {
+        if chunk.is_complete() {
+            continue;
+        }
+        let initial = if index == 0 { initial.take() } else { None };
+        fetches.push(fetch_chunk(
+            client,
+            &download,
+            state.if_range(),
+            index,
+            initial,
+            attempts,
+            &report,
+        ));
+    }
+    drop(initial);
+
+    let mut failure = None;
+    for result in futures::future::join_all(fetches).await {
+        match result {
+            Ok(()) => {}
+            Err(ChunkError::Changed(e)) => {
+                download.discard().await;
+                return Err(format!("{}; partial download discarded", e));
+            }
+            Err(ChunkError::Failed(e)) => failure = Some(e),
+        }
+    }
+    if let Some(e) = failure {
+        if !resumable {
+            download.discard().await;
+            return Err(e);
+        }
+        return Err(format!("{} (partial download kept for resume)", e));
+    }
+
+    finish(download, total, expected_sha256).await
+}
+
+/// Hash the completed file and check it against what the server promised.
+async fn finish(
+    download: PartialDownload,
+    total: u64,
+    expected_sha256: Option<String>,
+) -> Result<LargeFile, String> {
+    let state = download.state.lock().await.clone();
+    let size = tokio::fs::metadata(&download.part_path)
+        .await
+        .map(|m| m.len())
+        .map_err(|e| e.to_string())?;
+    if !state.is_complete() || size != total {
+        download.discard().await;
+        return Err(format!(
+            "Incomplete download: {} of {} bytes",
+            state.bytes_done(),
+            total
+        ));
+    }
+
+    let path = download.part_path.clone();
+    let check_sha256 = expected_sha256.is_some();
+    let (hash, sha256) = tokio::task::spawn_blocking(move || {
+        let hash = std::fs::File::open(&path).and_then(DocumentVersion::compute_hash_reader)?;
+        // SHA-256 costs a second pass, so only when there's a digest to check
+        let sha256 = if check_sha256 {
+            Some(ChecksumAlgorithm::Sha256.hash_reader(std::fs::File::open(&path)?)?)
+        } else {
+            None
+        };
+        Ok::<_, std::io::Error>((hash, sha256))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Failed to hash download: {}", e))?;
+
+    if let (Some(expected), Some(actual)) = (expected_sha256, sha256) {
+        if !expected.eq_ignore_ascii_case(&actual) {
+            download.discard().await;
+            return Err(format!(
+                "SHA-256 mismatch: server sent {}, got {}",
+                expected, actual
+            ));
+        }
+    }
+
+    let _ = tokio::fs::remove_file(&download.state_path).await;
+    Ok(LargeFile {
+        path: download.part_path,
+        hash,
+        size,
+    })
+}
+
+/// Fetch the missing part of one range, retrying from where it stopped.
+async fn fetch_chunk(
+    client: &HttpClient,
+    download: &PartialDownload,
+    if_range: Option<&str>,
+    index: usize,
+    mut initial: Option<HttpResponse>,
+    attempts: u32,
+    progress: &(dyn Fn(u64) + Sync),
+) -> Result<(), ChunkError> {
+    let url = download.state.lock().await.url.clone();
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(&download.part_path)
+        .await
+        .map_err(|e| ChunkError::Failed(e.to_string()))?;
+    let mut chunk = download.chunk(index).await;
+
+    for attempt in 1..=attempts {
+        let response = match initial.take() {
+            Some(response) => Ok(response),
+            None => request_range(client, &url, &chunk, if_range).await,
+        };
+        let result = match response {
+            Ok(response) => {
+                write_chunk(&mut file, download, index, &mut chunk, response, progress).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(ChunkError::Failed(e)) if attempt < attempts => {
+                warn!(
+                    "Range {} of {} failed at byte {} ({}), retrying",
+                    index,
+                    url,
+                    chunk.next_byte(),
+                    e
+                );
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(ChunkError::Failed(format!("Range {} failed", index)))
+}
+
+/// Request the missing bytes of a range.
+async fn request_range(
+    client: &HttpClient,
+    url: &str,
+    chunk: &ChunkState,
+    if_range: Option<&str>,
+) -> Result<HttpResponse, ChunkError> {
+    let mut headers = HashMap::new();
+    headers.insert("Range".to_string(), chunk.range_header());
+    // Byte offsets must refer to the stored representation, not a compressed one
+    headers.insert("Accept-Encoding".to_string(), "identity".to_string());
+    if let Some(validator) = if_range {
+        headers.insert("If-Range".to_string(), validator.to_string());
+    }
+
+    let response = client
+        .get_with_headers(url, headers)
+        .await
+        .map_err(|e| ChunkError::Failed(e.to_string()))?;
+    match response.status {
+        StatusCode::PARTIAL_CONTENT => match response.content_range() {
+            Some(range) if range.start == chunk.next_byte() => Ok(response),
+            _ => Err(ChunkError::Changed(format!(
+                "Unexpected Content-Range for {}",
+                url
+            ))),
+        },
+        // A full body means If-Range failed: the file changed upstream
+        StatusCode::OK | StatusCode::RANGE_NOT_SATISFIABLE => Err(ChunkError::Changed(format!(
+            "{} changed on the server or no longer supports ranges",
+            url
+        ))),
+        status => Err(ChunkError::Failed(format!("HTTP {}", status))),
+    }
+}
+
+/// Stream a response body into a range of the partial file.
+async fn write_chunk(
+    file: &mut tokio::fs::File,
+    download: &PartialDownload,
+    index: usize,
+    chunk: &mut ChunkState,
+    mut response: HttpResponse,
+    progress: &(dyn Fn(u64) + Sync),
+) -> Result<(), ChunkError> {
+    let io_err = |e: std::io::Error| ChunkError::Failed(e.to_string());
+    file.seek(SeekFrom::Start(chunk.next_byte()))
+        .await
+        .map_err(io_err)?;
+
+    let mut since_checkpoint = 0u64;
+    let mut since_progress = 0u64;
+    let result = loop {
+        match response.chunk().await {
+            Ok(Some(data)) => {
+                // The initial response runs to the end of the file; stop at the range end
+                let room = (chunk.end - chunk.next_byte()).min(data.len() as u64) as usize;
+                file.write_all(&data[..room]).await.map_err(io_err)?;
+                let written = room as u64;
+                chunk.done += written;
+                since_checkpoint += written;
+                since_progress += written;
+
+                if since_progress >= PROGRESS_BYTES || chunk.is_complete() {
+                    progress(since_progress);
+                    since_progress = 0;
+                }
+                if chunk.is_complete() {
+                    break Ok(());
+                }
+                if since_checkpoint >= CHECKPOINT_BYTES {
+                    file.sync_data().await.map_err(io_err)?;
+                    download.checkpoint(index, chunk.done).await;
+                    since_checkpoint = 0;
+                }
+            }
+            Ok(None) if chunk.is_complete() => break Ok(()),
+            Ok(None) => {
+                break Err(ChunkError::Failed(format!(
+                    "Connection closed at byte {}",
+                    chunk.next_byte()
+                )))
+            }
+            Err(e) => break Err(ChunkError::Failed(e.to_string())),
+        }
+    };
+
+    if since_progress > 0 {
+        progress(since_progress);
+    }
+    file.sync_data().await.map_err(io_err)?;
+    download.checkpoint(index, chunk.done).await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_split_into_ranges() {
+        let state = PartialState::new("https://x.gov/a.mp4", None, None, 100 * MIB + 1, 4);
+        assert_eq!(state.chunks.len(), 4);
+        assert_eq!(state.chunks[0].start, 0);
+        assert_eq!(state.chunks[3].end, 100 * MIB + 1);
+        for pair in state.chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+
+        // Small files aren't split below the minimum chunk size
+        let state = PartialState::new("https://x.gov/a.mp4", None, None, 20 * MIB, 8);
+        assert_eq!(state.chunks.len(), 1);
+    }
+
+    #[test]
+    fn test_range_header_resumes_from_done() {
+        let chunk = ChunkState {
+            start: 100,
+            end: 200,
+            done: 40,
+        };
+        assert_eq!(chunk.range_header(), "bytes=140-199");
+        assert!(!chunk.is_complete());
+    }
+
+    #[test]
+    fn test_matches_requires_same_file() {
+        let state = PartialState::new("https://x.gov/a.mp4", Some("\"v1\"".into()), None, 1000, 1);
+        assert!(state.matches(Some("\"v1\""), None, 1000));
+        assert!(!state.matches(Some("\"v2\""), None, 1000));
+        assert!(!state.matches(Some("\"v1\""), None, 999));
+
+        // Without a strong validator a resume can't be trusted
+        let weak = PartialState::new(
+            "https://x.gov/a.mp4",
+            Some("W/\"v1\"".into()),
+            None,
+            1000,
+            1,
+        );
+        assert!(!weak.matches(Some("W/\"v1\""), None, 1000));
+    }
+}
@@ -1,7 +1,7 @@
 //! Download service types and events.
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
@@ -11,9 +11,16 @@ use tokio::sync::mpsc;
 use tracing::warn;
 
 use crate::config::ViaMode;
+use foia::config::{
+    ChecksumConfig, DownloadPipelineConfig, Politeness, SessionConfig, ThrottleConfig,
+    WaybackConfig,
+};
+use foia::metrics;
 use foia::models::{CrawlUrl, Document, DocumentVersion, UrlStatus};
-use foia::privacy::PrivacyConfig;
+use foia::privacy::{PrivacyConfig, ProxyConfig};
 use foia::repository::{DieselCrawlRepository, DieselDocumentRepository};
+use foia::services::quarantine::{self, ErrorPage};
+use foia::storage::fingerprint_matches;
 
 /// Events emitted during download operations.
 /// Fields are populated when events are created, even if consumers don't read all of them.
@@ -46,6 +53,12 @@ pub enum DownloadEvent {
     },
     /// Document unchanged (304 Not Modified)
     Unchanged { worker_id: usize, url: String },
+    /// URL not fetched (e.g. disallowed by robots.txt)
+    Skipped {
+        worker_id: usize,
+        url: String,
+        reason: String,
+    },
     /// Download failed
     Failed {
         worker_id: usize,
@@ -63,7 +76,11 @@ pub struct DownloadResult {
     pub deduplicated: usize,
     pub skipped: usize,
     pub failed: usize,
+    /// Skipped for matching a known-file hash list.
+    pub known_files: usize,
     pub remaining: u64,
+    /// Stopped early on a shutdown request.
+    pub interrupted: bool,
 }
 
 /// Configuration for download service.
@@ -77,6 +94,32 @@ pub struct DownloadConfig {
     pub via: HashMap<String, String>,
     /// Via mode controlling when via mappings are used.
     pub via_mode: ViaMode,
+    /// Per-source proxies, keyed by source ID.
+    pub source_proxies: HashMap<String, ProxyConfig>,
+    /// Per-source login sessions, keyed by source ID.
+    pub source_sessions: HashMap<String, SessionConfig>,
+    /// Global bandwidth cap and schedule, shared by all workers.
+    pub throttle: ThrottleConfig,
+    /// Per-source bandwidth caps and schedules, keyed by source ID.
+    pub source_throttles: HashMap<String, ThrottleConfig>,
+    /// Per-source published checksums to verify downloads against, keyed by source ID.
+    pub source_checksums: HashMap<String, ChecksumConfig>,
+    /// Per-source wayback fallback and Save Page Now settings, keyed by source ID.
+    pub source_wayback: HashMap<String, WaybackConfig>,
+    /// Per-source pacing, concurrency caps and robots.txt, keyed by source ID.
+    pub source_politeness: HashMap<String, Politeness>,
+    /// Responses at least this many bytes are streamed to disk and resumable.
+    pub large_file_threshold: u64,
+    /// Concurrent range requests per large file when the server supports them.
+    pub parallel_chunks: usize,
+    /// Sources whose declared Content-Type is kept even when the content's
+    /// magic bytes disagree.
+    pub trusted_content_type: HashSet<String>,
+    /// Tags every document from a source gets, such as its agency's, keyed
+    /// by source ID.
+    pub source_tags: HashMap<String, Vec<String>>,
+    /// Workers per stage and channel capacity between stages.
+    pub pipeline: DownloadPipelineConfig,
 }
 
 /// Handle a download failure: update status, increment counter, send event.
@@ -102,6 +145,7 @@ pub async fn handle_download_failure(
         );
     }
     failed.fetch_add(1, Ordering::Relaxed);
+    metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "failed"]);
     let _ = event_tx
         .send(DownloadEvent::Failed {
             worker_id,
@@ -111,6 +155,36 @@ pub async fn handle_download_failure(
         .await;
 }
 
+/// Record that a download was an error page rather than the document. No
+/// version is saved; the URL is failed with a backoff so it's retried later.
+pub async fn handle_quarantined(
+    crawl_url: &CrawlUrl,
+    crawl_repo: &Arc<DieselCrawlRepository>,
+    failed: &Arc<AtomicUsize>,
+    event_tx: &mpsc::Sender<DownloadEvent>,
+    worker_id: usize,
+    page: ErrorPage,
+) {
+    let error = page.error_message();
+    let mut quarantined_url = crawl_url.clone();
+    quarantined_url.mark_failed(&error, quarantine::MAX_RETRIES);
+    if let Err(e) = crawl_repo.update_url(&quarantined_url).await {
+        warn!(
+            "Failed to update crawl URL status for {}: {}",
+            crawl_url.url, e
+        );
+    }
+    failed.fetch_add(1, Ordering::Relaxed);
+    metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "quarantined"]);
+    let _ = event_tx
+        .send(DownloadEvent::Failed {
+            worker_id,
+            url: crawl_url.url.clone(),
+            error,
+        })
+        .await;
+}
+
 /// Send a failure event without updating crawl status (for local errors like IO).
 pub async fn send_failure_event(
     url: &str,
@@ -129,7 +203,8 @@ pub async fn send_failure_event(
         .await;
 }
 
-/// Mark a URL as unchanged (304 Not Modified).
+/// Mark a URL as unchanged: 304 Not Modified, or a page whose content
+/// fingerprint matches the current version.
 pub async fn handle_unchanged(
     crawl_url: &CrawlUrl,
     crawl_repo: &Arc<DieselCrawlRepository>,
@@ -147,6 +222,7 @@ pub async fn handle_unchanged(
         );
     }
     skipped.fetch_add(1, Ordering::Relaxed);
+    metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "unchanged"]);
     let _ = event_tx
         .send(DownloadEvent::Unchanged {
             worker_id,
@@ -155,8 +231,59 @@ pub async fn handle_unchanged(
         .await;
 }
 
+/// Handle a URL that won't be fetched: mark it skipped so it leaves the queue.
+pub async fn handle_skipped(
+    crawl_url: &CrawlUrl,
+    crawl_repo: &Arc<DieselCrawlRepository>,
+    skipped: &Arc<AtomicUsize>,
+    event_tx: &mpsc::Sender<DownloadEvent>,
+    worker_id: usize,
+    reason: &str,
+) {
+    let mut skipped_url = crawl_url.clone();
+    skipped_url.mark_skipped(reason);
+    if let Err(e) = crawl_repo.update_url(&skipped_url).await {
+        warn!(
+            "Failed to update crawl URL status for {}: {}",
+            crawl_url.url, e
+        );
+    }
+    skipped.fetch_add(1, Ordering::Relaxed);
+    metrics::DOCUMENTS_DOWNLOADED.inc(&[&crawl_url.source_id, "skipped"]);
+    let _ = event_tx
+        .send(DownloadEvent::Skipped {
+            worker_id,
+            url: crawl_url.url.clone(),
+            reason: reason.to_string(),
+        })
+        .await;
+}
+
+/// Tags for a document downloaded from a queued URL: those recorded when
+/// it was discovered, then those configured for its source.
+pub fn document_tags(
+    crawl_url: &CrawlUrl,
+    source_tags: &HashMap<String, Vec<String>>,
+) -> Vec<String> {
+    let mut tags = crawl_url.discovery_tags();
+    for tag in source_tags.get(&crawl_url.source_id).into_iter().flatten() {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+    tags
+}
+
+/// A document saved by [`save_or_update_document`].
+pub struct SavedDocument {
+    pub id: String,
+    /// The document didn't exist before.
+    pub created: bool,
+    /// The version was new to the document, rather than one it already had.
+    pub new_version: bool,
+}
+
 /// Save a document version, either adding to existing document or creating new.
-/// Returns whether this created a new document.
 #[allow(clippy::too_many_arguments)]
 pub async fn save_or_update_document(
     doc_repo: &Arc<DieselDocumentRepository>,
@@ -166,17 +293,23 @@ pub async fn save_or_update_document(
     version: DocumentVersion,
     metadata: serde_json::Value,
     discovery_method: &str,
-) -> Result<bool, foia::repository::DieselError> {
-    let existing = doc_repo.get_by_url(url).await?.into_iter().next();
-    let new_document = existing.is_none();
+    tags: &[String],
+) -> Result<SavedDocument, foia::repository::DieselError> {
+    let existing = doc_repo.find_by_source_url(source_id, url).await?;
 
-    if let Some(mut doc) = existing {
-        if doc.add_version(version) {
+    let saved = if let Some(mut doc) = existing {
+        let new_version = doc.add_version(version);
+        if new_version {
             doc_repo.save_with_versions(&doc).await?;
         }
+        SavedDocument {
+            id: doc.id,
+            created: false,
+            new_version,
+        }
     } else {
         let doc = Document::with_discovery_method(
-            uuid::Uuid::new_v4().to_string(),
+            Document::stable_id(source_id, url),
             source_id.to_string(),
             title,
             url.to_string(),
@@ -185,7 +318,30 @@ pub async fn save_or_update_document(
             discovery_method.to_string(),
         );
         doc_repo.save_with_versions(&doc).await?;
-    }
+        SavedDocument {
+            id: doc.id,
+            created: true,
+            new_version: true,
+        }
+    };
+    doc_repo.add_tags(&saved.id, tags).await?;
+
+    Ok(saved)
+}
 
-    Ok(new_document)
+/// Whether a page fetched without ETag or Last-Modified matches the current
+/// version of its document. Such pages can't be fetched conditionally, and
+/// their bytes differ on every fetch.
+pub async fn fingerprint_unchanged(
+    doc_repo: &Arc<DieselDocumentRepository>,
+    documents_dir: &Path,
+    source_id: &str,
+    url: &str,
+    content: &[u8],
+    mime_type: &str,
+) -> bool {
+    match doc_repo.find_by_source_url(source_id, url).await {
+        Ok(Some(doc)) => fingerprint_matches(&doc, documents_dir, content, mime_type).await,
+        _ => false,
+    }
 }
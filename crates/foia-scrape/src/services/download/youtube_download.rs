@@ -27,6 +27,7 @@ pub async fn download_youtube_video(
     downloaded: &Arc<AtomicUsize>,
     failed: &Arc<AtomicUsize>,
     proxy_url: Option<&str>,
+    tags: &[String],
 ) -> bool {
     debug!("Attempting YouTube download: {}", url);
 
@@ -101,10 +102,11 @@ pub async fn download_youtube_video(
                 version,
                 metadata,
                 "youtube",
+                tags,
             )
             .await
             {
-                Ok(new_doc) => new_doc,
+                Ok(saved) => saved.created,
                 Err(e) => {
                     handle_download_failure(
                         crawl_url,
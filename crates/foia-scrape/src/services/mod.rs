@@ -1,4 +1,5 @@
 //! Scrape-related services.
 
 pub mod download;
+pub mod wayback;
 pub mod youtube;
@@ -0,0 +1,257 @@
+//! Wayback Machine fallback for dead URLs and Save Page Now submission.
+//!
+//! When a document URL returns 404 or 410, the CDX index is asked for the
+//! most recent good capture. The capture is recorded in `archive_snapshots`
+//! as a fallback offer; ingesting it queues the raw archive URL, and the
+//! download service saves the result as a new version of the original
+//! document with `archive_snapshot_id` set.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use tracing::{debug, info};
+
+use crate::archive::{ArchiveError, ArchiveSource, SnapshotInfo, WaybackSource};
+use crate::HttpClient;
+use foia::models::{ArchiveSnapshot, CrawlUrl, DiscoveryMethod, FallbackState, NewArchiveSnapshot};
+use foia::privacy::PrivacyConfig;
+use foia::repository::DieselCrawlRepository;
+
+/// Save Page Now endpoint; the URL to capture is appended.
+pub const SAVE_PAGE_NOW_URL: &str = "https://web.archive.org/save/";
+
+/// Whether a response status means the document is gone rather than failing.
+pub fn is_gone(status: u16) -> bool {
+    status == 404 || status == 410
+}
+
+/// Pick the most recent capture that was archived with a 200 response.
+pub fn best_capture(snapshots: Vec<SnapshotInfo>) -> Option<SnapshotInfo> {
+    snapshots
+        .into_iter()
+        .filter(|s| s.http_status == Some(200))
+        .max_by_key(|s| s.captured_at)
+}
+
+/// Archive snapshot and original URL carried by a queued fallback capture.
+pub fn fallback_target(crawl_url: &CrawlUrl) -> Option<(i32, String)> {
+    if crawl_url.discovery_method != DiscoveryMethod::WaybackMachine {
+        return None;
+    }
+    let snapshot_id = crawl_url
+        .discovery_context
+        .get("archive_snapshot_id")?
+        .as_i64()?;
+    let original_url = crawl_url.discovery_context.get("original_url")?.as_str()?;
+    Some((snapshot_id as i32, original_url.to_string()))
+}
+
+/// Look up a wayback capture for a dead URL and record it as a fallback.
+///
+/// Returns the snapshot ID when a new capture was recorded. With
+/// `auto_ingest` the capture is queued for download straight away.
+pub async fn offer_fallback(
+    crawl_repo: &DieselCrawlRepository,
+    crawl_url: &CrawlUrl,
+    privacy: &PrivacyConfig,
+    auto_ingest: bool,
+) -> Result<Option<i32>> {
+    let wayback = WaybackSource::with_privacy(privacy.clone());
+    let snapshots = match wayback.list_snapshots(&crawl_url.url).await {
+        Ok(s) => s,
+        Err(ArchiveError::NotFound) => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let Some(capture) = best_capture(snapshots) else {
+        debug!("No wayback capture for {}", crawl_url.url);
+        return Ok(None);
+    };
+
+    let mut snapshot = NewArchiveSnapshot::new(
+        capture.service.as_str(),
+        &crawl_url.url,
+        &capture.archive_url,
+        capture.captured_at,
+    )
+    .as_fallback(&crawl_url.source_id);
+    if let Some(status) = capture.http_status {
+        snapshot = snapshot.with_http_status(status as i32);
+    }
+    if let Some(mimetype) = capture.mimetype {
+        snapshot = snapshot.with_mimetype(mimetype);
+    }
+    if let Some(length) = capture.content_length {
+        snapshot = snapshot.with_content_length(length);
+    }
+    if let Some(digest) = capture.digest {
+        snapshot = snapshot.with_digest(digest);
+    }
+
+    let (id, created) = crawl_repo.insert_archive_snapshot(&snapshot).await?;
+    if !created {
+        return Ok(None);
+    }
+    info!(
+        "Wayback capture from {} found for {}",
+        capture.captured_at.format("%Y-%m-%d"),
+        crawl_url.url
+    );
+
+    if auto_ingest {
+        if let Some(snapshot) = crawl_repo.get_archive_snapshot(id).await? {
+            ingest_fallback(crawl_repo, &snapshot).await?;
+        }
+    }
+    Ok(Some(id))
+}
+
+/// Queue a fallback capture for download as a new version of its original URL.
+///
+/// Returns false if the snapshot is not a fallback offer.
+pub async fn ingest_fallback(
+    crawl_repo: &DieselCrawlRepository,
+    snapshot: &ArchiveSnapshot,
+) -> Result<bool> {
+    let Some(source_id) = snapshot.fallback_source() else {
+        return Ok(false);
+    };
+
+    let mut crawl_url = CrawlUrl::new(
+        snapshot.archive_url.clone(),
+        source_id,
+        DiscoveryMethod::WaybackMachine,
+        Some(snapshot.original_url.clone()),
+        0,
+    );
+    crawl_url
+        .discovery_context
+        .insert("archive_snapshot_id".to_string(), snapshot.id.into());
+    crawl_url.discovery_context.insert(
+        "original_url".to_string(),
+        snapshot.original_url.clone().into(),
+    );
+
+    // A capture queued before (then dismissed, say) is requeued in place
+    if !crawl_repo.add_url(&crawl_url).await? {
+        crawl_repo
+            .retry_url(&crawl_url.source_id, &crawl_url.url)
+            .await?;
+    }
+    crawl_repo
+        .set_fallback_state(snapshot.id, FallbackState::Queued)
+        .await?;
+    Ok(true)
+}
+
+/// Client for the Internet Archive's Save Page Now service.
+pub struct SavePageNow {
+    client: HttpClient,
+    authorization: Option<String>,
+}
+
+impl SavePageNow {
+    /// Create a client. Anonymous captures are spaced further apart, as the
+    /// service rate limits them much harder than authenticated ones.
+    pub fn new(privacy: &PrivacyConfig, authorization: Option<String>) -> Result<Self, String> {
+        let delay = if authorization.is_some() {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(10)
+        };
+        let client = HttpClient::builder("save_page_now", Duration::from_secs(120), delay)
+            .privacy(privacy)
+            .build()?;
+        Ok(Self {
+            client,
+            authorization,
+        })
+    }
+
+    /// Ask the Wayback Machine to capture `url`.
+    pub async fn submit(&self, url: &str) -> Result<(), String> {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), "application/json".to_string());
+        if let Some(auth) = &self.authorization {
+            headers.insert("Authorization".to_string(), auth.clone());
+        }
+
+        let response = self
+            .client
+            .get_with_headers(&format!("{}{}", SAVE_PAGE_NOW_URL, url), headers)
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.is_success() {
+            Ok(())
+        } else {
+            Err(format!("Save Page Now returned HTTP {}", response.status))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use foia::models::ArchiveService;
+
+    fn capture(day: u32, status: Option<u16>) -> SnapshotInfo {
+        SnapshotInfo {
+            service: ArchiveService::Wayback,
+            original_url: "https://agency.gov/report.pdf".to_string(),
+            archive_url: format!("https://web.archive.org/web/202401{:02}000000id_/x", day),
+            captured_at: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            http_status: status,
+            mimetype: None,
+            content_length: None,
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn test_best_capture_prefers_latest_ok() {
+        let best = best_capture(vec![
+            capture(1, Some(200)),
+            capture(3, Some(200)),
+            capture(5, Some(404)),
+            capture(7, None),
+        ])
+        .unwrap();
+        assert_eq!(best.captured_at.format("%d").to_string(), "03");
+        assert!(best_capture(vec![capture(5, Some(404))]).is_none());
+    }
+
+    #[test]
+    fn test_fallback_target() {
+        let mut url = CrawlUrl::new(
+            "https://web.archive.org/web/2024id_/https://agency.gov/a.pdf".to_string(),
+            "agency".to_string(),
+            DiscoveryMethod::WaybackMachine,
+            None,
+            0,
+        );
+        assert_eq!(fallback_target(&url), None);
+
+        url.discovery_context
+            .insert("archive_snapshot_id".to_string(), 7.into());
+        url.discovery_context.insert(
+            "original_url".to_string(),
+            "https://agency.gov/a.pdf".into(),
+        );
+        assert_eq!(
+            fallback_target(&url),
+            Some((7, "https://agency.gov/a.pdf".to_string()))
+        );
+
+        url.discovery_method = DiscoveryMethod::Seed;
+        assert_eq!(fallback_target(&url), None);
+    }
+
+    #[test]
+    fn test_is_gone() {
+        assert!(is_gone(404));
+        assert!(is_gone(410));
+        assert!(!is_gone(500));
+        assert!(!is_gone(403));
+    }
+}
@@ -0,0 +1,153 @@
+//! Access logging for served documents.
+//!
+//! Document page views and file downloads are counted per document and day
+//! in `document_access`. Nothing identifying a reader is kept: no address,
+//! no user agent, and with `server.access_log = "referrers"` only the host
+//! name of the referring site. Requests from user agents that call
+//! themselves bots are not counted.
+
+use std::sync::Arc;
+
+use axum::http::{header, HeaderMap};
+use chrono::{Duration, Utc};
+
+use foia::config::AccessLogMode;
+use foia::repository::diesel_access_log::AccessKind;
+use foia::repository::DieselAccessLogRepository;
+
+use super::AppState;
+
+/// User agent fragments of crawlers and other automated clients.
+const BOT_MARKERS: [&str; 5] = ["bot", "crawler", "spider", "slurp", "headless"];
+
+/// Whether a request with `headers` would be counted.
+pub fn is_counted(state: &AppState, headers: &HeaderMap) -> bool {
+    state.access_mode != AccessLogMode::Off && !is_bot(headers)
+}
+
+/// Count an access to `document_id`, in the background so the response
+/// isn't held up.
+pub fn record(state: &AppState, document_id: &str, kind: AccessKind, headers: &HeaderMap) {
+    if !is_counted(state, headers) {
+        return;
+    }
+    let referrer = match state.access_mode {
+        AccessLogMode::Referrers => referrer_host(headers),
+        _ => None,
+    };
+    let repo = state.access_log.clone();
+    let document_id = document_id.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = repo
+            .record(&document_id, kind, referrer.as_deref(), Utc::now())
+            .await
+        {
+            tracing::debug!("Failed to record access to {}: {}", document_id, e);
+        }
+    });
+}
+
+/// Delete counts older than `days` days, in the background.
+pub fn spawn_prune(repo: Arc<DieselAccessLogRepository>, days: u32) {
+    tokio::spawn(async move {
+        let before = Utc::now() - Duration::days(i64::from(days));
+        match repo.prune(before).await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Pruned {} document access counts", n),
+            Err(e) => tracing::warn!("Failed to prune document access counts: {}", e),
+        }
+    });
+}
+
+/// Whether the request comes from a crawler, or from no browser at all.
+fn is_bot(headers: &HeaderMap) -> bool {
+    let Some(agent) = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return true;
+    };
+    let agent = agent.to_ascii_lowercase();
+    BOT_MARKERS.iter().any(|marker| agent.contains(marker))
+}
+
+/// Host name of the referring site, or None for links within this site and
+/// requests without a usable `Referer`.
+fn referrer_host(headers: &HeaderMap) -> Option<String> {
+    let referrer = headers.get(header::REFERER)?.to_str().ok()?;
+    let host = url_host(referrer)?;
+    let own = headers
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok())
+        .map(|h| strip_port(h).to_ascii_lowercase());
+    (own.as_deref() != Some(host.as_str())).then_some(host)
+}
+
+/// Lowercased host of an absolute http(s) URL.
+fn url_host(url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = strip_port(authority.rsplit('@').next()?);
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn strip_port(authority: &str) -> &str {
+    if authority.starts_with('[') {
+        // IPv6 literal
+        return authority
+            .split_once(']')
+            .map_or(authority, |(host, _)| &host[1..]);
+    }
+    authority.split(':').next().unwrap_or(authority)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_referrer_host() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::HOST,
+            HeaderValue::from_static("docs.example.org:8080"),
+        );
+        assert_eq!(referrer_host(&headers), None);
+
+        headers.insert(
+            header::REFERER,
+            HeaderValue::from_static("https://user@News.Example.com:443/story?id=1"),
+        );
+        assert_eq!(referrer_host(&headers).as_deref(), Some("news.example.com"));
+
+        headers.insert(
+            header::REFERER,
+            HeaderValue::from_static("http://docs.example.org:8080/browse"),
+        );
+        assert_eq!(referrer_host(&headers), None);
+
+        headers.insert(header::REFERER, HeaderValue::from_static("android-app://x"));
+        assert_eq!(referrer_host(&headers), None);
+        assert_eq!(url_host("http://[::1]:3030/").as_deref(), Some("::1"));
+    }
+
+    #[test]
+    fn test_is_bot() {
+        let mut headers = HeaderMap::new();
+        assert!(is_bot(&headers));
+        headers.insert(
+            header::USER_AGENT,
+            HeaderValue::from_static("Mozilla/5.0 (compatible; Googlebot/2.1)"),
+        );
+        assert!(is_bot(&headers));
+        headers.insert(
+            header::USER_AGENT,
+            HeaderValue::from_static("Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0"),
+        );
+        assert!(!is_bot(&headers));
+    }
+}
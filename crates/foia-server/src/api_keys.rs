@@ -0,0 +1,226 @@
+//! API keys for the JSON API.
+//!
+//! A request under `/api/` may carry a key, as an `X-API-Key` header or
+//! `Authorization: Bearer <key>`. A keyed request counts against that key's
+//! per-minute limit, and is recorded in `api_usage`; unknown and revoked
+//! keys are refused. Requests without a key share a per-address limit.
+//! With `server.require_api_keys`, which is always on for a server
+//! reachable from other machines, requests that change state (anything but
+//! GET, HEAD and OPTIONS) must carry a key, except readers' reports of a
+//! document. Managing keys under `/api/keys` takes an admin key. Keys are
+//! issued with `foia api-key create` or from `/api-keys`.
+
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+
+use foia::config::ServerConfig;
+
+use super::flags::FLAGS_TAIL;
+use super::handlers::api_types::ApiResponse;
+use super::public::{client_addr, ClientLimiter};
+use super::AppState;
+
+/// Header carrying an API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// API path for issuing, listing and revoking keys.
+const KEYS_PATH: &str = "/api/keys";
+
+/// Rate limits and the key requirement for one workspace's API.
+pub struct ApiKeyGate {
+    limiter: ClientLimiter<i32>,
+    /// Requests per minute for keys without a limit of their own.
+    default_limit: u32,
+    /// Requests without a key, per client address.
+    unkeyed: ClientLimiter<IpAddr>,
+    unkeyed_limit: u32,
+    /// State-changing requests need a key.
+    required: bool,
+}
+
+impl ApiKeyGate {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            limiter: ClientLimiter::new(config.api_rate_limit()),
+            default_limit: config.api_rate_limit(),
+            unkeyed: ClientLimiter::new(config.api_unkeyed_rate_limit()),
+            unkeyed_limit: config.api_unkeyed_rate_limit(),
+            required: config.requires_api_keys(),
+        }
+    }
+
+    /// Whether state-changing requests need a key.
+    pub fn required(&self) -> bool {
+        self.required
+    }
+
+    /// Requests per minute for keys without a limit of their own.
+    pub fn default_limit(&self) -> u32 {
+        self.default_limit
+    }
+}
+
+/// The API key a request carries, if any.
+fn request_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(key) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(key.trim()).filter(|k| !k.is_empty());
+    }
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+}
+
+/// Whether a state-changing request is open to readers without a key:
+/// reporting a document.
+fn is_open_write(path: &str) -> bool {
+    path.starts_with("/api/documents/") && path.ends_with(FLAGS_TAIL)
+}
+
+/// Whether a request manages keys, which only admin keys may do.
+fn is_key_management(path: &str) -> bool {
+    path == KEYS_PATH
+        || path
+            .strip_prefix(KEYS_PATH)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 429 response for `limit` requests per minute, retrying after `wait`.
+fn too_many_requests(limit: u32, wait: Duration) -> Response {
+    let mut response = ApiResponse::error(
+        StatusCode::TOO_MANY_REQUESTS,
+        format!("Rate limit of {} requests per minute exceeded", limit),
+    )
+    .into_response();
+    if let Ok(value) = wait.as_secs().max(1).to_string().parse() {
+        response.headers_mut().insert(header::RETRY_AFTER, value);
+    }
+    response
+}
+
+/// Check the API key of requests under `/api/` and apply its rate limit.
+pub async fn check_api_key(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if !path.starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let writes = !matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+    let manages_keys = is_key_management(path);
+    let Some(key) = request_key(request.headers()) else {
+        if manages_keys {
+            return ApiResponse::error(StatusCode::UNAUTHORIZED, "An admin API key is required")
+                .into_response();
+        }
+        if writes && state.api_gate.required() && !is_open_write(path) {
+            return ApiResponse::error(StatusCode::UNAUTHORIZED, "An API key is required")
+                .into_response();
+        }
+        let gate = &state.api_gate;
+        return match gate.unkeyed.check_limit(
+            client_addr(&request),
+            gate.unkeyed_limit,
+            Instant::now(),
+        ) {
+            None => next.run(request).await,
+            Some(wait) => too_many_requests(gate.unkeyed_limit, wait),
+        };
+    };
+
+    let record = match state.api_keys.authenticate(key).await {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            return ApiResponse::error(StatusCode::UNAUTHORIZED, "Invalid or revoked API key")
+                .into_response()
+        }
+        Err(e) => {
+            tracing::warn!("Failed to check API key: {}", e);
+            return ApiResponse::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to check API key",
+            )
+            .into_response();
+        }
+    };
+
+    if manages_keys && !record.is_admin() {
+        return ApiResponse::error(StatusCode::FORBIDDEN, "Managing keys needs an admin key")
+            .into_response();
+    }
+
+    let limit = record
+        .rate_limit
+        .map_or(state.api_gate.default_limit, |limit| limit.max(1) as u32);
+    let wait = state
+        .api_gate
+        .limiter
+        .check_limit(record.id, limit, Instant::now());
+    if let Err(e) = state
+        .api_keys
+        .record_usage(record.id, wait.is_some(), Utc::now())
+        .await
+    {
+        tracing::warn!(
+            "Failed to record API usage for key '{}': {}",
+            record.name,
+            e
+        );
+    }
+
+    match wait {
+        None => next.run(request).await,
+        Some(wait) => too_many_requests(limit, wait),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_request_key() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_key(&headers), None);
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer foia_abc"),
+        );
+        assert_eq!(request_key(&headers), Some("foia_abc"));
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static(" foia_def "));
+        assert_eq!(request_key(&headers), Some("foia_def"));
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static(""));
+        assert_eq!(request_key(&headers), None);
+    }
+
+    #[test]
+    fn test_open_writes() {
+        assert!(is_open_write("/api/documents/abc/flags"));
+        assert!(!is_open_write("/api/documents/abc/reprocess"));
+        assert!(!is_open_write("/api/flags"));
+    }
+
+    #[test]
+    fn test_key_management_paths() {
+        assert!(is_key_management("/api/keys"));
+        assert!(is_key_management("/api/keys/newsroom"));
+        assert!(!is_key_management("/api/keysets"));
+        assert!(!is_key_management("/api-keys"));
+    }
+}
@@ -3,5 +3,11 @@
 /// Stylesheet for the web interface.
 pub const CSS: &str = include_str!("styles.css");
 
+/// Stylesheet for public mode, hiding links to pages that are not served.
+pub const PUBLIC_CSS: &str = concat!(
+    include_str!("styles.css"),
+    "\n.internal {\n    display: none !important;\n}\n"
+);
+
 /// JavaScript for timeline and page interactions.
 pub const JS: &str = include_str!("scripts.js");
@@ -0,0 +1,156 @@
+//! HTTP caching and compression.
+//!
+//! Pages and API responses are rendered in memory, so their ETag is a hash
+//! of the body: a client revalidating an unchanged listing gets a 304
+//! instead of the whole page again. Files, thumbnails and page images set
+//! their own content-hash ETags and are passed through untouched.
+//! Responses are gzip or brotli compressed when the client accepts it.
+
+use axum::{
+    body::{Body, HttpBody},
+    extract::Request,
+    http::{header, Extensions, HeaderMap, HeaderValue, Method, StatusCode, Version},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower_http::compression::{
+    predicate::{DefaultPredicate, Predicate},
+    CompressionLayer,
+};
+
+use super::handlers::header_matches_etag;
+
+/// Largest body hashed for an ETag; bigger responses are streamed as is.
+const MAX_ETAG_BODY: u64 = 8 * 1024 * 1024;
+
+/// Cache policy for pages and JSON: keep a copy, but revalidate each use.
+const REVALIDATE: &str = "no-cache";
+
+/// Cache policy for the bundled CSS and JavaScript.
+const ASSET_CACHE: &str = "public, max-age=3600";
+
+/// Add ETags and Cache-Control to in-memory responses.
+pub fn conditional(app: Router) -> Router {
+    app.layer(middleware::from_fn(etag_responses))
+}
+
+/// Compress responses. Must wrap every layer that reads response bodies.
+pub fn compress(app: Router) -> Router {
+    let predicate = DefaultPredicate::new().and(not_ranged);
+    app.layer(CompressionLayer::new().compress_when(predicate))
+}
+
+/// Ranged file downloads must keep their byte offsets, and stored files are
+/// usually compressed already.
+fn not_ranged(_: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions) -> bool {
+    !headers.contains_key(header::ACCEPT_RANGES)
+}
+
+async fn etag_responses(request: Request, next: Next) -> Response {
+    let cacheable_method = matches!(*request.method(), Method::GET | Method::HEAD);
+    let is_asset = request.uri().path().starts_with("/static/");
+    let if_none_match = request.headers().get(header::IF_NONE_MATCH).cloned();
+
+    let response = next.run(request).await;
+    if !cacheable_method
+        || response.status() != StatusCode::OK
+        || response.headers().contains_key(header::ETAG)
+        || !is_generated(response.headers())
+        || !response
+            .body()
+            .size_hint()
+            .exact()
+            .is_some_and(|len| len <= MAX_ETAG_BODY)
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to read response body for ETag: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let etag = body_etag(&bytes);
+    let weak = format!("W/{}", etag);
+
+    if !parts.headers.contains_key(header::CACHE_CONTROL) {
+        let policy = if is_asset { ASSET_CACHE } else { REVALIDATE };
+        parts
+            .headers
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static(policy));
+    }
+    if let Ok(value) = HeaderValue::from_str(&weak) {
+        parts.headers.insert(header::ETAG, value);
+    }
+
+    if header_matches_etag(if_none_match.as_ref(), &etag) {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        parts.headers.remove(header::CONTENT_TYPE);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Whether the response is one of ours rendered in memory, rather than a
+/// stored file.
+fn is_generated(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| {
+            ct.starts_with("text/html")
+                || ct.starts_with("application/json")
+                || ct.starts_with("text/css")
+                || ct.starts_with("application/javascript")
+        })
+        && !headers.contains_key(header::CONTENT_DISPOSITION)
+}
+
+/// ETag for a rendered body. Sent weak (`W/`), because compression changes
+/// the bytes on the wire but not the meaning.
+fn body_etag(body: &[u8]) -> String {
+    let hash = blake3::hash(body).to_hex();
+    format!("\"{}\"", &hash[..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_etag() {
+        let etag = body_etag(b"<html></html>");
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert_eq!(etag.len(), 34);
+        assert_eq!(etag, body_etag(b"<html></html>"));
+        assert_ne!(etag, body_etag(b"<html> </html>"));
+    }
+
+    #[test]
+    fn test_is_generated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        assert!(is_generated(&headers));
+
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_static("inline"),
+        );
+        assert!(!is_generated(&headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/pdf"),
+        );
+        assert!(!is_generated(&headers));
+    }
+}
@@ -0,0 +1,81 @@
+//! Withholding flagged documents from the public archive.
+//!
+//! With `server.withhold_flagged` in public mode, a document with an open
+//! flag stops being served: its pages, files, citations and API responses
+//! return 404, and its pages drop out of search results, until a curator
+//! resolves or dismisses the flag. Readers can still flag it again.
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use super::redirects::split_document_path;
+use super::AppState;
+
+/// Path tail for reporting a document, left open while it is withheld.
+pub const FLAGS_TAIL: &str = "/flags";
+
+/// Message for requests refused while a document is withheld.
+const WITHHELD_MESSAGE: &str = "This document is withheld pending review.";
+
+/// Whether `document_id` is withheld. Errors are logged and treated as
+/// withheld, so a database hiccup can't publish a flagged document.
+pub async fn is_withheld(state: &AppState, document_id: &str) -> bool {
+    if !state.withhold_flagged {
+        return false;
+    }
+    match state.flags.has_open(document_id).await {
+        Ok(open) => open,
+        Err(e) => {
+            tracing::warn!("Failed to check flags on {}: {}", document_id, e);
+            true
+        }
+    }
+}
+
+/// Document ID a path is about, for document and citation paths.
+fn path_document_id(path: &str) -> Option<&str> {
+    if let Some((_, id, tail)) = split_document_path(path) {
+        return (tail != FLAGS_TAIL).then_some(id);
+    }
+    let rest = path.strip_prefix("/cite/")?;
+    let id = rest.split('/').next()?;
+    (!id.is_empty()).then_some(id)
+}
+
+/// Answer requests for a withheld document with 404.
+pub async fn withhold_flagged(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.withhold_flagged {
+        return next.run(request).await;
+    }
+    let withheld = match path_document_id(request.uri().path()) {
+        Some(id) => is_withheld(&state, id).await,
+        None => false,
+    };
+    if withheld {
+        return (StatusCode::NOT_FOUND, WITHHELD_MESSAGE).into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_document_id() {
+        assert_eq!(path_document_id("/documents/abc/pages/2"), Some("abc"));
+        assert_eq!(path_document_id("/api/documents/abc"), Some("abc"));
+        assert_eq!(path_document_id("/thumbnails/abc"), Some("abc"));
+        assert_eq!(path_document_id("/cite/abc/0123abcd/4"), Some("abc"));
+        assert_eq!(path_document_id("/api/documents/abc/flags"), None);
+        assert_eq!(path_document_id("/browse"), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Agency registry API.
+//!
+//! Agencies group sources by who published their documents. Each agency's
+//! `tag` filters the document listing to it (`/api/documents?tags=agency:fbi`).
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+use foia::models::{Agency, AgencyLevel};
+
+/// Query parameters for listing agencies.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AgenciesQuery {
+    /// Only agencies at this level (federal, state, county, municipal, tribal, other)
+    pub level: Option<String>,
+}
+
+/// An agency with the sources linked to it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AgencyResponse {
+    pub id: String,
+    pub name: String,
+    pub abbreviation: Option<String>,
+    pub jurisdiction: String,
+    pub level: String,
+    /// The agency this one is a component of.
+    pub parent_id: Option<String>,
+    pub foia_email: Option<String>,
+    pub foia_phone: Option<String>,
+    pub foia_address: Option<String>,
+    /// Request portals and reading rooms.
+    pub portal_urls: Vec<String>,
+    pub website: Option<String>,
+    /// Tag carried by the agency's documents.
+    pub tag: String,
+    pub source_ids: Vec<String>,
+    /// IDs of the agency's components; only on single-agency responses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component_ids: Option<Vec<String>>,
+}
+
+impl AgencyResponse {
+    fn new(agency: Agency, source_ids: Vec<String>) -> Self {
+        Self {
+            tag: agency.tag(),
+            id: agency.id,
+            name: agency.name,
+            abbreviation: agency.abbreviation,
+            jurisdiction: agency.jurisdiction,
+            level: agency.level.as_str().to_string(),
+            parent_id: agency.parent_id,
+            foia_email: agency.foia_email,
+            foia_phone: agency.foia_phone,
+            foia_address: agency.foia_address,
+            portal_urls: agency.portal_urls,
+            website: agency.website,
+            source_ids,
+            component_ids: None,
+        }
+    }
+}
+
+/// List agencies in the registry.
+#[utoipa::path(
+    get,
+    path = "/api/agencies",
+    params(AgenciesQuery),
+    responses(
+        (status = 200, description = "Agencies by name", body = Vec<AgencyResponse>),
+        (status = 400, description = "Unknown level")
+    ),
+    tag = "Agencies"
+)]
+pub async fn api_list_agencies(
+    State(state): State<AppState>,
+    Query(params): Query<AgenciesQuery>,
+) -> impl IntoResponse {
+    let level = match params.level.as_deref().filter(|l| !l.is_empty()) {
+        Some(level) => match AgencyLevel::from_str(level) {
+            Some(level) => Some(level),
+            None => return bad_request("Unknown agency level").into_response(),
+        },
+        None => None,
+    };
+    let agencies = match state.agencies.list(level).await {
+        Ok(a) => a,
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let mut out = Vec::with_capacity(agencies.len());
+    for agency in agencies {
+        let source_ids = match state.agencies.source_ids(&agency.id).await {
+            Ok(ids) => ids,
+            Err(e) => return internal_error(e).into_response(),
+        };
+        out.push(AgencyResponse::new(agency, source_ids));
+    }
+    ApiResponse::ok(out).into_response()
+}
+
+/// Get an agency with its sources and components.
+#[utoipa::path(
+    get,
+    path = "/api/agencies/{id}",
+    params(("id" = String, Path, description = "Agency ID")),
+    responses(
+        (status = 200, description = "Agency", body = AgencyResponse),
+        (status = 404, description = "Agency not found")
+    ),
+    tag = "Agencies"
+)]
+pub async fn api_get_agency(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let agency = match state.agencies.get(&id).await {
+        Ok(Some(a)) => a,
+        Ok(None) => return not_found("Agency not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let source_ids = match state.agencies.source_ids(&id).await {
+        Ok(ids) => ids,
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let components = match state.agencies.children(&id).await {
+        Ok(c) => c,
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let mut response = AgencyResponse::new(agency, source_ids);
+    response.component_ids = Some(components.into_iter().map(|c| c.id).collect());
+    ApiResponse::ok(response).into_response()
+}
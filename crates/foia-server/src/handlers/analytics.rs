@@ -0,0 +1,175 @@
+//! Access analytics page and API: most viewed documents and referring sites.
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::template_structs::{
+    AnalyticsDocumentRow, AnalyticsReferrerRow, AnalyticsTemplate, ErrorTemplate,
+};
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::internal_error;
+
+/// Days covered when no period is given.
+const DEFAULT_DAYS: u32 = 30;
+
+/// Documents and referring sites listed.
+const DEFAULT_LIMIT: usize = 50;
+
+/// Period options offered on the page.
+const PERIODS: [u32; 4] = [1, 7, 30, 90];
+
+/// Query for access analytics.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AnalyticsQuery {
+    /// Days to cover, today included (default 30).
+    pub days: Option<u32>,
+    /// Documents and referring sites to list (default 50, at most 500).
+    pub limit: Option<usize>,
+}
+
+/// Views and downloads of one document.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DocumentAccessResponse {
+    pub document_id: String,
+    pub title: String,
+    pub source_id: String,
+    pub views: u64,
+    pub downloads: u64,
+}
+
+/// Visits from one referring site.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReferrerResponse {
+    /// Host name of the referring site.
+    pub referrer: String,
+    pub count: u64,
+}
+
+/// Document access over a period.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnalyticsResponse {
+    pub days: u32,
+    /// What the server records: `off`, `counts` or `referrers`.
+    pub access_log: String,
+    pub views: u64,
+    pub downloads: u64,
+    /// Most accessed documents, by views plus downloads.
+    pub documents: Vec<DocumentAccessResponse>,
+    /// Referring sites, by visits. Empty unless referrers are recorded.
+    pub referrers: Vec<ReferrerResponse>,
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+async fn load_analytics(
+    state: &AppState,
+    query: &AnalyticsQuery,
+) -> anyhow::Result<AnalyticsResponse> {
+    let days = query.days.unwrap_or(DEFAULT_DAYS).clamp(1, 3650);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 500);
+    let since = Utc::now() - Duration::days(i64::from(days) - 1);
+
+    let totals = state.access_log.totals(since).await?;
+    let documents = state.access_log.most_accessed(since, limit).await?;
+    let referrers = state.access_log.top_referrers(since, limit).await?;
+
+    Ok(AnalyticsResponse {
+        days,
+        access_log: state.access_mode.as_str().to_string(),
+        views: totals.views,
+        downloads: totals.downloads,
+        documents: documents
+            .into_iter()
+            .map(|d| DocumentAccessResponse {
+                document_id: d.document_id,
+                title: d.title,
+                source_id: d.source_id,
+                views: d.views,
+                downloads: d.downloads,
+            })
+            .collect(),
+        referrers: referrers
+            .into_iter()
+            .map(|r| ReferrerResponse {
+                referrer: r.referrer,
+                count: r.count,
+            })
+            .collect(),
+    })
+}
+
+/// Most viewed documents and referring sites over a period.
+pub async fn analytics_page(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> impl IntoResponse {
+    let analytics = match load_analytics(&state, &query).await {
+        Ok(a) => a,
+        Err(e) => return render_error(&format!("Failed to load analytics: {}", e)),
+    };
+    let template = AnalyticsTemplate {
+        title: "Analytics",
+        days: analytics.days,
+        periods: PERIODS.to_vec(),
+        access_log: analytics.access_log,
+        views: analytics.views,
+        downloads: analytics.downloads,
+        documents: analytics
+            .documents
+            .into_iter()
+            .map(|d| AnalyticsDocumentRow {
+                document_id: d.document_id,
+                title: d.title,
+                source_id: d.source_id,
+                views: d.views,
+                downloads: d.downloads,
+            })
+            .collect(),
+        referrers: analytics
+            .referrers
+            .into_iter()
+            .map(|r| AnalyticsReferrerRow {
+                referrer: r.referrer,
+                count: r.count,
+            })
+            .collect(),
+    };
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// Document views, downloads and referring sites over a period.
+#[utoipa::path(
+    get,
+    path = "/api/analytics",
+    params(AnalyticsQuery),
+    responses(
+        (status = 200, description = "Access counts for the period", body = AnalyticsResponse)
+    ),
+    tag = "Analytics"
+)]
+pub async fn api_analytics(
+    State(state): State<AppState>,
+    Query(query): Query<AnalyticsQuery>,
+) -> impl IntoResponse {
+    match load_analytics(&state, &query).await {
+        Ok(analytics) => ApiResponse::ok(analytics).into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
@@ -12,8 +12,9 @@ use super::super::AppState;
 use super::api_types::{
     AnnotationListStats, AnnotationsListResponse, ApiResponse, UpdateAnnotationResponse,
 };
-use super::helpers::{internal_error, not_found};
+use super::helpers::{internal_error, not_found, parse_cursor_param};
 use foia::repository::diesel_document::BrowseParams;
+use foia::repository::Page;
 
 /// Query params for annotations listing.
 #[derive(Debug, Deserialize, IntoParams)]
@@ -22,8 +23,8 @@ pub struct AnnotationsQuery {
     pub source: Option<String>,
     /// Filter to documents needing annotation
     pub needs_annotation: Option<bool>,
-    /// Page number
-    pub page: Option<usize>,
+    /// Opaque cursor from a previous response's `next_cursor`/`prev_cursor`
+    pub cursor: Option<String>,
     /// Items per page
     pub per_page: Option<usize>,
 }
@@ -60,29 +61,35 @@ pub async fn list_annotations(
     Query(params): Query<AnnotationsQuery>,
 ) -> impl IntoResponse {
     let per_page = params.per_page.unwrap_or(50).clamp(1, 200);
-    let page = params.page.unwrap_or(1).clamp(1, 100_000);
 
-    let documents = if params.needs_annotation.unwrap_or(false) {
-        state
-            .doc_repo
-            .get_needing_summarization(per_page)
-            .await
-            .unwrap_or_default()
+    let page = if params.needs_annotation.unwrap_or(false) {
+        // Annotated documents drop out of this set, so the first page is
+        // always the next batch of work.
+        Page {
+            items: state
+                .doc_repo
+                .get_needing_summarization(per_page)
+                .await
+                .unwrap_or_default(),
+            ..Default::default()
+        }
     } else {
-        let offset = page.saturating_sub(1) * per_page;
         state
             .doc_repo
             .browse(BrowseParams {
                 source_id: params.source.as_deref(),
                 limit: per_page as u32,
-                offset: offset as u32,
+                cursor: parse_cursor_param(params.cursor.as_ref()),
                 ..Default::default()
             })
             .await
             .unwrap_or_default()
     };
 
-    let items: Vec<AnnotationResponse> = documents
+    let next_cursor = page.next_cursor.as_ref().map(|c| c.encode());
+    let prev_cursor = page.prev_cursor.as_ref().map(|c| c.encode());
+    let items: Vec<AnnotationResponse> = page
+        .items
         .into_iter()
         .map(|doc| AnnotationResponse {
             document_id: doc.id,
@@ -106,8 +113,9 @@ pub async fn list_annotations(
 
     ApiResponse::ok(AnnotationsListResponse {
         items,
-        page,
         per_page,
+        next_cursor,
+        prev_cursor,
         stats: AnnotationListStats {
             annotated: total_annotated,
             needing_annotation: total_needing,
@@ -2,7 +2,7 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     response::IntoResponse,
 };
 use serde::Deserialize;
@@ -28,6 +28,25 @@ pub async fn health() -> impl IntoResponse {
     StatusCode::OK
 }
 
+/// Prometheus metrics for this process, plus crawl queue depths.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Metrics in Prometheus text format", content_type = "text/plain")
+    ),
+    tag = "Health"
+)]
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    // Queue depths live in the database, shared with any running scrapers
+    foia::metrics::refresh_queue_depths(&state.crawl_repo).await;
+
+    (
+        [(header::CONTENT_TYPE, foia::metrics::CONTENT_TYPE)],
+        foia::metrics::render(),
+    )
+}
+
 /// Parameters for recent documents.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct RecentParams {
@@ -83,6 +102,7 @@ pub async fn api_sources(State(state): State<AppState>) -> impl IntoResponse {
                 id: s.id,
                 name: s.name,
                 count,
+                agency_id: s.agency_id,
             }
         })
         .collect();
@@ -0,0 +1,211 @@
+//! API key page and API: issuing and revoking keys and showing their use.
+//! The API takes an admin key (see [`crate::api_keys`]); the page lists
+//! keys through it.
+
+use std::collections::HashMap;
+
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use foia::repository::diesel_api_key::ApiKeyUsage;
+
+use super::super::template_structs::ApiKeysTemplate;
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+
+/// Longest accepted key name.
+const MAX_NAME_LEN: usize = 100;
+
+/// Days of usage summed on the page and in the API.
+const USAGE_DAYS: i64 = 30;
+
+/// Issue an API key.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// Who or what the key is for.
+    pub name: String,
+    /// Requests per minute (default: `server.api_rate_limit`).
+    pub rate_limit: Option<u32>,
+    /// Let the key manage keys too (default false).
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// An API key, without the key itself.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub name: String,
+    /// Start of the key, to tell keys apart.
+    pub prefix: String,
+    /// Whether the key may manage keys.
+    pub admin: bool,
+    /// Requests per minute.
+    pub rate_limit: u32,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked: bool,
+    /// Requests served today (UTC).
+    pub requests_today: u64,
+    /// Requests served in the last 30 days.
+    pub requests_30d: u64,
+    /// Requests refused for the rate limit in the last 30 days.
+    pub rejected_30d: u64,
+}
+
+/// A newly issued key. The key is shown only once.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    pub name: String,
+    pub key: String,
+    pub rate_limit: u32,
+    pub admin: bool,
+}
+
+/// Every key with its recent usage.
+async fn keys_with_usage(state: &AppState) -> anyhow::Result<Vec<ApiKeyResponse>> {
+    let now = Utc::now();
+    let keys = state.api_keys.list().await?;
+    let today = by_key(state.api_keys.usage_since(now).await?);
+    let recent = by_key(
+        state
+            .api_keys
+            .usage_since(now - Duration::days(USAGE_DAYS - 1))
+            .await?,
+    );
+    let default_limit = state.api_gate.default_limit();
+
+    Ok(keys
+        .into_iter()
+        .map(|key| {
+            let today = today.get(&key.id).cloned().unwrap_or_default();
+            let recent = recent.get(&key.id).cloned().unwrap_or_default();
+            ApiKeyResponse {
+                rate_limit: key
+                    .rate_limit
+                    .map_or(default_limit, |limit| limit.max(1) as u32),
+                admin: key.is_admin(),
+                revoked: key.revoked_at.is_some(),
+                requests_today: today.requests,
+                requests_30d: recent.requests,
+                rejected_30d: recent.rejected,
+                name: key.name,
+                prefix: key.prefix,
+                created_at: key.created_at,
+                last_used_at: key.last_used_at,
+            }
+        })
+        .collect())
+}
+
+fn by_key(usage: Vec<ApiKeyUsage>) -> HashMap<i32, ApiKeyUsage> {
+    usage.into_iter().map(|u| (u.key_id, u)).collect()
+}
+
+/// API keys with their usage, with forms to issue and revoke keys.
+pub async fn api_keys_page(State(state): State<AppState>) -> impl IntoResponse {
+    let template = ApiKeysTemplate {
+        title: "API keys",
+        required: state.api_gate.required(),
+        default_limit: state.api_gate.default_limit(),
+    };
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// List API keys and their usage. Needs an admin key.
+#[utoipa::path(
+    get,
+    path = "/api/keys",
+    responses(
+        (status = 200, description = "API keys, by name", body = Vec<ApiKeyResponse>),
+        (status = 401, description = "No admin key"),
+        (status = 403, description = "Not an admin key")
+    ),
+    tag = "API keys"
+)]
+pub async fn api_list_keys(State(state): State<AppState>) -> impl IntoResponse {
+    match keys_with_usage(&state).await {
+        Ok(keys) => ApiResponse::ok(keys).into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Issue an API key. Needs an admin key.
+#[utoipa::path(
+    post,
+    path = "/api/keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "The new key, shown only this once", body = CreatedApiKeyResponse),
+        (status = 400, description = "Missing, overlong or taken name"),
+        (status = 401, description = "No admin key"),
+        (status = 403, description = "Not an admin key")
+    ),
+    tag = "API keys"
+)]
+pub async fn api_create_key(
+    State(state): State<AppState>,
+    Json(body): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return bad_request("Key name must be 1-100 characters").into_response();
+    }
+    match state.api_keys.get_by_name(name).await {
+        Ok(Some(_)) => return bad_request("A key with that name already exists").into_response(),
+        Ok(None) => {}
+        Err(e) => return internal_error(e).into_response(),
+    }
+    match state
+        .api_keys
+        .issue(name, body.rate_limit, body.admin)
+        .await
+    {
+        Ok(issued) => ApiResponse::ok(CreatedApiKeyResponse {
+            name: issued.record.name,
+            key: issued.key,
+            rate_limit: body
+                .rate_limit
+                .unwrap_or(state.api_gate.default_limit())
+                .max(1),
+            admin: issued.record.is_admin(),
+        })
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Revoke an API key. Its usage history is kept. Needs an admin key.
+#[utoipa::path(
+    delete,
+    path = "/api/keys/{name}",
+    params(("name" = String, Path, description = "Key name")),
+    responses(
+        (status = 200, description = "Revoked"),
+        (status = 401, description = "No admin key"),
+        (status = 403, description = "Not an admin key"),
+        (status = 404, description = "No unrevoked key with that name")
+    ),
+    tag = "API keys"
+)]
+pub async fn api_revoke_key(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.api_keys.revoke(&name).await {
+        Ok(true) => ApiResponse::ok(serde_json::json!({ "revoked": name })).into_response(),
+        Ok(false) => not_found("API key not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
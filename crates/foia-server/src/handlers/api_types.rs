@@ -94,6 +94,8 @@ pub struct SourceInfo {
     pub id: String,
     pub name: String,
     pub count: u64,
+    /// Registry ID of the agency the source collects from.
+    pub agency_id: Option<String>,
 }
 
 /// Category stat returned by `GET /api/types`.
@@ -267,6 +269,8 @@ pub struct QueueItem {
     pub discovered_at: String,
     pub retry_count: u32,
     pub depth: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
 }
 
 /// Queue listing response.
@@ -274,6 +278,8 @@ pub struct QueueItem {
 pub struct QueueResponse {
     pub items: Vec<QueueItem>,
     pub per_page: usize,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
 /// Retry response from `POST /api/scrapers/retry`.
@@ -283,6 +289,14 @@ pub struct RetryResponse {
     pub message: String,
 }
 
+/// Result of a per-URL queue action under `/api/scrapers/:source_id/urls`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UrlActionResponse {
+    /// URLs that were found and changed.
+    pub updated: u64,
+    pub message: String,
+}
+
 /// Versions listing response from `GET /api/documents/:id/versions`.
 #[derive(Debug, Serialize, ToSchema)]
 pub struct VersionsListResponse {
@@ -302,8 +316,9 @@ pub struct HashSearchResponse {
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AnnotationsListResponse {
     pub items: Vec<super::annotations_api::AnnotationResponse>,
-    pub page: usize,
     pub per_page: usize,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
     pub stats: AnnotationListStats,
 }
 
@@ -0,0 +1,123 @@
+//! Question answering API endpoint.
+
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error};
+use foia::llm::{LlmClient, LlmError, UsageTracker};
+use foia::services::ask::{self, AskError, DEFAULT_CONTEXT_PAGES};
+
+/// Question request body.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AskRequest {
+    /// The question to answer
+    pub question: String,
+    /// Only use documents from this source
+    pub source: Option<String>,
+    /// Pages given to the LLM as context (default: 8, max: 30)
+    pub pages: Option<usize>,
+}
+
+/// A page cited by an answer.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CitationResponse {
+    pub document_id: String,
+    pub title: String,
+    pub page_number: u32,
+}
+
+/// Answer to a question.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AskResponse {
+    pub answer: String,
+    /// Pages cited in the answer, in order of first citation
+    pub citations: Vec<CitationResponse>,
+    /// Pages the LLM was given
+    pub pages_consulted: usize,
+}
+
+/// Answer a question from the archive.
+///
+/// Retrieves the pages that best match the question's terms and has the
+/// configured LLM answer from them, citing document IDs and page numbers.
+/// Calls count toward the LLM budget.
+#[utoipa::path(
+    post,
+    path = "/api/ask",
+    request_body = AskRequest,
+    responses(
+        (status = 200, description = "Answer with citations", body = AskResponse),
+        (status = 400, description = "Empty question or no searchable terms"),
+        (status = 429, description = "LLM budget exceeded"),
+        (status = 503, description = "LLM disabled or unavailable")
+    ),
+    tag = "Search"
+)]
+pub async fn api_ask(
+    State(state): State<AppState>,
+    Json(request): Json<AskRequest>,
+) -> impl IntoResponse {
+    let question = request.question.trim();
+    if question.is_empty() {
+        return bad_request("Question cannot be empty").into_response();
+    }
+
+    let llm_config = state.config.borrow().llm.clone();
+    if !llm_config.enabled() {
+        return ApiResponse::error(StatusCode::SERVICE_UNAVAILABLE, "LLM is disabled")
+            .into_response();
+    }
+    let tracker = match UsageTracker::with_repository(
+        llm_config.budget().clone(),
+        (*state.llm_usage).clone(),
+    )
+    .await
+    {
+        Ok(t) => Arc::new(t),
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let client = LlmClient::new(llm_config).with_usage_tracker(tracker);
+
+    let answer = match ask::ask(
+        &state.doc_repo,
+        &client,
+        question,
+        request.source.as_deref(),
+        request.pages.unwrap_or(DEFAULT_CONTEXT_PAGES),
+    )
+    .await
+    {
+        Ok(a) => a,
+        Err(AskError::NoTerms) => {
+            return bad_request("Question has no searchable terms").into_response()
+        }
+        Err(AskError::Llm(e @ LlmError::BudgetExceeded(_))) => {
+            return ApiResponse::error(StatusCode::TOO_MANY_REQUESTS, e.to_string()).into_response()
+        }
+        Err(AskError::Llm(e)) => {
+            return ApiResponse::error(StatusCode::SERVICE_UNAVAILABLE, e.to_string())
+                .into_response()
+        }
+        Err(e) => return internal_error(e).into_response(),
+    };
+
+    Json(AskResponse {
+        answer: answer.answer,
+        citations: answer
+            .citations
+            .into_iter()
+            .map(|c| CitationResponse {
+                document_id: c.document_id,
+                title: c.title,
+                page_number: c.page_number,
+            })
+            .collect(),
+        pages_consulted: answer.pages_consulted,
+    })
+    .into_response()
+}
@@ -7,14 +7,21 @@ use axum::{
 };
 use serde::Deserialize;
 
+use foia::models::{BoundingBox, DocumentStatus, RecordType};
+use foia::repository::diesel_document::{
+    BrowseParams as DocumentFilter, DocumentSort, ProcessingState,
+};
 use foia::utils::MimeCategory;
 
 use super::super::template_structs::{
-    ActiveTagDisplay, BrowseTemplate, CategoryWithCount, DocumentRow, ErrorTemplate, SourceOption,
-    TagWithCount,
+    ActiveTagDisplay, BrowseTemplate, CategoryWithCount, DocumentRow, ErrorTemplate,
+    ExemptionOption, PdfProducerOption, PdfYearOption, ProcessingOption, RecordTypeOption,
+    SortColumn, SourceOption, StatusOption, TagWithCount,
 };
 use super::super::AppState;
-use super::helpers::{paginate, parse_csv_param_limit};
+use super::helpers::{
+    paginate, parse_csv_param_limit, parse_cursor_param, parse_date_param, parse_years_param,
+};
 
 /// Query params for the unified browse page.
 #[derive(Debug, Clone, Deserialize)]
@@ -22,82 +29,198 @@ pub struct BrowseParams {
     pub types: Option<String>,
     pub tags: Option<String>,
     pub source: Option<String>,
+    pub record_types: Option<String>,
+    /// Only documents with poorly recognized pages
+    #[serde(default)]
+    pub poor_ocr: bool,
+    /// Exemption codes cited, comma-separated, e.g. `(b)(6)`
+    pub exemptions: Option<String>,
+    /// Software that wrote the PDF, e.g. `Adobe PDF Library 15.0`
+    pub pdf_producer: Option<String>,
+    /// PDF creation years, comma-separated
+    pub pdf_years: Option<String>,
+    /// Only PDFs created after the date the document claims to be from
+    #[serde(default)]
+    pub pdf_postdated: bool,
+    /// Workflow status, e.g. `reviewed`
+    pub status: Option<String>,
+    /// Processing state, e.g. `needs_ocr`
+    pub processing: Option<String>,
+    /// Timeline range start (YYYY-MM-DD)
+    pub start: Option<String>,
+    /// Timeline range end (YYYY-MM-DD)
+    pub end: Option<String>,
+    /// Map area, `south,west,north,east`
+    pub bbox: Option<String>,
     pub q: Option<String>,
-    pub page: Option<usize>,
+    /// Sort key: title, size, acquired, date, pages or status (default:
+    /// most recently updated)
+    pub sort: Option<String>,
+    /// `asc` or `desc`; each sort key has its own default
+    pub order: Option<String>,
+    pub cursor: Option<String>,
     pub per_page: Option<usize>,
 }
 
+/// Listing columns, with the sort key for the sortable ones.
+const COLUMNS: [(&str, &str); 8] = [
+    ("Document", "title"),
+    ("Source", ""),
+    ("Type", ""),
+    ("Size", "size"),
+    ("Pages", "pages"),
+    ("Date", "date"),
+    ("Status", "status"),
+    ("Acquired", "acquired"),
+];
+
+/// Column headers marking the active sort.
+fn sort_columns(sort: DocumentSort, desc: bool) -> Vec<SortColumn> {
+    COLUMNS
+        .iter()
+        .map(|&(label, key)| {
+            let active = !key.is_empty() && key == sort.as_str();
+            SortColumn {
+                label,
+                key,
+                active,
+                arrow: match (active, desc) {
+                    (false, _) => "",
+                    (true, true) => "▼",
+                    (true, false) => "▲",
+                },
+            }
+        })
+        .collect()
+}
+
 /// Unified document browse page with filters.
 pub async fn browse_documents(
     State(state): State<AppState>,
     Query(params): Query<BrowseParams>,
 ) -> impl IntoResponse {
-    let (page, per_page, _offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
     let types = parse_csv_param_limit(params.types.as_ref(), Some(20));
     let tags = parse_csv_param_limit(params.tags.as_ref(), Some(50));
+    let record_types = parse_csv_param_limit(params.record_types.as_ref(), Some(20));
+    let exemptions = parse_csv_param_limit(params.exemptions.as_ref(), Some(20));
+    let pdf_producers: Vec<String> = params
+        .pdf_producer
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+        .cloned()
+        .collect();
+    let pdf_years = parse_years_param(params.pdf_years.as_ref());
+    let status = params.status.as_deref().and_then(DocumentStatus::from_str);
+    let processing = params
+        .processing
+        .as_deref()
+        .and_then(ProcessingState::from_str);
+    let date_from = parse_date_param(params.start.as_ref());
+    let date_to = parse_date_param(params.end.as_ref());
+    let bbox = params.bbox.as_deref().and_then(BoundingBox::parse);
+    let (sort, sort_desc) = DocumentSort::resolve(params.sort.as_deref(), params.order.as_deref());
+    let search_query = params
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+        .map(str::to_string);
 
-    let offset = page.saturating_sub(1) * per_page;
-    let (browse_result, count_result, category_stats, source_counts, sources, all_tags) =
-        tokio::join!(
-            state.doc_repo.browse_fast(
-                params.source.as_deref(),
-                None,
-                &types,
-                &tags,
-                per_page as u32,
-                offset as u32,
-            ),
-            state.doc_repo.browse_count(
-                params.source.as_deref(),
-                None,
-                &types,
-                &tags,
-                params.q.as_deref(),
-            ),
-            async {
-                match state.stats_cache.get_category_stats() {
-                    Some(cached) => cached,
-                    None => {
-                        let stats = state
-                            .doc_repo
-                            .get_category_stats(None)
-                            .await
-                            .unwrap_or_default();
-                        state.stats_cache.set_category_stats(stats.clone());
-                        stats
-                    }
+    let filter = DocumentFilter {
+        source_id: params.source.as_deref(),
+        status: status.map(|s| s.as_str()),
+        categories: &types,
+        tags: &tags,
+        record_types: &record_types,
+        poor_ocr: params.poor_ocr,
+        exemptions: &exemptions,
+        pdf_producers: &pdf_producers,
+        pdf_years: &pdf_years,
+        pdf_postdated: params.pdf_postdated,
+        processing,
+        date_from,
+        date_to,
+        bbox,
+        search_query: search_query.as_deref(),
+        sort_field: Some(sort.as_str()),
+        sort_order: Some(if sort_desc { "desc" } else { "asc" }),
+        limit: per_page as u32,
+        cursor: parse_cursor_param(params.cursor.as_ref()),
+        ..Default::default()
+    };
+    let (
+        browse_result,
+        count_result,
+        category_stats,
+        source_counts,
+        sources,
+        all_tags,
+        record_type_stats,
+        exemption_stats,
+        pdf_producer_stats,
+        pdf_year_stats,
+        status_counts,
+        processing_counts,
+    ) = tokio::join!(
+        state.doc_repo.browse_fast(&filter),
+        state.doc_repo.browse_count(&filter),
+        async {
+            match state.stats_cache.get_category_stats() {
+                Some(cached) => cached,
+                None => {
+                    let stats = state
+                        .doc_repo
+                        .get_category_stats(None)
+                        .await
+                        .unwrap_or_default();
+                    state.stats_cache.set_category_stats(stats.clone());
+                    stats
                 }
-            },
-            async {
-                match state.stats_cache.get_source_counts() {
-                    Some(cached) => cached,
-                    None => {
-                        let counts = state
-                            .doc_repo
-                            .get_all_source_counts()
-                            .await
-                            .unwrap_or_default();
-                        state.stats_cache.set_source_counts(counts.clone());
-                        counts
-                    }
+            }
+        },
+        async {
+            match state.stats_cache.get_source_counts() {
+                Some(cached) => cached,
+                None => {
+                    let counts = state
+                        .doc_repo
+                        .get_all_source_counts()
+                        .await
+                        .unwrap_or_default();
+                    state.stats_cache.set_source_counts(counts.clone());
+                    counts
                 }
-            },
-            state.source_repo.get_all(),
-            async {
-                match state.stats_cache.get_all_tags() {
-                    Some(cached) => cached,
-                    None => {
-                        let raw = state.doc_repo.get_all_tags().await.unwrap_or_default();
-                        let with_counts: Vec<(String, usize)> =
-                            raw.into_iter().map(|t| (t, 0)).collect();
-                        state.stats_cache.set_all_tags(with_counts.clone());
-                        with_counts
-                    }
+            }
+        },
+        state.source_repo.get_all(),
+        async {
+            match state.stats_cache.get_all_tags() {
+                Some(cached) => cached,
+                None => {
+                    let raw = state.doc_repo.get_all_tags().await.unwrap_or_default();
+                    let with_counts: Vec<(String, usize)> =
+                        raw.into_iter().map(|t| (t, 0)).collect();
+                    state.stats_cache.set_all_tags(with_counts.clone());
+                    with_counts
                 }
-            },
-        );
+            }
+        },
+        state
+            .doc_repo
+            .get_record_type_stats(params.source.as_deref()),
+        state.doc_repo.get_exemption_stats(params.source.as_deref()),
+        state
+            .doc_repo
+            .get_pdf_producer_counts(params.source.as_deref(), 50),
+        state.doc_repo.get_pdf_year_counts(params.source.as_deref()),
+        state.doc_repo.count_by_status(params.source.as_deref()),
+        state
+            .doc_repo
+            .count_by_processing_state(params.source.as_deref()),
+    );
 
-    let browse_rows = match browse_result {
+    let browse_page = match browse_result {
         Ok(result) => result,
         Err(e) => {
             let template = ErrorTemplate {
@@ -110,10 +233,13 @@ pub async fn browse_documents(
 
     let total = match count_result {
         Ok(count) => count,
-        Err(_) => browse_rows.len() as u64,
+        Err(_) => browse_page.items.len() as u64,
     };
 
-    let doc_rows: Vec<DocumentRow> = browse_rows
+    let prev_cursor = browse_page.prev_cursor.as_ref().map(|c| c.encode());
+    let next_cursor = browse_page.next_cursor.as_ref().map(|c| c.encode());
+    let doc_rows: Vec<DocumentRow> = browse_page
+        .items
         .into_iter()
         .map(DocumentRow::from_browse_row)
         .collect();
@@ -137,9 +263,18 @@ pub async fn browse_documents(
         })
         .collect();
 
+    let sources = sources.unwrap_or_default();
+    // A source published as a torrent offers its magnet link
+    let magnet = params
+        .source
+        .as_deref()
+        .and_then(|id| sources.iter().find(|s| s.id == id))
+        .and_then(|s| s.distribution())
+        .map(|d| d.magnet)
+        .unwrap_or_default();
+
     // Build source dropdown options
     let source_options: Vec<SourceOption> = sources
-        .unwrap_or_default()
         .into_iter()
         .map(|s| {
             let count = source_counts.get(&s.id).copied().unwrap_or(0);
@@ -153,27 +288,95 @@ pub async fn browse_documents(
         })
         .collect();
 
+    // Build record type dropdown options
+    let record_type_stats = record_type_stats.unwrap_or_default();
+    let record_type_options: Vec<RecordTypeOption> = RecordType::all()
+        .iter()
+        .filter_map(|rt| {
+            let count = record_type_stats.get(rt.as_str()).copied().unwrap_or(0);
+            let selected = record_types.iter().any(|r| r == rt.as_str());
+            if count == 0 && !selected {
+                return None;
+            }
+            Some(RecordTypeOption {
+                id: rt.as_str().to_string(),
+                name: rt.label().to_string(),
+                count,
+                selected,
+            })
+        })
+        .collect();
+
+    // Build exemption dropdown options
+    let mut exemption_options: Vec<ExemptionOption> = exemption_stats
+        .unwrap_or_default()
+        .into_iter()
+        .map(|stat| ExemptionOption {
+            selected: exemptions.contains(&stat.code),
+            code: stat.code,
+            count: stat.documents,
+        })
+        .collect();
+    exemption_options.sort_by(|a, b| a.code.cmp(&b.code));
+
+    // Build PDF producer and creation year dropdown options
+    let pdf_producer_options: Vec<PdfProducerOption> = pdf_producer_stats
+        .unwrap_or_default()
+        .into_iter()
+        .map(|stat| PdfProducerOption {
+            selected: pdf_producers.contains(&stat.value),
+            name: stat.value,
+            count: stat.documents,
+        })
+        .collect();
+    let pdf_year_options: Vec<PdfYearOption> = pdf_year_stats
+        .unwrap_or_default()
+        .into_iter()
+        .map(|stat| PdfYearOption {
+            selected: pdf_years.contains(&stat.value),
+            year: stat.value,
+            count: stat.documents,
+        })
+        .collect();
+
+    // Build status dropdown options, in workflow order
+    let status_counts = status_counts.unwrap_or_default();
+    let status_options: Vec<StatusOption> = DocumentStatus::ALL
+        .iter()
+        .filter_map(|s| {
+            let count = status_counts.get(s.as_str()).copied().unwrap_or(0);
+            let selected = status == Some(*s);
+            if count == 0 && !selected {
+                return None;
+            }
+            Some(StatusOption {
+                id: s.label().to_string(),
+                name: s.label().to_string(),
+                count,
+                selected,
+            })
+        })
+        .collect();
+
+    // Build processing state dropdown options
+    let processing_options: Vec<ProcessingOption> = processing_counts
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(state, count)| *count > 0 || processing == Some(*state))
+        .map(|(state, count)| ProcessingOption {
+            id: state.as_str(),
+            name: state.label(),
+            count,
+            selected: processing == Some(state),
+        })
+        .collect();
+
     // Build tag datalist
     let tag_list: Vec<TagWithCount> = all_tags
         .into_iter()
         .map(|(name, count)| TagWithCount::new(name, count))
         .collect();
 
-    // Calculate pagination cursors
-    let start_position = offset as u64;
-    let has_prev = page > 1;
-    let has_next = start_position + (per_page as u64) < total;
-    let prev_cursor = if has_prev {
-        Some(format!("{}", page - 1))
-    } else {
-        None
-    };
-    let next_cursor = if has_next {
-        Some(format!("{}", page + 1))
-    } else {
-        None
-    };
-
     // Build query string for document links
     let nav_query_string = {
         let mut qs_parts = Vec::new();
@@ -186,6 +389,53 @@ pub async fn browse_documents(
         if let Some(source) = params.source.as_deref() {
             qs_parts.push(format!("source={}", urlencoding::encode(source)));
         }
+        if !record_types.is_empty() {
+            qs_parts.push(format!(
+                "record_types={}",
+                urlencoding::encode(&record_types.join(","))
+            ));
+        }
+        if params.poor_ocr {
+            qs_parts.push("poor_ocr=true".to_string());
+        }
+        if !exemptions.is_empty() {
+            qs_parts.push(format!(
+                "exemptions={}",
+                urlencoding::encode(&exemptions.join(","))
+            ));
+        }
+        if let Some(producer) = pdf_producers.first() {
+            qs_parts.push(format!("pdf_producer={}", urlencoding::encode(producer)));
+        }
+        if !pdf_years.is_empty() {
+            let years: Vec<String> = pdf_years.iter().map(|y| y.to_string()).collect();
+            qs_parts.push(format!("pdf_years={}", years.join(",")));
+        }
+        if params.pdf_postdated {
+            qs_parts.push("pdf_postdated=true".to_string());
+        }
+        if let Some(s) = status {
+            qs_parts.push(format!("status={}", s.label()));
+        }
+        if let Some(p) = processing {
+            qs_parts.push(format!("processing={}", p.as_str()));
+        }
+        if let Some(from) = date_from {
+            qs_parts.push(format!("start={}", from.format("%Y-%m-%d")));
+        }
+        if let Some(to) = date_to {
+            qs_parts.push(format!("end={}", to.format("%Y-%m-%d")));
+        }
+        if let Some(b) = &bbox {
+            qs_parts.push(format!("bbox={}", urlencoding::encode(&b.to_param())));
+        }
+        if let Some(q) = search_query.as_deref() {
+            qs_parts.push(format!("q={}", urlencoding::encode(q)));
+        }
+        if params.sort.is_some() {
+            qs_parts.push(format!("sort={}", sort.as_str()));
+            qs_parts.push(format!("order={}", if sort_desc { "desc" } else { "asc" }));
+        }
         if qs_parts.is_empty() {
             String::new()
         } else {
@@ -206,26 +456,50 @@ pub async fn browse_documents(
     // JSON for JavaScript (passed via data attributes to avoid Askama HTML escaping)
     let active_tags_json = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string());
 
-    let end_position = start_position + doc_rows.len() as u64;
+    // Timeline ruler buckets come from the API, scoped to the selected source
+    let timeline_url = match params.source.as_deref() {
+        Some(source) => format!("/api/timeline/{}", urlencoding::encode(source)),
+        None => "/api/timeline".to_string(),
+    };
 
+    let has_pagination = prev_cursor.is_some() || next_cursor.is_some();
     let template = BrowseTemplate {
         title: "Browse",
         documents: doc_rows,
         categories,
         sources: source_options,
+        record_types: record_type_options,
+        poor_ocr: params.poor_ocr,
+        exemptions: exemption_options,
+        pdf_producers: pdf_producer_options,
+        pdf_years: pdf_year_options,
+        pdf_postdated: params.pdf_postdated,
+        statuses: status_options,
+        processing_states: processing_options,
         all_tags: tag_list,
         active_tags_display,
         has_prev_cursor: prev_cursor.is_some(),
         prev_cursor_val: prev_cursor.unwrap_or_default(),
         has_next_cursor: next_cursor.is_some(),
         next_cursor_val: next_cursor.unwrap_or_default(),
-        start_position,
-        end_position,
         total_count: total,
         per_page,
-        has_pagination: has_prev || has_next,
+        has_pagination,
         nav_query_string,
         active_tags_json,
+        timeline_url,
+        timeline_start: date_from
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        timeline_end: date_to
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        columns: sort_columns(sort, sort_desc),
+        sort: sort.as_str().to_string(),
+        order: if sort_desc { "desc" } else { "asc" }.to_string(),
+        query: search_query.unwrap_or_default(),
+        bbox: bbox.map(|b| b.to_param()).unwrap_or_default(),
+        magnet,
     };
 
     Html(
@@ -0,0 +1,191 @@
+//! Crawl request log and per-domain rate-limit pages.
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use chrono::{NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use super::super::template_structs::{
+    CrawlDomainsTemplate, CrawlLogTemplate, CrawlRequestRow, DomainRateRow, ErrorTemplate,
+    SourceOption,
+};
+use super::super::AppState;
+use super::helpers::{parse_date_param, parse_status_param};
+use foia::models::CrawlRequest;
+use foia::repository::diesel_crawl::RequestLogFilter;
+use foia::utils::format_size;
+
+/// Requests shown per page of the log.
+const PAGE_SIZE: u32 = 100;
+
+/// Query parameters for the crawl log.
+#[derive(Debug, Deserialize)]
+pub struct CrawlLogParams {
+    pub source: Option<String>,
+    /// Exact code (`404`), class (`4xx`) or range (`500-599`).
+    pub status: Option<String>,
+    /// Start date (YYYY-MM-DD), inclusive.
+    pub since: Option<String>,
+    /// End date (YYYY-MM-DD), inclusive.
+    pub until: Option<String>,
+    /// Show requests older than this log ID.
+    pub before: Option<i64>,
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn status_class(status: Option<u16>) -> &'static str {
+    match status {
+        Some(200..=299) => "status-ok",
+        Some(300..=399) => "status-redirect",
+        Some(429) => "status-rate-limited",
+        Some(400..=499) => "status-client-error",
+        Some(_) | None => "status-server-error",
+    }
+}
+
+pub(super) fn request_row(r: CrawlRequest) -> CrawlRequestRow {
+    CrawlRequestRow {
+        status: r
+            .response_status
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| "—".to_string()),
+        status_class: status_class(r.response_status),
+        duration: r
+            .duration_ms
+            .map(|ms| format!("{} ms", ms))
+            .unwrap_or_default(),
+        size: r.response_size.map(format_size).unwrap_or_default(),
+        request_at: r.request_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        conditional: r.was_conditional,
+        error: r.error.unwrap_or_default(),
+        source_id: r.source_id,
+        url: r.url,
+        method: r.method,
+    }
+}
+
+/// Crawl request log with source, status and date filters.
+pub async fn crawl_log(
+    State(state): State<AppState>,
+    Query(params): Query<CrawlLogParams>,
+) -> impl IntoResponse {
+    let source_id = params.source.clone().filter(|s| !s.is_empty());
+    let filter = RequestLogFilter {
+        source_id: source_id.clone(),
+        status: parse_status_param(params.status.as_ref()),
+        since: parse_date_param(params.since.as_ref())
+            .map(|d| Utc.from_utc_datetime(&d.and_time(NaiveTime::MIN))),
+        until: parse_date_param(params.until.as_ref())
+            .and_then(|d| d.succ_opt())
+            .map(|d| Utc.from_utc_datetime(&d.and_time(NaiveTime::MIN))),
+        before_id: params.before,
+        url: None,
+    };
+
+    let (requests, request_stats) = tokio::join!(
+        state.crawl_repo.list_requests(&filter, PAGE_SIZE),
+        state.crawl_repo.get_all_request_stats(),
+    );
+    let requests = match requests {
+        Ok(r) => r,
+        Err(e) => return render_error(&format!("Failed to load crawl log: {}", e)),
+    };
+
+    let mut sources: Vec<SourceOption> = request_stats
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(id, stats)| SourceOption {
+            selected: source_id.as_deref() == Some(id.as_str()),
+            name: id.clone(),
+            id,
+            count: stats.total_requests,
+        })
+        .collect();
+    sources.sort_by(|a, b| a.id.cmp(&b.id));
+
+    // Page back from the oldest request shown, keeping the other filters
+    let oldest_id = requests.last().and_then(|r| r.id);
+    let has_older = requests.len() == PAGE_SIZE as usize && oldest_id.is_some();
+    let older_url = match oldest_id {
+        Some(id) if has_older => {
+            let mut query: Vec<String> = [
+                ("source", &params.source),
+                ("status", &params.status),
+                ("since", &params.since),
+                ("until", &params.until),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| {
+                value
+                    .as_deref()
+                    .filter(|v| !v.is_empty())
+                    .map(|v| format!("{}={}", key, urlencoding::encode(v)))
+            })
+            .collect();
+            query.push(format!("before={}", id));
+            format!("/crawl?{}", query.join("&"))
+        }
+        _ => String::new(),
+    };
+
+    let rows: Vec<CrawlRequestRow> = requests.into_iter().map(request_row).collect();
+    let template = CrawlLogTemplate {
+        title: "Crawl Log",
+        sources,
+        status: params.status.as_deref().unwrap_or(""),
+        since: params.since.as_deref().unwrap_or(""),
+        until: params.until.as_deref().unwrap_or(""),
+        has_requests: !rows.is_empty(),
+        requests: rows,
+        older_url,
+        has_older,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// Persisted per-domain rate-limit state.
+pub async fn crawl_domains(State(state): State<AppState>) -> impl IntoResponse {
+    let domains = match state.crawl_repo.get_domain_rate_limits().await {
+        Ok(d) => d,
+        Err(e) => return render_error(&format!("Failed to load rate-limit state: {}", e)),
+    };
+
+    let rows: Vec<DomainRateRow> = domains
+        .into_iter()
+        .map(|d| DomainRateRow {
+            delay: format!("{:.1} s", d.current_delay_ms as f64 / 1000.0),
+            in_backoff: d.in_backoff,
+            total_requests: d.total_requests,
+            rate_limit_hits: d.rate_limit_hits,
+            updated_at: d.updated_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            domain: d.domain,
+        })
+        .collect();
+
+    let template = CrawlDomainsTemplate {
+        title: "Crawl Rate Limits",
+        has_domains: !rows.is_empty(),
+        domains: rows,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
@@ -0,0 +1,135 @@
+//! Per-source crawl queue page: list, retry, cancel and seed URLs.
+//!
+//! The page itself is read-only; its buttons call the JSON endpoints under
+//! `/api/scrapers/:source_id/urls`.
+
+use askama::Template;
+use axum::{
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse},
+};
+use serde::Deserialize;
+
+use super::super::template_structs::{
+    CrawlUrlRow, ErrorTemplate, SourceUrlsTemplate, UrlStatusTab,
+};
+use super::super::AppState;
+use super::helpers::{paginate, parse_cursor_param};
+use foia::models::{CrawlUrl, UrlStatus};
+
+/// Status tabs shown on the page, in order.
+const TABS: &[&str] = &["pending", "failed", "skipped", "fetched", "all"];
+
+/// Query parameters for the queue page.
+#[derive(Debug, Deserialize)]
+pub struct SourceUrlsParams {
+    pub status: Option<String>,
+    pub cursor: Option<String>,
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn url_row(u: CrawlUrl) -> CrawlUrlRow {
+    let status_class = match u.status {
+        UrlStatus::Fetched => "status-ok",
+        UrlStatus::Discovered | UrlStatus::Fetching => "status-redirect",
+        UrlStatus::Skipped => "status-client-error",
+        UrlStatus::Failed | UrlStatus::Exhausted => "status-server-error",
+    };
+    CrawlUrlRow {
+        status: u.status.as_str(),
+        status_class,
+        retry_count: u.retry_count,
+        discovered_at: u.discovered_at.format("%Y-%m-%d %H:%M").to_string(),
+        error: u.last_error.unwrap_or_default(),
+        can_retry: u.status != UrlStatus::Discovered,
+        can_cancel: u.status != UrlStatus::Skipped,
+        url: u.url,
+    }
+}
+
+/// Crawl queue for one source, filtered by status.
+pub async fn source_urls(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+    Query(params): Query<SourceUrlsParams>,
+) -> impl IntoResponse {
+    let source = match state.source_repo.get(&source_id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return render_error("Source not found"),
+        Err(e) => return render_error(&format!("Failed to load source: {}", e)),
+    };
+
+    let status = params.status.as_deref().unwrap_or("pending");
+    let Some(statuses) = UrlStatus::parse_filter(status) else {
+        return render_error(&format!("Unknown status filter: {}", status));
+    };
+    let (_, per_page, _) = paginate(None, None);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
+
+    let (urls, counts) = tokio::join!(
+        state
+            .crawl_repo
+            .list_urls(&source_id, &statuses, cursor.as_ref(), per_page as u32),
+        state.crawl_repo.count_by_status(&source_id),
+    );
+    let urls = match urls {
+        Ok(u) => u,
+        Err(e) => return render_error(&format!("Failed to load URLs: {}", e)),
+    };
+    let counts = counts.unwrap_or_default();
+
+    let tabs: Vec<UrlStatusTab> = TABS
+        .iter()
+        .map(|&name| {
+            let matched = UrlStatus::parse_filter(name).unwrap_or_default();
+            let count = if matched.is_empty() {
+                counts.values().sum()
+            } else {
+                matched
+                    .iter()
+                    .map(|s| counts.get(s.as_str()).copied().unwrap_or(0))
+                    .sum()
+            };
+            UrlStatusTab {
+                name,
+                count,
+                active: name == status,
+            }
+        })
+        .collect();
+    let failed_count = tabs
+        .iter()
+        .find(|t| t.name == "failed")
+        .map(|t| t.count)
+        .unwrap_or(0);
+
+    let rows: Vec<CrawlUrlRow> = urls.items.into_iter().map(url_row).collect();
+    let title = format!("URLs: {}", source.name);
+    let template = SourceUrlsTemplate {
+        title: &title,
+        source_id: &source_id,
+        source_name: &source.name,
+        tabs,
+        status,
+        has_urls: !rows.is_empty(),
+        urls: rows,
+        has_prev_cursor: urls.prev_cursor.is_some(),
+        prev_cursor_val: urls.prev_cursor.map(|c| c.encode()).unwrap_or_default(),
+        has_next_cursor: urls.next_cursor.is_some(),
+        next_cursor_val: urls.next_cursor.map(|c| c.encode()).unwrap_or_default(),
+        failed_count,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
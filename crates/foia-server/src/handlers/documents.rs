@@ -3,17 +3,29 @@
 use askama::Template;
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::{Html, IntoResponse},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
 };
 use serde::Deserialize;
 
+use super::super::redacted::{self, PublicCopy};
 use super::super::template_structs::{
-    DocumentDetailTemplate, ErrorTemplate, VersionItem, VirtualFileRow,
+    DocumentDetailTemplate, ErrorTemplate, LinkedDocumentRow, RelatedDocumentRow, TableRow,
+    VersionItem, VirtualFileRow,
 };
-use super::super::AppState;
+use super::super::{access_log, AppState};
 use super::helpers::{find_sources_with_hash, VersionInfo};
+use super::pages::CITATION_HASH_LEN;
+use foia::artifacts::ArtifactStore;
+use foia::models::{ArtifactKind, ChecksumStatus, POOR_OCR_QUALITY};
+use foia::repository::diesel_access_log::AccessKind;
+use foia::repository::sanitize_filename;
 use foia::utils::format_size;
+use foia_analysis::services::redaction::REDACTED_PDF_NAME;
+use foia_analysis::services::searchable_pdf::SEARCHABLE_PDF_NAME;
+
+/// Related documents listed on the document page.
+const MAX_RELATED_DOCUMENTS: usize = 50;
 
 /// Query params for document detail navigation context.
 #[derive(Debug, Clone, Deserialize, Default)]
@@ -21,7 +33,20 @@ pub struct DocumentDetailParams {
     pub types: Option<String>,
     pub tags: Option<String>,
     pub source: Option<String>,
+    pub record_types: Option<String>,
+    pub poor_ocr: Option<String>,
+    pub exemptions: Option<String>,
+    pub pdf_producer: Option<String>,
+    pub pdf_years: Option<String>,
+    pub pdf_postdated: Option<String>,
+    pub processing: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
     pub q: Option<String>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    /// Version to show instead of the current one, as cited permalinks do.
+    pub version: Option<i64>,
 }
 
 /// Document detail page.
@@ -29,7 +54,8 @@ pub async fn document_detail(
     State(state): State<AppState>,
     Path(doc_id): Path<String>,
     Query(params): Query<DocumentDetailParams>,
-) -> impl IntoResponse {
+    headers: HeaderMap,
+) -> Response {
     let doc = match state.doc_repo.get(&doc_id).await {
         Ok(Some(d)) => d,
         Ok(None) => {
@@ -37,11 +63,16 @@ pub async fn document_detail(
                 title: "Not Found",
                 message: "Document not found.",
             };
-            return Html(
-                template
-                    .render()
-                    .unwrap_or_else(|_| "Not found".to_string()),
-            );
+            // A 404 lets replaced IDs redirect (see `redirects`)
+            return (
+                StatusCode::NOT_FOUND,
+                Html(
+                    template
+                        .render()
+                        .unwrap_or_else(|_| "Not found".to_string()),
+                ),
+            )
+                .into_response();
         }
         Err(e) => {
             let msg = format!("Failed to load document: {}", e);
@@ -49,9 +80,10 @@ pub async fn document_detail(
                 title: "Error",
                 message: &msg,
             };
-            return Html(template.render().unwrap_or(msg));
+            return Html(template.render().unwrap_or(msg)).into_response();
         }
     };
+    access_log::record(&state, &doc.id, AccessKind::View, &headers);
 
     let source_for_nav = params.source.as_deref().unwrap_or("");
     let navigation = state
@@ -60,20 +92,32 @@ pub async fn document_detail(
         .await
         .ok();
 
+    // Carry the browse filters along so prev/next links keep the view
     let nav_query_string = {
-        let mut qs_parts = Vec::new();
-        if let Some(ref t) = params.types {
-            qs_parts.push(format!("types={}", urlencoding::encode(t)));
-        }
-        if let Some(ref t) = params.tags {
-            qs_parts.push(format!("tags={}", urlencoding::encode(t)));
-        }
-        if let Some(ref s) = params.source {
-            qs_parts.push(format!("source={}", urlencoding::encode(s)));
-        }
-        if let Some(ref q) = params.q {
-            qs_parts.push(format!("q={}", urlencoding::encode(q)));
-        }
+        let qs_parts: Vec<String> = [
+            ("types", &params.types),
+            ("tags", &params.tags),
+            ("source", &params.source),
+            ("record_types", &params.record_types),
+            ("poor_ocr", &params.poor_ocr),
+            ("exemptions", &params.exemptions),
+            ("pdf_producer", &params.pdf_producer),
+            ("pdf_years", &params.pdf_years),
+            ("pdf_postdated", &params.pdf_postdated),
+            ("processing", &params.processing),
+            ("start", &params.start),
+            ("end", &params.end),
+            ("q", &params.q),
+            ("sort", &params.sort),
+            ("order", &params.order),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| {
+            value
+                .as_deref()
+                .map(|v| format!("{}={}", key, urlencoding::encode(v)))
+        })
+        .collect();
         if qs_parts.is_empty() {
             String::new()
         } else {
@@ -100,42 +144,146 @@ pub async fn document_detail(
                 .clone()
                 .unwrap_or_else(|| "unknown".to_string());
 
+            let (checksum_class, checksum_label, checksum_title) = match &v.checksum {
+                Some(c) => {
+                    let label = match c.status {
+                        ChecksumStatus::Verified => "verified",
+                        ChecksumStatus::Mismatch => "checksum mismatch",
+                        ChecksumStatus::BadSignature => "bad signature",
+                    };
+                    let title = format!(
+                        "{} {} against {}: expected {}, got {}",
+                        c.algorithm.as_str(),
+                        label,
+                        c.manifest_url,
+                        c.expected,
+                        c.actual
+                    );
+                    (c.status.as_str().to_string(), label.to_string(), title)
+                }
+                None => Default::default(),
+            };
+
             VersionItem {
                 path: relative_path,
                 filename,
                 size_str: format_size(v.file_size),
                 date_str,
+                has_checksum: v.checksum.is_some(),
+                checksum_class,
+                checksum_label,
+                checksum_title,
             }
         })
         .collect();
 
     let other_sources = if let Some(version) = doc.current_version() {
-        find_sources_with_hash(&state, &version.content_hash, &doc.source_id).await
+        find_sources_with_hash(&state, version.dedup_hash(), &doc.source_id).await
     } else {
         vec![]
     };
 
-    let current_version = doc.current_version();
+    let requests: Vec<LinkedDocumentRow> = state
+        .foia_requests
+        .for_document(&doc_id)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|r| LinkedDocumentRow {
+            title: format!("{}: {}", r.agency, r.subject),
+            id: r.id,
+        })
+        .collect();
+
+    let relations = state
+        .doc_repo
+        .document_relation_graph(&doc_id, 1, MAX_RELATED_DOCUMENTS)
+        .await
+        .map(|graph| RelatedDocumentRow::from_graph(&doc_id, &graph))
+        .unwrap_or_default();
+
+    let current_version = params
+        .version
+        .and_then(|id| doc.versions.iter().find(|v| v.id == id))
+        .or_else(|| doc.current_version());
     let current_version_id = current_version.map(|v| v.id);
 
-    let virtual_files: Vec<VirtualFileRow> = if let Some(vid) = current_version_id {
-        state
+    let all_virtual_files = match current_version_id {
+        Some(vid) => state
             .doc_repo
             .get_virtual_files(&doc_id, vid as i32)
             .await
-            .unwrap_or_default()
-            .iter()
-            .map(VirtualFileRow::from_virtual_file)
-            .collect()
-    } else {
-        vec![]
+            .unwrap_or_default(),
+        None => vec![],
     };
+    let mut tables: Vec<TableRow> = all_virtual_files
+        .iter()
+        .filter_map(TableRow::from_virtual_file)
+        .collect();
+    tables.sort_by(|a, b| (a.page, &a.filename).cmp(&(b.page, &b.filename)));
+    let virtual_files: Vec<VirtualFileRow> = all_virtual_files
+        .iter()
+        .filter(|vf| vf.table_page().is_none())
+        .map(VirtualFileRow::from_virtual_file)
+        .collect();
+
+    // The searchable copy is made from the original, so it isn't offered
+    // where public mode serves a redacted copy
+    let serves_original = match current_version {
+        Some(version) => matches!(
+            redacted::public_copy(&state, &doc, version).await,
+            PublicCopy::Original
+        ),
+        None => true,
+    };
+    let searchable_pdf = match current_version_id.filter(|_| serves_original) {
+        Some(vid) => state
+            .doc_repo
+            .find_artifact(vid, ArtifactKind::SearchablePdf, SEARCHABLE_PDF_NAME)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    let redacted_pdf = match current_version_id.filter(|_| !state.public) {
+        Some(vid) => state
+            .doc_repo
+            .find_artifact(vid, ArtifactKind::RedactedPdf, REDACTED_PDF_NAME)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    let original_path = versions.first().map(|v| v.path.clone()).unwrap_or_default();
 
     let page_count: Option<u32> = match current_version_id {
         Some(vid) => state.doc_repo.count_pages(&doc_id, vid as i32).await.ok(),
         None => None,
     };
 
+    let ocr_quality = match current_version_id {
+        Some(vid) => state
+            .doc_repo
+            .get_ocr_quality(&doc_id, vid)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    let (ocr_quality_class, ocr_quality_label, ocr_quality_title) = match ocr_quality {
+        Some(q) => (
+            if q.poor_pages > 0 { "poor" } else { "" },
+            format!("OCR quality {}%", (q.score * 100.0).round()),
+            format!(
+                "Mean over {} scored pages; {} below {}%",
+                q.scored_pages,
+                q.poor_pages,
+                (POOR_OCR_QUALITY * 100.0).round()
+            ),
+        ),
+        None => ("", String::new(), String::new()),
+    };
+
     // Navigation helpers
     let (has_prev, prev_id_val, prev_title_val, prev_title_truncated) =
         if let Some(ref nav) = navigation {
@@ -183,16 +331,20 @@ pub async fn document_detail(
             && doc.current_version().is_some()
             && !find_sources_with_hash(
                 &state,
-                &doc.current_version().unwrap().content_hash,
+                doc.current_version().unwrap().dedup_hash(),
                 &doc.source_id,
             )
             .await
             .is_empty(),
+        requests,
+        relations,
         has_extracted_text: doc.extracted_text.is_some(),
         extracted_text_val: doc.extracted_text.clone().unwrap_or_default(),
         virtual_files: virtual_files.clone(),
         has_virtual_files: !virtual_files.is_empty(),
         virtual_files_count: virtual_files.len(),
+        has_tables: !tables.is_empty(),
+        tables,
         has_prev,
         prev_id_val,
         prev_title_val,
@@ -207,6 +359,18 @@ pub async fn document_detail(
         has_pages: page_count.is_some() && page_count.unwrap() > 0,
         page_count_val: page_count.unwrap_or(0),
         version_id_val: current_version_id.unwrap_or(0),
+        version_hash_val: current_version
+            .map(|v| v.content_hash.chars().take(CITATION_HASH_LEN).collect())
+            .unwrap_or_default(),
+        has_ocr_quality: ocr_quality.is_some(),
+        ocr_quality_class,
+        ocr_quality_label,
+        ocr_quality_title,
+        has_searchable_pdf: searchable_pdf.is_some(),
+        has_original: !original_path.is_empty(),
+        original_path,
+        has_redacted_pdf: redacted_pdf.is_some(),
+        redacted_pdf_path: redacted_pdf.map(|a| a.file_path).unwrap_or_default(),
     };
 
     Html(
@@ -214,6 +378,7 @@ pub async fn document_detail(
             .render()
             .unwrap_or_else(|e| format!("Template error: {}", e)),
     )
+    .into_response()
 }
 
 /// Get document versions as JSON.
@@ -244,3 +409,84 @@ pub async fn document_versions(
 
     axum::Json(versions).into_response()
 }
+
+/// Download a table extracted from a document as CSV.
+pub async fn document_table(
+    State(state): State<AppState>,
+    Path((doc_id, table_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let table = match state.doc_repo.get_virtual_file(&table_id).await {
+        Ok(Some(vf)) if vf.document_id == doc_id && vf.table_page().is_some() => vf,
+        Ok(_) => return (StatusCode::NOT_FOUND, "Table not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", table.filename),
+            ),
+        ],
+        table.extracted_text.unwrap_or_default(),
+    )
+        .into_response()
+}
+
+/// Download the searchable copy of a document's current version.
+pub async fn document_searchable_pdf(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let Some(version) = doc.current_version() else {
+        return (StatusCode::NOT_FOUND, "Document has no versions").into_response();
+    };
+    if !matches!(
+        redacted::public_copy(&state, &doc, version).await,
+        PublicCopy::Original
+    ) {
+        return (StatusCode::NOT_FOUND, "No searchable PDF").into_response();
+    }
+    let artifact = match state
+        .doc_repo
+        .find_artifact(version.id, ArtifactKind::SearchablePdf, SEARCHABLE_PDF_NAME)
+        .await
+    {
+        Ok(Some(a)) => a,
+        Ok(None) => return (StatusCode::NOT_FOUND, "No searchable PDF").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let path = ArtifactStore::new(&state.documents_dir).resolve(&artifact.file_path);
+    let content = match tokio::fs::read(&path).await {
+        Ok(c) => c,
+        Err(_) => return (StatusCode::NOT_FOUND, "Searchable PDF missing").into_response(),
+    };
+    let stem = version
+        .original_filename
+        .as_deref()
+        .and_then(|f| std::path::Path::new(f).file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| doc.id.clone());
+    let filename = sanitize_filename(&format!("{}-searchable.pdf", stem));
+    access_log::record(&state, &doc.id, AccessKind::Download, &headers);
+
+    (
+        [
+            (header::CONTENT_TYPE, artifact.mime_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", filename),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
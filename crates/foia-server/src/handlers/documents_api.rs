@@ -11,25 +11,49 @@ use utoipa::{IntoParams, ToSchema};
 use super::super::AppState;
 use super::api_types::ApiResponse;
 use super::helpers::{
-    internal_error, not_found, paginate, parse_csv_param, DocumentSummary, PaginatedResponse,
+    bad_request, internal_error, not_found, paginate, parse_csv_param, parse_cursor_param,
+    parse_date_param, parse_years_param, CursorPaginatedResponse, DocumentSummary,
 };
-use foia::repository::diesel_document::BrowseParams;
+use foia::models::{BoundingBox, DocumentStatus};
+use foia::repository::diesel_document::{BrowseParams, ProcessingState};
 
 /// Query parameters for document search/listing.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct DocumentsQuery {
     /// Filter by source ID
     pub source: Option<String>,
-    /// Filter by document status (pending, downloaded, ocr_complete, indexed, failed)
+    /// Filter by document status (pending, downloaded, extracted, analyzed, reviewed, published, failed)
     pub status: Option<String>,
     /// Filter by MIME type categories (comma-separated: documents,spreadsheets,images)
     pub types: Option<String>,
     /// Filter by tags (comma-separated)
     pub tags: Option<String>,
+    /// Filter by record types (comma-separated: correspondence,contract,invoice)
+    pub record_types: Option<String>,
+    /// Only documents with pages whose OCR quality scored below 0.5
+    #[serde(default)]
+    pub poor_ocr: bool,
+    /// Filter by exemption codes cited (comma-separated: (b)(6),(b)(7)(C))
+    pub exemptions: Option<String>,
+    /// Filter by the software that wrote the PDF, e.g. `Adobe PDF Library 15.0`
+    pub pdf_producer: Option<String>,
+    /// Filter by PDF creation years (comma-separated: 2019,2021)
+    pub pdf_years: Option<String>,
+    /// Only PDFs created after the date the document claims to be from
+    #[serde(default)]
+    pub pdf_postdated: bool,
+    /// Filter by processing state (needs_ocr, ocr_failed, needs_summary, poor_ocr, unsupported)
+    pub processing: Option<String>,
+    /// Earliest document date (YYYY-MM-DD); falls back to acquisition date
+    pub start: Option<String>,
+    /// Latest document date (YYYY-MM-DD); falls back to acquisition date
+    pub end: Option<String>,
+    /// Only documents placed within this map area (south,west,north,east)
+    pub bbox: Option<String>,
     /// Full-text search query
     pub q: Option<String>,
-    /// Page number (1-indexed)
-    pub page: Option<usize>,
+    /// Opaque cursor from a previous response's `next_cursor`/`prev_cursor`
+    pub cursor: Option<String>,
     /// Items per page (default: 50, max: 200)
     pub per_page: Option<usize>,
     /// Sort field (updated_at, created_at, title, file_size)
@@ -44,7 +68,7 @@ pub struct DocumentsQuery {
     path = "/api/documents",
     params(DocumentsQuery),
     responses(
-        (status = 200, description = "Paginated list of documents", body = PaginatedResponse<DocumentSummary>)
+        (status = 200, description = "Paginated list of documents", body = CursorPaginatedResponse<DocumentSummary>)
     ),
     tag = "Documents"
 )]
@@ -52,44 +76,70 @@ pub async fn list_documents(
     State(state): State<AppState>,
     Query(params): Query<DocumentsQuery>,
 ) -> impl IntoResponse {
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
     let types = parse_csv_param(params.types.as_ref());
     let tags = parse_csv_param(params.tags.as_ref());
+    let record_types = parse_csv_param(params.record_types.as_ref());
+    let exemptions = parse_csv_param(params.exemptions.as_ref());
+    let pdf_producers: Vec<String> = params
+        .pdf_producer
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+        .cloned()
+        .collect();
+    let pdf_years = parse_years_param(params.pdf_years.as_ref());
+    // Workflow stage names map to the stored ones
+    let status = params
+        .status
+        .as_deref()
+        .map(|s| DocumentStatus::from_str(s).map_or(s, |st| st.as_str()));
+    let processing = match params.processing.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(p) => match ProcessingState::from_str(p) {
+            Some(state) => Some(state),
+            None => return bad_request(
+                "processing must be needs_ocr, ocr_failed, needs_summary, poor_ocr or unsupported",
+            )
+            .into_response(),
+        },
+    };
 
-    let documents = match state
-        .doc_repo
-        .browse(BrowseParams {
-            source_id: params.source.as_deref(),
-            status: params.status.as_deref(),
-            categories: &types,
-            tags: &tags,
-            search_query: params.q.as_deref(),
-            sort_field: params.sort.as_deref(),
-            sort_order: params.order.as_deref(),
-            limit: per_page as u32,
-            offset: offset as u32,
-        })
-        .await
-    {
-        Ok(docs) => docs,
+    let filter = BrowseParams {
+        source_id: params.source.as_deref(),
+        status,
+        categories: &types,
+        tags: &tags,
+        record_types: &record_types,
+        poor_ocr: params.poor_ocr,
+        exemptions: &exemptions,
+        pdf_producers: &pdf_producers,
+        pdf_years: &pdf_years,
+        pdf_postdated: params.pdf_postdated,
+        processing,
+        date_from: parse_date_param(params.start.as_ref()),
+        date_to: parse_date_param(params.end.as_ref()),
+        bbox: params.bbox.as_deref().and_then(BoundingBox::parse),
+        search_query: params.q.as_deref(),
+        sort_field: params.sort.as_deref(),
+        sort_order: params.order.as_deref(),
+        limit: per_page as u32,
+        cursor: parse_cursor_param(params.cursor.as_ref()),
+    };
+
+    let page = match state.doc_repo.browse(filter.clone()).await {
+        Ok(page) => page,
         Err(e) => return internal_error(e).into_response(),
     };
 
     let total = state
         .doc_repo
-        .browse_count(
-            params.source.as_deref(),
-            params.status.as_deref(),
-            &types,
-            &tags,
-            params.q.as_deref(),
-        )
+        .browse_count(&filter)
         .await
-        .unwrap_or(documents.len() as u64);
+        .unwrap_or(page.items.len() as u64);
 
-    let items: Vec<DocumentSummary> = documents.into_iter().map(DocumentSummary::from).collect();
+    let page = page.map(DocumentSummary::from);
 
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    Json(CursorPaginatedResponse::new(page, per_page, total)).into_response()
 }
 
 /// Get a single document by ID.
@@ -10,8 +10,11 @@ use utoipa::{IntoParams, ToSchema};
 
 use super::super::AppState;
 use super::api_types::ApiResponse;
-use super::helpers::{bad_request, internal_error, not_found, paginate, PaginatedResponse};
+use super::helpers::{
+    bad_request, internal_error, not_found, paginate, parse_cursor_param, CursorPaginatedResponse,
+};
 use foia::repository::diesel_document::entities::EntityFilter;
+use foia::repository::Page;
 #[cfg(feature = "gis")]
 use foia::services::geolookup;
 
@@ -32,8 +35,8 @@ pub struct EntitySearchQuery {
     pub near_location: Option<String>,
     /// Filter by source
     pub source: Option<String>,
-    /// Page number (1-indexed)
-    pub page: Option<usize>,
+    /// Opaque cursor from a previous response's `next_cursor`/`prev_cursor`
+    pub cursor: Option<String>,
     /// Items per page (default: 50, max: 200)
     pub per_page: Option<usize>,
 }
@@ -50,7 +53,8 @@ pub struct TopEntitiesQuery {
 /// Query parameters for location listing.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct LocationsQuery {
-    pub page: Option<usize>,
+    /// Opaque cursor from a previous response's `next_cursor`/`prev_cursor`
+    pub cursor: Option<String>,
     pub per_page: Option<usize>,
 }
 
@@ -101,7 +105,7 @@ pub struct GeocodedLocation {
     path = "/api/entities/search",
     params(EntitySearchQuery),
     responses(
-        (status = 200, description = "Paginated entity search results", body = PaginatedResponse<EntitySearchResult>),
+        (status = 200, description = "Paginated entity search results", body = CursorPaginatedResponse<EntitySearchResult>),
         (status = 400, description = "Missing search parameters")
     ),
     tag = "Entities"
@@ -167,7 +171,8 @@ pub async fn search_entities(
         .into_response();
     }
 
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
     let total = match state
         .doc_repo
@@ -180,19 +185,19 @@ pub async fn search_entities(
 
     let doc_ids = match state
         .doc_repo
-        .search_by_entities(&filters, params.source.as_deref(), per_page, offset)
+        .search_by_entities(
+            &filters,
+            params.source.as_deref(),
+            cursor.as_ref(),
+            per_page,
+        )
         .await
     {
         Ok(ids) => ids,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let items = match build_search_results(&state, &doc_ids).await {
-        Ok(items) => items,
-        Err(e) => return internal_error(e).into_response(),
-    };
-
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    search_results_response(&state, doc_ids, per_page, total).await
 }
 
 /// Get entity type breakdown with counts.
@@ -293,7 +298,7 @@ pub async fn document_entities(
     path = "/api/entities/locations",
     params(LocationsQuery),
     responses(
-        (status = 200, description = "Geocoded locations", body = PaginatedResponse<GeocodedLocation>)
+        (status = 200, description = "Geocoded locations", body = CursorPaginatedResponse<GeocodedLocation>)
     ),
     tag = "Entities"
 )]
@@ -301,29 +306,31 @@ pub async fn entity_locations(
     State(state): State<AppState>,
     Query(params): Query<LocationsQuery>,
 ) -> impl IntoResponse {
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
     let total = match state.doc_repo.count_geocoded_entities().await {
         Ok(c) => c,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let entities = match state.doc_repo.get_geocoded_entities(per_page, offset).await {
+    let entities = match state
+        .doc_repo
+        .get_geocoded_entities(cursor.as_ref(), per_page)
+        .await
+    {
         Ok(e) => e,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let items: Vec<GeocodedLocation> = entities
-        .into_iter()
-        .map(|e| GeocodedLocation {
-            entity_text: e.entity_text,
-            latitude: e.latitude.unwrap_or(0.0),
-            longitude: e.longitude.unwrap_or(0.0),
-            document_id: e.document_id,
-        })
-        .collect();
-
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    let page = entities.map(|e| GeocodedLocation {
+        entity_text: e.entity_text,
+        latitude: e.latitude.unwrap_or(0.0),
+        longitude: e.longitude.unwrap_or(0.0),
+        document_id: e.document_id,
+    });
+
+    Json(CursorPaginatedResponse::new(page, per_page, total)).into_response()
 }
 
 async fn handle_near_query(
@@ -349,7 +356,8 @@ async fn handle_near_query(
         Err(_) => return bad_request("Invalid radius in 'near'").into_response(),
     };
 
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
     let total = match state
         .doc_repo
@@ -362,19 +370,14 @@ async fn handle_near_query(
 
     let doc_ids = match state
         .doc_repo
-        .search_near_location(lat, lon, radius_km, per_page, offset)
+        .search_near_location(lat, lon, radius_km, cursor.as_ref(), per_page)
         .await
     {
         Ok(ids) => ids,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let items = match build_search_results(state, &doc_ids).await {
-        Ok(items) => items,
-        Err(e) => return internal_error(e).into_response(),
-    };
-
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    search_results_response(state, doc_ids, per_page, total).await
 }
 
 #[cfg(feature = "gis")]
@@ -410,7 +413,8 @@ async fn handle_near_location_query(
         }
     };
 
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
     let total = match state
         .doc_repo
@@ -423,19 +427,34 @@ async fn handle_near_location_query(
 
     let doc_ids = match state
         .doc_repo
-        .search_near_location(lat, lon, radius_km, per_page, offset)
+        .search_near_location(lat, lon, radius_km, cursor.as_ref(), per_page)
         .await
     {
         Ok(ids) => ids,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let items = match build_search_results(state, &doc_ids).await {
+    search_results_response(state, doc_ids, per_page, total).await
+}
+
+/// Build search results for one page of document IDs, keeping its cursors.
+async fn search_results_response(
+    state: &AppState,
+    doc_ids: Page<String>,
+    per_page: usize,
+    total: u64,
+) -> axum::response::Response {
+    let items = match build_search_results(state, &doc_ids.items).await {
         Ok(items) => items,
         Err(e) => return internal_error(e).into_response(),
     };
+    let page = Page {
+        items,
+        next_cursor: doc_ids.next_cursor,
+        prev_cursor: doc_ids.prev_cursor,
+    };
 
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    Json(CursorPaginatedResponse::new(page, per_page, total)).into_response()
 }
 
 async fn build_search_results(
@@ -0,0 +1,244 @@
+//! FOIA exemption analytics page and API.
+
+use askama::Template;
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::template_structs::{
+    ErrorTemplate, ExemptionPivotRow, ExemptionTotalRow, ExemptionsTemplate,
+};
+use super::super::AppState;
+use super::helpers::internal_error;
+use foia::services::exemption_report::{self, ExemptionBreakdown};
+
+/// Topics shown on the analytics page; the CSV export has them all.
+const MAX_TOPICS: usize = 25;
+
+/// Query params for exemption analytics.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExemptionsQuery {
+    /// Only documents from this source
+    pub source: Option<String>,
+    /// `json` (default) or `csv`
+    pub format: Option<String>,
+}
+
+/// Citations of one exemption.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExemptionCountResponse {
+    pub code: String,
+    /// Documents citing the exemption
+    pub documents: u64,
+    /// Redactions citing it
+    pub citations: u64,
+}
+
+/// Citations of one exemption within an agency, year or topic.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExemptionBreakdownResponse {
+    pub group: String,
+    pub code: String,
+    pub documents: u64,
+    pub citations: u64,
+}
+
+impl From<ExemptionBreakdown> for ExemptionBreakdownResponse {
+    fn from(b: ExemptionBreakdown) -> Self {
+        Self {
+            group: b.group,
+            code: b.code,
+            documents: b.documents,
+            citations: b.citations,
+        }
+    }
+}
+
+/// Exemption codes cited across the archive.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExemptionReportResponse {
+    /// Documents citing any exemption
+    pub documents: u64,
+    /// Per code, most cited first
+    pub totals: Vec<ExemptionCountResponse>,
+    pub by_agency: Vec<ExemptionBreakdownResponse>,
+    /// Grouped by year of the document date
+    pub by_year: Vec<ExemptionBreakdownResponse>,
+    /// Grouped by `topic:` tag
+    pub by_topic: Vec<ExemptionBreakdownResponse>,
+}
+
+/// Exemption codes cited in redactions, by agency, year and topic.
+#[utoipa::path(
+    get,
+    path = "/api/exemptions",
+    params(ExemptionsQuery),
+    responses(
+        (status = 200, description = "Exemption analytics, or CSV with format=csv", body = ExemptionReportResponse)
+    ),
+    tag = "Documents"
+)]
+pub async fn api_exemptions(
+    State(state): State<AppState>,
+    Query(params): Query<ExemptionsQuery>,
+) -> impl IntoResponse {
+    let report = match exemption_report::exemption_report(
+        &state.doc_repo,
+        params.source.as_deref().filter(|s| !s.is_empty()),
+    )
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => return internal_error(e).into_response(),
+    };
+
+    if params.format.as_deref() == Some("csv") {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/csv")
+            .header(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"exemptions.csv\"",
+            )
+            .body(Body::from(report.to_csv()))
+            .unwrap()
+            .into_response();
+    }
+
+    Json(ExemptionReportResponse {
+        documents: report.documents,
+        totals: report
+            .totals
+            .into_iter()
+            .map(|t| ExemptionCountResponse {
+                code: t.code,
+                documents: t.documents,
+                citations: t.citations,
+            })
+            .collect(),
+        by_agency: report.by_agency.into_iter().map(Into::into).collect(),
+        by_year: report.by_year.into_iter().map(Into::into).collect(),
+        by_topic: report.by_topic.into_iter().map(Into::into).collect(),
+    })
+    .into_response()
+}
+
+/// Lay breakdown rows out as one row per group with a citation count per
+/// code, in `codes` order.
+fn pivot(rows: &[ExemptionBreakdown], codes: &[String]) -> Vec<ExemptionPivotRow> {
+    let mut pivoted: Vec<ExemptionPivotRow> = Vec::new();
+    for row in rows {
+        let Some(col) = codes.iter().position(|c| *c == row.code) else {
+            continue;
+        };
+        if pivoted.last().map(|p| &p.group) != Some(&row.group) {
+            pivoted.push(ExemptionPivotRow {
+                group: row.group.clone(),
+                citations: vec![0; codes.len()],
+                total: 0,
+            });
+        }
+        let last = pivoted.last_mut().expect("pushed above");
+        last.citations[col] += row.citations;
+        last.total += row.citations;
+    }
+    pivoted
+}
+
+/// Exemption analytics page.
+pub async fn exemption_analytics(
+    State(state): State<AppState>,
+    Query(params): Query<ExemptionsQuery>,
+) -> impl IntoResponse {
+    let source_id = params.source.as_deref().filter(|s| !s.is_empty());
+    let report = match exemption_report::exemption_report(&state.doc_repo, source_id).await {
+        Ok(r) => r,
+        Err(e) => {
+            let msg = format!("Failed to load exemption statistics: {}", e);
+            let template = ErrorTemplate {
+                title: "Error",
+                message: &msg,
+            };
+            return Html(template.render().unwrap_or(msg));
+        }
+    };
+
+    let mut codes: Vec<String> = report.totals.iter().map(|t| t.code.clone()).collect();
+    codes.sort();
+    let mut topics = pivot(&report.by_topic, &codes);
+    topics.sort_by(|a, b| b.total.cmp(&a.total).then(a.group.cmp(&b.group)));
+    topics.truncate(MAX_TOPICS);
+
+    let csv_url = match source_id {
+        Some(sid) => format!(
+            "/api/exemptions?format=csv&source={}",
+            urlencoding::encode(sid)
+        ),
+        None => "/api/exemptions?format=csv".to_string(),
+    };
+
+    let template = ExemptionsTemplate {
+        title: "Exemptions Cited",
+        source_id: source_id.unwrap_or_default().to_string(),
+        documents: report.documents,
+        totals: report
+            .totals
+            .iter()
+            .map(|t| ExemptionTotalRow {
+                browse_url: format!("/?exemptions={}", urlencoding::encode(&t.code)),
+                code: t.code.clone(),
+                documents: t.documents,
+                citations: t.citations,
+            })
+            .collect(),
+        agencies: pivot(&report.by_agency, &codes),
+        years: pivot(&report.by_year, &codes),
+        topics,
+        codes,
+        csv_url,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breakdown(group: &str, code: &str, citations: u64) -> ExemptionBreakdown {
+        ExemptionBreakdown {
+            group: group.to_string(),
+            code: code.to_string(),
+            documents: 1,
+            citations,
+        }
+    }
+
+    #[test]
+    fn test_pivot() {
+        let codes = vec!["(b)(1)".to_string(), "(b)(6)".to_string()];
+        let rows = pivot(
+            &[
+                breakdown("1971", "(b)(1)", 2),
+                breakdown("1971", "(b)(6)", 3),
+                breakdown("1972", "(b)(6)", 1),
+            ],
+            &codes,
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].citations, vec![2, 3]);
+        assert_eq!(rows[0].total, 5);
+        assert_eq!(rows[1].group, "1972");
+        assert_eq!(rows[1].citations, vec![0, 1]);
+    }
+}
@@ -13,6 +13,7 @@ use utoipa::{IntoParams, ToSchema};
 use super::super::AppState;
 use super::api_types::{AnnotationExport, ApiResponse, ExportStatsResponse};
 use super::helpers::{internal_error, parse_csv_param};
+use foia::models::Document;
 use foia::repository::diesel_document::BrowseParams;
 
 /// Export format options.
@@ -25,6 +26,24 @@ pub enum ExportFormat {
     Csv,
 }
 
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv",
+        }
+    }
+}
+
 /// Query params for export.
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct ExportQuery {
@@ -93,11 +112,27 @@ pub async fn export_documents(
         })
         .await
     {
-        Ok(docs) => docs,
+        Ok(page) => page.items,
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let export_docs: Vec<ExportDocument> = documents
+    let export_docs = export_records(documents, params.include_text);
+    let format = params.format;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"documents.{}\"", format.extension()),
+        )
+        .body(Body::from(render_export(&export_docs, format)))
+        .unwrap()
+        .into_response()
+}
+
+/// Convert documents to export records.
+pub(crate) fn export_records(documents: Vec<Document>, include_text: bool) -> Vec<ExportDocument> {
+    documents
         .into_iter()
         .map(|doc| {
             let (mime_type, file_size, page_count, content_hash) =
@@ -125,46 +160,28 @@ pub async fn export_documents(
                 file_size,
                 page_count,
                 content_hash,
-                extracted_text: if params.include_text {
+                extracted_text: if include_text {
                     doc.extracted_text
                 } else {
                     None
                 },
             }
         })
-        .collect();
+        .collect()
+}
 
-    match params.format {
-        ExportFormat::Json => {
-            let json = serde_json::to_string_pretty(&export_docs).unwrap_or_default();
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, "application/json")
-                .header(
-                    header::CONTENT_DISPOSITION,
-                    "attachment; filename=\"documents.json\"",
-                )
-                .body(Body::from(json))
-                .unwrap()
-                .into_response()
-        }
+/// Serialize export records in `format`.
+pub(crate) fn render_export(export_docs: &[ExportDocument], format: ExportFormat) -> Vec<u8> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(export_docs).unwrap_or_default(),
         ExportFormat::Jsonl => {
             let mut output = Vec::new();
-            for doc in &export_docs {
+            for doc in export_docs {
                 if let Ok(line) = serde_json::to_string(doc) {
                     writeln!(output, "{}", line).ok();
                 }
             }
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, "application/x-ndjson")
-                .header(
-                    header::CONTENT_DISPOSITION,
-                    "attachment; filename=\"documents.jsonl\"",
-                )
-                .body(Body::from(output))
-                .unwrap()
-                .into_response()
+            output
         }
         ExportFormat::Csv => {
             let mut output = Vec::new();
@@ -174,7 +191,7 @@ pub async fn export_documents(
             )
             .ok();
 
-            for doc in &export_docs {
+            for doc in export_docs {
                 let tags_str = doc.tags.join(";");
                 let synopsis_escaped = doc
                     .synopsis
@@ -202,17 +219,7 @@ pub async fn export_documents(
                 )
                 .ok();
             }
-
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, "text/csv")
-                .header(
-                    header::CONTENT_DISPOSITION,
-                    "attachment; filename=\"documents.csv\"",
-                )
-                .body(Body::from(output))
-                .unwrap()
-                .into_response()
+            output
         }
     }
 }
@@ -284,7 +291,7 @@ pub async fn export_annotations(
         })
         .await
     {
-        Ok(docs) => docs,
+        Ok(page) => page.items,
         Err(e) => return internal_error(e).into_response(),
     };
 
@@ -0,0 +1,147 @@
+//! Failure triage page and bulk retry endpoint.
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use super::super::template_structs::{ErrorTemplate, FailureRow, FailuresTemplate, SourceOption};
+use super::super::AppState;
+use super::api_types::{ApiResponse, RetryResponse};
+use super::helpers::{bad_request, internal_error};
+use foia::services::failures::{self, ErrorClass, FailureGroup, FailureKind, TREND_DAYS};
+use foia::utils::sparkline;
+
+/// Query parameters for the failures page.
+#[derive(Debug, Deserialize)]
+pub struct FailuresParams {
+    pub source: Option<String>,
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn failure_rows(groups: Vec<FailureGroup>) -> Vec<FailureRow> {
+    groups
+        .into_iter()
+        .map(|g| FailureRow {
+            class: g.class.as_str(),
+            label: g.class.label(),
+            count: g.count,
+            trend: sparkline(&g.daily),
+            sample: g.sample,
+        })
+        .collect()
+}
+
+/// Failed crawl URLs and analysis results grouped by error class.
+pub async fn list_failures(
+    State(state): State<AppState>,
+    Query(params): Query<FailuresParams>,
+) -> impl IntoResponse {
+    let source_id = params.source.filter(|s| !s.is_empty());
+
+    let report =
+        match failures::failure_report(&state.crawl_repo, &state.doc_repo, source_id.as_deref())
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => return render_error(&format!("Failed to load failures: {}", e)),
+        };
+
+    let sources: Vec<SourceOption> = state
+        .source_repo
+        .get_all()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| SourceOption {
+            selected: source_id.as_deref() == Some(s.id.as_str()),
+            id: s.id,
+            name: s.name,
+            count: 0,
+        })
+        .collect();
+
+    let crawl_total = report.crawl.iter().map(|g| g.count).sum();
+    let analysis_total = report.analysis.iter().map(|g| g.count).sum();
+    let template = FailuresTemplate {
+        title: "Failures",
+        sources,
+        source: source_id.as_deref().unwrap_or(""),
+        trend_days: TREND_DAYS,
+        crawl: failure_rows(report.crawl),
+        crawl_total,
+        analysis: failure_rows(report.analysis),
+        analysis_total,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// Retry all failures in one error class.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RetryClassRequest {
+    /// Error class, e.g. `dns`, `timeout`, `not_found`, `tool_missing`, `quarantined`.
+    pub class: String,
+    /// `crawl` or `analysis`; both when omitted.
+    pub kind: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Re-queue every failed crawl URL and/or analysis result in an error class.
+#[utoipa::path(
+    post,
+    path = "/api/failures/retry",
+    request_body = RetryClassRequest,
+    responses(
+        (status = 200, description = "Retry result", body = RetryResponse),
+        (status = 400, description = "Unknown class or kind")
+    ),
+    tag = "Scrapers"
+)]
+pub async fn retry_failure_class(
+    State(state): State<AppState>,
+    Json(body): Json<RetryClassRequest>,
+) -> impl IntoResponse {
+    let Some(class) = ErrorClass::from_str(&body.class) else {
+        return bad_request("Unknown error class").into_response();
+    };
+    let kind = match body.kind.as_deref().filter(|k| !k.is_empty()) {
+        Some(k) => match FailureKind::from_str(k) {
+            Some(kind) => Some(kind),
+            None => return bad_request("Unknown failure kind").into_response(),
+        },
+        None => None,
+    };
+
+    match failures::retry_class(
+        &state.crawl_repo,
+        &state.doc_repo,
+        class,
+        kind,
+        body.source.as_deref().filter(|s| !s.is_empty()),
+    )
+    .await
+    {
+        Ok(count) => ApiResponse::ok(RetryResponse {
+            reset_count: count,
+            message: format!("Re-queued {} {} failures", count, class.label()),
+        })
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
@@ -0,0 +1,265 @@
+//! Document flags: readers' reports and the curators' moderation queue.
+
+use askama::Template;
+use axum::{
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use foia::models::{DocumentFlag, FlagReason, FlagStatus, MAX_FLAG_NOTE_LEN};
+
+use super::super::template_structs::{ErrorTemplate, FlagRow, FlagsTemplate};
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+
+/// Flags listed on the page and in the API.
+const LIST_LIMIT: usize = 500;
+
+/// Report a document.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFlagRequest {
+    /// `pii`, `redaction`, `wrong_document` or `other`.
+    pub reason: String,
+    /// Page the report is about.
+    pub page: Option<u32>,
+    /// What was found, at most 2000 characters.
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Resolve, dismiss or reopen a flag.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateFlagRequest {
+    /// `open`, `resolved` or `dismissed`.
+    pub status: String,
+    /// What was done about it.
+    pub resolution: Option<String>,
+}
+
+/// Query for listing flags.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FlagsQuery {
+    /// `open` (default), `resolved`, `dismissed` or `all`.
+    pub status: Option<String>,
+}
+
+/// A flag against a document.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FlagResponse {
+    pub id: i32,
+    pub document_id: String,
+    pub reason: String,
+    pub page: Option<u32>,
+    pub note: String,
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,
+}
+
+impl From<DocumentFlag> for FlagResponse {
+    fn from(flag: DocumentFlag) -> Self {
+        Self {
+            id: flag.id,
+            document_id: flag.document_id,
+            reason: flag.reason.as_str().to_string(),
+            page: flag.page,
+            note: flag.note,
+            status: flag.status.as_str().to_string(),
+            created_at: flag.created_at.to_rfc3339(),
+            resolved_at: flag.resolved_at.map(|t| t.to_rfc3339()),
+            resolution: flag.resolution,
+        }
+    }
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+/// The status filter from a query: None for all.
+fn parse_status_filter(status: Option<&str>) -> Result<Option<FlagStatus>, &'static str> {
+    match status.unwrap_or("open") {
+        "all" => Ok(None),
+        s => FlagStatus::from_str(s)
+            .map(Some)
+            .ok_or("status must be open, resolved, dismissed or all"),
+    }
+}
+
+/// Moderation queue: flags with the documents they are against.
+pub async fn flags_page(
+    State(state): State<AppState>,
+    Query(query): Query<FlagsQuery>,
+) -> impl IntoResponse {
+    let status = match parse_status_filter(query.status.as_deref()) {
+        Ok(status) => status,
+        Err(msg) => return render_error(msg),
+    };
+    let flags = match state.flags.list(status, LIST_LIMIT).await {
+        Ok(flags) => flags,
+        Err(e) => return render_error(&format!("Failed to load flags: {}", e)),
+    };
+    let open_count = match state.flags.count_open().await {
+        Ok(count) => count,
+        Err(e) => return render_error(&format!("Failed to load flags: {}", e)),
+    };
+
+    let mut rows = Vec::with_capacity(flags.len());
+    for flag in flags {
+        let title = match state.doc_repo.get(&flag.document_id).await {
+            Ok(Some(doc)) => doc.title,
+            _ => flag.document_id.clone(),
+        };
+        rows.push(FlagRow {
+            id: flag.id,
+            title,
+            reason: flag.reason.label(),
+            page: flag.page.map(|p| p.to_string()).unwrap_or_default(),
+            note: flag.note,
+            status: flag.status.as_str(),
+            created_at: flag.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            resolution: flag.resolution.unwrap_or_default(),
+            document_id: flag.document_id,
+        });
+    }
+
+    let template = FlagsTemplate {
+        title: "Flags",
+        status: status.map_or("all", |s| s.as_str()),
+        statuses: FlagStatus::ALL
+            .iter()
+            .map(FlagStatus::as_str)
+            .chain(["all"])
+            .collect(),
+        open_count,
+        flags: rows,
+    };
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// Report a document: personal information, a missed redaction or the
+/// wrong file.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{doc_id}/flags",
+    params(("doc_id" = String, Path, description = "Document ID")),
+    request_body = CreateFlagRequest,
+    responses(
+        (status = 200, description = "Flag opened", body = FlagResponse),
+        (status = 400, description = "Unknown reason or overlong note"),
+        (status = 404, description = "Document not found")
+    ),
+    tag = "Flags"
+)]
+pub async fn api_create_flag(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    Json(body): Json<CreateFlagRequest>,
+) -> impl IntoResponse {
+    let Some(reason) = FlagReason::from_str(&body.reason) else {
+        return bad_request("reason must be pii, redaction, wrong_document or other")
+            .into_response();
+    };
+    let note = body.note.trim();
+    if note.chars().count() > MAX_FLAG_NOTE_LEN {
+        return bad_request("note must be at most 2000 characters").into_response();
+    }
+    match state.doc_repo.get(&doc_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found("Document not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    match state.flags.create(&doc_id, reason, body.page, note).await {
+        Ok(flag) => {
+            tracing::info!(
+                "Document {} flagged ({}) for review",
+                doc_id,
+                reason.as_str()
+            );
+            ApiResponse::ok(FlagResponse::from(flag)).into_response()
+        }
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// List flags, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/flags",
+    params(FlagsQuery),
+    responses(
+        (status = 200, description = "Flags, newest first", body = Vec<FlagResponse>),
+        (status = 400, description = "Unknown status")
+    ),
+    tag = "Flags"
+)]
+pub async fn api_list_flags(
+    State(state): State<AppState>,
+    Query(query): Query<FlagsQuery>,
+) -> impl IntoResponse {
+    let status = match parse_status_filter(query.status.as_deref()) {
+        Ok(status) => status,
+        Err(msg) => return bad_request(msg).into_response(),
+    };
+    match state.flags.list(status, LIST_LIMIT).await {
+        Ok(flags) => ApiResponse::ok(
+            flags
+                .into_iter()
+                .map(FlagResponse::from)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Resolve, dismiss or reopen a flag.
+#[utoipa::path(
+    put,
+    path = "/api/flags/{id}",
+    params(("id" = i32, Path, description = "Flag ID")),
+    request_body = UpdateFlagRequest,
+    responses(
+        (status = 200, description = "Updated flag", body = FlagResponse),
+        (status = 400, description = "Unknown status"),
+        (status = 404, description = "Flag not found")
+    ),
+    tag = "Flags"
+)]
+pub async fn api_update_flag(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    Json(body): Json<UpdateFlagRequest>,
+) -> impl IntoResponse {
+    let Some(status) = FlagStatus::from_str(&body.status) else {
+        return bad_request("status must be open, resolved or dismissed").into_response();
+    };
+    let resolution = body
+        .resolution
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    match state.flags.set_status(id, status, resolution).await {
+        Ok(true) => {}
+        Ok(false) => return not_found("Flag not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    match state.flags.get(id).await {
+        Ok(Some(flag)) => ApiResponse::ok(FlagResponse::from(flag)).into_response(),
+        Ok(None) => not_found("Flag not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
@@ -0,0 +1,601 @@
+//! FOIA request tracking pages and API.
+//!
+//! Reading requests is part of the archive (where documents came from);
+//! creating, editing and linking them are admin routes.
+
+use askama::Template;
+use axum::{
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::template_structs::{
+    ErrorTemplate, FoiaRequestDetailTemplate, FoiaRequestRow, FoiaRequestsTemplate,
+    LinkedDocumentRow, UrlStatusTab,
+};
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+use foia::config::requests::FEDERAL_JURISDICTION;
+use foia::config::RequestsConfig;
+use foia::models::{DeadlineRule, FoiaRequest, FoiaRequestStatus};
+
+/// Query parameters for listing requests.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FoiaRequestsQuery {
+    /// Only requests in this status (filed, acknowledged, fulfilled, appealed)
+    pub status: Option<String>,
+    /// Only requests past their response deadline
+    #[serde(default)]
+    pub overdue: bool,
+}
+
+/// Record a newly filed request.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateFoiaRequestRequest {
+    pub agency: String,
+    pub subject: String,
+    #[serde(default)]
+    pub request_text: String,
+    pub tracking_number: Option<String>,
+    pub source_id: Option<String>,
+    /// Whose deadline rules apply (default: the configured default).
+    pub jurisdiction: Option<String>,
+    /// Filing date, YYYY-MM-DD (default: today).
+    pub filed_on: Option<String>,
+}
+
+/// Changes to a request; omitted fields are left alone and empty strings
+/// clear optional ones.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateFoiaRequestRequest {
+    /// New status (filed, acknowledged, fulfilled, appealed).
+    pub status: Option<String>,
+    /// Date of the status change, YYYY-MM-DD (default: today).
+    pub date: Option<String>,
+    pub agency: Option<String>,
+    pub subject: Option<String>,
+    pub request_text: Option<String>,
+    pub tracking_number: Option<String>,
+    pub source_id: Option<String>,
+    pub jurisdiction: Option<String>,
+    pub notes: Option<String>,
+}
+
+/// Documents to link to a request.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LinkDocumentsRequest {
+    pub document_ids: Vec<String>,
+}
+
+/// A FOIA request with its deadline.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FoiaRequestResponse {
+    pub id: String,
+    pub agency: String,
+    pub subject: String,
+    pub request_text: String,
+    pub tracking_number: Option<String>,
+    pub source_id: Option<String>,
+    /// Whose deadline rules apply; null uses the configured default.
+    pub jurisdiction: Option<String>,
+    pub status: String,
+    pub filed_on: String,
+    pub acknowledged_on: Option<String>,
+    pub fulfilled_on: Option<String>,
+    pub appealed_on: Option<String>,
+    /// When the agency's answer is due; null once fulfilled.
+    pub due_on: Option<String>,
+    /// Days until the answer is due, negative when overdue.
+    pub days_until_due: Option<i64>,
+    pub overdue: bool,
+    /// When an overdue reminder was last sent.
+    pub reminded_on: Option<String>,
+    pub notes: Option<String>,
+    /// Documents received in response.
+    pub document_ids: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl FoiaRequestResponse {
+    fn new(request: FoiaRequest, rule: &DeadlineRule, document_ids: Vec<String>) -> Self {
+        let today = Utc::now().date_naive();
+        Self {
+            due_on: request.due_on(rule).map(|d| d.to_string()),
+            days_until_due: request.days_until_due(rule, today),
+            overdue: request.is_overdue(rule, today),
+            id: request.id,
+            agency: request.agency,
+            subject: request.subject,
+            request_text: request.request_text,
+            tracking_number: request.tracking_number,
+            source_id: request.source_id,
+            jurisdiction: request.jurisdiction,
+            status: request.status.as_str().to_string(),
+            filed_on: request.filed_on.to_string(),
+            acknowledged_on: request.acknowledged_on.map(|d| d.to_string()),
+            fulfilled_on: request.fulfilled_on.map(|d| d.to_string()),
+            appealed_on: request.appealed_on.map(|d| d.to_string()),
+            reminded_on: request.reminded_on.map(|d| d.to_string()),
+            notes: request.notes,
+            document_ids,
+            created_at: request.created_at.to_rfc3339(),
+            updated_at: request.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn parse_date(s: Option<&str>) -> Result<NaiveDate, &'static str> {
+    match s.filter(|s| !s.is_empty()) {
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| "Dates must be YYYY-MM-DD"),
+        None => Ok(Utc::now().date_naive()),
+    }
+}
+
+fn parse_status(s: Option<&str>) -> Result<Option<FoiaRequestStatus>, &'static str> {
+    match s.filter(|s| !s.is_empty()) {
+        Some(s) => FoiaRequestStatus::from_str(s)
+            .map(Some)
+            .ok_or("Unknown request status"),
+        None => Ok(None),
+    }
+}
+
+/// Empty strings clear an optional field.
+fn non_empty(s: String) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+/// Jurisdictions requests can be filed under, federal first.
+fn jurisdictions(config: &RequestsConfig) -> Vec<String> {
+    let mut names: Vec<String> = config
+        .jurisdictions
+        .keys()
+        .filter(|name| *name != FEDERAL_JURISDICTION)
+        .cloned()
+        .collect();
+    names.sort();
+    names.insert(0, FEDERAL_JURISDICTION.to_string());
+    names
+}
+
+/// Validate a jurisdiction from a request body; empty means the default.
+fn parse_jurisdiction(
+    config: &RequestsConfig,
+    name: Option<String>,
+) -> Result<Option<String>, &'static str> {
+    match name.and_then(non_empty) {
+        Some(name) if !config.has_jurisdiction(&name) => Err("Unknown jurisdiction"),
+        name => Ok(name),
+    }
+}
+
+fn rule_for(state: &AppState, request: &FoiaRequest) -> DeadlineRule {
+    state
+        .requests_config()
+        .rule(request.jurisdiction.as_deref())
+}
+
+/// Due date and days left, for display.
+fn due_label(request: &FoiaRequest, rule: &DeadlineRule, today: NaiveDate) -> String {
+    match (request.due_on(rule), request.days_until_due(rule, today)) {
+        (Some(due), Some(days)) if days < 0 => format!("{} ({} days overdue)", due, -days),
+        (Some(due), Some(days)) => format!("{} ({} days left)", due, days),
+        _ => "-".to_string(),
+    }
+}
+
+/// Tracked FOIA requests with their deadlines.
+pub async fn list_requests(
+    State(state): State<AppState>,
+    Query(params): Query<FoiaRequestsQuery>,
+) -> impl IntoResponse {
+    let status = match parse_status(params.status.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return render_error(e),
+    };
+    let all = match state.foia_requests.list(None).await {
+        Ok(r) => r,
+        Err(e) => return render_error(&format!("Failed to load requests: {}", e)),
+    };
+    let counts = state
+        .foia_requests
+        .document_counts()
+        .await
+        .unwrap_or_default();
+    let sources = state.source_repo.get_all().await.unwrap_or_default();
+    let today = Utc::now().date_naive();
+
+    let tabs = FoiaRequestStatus::ALL
+        .iter()
+        .map(|s| UrlStatusTab {
+            name: s.as_str(),
+            count: all.iter().filter(|r| r.status == *s).count() as u64,
+            active: status == Some(*s),
+        })
+        .collect();
+    let requests_config = state.requests_config();
+    let rules: Vec<DeadlineRule> = all
+        .iter()
+        .map(|r| requests_config.rule(r.jurisdiction.as_deref()))
+        .collect();
+    let overdue_count = all
+        .iter()
+        .zip(&rules)
+        .filter(|(r, rule)| r.is_overdue(rule, today))
+        .count();
+
+    let rows: Vec<FoiaRequestRow> = all
+        .iter()
+        .zip(&rules)
+        .filter(|(r, _)| status.is_none_or(|s| r.status == s))
+        .filter(|(r, rule)| !params.overdue || r.is_overdue(rule, today))
+        .map(|(r, rule)| FoiaRequestRow {
+            id: r.id.clone(),
+            agency: r.agency.clone(),
+            subject: r.subject.clone(),
+            tracking_number: r.tracking_number.clone().unwrap_or_default(),
+            status: r.status.as_str(),
+            filed_on: r.filed_on.to_string(),
+            due: due_label(r, rule, today),
+            overdue: r.is_overdue(rule, today),
+            documents: counts.get(&r.id).copied().unwrap_or(0),
+        })
+        .collect();
+
+    let template = FoiaRequestsTemplate {
+        title: "FOIA Requests",
+        tabs,
+        filtered: status.is_some() || params.overdue,
+        overdue_only: params.overdue,
+        overdue_count,
+        total: all.len(),
+        rows,
+        sources: sources.into_iter().map(|s| s.id).collect(),
+        jurisdictions: jurisdictions(&requests_config),
+        default_jurisdiction: requests_config.default_jurisdiction(),
+        today: today.to_string(),
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// One request: its text, deadline and the documents it produced.
+pub async fn request_detail(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let request = match state.foia_requests.get(&id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return render_error("Request not found"),
+        Err(e) => return render_error(&format!("Failed to load request: {}", e)),
+    };
+    let doc_ids = state
+        .foia_requests
+        .document_ids(&id)
+        .await
+        .unwrap_or_default();
+
+    let mut documents = Vec::with_capacity(doc_ids.len());
+    for doc_id in doc_ids {
+        let title = match state.doc_repo.get(&doc_id).await {
+            Ok(Some(doc)) => doc.title,
+            _ => "(missing)".to_string(),
+        };
+        documents.push(LinkedDocumentRow { id: doc_id, title });
+    }
+
+    let today = Utc::now().date_naive();
+    let requests_config = state.requests_config();
+    let rule = requests_config.rule(request.jurisdiction.as_deref());
+    let date = |d: Option<NaiveDate>| d.map(|d| d.to_string()).unwrap_or_default();
+    let title = format!("{}: {}", request.agency, request.subject);
+    let template = FoiaRequestDetailTemplate {
+        title: &title,
+        id: &request.id,
+        agency: &request.agency,
+        subject: &request.subject,
+        request_text: &request.request_text,
+        tracking_number: request.tracking_number.as_deref().unwrap_or_default(),
+        source_id: request.source_id.as_deref().unwrap_or_default(),
+        jurisdiction: request.jurisdiction.as_deref().unwrap_or_default(),
+        jurisdictions: jurisdictions(&requests_config),
+        default_jurisdiction: requests_config.default_jurisdiction(),
+        status: request.status.as_str(),
+        statuses: FoiaRequestStatus::ALL.iter().map(|s| s.as_str()).collect(),
+        filed_on: request.filed_on.to_string(),
+        acknowledged_on: date(request.acknowledged_on),
+        fulfilled_on: date(request.fulfilled_on),
+        appealed_on: date(request.appealed_on),
+        reminded_on: date(request.reminded_on),
+        due: due_label(&request, &rule, today),
+        overdue: request.is_overdue(&rule, today),
+        notes: request.notes.as_deref().unwrap_or_default(),
+        documents,
+        today: today.to_string(),
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// List tracked requests.
+#[utoipa::path(
+    get,
+    path = "/api/requests",
+    params(FoiaRequestsQuery),
+    responses(
+        (status = 200, description = "Requests, most recently filed first", body = Vec<FoiaRequestResponse>),
+        (status = 400, description = "Unknown status")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_list_requests(
+    State(state): State<AppState>,
+    Query(params): Query<FoiaRequestsQuery>,
+) -> impl IntoResponse {
+    let status = match parse_status(params.status.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return bad_request(e).into_response(),
+    };
+    let requests = match state.foia_requests.list(status).await {
+        Ok(r) => r,
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let today = Utc::now().date_naive();
+    let mut out = Vec::with_capacity(requests.len());
+    for request in requests {
+        let rule = rule_for(&state, &request);
+        if params.overdue && !request.is_overdue(&rule, today) {
+            continue;
+        }
+        let doc_ids = match state.foia_requests.document_ids(&request.id).await {
+            Ok(ids) => ids,
+            Err(e) => return internal_error(e).into_response(),
+        };
+        out.push(FoiaRequestResponse::new(request, &rule, doc_ids));
+    }
+    ApiResponse::ok(out).into_response()
+}
+
+/// Get a request with its deadline and linked documents.
+#[utoipa::path(
+    get,
+    path = "/api/requests/{id}",
+    params(("id" = String, Path, description = "Request ID")),
+    responses(
+        (status = 200, description = "Request", body = FoiaRequestResponse),
+        (status = 404, description = "Request not found")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_get_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    respond_with_request(&state, &id).await
+}
+
+async fn respond_with_request(state: &AppState, id: &str) -> axum::response::Response {
+    let request = match state.foia_requests.get(id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return not_found("Request not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let rule = rule_for(state, &request);
+    match state.foia_requests.document_ids(id).await {
+        Ok(ids) => ApiResponse::ok(FoiaRequestResponse::new(request, &rule, ids)).into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Record a newly filed request.
+#[utoipa::path(
+    post,
+    path = "/api/requests",
+    request_body = CreateFoiaRequestRequest,
+    responses(
+        (status = 200, description = "Created request", body = FoiaRequestResponse),
+        (status = 400, description = "Missing agency or subject, bad date or unknown jurisdiction")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_create_request(
+    State(state): State<AppState>,
+    Json(body): Json<CreateFoiaRequestRequest>,
+) -> impl IntoResponse {
+    let (Some(agency), Some(subject)) = (non_empty(body.agency), non_empty(body.subject)) else {
+        return bad_request("Agency and subject are required").into_response();
+    };
+    let filed_on = match parse_date(body.filed_on.as_deref()) {
+        Ok(d) => d,
+        Err(e) => return bad_request(e).into_response(),
+    };
+    let jurisdiction = match parse_jurisdiction(&state.requests_config(), body.jurisdiction) {
+        Ok(j) => j,
+        Err(e) => return bad_request(e).into_response(),
+    };
+    let mut request = FoiaRequest::new(agency, subject, filed_on);
+    request.request_text = body.request_text;
+    request.tracking_number = body.tracking_number.and_then(non_empty);
+    request.source_id = body.source_id.and_then(non_empty);
+    request.jurisdiction = jurisdiction;
+
+    let rule = rule_for(&state, &request);
+    match state.foia_requests.create(&request).await {
+        Ok(()) => ApiResponse::ok(FoiaRequestResponse::new(request, &rule, vec![])).into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Change a request's status or details.
+#[utoipa::path(
+    put,
+    path = "/api/requests/{id}",
+    params(("id" = String, Path, description = "Request ID")),
+    request_body = UpdateFoiaRequestRequest,
+    responses(
+        (status = 200, description = "Updated request", body = FoiaRequestResponse),
+        (status = 400, description = "Unknown status or jurisdiction, or bad date"),
+        (status = 404, description = "Request not found")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_update_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<UpdateFoiaRequestRequest>,
+) -> impl IntoResponse {
+    let mut request = match state.foia_requests.get(&id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return not_found("Request not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let status = match parse_status(body.status.as_deref()) {
+        Ok(s) => s,
+        Err(e) => return bad_request(e).into_response(),
+    };
+    if let Some(status) = status {
+        match parse_date(body.date.as_deref()) {
+            Ok(date) => request.set_status(status, date),
+            Err(e) => return bad_request(e).into_response(),
+        }
+    }
+    if let Some(agency) = body.agency.and_then(non_empty) {
+        request.agency = agency;
+    }
+    if let Some(subject) = body.subject.and_then(non_empty) {
+        request.subject = subject;
+    }
+    if let Some(text) = body.request_text {
+        request.request_text = text;
+    }
+    if let Some(tracking) = body.tracking_number {
+        request.tracking_number = non_empty(tracking);
+    }
+    if let Some(source) = body.source_id {
+        request.source_id = non_empty(source);
+    }
+    if body.jurisdiction.is_some() {
+        match parse_jurisdiction(&state.requests_config(), body.jurisdiction) {
+            Ok(j) => request.jurisdiction = j,
+            Err(e) => return bad_request(e).into_response(),
+        }
+    }
+    if let Some(notes) = body.notes {
+        request.notes = non_empty(notes);
+    }
+
+    if let Err(e) = state.foia_requests.update(&request).await {
+        return internal_error(e).into_response();
+    }
+    respond_with_request(&state, &id).await
+}
+
+/// Delete a request. Linked documents are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/requests/{id}",
+    params(("id" = String, Path, description = "Request ID")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 404, description = "Request not found")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_delete_request(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.foia_requests.delete(&id).await {
+        Ok(true) => ApiResponse::ok(serde_json::json!({ "deleted": id })).into_response(),
+        Ok(false) => not_found("Request not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Link received documents to a request.
+#[utoipa::path(
+    post,
+    path = "/api/requests/{id}/documents",
+    params(("id" = String, Path, description = "Request ID")),
+    request_body = LinkDocumentsRequest,
+    responses(
+        (status = 200, description = "Request with its linked documents", body = FoiaRequestResponse),
+        (status = 400, description = "Unknown document"),
+        (status = 404, description = "Request not found")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_link_documents(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<LinkDocumentsRequest>,
+) -> impl IntoResponse {
+    match state.foia_requests.get(&id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found("Request not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    for doc_id in &body.document_ids {
+        match state.doc_repo.get(doc_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return bad_request(&format!("Document '{}' not found", doc_id)).into_response()
+            }
+            Err(e) => return internal_error(e).into_response(),
+        }
+    }
+    for doc_id in &body.document_ids {
+        if let Err(e) = state.foia_requests.link_document(&id, doc_id).await {
+            return internal_error(e).into_response();
+        }
+    }
+    respond_with_request(&state, &id).await
+}
+
+/// Unlink a document from a request.
+#[utoipa::path(
+    delete,
+    path = "/api/requests/{id}/documents/{doc_id}",
+    params(
+        ("id" = String, Path, description = "Request ID"),
+        ("doc_id" = String, Path, description = "Document ID")
+    ),
+    responses(
+        (status = 200, description = "Unlinked"),
+        (status = 404, description = "Document was not linked to the request")
+    ),
+    tag = "Requests"
+)]
+pub async fn api_unlink_document(
+    State(state): State<AppState>,
+    Path((id, doc_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match state.foia_requests.unlink_document(&id, &doc_id).await {
+        Ok(true) => ApiResponse::ok(serde_json::json!({ "unlinked": doc_id })).into_response(),
+        Ok(false) => not_found("Document is not linked to this request").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
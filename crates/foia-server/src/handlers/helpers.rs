@@ -7,6 +7,7 @@ use utoipa::ToSchema;
 use super::super::AppState;
 use super::api_types::ApiResponse;
 use foia::models::{Document, DocumentVersion};
+use foia::repository::{Page, PageCursor};
 
 /// Create an internal server error response.
 pub fn internal_error(e: impl std::fmt::Display) -> impl IntoResponse {
@@ -90,29 +91,36 @@ impl From<Document> for DocumentSummary {
     }
 }
 
-/// Paginated response wrapper.
+/// Keyset-paginated response wrapper.
+///
+/// Pass `next_cursor` or `prev_cursor` back as the `cursor` query param to
+/// fetch the neighbouring page.
 #[derive(Debug, Serialize, ToSchema)]
-pub struct PaginatedResponse<T: Serialize> {
+pub struct CursorPaginatedResponse<T: Serialize> {
     pub items: Vec<T>,
-    pub page: usize,
     pub per_page: usize,
     pub total: u64,
-    pub total_pages: u64,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
 }
 
-impl<T: Serialize> PaginatedResponse<T> {
-    pub fn new(items: Vec<T>, page: usize, per_page: usize, total: u64) -> Self {
-        let total_pages = total.div_ceil(per_page as u64);
+impl<T: Serialize> CursorPaginatedResponse<T> {
+    pub fn new(page: Page<T>, per_page: usize, total: u64) -> Self {
         Self {
-            items,
-            page,
+            items: page.items,
             per_page,
             total,
-            total_pages,
+            next_cursor: page.next_cursor.map(|c| c.encode()),
+            prev_cursor: page.prev_cursor.map(|c| c.encode()),
         }
     }
 }
 
+/// Decode an opaque `cursor` query parameter, ignoring malformed values.
+pub fn parse_cursor_param(param: Option<&String>) -> Option<PageCursor> {
+    param.and_then(|s| PageCursor::decode(s))
+}
+
 /// Parse a comma-separated query parameter into a Vec of trimmed, non-empty strings.
 pub fn parse_csv_param(param: Option<&String>) -> Vec<String> {
     parse_csv_param_limit(param, None)
@@ -134,6 +142,35 @@ pub fn parse_csv_param_limit(param: Option<&String>, limit: Option<usize>) -> Ve
         .unwrap_or_default()
 }
 
+/// Parse a comma-separated list of years, ignoring malformed values.
+pub fn parse_years_param(param: Option<&String>) -> Vec<i32> {
+    parse_csv_param_limit(param, Some(20))
+        .iter()
+        .filter_map(|y| y.parse().ok())
+        .collect()
+}
+
+/// Parse a `YYYY-MM-DD` date query parameter, ignoring malformed values.
+pub fn parse_date_param(param: Option<&String>) -> Option<chrono::NaiveDate> {
+    param.and_then(|s| chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok())
+}
+
+/// Parse an HTTP status filter: an exact code (`404`), a class (`4xx`) or
+/// an inclusive range (`500-599`). Returns the inclusive code range.
+pub fn parse_status_param(param: Option<&String>) -> Option<(u16, u16)> {
+    let s = param?.trim().to_ascii_lowercase();
+    if let Some(class) = s.strip_suffix("xx") {
+        let digit: u16 = class.parse().ok().filter(|d| (1..=5).contains(d))?;
+        return Some((digit * 100, digit * 100 + 99));
+    }
+    if let Some((low, high)) = s.split_once('-') {
+        let low: u16 = low.trim().parse().ok()?;
+        let high: u16 = high.trim().parse().ok()?;
+        return (low <= high).then_some((low, high));
+    }
+    s.parse().ok().map(|code| (code, code))
+}
+
 /// Calculate pagination offset from page and per_page values.
 /// Returns (page, per_page, offset) with clamped values.
 pub fn paginate(page: Option<usize>, per_page: Option<usize>) -> (usize, usize, usize) {
@@ -204,6 +241,19 @@ pub async fn find_sources_with_hash(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_status_param() {
+        let parse = |s: &str| parse_status_param(Some(&s.to_string()));
+        assert_eq!(parse("404"), Some((404, 404)));
+        assert_eq!(parse("4xx"), Some((400, 499)));
+        assert_eq!(parse("5XX"), Some((500, 599)));
+        assert_eq!(parse("500-503"), Some((500, 503)));
+        assert_eq!(parse("503-500"), None);
+        assert_eq!(parse("9xx"), None);
+        assert_eq!(parse("ok"), None);
+        assert_eq!(parse_status_param(None), None);
+    }
+
     #[test]
     fn test_parse_csv_param_none() {
         let result = parse_csv_param(None);
@@ -245,6 +295,28 @@ mod tests {
         assert_eq!(result, vec!["a", "b", "c"]);
     }
 
+    #[test]
+    fn test_parse_date_param() {
+        let valid = "1963-11-22".to_string();
+        assert_eq!(
+            parse_date_param(Some(&valid)),
+            chrono::NaiveDate::from_ymd_opt(1963, 11, 22)
+        );
+        let invalid = "11/22/1963".to_string();
+        assert_eq!(parse_date_param(Some(&invalid)), None);
+        assert_eq!(parse_date_param(None), None);
+    }
+
+    #[test]
+    fn test_parse_cursor_param() {
+        let cursor = PageCursor::after(vec!["2024-01-01".into(), "doc-1".into()]);
+        let token = cursor.encode();
+        assert_eq!(parse_cursor_param(Some(&token)), Some(cursor));
+        let garbage = "%%%".to_string();
+        assert_eq!(parse_cursor_param(Some(&garbage)), None);
+        assert_eq!(parse_cursor_param(None), None);
+    }
+
     #[test]
     fn test_paginate_defaults() {
         let (page, per_page, offset) = paginate(None, None);
@@ -0,0 +1,355 @@
+//! Background job page and API.
+
+use askama::Template;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::template_structs::{ErrorTemplate, JobKindOption, JobRow, JobsTemplate};
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+use foia::models::{Job, JobKind, JobStatus};
+
+/// Jobs listed by default.
+const DEFAULT_LIMIT: usize = 50;
+
+/// Query parameters for listing jobs.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct JobsQuery {
+    /// Only jobs in this status (queued, running, completed, failed, cancelled)
+    pub status: Option<String>,
+    /// Maximum jobs to return (default: 50)
+    pub limit: Option<usize>,
+}
+
+/// Start a background job.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateJobRequest {
+    /// `reocr`, `extract_tables`, `searchable_pdf`, `export` or `reprocess`.
+    pub kind: String,
+    /// Kind-specific parameters, e.g. `{"source_id": "fbi"}`.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub params: serde_json::Value,
+}
+
+/// Re-run a processing step on one document.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReprocessRequest {
+    /// `ocr`, `extract`, `summarize` or `redownload`.
+    pub action: String,
+}
+
+/// A background job.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct JobResponse {
+    pub id: String,
+    pub kind: String,
+    #[schema(value_type = Object)]
+    pub params: serde_json::Value,
+    pub status: String,
+    pub progress: u64,
+    pub total: Option<u64>,
+    pub percent: Option<u8>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    pub cancel_requested: bool,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            percent: job.percent(),
+            id: job.id,
+            kind: job.kind.as_str().to_string(),
+            params: job.params,
+            status: job.status.as_str().to_string(),
+            progress: job.progress,
+            total: job.total,
+            message: job.message,
+            error: job.error,
+            result: job.result,
+            cancel_requested: job.cancel_requested,
+            created_at: job.created_at.to_rfc3339(),
+            started_at: job.started_at.map(|t| t.to_rfc3339()),
+            finished_at: job.finished_at.map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn job_row(job: Job) -> JobRow {
+    let progress = match (job.total, job.percent()) {
+        (Some(total), Some(percent)) => format!("{}/{} ({}%)", job.progress, total, percent),
+        _ if job.progress > 0 => job.progress.to_string(),
+        _ => String::new(),
+    };
+    let params = job
+        .params
+        .as_object()
+        .map(|params| {
+            params
+                .iter()
+                .map(|(k, v)| match v.as_str() {
+                    Some(s) => format!("{}={}", k, s),
+                    None => format!("{}={}", k, v),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+    JobRow {
+        active: !job.status.is_finished(),
+        has_download: job.kind == JobKind::Export && job.status == JobStatus::Completed,
+        kind: job.kind.label(),
+        status: job.status.as_str(),
+        progress,
+        params,
+        detail: job.error.or(job.message).unwrap_or_default(),
+        created_at: job.created_at.format("%Y-%m-%d %H:%M").to_string(),
+        id: job.id,
+    }
+}
+
+/// Recent background jobs, with a form to start one.
+pub async fn list_jobs(State(state): State<AppState>) -> impl IntoResponse {
+    let jobs = match state.jobs.repository().list(None, DEFAULT_LIMIT).await {
+        Ok(jobs) => jobs,
+        Err(e) => return render_error(&format!("Failed to load jobs: {}", e)),
+    };
+    let sources = state.source_repo.get_all().await.unwrap_or_default();
+
+    let rows: Vec<JobRow> = jobs.into_iter().map(job_row).collect();
+    let template = JobsTemplate {
+        title: "Jobs",
+        any_active: rows.iter().any(|r| r.active),
+        jobs: rows,
+        // Reprocessing is started from the document page
+        kinds: JobKind::ALL
+            .iter()
+            .filter(|k| **k != JobKind::Reprocess)
+            .map(|k| JobKindOption {
+                id: k.as_str(),
+                label: k.label(),
+            })
+            .collect(),
+        sources: sources.into_iter().map(|s| s.id).collect(),
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// List recent background jobs.
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    params(JobsQuery),
+    responses(
+        (status = 200, description = "Jobs, newest first", body = Vec<JobResponse>),
+        (status = 400, description = "Unknown status")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_list_jobs(
+    State(state): State<AppState>,
+    Query(params): Query<JobsQuery>,
+) -> impl IntoResponse {
+    let status = match params.status.as_deref().filter(|s| !s.is_empty()) {
+        Some(s) => match JobStatus::from_str(s) {
+            Some(status) => Some(status),
+            None => return bad_request("Unknown job status").into_response(),
+        },
+        None => None,
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).min(1000);
+    match state.jobs.repository().list(status, limit).await {
+        Ok(jobs) => ApiResponse::ok(jobs.into_iter().map(JobResponse::from).collect::<Vec<_>>())
+            .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Start a background job.
+#[utoipa::path(
+    post,
+    path = "/api/jobs",
+    request_body = CreateJobRequest,
+    responses(
+        (status = 200, description = "Queued job", body = JobResponse),
+        (status = 400, description = "Unknown kind or invalid parameters")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_create_job(
+    State(state): State<AppState>,
+    Json(body): Json<CreateJobRequest>,
+) -> impl IntoResponse {
+    let Some(kind) = JobKind::from_str(&body.kind) else {
+        return bad_request("Unknown job kind").into_response();
+    };
+    let params = if body.params.is_null() {
+        serde_json::json!({})
+    } else {
+        body.params
+    };
+    match state.jobs.submit(kind, params).await {
+        Ok(job) => ApiResponse::ok(JobResponse::from(job)).into_response(),
+        Err(e) => bad_request(&e).into_response(),
+    }
+}
+
+/// Re-run OCR, text extraction or summarization on a document, or
+/// download it again, as a background job.
+#[utoipa::path(
+    post,
+    path = "/api/documents/{document_id}/reprocess",
+    params(("document_id" = String, Path, description = "Document ID")),
+    request_body = ReprocessRequest,
+    responses(
+        (status = 200, description = "Queued job", body = JobResponse),
+        (status = 400, description = "Unknown action"),
+        (status = 404, description = "Document not found")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_reprocess_document(
+    State(state): State<AppState>,
+    Path(document_id): Path<String>,
+    Json(body): Json<ReprocessRequest>,
+) -> impl IntoResponse {
+    match state.doc_repo.exists(&document_id).await {
+        Ok(true) => {}
+        Ok(false) => return not_found("Document not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    let params = serde_json::json!({ "document_id": document_id, "action": body.action });
+    match state.jobs.submit(JobKind::Reprocess, params).await {
+        Ok(job) => ApiResponse::ok(JobResponse::from(job)).into_response(),
+        Err(e) => bad_request(&e).into_response(),
+    }
+}
+
+/// Get a background job.
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Job", body = JobResponse),
+        (status = 404, description = "Job not found")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_get_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.jobs.repository().get(&id).await {
+        Ok(Some(job)) => ApiResponse::ok(JobResponse::from(job)).into_response(),
+        Ok(None) => not_found("Job not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Ask a background job to stop.
+#[utoipa::path(
+    post,
+    path = "/api/jobs/{id}/cancel",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Cancellation requested", body = JobResponse),
+        (status = 404, description = "Job not found or already finished")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_cancel_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let repo = state.jobs.repository();
+    match repo.request_cancel(&id).await {
+        Ok(true) => {}
+        Ok(false) => return not_found("Job not found or already finished").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    match repo.get(&id).await {
+        Ok(Some(job)) => ApiResponse::ok(JobResponse::from(job)).into_response(),
+        Ok(None) => not_found("Job not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Download the file written by a completed export job.
+#[utoipa::path(
+    get,
+    path = "/api/jobs/{id}/download",
+    params(("id" = String, Path, description = "Job ID")),
+    responses(
+        (status = 200, description = "Export file"),
+        (status = 404, description = "Job not found or has no output")
+    ),
+    tag = "Jobs"
+)]
+pub async fn api_download_job(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let job = match state.jobs.repository().get(&id).await {
+        Ok(Some(job)) if job.status == JobStatus::Completed => job,
+        Ok(_) => return not_found("Job not found or not completed").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let Some(path) = state.jobs.export_path(&job) else {
+        return not_found("Job has no output").into_response();
+    };
+    let content = match tokio::fs::read(&path).await {
+        Ok(c) => c,
+        Err(_) => return not_found("Job output missing").into_response(),
+    };
+    let content_type = job
+        .result
+        .as_ref()
+        .and_then(|r| r.get("content_type"))
+        .and_then(|c| c.as_str())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    let filename = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
@@ -0,0 +1,277 @@
+//! Map view handlers.
+//!
+//! Documents are placed at the location they mention most and grouped
+//! into grid clusters sized to the visible area. The map takes the browse
+//! page's filters, and a cluster's `bbox` narrows the browse listing to it.
+
+use askama::Template;
+use axum::{
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use foia::models::{cluster_locations, BoundingBox, MapCluster};
+use foia::repository::diesel_document::{BrowseParams as DocumentFilter, ProcessingState};
+
+use super::super::template_structs::{ErrorTemplate, MapTemplate, SourceOption};
+use super::super::AppState;
+use super::helpers::{
+    bad_request, internal_error, parse_csv_param, parse_date_param, parse_years_param,
+};
+
+/// Grid cells across the longer side of the visible area.
+const GRID_CELLS: f64 = 24.0;
+
+/// Query params for the map, matching the browse page's filters.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+pub struct MapQuery {
+    /// Filter by source ID
+    pub source: Option<String>,
+    /// Filter by MIME type categories (comma-separated)
+    pub types: Option<String>,
+    /// Filter by tags (comma-separated)
+    pub tags: Option<String>,
+    /// Filter by record types (comma-separated)
+    pub record_types: Option<String>,
+    /// Only documents with pages whose OCR quality scored below 0.5
+    #[serde(default)]
+    pub poor_ocr: bool,
+    /// Filter by exemption codes cited (comma-separated)
+    pub exemptions: Option<String>,
+    /// Filter by the software that wrote the PDF
+    pub pdf_producer: Option<String>,
+    /// Filter by PDF creation years (comma-separated)
+    pub pdf_years: Option<String>,
+    /// Only PDFs created after the date the document claims to be from
+    #[serde(default)]
+    pub pdf_postdated: bool,
+    /// Filter by processing state (needs_ocr, ocr_failed, needs_summary, poor_ocr, unsupported)
+    pub processing: Option<String>,
+    /// Earliest document date (YYYY-MM-DD)
+    pub start: Option<String>,
+    /// Latest document date (YYYY-MM-DD)
+    pub end: Option<String>,
+    /// Title and synopsis search
+    pub q: Option<String>,
+    /// Visible area as `south,west,north,east` (default: the whole map)
+    pub bbox: Option<String>,
+}
+
+impl MapQuery {
+    /// The filters as a query string, without the visible area.
+    fn filter_query_string(&self) -> String {
+        let mut parts = Vec::new();
+        let params = [
+            ("source", &self.source),
+            ("types", &self.types),
+            ("tags", &self.tags),
+            ("record_types", &self.record_types),
+            ("exemptions", &self.exemptions),
+            ("pdf_producer", &self.pdf_producer),
+            ("pdf_years", &self.pdf_years),
+            ("processing", &self.processing),
+            ("start", &self.start),
+            ("end", &self.end),
+            ("q", &self.q),
+        ];
+        for (key, value) in params {
+            if let Some(value) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                parts.push(format!("{}={}", key, urlencoding::encode(value)));
+            }
+        }
+        if self.poor_ocr {
+            parts.push("poor_ocr=true".to_string());
+        }
+        if self.pdf_postdated {
+            parts.push("pdf_postdated=true".to_string());
+        }
+        parts.join("&")
+    }
+}
+
+/// A group of nearby documents.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MapClusterResponse {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub count: u64,
+    /// The place most of the documents mention most.
+    pub place: String,
+    /// Extent of the documents as `south,west,north,east`, for `bbox` filters.
+    pub bbox: String,
+}
+
+impl From<MapCluster> for MapClusterResponse {
+    fn from(cluster: MapCluster) -> Self {
+        Self {
+            latitude: cluster.latitude,
+            longitude: cluster.longitude,
+            count: cluster.count,
+            place: cluster.place,
+            bbox: cluster.bounds.to_param(),
+        }
+    }
+}
+
+/// Clusters for the visible area.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MapResponse {
+    pub clusters: Vec<MapClusterResponse>,
+    /// Documents placed within the visible area.
+    pub total: u64,
+    /// The visible area as `south,west,north,east`.
+    pub bbox: String,
+    /// Grid cell size the clusters were built with.
+    pub cell_degrees: f64,
+}
+
+/// Document clusters for the map.
+#[utoipa::path(
+    get,
+    path = "/api/map",
+    params(MapQuery),
+    responses(
+        (status = 200, description = "Document clusters, largest first", body = MapResponse),
+        (status = 400, description = "Invalid bbox")
+    ),
+    tag = "Map"
+)]
+pub async fn api_map(
+    State(state): State<AppState>,
+    Query(params): Query<MapQuery>,
+) -> impl IntoResponse {
+    let bbox = match params.bbox.as_deref().filter(|b| !b.is_empty()) {
+        Some(b) => match BoundingBox::parse(b) {
+            Some(bbox) => Some(bbox),
+            None => {
+                return bad_request("bbox must be south,west,north,east in degrees").into_response()
+            }
+        },
+        None => None,
+    };
+    let types = parse_csv_param(params.types.as_ref());
+    let tags = parse_csv_param(params.tags.as_ref());
+    let record_types = parse_csv_param(params.record_types.as_ref());
+    let exemptions = parse_csv_param(params.exemptions.as_ref());
+    let pdf_producers: Vec<String> = params
+        .pdf_producer
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+        .cloned()
+        .collect();
+    let pdf_years = parse_years_param(params.pdf_years.as_ref());
+    let filter = DocumentFilter {
+        source_id: params.source.as_deref().filter(|s| !s.is_empty()),
+        categories: &types,
+        tags: &tags,
+        record_types: &record_types,
+        poor_ocr: params.poor_ocr,
+        exemptions: &exemptions,
+        pdf_producers: &pdf_producers,
+        pdf_years: &pdf_years,
+        pdf_postdated: params.pdf_postdated,
+        processing: params
+            .processing
+            .as_deref()
+            .and_then(|p| ProcessingState::from_str(p.trim())),
+        date_from: parse_date_param(params.start.as_ref()),
+        date_to: parse_date_param(params.end.as_ref()),
+        search_query: params.q.as_deref(),
+        bbox,
+        ..Default::default()
+    };
+
+    let locations = match state.doc_repo.map_locations(&filter).await {
+        Ok(l) => l,
+        Err(e) => return internal_error(e).into_response(),
+    };
+    let view = bbox.unwrap_or(BoundingBox::WORLD);
+    let cell_degrees =
+        ((view.east - view.west).max(view.north - view.south) / GRID_CELLS).max(0.001);
+    let clusters = cluster_locations(&locations, cell_degrees);
+
+    Json(MapResponse {
+        total: locations.len() as u64,
+        clusters: clusters.into_iter().map(MapClusterResponse::from).collect(),
+        bbox: view.to_param(),
+        cell_degrees,
+    })
+    .into_response()
+}
+
+/// Map page; clusters are loaded from `/api/map`.
+pub async fn map_view(
+    State(state): State<AppState>,
+    Query(params): Query<MapQuery>,
+) -> impl IntoResponse {
+    let sources = match state.source_repo.get_all().await {
+        Ok(s) => s,
+        Err(e) => {
+            let message = format!("Failed to load sources: {}", e);
+            let template = ErrorTemplate {
+                title: "Error",
+                message: &message,
+            };
+            return Html(template.render().unwrap_or(message));
+        }
+    };
+    let source_counts = state.stats_cache.get_source_counts().unwrap_or_default();
+    let sources: Vec<SourceOption> = sources
+        .into_iter()
+        .map(|s| SourceOption {
+            count: source_counts.get(&s.id).copied().unwrap_or(0),
+            selected: params.source.as_deref() == Some(&s.id),
+            id: s.id,
+            name: s.name,
+        })
+        .collect();
+
+    let template = MapTemplate {
+        title: "Map",
+        sources,
+        filter_query: params.filter_query_string(),
+        bbox: params
+            .bbox
+            .as_deref()
+            .and_then(BoundingBox::parse)
+            .map(|b| b.to_param())
+            .unwrap_or_default(),
+    };
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_query_string() {
+        let query = MapQuery {
+            source: Some("fbi".to_string()),
+            types: None,
+            tags: Some("agency:fbi,jfk".to_string()),
+            record_types: Some(" ".to_string()),
+            poor_ocr: true,
+            exemptions: None,
+            pdf_producer: None,
+            pdf_years: Some("2021".to_string()),
+            pdf_postdated: false,
+            processing: None,
+            start: Some("1963-01-01".to_string()),
+            end: None,
+            q: Some("dallas field office".to_string()),
+            bbox: Some("30,-100,35,-95".to_string()),
+        };
+        assert_eq!(
+            query.filter_query_string(),
+            "source=fbi&tags=agency%3Afbi%2Cjfk&pdf_years=2021&start=1963-01-01&q=dallas%20field%20office&poor_ocr=true"
+        );
+    }
+}
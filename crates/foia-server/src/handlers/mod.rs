@@ -1,20 +1,36 @@
 //! HTTP request handlers for the web server.
 
+mod agencies;
+mod analytics;
 mod annotations_api;
 mod api;
+mod api_keys;
 pub mod api_types;
+mod ask_api;
 mod browse;
+mod crawl_log;
+mod crawl_queue;
 mod documents;
 mod documents_api;
 mod duplicates;
 mod entities_api;
+mod exemptions;
 mod export_api;
+mod failures;
+mod flags;
+mod foia_requests;
 mod helpers;
+mod jobs;
+mod map;
 mod ocr;
 pub mod openapi;
 mod pages;
+mod provenance;
+mod relations;
+mod saved_views;
 mod scrape_api;
 mod search_api;
+mod source_health;
 mod static_files;
 mod tags;
 mod timeline;
@@ -22,27 +38,57 @@ mod types;
 mod versions_api;
 
 // Re-export handlers for use by the router
+pub use agencies::{api_get_agency, api_list_agencies};
+pub use analytics::{analytics_page, api_analytics};
 pub use annotations_api::{annotation_stats, get_annotation, list_annotations, update_annotation};
 pub use api::{
     api_recent_docs, api_search_tags, api_source_status, api_sources, api_status, api_type_stats,
-    health,
+    health, metrics,
 };
+pub use api_keys::{api_create_key, api_keys_page, api_list_keys, api_revoke_key};
+pub use ask_api::api_ask;
 pub use browse::browse_documents;
-pub use documents::{document_detail, document_versions};
+pub use crawl_log::{crawl_domains, crawl_log};
+pub use crawl_queue::source_urls;
+pub use documents::{document_detail, document_searchable_pdf, document_table, document_versions};
 pub use documents_api::{get_document, get_document_content, list_documents};
 pub use duplicates::list_duplicates;
 pub use entities_api::{
     document_entities, entity_locations, entity_types, search_entities, top_entities,
 };
+pub use exemptions::{api_exemptions, exemption_analytics};
 pub use export_api::{export_annotations, export_documents, export_stats};
+pub(crate) use export_api::{export_records, render_export, ExportFormat};
+pub use failures::{list_failures, retry_failure_class};
+pub use flags::{api_create_flag, api_list_flags, api_update_flag, flags_page};
+pub use foia_requests::{
+    api_create_request, api_delete_request, api_get_request, api_link_documents, api_list_requests,
+    api_unlink_document, api_update_request, list_requests, request_detail,
+};
+pub use jobs::{
+    api_cancel_job, api_create_job, api_download_job, api_get_job, api_list_jobs,
+    api_reprocess_document, list_jobs,
+};
+pub use map::{api_map, map_view};
 pub use ocr::{api_reocr_document, api_reocr_status};
-pub use pages::api_document_pages;
-pub use scrape_api::{get_scrape_status, list_queue, list_scrapers, retry_failed};
+pub use pages::{api_correct_page_text, api_document_pages, cite_page, page_image, page_range_pdf};
+pub use provenance::{document_provenance, get_provenance};
+pub use relations::{api_document_graph, api_document_relations, document_graph};
+pub use saved_views::{
+    api_delete_view, api_list_subscriptions, api_list_views, api_save_view, api_subscribe_view,
+    api_unsubscribe_view,
+};
+pub use scrape_api::{
+    add_source_urls, cancel_source_urls, get_scrape_status, list_queue, list_scrapers,
+    list_source_urls, retry_failed, retry_source_urls,
+};
 pub use search_api::search_content;
-pub use static_files::{serve_css, serve_file, serve_js};
-pub use tags::{api_tags, list_tag_documents, list_tags};
+pub use source_health::source_health;
+pub(crate) use static_files::header_matches_etag;
+pub use static_files::{serve_css, serve_file, serve_js, serve_thumbnail};
+pub use tags::{api_tags, list_tag_documents, list_tag_namespace, list_tags};
 pub use timeline::{timeline_aggregate, timeline_source};
 pub use types::{list_by_type, list_types};
 pub use versions_api::{find_by_hash, get_version, list_versions};
 
-pub use openapi::openapi_spec;
+pub use openapi::{openapi_spec, swagger_ui};
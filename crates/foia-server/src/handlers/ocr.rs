@@ -8,6 +8,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use foia::repository::diesel_document::OcrRunSettings;
+
 use super::super::{AppState, DeepSeekJobStatus};
 
 /// Request body for re-OCR API.
@@ -50,7 +52,7 @@ pub async fn api_reocr_document(
     Path(document_id): Path<String>,
     axum::Json(request): axum::Json<ReOcrRequest>,
 ) -> impl IntoResponse {
-    use foia_analysis::ocr::{DeepSeekBackend, OcrBackend, OcrConfig};
+    use foia_analysis::ocr::{quality_score, DeepSeekBackend, OcrBackend, OcrConfig};
 
     if request.backend != "deepseek" {
         return axum::Json(ReOcrResponse {
@@ -246,8 +248,10 @@ pub async fn api_reocr_document(
                             result.model.as_deref(),
                             Some(&result.text),
                             result.confidence,
+                            quality_score(&result.text, result.confidence),
                             None,
                             None,
+                            &result.run_settings(),
                         )
                         .await
                     {
@@ -264,7 +268,17 @@ pub async fn api_reocr_document(
                     tracing::error!("OCR failed for page {}: {:?}", page_number, e);
                     let _ = job_state
                         .doc_repo
-                        .store_page_ocr_result(page_id, "deepseek", None, None, None, None, None)
+                        .store_page_ocr_result(
+                            page_id,
+                            "deepseek",
+                            None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            None,
+                            &OcrRunSettings::default(),
+                        )
                         .await;
                 }
                 Err(e) => {
@@ -1,21 +1,41 @@
 //! OpenAPI spec generation and serving.
+//!
+//! The spec is derived from the handlers' `#[utoipa::path]` attributes and
+//! their request and response types, so it can't drift from the code.
 
 use axum::{http::StatusCode, response::IntoResponse};
-use utoipa::OpenApi;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::{Config, SwaggerUi};
 
+use super::agencies;
+use super::analytics;
 use super::annotations_api;
 use super::api;
+use super::api_keys;
 use super::api_types;
+use super::ask_api;
 use super::documents_api;
 use super::entities_api;
+use super::exemptions;
 use super::export_api;
+use super::failures;
+use super::flags;
+use super::foia_requests;
 use super::helpers;
+use super::jobs;
+use super::map;
 use super::ocr;
 use super::pages;
+use super::provenance;
+use super::relations;
+use super::saved_views;
 use super::scrape_api;
+use super::search_api;
 use super::tags;
 use super::timeline;
 use super::versions_api;
+use crate::workspaces;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -27,12 +47,27 @@ use super::versions_api;
     paths(
         // Health
         api::health,
+        api::metrics,
         // Documents
         documents_api::list_documents,
         documents_api::get_document,
         documents_api::get_document_content,
+        provenance::get_provenance,
+        relations::api_document_relations,
+        relations::api_document_graph,
+        exemptions::api_exemptions,
+        saved_views::api_list_views,
+        saved_views::api_save_view,
+        saved_views::api_delete_view,
+        saved_views::api_list_subscriptions,
+        saved_views::api_subscribe_view,
+        saved_views::api_unsubscribe_view,
+        // Search
+        search_api::search_content,
+        ask_api::api_ask,
         // Pages
         pages::api_document_pages,
+        pages::api_correct_page_text,
         // OCR
         ocr::api_reocr_document,
         ocr::api_reocr_status,
@@ -50,6 +85,29 @@ use super::versions_api;
         scrape_api::get_scrape_status,
         scrape_api::list_queue,
         scrape_api::retry_failed,
+        scrape_api::list_source_urls,
+        scrape_api::add_source_urls,
+        scrape_api::retry_source_urls,
+        scrape_api::cancel_source_urls,
+        failures::retry_failure_class,
+        // Jobs
+        jobs::api_list_jobs,
+        jobs::api_create_job,
+        jobs::api_get_job,
+        jobs::api_cancel_job,
+        jobs::api_download_job,
+        jobs::api_reprocess_document,
+        // Requests
+        foia_requests::api_list_requests,
+        foia_requests::api_get_request,
+        foia_requests::api_create_request,
+        foia_requests::api_update_request,
+        foia_requests::api_delete_request,
+        foia_requests::api_link_documents,
+        foia_requests::api_unlink_document,
+        // Agencies
+        agencies::api_list_agencies,
+        agencies::api_get_agency,
         // Export
         export_api::export_documents,
         export_api::export_annotations,
@@ -60,6 +118,8 @@ use super::versions_api;
         entities_api::top_entities,
         entities_api::entity_locations,
         entities_api::document_entities,
+        // Map
+        map::api_map,
         // Timeline
         timeline::timeline_aggregate,
         timeline::timeline_source,
@@ -71,6 +131,17 @@ use super::versions_api;
         api::api_type_stats,
         api::api_search_tags,
         tags::api_tags,
+        workspaces::list_workspaces,
+        // API keys
+        api_keys::api_list_keys,
+        api_keys::api_create_key,
+        api_keys::api_revoke_key,
+        // Analytics
+        analytics::api_analytics,
+        // Flags
+        flags::api_create_flag,
+        flags::api_list_flags,
+        flags::api_update_flag,
     ),
     components(schemas(
         // Envelope types
@@ -86,6 +157,10 @@ use super::versions_api;
         // Document API types
         documents_api::DocumentContentResponse,
         documents_api::PageContent,
+        // Saved view types
+        saved_views::SaveViewRequest,
+        saved_views::SavedViewResponse,
+        saved_views::SubscribeRequest,
         // Version API types
         versions_api::VersionResponse,
         api_types::VersionsListResponse,
@@ -100,6 +175,8 @@ use super::versions_api;
         api_types::UpdateAnnotationResponse,
         // Scraper API types
         scrape_api::RetryRequest,
+        scrape_api::UrlActionRequest,
+        failures::RetryClassRequest,
         api_types::ScraperInfo,
         api_types::ScraperCrawlStats,
         api_types::ScraperStatusResponse,
@@ -108,8 +185,20 @@ use super::versions_api;
         api_types::QueueItem,
         api_types::QueueResponse,
         api_types::RetryResponse,
+        api_types::UrlActionResponse,
         api_types::RecentUrl,
         api_types::FailedUrl,
+        // Job API types
+        jobs::CreateJobRequest,
+        jobs::JobResponse,
+        jobs::ReprocessRequest,
+        // FOIA request API types
+        foia_requests::CreateFoiaRequestRequest,
+        foia_requests::UpdateFoiaRequestRequest,
+        foia_requests::LinkDocumentsRequest,
+        foia_requests::FoiaRequestResponse,
+        // Agency API types
+        agencies::AgencyResponse,
         // Export API types
         export_api::ExportFormat,
         export_api::ExportDocument,
@@ -121,13 +210,34 @@ use super::versions_api;
         entities_api::EntityTypeStats,
         entities_api::TopEntity,
         entities_api::GeocodedLocation,
+        // Map types
+        map::MapResponse,
+        map::MapClusterResponse,
+        // Exemption analytics types
+        exemptions::ExemptionReportResponse,
+        exemptions::ExemptionCountResponse,
+        exemptions::ExemptionBreakdownResponse,
+        // Relation types
+        relations::RelationResponse,
+        relations::RelationGraphResponse,
+        relations::GraphNodeResponse,
+        relations::GraphEdgeResponse,
         // OCR types
         ocr::ReOcrRequest,
         ocr::ReOcrResponse,
+        // Search types
+        search_api::SearchResult,
+        // Question answering types
+        ask_api::AskRequest,
+        ask_api::AskResponse,
+        ask_api::CitationResponse,
         // Page types
         pages::PageData,
         pages::PagesResponse,
+        pages::PageTextRequest,
+        pages::PageTextResponse,
         // Status types
+        workspaces::WorkspaceInfo,
         api_types::SourceInfo,
         api_types::CategoryStat,
         api_types::TagCount,
@@ -138,23 +248,60 @@ use super::versions_api;
         api_types::CrawlStats,
         api_types::SourceCrawlStat,
         api_types::SourceStatusResponse,
+        // API key types
+        api_keys::CreateApiKeyRequest,
+        api_keys::ApiKeyResponse,
+        api_keys::CreatedApiKeyResponse,
+        // Analytics types
+        analytics::AnalyticsResponse,
+        analytics::DocumentAccessResponse,
+        analytics::ReferrerResponse,
+        // Flag types
+        flags::CreateFlagRequest,
+        flags::UpdateFlagRequest,
+        flags::FlagResponse,
     )),
     tags(
         (name = "Health", description = "Health check"),
         (name = "Documents", description = "Document search, filter, and details"),
         (name = "Versions", description = "Document version history"),
+        (name = "Search", description = "Full-text search and question answering"),
         (name = "Pages", description = "Document page content and OCR"),
         (name = "OCR", description = "Re-OCR document processing"),
         (name = "Annotations", description = "LLM-generated metadata and tags"),
         (name = "Scrapers", description = "Scraper control and monitoring"),
+        (name = "Jobs", description = "Background jobs with progress and cancellation"),
+        (name = "Requests", description = "FOIA requests, deadlines and the documents they produced"),
+        (name = "Agencies", description = "Agency registry and the sources linked to each agency"),
         (name = "Export", description = "Bulk data export"),
         (name = "Entities", description = "NER-extracted entity search"),
+        (name = "Map", description = "Documents clustered by the locations they mention"),
         (name = "Timeline", description = "Document timeline visualization"),
         (name = "Status", description = "System status, sources, types, and tags"),
-    )
+        (name = "API keys", description = "API key issuance, revocation and usage"),
+        (name = "Analytics", description = "Document views, downloads and referring sites"),
+        (name = "Flags", description = "Reports of sensitive or misfiled documents and their review"),
+    ),
+    modifiers(&ApiKeyAuth),
+    security((), ("api_key" = []))
 )]
 struct ApiDoc;
 
+/// Declares the `X-API-Key` header, so Swagger UI can send a key.
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-API-Key"))),
+            );
+    }
+}
+
 /// Serve the OpenAPI spec as JSON.
 pub async fn openapi_spec() -> impl IntoResponse {
     let mut doc = ApiDoc::openapi();
@@ -164,3 +311,46 @@ pub async fn openapi_spec() -> impl IntoResponse {
         .unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e));
     (StatusCode::OK, [("content-type", "application/json")], spec)
 }
+
+/// Swagger UI at `/api/docs`, reading the spec from `/api/openapi.json`.
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/api/docs").config(Config::from("/api/openapi.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use std::collections::HashSet;
+
+    /// `/api` routes that aren't JSON endpoints.
+    const UNDOCUMENTED: &[&str] = &["/api", "/api/openapi.json", "/api/duplicates"];
+
+    #[test]
+    fn test_every_api_route_is_documented() {
+        let param = Regex::new(r"[:{]\w+\}?").unwrap();
+        let documented: HashSet<String> = ApiDoc::openapi()
+            .paths
+            .paths
+            .keys()
+            .map(|path| param.replace_all(path, "{}").into_owned())
+            .collect();
+
+        let route = Regex::new(r#""(/api(?:/[^"]*)?)""#).unwrap();
+        let sources = [
+            include_str!("../routes.rs"),
+            include_str!("../workspaces.rs"),
+        ];
+        for captures in sources.iter().flat_map(|s| route.captures_iter(s)) {
+            let path = &captures[1];
+            if UNDOCUMENTED.contains(&path) {
+                continue;
+            }
+            assert!(
+                documented.contains(param.replace_all(path, "{}").as_ref()),
+                "{} is routed but missing from the OpenAPI spec",
+                path
+            );
+        }
+    }
+}
@@ -2,13 +2,30 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
 };
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
-use super::super::AppState;
+use super::super::redacted::{self, PublicCopy};
+use super::super::{access_log, AppState};
+use super::static_files::header_matches_etag;
+use foia::models::{Document, DocumentVersion};
+use foia::repository::diesel_access_log::AccessKind;
+use foia::repository::diesel_document::MERGE_ACTION;
+use foia::repository::sanitize_filename;
+use foia::utils::{extract_pdf_pages, pdf_page_count};
+
+/// Hex digits of the content hash embedded in a citation permalink.
+pub const CITATION_HASH_LEN: usize = 12;
+
+/// Shortest hash prefix a citation permalink may carry.
+const MIN_CITATION_HASH_LEN: usize = 8;
+
+/// Merges followed when a cited document has been merged away.
+const MAX_MERGE_HOPS: usize = 5;
 
 /// Parameters for pages view/API.
 #[derive(Debug, Deserialize, IntoParams)]
@@ -25,8 +42,13 @@ pub struct PageData {
     pub ocr_text: Option<String>,
     pub pdf_text: Option<String>,
     pub final_text: Option<String>,
-    pub image_base64: Option<String>,
+    /// Rendered page image, served by `/documents/{doc_id}/pages/{page}/image`.
+    pub image_url: Option<String>,
     pub ocr_status: String,
+    /// OCR quality score (0.0 - 1.0) of the page's chosen text.
+    pub ocr_quality: Option<f32>,
+    /// When a curator corrected `final_text` by hand, if they did.
+    pub corrected_at: Option<String>,
     pub deepseek_text: Option<String>,
 }
 
@@ -86,6 +108,14 @@ pub async fn api_document_pages(
             return (StatusCode::NOT_FOUND, "Version not found").into_response();
         }
     };
+    // A redacted version's text is the redacted copy's OCR, kept in final_text
+    let original_text = match redacted::public_copy(&state, &doc, version).await {
+        PublicCopy::Original => true,
+        PublicCopy::Redacted(_) => false,
+        PublicCopy::Withheld => {
+            return (StatusCode::NOT_FOUND, "Version not found").into_response();
+        }
+    };
 
     let all_pages: Vec<foia::models::DocumentPage> =
         match state.doc_repo.get_pages(&doc_id, version_id as i32).await {
@@ -127,60 +157,36 @@ pub async fn api_document_pages(
     }
 
     let is_pdf = version.mime_type.contains("pdf");
-    let pdf_path = version.resolve_path(&state.documents_dir, &doc.source_url, &doc.title);
-
-    let page_data_list: Vec<PageData> = if is_pdf {
-        let mut handles = Vec::new();
-        for page in selected_pages {
-            let path = pdf_path.clone();
-            let page_num = page.page_number;
-            let page_id = page.id;
-            let ocr_text = page.ocr_text;
-            let pdf_text = page.pdf_text;
-            let final_text = page.final_text;
-            let ocr_status = page.ocr_status.as_str().to_string();
-            let deepseek_text = deepseek_map.get(&page_id).cloned().flatten();
-
-            let handle = tokio::task::spawn_blocking(move || {
-                let image_base64 = render_pdf_page_to_base64(&path, page_num);
-                PageData {
-                    page_number: page_num,
-                    ocr_text,
-                    pdf_text,
-                    final_text,
-                    image_base64,
-                    ocr_status,
-                    deepseek_text,
-                }
-            });
-            handles.push(handle);
-        }
 
-        let mut results = Vec::with_capacity(handles.len());
-        for handle in handles {
-            if let Ok(page_data) = handle.await {
-                results.push(page_data);
+    let page_data_list: Vec<PageData> = selected_pages
+        .into_iter()
+        .map(|page| {
+            let deepseek_text = deepseek_map
+                .get(&page.id)
+                .cloned()
+                .flatten()
+                .filter(|_| original_text);
+            let image_url = is_pdf.then(|| {
+                format!(
+                    "/documents/{}/pages/{}/image?version={}",
+                    urlencoding::encode(&doc_id),
+                    page.page_number,
+                    version_id
+                )
+            });
+            PageData {
+                page_number: page.page_number,
+                ocr_text: page.ocr_text.filter(|_| original_text),
+                pdf_text: page.pdf_text.filter(|_| original_text),
+                final_text: page.final_text,
+                image_url,
+                ocr_status: page.ocr_status.as_str().to_string(),
+                ocr_quality: page.ocr_quality,
+                corrected_at: page.corrected_at.map(|t| t.to_rfc3339()),
+                deepseek_text,
             }
-        }
-        results.sort_by_key(|p| p.page_number);
-        results
-    } else {
-        selected_pages
-            .into_iter()
-            .map(|page| {
-                let deepseek_text = deepseek_map.get(&page.id).cloned().flatten();
-                PageData {
-                    page_number: page.page_number,
-                    ocr_text: page.ocr_text,
-                    pdf_text: page.pdf_text,
-                    final_text: page.final_text,
-                    image_base64: None,
-                    ocr_status: page.ocr_status.as_str().to_string(),
-                    deepseek_text,
-                }
-            })
-            .collect()
-    };
+        })
+        .collect();
 
     let has_more = (start + limit as usize) < total_pages as usize;
 
@@ -194,43 +200,398 @@ pub async fn api_document_pages(
     .into_response()
 }
 
-fn render_pdf_page_to_base64(pdf_path: &std::path::Path, page_number: u32) -> Option<String> {
-    use base64::Engine;
-    use std::process::Command;
+/// Page text correction request body.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PageTextRequest {
+    /// Corrected text; empty or omitted reverts to the OCR text
+    pub text: Option<String>,
+    /// Document version (default: current)
+    pub version: Option<i64>,
+}
+
+/// A page's text after a correction.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageTextResponse {
+    pub page_number: u32,
+    pub final_text: Option<String>,
+    /// When the text was corrected; `None` after a revert.
+    pub corrected_at: Option<String>,
+}
+
+/// Correct a page's text by hand.
+///
+/// Replaces the page's final text, which search and the document's combined
+/// text read from. The OCR and PDF text are kept, and later OCR runs leave
+/// the correction alone. Sending empty text reverts to the OCR text.
+#[utoipa::path(
+    put,
+    path = "/api/documents/{doc_id}/pages/{page}/text",
+    params(
+        ("doc_id" = String, Path, description = "Document ID"),
+        ("page" = u32, Path, description = "Page number"),
+    ),
+    request_body = PageTextRequest,
+    responses(
+        (status = 200, description = "Page text after the change", body = PageTextResponse),
+        (status = 404, description = "Document or page not found")
+    ),
+    tag = "Pages"
+)]
+pub async fn api_correct_page_text(
+    State(state): State<AppState>,
+    Path((doc_id, page_number)): Path<(String, u32)>,
+    Json(request): Json<PageTextRequest>,
+) -> impl IntoResponse {
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let Some(version) = pick_version(&doc, request.version) else {
+        return (StatusCode::NOT_FOUND, "Version not found").into_response();
+    };
+
+    let text = request.text.filter(|t| !t.trim().is_empty());
+    let result = match text {
+        Some(text) => {
+            state
+                .doc_repo
+                .correct_page_text(&doc_id, version.id as i32, page_number as i32, &text)
+                .await
+        }
+        None => {
+            state
+                .doc_repo
+                .revert_page_correction(&doc_id, version.id as i32, page_number as i32)
+                .await
+        }
+    };
+    match result {
+        Ok(Some(page)) => Json(PageTextResponse {
+            page_number: page.page_number,
+            final_text: page.final_text,
+            corrected_at: page.corrected_at.map(|t| t.to_rfc3339()),
+        })
+        .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Page not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Query params for a page image.
+#[derive(Debug, Deserialize)]
+pub struct PageImageParams {
+    pub version: Option<i64>,
+}
+
+/// Serve a rendered PDF page, rendering it into the page store if needed.
+pub async fn page_image(
+    State(state): State<AppState>,
+    Path((doc_id, page_number)): Path<(String, u32)>,
+    Query(params): Query<PageImageParams>,
+    headers: HeaderMap,
+) -> Response {
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let version = match params.version {
+        Some(id) => doc.versions.iter().find(|v| v.id == id),
+        None => doc.current_version(),
+    };
+    let Some(version) = version.filter(|v| v.mime_type.contains("pdf")) else {
+        return (StatusCode::NOT_FOUND, "No page image").into_response();
+    };
+    if page_number == 0 || version.page_count.is_some_and(|count| page_number > count) {
+        return (StatusCode::NOT_FOUND, "Page not found").into_response();
+    }
+
+    let (pdf_path, content_hash) = match redacted::served_file(&state, &doc, version).await {
+        Some(served) => served,
+        None => return (StatusCode::NOT_FOUND, "No page image").into_response(),
+    };
+
+    let etag = format!("\"{}-{}\"", content_hash, page_number);
+    if header_matches_etag(headers.get(header::IF_NONE_MATCH), &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    let store = state.page_images.clone();
+    let rendered = tokio::task::spawn_blocking(move || {
+        store.get_or_render(&pdf_path, &content_hash, page_number)
+    })
+    .await;
+
+    let (path, format) = match rendered {
+        Ok(Ok(found)) => found,
+        Ok(Err(e)) => {
+            tracing::debug!("Page {} of {} not rendered: {}", page_number, doc_id, e);
+            return (StatusCode::NOT_FOUND, "Page could not be rendered").into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    match tokio::fs::read(&path).await {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, format.mime_type().to_string()),
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// A page or inclusive page range from a URL segment such as `37`, `10-14`
+/// or `10-14.pdf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRange {
+    pub first: u32,
+    pub last: u32,
+    /// The segment asked for the pages as a PDF.
+    pub pdf: bool,
+}
 
-    let temp_dir = std::env::temp_dir();
-    let output_prefix = temp_dir.join(format!("foia_page_{}", uuid::Uuid::new_v4()));
-    let output_path = output_prefix.with_extension("png");
+impl PageRange {
+    pub fn parse(segment: &str) -> Option<Self> {
+        let (pages, pdf) = match segment.strip_suffix(".pdf") {
+            Some(pages) => (pages, true),
+            None => (segment, false),
+        };
+        let (first, last) = match pages.split_once('-') {
+            Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+            None => {
+                let page = pages.parse().ok()?;
+                (page, page)
+            }
+        };
+        (first >= 1 && first <= last).then_some(Self { first, last, pdf })
+    }
 
-    struct CleanupGuard<'a>(&'a std::path::Path);
-    impl Drop for CleanupGuard<'_> {
-        fn drop(&mut self) {
-            let _ = std::fs::remove_file(self.0);
+    /// `37` or `10-14`.
+    pub fn label(&self) -> String {
+        if self.first == self.last {
+            self.first.to_string()
+        } else {
+            format!("{}-{}", self.first, self.last)
         }
     }
-    let _cleanup = CleanupGuard(&output_path);
-
-    let status = Command::new("pdftoppm")
-        .args([
-            "-png",
-            "-r",
-            "150",
-            "-f",
-            &page_number.to_string(),
-            "-l",
-            &page_number.to_string(),
-            "-singlefile",
-        ])
-        .arg(pdf_path)
-        .arg(&output_prefix)
-        .status();
-
-    if status.map(|s| s.success()).unwrap_or(false) {
-        if let Ok(image_data) = std::fs::read(&output_path) {
-            let base64_str = base64::engine::general_purpose::STANDARD.encode(&image_data);
-            return Some(format!("data:image/png;base64,{}", base64_str));
+}
+
+fn pick_version(doc: &Document, version_id: Option<i64>) -> Option<&DocumentVersion> {
+    match version_id {
+        Some(id) => doc.versions.iter().find(|v| v.id == id),
+        None => doc.current_version(),
+    }
+}
+
+/// Serve pages of a PDF version as a new PDF, for
+/// `/documents/{doc_id}/pages/{first}-{last}.pdf` or `/pages/{page}.pdf`.
+pub async fn page_range_pdf(
+    State(state): State<AppState>,
+    Path((doc_id, pages)): Path<(String, String)>,
+    Query(params): Query<PageImageParams>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(range) = PageRange::parse(&pages).filter(|r| r.pdf) else {
+        return (StatusCode::NOT_FOUND, "Invalid page range").into_response();
+    };
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let Some(version) = pick_version(&doc, params.version) else {
+        return (StatusCode::NOT_FOUND, "Version not found").into_response();
+    };
+    if !version.mime_type.contains("pdf") {
+        return (StatusCode::NOT_FOUND, "Document is not a PDF").into_response();
+    }
+
+    let Some((pdf_path, content_hash)) = redacted::served_file(&state, &doc, version).await else {
+        return (StatusCode::NOT_FOUND, "Version not found").into_response();
+    };
+
+    let etag = format!("\"{}-{}\"", content_hash, range.label());
+    if header_matches_etag(headers.get(header::IF_NONE_MATCH), &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    let known_count = version.page_count;
+    let extracted = tokio::task::spawn_blocking(move || {
+        let page_count = match known_count {
+            Some(count) => count,
+            None => pdf_page_count(&pdf_path)?,
+        };
+        if range.last > page_count {
+            return Ok(None);
         }
+        extract_pdf_pages(&pdf_path, (range.first, range.last)).map(Some)
+    })
+    .await;
+
+    let content = match extracted {
+        Ok(Ok(Some(content))) => content,
+        Ok(Ok(None)) => return (StatusCode::NOT_FOUND, "Page not found").into_response(),
+        Ok(Err(e)) => {
+            tracing::debug!("Pages {} of {} not extracted: {}", range.label(), doc_id, e);
+            return (StatusCode::NOT_FOUND, "Pages could not be extracted").into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let stem = version
+        .original_filename
+        .as_deref()
+        .and_then(|f| std::path::Path::new(f).file_stem())
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| doc.id.clone());
+    let filename = sanitize_filename(&format!("{}-p{}.pdf", stem, range.label()));
+    access_log::record(&state, &doc.id, AccessKind::Download, &headers);
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", filename),
+            ),
+            (header::ETAG, etag),
+            (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+/// The document now holding `doc_id`, following ID redirects and merges
+/// recorded in the curation log.
+async fn resolve_cited_document(
+    state: &AppState,
+    doc_id: &str,
+) -> Result<Option<Document>, String> {
+    let mut id = doc_id.to_string();
+    for _ in 0..=MAX_MERGE_HOPS {
+        if let Some(doc) = state.doc_repo.get(&id).await.map_err(|e| e.to_string())? {
+            return Ok(Some(doc));
+        }
+        if let Some(new_id) = state
+            .doc_repo
+            .resolve_redirect(&id)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            id = new_id;
+            continue;
+        }
+        let log = state
+            .doc_repo
+            .get_curation_log(Some(&id), 20)
+            .await
+            .map_err(|e| e.to_string())?;
+        match log
+            .into_iter()
+            .find(|entry| entry.action == MERGE_ACTION && entry.related_ids.contains(&id))
+        {
+            Some(entry) => id = entry.document_id,
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// Resolve a citation permalink, `/cite/{doc_id}/{hash}/{pages}`.
+///
+/// The hash is a prefix of the cited version's content hash, so the link
+/// keeps pointing at the text that was cited after the document gains new
+/// versions or is merged into another. Redirects to the viewer at that
+/// page, or to the extracted pages when `pages` ends in `.pdf`.
+pub async fn cite_page(
+    State(state): State<AppState>,
+    Path((doc_id, hash, pages)): Path<(String, String, String)>,
+) -> Response {
+    let Some(range) = PageRange::parse(&pages) else {
+        return (StatusCode::NOT_FOUND, "Invalid page").into_response();
+    };
+    let hash = hash.to_ascii_lowercase();
+    if hash.len() < MIN_CITATION_HASH_LEN || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return (StatusCode::NOT_FOUND, "Invalid version hash").into_response();
     }
+    let doc = match resolve_cited_document(&state, &doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let Some(version) = doc
+        .versions
+        .iter()
+        .find(|v| v.content_hash.starts_with(&hash))
+    else {
+        return (StatusCode::NOT_FOUND, "Cited version not found").into_response();
+    };
+    if version.page_count.is_some_and(|count| range.last > count) {
+        return (StatusCode::NOT_FOUND, "Page not found").into_response();
+    }
+
+    let doc_id = urlencoding::encode(&doc.id);
+    let target = if range.pdf {
+        format!(
+            "/documents/{}/pages/{}.pdf?version={}",
+            doc_id,
+            range.label(),
+            version.id
+        )
+    } else {
+        format!(
+            "/documents/{}?version={}#page-{}",
+            doc_id, version.id, range.first
+        )
+    };
+    Redirect::to(&target).into_response()
+}
 
-    None
+/// Permalink citing `pages` of a document version.
+pub fn citation_url(doc_id: &str, content_hash: &str, pages: &str) -> String {
+    let hash: String = content_hash.chars().take(CITATION_HASH_LEN).collect();
+    format!("/cite/{}/{}/{}", urlencoding::encode(doc_id), hash, pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_range_parse() {
+        assert_eq!(
+            PageRange::parse("10-14.pdf"),
+            Some(PageRange {
+                first: 10,
+                last: 14,
+                pdf: true
+            })
+        );
+        assert_eq!(
+            PageRange::parse("37"),
+            Some(PageRange {
+                first: 37,
+                last: 37,
+                pdf: false
+            })
+        );
+        assert_eq!(
+            PageRange::parse("37.pdf").map(|r| r.label()),
+            Some("37".to_string())
+        );
+        assert_eq!(PageRange::parse("0"), None);
+        assert_eq!(PageRange::parse("14-10.pdf"), None);
+        assert_eq!(PageRange::parse("image"), None);
+        assert_eq!(PageRange::parse("3-"), None);
+
+        assert_eq!(
+            citation_url("doc 1", "0123456789abcdef0123", "37"),
+            "/cite/doc%201/0123456789ab/37"
+        );
+    }
 }
@@ -0,0 +1,165 @@
+//! Document provenance page and JSON export.
+
+use askama::Template;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{Html, IntoResponse, Response},
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use super::super::template_structs::{
+    AnalysisRunRow, ChainLinkRow, ErrorTemplate, ProvenanceTemplate, ProvenanceVersionRow,
+};
+use super::super::AppState;
+use super::crawl_log::request_row;
+use super::helpers::{internal_error, not_found};
+use foia::services::provenance::{self, ProvenanceRecord};
+use foia::utils::format_size;
+
+/// Query params for the provenance export.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ProvenanceQuery {
+    /// Serve as a file download
+    #[serde(default)]
+    pub download: bool,
+}
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Chain-of-custody view of one document.
+pub async fn document_provenance(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+) -> impl IntoResponse {
+    let record =
+        match provenance::provenance_record(&state.doc_repo, &state.crawl_repo, &doc_id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return render_error("Document not found"),
+            Err(e) => return render_error(&format!("Failed to load provenance: {}", e)),
+        };
+
+    let ProvenanceRecord {
+        document,
+        seed,
+        discovery_chain,
+        requests,
+        versions,
+        analysis,
+        ..
+    } = record;
+
+    let chain = discovery_chain
+        .into_iter()
+        .map(|link| ChainLinkRow {
+            url: link.url,
+            method: link.discovery_method,
+            depth: link.depth,
+            discovered_at: link.discovered_at.format(TIME_FORMAT).to_string(),
+            fetched_at: link
+                .fetched_at
+                .map(|t| t.format(TIME_FORMAT).to_string())
+                .unwrap_or_default(),
+            status: link.status,
+        })
+        .collect();
+    let versions = versions
+        .into_iter()
+        .map(|v| ProvenanceVersionRow {
+            id: v.id,
+            acquired_at: v.acquired_at.format(TIME_FORMAT).to_string(),
+            size: format_size(v.file_size),
+            sha256: v.sha256().unwrap_or_default().to_string(),
+            blake3: v.content_hash_blake3.clone().unwrap_or_default(),
+            mime_type: v.mime_type,
+            source_url: v.source_url.unwrap_or_default(),
+            checksum: v
+                .checksum
+                .map(|c| c.status.as_str().to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+    let analysis = analysis
+        .into_iter()
+        .map(|run| AnalysisRunRow {
+            version_id: run.version_id,
+            kind: run.kind,
+            tool: run.tool,
+            model: run.model.unwrap_or_default(),
+            pages: run.pages,
+            failed: run.failed,
+            started_at: run.started_at.format(TIME_FORMAT).to_string(),
+            finished_at: run.finished_at.format(TIME_FORMAT).to_string(),
+        })
+        .collect();
+
+    let template = ProvenanceTemplate {
+        title: &document.title,
+        doc_id: &document.id,
+        source_id: &document.source_id,
+        source_url: &document.source_url,
+        discovery_method: &document.discovery_method,
+        created_at: document.created_at.format(TIME_FORMAT).to_string(),
+        seed: seed.unwrap_or_default(),
+        chain,
+        requests: requests.into_iter().map(request_row).collect(),
+        versions,
+        analysis,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// Export a document's provenance record: discovery chain, HTTP requests,
+/// version hashes and analysis runs.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{doc_id}/provenance",
+    params(
+        ("doc_id" = String, Path, description = "Document ID"),
+        ProvenanceQuery
+    ),
+    responses(
+        (status = 200, description = "Provenance record", content_type = "application/json"),
+        (status = 404, description = "Document not found")
+    ),
+    tag = "Documents"
+)]
+pub async fn get_provenance(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    Query(params): Query<ProvenanceQuery>,
+) -> impl IntoResponse {
+    let record =
+        match provenance::provenance_record(&state.doc_repo, &state.crawl_repo, &doc_id).await {
+            Ok(Some(r)) => r,
+            Ok(None) => return not_found("Document not found").into_response(),
+            Err(e) => return internal_error(e).into_response(),
+        };
+
+    let json = serde_json::to_string_pretty(&record).unwrap_or_default();
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json");
+    if params.download {
+        response = response.header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"provenance-{}.json\"", doc_id),
+        );
+    }
+    response.body(Body::from(json)).unwrap().into_response()
+}
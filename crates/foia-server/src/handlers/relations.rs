@@ -0,0 +1,240 @@
+//! Document relationship handlers.
+//!
+//! Relations (attachments, replies to requests, shared case numbers) are
+//! detected when documents are annotated or by `foia link-documents`. The
+//! graph page draws them from `/api/documents/{doc_id}/graph`.
+
+use askama::Template;
+use axum::{
+    extract::{Path, Query, State},
+    response::{Html, IntoResponse},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::super::template_structs::{ErrorTemplate, RelationGraphTemplate};
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{internal_error, not_found};
+use foia::models::RelationGraph;
+
+/// Relation hops shown when none are asked for.
+const DEFAULT_DEPTH: u32 = 2;
+const MAX_DEPTH: u32 = 3;
+/// Documents drawn in one graph.
+const MAX_GRAPH_NODES: usize = 60;
+/// Related documents listed for one document.
+const MAX_RELATIONS: usize = 200;
+
+/// Query params for the relation graph.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RelationGraphQuery {
+    /// Relation hops to follow (default: 2, max: 3)
+    pub depth: Option<u32>,
+}
+
+impl RelationGraphQuery {
+    fn depth(&self) -> u32 {
+        self.depth.unwrap_or(DEFAULT_DEPTH).clamp(1, MAX_DEPTH)
+    }
+}
+
+/// A document related to the requested one.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RelationResponse {
+    pub document_id: String,
+    pub title: String,
+    /// attachment, in_response_to or shared_case_number
+    pub kind: String,
+    /// The relation as seen from the requested document, e.g. "Attached to".
+    pub label: String,
+    /// Whether the requested document is the one making the reference.
+    pub outgoing: bool,
+    /// The filename, request number or case number the link was found from.
+    pub evidence: String,
+}
+
+/// A document in a relation graph.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GraphNodeResponse {
+    pub id: String,
+    pub title: String,
+    /// Hops from the requested document.
+    pub depth: u32,
+}
+
+/// A relation in a graph, from the referencing document to the referenced one.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GraphEdgeResponse {
+    pub source: String,
+    pub target: String,
+    pub kind: String,
+    pub label: String,
+    pub evidence: String,
+}
+
+/// The documents around one document.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RelationGraphResponse {
+    /// The requested document first, then by distance.
+    pub nodes: Vec<GraphNodeResponse>,
+    pub edges: Vec<GraphEdgeResponse>,
+}
+
+impl From<RelationGraph> for RelationGraphResponse {
+    fn from(graph: RelationGraph) -> Self {
+        Self {
+            nodes: graph
+                .nodes
+                .into_iter()
+                .map(|n| GraphNodeResponse {
+                    id: n.id,
+                    title: n.title,
+                    depth: n.depth,
+                })
+                .collect(),
+            edges: graph
+                .edges
+                .into_iter()
+                .map(|e| GraphEdgeResponse {
+                    source: e.source_document_id,
+                    target: e.target_document_id,
+                    kind: e.kind.as_str().to_string(),
+                    label: e.kind.label().to_string(),
+                    evidence: e.evidence,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Documents related to a document.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{doc_id}/relations",
+    params(("doc_id" = String, Path, description = "Document ID")),
+    responses(
+        (status = 200, description = "Related documents", body = Vec<RelationResponse>),
+        (status = 404, description = "Document not found")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_document_relations(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+) -> impl IntoResponse {
+    match state.doc_repo.get(&doc_id).await {
+        Ok(None) => return not_found("Document not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+        Ok(Some(_)) => {}
+    }
+    let graph = match state
+        .doc_repo
+        .document_relation_graph(&doc_id, 1, MAX_RELATIONS)
+        .await
+    {
+        Ok(g) => g,
+        Err(e) => return internal_error(e).into_response(),
+    };
+
+    let items: Vec<RelationResponse> = graph
+        .edges
+        .iter()
+        .map(|e| {
+            let outgoing = e.source_document_id == doc_id;
+            let other = e.other(&doc_id);
+            RelationResponse {
+                document_id: other.to_string(),
+                title: graph
+                    .nodes
+                    .iter()
+                    .find(|n| n.id == other)
+                    .map(|n| n.title.clone())
+                    .unwrap_or_default(),
+                kind: e.kind.as_str().to_string(),
+                label: if outgoing {
+                    e.kind.label()
+                } else {
+                    e.kind.inverse_label()
+                }
+                .to_string(),
+                outgoing,
+                evidence: e.evidence.clone(),
+            }
+        })
+        .collect();
+    ApiResponse::ok(items).into_response()
+}
+
+/// Relation graph around a document.
+#[utoipa::path(
+    get,
+    path = "/api/documents/{doc_id}/graph",
+    params(("doc_id" = String, Path, description = "Document ID"), RelationGraphQuery),
+    responses(
+        (status = 200, description = "Related documents and the relations between them", body = RelationGraphResponse),
+        (status = 404, description = "Document not found")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_document_graph(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    Query(params): Query<RelationGraphQuery>,
+) -> impl IntoResponse {
+    match state.doc_repo.get(&doc_id).await {
+        Ok(None) => return not_found("Document not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+        Ok(Some(_)) => {}
+    }
+    match state
+        .doc_repo
+        .document_relation_graph(&doc_id, params.depth(), MAX_GRAPH_NODES)
+        .await
+    {
+        Ok(graph) => ApiResponse::ok(RelationGraphResponse::from(graph)).into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Relation graph page; the graph is loaded from `/api/documents/{doc_id}/graph`.
+pub async fn document_graph(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    Query(params): Query<RelationGraphQuery>,
+) -> impl IntoResponse {
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(d)) => d,
+        Ok(None) => {
+            let template = ErrorTemplate {
+                title: "Not Found",
+                message: "Document not found.",
+            };
+            return Html(
+                template
+                    .render()
+                    .unwrap_or_else(|_| "Not found".to_string()),
+            );
+        }
+        Err(e) => {
+            let msg = format!("Failed to load document: {}", e);
+            let template = ErrorTemplate {
+                title: "Error",
+                message: &msg,
+            };
+            return Html(template.render().unwrap_or(msg));
+        }
+    };
+
+    let template = RelationGraphTemplate {
+        title: &doc.title,
+        doc_id: &doc.id,
+        source_id: &doc.source_id,
+        depth: params.depth(),
+    };
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
@@ -0,0 +1,282 @@
+//! Saved browse views: named filter bookmarks.
+//!
+//! A view is the browse page's query string, so opening one is just
+//! following `/?<query>` and any filtered URL can be shared as is. Addresses
+//! subscribed to a view are emailed new matches after each crawl.
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use foia::repository::SavedViewRecord;
+
+use super::super::AppState;
+use super::api_types::ApiResponse;
+use super::helpers::{bad_request, internal_error, not_found};
+
+/// Browse parameters kept in a saved view. Cursors are dropped so a view
+/// always opens on its first page.
+const VIEW_PARAMS: [&str; 17] = [
+    "types",
+    "tags",
+    "source",
+    "record_types",
+    "poor_ocr",
+    "exemptions",
+    "pdf_producer",
+    "pdf_years",
+    "pdf_postdated",
+    "status",
+    "processing",
+    "start",
+    "end",
+    "q",
+    "sort",
+    "order",
+    "per_page",
+];
+
+/// Longest accepted view name.
+const MAX_NAME_LEN: usize = 100;
+
+/// Save the current browse filters under a name.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveViewRequest {
+    pub name: String,
+    /// Browse query string, e.g. `source=fbi&tags=cointelpro`.
+    pub query: String,
+}
+
+/// Subscribe an address to new matches of a view.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscribeRequest {
+    pub email: String,
+}
+
+/// A saved browse view.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SavedViewResponse {
+    pub name: String,
+    pub query: String,
+    /// Link to the browse page with these filters.
+    pub url: String,
+    pub updated_at: String,
+}
+
+impl From<SavedViewRecord> for SavedViewResponse {
+    fn from(record: SavedViewRecord) -> Self {
+        Self {
+            url: view_url(&record.query),
+            name: record.name,
+            query: record.query,
+            updated_at: record.updated_at,
+        }
+    }
+}
+
+fn view_url(query: &str) -> String {
+    if query.is_empty() {
+        "/".to_string()
+    } else {
+        format!("/?{}", query)
+    }
+}
+
+/// Keep only browse filter parameters, in their original encoding.
+fn normalize_query(query: &str) -> String {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            !value.is_empty() && VIEW_PARAMS.contains(&key)
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// List saved views.
+#[utoipa::path(
+    get,
+    path = "/api/views",
+    responses(
+        (status = 200, description = "Saved views, by name", body = Vec<SavedViewResponse>)
+    ),
+    tag = "Documents"
+)]
+pub async fn api_list_views(State(state): State<AppState>) -> impl IntoResponse {
+    match state.saved_views.list().await {
+        Ok(views) => ApiResponse::ok(
+            views
+                .into_iter()
+                .map(SavedViewResponse::from)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Save a view; an existing view with the same name is replaced.
+#[utoipa::path(
+    post,
+    path = "/api/views",
+    request_body = SaveViewRequest,
+    responses(
+        (status = 200, description = "Saved view", body = SavedViewResponse),
+        (status = 400, description = "Missing or overlong name")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_save_view(
+    State(state): State<AppState>,
+    Json(body): Json<SaveViewRequest>,
+) -> impl IntoResponse {
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return bad_request("View name must be 1-100 characters").into_response();
+    }
+    let query = normalize_query(&body.query);
+    if let Err(e) = state.saved_views.save(name, &query).await {
+        return internal_error(e).into_response();
+    }
+    ApiResponse::ok(SavedViewResponse {
+        name: name.to_string(),
+        url: view_url(&query),
+        query,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    })
+    .into_response()
+}
+
+/// Delete a saved view.
+#[utoipa::path(
+    delete,
+    path = "/api/views/{name}",
+    params(("name" = String, Path, description = "View name")),
+    responses(
+        (status = 200, description = "Deleted"),
+        (status = 404, description = "No view with that name")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_delete_view(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.saved_views.delete(&name).await {
+        Ok(true) => ApiResponse::ok(serde_json::json!({ "deleted": name })).into_response(),
+        Ok(false) => not_found("View not found").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// List the addresses subscribed to a view.
+#[utoipa::path(
+    get,
+    path = "/api/views/{name}/subscriptions",
+    params(("name" = String, Path, description = "View name")),
+    responses(
+        (status = 200, description = "Subscribed addresses", body = Vec<String>)
+    ),
+    tag = "Documents"
+)]
+pub async fn api_list_subscriptions(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> impl IntoResponse {
+    match state.saved_views.list_subscriptions().await {
+        Ok(subscriptions) => ApiResponse::ok(
+            subscriptions
+                .into_iter()
+                .filter(|s| s.view_name == name)
+                .map(|s| s.email)
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Email an address when new documents match a view.
+#[utoipa::path(
+    post,
+    path = "/api/views/{name}/subscriptions",
+    params(("name" = String, Path, description = "View name")),
+    request_body = SubscribeRequest,
+    responses(
+        (status = 200, description = "Subscribed"),
+        (status = 400, description = "Not an email address"),
+        (status = 404, description = "No view with that name")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_subscribe_view(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(body): Json<SubscribeRequest>,
+) -> impl IntoResponse {
+    let email = body.email.trim();
+    if email.is_empty() || !email.contains('@') {
+        return bad_request("Not an email address").into_response();
+    }
+    match state.saved_views.get(&name).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found("View not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    match state.saved_views.subscribe(&name, email).await {
+        Ok(created) => ApiResponse::ok(serde_json::json!({
+            "view": name,
+            "email": email,
+            "created": created,
+        }))
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// Stop emailing an address about a view.
+#[utoipa::path(
+    delete,
+    path = "/api/views/{name}/subscriptions/{email}",
+    params(
+        ("name" = String, Path, description = "View name"),
+        ("email" = String, Path, description = "Subscribed address")
+    ),
+    responses(
+        (status = 200, description = "Unsubscribed"),
+        (status = 404, description = "Not subscribed")
+    ),
+    tag = "Documents"
+)]
+pub async fn api_unsubscribe_view(
+    State(state): State<AppState>,
+    Path((name, email)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match state.saved_views.unsubscribe(&name, &email).await {
+        Ok(true) => ApiResponse::ok(serde_json::json!({ "unsubscribed": email })).into_response(),
+        Ok(false) => not_found("Not subscribed").into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("?source=fbi&cursor=abc&tags=a%2Cb&bogus=1&q="),
+            "source=fbi&tags=a%2Cb"
+        );
+        assert_eq!(normalize_query(""), "");
+        assert_eq!(view_url(""), "/");
+        assert_eq!(view_url("sort=title"), "/?sort=title");
+    }
+}
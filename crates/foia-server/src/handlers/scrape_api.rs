@@ -11,9 +11,10 @@ use utoipa::{IntoParams, ToSchema};
 use super::super::AppState;
 use super::api_types::{
     ApiResponse, CrawlState, FailedUrl, QueueItem, QueueResponse, RecentUrl, RequestStats,
-    RetryResponse, ScraperCrawlStats, ScraperInfo, ScraperStatusResponse,
+    RetryResponse, ScraperCrawlStats, ScraperInfo, ScraperStatusResponse, UrlActionResponse,
 };
-use super::helpers::{internal_error, not_found};
+use super::helpers::{bad_request, internal_error, not_found, paginate, parse_cursor_param};
+use foia::models::{CrawlUrl, DiscoveryMethod, UrlStatus};
 
 /// List all scrapers/sources with their configuration.
 #[utoipa::path(
@@ -171,20 +172,196 @@ pub async fn list_queue(
         Vec::new()
     };
 
-    let items: Vec<QueueItem> = pending
-        .into_iter()
-        .map(|u| QueueItem {
-            url: u.url,
-            source_id: u.source_id,
-            status: format!("{:?}", u.status),
-            discovery_method: format!("{:?}", u.discovery_method),
-            discovered_at: u.discovered_at.to_rfc3339(),
-            retry_count: u.retry_count,
-            depth: u.depth,
+    // Fetched URLs drop out of the queue, so there is only ever a first page
+    let items: Vec<QueueItem> = pending.into_iter().map(queue_item).collect();
+
+    ApiResponse::ok(QueueResponse {
+        items,
+        per_page,
+        next_cursor: None,
+        prev_cursor: None,
+    })
+    .into_response()
+}
+
+fn queue_item(u: CrawlUrl) -> QueueItem {
+    QueueItem {
+        url: u.url,
+        source_id: u.source_id,
+        status: format!("{:?}", u.status),
+        discovery_method: format!("{:?}", u.discovery_method),
+        discovered_at: u.discovered_at.to_rfc3339(),
+        retry_count: u.retry_count,
+        depth: u.depth,
+        last_error: u.last_error,
+    }
+}
+
+/// Query for a source's URL listing.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SourceUrlsQuery {
+    /// `pending` (default), `failed`, `skipped`, `fetched`, or `all`.
+    pub status: Option<String>,
+    /// Opaque cursor from a previous response's `next_cursor`/`prev_cursor`
+    pub cursor: Option<String>,
+    pub per_page: Option<usize>,
+}
+
+/// List a source's crawl URLs by status.
+#[utoipa::path(
+    get,
+    path = "/api/scrapers/{source_id}/urls",
+    params(("source_id" = String, Path, description = "Source ID"), SourceUrlsQuery),
+    responses(
+        (status = 200, description = "URL listing", body = QueueResponse),
+        (status = 400, description = "Unknown status filter")
+    ),
+    tag = "Scrapers"
+)]
+pub async fn list_source_urls(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+    Query(params): Query<SourceUrlsQuery>,
+) -> impl IntoResponse {
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
+    let Some(statuses) = UrlStatus::parse_filter(params.status.as_deref().unwrap_or("pending"))
+    else {
+        return bad_request("Unknown status filter").into_response();
+    };
+
+    match state
+        .crawl_repo
+        .list_urls(&source_id, &statuses, cursor.as_ref(), per_page as u32)
+        .await
+    {
+        Ok(urls) => ApiResponse::ok(QueueResponse {
+            next_cursor: urls.next_cursor.map(|c| c.encode()),
+            prev_cursor: urls.prev_cursor.map(|c| c.encode()),
+            items: urls.items.into_iter().map(queue_item).collect(),
+            per_page,
         })
-        .collect();
+        .into_response(),
+        Err(e) => internal_error(e).into_response(),
+    }
+}
+
+/// URLs to act on.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UrlActionRequest {
+    pub urls: Vec<String>,
+}
+
+/// Add seed URLs to a source's queue.
+#[utoipa::path(
+    post,
+    path = "/api/scrapers/{source_id}/urls",
+    params(("source_id" = String, Path, description = "Source ID")),
+    request_body = UrlActionRequest,
+    responses(
+        (status = 200, description = "URLs added", body = UrlActionResponse),
+        (status = 400, description = "Invalid URL"),
+        (status = 404, description = "Source not found")
+    ),
+    tag = "Scrapers"
+)]
+pub async fn add_source_urls(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+    Json(body): Json<UrlActionRequest>,
+) -> impl IntoResponse {
+    match state.source_repo.get(&source_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found("Source not found").into_response(),
+        Err(e) => return internal_error(e).into_response(),
+    }
+    if let Some(bad) = body
+        .urls
+        .iter()
+        .find(|u| !u.starts_with("http://") && !u.starts_with("https://"))
+    {
+        return bad_request(&format!("Not an http(s) URL: {}", bad)).into_response();
+    }
+
+    let mut added = 0;
+    for url in body.urls {
+        let crawl_url = CrawlUrl::new(url, source_id.clone(), DiscoveryMethod::Seed, None, 0);
+        match state.crawl_repo.add_url(&crawl_url).await {
+            Ok(true) => added += 1,
+            Ok(false) => {}
+            Err(e) => return internal_error(e).into_response(),
+        }
+    }
+
+    ApiResponse::ok(UrlActionResponse {
+        updated: added,
+        message: format!("Added {} seed URLs", added),
+    })
+    .into_response()
+}
+
+/// Re-queue individual URLs, clearing their retry state.
+#[utoipa::path(
+    post,
+    path = "/api/scrapers/{source_id}/urls/retry",
+    params(("source_id" = String, Path, description = "Source ID")),
+    request_body = UrlActionRequest,
+    responses(
+        (status = 200, description = "URLs re-queued", body = UrlActionResponse)
+    ),
+    tag = "Scrapers"
+)]
+pub async fn retry_source_urls(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+    Json(body): Json<UrlActionRequest>,
+) -> impl IntoResponse {
+    let mut updated = 0;
+    for url in &body.urls {
+        match state.crawl_repo.retry_url(&source_id, url).await {
+            Ok(true) => updated += 1,
+            Ok(false) => {}
+            Err(e) => return internal_error(e).into_response(),
+        }
+    }
+
+    ApiResponse::ok(UrlActionResponse {
+        updated,
+        message: format!("Re-queued {} URLs", updated),
+    })
+    .into_response()
+}
+
+/// Take individual URLs out of the queue (marked skipped).
+#[utoipa::path(
+    post,
+    path = "/api/scrapers/{source_id}/urls/cancel",
+    params(("source_id" = String, Path, description = "Source ID")),
+    request_body = UrlActionRequest,
+    responses(
+        (status = 200, description = "URLs cancelled", body = UrlActionResponse)
+    ),
+    tag = "Scrapers"
+)]
+pub async fn cancel_source_urls(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+    Json(body): Json<UrlActionRequest>,
+) -> impl IntoResponse {
+    let mut updated = 0;
+    for url in &body.urls {
+        match state.crawl_repo.cancel_url(&source_id, url).await {
+            Ok(true) => updated += 1,
+            Ok(false) => {}
+            Err(e) => return internal_error(e).into_response(),
+        }
+    }
 
-    ApiResponse::ok(QueueResponse { items, per_page }).into_response()
+    ApiResponse::ok(UrlActionResponse {
+        updated,
+        message: format!("Cancelled {} URLs", updated),
+    })
+    .into_response()
 }
 
 /// Clear failed URLs for retry.
@@ -9,7 +9,9 @@ use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, ToSchema};
 
 use super::super::AppState;
-use super::helpers::{bad_request, internal_error, paginate, PaginatedResponse};
+use super::helpers::{
+    bad_request, internal_error, paginate, parse_cursor_param, CursorPaginatedResponse,
+};
 use foia::models::DocumentVersion;
 
 #[derive(Debug, Deserialize, IntoParams)]
@@ -20,8 +22,8 @@ pub struct SearchQuery {
     pub source: Option<String>,
     /// Filter to a single document
     pub document_id: Option<String>,
-    /// Page number (1-indexed)
-    pub page: Option<usize>,
+    /// Opaque cursor from a previous response's `next_cursor`
+    pub cursor: Option<String>,
     /// Items per page (default: 50, max: 200)
     pub per_page: Option<usize>,
 }
@@ -39,14 +41,14 @@ pub struct SearchResult {
 /// Search document page content.
 ///
 /// Uses Postgres full-text search (tsvector/tsquery) with headline snippets,
-/// or LIKE fallback on SQLite. Returns page-level matches — a document can
+/// or the page search index on SQLite. Returns page-level matches — a document can
 /// appear multiple times with different page numbers and snippets.
 #[utoipa::path(
     get,
     path = "/api/search",
     params(SearchQuery),
     responses(
-        (status = 200, description = "Paginated search results", body = PaginatedResponse<SearchResult>),
+        (status = 200, description = "Paginated search results", body = CursorPaginatedResponse<SearchResult>),
         (status = 400, description = "Missing or empty search query")
     ),
     tag = "Search"
@@ -60,7 +62,8 @@ pub async fn search_content(
         return bad_request("Search query 'q' cannot be empty").into_response();
     }
 
-    let (page, per_page, offset) = paginate(params.page, params.per_page);
+    let (_, per_page, _) = paginate(None, params.per_page);
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
     let total = match state
         .doc_repo
@@ -71,42 +74,45 @@ pub async fn search_content(
         Err(e) => return internal_error(e).into_response(),
     };
 
-    let rows = match state
+    let mut page = match state
         .doc_repo
         .search_page_content(
             q,
             params.source.as_deref(),
             params.document_id.as_deref(),
+            cursor.as_ref(),
             per_page,
-            offset,
         )
         .await
     {
-        Ok(r) => r,
+        Ok(p) => p,
         Err(e) => return internal_error(e).into_response(),
     };
+    if state.withhold_flagged {
+        match state.flags.open_document_ids().await {
+            Ok(withheld) => page.items.retain(|r| !withheld.contains(&r.document_id)),
+            Err(e) => return internal_error(e).into_response(),
+        }
+    }
 
-    let items: Vec<SearchResult> = rows
-        .into_iter()
-        .map(|r| {
-            let file_url = DocumentVersion::build_file_url(
-                &r.content_hash,
-                &r.version_mime_type,
-                r.original_filename.as_deref(),
-                r.dedup_index.map(|i| i as u32),
-                &r.source_url,
-                &r.title,
-            );
-            SearchResult {
-                document_id: r.document_id,
-                title: r.title,
-                source_id: r.source_id,
-                page_number: r.page_number,
-                headline: r.headline,
-                file_url,
-            }
-        })
-        .collect();
+    let page = page.map(|r| {
+        let file_url = DocumentVersion::build_file_url(
+            &r.content_hash,
+            &r.version_mime_type,
+            r.original_filename.as_deref(),
+            r.dedup_index.map(|i| i as u32),
+            &r.source_url,
+            &r.title,
+        );
+        SearchResult {
+            document_id: r.document_id,
+            title: r.title,
+            source_id: r.source_id,
+            page_number: r.page_number,
+            headline: r.headline,
+            file_url,
+        }
+    });
 
-    Json(PaginatedResponse::new(items, page, per_page, total)).into_response()
+    Json(CursorPaginatedResponse::new(page, per_page, total)).into_response()
 }
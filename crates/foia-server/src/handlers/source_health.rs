@@ -0,0 +1,123 @@
+//! Per-source crawl health page.
+
+use askama::Template;
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse},
+};
+use chrono::Utc;
+
+use super::super::template_structs::{ErrorTemplate, SourceHealthRow, SourceHealthTemplate};
+use super::super::AppState;
+use foia::services::source_health::{self, HEALTH_DAYS};
+use foia::utils::sparkline;
+
+fn render_error(msg: &str) -> Html<String> {
+    let template = ErrorTemplate {
+        title: "Error",
+        message: msg,
+    };
+    Html(template.render().unwrap_or_else(|_| msg.to_string()))
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.1}%", r))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_latency(ms: Option<u64>) -> String {
+    ms.map(|ms| format!("{} ms", ms))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn rate_class(rate: Option<f64>) -> &'static str {
+    match rate {
+        Some(r) if r >= 95.0 => "status-ok",
+        Some(r) if r >= 75.0 => "status-client-error",
+        Some(_) => "status-server-error",
+        None => "status-redirect",
+    }
+}
+
+/// Success rate, latency and rate limiting for one source, from its
+/// request log.
+pub async fn source_health(
+    State(state): State<AppState>,
+    Path(source_id): Path<String>,
+) -> impl IntoResponse {
+    let source = match state.source_repo.get(&source_id).await {
+        Ok(Some(s)) => s,
+        Ok(None) => return render_error("Source not found"),
+        Err(e) => return render_error(&format!("Failed to load source: {}", e)),
+    };
+
+    let health = match source_health::source_health(&state.crawl_repo, &source_id).await {
+        Ok(h) => h,
+        Err(e) => return render_error(&format!("Failed to load crawl health: {}", e)),
+    };
+
+    let now = Utc::now();
+    let stale = health.is_stale(now);
+    let last_success = match health.last_success {
+        Some(at) => {
+            let days = (now - at).num_days();
+            let ago = match days {
+                0 => "today".to_string(),
+                1 => "1 day ago".to_string(),
+                n => format!("{} days ago", n),
+            };
+            format!("{} ({})", at.format("%Y-%m-%d %H:%M"), ago)
+        }
+        None => "never".to_string(),
+    };
+
+    // Days without requests show as gaps
+    let success_trend = sparkline(
+        &health
+            .days
+            .iter()
+            .map(|d| d.success_rate().map_or(0, |r| r.round().max(1.0) as u64))
+            .collect::<Vec<_>>(),
+    );
+    let request_trend = sparkline(&health.days.iter().map(|d| d.requests).collect::<Vec<_>>());
+
+    let rows: Vec<SourceHealthRow> = health
+        .days
+        .iter()
+        .rev()
+        .filter(|d| d.requests > 0)
+        .map(|d| SourceHealthRow {
+            date: d.date.format("%Y-%m-%d").to_string(),
+            requests: d.requests,
+            success_rate: format_rate(d.success_rate()),
+            success_class: rate_class(d.success_rate()),
+            avg_latency: format_latency(d.avg_latency_ms),
+            rate_limited: d.rate_limited,
+        })
+        .collect();
+
+    let title = format!("Health: {}", source.name);
+    let template = SourceHealthTemplate {
+        title: &title,
+        source_id: &source_id,
+        source_name: &source.name,
+        days: HEALTH_DAYS,
+        requests: health.requests,
+        success_rate: format_rate(health.success_rate()),
+        success_class: rate_class(health.success_rate()),
+        avg_latency: format_latency(health.avg_latency_ms),
+        rate_limited: health.rate_limited,
+        rate_limited_days: health.rate_limited_days(),
+        last_success,
+        stale,
+        success_trend,
+        request_trend,
+        rows,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
@@ -1,29 +1,54 @@
 //! Static file serving handlers.
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::{header, StatusCode},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
     response::{IntoResponse, Response},
 };
 use serde::Deserialize;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+use foia::artifacts::{ArtifactStore, ARTIFACTS_SUBDIR};
+use foia::repository::diesel_access_log::AccessKind;
 
 use super::super::assets;
-use super::super::AppState;
+use super::super::redacted::{self, PublicCopy};
+use super::super::{access_log, flags, AppState};
 
 #[derive(Debug, Deserialize)]
 pub struct FileQuery {
     pub filename: Option<String>,
 }
 
+/// Request headers passed through to the file service for ranges and
+/// date-based conditional requests.
+const FORWARDED_HEADERS: [header::HeaderName; 4] = [
+    header::RANGE,
+    header::IF_RANGE,
+    header::IF_MODIFIED_SINCE,
+    header::IF_UNMODIFIED_SINCE,
+];
+
 /// Serve a document file.
 ///
-/// When a `filename` query parameter is provided, the response includes a
-/// `Content-Disposition` header so browsers use the original filename for
-/// downloads instead of the content-addressable storage name.
+/// Files are streamed from disk with byte-range support, so large PDFs can
+/// be seeked in browser viewers without loading them into memory. Stored
+/// files are content-addressed and never change, so the ETag is the
+/// content hash and `If-None-Match` is answered with 304.
+///
+/// The `Content-Disposition` filename is the `filename` query parameter if
+/// given, else the original filename recorded for the content, so downloads
+/// get a meaningful name instead of the storage name.
+///
+/// In public mode a file with a redacted copy is served as that copy, and
+/// derived files aren't served directly.
 pub async fn serve_file(
     State(state): State<AppState>,
     Path(path): Path<String>,
     Query(params): Query<FileQuery>,
+    headers: HeaderMap,
 ) -> Response {
     let canonical_docs_dir = match state.documents_dir.canonicalize() {
         Ok(p) => p,
@@ -36,34 +61,98 @@ pub async fn serve_file(
         }
     };
 
-    if path.contains("..") || path.starts_with('/') {
+    if path.contains("..")
+        || path.starts_with('/')
+        || (state.public && path.split('/').next() == Some(ARTIFACTS_SUBDIR))
+    {
         return (StatusCode::NOT_FOUND, "File not found").into_response();
     }
 
     let file_path = canonical_docs_dir.join(&path);
 
-    let canonical_file = match file_path.canonicalize() {
+    let mut canonical_file = match file_path.canonicalize() {
         Ok(p) => p,
         Err(_) => {
             return (StatusCode::NOT_FOUND, "File not found").into_response();
         }
     };
 
-    if !canonical_file.starts_with(&canonical_docs_dir) {
+    if !canonical_file.starts_with(&canonical_docs_dir) || !canonical_file.is_file() {
         return (StatusCode::NOT_FOUND, "File not found").into_response();
     }
 
-    let content = match tokio::fs::read(&canonical_file).await {
-        Ok(c) => c,
-        Err(_) => {
-            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read file").into_response();
-        }
+    let stored = match stored_hash_prefix(&path) {
+        Some(prefix) => state
+            .doc_repo
+            .find_file_by_hash_prefix(prefix)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
     };
+    if let Some((hash, _)) = &stored {
+        if state.withhold_flagged && file_withheld(&state, hash).await {
+            return (StatusCode::NOT_FOUND, "File not found").into_response();
+        }
+    }
+    let mut served_hash = stored.as_ref().map(|(hash, _)| hash.clone());
+    if let Some((hash, _)) = &stored {
+        match redacted::file_copy(&state, hash).await {
+            PublicCopy::Original => {}
+            PublicCopy::Redacted(artifact) => {
+                canonical_file =
+                    ArtifactStore::new(&state.documents_dir).resolve(&artifact.file_path);
+                served_hash = Some(artifact.content_hash);
+            }
+            PublicCopy::Withheld => {
+                return (StatusCode::NOT_FOUND, "File not found").into_response();
+            }
+        }
+    }
+    let etag = served_hash.map(|hash| format!("\"{}\"", hash));
+
+    if let Some(etag) = &etag {
+        if header_matches_etag(headers.get(header::IF_NONE_MATCH), etag) {
+            return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag.clone())]).into_response();
+        }
+    }
+
+    let mut request = Request::new(Body::empty());
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = headers.get(&name) {
+            request.headers_mut().insert(name, value.clone());
+        }
+    }
+    // The file service only understands dates in If-Range; resolve ETags here
+    if let Some(if_range) = request.headers_mut().remove(header::IF_RANGE) {
+        let is_etag =
+            if_range.as_bytes().starts_with(b"\"") || if_range.as_bytes().starts_with(b"W/");
+        if !is_etag {
+            request.headers_mut().insert(header::IF_RANGE, if_range);
+        } else if !etag
+            .as_deref()
+            .is_some_and(|etag| if_range.as_bytes() == etag.as_bytes())
+        {
+            request.headers_mut().remove(header::RANGE);
+        }
+    }
 
-    let mut mime = mime_guess::from_path(&canonical_file)
+    // Errors from the file service come back as responses
+    let Ok(response) = ServeFile::new(&canonical_file).oneshot(request).await;
+    let mut response = response.map(Body::new);
+    if let Some((hash, _)) = &stored {
+        if access_log::is_counted(&state, &headers)
+            && is_whole_download(response.status(), &headers)
+        {
+            record_download(&state, hash, &headers).await;
+        }
+    }
+
+    let mime = mime_guess::from_path(&canonical_file)
         .first_or_octet_stream()
         .to_string();
 
+    let response_headers = response.headers_mut();
     // Serve HTML/SVG/XML as plain text to prevent stored XSS from scraped content
     if mime.starts_with("text/html")
         || mime.starts_with("application/xhtml")
@@ -71,27 +160,179 @@ pub async fn serve_file(
         || mime.starts_with("text/xml")
         || mime.starts_with("application/xml")
     {
-        mime = "text/plain; charset=utf-8".to_string();
+        response_headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+    }
+
+    let filename = params
+        .filename
+        .or_else(|| stored.and_then(|(_, name)| name));
+    if let Ok(value) = HeaderValue::from_str(&content_disposition(filename.as_deref())) {
+        response_headers.insert(header::CONTENT_DISPOSITION, value);
+    }
+    response_headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if let Some(value) = etag.and_then(|e| HeaderValue::from_str(&e).ok()) {
+        response_headers.insert(header::ETAG, value);
+        response_headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("private, max-age=86400"),
+        );
+    }
+
+    response
+}
+
+/// Whether the file with `hash` belongs to a withheld document. Lookup
+/// errors count as withheld.
+async fn file_withheld(state: &AppState, hash: &str) -> bool {
+    let found = match state.doc_repo.find_sources_by_hash(hash, None).await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!("Failed to look up documents for file {}: {}", hash, e);
+            return true;
+        }
+    };
+    for (_, document_id, _) in &found {
+        if flags::is_withheld(state, document_id).await {
+            return true;
+        }
+    }
+    false
+}
+
+/// Whether a response starts a download of the file, rather than being a
+/// later range request from a viewer seeking through it.
+fn is_whole_download(status: StatusCode, headers: &HeaderMap) -> bool {
+    match status {
+        StatusCode::OK => true,
+        StatusCode::PARTIAL_CONTENT => headers
+            .get(header::RANGE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|range| range.trim().starts_with("bytes=0-")),
+        _ => false,
+    }
+}
+
+/// Count a download against the document holding the file with `hash`.
+async fn record_download(state: &AppState, hash: &str, headers: &HeaderMap) {
+    match state.doc_repo.find_sources_by_hash(hash, None).await {
+        Ok(found) => {
+            if let Some((_, document_id, _)) = found.first() {
+                access_log::record(state, document_id, AccessKind::Download, headers);
+            }
+        }
+        Err(e) => tracing::debug!("No document found for file {}: {}", hash, e),
     }
+}
+
+/// Hash prefix in a storage path's file name (`{prefix}/{name}-{hash8}.{ext}`).
+fn stored_hash_prefix(path: &str) -> Option<&str> {
+    let file_name = path.rsplit('/').next()?;
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem);
+    let prefix = stem.rsplit_once('-')?.1;
+    (prefix.len() == 8 && prefix.bytes().all(|b| b.is_ascii_hexdigit())).then_some(prefix)
+}
 
-    let disposition = match params.filename {
-        Some(name) => format!("inline; filename=\"{}\"", name.replace('"', "_")),
+/// Whether an `If-None-Match` header lists `etag` (or is `*`).
+pub(crate) fn header_matches_etag(header: Option<&HeaderValue>, etag: &str) -> bool {
+    let Some(value) = header.and_then(|h| h.to_str().ok()) else {
+        return false;
+    };
+    value
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag)
+}
+
+/// `inline` disposition, with a filename in both the plain and RFC 5987
+/// forms so non-ASCII names survive.
+fn content_disposition(filename: Option<&str>) -> String {
+    match filename {
+        Some(name) => {
+            let ascii: String = name
+                .chars()
+                .map(|c| {
+                    if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            format!(
+                "inline; filename=\"{}\"; filename*=UTF-8''{}",
+                ascii,
+                urlencoding::encode(name)
+            )
+        }
         None => "inline".to_string(),
+    }
+}
+
+/// Serve a document's listing thumbnail, rendering it on first request.
+pub async fn serve_thumbnail(
+    State(state): State<AppState>,
+    Path(doc_id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let doc = match state.doc_repo.get(&doc_id).await {
+        Ok(Some(doc)) => doc,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Document not found").into_response(),
+        Err(_) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to load document").into_response()
+        }
+    };
+    let Some(version) = doc.current_version() else {
+        return (StatusCode::NOT_FOUND, "No thumbnail").into_response();
     };
 
-    (
-        [
-            (header::CONTENT_TYPE, mime),
-            (header::CONTENT_DISPOSITION, disposition),
-        ],
-        content,
-    )
-        .into_response()
+    let Some((source, content_hash)) = redacted::served_file(&state, &doc, version).await else {
+        return (StatusCode::NOT_FOUND, "No thumbnail").into_response();
+    };
+
+    let etag = format!("\"thumb-{}\"", content_hash);
+    if header_matches_etag(headers.get(header::IF_NONE_MATCH), &etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    match state
+        .thumbnails
+        .get_or_render(&source, &version.mime_type, &content_hash)
+        .await
+    {
+        Ok(Some(bytes)) => (
+            [
+                (header::CONTENT_TYPE, "image/jpeg".to_string()),
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, "private, max-age=86400".to_string()),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No thumbnail").into_response(),
+        Err(e) => {
+            tracing::warn!("Thumbnail for {} failed: {}", doc_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to render thumbnail",
+            )
+                .into_response()
+        }
+    }
 }
 
 /// Serve CSS.
-pub async fn serve_css() -> impl IntoResponse {
-    ([(header::CONTENT_TYPE, "text/css")], assets::CSS)
+pub async fn serve_css(State(state): State<AppState>) -> impl IntoResponse {
+    let css = if state.public {
+        assets::PUBLIC_CSS
+    } else {
+        assets::CSS
+    };
+    ([(header::CONTENT_TYPE, "text/css")], css)
 }
 
 /// Serve JavaScript.
@@ -101,3 +342,42 @@ pub async fn serve_js() -> impl IntoResponse {
         assets::JS,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stored_hash_prefix() {
+        assert_eq!(
+            stored_hash_prefix("ab/report-ab12cd34.pdf"),
+            Some("ab12cd34")
+        );
+        assert_eq!(
+            stored_hash_prefix("ab12/my.file-name-ab12cd34.pdf"),
+            Some("ab12cd34")
+        );
+        assert_eq!(stored_hash_prefix("ab/report.pdf"), None);
+        assert_eq!(stored_hash_prefix("ab/report-zz12cd34.pdf"), None);
+    }
+
+    #[test]
+    fn test_etag_matching() {
+        let etag = "\"abc\"";
+        let header = |v: &'static str| HeaderValue::from_static(v);
+        assert!(header_matches_etag(Some(&header("\"abc\"")), etag));
+        assert!(header_matches_etag(Some(&header("\"x\", W/\"abc\"")), etag));
+        assert!(header_matches_etag(Some(&header("*")), etag));
+        assert!(!header_matches_etag(Some(&header("\"abd\"")), etag));
+        assert!(!header_matches_etag(None, etag));
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        assert_eq!(content_disposition(None), "inline");
+        assert_eq!(
+            content_disposition(Some("Memo \"final\" – 2019.pdf")),
+            "inline; filename=\"Memo _final_ _ 2019.pdf\"; filename*=UTF-8''Memo%20%22final%22%20%E2%80%93%202019.pdf"
+        );
+    }
+}
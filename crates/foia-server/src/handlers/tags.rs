@@ -2,20 +2,36 @@
 
 use askama::Template;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::{Html, IntoResponse},
 };
+use foia::models::{is_valid_tag_namespace, split_tag_namespace, KNOWN_TAG_NAMESPACES};
+use serde::Deserialize;
 
 use super::super::template_structs::{
-    DocumentRow, ErrorTemplate, TagDocumentsTemplate, TagWithCount, TagsTemplate,
+    DocumentRow, ErrorTemplate, TagDocumentsTemplate, TagGroup, TagNamespaceTemplate, TagWithCount,
+    TagsTemplate,
 };
 use super::super::AppState;
 use super::api_types::{ApiResponse, TagCount};
+use super::helpers::parse_cursor_param;
 
-/// List all tags with document counts.
+/// Documents shown per page on a tag's listing.
+const TAG_PAGE_SIZE: usize = 100;
+
+/// Query params for a tag's document listing.
+#[derive(Debug, Deserialize)]
+pub struct TagDocumentsParams {
+    pub cursor: Option<String>,
+}
+
+/// List all tags with document counts, grouped by namespace.
 pub async fn list_tags(State(state): State<AppState>) -> impl IntoResponse {
-    let tags = match state.doc_repo.get_all_tags().await {
-        Ok(t) => t,
+    let (tags, namespaces) = match tokio::try_join!(
+        state.doc_repo.get_tag_counts(None),
+        state.doc_repo.get_tag_namespaces(),
+    ) {
+        Ok(r) => r,
         Err(e) => {
             let msg = format!("Failed to load tags: {}", e);
             let template = ErrorTemplate {
@@ -26,13 +42,87 @@ pub async fn list_tags(State(state): State<AppState>) -> impl IntoResponse {
         }
     };
 
-    let tags_with_counts: Vec<TagWithCount> =
-        tags.into_iter().map(|t| TagWithCount::new(t, 0)).collect();
+    // Known namespaces first, then the rest by usage, then unprefixed tags
+    let mut groups: Vec<TagGroup> = namespaces
+        .into_iter()
+        .map(|ns| TagGroup {
+            namespace: ns.namespace,
+            document_count: ns.document_count,
+            tags: Vec::new(),
+        })
+        .collect();
+    groups.sort_by_key(|g| {
+        KNOWN_TAG_NAMESPACES
+            .iter()
+            .position(|k| *k == g.namespace)
+            .unwrap_or(KNOWN_TAG_NAMESPACES.len())
+    });
+    let mut other = TagGroup {
+        namespace: String::new(),
+        document_count: 0,
+        tags: Vec::new(),
+    };
+
+    for (tag, count) in tags {
+        let index = split_tag_namespace(&tag)
+            .and_then(|(ns, _)| groups.iter().position(|g| g.namespace == ns));
+        let group = match index {
+            Some(i) => &mut groups[i],
+            None => &mut other,
+        };
+        group.tags.push(TagWithCount::new(tag, count as usize));
+    }
+    if !other.tags.is_empty() {
+        groups.push(other);
+    }
 
     let template = TagsTemplate {
         title: "Tags",
-        has_tags: !tags_with_counts.is_empty(),
-        tags: tags_with_counts,
+        has_tags: !groups.is_empty(),
+        groups,
+    };
+
+    Html(
+        template
+            .render()
+            .unwrap_or_else(|e| format!("Template error: {}", e)),
+    )
+}
+
+/// List the tags within one namespace (`/tags/agency/`).
+pub async fn list_tag_namespace(
+    State(state): State<AppState>,
+    Path(namespace): Path<String>,
+) -> impl IntoResponse {
+    if !is_valid_tag_namespace(&namespace) {
+        let msg = format!("Invalid tag namespace: {}", namespace);
+        let template = ErrorTemplate {
+            title: "Error",
+            message: &msg,
+        };
+        return Html(template.render().unwrap_or(msg));
+    }
+
+    let tags = match state.doc_repo.get_tag_counts(Some(&namespace)).await {
+        Ok(t) => t,
+        Err(e) => {
+            let msg = format!("Failed to load tags: {}", e);
+            let template = ErrorTemplate {
+                title: "Error",
+                message: &msg,
+            };
+            return Html(template.render().unwrap_or(msg));
+        }
+    };
+
+    let title = format!("Tags: {}", namespace);
+    let template = TagNamespaceTemplate {
+        title: &title,
+        namespace: &namespace,
+        tags: tags
+            .into_iter()
+            .map(|(tag, count)| TagWithCount::new(tag, count as usize))
+            .collect(),
     };
 
     Html(
@@ -46,13 +136,19 @@ pub async fn list_tags(State(state): State<AppState>) -> impl IntoResponse {
 pub async fn list_tag_documents(
     State(state): State<AppState>,
     Path(tag): Path<String>,
+    Query(params): Query<TagDocumentsParams>,
 ) -> impl IntoResponse {
     let tag = urlencoding::decode(&tag)
         .unwrap_or(std::borrow::Cow::Borrowed(&tag))
         .to_string();
+    let cursor = parse_cursor_param(params.cursor.as_ref());
 
-    let documents = match state.doc_repo.get_by_tag(&tag, None).await {
-        Ok(docs) => docs,
+    let page = match state
+        .doc_repo
+        .get_by_tag(&tag, None, cursor.as_ref(), TAG_PAGE_SIZE)
+        .await
+    {
+        Ok(page) => page,
         Err(e) => {
             let msg = format!("Failed to load documents: {}", e);
             let template = ErrorTemplate {
@@ -63,17 +159,24 @@ pub async fn list_tag_documents(
         }
     };
 
-    let doc_rows: Vec<DocumentRow> = documents
+    let doc_rows: Vec<DocumentRow> = page
+        .items
         .iter()
         .filter_map(|doc| DocumentRow::from_document(doc).map(|row| row.with_other_tags(&tag)))
         .collect();
 
     let title = format!("Tag: {}", tag);
+    let tag_namespace = split_tag_namespace(&tag).map_or("", |(ns, _)| ns);
     let template = TagDocumentsTemplate {
         title: &title,
         tag: &tag,
+        tag_namespace,
         document_count: doc_rows.len(),
         documents: doc_rows,
+        has_prev_cursor: page.prev_cursor.is_some(),
+        prev_cursor_val: page.prev_cursor.map(|c| c.encode()).unwrap_or_default(),
+        has_next_cursor: page.next_cursor.is_some(),
+        next_cursor_val: page.next_cursor.map(|c| c.encode()).unwrap_or_default(),
     };
 
     Html(
@@ -20,6 +20,9 @@ pub struct VersionResponse {
     pub file_url: String,
     pub file_size: u64,
     pub mime_type: String,
+    /// Content-Type the server sent, when the content showed it was wrong
+    /// and `mime_type` is the detected type.
+    pub declared_mime_type: Option<String>,
     pub acquired_at: String,
     pub source_url: Option<String>,
     pub original_filename: Option<String>,
@@ -27,10 +30,18 @@ pub struct VersionResponse {
     pub page_count: Option<u32>,
     pub archive_snapshot_id: Option<i32>,
     pub earliest_archived_at: Option<String>,
+    /// Published checksum check: `verified`, `mismatch` or `bad_signature`.
+    pub checksum_status: Option<String>,
+    /// Manifest or sidecar the published checksum came from.
+    pub checksum_manifest_url: Option<String>,
 }
 
 impl VersionResponse {
-    fn from_version(v: foia::models::DocumentVersion, doc_source_url: &str, doc_title: &str) -> Self {
+    fn from_version(
+        v: foia::models::DocumentVersion,
+        doc_source_url: &str,
+        doc_title: &str,
+    ) -> Self {
         let file_url = v.file_url(doc_source_url, doc_title);
         Self {
             id: v.id,
@@ -39,6 +50,7 @@ impl VersionResponse {
             file_url,
             file_size: v.file_size,
             mime_type: v.mime_type,
+            declared_mime_type: v.declared_mime_type,
             acquired_at: v.acquired_at.to_rfc3339(),
             source_url: v.source_url,
             original_filename: v.original_filename,
@@ -46,6 +58,8 @@ impl VersionResponse {
             page_count: v.page_count,
             archive_snapshot_id: v.archive_snapshot_id,
             earliest_archived_at: v.earliest_archived_at.map(|d| d.to_rfc3339()),
+            checksum_status: v.checksum.as_ref().map(|c| c.status.as_str().to_string()),
+            checksum_manifest_url: v.checksum.map(|c| c.manifest_url),
         }
     }
 }
@@ -108,7 +122,12 @@ pub async fn get_version(
     match state.doc_repo.get(&doc_id).await {
         Ok(Some(doc)) => {
             if let Some(version) = doc.versions.into_iter().find(|v| v.id == version_id) {
-                ApiResponse::ok(VersionResponse::from_version(version, &doc.source_url, &doc.title)).into_response()
+                ApiResponse::ok(VersionResponse::from_version(
+                    version,
+                    &doc.source_url,
+                    &doc.title,
+                ))
+                .into_response()
             } else {
                 not_found("Version not found").into_response()
             }
@@ -122,7 +141,7 @@ pub async fn get_version(
 #[utoipa::path(
     get,
     path = "/api/versions/hash/{hash}",
-    params(("hash" = String, Path, description = "Content hash to search for")),
+    params(("hash" = String, Path, description = "BLAKE3 or SHA-256 content hash to search for")),
     responses(
         (status = 200, description = "Sources containing this hash", body = HashSearchResponse)
     ),
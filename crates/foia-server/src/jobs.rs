@@ -0,0 +1,714 @@
+//! Background jobs started from the web UI or API.
+//!
+//! Submitting a job records it as queued and spawns a task that waits for
+//! a free slot, so only a few run at once. Runners report progress after
+//! each item, and stop at that point when cancellation was requested
+//! (from the UI or `foia jobs cancel`). On shutdown, running jobs stop at
+//! their next report and are failed as interrupted; jobs left running by a
+//! crash are failed at startup.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+
+use foia::config::{ExtractionLimits, OcrConfig};
+use foia::http_client::HttpClient;
+use foia::models::{Document, Job, JobKind, JobStatus, PageOcrStatus};
+use foia::privacy::PrivacyConfig;
+use foia::repository::diesel_document::BrowseParams;
+use foia::repository::{DieselDocumentRepository, DieselJobRepository};
+use foia::services::ocr_reprocess::OcrRunFilter;
+use foia::storage::{save_document_async, DocumentInput};
+use foia::utils::sniff_mime_type;
+use foia_analysis::searchable_pdf::SearchablePdfTool;
+use foia_analysis::services::analysis::{
+    extract_document_text_per_page, ocr_document_page_with_config,
+};
+use foia_analysis::services::{SearchablePdfService, TableExtractionService};
+
+use crate::handlers::{export_records, render_export, ExportFormat};
+
+/// Jobs allowed to run at the same time.
+const MAX_RUNNING_JOBS: usize = 2;
+
+/// Documents fetched per query by document jobs.
+const BATCH_SIZE: usize = 100;
+
+/// OCR results requeued per progress report.
+const REQUEUE_CHUNK: usize = 500;
+
+/// Most documents an export job writes.
+const MAX_EXPORT_DOCUMENTS: u32 = 100_000;
+
+/// Timeout for re-downloading a document.
+const REDOWNLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Jobs running in this process, across workspaces.
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+/// Wait up to `timeout` for running jobs to stop after a shutdown request.
+/// Returns how many are still running.
+pub async fn wait_for_running(timeout: Duration) -> usize {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let running = RUNNING.load(Ordering::SeqCst);
+        if running == 0 || tokio::time::Instant::now() >= deadline {
+            return running;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+/// Counts a job as running while held.
+struct RunningGuard;
+
+impl RunningGuard {
+    fn new() -> Self {
+        RUNNING.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Why a job stopped early.
+enum JobError {
+    Cancelled,
+    Interrupted,
+    Failed(String),
+}
+
+impl<E: std::fmt::Display> From<E> for JobError {
+    fn from(e: E) -> Self {
+        Self::Failed(e.to_string())
+    }
+}
+
+/// Progress reporting for a running job.
+struct Progress<'a> {
+    jobs: &'a DieselJobRepository,
+    id: &'a str,
+    done: u64,
+    total: Option<u64>,
+}
+
+impl Progress<'_> {
+    /// Record progress, failing with [`JobError::Cancelled`] if the job
+    /// was asked to stop, or [`JobError::Interrupted`] if the server is
+    /// shutting down.
+    async fn report(&mut self, message: Option<&str>) -> Result<(), JobError> {
+        let cancel = self
+            .jobs
+            .update_progress(self.id, self.done, self.total, message)
+            .await?;
+        if cancel {
+            Err(JobError::Cancelled)
+        } else if foia::shutdown::is_requested() {
+            Err(JobError::Interrupted)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Step a reprocess job re-runs on its document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReprocessAction {
+    /// Discard the latest version's OCR and run it again.
+    Ocr,
+    /// Discard the latest version's pages, extract its text and OCR it.
+    Extract,
+    /// Queue the document for summarization again.
+    Summarize,
+    /// Fetch the document's URL again.
+    Redownload,
+}
+
+impl ReprocessAction {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ocr" => Some(Self::Ocr),
+            "extract" => Some(Self::Extract),
+            "summarize" => Some(Self::Summarize),
+            "redownload" => Some(Self::Redownload),
+            _ => None,
+        }
+    }
+}
+
+/// A service processing documents one at a time.
+enum DocumentService {
+    Tables(TableExtractionService),
+    SearchablePdf(SearchablePdfService),
+}
+
+impl DocumentService {
+    async fn count_pending(&self, source_id: Option<&str>) -> anyhow::Result<u64> {
+        match self {
+            Self::Tables(s) => s.count_pending(source_id).await,
+            Self::SearchablePdf(s) => s.count_pending(source_id).await,
+        }
+    }
+
+    async fn pending(
+        &self,
+        source_id: Option<&str>,
+        after_id: Option<&str>,
+    ) -> anyhow::Result<Vec<Document>> {
+        match self {
+            Self::Tables(s) => s.pending(source_id, BATCH_SIZE, after_id).await,
+            Self::SearchablePdf(s) => s.pending(source_id, BATCH_SIZE, after_id).await,
+        }
+    }
+
+    async fn process(&self, doc: &Document) -> anyhow::Result<()> {
+        match self {
+            Self::Tables(s) => s.process_document(doc).await.map(|_| ()),
+            Self::SearchablePdf(s) => s.process_document(doc).await,
+        }
+    }
+}
+
+/// Runs background jobs for one workspace.
+pub struct JobRunner {
+    jobs: DieselJobRepository,
+    doc_repo: DieselDocumentRepository,
+    documents_dir: PathBuf,
+    exports_dir: PathBuf,
+    ocr: OcrConfig,
+    limits: ExtractionLimits,
+    privacy: PrivacyConfig,
+    slots: Semaphore,
+}
+
+impl JobRunner {
+    pub fn new(
+        jobs: DieselJobRepository,
+        doc_repo: DieselDocumentRepository,
+        documents_dir: PathBuf,
+        exports_dir: PathBuf,
+        ocr: OcrConfig,
+        limits: ExtractionLimits,
+        privacy: PrivacyConfig,
+    ) -> Self {
+        Self {
+            jobs,
+            doc_repo,
+            documents_dir,
+            exports_dir,
+            ocr,
+            limits,
+            privacy,
+            slots: Semaphore::new(MAX_RUNNING_JOBS),
+        }
+    }
+
+    pub fn repository(&self) -> &DieselJobRepository {
+        &self.jobs
+    }
+
+    /// Where an export job's output file is written.
+    pub fn export_path(&self, job: &Job) -> Option<PathBuf> {
+        let file = job.result.as_ref()?.get("file")?.as_str()?;
+        Some(self.exports_dir.join(file))
+    }
+
+    /// Validate, record and start a job.
+    pub async fn submit(self: &Arc<Self>, kind: JobKind, params: Value) -> Result<Job, String> {
+        validate(kind, &params)?;
+        let job = Job::new(kind, params);
+        self.jobs
+            .create(&job)
+            .await
+            .map_err(|e| format!("Failed to record job: {}", e))?;
+
+        let runner = Arc::clone(self);
+        let queued = job.clone();
+        tokio::spawn(async move { runner.run(queued).await });
+        Ok(job)
+    }
+
+    async fn run(&self, job: Job) {
+        let Ok(_slot) = self.slots.acquire().await else {
+            return;
+        };
+        // Left queued; failed as interrupted at the next start
+        if foia::shutdown::is_requested() {
+            return;
+        }
+        let _running = RunningGuard::new();
+        match self.jobs.start(&job.id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                tracing::error!("Failed to start job {}: {}", job.id, e);
+                return;
+            }
+        }
+        tracing::info!("Job {} ({}) started", job.id, job.kind.as_str());
+
+        let mut progress = Progress {
+            jobs: &self.jobs,
+            id: &job.id,
+            done: 0,
+            total: None,
+        };
+        let outcome = match job.kind {
+            JobKind::Reocr => self.run_reocr(&job, &mut progress).await,
+            JobKind::ExtractTables => {
+                let service = TableExtractionService::new(
+                    self.doc_repo.clone(),
+                    self.documents_dir.clone(),
+                    &self.ocr.language,
+                );
+                self.run_documents(&job, DocumentService::Tables(service), &mut progress)
+                    .await
+            }
+            JobKind::SearchablePdf => match SearchablePdfTool::detect() {
+                Some(tool) => {
+                    let service = SearchablePdfService::new(
+                        self.doc_repo.clone(),
+                        self.documents_dir.clone(),
+                        &self.ocr.language,
+                        tool,
+                    );
+                    self.run_documents(&job, DocumentService::SearchablePdf(service), &mut progress)
+                        .await
+                }
+                None => Err(JobError::Failed(
+                    "No searchable PDF tool installed (ocrmypdf, or tesseract and poppler-utils)"
+                        .to_string(),
+                )),
+            },
+            JobKind::Export => self.run_export(&job, &mut progress).await,
+            JobKind::Reprocess => self.run_reprocess(&job, &mut progress).await,
+        };
+
+        let finished = match &outcome {
+            Ok(result) => {
+                self.jobs
+                    .finish(&job.id, JobStatus::Completed, None, None, Some(result))
+                    .await
+            }
+            Err(JobError::Cancelled) => {
+                self.jobs
+                    .finish(&job.id, JobStatus::Cancelled, Some("Cancelled"), None, None)
+                    .await
+            }
+            Err(JobError::Interrupted) => {
+                tracing::info!("Job {} interrupted by shutdown", job.id);
+                self.jobs
+                    .finish(
+                        &job.id,
+                        JobStatus::Failed,
+                        None,
+                        Some("Interrupted by shutdown"),
+                        None,
+                    )
+                    .await
+            }
+            Err(JobError::Failed(e)) => {
+                tracing::warn!("Job {} failed: {}", job.id, e);
+                self.jobs
+                    .finish(&job.id, JobStatus::Failed, None, Some(e), None)
+                    .await
+            }
+        };
+        if let Err(e) = finished {
+            tracing::error!("Failed to record end of job {}: {}", job.id, e);
+        }
+    }
+
+    /// Queue OCR results matching the job's source and filter for OCR again.
+    async fn run_reocr(&self, job: &Job, progress: &mut Progress<'_>) -> Result<Value, JobError> {
+        let filter = job
+            .param_str("filter")
+            .map(OcrRunFilter::parse)
+            .transpose()
+            .map_err(JobError::Failed)?;
+        let runs: Vec<_> = self
+            .doc_repo
+            .get_ocr_runs(job.param_str("source_id"))
+            .await?
+            .into_iter()
+            .filter(|run| filter.as_ref().is_none_or(|f| f.matches(run)))
+            .collect();
+
+        progress.total = Some(runs.len() as u64);
+        progress.report(Some("Queueing pages")).await?;
+        let mut pages = 0;
+        for chunk in runs.chunks(REQUEUE_CHUNK) {
+            pages += self.doc_repo.requeue_ocr_results(chunk).await?;
+            progress.done += chunk.len() as u64;
+            progress.report(Some("Queueing pages")).await?;
+        }
+        Ok(json!({ "results": runs.len(), "pages": pages }))
+    }
+
+    /// Process every pending document of the job's source with `service`.
+    async fn run_documents(
+        &self,
+        job: &Job,
+        service: DocumentService,
+        progress: &mut Progress<'_>,
+    ) -> Result<Value, JobError> {
+        let source_id = job.param_str("source_id");
+        let limit = job.params.get("limit").and_then(Value::as_u64).unwrap_or(0);
+        let pending = service.count_pending(source_id).await?;
+        let total = if limit > 0 {
+            pending.min(limit)
+        } else {
+            pending
+        };
+        progress.total = Some(total);
+        progress.report(None).await?;
+
+        let mut failed = 0u64;
+        let mut cursor: Option<String> = None;
+        while progress.done < total {
+            let batch = service.pending(source_id, cursor.as_deref()).await?;
+            if batch.is_empty() {
+                break;
+            }
+            cursor = batch.last().map(|doc| doc.id.clone());
+
+            for doc in &batch {
+                if progress.done >= total {
+                    break;
+                }
+                if let Err(e) = service.process(doc).await {
+                    tracing::warn!("Job {}: {}: {}", job.id, doc.id, e);
+                    failed += 1;
+                }
+                progress.done += 1;
+                progress.report(Some(doc.title.as_str())).await?;
+            }
+        }
+        Ok(json!({ "processed": progress.done, "failed": failed }))
+    }
+
+    /// Write the documents matching the job's filters to an export file.
+    async fn run_export(&self, job: &Job, progress: &mut Progress<'_>) -> Result<Value, JobError> {
+        let format = export_format(&job.params).map_err(JobError::Failed)?;
+        let split = |name: &str| -> Vec<String> {
+            job.param_str(name)
+                .map(|v| {
+                    v.split(',')
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let (tags, types) = (split("tags"), split("types"));
+
+        progress.report(Some("Loading documents")).await?;
+        let documents = self
+            .doc_repo
+            .browse(BrowseParams {
+                source_id: job.param_str("source_id"),
+                categories: &types,
+                tags: &tags,
+                limit: MAX_EXPORT_DOCUMENTS,
+                ..Default::default()
+            })
+            .await?
+            .items;
+        progress.total = Some(documents.len() as u64);
+        progress.report(Some("Writing export")).await?;
+
+        let include_text = job
+            .params
+            .get("include_text")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let records = export_records(documents, include_text);
+        let file = format!("{}.{}", job.id, format.extension());
+        tokio::fs::create_dir_all(&self.exports_dir).await?;
+        tokio::fs::write(
+            self.exports_dir.join(&file),
+            render_export(&records, format),
+        )
+        .await?;
+
+        progress.done = records.len() as u64;
+        progress.report(None).await?;
+        Ok(json!({
+            "file": file,
+            "documents": records.len(),
+            "content_type": format.content_type(),
+        }))
+    }
+
+    /// Re-run one step on the job's document.
+    async fn run_reprocess(
+        &self,
+        job: &Job,
+        progress: &mut Progress<'_>,
+    ) -> Result<Value, JobError> {
+        let action = job
+            .param_str("action")
+            .and_then(ReprocessAction::from_str)
+            .ok_or_else(|| JobError::Failed("Unknown reprocess action".to_string()))?;
+        let doc_id = job.param_str("document_id").unwrap_or_default();
+        let doc = self
+            .doc_repo
+            .get(doc_id)
+            .await?
+            .ok_or_else(|| JobError::Failed(format!("Document not found: {}", doc_id)))?;
+        let version = doc
+            .current_version()
+            .ok_or_else(|| JobError::Failed("Document has no versions".to_string()))?;
+        let version_id = version.id as i32;
+
+        match action {
+            ReprocessAction::Ocr => {
+                if version.mime_type != "application/pdf" {
+                    return Err(JobError::Failed(
+                        "Only PDFs are OCR'd; re-extract the text instead".to_string(),
+                    ));
+                }
+                progress.report(Some("Queueing pages")).await?;
+                // Never extracted, so there was nothing to requeue
+                if self
+                    .doc_repo
+                    .requeue_version_ocr(&doc.id, version_id)
+                    .await?
+                    == 0
+                {
+                    self.extract_text(&doc).await?;
+                }
+                self.ocr_pages(&doc.id, version_id, progress).await
+            }
+            ReprocessAction::Extract => {
+                progress.report(Some("Extracting text")).await?;
+                self.doc_repo.delete_pages(&doc.id, version_id).await?;
+                self.extract_text(&doc).await?;
+                self.ocr_pages(&doc.id, version_id, progress).await
+            }
+            ReprocessAction::Summarize => {
+                self.doc_repo.reset_annotation(&doc.id).await?;
+                progress.report(Some("Queued for summarization")).await?;
+                Ok(json!({ "queued": true }))
+            }
+            ReprocessAction::Redownload => self.redownload(&doc, progress).await,
+        }
+    }
+
+    /// Extract the text of a document's latest version into pages.
+    async fn extract_text(&self, doc: &Document) -> Result<usize, JobError> {
+        let doc = doc.clone();
+        let doc_repo = self.doc_repo.clone();
+        let documents_dir = self.documents_dir.clone();
+        let limits = self.limits.clone();
+        let pages = tokio::task::spawn_blocking(move || {
+            let handle = tokio::runtime::Handle::current();
+            extract_document_text_per_page(&doc, &doc_repo, &handle, &documents_dir, &limits)
+        })
+        .await??;
+        Ok(pages)
+    }
+
+    /// OCR a version's pages waiting for it, one progress report per page.
+    /// The document is finalized once its last page is done.
+    async fn ocr_pages(
+        &self,
+        doc_id: &str,
+        version_id: i32,
+        progress: &mut Progress<'_>,
+    ) -> Result<Value, JobError> {
+        let pages: Vec<_> = self
+            .doc_repo
+            .get_pages(doc_id, version_id)
+            .await?
+            .into_iter()
+            .filter(|page| {
+                matches!(
+                    page.ocr_status,
+                    PageOcrStatus::Pending | PageOcrStatus::TextExtracted
+                )
+            })
+            .collect();
+        progress.total = Some(pages.len() as u64);
+        progress.report(Some("Running OCR")).await?;
+
+        let mut failed = 0u64;
+        for page in pages {
+            let message = format!("Page {}", page.page_number);
+            let doc_repo = self.doc_repo.clone();
+            let ocr = self.ocr.clone();
+            let documents_dir = self.documents_dir.clone();
+            let limits = self.limits.clone();
+            let outcome = tokio::task::spawn_blocking(move || {
+                let handle = tokio::runtime::Handle::current();
+                ocr_document_page_with_config(
+                    &page,
+                    &doc_repo,
+                    &handle,
+                    &ocr,
+                    None,
+                    &documents_dir,
+                    &limits,
+                )
+            })
+            .await?;
+            if let Err(e) = outcome {
+                tracing::warn!("Reprocess {}: {}: {}", doc_id, message, e);
+                failed += 1;
+            }
+            progress.done += 1;
+            progress.report(Some(&message)).await?;
+        }
+        Ok(json!({ "pages": progress.done, "failed": failed }))
+    }
+
+    /// Fetch a document's URL again, adding a version if the content changed.
+    async fn redownload(
+        &self,
+        doc: &Document,
+        progress: &mut Progress<'_>,
+    ) -> Result<Value, JobError> {
+        progress.report(Some("Downloading")).await?;
+        let client = HttpClient::builder("reprocess", REDOWNLOAD_TIMEOUT, Duration::ZERO)
+            .privacy(&self.privacy)
+            .build()
+            .map_err(JobError::Failed)?;
+        let response = client.get(&doc.source_url, None, None).await?;
+        if !response.is_success() {
+            return Err(JobError::Failed(format!(
+                "{} returned {}",
+                doc.source_url, response.status
+            )));
+        }
+
+        let current = doc.current_version();
+        let declared = response
+            .content_type()
+            .map(|ct| ct.split(';').next().unwrap_or(ct).trim().to_string())
+            .or_else(|| current.map(|v| v.mime_type.clone()))
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let original_filename = response
+            .content_disposition_filename()
+            .or_else(|| current.and_then(|v| v.original_filename.clone()));
+        let server_date = response
+            .last_modified()
+            .and_then(|lm| chrono::DateTime::parse_from_rfc2822(lm).ok())
+            .map(|dt| dt.with_timezone(&chrono::Utc));
+        let content = response.bytes().await?;
+        let sniffed = sniff_mime_type(&content, &declared);
+
+        let input = DocumentInput {
+            url: doc.source_url.clone(),
+            title: doc.title.clone(),
+            mime_type: sniffed
+                .map(str::to_string)
+                .unwrap_or_else(|| declared.clone()),
+            metadata: doc.metadata.clone(),
+            original_filename,
+            server_date,
+            declared_mime_type: sniffed.map(|_| declared),
+            tags: Vec::new(),
+        };
+        save_document_async(
+            &self.doc_repo,
+            &content,
+            &input,
+            &doc.source_id,
+            &self.documents_dir,
+        )
+        .await?;
+
+        let versions = match self.doc_repo.get(&doc.id).await? {
+            Some(updated) => updated.versions.len(),
+            None => doc.versions.len(),
+        };
+        progress.done = 1;
+        progress.total = Some(1);
+        progress.report(None).await?;
+        Ok(json!({
+            "bytes": content.len(),
+            "new_version": versions > doc.versions.len(),
+        }))
+    }
+}
+
+/// The export format parameter, defaulting to JSON.
+fn export_format(params: &Value) -> Result<ExportFormat, String> {
+    match params.get("format") {
+        None => Ok(ExportFormat::default()),
+        Some(format) => serde_json::from_value(format.clone())
+            .map_err(|_| format!("Unknown export format {} (json, jsonl, csv)", format)),
+    }
+}
+
+/// Reject parameters a job of `kind` couldn't run with.
+fn validate(kind: JobKind, params: &Value) -> Result<(), String> {
+    if !params.is_object() {
+        return Err("Job parameters must be an object".to_string());
+    }
+    match kind {
+        JobKind::Reocr => {
+            let source = params.get("source_id").and_then(Value::as_str);
+            let filter = params.get("filter").and_then(Value::as_str);
+            if source.is_none_or(str::is_empty) && filter.is_none_or(str::is_empty) {
+                return Err("Re-OCR needs a source_id or a filter".to_string());
+            }
+            if let Some(filter) = filter.filter(|f| !f.is_empty()) {
+                OcrRunFilter::parse(filter)?;
+            }
+            Ok(())
+        }
+        JobKind::Export => export_format(params).map(|_| ()),
+        JobKind::Reprocess => {
+            let document = params.get("document_id").and_then(Value::as_str);
+            if document.is_none_or(str::is_empty) {
+                return Err("Reprocessing needs a document_id".to_string());
+            }
+            let action = params.get("action").and_then(Value::as_str);
+            if action.and_then(ReprocessAction::from_str).is_none() {
+                return Err(
+                    "Reprocess action must be ocr, extract, summarize or redownload".to_string(),
+                );
+            }
+            Ok(())
+        }
+        JobKind::ExtractTables | JobKind::SearchablePdf => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(JobKind::Reocr, &json!({})).is_err());
+        assert!(validate(JobKind::Reocr, &json!({ "source_id": "fbi" })).is_ok());
+        assert!(validate(JobKind::Reocr, &json!({ "filter": "dpi<" })).is_err());
+        assert!(validate(JobKind::Reocr, &json!({ "filter": "backend=tesseract" })).is_ok());
+
+        assert!(validate(JobKind::Export, &json!({ "format": "csv" })).is_ok());
+        assert!(validate(JobKind::Export, &json!({ "format": "xml" })).is_err());
+        assert!(validate(JobKind::ExtractTables, &json!([])).is_err());
+
+        assert!(validate(JobKind::Reprocess, &json!({ "action": "ocr" })).is_err());
+        assert!(validate(
+            JobKind::Reprocess,
+            &json!({ "document_id": "abc", "action": "translate" })
+        )
+        .is_err());
+        assert!(validate(
+            JobKind::Reprocess,
+            &json!({ "document_id": "abc", "action": "redownload" })
+        )
+        .is_ok());
+    }
+}
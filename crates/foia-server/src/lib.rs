@@ -6,23 +6,51 @@
 //! - Cross-source deduplication display
 //! - Document version history
 
+mod access_log;
+mod api_keys;
 mod assets;
 mod cache;
+mod caching;
+mod flags;
 mod handlers;
+mod jobs;
+mod public;
+mod publish;
+mod redacted;
+mod redirects;
 mod routes;
 mod template_structs;
+mod thumbnails;
+mod workspaces;
 
+pub use publish::{publish_static, PublishOptions, PublishSummary};
 pub use routes::create_router;
+pub use workspaces::{create_app, Workspace, WorkspaceInfo};
 
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use foia::config::Settings;
-use foia::repository::{DieselCrawlRepository, DieselDocumentRepository, DieselSourceRepository};
+use foia::config::{
+    AccessLogMode, Config, ConfigReloader, LiveConfig, PageImageConfig, RequestsConfig,
+    ServerConfig, Settings,
+};
+use foia::page_images::PageImageStore;
+use foia::repository::{
+    DieselAccessLogRepository, DieselAgencyRepository, DieselApiKeyRepository,
+    DieselCrawlRepository, DieselDocumentRepository, DieselFlagRepository,
+    DieselFoiaRequestRepository, DieselLlmUsageRepository, DieselSavedViewRepository,
+    DieselSourceRepository,
+};
 
+use api_keys::ApiKeyGate;
 use cache::StatsCache;
+use jobs::JobRunner;
+use thumbnails::ThumbnailCache;
+
+/// How long a stopping server waits for background jobs to wind down.
+const JOB_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
 /// Status of a DeepSeek OCR job.
 #[derive(Clone, Debug, Default)]
@@ -45,37 +73,162 @@ pub struct AppState {
     pub doc_repo: Arc<DieselDocumentRepository>,
     pub source_repo: Arc<DieselSourceRepository>,
     pub crawl_repo: Arc<DieselCrawlRepository>,
+    /// Named browse filter bookmarks.
+    pub saved_views: Arc<DieselSavedViewRepository>,
+    /// Tracked FOIA requests and their linked documents.
+    pub foia_requests: Arc<DieselFoiaRequestRepository>,
+    /// Agency registry and the sources linked to each agency.
+    pub agencies: Arc<DieselAgencyRepository>,
+    /// LLM usage, so questions asked through the API count toward budgets.
+    pub llm_usage: Arc<DieselLlmUsageRepository>,
+    /// JSON API keys and their usage.
+    pub api_keys: Arc<DieselApiKeyRepository>,
+    /// Per-key rate limits, and whether writes need a key.
+    pub api_gate: Arc<ApiKeyGate>,
+    /// Daily view and download counts per document.
+    pub access_log: Arc<DieselAccessLogRepository>,
+    /// What is recorded about document views and downloads.
+    pub access_mode: AccessLogMode,
+    /// Reader reports of sensitive or misfiled documents.
+    pub flags: Arc<DieselFlagRepository>,
+    /// Public mode with `server.withhold_flagged`: documents with an open
+    /// flag are not served.
+    pub withhold_flagged: bool,
+    /// The config file, reloaded when it changes.
+    pub config: LiveConfig,
     pub documents_dir: PathBuf,
     pub stats_cache: Arc<StatsCache>,
+    /// Rendered PDF pages for the document viewer, under `documents/pages`.
+    pub page_images: Arc<PageImageStore>,
+    /// Rendered listing thumbnails, under `<data_dir>/thumbnails`.
+    pub thumbnails: Arc<ThumbnailCache>,
     /// DeepSeek OCR job status (only one can run at a time).
     pub deepseek_job: Arc<RwLock<DeepSeekJobStatus>>,
+    /// Background jobs started from the UI or API.
+    pub jobs: Arc<JobRunner>,
+    /// Read-only public mode: admin routes are not mounted.
+    pub public: bool,
 }
 
+/// Config settings the server applies without a restart.
+const LIVE_SETTINGS: &[&str] = &["requests"];
+
 impl AppState {
     pub async fn new(settings: &Settings) -> anyhow::Result<Self> {
         let ctx = settings.create_db_context()?;
+        let config = Config::load().await;
+        foia_analysis::ocr::configure_pdf_engine(&config.analysis.pdf);
+
+        let job_repo = ctx.jobs();
+        let interrupted = job_repo.fail_interrupted().await?;
+        if interrupted > 0 {
+            tracing::warn!("Marked {} interrupted jobs as failed", interrupted);
+        }
+        let jobs = JobRunner::new(
+            job_repo,
+            ctx.documents(),
+            settings.documents_dir.clone(),
+            settings.data_dir.join("exports"),
+            config.analysis.ocr.clone(),
+            config.analysis.limits.clone(),
+            config.privacy.clone(),
+        );
+        let config = ConfigReloader::spawn(config, ctx.config_history(), LIVE_SETTINGS).await;
 
         Ok(Self {
             doc_repo: Arc::new(ctx.documents()),
             source_repo: Arc::new(ctx.sources()),
             crawl_repo: Arc::new(ctx.crawl()),
+            saved_views: Arc::new(ctx.saved_views()),
+            foia_requests: Arc::new(ctx.foia_requests()),
+            agencies: Arc::new(ctx.agencies()),
+            llm_usage: Arc::new(ctx.llm_usage()),
+            api_keys: Arc::new(ctx.api_keys()),
+            api_gate: Arc::new(ApiKeyGate::new(&ServerConfig::default())),
+            access_log: Arc::new(ctx.access_log()),
+            access_mode: AccessLogMode::default(),
+            flags: Arc::new(ctx.flags()),
+            withhold_flagged: false,
+            config,
             documents_dir: settings.documents_dir.clone(),
             stats_cache: Arc::new(StatsCache::new()),
+            page_images: Arc::new(PageImageStore::new(
+                &settings.documents_dir,
+                &PageImageConfig::default(),
+            )),
+            thumbnails: Arc::new(ThumbnailCache::new(settings.data_dir.join("thumbnails"))),
             deepseek_job: Arc::new(RwLock::new(DeepSeekJobStatus::default())),
+            jobs: Arc::new(jobs),
+            public: false,
         })
     }
+
+    /// Deadline rules for FOIA requests, by jurisdiction.
+    pub fn requests_config(&self) -> RequestsConfig {
+        self.config.borrow().requests.clone()
+    }
 }
 
-/// Start the web server.
-pub async fn serve(settings: &Settings, host: &str, port: u16) -> anyhow::Result<()> {
-    let state = AppState::new(settings).await?;
-    let app = create_router(state);
+/// Start the web server with a single workspace.
+pub async fn serve(
+    settings: &Settings,
+    host: &str,
+    port: u16,
+    server: &ServerConfig,
+) -> anyhow::Result<()> {
+    let workspace = Workspace {
+        id: foia::config::DEFAULT_WORKSPACE.to_string(),
+        name: foia::config::DEFAULT_WORKSPACE.to_string(),
+        settings: settings.clone(),
+    };
+    serve_workspaces(
+        &[workspace],
+        foia::config::DEFAULT_WORKSPACE,
+        host,
+        port,
+        server,
+    )
+    .await
+}
 
+/// Start the web server with every workspace mounted; `active` is served
+/// to clients that have not picked one. Listening beyond loopback, API
+/// writes always need a key.
+pub async fn serve_workspaces(
+    workspaces: &[Workspace],
+    active: &str,
+    host: &str,
+    port: u16,
+    server: &ServerConfig,
+) -> anyhow::Result<()> {
     let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    let server = if addr.ip().is_loopback() {
+        server.clone()
+    } else {
+        server.clone().exposed()
+    };
+    let app = create_app(workspaces, active, &server).await?;
+
     tracing::info!("Starting server at http://{}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Client addresses feed the public-mode rate limiter
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(foia::shutdown::requested())
+    .await?;
+
+    // Running jobs stop at their next progress report
+    let left = jobs::wait_for_running(JOB_SHUTDOWN_TIMEOUT).await;
+    if left > 0 {
+        tracing::warn!(
+            "{} jobs still running at shutdown; they will be failed at next start",
+            left
+        );
+    }
+    tracing::info!("Server stopped");
 
     Ok(())
 }
@@ -0,0 +1,529 @@
+//! Static site publishing.
+//!
+//! Renders a read-only snapshot of the archive as plain HTML: a browse
+//! listing, one listing per source and per tag, and a page per document
+//! with its synopsis, extracted text and files. Every link is relative, so
+//! the output directory can be hosted as-is from S3, GitHub Pages or any
+//! web server without running `foia serve`. As in public mode, documents
+//! with a redacted copy are published as that copy alone.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use askama::Template;
+use chrono::Utc;
+
+use foia::artifacts::ArtifactStore;
+use foia::config::Settings;
+use foia::models::{ArtifactKind, DerivedArtifact, Document};
+use foia::repository::DieselDocumentRepository;
+use foia::utils::format_size;
+use foia_analysis::services::redaction::REDACTED_PDF_NAME;
+
+use super::assets;
+use super::template_structs::{
+    DocumentRow, SiteDocumentRow, SiteDocumentTemplate, SiteLink, SiteListingTemplate,
+    SiteSourceRow, SiteTagRow, SiteTagsTemplate, VersionItem,
+};
+
+/// Documents per listing page.
+const PAGE_SIZE: usize = 100;
+
+/// Documents loaded per database round trip.
+const BATCH_SIZE: u32 = 200;
+
+/// Tags shown under each document in listings.
+const LISTING_TAGS: usize = 5;
+
+/// What to publish.
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    /// Only publish documents from this source.
+    pub source_id: Option<String>,
+    /// Site name shown in the header and page titles.
+    pub title: String,
+    /// Copy document files into the site; without them pages show
+    /// metadata and text only.
+    pub include_files: bool,
+    /// Publish documents with an open flag, which are left out by default
+    /// until a curator resolves or dismisses it.
+    pub include_flagged: bool,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        Self {
+            source_id: None,
+            title: "foia".to_string(),
+            include_files: true,
+            include_flagged: false,
+        }
+    }
+}
+
+/// What a publish run wrote.
+#[derive(Debug, Default, Clone)]
+pub struct PublishSummary {
+    pub documents: usize,
+    /// HTML pages written, including listings.
+    pub pages: usize,
+    pub files: usize,
+    pub file_bytes: u64,
+    /// Files recorded in the database but missing from disk.
+    pub missing_files: usize,
+    /// Documents left out because they have an open flag.
+    pub withheld: usize,
+    /// Documents whose files were published as their redacted copy.
+    pub redacted: usize,
+}
+
+/// Maps names (document IDs, sources, tags) to unique file-safe slugs.
+#[derive(Default)]
+struct Slugs {
+    assigned: HashMap<String, String>,
+    used: HashSet<String>,
+}
+
+impl Slugs {
+    fn get(&mut self, name: &str) -> String {
+        if let Some(slug) = self.assigned.get(name) {
+            return slug.clone();
+        }
+        let base = slugify(name);
+        let mut slug = base.clone();
+        let mut n = 2;
+        while !self.used.insert(slug.clone()) {
+            slug = format!("{}-{}", base, n);
+            n += 1;
+        }
+        self.assigned.insert(name.to_string(), slug.clone());
+        slug
+    }
+}
+
+/// Lowercase ASCII alphanumerics, with runs of anything else as one `-`.
+fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug: String = slug.trim_matches('-').chars().take(80).collect();
+    if slug.is_empty() {
+        "item".to_string()
+    } else {
+        slug
+    }
+}
+
+/// A document as it appears in listings.
+struct Entry {
+    slug: String,
+    row: DocumentRow,
+}
+
+struct SiteWriter<'a> {
+    out: &'a Path,
+    title: &'a str,
+    generated: String,
+    documents: Slugs,
+    sources: Slugs,
+    tags: Slugs,
+    summary: PublishSummary,
+}
+
+impl SiteWriter<'_> {
+    fn write_page(&mut self, relative: &str, html: String) -> anyhow::Result<()> {
+        let path = self.out.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, html)?;
+        self.summary.pages += 1;
+        Ok(())
+    }
+
+    fn source_href(&mut self, root: &str, source_id: &str) -> String {
+        format!("{}sources/{}/index.html", root, self.sources.get(source_id))
+    }
+
+    fn tag_link(&mut self, root: &str, tag: &str) -> SiteLink {
+        SiteLink {
+            name: tag.to_string(),
+            href: format!("{}tags/{}/index.html", root, self.tags.get(tag)),
+        }
+    }
+
+    fn listing_row(&mut self, root: &str, entry: &Entry) -> SiteDocumentRow {
+        let row = &entry.row;
+        let tags = row
+            .tags
+            .iter()
+            .take(LISTING_TAGS)
+            .map(|t| self.tag_link(root, &t.name))
+            .collect();
+        SiteDocumentRow {
+            href: format!("{}documents/{}.html", root, entry.slug),
+            title: row.title.clone(),
+            icon: row.icon.clone(),
+            source_id: row.source_id.clone(),
+            source_href: self.source_href(root, &row.source_id),
+            mime_type: row.mime_type.clone(),
+            size_str: row.size_str.clone(),
+            date_str: row.date_str.clone(),
+            has_synopsis: row.has_synopsis,
+            synopsis_preview: row.synopsis_preview.clone(),
+            tags,
+        }
+    }
+
+    /// Write `entries` as `index.html`, `page-2.html`, ... under `dir`.
+    fn write_listing(
+        &mut self,
+        dir: &str,
+        root: &str,
+        title: &str,
+        entries: &[&Entry],
+        sources: Vec<SiteSourceRow>,
+        magnet: &str,
+    ) -> anyhow::Result<()> {
+        let page_count = entries.len().div_ceil(PAGE_SIZE).max(1);
+        let page_file = |page: usize| {
+            if page == 1 {
+                "index.html".to_string()
+            } else {
+                format!("page-{}.html", page)
+            }
+        };
+        let mut sources = Some(sources);
+
+        for page in 1..=page_count {
+            let start = (page - 1) * PAGE_SIZE;
+            let chunk = &entries[start..(start + PAGE_SIZE).min(entries.len())];
+            let documents = chunk.iter().map(|e| self.listing_row(root, e)).collect();
+            let sources = sources.take().unwrap_or_default();
+            let summary = if page_count > 1 {
+                format!(
+                    "Showing {}-{} of {} documents",
+                    start + 1,
+                    start + chunk.len(),
+                    entries.len()
+                )
+            } else {
+                format!("{} documents", entries.len())
+            };
+            let html = SiteListingTemplate {
+                title,
+                site_title: self.title,
+                generated: &self.generated,
+                root,
+                summary,
+                has_sources: !sources.is_empty(),
+                sources,
+                documents,
+                has_prev: page > 1,
+                prev_href: page_file(page.saturating_sub(1)),
+                has_next: page < page_count,
+                next_href: page_file(page + 1),
+                page_label: format!("Page {} of {}", page, page_count),
+                magnet,
+            }
+            .render()?;
+            let relative = if dir.is_empty() {
+                page_file(page)
+            } else {
+                format!("{}/{}", dir, page_file(page))
+            };
+            self.write_page(&relative, html)?;
+        }
+        Ok(())
+    }
+
+    /// Copy each version's file into `files/`, returning links to them.
+    ///
+    /// Versions with a copy in `redacted` are published as that copy, and
+    /// if there are any, the document's other versions are left out since
+    /// they likely hold what was blacked out.
+    fn publish_files(
+        &mut self,
+        doc: &Document,
+        documents_dir: &Path,
+        redacted: &HashMap<i64, DerivedArtifact>,
+    ) -> Vec<VersionItem> {
+        let mut items = Vec::new();
+        for version in &doc.versions {
+            let copy = redacted.get(&version.id);
+            if copy.is_none() && !redacted.is_empty() {
+                continue;
+            }
+            let (relative, file_size) = match copy {
+                Some(artifact) => (PathBuf::from(&artifact.file_path), artifact.file_size),
+                None => (
+                    version.compute_storage_path(&doc.source_url, &doc.title),
+                    version.file_size,
+                ),
+            };
+            let dest = self.out.join("files").join(&relative);
+            if !dest.exists() {
+                let src = match copy {
+                    Some(artifact) => {
+                        ArtifactStore::new(documents_dir).resolve(&artifact.file_path)
+                    }
+                    None => version.resolve_path(documents_dir, &doc.source_url, &doc.title),
+                };
+                let copied = dest
+                    .parent()
+                    .map_or(Ok(()), std::fs::create_dir_all)
+                    .and_then(|_| std::fs::copy(&src, &dest));
+                match copied {
+                    Ok(bytes) => {
+                        self.summary.files += 1;
+                        self.summary.file_bytes += bytes;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Skipping file {} for {}: {}", src.display(), doc.id, e);
+                        self.summary.missing_files += 1;
+                        continue;
+                    }
+                }
+            }
+            let href: Vec<String> = relative
+                .iter()
+                .map(|part| urlencoding::encode(&part.to_string_lossy()).into_owned())
+                .collect();
+            items.push(VersionItem {
+                path: format!("../files/{}", href.join("/")),
+                filename: version.original_filename.clone().unwrap_or_else(|| {
+                    relative
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                }),
+                size_str: format_size(file_size),
+                date_str: version.acquired_at.format("%Y-%m-%d").to_string(),
+                has_checksum: false,
+                checksum_class: String::new(),
+                checksum_label: String::new(),
+                checksum_title: String::new(),
+            });
+        }
+        items
+    }
+}
+
+/// Redacted copies of `doc`'s versions, by version ID.
+async fn redacted_copies(
+    doc_repo: &DieselDocumentRepository,
+    doc: &Document,
+) -> anyhow::Result<HashMap<i64, DerivedArtifact>> {
+    let mut copies = HashMap::new();
+    for version in &doc.versions {
+        if let Some(artifact) = doc_repo
+            .find_artifact(version.id, ArtifactKind::RedactedPdf, REDACTED_PDF_NAME)
+            .await?
+        {
+            copies.insert(version.id, artifact);
+        }
+    }
+    Ok(copies)
+}
+
+/// Render the archive (or one source) as a static site in `out`.
+///
+/// Existing files in `out` are overwritten but not removed, so publish
+/// into an empty directory to avoid leaving pages for deleted documents.
+pub async fn publish_static(
+    settings: &Settings,
+    out: &Path,
+    options: &PublishOptions,
+) -> anyhow::Result<PublishSummary> {
+    let repos = settings.repositories()?;
+    let all_sources = repos.sources.get_all().await?;
+    let magnets: HashMap<String, String> = all_sources
+        .iter()
+        .filter_map(|s| Some((s.id.clone(), s.distribution()?.magnet)))
+        .collect();
+    let source_names: HashMap<String, String> =
+        all_sources.into_iter().map(|s| (s.id, s.name)).collect();
+    if let Some(source_id) = &options.source_id {
+        if !source_names.contains_key(source_id) {
+            anyhow::bail!("Source '{}' not found", source_id);
+        }
+    }
+
+    let flagged = if options.include_flagged {
+        HashSet::new()
+    } else {
+        repos.flags.open_document_ids().await?
+    };
+
+    std::fs::create_dir_all(out)?;
+    std::fs::write(out.join("style.css"), assets::CSS)?;
+
+    let mut site = SiteWriter {
+        out,
+        title: &options.title,
+        generated: Utc::now().format("%Y-%m-%d %H:%M UTC").to_string(),
+        documents: Slugs::default(),
+        sources: Slugs::default(),
+        tags: Slugs::default(),
+        summary: PublishSummary::default(),
+    };
+    let mut entries: Vec<Entry> = Vec::new();
+
+    let mut after: Option<String> = None;
+    loop {
+        let records = repos
+            .documents
+            .get_records_after(options.source_id.as_deref(), after.as_deref(), BATCH_SIZE)
+            .await?;
+        let Some(last) = records.last() else {
+            break;
+        };
+        after = Some(last.id.clone());
+        let ids: Vec<String> = records.into_iter().map(|r| r.id).collect();
+
+        for doc in repos.documents.get_batch(&ids).await? {
+            if flagged.contains(&doc.id) {
+                site.summary.withheld += 1;
+                continue;
+            }
+            let Some(row) = DocumentRow::from_document(&doc) else {
+                continue;
+            };
+            let slug = site.documents.get(&doc.id);
+
+            let text = match (&doc.extracted_text, doc.current_version()) {
+                (Some(text), _) => Some(text.clone()),
+                (None, Some(version)) => {
+                    repos
+                        .documents
+                        .get_combined_page_text(&doc.id, version.id as i32)
+                        .await?
+                }
+                (None, None) => None,
+            };
+            let files = if options.include_files {
+                let redacted = redacted_copies(&repos.documents, &doc).await?;
+                if !redacted.is_empty() {
+                    site.summary.redacted += 1;
+                }
+                site.publish_files(&doc, &settings.documents_dir, &redacted)
+            } else {
+                Vec::new()
+            };
+            let tags = doc.tags.iter().map(|t| site.tag_link("../", t)).collect();
+            let source = SiteLink {
+                name: source_names
+                    .get(&doc.source_id)
+                    .cloned()
+                    .unwrap_or_else(|| doc.source_id.clone()),
+                href: site.source_href("../", &doc.source_id),
+            };
+            let html = SiteDocumentTemplate {
+                title: &row.title,
+                site_title: site.title,
+                generated: &site.generated,
+                root: "../",
+                source,
+                source_url: &doc.source_url,
+                acquired: row.date_str.clone(),
+                has_synopsis: doc.synopsis.is_some(),
+                synopsis: doc.synopsis.as_deref().unwrap_or_default(),
+                tags,
+                files,
+                has_text: text.is_some(),
+                text: text.unwrap_or_default(),
+            }
+            .render()?;
+            site.write_page(&format!("documents/{}.html", slug), html)?;
+            site.summary.documents += 1;
+
+            entries.push(Entry { slug, row });
+        }
+    }
+
+    // Listings are newest first, like the live browse page
+    entries.sort_by(|a, b| b.row.timestamp.cmp(&a.row.timestamp));
+
+    let mut by_source: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    let mut by_tag: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    for entry in &entries {
+        by_source
+            .entry(entry.row.source_id.as_str())
+            .or_default()
+            .push(entry);
+        for tag in &entry.row.tags {
+            by_tag.entry(tag.name.as_str()).or_default().push(entry);
+        }
+    }
+
+    let source_rows = by_source
+        .iter()
+        .map(|(source_id, docs)| SiteSourceRow {
+            name: source_names
+                .get(*source_id)
+                .cloned()
+                .unwrap_or_else(|| source_id.to_string()),
+            href: site.source_href("", source_id),
+            count: docs.len(),
+        })
+        .collect();
+    let all: Vec<&Entry> = entries.iter().collect();
+    site.write_listing("", "", &options.title, &all, source_rows, "")?;
+
+    for (source_id, docs) in &by_source {
+        let name = source_names
+            .get(*source_id)
+            .map_or(*source_id, String::as_str);
+        let dir = format!("sources/{}", site.sources.get(source_id));
+        let magnet = magnets.get(*source_id).map_or("", String::as_str);
+        site.write_listing(&dir, "../../", name, docs, Vec::new(), magnet)?;
+    }
+
+    let mut tag_rows = Vec::with_capacity(by_tag.len());
+    for (tag, docs) in &by_tag {
+        let link = site.tag_link("../", tag);
+        tag_rows.push(SiteTagRow {
+            name: link.name,
+            href: link.href,
+            count: docs.len(),
+        });
+        let dir = format!("tags/{}", site.tags.get(tag));
+        site.write_listing(&dir, "../../", tag, docs, Vec::new(), "")?;
+    }
+    let html = SiteTagsTemplate {
+        title: "Tags",
+        site_title: site.title,
+        generated: &site.generated,
+        root: "../",
+        tags: tag_rows,
+    }
+    .render()?;
+    site.write_page("tags/index.html", html)?;
+
+    Ok(site.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("FBI Vault"), "fbi-vault");
+        assert_eq!(slugify("topic:cia / mkultra"), "topic-cia-mkultra");
+        assert_eq!(slugify("???"), "item");
+    }
+
+    #[test]
+    fn test_slugs_are_unique_and_stable() {
+        let mut slugs = Slugs::default();
+        assert_eq!(slugs.get("Topic: CIA"), "topic-cia");
+        assert_eq!(slugs.get("topic-cia"), "topic-cia-2");
+        assert_eq!(slugs.get("Topic: CIA"), "topic-cia");
+    }
+}
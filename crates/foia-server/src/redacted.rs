@@ -0,0 +1,120 @@
+//! Serving redacted copies in place of originals.
+//!
+//! In public mode, a document version with a redacted copy (made with
+//! `foia curate redact`) is served as that copy: its file, page images,
+//! page-range PDFs and thumbnail come from the copy, and the pages API
+//! leaves out the original's OCR and PDF text. Other versions of a
+//! redacted document aren't served at all, since they likely hold what
+//! was blacked out. Lookups that fail are treated as withheld.
+
+use std::path::PathBuf;
+
+use foia::artifacts::ArtifactStore;
+use foia::models::{ArtifactKind, DerivedArtifact, Document, DocumentVersion};
+use foia_analysis::services::redaction::REDACTED_PDF_NAME;
+
+use super::AppState;
+
+/// What public mode serves for a document version.
+#[derive(Debug, Clone)]
+pub enum PublicCopy {
+    /// The version as stored.
+    Original,
+    /// The version's redacted copy.
+    Redacted(DerivedArtifact),
+    /// Nothing; another version of the document is redacted.
+    Withheld,
+}
+
+/// What to serve for `version` of `doc`. Always the original outside
+/// public mode.
+pub async fn public_copy(
+    state: &AppState,
+    doc: &Document,
+    version: &DocumentVersion,
+) -> PublicCopy {
+    if !state.public {
+        return PublicCopy::Original;
+    }
+    let mut redacted_elsewhere = false;
+    for v in &doc.versions {
+        match state
+            .doc_repo
+            .find_artifact(v.id, ArtifactKind::RedactedPdf, REDACTED_PDF_NAME)
+            .await
+        {
+            Ok(Some(artifact)) if v.id == version.id => return PublicCopy::Redacted(artifact),
+            Ok(Some(_)) => redacted_elsewhere = true,
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!("Failed to look up redacted copies of {}: {}", doc.id, e);
+                return PublicCopy::Withheld;
+            }
+        }
+    }
+    if redacted_elsewhere {
+        PublicCopy::Withheld
+    } else {
+        PublicCopy::Original
+    }
+}
+
+/// What to serve for the stored file with content `hash`, which may belong
+/// to several documents. Withheld if any of them withholds it; otherwise
+/// the first redacted copy of it, if there is one.
+pub async fn file_copy(state: &AppState, hash: &str) -> PublicCopy {
+    if !state.public {
+        return PublicCopy::Original;
+    }
+    let found = match state.doc_repo.find_sources_by_hash(hash, None).await {
+        Ok(found) => found,
+        Err(e) => {
+            tracing::warn!("Failed to look up documents for file {}: {}", hash, e);
+            return PublicCopy::Withheld;
+        }
+    };
+    let mut copy = PublicCopy::Original;
+    for (_, document_id, _) in &found {
+        let doc = match state.doc_repo.get(document_id).await {
+            Ok(Some(doc)) => doc,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Failed to load {}: {}", document_id, e);
+                return PublicCopy::Withheld;
+            }
+        };
+        for version in doc.versions.iter().filter(|v| v.content_hash == hash) {
+            match public_copy(state, &doc, version).await {
+                PublicCopy::Withheld => return PublicCopy::Withheld,
+                PublicCopy::Redacted(artifact) => {
+                    if matches!(copy, PublicCopy::Original) {
+                        copy = PublicCopy::Redacted(artifact);
+                    }
+                }
+                PublicCopy::Original => {}
+            }
+        }
+    }
+    copy
+}
+
+/// The file to serve for `version` and its content hash: the redacted copy
+/// if public mode serves one, else the stored file. `None` if the version
+/// is withheld.
+pub async fn served_file(
+    state: &AppState,
+    doc: &Document,
+    version: &DocumentVersion,
+) -> Option<(PathBuf, String)> {
+    match public_copy(state, doc, version).await {
+        PublicCopy::Original => Some((
+            version.resolve_path(&state.documents_dir, &doc.source_url, &doc.title),
+            version.content_hash.clone(),
+        )),
+        PublicCopy::Redacted(artifact) => Some((
+            ArtifactStore::new(&state.documents_dir).resolve(&artifact.file_path),
+            artifact.content_hash,
+        )),
+        PublicCopy::Withheld => None,
+    }
+}
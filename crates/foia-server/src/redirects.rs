@@ -0,0 +1,79 @@
+//! Redirects from replaced document IDs.
+//!
+//! A document's ID changes when it moves to its stable ID or is merged
+//! into another. Requests for the old ID that would 404 are sent to the
+//! same page under the new one, so bookmarks and API clients keep working.
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+
+use super::AppState;
+
+/// Paths with a document ID as their next segment.
+const DOCUMENT_PATHS: &[&str] = &["/documents/", "/api/documents/", "/thumbnails/"];
+
+/// Split a document path into its prefix, document ID and the rest.
+pub(crate) fn split_document_path(path: &str) -> Option<(&str, &str, &str)> {
+    DOCUMENT_PATHS.iter().find_map(|prefix| {
+        let rest = path.strip_prefix(prefix)?;
+        let end = rest.find('/').unwrap_or(rest.len());
+        let (id, tail) = rest.split_at(end);
+        (!id.is_empty()).then_some((*prefix, id, tail))
+    })
+}
+
+/// Redirect a 404 for a replaced document ID to the same path under the
+/// new ID.
+pub async fn follow_replaced_ids(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let uri = request.uri().clone();
+    let response = next.run(request).await;
+    if response.status() != StatusCode::NOT_FOUND {
+        return response;
+    }
+    let Some((prefix, doc_id, tail)) = split_document_path(uri.path()) else {
+        return response;
+    };
+
+    match state.doc_repo.resolve_redirect(doc_id).await {
+        Ok(Some(new_id)) => {
+            let mut location = format!("{}{}{}", prefix, urlencoding::encode(&new_id), tail);
+            if let Some(query) = uri.query() {
+                location.push('?');
+                location.push_str(query);
+            }
+            Redirect::permanent(&location).into_response()
+        }
+        Ok(None) => response,
+        Err(e) => {
+            tracing::warn!("Failed to look up redirect for {}: {}", doc_id, e);
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_document_path() {
+        assert_eq!(
+            split_document_path("/documents/abc/pages/2"),
+            Some(("/documents/", "abc", "/pages/2"))
+        );
+        assert_eq!(
+            split_document_path("/api/documents/abc"),
+            Some(("/api/documents/", "abc", ""))
+        );
+        assert_eq!(split_document_path("/documents/"), None);
+        assert_eq!(split_document_path("/browse"), None);
+    }
+}
@@ -1,16 +1,45 @@
 //! Router configuration for the web server.
 
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::cors::CorsLayer;
 
+use super::api_keys::check_api_key;
+use super::flags::withhold_flagged;
 use super::handlers;
+use super::redirects::follow_replaced_ids;
 use super::AppState;
 
 /// Create the main router with all routes.
+///
+/// In public mode only the read-only routes are mounted. Requests for a
+/// replaced document ID are redirected to its new ID, flagged documents can
+/// be withheld, and API requests go through the API key check.
 pub fn create_router(state: AppState) -> Router {
+    let mut router = read_only_routes();
+    if !state.public {
+        router = router.merge(admin_routes());
+    }
+    router
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            follow_replaced_ids,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            withhold_flagged,
+        ))
+        .layer(middleware::from_fn_with_state(state.clone(), check_api_key))
+        .layer(CorsLayer::permissive())
+        .with_state(state)
+}
+
+/// Routes for reading the archive: browsing, documents, search and the
+/// read side of the API.
+fn read_only_routes() -> Router<AppState> {
     Router::new()
         // Health check for container orchestration
         .route("/health", get(handlers::health))
@@ -23,10 +52,41 @@ pub fn create_router(state: AppState) -> Router {
             "/documents/:doc_id/versions",
             get(handlers::document_versions),
         )
+        .route(
+            "/documents/:doc_id/pages/:page",
+            get(handlers::page_range_pdf),
+        )
+        .route(
+            "/documents/:doc_id/pages/:page/image",
+            get(handlers::page_image),
+        )
+        .route(
+            "/documents/:doc_id/tables/:table_id",
+            get(handlers::document_table),
+        )
+        .route(
+            "/documents/:doc_id/searchable.pdf",
+            get(handlers::document_searchable_pdf),
+        )
+        .route(
+            "/documents/:doc_id/provenance",
+            get(handlers::document_provenance),
+        )
+        .route("/documents/:doc_id/graph", get(handlers::document_graph))
+        .route("/cite/:doc_id/:hash/:pages", get(handlers::cite_page))
         .route("/files/*path", get(handlers::serve_file))
+        .route("/thumbnails/:doc_id", get(handlers::serve_thumbnail))
         // Tags (HTML views)
         .route("/tags", get(handlers::list_tags))
         .route("/tags/:tag", get(handlers::list_tag_documents))
+        .route("/tags/:namespace/", get(handlers::list_tag_namespace))
+        // FOIA requests (HTML views)
+        .route("/requests", get(handlers::list_requests))
+        .route("/requests/:id", get(handlers::request_detail))
+        // Documents by location (HTML view)
+        .route("/map", get(handlers::map_view))
+        // Exemption analytics (HTML view)
+        .route("/exemptions", get(handlers::exemption_analytics))
         // Type filtering (HTML views)
         .route("/types", get(handlers::list_types))
         .route("/types/:type_name", get(handlers::list_by_type))
@@ -44,16 +104,20 @@ pub fn create_router(state: AppState) -> Router {
             get(handlers::get_document_content),
         )
         .route(
-            "/api/documents/:doc_id/pages",
-            get(handlers::api_document_pages),
+            "/api/documents/:doc_id/provenance",
+            get(handlers::get_provenance),
         )
         .route(
-            "/api/documents/:doc_id/reocr",
-            post(handlers::api_reocr_document),
+            "/api/documents/:doc_id/relations",
+            get(handlers::api_document_relations),
         )
         .route(
-            "/api/documents/reocr/status",
-            get(handlers::api_reocr_status),
+            "/api/documents/:doc_id/graph",
+            get(handlers::api_document_graph),
+        )
+        .route(
+            "/api/documents/:doc_id/pages",
+            get(handlers::api_document_pages),
         )
         // Versions API - document version history
         .route(
@@ -68,19 +132,7 @@ pub fn create_router(state: AppState) -> Router {
         // Annotations API - LLM-generated metadata
         .route("/api/annotations", get(handlers::list_annotations))
         .route("/api/annotations/stats", get(handlers::annotation_stats))
-        .route(
-            "/api/annotations/:doc_id",
-            get(handlers::get_annotation).put(handlers::update_annotation),
-        )
-        // Scrape API - scraper control and monitoring
-        .route("/api/scrapers", get(handlers::list_scrapers))
-        .route("/api/scrapers/:source_id", get(handlers::get_scrape_status))
-        .route("/api/scrapers/queue", get(handlers::list_queue))
-        .route("/api/scrapers/retry", post(handlers::retry_failed))
-        // Export API - bulk data export
-        .route("/api/export/documents", get(handlers::export_documents))
-        .route("/api/export/annotations", get(handlers::export_annotations))
-        .route("/api/export/stats", get(handlers::export_stats))
+        .route("/api/annotations/:doc_id", get(handlers::get_annotation))
         // Search API - full-text page content search
         .route("/api/search", get(handlers::search_content))
         // Entities API - NER-extracted entity search
@@ -88,6 +140,8 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/entities/types", get(handlers::entity_types))
         .route("/api/entities/top", get(handlers::top_entities))
         .route("/api/entities/locations", get(handlers::entity_locations))
+        .route("/api/map", get(handlers::api_map))
+        .route("/api/exemptions", get(handlers::api_exemptions))
         .route(
             "/api/documents/:doc_id/entities",
             get(handlers::document_entities),
@@ -98,14 +152,141 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/duplicates", get(handlers::list_duplicates))
         .route("/api/tags", get(handlers::api_tags))
         .route("/api/tags/search", get(handlers::api_search_tags))
-        .route("/api/status", get(handlers::api_status))
-        .route("/api/status/:source_id", get(handlers::api_source_status))
         .route("/api/recent", get(handlers::api_recent_docs))
         .route("/api/types", get(handlers::api_type_stats))
         .route("/api/sources", get(handlers::api_sources))
+        .route("/api/views", get(handlers::api_list_views))
+        .route("/api/requests", get(handlers::api_list_requests))
+        .route("/api/requests/:id", get(handlers::api_get_request))
+        .route("/api/agencies", get(handlers::api_list_agencies))
+        .route("/api/agencies/:id", get(handlers::api_get_agency))
+        // Readers' reports of sensitive or misfiled documents
+        .route(
+            "/api/documents/:doc_id/flags",
+            post(handlers::api_create_flag),
+        )
         // OpenAPI spec
-        .route("/api", get(handlers::openapi_spec).options(handlers::openapi_spec))
+        .route(
+            "/api",
+            get(handlers::openapi_spec).options(handlers::openapi_spec),
+        )
         .route("/api/openapi.json", get(handlers::openapi_spec))
-        .layer(CorsLayer::permissive())
-        .with_state(state)
+        .merge(handlers::swagger_ui())
+}
+
+/// Routes that change state or expose crawl internals, left out of
+/// public mode.
+fn admin_routes() -> Router<AppState> {
+    Router::new()
+        // Prometheus scrape target
+        .route("/metrics", get(handlers::metrics))
+        // Crawl request log and rate-limit state (HTML views)
+        .route("/crawl", get(handlers::crawl_log))
+        .route("/crawl/domains", get(handlers::crawl_domains))
+        // Failure triage by error class (HTML view)
+        .route("/failures", get(handlers::list_failures))
+        // Background jobs (HTML view)
+        .route("/jobs", get(handlers::list_jobs))
+        // Per-source crawl queue management (HTML view)
+        .route("/sources/:source_id/urls", get(handlers::source_urls))
+        // Per-source crawl health (HTML view)
+        .route("/sources/:source_id/health", get(handlers::source_health))
+        // JSON API keys and their usage (HTML view)
+        .route("/api-keys", get(handlers::api_keys_page))
+        // Document views, downloads and referrers (HTML view)
+        .route("/analytics", get(handlers::analytics_page))
+        // Moderation queue of flagged documents (HTML view)
+        .route("/flags", get(handlers::flags_page))
+        // DeepSeek re-OCR of a document
+        .route(
+            "/api/documents/:doc_id/reocr",
+            post(handlers::api_reocr_document),
+        )
+        .route(
+            "/api/documents/reocr/status",
+            get(handlers::api_reocr_status),
+        )
+        // Re-run a processing step on one document as a job
+        .route(
+            "/api/documents/:doc_id/reprocess",
+            post(handlers::api_reprocess_document),
+        )
+        // Hand corrections of page text
+        .route(
+            "/api/documents/:doc_id/pages/:page/text",
+            put(handlers::api_correct_page_text),
+        )
+        // Scrape API - scraper control and monitoring
+        .route("/api/scrapers", get(handlers::list_scrapers))
+        .route("/api/scrapers/:source_id", get(handlers::get_scrape_status))
+        .route("/api/scrapers/queue", get(handlers::list_queue))
+        .route("/api/scrapers/retry", post(handlers::retry_failed))
+        .route(
+            "/api/scrapers/:source_id/urls",
+            get(handlers::list_source_urls).post(handlers::add_source_urls),
+        )
+        .route(
+            "/api/scrapers/:source_id/urls/retry",
+            post(handlers::retry_source_urls),
+        )
+        .route(
+            "/api/scrapers/:source_id/urls/cancel",
+            post(handlers::cancel_source_urls),
+        )
+        .route("/api/failures/retry", post(handlers::retry_failure_class))
+        // Question answering (calls the LLM, so it counts against budgets)
+        .route("/api/ask", post(handlers::api_ask))
+        // Background jobs API
+        .route(
+            "/api/jobs",
+            get(handlers::api_list_jobs).post(handlers::api_create_job),
+        )
+        .route("/api/jobs/:id", get(handlers::api_get_job))
+        .route("/api/jobs/:id/cancel", post(handlers::api_cancel_job))
+        .route("/api/jobs/:id/download", get(handlers::api_download_job))
+        // Export API - bulk data export
+        .route("/api/export/documents", get(handlers::export_documents))
+        .route("/api/export/annotations", get(handlers::export_annotations))
+        .route("/api/export/stats", get(handlers::export_stats))
+        // Per-source crawl status
+        .route("/api/status", get(handlers::api_status))
+        .route("/api/status/:source_id", get(handlers::api_source_status))
+        // Annotation edits
+        .route("/api/annotations/:doc_id", put(handlers::update_annotation))
+        // FOIA request tracking
+        .route("/api/requests", post(handlers::api_create_request))
+        .route(
+            "/api/requests/:id",
+            put(handlers::api_update_request).delete(handlers::api_delete_request),
+        )
+        .route(
+            "/api/requests/:id/documents",
+            post(handlers::api_link_documents),
+        )
+        .route(
+            "/api/requests/:id/documents/:doc_id",
+            delete(handlers::api_unlink_document),
+        )
+        // Saved browse views
+        .route("/api/views", post(handlers::api_save_view))
+        .route("/api/views/:name", delete(handlers::api_delete_view))
+        .route(
+            "/api/views/:name/subscriptions",
+            get(handlers::api_list_subscriptions).post(handlers::api_subscribe_view),
+        )
+        .route(
+            "/api/views/:name/subscriptions/:email",
+            delete(handlers::api_unsubscribe_view),
+        )
+        // API key issuance and revocation
+        .route(
+            "/api/keys",
+            get(handlers::api_list_keys).post(handlers::api_create_key),
+        )
+        .route("/api/keys/:name", delete(handlers::api_revoke_key))
+        // Document access counts
+        .route("/api/analytics", get(handlers::api_analytics))
+        // Flag review
+        .route("/api/flags", get(handlers::api_list_flags))
+        .route("/api/flags/:id", put(handlers::api_update_flag))
 }
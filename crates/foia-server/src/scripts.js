@@ -1,7 +1,11 @@
 // JavaScript for timeline ruler interaction (Wayback Machine style).
+//
+// The ruler is drawn from /api/timeline buckets. Moving the range sliders
+// reloads the page with `start`/`end` query params so filtering and
+// pagination happen server-side over the whole result set.
 (function() {
-    const data = window.TIMELINE_DATA;
-    if (!data || !data.buckets || data.buckets.length === 0) return;
+    const container = document.getElementById('timeline-container');
+    if (!container) return;
 
     const rulerLabels = document.getElementById('ruler-labels');
     const rulerSelection = document.getElementById('ruler-selection');
@@ -10,173 +14,328 @@
     const dateRangeDisplay = document.getElementById('date-range');
     const docCountDisplay = document.getElementById('doc-count');
     const resetBtn = document.getElementById('reset-timeline');
-    const table = document.getElementById('document-table');
-
-    // Find min/max dates and max count
-    const dates = data.buckets.map(b => new Date(b.date));
-    const minDate = new Date(Math.min(...dates));
-    const maxDate = new Date(Math.max(...dates));
-    const maxCount = Math.max(...data.buckets.map(b => b.count));
-    const totalDocs = data.total;
+    const cfg = container.dataset;
+
+    fetch(cfg.url)
+        .then(r => r.json())
+        .then(data => {
+            if (!data || !data.buckets || data.buckets.length === 0) {
+                container.hidden = true;
+                return;
+            }
+            init(data);
+        })
+        .catch(() => { container.hidden = true; });
 
-    // Build a map of date -> count for quick lookup
-    const dateCountMap = {};
-    data.buckets.forEach(b => { dateCountMap[b.date] = b.count; });
+    // Parse YYYY-MM-DD as UTC midnight (buckets are UTC days)
+    function parseDay(s) {
+        return s ? new Date(s + 'T00:00:00Z') : null;
+    }
 
-    // Generate ruler ticks - show years as major, months with activity as minor
-    function buildRuler() {
-        rulerLabels.innerHTML = '';
+    function formatDay(d) {
+        return d.toISOString().slice(0, 10);
+    }
 
-        const startYear = minDate.getFullYear();
-        const endYear = maxDate.getFullYear();
+    function init(data) {
+        // Find min/max dates and max count
+        const dates = data.buckets.map(b => parseDay(b.date));
+        const minDate = new Date(Math.min(...dates));
+        const maxDate = new Date(Math.max(...dates));
+        const maxCount = Math.max(...data.buckets.map(b => b.count));
+        const totalDocs = data.total;
         const totalMs = maxDate - minDate;
 
-        // If span is less than 2 years, show months; otherwise show years
-        const showMonths = (endYear - startYear) <= 2;
+        function pctToDate(pct) {
+            return new Date(minDate.getTime() + (totalMs * pct / 100));
+        }
 
-        if (showMonths) {
-            // Show each month
-            let current = new Date(minDate.getFullYear(), minDate.getMonth(), 1);
-            const end = new Date(maxDate.getFullYear(), maxDate.getMonth() + 1, 1);
+        function dateToPct(d) {
+            if (!d || totalMs <= 0) return null;
+            return Math.max(0, Math.min(100, ((d - minDate) / totalMs) * 100));
+        }
 
-            while (current <= end) {
-                const pos = totalMs > 0 ? ((current - minDate) / totalMs) * 100 : 0;
-                const isJan = current.getMonth() === 0;
-                const label = isJan
-                    ? current.getFullYear().toString()
-                    : current.toLocaleString('default', { month: 'short' });
+        // Generate ruler ticks - show years as major, months with activity as minor
+        function buildRuler() {
+            rulerLabels.innerHTML = '';
 
-                // Count docs in this month
-                const monthKey = current.toISOString().slice(0, 7);
-                const monthCount = data.buckets
-                    .filter(b => b.date.startsWith(monthKey))
-                    .reduce((sum, b) => sum + b.count, 0);
+            const startYear = minDate.getUTCFullYear();
+            const endYear = maxDate.getUTCFullYear();
 
-                createTick(pos, label, isJan ? 'major' : 'minor', monthCount, current.getTime());
+            // If span is less than 2 years, show months; otherwise show years
+            const showMonths = (endYear - startYear) <= 2;
 
-                current.setMonth(current.getMonth() + 1);
-            }
-        } else {
-            // Show years
-            for (let year = startYear; year <= endYear; year++) {
-                const yearStart = new Date(year, 0, 1);
-                const pos = totalMs > 0 ? ((yearStart - minDate) / totalMs) * 100 : 0;
+            if (showMonths) {
+                // Show each month
+                let current = new Date(Date.UTC(startYear, minDate.getUTCMonth(), 1));
+                const end = new Date(Date.UTC(endYear, maxDate.getUTCMonth() + 1, 1));
 
-                // Count docs in this year
-                const yearCount = data.buckets
-                    .filter(b => b.date.startsWith(year.toString()))
-                    .reduce((sum, b) => sum + b.count, 0);
+                while (current <= end) {
+                    const pos = totalMs > 0 ? ((current - minDate) / totalMs) * 100 : 0;
+                    const isJan = current.getUTCMonth() === 0;
+                    const label = isJan
+                        ? current.getUTCFullYear().toString()
+                        : current.toLocaleString('default', { month: 'short', timeZone: 'UTC' });
 
-                createTick(Math.max(0, Math.min(100, pos)), year.toString(), 'major', yearCount, yearStart.getTime());
-            }
-        }
+                    // Count docs in this month
+                    const monthKey = current.toISOString().slice(0, 7);
+                    const monthCount = data.buckets
+                        .filter(b => b.date.startsWith(monthKey))
+                        .reduce((sum, b) => sum + b.count, 0);
 
-        // Add end cap
-        createTick(100, '', 'minor', 0, maxDate.getTime());
-    }
+                    createTick(Math.max(0, Math.min(100, pos)), label, isJan ? 'major' : 'minor', monthCount, current.getTime());
+
+                    current.setUTCMonth(current.getUTCMonth() + 1);
+                }
+            } else {
+                // Show years
+                for (let year = startYear; year <= endYear; year++) {
+                    const yearStart = new Date(Date.UTC(year, 0, 1));
+                    const pos = totalMs > 0 ? ((yearStart - minDate) / totalMs) * 100 : 0;
 
-    function createTick(position, label, type, count, timestamp) {
-        const tick = document.createElement('div');
-        tick.className = `ruler-tick ${type}`;
-        tick.style.left = `${position}%`;
-        tick.dataset.timestamp = timestamp;
-
-        // Density indicator based on document count
-        if (count > 0) {
-            const density = document.createElement('div');
-            density.className = 'density';
-            if (count >= maxCount * 0.7) {
-                density.classList.add('high');
-            } else if (count >= maxCount * 0.3) {
-                density.classList.add('medium');
+                    // Count docs in this year
+                    const yearCount = data.buckets
+                        .filter(b => b.date.startsWith(year.toString()))
+                        .reduce((sum, b) => sum + b.count, 0);
+
+                    createTick(Math.max(0, Math.min(100, pos)), year.toString(), 'major', yearCount, yearStart.getTime());
+                }
             }
-            density.title = `${count} documents`;
-            tick.appendChild(density);
+
+            // Add end cap
+            createTick(100, '', 'minor', 0, maxDate.getTime());
         }
 
-        const mark = document.createElement('div');
-        mark.className = 'tick-mark';
-        tick.appendChild(mark);
+        function createTick(position, label, type, count, timestamp) {
+            const tick = document.createElement('div');
+            tick.className = `ruler-tick ${type}`;
+            tick.style.left = `${position}%`;
+            tick.dataset.timestamp = timestamp;
+
+            // Density indicator based on document count
+            if (count > 0) {
+                const density = document.createElement('div');
+                density.className = 'density';
+                if (count >= maxCount * 0.7) {
+                    density.classList.add('high');
+                } else if (count >= maxCount * 0.3) {
+                    density.classList.add('medium');
+                }
+                density.title = `${count} documents`;
+                tick.appendChild(density);
+            }
 
-        if (label) {
-            const labelEl = document.createElement('div');
-            labelEl.className = 'tick-label';
-            labelEl.textContent = label;
-            tick.appendChild(labelEl);
-        }
+            const mark = document.createElement('div');
+            mark.className = 'tick-mark';
+            tick.appendChild(mark);
 
-        rulerLabels.appendChild(tick);
-    }
+            if (label) {
+                const labelEl = document.createElement('div');
+                labelEl.className = 'tick-label';
+                labelEl.textContent = label;
+                tick.appendChild(labelEl);
+            }
 
-    // Update selection highlight on ruler
-    function updateRulerSelection() {
-        const startPct = parseFloat(startRange.value);
-        const endPct = parseFloat(endRange.value);
-        rulerSelection.style.left = `${startPct}%`;
-        rulerSelection.style.width = `${endPct - startPct}%`;
-    }
+            rulerLabels.appendChild(tick);
+        }
 
-    // Filter function
-    function filterByDateRange() {
-        const startPct = parseFloat(startRange.value) / 100;
-        const endPct = parseFloat(endRange.value) / 100;
+        // Update selection highlight on ruler
+        function updateRulerSelection() {
+            const startPct = parseFloat(startRange.value);
+            const endPct = parseFloat(endRange.value);
+            rulerSelection.style.left = `${startPct}%`;
+            rulerSelection.style.width = `${endPct - startPct}%`;
+        }
 
-        const totalMs = maxDate - minDate;
-        const startTs = minDate.getTime() + (totalMs * startPct);
-        const endTs = minDate.getTime() + (totalMs * endPct);
-
-        const startDate = new Date(startTs);
-        const endDate = new Date(endTs);
-
-        // Update display
-        const formatDate = d => d.toLocaleDateString('en-US', { year: 'numeric', month: 'short', day: 'numeric' });
-        dateRangeDisplay.textContent = `${formatDate(startDate)} — ${formatDate(endDate)}`;
-
-        // Count visible docs
-        let visibleCount = 0;
-
-        // Filter table rows
-        if (table) {
-            const rows = table.querySelectorAll('tbody tr');
-            rows.forEach(row => {
-                const rowTs = parseInt(row.dataset.date, 10) * 1000;
-                if (rowTs >= startTs && rowTs <= endTs) {
-                    row.classList.remove('hidden');
-                    visibleCount++;
-                } else {
-                    row.classList.add('hidden');
-                }
+        // Update the range display and bucket-based count while dragging
+        function updateDisplay() {
+            if (parseFloat(startRange.value) > parseFloat(endRange.value)) {
+                [startRange.value, endRange.value] = [endRange.value, startRange.value];
+            }
+            const startDate = pctToDate(parseFloat(startRange.value));
+            const endDate = pctToDate(parseFloat(endRange.value));
+            const startKey = formatDay(startDate);
+            const endKey = formatDay(endDate);
+
+            const fmt = d => d.toLocaleDateString('en-US', { year: 'numeric', month: 'short', day: 'numeric', timeZone: 'UTC' });
+            dateRangeDisplay.textContent = `${fmt(startDate)} — ${fmt(endDate)}`;
+
+            const inRange = data.buckets
+                .filter(b => b.date >= startKey && b.date <= endKey)
+                .reduce((sum, b) => sum + b.count, 0);
+            docCountDisplay.textContent = `(${inRange} of ${totalDocs} docs)`;
+
+            updateRulerSelection();
+
+            // Update tick active states
+            const startTs = startDate.getTime();
+            const endTs = endDate.getTime();
+            rulerLabels.querySelectorAll('.ruler-tick').forEach(tick => {
+                const tickTs = parseInt(tick.dataset.timestamp, 10);
+                tick.classList.toggle('active', tickTs >= startTs && tickTs <= endTs);
             });
         }
 
-        docCountDisplay.textContent = `(${visibleCount} of ${totalDocs} docs)`;
+        // Reload with the selected range; the server filters and paginates
+        function applyRange(start, end) {
+            const params = new URLSearchParams(window.location.search);
+            params.delete('cursor');
+            if (start) params.set('start', start); else params.delete('start');
+            if (end) params.set('end', end); else params.delete('end');
+            const qs = params.toString();
+            window.location.href = window.location.pathname + (qs ? '?' + qs : '');
+        }
+
+        function commitRange() {
+            const startPct = parseFloat(startRange.value);
+            const endPct = parseFloat(endRange.value);
+            applyRange(
+                startPct > 0 ? formatDay(pctToDate(startPct)) : null,
+                endPct < 100 ? formatDay(pctToDate(endPct)) : null
+            );
+        }
 
-        // Update ruler selection highlight
-        updateRulerSelection();
+        startRange.addEventListener('input', updateDisplay);
+        endRange.addEventListener('input', updateDisplay);
+        startRange.addEventListener('change', commitRange);
+        endRange.addEventListener('change', commitRange);
 
-        // Update tick active states
-        const ticks = rulerLabels.querySelectorAll('.ruler-tick');
-        ticks.forEach(tick => {
-            const tickTs = parseInt(tick.dataset.timestamp, 10);
-            if (tickTs >= startTs && tickTs <= endTs) {
-                tick.classList.add('active');
+        resetBtn.addEventListener('click', () => {
+            if (cfg.start || cfg.end) {
+                applyRange(null, null);
             } else {
-                tick.classList.remove('active');
+                startRange.value = 0;
+                endRange.value = 100;
+                updateDisplay();
             }
         });
+
+        // Build the ruler and restore the active range from the URL
+        buildRuler();
+        const startPct = dateToPct(parseDay(cfg.start));
+        const endPct = dateToPct(parseDay(cfg.end));
+        startRange.value = startPct === null ? 0 : startPct;
+        endRange.value = endPct === null ? 100 : endPct;
+        if (cfg.start || cfg.end) {
+            updateDisplay();
+        } else {
+            docCountDisplay.textContent = `(${totalDocs} docs)`;
+            updateRulerSelection();
+        }
     }
+})();
 
-    startRange.addEventListener('input', filterByDateRange);
-    endRange.addEventListener('input', filterByDateRange);
+// Workspace switcher: only shown when the server mounts more than one
+// workspace. Switching sets a cookie server-side and returns to the index.
+(function() {
+    const switcher = document.getElementById('workspace-switcher');
+    if (!switcher) return;
+
+    fetch('/api/workspaces')
+        .then(r => r.json())
+        .then(body => {
+            const workspaces = body.data || [];
+            if (workspaces.length < 2) return;
+            for (const ws of workspaces) {
+                const option = document.createElement('option');
+                option.value = ws.id;
+                option.textContent = ws.name;
+                option.selected = ws.active;
+                switcher.appendChild(option);
+            }
+            switcher.hidden = false;
+        })
+        .catch(() => {});
 
-    resetBtn.addEventListener('click', () => {
-        startRange.value = 0;
-        endRange.value = 100;
-        filterByDateRange();
+    switcher.addEventListener('change', () => {
+        window.location.href = '/workspaces/' + encodeURIComponent(switcher.value);
     });
+})();
 
-    // Build the ruler and initialize
-    buildRuler();
-    docCountDisplay.textContent = `(${totalDocs} docs)`;
-    updateRulerSelection();
+// Saved views: named browse filter bookmarks. Opening one is just following
+// its browse URL. The list is also announced as a `saved-views` event so the
+// browse page can offer to delete the view it is showing.
+(function() {
+    const picker = document.getElementById('saved-views');
+    if (!picker) return;
+
+    const current = new URLSearchParams(window.location.search);
+    current.delete('cursor');
+    const onBrowse = window.location.pathname === '/' || window.location.pathname === '/browse';
+
+    fetch('/api/views')
+        .then(r => r.json())
+        .then(body => {
+            const views = body.data || [];
+            let active = null;
+            for (const view of views) {
+                const option = document.createElement('option');
+                option.value = view.url;
+                option.textContent = view.name;
+                if (onBrowse && new URLSearchParams(view.query).toString() === current.toString()) {
+                    option.selected = true;
+                    active = view;
+                }
+                picker.appendChild(option);
+            }
+            picker.hidden = views.length === 0;
+            document.dispatchEvent(new CustomEvent('saved-views', { detail: { views, active } }));
+        })
+        .catch(() => {});
+
+    picker.addEventListener('change', () => {
+        if (picker.value) window.location.href = picker.value;
+    });
+})();
+
+// API key: when a key is saved for this browser on /api-keys, send it with
+// the page's own API requests, so edits keep working when the server
+// requires keys for writes.
+(function() {
+    const key = localStorage.getItem('foia_api_key');
+    if (!key) return;
+
+    const fetch = window.fetch;
+    window.fetch = (resource, init = {}) => {
+        const url = new URL(resource instanceof Request ? resource.url : resource, window.location.href);
+        if (url.origin !== window.location.origin || !url.pathname.startsWith('/api/')) {
+            return fetch(resource, init);
+        }
+        const headers = new Headers(init.headers || (resource instanceof Request ? resource.headers : undefined));
+        if (!headers.has('X-API-Key')) headers.set('X-API-Key', key);
+        return fetch(resource, { ...init, headers });
+    };
+})();
+
+// Reporting a document: personal information, a missed redaction or the
+// wrong file. Reports go to the moderation queue at /flags.
+(function() {
+    document.querySelectorAll('.flag-form').forEach(form => {
+        const status = form.querySelector('.flag-status');
+        form.addEventListener('submit', async (event) => {
+            event.preventDefault();
+            const page = parseInt(form.elements.page.value, 10);
+            const body = {
+                reason: form.elements.reason.value,
+                note: form.elements.note.value.trim()
+            };
+            if (page > 0) body.page = page;
+            status.textContent = 'Sending...';
+            try {
+                const response = await fetch(`/api/documents/${encodeURIComponent(form.dataset.docId)}/flags`, {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify(body)
+                });
+                const data = await response.json();
+                if (data.error) {
+                    status.textContent = data.data.message;
+                    return;
+                }
+                form.reset();
+                status.textContent = 'Thanks, a curator will review it.';
+            } catch (err) {
+                status.textContent = `Error: ${err.message}`;
+            }
+        });
+    });
 })();
@@ -5,23 +5,38 @@
 
 use askama::Template;
 
-use foia::models::{Document, VirtualFile, VirtualFileStatus};
+use foia::models::{
+    Document, DocumentStatus, RecordType, RelationGraph, VirtualFile, VirtualFileStatus,
+};
 use foia::repository::diesel_document::BrowseRow;
-use foia::repository::parse_datetime;
+use foia::repository::{parse_datetime, parse_datetime_opt};
 use foia::utils::{format_size, mime_icon};
 
+use super::thumbnails;
+
 /// Helper struct for document rows in listings.
 pub struct DocumentRow {
     pub id: String,
     pub title: String,
     pub icon: String,
     pub mime_type: String,
+    /// Whether `/thumbnails/{id}` can render a preview of this type.
+    pub has_thumbnail: bool,
     pub size_str: String,
     pub date_str: String,
+    /// The document's own date (`YYYY-MM-DD`), empty if unknown.
+    pub document_date_str: String,
+    /// Page count of the current version, empty if unknown.
+    pub pages_str: String,
+    /// Processing status, e.g. `ocr complete`.
+    pub status: String,
+    /// Timeline position: the document's own date when known, else acquisition time.
     pub timestamp: i64,
     pub source_id: String,
     pub has_synopsis: bool,
     pub synopsis_preview: String,
+    pub has_record_type: bool,
+    pub record_type_label: String,
     pub tags: Vec<TagRef>,
     pub other_tags: Vec<TagRef>,
 }
@@ -51,6 +66,11 @@ pub struct VersionItem {
     pub filename: String,
     pub size_str: String,
     pub date_str: String,
+    pub has_checksum: bool,
+    /// CSS class for the checksum badge: `verified`, `mismatch` or `bad_signature`.
+    pub checksum_class: String,
+    pub checksum_label: String,
+    pub checksum_title: String,
 }
 
 /// Helper struct for virtual file display.
@@ -64,6 +84,15 @@ pub struct VirtualFileRow {
     pub status_badge: String,
 }
 
+/// Helper struct for a table extracted from a document page.
+#[derive(Clone)]
+pub struct TableRow {
+    pub id: String,
+    pub filename: String,
+    pub page: u32,
+    pub size_str: String,
+}
+
 /// Helper struct for type statistics.
 pub struct TypeStat {
     pub category: String,
@@ -89,6 +118,55 @@ pub struct SourceOption {
     pub selected: bool,
 }
 
+/// Helper struct for record type in dropdown.
+pub struct RecordTypeOption {
+    pub id: String,
+    pub name: String,
+    pub count: u64,
+    pub selected: bool,
+}
+
+/// Helper struct for a document status in dropdown.
+pub struct StatusOption {
+    pub id: String,
+    /// Workflow stage name.
+    pub name: String,
+    pub count: u64,
+    pub selected: bool,
+}
+
+/// Helper struct for a processing state in dropdown.
+pub struct ProcessingOption {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub count: u64,
+    pub selected: bool,
+}
+
+/// Helper struct for an exemption code in dropdown.
+pub struct ExemptionOption {
+    pub code: String,
+    /// Documents citing the exemption.
+    pub count: u64,
+    pub selected: bool,
+}
+
+/// Helper struct for a PDF producer in dropdown.
+pub struct PdfProducerOption {
+    pub name: String,
+    /// PDFs the producer wrote.
+    pub count: u64,
+    pub selected: bool,
+}
+
+/// Helper struct for a PDF creation year in dropdown.
+pub struct PdfYearOption {
+    pub year: i32,
+    /// PDFs created that year.
+    pub count: u64,
+    pub selected: bool,
+}
+
 /// Helper struct for duplicate groups.
 pub struct DuplicateGroup {
     pub hash_prefix: String,
@@ -116,18 +194,41 @@ pub struct DuplicatesTemplate<'a> {
 #[template(path = "tags.html")]
 pub struct TagsTemplate<'a> {
     pub title: &'a str,
-    pub tags: Vec<TagWithCount>,
+    pub groups: Vec<TagGroup>,
     pub has_tags: bool,
 }
 
+/// Tags sharing a `namespace:` prefix, for the grouped tags page.
+pub struct TagGroup {
+    /// Namespace name; empty for unprefixed tags.
+    pub namespace: String,
+    pub document_count: u64,
+    pub tags: Vec<TagWithCount>,
+}
+
+/// Tags within one namespace.
+#[derive(Template)]
+#[template(path = "tag_namespace.html")]
+pub struct TagNamespaceTemplate<'a> {
+    pub title: &'a str,
+    pub namespace: &'a str,
+    pub tags: Vec<TagWithCount>,
+}
+
 /// Documents filtered by tag.
 #[derive(Template)]
 #[template(path = "tag_documents.html")]
 pub struct TagDocumentsTemplate<'a> {
     pub title: &'a str,
     pub tag: &'a str,
+    /// Namespace of `tag`, or empty if unprefixed.
+    pub tag_namespace: &'a str,
     pub document_count: usize,
     pub documents: Vec<DocumentRow>,
+    pub has_prev_cursor: bool,
+    pub prev_cursor_val: String,
+    pub has_next_cursor: bool,
+    pub next_cursor_val: String,
 }
 
 /// Types list page.
@@ -163,11 +264,17 @@ pub struct DocumentDetailTemplate<'a> {
     pub has_versions: bool,
     pub other_sources: Vec<String>,
     pub has_other_sources: bool,
+    /// FOIA requests this document was received under.
+    pub requests: Vec<LinkedDocumentRow>,
+    /// Documents this one references, is referenced by, or shares a case with.
+    pub relations: Vec<RelatedDocumentRow>,
     pub has_extracted_text: bool,
     pub extracted_text_val: String,
     pub virtual_files: Vec<VirtualFileRow>,
     pub has_virtual_files: bool,
     pub virtual_files_count: usize,
+    pub tables: Vec<TableRow>,
+    pub has_tables: bool,
     pub has_prev: bool,
     pub prev_id_val: String,
     pub prev_title_val: String,
@@ -182,6 +289,22 @@ pub struct DocumentDetailTemplate<'a> {
     pub has_pages: bool,
     pub page_count_val: u32,
     pub version_id_val: i64,
+    /// Content hash prefix used in citation permalinks.
+    pub version_hash_val: String,
+    pub has_ocr_quality: bool,
+    pub ocr_quality_class: &'static str,
+    pub ocr_quality_label: String,
+    pub ocr_quality_title: String,
+    /// The current version has a searchable copy, offered as the download.
+    pub has_searchable_pdf: bool,
+    pub has_original: bool,
+    /// Current version path under `/files/`.
+    pub original_path: String,
+    /// The current version has a redacted copy; only set outside public
+    /// mode, which serves the copy in place of the original.
+    pub has_redacted_pdf: bool,
+    /// Redacted copy path under `/files/`.
+    pub redacted_pdf_path: String,
 }
 
 /// Main browse page with filters.
@@ -192,19 +315,520 @@ pub struct BrowseTemplate<'a> {
     pub documents: Vec<DocumentRow>,
     pub categories: Vec<CategoryWithCount>,
     pub sources: Vec<SourceOption>,
+    pub record_types: Vec<RecordTypeOption>,
+    pub poor_ocr: bool,
+    pub exemptions: Vec<ExemptionOption>,
+    pub pdf_producers: Vec<PdfProducerOption>,
+    pub pdf_years: Vec<PdfYearOption>,
+    /// Only PDFs created after their document date.
+    pub pdf_postdated: bool,
+    pub statuses: Vec<StatusOption>,
+    pub processing_states: Vec<ProcessingOption>,
     pub all_tags: Vec<TagWithCount>,
     pub active_tags_display: Vec<ActiveTagDisplay>,
     pub has_prev_cursor: bool,
     pub prev_cursor_val: String,
     pub has_next_cursor: bool,
     pub next_cursor_val: String,
-    pub start_position: u64,
-    pub end_position: u64,
     pub total_count: u64,
     pub per_page: usize,
     pub has_pagination: bool,
     pub nav_query_string: String,
     pub active_tags_json: String,
+    pub timeline_url: String,
+    pub timeline_start: String,
+    pub timeline_end: String,
+    /// Listing column headers, with sort links where sortable.
+    pub columns: Vec<SortColumn>,
+    /// Current sort key and direction, kept across filter changes.
+    pub sort: String,
+    pub order: String,
+    /// Full-text search terms.
+    pub query: String,
+    /// Map area filter, `south,west,north,east`; empty if none.
+    pub bbox: String,
+    /// Magnet link of the selected source's torrent; empty if none.
+    pub magnet: String,
+}
+
+/// A listing column header.
+pub struct SortColumn {
+    pub label: &'static str,
+    /// `DocumentSort` name, empty if the column is not sortable.
+    pub key: &'static str,
+    pub active: bool,
+    /// `▲`/`▼` on the active column.
+    pub arrow: &'static str,
+}
+
+/// Helper struct for rows in the crawl request log.
+pub struct CrawlRequestRow {
+    pub source_id: String,
+    pub url: String,
+    pub method: String,
+    pub status: String,
+    /// CSS class for the status code (`status-ok`, `status-redirect`, ...).
+    pub status_class: &'static str,
+    pub duration: String,
+    pub size: String,
+    pub request_at: String,
+    pub conditional: bool,
+    pub error: String,
+}
+
+/// Crawl request log page.
+#[derive(Template)]
+#[template(path = "crawl_log.html")]
+pub struct CrawlLogTemplate<'a> {
+    pub title: &'a str,
+    pub sources: Vec<SourceOption>,
+    pub status: &'a str,
+    pub since: &'a str,
+    pub until: &'a str,
+    pub requests: Vec<CrawlRequestRow>,
+    pub has_requests: bool,
+    /// Link to the next (older) page of the log.
+    pub older_url: String,
+    pub has_older: bool,
+}
+
+/// Helper struct for per-domain rate-limit state.
+pub struct DomainRateRow {
+    pub domain: String,
+    pub delay: String,
+    pub in_backoff: bool,
+    pub total_requests: u64,
+    pub rate_limit_hits: u64,
+    pub updated_at: String,
+}
+
+/// Per-domain rate-limit state page.
+#[derive(Template)]
+#[template(path = "crawl_domains.html")]
+pub struct CrawlDomainsTemplate<'a> {
+    pub title: &'a str,
+    pub domains: Vec<DomainRateRow>,
+    pub has_domains: bool,
+}
+
+/// Helper struct for a status tab on the source URL queue page.
+pub struct UrlStatusTab {
+    pub name: &'static str,
+    pub count: u64,
+    pub active: bool,
+}
+
+/// Helper struct for a crawl URL row.
+pub struct CrawlUrlRow {
+    pub url: String,
+    pub status: &'static str,
+    /// CSS class for the status badge (`status-ok`, `status-server-error`, ...).
+    pub status_class: &'static str,
+    pub retry_count: u32,
+    pub discovered_at: String,
+    pub error: String,
+    pub can_retry: bool,
+    pub can_cancel: bool,
+}
+
+/// Per-source crawl queue page.
+#[derive(Template)]
+#[template(path = "source_urls.html")]
+pub struct SourceUrlsTemplate<'a> {
+    pub title: &'a str,
+    pub source_id: &'a str,
+    pub source_name: &'a str,
+    pub tabs: Vec<UrlStatusTab>,
+    pub status: &'a str,
+    pub urls: Vec<CrawlUrlRow>,
+    pub has_urls: bool,
+    pub has_prev_cursor: bool,
+    pub prev_cursor_val: String,
+    pub has_next_cursor: bool,
+    pub next_cursor_val: String,
+    pub failed_count: u64,
+}
+
+/// Helper struct for one error class on the failures page.
+pub struct FailureRow {
+    pub class: &'static str,
+    pub label: &'static str,
+    pub count: u64,
+    /// Daily counts as a text sparkline.
+    pub trend: String,
+    pub sample: String,
+}
+
+/// Failure triage page.
+#[derive(Template)]
+#[template(path = "failures.html")]
+pub struct FailuresTemplate<'a> {
+    pub title: &'a str,
+    pub sources: Vec<SourceOption>,
+    pub source: &'a str,
+    pub trend_days: usize,
+    pub crawl: Vec<FailureRow>,
+    pub crawl_total: u64,
+    pub analysis: Vec<FailureRow>,
+    pub analysis_total: u64,
+}
+
+/// Crawl health page for one source.
+#[derive(Template)]
+#[template(path = "source_health.html")]
+pub struct SourceHealthTemplate<'a> {
+    pub title: &'a str,
+    pub source_id: &'a str,
+    pub source_name: &'a str,
+    pub days: usize,
+    pub requests: u64,
+    pub success_rate: String,
+    /// `status-*` class for the overall success rate.
+    pub success_class: &'static str,
+    pub avg_latency: String,
+    pub rate_limited: u64,
+    pub rate_limited_days: usize,
+    pub last_success: String,
+    /// No successful request within the stale threshold.
+    pub stale: bool,
+    /// Daily success rate as a text sparkline, oldest first.
+    pub success_trend: String,
+    pub request_trend: String,
+    /// Days with requests, newest first.
+    pub rows: Vec<SourceHealthRow>,
+}
+
+/// Helper struct for one day on the source health page.
+pub struct SourceHealthRow {
+    pub date: String,
+    pub requests: u64,
+    pub success_rate: String,
+    pub success_class: &'static str,
+    pub avg_latency: String,
+    pub rate_limited: u64,
+}
+
+/// Exemption analytics page.
+#[derive(Template)]
+#[template(path = "exemptions.html")]
+pub struct ExemptionsTemplate<'a> {
+    pub title: &'a str,
+    /// Source the page is limited to, or empty for the whole archive.
+    pub source_id: String,
+    /// Documents citing any exemption.
+    pub documents: u64,
+    /// Most cited first.
+    pub totals: Vec<ExemptionTotalRow>,
+    /// Column order of the pivot tables.
+    pub codes: Vec<String>,
+    pub agencies: Vec<ExemptionPivotRow>,
+    pub years: Vec<ExemptionPivotRow>,
+    pub topics: Vec<ExemptionPivotRow>,
+    pub csv_url: String,
+}
+
+/// Helper struct for one exemption code on the analytics page.
+pub struct ExemptionTotalRow {
+    pub code: String,
+    pub documents: u64,
+    pub citations: u64,
+    /// Browse page filtered to documents citing the code.
+    pub browse_url: String,
+}
+
+/// Helper struct for an agency, year or topic on the analytics page, with
+/// citations per code.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExemptionPivotRow {
+    pub group: String,
+    pub citations: Vec<u64>,
+    pub total: u64,
+}
+
+/// Background jobs page.
+#[derive(Template)]
+#[template(path = "jobs.html")]
+pub struct JobsTemplate<'a> {
+    pub title: &'a str,
+    pub jobs: Vec<JobRow>,
+    /// Some job is still queued or running, so the page refreshes itself.
+    pub any_active: bool,
+    pub kinds: Vec<JobKindOption>,
+    pub sources: Vec<String>,
+}
+
+/// Helper struct for one job on the jobs page.
+pub struct JobRow {
+    pub id: String,
+    pub kind: &'static str,
+    pub status: &'static str,
+    pub active: bool,
+    pub progress: String,
+    pub params: String,
+    pub detail: String,
+    pub created_at: String,
+    pub has_download: bool,
+}
+
+/// Helper struct for a job kind in the new-job form.
+pub struct JobKindOption {
+    pub id: &'static str,
+    pub label: &'static str,
+}
+
+/// JSON API keys page. The keys are loaded from `/api/keys` with the
+/// browser's saved key, which must be an admin key.
+#[derive(Template)]
+#[template(path = "api_keys.html")]
+pub struct ApiKeysTemplate<'a> {
+    pub title: &'a str,
+    /// State-changing API requests need a key.
+    pub required: bool,
+    /// Requests per minute for keys without their own limit.
+    pub default_limit: u32,
+}
+
+/// Document access analytics page.
+#[derive(Template)]
+#[template(path = "analytics.html")]
+pub struct AnalyticsTemplate<'a> {
+    pub title: &'a str,
+    /// Days covered, today included.
+    pub days: u32,
+    /// Period options, in days.
+    pub periods: Vec<u32>,
+    /// `server.access_log` setting in effect.
+    pub access_log: String,
+    pub views: u64,
+    pub downloads: u64,
+    pub documents: Vec<AnalyticsDocumentRow>,
+    pub referrers: Vec<AnalyticsReferrerRow>,
+}
+
+/// Helper struct for one document on the analytics page.
+pub struct AnalyticsDocumentRow {
+    pub document_id: String,
+    pub title: String,
+    pub source_id: String,
+    pub views: u64,
+    pub downloads: u64,
+}
+
+/// Helper struct for one referring site on the analytics page.
+pub struct AnalyticsReferrerRow {
+    pub referrer: String,
+    pub count: u64,
+}
+
+/// Moderation queue of flagged documents.
+#[derive(Template)]
+#[template(path = "flags.html")]
+pub struct FlagsTemplate<'a> {
+    pub title: &'a str,
+    /// Status shown: `open`, `resolved`, `dismissed` or `all`.
+    pub status: &'a str,
+    /// Status options.
+    pub statuses: Vec<&'static str>,
+    pub open_count: u64,
+    pub flags: Vec<FlagRow>,
+}
+
+/// Helper struct for one flag in the moderation queue.
+pub struct FlagRow {
+    pub id: i32,
+    pub document_id: String,
+    pub title: String,
+    pub reason: &'static str,
+    /// Empty if no page was given.
+    pub page: String,
+    pub note: String,
+    pub status: &'static str,
+    pub created_at: String,
+    pub resolution: String,
+}
+
+/// Map of documents by the locations they mention.
+#[derive(Template)]
+#[template(path = "map.html")]
+pub struct MapTemplate<'a> {
+    pub title: &'a str,
+    pub sources: Vec<SourceOption>,
+    /// Browse filters carried over, as a query string without `bbox`.
+    pub filter_query: String,
+    /// Visible area to open at, `south,west,north,east`; empty for the world.
+    pub bbox: String,
+}
+
+/// Graph of the documents related to one document.
+#[derive(Template)]
+#[template(path = "relation_graph.html")]
+pub struct RelationGraphTemplate<'a> {
+    pub title: &'a str,
+    pub doc_id: &'a str,
+    pub source_id: &'a str,
+    /// Relation hops shown.
+    pub depth: u32,
+}
+
+/// FOIA requests page.
+#[derive(Template)]
+#[template(path = "requests.html")]
+pub struct FoiaRequestsTemplate<'a> {
+    pub title: &'a str,
+    /// Status filter links, with counts over all requests.
+    pub tabs: Vec<UrlStatusTab>,
+    /// A status or overdue filter is applied.
+    pub filtered: bool,
+    pub overdue_only: bool,
+    pub overdue_count: usize,
+    pub total: usize,
+    pub rows: Vec<FoiaRequestRow>,
+    pub sources: Vec<String>,
+    pub jurisdictions: Vec<String>,
+    pub default_jurisdiction: &'a str,
+    /// Default filing date for the new-request form.
+    pub today: String,
+}
+
+/// Helper struct for one request on the requests page.
+pub struct FoiaRequestRow {
+    pub id: String,
+    pub agency: String,
+    pub subject: String,
+    pub tracking_number: String,
+    pub status: &'static str,
+    pub filed_on: String,
+    pub due: String,
+    pub overdue: bool,
+    pub documents: u64,
+}
+
+/// FOIA request detail page. Empty strings are unset fields.
+#[derive(Template)]
+#[template(path = "request_detail.html")]
+pub struct FoiaRequestDetailTemplate<'a> {
+    pub title: &'a str,
+    pub id: &'a str,
+    pub agency: &'a str,
+    pub subject: &'a str,
+    pub request_text: &'a str,
+    pub tracking_number: &'a str,
+    pub source_id: &'a str,
+    /// Empty when the default jurisdiction applies.
+    pub jurisdiction: &'a str,
+    pub jurisdictions: Vec<String>,
+    pub default_jurisdiction: &'a str,
+    pub status: &'static str,
+    pub statuses: Vec<&'static str>,
+    pub filed_on: String,
+    pub acknowledged_on: String,
+    pub fulfilled_on: String,
+    pub appealed_on: String,
+    pub reminded_on: String,
+    pub due: String,
+    pub overdue: bool,
+    pub notes: &'a str,
+    pub documents: Vec<LinkedDocumentRow>,
+    /// Default date for the status form.
+    pub today: String,
+}
+
+/// Helper struct for a document linked to a FOIA request, or a request
+/// linked to a document.
+pub struct LinkedDocumentRow {
+    pub id: String,
+    pub title: String,
+}
+
+/// Helper struct for a document related to the one being viewed.
+pub struct RelatedDocumentRow {
+    pub id: String,
+    pub title: String,
+    /// The relation as seen from the viewed document, e.g. "Attached to".
+    pub label: String,
+    pub evidence: String,
+}
+
+impl RelatedDocumentRow {
+    /// Rows for the relations of `doc_id` in a graph built around it.
+    pub fn from_graph(doc_id: &str, graph: &RelationGraph) -> Vec<Self> {
+        graph
+            .edges
+            .iter()
+            .filter(|e| e.source_document_id == doc_id || e.target_document_id == doc_id)
+            .map(|e| {
+                let id = e.other(doc_id);
+                let label = if e.source_document_id == doc_id {
+                    e.kind.label()
+                } else {
+                    e.kind.inverse_label()
+                };
+                Self {
+                    id: id.to_string(),
+                    title: graph
+                        .nodes
+                        .iter()
+                        .find(|n| n.id == id)
+                        .map(|n| n.title.clone())
+                        .unwrap_or_else(|| id.to_string()),
+                    label: label.to_string(),
+                    evidence: e.evidence.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Helper struct for one link in a document's discovery chain.
+pub struct ChainLinkRow {
+    pub url: String,
+    pub method: String,
+    pub depth: u32,
+    pub discovered_at: String,
+    pub fetched_at: String,
+    pub status: String,
+}
+
+/// Helper struct for one version on the provenance page.
+pub struct ProvenanceVersionRow {
+    pub id: i64,
+    pub acquired_at: String,
+    pub size: String,
+    pub mime_type: String,
+    pub sha256: String,
+    pub blake3: String,
+    pub source_url: String,
+    /// Published-checksum status, empty if none was published.
+    pub checksum: String,
+}
+
+/// Helper struct for one OCR or analysis run on the provenance page.
+pub struct AnalysisRunRow {
+    pub version_id: i64,
+    pub kind: String,
+    pub tool: String,
+    pub model: String,
+    pub pages: u32,
+    pub failed: u32,
+    pub started_at: String,
+    pub finished_at: String,
+}
+
+/// Document provenance page.
+#[derive(Template)]
+#[template(path = "provenance.html")]
+pub struct ProvenanceTemplate<'a> {
+    pub title: &'a str,
+    pub doc_id: &'a str,
+    pub source_id: &'a str,
+    pub source_url: &'a str,
+    pub discovery_method: &'a str,
+    pub created_at: String,
+    pub seed: String,
+    pub chain: Vec<ChainLinkRow>,
+    pub requests: Vec<CrawlRequestRow>,
+    pub versions: Vec<ProvenanceVersionRow>,
+    pub analysis: Vec<AnalysisRunRow>,
 }
 
 /// Error page template.
@@ -215,6 +839,94 @@ pub struct ErrorTemplate<'a> {
     pub message: &'a str,
 }
 
+/// Link in a published static site, relative to the linking page.
+pub struct SiteLink {
+    pub name: String,
+    pub href: String,
+}
+
+/// Document row in a static site listing.
+pub struct SiteDocumentRow {
+    pub href: String,
+    pub title: String,
+    pub icon: String,
+    pub source_id: String,
+    pub source_href: String,
+    pub mime_type: String,
+    pub size_str: String,
+    pub date_str: String,
+    pub has_synopsis: bool,
+    pub synopsis_preview: String,
+    pub tags: Vec<SiteLink>,
+}
+
+/// Source row on the static site index.
+pub struct SiteSourceRow {
+    pub name: String,
+    pub href: String,
+    pub count: usize,
+}
+
+/// Paged document listing in a static site (index, source, tag).
+#[derive(Template)]
+#[template(path = "static_site/listing.html")]
+pub struct SiteListingTemplate<'a> {
+    pub title: &'a str,
+    pub site_title: &'a str,
+    pub generated: &'a str,
+    /// Prefix from this page to the site root, e.g. `../../`.
+    pub root: &'a str,
+    pub summary: String,
+    pub sources: Vec<SiteSourceRow>,
+    pub has_sources: bool,
+    pub documents: Vec<SiteDocumentRow>,
+    pub has_prev: bool,
+    pub prev_href: String,
+    pub has_next: bool,
+    pub next_href: String,
+    pub page_label: String,
+    /// Magnet link of the source's torrent, on source listings; empty if none.
+    pub magnet: &'a str,
+}
+
+/// Tag list in a static site.
+#[derive(Template)]
+#[template(path = "static_site/tags.html")]
+pub struct SiteTagsTemplate<'a> {
+    pub title: &'a str,
+    pub site_title: &'a str,
+    pub generated: &'a str,
+    pub root: &'a str,
+    pub tags: Vec<SiteTagRow>,
+}
+
+/// Tag with its document count in a static site.
+pub struct SiteTagRow {
+    pub name: String,
+    pub href: String,
+    pub count: usize,
+}
+
+/// Document page in a static site.
+#[derive(Template)]
+#[template(path = "static_site/document.html")]
+pub struct SiteDocumentTemplate<'a> {
+    pub title: &'a str,
+    pub site_title: &'a str,
+    pub generated: &'a str,
+    pub root: &'a str,
+    pub source: SiteLink,
+    pub source_url: &'a str,
+    pub acquired: String,
+    pub has_synopsis: bool,
+    pub synopsis: &'a str,
+    pub tags: Vec<SiteLink>,
+    /// Published copies of each version's file, newest first.
+    pub files: Vec<VersionItem>,
+    pub has_text: bool,
+    pub text: String,
+}
+
 // Helper implementations for converting data to template structs
 
 impl TagRef {
@@ -254,6 +966,18 @@ impl VirtualFileRow {
     }
 }
 
+impl TableRow {
+    /// Row for an extracted table, or `None` for other virtual files.
+    pub fn from_virtual_file(vf: &VirtualFile) -> Option<Self> {
+        Some(Self {
+            page: vf.table_page()?,
+            id: vf.id.clone(),
+            filename: vf.filename.clone(),
+            size_str: format_size(vf.file_size),
+        })
+    }
+}
+
 impl DocumentRow {
     /// Create a DocumentRow with basic fields, no other_sources info.
     #[allow(clippy::too_many_arguments)] // Template struct initialization
@@ -283,13 +1007,19 @@ impl DocumentRow {
             id,
             title,
             icon: mime_icon(&mime_type).to_string(),
+            has_thumbnail: thumbnails::supports(&mime_type),
             mime_type,
             size_str: format_size(size),
             date_str: acquired_at.format("%Y-%m-%d %H:%M").to_string(),
+            document_date_str: String::new(),
+            pages_str: String::new(),
+            status: String::new(),
             timestamp: acquired_at.timestamp(),
             source_id,
             has_synopsis: synopsis.is_some(),
             synopsis_preview,
+            has_record_type: false,
+            record_type_label: String::new(),
             tags: tags.iter().map(|t| TagRef::new(t.clone())).collect(),
             other_tags: Vec::new(),
         }
@@ -315,6 +1045,15 @@ impl DocumentRow {
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
         let acquired_at = parse_datetime(&row.acquired_at);
+        let document_date_str: String = row
+            .document_date
+            .as_deref()
+            .unwrap_or_default()
+            .chars()
+            .take(10)
+            .collect();
+        let timeline_at = parse_datetime_opt(row.document_date).unwrap_or(acquired_at);
+        let record_type = row.record_type.as_deref().and_then(RecordType::from_str);
 
         let synopsis_preview = row
             .synopsis
@@ -333,13 +1072,23 @@ impl DocumentRow {
             id: row.id,
             title: display_name,
             icon: mime_icon(&row.mime_type).to_string(),
+            has_thumbnail: thumbnails::supports(&row.mime_type),
             mime_type: row.mime_type,
             size_str: format_size(row.file_size as u64),
             date_str: acquired_at.format("%Y-%m-%d %H:%M").to_string(),
-            timestamp: acquired_at.timestamp(),
+            document_date_str,
+            pages_str: row.page_count.map(|n| n.to_string()).unwrap_or_default(),
+            status: DocumentStatus::from_str(&row.status)
+                .map(|s| s.label().to_string())
+                .unwrap_or_else(|| row.status.replace('_', " ")),
+            timestamp: timeline_at.timestamp(),
             source_id: row.source_id,
             has_synopsis: row.synopsis.is_some(),
             synopsis_preview,
+            has_record_type: record_type.is_some(),
+            record_type_label: record_type
+                .map(|rt| rt.label().to_string())
+                .unwrap_or_default(),
             tags: tags.iter().map(|t| TagRef::new(t.clone())).collect(),
             other_tags: Vec::new(),
         }
@@ -353,7 +1102,7 @@ impl DocumentRow {
             .clone()
             .unwrap_or_else(|| doc.title.clone());
 
-        Some(Self::new(
+        let mut row = Self::new(
             doc.id.clone(),
             display_name,
             doc.source_id.clone(),
@@ -362,7 +1111,13 @@ impl DocumentRow {
             version.acquired_at,
             doc.synopsis.clone(),
             doc.tags.clone(),
-        ))
+        );
+        row.pages_str = version
+            .page_count
+            .map(|n| n.to_string())
+            .unwrap_or_default();
+        row.status = doc.status.as_str().replace('_', " ");
+        Some(row)
     }
 }
 
@@ -0,0 +1,184 @@
+//! Thumbnails for document listings.
+//!
+//! First-page thumbnails for PDFs (rendered with `pdftoppm`) and shrunken
+//! previews for images are rendered the first time they are requested and
+//! cached on disk, keyed by content hash so identical files share one
+//! thumbnail. Files that can't be rendered leave a marker so they aren't
+//! retried on every page load.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Longest side of a thumbnail in pixels.
+pub const THUMBNAIL_SIZE: u32 = 240;
+
+/// Whether thumbnails can be rendered for this MIME type.
+pub fn supports(mime_type: &str) -> bool {
+    mime_type == "application/pdf"
+        || (mime_type.starts_with("image/") && !mime_type.starts_with("image/svg"))
+}
+
+/// On-disk thumbnail cache.
+pub struct ThumbnailCache {
+    dir: PathBuf,
+}
+
+impl ThumbnailCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Cached thumbnail path for a content hash.
+    fn path(&self, content_hash: &str) -> PathBuf {
+        let prefix = &content_hash[..content_hash.len().min(2)];
+        self.dir.join(prefix).join(format!("{}.jpg", content_hash))
+    }
+
+    /// Return the thumbnail for `source`, rendering it if it isn't cached.
+    ///
+    /// Returns `Ok(None)` if the file can't be thumbnailed.
+    pub async fn get_or_render(
+        &self,
+        source: &Path,
+        mime_type: &str,
+        content_hash: &str,
+    ) -> Result<Option<Vec<u8>>, String> {
+        if !supports(mime_type)
+            || content_hash.is_empty()
+            || !content_hash.bytes().all(|b| b.is_ascii_hexdigit())
+        {
+            return Ok(None);
+        }
+
+        let path = self.path(content_hash);
+        let failed_marker = path.with_extension("failed");
+        if let Ok(bytes) = tokio::fs::read(&path).await {
+            return Ok(Some(bytes));
+        }
+        if failed_marker.exists() {
+            return Ok(None);
+        }
+
+        let source = source.to_path_buf();
+        let mime_type = mime_type.to_string();
+        let dest = path.clone();
+        let rendered = tokio::task::spawn_blocking(move || render(&source, &mime_type, &dest))
+            .await
+            .map_err(|e| format!("Thumbnail task failed: {}", e))?;
+
+        match rendered {
+            Ok(()) => tokio::fs::read(&path)
+                .await
+                .map(Some)
+                .map_err(|e| format!("Failed to read thumbnail: {}", e)),
+            Err(e) => {
+                tracing::debug!("No thumbnail for {}: {}", content_hash, e);
+                let _ = tokio::fs::write(&failed_marker, e.as_bytes()).await;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Render a thumbnail of `source` to `dest` as JPEG.
+fn render(source: &Path, mime_type: &str, dest: &Path) -> Result<(), String> {
+    let dir = dest.parent().ok_or("Thumbnail path has no parent")?;
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    // Render beside the destination and rename, so concurrent requests for
+    // the same thumbnail never see a partial file
+    let temp = dir.join(format!(".{}", uuid::Uuid::new_v4()));
+    let temp_jpg = temp.with_extension("jpg");
+    let result = if mime_type == "application/pdf" {
+        render_pdf(source, &temp)
+    } else {
+        render_image(source, &temp_jpg)
+    };
+    let result = result.and_then(|()| {
+        std::fs::rename(&temp_jpg, dest).map_err(|e| format!("Failed to save thumbnail: {}", e))
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_jpg);
+    }
+    result
+}
+
+/// First page of a PDF, via `pdftoppm`, to `<output_prefix>.jpg`.
+fn render_pdf(source: &Path, output_prefix: &Path) -> Result<(), String> {
+    let status = Command::new("pdftoppm")
+        .args(["-jpeg", "-f", "1", "-l", "1", "-singlefile", "-scale-to"])
+        .arg(THUMBNAIL_SIZE.to_string())
+        .arg(source)
+        .arg(output_prefix)
+        .status()
+        .map_err(|e| format!("Failed to run pdftoppm: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err("pdftoppm failed to render the first page".to_string())
+    }
+}
+
+/// Image scaled down to fit the thumbnail size.
+fn render_image(source: &Path, dest: &Path) -> Result<(), String> {
+    let image = image::open(source).map_err(|e| format!("Failed to decode image: {}", e))?;
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .to_rgb8()
+        .save_with_format(dest, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supports() {
+        assert!(supports("application/pdf"));
+        assert!(supports("image/png"));
+        assert!(!supports("image/svg+xml"));
+        assert!(!supports("text/html"));
+    }
+
+    #[tokio::test]
+    async fn test_image_thumbnail_is_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("scan.png");
+        image::RgbImage::new(960, 480).save(&source).unwrap();
+
+        let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+        let hash = "ab12cd34";
+        let bytes = cache
+            .get_or_render(&source, "image/png", hash)
+            .await
+            .unwrap()
+            .unwrap();
+        let thumb = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(
+            (thumb.width(), thumb.height()),
+            (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2)
+        );
+
+        // Served from the cache once the source is gone
+        std::fs::remove_file(&source).unwrap();
+        assert!(cache
+            .get_or_render(&source, "image/png", hash)
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_file_is_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("broken.png");
+        std::fs::write(&source, b"not an image").unwrap();
+
+        let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+        let result = cache.get_or_render(&source, "image/png", "ffee").await;
+        assert_eq!(result, Ok(None));
+        assert!(cache.path("ffee").with_extension("failed").exists());
+    }
+}
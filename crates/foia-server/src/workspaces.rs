@@ -0,0 +1,204 @@
+//! Workspace mounting: one router per workspace, picked per request.
+//!
+//! Each workspace gets its own `AppState`, and with it its own database,
+//! documents directory and stats cache. The workspace for a request comes
+//! from the `X-Foia-Workspace` header or the `foia_workspace` cookie set by
+//! `/workspaces/:id`; anything else gets the server's default workspace.
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Request},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Router,
+};
+use serde::Serialize;
+use tower::ServiceExt;
+use utoipa::ToSchema;
+
+use foia::config::{ServerConfig, Settings};
+
+use super::access_log;
+use super::api_keys::ApiKeyGate;
+use super::handlers::api_types::ApiResponse;
+use super::{caching, create_router, public, AppState};
+
+/// Cookie holding the workspace the browser last switched to.
+pub const WORKSPACE_COOKIE: &str = "foia_workspace";
+
+/// Header API clients can use to pick a workspace per request.
+pub const WORKSPACE_HEADER: &str = "x-foia-workspace";
+
+/// A named data directory and database to mount.
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub settings: Settings,
+}
+
+/// A workspace as listed by `GET /api/workspaces`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkspaceInfo {
+    pub id: String,
+    pub name: String,
+    /// Whether this request was served from this workspace.
+    pub active: bool,
+}
+
+struct Mounted {
+    id: String,
+    name: String,
+    router: Router,
+}
+
+/// Every mounted workspace, shared by the dispatching router.
+#[derive(Clone)]
+pub struct WorkspaceSet {
+    mounted: Arc<Vec<Mounted>>,
+    default: usize,
+}
+
+impl WorkspaceSet {
+    /// Index of the workspace a request asked for, or the default.
+    fn select(&self, headers: &HeaderMap) -> usize {
+        requested_workspace(headers)
+            .and_then(|id| self.mounted.iter().position(|m| m.id == id))
+            .unwrap_or(self.default)
+    }
+}
+
+/// Workspace ID from the request header or cookie, if any.
+fn requested_workspace(headers: &HeaderMap) -> Option<String> {
+    if let Some(id) = headers.get(WORKSPACE_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(id.trim().to_string());
+    }
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            (name == WORKSPACE_COOKIE).then(|| value.to_string())
+        })
+}
+
+/// Build the app: every workspace's routes behind a dispatcher, plus the
+/// workspace list and switch endpoints. In public mode the admin routes
+/// are left out and the public-mode layers wrap the whole app; compression
+/// goes outermost, after anything that rewrites bodies.
+pub async fn create_app(
+    workspaces: &[Workspace],
+    active: &str,
+    server: &ServerConfig,
+) -> anyhow::Result<Router> {
+    let mut mounted = Vec::with_capacity(workspaces.len());
+    for workspace in workspaces {
+        let mut state = AppState::new(&workspace.settings).await?;
+        state.public = server.is_public();
+        state.api_gate = Arc::new(ApiKeyGate::new(server));
+        state.access_mode = server.access_log();
+        state.withhold_flagged = server.withholds_flagged();
+        access_log::spawn_prune(state.access_log.clone(), server.access_log_days());
+        mounted.push(Mounted {
+            id: workspace.id.clone(),
+            name: workspace.name.clone(),
+            router: create_router(state),
+        });
+    }
+    let default = mounted
+        .iter()
+        .position(|m| m.id == active)
+        .ok_or_else(|| anyhow::anyhow!("Workspace '{}' is not mounted", active))?;
+
+    let set = WorkspaceSet {
+        mounted: Arc::new(mounted),
+        default,
+    };
+    let mut app = caching::conditional(
+        Router::new()
+            .route("/api/workspaces", get(list_workspaces))
+            .route("/workspaces/:id", get(switch_workspace))
+            .fallback(dispatch)
+            .with_state(set),
+    );
+    if server.is_public() {
+        app = public::apply(app, server);
+    }
+    Ok(caching::compress(app))
+}
+
+/// Hand the request to the selected workspace's router.
+async fn dispatch(State(set): State<WorkspaceSet>, request: Request<Body>) -> Response {
+    let router = set.mounted[set.select(request.headers())].router.clone();
+    match router.oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
+}
+
+/// List the workspaces this server has mounted.
+#[utoipa::path(
+    get,
+    path = "/api/workspaces",
+    responses(
+        (status = 200, description = "Mounted workspaces", body = Vec<WorkspaceInfo>)
+    ),
+    tag = "Status"
+)]
+pub async fn list_workspaces(State(set): State<WorkspaceSet>, headers: HeaderMap) -> Response {
+    let active = set.select(&headers);
+    let workspaces: Vec<WorkspaceInfo> = set
+        .mounted
+        .iter()
+        .enumerate()
+        .map(|(i, m)| WorkspaceInfo {
+            id: m.id.clone(),
+            name: m.name.clone(),
+            active: i == active,
+        })
+        .collect();
+    ApiResponse::ok(workspaces).into_response()
+}
+
+/// Switch the browser to another workspace and go to its front page.
+async fn switch_workspace(State(set): State<WorkspaceSet>, Path(id): Path<String>) -> Response {
+    if !set.mounted.iter().any(|m| m.id == id) {
+        return Redirect::to("/").into_response();
+    }
+    (
+        [(
+            header::SET_COOKIE,
+            format!("{}={}; Path=/; SameSite=Lax", WORKSPACE_COOKIE, id),
+        )],
+        Redirect::to("/"),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_requested_workspace() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(requested_workspace(&headers), None);
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; foia_workspace=environmental"),
+        );
+        assert_eq!(
+            requested_workspace(&headers).as_deref(),
+            Some("environmental")
+        );
+
+        headers.insert(WORKSPACE_HEADER, HeaderValue::from_static("police"));
+        assert_eq!(requested_workspace(&headers).as_deref(), Some("police"));
+    }
+}
@@ -0,0 +1,222 @@
+//! On-disk storage for derived artifacts.
+//!
+//! Artifacts are written under
+//! `documents/derived/{hash[0..2]}/{hash}/{kind}/{name}`, keyed by the
+//! source version's content hash like rendered page images. Identical
+//! files in different documents share an artifact file, so a file is only
+//! removed once no artifact record points at it.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::models::{ArtifactKind, DocumentVersion, NewDerivedArtifact};
+use crate::repository::{sanitize_filename, DieselDocumentRepository, DieselError};
+
+/// Subdirectory of the documents directory holding derived artifacts.
+pub const ARTIFACTS_SUBDIR: &str = "derived";
+
+/// On-disk store of derived artifacts.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    documents_dir: PathBuf,
+}
+
+impl ArtifactStore {
+    /// Store under `documents_dir/derived`.
+    pub fn new(documents_dir: &Path) -> Self {
+        Self {
+            documents_dir: documents_dir.to_path_buf(),
+        }
+    }
+
+    /// Path of an artifact file relative to the documents directory.
+    pub fn relative_path(
+        &self,
+        content_hash: &str,
+        kind: ArtifactKind,
+        name: &str,
+    ) -> Result<String, String> {
+        if content_hash.len() < 2 || !content_hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(format!("Invalid content hash: {}", content_hash));
+        }
+        let name = sanitize_filename(name);
+        if name.starts_with('.') {
+            return Err(format!("Invalid artifact name: {}", name));
+        }
+        Ok(format!(
+            "{}/{}/{}/{}/{}",
+            ARTIFACTS_SUBDIR,
+            &content_hash[..2],
+            content_hash,
+            kind.as_str(),
+            name
+        ))
+    }
+
+    /// Absolute path of a stored artifact file.
+    pub fn resolve(&self, file_path: &str) -> PathBuf {
+        self.documents_dir.join(file_path)
+    }
+
+    /// Write an artifact for `version` and describe it for the repository.
+    ///
+    /// The file is written beside its destination and renamed into place,
+    /// so readers never see a partial artifact.
+    pub fn write(
+        &self,
+        document_id: &str,
+        version: &DocumentVersion,
+        kind: ArtifactKind,
+        name: &str,
+        mime_type: &str,
+        content: &[u8],
+    ) -> Result<NewDerivedArtifact, String> {
+        let file_path = self.relative_path(&version.content_hash, kind, name)?;
+        let dest = self.resolve(&file_path);
+        let dir = dest.parent().expect("artifact path has a parent");
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        let temp = dir.join(format!(".{}", uuid::Uuid::new_v4()));
+        std::fs::write(&temp, content)
+            .and_then(|()| std::fs::rename(&temp, &dest))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&temp);
+                format!("Failed to save artifact {}: {}", dest.display(), e)
+            })?;
+
+        Ok(NewDerivedArtifact {
+            document_id: document_id.to_string(),
+            version_id: version.id,
+            kind,
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            file_path,
+            file_size: content.len() as u64,
+            content_hash: DocumentVersion::compute_hash(content),
+            analysis_result_id: None,
+            metadata: None,
+        })
+    }
+
+    /// Remove artifact files not in `referenced`, returning how many.
+    pub fn sweep(&self, referenced: &HashSet<String>, dry_run: bool) -> usize {
+        let root = self.documents_dir.join(ARTIFACTS_SUBDIR);
+        let mut removed = 0;
+        let mut dirs = vec![root];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&self.documents_dir) else {
+                    continue;
+                };
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                if !referenced.contains(&relative) {
+                    if !dry_run {
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+                            continue;
+                        }
+                    }
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+}
+
+/// Outcome of pruning artifacts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Stale artifact records deleted.
+    pub records: usize,
+    /// Files removed because nothing references them any more.
+    pub files: usize,
+}
+
+/// Delete artifacts stale for longer than `stale_for`, then remove files no
+/// artifact references, including those of deleted documents.
+pub async fn prune_artifacts(
+    repo: &DieselDocumentRepository,
+    store: &ArtifactStore,
+    stale_for: chrono::Duration,
+    dry_run: bool,
+) -> Result<PruneReport, DieselError> {
+    let stale = repo
+        .get_stale_artifacts(chrono::Utc::now() - stale_for)
+        .await?;
+    let ids: Vec<i64> = stale.iter().map(|a| a.id).collect();
+
+    let records = if dry_run {
+        ids.len()
+    } else {
+        repo.delete_artifacts(&ids).await?
+    };
+    // On a dry run, count the files deleting the records would orphan
+    let excluded: &[i64] = if dry_run { &ids } else { &[] };
+    let referenced = repo.get_artifact_paths(excluded).await?;
+
+    let store = store.clone();
+    let files = tokio::task::spawn_blocking(move || store.sweep(&referenced, dry_run))
+        .await
+        .unwrap_or(0);
+    Ok(PruneReport { records, files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_sweep() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ArtifactStore::new(dir.path());
+        let version = DocumentVersion::new(b"original", "application/pdf".to_string(), None);
+
+        let artifact = store
+            .write(
+                "doc-1",
+                &version,
+                ArtifactKind::SearchablePdf,
+                "searchable.pdf",
+                "application/pdf",
+                b"%PDF",
+            )
+            .unwrap();
+        assert!(artifact.file_path.starts_with("derived/"));
+        assert!(artifact
+            .file_path
+            .ends_with("/searchable_pdf/searchable.pdf"));
+        assert_eq!(artifact.file_size, 4);
+        assert_eq!(
+            std::fs::read(store.resolve(&artifact.file_path)).unwrap(),
+            b"%PDF"
+        );
+
+        let referenced: HashSet<String> = [artifact.file_path.clone()].into();
+        assert_eq!(store.sweep(&referenced, false), 0);
+        assert_eq!(store.sweep(&HashSet::new(), true), 1);
+        assert!(store.resolve(&artifact.file_path).exists());
+        assert_eq!(store.sweep(&HashSet::new(), false), 1);
+        assert!(!store.resolve(&artifact.file_path).exists());
+    }
+
+    #[test]
+    fn test_rejects_non_hash_keys() {
+        let store = ArtifactStore::new(Path::new("/tmp"));
+        assert!(store
+            .relative_path("../../etc", ArtifactKind::Table, "x.csv")
+            .is_err());
+        assert!(store
+            .relative_path("abcd", ArtifactKind::Table, "..")
+            .is_err());
+    }
+}
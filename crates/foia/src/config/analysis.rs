@@ -88,6 +88,9 @@ pub struct OcrConfig {
     /// Backend entries to run. Each entry produces a separate result.
     #[serde(default = "default_ocr_backends")]
     pub backends: Vec<BackendEntry>,
+    /// Language for backends that take one (Tesseract), e.g. `eng+fra`.
+    #[serde(default = "default_ocr_language")]
+    pub language: String,
 }
 
 impl prefer::FromValue for OcrConfig {
@@ -110,10 +113,26 @@ impl prefer::FromValue for OcrConfig {
         } else {
             default_ocr_backends()
         };
-        Ok(OcrConfig { backends })
+        let language = value
+            .as_object()
+            .and_then(|obj| obj.get("language"))
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .unwrap_or_else(default_ocr_language);
+        Ok(OcrConfig {
+            backends,
+            language,
+        })
     }
 }
 
+fn default_ocr_language() -> String {
+    std::env::var("ANALYSIS_OCR_LANGUAGE")
+        .ok()
+        .filter(|lang| !lang.trim().is_empty())
+        .unwrap_or_else(|| "eng".to_string())
+}
+
 fn default_ocr_backends() -> Vec<BackendEntry> {
     if let Ok(val) = std::env::var("ANALYSIS_OCR_BACKENDS") {
         let backends: Vec<BackendEntry> = val
@@ -148,6 +167,7 @@ impl Default for OcrConfig {
     fn default() -> Self {
         Self {
             backends: default_ocr_backends(),
+            language: default_ocr_language(),
         }
     }
 }
@@ -169,12 +189,328 @@ pub struct AnalysisConfig {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     #[prefer(default)]
     pub default_methods: Vec<String>,
+    /// Page images rendered during OCR for the document viewer.
+    #[serde(default, skip_serializing_if = "PageImageConfig::is_default")]
+    #[prefer(default)]
+    pub page_images: PageImageConfig,
+    /// Safeguards against huge or malformed documents.
+    #[serde(default, skip_serializing_if = "ExtractionLimits::is_default")]
+    #[prefer(default)]
+    pub limits: ExtractionLimits,
+    /// Local OCR process pool.
+    #[serde(default, skip_serializing_if = "OcrPoolConfig::is_default")]
+    #[prefer(default)]
+    pub pool: OcrPoolConfig,
+    /// Engine used to read and render PDFs.
+    #[serde(default, skip_serializing_if = "PdfConfig::is_default")]
+    #[prefer(default)]
+    pub pdf: PdfConfig,
 }
 
 impl AnalysisConfig {
     /// Check if this is the default (empty) config.
     pub fn is_default(&self) -> bool {
-        self.methods.is_empty() && self.default_methods.is_empty()
+        self.methods.is_empty()
+            && self.default_methods.is_empty()
+            && self.page_images.is_default()
+            && self.limits.is_default()
+            && self.pool.is_default()
+            && self.pdf.is_default()
+    }
+}
+
+/// Limits that keep pathological PDFs (thousands of pages, poster-sized
+/// scans) from stalling or exhausting memory during extraction and OCR.
+///
+/// A page that breaks a limit is marked failed; the rest of the document
+/// is still processed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ExtractionLimits {
+    /// Pages whose text is extracted and saved at a time.
+    #[serde(default = "default_page_batch")]
+    #[prefer(default = "200")]
+    pub page_batch: u32,
+    /// Seconds a single pdftotext or pdftoppm run may take before it is killed.
+    #[serde(default = "default_render_timeout_secs")]
+    #[prefer(default = "120")]
+    pub render_timeout_secs: u64,
+    /// Largest image a page is rendered to for OCR, in megapixels. Larger
+    /// pages are rendered at a lower resolution to fit.
+    #[serde(default = "default_max_page_megapixels")]
+    #[prefer(default = "50")]
+    pub max_page_megapixels: u32,
+    /// Temporary disk space a rendered page image may take, in MiB. A pool
+    /// slot holds up to one batch of page images at a time.
+    #[serde(default = "default_max_temp_mb")]
+    #[prefer(default = "512")]
+    pub max_temp_mb: u64,
+}
+
+fn default_page_batch() -> u32 {
+    200
+}
+
+fn default_render_timeout_secs() -> u64 {
+    120
+}
+
+fn default_max_page_megapixels() -> u32 {
+    50
+}
+
+fn default_max_temp_mb() -> u64 {
+    512
+}
+
+impl Default for ExtractionLimits {
+    fn default() -> Self {
+        Self {
+            page_batch: default_page_batch(),
+            render_timeout_secs: default_render_timeout_secs(),
+            max_page_megapixels: default_max_page_megapixels(),
+            max_temp_mb: default_max_temp_mb(),
+        }
+    }
+}
+
+impl ExtractionLimits {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn render_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.render_timeout_secs.max(1))
+    }
+
+    /// Largest page image, in pixels.
+    pub fn max_page_pixels(&self) -> u64 {
+        u64::from(self.max_page_megapixels.max(1)) * 1_000_000
+    }
+
+    /// Largest page image file, in bytes.
+    pub fn max_temp_bytes(&self) -> u64 {
+        self.max_temp_mb.max(1) * 1024 * 1024
+    }
+}
+
+/// Pool of local OCR processes (pdftoppm and Tesseract).
+///
+/// Pages are OCR'd in batches of consecutive pages from one document: each
+/// batch is rendered by one pdftoppm run and recognized by one Tesseract
+/// run, so process startup is paid per batch rather than per page. Each
+/// pool slot keeps its own scratch directory between batches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct OcrPoolConfig {
+    /// Batches run at once. `0` runs one per CPU.
+    #[serde(default)]
+    #[prefer(default)]
+    pub size: usize,
+    /// Consecutive pages of a document per batch.
+    #[serde(default = "default_batch_pages")]
+    #[prefer(default = "8")]
+    pub batch_pages: u32,
+}
+
+fn default_batch_pages() -> u32 {
+    8
+}
+
+impl Default for OcrPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            batch_pages: default_batch_pages(),
+        }
+    }
+}
+
+impl OcrPoolConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Batches run at once.
+    pub fn workers(&self) -> usize {
+        if self.size > 0 {
+            self.size
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        }
+    }
+
+    /// Pages per batch.
+    pub fn batch_pages(&self) -> u32 {
+        self.batch_pages.max(1)
+    }
+}
+
+/// Engine used to count, read and render PDF pages during extraction and
+/// OCR.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct PdfConfig {
+    /// Which engine to use.
+    #[serde(default)]
+    #[prefer(default)]
+    pub engine: PdfEngineKind,
+    /// Directory holding the pdfium library. Defaults to the directory of
+    /// the running executable, then the system library path.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub pdfium_library: Option<String>,
+}
+
+impl PdfConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// PDF engine choice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfEngineKind {
+    /// Poppler when its tools are installed, pdfium otherwise.
+    #[default]
+    Auto,
+    /// Poppler command-line tools (pdfinfo, pdftotext, pdftoppm).
+    Poppler,
+    /// The pdfium library, loaded at runtime. Needs the `pdfium` feature.
+    Pdfium,
+}
+
+impl prefer::FromValue for PdfEngineKind {
+    fn from_value(value: &prefer::ConfigValue) -> prefer::Result<Self> {
+        match value.as_str() {
+            Some(s) => Self::from_str(s).ok_or_else(|| prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PdfEngineKind".to_string(),
+                source: format!("unknown PDF engine: {}", s).into(),
+            }),
+            None => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PdfEngineKind".to_string(),
+                source: "expected string".into(),
+            }),
+        }
+    }
+}
+
+impl PdfEngineKind {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(Self::Auto),
+            "poppler" => Some(Self::Poppler),
+            "pdfium" => Some(Self::Pdfium),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Poppler => "poppler",
+            Self::Pdfium => "pdfium",
+        }
+    }
+}
+
+/// Rendering of page images for the document viewer.
+///
+/// Pages are rendered once, during OCR, and cached under
+/// `documents/pages`. The web server renders any page that is missing on
+/// first view at the default settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct PageImageConfig {
+    /// Render page images while running OCR.
+    #[serde(default = "default_true")]
+    #[prefer(default = "true")]
+    pub enabled: bool,
+    /// Resolution to render at.
+    #[serde(default = "default_page_dpi")]
+    #[prefer(default = "150")]
+    pub dpi: u32,
+    /// Image format: "png" or "webp".
+    #[serde(default)]
+    #[prefer(default)]
+    pub format: PageImageFormat,
+}
+
+fn default_page_dpi() -> u32 {
+    DEFAULT_PAGE_DPI
+}
+
+/// Resolution page images are rendered at unless configured.
+pub const DEFAULT_PAGE_DPI: u32 = 150;
+
+impl Default for PageImageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dpi: DEFAULT_PAGE_DPI,
+            format: PageImageFormat::default(),
+        }
+    }
+}
+
+impl PageImageConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// File format for rendered page images.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageImageFormat {
+    #[default]
+    Png,
+    /// Lossless WebP; smaller files, slower to encode.
+    Webp,
+}
+
+impl prefer::FromValue for PageImageFormat {
+    fn from_value(value: &prefer::ConfigValue) -> prefer::Result<Self> {
+        match value.as_str() {
+            Some(s) => Self::from_str(s).ok_or_else(|| prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PageImageFormat".to_string(),
+                source: format!("unknown page image format: {}", s).into(),
+            }),
+            None => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PageImageFormat".to_string(),
+                source: "expected string".into(),
+            }),
+        }
+    }
+}
+
+impl PageImageFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "png" => Some(Self::Png),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// File extension, which is also the format name.
+    pub fn extension(&self) -> &'static str {
+        self.as_str()
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+        }
     }
 }
 
@@ -0,0 +1,51 @@
+//! Published checksum configuration.
+//!
+//! Some sources publish checksum manifests (`SHA256SUMS`, `md5sums.txt`)
+//! or per-file sidecars (`report.pdf.sha256`), sometimes with a detached
+//! GPG signature. A source's `checksums` block says where to find them so
+//! downloads can be verified against what the agency says it published.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Where a source publishes checksums for its documents.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ChecksumConfig {
+    /// URLs of checksum manifests listing many files, e.g.
+    /// `["https://agency.gov/releases/SHA256SUMS"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub manifests: Vec<String>,
+
+    /// Suffixes appended to each document URL to find a per-file checksum,
+    /// e.g. `[".sha256", ".md5"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub sidecars: Vec<String>,
+
+    /// Suffix of the detached signature next to each manifest or sidecar
+    /// (`".asc"` or `".sig"`). Unset means checksums are not signed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub signature: Option<String>,
+
+    /// GPG keyring holding the publisher's key, used to check signatures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub keyring: Option<PathBuf>,
+}
+
+impl ChecksumConfig {
+    /// Whether any checksum location is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.manifests.is_empty() || !self.sidecars.is_empty()
+    }
+
+    /// URL of the detached signature for a manifest or sidecar, if signed.
+    pub fn signature_url(&self, checksum_url: &str) -> Option<String> {
+        self.signature
+            .as_deref()
+            .map(|suffix| format!("{}{}", checksum_url, suffix))
+    }
+}
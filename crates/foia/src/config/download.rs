@@ -0,0 +1,121 @@
+//! Download pipeline configuration.
+//!
+//! Downloads run as a staged pipeline: fetch workers hand bodies to hash
+//! workers (hashing and type detection), which hand them to store workers
+//! (writing files and saving documents). Stages are connected by bounded
+//! channels, so a slow stage holds the ones before it back instead of
+//! buffering without limit. The number of fetch workers is set per run
+//! with `--workers`.
+//!
+//! Hash lists of known files (agency logos, blank scan pages, form
+//! templates) keep repeated boilerplate out of storage; see
+//! [`KnownFilesConfig`].
+
+use serde::{Deserialize, Serialize};
+
+/// Default documents held between two stages.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
+/// Default hash workers.
+pub const DEFAULT_HASH_WORKERS: usize = 2;
+
+/// Default store workers.
+pub const DEFAULT_STORE_WORKERS: usize = 4;
+
+/// Download pipeline parallelism and backpressure.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct DownloadPipelineConfig {
+    /// Documents held between two stages before the earlier stage waits
+    /// (default 16).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub channel_capacity: Option<usize>,
+
+    /// Workers hashing and detecting the type of fetched content (default 2).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub hash_workers: Option<usize>,
+
+    /// Workers writing files and saving documents (default 4).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub store_workers: Option<usize>,
+
+    /// Publish an OCR task to the worker queue for every downloaded
+    /// document, instead of leaving it for `foia analyze`.
+    #[serde(default)]
+    #[prefer(default)]
+    pub enqueue_ocr: bool,
+
+    /// Hash lists of files not to store.
+    #[serde(default, skip_serializing_if = "KnownFilesConfig::is_default")]
+    #[prefer(default)]
+    pub known_files: KnownFilesConfig,
+}
+
+/// Hash lists, NSRL-style, of files the hash stage drops instead of
+/// storing. Each list is a text file of hex digests (MD5, SHA-1, SHA-256,
+/// BLAKE3 or SHA-512), one per line, optionally followed by a filename as
+/// `sha256sum` writes them; NSRL RDS CSV files work as they are.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct KnownFilesConfig {
+    /// Lists of files not worth storing, e.g. `["lists/logos.txt"]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub skip: Vec<String>,
+
+    /// Lists of files always stored, even when a skip list has them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub keep: Vec<String>,
+}
+
+impl KnownFilesConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl DownloadPipelineConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Documents held between two stages.
+    pub fn channel_capacity(&self) -> usize {
+        self.channel_capacity
+            .unwrap_or(DEFAULT_CHANNEL_CAPACITY)
+            .max(1)
+    }
+
+    /// Hash workers.
+    pub fn hash_workers(&self) -> usize {
+        self.hash_workers.unwrap_or(DEFAULT_HASH_WORKERS).max(1)
+    }
+
+    /// Store workers.
+    pub fn store_workers(&self) -> usize {
+        self.store_workers.unwrap_or(DEFAULT_STORE_WORKERS).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_and_floors() {
+        let config = DownloadPipelineConfig::default();
+        assert_eq!(config.channel_capacity(), DEFAULT_CHANNEL_CAPACITY);
+        assert_eq!(config.hash_workers(), DEFAULT_HASH_WORKERS);
+        assert_eq!(config.store_workers(), DEFAULT_STORE_WORKERS);
+
+        let config: DownloadPipelineConfig =
+            serde_json::from_str(r#"{"channel_capacity": 0, "store_workers": 8}"#).unwrap();
+        assert_eq!(config.channel_capacity(), 1);
+        assert_eq!(config.store_workers(), 8);
+        assert!(!config.enqueue_ocr);
+    }
+}
@@ -0,0 +1,155 @@
+//! Outgoing email configuration.
+//!
+//! Mail goes through an SMTP server when `smtp_host` is set, otherwise
+//! through the local sendmail binary.
+
+use serde::{Deserialize, Serialize};
+
+/// Default sendmail-compatible binary when no SMTP server is configured.
+pub const DEFAULT_SENDMAIL: &str = "sendmail";
+
+/// Default From address.
+pub const DEFAULT_FROM: &str = "foia@localhost";
+
+/// Environment variable that overrides `smtp_password`, so the password
+/// can stay out of the config file.
+pub const SMTP_PASSWORD_ENV: &str = "FOIA_SMTP_PASSWORD";
+
+/// How to secure the SMTP connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    /// Plain connection upgraded with STARTTLS (port 587).
+    #[default]
+    StartTls,
+    /// TLS from the start (port 465).
+    Tls,
+    /// No encryption, e.g. a relay on localhost (port 25).
+    None,
+}
+
+impl SmtpSecurity {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "starttls" => Some(Self::StartTls),
+            "tls" | "ssl" => Some(Self::Tls),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Standard port for this kind of connection.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Self::StartTls => 587,
+            Self::Tls => 465,
+            Self::None => 25,
+        }
+    }
+}
+
+/// How to send email and who receives reports by default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct EmailConfig {
+    /// SMTP server; when unset, mail is handed to sendmail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_host: Option<String>,
+
+    /// SMTP port (default: 587, 465 or 25 by `smtp_security`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_port: Option<u16>,
+
+    /// `starttls` (default), `tls` or `none`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_security: Option<String>,
+
+    /// SMTP login.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_username: Option<String>,
+
+    /// SMTP password. `FOIA_SMTP_PASSWORD` takes precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_password: Option<String>,
+
+    /// Connect to a remote `smtp_host` even when Tor is enabled. SMTP
+    /// doesn't go through Tor, so the server sees this machine's address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub smtp_direct: Option<bool>,
+
+    /// sendmail-compatible binary used without SMTP (default `sendmail`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub sendmail: Option<String>,
+
+    /// From address (default `foia@localhost`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub from: Option<String>,
+
+    /// Recipients of crawl summaries, scraper alerts and digests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub to: Vec<String>,
+
+    /// Web UI address used for links in emails, e.g.
+    /// `https://foia.example.org`. Without it emails carry no links.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub base_url: Option<String>,
+}
+
+impl EmailConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Connection security; unknown values fall back to STARTTLS.
+    pub fn security(&self) -> SmtpSecurity {
+        self.smtp_security
+            .as_deref()
+            .and_then(SmtpSecurity::from_str)
+            .unwrap_or_default()
+    }
+
+    /// SMTP port.
+    pub fn smtp_port(&self) -> u16 {
+        self.smtp_port
+            .unwrap_or_else(|| self.security().default_port())
+    }
+
+    /// SMTP password, from the environment or the config.
+    pub fn smtp_password(&self) -> Option<String> {
+        std::env::var(SMTP_PASSWORD_ENV)
+            .ok()
+            .filter(|s| !s.is_empty())
+            .or_else(|| self.smtp_password.clone())
+    }
+
+    /// Whether a remote SMTP server may be reached without Tor.
+    pub fn smtp_direct(&self) -> bool {
+        self.smtp_direct.unwrap_or(false)
+    }
+
+    /// sendmail binary.
+    pub fn sendmail(&self) -> &str {
+        self.sendmail.as_deref().unwrap_or(DEFAULT_SENDMAIL)
+    }
+
+    /// From address.
+    pub fn from(&self) -> &str {
+        self.from.as_deref().unwrap_or(DEFAULT_FROM)
+    }
+
+    /// Absolute link to a web UI path, if `base_url` is set.
+    pub fn link(&self, path: &str) -> Option<String> {
+        self.base_url
+            .as_deref()
+            .map(|base| format!("{}{}", base.trim_end_matches('/'), path))
+    }
+}
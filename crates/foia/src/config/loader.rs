@@ -142,13 +142,7 @@ pub async fn load_settings_with_options(options: LoadOptions) -> (Settings, Conf
     let mut settings = Settings::default();
 
     // Determine base directory for resolving relative paths
-    let base_dir = if options.use_cwd {
-        std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
-    } else {
-        config
-            .base_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")))
-    };
+    let base_dir = config.resolve_base_dir(options.use_cwd);
 
     config.apply_to_settings(&mut settings, &base_dir);
 
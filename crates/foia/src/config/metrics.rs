@@ -0,0 +1,84 @@
+//! Metrics export configuration.
+//!
+//! The server always exposes `/metrics` for scraping. Short-lived CLI runs
+//! (cron-driven scrapes, OCR batches) exit before Prometheus would scrape
+//! them, so they can push to a Pushgateway instead.
+
+use serde::{Deserialize, Serialize};
+
+/// Default seconds between pushes while a command runs.
+pub const DEFAULT_PUSH_INTERVAL_SECS: u64 = 15;
+
+/// Default Pushgateway job name.
+pub const DEFAULT_PUSH_JOB: &str = "foia";
+
+/// Pushgateway settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct MetricsConfig {
+    /// Pushgateway base URL, e.g. `"http://pushgateway:9091"`.
+    /// Unset disables pushing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub pushgateway: Option<String>,
+
+    /// Seconds between pushes (default 15). A final push is always made
+    /// when the command exits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub push_interval_secs: Option<u64>,
+
+    /// Job label for pushed metrics (default `"foia"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub job: Option<String>,
+}
+
+impl MetricsConfig {
+    /// Check if this is the default configuration (no pushing).
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Seconds between pushes.
+    pub fn push_interval_secs(&self) -> u64 {
+        self.push_interval_secs
+            .unwrap_or(DEFAULT_PUSH_INTERVAL_SECS)
+            .max(1)
+    }
+
+    /// Pushgateway job name.
+    pub fn job(&self) -> &str {
+        self.job.as_deref().unwrap_or(DEFAULT_PUSH_JOB)
+    }
+
+    /// Pushgateway URL grouping pushed metrics under `job` and `instance`.
+    pub fn push_url(&self, instance: &str) -> Option<String> {
+        self.pushgateway.as_deref().map(|base| {
+            format!(
+                "{}/metrics/job/{}/instance/{}",
+                base.trim_end_matches('/'),
+                urlencoding::encode(self.job()),
+                urlencoding::encode(instance)
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_url() {
+        let config = MetricsConfig {
+            pushgateway: Some("http://pushgateway:9091/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.push_url("host-1").as_deref(),
+            Some("http://pushgateway:9091/metrics/job/foia/instance/host-1")
+        );
+        assert_eq!(MetricsConfig::default().push_url("host-1"), None);
+        assert!(MetricsConfig::default().is_default());
+    }
+}
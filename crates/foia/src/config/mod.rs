@@ -2,10 +2,24 @@
 
 mod analysis;
 pub mod browser;
+pub mod checksum;
 pub mod discovery;
+pub mod download;
+pub mod email;
 mod loader;
+pub mod metrics;
+pub mod politeness;
+pub mod reload;
+pub mod requests;
 pub mod scraper;
+pub mod server;
+pub mod session;
 mod settings;
+pub mod shards;
+pub mod throttle;
+pub mod wayback;
+pub mod workflow;
+pub mod workspace;
 
 use std::collections::HashMap;
 use std::fs;
@@ -18,11 +32,28 @@ use crate::llm::LlmConfig;
 use crate::privacy::PrivacyConfig;
 use crate::repository::util::validate_database_url;
 
-pub use analysis::{AnalysisConfig, AnalysisMethodConfig, OcrConfig};
+pub use analysis::{
+    AnalysisConfig, AnalysisMethodConfig, BackendEntry, ExtractionLimits, OcrConfig, OcrPoolConfig,
+    PageImageConfig, PageImageFormat, PdfConfig, PdfEngineKind, DEFAULT_PAGE_DPI,
+};
 pub use browser::{BrowserEngineConfig, BrowserEngineType, SelectionStrategyType};
+pub use checksum::ChecksumConfig;
+pub use download::{DownloadPipelineConfig, KnownFilesConfig};
+pub use email::{EmailConfig, SmtpSecurity};
 pub use loader::{load_settings_with_options, LoadOptions};
-pub use scraper::{ScraperConfig, ViaMode};
+pub use metrics::MetricsConfig;
+pub use politeness::{Politeness, PolitenessProfile};
+pub use reload::{ConfigReloader, LiveConfig, ReloadPlan};
+pub use requests::{JurisdictionConfig, RemindersConfig, RequestsConfig};
+pub use scraper::{ScopeConfig, ScraperConfig, ViaMode};
+pub use server::{AccessLogMode, ServerConfig};
+pub use session::{LoginConfig, LoginType, SessionConfig};
 pub use settings::Settings;
+pub use shards::{ShardConfig, MAX_SHARDS};
+pub use throttle::ThrottleConfig;
+pub use wayback::WaybackConfig;
+pub use workflow::WorkflowConfig;
+pub use workspace::{WorkspaceConfig, DEFAULT_WORKSPACE};
 
 /// Default refresh TTL in days (14 days).
 pub const DEFAULT_REFRESH_TTL_DAYS: u64 = 14;
@@ -59,6 +90,9 @@ pub struct Config {
     /// Worker queue broker URL.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub broker_url: Option<String>,
+    /// Minutes an analysis claim holds a document without renewal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim_lease_minutes: Option<u32>,
     /// Default refresh TTL in days.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default_refresh_ttl_days: Option<u64>,
@@ -86,6 +120,49 @@ pub struct Config {
     #[serde(default, skip_serializing_if = "is_via_mode_default")]
     #[prefer(default)]
     pub via_mode: ViaMode,
+    /// Global bandwidth cap and schedule (sources may override).
+    #[serde(default, skip_serializing_if = "ThrottleConfig::is_default")]
+    #[prefer(default)]
+    pub throttle: ThrottleConfig,
+    /// Default politeness profile for sources that don't set one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub politeness: Option<PolitenessProfile>,
+    /// Never fetch URLs robots.txt disallows, unless a source says otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_robots: Option<bool>,
+    /// Pushgateway settings for exporting metrics from CLI runs.
+    #[serde(default, skip_serializing_if = "MetricsConfig::is_default")]
+    #[prefer(default)]
+    pub metrics: MetricsConfig,
+    /// Download pipeline parallelism and backpressure.
+    #[serde(default, skip_serializing_if = "DownloadPipelineConfig::is_default")]
+    #[prefer(default)]
+    pub download: DownloadPipelineConfig,
+    /// Web server settings (public read-only mode).
+    #[serde(default, skip_serializing_if = "ServerConfig::is_default")]
+    #[prefer(default)]
+    pub server: ServerConfig,
+    /// FOIA request deadline rules and overdue reminders.
+    #[serde(default, skip_serializing_if = "RequestsConfig::is_default")]
+    #[prefer(default)]
+    pub requests: RequestsConfig,
+    /// Outgoing email for reminders, crawl summaries and digests.
+    #[serde(default, skip_serializing_if = "EmailConfig::is_default")]
+    #[prefer(default)]
+    pub email: EmailConfig,
+    /// Named workspaces, each with its own data directory and database.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[prefer(default)]
+    pub workspaces: HashMap<String, WorkspaceConfig>,
+    /// Allowed document status transitions.
+    #[serde(default, skip_serializing_if = "WorkflowConfig::is_default")]
+    #[prefer(default)]
+    pub workflow: WorkflowConfig,
+    /// Database shards, each holding some sources' documents and crawl
+    /// state in its own SQLite file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub shards: Vec<ShardConfig>,
     /// Path to the config file this was loaded from (not serialized).
     #[serde(skip)]
     #[prefer(skip)]
@@ -98,7 +175,7 @@ fn is_via_mode_default(mode: &ViaMode) -> bool {
 
 /// Source interaction settings synced to database.
 /// Describes how to reach and interact with sources (HTTP behavior, scraper configs, proxy routing).
-/// Excludes device-specific (data_dir, privacy, analysis, llm) and bootstrap (rate_limit_backend, broker_url) settings.
+/// Excludes device-specific (data_dir, privacy, analysis, llm) and bootstrap (rate_limit_backend, broker_url, claim_lease_minutes) settings.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, prefer::FromValue)]
 pub struct SourcesConfig {
     /// User agent string.
@@ -125,6 +202,16 @@ pub struct SourcesConfig {
     #[serde(default, skip_serializing_if = "is_via_mode_default")]
     #[prefer(default)]
     pub via_mode: ViaMode,
+    /// Global bandwidth cap and schedule.
+    #[serde(default, skip_serializing_if = "ThrottleConfig::is_default")]
+    #[prefer(default)]
+    pub throttle: ThrottleConfig,
+    /// Default politeness profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub politeness: Option<PolitenessProfile>,
+    /// Global robots.txt switch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_robots: Option<bool>,
 }
 
 /// Resolved data path information for SQLite databases.
@@ -233,6 +320,19 @@ impl Config {
         Ok(config)
     }
 
+    /// Serialize config in the format `load_from_path` reads for `path`.
+    pub fn to_file_contents(&self, path: &Path) -> Result<String, String> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("json");
+        match ext {
+            "toml" => toml::to_string_pretty(self)
+                .map_err(|e| format!("Failed to write TOML config: {}", e)),
+            "yaml" | "yml" => serde_yaml::to_string(self)
+                .map_err(|e| format!("Failed to write YAML config: {}", e)),
+            _ => serde_json::to_string_pretty(self)
+                .map_err(|e| format!("Failed to write JSON config: {}", e)),
+        }
+    }
+
     /// Get the base directory for resolving relative paths.
     /// Returns the config file's parent directory if available, otherwise None.
     pub fn base_dir(&self) -> Option<PathBuf> {
@@ -289,6 +389,64 @@ impl Config {
         if let Some(ref broker) = self.broker_url {
             settings.broker_url = Some(broker.clone());
         }
+        if let Some(minutes) = self.claim_lease_minutes {
+            settings.claim_lease_minutes = minutes;
+        }
+        if !self.workflow.is_default() {
+            settings.workflow = self.workflow.workflow();
+        }
+        if !self.shards.is_empty() {
+            settings.shards = self.shards.clone();
+        }
+    }
+
+    /// Directory relative paths are resolved from: the current directory with
+    /// `use_cwd`, otherwise the config file's directory (or the current
+    /// directory when there is no config file).
+    pub fn resolve_base_dir(&self, use_cwd: bool) -> PathBuf {
+        let cwd = || std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        if use_cwd {
+            cwd()
+        } else {
+            self.base_dir().unwrap_or_else(cwd)
+        }
+    }
+
+    /// Fill a source's unset politeness profile and robots switch from the
+    /// global settings.
+    pub fn apply_politeness_defaults(&self, source: &mut ScraperConfig) {
+        source.politeness = source.politeness.or(self.politeness);
+        source.respect_robots = source.respect_robots.or(self.respect_robots);
+    }
+
+    /// Configured workspace IDs, sorted, with `default` first.
+    pub fn workspace_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.workspaces.keys().cloned().collect();
+        ids.sort();
+        ids.retain(|id| id != DEFAULT_WORKSPACE);
+        ids.insert(0, DEFAULT_WORKSPACE.to_string());
+        ids
+    }
+
+    /// Settings for a named workspace, derived from the top-level `base`.
+    ///
+    /// `default` is the top-level data directory and database unless a
+    /// workspace of that name is configured.
+    pub fn workspace_settings(
+        &self,
+        id: &str,
+        base: &Settings,
+        base_dir: &Path,
+    ) -> Result<Settings, String> {
+        match self.workspaces.get(id) {
+            Some(workspace) => workspace.settings(base, base_dir),
+            None if id == DEFAULT_WORKSPACE => Ok(base.clone()),
+            None => Err(format!(
+                "Unknown workspace '{}' (configured: {})",
+                id,
+                self.workspace_ids().join(", ")
+            )),
+        }
     }
 
     /// Get the effective refresh TTL in days for a scraper.
@@ -316,7 +474,6 @@ impl Config {
     /// Serialize config to JSON with paths converted to relative.
     /// Any paths pointing to `base_dir` are converted to relative paths.
     /// Note: This serializes the full config (for config files). For DB storage, use `to_sources_config()`.
-    #[allow(dead_code)]
     pub fn to_json_relative(&self, base_dir: &Path) -> String {
         let mut config = self.clone();
         config.source_path = None; // Don't serialize the source path
@@ -358,6 +515,8 @@ impl Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::StatusWorkflow;
+    use crate::repository::diesel_document::DEFAULT_CLAIM_LEASE_MINUTES;
     use std::path::PathBuf;
 
     fn default_settings() -> Settings {
@@ -371,6 +530,9 @@ mod tests {
             request_delay_ms: 500,
             rate_limit_backend: None,
             broker_url: None,
+            claim_lease_minutes: DEFAULT_CLAIM_LEASE_MINUTES,
+            workflow: StatusWorkflow::default(),
+            shards: Vec::new(),
             no_tls: false,
         }
     }
@@ -433,6 +595,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn workspace_settings_resolves_named_and_default() {
+        let mut config = Config::default();
+        config.workspaces.insert(
+            "environmental".to_string(),
+            WorkspaceConfig {
+                name: None,
+                data_dir: "env".to_string(),
+                database: None,
+            },
+        );
+        let base = default_settings();
+        let base_dir = PathBuf::from("/etc/foia");
+
+        assert_eq!(config.workspace_ids(), vec!["default", "environmental"]);
+        let default = config
+            .workspace_settings(DEFAULT_WORKSPACE, &base, &base_dir)
+            .unwrap();
+        assert_eq!(default.data_dir, base.data_dir);
+        let env = config
+            .workspace_settings("environmental", &base, &base_dir)
+            .unwrap();
+        assert_eq!(env.data_dir, PathBuf::from("/etc/foia/env"));
+        assert!(config
+            .workspace_settings("missing", &base, &base_dir)
+            .is_err());
+    }
+
     #[test]
     fn to_json_relative_preserves_url_values() {
         let config = Config {
@@ -445,6 +635,24 @@ mod tests {
         assert!(json.contains("sqlite:///absolute/path/to/db"));
     }
 
+    #[test]
+    fn to_file_contents_round_trips() {
+        let config = Config {
+            request_delay_ms: Some(750),
+            ..Config::default()
+        };
+        for ext in ["json", "toml", "yaml"] {
+            let path = PathBuf::from(format!("foia.{}", ext));
+            let contents = config.to_file_contents(&path).unwrap();
+            let parsed: Config = match ext {
+                "toml" => toml::from_str(&contents).unwrap(),
+                "yaml" => serde_yaml::from_str(&contents).unwrap(),
+                _ => serde_json::from_str(&contents).unwrap(),
+            };
+            assert_eq!(parsed.hash(), config.hash(), "{}", ext);
+        }
+    }
+
     #[test]
     fn apply_no_database_leaves_defaults() {
         let config = Config::default();
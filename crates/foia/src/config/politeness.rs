@@ -0,0 +1,222 @@
+//! Politeness profiles.
+//!
+//! A profile bundles request delay, concurrency, backoff and user agent so a
+//! big federal CDN and a tiny county site can be crawled at different paces
+//! without tuning each knob. Set `politeness` globally or per source; a
+//! source's own `request_delay_ms` and `user_agent` still win over its
+//! profile. `respect_robots` is a separate hard switch: when on, URLs that
+//! robots.txt disallows are never fetched.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::scraper::ScraperConfig;
+use crate::rate_limit::RateLimitConfig;
+
+/// Named bundle of crawl pacing settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolitenessProfile {
+    /// Short delay, quick recovery, browser user agent. For large CDNs.
+    Aggressive,
+    /// The global request delay and standard backoff.
+    #[default]
+    Default,
+    /// Long delay, one request at a time, slow recovery, honors robots.txt.
+    /// For small sites that fall over under load.
+    Gentle,
+}
+
+impl prefer::FromValue for PolitenessProfile {
+    fn from_value(value: &prefer::ConfigValue) -> prefer::Result<Self> {
+        match value.as_str() {
+            Some(s) => Self::from_str(s).ok_or_else(|| prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PolitenessProfile".to_string(),
+                source: format!("unknown politeness profile: {}", s).into(),
+            }),
+            None => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "PolitenessProfile".to_string(),
+                source: "expected string".into(),
+            }),
+        }
+    }
+}
+
+impl PolitenessProfile {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "aggressive" => Some(Self::Aggressive),
+            "default" => Some(Self::Default),
+            "gentle" => Some(Self::Gentle),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Aggressive => "aggressive",
+            Self::Default => "default",
+            Self::Gentle => "gentle",
+        }
+    }
+
+    /// Delay between requests, or `None` for the global `request_delay_ms`.
+    pub fn request_delay(&self) -> Option<Duration> {
+        match self {
+            Self::Aggressive => Some(Duration::from_millis(100)),
+            Self::Default => None,
+            Self::Gentle => Some(Duration::from_secs(5)),
+        }
+    }
+
+    /// Most requests in flight at once, or `None` for no cap.
+    pub fn concurrency(&self) -> Option<usize> {
+        match self {
+            Self::Gentle => Some(1),
+            Self::Aggressive | Self::Default => None,
+        }
+    }
+
+    /// User agent to send unless the source sets one.
+    pub fn user_agent(&self) -> Option<&'static str> {
+        match self {
+            Self::Aggressive => Some("impersonate"),
+            Self::Default | Self::Gentle => None,
+        }
+    }
+
+    /// Whether the profile honors robots.txt on its own.
+    pub fn respects_robots(&self) -> bool {
+        matches!(self, Self::Gentle)
+    }
+
+    /// Backoff and recovery settings around `base_delay`.
+    pub fn rate_limit(&self, base_delay: Duration) -> RateLimitConfig {
+        let default = RateLimitConfig {
+            base_delay,
+            ..RateLimitConfig::default()
+        };
+        match self {
+            Self::Aggressive => RateLimitConfig {
+                min_delay: Duration::from_millis(50),
+                max_delay: Duration::from_secs(30),
+                backoff_multiplier: 1.5,
+                recovery_multiplier: 0.5,
+                recovery_threshold: 3,
+                ..default
+            },
+            Self::Default => default,
+            // Never speeds up past the base delay, backs off hard
+            Self::Gentle => RateLimitConfig {
+                min_delay: base_delay,
+                max_delay: Duration::from_secs(10 * 60),
+                backoff_multiplier: 3.0,
+                recovery_multiplier: 0.9,
+                recovery_threshold: 20,
+                ..default
+            },
+        }
+    }
+}
+
+/// A source's politeness settings with profile and overrides applied.
+#[derive(Debug, Clone)]
+pub struct Politeness {
+    pub profile: PolitenessProfile,
+    pub request_delay: Duration,
+    pub concurrency: Option<usize>,
+    pub rate_limit: RateLimitConfig,
+    pub user_agent: Option<String>,
+    pub respect_robots: bool,
+}
+
+impl Politeness {
+    /// Resolve a source's settings. `global_delay` is the global
+    /// `request_delay_ms`; global profile and robots fallbacks should
+    /// already be applied to `source`.
+    pub fn resolve(source: &ScraperConfig, global_delay: Duration) -> Self {
+        let profile = source.politeness.unwrap_or_default();
+        let request_delay = source
+            .request_delay_ms
+            .map(Duration::from_millis)
+            .or(profile.request_delay())
+            .unwrap_or(global_delay);
+        Self {
+            profile,
+            request_delay,
+            concurrency: profile.concurrency(),
+            rate_limit: profile.rate_limit(request_delay),
+            user_agent: source
+                .user_agent
+                .clone()
+                .or_else(|| profile.user_agent().map(str::to_string)),
+            respect_robots: source
+                .respect_robots
+                .unwrap_or_else(|| profile.respects_robots()),
+        }
+    }
+
+    /// Workers to run for this source when `requested` were asked for.
+    pub fn workers(&self, requested: usize) -> usize {
+        self.concurrency
+            .map_or(requested, |cap| requested.min(cap))
+            .max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_profiles() {
+        let global = Duration::from_millis(500);
+
+        let plain = Politeness::resolve(&ScraperConfig::default(), global);
+        assert_eq!(plain.request_delay, global);
+        assert_eq!(plain.workers(8), 8);
+        assert_eq!(plain.user_agent, None);
+        assert!(!plain.respect_robots);
+
+        let gentle = ScraperConfig {
+            politeness: Some(PolitenessProfile::Gentle),
+            ..Default::default()
+        };
+        let gentle = Politeness::resolve(&gentle, global);
+        assert_eq!(gentle.request_delay, Duration::from_secs(5));
+        assert_eq!(gentle.rate_limit.min_delay, Duration::from_secs(5));
+        assert_eq!(gentle.workers(8), 1);
+        assert!(gentle.respect_robots);
+
+        let aggressive = ScraperConfig {
+            politeness: Some(PolitenessProfile::Aggressive),
+            ..Default::default()
+        };
+        let aggressive = Politeness::resolve(&aggressive, global);
+        assert_eq!(aggressive.request_delay, Duration::from_millis(100));
+        assert_eq!(aggressive.user_agent.as_deref(), Some("impersonate"));
+    }
+
+    #[test]
+    fn test_source_settings_override_profile() {
+        let source = ScraperConfig {
+            politeness: Some(PolitenessProfile::Gentle),
+            request_delay_ms: Some(2000),
+            user_agent: Some("foia-research (ops@example.org)".to_string()),
+            respect_robots: Some(false),
+            ..Default::default()
+        };
+        let politeness = Politeness::resolve(&source, Duration::from_millis(500));
+        assert_eq!(politeness.request_delay, Duration::from_secs(2));
+        assert_eq!(politeness.rate_limit.base_delay, Duration::from_secs(2));
+        assert_eq!(
+            politeness.user_agent.as_deref(),
+            Some("foia-research (ops@example.org)")
+        );
+        assert!(!politeness.respect_robots);
+        assert_eq!(politeness.workers(4), 1);
+    }
+}
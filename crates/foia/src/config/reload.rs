@@ -0,0 +1,291 @@
+//! Picking up config file edits in long-running processes.
+//!
+//! The server and daemons load the config file once at startup. A
+//! [`ConfigReloader`] polls that file, validates each edit, snapshots it to
+//! the configuration history and publishes it to the running process, which
+//! applies the settings it can change in place (request delay, LLM settings,
+//! request deadline rules). Other settings, such as the data directory or
+//! database, wait for the next daemon cycle or a restart; a reload names
+//! them in the log.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::sync::watch;
+
+use super::shards::validate_shards;
+use super::Config;
+use crate::repository::util::{is_postgres_url, validate_database_url};
+use crate::repository::DieselConfigHistoryRepository;
+
+/// How often the config file is checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Format recorded in the configuration history for snapshots.
+const SNAPSHOT_FORMAT: &str = "json";
+
+/// The latest valid config, updated as the config file changes.
+pub type LiveConfig = watch::Receiver<Arc<Config>>;
+
+/// What a config edit changed, split by whether the running process
+/// applies it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadPlan {
+    /// Top-level settings applied without a restart.
+    pub applied: Vec<String>,
+    /// Top-level settings left for the next daemon cycle or restart.
+    pub deferred: Vec<String>,
+}
+
+impl ReloadPlan {
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty() && self.deferred.is_empty()
+    }
+}
+
+/// Compare two configs setting by setting. `hot` names the top-level keys
+/// the caller applies in place.
+pub fn plan_reload(old: &Config, new: &Config, hot: &[&str]) -> ReloadPlan {
+    let as_object = |config: &Config| match serde_json::to_value(config) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let (old, new) = (as_object(old), as_object(new));
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    let mut plan = ReloadPlan::default();
+    for key in keys {
+        if old.get(key) == new.get(key) {
+            continue;
+        }
+        if hot.contains(&key.as_str()) {
+            plan.applied.push(key.clone());
+        } else {
+            plan.deferred.push(key.clone());
+        }
+    }
+    plan
+}
+
+/// Problems that make a config unsafe to apply to a running process.
+pub fn validate(config: &Config) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if let Some(database) = config.database.as_deref().filter(|d| d.contains("://")) {
+        if let Err(e) = validate_database_url(database) {
+            errors.push(format!("database: {}", e));
+        }
+    }
+    if config.request_timeout == Some(0) {
+        errors.push("request_timeout: must be at least 1 second".to_string());
+    }
+    for (from, to) in &config.via {
+        for url in [from, to] {
+            if url::Url::parse(url).is_err() {
+                errors.push(format!("via: '{}' is not a URL", url));
+            }
+        }
+    }
+    for (id, scraper) in &config.scrapers {
+        if let Some(base_url) = &scraper.base_url {
+            if url::Url::parse(base_url).is_err() {
+                errors.push(format!(
+                    "scrapers.{}.base_url: '{}' is not a URL",
+                    id, base_url
+                ));
+            }
+        }
+        if let Some(Err(e)) = scraper.scope.as_ref().map(|s| s.exclude_patterns()) {
+            errors.push(format!("scrapers.{}.scope: {}", id, e));
+        }
+    }
+    if !(0.0..=2.0).contains(&config.llm.app.temperature) {
+        errors.push("llm.temperature: must be between 0 and 2".to_string());
+    }
+    if config.llm.app.max_tokens == 0 {
+        errors.push("llm.max_tokens: must be at least 1".to_string());
+    }
+    if let Err(e) = config.throttle.bandwidth_limiter() {
+        errors.push(format!("throttle: {}", e));
+    }
+    if let Err(e) = config.throttle.schedule() {
+        errors.push(format!("throttle: {}", e));
+    }
+    errors.extend(validate_shards(&config.shards));
+    if !config.shards.is_empty() && config.database.as_deref().is_some_and(is_postgres_url) {
+        errors.push("shards: only SQLite databases can be sharded".to_string());
+    }
+    let base_dir = config.resolve_base_dir(false);
+    let known_files = &config.download.known_files;
+    for (key, path) in known_files
+        .skip
+        .iter()
+        .map(|p| ("skip", p))
+        .chain(known_files.keep.iter().map(|p| ("keep", p)))
+    {
+        if !config.resolve_path(path, &base_dir).is_file() {
+            errors.push(format!(
+                "download.known_files.{}: '{}' does not exist",
+                key, path
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// How `config` is stored in the configuration history, with paths under
+/// the config file's directory made relative.
+pub fn snapshot_data(config: &Config) -> String {
+    match config.base_dir() {
+        Some(base_dir) => config.to_json_relative(&base_dir),
+        None => serde_json::to_string(config).unwrap_or_default(),
+    }
+}
+
+/// Record `config` in the configuration history unless it is already there.
+pub async fn snapshot(history: &DieselConfigHistoryRepository, config: &Config) {
+    if let Err(e) = history
+        .insert_if_new(&snapshot_data(config), SNAPSHOT_FORMAT, &config.hash())
+        .await
+    {
+        tracing::warn!("Failed to record config history: {}", e);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Watches the file a config was loaded from and publishes valid edits.
+pub struct ConfigReloader {
+    path: PathBuf,
+    history: DieselConfigHistoryRepository,
+    hot: &'static [&'static str],
+    current: Arc<Config>,
+    modified: Option<SystemTime>,
+    tx: watch::Sender<Arc<Config>>,
+}
+
+impl ConfigReloader {
+    /// Snapshot `config` and start watching its file. `hot` names the
+    /// top-level settings the caller applies in place, for the reload log.
+    ///
+    /// A config that wasn't loaded from a file never changes; its receiver
+    /// always holds `config`. The watch stops on shutdown or once every
+    /// receiver is dropped.
+    pub async fn spawn(
+        config: Config,
+        history: DieselConfigHistoryRepository,
+        hot: &'static [&'static str],
+    ) -> LiveConfig {
+        snapshot(&history, &config).await;
+        let path = config.source_path.clone();
+        let current = Arc::new(config);
+        let (tx, rx) = watch::channel(current.clone());
+        let Some(path) = path else {
+            return rx;
+        };
+
+        let mut reloader = Self {
+            modified: modified(&path),
+            path,
+            history,
+            hot,
+            current,
+            tx,
+        };
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                    _ = crate::shutdown::requested() => break,
+                    _ = reloader.tx.closed() => break,
+                }
+                reloader.check().await;
+            }
+        });
+        rx
+    }
+
+    /// Reload the file if it changed since the last check.
+    async fn check(&mut self) {
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return;
+        }
+        self.modified = modified;
+
+        let config = match Config::load_from_path(&self.path).await {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Config change not applied: {}", e);
+                return;
+            }
+        };
+        if let Err(errors) = validate(&config) {
+            tracing::warn!(
+                "Config change in {} not applied: {}",
+                self.path.display(),
+                errors.join("; ")
+            );
+            return;
+        }
+
+        let plan = plan_reload(&self.current, &config, self.hot);
+        if plan.is_empty() {
+            return;
+        }
+        snapshot(&self.history, &config).await;
+        if !plan.applied.is_empty() {
+            tracing::info!("Config reloaded: {}", plan.applied.join(", "));
+        }
+        if !plan.deferred.is_empty() {
+            tracing::warn!(
+                "Config changes to {} apply on the next run or restart",
+                plan.deferred.join(", ")
+            );
+        }
+        self.current = Arc::new(config);
+        self.tx.send_replace(self.current.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_reload() {
+        let old = Config::default();
+        let mut new = old.clone();
+        new.request_delay_ms = Some(2000);
+        new.data_dir = Some("/srv/foia".to_string());
+        new.llm.app.max_tokens = 100;
+
+        let plan = plan_reload(&old, &new, &["request_delay_ms", "llm"]);
+        assert_eq!(plan.applied, vec!["llm", "request_delay_ms"]);
+        assert_eq!(plan.deferred, vec!["data_dir"]);
+        assert!(plan_reload(&old, &old, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&Config::default()).is_ok());
+
+        let mut config = Config::default();
+        config.request_timeout = Some(0);
+        config
+            .via
+            .insert("https://agency.gov".to_string(), "not a url".to_string());
+        config.llm.app.temperature = 5.0;
+        let errors = validate(&config).unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[1].contains("not a url"));
+    }
+}
@@ -0,0 +1,196 @@
+//! FOIA request deadline and reminder configuration.
+//!
+//! Federal deadlines are built in. State public records laws set their own
+//! response times and count them in business or calendar days, so each
+//! jurisdiction a request can be filed under gets its own rules.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{DeadlineRule, RESPONSE_WORKING_DAYS};
+
+/// Built-in jurisdiction using the federal FOIA rule.
+pub const FEDERAL_JURISDICTION: &str = "federal";
+
+/// Default days between repeated reminders for a request still overdue.
+pub const DEFAULT_REMIND_EVERY_DAYS: u32 = 7;
+
+/// Deadline rules and reminders for tracked FOIA requests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct RequestsConfig {
+    /// Jurisdiction for requests that don't name one (default `"federal"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub default_jurisdiction: Option<String>,
+
+    /// Deadline rules by jurisdiction name. A `"federal"` entry overrides
+    /// the built-in federal rule.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[prefer(default)]
+    pub jurisdictions: HashMap<String, JurisdictionConfig>,
+
+    /// Where to send reminders about overdue requests.
+    #[serde(default, skip_serializing_if = "RemindersConfig::is_default")]
+    #[prefer(default)]
+    pub reminders: RemindersConfig,
+}
+
+/// Response deadline rules for one jurisdiction. Unset fields take the
+/// federal values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct JurisdictionConfig {
+    /// Days to answer a request (default 20).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub response_days: Option<u32>,
+
+    /// Days to decide an appeal (default: `response_days`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub appeal_days: Option<u32>,
+
+    /// Count business days rather than calendar days (default true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub business_days: Option<bool>,
+
+    /// Agencies close on US federal holidays (default true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub federal_holidays: Option<bool>,
+
+    /// Other closure days, as `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub holidays: Vec<String>,
+}
+
+/// Overdue request notifications. Nothing is sent unless a webhook or an
+/// email address is set.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct RemindersConfig {
+    /// URL to POST a JSON list of overdue requests to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub webhook: Option<String>,
+
+    /// Address to email reminders to, sent as configured under `email`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub email: Option<String>,
+
+    /// Days before reminding again about a request still overdue
+    /// (default 7).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub remind_every_days: Option<u32>,
+}
+
+impl RequestsConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Jurisdiction for requests that don't name one.
+    pub fn default_jurisdiction(&self) -> &str {
+        self.default_jurisdiction
+            .as_deref()
+            .unwrap_or(FEDERAL_JURISDICTION)
+    }
+
+    /// Whether `name` is a jurisdiction requests can be filed under.
+    pub fn has_jurisdiction(&self, name: &str) -> bool {
+        name == FEDERAL_JURISDICTION || self.jurisdictions.contains_key(name)
+    }
+
+    /// Deadline rule for a request's jurisdiction. Unknown names get the
+    /// federal rule.
+    pub fn rule(&self, jurisdiction: Option<&str>) -> DeadlineRule {
+        let name = jurisdiction.unwrap_or_else(|| self.default_jurisdiction());
+        match self.jurisdictions.get(name) {
+            Some(config) => config.rule(),
+            None => DeadlineRule::federal(),
+        }
+    }
+}
+
+impl JurisdictionConfig {
+    /// The rule this configures, with federal values for unset fields.
+    /// Unparseable holiday dates are skipped.
+    pub fn rule(&self) -> DeadlineRule {
+        let response_days = self.response_days.unwrap_or(RESPONSE_WORKING_DAYS);
+        DeadlineRule {
+            response_days,
+            appeal_days: self.appeal_days.unwrap_or(response_days),
+            business_days: self.business_days.unwrap_or(true),
+            federal_holidays: self.federal_holidays.unwrap_or(true),
+            holidays: self
+                .holidays
+                .iter()
+                .filter_map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .collect(),
+        }
+    }
+}
+
+impl RemindersConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether any reminder channel is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.webhook.is_some() || self.email.is_some()
+    }
+
+    /// Days before reminding again.
+    pub fn remind_every_days(&self) -> u32 {
+        self.remind_every_days
+            .unwrap_or(DEFAULT_REMIND_EVERY_DAYS)
+            .max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_for_jurisdiction() {
+        let config: RequestsConfig = serde_json::from_str(
+            r#"{
+                "jurisdictions": {
+                    "california": {"response_days": 10, "business_days": false},
+                    "texas": {"response_days": 10, "holidays": ["2024-03-02", "bad"]}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rule(None), DeadlineRule::federal());
+        assert_eq!(config.rule(Some("nowhere")), DeadlineRule::federal());
+
+        let california = config.rule(Some("california"));
+        assert_eq!(california.response_days, 10);
+        assert_eq!(california.appeal_days, 10);
+        assert!(!california.business_days);
+
+        let texas = config.rule(Some("texas"));
+        assert!(texas.business_days);
+        assert_eq!(texas.holidays, vec!["2024-03-02".parse().unwrap()]);
+
+        assert!(config.has_jurisdiction("federal"));
+        assert!(config.has_jurisdiction("texas"));
+        assert!(!config.has_jurisdiction("nowhere"));
+
+        let config = RequestsConfig {
+            default_jurisdiction: Some("california".to_string()),
+            ..config
+        };
+        assert_eq!(config.rule(None), california);
+    }
+}
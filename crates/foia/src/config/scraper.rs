@@ -12,7 +12,12 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use super::browser::BrowserEngineConfig;
+use super::checksum::ChecksumConfig;
 use super::discovery::ExternalDiscoveryConfig;
+use super::politeness::PolitenessProfile;
+use super::session::SessionConfig;
+use super::throttle::ThrottleConfig;
+use super::wayback::WaybackConfig;
 use crate::privacy::SourcePrivacyConfig;
 
 /// Via proxy mode - controls how URL rewriting through caching proxies works.
@@ -75,6 +80,10 @@ pub struct ScraperConfig {
     /// Base URL for the scraper (optional, can be derived from discovery).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub base_url: Option<String>,
+    /// Registry ID of the agency this source collects from (`foia agencies
+    /// list`). Its documents are tagged `agency:<id>`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agency: Option<String>,
     /// User agent configuration.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub user_agent: Option<String>,
@@ -101,6 +110,12 @@ pub struct ScraperConfig {
     /// Per-source request delay in milliseconds (overrides global setting).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub request_delay_ms: Option<u64>,
+    /// Politeness profile: `aggressive`, `default` or `gentle` (overrides global setting).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub politeness: Option<PolitenessProfile>,
+    /// Never fetch URLs robots.txt disallows (overrides global setting and profile).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub respect_robots: Option<bool>,
     /// Per-source URL rewriting for caching proxies (overrides global setting).
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     #[prefer(default)]
@@ -108,6 +123,21 @@ pub struct ScraperConfig {
     /// Per-source via proxy mode (overrides global setting).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub via_mode: Option<ViaMode>,
+    /// Login session for portals that require an account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionConfig>,
+    /// Per-source bandwidth cap and schedule (overrides global setting).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleConfig>,
+    /// Published checksum manifests or sidecars to verify downloads against.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksums: Option<ChecksumConfig>,
+    /// Wayback Machine fallback for dead URLs and Save Page Now submission.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wayback: Option<WaybackConfig>,
+    /// Limits on which URLs the crawl may follow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<ScopeConfig>,
 }
 
 impl ScraperConfig {
@@ -142,6 +172,26 @@ pub struct DiscoveryConfig {
     #[serde(default)]
     #[prefer(default)]
     pub api: Option<ApiConfig>,
+    /// GovQA portal settings (for `type: "govqa"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub govqa: Option<GovQaConfig>,
+    /// NextRequest portal settings (for `type: "nextrequest"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub nextrequest: Option<NextRequestConfig>,
+    /// Reading room overrides (for `type: "fbi_vault"` and `"doj_foia_library"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub reading_room: Option<ReadingRoomConfig>,
+    /// Aggregator site settings (for `type: "aggregator"` and `"governmentattic"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub aggregator: Option<AggregatorConfig>,
+    /// CourtListener settings (for `type: "courtlistener"`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub courtlistener: Option<CourtListenerConfig>,
     /// Maximum recursion depth for BFS crawling (default: 10)
     #[serde(default)]
     #[prefer(default)]
@@ -171,6 +221,11 @@ pub struct DiscoveryConfig {
     #[serde(default)]
     #[prefer(default)]
     pub expand_search_terms: bool,
+    /// Also save the listing/index pages crawled through as documents, so
+    /// each change to a reading room's structure is kept as a new version
+    #[serde(default)]
+    #[prefer(default)]
+    pub archive_listings: bool,
 
     /// External discovery configuration (search engines, sitemaps, Wayback, etc.)
     #[serde(default, skip_serializing_if = "ExternalDiscoveryConfig::is_default")]
@@ -196,6 +251,47 @@ fn default_discovery_type() -> String {
     "html_crawl".to_string()
 }
 
+/// Limits on where a crawl may go, checked before URLs are queued.
+///
+/// Host, depth and exclude rules apply to every URL; path prefixes and the
+/// page limit apply to the pages crawled through, so documents they link
+/// to elsewhere on the site are still fetched.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ScopeConfig {
+    /// Hosts URLs may be on; each also allows its subdomains (default: any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Path prefixes pages must start with, e.g. `/foia/` (default: any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub path_prefixes: Vec<String>,
+    /// Links followed from a seed before URLs are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub max_depth: Option<u32>,
+    /// Pages crawled before no more are queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub max_pages: Option<u64>,
+    /// Regexes; URLs matching any of them are dropped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub exclude: Vec<String>,
+}
+
+impl ScopeConfig {
+    /// Compiled `exclude` patterns.
+    pub fn exclude_patterns(&self) -> Result<Vec<regex::Regex>, String> {
+        self.exclude
+            .iter()
+            .map(|p| {
+                regex::Regex::new(p).map_err(|e| format!("invalid exclude pattern '{}': {}", p, e))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
 pub struct LevelConfig {
     #[serde(default)]
@@ -228,6 +324,115 @@ pub struct PaginationConfig {
     pub page_size: Option<u32>,
 }
 
+/// A GovQA public records portal. Each city or county runs its own
+/// install, so page names and link formats can be overridden per portal.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct GovQaConfig {
+    /// Portal root, e.g. `https://cityofx.govqa.us/WEBAPP/_rs/` (default: `base_url`)
+    #[serde(default)]
+    #[prefer(default)]
+    pub portal_url: Option<String>,
+    /// Pages listing released requests, relative to the portal (default: `RequestArchive.aspx`)
+    #[serde(default)]
+    #[prefer(default)]
+    pub archive_paths: Vec<String>,
+    /// Regex matching links from an archive page to a request's details
+    #[serde(default)]
+    #[prefer(default)]
+    pub detail_pattern: Option<String>,
+    /// Regex matching attachment download links on a request's details
+    #[serde(default)]
+    #[prefer(default)]
+    pub attachment_pattern: Option<String>,
+    /// Stop after this many pages of each archive
+    #[serde(default)]
+    #[prefer(default)]
+    pub max_pages: Option<u32>,
+}
+
+/// A NextRequest public records portal, one instance per agency.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct NextRequestConfig {
+    /// Instance URL, e.g. `https://cityofx.nextrequest.com` (default: `base_url`)
+    #[serde(default)]
+    #[prefer(default)]
+    pub instance_url: Option<String>,
+    /// Stop after this many pages of the request list
+    #[serde(default)]
+    #[prefer(default)]
+    pub max_pages: Option<u32>,
+}
+
+/// Overrides for a reading room preset. Unset fields keep the preset's value;
+/// `base_url`, `start_paths` and `max_depth` come from the discovery config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ReadingRoomConfig {
+    /// CSS selector for the part of a page holding the listing
+    #[serde(default)]
+    #[prefer(default)]
+    pub content_selector: Option<String>,
+    /// Regex matching links to subject folders
+    #[serde(default)]
+    #[prefer(default)]
+    pub folder_pattern: Option<String>,
+    /// Regex matching links to documents
+    #[serde(default)]
+    #[prefer(default)]
+    pub document_pattern: Option<String>,
+    /// CSS selectors for a listing's "next page" link
+    #[serde(default)]
+    #[prefer(default)]
+    pub next_selectors: Vec<String>,
+    /// Tag given to every document, e.g. `agency:fbi`
+    #[serde(default)]
+    #[prefer(default)]
+    pub agency_tag: Option<String>,
+}
+
+/// A site republishing FOIA responses obtained by others. Unset fields
+/// keep the preset's value; `base_url`, `start_paths` and `max_depth` come
+/// from the discovery config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct AggregatorConfig {
+    /// Regex matching listing pages to follow
+    #[serde(default)]
+    #[prefer(default)]
+    pub page_pattern: Option<String>,
+    /// Regex matching links to documents
+    #[serde(default)]
+    #[prefer(default)]
+    pub document_pattern: Option<String>,
+    /// CSS selector for the element describing one document
+    #[serde(default)]
+    #[prefer(default)]
+    pub entry_selector: Option<String>,
+    /// Requester to record when a listing doesn't name one
+    #[serde(default)]
+    #[prefer(default)]
+    pub requester: Option<String>,
+}
+
+/// Court records from CourtListener's RECAP archive of PACER filings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct CourtListenerConfig {
+    /// Dockets to pull, as CourtListener docket IDs or docket page URLs
+    #[serde(default)]
+    #[prefer(default)]
+    pub dockets: Vec<String>,
+    /// Search queries; every docket they match is pulled
+    #[serde(default)]
+    #[prefer(default)]
+    pub queries: Vec<String>,
+    /// Limit searches to one court, by CourtListener court ID (e.g. `dcd`)
+    #[serde(default)]
+    #[prefer(default)]
+    pub court: Option<String>,
+    /// Stop after this many pages of each search or docket
+    #[serde(default)]
+    #[prefer(default)]
+    pub max_pages: Option<u32>,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
 pub struct ApiConfig {
     #[serde(default)]
@@ -367,6 +572,11 @@ pub struct FetchConfig {
     #[serde(default)]
     #[prefer(default)]
     pub title_selectors: Vec<String>,
+    /// Keep the server's Content-Type even when the content's magic bytes
+    /// say otherwise (by default the detected type wins).
+    #[serde(default)]
+    #[prefer(default)]
+    pub trust_content_type: bool,
 }
 
 impl FetchConfig {
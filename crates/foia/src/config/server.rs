@@ -0,0 +1,178 @@
+//! Web server configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Default requests per minute per client in public mode.
+pub const DEFAULT_PUBLIC_RATE_LIMIT: u32 = 120;
+
+/// Default requests per minute per API key.
+pub const DEFAULT_API_RATE_LIMIT: u32 = 60;
+
+/// Default API requests per minute per client address without a key.
+pub const DEFAULT_API_UNKEYED_RATE_LIMIT: u32 = 120;
+
+/// Default days of document access counts kept.
+pub const DEFAULT_ACCESS_LOG_DAYS: u32 = 90;
+
+/// What the server records about document views and downloads. Nothing
+/// identifying a reader is ever stored: no addresses, user agents or
+/// cookies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogMode {
+    /// Record nothing.
+    Off,
+    /// Views and downloads per document and day.
+    Counts,
+    /// Counts, plus the site each reader came from (host name only).
+    #[default]
+    Referrers,
+}
+
+impl prefer::FromValue for AccessLogMode {
+    fn from_value(value: &prefer::ConfigValue) -> prefer::Result<Self> {
+        match value.as_str() {
+            Some(s) => Self::from_str(s).ok_or_else(|| prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "AccessLogMode".to_string(),
+                source: format!("unknown access log mode: {}", s).into(),
+            }),
+            None => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "AccessLogMode".to_string(),
+                source: "expected string".into(),
+            }),
+        }
+    }
+}
+
+impl AccessLogMode {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(Self::Off),
+            "counts" => Some(Self::Counts),
+            "referrers" => Some(Self::Referrers),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Counts => "counts",
+            Self::Referrers => "referrers",
+        }
+    }
+}
+
+/// Settings for `foia serve`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ServerConfig {
+    /// Serve a read-only archive: no mutating endpoints, no crawl or
+    /// internal pages, credentials stripped from URLs. Same as `--public`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub public: Option<bool>,
+
+    /// Requests per minute allowed from one client address in public mode
+    /// (default 120).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub public_rate_limit: Option<u32>,
+
+    /// Refuse JSON API requests that change state unless they carry an API
+    /// key. Always on when the server is reachable from other machines;
+    /// otherwise defaults to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub require_api_keys: Option<bool>,
+
+    /// Requests per minute allowed with one API key that has no limit of
+    /// its own (default 60).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub api_rate_limit: Option<u32>,
+
+    /// API requests per minute allowed from one client address without a
+    /// key (default 120).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub api_unkeyed_rate_limit: Option<u32>,
+
+    /// Document views and downloads to record for the analytics page:
+    /// `off`, `counts` or `referrers` (default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub access_log: Option<AccessLogMode>,
+
+    /// Days of access counts to keep (default 90).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub access_log_days: Option<u32>,
+
+    /// In public mode, stop serving documents with an open flag until a
+    /// curator resolves it (default false).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub withhold_flagged: Option<bool>,
+}
+
+impl ServerConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether to serve read-only.
+    pub fn is_public(&self) -> bool {
+        self.public.unwrap_or(false)
+    }
+
+    /// Requests per minute per client in public mode.
+    pub fn public_rate_limit(&self) -> u32 {
+        self.public_rate_limit
+            .unwrap_or(DEFAULT_PUBLIC_RATE_LIMIT)
+            .max(1)
+    }
+
+    /// Whether state-changing API requests need a key.
+    pub fn requires_api_keys(&self) -> bool {
+        self.require_api_keys.unwrap_or(false)
+    }
+
+    /// Requests per minute for API keys without their own limit.
+    pub fn api_rate_limit(&self) -> u32 {
+        self.api_rate_limit.unwrap_or(DEFAULT_API_RATE_LIMIT).max(1)
+    }
+
+    /// API requests per minute per client address without a key.
+    pub fn api_unkeyed_rate_limit(&self) -> u32 {
+        self.api_unkeyed_rate_limit
+            .unwrap_or(DEFAULT_API_UNKEYED_RATE_LIMIT)
+            .max(1)
+    }
+
+    /// This configuration for a server reachable from other machines,
+    /// where state-changing API requests always need a key.
+    pub fn exposed(mut self) -> Self {
+        self.require_api_keys = Some(true);
+        self
+    }
+
+    /// What to record about document views and downloads.
+    pub fn access_log(&self) -> AccessLogMode {
+        self.access_log.unwrap_or_default()
+    }
+
+    /// Days of access counts to keep.
+    pub fn access_log_days(&self) -> u32 {
+        self.access_log_days
+            .unwrap_or(DEFAULT_ACCESS_LOG_DAYS)
+            .max(1)
+    }
+
+    /// Whether flagged documents are withheld from the public archive.
+    pub fn withholds_flagged(&self) -> bool {
+        self.is_public() && self.withhold_flagged.unwrap_or(false)
+    }
+}
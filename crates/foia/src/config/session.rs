@@ -0,0 +1,223 @@
+//! Login session configuration for authenticated portals.
+//!
+//! Some records portals only serve documents to (free) registered accounts.
+//! A source's `session` block keeps a persistent cookie jar and describes how
+//! to log in, so requests carry the session and re-login happens when it
+//! expires. Credential values may reference environment variables as
+//! `${VAR}` so secrets stay out of the config file.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// How a source logs in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginType {
+    /// POST the fields as a form; the portal answers with session cookies.
+    #[default]
+    Form,
+    /// Send a token header, either fixed or obtained from a JSON login endpoint.
+    Token,
+}
+
+impl prefer::FromValue for LoginType {
+    fn from_value(value: &prefer::ConfigValue) -> prefer::Result<Self> {
+        match value.as_str() {
+            Some("form") => Ok(LoginType::Form),
+            Some("token") => Ok(LoginType::Token),
+            Some(other) => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "LoginType".to_string(),
+                source: format!("unknown login type: {}", other).into(),
+            }),
+            None => Err(prefer::Error::ConversionError {
+                key: String::new(),
+                type_name: "LoginType".to_string(),
+                source: "expected string".into(),
+            }),
+        }
+    }
+}
+
+/// Login step for a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct LoginConfig {
+    /// Form POST or token header.
+    #[serde(rename = "type", default)]
+    #[prefer(default, rename = "type")]
+    pub login_type: LoginType,
+
+    /// Login endpoint. Optional for token logins with a fixed `token`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub url: Option<String>,
+
+    /// Fields sent to the login endpoint (form fields, or the JSON body for
+    /// token logins).
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[prefer(default)]
+    pub fields: HashMap<String, String>,
+
+    /// Fixed token for token logins (e.g. an API key).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub token: Option<String>,
+
+    /// JSON pointer to the token in the login response (default: `/token`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub token_pointer: Option<String>,
+
+    /// Header carrying the token (default: `Authorization`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub header: Option<String>,
+
+    /// Prefix put before the token (default: `Bearer `).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub prefix: Option<String>,
+}
+
+impl LoginConfig {
+    /// Header name for token logins.
+    pub fn header_name(&self) -> &str {
+        self.header.as_deref().unwrap_or("Authorization")
+    }
+
+    /// Header value prefix for token logins.
+    pub fn token_prefix(&self) -> &str {
+        self.prefix.as_deref().unwrap_or("Bearer ")
+    }
+
+    /// JSON pointer to the token in the login response.
+    pub fn token_pointer(&self) -> &str {
+        self.token_pointer.as_deref().unwrap_or("/token")
+    }
+}
+
+/// Per-source session handling.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct SessionConfig {
+    /// Where cookies are saved between runs.
+    /// Default: `<data_dir>/sessions/<source_id>.json`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub cookie_file: Option<PathBuf>,
+
+    /// How to log in. Without it the session only keeps cookies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub login: Option<LoginConfig>,
+
+    /// Response statuses meaning the session expired (default: `[401]`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub expired_statuses: Vec<u16>,
+
+    /// Substring of the final URL meaning we were bounced to the login page
+    /// (e.g. `/login`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub expired_url_pattern: Option<String>,
+}
+
+impl SessionConfig {
+    /// Fill in the default cookie file location if none is set.
+    pub fn with_default_cookie_file(mut self, data_dir: &Path, source_id: &str) -> Self {
+        if self.cookie_file.is_none() {
+            self.cookie_file = Some(
+                data_dir
+                    .join("sessions")
+                    .join(format!("{}.json", source_id)),
+            );
+        }
+        self
+    }
+
+    /// Whether a response status means the session expired.
+    pub fn is_expired_status(&self, status: u16) -> bool {
+        if self.expired_statuses.is_empty() {
+            status == 401
+        } else {
+            self.expired_statuses.contains(&status)
+        }
+    }
+
+    /// Whether a response's final URL is the login page.
+    pub fn is_login_redirect(&self, final_url: &str) -> bool {
+        self.expired_url_pattern
+            .as_deref()
+            .is_some_and(|p| !p.is_empty() && final_url.contains(p))
+    }
+}
+
+/// Expand `${VAR}` references from the environment.
+///
+/// Fails on unset variables rather than logging in with an empty password.
+pub fn expand_env_vars(value: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unterminated ${{ in '{}'", value))?;
+        let name = &after[..end];
+        let var =
+            std::env::var(name).map_err(|_| format!("Environment variable {} is not set", name))?;
+        out.push_str(&var);
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_session_config() {
+        let json = r#"{
+            "login": {
+                "type": "form",
+                "url": "https://portal.example.gov/login",
+                "fields": {"username": "${PORTAL_USER}", "password": "${PORTAL_PASS}"}
+            },
+            "expired_url_pattern": "/login"
+        }"#;
+        let config: SessionConfig = serde_json::from_str(json).unwrap();
+        let login = config.login.as_ref().unwrap();
+        assert_eq!(login.login_type, LoginType::Form);
+        assert_eq!(login.fields.len(), 2);
+        assert!(config.is_expired_status(401));
+        assert!(!config.is_expired_status(403));
+        assert!(config.is_login_redirect("https://portal.example.gov/login?next=/doc"));
+    }
+
+    #[test]
+    fn test_default_cookie_file() {
+        let config =
+            SessionConfig::default().with_default_cookie_file(Path::new("/data"), "muckrock");
+        assert_eq!(
+            config.cookie_file,
+            Some(PathBuf::from("/data/sessions/muckrock.json"))
+        );
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        std::env::set_var("FOIA_TEST_SESSION_USER", "alice");
+        assert_eq!(
+            expand_env_vars("user=${FOIA_TEST_SESSION_USER}!").unwrap(),
+            "user=alice!"
+        );
+        assert_eq!(expand_env_vars("plain").unwrap(), "plain");
+        assert!(expand_env_vars("${FOIA_TEST_SESSION_UNSET_VAR}").is_err());
+        assert!(expand_env_vars("${OOPS").is_err());
+    }
+}
@@ -5,11 +5,14 @@ use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::models::StatusWorkflow;
 use crate::repository::diesel_context::DieselDbContext;
+use crate::repository::diesel_document::DEFAULT_CLAIM_LEASE_MINUTES;
+use crate::repository::shards::{self, Shard};
 use crate::repository::util::is_postgres_url;
 use crate::repository::Repositories;
 
-use super::DEFAULT_DATABASE_FILENAME;
+use super::{ShardConfig, DEFAULT_DATABASE_FILENAME};
 
 /// Default documents subdirectory name.
 const DOCUMENTS_SUBDIR: &str = "documents";
@@ -37,6 +40,12 @@ pub struct Settings {
     pub rate_limit_backend: Option<String>,
     /// Worker queue broker URL (None = local DB, "amqp://..." = RabbitMQ).
     pub broker_url: Option<String>,
+    /// Minutes an analysis claim holds a document without renewal.
+    pub claim_lease_minutes: u32,
+    /// Allowed document status transitions.
+    pub workflow: StatusWorkflow,
+    /// Shards splitting a SQLite archive by source.
+    pub shards: Vec<ShardConfig>,
     /// Disable TLS for PostgreSQL connections.
     pub no_tls: bool,
 }
@@ -60,6 +69,9 @@ impl Default for Settings {
             request_delay_ms: 500,
             rate_limit_backend: None, // In-memory by default
             broker_url: None,         // Local DB by default
+            claim_lease_minutes: DEFAULT_CLAIM_LEASE_MINUTES,
+            workflow: StatusWorkflow::default(),
+            shards: Vec::new(),
             no_tls: false,
         }
     }
@@ -199,8 +211,57 @@ impl Settings {
     ///
     /// This is the preferred way to get a DieselDbContext from settings.
     /// Returns an error if the database URL is invalid.
+    ///
+    /// In a sharded archive reads cover every shard, and document writes go
+    /// to the shard holding the document; other writes, crawl state
+    /// included, go to the main database. Use
+    /// [`Self::repositories_for_source`] to write a source's crawl state.
     pub fn create_db_context(&self) -> Result<DieselDbContext, diesel::result::Error> {
-        DieselDbContext::from_url(&self.database_url(), self.no_tls)
+        let ctx = DieselDbContext::from_url(&self.database_url(), self.no_tls)?;
+        if !self.is_sharded() {
+            return Ok(ctx);
+        }
+        let main = shards::sqlite_path(&self.database_url());
+        let shard_databases = self.shard_databases();
+        let sql = shards::unified_read_sql(&main, &shard_databases)
+            .map_err(|e| diesel::result::Error::QueryBuilderError(e.to_string().into()))?;
+        let mut ctx = DieselDbContext::with_pool(ctx.pool().clone().with_read_init(sql));
+        for shard in shard_databases {
+            if shard.path.exists() {
+                let pool = DieselDbContext::from_sqlite_path(&shard.path)?.pool().clone();
+                ctx = ctx.with_shard(shard.sources, pool);
+            }
+        }
+        Ok(ctx)
+    }
+
+    /// Whether the archive is split into shards. Only SQLite archives are.
+    pub fn is_sharded(&self) -> bool {
+        !self.shards.is_empty() && !self.is_postgres()
+    }
+
+    /// Configured shards with their database files.
+    pub fn shard_databases(&self) -> Vec<Shard> {
+        if !self.is_sharded() {
+            return Vec::new();
+        }
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(i, shard)| Shard {
+                index: i + 1,
+                name: shard.name.clone(),
+                sources: shard.sources.clone(),
+                path: shard.path(&self.data_dir),
+            })
+            .collect()
+    }
+
+    /// The shard holding `source_id`, if it is in one.
+    pub fn shard_for(&self, source_id: &str) -> Option<Shard> {
+        self.shard_databases()
+            .into_iter()
+            .find(|shard| shard.has_source(source_id))
     }
 
     /// Create bundled repositories for all database operations.
@@ -209,7 +270,46 @@ impl Settings {
     /// field access to all repository types without intermediate context.
     pub fn repositories(&self) -> Result<Repositories, diesel::result::Error> {
         let ctx = self.create_db_context()?;
-        Ok(Repositories::new(ctx))
+        Ok(self.configure(Repositories::new(ctx)))
+    }
+
+    /// Repositories for work on one source: documents and crawl state in
+    /// the database holding `source_id`, everything else in the main
+    /// database. The same as [`Self::repositories`] when there are no
+    /// shards.
+    pub fn repositories_for_source(
+        &self,
+        source_id: &str,
+    ) -> Result<Repositories, diesel::result::Error> {
+        if !self.is_sharded() {
+            return self.repositories();
+        }
+        let main = DieselDbContext::from_url(&self.database_url(), self.no_tls)?;
+        let ctx = match self.shard_for(source_id) {
+            Some(shard) if !shard.path.exists() => {
+                return Err(diesel::result::Error::QueryBuilderError(
+                    format!(
+                        "Shard '{}' has no database yet. Run 'foia db migrate' to create it.",
+                        shard.name
+                    )
+                    .into(),
+                ));
+            }
+            Some(shard) => DieselDbContext::from_sqlite_path(&shard.path)?,
+            None => main.clone(),
+        };
+        let mut repos = Repositories::new(main);
+        repos.crawl = ctx.crawl();
+        repos.documents = ctx.documents();
+        Ok(self.configure(repos))
+    }
+
+    fn configure(&self, mut repos: Repositories) -> Repositories {
+        repos.documents = repos
+            .documents
+            .with_claim_lease(self.claim_lease_minutes)
+            .with_workflow(self.workflow.clone());
+        repos
     }
 
     /// Create a database context and verify the connection works.
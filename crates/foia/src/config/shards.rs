@@ -0,0 +1,124 @@
+//! Database shard configuration.
+//!
+//! One SQLite file gets slow to back up, vacuum and query somewhere past
+//! tens of millions of pages. Shards split a SQLite archive by source: each
+//! shard is its own database file holding the documents, pages, analysis
+//! results and crawl state of the sources it lists, while sources,
+//! agencies, jobs, saved views and the other shared tables stay in the main
+//! database. Sources not in any shard stay in the main database too.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Most shards an archive can have. SQLite attaches at most ten databases
+/// to a connection, and the web server reads every shard through one.
+pub const MAX_SHARDS: usize = 10;
+
+/// One shard: a database file and the sources stored in it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ShardConfig {
+    /// Shard name (letters, digits, `-` and `_`).
+    pub name: String,
+    /// Sources whose documents and crawl state live in this shard.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub sources: Vec<String>,
+    /// Database file, relative to the data directory unless absolute
+    /// (default `shards/<name>.db`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub database: Option<String>,
+}
+
+impl ShardConfig {
+    /// Path of this shard's database file for an archive in `data_dir`.
+    pub fn path(&self, data_dir: &Path) -> PathBuf {
+        match &self.database {
+            Some(database) => {
+                let expanded = shellexpand::tilde(database);
+                data_dir.join(expanded.as_ref())
+            }
+            None => data_dir.join("shards").join(format!("{}.db", self.name)),
+        }
+    }
+}
+
+/// Problems with a shard list, for config validation.
+pub fn validate_shards(shards: &[ShardConfig]) -> Vec<String> {
+    let mut errors = Vec::new();
+    if shards.len() > MAX_SHARDS {
+        errors.push(format!(
+            "shards: at most {} shards are supported, found {}",
+            MAX_SHARDS,
+            shards.len()
+        ));
+    }
+    let mut names = HashSet::new();
+    let mut sources = HashSet::new();
+    for shard in shards {
+        let valid_name = !shard.name.is_empty()
+            && shard
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            errors.push(format!(
+                "shards: '{}' is not a valid shard name (use letters, digits, - and _)",
+                shard.name
+            ));
+        }
+        if !names.insert(shard.name.as_str()) {
+            errors.push(format!("shards.{}: name used more than once", shard.name));
+        }
+        for source in &shard.sources {
+            if !sources.insert(source.as_str()) {
+                errors.push(format!(
+                    "shards.{}: source '{}' is already in another shard",
+                    shard.name, source
+                ));
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard(name: &str, sources: &[&str]) -> ShardConfig {
+        ShardConfig {
+            name: name.to_string(),
+            sources: sources.iter().map(|s| s.to_string()).collect(),
+            database: None,
+        }
+    }
+
+    #[test]
+    fn test_shard_path() {
+        let data_dir = Path::new("/srv/foia");
+        let mut big = shard("big", &["fbi"]);
+        assert_eq!(big.path(data_dir), PathBuf::from("/srv/foia/shards/big.db"));
+        big.database = Some("/mnt/disk2/fbi.db".to_string());
+        assert_eq!(big.path(data_dir), PathBuf::from("/mnt/disk2/fbi.db"));
+    }
+
+    #[test]
+    fn test_validate_shards() {
+        assert!(validate_shards(&[shard("a", &["fbi"]), shard("b", &["cia"])]).is_empty());
+
+        let errors = validate_shards(&[
+            shard("a", &["fbi"]),
+            shard("a", &["fbi"]),
+            shard("bad name", &[]),
+        ]);
+        assert_eq!(errors.len(), 3);
+
+        let many: Vec<ShardConfig> = (0..=MAX_SHARDS)
+            .map(|i| shard(&format!("s{}", i), &[]))
+            .collect();
+        assert_eq!(validate_shards(&many).len(), 1);
+    }
+}
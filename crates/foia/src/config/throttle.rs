@@ -0,0 +1,91 @@
+//! Bandwidth and schedule throttling configuration.
+//!
+//! Set globally under `throttle` or per source. Users on home connections
+//! cap bandwidth; small municipal servers can be limited to off-peak hours.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rate_limit::{parse_bandwidth, BandwidthLimiter, Schedule};
+
+/// Bandwidth cap and time-of-day windows.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ThrottleConfig {
+    /// Bandwidth cap, e.g. `"2MB/s"`, `"500KiB/s"` or `"10Mbit/s"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub bandwidth: Option<String>,
+
+    /// Local time windows when requests may be made, e.g. `["01:00-06:00"]`.
+    /// Empty means any time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub schedule: Vec<String>,
+}
+
+impl ThrottleConfig {
+    /// Check if this is the default configuration (no throttling).
+    pub fn is_default(&self) -> bool {
+        self.bandwidth.is_none() && self.schedule.is_empty()
+    }
+
+    /// Fill unset fields from `defaults` (the global settings).
+    pub fn or(mut self, defaults: &ThrottleConfig) -> Self {
+        if self.bandwidth.is_none() {
+            self.bandwidth = defaults.bandwidth.clone();
+        }
+        if self.schedule.is_empty() {
+            self.schedule = defaults.schedule.clone();
+        }
+        self
+    }
+
+    /// Bandwidth limiter for the configured cap, if any.
+    pub fn bandwidth_limiter(&self) -> Result<Option<Arc<BandwidthLimiter>>, String> {
+        self.bandwidth
+            .as_deref()
+            .map(|b| parse_bandwidth(b).map(|rate| Arc::new(BandwidthLimiter::new(rate))))
+            .transpose()
+    }
+
+    /// Parsed schedule, if any windows are set.
+    pub fn schedule(&self) -> Result<Option<Schedule>, String> {
+        Schedule::parse(&self.schedule)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_overrides_global() {
+        let global = ThrottleConfig {
+            bandwidth: Some("5MB/s".into()),
+            schedule: vec!["00:00-06:00".into()],
+        };
+        let source = ThrottleConfig {
+            schedule: vec!["01:00-05:00".into()],
+            ..Default::default()
+        }
+        .or(&global);
+        assert_eq!(source.bandwidth.as_deref(), Some("5MB/s"));
+        assert_eq!(source.schedule, vec!["01:00-05:00".to_string()]);
+        assert_eq!(
+            source.bandwidth_limiter().unwrap().unwrap().bytes_per_sec(),
+            5_000_000
+        );
+    }
+
+    #[test]
+    fn test_invalid_values_error() {
+        let config = ThrottleConfig {
+            bandwidth: Some("lots".into()),
+            schedule: vec!["nightly".into()],
+        };
+        assert!(config.bandwidth_limiter().is_err());
+        assert!(config.schedule().is_err());
+        assert!(ThrottleConfig::default().schedule().unwrap().is_none());
+    }
+}
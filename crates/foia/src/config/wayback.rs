@@ -0,0 +1,107 @@
+//! Wayback Machine fallback configuration.
+//!
+//! Agencies pull documents. When a document URL starts answering 404 or
+//! 410, a source's `wayback` block lets the downloader ask the Internet
+//! Archive's CDX index for the last good capture and offer it for ingest
+//! (or queue it straight away with `auto_ingest`). Newly downloaded URLs
+//! can also be submitted to Save Page Now so a capture exists for later.
+
+use serde::{Deserialize, Serialize};
+
+use super::session::expand_env_vars;
+
+/// Wayback Machine fallback and Save Page Now settings for a source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct WaybackConfig {
+    /// Look up a wayback capture when a document URL is gone (default: true).
+    #[serde(default = "default_true")]
+    #[prefer(default = "true")]
+    pub fallback: bool,
+
+    /// Queue found captures for download immediately instead of recording
+    /// them as offers for `foia wayback ingest`.
+    #[serde(default)]
+    #[prefer(default)]
+    pub auto_ingest: bool,
+
+    /// Submit every newly downloaded URL to Save Page Now.
+    #[serde(default)]
+    #[prefer(default)]
+    pub save_new_urls: bool,
+
+    /// Save Page Now access key, e.g. `"${IA_ACCESS_KEY}"`. Anonymous
+    /// submissions are heavily rate limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub access_key: Option<String>,
+
+    /// Save Page Now secret key, e.g. `"${IA_SECRET_KEY}"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub secret_key: Option<String>,
+}
+
+impl Default for WaybackConfig {
+    fn default() -> Self {
+        Self {
+            fallback: true,
+            auto_ingest: false,
+            save_new_urls: false,
+            access_key: None,
+            secret_key: None,
+        }
+    }
+}
+
+impl WaybackConfig {
+    /// `Authorization` header value for Save Page Now, if keys are configured.
+    pub fn authorization(&self) -> Result<Option<String>, String> {
+        match (&self.access_key, &self.secret_key) {
+            (Some(access), Some(secret)) => Ok(Some(format!(
+                "LOW {}:{}",
+                expand_env_vars(access)?,
+                expand_env_vars(secret)?
+            ))),
+            (None, None) => Ok(None),
+            _ => Err("wayback access_key and secret_key must be set together".to_string()),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let config: WaybackConfig = serde_json::from_str("{}").unwrap();
+        assert!(config.fallback);
+        assert!(!config.auto_ingest);
+        assert!(!config.save_new_urls);
+        assert_eq!(config.authorization().unwrap(), None);
+    }
+
+    #[test]
+    fn test_authorization() {
+        std::env::set_var("FOIA_TEST_WAYBACK_SECRET", "s3cret");
+        let config = WaybackConfig {
+            access_key: Some("key".to_string()),
+            secret_key: Some("${FOIA_TEST_WAYBACK_SECRET}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.authorization().unwrap().as_deref(),
+            Some("LOW key:s3cret")
+        );
+
+        let half = WaybackConfig {
+            access_key: Some("key".to_string()),
+            ..Default::default()
+        };
+        assert!(half.authorization().is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! Document status workflow configuration.
+//!
+//! Archives with their own review process can change which status moves
+//! are allowed, e.g. to publish analyzed documents without a review step.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{DocumentStatus, StatusWorkflow};
+
+/// Overrides for the default status workflow.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct WorkflowConfig {
+    /// Statuses each status may move to, replacing the defaults for the
+    /// statuses listed, e.g. `analyzed = ["reviewed", "published"]`.
+    /// Workflow stage names and stored names are both accepted.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[prefer(default)]
+    pub transitions: HashMap<String, Vec<String>>,
+}
+
+impl WorkflowConfig {
+    /// Check if this is the default configuration.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The workflow this configures. Unknown status names are skipped.
+    pub fn workflow(&self) -> StatusWorkflow {
+        let mut workflow = StatusWorkflow::default();
+        for (from, to) in &self.transitions {
+            let Some(from_status) = DocumentStatus::from_str(from) else {
+                tracing::warn!("Unknown document status '{}' in workflow config", from);
+                continue;
+            };
+            let to = to
+                .iter()
+                .filter_map(|name| {
+                    let status = DocumentStatus::from_str(name);
+                    if status.is_none() {
+                        tracing::warn!("Unknown document status '{}' in workflow config", name);
+                    }
+                    status
+                })
+                .collect();
+            workflow.set_transitions(from_status, to);
+        }
+        workflow
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workflow_overrides() {
+        let config: WorkflowConfig = serde_json::from_str(
+            r#"{"transitions": {"analyzed": ["published", "bogus"], "nowhere": ["pending"]}}"#,
+        )
+        .unwrap();
+        let workflow = config.workflow();
+        assert!(workflow.allows(DocumentStatus::Indexed, DocumentStatus::Published));
+        assert!(!workflow.allows(DocumentStatus::Indexed, DocumentStatus::Reviewed));
+        assert!(workflow.allows(DocumentStatus::Reviewed, DocumentStatus::Published));
+    }
+}
@@ -0,0 +1,104 @@
+//! Workspace configuration.
+//!
+//! A workspace is a separate data directory and database for one
+//! investigation ("police-misconduct", "environmental"). All workspaces share
+//! the rest of the config file; the CLI picks one with `--workspace` and
+//! `foia serve` mounts them all behind a switcher.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Settings, DEFAULT_DATABASE_FILENAME, DOCUMENTS_SUBDIR};
+use crate::repository::util::validate_database_url;
+
+/// Name of the workspace formed by the top-level `data_dir` and `database`.
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// Data directory and database for one workspace.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct WorkspaceConfig {
+    /// Display name shown in the web UI (defaults to the workspace ID).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub name: Option<String>,
+    /// Data directory, relative to the config file unless absolute.
+    pub data_dir: String,
+    /// Database filename within `data_dir`, or a full database URL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub database: Option<String>,
+}
+
+impl WorkspaceConfig {
+    /// Display name, falling back to the workspace ID.
+    pub fn name_or<'a>(&'a self, id: &'a str) -> &'a str {
+        self.name.as_deref().unwrap_or(id)
+    }
+
+    /// Settings for this workspace: `base` with its own data directory and
+    /// database. A workspace never inherits another database URL, so its
+    /// documents cannot land in someone else's investigation.
+    pub fn settings(&self, base: &Settings, base_dir: &Path) -> Result<Settings, String> {
+        let expanded = shellexpand::tilde(&self.data_dir);
+        let data_dir = Path::new(expanded.as_ref());
+        let data_dir = if data_dir.is_absolute() {
+            data_dir.to_path_buf()
+        } else {
+            base_dir.join(data_dir)
+        };
+
+        let mut settings = base.clone();
+        settings.documents_dir = data_dir.join(DOCUMENTS_SUBDIR);
+        settings.data_dir = data_dir;
+        settings.database_url = None;
+        settings.database_filename = DEFAULT_DATABASE_FILENAME.to_string();
+        match &self.database {
+            Some(database) if database.contains("://") => {
+                validate_database_url(database)?;
+                settings.database_url = Some(database.clone());
+            }
+            Some(filename) => settings.database_filename = filename.clone(),
+            None => {}
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_workspace_settings() {
+        let mut base = Settings::with_data_dir(PathBuf::from("/srv/foia"));
+        base.database_url = Some("sqlite:///srv/foia/shared.db".to_string());
+        base.request_timeout = 90;
+
+        let ws = WorkspaceConfig {
+            name: Some("Police misconduct".to_string()),
+            data_dir: "police".to_string(),
+            database: None,
+        };
+        let settings = ws.settings(&base, Path::new("/etc/foia")).unwrap();
+        assert_eq!(settings.data_dir, PathBuf::from("/etc/foia/police"));
+        assert_eq!(
+            settings.documents_dir,
+            PathBuf::from("/etc/foia/police/documents")
+        );
+        assert_eq!(settings.database_url, None);
+        assert_eq!(settings.database_filename, DEFAULT_DATABASE_FILENAME);
+        assert_eq!(settings.request_timeout, 90);
+        assert_eq!(ws.name_or("police"), "Police misconduct");
+
+        let ws = WorkspaceConfig {
+            name: None,
+            data_dir: "/data/env".to_string(),
+            database: Some("env.db".to_string()),
+        };
+        let settings = ws.settings(&base, Path::new("/etc/foia")).unwrap();
+        assert_eq!(settings.database_path(), PathBuf::from("/data/env/env.db"));
+        assert_eq!(ws.name_or("environmental"), "environmental");
+    }
+}
@@ -0,0 +1,210 @@
+//! Recorded HTTP fixtures for scraper development.
+//!
+//! In record mode every response a client receives is written to a fixture
+//! directory; in replay mode responses are served from that directory and
+//! nothing goes out over the network. Each exchange is two files named by a
+//! hash of the method, URL and request body: `<key>.json` with the status
+//! and headers, and `<key>.body` with the raw body.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::response::HttpResponse;
+
+/// Header set on responses synthesized for requests with no fixture.
+pub const FIXTURE_MISSING_HEADER: &str = "x-foia-fixture";
+
+/// Whether a fixture store writes or serves responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    /// Make live requests and save each response.
+    Record,
+    /// Serve saved responses; never touch the network.
+    Replay,
+}
+
+/// Status and headers of one recorded exchange.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FixtureMeta {
+    method: String,
+    url: String,
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+}
+
+/// A directory of recorded responses.
+pub struct FixtureStore {
+    dir: PathBuf,
+    mode: FixtureMode,
+    recorded: Mutex<usize>,
+    misses: Mutex<Vec<String>>,
+}
+
+impl FixtureStore {
+    /// Open a fixture directory, creating it when recording.
+    pub fn new(dir: impl Into<PathBuf>, mode: FixtureMode) -> Result<Self, String> {
+        let dir = dir.into();
+        match mode {
+            FixtureMode::Record => fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?,
+            FixtureMode::Replay if !dir.is_dir() => {
+                return Err(format!("No fixtures at {}", dir.display()));
+            }
+            FixtureMode::Replay => {}
+        }
+        Ok(Self {
+            dir,
+            mode,
+            recorded: Mutex::new(0),
+            misses: Mutex::new(Vec::new()),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// Number of responses written so far.
+    pub fn recorded(&self) -> usize {
+        *self.recorded.lock().unwrap()
+    }
+
+    /// Requests replayed without a fixture, as `"METHOD url"`.
+    pub fn misses(&self) -> Vec<String> {
+        self.misses.lock().unwrap().clone()
+    }
+
+    /// Save a response. Failures are logged rather than returned so a
+    /// recording run is never cut short by a bad write.
+    pub fn record(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&[u8]>,
+        status: StatusCode,
+        headers: &HashMap<String, String>,
+        content: &[u8],
+    ) {
+        let key = fixture_key(method, url, body);
+        let meta = FixtureMeta {
+            method: method.to_string(),
+            url: url.to_string(),
+            status: status.as_u16(),
+            headers: headers.clone(),
+        };
+        let result = serde_json::to_vec_pretty(&meta)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                fs::write(self.dir.join(format!("{}.json", key)), json).map_err(|e| e.to_string())
+            })
+            .and_then(|_| {
+                fs::write(self.dir.join(format!("{}.body", key)), content)
+                    .map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(()) => *self.recorded.lock().unwrap() += 1,
+            Err(e) => tracing::warn!("Failed to record fixture for {} {}: {}", method, url, e),
+        }
+    }
+
+    /// Serve the recorded response for a request.
+    ///
+    /// A request with no fixture gets a 404 carrying [`FIXTURE_MISSING_HEADER`]
+    /// and is remembered in [`misses`](Self::misses).
+    pub fn replay(&self, method: &str, url: &str, body: Option<&[u8]>) -> HttpResponse {
+        let key = fixture_key(method, url, body);
+        let meta = fs::read(self.dir.join(format!("{}.json", key)))
+            .ok()
+            .and_then(|json| serde_json::from_slice::<FixtureMeta>(&json).ok());
+        let Some(meta) = meta else {
+            tracing::debug!("No fixture for {} {}", method, url);
+            self.misses
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", method, url));
+            let mut headers = HashMap::new();
+            headers.insert(FIXTURE_MISSING_HEADER.to_string(), "missing".to_string());
+            return HttpResponse::from_bytes(StatusCode::NOT_FOUND, headers, Vec::new());
+        };
+
+        let content = fs::read(self.dir.join(format!("{}.body", key))).unwrap_or_default();
+        HttpResponse::from_bytes(
+            StatusCode::from_u16(meta.status).unwrap_or(StatusCode::OK),
+            meta.headers,
+            content,
+        )
+    }
+}
+
+/// File name stem for an exchange: the method plus a hash of the request.
+fn fixture_key(method: &str, url: &str, body: Option<&[u8]>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(url.as_bytes());
+    if let Some(body) = body {
+        hasher.update(b"\n");
+        hasher.update(body);
+    }
+    let hash = hex::encode(hasher.finalize());
+    format!("{}-{}", method.to_ascii_lowercase(), &hash[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(FixtureStore::new(dir.path().join("missing"), FixtureMode::Replay).is_err());
+
+        let recorder = FixtureStore::new(dir.path(), FixtureMode::Record).unwrap();
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), "text/html".to_string());
+        recorder.record(
+            "GET",
+            "https://agency.gov/foia",
+            None,
+            StatusCode::OK,
+            &headers,
+            b"<a href=\"/a.pdf\">",
+        );
+        recorder.record(
+            "POST",
+            "https://agency.gov/search",
+            Some(b"{\"page\":2}"),
+            StatusCode::CREATED,
+            &HashMap::new(),
+            b"[]",
+        );
+        assert_eq!(recorder.recorded(), 2);
+
+        let replayer = FixtureStore::new(dir.path(), FixtureMode::Replay).unwrap();
+        let page = replayer.replay("GET", "https://agency.gov/foia", None);
+        assert_eq!(page.status, StatusCode::OK);
+        assert_eq!(page.content_type(), Some("text/html"));
+        assert_eq!(page.text().await.unwrap(), "<a href=\"/a.pdf\">");
+
+        let search = replayer.replay("POST", "https://agency.gov/search", Some(b"{\"page\":2}"));
+        assert_eq!(search.status, StatusCode::CREATED);
+        assert!(replayer.misses().is_empty());
+
+        // Same URL with another body is a different exchange
+        let other = replayer.replay("POST", "https://agency.gov/search", Some(b"{\"page\":3}"));
+        assert_eq!(other.status, StatusCode::NOT_FOUND);
+        assert!(other.headers.contains_key(FIXTURE_MISSING_HEADER));
+        assert_eq!(replayer.misses(), vec!["POST https://agency.gov/search"]);
+    }
+}
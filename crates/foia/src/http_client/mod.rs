@@ -7,33 +7,66 @@
 //! - Routes requests through SOCKS proxy when `SOCKS_PROXY` env var is set
 //! - Supports Tor with obfuscation (default) or direct Tor
 //! - Can be configured to bypass proxy for specific sources
+//! - Sources can use their own proxies, rotating through a pool with
+//!   health checks and a rate limiter per proxy
+//!
+//! For scraper development a client can record every response into a
+//! fixture directory, or replay one offline (see [`FixtureStore`]).
+//!
+//! Sources that respect robots.txt never fetch disallowed URLs; those
+//! requests get a local 403 (see [`HttpResponse::is_robots_disallowed`]).
+//!
+//! A source's crawl scope decides which discovered URLs are queued at all
+//! (see [`CrawlScope`]).
 
 #![allow(dead_code)]
 // This module is the privacy wrapper - it's allowed to use reqwest directly
 #![allow(clippy::disallowed_methods)]
 
+mod fixtures;
+mod proxy;
 mod response;
+mod robots;
+mod scope;
+mod session;
 mod user_agent;
 
+pub use fixtures::{FixtureMode, FixtureStore, FIXTURE_MISSING_HEADER};
+#[allow(unused_imports)]
+pub use proxy::{parse_proxy_url, redact_proxy_url, PooledProxy, ProxyPool};
 #[allow(unused_imports)]
-pub use response::{parse_content_disposition_filename, HeadResponse, HttpResponse};
+pub use response::{
+    parse_content_disposition_filename, parse_content_range, parse_sha256_digest, ContentRange,
+    HeadResponse, HttpResponse,
+};
+pub use robots::{RobotsRules, ROBOTS_DISALLOWED_HEADER, ROBOTS_SKIP_REASON};
+pub use scope::{CrawlScope, ScopeExclusion};
+#[allow(unused_imports)]
+pub use session::{Session, SessionCookies};
 #[allow(unused_imports)]
 pub use user_agent::{resolve_user_agent, IMPERSONATE_USER_AGENTS, USER_AGENT};
 
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use chrono::Utc;
-use reqwest::{Client, Proxy, Response, StatusCode};
+use reqwest::{Client, Proxy, RequestBuilder, Response, StatusCode};
 #[cfg(feature = "browser")]
 use tracing::debug;
 
 use crate::config::scraper::ViaMode;
+use crate::config::{Politeness, ScopeConfig, SessionConfig, ThrottleConfig};
+use crate::metrics;
 use crate::models::{CrawlRequest, CrawlUrl, UrlStatus};
-use crate::privacy::{PrivacyConfig, PrivacyMode};
-use crate::rate_limit::{InMemoryRateLimitBackend, RateLimiter};
+use crate::privacy::{PrivacyConfig, PrivacyMode, ProxyConfig};
+use crate::rate_limit::{
+    BandwidthLimiter, InMemoryRateLimitBackend, RateLimitConfig, RateLimiter, Schedule,
+};
 use crate::repository::DieselCrawlRepository;
+use proxy::Route;
+use robots::RobotsCache;
 
 #[cfg(feature = "browser")]
 use crate::browser::{BrowserPool, BrowserPoolConfig};
@@ -67,10 +100,35 @@ pub struct HttpClient {
     via_mappings: Arc<HashMap<String, String>>,
     /// Via mode controlling when via mappings are used for requests.
     via_mode: ViaMode,
+    /// Per-source proxies; when set, requests rotate through these instead
+    /// of `client` and `rate_limiter`.
+    proxy_pool: Option<Arc<ProxyPool>>,
+    /// Login session and cookie jar for authenticated portals.
+    session: Option<Arc<Session>>,
+    /// Bandwidth caps applied to response bodies (own and shared).
+    bandwidth: Vec<Arc<BandwidthLimiter>>,
+    /// Time-of-day windows; requests outside them wait.
+    schedule: Option<Arc<Schedule>>,
+    /// Recorded responses to save to or serve from.
+    fixtures: Option<Arc<FixtureStore>>,
+    /// robots.txt rules per origin, when the source respects them.
+    robots: Option<Arc<RobotsCache>>,
+    /// Which URLs the source's crawl may follow.
+    scope: Option<Arc<CrawlScope>>,
     #[cfg(feature = "browser")]
     browser_pool: Option<Arc<BrowserPool>>,
 }
 
+/// Local 403 for a request robots.txt disallows.
+fn robots_disallowed_response() -> HttpResponse {
+    let mut headers = HashMap::new();
+    headers.insert(
+        ROBOTS_DISALLOWED_HEADER.to_string(),
+        "disallowed".to_string(),
+    );
+    HttpResponse::from_bytes(StatusCode::FORBIDDEN, headers, Vec::new())
+}
+
 fn extract_response_headers(response: &Response) -> HashMap<String, String> {
     response
         .headers()
@@ -95,7 +153,14 @@ pub struct HttpClientBuilder {
     request_delay: Duration,
     user_agent: Option<String>,
     privacy: Option<PrivacyConfig>,
+    proxy: Option<ProxyConfig>,
+    session: Option<SessionConfig>,
+    throttle: Option<ThrottleConfig>,
+    bandwidth: Vec<Arc<BandwidthLimiter>>,
     rate_limiter: Option<RateLimiter>,
+    rate_limit_config: Option<RateLimitConfig>,
+    respect_robots: bool,
+    scope: Option<ScopeConfig>,
     via_mappings: Option<HashMap<String, String>>,
     via_mode: Option<ViaMode>,
     crawl_repo: Option<Arc<DieselCrawlRepository>>,
@@ -119,6 +184,34 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Route requests through the source's own proxies.
+    /// Overrides the privacy mode: Tor and `SOCKS_PROXY` are not used.
+    pub fn proxy(mut self, config: &ProxyConfig) -> Self {
+        if config.is_enabled() {
+            self.proxy = Some(config.clone());
+        }
+        self
+    }
+
+    /// Keep a persistent cookie jar and log in as configured.
+    pub fn session(mut self, config: &SessionConfig) -> Self {
+        self.session = Some(config.clone());
+        self
+    }
+
+    /// Cap bandwidth and restrict requests to the configured time windows.
+    pub fn throttle(mut self, config: &ThrottleConfig) -> Self {
+        self.throttle = Some(config.clone());
+        self
+    }
+
+    /// Also count response bodies against a bandwidth limiter shared with
+    /// other clients.
+    pub fn bandwidth_limiter(mut self, limiter: Arc<BandwidthLimiter>) -> Self {
+        self.bandwidth.push(limiter);
+        self
+    }
+
     /// Set a shared rate limiter.
     /// Without this, creates a per-client `InMemoryRateLimitBackend`.
     pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
@@ -126,6 +219,30 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Apply a source's politeness settings: request delay, backoff,
+    /// user agent and robots.txt.
+    pub fn politeness(mut self, politeness: &Politeness) -> Self {
+        self.request_delay = politeness.request_delay;
+        self.rate_limit_config = Some(politeness.rate_limit.clone());
+        self.respect_robots = politeness.respect_robots;
+        if let Some(ua) = &politeness.user_agent {
+            self.user_agent = Some(ua.clone());
+        }
+        self
+    }
+
+    /// Never fetch URLs that the site's robots.txt disallows.
+    pub fn respect_robots(mut self, respect: bool) -> Self {
+        self.respect_robots = respect;
+        self
+    }
+
+    /// Limit the crawl to the URLs `config` allows.
+    pub fn scope(mut self, config: &ScopeConfig) -> Self {
+        self.scope = Some(config.clone());
+        self
+    }
+
     /// Set via URL rewriting mappings and mode for caching proxies.
     pub fn via(mut self, mappings: HashMap<String, String>, mode: ViaMode) -> Self {
         self.via_mappings = Some(mappings);
@@ -148,24 +265,79 @@ impl HttpClientBuilder {
     /// Build the `HttpClient`.
     ///
     /// # Errors
-    /// Returns an error if Tor mode is requested but unavailable, or if a
-    /// proxy is configured but cannot be initialized.
+    /// Returns an error if Tor mode is requested but unavailable, if a
+    /// proxy is configured but cannot be initialized, or if the throttle
+    /// settings or scope patterns don't parse.
     pub fn build(self) -> Result<HttpClient, String> {
         let user_agent = resolve_user_agent(self.user_agent.as_deref());
 
+        let mut bandwidth = self.bandwidth;
+        let mut schedule = None;
+        if let Some(throttle) = &self.throttle {
+            bandwidth.extend(throttle.bandwidth_limiter()?);
+            schedule = throttle.schedule()?.map(Arc::new);
+        }
+
         let privacy_config = self
             .privacy
             .unwrap_or_else(|| PrivacyConfig::default().with_env_overrides());
 
-        let (client, privacy_mode) =
-            HttpClient::build_client(&user_agent, self.timeout, Some(&privacy_config))?;
+        let session = self.session.as_ref().map(|c| Arc::new(Session::new(c)));
+        let cookies = session.as_ref().map(|s| s.cookies());
+
+        let (client, privacy_mode, proxy_pool) = match self.proxy {
+            Some(proxy_config) => {
+                let timeout = self.timeout;
+                let pool = Arc::new(ProxyPool::new(
+                    &proxy_config,
+                    || HttpClient::client_builder(&user_agent, timeout, cookies.clone()),
+                    self.request_delay,
+                )?);
+                tracing::info!(
+                    "HTTP client for {} routed through {} proxy(ies)",
+                    self.source_id,
+                    pool.proxies().len()
+                );
+                if let Some(url) = proxy_config.health_check_url {
+                    ProxyPool::spawn_health_checks(
+                        &pool,
+                        url,
+                        Duration::from_secs(proxy_config.health_check_interval),
+                    );
+                }
+                let client = pool.proxies()[0].client().clone();
+                (client, PrivacyMode::ExternalProxy, Some(pool))
+            }
+            None => {
+                let (client, mode) = HttpClient::build_client(
+                    &user_agent,
+                    self.timeout,
+                    Some(&privacy_config),
+                    cookies,
+                )?;
+                (client, mode, None)
+            }
+        };
+
+        let rate_limiter = match (self.rate_limiter, self.rate_limit_config) {
+            (Some(limiter), Some(config)) => limiter.reconfigured(config),
+            (Some(limiter), None) => limiter,
+            (None, config) => {
+                let backend = Arc::new(InMemoryRateLimitBackend::new(
+                    self.request_delay.as_millis() as u64,
+                ));
+                RateLimiter::with_config(backend, config.unwrap_or_default())
+            }
+        };
 
-        let rate_limiter = self.rate_limiter.unwrap_or_else(|| {
-            let backend = Arc::new(InMemoryRateLimitBackend::new(
-                self.request_delay.as_millis() as u64,
-            ));
-            RateLimiter::new(backend)
-        });
+        let robots = self
+            .respect_robots
+            .then(|| Arc::new(RobotsCache::new(&user_agent)));
+        let scope = self
+            .scope
+            .as_ref()
+            .map(|config| CrawlScope::new(&self.source_id, config).map(Arc::new))
+            .transpose()?;
 
         let via_mappings = self.via_mappings.unwrap_or_default();
         let via_mode = self.via_mode.unwrap_or_default();
@@ -191,6 +363,13 @@ impl HttpClientBuilder {
             privacy_mode,
             via_mappings: Arc::new(via_mappings),
             via_mode,
+            proxy_pool,
+            session,
+            bandwidth,
+            schedule,
+            fixtures: None,
+            robots,
+            scope,
             #[cfg(feature = "browser")]
             browser_pool: HttpClient::create_browser_pool(),
         })
@@ -215,7 +394,14 @@ impl HttpClient {
             request_delay,
             user_agent: None,
             privacy: None,
+            proxy: None,
+            session: None,
+            throttle: None,
+            bandwidth: Vec::new(),
             rate_limiter: None,
+            rate_limit_config: None,
+            respect_robots: false,
+            scope: None,
             via_mappings: None,
             via_mode: None,
             crawl_repo: None,
@@ -252,6 +438,23 @@ impl HttpClient {
         })
     }
 
+    /// Base reqwest client settings shared by every route.
+    fn client_builder(
+        user_agent: &str,
+        timeout: Duration,
+        cookies: Option<Arc<SessionCookies>>,
+    ) -> reqwest::ClientBuilder {
+        let builder = Client::builder()
+            .user_agent(user_agent)
+            .timeout(timeout)
+            .gzip(true)
+            .brotli(true);
+        match cookies {
+            Some(cookies) => builder.cookie_provider(cookies),
+            None => builder,
+        }
+    }
+
     /// Build a reqwest Client with the appropriate proxy settings.
     ///
     /// # Errors
@@ -262,12 +465,9 @@ impl HttpClient {
         user_agent: &str,
         timeout: Duration,
         privacy_config: Option<&PrivacyConfig>,
+        cookies: Option<Arc<SessionCookies>>,
     ) -> Result<(Client, PrivacyMode), String> {
-        let mut builder = Client::builder()
-            .user_agent(user_agent)
-            .timeout(timeout)
-            .gzip(true)
-            .brotli(true);
+        let mut builder = Self::client_builder(user_agent, timeout, cookies);
 
         let mode = privacy_config
             .map(|c| c.mode())
@@ -381,6 +581,17 @@ impl HttpClient {
         self
     }
 
+    /// Record responses into, or replay them from, a fixture store.
+    pub fn with_fixtures(mut self, fixtures: Arc<FixtureStore>) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Get the fixture store, if recording or replaying.
+    pub fn fixtures(&self) -> Option<&Arc<FixtureStore>> {
+        self.fixtures.as_ref()
+    }
+
     /// Get the rate limiter for this client.
     pub fn rate_limiter(&self) -> &RateLimiter {
         &self.rate_limiter
@@ -396,17 +607,171 @@ impl HttpClient {
         !matches!(self.privacy_mode, PrivacyMode::Direct)
     }
 
+    /// Get the source's proxy pool, if it has its own proxies.
+    pub fn proxy_pool(&self) -> Option<&Arc<ProxyPool>> {
+        self.proxy_pool.as_ref()
+    }
+
     /// Get the via mappings for URL rewriting detection.
     /// Useful for detecting when URLs will be rewritten to specific domains.
     pub fn via_mappings(&self) -> &HashMap<String, String> {
         &self.via_mappings
     }
 
+    /// Pick the client for a request (rotating through the proxy pool if
+    /// one is configured) and wait for its schedule window and rate limiter.
+    async fn route(&self, url: &str) -> Route {
+        if let Some(schedule) = &self.schedule {
+            schedule.wait_until_open(&self.source_id).await;
+        }
+        Route::acquire(
+            url,
+            &self.client,
+            &self.rate_limiter,
+            self.proxy_pool.as_deref(),
+        )
+        .await
+    }
+
+    /// Send a request on `route`.
+    ///
+    /// With a login session, logs in before the first request and, if the
+    /// response shows the session expired, logs in again and retries once.
+    async fn send(
+        &self,
+        route: &Route,
+        request: RequestBuilder,
+    ) -> Result<Response, reqwest::Error> {
+        let Some(session) = &self.session else {
+            return route.send(request).await;
+        };
+
+        if let Err(e) = session.ensure_logged_in(&self.client).await {
+            tracing::warn!("{}: login failed: {}", self.source_id, e);
+        }
+
+        let generation = session.generation();
+        let retry = request.try_clone();
+        let response = route.send(session.authorize(request)).await?;
+        if !session.is_expired(&response) {
+            return Ok(response);
+        }
+        let Some(retry) = retry else {
+            return Ok(response);
+        };
+
+        tracing::info!("{}: session expired, logging in again", self.source_id);
+        if let Err(e) = session.relogin(&self.client, generation).await {
+            tracing::warn!("{}: login failed: {}", self.source_id, e);
+            return Ok(response);
+        }
+        route.send(session.authorize(retry)).await
+    }
+
+    /// Run a request, unless robots.txt disallows it, through the fixture
+    /// store if there is one.
+    async fn exchange<F>(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&[u8]>,
+        live: F,
+    ) -> Result<HttpResponse, reqwest::Error>
+    where
+        F: Future<Output = Result<HttpResponse, reqwest::Error>>,
+    {
+        if !self.robots_allows(url).await {
+            return Ok(robots_disallowed_response());
+        }
+        self.fixture_exchange(method, url, body, live).await
+    }
+
+    /// Run a request through the fixture store, if there is one.
+    ///
+    /// Replaying never polls `live`; recording buffers the body so it can
+    /// be both saved and returned.
+    async fn fixture_exchange<F>(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<&[u8]>,
+        live: F,
+    ) -> Result<HttpResponse, reqwest::Error>
+    where
+        F: Future<Output = Result<HttpResponse, reqwest::Error>>,
+    {
+        let Some(fixtures) = &self.fixtures else {
+            return live.await;
+        };
+        match fixtures.mode() {
+            FixtureMode::Replay => Ok(fixtures.replay(method, url, body)),
+            FixtureMode::Record => {
+                let response = live.await?;
+                let (status, headers) = (response.status, response.headers.clone());
+                let content = response.bytes().await?;
+                fixtures.record(method, url, body, status, &headers, &content);
+                Ok(HttpResponse::from_bytes(status, headers, content))
+            }
+        }
+    }
+
+    /// Whether robots.txt lets us fetch `url`. Always true for sources
+    /// that don't respect robots.txt.
+    ///
+    /// Each origin's robots.txt is fetched once. A missing file (4xx)
+    /// allows everything; a server error or network failure disallows the
+    /// request and is retried on the next one.
+    pub async fn robots_allows(&self, url: &str) -> bool {
+        let Some(robots) = &self.robots else {
+            return true;
+        };
+        let Some((origin, path)) = robots::split_url(url) else {
+            return true;
+        };
+        if let Some(rules) = robots.get(&origin) {
+            return rules.is_allowed(&path);
+        }
+
+        let robots_url = format!("{}/robots.txt", origin);
+        let fetched = self
+            .fixture_exchange(
+                "GET",
+                &robots_url,
+                None,
+                self.get_via_reqwest(&robots_url, None, None),
+            )
+            .await;
+        let rules = match fetched {
+            Ok(response) if response.is_success() => match response.text().await {
+                Ok(text) => RobotsRules::parse(&text, robots.user_agent()),
+                Err(e) => {
+                    tracing::warn!("{}: failed to read {}: {}", self.source_id, robots_url, e);
+                    return false;
+                }
+            },
+            Ok(response) if response.status.is_client_error() => RobotsRules::allow_all(),
+            Ok(response) => {
+                tracing::warn!(
+                    "{}: {} returned {}, holding off",
+                    self.source_id,
+                    robots_url,
+                    response.status
+                );
+                return false;
+            }
+            Err(e) => {
+                tracing::warn!("{}: failed to fetch {}: {}", self.source_id, robots_url, e);
+                return false;
+            }
+        };
+        robots.insert(&origin, rules).is_allowed(&path)
+    }
+
     async fn finalize_request(
         &self,
         request_log: &mut CrawlRequest,
         url: &str,
-        domain: &Option<String>,
+        route: &Route,
         status_code: u16,
         response_headers: &HashMap<String, String>,
         duration: Duration,
@@ -416,12 +781,24 @@ impl HttpClient {
         request_log.response_status = Some(status_code);
         request_log.response_headers = response_headers.clone();
 
+        metrics::HTTP_REQUESTS.inc(&[
+            &self.source_id,
+            &request_log.method,
+            &status_code.to_string(),
+        ]);
+        metrics::HTTP_REQUEST_DURATION.observe(&[&self.source_id], duration.as_secs_f64());
+
         if let Some(repo) = &self.crawl_repo {
             let _ = repo.log_request(request_log).await;
         }
 
-        if let Some(ref domain) = domain {
-            self.rate_limiter
+        if let Some(session) = &self.session {
+            session.save_cookies();
+        }
+
+        if let Some(ref domain) = route.domain {
+            route
+                .limiter
                 .report_response_status(domain, status_code, url, response_headers)
                 .await;
         }
@@ -437,6 +814,26 @@ impl HttpClient {
         url: &str,
         etag: Option<&str>,
         last_modified: Option<&str>,
+    ) -> Result<HttpResponse, reqwest::Error> {
+        // Fixtures hold full responses, so never send conditional headers
+        let (etag, last_modified) = match self.fixtures {
+            Some(_) => (None, None),
+            None => (etag, last_modified),
+        };
+        self.exchange(
+            "GET",
+            url,
+            None,
+            self.get_uncached(url, etag, last_modified),
+        )
+        .await
+    }
+
+    async fn get_uncached(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
     ) -> Result<HttpResponse, reqwest::Error> {
         // Check if browser mode is enabled
         #[cfg(feature = "browser")]
@@ -520,7 +917,8 @@ impl HttpClient {
         fetch_url: &str,
         original_url: &str,
     ) -> Option<HttpResponse> {
-        let domain = self.rate_limiter.acquire(original_url).await;
+        // The browser does its own routing, so skip the source's proxy pool
+        let route = Route::acquire(original_url, &self.client, &self.rate_limiter, None).await;
         let start = Instant::now();
 
         let result = pool.fetch(fetch_url).await;
@@ -542,7 +940,7 @@ impl HttpClient {
                 self.finalize_request(
                     &mut request_log,
                     original_url,
-                    &domain,
+                    &route,
                     status_code,
                     &headers,
                     duration,
@@ -558,8 +956,8 @@ impl HttpClient {
             Err(e) => {
                 debug!("Browser pool fetch failed for {}: {}", original_url, e);
 
-                if let Some(ref domain) = domain {
-                    self.rate_limiter.report_server_error(domain).await;
+                if let Some(ref domain) = route.domain {
+                    route.limiter.report_server_error(domain).await;
                 }
 
                 None
@@ -633,9 +1031,9 @@ impl HttpClient {
         last_modified: Option<&str>,
     ) -> Result<HttpResponse, reqwest::Error> {
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(original_url).await;
+        let route = self.route(original_url).await;
 
-        let mut request = self.client.get(fetch_url);
+        let mut request = route.client.get(fetch_url);
 
         let mut headers = HashMap::new();
 
@@ -661,7 +1059,7 @@ impl HttpClient {
         request_log.was_conditional = was_conditional;
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -671,18 +1069,17 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             original_url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
         )
         .await;
 
-        Ok(HttpResponse::from_reqwest(
-            response.status(),
-            response_headers,
-            response,
-        ))
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
     }
 
     /// Get page content as text.
@@ -696,14 +1093,28 @@ impl HttpClient {
         &self,
         url: &str,
         headers: HashMap<String, String>,
+    ) -> Result<HttpResponse, reqwest::Error> {
+        self.exchange(
+            "GET",
+            url,
+            None,
+            self.get_with_headers_via_reqwest(url, headers),
+        )
+        .await
+    }
+
+    async fn get_with_headers_via_reqwest(
+        &self,
+        url: &str,
+        headers: HashMap<String, String>,
     ) -> Result<HttpResponse, reqwest::Error> {
         // Apply via rewriting if configured (fetch via caching proxy)
         let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
 
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(url).await;
+        let route = self.route(url).await;
 
-        let mut request = self.client.get(&fetch_url);
+        let mut request = route.client.get(&fetch_url);
         for (name, value) in &headers {
             request = request.header(name, value);
         }
@@ -714,7 +1125,7 @@ impl HttpClient {
         request_log.request_headers = headers.clone();
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -723,18 +1134,17 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
         )
         .await;
 
-        Ok(HttpResponse::from_reqwest(
-            response.status(),
-            response_headers,
-            response,
-        ))
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
     }
 
     /// Make a POST request with form data.
@@ -745,7 +1155,14 @@ impl HttpClient {
         url: &str,
         form: &T,
     ) -> Result<HttpResponse, reqwest::Error> {
-        self.post_via_reqwest(url, form).await
+        let body = serde_json::to_vec(form).ok();
+        self.exchange(
+            "POST",
+            url,
+            body.as_deref(),
+            self.post_via_reqwest(url, form),
+        )
+        .await
     }
 
     /// Make a POST request with JSON body.
@@ -756,7 +1173,30 @@ impl HttpClient {
         url: &str,
         json: &T,
     ) -> Result<HttpResponse, reqwest::Error> {
-        self.post_json_via_reqwest(url, json).await
+        let body = serde_json::to_vec(json).ok();
+        self.exchange(
+            "POST",
+            url,
+            body.as_deref(),
+            self.post_json_via_reqwest(url, json),
+        )
+        .await
+    }
+
+    /// Make a PUT request with a raw body of type `content_type`.
+    pub async fn put(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<HttpResponse, reqwest::Error> {
+        self.exchange(
+            "PUT",
+            url,
+            Some(&body),
+            self.put_via_reqwest(url, body.clone(), content_type),
+        )
+        .await
     }
 
     /// POST JSON request with custom headers.
@@ -765,14 +1205,30 @@ impl HttpClient {
         url: &str,
         json: &T,
         headers: HashMap<String, String>,
+    ) -> Result<HttpResponse, reqwest::Error> {
+        let body = serde_json::to_vec(json).ok();
+        self.exchange(
+            "POST",
+            url,
+            body.as_deref(),
+            self.post_json_with_headers_via_reqwest(url, json, headers),
+        )
+        .await
+    }
+
+    async fn post_json_with_headers_via_reqwest<T: serde::Serialize + ?Sized>(
+        &self,
+        url: &str,
+        json: &T,
+        headers: HashMap<String, String>,
     ) -> Result<HttpResponse, reqwest::Error> {
         // Apply via rewriting if configured (fetch via caching proxy)
         let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
 
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(url).await;
+        let route = self.route(url).await;
 
-        let mut request = self.client.post(&fetch_url).json(json);
+        let mut request = route.client.post(&fetch_url).json(json);
         for (name, value) in &headers {
             request = request.header(name, value);
         }
@@ -783,7 +1239,7 @@ impl HttpClient {
         request_log.request_headers = headers.clone();
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -792,18 +1248,17 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
         )
         .await;
 
-        Ok(HttpResponse::from_reqwest(
-            response.status(),
-            response_headers,
-            response,
-        ))
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
     }
 
     /// POST via reqwest (direct HTTP).
@@ -816,16 +1271,16 @@ impl HttpClient {
         let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
 
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(url).await;
+        let route = self.route(url).await;
 
-        let request = self.client.post(&fetch_url).form(form);
+        let request = route.client.post(&fetch_url).form(form);
 
         // Create request log
         let mut request_log =
             CrawlRequest::new(self.source_id.clone(), url.to_string(), "POST".to_string());
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -834,18 +1289,17 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
         )
         .await;
 
-        Ok(HttpResponse::from_reqwest(
-            response.status(),
-            response_headers,
-            response,
-        ))
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
     }
 
     /// POST JSON via reqwest (direct HTTP).
@@ -858,16 +1312,16 @@ impl HttpClient {
         let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
 
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(url).await;
+        let route = self.route(url).await;
 
-        let request = self.client.post(&fetch_url).json(json);
+        let request = route.client.post(&fetch_url).json(json);
 
         // Create request log
         let mut request_log =
             CrawlRequest::new(self.source_id.clone(), url.to_string(), "POST".to_string());
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -876,18 +1330,63 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
         )
         .await;
 
-        Ok(HttpResponse::from_reqwest(
-            response.status(),
-            response_headers,
-            response,
-        ))
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
+    }
+
+    /// PUT via reqwest (direct HTTP).
+    async fn put_via_reqwest(
+        &self,
+        url: &str,
+        body: Vec<u8>,
+        content_type: &str,
+    ) -> Result<HttpResponse, reqwest::Error> {
+        // Apply via rewriting if configured (fetch via caching proxy)
+        let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
+
+        // Wait for rate limiter before making request (use original URL for rate limiting)
+        let route = self.route(url).await;
+
+        let request = route
+            .client
+            .put(&fetch_url)
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body);
+
+        // Create request log
+        let mut request_log =
+            CrawlRequest::new(self.source_id.clone(), url.to_string(), "PUT".to_string());
+
+        let start = Instant::now();
+        let response = self.send(&route, request).await?;
+        let duration = start.elapsed();
+
+        let status_code = response.status().as_u16();
+
+        let response_headers = extract_response_headers(&response);
+        self.finalize_request(
+            &mut request_log,
+            url,
+            &route,
+            status_code,
+            &response_headers,
+            duration,
+        )
+        .await;
+
+        Ok(
+            HttpResponse::from_reqwest(response.status(), response_headers, response)
+                .with_bandwidth(&self.bandwidth),
+        )
     }
 
     /// Make a HEAD request to check headers without downloading content.
@@ -897,14 +1396,44 @@ impl HttpClient {
         url: &str,
         etag: Option<&str>,
         last_modified: Option<&str>,
+    ) -> Result<HeadResponse, reqwest::Error> {
+        if !self.robots_allows(url).await {
+            let response = robots_disallowed_response();
+            return Ok(HeadResponse {
+                status: response.status,
+                headers: response.headers,
+            });
+        }
+        let Some(fixtures) = &self.fixtures else {
+            return self.head_via_reqwest(url, etag, last_modified).await;
+        };
+        let response = match fixtures.mode() {
+            FixtureMode::Replay => fixtures.replay("HEAD", url, None),
+            FixtureMode::Record => {
+                let response = self.head_via_reqwest(url, None, None).await?;
+                fixtures.record("HEAD", url, None, response.status, &response.headers, &[]);
+                return Ok(response);
+            }
+        };
+        Ok(HeadResponse {
+            status: response.status,
+            headers: response.headers,
+        })
+    }
+
+    async fn head_via_reqwest(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
     ) -> Result<HeadResponse, reqwest::Error> {
         // Apply via rewriting if configured (fetch via caching proxy)
         let (fetch_url, _via_rewritten) = self.apply_via_rewrite(url);
 
         // Wait for rate limiter before making request (use original URL for rate limiting)
-        let domain = self.rate_limiter.acquire(url).await;
+        let route = self.route(url).await;
 
-        let mut request = self.client.head(&fetch_url);
+        let mut request = route.client.head(&fetch_url);
 
         let mut headers = HashMap::new();
 
@@ -927,7 +1456,7 @@ impl HttpClient {
         request_log.was_conditional = was_conditional;
 
         let start = Instant::now();
-        let response = request.send().await?;
+        let response = self.send(&route, request).await?;
         let duration = start.elapsed();
 
         let status_code = response.status().as_u16();
@@ -937,7 +1466,7 @@ impl HttpClient {
         self.finalize_request(
             &mut request_log,
             url,
-            &domain,
+            &route,
             status_code,
             &response_headers,
             duration,
@@ -997,8 +1526,35 @@ impl HttpClient {
         }
     }
 
-    /// Track a discovered URL.
+    /// Whether the crawl scope lets a document URL be queued or fetched.
+    /// `depth` is links followed from a seed, if known.
+    pub fn in_scope(&self, url: &str, depth: Option<u32>) -> bool {
+        self.scope
+            .as_ref()
+            .is_none_or(|scope| scope.admit(url, depth))
+    }
+
+    /// Whether the crawl scope lets a page be queued for crawling; counts
+    /// it towards the scope's page limit.
+    pub fn page_in_scope(&self, url: &str, depth: u32) -> bool {
+        self.scope
+            .as_ref()
+            .is_none_or(|scope| scope.admit_page(url, depth))
+    }
+
+    /// URLs the crawl scope has excluded, by reason; empty without a scope.
+    pub fn scope_exclusions(&self) -> Vec<(ScopeExclusion, u64)> {
+        self.scope
+            .as_ref()
+            .map(|scope| scope.exclusions())
+            .unwrap_or_default()
+    }
+
+    /// Track a discovered URL, unless it is outside the crawl scope.
     pub async fn track_url(&self, crawl_url: &CrawlUrl) -> bool {
+        if !self.in_scope(&crawl_url.url, Some(crawl_url.depth)) {
+            return false;
+        }
         if let Some(repo) = &self.crawl_repo {
             repo.add_url(crawl_url).await.unwrap_or(false)
         } else {
@@ -1028,6 +1584,13 @@ impl HttpClient {
         }
         (None, None)
     }
+
+    /// The crawl record for a URL, holding what was learned when it was
+    /// discovered.
+    pub async fn crawl_url(&self, url: &str) -> Option<CrawlUrl> {
+        let repo = self.crawl_repo.as_ref()?;
+        repo.get_url(&self.source_id, url).await.ok().flatten()
+    }
 }
 
 #[cfg(test)]
@@ -1071,7 +1634,7 @@ mod tests {
         let config = tor_direct_config();
         assert_eq!(config.mode(), PrivacyMode::TorDirect);
 
-        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config));
+        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config), None);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(
@@ -1086,7 +1649,7 @@ mod tests {
         let config = tor_obfuscated_config();
         assert!(matches!(config.mode(), PrivacyMode::TorObfuscated(_)));
 
-        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config));
+        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config), None);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(
@@ -1100,7 +1663,7 @@ mod tests {
     fn test_build_client_external_proxy_fails_without_url() {
         let config = external_proxy_no_url_config();
 
-        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config));
+        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config), None);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(
@@ -1115,7 +1678,7 @@ mod tests {
         let config = direct_config();
         assert_eq!(config.mode(), PrivacyMode::Direct);
 
-        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config));
+        let result = HttpClient::build_client("test-agent", test_timeout(), Some(&config), None);
         assert!(result.is_ok());
         let (_, mode) = result.unwrap();
         assert_eq!(mode, PrivacyMode::Direct);
@@ -1151,6 +1714,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_builder_proxy_overrides_tor() {
+        let proxy = ProxyConfig {
+            urls: vec![
+                "socks5h://127.0.0.1:1080".to_string(),
+                "http://127.0.0.1:3128".to_string(),
+            ],
+            ..Default::default()
+        };
+        let client = HttpClient::builder("test", test_timeout(), test_delay())
+            .privacy(&tor_direct_config())
+            .proxy(&proxy)
+            .build()
+            .expect("proxies should not need Tor");
+        assert_eq!(client.privacy_mode(), PrivacyMode::ExternalProxy);
+        assert_eq!(client.proxy_pool().map(|p| p.proxies().len()), Some(2));
+    }
+
+    #[test]
+    fn test_builder_rejects_bad_proxy() {
+        let proxy = ProxyConfig {
+            urls: vec!["ftp://127.0.0.1:21".to_string()],
+            ..Default::default()
+        };
+        let result = HttpClient::builder("test", test_timeout(), test_delay())
+            .proxy(&proxy)
+            .build();
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_builder_with_rate_limiter() {
         let backend = Arc::new(InMemoryRateLimitBackend::new(100));
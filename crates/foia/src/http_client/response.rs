@@ -1,9 +1,12 @@
 //! HTTP response wrappers.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use reqwest::{Response, StatusCode};
 
+use crate::rate_limit::BandwidthLimiter;
+
 /// Response body source - either pending (reqwest) or already fetched (browser).
 pub(crate) enum ResponseBody {
     /// Pending response from reqwest.
@@ -16,7 +19,11 @@ pub(crate) enum ResponseBody {
 pub struct HttpResponse {
     pub status: StatusCode,
     pub headers: HashMap<String, String>,
+    /// URL the response came from after redirects (HTTP fetches only).
+    pub url: Option<String>,
     pub(crate) body: ResponseBody,
+    /// Bandwidth caps that body reads count against.
+    pub(crate) bandwidth: Vec<Arc<BandwidthLimiter>>,
 }
 
 impl HttpResponse {
@@ -29,7 +36,21 @@ impl HttpResponse {
         Self {
             status,
             headers,
+            url: Some(response.url().to_string()),
             body: ResponseBody::Pending(response),
+            bandwidth: Vec::new(),
+        }
+    }
+
+    /// Count body reads against these bandwidth caps.
+    pub(crate) fn with_bandwidth(mut self, limiters: &[Arc<BandwidthLimiter>]) -> Self {
+        self.bandwidth = limiters.to_vec();
+        self
+    }
+
+    async fn throttle(&self, bytes: usize) {
+        for limiter in &self.bandwidth {
+            limiter.consume(bytes as u64).await;
         }
     }
 
@@ -42,7 +63,9 @@ impl HttpResponse {
         Self {
             status,
             headers,
+            url: None,
             body: ResponseBody::Ready(content),
+            bandwidth: Vec::new(),
         }
     }
 
@@ -62,6 +85,13 @@ impl HttpResponse {
         code == 429 || code == 503
     }
 
+    /// Check if the request was refused locally because robots.txt
+    /// disallows it.
+    pub fn is_robots_disallowed(&self) -> bool {
+        self.headers
+            .contains_key(super::robots::ROBOTS_DISALLOWED_HEADER)
+    }
+
     /// Get the ETag header.
     pub fn etag(&self) -> Option<&str> {
         self.headers.get("etag").map(|s| s.as_str())
@@ -91,8 +121,60 @@ impl HttpResponse {
             .and_then(|h| parse_content_disposition_filename(h))
     }
 
+    /// Check if the server advertises byte range support (`Accept-Ranges: bytes`).
+    pub fn accepts_ranges(&self) -> bool {
+        self.headers
+            .get("accept-ranges")
+            .is_some_and(|v| v.trim().eq_ignore_ascii_case("bytes"))
+    }
+
+    /// Get the parsed Content-Range header of a 206 response.
+    pub fn content_range(&self) -> Option<ContentRange> {
+        self.headers
+            .get("content-range")
+            .and_then(|h| parse_content_range(h))
+    }
+
+    /// Get the SHA-256 the server reports for the full content, as hex.
+    ///
+    /// Reads `Repr-Digest` (RFC 9530) or the older `Digest` header (RFC 3230).
+    pub fn sha256_digest(&self) -> Option<String> {
+        ["repr-digest", "digest"]
+            .iter()
+            .filter_map(|name| self.headers.get(*name))
+            .find_map(|h| parse_sha256_digest(h))
+    }
+
+    /// Read the next chunk of the body, or `None` once it is exhausted.
+    ///
+    /// Lets large bodies be written to disk as they arrive instead of
+    /// buffered whole with [`bytes`](Self::bytes).
+    pub async fn chunk(&mut self) -> Result<Option<Vec<u8>>, reqwest::Error> {
+        let chunk = match &mut self.body {
+            ResponseBody::Pending(response) => {
+                response.chunk().await.map(|c| c.map(|b| b.to_vec()))?
+            }
+            ResponseBody::Ready(bytes) if bytes.is_empty() => None,
+            ResponseBody::Ready(bytes) => Some(std::mem::take(bytes)),
+        };
+        if let Some(data) = &chunk {
+            self.throttle(data.len()).await;
+        }
+        Ok(chunk)
+    }
+
     /// Get response body as bytes.
-    pub async fn bytes(self) -> Result<Vec<u8>, reqwest::Error> {
+    ///
+    /// With a bandwidth cap the body is read chunk by chunk so the cap
+    /// holds while it downloads.
+    pub async fn bytes(mut self) -> Result<Vec<u8>, reqwest::Error> {
+        if !self.bandwidth.is_empty() {
+            let mut content = Vec::new();
+            while let Some(chunk) = self.chunk().await? {
+                content.extend_from_slice(&chunk);
+            }
+            return Ok(content);
+        }
         match self.body {
             ResponseBody::Pending(response) => response.bytes().await.map(|b| b.to_vec()),
             ResponseBody::Ready(bytes) => Ok(bytes),
@@ -102,7 +184,13 @@ impl HttpResponse {
     /// Get response body as text.
     pub async fn text(self) -> Result<String, reqwest::Error> {
         match self.body {
-            ResponseBody::Pending(response) => response.text().await,
+            ResponseBody::Pending(response) => {
+                let text = response.text().await?;
+                for limiter in &self.bandwidth {
+                    limiter.consume(text.len() as u64).await;
+                }
+                Ok(text)
+            }
             ResponseBody::Ready(bytes) => {
                 // Best effort UTF-8 conversion
                 Ok(String::from_utf8_lossy(&bytes).into_owned())
@@ -148,6 +236,13 @@ impl HeadResponse {
         code == 429 || code == 503
     }
 
+    /// Check if the request was refused locally because robots.txt
+    /// disallows it.
+    pub fn is_robots_disallowed(&self) -> bool {
+        self.headers
+            .contains_key(super::robots::ROBOTS_DISALLOWED_HEADER)
+    }
+
     /// Get the ETag header.
     pub fn etag(&self) -> Option<&str> {
         self.headers.get("etag").map(|s| s.as_str())
@@ -178,6 +273,51 @@ impl HeadResponse {
     }
 }
 
+/// Byte range from a `Content-Range` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// First byte position (inclusive).
+    pub start: u64,
+    /// Last byte position (inclusive).
+    pub end: u64,
+    /// Complete length of the representation, if the server knows it.
+    pub total: Option<u64>,
+}
+
+/// Parse a `Content-Range` value such as `bytes 0-99/1000` or `bytes 0-99/*`.
+pub fn parse_content_range(header: &str) -> Option<ContentRange> {
+    let rest = header.trim().strip_prefix("bytes ")?;
+    let (range, total) = rest.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let end: u64 = end.trim().parse().ok()?;
+    if end < start {
+        return None;
+    }
+    let total = match total.trim() {
+        "*" => None,
+        t => Some(t.parse().ok()?),
+    };
+    Some(ContentRange { start, end, total })
+}
+
+/// Extract a hex SHA-256 from a `Repr-Digest` or `Digest` header value.
+pub fn parse_sha256_digest(header: &str) -> Option<String> {
+    use base64::Engine;
+
+    header.split(',').find_map(|entry| {
+        let (algorithm, value) = entry.trim().split_once('=')?;
+        if !algorithm.trim().eq_ignore_ascii_case("sha-256") {
+            return None;
+        }
+        let value = value.trim().trim_matches(':');
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .ok()?;
+        (bytes.len() == 32).then(|| hex::encode(bytes))
+    })
+}
+
 /// Parse filename from Content-Disposition header value.
 /// Parses both `filename="name.pdf"` and `filename*=UTF-8''name.pdf` formats.
 pub fn parse_content_disposition_filename(header: &str) -> Option<String> {
@@ -256,6 +396,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/1000"),
+            Some(ContentRange {
+                start: 100,
+                end: 199,
+                total: Some(1000)
+            })
+        );
+        assert_eq!(
+            parse_content_range("bytes 0-99/*").and_then(|r| r.total),
+            None
+        );
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("bytes 10-5/100"), None);
+    }
+
+    #[test]
+    fn test_parse_sha256_digest() {
+        // sha-256 of "hello"
+        let hex = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        let b64 = "LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+        assert_eq!(
+            parse_sha256_digest(&format!("sha-256=:{}:", b64)).as_deref(),
+            Some(hex)
+        );
+        assert_eq!(
+            parse_sha256_digest(&format!("md5=abc, SHA-256={}", b64)).as_deref(),
+            Some(hex)
+        );
+        assert_eq!(parse_sha256_digest("sha-512=:AAAA:"), None);
+    }
+
     #[test]
     fn test_parse_content_disposition_none() {
         assert_eq!(parse_content_disposition_filename("attachment"), None);
@@ -0,0 +1,232 @@
+//! robots.txt rules for sources that must respect them.
+//!
+//! Rules are fetched once per origin and cached for the life of the client.
+//! Matching follows RFC 9309: the group naming our user agent wins over `*`,
+//! the longest matching pattern decides, and `*` and `$` are supported in
+//! patterns. A robots.txt that is missing (4xx) allows everything; one that
+//! cannot be fetched (5xx or a network error) allows nothing until it can.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use url::Url;
+
+/// Header set on the 403 synthesized for a request robots.txt disallows.
+pub const ROBOTS_DISALLOWED_HEADER: &str = "x-foia-robots";
+
+/// Reason recorded on crawl URLs that robots.txt kept us from fetching.
+pub const ROBOTS_SKIP_REASON: &str = "disallowed by robots.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Allow and disallow rules that apply to our user agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+}
+
+impl RobotsRules {
+    /// Rules that allow every path.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Rules that allow no path.
+    pub fn disallow_all() -> Self {
+        Self {
+            rules: vec![Rule {
+                allow: false,
+                pattern: "/".to_string(),
+            }],
+        }
+    }
+
+    /// Parse robots.txt, keeping the rules for `user_agent`.
+    pub fn parse(text: &str, user_agent: &str) -> Self {
+        let mut groups: Vec<(Vec<String>, Vec<Rule>)> = Vec::new();
+        let mut reading_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !reading_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.0.push(value.to_ascii_lowercase());
+                    }
+                    reading_agents = true;
+                }
+                key @ ("allow" | "disallow") => {
+                    reading_agents = false;
+                    if let Some(group) = groups.last_mut() {
+                        if !value.is_empty() {
+                            group.1.push(Rule {
+                                allow: key == "allow",
+                                pattern: value.to_string(),
+                            });
+                        }
+                    }
+                }
+                _ => reading_agents = false,
+            }
+        }
+
+        // The most specific agent token contained in our user agent, else `*`
+        let user_agent = user_agent.to_ascii_lowercase();
+        let token = groups
+            .iter()
+            .flat_map(|(agents, _)| agents)
+            .filter(|a| a.as_str() != "*" && user_agent.contains(a.as_str()))
+            .max_by_key(|a| a.len())
+            .cloned()
+            .unwrap_or_else(|| "*".to_string());
+
+        Self {
+            rules: groups
+                .into_iter()
+                .filter(|(agents, _)| agents.contains(&token))
+                .flat_map(|(_, rules)| rules)
+                .collect(),
+        }
+    }
+
+    /// Whether a path (with query string) may be fetched.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter_map(|rule| pattern_len(&rule.pattern, path).map(|len| (len, rule.allow)))
+            // Longest match wins; on a tie, allow
+            .max_by_key(|&(len, allow)| (len, allow))
+            .is_none_or(|(_, allow)| allow)
+    }
+}
+
+/// Length of `pattern` if it matches the start of `path`.
+fn pattern_len(pattern: &str, path: &str) -> Option<usize> {
+    let (pattern_body, anchored) = match pattern.strip_suffix('$') {
+        Some(body) => (body, true),
+        None => (pattern, false),
+    };
+    if matches_from(pattern_body.as_bytes(), path.as_bytes(), anchored) {
+        Some(pattern.len())
+    } else {
+        None
+    }
+}
+
+fn matches_from(pattern: &[u8], path: &[u8], anchored: bool) -> bool {
+    match pattern.split_first() {
+        None => !anchored || path.is_empty(),
+        Some((b'*', rest)) => (0..=path.len()).any(|i| matches_from(rest, &path[i..], anchored)),
+        Some((c, rest)) => path
+            .split_first()
+            .is_some_and(|(p, path_rest)| p == c && matches_from(rest, path_rest, anchored)),
+    }
+}
+
+/// Cached robots.txt rules per origin.
+pub(crate) struct RobotsCache {
+    user_agent: String,
+    rules: Mutex<HashMap<String, Arc<RobotsRules>>>,
+}
+
+impl RobotsCache {
+    pub(crate) fn new(user_agent: &str) -> Self {
+        Self {
+            user_agent: user_agent.to_string(),
+            rules: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+
+    pub(crate) fn get(&self, origin: &str) -> Option<Arc<RobotsRules>> {
+        self.rules.lock().unwrap().get(origin).cloned()
+    }
+
+    pub(crate) fn insert(&self, origin: &str, rules: RobotsRules) -> Arc<RobotsRules> {
+        let rules = Arc::new(rules);
+        self.rules
+            .lock()
+            .unwrap()
+            .insert(origin.to_string(), rules.clone());
+        rules
+    }
+}
+
+/// Origin (`scheme://host[:port]`) and path-with-query of a URL.
+pub(crate) fn split_url(url: &str) -> Option<(String, String)> {
+    let parsed = Url::parse(url).ok()?;
+    let origin = parsed.origin().ascii_serialization();
+    let mut path = parsed.path().to_string();
+    if let Some(query) = parsed.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    Some((origin, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROBOTS: &str = "\
+# county site
+User-agent: *
+Disallow: /records/private
+Allow: /records/private/index.html
+Disallow: /*.xls$
+
+User-agent: foia
+User-agent: otherbot
+Disallow: /search
+Crawl-delay: 10
+";
+
+    #[test]
+    fn test_wildcard_group() {
+        let rules = RobotsRules::parse(ROBOTS, "Mozilla/5.0 Firefox/133.0");
+        assert!(rules.is_allowed("/records/public/a.pdf"));
+        assert!(!rules.is_allowed("/records/private/a.pdf"));
+        assert!(rules.is_allowed("/records/private/index.html"));
+        assert!(!rules.is_allowed("/data/budget.xls"));
+        assert!(rules.is_allowed("/data/budget.xlsx"));
+        assert!(rules.is_allowed("/search?q=x"));
+    }
+
+    #[test]
+    fn test_named_group_replaces_wildcard() {
+        let rules = RobotsRules::parse(ROBOTS, "foia/0.1 (academic research)");
+        assert!(!rules.is_allowed("/search?q=x"));
+        assert!(rules.is_allowed("/records/private/a.pdf"));
+    }
+
+    #[test]
+    fn test_allow_and_disallow_all() {
+        assert!(RobotsRules::allow_all().is_allowed("/anything"));
+        assert!(!RobotsRules::disallow_all().is_allowed("/"));
+        assert!(RobotsRules::parse("User-agent: *\nDisallow:\n", "foia").is_allowed("/a"));
+    }
+
+    #[test]
+    fn test_split_url() {
+        assert_eq!(
+            split_url("https://county.gov:8443/a/b.pdf?id=3"),
+            Some((
+                "https://county.gov:8443".to_string(),
+                "/a/b.pdf?id=3".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Crawl scope: which URLs a source's crawl may follow.
+//!
+//! Discovery asks the client before queueing a page or document, and the
+//! download workers ask again before fetching, so URLs queued by an
+//! earlier crawl are held to the current rules too. Every exclusion is
+//! counted by reason, per crawl and in `foia_scope_excluded_urls_total`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use regex::Regex;
+use url::Url;
+
+use crate::config::ScopeConfig;
+use crate::metrics;
+
+/// Why a URL was kept out of the crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeExclusion {
+    /// Host not in `allowed_hosts`.
+    Host,
+    /// Page path outside `path_prefixes`.
+    Path,
+    /// Deeper than `max_depth`.
+    Depth,
+    /// Matched an `exclude` pattern.
+    Pattern,
+    /// Arrived after `max_pages` pages were queued.
+    PageLimit,
+}
+
+impl ScopeExclusion {
+    pub const ALL: [ScopeExclusion; 5] = [
+        Self::Host,
+        Self::Path,
+        Self::Depth,
+        Self::Pattern,
+        Self::PageLimit,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Host => "host",
+            Self::Path => "path",
+            Self::Depth => "depth",
+            Self::Pattern => "pattern",
+            Self::PageLimit => "page_limit",
+        }
+    }
+}
+
+/// A source's scope rules with counts of what they excluded.
+pub struct CrawlScope {
+    source_id: String,
+    allowed_hosts: Vec<String>,
+    path_prefixes: Vec<String>,
+    max_depth: Option<u32>,
+    max_pages: Option<u64>,
+    exclude: Vec<Regex>,
+    pages: AtomicU64,
+    excluded: [AtomicU64; 5],
+}
+
+impl CrawlScope {
+    /// Compile `config` for `source_id`.
+    pub fn new(source_id: &str, config: &ScopeConfig) -> Result<Self, String> {
+        Ok(Self {
+            source_id: source_id.to_string(),
+            allowed_hosts: config
+                .allowed_hosts
+                .iter()
+                .map(|h| h.trim_start_matches("*.").to_ascii_lowercase())
+                .collect(),
+            path_prefixes: config.path_prefixes.clone(),
+            max_depth: config.max_depth,
+            max_pages: config.max_pages,
+            exclude: config.exclude_patterns()?,
+            pages: AtomicU64::new(0),
+            excluded: Default::default(),
+        })
+    }
+
+    /// Check `url` without counting it. `depth` is links followed from a
+    /// seed, if known; `page` applies the rules for pages crawled through.
+    pub fn check(&self, url: &str, depth: Option<u32>, page: bool) -> Result<(), ScopeExclusion> {
+        let parsed = Url::parse(url).map_err(|_| ScopeExclusion::Host)?;
+        if !self.allowed_hosts.is_empty() {
+            let host = parsed.host_str().unwrap_or("").to_ascii_lowercase();
+            let allowed = self.allowed_hosts.iter().any(|allowed| {
+                host == *allowed
+                    || host
+                        .strip_suffix(allowed.as_str())
+                        .is_some_and(|sub| sub.ends_with('.'))
+            });
+            if !allowed {
+                return Err(ScopeExclusion::Host);
+            }
+        }
+        if page
+            && !self.path_prefixes.is_empty()
+            && !self
+                .path_prefixes
+                .iter()
+                .any(|prefix| parsed.path().starts_with(prefix.as_str()))
+        {
+            return Err(ScopeExclusion::Path);
+        }
+        if let (Some(depth), Some(max)) = (depth, self.max_depth) {
+            if depth > max {
+                return Err(ScopeExclusion::Depth);
+            }
+        }
+        if self.exclude.iter().any(|p| p.is_match(url)) {
+            return Err(ScopeExclusion::Pattern);
+        }
+        Ok(())
+    }
+
+    /// Whether a document URL may be queued or fetched, counting it if not.
+    pub fn admit(&self, url: &str, depth: Option<u32>) -> bool {
+        self.record(url, self.check(url, depth, false))
+    }
+
+    /// Whether a page may be queued for crawling, counting it towards
+    /// `max_pages` if so and as excluded if not.
+    pub fn admit_page(&self, url: &str, depth: u32) -> bool {
+        let result = self.check(url, Some(depth), true).and_then(|()| {
+            let queued = self.pages.fetch_add(1, Ordering::Relaxed);
+            match self.max_pages {
+                Some(max) if queued >= max => Err(ScopeExclusion::PageLimit),
+                _ => Ok(()),
+            }
+        });
+        self.record(url, result)
+    }
+
+    fn record(&self, url: &str, result: Result<(), ScopeExclusion>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(reason) => {
+                tracing::debug!(
+                    "[{}] Out of scope ({}): {}",
+                    self.source_id,
+                    reason.as_str(),
+                    url
+                );
+                self.excluded[reason as usize].fetch_add(1, Ordering::Relaxed);
+                metrics::SCOPE_EXCLUSIONS.inc(&[&self.source_id, reason.as_str()]);
+                false
+            }
+        }
+    }
+
+    /// URLs excluded so far, by reason.
+    pub fn exclusions(&self) -> Vec<(ScopeExclusion, u64)> {
+        ScopeExclusion::ALL
+            .iter()
+            .map(|&reason| {
+                (
+                    reason,
+                    self.excluded[reason as usize].load(Ordering::Relaxed),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(config: ScopeConfig) -> CrawlScope {
+        CrawlScope::new("test", &config).unwrap()
+    }
+
+    #[test]
+    fn test_hosts_include_subdomains() {
+        let scope = scope(ScopeConfig {
+            allowed_hosts: vec!["fbi.gov".to_string()],
+            ..Default::default()
+        });
+        assert!(scope.check("https://vault.fbi.gov/a", None, true).is_ok());
+        assert!(scope.check("https://fbi.gov/a", None, true).is_ok());
+        assert_eq!(
+            scope.check("https://notfbi.gov/a", None, true),
+            Err(ScopeExclusion::Host)
+        );
+    }
+
+    #[test]
+    fn test_path_prefixes_only_limit_pages() {
+        let scope = scope(ScopeConfig {
+            path_prefixes: vec!["/foia/".to_string()],
+            ..Default::default()
+        });
+        assert!(scope
+            .check("https://agency.gov/foia/2024", None, true)
+            .is_ok());
+        assert_eq!(
+            scope.check("https://agency.gov/news/", None, true),
+            Err(ScopeExclusion::Path)
+        );
+        assert!(scope
+            .check("https://agency.gov/files/a.pdf", None, false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_depth_pattern_and_page_limit() {
+        let scope = scope(ScopeConfig {
+            max_depth: Some(2),
+            max_pages: Some(2),
+            exclude: vec!["/calendar".to_string()],
+            ..Default::default()
+        });
+        assert!(!scope.admit_page("https://agency.gov/a", 3));
+        assert!(!scope.admit("https://agency.gov/calendar/2024", Some(1)));
+        assert!(scope.admit_page("https://agency.gov/a", 1));
+        assert!(scope.admit_page("https://agency.gov/b", 2));
+        assert!(!scope.admit_page("https://agency.gov/c", 2));
+
+        let counts = scope.exclusions();
+        assert!(counts.contains(&(ScopeExclusion::Depth, 1)));
+        assert!(counts.contains(&(ScopeExclusion::Pattern, 1)));
+        assert!(counts.contains(&(ScopeExclusion::PageLimit, 1)));
+    }
+
+    #[test]
+    fn test_invalid_exclude_pattern() {
+        let config = ScopeConfig {
+            exclude: vec!["(".to_string()],
+            ..Default::default()
+        };
+        assert!(CrawlScope::new("test", &config).is_err());
+    }
+}
@@ -0,0 +1,356 @@
+//! Login sessions: persistent cookies, login step and re-login on expiry.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::HeaderValue;
+use reqwest::{Client, RequestBuilder, Response, Url};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::config::session::expand_env_vars;
+use crate::config::{LoginConfig, LoginType, SessionConfig};
+
+/// A `Set-Cookie` value and the URL it came from, as saved to disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredCookie {
+    url: String,
+    cookie: String,
+}
+
+/// Cookie jar that is saved to disk so sessions survive restarts.
+///
+/// Wraps reqwest's `Jar` for matching and expiry, and keeps the raw
+/// `Set-Cookie` values (latest per host and name) to replay on load.
+pub struct SessionCookies {
+    jar: Jar,
+    stored: Mutex<HashMap<(String, String), StoredCookie>>,
+    path: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl SessionCookies {
+    /// Load cookies from `path` if it exists; start empty otherwise.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let cookies = Self {
+            jar: Jar::default(),
+            stored: Mutex::new(HashMap::new()),
+            path,
+            dirty: AtomicBool::new(false),
+        };
+
+        let saved: Vec<StoredCookie> = cookies
+            .path
+            .as_deref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        for entry in saved {
+            if let Ok(url) = Url::parse(&entry.url) {
+                cookies.jar.add_cookie_str(&entry.cookie, &url);
+                cookies.remember(&url, &entry.cookie);
+            }
+        }
+        cookies
+    }
+
+    /// Whether any cookies were loaded or received.
+    pub fn is_empty(&self) -> bool {
+        self.stored
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_empty()
+    }
+
+    /// Save to disk if anything changed since the last save.
+    pub fn save_if_changed(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        if let Some(path) = &self.path {
+            if let Err(e) = self.save_to(path) {
+                warn!(
+                    "Failed to save session cookies to {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    fn save_to(&self, path: &Path) -> std::io::Result<()> {
+        let entries: Vec<StoredCookie> = self
+            .stored
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&entries)?;
+        std::fs::write(path, json)
+    }
+
+    fn remember(&self, url: &Url, set_cookie: &str) {
+        let Some(name) = set_cookie
+            .split(';')
+            .next()
+            .and_then(|pair| pair.split_once('='))
+            .map(|(name, _)| name.trim().to_string())
+        else {
+            return;
+        };
+        let host = url.host_str().unwrap_or_default().to_string();
+        self.stored
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                (host, name),
+                StoredCookie {
+                    url: url.to_string(),
+                    cookie: set_cookie.to_string(),
+                },
+            );
+    }
+}
+
+impl CookieStore for SessionCookies {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let headers: Vec<&HeaderValue> = cookie_headers.collect();
+        for value in &headers {
+            if let Ok(s) = value.to_str() {
+                self.remember(url, s);
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        self.jar.set_cookies(&mut headers.into_iter(), url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        self.jar.cookies(url)
+    }
+}
+
+/// Per-source login session.
+///
+/// Cookies are handled by reqwest through [`SessionCookies`]; this adds the
+/// login step, the token header and re-login when the portal says the
+/// session expired.
+pub struct Session {
+    config: SessionConfig,
+    cookies: Arc<SessionCookies>,
+    token_header: RwLock<Option<(String, String)>>,
+    /// Whether the initial login has been tried (or isn't needed).
+    login_attempted: AtomicBool,
+    /// Bumped on every login so concurrent requests that all see an
+    /// expired session only trigger one re-login.
+    generation: AtomicU64,
+    login_lock: tokio::sync::Mutex<()>,
+}
+
+impl Session {
+    /// Create a session, restoring saved cookies.
+    pub fn new(config: &SessionConfig) -> Self {
+        let cookies = Arc::new(SessionCookies::load(config.cookie_file.clone()));
+
+        // Restored form-login cookies may still be valid; try them before
+        // logging in again. Tokens aren't saved, so token logins start fresh.
+        let login_attempted = match &config.login {
+            None => true,
+            Some(login) => login.login_type == LoginType::Form && !cookies.is_empty(),
+        };
+
+        Self {
+            config: config.clone(),
+            cookies,
+            token_header: RwLock::new(None),
+            login_attempted: AtomicBool::new(login_attempted),
+            generation: AtomicU64::new(0),
+            login_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Cookie store to install on every client for this source.
+    pub fn cookies(&self) -> Arc<SessionCookies> {
+        self.cookies.clone()
+    }
+
+    /// Save cookies if any changed.
+    pub fn save_cookies(&self) {
+        self.cookies.save_if_changed();
+    }
+
+    /// Add the token header, if this is a token session.
+    pub(super) fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        let header = self
+            .token_header
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        match header {
+            Some((name, value)) => request.header(name, value),
+            None => request,
+        }
+    }
+
+    /// Whether a response shows the session has expired.
+    pub(super) fn is_expired(&self, response: &Response) -> bool {
+        self.config.login.is_some()
+            && (self.config.is_expired_status(response.status().as_u16())
+                || self.config.is_login_redirect(response.url().as_str()))
+    }
+
+    pub(super) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Log in before the first request, if needed.
+    ///
+    /// Only tried once; after that, re-login is driven by expired responses.
+    pub(super) async fn ensure_logged_in(&self, client: &Client) -> Result<(), String> {
+        if self.login_attempted.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        let seen = self.generation();
+        self.relogin(client, seen).await
+    }
+
+    /// Log in again, unless another request already did since `seen`.
+    pub(super) async fn relogin(&self, client: &Client, seen: u64) -> Result<(), String> {
+        let _guard = self.login_lock.lock().await;
+        if self.generation() != seen {
+            return Ok(());
+        }
+        let Some(login) = &self.config.login else {
+            return Ok(());
+        };
+
+        let result = self.login(client, login).await;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.login_attempted.store(true, Ordering::Release);
+        self.cookies.save_if_changed();
+        result
+    }
+
+    async fn login(&self, client: &Client, login: &LoginConfig) -> Result<(), String> {
+        let fields = login
+            .fields
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), expand_env_vars(v)?)))
+            .collect::<Result<HashMap<String, String>, String>>()?;
+
+        match login.login_type {
+            LoginType::Form => {
+                let url = login.url.as_deref().ok_or("Form login needs a login url")?;
+                debug!("Logging in via form POST to {}", url);
+                let response = client
+                    .post(url)
+                    .form(&fields)
+                    .send()
+                    .await
+                    .map_err(|e| format!("Login request failed: {}", e))?;
+                let status = response.status();
+                if !status.is_success() || self.config.is_login_redirect(response.url().as_str()) {
+                    return Err(format!("Login to {} failed (HTTP {})", url, status));
+                }
+            }
+            LoginType::Token => {
+                let token = match (&login.token, &login.url) {
+                    (Some(token), _) => expand_env_vars(token)?,
+                    (None, Some(url)) => {
+                        debug!("Requesting session token from {}", url);
+                        let response = client
+                            .post(url)
+                            .json(&fields)
+                            .send()
+                            .await
+                            .map_err(|e| format!("Login request failed: {}", e))?;
+                        let status = response.status();
+                        if !status.is_success() {
+                            return Err(format!("Login to {} failed (HTTP {})", url, status));
+                        }
+                        let body: serde_json::Value = response
+                            .json()
+                            .await
+                            .map_err(|e| format!("Login response is not JSON: {}", e))?;
+                        body.pointer(login.token_pointer())
+                            .and_then(|v| v.as_str())
+                            .ok_or_else(|| {
+                                format!("No token at {} in login response", login.token_pointer())
+                            })?
+                            .to_string()
+                    }
+                    (None, None) => return Err("Token login needs a token or a url".to_string()),
+                };
+                *self.token_header.write().unwrap_or_else(|e| e.into_inner()) = Some((
+                    login.header_name().to_string(),
+                    format!("{}{}", login.token_prefix(), token),
+                ));
+            }
+        }
+
+        info!("Logged in");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookies_persist_across_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").join("portal.json");
+        let url = Url::parse("https://portal.example.gov/login").unwrap();
+
+        let cookies = SessionCookies::load(Some(path.clone()));
+        assert!(cookies.is_empty());
+        let header = HeaderValue::from_static("sid=abc123; Path=/");
+        cookies.set_cookies(&mut std::iter::once(&header), &url);
+        cookies.save_if_changed();
+        assert!(path.exists());
+
+        let restored = SessionCookies::load(Some(path));
+        assert!(!restored.is_empty());
+        let doc = Url::parse("https://portal.example.gov/records/1").unwrap();
+        assert_eq!(
+            restored.cookies(&doc).unwrap().to_str().unwrap(),
+            "sid=abc123"
+        );
+    }
+
+    #[test]
+    fn test_newer_cookie_replaces_stored() {
+        let cookies = SessionCookies::load(None);
+        let url = Url::parse("https://portal.example.gov/").unwrap();
+        for value in ["sid=old; Path=/", "sid=new; Path=/"] {
+            let header = HeaderValue::from_static(value);
+            cookies.set_cookies(&mut std::iter::once(&header), &url);
+        }
+        let stored = cookies.stored.lock().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored.values().all(|c| c.cookie.starts_with("sid=new")));
+    }
+
+    #[test]
+    fn test_login_pending_until_logged_in() {
+        let config = SessionConfig {
+            login: Some(LoginConfig {
+                url: Some("https://portal.example.gov/login".into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let session = Session::new(&config);
+        assert!(!session.login_attempted.load(Ordering::Acquire));
+
+        let no_login = Session::new(&SessionConfig::default());
+        assert!(no_login.login_attempted.load(Ordering::Acquire));
+    }
+}
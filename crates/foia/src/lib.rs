@@ -6,6 +6,7 @@
 // not Result<Self, Error> as std::str::FromStr requires.
 #![allow(clippy::should_implement_trait)]
 
+pub mod artifacts;
 #[cfg(feature = "browser")]
 pub mod browser;
 pub mod config;
@@ -13,14 +14,17 @@ pub mod config;
 pub mod gis_data;
 pub mod http_client;
 pub mod llm;
+pub mod metrics;
 pub mod migrations;
 pub mod models;
+pub mod page_images;
 pub mod prefer_db;
 pub mod privacy;
 pub mod rate_limit;
 pub mod repository;
 pub mod schema;
 pub mod services;
+pub mod shutdown;
 pub mod storage;
 pub mod utils;
 pub mod work_queue;
@@ -0,0 +1,366 @@
+//! LLM spending limits and cost estimation.
+//!
+//! Budgets cap tokens, requests and estimated cost for a single run and for
+//! the calendar month. Once a limit is reached the client refuses further
+//! calls with [`LlmError::BudgetExceeded`] rather than reaching the
+//! provider, so a misconfigured paid API can't run up a bill across a whole
+//! archive. Limits are checked before each call, so the call that crosses a
+//! limit still completes.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::config::LlmProvider;
+use super::LlmError;
+use crate::models::{month_start, LlmUsageEntry, TokenUsage, UsageTotals};
+use crate::repository::{DieselError, DieselLlmUsageRepository};
+
+/// List prices of common hosted models in US dollars per million input and
+/// output tokens. Override or extend with `llm.budget.prices`.
+const KNOWN_PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("llama-3.1-8b-instant", 0.05, 0.08),
+    ("llama-3.3-70b-versatile", 0.59, 0.79),
+    ("meta-llama/Meta-Llama-3.1-70B-Instruct-Turbo", 0.88, 0.88),
+];
+
+/// Price of a model in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ModelPrice {
+    /// Price per million prompt tokens.
+    pub input: f64,
+    /// Price per million completion tokens.
+    pub output: f64,
+}
+
+impl ModelPrice {
+    pub const FREE: Self = Self {
+        input: 0.0,
+        output: 0.0,
+    };
+
+    /// Cost of a call in US dollars.
+    pub fn cost(&self, usage: TokenUsage) -> f64 {
+        (usage.prompt_tokens as f64 * self.input + usage.completion_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+/// Limits over one window. Unset limits are not enforced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct BudgetLimits {
+    /// Maximum prompt plus completion tokens.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub tokens: Option<u64>,
+    /// Maximum number of calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub requests: Option<u64>,
+    /// Maximum estimated cost in US dollars.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub cost_usd: Option<f64>,
+}
+
+impl BudgetLimits {
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_none() && self.requests.is_none() && self.cost_usd.is_none()
+    }
+
+    /// Describe the first limit `totals` has reached, if any.
+    pub fn exceeded(&self, totals: &UsageTotals) -> Option<String> {
+        if let Some(max) = self.requests.filter(|max| totals.requests >= *max) {
+            return Some(format!("{} of {} requests used", totals.requests, max));
+        }
+        if let Some(max) = self.tokens.filter(|max| totals.tokens() >= *max) {
+            return Some(format!("{} of {} tokens used", totals.tokens(), max));
+        }
+        if let Some(max) = self.cost_usd.filter(|max| totals.cost_usd >= *max) {
+            return Some(format!("${:.2} of ${:.2} spent", totals.cost_usd, max));
+        }
+        None
+    }
+}
+
+/// Budget settings for LLM analysis (`llm.budget`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct LlmBudgetConfig {
+    /// Limits for a single run. A run is one annotation batch; in daemon
+    /// mode each cycle starts a new run.
+    #[serde(default, skip_serializing_if = "BudgetLimits::is_empty")]
+    #[prefer(default)]
+    pub run: BudgetLimits,
+    /// Limits for the calendar month (UTC), across all runs and devices
+    /// sharing the database.
+    #[serde(default, skip_serializing_if = "BudgetLimits::is_empty")]
+    #[prefer(default)]
+    pub monthly: BudgetLimits,
+    /// Model prices, keyed by model ID. Take precedence over the built-in
+    /// list prices.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[prefer(default)]
+    pub prices: HashMap<String, ModelPrice>,
+}
+
+impl LlmBudgetConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Price of `model`, or `None` if unknown. Ollama runs locally and is free.
+    pub fn price(&self, provider: &LlmProvider, model: &str) -> Option<ModelPrice> {
+        if let Some(price) = self.prices.get(model) {
+            return Some(*price);
+        }
+        if *provider == LlmProvider::Ollama {
+            return Some(ModelPrice::FREE);
+        }
+        KNOWN_PRICES
+            .iter()
+            .find(|(name, _, _)| *name == model)
+            .map(|&(_, input, output)| ModelPrice { input, output })
+    }
+
+    /// Estimated cost of a call, or `None` if the model's price is unknown.
+    pub fn estimate_cost(
+        &self,
+        provider: &LlmProvider,
+        model: &str,
+        usage: TokenUsage,
+    ) -> Option<f64> {
+        self.price(provider, model).map(|p| p.cost(usage))
+    }
+
+    fn limits_cost(&self) -> bool {
+        self.run.cost_usd.is_some() || self.monthly.cost_usd.is_some()
+    }
+}
+
+struct TrackerState {
+    run: UsageTotals,
+    month: UsageTotals,
+    month_start: DateTime<Utc>,
+}
+
+impl TrackerState {
+    /// Start counting a new month once the calendar turns over.
+    fn roll_month(&mut self, now: DateTime<Utc>) {
+        let start = month_start(now);
+        if start != self.month_start {
+            self.month = UsageTotals::default();
+            self.month_start = start;
+        }
+    }
+}
+
+/// Counts usage for a run and enforces the budget.
+///
+/// Shared by every client in a run. With a repository each call is also
+/// recorded in `llm_usage`, and the month's earlier usage counts toward
+/// the monthly limits.
+pub struct UsageTracker {
+    budget: LlmBudgetConfig,
+    repo: Option<DieselLlmUsageRepository>,
+    state: Mutex<TrackerState>,
+}
+
+impl UsageTracker {
+    /// Track usage in memory only; monthly limits see this run alone.
+    pub fn new(budget: LlmBudgetConfig) -> Self {
+        let now = Utc::now();
+        Self {
+            budget,
+            repo: None,
+            state: Mutex::new(TrackerState {
+                run: UsageTotals::default(),
+                month: UsageTotals::default(),
+                month_start: month_start(now),
+            }),
+        }
+    }
+
+    /// Track usage and record it, starting from the month-to-date totals.
+    pub async fn with_repository(
+        budget: LlmBudgetConfig,
+        repo: DieselLlmUsageRepository,
+    ) -> Result<Self, DieselError> {
+        let start = month_start(Utc::now());
+        let month = repo.totals_since(start).await?;
+        let tracker = Self::new(budget);
+        tracker.state.lock().unwrap().month = month;
+        Ok(Self {
+            repo: Some(repo),
+            ..tracker
+        })
+    }
+
+    pub fn budget(&self) -> &LlmBudgetConfig {
+        &self.budget
+    }
+
+    /// Usage so far in this run.
+    pub fn run_totals(&self) -> UsageTotals {
+        self.state.lock().unwrap().run
+    }
+
+    /// Usage so far this month, including earlier runs.
+    pub fn month_totals(&self) -> UsageTotals {
+        let mut state = self.state.lock().unwrap();
+        state.roll_month(Utc::now());
+        state.month
+    }
+
+    /// Fail if a limit has been reached, or if a cost limit is set and
+    /// the model's price is unknown.
+    pub fn check(&self, provider: &LlmProvider, model: &str) -> Result<(), LlmError> {
+        if self.budget.limits_cost() && self.budget.price(provider, model).is_none() {
+            return Err(LlmError::BudgetExceeded(format!(
+                "no price known for model '{}'; add it to llm.budget.prices",
+                model
+            )));
+        }
+        let mut state = self.state.lock().unwrap();
+        state.roll_month(Utc::now());
+        if let Some(reason) = self.budget.run.exceeded(&state.run) {
+            return Err(LlmError::BudgetExceeded(format!("run budget: {}", reason)));
+        }
+        if let Some(reason) = self.budget.monthly.exceeded(&state.month) {
+            return Err(LlmError::BudgetExceeded(format!(
+                "monthly budget: {}",
+                reason
+            )));
+        }
+        Ok(())
+    }
+
+    /// Count a completed call and record it.
+    pub async fn record(
+        &self,
+        provider: &LlmProvider,
+        model: &str,
+        operation: &str,
+        usage: TokenUsage,
+    ) {
+        let now = Utc::now();
+        let cost_usd = self.budget.estimate_cost(provider, model, usage);
+        {
+            let mut state = self.state.lock().unwrap();
+            state.roll_month(now);
+            state.run.add(usage, cost_usd);
+            state.month.add(usage, cost_usd);
+        }
+
+        if let Some(ref repo) = self.repo {
+            let entry = LlmUsageEntry {
+                provider: provider.as_str().to_string(),
+                model: model.to_string(),
+                operation: operation.to_string(),
+                usage,
+                cost_usd,
+                created_at: now,
+            };
+            if let Err(e) = repo.record(&entry).await {
+                tracing::warn!("Failed to record LLM usage: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+        }
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let mut budget = LlmBudgetConfig::default();
+        let cost = budget
+            .estimate_cost(
+                &LlmProvider::OpenAI,
+                "gpt-4o-mini",
+                usage(1_000_000, 500_000),
+            )
+            .unwrap();
+        assert!((cost - 0.45).abs() < 1e-9);
+        assert_eq!(
+            budget.estimate_cost(&LlmProvider::Ollama, "llama3", usage(5000, 500)),
+            Some(0.0)
+        );
+        assert_eq!(
+            budget.estimate_cost(&LlmProvider::OpenAI, "unlisted", usage(10, 10)),
+            None
+        );
+
+        budget.prices.insert(
+            "unlisted".to_string(),
+            ModelPrice {
+                input: 1.0,
+                output: 2.0,
+            },
+        );
+        let cost = budget
+            .estimate_cost(
+                &LlmProvider::OpenAI,
+                "unlisted",
+                usage(1_000_000, 1_000_000),
+            )
+            .unwrap();
+        assert!((cost - 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_budget_hard_stop() {
+        let budget = LlmBudgetConfig {
+            run: BudgetLimits {
+                tokens: Some(2500),
+                ..Default::default()
+            },
+            monthly: BudgetLimits {
+                requests: Some(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let tracker = UsageTracker::new(budget);
+        let provider = LlmProvider::OpenAI;
+
+        tracker.check(&provider, "gpt-4o-mini").unwrap();
+        tracker
+            .record(&provider, "gpt-4o-mini", "synopsis", usage(1000, 200))
+            .await;
+        tracker.check(&provider, "gpt-4o-mini").unwrap();
+        tracker
+            .record(&provider, "gpt-4o-mini", "tags", usage(1000, 300))
+            .await;
+        let err = tracker.check(&provider, "gpt-4o-mini").unwrap_err();
+        assert!(matches!(err, LlmError::BudgetExceeded(_)));
+        assert!(err.to_string().contains("run budget"));
+        assert_eq!(tracker.run_totals().requests, 2);
+        assert!(tracker.run_totals().cost_usd > 0.0);
+
+        let costly = LlmBudgetConfig {
+            monthly: BudgetLimits {
+                cost_usd: Some(5.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let tracker = UsageTracker::new(costly);
+        assert!(tracker.check(&provider, "unlisted").is_err());
+        assert!(tracker.check(&LlmProvider::Ollama, "unlisted").is_ok());
+    }
+}
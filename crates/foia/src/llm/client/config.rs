@@ -11,6 +11,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::budget::LlmBudgetConfig;
 use super::prompts::{DEFAULT_SYNOPSIS_PROMPT, DEFAULT_TAGS_PROMPT};
 
 /// LLM provider type.
@@ -53,6 +54,13 @@ impl LlmProvider {
             _ => None,
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ollama => "ollama",
+            Self::OpenAI => "openai",
+        }
+    }
 }
 
 /// Application-level LLM config (stored in DB, synced across devices).
@@ -83,6 +91,18 @@ pub struct LlmAppConfig {
     #[serde(default = "default_max_content_chars")]
     #[prefer(default)]
     pub max_content_chars: usize,
+    /// Ask the provider for JSON output on structured requests
+    #[serde(default = "default_json_mode")]
+    #[prefer(default)]
+    pub json_mode: bool,
+    /// Retries with a repair prompt when a response fails validation
+    #[serde(default = "default_repair_attempts")]
+    #[prefer(default)]
+    pub repair_attempts: u32,
+    /// Token, request and cost limits for LLM analysis
+    #[serde(default, skip_serializing_if = "LlmBudgetConfig::is_default")]
+    #[prefer(default)]
+    pub budget: LlmBudgetConfig,
 }
 
 /// Device-level LLM config (from env vars, varies per device).
@@ -166,6 +186,14 @@ fn default_max_content_chars() -> usize {
     12000
 }
 
+fn default_json_mode() -> bool {
+    true
+}
+
+fn default_repair_attempts() -> u32 {
+    1
+}
+
 // === LlmAppConfig implementations ===
 
 impl Default for LlmAppConfig {
@@ -177,6 +205,9 @@ impl Default for LlmAppConfig {
             synopsis_prompt: None,
             tags_prompt: None,
             max_content_chars: default_max_content_chars(),
+            json_mode: default_json_mode(),
+            repair_attempts: default_repair_attempts(),
+            budget: LlmBudgetConfig::default(),
         }
     }
 }
@@ -245,8 +276,7 @@ impl LlmDeviceConfig {
         }
 
         // Explicit API key always wins
-        if let Ok(val) = std::env::var("ANNOTATE_API_KEY")
-            .or_else(|_| std::env::var("LLM_API_KEY"))
+        if let Ok(val) = std::env::var("ANNOTATE_API_KEY").or_else(|_| std::env::var("LLM_API_KEY"))
         {
             config.api_key = Some(val);
         }
@@ -410,6 +440,18 @@ impl LlmConfig {
         self.app.max_content_chars
     }
 
+    pub fn json_mode(&self) -> bool {
+        self.app.json_mode
+    }
+
+    pub fn repair_attempts(&self) -> u32 {
+        self.app.repair_attempts
+    }
+
+    pub fn budget(&self) -> &LlmBudgetConfig {
+        &self.app.budget
+    }
+
     pub fn get_synopsis_prompt(&self) -> &str {
         self.app.get_synopsis_prompt()
     }
@@ -472,6 +514,9 @@ impl LlmConfigLegacy {
             synopsis_prompt: self.synopsis_prompt,
             tags_prompt: self.tags_prompt,
             max_content_chars: self.max_content_chars,
+            json_mode: default_json_mode(),
+            repair_attempts: default_repair_attempts(),
+            budget: LlmBudgetConfig::default(),
         };
         // Device config always comes from env, ignoring legacy provider/endpoint/model/key
         let device = LlmDeviceConfig::from_env();
@@ -487,6 +532,9 @@ impl LlmConfigLegacy {
             synopsis_prompt: self.synopsis_prompt.clone(),
             tags_prompt: self.tags_prompt.clone(),
             max_content_chars: self.max_content_chars,
+            json_mode: default_json_mode(),
+            repair_attempts: default_repair_attempts(),
+            budget: LlmBudgetConfig::default(),
         }
     }
 }
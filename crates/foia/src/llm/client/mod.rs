@@ -4,17 +4,23 @@
 
 #![allow(dead_code)]
 
+mod budget;
 mod config;
 mod prompts;
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
 use std::time::Duration;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 use crate::http_client::HttpClient;
+use crate::metrics;
+use crate::models::{ExtractedMetadata, RecordType, TokenUsage, VirtualFile};
 use crate::privacy::PrivacyConfig;
 
+pub use budget::{BudgetLimits, LlmBudgetConfig, ModelPrice, UsageTracker};
 pub use config::{LlmConfig, LlmProvider};
 
 /// Result of summarizing a document.
@@ -26,10 +32,53 @@ pub struct SummarizeResult {
     pub tags: Vec<String>,
 }
 
+/// Result of classifying a document's record type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordTypeClassification {
+    pub record_type: RecordType,
+    /// Model-reported confidence, clamped to 0.0-1.0.
+    pub confidence: f32,
+}
+
+/// A well-formed tag: lowercase words joined by hyphens or underscores,
+/// optionally namespaced (`agency:fbi`).
+static TAG_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[a-z0-9]+(?:[-_][a-z0-9]+){0,5}(?::[a-z0-9]+(?:[-_][a-z0-9]+){0,5})?$")
+        .expect("valid regex")
+});
+
+/// Bullet or number in front of a list item (`- `, `* `, `2. `, `3) `).
+static LIST_MARKER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?:[-*•#]+|\d+[.)])\s+").expect("valid regex"));
+
+/// Tags too vague to help anyone find a document; dropped without
+/// counting against the response.
+const GENERIC_TAGS: &[&str] = &[
+    "document",
+    "documents",
+    "government",
+    "information",
+    "record",
+    "records",
+    "tag",
+    "tags",
+    "none",
+];
+
+/// Longest tag accepted.
+const MAX_TAG_LEN: usize = 50;
+
+/// Most words a tag may have before it is treated as prose.
+const MAX_TAG_WORDS: usize = 4;
+
+/// Most tags kept from a response.
+const MAX_TAGS: usize = 10;
+
 /// LLM client for document processing.
 pub struct LlmClient {
     config: LlmConfig,
     privacy: Option<PrivacyConfig>,
+    usage: Option<Arc<UsageTracker>>,
 }
 
 // ============================================================================
@@ -42,6 +91,9 @@ struct OllamaRequest {
     model: String,
     prompt: String,
     stream: bool,
+    /// `"json"` constrains the output to valid JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
     options: OllamaOptions,
 }
 
@@ -57,6 +109,10 @@ struct OllamaResponse {
     response: String,
     #[allow(dead_code)]
     done: bool,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
 }
 
 // ============================================================================
@@ -69,6 +125,15 @@ struct OpenAIRequest {
     messages: Vec<OpenAIMessage>,
     max_tokens: u32,
     temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<OpenAIResponseFormat>,
+}
+
+/// JSON mode for OpenAI-compatible APIs (`{"type": "json_object"}`).
+#[derive(Debug, Serialize)]
+struct OpenAIResponseFormat {
+    #[serde(rename = "type")]
+    kind: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -80,6 +145,14 @@ struct OpenAIMessage {
 #[derive(Debug, Deserialize)]
 struct OpenAIResponse {
     choices: Vec<OpenAIChoice>,
+    #[serde(default)]
+    usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIUsage {
+    prompt_tokens: u64,
+    completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -101,6 +174,7 @@ impl LlmClient {
         Self {
             config,
             privacy: None,
+            usage: None,
         }
     }
 
@@ -112,9 +186,17 @@ impl LlmClient {
         Self {
             config,
             privacy: Some(privacy),
+            usage: None,
         }
     }
 
+    /// Count calls against a usage tracker, refusing them once its budget
+    /// is spent.
+    pub fn with_usage_tracker(mut self, tracker: Arc<UsageTracker>) -> Self {
+        self.usage = Some(tracker);
+        self
+    }
+
     /// Get the config.
     pub fn config(&self) -> &LlmConfig {
         &self.config
@@ -252,7 +334,7 @@ impl LlmClient {
             .replace("{content}", truncated);
 
         debug!("Generating synopsis for: {}", title);
-        let response = self.call_llm(&prompt).await?;
+        let response = self.call_llm("synopsis", &prompt, false).await?;
 
         // Clean up the response
         let synopsis = response.trim().to_string();
@@ -273,15 +355,14 @@ impl LlmClient {
             .replace("{content}", truncated);
 
         debug!("Generating tags for: {}", title);
-        let response = self.call_llm(&prompt).await?;
-
-        // Parse tags from response
-        let tags = self.parse_tags(&response);
-        if tags.is_empty() {
-            return Err(LlmError::Parse("No tags parsed from response".to_string()));
-        }
-
-        Ok(tags)
+        self.call_validated(
+            "tags",
+            &prompt,
+            false,
+            "3-5 comma-separated lowercase tags, with hyphens joining multi-word tags. Example: cia, mind-control, mkultra, memo, cold-war",
+            |response| self.validate_tags(response),
+        )
+        .await
     }
 
     /// Summarize a document (generates both synopsis and tags sequentially).
@@ -295,6 +376,140 @@ impl LlmClient {
         Ok(SummarizeResult { synopsis, tags })
     }
 
+    /// Summarize an archive from its members' synopses.
+    ///
+    /// Members without a synopsis are ignored. The overview comes from one
+    /// LLM call over the member synopses, truncated to the content limit;
+    /// tags are the ones most common across members.
+    pub async fn summarize_archive(
+        &self,
+        title: &str,
+        members: &[VirtualFile],
+    ) -> Result<SummarizeResult, LlmError> {
+        info!("Summarizing archive: {} ({} files)", title, members.len());
+
+        let summarized: Vec<&VirtualFile> =
+            members.iter().filter(|m| m.synopsis.is_some()).collect();
+        if summarized.is_empty() {
+            return Err(LlmError::Parse(
+                "No member synopses to summarize".to_string(),
+            ));
+        }
+
+        let max = self.config.max_content_chars();
+        let mut summaries = String::new();
+        for (i, member) in summarized.iter().enumerate() {
+            let line = format!(
+                "- {}: {}\n",
+                member.archive_path,
+                member.synopsis.as_deref().unwrap_or_default().trim()
+            );
+            if summaries.len() + line.len() > max {
+                summaries.push_str(&format!("- ... and {} more files\n", summarized.len() - i));
+                break;
+            }
+            summaries.push_str(&line);
+        }
+
+        let prompt = prompts::ARCHIVE_SYNOPSIS_PROMPT
+            .replace("{count}", &summarized.len().to_string())
+            .replace("{title}", title)
+            .replace("{summaries}", &summaries);
+
+        debug!("Generating archive overview for: {}", title);
+        let response = self.call_llm("archive_synopsis", &prompt, false).await?;
+        let synopsis = response.trim().to_string();
+        if synopsis.is_empty() {
+            return Err(LlmError::Parse("Empty synopsis response".to_string()));
+        }
+
+        let tags = roll_up_tags(summarized.iter().map(|m| m.tags.as_slice()));
+        Ok(SummarizeResult { synopsis, tags })
+    }
+
+    /// Answer a question from `pages`, already labelled for citation.
+    pub async fn answer_question(&self, question: &str, pages: &str) -> Result<String, LlmError> {
+        let prompt = prompts::ASK_PROMPT
+            .replace("{question}", question)
+            .replace("{pages}", pages);
+
+        debug!("Answering question: {}", question);
+        let response = self.call_llm("ask", &prompt, false).await?;
+        let answer = response.trim().to_string();
+        if answer.is_empty() {
+            return Err(LlmError::Parse("Empty answer response".to_string()));
+        }
+        Ok(answer)
+    }
+
+    /// Suggest a title for a document from its text.
+    pub async fn suggest_title(&self, text: &str, title: &str) -> Result<String, LlmError> {
+        let truncated = self.truncate_content(text);
+        let prompt = prompts::TITLE_PROMPT
+            .replace("{title}", title)
+            .replace("{content}", truncated);
+
+        debug!("Suggesting title for: {}", title);
+        let response = self.call_llm("title", &prompt, false).await?;
+        let suggested = response
+            .lines()
+            .map(|line| line.trim().trim_matches(['"', '\'']).trim_end_matches('.'))
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string();
+        if suggested.is_empty() {
+            return Err(LlmError::Parse("Empty title response".to_string()));
+        }
+        Ok(suggested)
+    }
+
+    /// Classify a document into a controlled-vocabulary record type.
+    pub async fn classify_record_type(
+        &self,
+        text: &str,
+        title: &str,
+    ) -> Result<RecordTypeClassification, LlmError> {
+        let truncated = self.truncate_content(text);
+        let record_types: Vec<&str> = RecordType::all().iter().map(|t| t.as_str()).collect();
+        let prompt = prompts::DEFAULT_CLASSIFY_PROMPT
+            .replace("{record_types}", &record_types.join(", "))
+            .replace("{title}", title)
+            .replace("{content}", truncated);
+
+        debug!("Classifying record type for: {}", title);
+        let expected = format!(
+            r#"a JSON object like {{"record_type": "contract", "confidence": 0.85}} where record_type is exactly one of: {}"#,
+            record_types.join(", ")
+        );
+        self.call_validated("classify", &prompt, true, &expected, |response| {
+            self.parse_classification(response)
+        })
+        .await
+    }
+
+    /// Extract structured metadata fields (document date, originating
+    /// agency/office, case number, classification markings) from a document.
+    pub async fn extract_metadata(
+        &self,
+        text: &str,
+        title: &str,
+    ) -> Result<ExtractedMetadata, LlmError> {
+        let truncated = self.truncate_content(text);
+        let prompt = prompts::DEFAULT_EXTRACT_METADATA_PROMPT
+            .replace("{title}", title)
+            .replace("{content}", truncated);
+
+        debug!("Extracting metadata for: {}", title);
+        self.call_validated(
+            "metadata",
+            &prompt,
+            true,
+            "a JSON object with exactly the keys document_date, originating_agency, originating_office, case_number and classification_markings",
+            |response| self.parse_extracted_metadata(response),
+        )
+        .await
+    }
+
     /// Expand search terms using LLM to generate related terms.
     /// Takes seed terms and a domain description, returns expanded list.
     pub async fn expand_search_terms(
@@ -326,7 +541,7 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
         );
 
         debug!("Expanding search terms for: {}", domain);
-        let response = self.call_llm(&prompt).await?;
+        let response = self.call_llm("expand_search", &prompt, false).await?;
 
         // Parse the response into individual terms
         let expanded: Vec<String> = response
@@ -345,28 +560,97 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
 
     /// Truncate content to configured maximum (UTF-8 safe).
     fn truncate_content<'a>(&self, text: &'a str) -> &'a str {
-        let max_chars = self.config.max_content_chars();
-        if text.len() <= max_chars {
-            return text;
-        }
-        // Find a valid UTF-8 boundary at or before max_content_chars
-        let mut end = max_chars;
-        while end > 0 && !text.is_char_boundary(end) {
-            end -= 1;
+        truncate_chars(text, self.config.max_content_chars())
+    }
+
+    /// Call the LLM and validate its response with `parse`.
+    ///
+    /// A response that fails validation is sent back with a repair prompt
+    /// describing what was `expected`, up to `repair_attempts` times.
+    /// Failures are counted in `foia_llm_validation_failures_total`.
+    async fn call_validated<T>(
+        &self,
+        operation: &str,
+        prompt: &str,
+        json: bool,
+        expected: &str,
+        parse: impl Fn(&str) -> Result<T, LlmError>,
+    ) -> Result<T, LlmError> {
+        let mut response = self.call_llm(operation, prompt, json).await?;
+        let repair_operation = format!("{}_repair", operation);
+        let mut attempt = 0;
+        loop {
+            let error = match parse(&response) {
+                Ok(value) => {
+                    if attempt > 0 {
+                        metrics::LLM_VALIDATION_FAILURES.inc(&[operation, "repaired"]);
+                    }
+                    return Ok(value);
+                }
+                Err(LlmError::Parse(reason)) | Err(LlmError::Validation(reason)) => reason,
+                Err(e) => return Err(e),
+            };
+            if attempt >= self.config.repair_attempts() {
+                metrics::LLM_VALIDATION_FAILURES.inc(&[operation, "rejected"]);
+                return Err(LlmError::Validation(format!("{}: {}", operation, error)));
+            }
+            attempt += 1;
+            warn!(
+                "Invalid {} response ({}), retrying with repair prompt",
+                operation, error
+            );
+            let repair = prompts::REPAIR_PROMPT
+                .replace("{error}", &error)
+                .replace("{expected}", expected)
+                .replace("{response}", truncate_chars(&response, 4000));
+            response = self.call_llm(&repair_operation, &repair, json).await?;
         }
-        &text[..end]
     }
 
     /// Call LLM API with a prompt (provider-aware).
-    async fn call_llm(&self, prompt: &str) -> Result<String, LlmError> {
-        match self.config.provider() {
-            LlmProvider::Ollama => self.call_ollama(prompt).await,
-            LlmProvider::OpenAI => self.call_openai(prompt).await,
+    ///
+    /// `operation` labels the call in the usage log. `json` requests JSON
+    /// output where the provider supports it and `json_mode` is on.
+    async fn call_llm(
+        &self,
+        operation: &str,
+        prompt: &str,
+        json: bool,
+    ) -> Result<String, LlmError> {
+        let provider = self.config.provider();
+        if let Some(ref tracker) = self.usage {
+            tracker.check(provider, self.config.model())?;
         }
+        let start = std::time::Instant::now();
+        let json = json && self.config.json_mode();
+        let result = match provider {
+            LlmProvider::Ollama => self.call_ollama(prompt, json).await,
+            LlmProvider::OpenAI => self.call_openai(prompt, json).await,
+        };
+        let outcome = if result.is_ok() { "success" } else { "error" };
+        metrics::LLM_CALLS.inc(&[provider.as_str(), outcome]);
+        metrics::LLM_CALL_DURATION.observe(&[provider.as_str()], start.elapsed().as_secs_f64());
+
+        let (response, usage) = result?;
+        metrics::LLM_TOKENS.inc_by(&[provider.as_str(), "prompt"], usage.prompt_tokens as f64);
+        metrics::LLM_TOKENS.inc_by(
+            &[provider.as_str(), "completion"],
+            usage.completion_tokens as f64,
+        );
+        if let Some(ref tracker) = self.usage {
+            tracker
+                .record(provider, self.config.model(), operation, usage)
+                .await;
+        }
+        Ok(response)
     }
 
     /// Call Ollama API with a prompt.
-    async fn call_ollama(&self, prompt: &str) -> Result<String, LlmError> {
+    async fn call_ollama(
+        &self,
+        prompt: &str,
+        json: bool,
+    ) -> Result<(String, TokenUsage), LlmError> {
         let client = self
             .create_client()
             .map_err(|e| LlmError::Connection(e.to_string()))?;
@@ -375,6 +659,7 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
             model: self.config.model().to_string(),
             prompt: prompt.to_string(),
             stream: false,
+            format: json.then(|| "json".to_string()),
             options: OllamaOptions {
                 temperature: self.config.temperature(),
                 num_predict: self.config.max_tokens(),
@@ -398,11 +683,22 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
             .await
             .map_err(|e| LlmError::Parse(e.to_string()))?;
 
-        Ok(ollama_resp.response)
+        let usage = match (ollama_resp.prompt_eval_count, ollama_resp.eval_count) {
+            (Some(prompt_tokens), Some(completion_tokens)) => TokenUsage {
+                prompt_tokens,
+                completion_tokens,
+            },
+            _ => TokenUsage::estimate(prompt, &ollama_resp.response),
+        };
+        Ok((ollama_resp.response, usage))
     }
 
     /// Call OpenAI-compatible API (Groq, Together.ai, OpenAI, etc.)
-    async fn call_openai(&self, prompt: &str) -> Result<String, LlmError> {
+    async fn call_openai(
+        &self,
+        prompt: &str,
+        json: bool,
+    ) -> Result<(String, TokenUsage), LlmError> {
         let client = self
             .create_client()
             .map_err(|e| LlmError::Connection(e.to_string()))?;
@@ -415,6 +711,9 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
             }],
             max_tokens: self.config.max_tokens(),
             temperature: self.config.temperature(),
+            response_format: json.then(|| OpenAIResponseFormat {
+                kind: "json_object".to_string(),
+            }),
         };
 
         let url = format!("{}/v1/chat/completions", self.config.endpoint());
@@ -439,16 +738,28 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
             .await
             .map_err(|e| LlmError::Parse(e.to_string()))?;
 
-        openai_resp
+        let content = openai_resp
             .choices
             .into_iter()
             .next()
             .map(|c| c.message.content)
-            .ok_or_else(|| LlmError::Parse("No response choices".to_string()))
+            .ok_or_else(|| LlmError::Parse("No response choices".to_string()))?;
+        let usage = match openai_resp.usage {
+            Some(u) => TokenUsage {
+                prompt_tokens: u.prompt_tokens,
+                completion_tokens: u.completion_tokens,
+            },
+            None => TokenUsage::estimate(prompt, &content),
+        };
+        Ok((content, usage))
     }
 
-    /// Parse tags from LLM response.
-    fn parse_tags(&self, response: &str) -> Vec<String> {
+    /// Split an LLM tag response into well-formed tags and rejected items.
+    ///
+    /// Accepts comma- or newline-separated lists with optional `Tags:`
+    /// prefix, brackets, quotes and bullets. Multi-word tags are joined with
+    /// hyphens; items longer than a few words are prose, not tags.
+    fn split_tags(&self, response: &str) -> (Vec<String>, Vec<String>) {
         // Remove common prefixes/formatting
         let cleaned = response
             .trim()
@@ -458,23 +769,204 @@ Focus on terms specifically relevant to {domain}. Return ONLY a comma-separated
             .trim_end_matches(']')
             .trim();
 
-        cleaned
-            .split(',')
-            .map(|t| {
-                t.trim()
-                    .to_lowercase()
-                    // Allow colons for hierarchical tags (agency:fbi, topic:surveillance)
-                    .trim_matches(|c: char| {
-                        !c.is_alphanumeric() && c != '-' && c != '_' && c != ':'
-                    })
-                    .to_string()
-            })
-            .filter(|t| !t.is_empty() && t.len() <= 50)
-            .take(10) // Max 10 tags
-            .collect()
+        let mut tags: Vec<String> = Vec::new();
+        let mut rejected = Vec::new();
+        for item in cleaned.split([',', '\n']) {
+            let item = LIST_MARKER.replace(item, "").to_lowercase();
+            // Allow colons for hierarchical tags (agency:fbi, topic:surveillance)
+            let item = item
+                .trim_matches(|c: char| !c.is_alphanumeric() && c != '-' && c != '_' && c != ':');
+            if item.is_empty() {
+                continue;
+            }
+            let words: Vec<&str> = item.split_whitespace().collect();
+            let tag = words.join("-");
+            if words.len() > MAX_TAG_WORDS || tag.len() > MAX_TAG_LEN || !TAG_PATTERN.is_match(&tag)
+            {
+                rejected.push(item.to_string());
+            } else if !GENERIC_TAGS.contains(&tag.as_str()) && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        tags.truncate(MAX_TAGS);
+        (tags, rejected)
+    }
+
+    /// Parse tags from LLM response, dropping malformed items.
+    fn parse_tags(&self, response: &str) -> Vec<String> {
+        self.split_tags(response).0
+    }
+
+    /// Parse tags, failing when the response is mostly not tags.
+    fn validate_tags(&self, response: &str) -> Result<Vec<String>, LlmError> {
+        let (tags, rejected) = self.split_tags(response);
+        if tags.is_empty() {
+            return Err(LlmError::Validation(
+                "no usable tags in response".to_string(),
+            ));
+        }
+        if rejected.len() > tags.len() {
+            return Err(LlmError::Validation(format!(
+                "{} of {} items are not tags, e.g. '{}'",
+                rejected.len(),
+                rejected.len() + tags.len(),
+                truncate_chars(&rejected[0], 80)
+            )));
+        }
+        Ok(tags)
+    }
+
+    /// Parse a record type classification from an LLM response.
+    ///
+    /// Expects a JSON object, but tolerates surrounding prose and falls back
+    /// to treating a bare response as the record type name.
+    fn parse_classification(&self, response: &str) -> Result<RecordTypeClassification, LlmError> {
+        #[derive(Deserialize)]
+        struct RawClassification {
+            record_type: String,
+            #[serde(default)]
+            confidence: Option<f32>,
+        }
+
+        let trimmed = response.trim();
+        let json_span = match (trimmed.find('{'), trimmed.rfind('}')) {
+            (Some(start), Some(end)) if start < end => Some(&trimmed[start..=end]),
+            _ => None,
+        };
+
+        let (name, confidence) = match json_span {
+            Some(span) => {
+                let raw: RawClassification = serde_json::from_str(span)
+                    .map_err(|e| LlmError::Parse(format!("Invalid classification JSON: {}", e)))?;
+                (raw.record_type, raw.confidence)
+            }
+            None => (
+                trimmed
+                    .trim_matches(|c: char| !c.is_alphanumeric())
+                    .to_string(),
+                None,
+            ),
+        };
+
+        let record_type = RecordType::from_str(&name)
+            .ok_or_else(|| LlmError::Parse(format!("Unknown record type: '{}'", name)))?;
+
+        Ok(RecordTypeClassification {
+            record_type,
+            confidence: confidence.unwrap_or(0.5).clamp(0.0, 1.0),
+        })
+    }
+
+    /// Parse extracted metadata from an LLM response.
+    ///
+    /// Placeholder values ("unknown", "N/A", "") are treated as missing, and
+    /// partial dates (YYYY-MM, YYYY) resolve to the first day of the period.
+    fn parse_extracted_metadata(&self, response: &str) -> Result<ExtractedMetadata, LlmError> {
+        #[derive(Deserialize)]
+        struct RawMetadata {
+            #[serde(default)]
+            document_date: Option<String>,
+            #[serde(default)]
+            originating_agency: Option<String>,
+            #[serde(default)]
+            originating_office: Option<String>,
+            #[serde(default)]
+            case_number: Option<String>,
+            #[serde(default)]
+            classification_markings: Option<Vec<String>>,
+        }
+
+        fn clean(value: Option<String>) -> Option<String> {
+            let value = value?.trim().to_string();
+            match value.to_lowercase().as_str() {
+                "" | "null" | "none" | "unknown" | "n/a" | "na" => None,
+                _ => Some(value),
+            }
+        }
+
+        let trimmed = response.trim();
+        let span = match (trimmed.find('{'), trimmed.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+            _ => {
+                return Err(LlmError::Parse(
+                    "No JSON object in metadata response".to_string(),
+                ))
+            }
+        };
+
+        let raw: RawMetadata = serde_json::from_str(span)
+            .map_err(|e| LlmError::Parse(format!("Invalid metadata JSON: {}", e)))?;
+
+        let mut markings: Vec<String> = Vec::new();
+        for marking in raw.classification_markings.unwrap_or_default() {
+            if let Some(m) = clean(Some(marking)) {
+                let m = m.to_uppercase();
+                if !markings.contains(&m) {
+                    markings.push(m);
+                }
+            }
+        }
+
+        Ok(ExtractedMetadata {
+            document_date: clean(raw.document_date).and_then(|d| parse_partial_date(&d)),
+            originating_agency: clean(raw.originating_agency),
+            originating_office: clean(raw.originating_office),
+            case_number: clean(raw.case_number),
+            classification_markings: markings,
+        })
     }
 }
 
+/// Tags used by the most members, most common first; ties keep the order
+/// they were first seen in.
+fn roll_up_tags<'a>(member_tags: impl Iterator<Item = &'a [String]>) -> Vec<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for tags in member_tags {
+        for tag in tags {
+            match counts.iter_mut().find(|(t, _)| *t == tag.as_str()) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((tag, 1)),
+            }
+        }
+    }
+    // Stable sort keeps first-seen order among equal counts
+    counts.sort_by(|a, b| b.1.cmp(&a.1));
+    counts
+        .into_iter()
+        .take(MAX_TAGS)
+        .map(|(tag, _)| tag.to_string())
+        .collect()
+}
+
+/// At most `max` bytes of `s`, cut at a character boundary.
+fn truncate_chars(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Parse a full or partial ISO date (YYYY-MM-DD, YYYY-MM, YYYY).
+///
+/// Dates in the future are rejected since they can't be authoring dates.
+fn parse_partial_date(s: &str) -> Option<chrono::NaiveDate> {
+    use chrono::NaiveDate;
+
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d").ok())
+        .or_else(|| NaiveDate::parse_from_str(&format!("{}-01-01", s), "%Y-%m-%d").ok())?;
+
+    if date > chrono::Utc::now().date_naive() {
+        return None;
+    }
+    Some(date)
+}
+
 /// Errors that can occur during LLM operations.
 #[derive(Debug, thiserror::Error)]
 pub enum LlmError {
@@ -488,6 +980,10 @@ pub enum LlmError {
     ModelNotFound(String),
     #[error("LLM is disabled")]
     Disabled,
+    #[error("LLM budget exceeded: {0}")]
+    BudgetExceeded(String),
+    #[error("Invalid LLM response: {0}")]
+    Validation(String),
 }
 
 #[cfg(test)]
@@ -527,6 +1023,121 @@ mod tests {
         assert_eq!(tags, vec!["cia", "mkultra", "cold-war", "memo"]);
     }
 
+    #[test]
+    fn test_validate_tags() {
+        let client = LlmClient::new(LlmConfig::default());
+
+        // Bulleted lists, spaces and generic tags are normalized
+        let tags = client
+            .validate_tags("- Cold War\n- CIA\n2. mind control\n- document\n- cia")
+            .unwrap();
+        assert_eq!(tags, vec!["cold-war", "cia", "mind-control"]);
+
+        // A stray bad item is dropped
+        let tags = client
+            .validate_tags("fbi, surveillance, this is a sentence about the document, memo")
+            .unwrap();
+        assert_eq!(tags, vec!["fbi", "surveillance", "memo"]);
+
+        // Prose instead of tags fails validation
+        let err = client
+            .validate_tags(
+                "I'm sorry, but I cannot determine appropriate tags for this text without more context.",
+            )
+            .unwrap_err();
+        assert!(matches!(err, LlmError::Validation(_)));
+        assert!(client.validate_tags("").is_err());
+        assert!(client.validate_tags("document, information").is_err());
+    }
+
+    #[test]
+    fn test_roll_up_tags() {
+        let members: Vec<Vec<String>> = vec![
+            vec!["email".into(), "fbi".into()],
+            vec!["email".into(), "surveillance".into()],
+            vec!["memo".into(), "surveillance".into(), "email".into()],
+        ];
+        let tags = roll_up_tags(members.iter().map(|t| t.as_slice()));
+        assert_eq!(tags, vec!["email", "surveillance", "fbi", "memo"]);
+        assert!(roll_up_tags(std::iter::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_classification() {
+        let client = LlmClient::new(LlmConfig::default());
+
+        let result = client
+            .parse_classification(r#"{"record_type": "contract", "confidence": 0.85}"#)
+            .unwrap();
+        assert_eq!(result.record_type, RecordType::Contract);
+        assert!((result.confidence - 0.85).abs() < f32::EPSILON);
+
+        // JSON wrapped in prose, label-style record type
+        let result = client
+            .parse_classification(
+                "Here is the classification:\n{\"record_type\": \"Policy Memo\", \"confidence\": 1.7}",
+            )
+            .unwrap();
+        assert_eq!(result.record_type, RecordType::PolicyMemo);
+        assert_eq!(result.confidence, 1.0);
+
+        // Bare record type without JSON
+        let result = client.parse_classification("invoice.").unwrap();
+        assert_eq!(result.record_type, RecordType::Invoice);
+        assert_eq!(result.confidence, 0.5);
+
+        assert!(client.parse_classification("sonnet").is_err());
+    }
+
+    #[test]
+    fn test_parse_extracted_metadata() {
+        let client = LlmClient::new(LlmConfig::default());
+
+        let meta = client
+            .parse_extracted_metadata(
+                r#"{"document_date": "1963-11-22", "originating_agency": "Central Intelligence Agency",
+                    "originating_office": null, "case_number": "F-2017-01234",
+                    "classification_markings": ["secret", "SECRET", "NOFORN"]}"#,
+            )
+            .unwrap();
+        assert_eq!(
+            meta.document_date,
+            chrono::NaiveDate::from_ymd_opt(1963, 11, 22)
+        );
+        assert_eq!(
+            meta.originating_agency.as_deref(),
+            Some("Central Intelligence Agency")
+        );
+        assert_eq!(meta.originating_office, None);
+        assert_eq!(meta.case_number.as_deref(), Some("F-2017-01234"));
+        assert_eq!(meta.classification_markings, vec!["SECRET", "NOFORN"]);
+
+        // Partial date, placeholder values, missing keys
+        let meta = client
+            .parse_extracted_metadata(
+                "Sure:\n{\"document_date\": \"1975-06\", \"originating_agency\": \"unknown\"}",
+            )
+            .unwrap();
+        assert_eq!(
+            meta.document_date,
+            chrono::NaiveDate::from_ymd_opt(1975, 6, 1)
+        );
+        assert_eq!(meta.originating_agency, None);
+        assert!(meta.classification_markings.is_empty());
+
+        // Unparseable and future dates are dropped
+        let meta = client
+            .parse_extracted_metadata(r#"{"document_date": "sometime in 1962"}"#)
+            .unwrap();
+        assert!(meta.is_empty());
+        let meta = client
+            .parse_extracted_metadata(r#"{"document_date": "9999-01-01"}"#)
+            .unwrap();
+        assert!(meta.is_empty());
+
+        assert!(client.parse_extracted_metadata("no idea").is_err());
+    }
+
     #[test]
     fn test_default_config() {
         let config = LlmConfig::default();
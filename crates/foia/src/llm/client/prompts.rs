@@ -41,3 +41,105 @@ Document Content:
 {content}
 
 Respond with ONLY 3-5 comma-separated lowercase tags. Example: cia, mind-control, mkultra, memo, cold-war"#;
+
+/// Prompt for an archive overview built from the synopses of its members.
+pub const ARCHIVE_SYNOPSIS_PROMPT: &str = r#"You are analyzing a FOIA release that arrived as an archive of {count} files. Each file has already been summarized. Write an overview of the archive AS A WHOLE from these summaries.
+
+Your overview should answer:
+1. What is this collection ABOUT? (the common subject, investigation, or request it answers)
+2. What KINDS of records does it contain? (e.g. emails between two offices, weekly reports, case files)
+3. What are the most SIGNIFICANT findings across the files? Mention notable individual files only if they stand out.
+
+Archive Title: {title}
+
+File Summaries:
+{summaries}
+
+Respond with ONLY a 3-4 sentence overview of the whole archive. No formatting or preamble."#;
+
+/// Default prompt for classifying a document into a controlled-vocabulary record type.
+pub const DEFAULT_CLASSIFY_PROMPT: &str = r#"You are classifying a FOIA document by RECORD TYPE - what kind of record it is, not what it is about.
+
+Choose exactly ONE record type from this list:
+{record_types}
+
+Guidance:
+- correspondence: letters and formal written communication between parties
+- email: email messages or printed email threads
+- policy_memo: memoranda, directives, guidance, or policy statements
+- incident_report: reports describing a specific event or incident
+- report: studies, assessments, or other narrative reports
+- log: logs, registers, or tabular records of recurring entries
+- meeting_record: minutes, agendas, or notes of meetings
+- other: ONLY if none of the above fit
+
+Document Title: {title}
+
+Document Content:
+{content}
+
+Respond with ONLY a JSON object, no explanation. Example: {"record_type": "contract", "confidence": 0.85}
+The confidence is a number between 0 and 1 reflecting how sure you are."#;
+
+/// Default prompt for extracting structured metadata fields from a document.
+pub const DEFAULT_EXTRACT_METADATA_PROMPT: &str = r#"You are extracting structured metadata from a FOIA document. Read the document header, letterhead, dates, stamps, and markings.
+
+Extract these fields:
+- document_date: the date the document was WRITTEN or ISSUED (not a release, processing, or declassification date), as YYYY-MM-DD. Use YYYY-MM or YYYY if only partially known.
+- originating_agency: the agency that produced the document (e.g. "Federal Bureau of Investigation")
+- originating_office: the office, division, or field office within that agency (e.g. "New York Field Office")
+- case_number: the FOIA request or case tracking number, if shown (e.g. "FOIPA 1234567-000")
+- classification_markings: classification and handling markings on the document (e.g. ["SECRET", "NOFORN"]), or [] if none
+
+Use null for any field that is not clearly stated in the document. Do NOT guess.
+
+Document Title: {title}
+
+Document Content:
+{content}
+
+Respond with ONLY a JSON object with exactly these keys, no explanation. Example:
+{"document_date": "1963-11-22", "originating_agency": "Central Intelligence Agency", "originating_office": null, "case_number": "F-2017-01234", "classification_markings": ["SECRET"]}"#;
+
+/// Prompt for answering a question from retrieved document pages.
+pub const ASK_PROMPT: &str = r#"You are a research assistant answering questions about a FOIA document archive. Answer using ONLY the document pages below. Each page starts with a label like [doc:ID p.N].
+
+RULES:
+1. Cite every statement with the label of the page it comes from, e.g. [doc:ID p.N]. Cite several pages if several support it.
+2. Do NOT use outside knowledge. If the pages do not answer the question, say so plainly.
+3. Be concise. Quote short phrases where the exact wording matters.
+
+Question: {question}
+
+Document Pages:
+{pages}
+
+Answer:"#;
+
+/// Prompt for a title for a document whose own title is a filename.
+pub const TITLE_PROMPT: &str = r#"You are titling a FOIA document that was released under a meaningless filename. Read the opening of the document and write the title a librarian would catalog it under.
+
+RULES:
+1. Use the document's own title or subject line if it has one.
+2. Otherwise describe what the document is: its type, author or agency, subject and date if known, e.g. "FBI memorandum on surveillance of Martin Luther King Jr., 1964".
+3. At most 15 words. No quotes, no trailing period, no explanation.
+
+Filename: {title}
+
+Document:
+{content}
+
+Title:"#;
+
+/// Prompt asking the model to fix a response that failed validation.
+///
+/// Only the previous response is sent back, not the document, so a repair
+/// costs a fraction of the original call.
+pub const REPAIR_PROMPT: &str = r#"Your previous response could not be used: {error}
+
+Expected: {expected}
+
+Previous response:
+{response}
+
+Rewrite the previous response so it matches the expected format, keeping its content. Respond with ONLY the corrected output, no explanation."#;
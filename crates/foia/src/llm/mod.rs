@@ -4,4 +4,7 @@
 
 mod client;
 
-pub use client::{LlmClient, LlmConfig};
+pub use client::{
+    BudgetLimits, LlmBudgetConfig, LlmClient, LlmConfig, LlmError, ModelPrice,
+    RecordTypeClassification, SummarizeResult, UsageTracker,
+};
@@ -0,0 +1,172 @@
+//! Process-wide metrics in Prometheus text exposition format.
+//!
+//! Counters are updated where the work happens (HTTP client, rate limiter,
+//! OCR, LLM client, downloads) and read either by the server's `/metrics`
+//! endpoint or pushed to a Pushgateway by short-lived CLI runs.
+
+mod push;
+mod types;
+
+use crate::repository::DieselCrawlRepository;
+
+pub use push::MetricsPusher;
+pub use types::{Counter, Gauge, Histogram, Metric};
+
+/// Bucket bounds for request and call durations, in seconds.
+const DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+/// HTTP requests made, by source, method and response status.
+pub static HTTP_REQUESTS: Counter = Counter::new(
+    "foia_http_requests_total",
+    "HTTP requests made to sources.",
+    &["source", "method", "status"],
+);
+
+/// HTTP request latency by source.
+pub static HTTP_REQUEST_DURATION: Histogram = Histogram::new(
+    "foia_http_request_duration_seconds",
+    "HTTP request duration in seconds.",
+    &["source"],
+    DURATION_BUCKETS,
+);
+
+/// Document bytes downloaded, by source.
+pub static BYTES_DOWNLOADED: Counter = Counter::new(
+    "foia_downloaded_bytes_total",
+    "Bytes of document content downloaded.",
+    &["source"],
+);
+
+/// Documents processed by download workers, by source and outcome.
+pub static DOCUMENTS_DOWNLOADED: Counter = Counter::new(
+    "foia_documents_downloaded_total",
+    "Documents processed by download workers.",
+    &["source", "outcome"],
+);
+
+/// URLs kept out of a crawl by the source's scope rules, by reason.
+pub static SCOPE_EXCLUSIONS: Counter = Counter::new(
+    "foia_scope_excluded_urls_total",
+    "URLs excluded by a source's crawl scope.",
+    &["source", "reason"],
+);
+
+/// Rate-limit backoffs, by domain and what triggered them.
+pub static RATE_LIMIT_BACKOFFS: Counter = Counter::new(
+    "foia_rate_limit_backoffs_total",
+    "Rate-limit backoffs applied to a domain.",
+    &["domain", "reason"],
+);
+
+/// OCR pages processed, by outcome.
+pub static OCR_PAGES: Counter = Counter::new(
+    "foia_ocr_pages_total",
+    "Pages processed by OCR.",
+    &["status"],
+);
+
+/// LLM calls, by provider and outcome.
+pub static LLM_CALLS: Counter = Counter::new(
+    "foia_llm_calls_total",
+    "Calls made to the LLM provider.",
+    &["provider", "outcome"],
+);
+
+/// LLM call latency by provider.
+pub static LLM_CALL_DURATION: Histogram = Histogram::new(
+    "foia_llm_call_duration_seconds",
+    "LLM call duration in seconds.",
+    &["provider"],
+    DURATION_BUCKETS,
+);
+
+/// LLM tokens consumed, by provider and kind (prompt or completion).
+pub static LLM_TOKENS: Counter = Counter::new(
+    "foia_llm_tokens_total",
+    "Tokens consumed by LLM calls.",
+    &["provider", "kind"],
+);
+
+/// LLM responses that failed validation, by operation and whether a
+/// repair prompt fixed them.
+pub static LLM_VALIDATION_FAILURES: Counter = Counter::new(
+    "foia_llm_validation_failures_total",
+    "LLM responses that failed validation.",
+    &["operation", "outcome"],
+);
+
+/// Crawl queue depth by source and URL status; see [`refresh_queue_depths`].
+pub static CRAWL_QUEUE: Gauge = Gauge::new(
+    "foia_crawl_queue_urls",
+    "URLs in the crawl queue by status.",
+    &["source", "status"],
+);
+
+static ALL: &[&dyn Metric] = &[
+    &HTTP_REQUESTS,
+    &HTTP_REQUEST_DURATION,
+    &BYTES_DOWNLOADED,
+    &DOCUMENTS_DOWNLOADED,
+    &SCOPE_EXCLUSIONS,
+    &RATE_LIMIT_BACKOFFS,
+    &OCR_PAGES,
+    &LLM_CALLS,
+    &LLM_CALL_DURATION,
+    &LLM_TOKENS,
+    &LLM_VALIDATION_FAILURES,
+    &CRAWL_QUEUE,
+];
+
+/// Content type of [`render`]'s output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Reload crawl queue depths from the database.
+///
+/// The queue is shared by every process using the database, so it is read
+/// fresh before rendering rather than counted in-process.
+pub async fn refresh_queue_depths(crawl_repo: &DieselCrawlRepository) {
+    let Ok(stats) = crawl_repo.get_all_stats().await else {
+        return;
+    };
+    CRAWL_QUEUE.reset();
+    for (source_id, s) in &stats {
+        for (status, count) in [
+            ("pending", s.urls_pending),
+            ("fetched", s.urls_fetched),
+            ("failed", s.urls_failed),
+        ] {
+            CRAWL_QUEUE.set(&[source_id, status], count as f64);
+        }
+    }
+}
+
+/// Render every metric in Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = String::new();
+    for metric in ALL {
+        metric.render(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_all_families() {
+        HTTP_REQUESTS.inc(&["test-source", "GET", "200"]);
+        let out = render();
+        for name in [
+            "foia_http_requests_total",
+            "foia_downloaded_bytes_total",
+            "foia_rate_limit_backoffs_total",
+            "foia_ocr_pages_total",
+            "foia_llm_calls_total",
+            "foia_crawl_queue_urls",
+        ] {
+            assert!(out.contains(&format!("# TYPE {} ", name)), "{}", name);
+        }
+        assert!(out.contains("source=\"test-source\",method=\"GET\",status=\"200\""));
+    }
+}
@@ -0,0 +1,67 @@
+//! Pushgateway support for short-lived commands.
+
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::config::metrics::MetricsConfig;
+use crate::http_client::HttpClient;
+
+/// Periodically pushes [`super::render`] output to a Pushgateway.
+pub struct MetricsPusher {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl MetricsPusher {
+    /// Start pushing in the background if a Pushgateway is configured.
+    ///
+    /// Metrics are grouped under the configured job and this host's name as
+    /// the instance.
+    pub fn start(config: &MetricsConfig) -> Option<Self> {
+        let instance = hostname::get()
+            .ok()
+            .and_then(|h| h.into_string().ok())
+            .unwrap_or_else(|| "unknown".to_string());
+        let url = config.push_url(&instance)?;
+        let interval = Duration::from_secs(config.push_interval_secs());
+        let client = HttpClient::builder("metrics", Duration::from_secs(10), Duration::ZERO)
+            .build()
+            .ok()?;
+
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => push(&client, &url).await,
+                    _ = &mut stopped => break,
+                }
+            }
+            // Final push so the last counts of a finished run are kept
+            push(&client, &url).await;
+        });
+
+        Some(Self { stop, task })
+    }
+
+    /// Stop the background task after one final push.
+    pub async fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+async fn push(client: &HttpClient, url: &str) {
+    let result = client
+        .put(url, super::render().into_bytes(), super::CONTENT_TYPE)
+        .await;
+    match result {
+        Ok(r) if r.is_success() => debug!("Pushed metrics to {}", url),
+        Ok(r) => warn!("Pushgateway {} returned {}", url, r.status),
+        Err(e) => warn!("Failed to push metrics to {}: {}", url, e),
+    }
+}
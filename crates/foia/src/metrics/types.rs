@@ -0,0 +1,291 @@
+//! Counter, gauge and histogram types rendered in Prometheus text format.
+//!
+//! Each metric keeps its labelled series in a mutex-guarded map, so they can
+//! be declared as plain `static`s and updated from any task.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard};
+
+/// Common interface for rendering a metric family.
+pub trait Metric: Sync {
+    /// Append this metric's `# HELP`, `# TYPE` and sample lines to `out`.
+    fn render(&self, out: &mut String);
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn key(labels: &[&'static str], values: &[&str]) -> Vec<String> {
+    debug_assert_eq!(labels.len(), values.len(), "label count mismatch");
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+/// Escape a label value per the text exposition format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Render `{a="x",b="y"}` (plus an optional extra pair), or nothing if empty.
+fn label_set(names: &[&str], values: &[String], extra: Option<(&str, &str)>) -> String {
+    let mut pairs: Vec<String> = names
+        .iter()
+        .zip(values)
+        .map(|(n, v)| format!("{}=\"{}\"", n, escape(v)))
+        .collect();
+    if let Some((n, v)) = extra {
+        pairs.push(format!("{}=\"{}\"", n, v));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Monotonically increasing count.
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    series: Mutex<BTreeMap<Vec<String>, f64>>,
+}
+
+impl Counter {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Increment the series for `labels` by one.
+    pub fn inc(&self, labels: &[&str]) {
+        self.inc_by(labels, 1.0);
+    }
+
+    /// Increment the series for `labels` by `amount`.
+    pub fn inc_by(&self, labels: &[&str], amount: f64) {
+        *lock(&self.series)
+            .entry(key(self.labels, labels))
+            .or_insert(0.0) += amount;
+    }
+
+    /// Current value of the series for `labels`.
+    pub fn get(&self, labels: &[&str]) -> f64 {
+        lock(&self.series)
+            .get(&key(self.labels, labels))
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+impl Metric for Counter {
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "counter");
+        for (values, v) in lock(&self.series).iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.name,
+                label_set(self.labels, values, None),
+                v
+            );
+        }
+    }
+}
+
+/// Value that can go up and down, such as a queue depth.
+pub struct Gauge {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    series: Mutex<BTreeMap<Vec<String>, f64>>,
+}
+
+impl Gauge {
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Set the series for `labels` to `value`.
+    pub fn set(&self, labels: &[&str], value: f64) {
+        lock(&self.series).insert(key(self.labels, labels), value);
+    }
+
+    /// Drop all series, e.g. before refreshing from the database.
+    pub fn reset(&self) {
+        lock(&self.series).clear();
+    }
+
+    /// Current value of the series for `labels`.
+    pub fn get(&self, labels: &[&str]) -> Option<f64> {
+        lock(&self.series).get(&key(self.labels, labels)).copied()
+    }
+}
+
+impl Metric for Gauge {
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "gauge");
+        for (values, v) in lock(&self.series).iter() {
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                self.name,
+                label_set(self.labels, values, None),
+                v
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct HistogramSeries {
+    /// Per-bucket (non-cumulative) counts; one extra slot for `+Inf`.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Distribution of observed values in fixed buckets.
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    labels: &'static [&'static str],
+    bounds: &'static [f64],
+    series: Mutex<BTreeMap<Vec<String>, HistogramSeries>>,
+}
+
+impl Histogram {
+    /// `bounds` are the bucket upper bounds in increasing order.
+    pub const fn new(
+        name: &'static str,
+        help: &'static str,
+        labels: &'static [&'static str],
+        bounds: &'static [f64],
+    ) -> Self {
+        Self {
+            name,
+            help,
+            labels,
+            bounds,
+            series: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record one observation for `labels`.
+    pub fn observe(&self, labels: &[&str], value: f64) {
+        let mut series = lock(&self.series);
+        let entry = series
+            .entry(key(self.labels, labels))
+            .or_insert_with(|| HistogramSeries {
+                buckets: vec![0; self.bounds.len() + 1],
+                ..Default::default()
+            });
+        let slot = self
+            .bounds
+            .iter()
+            .position(|b| value <= *b)
+            .unwrap_or(self.bounds.len());
+        entry.buckets[slot] += 1;
+        entry.sum += value;
+        entry.count += 1;
+    }
+}
+
+impl Metric for Histogram {
+    fn render(&self, out: &mut String) {
+        header(out, self.name, self.help, "histogram");
+        for (values, s) in lock(&self.series).iter() {
+            let mut cumulative = 0;
+            for (i, n) in s.buckets.iter().enumerate() {
+                cumulative += n;
+                let le = self
+                    .bounds
+                    .get(i)
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    self.name,
+                    label_set(self.labels, values, Some(("le", &le))),
+                    cumulative
+                );
+            }
+            let labels = label_set(self.labels, values, None);
+            let _ = writeln!(out, "{}_sum{} {}", self.name, labels, s.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, labels, s.count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_render() {
+        let c = Counter::new("test_total", "A test counter.", &["source", "status"]);
+        c.inc(&["fbi", "200"]);
+        c.inc_by(&["fbi", "200"], 2.0);
+        c.inc(&["cia \"x\"", "404"]);
+
+        let mut out = String::new();
+        c.render(&mut out);
+        assert!(out.contains("# TYPE test_total counter"));
+        assert!(out.contains("test_total{source=\"fbi\",status=\"200\"} 3"));
+        assert!(out.contains("test_total{source=\"cia \\\"x\\\"\",status=\"404\"} 1"));
+        assert_eq!(c.get(&["fbi", "200"]), 3.0);
+    }
+
+    #[test]
+    fn test_gauge_reset() {
+        let g = Gauge::new("test_depth", "A test gauge.", &["status"]);
+        g.set(&["pending"], 5.0);
+        g.set(&["pending"], 2.0);
+        assert_eq!(g.get(&["pending"]), Some(2.0));
+        g.reset();
+        assert_eq!(g.get(&["pending"]), None);
+    }
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let h = Histogram::new("test_seconds", "A test histogram.", &[], &[0.1, 1.0]);
+        h.observe(&[], 0.05);
+        h.observe(&[], 0.5);
+        h.observe(&[], 5.0);
+
+        let mut out = String::new();
+        h.render(&mut out);
+        assert!(out.contains("test_seconds_bucket{le=\"0.1\"} 1"));
+        assert!(out.contains("test_seconds_bucket{le=\"1\"} 2"));
+        assert!(out.contains("test_seconds_bucket{le=\"+Inf\"} 3"));
+        assert!(out.contains("test_seconds_count 3"));
+        assert!(out.contains("test_seconds_sum 5.55"));
+    }
+}
@@ -0,0 +1,25 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0015_record_type")
+        .depends_on(&["0001_initial_schema"])
+        .operation(AddField::new(
+            "documents",
+            Field::new("record_type", FieldType::Text),
+        ))
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE documents ADD COLUMN record_type_confidence REAL",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE documents ADD COLUMN record_type_confidence REAL",
+                ),
+        )
+        .operation(AddIndex::new(
+            "documents",
+            Index::new("idx_documents_record_type").column("record_type"),
+        ))
+}
@@ -0,0 +1,38 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0016_extracted_metadata")
+        .depends_on(&["0015_record_type"])
+        .operation(AddField::new(
+            "documents",
+            Field::new("document_date", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "documents",
+            Field::new("originating_agency", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "documents",
+            Field::new("originating_office", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "documents",
+            Field::new("case_number", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "documents",
+            Field::new("classification_markings", FieldType::Text),
+        ))
+        .operation(AddIndex::new(
+            "documents",
+            Index::new("idx_documents_document_date")
+                .column("document_date")
+                .filter("document_date IS NOT NULL"),
+        ))
+        .operation(AddIndex::new(
+            "documents",
+            Index::new("idx_documents_case_number")
+                .column("case_number")
+                .filter("case_number IS NOT NULL"),
+        ))
+}
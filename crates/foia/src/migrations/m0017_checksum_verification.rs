@@ -0,0 +1,10 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0017_checksum_verification")
+        .depends_on(&["0016_extracted_metadata"])
+        .operation(AddField::new(
+            "document_versions",
+            Field::new("checksum_verification", FieldType::Text),
+        ))
+}
@@ -0,0 +1,21 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0018_archive_snapshot_text")
+        .depends_on(&["0005_archive_history"])
+        // Store archive snapshot timestamps and metadata as text on Postgres,
+        // matching the schema and every other table
+        .operation(
+            RunSql::portable()
+                .for_backend("sqlite", "SELECT 1")
+                .for_backend(
+                    "postgres",
+                    r#"ALTER TABLE archive_snapshots ALTER COLUMN captured_at TYPE TEXT USING to_char(captured_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"+00:00"');
+ALTER TABLE archive_snapshots ALTER COLUMN discovered_at DROP DEFAULT;
+ALTER TABLE archive_snapshots ALTER COLUMN discovered_at TYPE TEXT USING to_char(discovered_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS"+00:00"');
+ALTER TABLE archive_snapshots ALTER COLUMN metadata DROP DEFAULT;
+ALTER TABLE archive_snapshots ALTER COLUMN metadata TYPE TEXT USING metadata::text;
+ALTER TABLE archive_snapshots ALTER COLUMN metadata SET DEFAULT '{}'"#,
+                ),
+        )
+}
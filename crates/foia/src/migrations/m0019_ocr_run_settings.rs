@@ -0,0 +1,20 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0019_ocr_run_settings")
+        .depends_on(&["0008_page_image_hash"])
+        // Record the tool version, language and render DPI behind each OCR
+        // result so stale results can be selectively reprocessed
+        .operation(AddField::new(
+            "page_ocr_results",
+            Field::new("tool_version", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "page_ocr_results",
+            Field::new("language", FieldType::Text),
+        ))
+        .operation(AddField::new(
+            "page_ocr_results",
+            Field::new("dpi", FieldType::Integer),
+        ))
+}
@@ -0,0 +1,22 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0020_ocr_quality")
+        .depends_on(&["0019_ocr_run_settings"])
+        // Per-page OCR quality score, for finding poorly recognized documents
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE document_pages ADD COLUMN ocr_quality REAL",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE document_pages ADD COLUMN ocr_quality REAL",
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_pages",
+            Index::new("idx_document_pages_ocr_quality").column("ocr_quality"),
+        ))
+}
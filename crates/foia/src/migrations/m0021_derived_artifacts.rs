@@ -0,0 +1,69 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0021_derived_artifacts")
+        .depends_on(&["0020_ocr_quality"])
+        // Files generated from a document version (searchable PDFs, tables,
+        // transcripts, ...), stored under the documents directory
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS derived_artifacts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id TEXT NOT NULL,
+    version_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    content_hash TEXT NOT NULL,
+    analysis_result_id INTEGER,
+    status TEXT NOT NULL DEFAULT 'ready',
+    metadata TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (document_id) REFERENCES documents(id),
+    FOREIGN KEY (version_id) REFERENCES document_versions(id),
+    FOREIGN KEY (analysis_result_id) REFERENCES document_analysis_results(id) ON DELETE SET NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS derived_artifacts (
+    id SERIAL PRIMARY KEY,
+    document_id TEXT NOT NULL REFERENCES documents(id),
+    version_id INTEGER NOT NULL REFERENCES document_versions(id),
+    kind TEXT NOT NULL,
+    name TEXT NOT NULL,
+    mime_type TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    file_size BIGINT NOT NULL,
+    content_hash TEXT NOT NULL,
+    analysis_result_id INTEGER REFERENCES document_analysis_results(id) ON DELETE SET NULL,
+    status TEXT NOT NULL DEFAULT 'ready',
+    metadata TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "derived_artifacts",
+            Index::new("idx_derived_artifacts_document").column("document_id"),
+        ))
+        .operation(AddIndex::new(
+            "derived_artifacts",
+            Index::new("idx_derived_artifacts_status").column("status"),
+        ))
+        // One artifact per name and kind for each version; regenerating replaces it
+        .operation(AddIndex::new(
+            "derived_artifacts",
+            Index::new("idx_derived_artifacts_unique")
+                .column("version_id")
+                .column("kind")
+                .column("name")
+                .unique(),
+        ))
+}
@@ -0,0 +1,57 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0022_jobs")
+        .depends_on(&["0021_derived_artifacts"])
+        // Long operations started from the web UI or API, with progress and
+        // a cancellation flag polled by the process running them
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    params TEXT NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'queued',
+    progress INTEGER NOT NULL DEFAULT 0,
+    total INTEGER,
+    message TEXT,
+    error TEXT,
+    result TEXT,
+    cancel_requested INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY NOT NULL,
+    kind TEXT NOT NULL,
+    params TEXT NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'queued',
+    progress BIGINT NOT NULL DEFAULT 0,
+    total BIGINT,
+    message TEXT,
+    error TEXT,
+    result TEXT,
+    cancel_requested INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "jobs",
+            Index::new("idx_jobs_status").column("status"),
+        ))
+        .operation(AddIndex::new(
+            "jobs",
+            Index::new("idx_jobs_created").column("created_at"),
+        ))
+}
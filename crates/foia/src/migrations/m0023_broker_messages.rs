@@ -0,0 +1,47 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0023_broker_messages")
+        .depends_on(&["0022_jobs"])
+        // Worker queue for the local-DB broker: a message is claimed for a
+        // visibility timeout, deleted on ack and made available again (or
+        // marked dead) on nack
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS broker_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    queue TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'ready',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    available_at TEXT NOT NULL,
+    claimed_until TEXT,
+    last_error TEXT,
+    created_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS broker_messages (
+    id SERIAL PRIMARY KEY,
+    queue TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'ready',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    available_at TEXT NOT NULL,
+    claimed_until TEXT,
+    last_error TEXT,
+    created_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "broker_messages",
+            Index::new("idx_broker_messages_queue")
+                .column("queue")
+                .column("status")
+                .column("available_at"),
+        ))
+}
@@ -0,0 +1,39 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0024_listing_sort_indexes")
+        .depends_on(&["0023_broker_messages"])
+        // Sortable listing columns; status already has idx_documents_status
+        .operation(AddIndex::new(
+            "documents",
+            Index::new("idx_documents_title").column("title"),
+        ))
+        .operation(AddIndex::new(
+            "documents",
+            Index::new("idx_documents_source_title")
+                .column("source_id")
+                .column("title"),
+        ))
+        // Matches the COALESCE used to sort undated documents together
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "CREATE INDEX IF NOT EXISTS idx_documents_document_date_sort \
+                     ON documents (COALESCE(document_date, ''))",
+                )
+                .for_backend(
+                    "postgres",
+                    "CREATE INDEX IF NOT EXISTS idx_documents_document_date_sort \
+                     ON documents ((COALESCE(document_date, '')))",
+                ),
+        )
+        // Latest-version lookups when sorting by size, acquisition date or
+        // page count
+        .operation(AddIndex::new(
+            "document_versions",
+            Index::new("idx_versions_document_latest")
+                .column("document_id")
+                .column_desc("id"),
+        ))
+}
@@ -0,0 +1,29 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0025_saved_views")
+        .depends_on(&["0024_listing_sort_indexes"])
+        // Named browse filter combinations, stored as the listing's query
+        // string so a bookmark is just a URL
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS saved_views (
+    name TEXT PRIMARY KEY NOT NULL,
+    query TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS saved_views (
+    name TEXT PRIMARY KEY NOT NULL,
+    query TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+}
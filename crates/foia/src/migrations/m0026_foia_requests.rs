@@ -0,0 +1,79 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0026_foia_requests")
+        .depends_on(&["0025_saved_views"])
+        // The FOIA requests themselves, so responses can be traced back to
+        // the request that produced them and deadlines tracked
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS foia_requests (
+    id TEXT PRIMARY KEY NOT NULL,
+    agency TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    request_text TEXT NOT NULL DEFAULT '',
+    tracking_number TEXT,
+    source_id TEXT,
+    status TEXT NOT NULL DEFAULT 'filed',
+    filed_on TEXT NOT NULL,
+    acknowledged_on TEXT,
+    fulfilled_on TEXT,
+    appealed_on TEXT,
+    notes TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS foia_requests (
+    id TEXT PRIMARY KEY NOT NULL,
+    agency TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    request_text TEXT NOT NULL DEFAULT '',
+    tracking_number TEXT,
+    source_id TEXT,
+    status TEXT NOT NULL DEFAULT 'filed',
+    filed_on TEXT NOT NULL,
+    acknowledged_on TEXT,
+    fulfilled_on TEXT,
+    appealed_on TEXT,
+    notes TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "foia_requests",
+            Index::new("idx_foia_requests_status").column("status"),
+        ))
+        // Documents received in response to a request
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS foia_request_documents (
+    request_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    linked_at TEXT NOT NULL,
+    PRIMARY KEY (request_id, document_id)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS foia_request_documents (
+    request_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    linked_at TEXT NOT NULL,
+    PRIMARY KEY (request_id, document_id)
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "foia_request_documents",
+            Index::new("idx_foia_request_documents_doc").column("document_id"),
+        ))
+}
@@ -0,0 +1,32 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0027_request_deadlines")
+        .depends_on(&["0026_foia_requests"])
+        // Which deadline rules apply to a request; NULL uses the default
+        // jurisdiction
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE foia_requests ADD COLUMN jurisdiction TEXT",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE foia_requests ADD COLUMN jurisdiction TEXT",
+                ),
+        )
+        // Last overdue reminder, so reminders repeat on a schedule rather
+        // than on every run
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE foia_requests ADD COLUMN reminded_on TEXT",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE foia_requests ADD COLUMN reminded_on TEXT",
+                ),
+        )
+}
@@ -0,0 +1,53 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0028_saved_view_alerts")
+        .depends_on(&["0027_request_deadlines"])
+        // Addresses to alert when new documents match a saved view
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS saved_view_subscriptions (
+    view_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (view_name, email)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS saved_view_subscriptions (
+    view_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (view_name, email)
+)"#,
+                ),
+        )
+        // Documents each subscriber has already been alerted about, so a
+        // document is only ever reported once
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS saved_view_alerts (
+    view_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    alerted_at TEXT NOT NULL,
+    PRIMARY KEY (view_name, email, document_id)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS saved_view_alerts (
+    view_name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    alerted_at TEXT NOT NULL,
+    PRIMARY KEY (view_name, email, document_id)
+)"#,
+                ),
+        )
+}
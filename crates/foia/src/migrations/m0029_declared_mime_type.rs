@@ -0,0 +1,10 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0029_declared_mime_type")
+        .depends_on(&["0028_saved_view_alerts"])
+        .operation(AddField::new(
+            "document_versions",
+            Field::new("declared_mime_type", FieldType::Text),
+        ))
+}
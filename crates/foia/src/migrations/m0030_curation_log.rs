@@ -0,0 +1,40 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0030_curation_log")
+        .depends_on(&["0029_declared_mime_type"])
+        // Audit trail of curator edits to the document set: merges record
+        // the surviving document and the ids merged into it, splits the
+        // original and the child documents cut from it
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS curation_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    action TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    related_ids TEXT NOT NULL,
+    details TEXT NOT NULL,
+    actor TEXT,
+    created_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS curation_log (
+    id SERIAL PRIMARY KEY,
+    action TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    related_ids TEXT NOT NULL,
+    details TEXT NOT NULL,
+    actor TEXT,
+    created_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "curation_log",
+            Index::new("idx_curation_log_document").column("document_id"),
+        ))
+}
@@ -0,0 +1,59 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0031_agencies")
+        .depends_on(&["0030_curation_log"])
+        // Registry of the agencies sources collect from, so documents can be
+        // browsed and tagged by agency rather than by scraper instance
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS agencies (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    abbreviation TEXT,
+    jurisdiction TEXT NOT NULL DEFAULT 'US',
+    level TEXT NOT NULL DEFAULT 'federal',
+    parent_id TEXT,
+    foia_email TEXT,
+    foia_phone TEXT,
+    foia_address TEXT,
+    portal_urls TEXT NOT NULL DEFAULT '[]',
+    website TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS agencies (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    abbreviation TEXT,
+    jurisdiction TEXT NOT NULL DEFAULT 'US',
+    level TEXT NOT NULL DEFAULT 'federal',
+    parent_id TEXT,
+    foia_email TEXT,
+    foia_phone TEXT,
+    foia_address TEXT,
+    portal_urls TEXT NOT NULL DEFAULT '[]',
+    website TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "agencies",
+            Index::new("idx_agencies_parent").column("parent_id"),
+        ))
+        .operation(AddField::new(
+            "sources",
+            Field::new("agency_id", FieldType::Text),
+        ))
+        .operation(AddIndex::new(
+            "sources",
+            Index::new("idx_sources_agency").column("agency_id"),
+        ))
+}
@@ -0,0 +1,39 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0032_document_locations")
+        .depends_on(&["0031_agencies"])
+        // Each document's map position, chosen from its geocoded location
+        // entities so the map and bbox filters don't re-rank entities per query
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_locations (
+    document_id TEXT PRIMARY KEY NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    place TEXT NOT NULL,
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    mentions INTEGER NOT NULL DEFAULT 1,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_locations (
+    document_id TEXT PRIMARY KEY NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    place TEXT NOT NULL,
+    latitude DOUBLE PRECISION NOT NULL,
+    longitude DOUBLE PRECISION NOT NULL,
+    mentions INTEGER NOT NULL DEFAULT 1,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_locations",
+            Index::new("idx_document_locations_coords")
+                .column("latitude")
+                .column("longitude"),
+        ))
+}
@@ -0,0 +1,37 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0033_document_relations")
+        .depends_on(&["0032_document_locations"])
+        // Links between documents detected from their text and entities:
+        // attachments, replies to requests, and shared case numbers
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_relations (
+    source_document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    target_document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    evidence TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (source_document_id, target_document_id, kind)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_relations (
+    source_document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    target_document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    evidence TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (source_document_id, target_document_id, kind)
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_relations",
+            Index::new("idx_document_relations_target").column("target_document_id"),
+        ))
+}
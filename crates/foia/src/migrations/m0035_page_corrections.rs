@@ -0,0 +1,19 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0035_page_corrections")
+        .depends_on(&["0034_llm_usage"])
+        // When a curator corrected a page's final text by hand, so re-OCR
+        // keeps the correction
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE document_pages ADD COLUMN corrected_at TEXT",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE document_pages ADD COLUMN corrected_at TEXT",
+                ),
+        )
+}
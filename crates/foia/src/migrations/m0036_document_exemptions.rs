@@ -0,0 +1,33 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0036_document_exemptions")
+        .depends_on(&["0035_page_corrections"])
+        // How many redactions in each document cite each FOIA exemption, for
+        // the "exemptions cited" facet and corpus exemption statistics
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_exemptions (
+    document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    code TEXT NOT NULL,
+    citations INTEGER NOT NULL,
+    PRIMARY KEY (document_id, code)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_exemptions (
+    document_id TEXT NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    code TEXT NOT NULL,
+    citations INTEGER NOT NULL,
+    PRIMARY KEY (document_id, code)
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_exemptions",
+            Index::new("idx_document_exemptions_code").column("code"),
+        ))
+}
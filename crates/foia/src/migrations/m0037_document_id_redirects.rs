@@ -0,0 +1,31 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0037_document_id_redirects")
+        .depends_on(&["0036_document_exemptions"])
+        // Document IDs replaced by a stable ID or merged away, so links to
+        // the old ID keep working
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_id_redirects (
+    old_id TEXT PRIMARY KEY,
+    new_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_id_redirects (
+    old_id TEXT PRIMARY KEY,
+    new_id TEXT NOT NULL,
+    created_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_id_redirects",
+            Index::new("idx_document_id_redirects_new").column("new_id"),
+        ))
+}
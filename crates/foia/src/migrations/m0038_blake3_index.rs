@@ -0,0 +1,11 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0038_blake3_index")
+        .depends_on(&["0037_document_id_redirects"])
+        // Deduplication looks versions up by BLAKE3 hash
+        .operation(AddIndex::new(
+            "document_versions",
+            Index::new("idx_versions_blake3").column("content_hash_blake3"),
+        ))
+}
@@ -0,0 +1,43 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0039_document_pdf_metadata")
+        .depends_on(&["0038_blake3_index"])
+        // Info dictionary and XMP metadata of each document's current PDF,
+        // for the producer and creation year facets
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_pdf_metadata (
+    document_id TEXT PRIMARY KEY NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    author TEXT,
+    creator TEXT,
+    producer TEXT,
+    created_at TEXT,
+    modified_at TEXT,
+    updated_at TEXT NOT NULL
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_pdf_metadata (
+    document_id TEXT PRIMARY KEY NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    author TEXT,
+    creator TEXT,
+    producer TEXT,
+    created_at TEXT,
+    modified_at TEXT,
+    updated_at TEXT NOT NULL
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_pdf_metadata",
+            Index::new("idx_document_pdf_metadata_producer").column("producer"),
+        ))
+        .operation(AddIndex::new(
+            "document_pdf_metadata",
+            Index::new("idx_document_pdf_metadata_created").column("created_at"),
+        ))
+}
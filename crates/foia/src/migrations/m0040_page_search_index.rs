@@ -0,0 +1,73 @@
+use cetane::prelude::*;
+
+/// SQLite trigger keeping one page's index entry current.
+fn reindex_trigger(name: &str, event: &str, when: &str) -> String {
+    format!(
+        r#"CREATE TRIGGER IF NOT EXISTS {name}
+AFTER {event} ON document_pages
+{when}BEGIN
+    INSERT OR REPLACE INTO document_pages_fts (rowid, body)
+    VALUES (NEW.id, COALESCE(NEW.final_text, NEW.ocr_text, NEW.pdf_text, ''));
+END"#
+    )
+}
+
+pub fn migration() -> Migration {
+    Migration::new("0040_page_search_index")
+        .depends_on(&["0039_document_pdf_metadata"])
+        // Word index of page text for SQLite search, kept current by the
+        // triggers below. PostgreSQL uses idx_pages_fts from 0014.
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "CREATE VIRTUAL TABLE IF NOT EXISTS document_pages_fts \
+                     USING fts5(body, content='', contentless_delete=1)",
+                )
+                .for_backend("postgres", "SELECT 1"),
+        )
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "INSERT INTO document_pages_fts (rowid, body) \
+                     SELECT id, COALESCE(final_text, ocr_text, pdf_text, '') FROM document_pages \
+                     WHERE id NOT IN (SELECT rowid FROM document_pages_fts)",
+                )
+                .for_backend("postgres", "SELECT 1"),
+        )
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    reindex_trigger("tr_document_pages_fts_insert", "INSERT", ""),
+                )
+                .for_backend("postgres", "SELECT 1"),
+        )
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    reindex_trigger(
+                        "tr_document_pages_fts_update",
+                        "UPDATE OF pdf_text, ocr_text, final_text",
+                        // Compressing pdf_text or ocr_text leaves the indexed text as is
+                        "WHEN COALESCE(NEW.final_text, NEW.ocr_text, NEW.pdf_text, '') \
+                         IS NOT COALESCE(OLD.final_text, OLD.ocr_text, OLD.pdf_text, '')\n",
+                    ),
+                )
+                .for_backend("postgres", "SELECT 1"),
+        )
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TRIGGER IF NOT EXISTS tr_document_pages_fts_delete
+AFTER DELETE ON document_pages
+BEGIN
+    DELETE FROM document_pages_fts WHERE rowid = OLD.id;
+END"#,
+                )
+                .for_backend("postgres", "SELECT 1"),
+        )
+}
@@ -0,0 +1,37 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0042_document_access")
+        .depends_on(&["0041_api_keys"])
+        // Views and downloads per document, day and referring site, for the
+        // analytics page. Aggregated so nothing identifies a reader
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_access (
+    document_id TEXT NOT NULL,
+    day TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    referrer TEXT NOT NULL DEFAULT '',
+    count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (document_id, day, kind, referrer)
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_access (
+    document_id TEXT NOT NULL,
+    day TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    referrer TEXT NOT NULL DEFAULT '',
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (document_id, day, kind, referrer)
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_access",
+            Index::new("idx_document_access_day").column("day"),
+        ))
+}
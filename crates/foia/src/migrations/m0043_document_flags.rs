@@ -0,0 +1,47 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0043_document_flags")
+        .depends_on(&["0042_document_access"])
+        // Reader reports of sensitive or misfiled documents, worked through
+        // by curators in the moderation queue
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    r#"CREATE TABLE IF NOT EXISTS document_flags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    page INTEGER,
+    note TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'open',
+    created_at TEXT NOT NULL,
+    resolved_at TEXT,
+    resolution TEXT
+)"#,
+                )
+                .for_backend(
+                    "postgres",
+                    r#"CREATE TABLE IF NOT EXISTS document_flags (
+    id SERIAL PRIMARY KEY,
+    document_id TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    page INTEGER,
+    note TEXT NOT NULL DEFAULT '',
+    status TEXT NOT NULL DEFAULT 'open',
+    created_at TEXT NOT NULL,
+    resolved_at TEXT,
+    resolution TEXT
+)"#,
+                ),
+        )
+        .operation(AddIndex::new(
+            "document_flags",
+            Index::new("idx_document_flags_document").column("document_id"),
+        ))
+        .operation(AddIndex::new(
+            "document_flags",
+            Index::new("idx_document_flags_status").column("status"),
+        ))
+}
@@ -0,0 +1,18 @@
+use cetane::prelude::*;
+
+pub fn migration() -> Migration {
+    Migration::new("0044_api_key_roles")
+        .depends_on(&["0043_document_flags"])
+        // Admin keys may also issue, list and revoke keys
+        .operation(
+            RunSql::portable()
+                .for_backend(
+                    "sqlite",
+                    "ALTER TABLE api_keys ADD COLUMN role TEXT NOT NULL DEFAULT 'client'",
+                )
+                .for_backend(
+                    "postgres",
+                    "ALTER TABLE api_keys ADD COLUMN role TEXT NOT NULL DEFAULT 'client'",
+                ),
+        )
+}
@@ -12,9 +12,56 @@ mod m0011_constraints;
 mod m0012_scraper_configs;
 mod m0013_analysis_lookup_index;
 mod m0014_search_indexes;
+mod m0015_record_type;
+mod m0016_extracted_metadata;
+mod m0017_checksum_verification;
+mod m0018_archive_snapshot_text;
+mod m0019_ocr_run_settings;
+mod m0020_ocr_quality;
+mod m0021_derived_artifacts;
+mod m0022_jobs;
+mod m0023_broker_messages;
+mod m0024_listing_sort_indexes;
+mod m0025_saved_views;
+mod m0026_foia_requests;
+mod m0027_request_deadlines;
+mod m0028_saved_view_alerts;
+mod m0029_declared_mime_type;
+mod m0030_curation_log;
+mod m0031_agencies;
+mod m0032_document_locations;
+mod m0033_document_relations;
+mod m0034_llm_usage;
+mod m0035_page_corrections;
+mod m0036_document_exemptions;
+mod m0037_document_id_redirects;
+mod m0038_blake3_index;
+mod m0039_document_pdf_metadata;
+mod m0040_page_search_index;
+mod m0041_api_keys;
+mod m0042_document_access;
+mod m0043_document_flags;
+mod m0044_api_key_roles;
 
 use cetane::prelude::MigrationRegistry;
 
+/// Name of the newest migration, identifying the schema this build writes.
+pub fn schema_version() -> String {
+    registry()
+        .resolve_order()
+        .ok()
+        .and_then(|order| order.last().map(|name| name.to_string()))
+        .unwrap_or_default()
+}
+
+/// Whether this build knows the migration `name`, i.e. its schema is at
+/// least as new as one that has applied it.
+pub fn is_known_migration(name: &str) -> bool {
+    registry()
+        .resolve_order()
+        .is_ok_and(|order| order.iter().any(|known| known.to_string() == name))
+}
+
 pub fn registry() -> MigrationRegistry {
     let mut reg = MigrationRegistry::new();
     reg.register(m0001_initial::migration());
@@ -31,5 +78,35 @@ pub fn registry() -> MigrationRegistry {
     reg.register(m0012_scraper_configs::migration());
     reg.register(m0013_analysis_lookup_index::migration());
     reg.register(m0014_search_indexes::migration());
+    reg.register(m0015_record_type::migration());
+    reg.register(m0016_extracted_metadata::migration());
+    reg.register(m0017_checksum_verification::migration());
+    reg.register(m0018_archive_snapshot_text::migration());
+    reg.register(m0019_ocr_run_settings::migration());
+    reg.register(m0020_ocr_quality::migration());
+    reg.register(m0021_derived_artifacts::migration());
+    reg.register(m0022_jobs::migration());
+    reg.register(m0023_broker_messages::migration());
+    reg.register(m0024_listing_sort_indexes::migration());
+    reg.register(m0025_saved_views::migration());
+    reg.register(m0026_foia_requests::migration());
+    reg.register(m0027_request_deadlines::migration());
+    reg.register(m0028_saved_view_alerts::migration());
+    reg.register(m0029_declared_mime_type::migration());
+    reg.register(m0030_curation_log::migration());
+    reg.register(m0031_agencies::migration());
+    reg.register(m0032_document_locations::migration());
+    reg.register(m0033_document_relations::migration());
+    reg.register(m0034_llm_usage::migration());
+    reg.register(m0035_page_corrections::migration());
+    reg.register(m0036_document_exemptions::migration());
+    reg.register(m0037_document_id_redirects::migration());
+    reg.register(m0038_blake3_index::migration());
+    reg.register(m0039_document_pdf_metadata::migration());
+    reg.register(m0040_page_search_index::migration());
+    reg.register(m0041_api_keys::migration());
+    reg.register(m0042_document_access::migration());
+    reg.register(m0043_document_flags::migration());
+    reg.register(m0044_api_key_roles::migration());
     reg
 }
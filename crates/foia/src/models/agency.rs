@@ -0,0 +1,153 @@
+//! Agency registry models.
+//!
+//! A source is one scraper instance; an agency is who published what it
+//! collects. Several sources can cover one agency (its reading room, its
+//! records portal, an aggregator mirror), and components nest under their
+//! parent department. Linking sources to registry entries gives every
+//! document the same `agency:<id>` tag however it was acquired.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::tag::tag_slug;
+
+/// Level of government an agency belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgencyLevel {
+    #[default]
+    Federal,
+    State,
+    County,
+    Municipal,
+    Tribal,
+    Other,
+}
+
+impl AgencyLevel {
+    pub const ALL: [Self; 6] = [
+        Self::Federal,
+        Self::State,
+        Self::County,
+        Self::Municipal,
+        Self::Tribal,
+        Self::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Federal => "federal",
+            Self::State => "state",
+            Self::County => "county",
+            Self::Municipal => "municipal",
+            Self::Tribal => "tribal",
+            Self::Other => "other",
+        }
+    }
+
+    /// Parse a level, accepting a few common synonyms ("city", "local").
+    pub fn from_str(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        match s.as_str() {
+            "city" | "local" => Some(Self::Municipal),
+            _ => Self::ALL.into_iter().find(|level| level.as_str() == s),
+        }
+    }
+}
+
+/// An agency in the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Agency {
+    /// Slug used in `agency:` tags, e.g. `fbi`.
+    pub id: String,
+    pub name: String,
+    pub abbreviation: Option<String>,
+    /// Whose laws govern its records, e.g. `US` or `US-CA`.
+    pub jurisdiction: String,
+    pub level: AgencyLevel,
+    /// The department or agency this one is a component of.
+    pub parent_id: Option<String>,
+    pub foia_email: Option<String>,
+    pub foia_phone: Option<String>,
+    /// Mailing address for requests.
+    pub foia_address: Option<String>,
+    /// Request portals and reading rooms.
+    pub portal_urls: Vec<String>,
+    pub website: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Agency {
+    /// Create a federal agency with an ID derived from its abbreviation,
+    /// or its name if it has none.
+    pub fn new(name: String, abbreviation: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: agency_id(&name, abbreviation.as_deref()),
+            name,
+            abbreviation,
+            jurisdiction: "US".to_string(),
+            level: AgencyLevel::Federal,
+            parent_id: None,
+            foia_email: None,
+            foia_phone: None,
+            foia_address: None,
+            portal_urls: Vec::new(),
+            website: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The tag given to this agency's documents.
+    pub fn tag(&self) -> String {
+        agency_tag(&self.id)
+    }
+}
+
+/// Registry ID for an agency: its abbreviation, or its name, as a tag slug.
+pub fn agency_id(name: &str, abbreviation: Option<&str>) -> String {
+    abbreviation
+        .map(tag_slug)
+        .filter(|slug| !slug.is_empty())
+        .unwrap_or_else(|| tag_slug(name))
+}
+
+/// The `agency:` tag for a registry ID.
+pub fn agency_tag(id: &str) -> String {
+    format!("agency:{}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agency_level() {
+        for level in AgencyLevel::ALL {
+            assert_eq!(AgencyLevel::from_str(level.as_str()), Some(level));
+        }
+        assert_eq!(AgencyLevel::from_str(" State "), Some(AgencyLevel::State));
+        assert_eq!(AgencyLevel::from_str("city"), Some(AgencyLevel::Municipal));
+        assert_eq!(AgencyLevel::from_str("galactic"), None);
+    }
+
+    #[test]
+    fn test_agency_id_and_tag() {
+        let fbi = Agency::new(
+            "Federal Bureau of Investigation".to_string(),
+            Some("FBI".to_string()),
+        );
+        assert_eq!(fbi.id, "fbi");
+        assert_eq!(fbi.tag(), "agency:fbi");
+        assert_eq!(
+            agency_id("Office of Information Policy", None),
+            "office-of-information-policy"
+        );
+        assert_eq!(
+            agency_id("Office of Information Policy", Some(" ")),
+            "office-of-information-policy"
+        );
+    }
+}
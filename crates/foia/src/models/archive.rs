@@ -35,6 +35,70 @@ pub struct ArchiveSnapshot {
     pub metadata: String,
 }
 
+impl ArchiveSnapshot {
+    fn metadata_str(&self, key: &str) -> Option<String> {
+        let metadata: serde_json::Value = serde_json::from_str(&self.metadata).ok()?;
+        metadata.get(key)?.as_str().map(|s| s.to_string())
+    }
+
+    /// Source this capture was found for, if it was recorded as a fallback
+    /// for a dead URL.
+    pub fn fallback_source(&self) -> Option<String> {
+        self.metadata_str("fallback_source")
+    }
+
+    /// Where a fallback capture is in the offer/ingest cycle.
+    pub fn fallback_state(&self) -> Option<FallbackState> {
+        self.metadata_str("fallback_state")
+            .and_then(|s| FallbackState::from_str(&s))
+    }
+
+    /// Metadata with the fallback state replaced.
+    pub fn metadata_with_state(&self, state: FallbackState) -> serde_json::Value {
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(&self.metadata).unwrap_or_else(|_| serde_json::json!({}));
+        if let Some(obj) = metadata.as_object_mut() {
+            obj.insert("fallback_state".to_string(), state.as_str().into());
+        }
+        metadata
+    }
+}
+
+/// State of a wayback capture offered in place of a dead source URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackState {
+    /// Found and waiting for someone to ingest or dismiss it.
+    Offered,
+    /// Queued for download as a new version.
+    Queued,
+    /// Downloaded and saved as a version of the original document.
+    Ingested,
+    /// Declined; kept so the capture is not offered again.
+    Dismissed,
+}
+
+impl FallbackState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Offered => "offered",
+            Self::Queued => "queued",
+            Self::Ingested => "ingested",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "offered" => Some(Self::Offered),
+            "queued" => Some(Self::Queued),
+            "ingested" => Some(Self::Ingested),
+            "dismissed" => Some(Self::Dismissed),
+            _ => None,
+        }
+    }
+}
+
 /// New archive snapshot for insertion.
 #[derive(Debug, Clone, Insertable)]
 #[diesel(table_name = archive_snapshots)]
@@ -96,6 +160,14 @@ impl NewArchiveSnapshot {
         self.metadata = metadata.to_string();
         self
     }
+
+    /// Mark this snapshot as a fallback offer for a dead URL in `source_id`.
+    pub fn as_fallback(self, source_id: &str) -> Self {
+        self.with_metadata(serde_json::json!({
+            "fallback_source": source_id,
+            "fallback_state": FallbackState::Offered.as_str(),
+        }))
+    }
 }
 
 /// Record of checking an archive for historical versions of a document.
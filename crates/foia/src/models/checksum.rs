@@ -0,0 +1,217 @@
+//! Verification of downloads against checksums published by the source.
+//!
+//! Some agencies publish `SHA256SUMS`/`md5sums` files or per-file checksum
+//! sidecars, sometimes signed. Recording the outcome on each version lets
+//! a document's integrity be demonstrated when it is later cited.
+
+use std::io::Read;
+
+use chrono::{DateTime, Utc};
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Hash algorithm used by a published checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Md5 => "md5",
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Parse an algorithm name such as `SHA256`, `sha-256` or `md5`.
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "md5" => Some(Self::Md5),
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Infer the algorithm from the length of a hex digest.
+    pub fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            32 => Some(Self::Md5),
+            40 => Some(Self::Sha1),
+            64 => Some(Self::Sha256),
+            128 => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Hex digest of `content`.
+    pub fn hash_bytes(&self, content: &[u8]) -> String {
+        match self {
+            Self::Md5 => hex::encode(Md5::digest(content)),
+            Self::Sha1 => hex::encode(Sha1::digest(content)),
+            Self::Sha256 => hex::encode(Sha256::digest(content)),
+            Self::Sha512 => hex::encode(Sha512::digest(content)),
+        }
+    }
+
+    /// Hex digest of everything read from `reader`.
+    pub fn hash_reader(&self, reader: impl Read) -> std::io::Result<String> {
+        match self {
+            Self::Md5 => digest_reader::<Md5>(reader),
+            Self::Sha1 => digest_reader::<Sha1>(reader),
+            Self::Sha256 => digest_reader::<Sha256>(reader),
+            Self::Sha512 => digest_reader::<Sha512>(reader),
+        }
+    }
+}
+
+fn digest_reader<D: Digest>(mut reader: impl Read) -> std::io::Result<String> {
+    let mut hasher = D::new();
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Outcome of checking a download against a published checksum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumStatus {
+    /// Content matches the published checksum (and its signature, if any).
+    Verified,
+    /// Content does not match the published checksum.
+    Mismatch,
+    /// Content matches, but the manifest's signature did not verify.
+    BadSignature,
+}
+
+impl ChecksumStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Verified => "verified",
+            Self::Mismatch => "mismatch",
+            Self::BadSignature => "bad_signature",
+        }
+    }
+}
+
+/// Result of verifying a version against a published checksum.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumVerification {
+    pub status: ChecksumStatus,
+    pub algorithm: ChecksumAlgorithm,
+    /// Digest published by the source (lowercase hex).
+    pub expected: String,
+    /// Digest of the content we downloaded (lowercase hex).
+    pub actual: String,
+    /// Manifest or sidecar file the expected digest came from.
+    pub manifest_url: String,
+    /// Whether the manifest's detached signature verified; `None` if unsigned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature_valid: Option<bool>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ChecksumVerification {
+    /// Compare `actual` with the published `expected` digest.
+    pub fn new(
+        algorithm: ChecksumAlgorithm,
+        expected: &str,
+        actual: &str,
+        manifest_url: &str,
+        signature_valid: Option<bool>,
+    ) -> Self {
+        let expected = expected.to_ascii_lowercase();
+        let actual = actual.to_ascii_lowercase();
+        let status = if expected != actual {
+            ChecksumStatus::Mismatch
+        } else if signature_valid == Some(false) {
+            ChecksumStatus::BadSignature
+        } else {
+            ChecksumStatus::Verified
+        };
+        Self {
+            status,
+            algorithm,
+            expected,
+            actual,
+            manifest_url: manifest_url.to_string(),
+            signature_valid,
+            checked_at: Utc::now(),
+        }
+    }
+
+    /// Whether the content and any signature checked out.
+    pub fn is_verified(&self) -> bool {
+        self.status == ChecksumStatus::Verified
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_algorithm_digests() {
+        let content = b"hello\n";
+        assert_eq!(
+            ChecksumAlgorithm::Md5.hash_bytes(content),
+            "b1946ac92492d2347c6235b4d2611184"
+        );
+        assert_eq!(
+            ChecksumAlgorithm::Sha1.hash_bytes(content),
+            "f572d396fae9206628714fb2ce00f72e94f2258f"
+        );
+        for algorithm in [
+            ChecksumAlgorithm::Md5,
+            ChecksumAlgorithm::Sha1,
+            ChecksumAlgorithm::Sha256,
+            ChecksumAlgorithm::Sha512,
+        ] {
+            let digest = algorithm.hash_bytes(content);
+            assert_eq!(
+                ChecksumAlgorithm::from_hex_len(digest.len()),
+                Some(algorithm)
+            );
+            assert_eq!(algorithm.hash_reader(&content[..]).unwrap(), digest);
+            assert_eq!(
+                ChecksumAlgorithm::from_str(algorithm.as_str()),
+                Some(algorithm)
+            );
+        }
+        assert_eq!(
+            ChecksumAlgorithm::from_str("SHA-256"),
+            Some(ChecksumAlgorithm::Sha256)
+        );
+    }
+
+    #[test]
+    fn test_verification_status() {
+        let sha = ChecksumAlgorithm::Sha256;
+        let ok = ChecksumVerification::new(sha, "ABC", "abc", "https://x/SHA256SUMS", None);
+        assert!(ok.is_verified());
+
+        let mismatch =
+            ChecksumVerification::new(sha, "abc", "def", "https://x/SHA256SUMS", Some(true));
+        assert_eq!(mismatch.status, ChecksumStatus::Mismatch);
+
+        let unsigned =
+            ChecksumVerification::new(sha, "abc", "abc", "https://x/SHA256SUMS", Some(false));
+        assert_eq!(unsigned.status, ChecksumStatus::BadSignature);
+    }
+}
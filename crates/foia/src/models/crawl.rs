@@ -50,6 +50,20 @@ impl UrlStatus {
             _ => None,
         }
     }
+
+    /// Statuses matched by a queue filter name.
+    ///
+    /// `pending` covers discovered and in-flight URLs, `failed` includes
+    /// exhausted ones, and `all` matches everything (an empty list). Any
+    /// single status name is also accepted.
+    pub fn parse_filter(name: &str) -> Option<Vec<Self>> {
+        match name {
+            "all" => Some(Vec::new()),
+            "pending" => Some(vec![Self::Discovered, Self::Fetching]),
+            "failed" => Some(vec![Self::Failed, Self::Exhausted]),
+            other => Self::from_str(other).map(|s| vec![s]),
+        }
+    }
 }
 
 /// How a URL was discovered.
@@ -131,6 +145,12 @@ impl DiscoveryMethod {
     }
 }
 
+/// Discovery context key for tags to give documents fetched from a URL.
+const DISCOVERY_TAGS_KEY: &str = "tags";
+
+/// Discovery context key for metadata to give documents fetched from a URL.
+const DISCOVERY_METADATA_KEY: &str = "metadata";
+
 /// A URL discovered during crawling with its discovery context.
 ///
 /// Tracks how the URL was found, its relationship to parent URLs,
@@ -200,6 +220,44 @@ impl CrawlUrl {
         }
     }
 
+    /// Tag documents fetched from this URL with `tags`, as when a reading
+    /// room files it under a subject.
+    pub fn with_tags(mut self, tags: &[String]) -> Self {
+        if !tags.is_empty() {
+            self.discovery_context
+                .insert(DISCOVERY_TAGS_KEY.to_string(), tags.into());
+        }
+        self
+    }
+
+    /// Tags given to documents fetched from this URL.
+    pub fn discovery_tags(&self) -> Vec<String> {
+        self.discovery_context
+            .get(DISCOVERY_TAGS_KEY)
+            .and_then(|tags| serde_json::from_value(tags.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record metadata found alongside the link (e.g. an aggregator's
+    /// request and response dates) for documents fetched from this URL.
+    pub fn with_metadata(mut self, metadata: serde_json::Map<String, serde_json::Value>) -> Self {
+        if !metadata.is_empty() {
+            self.discovery_context.insert(
+                DISCOVERY_METADATA_KEY.to_string(),
+                serde_json::Value::Object(metadata),
+            );
+        }
+        self
+    }
+
+    /// Metadata given to documents fetched from this URL.
+    pub fn discovery_metadata(&self) -> serde_json::Map<String, serde_json::Value> {
+        match self.discovery_context.get(DISCOVERY_METADATA_KEY) {
+            Some(serde_json::Value::Object(metadata)) => metadata.clone(),
+            _ => serde_json::Map::new(),
+        }
+    }
+
     /// Mark URL as currently being fetched.
     pub fn mark_fetching(&mut self) {
         self.status = UrlStatus::Fetching;
@@ -244,6 +302,19 @@ impl CrawlUrl {
             self.next_retry_at = Some(Utc::now() + Duration::minutes(backoff_minutes));
         }
     }
+
+    /// When the last failure happened, recovered from the retry schedule
+    /// [`Self::mark_failed`] set. `None` unless the URL is failed or exhausted.
+    pub fn failed_at(&self) -> Option<DateTime<Utc>> {
+        let next_retry_at = self.next_retry_at?;
+        match self.status {
+            UrlStatus::Exhausted => Some(next_retry_at - Duration::days(70)),
+            UrlStatus::Failed => {
+                Some(next_retry_at - Duration::minutes(5_i64.pow(self.retry_count.min(10))))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Record of an HTTP request made during crawling.
@@ -373,6 +444,43 @@ mod tests {
         assert_eq!(UrlStatus::from_str("invalid"), None);
     }
 
+    #[test]
+    fn test_url_status_parse_filter() {
+        assert_eq!(
+            UrlStatus::parse_filter("failed"),
+            Some(vec![UrlStatus::Failed, UrlStatus::Exhausted])
+        );
+        assert_eq!(
+            UrlStatus::parse_filter("skipped"),
+            Some(vec![UrlStatus::Skipped])
+        );
+        assert_eq!(UrlStatus::parse_filter("all"), Some(vec![]));
+        assert_eq!(UrlStatus::parse_filter("bogus"), None);
+    }
+
+    #[test]
+    fn test_discovery_context() {
+        let url = CrawlUrl::new(
+            "https://vault.fbi.gov/al-capone/part-1/at_download/file".to_string(),
+            "fbi_vault".to_string(),
+            DiscoveryMethod::HtmlLink,
+            None,
+            2,
+        );
+        assert!(url.discovery_tags().is_empty());
+
+        let tags = vec!["agency:fbi".to_string(), "topic:al-capone".to_string()];
+        let url = url.with_tags(&tags);
+        assert_eq!(url.discovery_tags(), tags);
+        assert!(url.discovery_metadata().is_empty());
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("requester".to_string(), "Government Attic".into());
+        let url = url.with_metadata(metadata.clone());
+        assert_eq!(url.discovery_metadata(), metadata);
+        assert_eq!(url.discovery_tags(), tags);
+    }
+
     #[test]
     fn test_discovery_method_roundtrip() {
         let methods = [
@@ -500,10 +608,15 @@ mod tests {
         assert_eq!(url.status, UrlStatus::Failed);
         assert_eq!(url.retry_count, 2);
 
+        let failed_at = url.failed_at().unwrap();
+        assert!((Utc::now() - failed_at).num_seconds().abs() < 5);
+
         // Third failure - should be exhausted
         url.mark_failed("connection timeout", 3);
         assert_eq!(url.status, UrlStatus::Exhausted);
         assert_eq!(url.retry_count, 3);
+        let failed_at = url.failed_at().unwrap();
+        assert!((Utc::now() - failed_at).num_seconds().abs() < 5);
     }
 
     #[test]
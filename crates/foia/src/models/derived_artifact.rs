@@ -0,0 +1,125 @@
+//! Derived artifact model for files generated from documents.
+//!
+//! Artifacts are outputs of processing a document version that aren't plain
+//! text: searchable PDFs, redacted copies, tables, transcripts, thumbnails
+//! and text layers.
+//! Each is stored on disk under the documents directory and tied to the
+//! version it was made from and, when there is one, the analysis result
+//! that produced it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What an artifact is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// The original PDF with an invisible OCR text layer.
+    SearchablePdf,
+    /// A copy of the original PDF with regions blacked out and no text
+    /// under them, published in its place.
+    RedactedPdf,
+    /// A table extracted from a page.
+    Table,
+    /// A transcript of audio or video.
+    Transcript,
+    /// A preview image.
+    Thumbnail,
+    /// Positioned OCR text (hOCR or similar) for a page or document.
+    TextLayer,
+}
+
+impl ArtifactKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SearchablePdf => "searchable_pdf",
+            Self::RedactedPdf => "redacted_pdf",
+            Self::Table => "table",
+            Self::Transcript => "transcript",
+            Self::Thumbnail => "thumbnail",
+            Self::TextLayer => "text_layer",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "searchable_pdf" => Some(Self::SearchablePdf),
+            "redacted_pdf" => Some(Self::RedactedPdf),
+            "table" => Some(Self::Table),
+            "transcript" => Some(Self::Transcript),
+            "thumbnail" => Some(Self::Thumbnail),
+            "text_layer" => Some(Self::TextLayer),
+            _ => None,
+        }
+    }
+
+    /// Kinds built from OCR text, which go stale when pages are re-OCR'd.
+    pub fn from_ocr() -> &'static [Self] {
+        &[Self::SearchablePdf, Self::TextLayer]
+    }
+}
+
+/// Lifecycle state of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactStatus {
+    /// Up to date with its inputs.
+    Ready,
+    /// Its inputs changed; still servable until regenerated or pruned.
+    Stale,
+}
+
+impl ArtifactStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ready => "ready",
+            Self::Stale => "stale",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "ready" => Some(Self::Ready),
+            "stale" => Some(Self::Stale),
+            _ => None,
+        }
+    }
+}
+
+/// A file generated from a document version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedArtifact {
+    pub id: i64,
+    pub document_id: String,
+    pub version_id: i64,
+    pub kind: ArtifactKind,
+    /// Name, unique per version and kind (e.g. `searchable.pdf`).
+    pub name: String,
+    pub mime_type: String,
+    /// Path relative to the documents directory.
+    pub file_path: String,
+    pub file_size: u64,
+    /// SHA-256 of the file.
+    pub content_hash: String,
+    /// Analysis result that produced this artifact, if any.
+    pub analysis_result_id: Option<i64>,
+    pub status: ArtifactStatus,
+    pub metadata: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An artifact to record, after its file has been written.
+#[derive(Debug, Clone)]
+pub struct NewDerivedArtifact {
+    pub document_id: String,
+    pub version_id: i64,
+    pub kind: ArtifactKind,
+    pub name: String,
+    pub mime_type: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub content_hash: String,
+    pub analysis_result_id: Option<i64>,
+    pub metadata: Option<serde_json::Value>,
+}
@@ -10,41 +10,72 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 
-/// Dual content hashes for collision-resistant deduplication.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ContentHashes {
-    pub sha256: String,
-    pub blake3: String,
-}
+use super::checksum::ChecksumVerification;
 
 /// Processing status of a document.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Documents move through the workflow stages pending, downloaded,
+/// extracted, analyzed, reviewed and published; which moves are allowed is
+/// set by a [`StatusWorkflow`](super::StatusWorkflow). Extracted and analyzed
+/// are stored under their older names, `ocr_complete` and `indexed`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DocumentStatus {
     Pending,
     Downloaded,
+    /// Text extracted from every page.
     OcrComplete,
+    /// Summarized and tagged.
     Indexed,
+    /// Checked by a curator.
+    Reviewed,
+    /// Cleared for release.
+    Published,
     Failed,
 }
 
 impl DocumentStatus {
+    /// Every status, in workflow order.
+    pub const ALL: [DocumentStatus; 7] = [
+        Self::Pending,
+        Self::Downloaded,
+        Self::OcrComplete,
+        Self::Indexed,
+        Self::Reviewed,
+        Self::Published,
+        Self::Failed,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Pending => "pending",
             Self::Downloaded => "downloaded",
             Self::OcrComplete => "ocr_complete",
             Self::Indexed => "indexed",
+            Self::Reviewed => "reviewed",
+            Self::Published => "published",
             Self::Failed => "failed",
         }
     }
 
+    /// Workflow stage name shown to users.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::OcrComplete => "extracted",
+            Self::Indexed => "analyzed",
+            other => other.as_str(),
+        }
+    }
+
+    /// Parse a stored status or a workflow stage name.
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "pending" => Some(Self::Pending),
             "downloaded" => Some(Self::Downloaded),
-            "ocr_complete" => Some(Self::OcrComplete),
-            "indexed" => Some(Self::Indexed),
+            "ocr_complete" | "extracted" => Some(Self::OcrComplete),
+            "indexed" | "analyzed" => Some(Self::Indexed),
+            "reviewed" => Some(Self::Reviewed),
+            "published" => Some(Self::Published),
             "failed" => Some(Self::Failed),
             _ => None,
         }
@@ -53,8 +84,11 @@ impl DocumentStatus {
 
 /// A specific version of a document's content.
 ///
-/// Content is identified by dual hashes (SHA-256 + BLAKE3) for
-/// collision-resistant deduplication across crawls.
+/// Content is identified by its BLAKE3 hash, which is fast enough for
+/// multi-GB files. Versions stored before the switch keep their SHA-256
+/// hash in `content_hash`, and with it their storage path; their BLAKE3
+/// hash is backfilled by `foia db migrate`. Until then they are compared
+/// by SHA-256.
 ///
 /// File paths are deterministic and computed at runtime from the content hash,
 /// original filename, and dedup_index. Legacy records may have a stored
@@ -63,9 +97,11 @@ impl DocumentStatus {
 pub struct DocumentVersion {
     /// Database row ID.
     pub id: i64,
-    /// SHA-256 hash of the document content.
+    /// Hash the storage path is derived from: BLAKE3, or SHA-256 for
+    /// content first stored before BLAKE3 hashing.
     pub content_hash: String,
-    /// BLAKE3 hash of the document content (for deduplication verification).
+    /// BLAKE3 hash of the document content. Missing only on old versions
+    /// not yet backfilled.
     pub content_hash_blake3: Option<String>,
     /// Legacy stored file path. New records store None (path is deterministic).
     pub file_path: Option<PathBuf>,
@@ -89,26 +125,88 @@ pub struct DocumentVersion {
     pub earliest_archived_at: Option<DateTime<Utc>>,
     /// Collision index for deterministic path computation. None means depth=2.
     pub dedup_index: Option<u32>,
+    /// Result of checking against a checksum the source published, if any.
+    pub checksum: Option<ChecksumVerification>,
+    /// Content-Type the server declared, when the content showed it was wrong
+    /// and `mime_type` holds the detected type instead.
+    pub declared_mime_type: Option<String>,
+    /// SHA-256 of newly fetched content, for comparing it against versions
+    /// not yet backfilled. Not stored.
+    #[serde(skip)]
+    pub computed_sha256: Option<String>,
 }
 
 impl DocumentVersion {
-    /// Compute SHA-256 hash of content.
+    /// Compute the content hash (BLAKE3).
     pub fn compute_hash(content: &[u8]) -> String {
+        hex::encode(blake3::hash(content).as_bytes())
+    }
+
+    /// Compute the content hash from a reader, for files too large to hold
+    /// in memory.
+    pub fn compute_hash_reader(mut reader: impl std::io::Read) -> std::io::Result<String> {
+        let mut hasher = blake3::Hasher::new();
+        let mut buf = vec![0u8; 1024 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hex::encode(hasher.finalize().as_bytes()))
+    }
+
+    /// Compute the SHA-256 hash content was identified by before BLAKE3.
+    pub fn compute_hash_sha256(content: &[u8]) -> String {
+        hex::encode(Sha256::digest(content))
+    }
+
+    /// Compute the SHA-256 hash from a reader.
+    pub fn compute_hash_sha256_reader(mut reader: impl std::io::Read) -> std::io::Result<String> {
         let mut hasher = Sha256::new();
-        hasher.update(content);
-        hex::encode(hasher.finalize())
+        let mut buf = vec![0u8; 1024 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        Ok(hex::encode(hasher.finalize()))
     }
 
-    /// Compute BLAKE3 hash of content.
-    pub fn compute_hash_blake3(content: &[u8]) -> String {
-        hex::encode(blake3::hash(content).as_bytes())
+    /// The hash identifying this version's content across documents: its
+    /// BLAKE3 hash, or its SHA-256 hash if that hasn't been backfilled.
+    pub fn dedup_hash(&self) -> &str {
+        self.content_hash_blake3
+            .as_deref()
+            .unwrap_or(&self.content_hash)
     }
 
-    /// Compute both SHA-256 and BLAKE3 hashes for deduplication.
-    pub fn compute_dual_hashes(content: &[u8]) -> ContentHashes {
-        ContentHashes {
-            sha256: Self::compute_hash(content),
-            blake3: Self::compute_hash_blake3(content),
+    /// The SHA-256 hash, for versions stored before BLAKE3 hashing or
+    /// new content hashed with both.
+    pub fn sha256(&self) -> Option<&str> {
+        match &self.content_hash_blake3 {
+            Some(blake3) if *blake3 == self.content_hash => self.computed_sha256.as_deref(),
+            _ => Some(&self.content_hash),
+        }
+    }
+
+    /// Whether `other` holds the same content. Compares BLAKE3 hashes, or
+    /// SHA-256 when either version hasn't been backfilled.
+    pub fn same_content(&self, other: &DocumentVersion) -> bool {
+        match (&self.content_hash_blake3, &other.content_hash_blake3) {
+            (Some(a), Some(b)) => a == b,
+            _ => matches!((self.sha256(), other.sha256()), (Some(a), Some(b)) if a == b),
+        }
+    }
+
+    /// Whether `content`, whose BLAKE3 hash is `hash`, is this version's.
+    pub fn holds_content(&self, hash: &str, content: &[u8]) -> bool {
+        match &self.content_hash_blake3 {
+            Some(blake3) => blake3 == hash,
+            None => Self::compute_hash_sha256(content) == self.content_hash,
         }
     }
 
@@ -125,11 +223,12 @@ impl DocumentVersion {
         original_filename: Option<String>,
         server_date: Option<DateTime<Utc>>,
     ) -> Self {
-        let hashes = Self::compute_dual_hashes(content);
+        let hash = Self::compute_hash(content);
         Self {
             id: 0, // Set by database
-            content_hash: hashes.sha256,
-            content_hash_blake3: Some(hashes.blake3),
+            content_hash: hash.clone(),
+            content_hash_blake3: Some(hash),
+            computed_sha256: Some(Self::compute_hash_sha256(content)),
             file_path: None,
             file_size: content.len() as u64,
             mime_type,
@@ -141,12 +240,14 @@ impl DocumentVersion {
             archive_snapshot_id: None,
             earliest_archived_at: None,
             dedup_index: None,
+            checksum: None,
+            declared_mime_type: None,
         }
     }
 
-    /// Create a new document version with pre-computed hashes.
-    pub fn with_precomputed_hashes(
-        hashes: ContentHashes,
+    /// Create a new document version with a pre-computed content hash.
+    pub fn with_precomputed_hash(
+        hash: String,
         file_size: u64,
         mime_type: String,
         source_url: Option<String>,
@@ -155,8 +256,9 @@ impl DocumentVersion {
     ) -> Self {
         Self {
             id: 0, // Set by database
-            content_hash: hashes.sha256,
-            content_hash_blake3: Some(hashes.blake3),
+            content_hash: hash.clone(),
+            content_hash_blake3: Some(hash),
+            computed_sha256: None,
             file_path: None,
             file_size,
             mime_type,
@@ -168,6 +270,8 @@ impl DocumentVersion {
             archive_snapshot_id: None,
             earliest_archived_at: None,
             dedup_index: None,
+            checksum: None,
+            declared_mime_type: None,
         }
     }
 
@@ -383,6 +487,22 @@ impl Document {
         }
     }
 
+    /// The stable ID of the document at `url` in a source: a UUID-shaped
+    /// hash of the source and the canonical URL, so re-crawling a URL, with
+    /// or without tracking parameters, always maps to the same document.
+    pub fn stable_id(source_id: &str, url: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(source_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(crate::utils::canonical_url(url).as_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hasher.finalize()[..16]);
+        // Mark it as a version 8 (custom) UUID
+        bytes[6] = (bytes[6] & 0x0f) | 0x80;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        uuid::Uuid::from_bytes(bytes).to_string()
+    }
+
     /// Get the most recent version of this document.
     pub fn current_version(&self) -> Option<&DocumentVersion> {
         self.versions.first()
@@ -391,16 +511,11 @@ impl Document {
     /// Add a new version if content differs from current.
     ///
     /// Returns true if a new version was added, false if content unchanged.
-    /// Uses both SHA-256 and BLAKE3 hashes for collision-resistant comparison.
+    /// Compares BLAKE3 hashes, or SHA-256 when the current version's
+    /// hasn't been backfilled.
     pub fn add_version(&mut self, version: DocumentVersion) -> bool {
         if let Some(current) = self.current_version() {
-            // Check both hashes match (if blake3 available on both)
-            let sha_match = current.content_hash == version.content_hash;
-            let blake_match = match (&current.content_hash_blake3, &version.content_hash_blake3) {
-                (Some(a), Some(b)) => a == b,
-                _ => true, // If either missing, rely on SHA-256 alone
-            };
-            if sha_match && blake_match {
+            if current.same_content(&version) {
                 return false;
             }
         }
@@ -480,7 +595,89 @@ mod tests {
     fn test_compute_hash() {
         let content = b"Hello, World!";
         let hash = DocumentVersion::compute_hash(content);
-        assert_eq!(hash.len(), 64); // SHA-256 produces 64 hex chars
+        assert_eq!(hash.len(), 64); // BLAKE3 produces 64 hex chars
+        assert_eq!(
+            DocumentVersion::compute_hash(b""),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn test_hash_reader_matches_in_memory() {
+        let content = vec![7u8; 3 * 1024 * 1024 + 17];
+        let streamed = DocumentVersion::compute_hash_reader(&content[..]).unwrap();
+        assert_eq!(streamed, DocumentVersion::compute_hash(&content));
+    }
+
+    #[test]
+    fn test_legacy_sha256_version() {
+        let content = b"scanned before blake3";
+        let mut legacy = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        assert_eq!(legacy.sha256(), None);
+
+        legacy.content_hash = DocumentVersion::compute_hash_sha256(content);
+        legacy.content_hash_blake3 = None;
+        assert_eq!(legacy.dedup_hash(), legacy.content_hash);
+
+        legacy.content_hash_blake3 = Some(DocumentVersion::compute_hash(content));
+        assert_eq!(legacy.sha256(), Some(legacy.content_hash.as_str()));
+
+        // The same content fetched again isn't a new version
+        let mut doc = Document::new(
+            "doc1".to_string(),
+            "source1".to_string(),
+            "Test Doc".to_string(),
+            "https://example.com/doc.pdf".to_string(),
+            legacy,
+            serde_json::json!({}),
+        );
+        let refetched = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        assert!(!doc.add_version(refetched));
+    }
+
+    #[test]
+    fn test_legacy_sha256_version_before_backfill() {
+        let content = b"scanned before blake3";
+        let mut legacy = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        legacy.content_hash = DocumentVersion::compute_hash_sha256(content);
+        legacy.content_hash_blake3 = None;
+        let hash = DocumentVersion::compute_hash(content);
+        assert!(legacy.holds_content(&hash, content));
+        assert!(!legacy.holds_content(&DocumentVersion::compute_hash(b"changed"), b"changed"));
+
+        let mut doc = Document::new(
+            "doc1".to_string(),
+            "source1".to_string(),
+            "Test Doc".to_string(),
+            "https://example.com/doc.pdf".to_string(),
+            legacy,
+            serde_json::json!({}),
+        );
+        let refetched = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        assert!(!doc.add_version(refetched));
+
+        // Streamed content only carries a SHA-256 when one was computed
+        let mut streamed = DocumentVersion::with_precomputed_hash(
+            hash,
+            content.len() as u64,
+            "application/pdf".to_string(),
+            None,
+            None,
+            None,
+        );
+        streamed.computed_sha256 = Some(DocumentVersion::compute_hash_sha256(content));
+        assert!(!doc.add_version(streamed));
+
+        let changed = DocumentVersion::new(b"changed", "application/pdf".to_string(), None);
+        assert!(doc.add_version(changed));
+        assert_eq!(doc.versions.len(), 2);
+    }
+
+    #[test]
+    fn test_sha256_reader_matches_in_memory() {
+        let content = vec![7u8; 3 * 1024 * 1024 + 17];
+        let streamed = DocumentVersion::compute_hash_sha256_reader(&content[..]).unwrap();
+        assert_eq!(streamed, DocumentVersion::compute_hash_sha256(&content));
     }
 
     #[test]
@@ -522,6 +719,25 @@ mod tests {
         assert_eq!(doc.versions.len(), 1);
     }
 
+    #[test]
+    fn test_stable_id() {
+        let id = Document::stable_id("fbi", "https://vault.fbi.gov/a.pdf");
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "8");
+        assert_eq!(
+            id,
+            Document::stable_id("fbi", "https://VAULT.fbi.gov/a.pdf?utm_source=feed")
+        );
+        assert_ne!(
+            id,
+            Document::stable_id("cia", "https://vault.fbi.gov/a.pdf")
+        );
+        assert_ne!(
+            id,
+            Document::stable_id("fbi", "https://vault.fbi.gov/b.pdf")
+        );
+    }
+
     #[test]
     #[cfg(not(target_os = "windows"))]
     fn test_resolve_path_legacy_absolute() {
@@ -5,6 +5,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Pages scoring below this OCR quality are considered poorly recognized.
+pub const POOR_OCR_QUALITY: f32 = 0.5;
+
 /// OCR processing status for a page.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -67,6 +70,13 @@ pub struct DocumentPage {
     pub created_at: DateTime<Utc>,
     /// When this page was last updated.
     pub updated_at: DateTime<Utc>,
+    /// Quality score (0.0 - 1.0) of the chosen OCR text, if OCR ran.
+    #[serde(default)]
+    pub ocr_quality: Option<f32>,
+    /// When a curator last corrected `final_text` by hand; corrected text is
+    /// kept when the page is re-OCRed.
+    #[serde(default)]
+    pub corrected_at: Option<DateTime<Utc>>,
 }
 
 impl DocumentPage {
@@ -84,6 +94,8 @@ impl DocumentPage {
             ocr_status: PageOcrStatus::Pending,
             created_at: now,
             updated_at: now,
+            ocr_quality: None,
+            corrected_at: None,
         }
     }
 
@@ -98,6 +110,11 @@ impl DocumentPage {
         }
     }
 
+    /// Whether a curator has corrected this page's text by hand.
+    pub fn is_corrected(&self) -> bool {
+        self.corrected_at.is_some()
+    }
+
     /// Compute final text by choosing the best result.
     /// Prefers OCR over extracted PDF text (unless OCR is empty).
     pub fn compute_final_text(&mut self) {
@@ -0,0 +1,33 @@
+//! Structured fields extracted from document content.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Structured metadata pulled from a document's text.
+///
+/// These describe the record itself (when it was written, who produced it),
+/// as opposed to how and when we acquired it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtractedMetadata {
+    /// Date the document was authored or issued.
+    pub document_date: Option<NaiveDate>,
+    /// Originating agency (e.g. "Federal Bureau of Investigation").
+    pub originating_agency: Option<String>,
+    /// Office, division, or field office within the agency.
+    pub originating_office: Option<String>,
+    /// FOIA request/case tracking number.
+    pub case_number: Option<String>,
+    /// Classification markings found on the document (e.g. "SECRET", "NOFORN").
+    pub classification_markings: Vec<String>,
+}
+
+impl ExtractedMetadata {
+    /// Whether no fields were extracted.
+    pub fn is_empty(&self) -> bool {
+        self.document_date.is_none()
+            && self.originating_agency.is_none()
+            && self.originating_office.is_none()
+            && self.case_number.is_none()
+            && self.classification_markings.is_empty()
+    }
+}
@@ -0,0 +1,114 @@
+//! Reader reports of sensitive or misfiled documents.
+//!
+//! Released records sometimes carry what the agency meant to withhold: a
+//! missed redaction, personal details of a third party, or an unrelated
+//! file attached by mistake. A flag records such a report against a
+//! document, optionally a page, until a curator resolves or dismisses it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Longest note accepted with a flag.
+pub const MAX_FLAG_NOTE_LEN: usize = 2000;
+
+/// Why a document was flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagReason {
+    /// Personal information about someone, e.g. a home address or SSN.
+    Pii,
+    /// Text that should have been redacted, or a redaction that can be
+    /// lifted by selecting the text under it.
+    Redaction,
+    /// The file isn't the document it is listed as.
+    WrongDocument,
+    Other,
+}
+
+impl FlagReason {
+    pub const ALL: [Self; 4] = [Self::Pii, Self::Redaction, Self::WrongDocument, Self::Other];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pii => "pii",
+            Self::Redaction => "redaction",
+            Self::WrongDocument => "wrong_document",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|reason| reason.as_str() == s)
+    }
+
+    /// Short description for listings.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Pii => "Personal information",
+            Self::Redaction => "Should be redacted",
+            Self::WrongDocument => "Wrong document",
+            Self::Other => "Other",
+        }
+    }
+}
+
+/// Where a flag stands in the moderation queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagStatus {
+    /// Waiting for a curator.
+    Open,
+    /// Acted on, e.g. the document was redacted or removed.
+    Resolved,
+    /// Reviewed and found not to need action.
+    Dismissed,
+}
+
+impl FlagStatus {
+    pub const ALL: [Self; 3] = [Self::Open, Self::Resolved, Self::Dismissed];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Open => "open",
+            Self::Resolved => "resolved",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == s)
+    }
+}
+
+/// A report against a document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentFlag {
+    pub id: i32,
+    pub document_id: String,
+    pub reason: FlagReason,
+    /// Page the report is about, if one was given.
+    pub page: Option<u32>,
+    /// What the reader saw, in their words.
+    pub note: String,
+    pub status: FlagStatus,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// What the curator did about it.
+    pub resolution: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for reason in FlagReason::ALL {
+            assert_eq!(FlagReason::from_str(reason.as_str()), Some(reason));
+        }
+        for status in FlagStatus::ALL {
+            assert_eq!(FlagStatus::from_str(status.as_str()), Some(status));
+        }
+        assert_eq!(FlagReason::from_str("spam"), None);
+    }
+}
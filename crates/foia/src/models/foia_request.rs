@@ -0,0 +1,342 @@
+//! FOIA request tracking models.
+//!
+//! A FOIA request is what produces the documents this tool acquires. Tracking
+//! requests alongside their responses records where each document came from
+//! and keeps statutory deadlines in view: federal agencies must respond within
+//! 20 working days of receiving a request, and decide an appeal within 20
+//! working days of receiving it (5 U.S.C. § 552(a)(6)(A)). State public
+//! records laws count differently; see [`DeadlineRule`].
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Working days a federal agency has to answer a request or an appeal.
+pub const RESPONSE_WORKING_DAYS: u32 = 20;
+
+/// How a jurisdiction counts response deadlines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadlineRule {
+    /// Days the agency has to answer a request.
+    pub response_days: u32,
+    /// Days the agency has to decide an appeal.
+    pub appeal_days: u32,
+    /// Count business days; otherwise calendar days, with a deadline that
+    /// lands on a closed day moved to the next business day.
+    pub business_days: bool,
+    /// Agencies close on US federal holidays.
+    pub federal_holidays: bool,
+    /// Other days agencies are closed, e.g. state holidays.
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for DeadlineRule {
+    fn default() -> Self {
+        Self::federal()
+    }
+}
+
+impl DeadlineRule {
+    /// The federal FOIA rule: 20 working days for requests and appeals.
+    pub fn federal() -> Self {
+        Self {
+            response_days: RESPONSE_WORKING_DAYS,
+            appeal_days: RESPONSE_WORKING_DAYS,
+            business_days: true,
+            federal_holidays: true,
+            holidays: Vec::new(),
+        }
+    }
+
+    /// The deadline `days` days after `start`.
+    pub fn deadline(&self, start: NaiveDate, days: u32) -> NaiveDate {
+        if !self.business_days {
+            let mut date = start + Duration::days(days as i64);
+            while !self.is_business_day(date) {
+                date += Duration::days(1);
+            }
+            return date;
+        }
+        let mut date = start;
+        let mut remaining = days;
+        while remaining > 0 {
+            date += Duration::days(1);
+            if self.is_business_day(date) {
+                remaining -= 1;
+            }
+        }
+        date
+    }
+
+    /// Whether agencies are open on `date`.
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !(self.federal_holidays && is_federal_holiday(date))
+            && !self.holidays.contains(&date)
+    }
+}
+
+/// Where a request stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FoiaRequestStatus {
+    /// Sent to the agency; no reply yet.
+    Filed,
+    /// The agency confirmed receipt, usually with a tracking number.
+    Acknowledged,
+    /// Records were released or the request was closed.
+    Fulfilled,
+    /// A denial or partial release was appealed.
+    Appealed,
+}
+
+impl FoiaRequestStatus {
+    pub const ALL: [Self; 4] = [
+        Self::Filed,
+        Self::Acknowledged,
+        Self::Fulfilled,
+        Self::Appealed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Filed => "filed",
+            Self::Acknowledged => "acknowledged",
+            Self::Fulfilled => "fulfilled",
+            Self::Appealed => "appealed",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == s)
+    }
+
+    /// Whether the agency still owes a response.
+    pub fn is_open(&self) -> bool {
+        !matches!(self, Self::Fulfilled)
+    }
+}
+
+/// A FOIA request filed with an agency.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoiaRequest {
+    pub id: String,
+    pub agency: String,
+    /// Short description, e.g. "COINTELPRO files on ...".
+    pub subject: String,
+    /// Full text of the request as sent.
+    pub request_text: String,
+    /// Agency-assigned tracking or case number.
+    pub tracking_number: Option<String>,
+    /// Source the responsive documents are scraped or imported into.
+    pub source_id: Option<String>,
+    /// Whose deadline rules apply; None uses the configured default.
+    pub jurisdiction: Option<String>,
+    pub status: FoiaRequestStatus,
+    pub filed_on: NaiveDate,
+    pub acknowledged_on: Option<NaiveDate>,
+    pub fulfilled_on: Option<NaiveDate>,
+    pub appealed_on: Option<NaiveDate>,
+    /// When an overdue reminder was last sent.
+    pub reminded_on: Option<NaiveDate>,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FoiaRequest {
+    /// A newly filed request.
+    pub fn new(agency: String, subject: String, filed_on: NaiveDate) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            agency,
+            subject,
+            request_text: String::new(),
+            tracking_number: None,
+            source_id: None,
+            jurisdiction: None,
+            status: FoiaRequestStatus::Filed,
+            filed_on,
+            acknowledged_on: None,
+            fulfilled_on: None,
+            appealed_on: None,
+            reminded_on: None,
+            notes: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Move to `status` on `date`, recording the date of the transition.
+    pub fn set_status(&mut self, status: FoiaRequestStatus, date: NaiveDate) {
+        match status {
+            FoiaRequestStatus::Filed => {}
+            FoiaRequestStatus::Acknowledged => self.acknowledged_on = Some(date),
+            FoiaRequestStatus::Fulfilled => self.fulfilled_on = Some(date),
+            FoiaRequestStatus::Appealed => {
+                self.appealed_on = Some(date);
+                self.fulfilled_on = None;
+            }
+        }
+        self.status = status;
+    }
+
+    /// When the agency's answer is due under `rule`: counted from filing,
+    /// or from the appeal once appealed. None once fulfilled.
+    pub fn due_on(&self, rule: &DeadlineRule) -> Option<NaiveDate> {
+        match self.status {
+            FoiaRequestStatus::Fulfilled => None,
+            FoiaRequestStatus::Appealed => {
+                Some(rule.deadline(self.appealed_on.unwrap_or(self.filed_on), rule.appeal_days))
+            }
+            FoiaRequestStatus::Filed | FoiaRequestStatus::Acknowledged => {
+                Some(rule.deadline(self.filed_on, rule.response_days))
+            }
+        }
+    }
+
+    /// Calendar days until the answer is due; negative when overdue.
+    pub fn days_until_due(&self, rule: &DeadlineRule, today: NaiveDate) -> Option<i64> {
+        self.due_on(rule).map(|due| (due - today).num_days())
+    }
+
+    /// Whether the agency has missed its deadline.
+    pub fn is_overdue(&self, rule: &DeadlineRule, today: NaiveDate) -> bool {
+        self.days_until_due(rule, today)
+            .is_some_and(|days| days < 0)
+    }
+}
+
+/// US federal holidays (5 U.S.C. § 6103), as observed: a holiday on a
+/// Saturday is observed the Friday before, on a Sunday the Monday after.
+fn is_federal_holiday(date: NaiveDate) -> bool {
+    let year = date.year();
+    let fixed = [(1, 1), (6, 19), (7, 4), (11, 11), (12, 25)];
+    let floating = [
+        nth_weekday(year, 1, Weekday::Mon, 3), // Martin Luther King Jr. Day
+        nth_weekday(year, 2, Weekday::Mon, 3), // Washington's Birthday
+        last_weekday(year, 5, Weekday::Mon),   // Memorial Day
+        nth_weekday(year, 9, Weekday::Mon, 1), // Labor Day
+        nth_weekday(year, 10, Weekday::Mon, 2), // Columbus Day
+        nth_weekday(year, 11, Weekday::Thu, 4), // Thanksgiving Day
+    ];
+
+    // Next New Year's Day can be observed on December 31st
+    let mut fixed_observed = fixed
+        .iter()
+        .filter_map(|&(month, day)| NaiveDate::from_ymd_opt(year, month, day))
+        .chain(NaiveDate::from_ymd_opt(year + 1, 1, 1))
+        .map(observed);
+
+    floating.into_iter().flatten().any(|d| d == date) || fixed_observed.any(|d| d == date)
+}
+
+fn observed(holiday: NaiveDate) -> NaiveDate {
+    match holiday.weekday() {
+        Weekday::Sat => holiday - Duration::days(1),
+        Weekday::Sun => holiday + Duration::days(1),
+        _ => holiday,
+    }
+}
+
+/// The `n`th `weekday` of a month.
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n)
+}
+
+/// The last `weekday` of a month.
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> Option<NaiveDate> {
+    nth_weekday(year, month, weekday, 5).or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in FoiaRequestStatus::ALL {
+            assert_eq!(FoiaRequestStatus::from_str(status.as_str()), Some(status));
+        }
+        assert_eq!(FoiaRequestStatus::from_str("denied"), None);
+        assert!(!FoiaRequestStatus::Fulfilled.is_open());
+    }
+
+    #[test]
+    fn test_federal_holidays() {
+        assert!(is_federal_holiday(date("2024-01-15"))); // MLK Day
+        assert!(is_federal_holiday(date("2024-05-27"))); // Memorial Day
+        assert!(is_federal_holiday(date("2024-11-28"))); // Thanksgiving
+        assert!(is_federal_holiday(date("2026-07-03"))); // July 4th on a Saturday
+        assert!(is_federal_holiday(date("2022-12-26"))); // Christmas on a Sunday
+        assert!(is_federal_holiday(date("2021-12-31"))); // New Year's 2022 on a Saturday
+        assert!(!is_federal_holiday(date("2024-07-05")));
+    }
+
+    #[test]
+    fn test_business_day_deadlines() {
+        let rule = DeadlineRule::federal();
+        // Friday + 1 working day is Monday
+        assert_eq!(rule.deadline(date("2024-03-01"), 1), date("2024-03-04"));
+        // Four plain weeks
+        assert_eq!(rule.deadline(date("2024-03-01"), 20), date("2024-03-29"));
+        // Skips Thanksgiving
+        assert_eq!(rule.deadline(date("2024-11-27"), 1), date("2024-11-29"));
+
+        let state = DeadlineRule {
+            federal_holidays: false,
+            holidays: vec![date("2024-03-04")],
+            ..DeadlineRule::federal()
+        };
+        assert_eq!(state.deadline(date("2024-03-01"), 1), date("2024-03-05"));
+        assert_eq!(state.deadline(date("2024-11-27"), 1), date("2024-11-28"));
+    }
+
+    #[test]
+    fn test_calendar_day_deadlines() {
+        let rule = DeadlineRule {
+            response_days: 10,
+            appeal_days: 10,
+            business_days: false,
+            ..DeadlineRule::federal()
+        };
+        assert_eq!(rule.deadline(date("2024-03-01"), 10), date("2024-03-11"));
+        // Lands on Saturday, moves to Monday
+        assert_eq!(rule.deadline(date("2024-03-06"), 10), date("2024-03-18"));
+        // Lands on Memorial Day, moves to Tuesday
+        assert_eq!(rule.deadline(date("2024-05-17"), 10), date("2024-05-28"));
+    }
+
+    #[test]
+    fn test_due_dates() {
+        let mut request = FoiaRequest::new(
+            "FBI".to_string(),
+            "Records on X".to_string(),
+            date("2024-03-01"),
+        );
+        let rule = DeadlineRule::federal();
+        assert_eq!(request.due_on(&rule), Some(date("2024-03-29")));
+        assert!(!request.is_overdue(&rule, date("2024-03-29")));
+        assert!(request.is_overdue(&rule, date("2024-04-01")));
+        assert_eq!(request.days_until_due(&rule, date("2024-03-19")), Some(10));
+
+        request.set_status(FoiaRequestStatus::Fulfilled, date("2024-04-10"));
+        assert_eq!(request.due_on(&rule), None);
+        assert!(!request.is_overdue(&rule, date("2025-01-01")));
+
+        request.set_status(FoiaRequestStatus::Appealed, date("2024-05-01"));
+        assert_eq!(request.fulfilled_on, None);
+        // Skips Memorial Day
+        assert_eq!(request.due_on(&rule), Some(date("2024-05-30")));
+
+        let short_appeals = DeadlineRule {
+            appeal_days: 5,
+            ..DeadlineRule::federal()
+        };
+        assert_eq!(request.due_on(&short_appeals), Some(date("2024-05-08")));
+    }
+}
@@ -0,0 +1,254 @@
+//! Geographic models for placing documents on a map.
+//!
+//! A document's map position is its most-mentioned geocoded location entity.
+//! The map view groups nearby positions into grid cells so a large archive
+//! stays readable at any zoom.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A latitude/longitude rectangle in degrees. Boxes crossing the
+/// antimeridian are not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    /// The whole map.
+    pub const WORLD: Self = Self {
+        south: -90.0,
+        west: -180.0,
+        north: 90.0,
+        east: 180.0,
+    };
+
+    /// Parse `south,west,north,east`, as used in `bbox` query parameters.
+    pub fn parse(s: &str) -> Option<Self> {
+        let parts: Vec<f64> = s
+            .split(',')
+            .map(|p| p.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        let &[south, west, north, east] = parts.as_slice() else {
+            return None;
+        };
+        let bbox = Self {
+            south,
+            west,
+            north,
+            east,
+        };
+        let valid = (-90.0..=90.0).contains(&south)
+            && (-90.0..=90.0).contains(&north)
+            && (-180.0..=180.0).contains(&west)
+            && (-180.0..=180.0).contains(&east)
+            && south <= north
+            && west <= east;
+        valid.then_some(bbox)
+    }
+
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        (self.south..=self.north).contains(&latitude)
+            && (self.west..=self.east).contains(&longitude)
+    }
+
+    /// Format for a `bbox` query parameter.
+    pub fn to_param(&self) -> String {
+        format!("{},{},{},{}", self.south, self.west, self.north, self.east)
+    }
+}
+
+/// Where a document sits on the map.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentLocation {
+    pub document_id: String,
+    /// The location entity the position came from, e.g. "Dallas".
+    pub place: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// How often the document mentions the place.
+    pub mentions: u32,
+}
+
+/// Nearby documents shown as one map marker.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapCluster {
+    /// Mean position of the members.
+    pub latitude: f64,
+    pub longitude: f64,
+    pub count: u64,
+    /// The place most of the members are placed at.
+    pub place: String,
+    /// Extent of the members, for zooming in or browsing them.
+    pub bounds: BoundingBox,
+}
+
+/// Choose where to place a document among its geocoded places
+/// (`(place, latitude, longitude)`): the one its text mentions most,
+/// ignoring case. Ties go to the alphabetically first place.
+pub fn primary_location(
+    document_id: &str,
+    text: &str,
+    places: &[(String, f64, f64)],
+) -> Option<DocumentLocation> {
+    let text = text.to_lowercase();
+    places
+        .iter()
+        .map(|(place, latitude, longitude)| {
+            let needle = place.to_lowercase();
+            // The place was extracted from the text, so it's there at least once
+            let mentions = if needle.is_empty() {
+                1
+            } else {
+                text.matches(&needle).count().max(1) as u32
+            };
+            (place, *latitude, *longitude, mentions)
+        })
+        .max_by(|a, b| a.3.cmp(&b.3).then_with(|| b.0.cmp(a.0)))
+        .map(|(place, latitude, longitude, mentions)| DocumentLocation {
+            document_id: document_id.to_string(),
+            place: place.clone(),
+            latitude,
+            longitude,
+            mentions,
+        })
+}
+
+/// Group locations into clusters, one per grid cell of `cell_degrees`
+/// that has any. Largest clusters come first.
+pub fn cluster_locations(locations: &[DocumentLocation], cell_degrees: f64) -> Vec<MapCluster> {
+    struct Cell<'a> {
+        lat_sum: f64,
+        lon_sum: f64,
+        count: u64,
+        places: HashMap<&'a str, u64>,
+        bounds: BoundingBox,
+    }
+
+    let cell_degrees = cell_degrees.max(1e-6);
+    let mut cells: HashMap<(i64, i64), Cell<'_>> = HashMap::new();
+    for loc in locations {
+        let key = (
+            (loc.latitude / cell_degrees).floor() as i64,
+            (loc.longitude / cell_degrees).floor() as i64,
+        );
+        let cell = cells.entry(key).or_insert_with(|| Cell {
+            lat_sum: 0.0,
+            lon_sum: 0.0,
+            count: 0,
+            places: HashMap::new(),
+            bounds: BoundingBox {
+                south: loc.latitude,
+                west: loc.longitude,
+                north: loc.latitude,
+                east: loc.longitude,
+            },
+        });
+        cell.lat_sum += loc.latitude;
+        cell.lon_sum += loc.longitude;
+        cell.count += 1;
+        *cell.places.entry(&loc.place).or_default() += 1;
+        cell.bounds.south = cell.bounds.south.min(loc.latitude);
+        cell.bounds.north = cell.bounds.north.max(loc.latitude);
+        cell.bounds.west = cell.bounds.west.min(loc.longitude);
+        cell.bounds.east = cell.bounds.east.max(loc.longitude);
+    }
+
+    let mut clusters: Vec<MapCluster> = cells
+        .into_values()
+        .map(|cell| {
+            let place = cell
+                .places
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(place, _)| place.to_string())
+                .unwrap_or_default();
+            MapCluster {
+                latitude: cell.lat_sum / cell.count as f64,
+                longitude: cell.lon_sum / cell.count as f64,
+                count: cell.count,
+                place,
+                bounds: cell.bounds,
+            }
+        })
+        .collect();
+    clusters.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.place.cmp(&b.place)));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loc(id: &str, place: &str, latitude: f64, longitude: f64) -> DocumentLocation {
+        DocumentLocation {
+            document_id: id.to_string(),
+            place: place.to_string(),
+            latitude,
+            longitude,
+            mentions: 1,
+        }
+    }
+
+    #[test]
+    fn test_bounding_box_parse() {
+        let bbox = BoundingBox::parse("24.5, -125, 49.5, -66.9").unwrap();
+        assert_eq!(bbox.south, 24.5);
+        assert_eq!(bbox.east, -66.9);
+        assert!(bbox.contains(38.9, -77.0));
+        assert!(!bbox.contains(51.5, -0.1));
+        assert_eq!(BoundingBox::parse(&bbox.to_param()), Some(bbox));
+
+        assert_eq!(BoundingBox::parse("1,2,3"), None);
+        assert_eq!(BoundingBox::parse("a,b,c,d"), None);
+        assert_eq!(BoundingBox::parse("50,0,40,10"), None, "south above north");
+        assert_eq!(BoundingBox::parse("0,0,95,10"), None, "out of range");
+    }
+
+    #[test]
+    fn test_primary_location() {
+        let places = vec![
+            ("Dallas".to_string(), 32.78, -96.80),
+            ("New Orleans".to_string(), 29.95, -90.07),
+        ];
+        let text = "Subject travelled from New Orleans to Dallas. Dallas office \
+                    advised; DALLAS files attached.";
+        let primary = primary_location("doc", text, &places).unwrap();
+        assert_eq!(primary.place, "Dallas");
+        assert_eq!(primary.mentions, 3);
+        assert_eq!(primary.document_id, "doc");
+
+        // Without the text each place counts once; ties go alphabetically
+        let primary = primary_location("doc", "", &places).unwrap();
+        assert_eq!(primary.place, "Dallas");
+        assert_eq!(primary.mentions, 1);
+        assert_eq!(primary_location("doc", text, &[]), None);
+    }
+
+    #[test]
+    fn test_cluster_locations() {
+        let locations = vec![
+            loc("a", "Washington", 38.90, -77.04),
+            loc("b", "Washington", 38.91, -77.03),
+            loc("c", "Arlington", 38.88, -77.10),
+            loc("d", "Dallas", 32.78, -96.80),
+        ];
+
+        let clusters = cluster_locations(&locations, 5.0);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].count, 3);
+        assert_eq!(clusters[0].place, "Washington");
+        assert_eq!(clusters[0].bounds.south, 38.88);
+        assert_eq!(clusters[0].bounds.west, -77.10);
+        assert!((clusters[0].latitude - 38.8967).abs() < 0.001);
+        assert_eq!(clusters[1].place, "Dallas");
+
+        // Fine cells separate Arlington from Washington
+        assert_eq!(cluster_locations(&locations, 0.05).len(), 3);
+        assert!(cluster_locations(&[], 1.0).is_empty());
+    }
+}
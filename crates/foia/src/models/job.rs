@@ -0,0 +1,211 @@
+//! Background job models.
+//!
+//! Jobs are long operations started from the web UI or API, such as
+//! re-OCRing a source or exporting a collection. The process running a job
+//! records its progress; anyone can request cancellation, which the runner
+//! notices the next time it reports progress.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Operation a job performs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Queue a source's pages for OCR again.
+    Reocr,
+    /// Extract tables from PDFs to CSV.
+    ExtractTables,
+    /// Generate searchable PDFs.
+    SearchablePdf,
+    /// Export documents to a file.
+    Export,
+    /// Re-run one processing step on a single document.
+    Reprocess,
+}
+
+impl JobKind {
+    pub const ALL: [Self; 5] = [
+        Self::Reocr,
+        Self::ExtractTables,
+        Self::SearchablePdf,
+        Self::Export,
+        Self::Reprocess,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reocr => "reocr",
+            Self::ExtractTables => "extract_tables",
+            Self::SearchablePdf => "searchable_pdf",
+            Self::Export => "export",
+            Self::Reprocess => "reprocess",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "reocr" => Some(Self::Reocr),
+            "extract_tables" => Some(Self::ExtractTables),
+            "searchable_pdf" => Some(Self::SearchablePdf),
+            "export" => Some(Self::Export),
+            "reprocess" => Some(Self::Reprocess),
+            _ => None,
+        }
+    }
+
+    /// Human-readable label.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Reocr => "Re-OCR",
+            Self::ExtractTables => "Extract tables",
+            Self::SearchablePdf => "Searchable PDFs",
+            Self::Export => "Export",
+            Self::Reprocess => "Reprocess document",
+        }
+    }
+}
+
+/// Lifecycle state of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "queued" => Some(Self::Queued),
+            "running" => Some(Self::Running),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Whether the job has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A tracked background job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    /// Kind-specific parameters (source, filters, format, ...).
+    pub params: serde_json::Value,
+    pub status: JobStatus,
+    /// Items processed so far.
+    pub progress: u64,
+    /// Items to process, once known.
+    pub total: Option<u64>,
+    /// What the job is doing now, or how it ended.
+    pub message: Option<String>,
+    pub error: Option<String>,
+    /// Kind-specific outcome (counts, output file, ...).
+    pub result: Option<serde_json::Value>,
+    pub cancel_requested: bool,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Job {
+    /// A new queued job.
+    pub fn new(kind: JobKind, params: serde_json::Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            params,
+            status: JobStatus::Queued,
+            progress: 0,
+            total: None,
+            message: None,
+            error: None,
+            result: None,
+            cancel_requested: false,
+            created_at: now,
+            started_at: None,
+            finished_at: None,
+            updated_at: now,
+        }
+    }
+
+    /// A string parameter, if set and non-empty.
+    pub fn param_str(&self, name: &str) -> Option<&str> {
+        self.params
+            .get(name)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Completion percentage, once the total is known.
+    pub fn percent(&self) -> Option<u8> {
+        match self.total {
+            Some(0) => Some(100),
+            Some(total) => Some((self.progress.min(total) * 100 / total) as u8),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_and_status_round_trip() {
+        for kind in JobKind::ALL {
+            assert_eq!(JobKind::from_str(kind.as_str()), Some(kind));
+        }
+        for status in [
+            JobStatus::Queued,
+            JobStatus::Running,
+            JobStatus::Completed,
+            JobStatus::Failed,
+            JobStatus::Cancelled,
+        ] {
+            assert_eq!(JobStatus::from_str(status.as_str()), Some(status));
+        }
+        assert!(!JobStatus::Running.is_finished());
+        assert!(JobStatus::Cancelled.is_finished());
+    }
+
+    #[test]
+    fn test_percent_and_params() {
+        let mut job = Job::new(
+            JobKind::Export,
+            serde_json::json!({ "source": "fbi", "tags": "" }),
+        );
+        assert_eq!(job.percent(), None);
+        job.total = Some(8);
+        job.progress = 2;
+        assert_eq!(job.percent(), Some(25));
+        job.total = Some(0);
+        assert_eq!(job.percent(), Some(100));
+
+        assert_eq!(job.param_str("source"), Some("fbi"));
+        assert_eq!(job.param_str("tags"), None);
+        assert_eq!(job.param_str("missing"), None);
+    }
+}
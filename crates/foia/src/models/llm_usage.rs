@@ -0,0 +1,128 @@
+//! LLM token usage and spending.
+//!
+//! Each call to the LLM provider is recorded with the tokens it consumed
+//! and, for hosted providers with a known price, what it cost. Totals over
+//! a run or a calendar month are what budgets are checked against.
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Tokens consumed by one LLM call.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Rough count for providers that don't report usage, at about four
+    /// characters per token.
+    pub fn estimate(prompt: &str, completion: &str) -> Self {
+        Self {
+            prompt_tokens: prompt.len().div_ceil(4) as u64,
+            completion_tokens: completion.len().div_ceil(4) as u64,
+        }
+    }
+}
+
+/// One recorded LLM call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsageEntry {
+    pub provider: String,
+    pub model: String,
+    /// What the call was for, e.g. `synopsis` or `classify`.
+    pub operation: String,
+    pub usage: TokenUsage,
+    /// Estimated cost in US dollars; `None` when the model's price is unknown.
+    pub cost_usd: Option<f64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Usage summed over a set of calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated cost of the priced calls.
+    pub cost_usd: f64,
+    /// Calls whose cost could not be estimated.
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    pub fn tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Count one call.
+    pub fn add(&mut self, usage: TokenUsage, cost_usd: Option<f64>) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        match cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+
+    /// Add another set of totals to these.
+    pub fn merge(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost_usd += other.cost_usd;
+        self.unpriced_requests += other.unpriced_requests;
+    }
+}
+
+/// Usage of one model by one provider in one calendar month.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsageSummary {
+    /// Month as `YYYY-MM`.
+    pub month: String,
+    pub provider: String,
+    pub model: String,
+    pub totals: UsageTotals,
+}
+
+/// Start of the calendar month (UTC) containing `at`.
+pub fn month_start(at: DateTime<Utc>) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(at.year(), at.month(), 1, 0, 0, 0)
+        .single()
+        .unwrap_or(at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals() {
+        let mut totals = UsageTotals::default();
+        let usage = TokenUsage {
+            prompt_tokens: 1000,
+            completion_tokens: 200,
+        };
+        totals.add(usage, Some(0.01));
+        totals.add(usage, None);
+        assert_eq!(totals.requests, 2);
+        assert_eq!(totals.tokens(), 2400);
+        assert_eq!(totals.unpriced_requests, 1);
+        assert!((totals.cost_usd - 0.01).abs() < 1e-9);
+
+        let mut sum = UsageTotals::default();
+        sum.merge(&totals);
+        sum.merge(&totals);
+        assert_eq!(sum.requests, 4);
+        assert_eq!(sum.prompt_tokens, 4000);
+
+        assert_eq!(TokenUsage::estimate("abcdefgh", "abc").total(), 3);
+        let start = month_start(Utc.with_ymd_and_hms(2026, 3, 17, 9, 30, 0).unwrap());
+        assert_eq!(start.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+    }
+}
@@ -1,17 +1,53 @@
 //! Data models for foia.
 
+mod agency;
 mod archive;
+mod checksum;
 mod crawl;
+mod derived_artifact;
 mod document;
 mod document_page;
+mod extracted_metadata;
+mod flag;
+mod foia_request;
+mod geo;
+mod job;
+mod llm_usage;
+mod pdf_metadata;
+mod pii;
+mod record_type;
+mod redaction;
+mod relation;
 mod service_status;
 mod source;
+mod tag;
 mod virtual_file;
+mod workflow;
 
-pub use archive::ArchiveService;
+pub use agency::{agency_id, agency_tag, Agency, AgencyLevel};
+pub use archive::{ArchiveService, ArchiveSnapshot, FallbackState, NewArchiveSnapshot};
+pub use checksum::{ChecksumAlgorithm, ChecksumStatus, ChecksumVerification};
 pub use crawl::{CrawlRequest, CrawlUrl, DiscoveryMethod, UrlStatus};
+pub use derived_artifact::{ArtifactKind, ArtifactStatus, DerivedArtifact, NewDerivedArtifact};
 pub use document::{Document, DocumentStatus, DocumentVersion};
-pub use document_page::{DocumentPage, PageOcrStatus};
+pub use document_page::{DocumentPage, PageOcrStatus, POOR_OCR_QUALITY};
+pub use extracted_metadata::ExtractedMetadata;
+pub use flag::{DocumentFlag, FlagReason, FlagStatus, MAX_FLAG_NOTE_LEN};
+pub use foia_request::{DeadlineRule, FoiaRequest, FoiaRequestStatus, RESPONSE_WORKING_DAYS};
+pub use geo::{cluster_locations, primary_location, BoundingBox, DocumentLocation, MapCluster};
+pub use job::{Job, JobKind, JobStatus};
+pub use llm_usage::{month_start, LlmUsageEntry, LlmUsageSummary, TokenUsage, UsageTotals};
+pub use pdf_metadata::{parse_pdf_date, PdfMetadata};
+pub use pii::{count_pii, describe_pii, pii_score, scan_pii, PiiKind, PiiMatch};
+pub use record_type::RecordType;
+pub use redaction::{
+    count_exemptions, is_exemption_code, mark_redactions, ExemptionStats, Redaction,
+};
+pub use relation::{
+    find_references, DocumentRelation, RelationGraph, RelationKind, RelationNode, TextReference,
+};
 pub use service_status::{ScraperStats, ServiceState, ServiceStatus, ServiceType};
-pub use source::{Source, SourceType};
-pub use virtual_file::{VirtualFile, VirtualFileStatus};
+pub use source::{Source, SourceDistribution, SourceType};
+pub use tag::{is_valid_tag_namespace, split_tag_namespace, tag_slug, KNOWN_TAG_NAMESPACES};
+pub use virtual_file::{VirtualFile, VirtualFileStatus, TABLES_PREFIX};
+pub use workflow::StatusWorkflow;
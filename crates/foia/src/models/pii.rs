@@ -0,0 +1,460 @@
+//! Personal information left in released text.
+//!
+//! Agencies occasionally release a record with a Social Security number,
+//! a date of birth, a phone number or a home address that should have been
+//! redacted. The scan here finds likely instances by pattern, keeps those
+//! that pass validation (a possible SSN area, a dialable phone number, a
+//! real past date, a street suffix), and scores a document so curators can
+//! review the worst before publishing it.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use chrono::{Datelike, NaiveDate, Utc};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+/// Highest score a document can get.
+const MAX_PII_SCORE: u32 = 100;
+
+/// Findings of one kind that count towards a score; a directory of office
+/// phone numbers shouldn't outscore a single SSN.
+const SCORED_PER_KIND: u32 = 2;
+
+/// Social Security numbers, `123-45-6789` or `123 45 6789`, or nine bare
+/// digits after an SSN label.
+static SSN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\b(?P<area>\d{3})(?P<sep1>[- ])(?P<group>\d{2})(?P<sep2>[- ])(?P<serial>\d{4})\b",
+        r"|(?i:\b(?:SSN|social\s+security(?:\s+(?:number|no\.?|#))?)\s*[:#]?\s*)",
+        r"(?P<bare>[0-9]{9})\b",
+    ))
+    .expect("valid regex")
+});
+
+/// North American phone numbers with separators: `(202) 555-0143`,
+/// `202-555-0143`, `+1 202.555.0143`.
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?:\+?1[-. ]?)?(?:\((?P<area1>\d{3})\)\s?|(?P<area2>\d{3})[-. ])",
+        r"(?P<exchange>\d{3})[-. ](?P<line>\d{4})\b",
+    ))
+    .expect("valid regex")
+});
+
+/// A date after a birth label: `DOB: 03/14/1962`, `born March 14, 1962`,
+/// `date of birth 14 Mar 1962`. The match is the date alone.
+static DATE_OF_BIRTH: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?i)\b(?:DOB|D\.O\.B\.?|date\s+of\s+birth|birth\s*date|born(?:\s+on)?)\s*[:\-]?\s*",
+        r"(?P<date>(?P<m>\d{1,2})[/.-](?P<d>\d{1,2})[/.-](?P<y>\d{4}|\d{2})",
+        r"|(?P<mn>[a-z]{3})[a-z]*\.?\s+(?P<dn>\d{1,2}),?\s+(?P<yn>\d{4})",
+        r"|(?P<dd>\d{1,2})\s+(?P<md>[a-z]{3})[a-z]*\.?\s+(?P<yd>\d{4}))\b",
+    ))
+    .expect("valid regex")
+});
+
+/// Street suffixes recognized in addresses.
+const STREET_SUFFIXES: &[&str] = &[
+    "Street",
+    "St",
+    "Avenue",
+    "Ave",
+    "Road",
+    "Rd",
+    "Boulevard",
+    "Blvd",
+    "Drive",
+    "Dr",
+    "Lane",
+    "Ln",
+    "Parkway",
+    "Pkwy",
+    "Court",
+    "Ct",
+    "Place",
+    "Pl",
+    "Circle",
+    "Cir",
+    "Terrace",
+    "Ter",
+    "Way",
+];
+
+/// Suffixes that also end ordinary phrases ("in 1972 Supreme Court"), so
+/// they count only when a state and ZIP code follow on the same line.
+const WEAK_SUFFIXES: &[&str] = &[
+    "Court", "Ct", "Place", "Pl", "Circle", "Cir", "Terrace", "Ter", "Way",
+];
+
+/// Street addresses: a house number, one to three capitalized words and a
+/// street suffix, as in `1234 Elm Street` or `56 N. OAK AVE`.
+static ADDRESS: LazyLock<Regex> = LazyLock::new(|| {
+    let suffixes: Vec<String> = STREET_SUFFIXES
+        .iter()
+        .flat_map(|s| [s.to_string(), s.to_uppercase()])
+        .collect();
+    Regex::new(&format!(
+        concat!(
+            r"\b(?P<number>\d{{1,6}})\s+(?:[NSEW]\.?\s+)?",
+            r"(?:(?:[A-Z][a-z]+|[A-Z]{{2,}}|\d+(?:st|nd|rd|th|ST|ND|RD|TH))\s+){{1,3}}",
+            r"(?P<suffix>{})\b\.?",
+        ),
+        suffixes.join("|")
+    ))
+    .expect("valid regex")
+});
+
+/// A state abbreviation and ZIP code, as ends the line of an address.
+static STATE_ZIP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Z]{2},?\s+\d{5}(?:-\d{4})?\b").expect("valid regex"));
+
+/// How far past an address to look for its state and ZIP code.
+const STATE_ZIP_WINDOW: usize = 80;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// A kind of personal information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Ssn,
+    DateOfBirth,
+    Phone,
+    Address,
+}
+
+impl PiiKind {
+    pub const ALL: [Self; 4] = [Self::Ssn, Self::DateOfBirth, Self::Phone, Self::Address];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ssn => "ssn",
+            Self::DateOfBirth => "date_of_birth",
+            Self::Phone => "phone",
+            Self::Address => "address",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+
+    /// Short description for summaries.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ssn => "SSN",
+            Self::DateOfBirth => "date of birth",
+            Self::Phone => "phone number",
+            Self::Address => "street address",
+        }
+    }
+
+    /// Points each finding of this kind adds to a score.
+    pub fn weight(&self) -> u32 {
+        match self {
+            Self::Ssn => 50,
+            Self::DateOfBirth => 35,
+            Self::Address => 15,
+            Self::Phone => 10,
+        }
+    }
+}
+
+/// A likely piece of personal information: its kind and byte range in
+/// the scanned text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiMatch {
+    pub kind: PiiKind,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl PiiMatch {
+    /// The match with all but its last two digits hidden, for logs and the
+    /// review queue, which shouldn't repeat what they report.
+    pub fn masked(&self) -> String {
+        let digits = self.text.chars().filter(char::is_ascii_digit).count();
+        let mut seen = 0;
+        self.text
+            .chars()
+            .map(|c| {
+                if !c.is_ascii_digit() {
+                    return c;
+                }
+                seen += 1;
+                if seen + 2 > digits {
+                    c
+                } else {
+                    '*'
+                }
+            })
+            .collect()
+    }
+}
+
+/// Whether an SSN could have been issued: no area 000, 666 or 900-999,
+/// group 00 or serial 0000.
+fn is_valid_ssn(area: &str, group: &str, serial: &str) -> bool {
+    !matches!(area, "000" | "666") && !area.starts_with('9') && group != "00" && serial != "0000"
+}
+
+fn ssn_match(cap: &Captures) -> bool {
+    if let Some(bare) = cap.name("bare") {
+        let bare = bare.as_str();
+        return is_valid_ssn(&bare[..3], &bare[3..5], &bare[5..]);
+    }
+    cap["sep1"] == cap["sep2"] && is_valid_ssn(&cap["area"], &cap["group"], &cap["serial"])
+}
+
+/// Whether a NANP number is dialable: area code and exchange start with
+/// 2-9 and aren't N11 service codes, and it isn't a 555-01XX number
+/// reserved for fiction.
+fn is_valid_phone(area: &str, exchange: &str, line: &str) -> bool {
+    let plausible = |code: &str| !code.starts_with(['0', '1']) && !code.ends_with("11");
+    plausible(area) && plausible(exchange) && !(exchange == "555" && line.starts_with("01"))
+}
+
+/// Whether a phone match stands alone rather than being the tail of a
+/// longer number, such as a case or docket number.
+fn phone_stands_alone(text: &str, start: usize) -> bool {
+    !text[..start]
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '/'))
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(name))
+        .map(|i| i as u32 + 1)
+}
+
+/// The date a birth label introduces, if it is a real date in the past.
+/// Two-digit years are read as the most recent such year.
+fn birth_date(cap: &Captures) -> Option<NaiveDate> {
+    let today = Utc::now().date_naive();
+    let (year, month, day) = if let Some(y) = cap.name("y") {
+        let year: i32 = y.as_str().parse().ok()?;
+        let year = if y.as_str().len() == 2 {
+            let century = today.year() / 100 * 100;
+            if century + year > today.year() {
+                century - 100 + year
+            } else {
+                century + year
+            }
+        } else {
+            year
+        };
+        (
+            year,
+            cap["m"].parse::<u32>().ok()?,
+            cap["d"].parse::<u32>().ok()?,
+        )
+    } else if let Some(name) = cap.name("mn") {
+        (
+            cap["yn"].parse().ok()?,
+            month_number(name.as_str())?,
+            cap["dn"].parse().ok()?,
+        )
+    } else {
+        (
+            cap["yd"].parse().ok()?,
+            month_number(&cap["md"])?,
+            cap["dd"].parse().ok()?,
+        )
+    };
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    (year >= 1880 && date <= today).then_some(date)
+}
+
+/// Whether an address match is a plausible street address.
+fn is_valid_address(text: &str, cap: &Captures) -> bool {
+    if cap["number"].starts_with('0') {
+        return false;
+    }
+    let suffix = &cap["suffix"];
+    if !WEAK_SUFFIXES.iter().any(|s| s.eq_ignore_ascii_case(suffix)) {
+        return true;
+    }
+    let rest = &text[cap.get(0).expect("match").end()..];
+    let line = rest.split('\n').next().unwrap_or_default();
+    let window = match line.char_indices().nth(STATE_ZIP_WINDOW) {
+        Some((i, _)) => &line[..i],
+        None => line,
+    };
+    STATE_ZIP.is_match(window)
+}
+
+/// Find likely personal information in `text`, in order of position.
+pub fn scan_pii(text: &str) -> Vec<PiiMatch> {
+    let mut matches = Vec::new();
+    let mut push = |kind: PiiKind, m: regex::Match| {
+        matches.push(PiiMatch {
+            kind,
+            start: m.start(),
+            end: m.end(),
+            text: m.as_str().to_string(),
+        })
+    };
+
+    for cap in SSN.captures_iter(text) {
+        if ssn_match(&cap) {
+            let whole = cap.get(0).expect("match");
+            push(PiiKind::Ssn, cap.name("bare").unwrap_or(whole));
+        }
+    }
+    for cap in PHONE.captures_iter(text) {
+        let whole = cap.get(0).expect("match");
+        let area = cap
+            .name("area1")
+            .or_else(|| cap.name("area2"))
+            .expect("area code")
+            .as_str();
+        if phone_stands_alone(text, whole.start())
+            && is_valid_phone(area, &cap["exchange"], &cap["line"])
+        {
+            push(PiiKind::Phone, whole);
+        }
+    }
+    for cap in DATE_OF_BIRTH.captures_iter(text) {
+        if birth_date(&cap).is_some() {
+            push(PiiKind::DateOfBirth, cap.name("date").expect("date"));
+        }
+    }
+    for cap in ADDRESS.captures_iter(text) {
+        if is_valid_address(text, &cap) {
+            push(PiiKind::Address, cap.get(0).expect("match"));
+        }
+    }
+
+    matches.sort_by_key(|m| (m.start, m.end));
+    matches
+}
+
+/// Number of findings of each kind.
+pub fn count_pii(matches: &[PiiMatch]) -> BTreeMap<PiiKind, u32> {
+    let mut counts = BTreeMap::new();
+    for m in matches {
+        *counts.entry(m.kind).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Score a document's findings from 0 to 100. Each finding adds its kind's
+/// weight, counting at most two of a kind, so one SSN or a date of birth
+/// with an address reaches the default threshold of 50 but a letterhead's
+/// phone numbers don't.
+pub fn pii_score(matches: &[PiiMatch]) -> u32 {
+    let score: u32 = count_pii(matches)
+        .into_iter()
+        .map(|(kind, n)| kind.weight() * n.min(SCORED_PER_KIND))
+        .sum();
+    score.min(MAX_PII_SCORE)
+}
+
+/// A one-line summary of counts, e.g. `1 SSN, 2 phone numbers`.
+pub fn describe_pii(counts: &BTreeMap<PiiKind, u32>) -> String {
+    counts
+        .iter()
+        .map(|(kind, n)| {
+            if *n == 1 {
+                format!("1 {}", kind.label())
+            } else if *kind == PiiKind::DateOfBirth {
+                format!("{} dates of birth", n)
+            } else {
+                format!("{} {}s", n, kind.label())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<(PiiKind, String)> {
+        scan_pii(text)
+            .into_iter()
+            .map(|m| (m.kind, m.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_ssn() {
+        assert_eq!(
+            kinds("Subject SSN: 123456789, alias 219-09-9999."),
+            vec![
+                (PiiKind::Ssn, "123456789".to_string()),
+                (PiiKind::Ssn, "219-09-9999".to_string()),
+            ]
+        );
+        assert!(kinds("File 000-12-3456, 666-12-3456, 912-34-5678").is_empty());
+        assert!(kinds("Serial 123-45 6789 and 123-00-4567").is_empty());
+    }
+
+    #[test]
+    fn test_phone() {
+        assert_eq!(
+            kinds("Call (703) 482-0623 or +1 202.324.3000 today"),
+            vec![
+                (PiiKind::Phone, "(703) 482-0623".to_string()),
+                (PiiKind::Phone, "+1 202.324.3000".to_string()),
+            ]
+        );
+        assert!(kinds("Fictional 202-555-0143 and 011-234-5678").is_empty());
+        assert!(kinds("Case No. 2019-CV-703-482-0623").is_empty());
+    }
+
+    #[test]
+    fn test_date_of_birth() {
+        assert_eq!(
+            kinds("DOB: 03/14/1962; born March 4, 1950; date of birth 9 Sept 1941"),
+            vec![
+                (PiiKind::DateOfBirth, "03/14/1962".to_string()),
+                (PiiKind::DateOfBirth, "March 4, 1950".to_string()),
+                (PiiKind::DateOfBirth, "9 Sept 1941".to_string()),
+            ]
+        );
+        assert!(kinds("DOB 02/30/1962, born 13/01/1962").is_empty());
+        assert!(kinds("Memo dated 03/14/1962").is_empty());
+    }
+
+    #[test]
+    fn test_address() {
+        assert_eq!(
+            kinds("He lives at 1234 Elm Street and works at 56 N. OAK AVE."),
+            vec![
+                (PiiKind::Address, "1234 Elm Street".to_string()),
+                (PiiKind::Address, "56 N. OAK AVE.".to_string()),
+            ]
+        );
+        assert!(kinds("In 1972 Supreme Court justices").is_empty());
+        assert_eq!(
+            kinds("12 Laurel Court, Fairfax, VA 22030").len(),
+            1,
+            "weak suffix with a ZIP code"
+        );
+    }
+
+    #[test]
+    fn test_score_and_mask() {
+        let matches = scan_pii("SSN 219-09-9999, tel 703-482-0623");
+        assert_eq!(pii_score(&matches), 60);
+        assert_eq!(matches[0].masked(), "***-**-**99");
+        assert_eq!(describe_pii(&count_pii(&matches)), "1 SSN, 1 phone number");
+
+        let phones = scan_pii("703-482-0623 703-482-0624 703-482-0625 703-482-0626");
+        assert_eq!(pii_score(&phones), 20);
+        assert_eq!(pii_score(&[]), 0);
+
+        for kind in PiiKind::ALL {
+            assert_eq!(PiiKind::from_str(kind.as_str()), Some(kind));
+        }
+    }
+}
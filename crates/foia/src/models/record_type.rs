@@ -0,0 +1,134 @@
+//! Controlled vocabulary of FOIA record types.
+//!
+//! Unlike free-form tags, record types come from a fixed taxonomy so corpus
+//! statistics stay comparable across sources and over time.
+
+use serde::{Deserialize, Serialize};
+
+/// Kind of record a document represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordType {
+    Correspondence,
+    Email,
+    PolicyMemo,
+    Contract,
+    IncidentReport,
+    Invoice,
+    Log,
+    Report,
+    Form,
+    Transcript,
+    LegalFiling,
+    MeetingRecord,
+    Other,
+}
+
+impl RecordType {
+    /// All record types, in display order.
+    pub fn all() -> &'static [RecordType] {
+        &[
+            Self::Correspondence,
+            Self::Email,
+            Self::PolicyMemo,
+            Self::Contract,
+            Self::IncidentReport,
+            Self::Invoice,
+            Self::Log,
+            Self::Report,
+            Self::Form,
+            Self::Transcript,
+            Self::LegalFiling,
+            Self::MeetingRecord,
+            Self::Other,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Correspondence => "correspondence",
+            Self::Email => "email",
+            Self::PolicyMemo => "policy_memo",
+            Self::Contract => "contract",
+            Self::IncidentReport => "incident_report",
+            Self::Invoice => "invoice",
+            Self::Log => "log",
+            Self::Report => "report",
+            Self::Form => "form",
+            Self::Transcript => "transcript",
+            Self::LegalFiling => "legal_filing",
+            Self::MeetingRecord => "meeting_record",
+            Self::Other => "other",
+        }
+    }
+
+    /// Human-readable label for UI display.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Correspondence => "Correspondence",
+            Self::Email => "Email",
+            Self::PolicyMemo => "Policy Memo",
+            Self::Contract => "Contract",
+            Self::IncidentReport => "Incident Report",
+            Self::Invoice => "Invoice",
+            Self::Log => "Log",
+            Self::Report => "Report",
+            Self::Form => "Form",
+            Self::Transcript => "Transcript",
+            Self::LegalFiling => "Legal Filing",
+            Self::MeetingRecord => "Meeting Record",
+            Self::Other => "Other",
+        }
+    }
+
+    /// Parse a record type, tolerating the spacing/casing variations LLMs
+    /// tend to produce ("Policy Memo", "policy-memo", "POLICY_MEMO").
+    pub fn from_str(s: &str) -> Option<Self> {
+        let normalized = s.trim().to_lowercase().replace([' ', '-'], "_");
+        match normalized.as_str() {
+            "correspondence" | "letter" => Some(Self::Correspondence),
+            "email" | "e_mail" => Some(Self::Email),
+            "policy_memo" | "memo" | "memorandum" | "policy" => Some(Self::PolicyMemo),
+            "contract" | "agreement" => Some(Self::Contract),
+            "incident_report" => Some(Self::IncidentReport),
+            "invoice" | "receipt" => Some(Self::Invoice),
+            "log" => Some(Self::Log),
+            "report" => Some(Self::Report),
+            "form" => Some(Self::Form),
+            "transcript" => Some(Self::Transcript),
+            "legal_filing" | "court_filing" => Some(Self::LegalFiling),
+            "meeting_record" | "minutes" | "meeting_minutes" => Some(Self::MeetingRecord),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for rt in RecordType::all() {
+            assert_eq!(RecordType::from_str(rt.as_str()), Some(*rt));
+        }
+    }
+
+    #[test]
+    fn test_from_str_normalizes() {
+        assert_eq!(
+            RecordType::from_str("Policy Memo"),
+            Some(RecordType::PolicyMemo)
+        );
+        assert_eq!(
+            RecordType::from_str(" incident-report "),
+            Some(RecordType::IncidentReport)
+        );
+        assert_eq!(
+            RecordType::from_str("MINUTES"),
+            Some(RecordType::MeetingRecord)
+        );
+        assert_eq!(RecordType::from_str("haiku"), None);
+    }
+}
@@ -0,0 +1,191 @@
+//! Redactions and the FOIA exemptions cited for them.
+//!
+//! Released documents mark withheld text with solid boxes, which text
+//! extraction turns into runs of block characters, and cite the exemption
+//! justifying each withholding as `(b)(6)`, `(b)(7)(C)` or an FBI-style
+//! `b6`/`b7C` stamp. Adjacent boxes and codes form one redaction, replaced
+//! in page text by a `[REDACTED (b)(6), (b)(7)(C)]` placeholder so readers,
+//! search and the LLM see one consistent marker.
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+/// Anything that marks a redaction: an existing placeholder, a
+/// parenthesized exemption code, a stamped code or a run of block characters.
+static MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\[REDACTED(?: (?P<cited>[^\]]*))?\]",
+        r"|(?i:\(\s*b\s*\)\s*\(\s*(?:(?P<p7>7)\s*\)(?:\s*\(\s*(?P<p7sub>[a-f])\s*\))?|(?P<pn>[1-689])\s*\)))",
+        r"|\bb(?:(?P<s7>7)(?P<s7sub>[A-Fa-f])?|(?P<sn>[1-689]))\b",
+        r"|(?P<block>[\u{2580}-\u{259F}\u{25A0}\u{25AE}]{3,})",
+    ))
+    .expect("valid regex")
+});
+
+/// What may separate the markers of a single redaction.
+fn is_separator(gap: &str) -> bool {
+    gap.chars().all(|c| matches!(c, ' ' | '\t' | ',' | ';'))
+}
+
+/// One redaction in a text: the byte range of its markers and the
+/// exemption codes cited for it, normalized and without duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    pub start: usize,
+    pub end: usize,
+    pub codes: Vec<String>,
+}
+
+impl Redaction {
+    /// The placeholder that replaces this redaction in text.
+    pub fn placeholder(&self) -> String {
+        if self.codes.is_empty() {
+            "[REDACTED]".to_string()
+        } else {
+            format!("[REDACTED {}]", self.codes.join(", "))
+        }
+    }
+}
+
+/// How often an exemption is cited across a set of documents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExemptionStats {
+    pub code: String,
+    /// Documents citing the exemption at least once.
+    pub documents: u64,
+    /// Redactions citing it, over all those documents.
+    pub citations: u64,
+}
+
+/// Normalize a matched code to `(b)(N)` or `(b)(7)(X)`.
+fn code(number: &str, subsection: Option<&str>) -> String {
+    match subsection {
+        Some(sub) => format!("(b)({})({})", number, sub.to_uppercase()),
+        None => format!("(b)({})", number),
+    }
+}
+
+/// Exemption codes named by one marker.
+fn marker_codes(cap: &Captures<'_>) -> Vec<String> {
+    if let Some(cited) = cap.name("cited") {
+        return scan(cited.as_str())
+            .into_iter()
+            .flat_map(|r| r.codes)
+            .collect();
+    }
+    if cap.name("p7").is_some() {
+        return vec![code("7", cap.name("p7sub").map(|m| m.as_str()))];
+    }
+    if let Some(n) = cap.name("pn") {
+        return vec![code(n.as_str(), None)];
+    }
+    if cap.name("s7").is_some() {
+        return vec![code("7", cap.name("s7sub").map(|m| m.as_str()))];
+    }
+    if let Some(n) = cap.name("sn") {
+        return vec![code(n.as_str(), None)];
+    }
+    Vec::new()
+}
+
+/// Find the redactions in `text`, in order.
+pub fn scan(text: &str) -> Vec<Redaction> {
+    let mut redactions: Vec<Redaction> = Vec::new();
+    for cap in MARKER.captures_iter(text) {
+        let whole = cap.get(0).expect("match");
+        let codes = marker_codes(&cap);
+        match redactions.last_mut() {
+            Some(last) if is_separator(&text[last.end..whole.start()]) => {
+                last.end = whole.end();
+                for c in codes {
+                    if !last.codes.contains(&c) {
+                        last.codes.push(c);
+                    }
+                }
+            }
+            _ => {
+                let mut unique: Vec<String> = Vec::new();
+                for c in codes {
+                    if !unique.contains(&c) {
+                        unique.push(c);
+                    }
+                }
+                redactions.push(Redaction {
+                    start: whole.start(),
+                    end: whole.end(),
+                    codes: unique,
+                });
+            }
+        }
+    }
+    redactions
+}
+
+/// Replace each redaction in `text` with its placeholder. Marking text
+/// that is already marked leaves it unchanged.
+pub fn mark_redactions(text: &str) -> String {
+    let mut marked = String::with_capacity(text.len());
+    let mut pos = 0;
+    for redaction in scan(text) {
+        marked.push_str(&text[pos..redaction.start]);
+        marked.push_str(&redaction.placeholder());
+        pos = redaction.end;
+    }
+    marked.push_str(&text[pos..]);
+    marked
+}
+
+/// Whether `code` is a normalized exemption code such as `(b)(6)` or
+/// `(b)(7)(C)`.
+pub fn is_exemption_code(code: &str) -> bool {
+    matches!(scan(code).as_slice(), [r] if r.start == 0 && r.end == code.len() && r.codes == [code])
+}
+
+/// How many redactions cite each exemption code. A code counts once per
+/// redaction, so marked and unmarked text give the same counts.
+pub fn count_exemptions(text: &str) -> BTreeMap<String, u32> {
+    let mut counts = BTreeMap::new();
+    for redaction in scan(text) {
+        for c in redaction.codes {
+            *counts.entry(c).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_redactions() {
+        let text = "Agent ████████ (b)(6), (b)(7)(C) met with b7D on ▇▇▇▇ in 1971.";
+        let marked = mark_redactions(text);
+        assert_eq!(
+            marked,
+            "Agent [REDACTED (b)(6), (b)(7)(C)] met with [REDACTED (b)(7)(D)] on [REDACTED] in 1971."
+        );
+        assert_eq!(mark_redactions(&marked), marked);
+        assert_eq!(
+            mark_redactions("Plan B6 and b52 bombers"),
+            "Plan B6 and b52 bombers"
+        );
+    }
+
+    #[test]
+    fn test_count_exemptions() {
+        let text = "b6 b7C ████ (B) (6)\n(b)(1) (b)(6) (b)(7)";
+        let counts = count_exemptions(text);
+        assert_eq!(counts.get("(b)(6)"), Some(&2));
+        assert_eq!(counts.get("(b)(7)(C)"), Some(&1));
+        assert_eq!(counts.get("(b)(1)"), Some(&1));
+        assert_eq!(counts.get("(b)(7)"), Some(&1));
+        assert_eq!(count_exemptions(&mark_redactions(text)), counts);
+
+        assert!(is_exemption_code("(b)(7)(C)"));
+        assert!(!is_exemption_code("b6"));
+        assert!(!is_exemption_code("(b)(6)' OR 1=1"));
+    }
+}
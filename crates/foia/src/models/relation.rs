@@ -0,0 +1,213 @@
+//! Relationships between documents.
+//!
+//! Responsive records reference each other: a cover letter lists its
+//! enclosures, a reply cites the request it answers, and documents from the
+//! same case share a file number. Relations are detected from document text
+//! and entities and stored so the document page can show the network.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// How two documents are related.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    /// The source lists the target as an attachment, enclosure or exhibit.
+    Attachment,
+    /// The source answers the request or letter the target is.
+    InResponseTo,
+    /// Both documents carry the same case or file number.
+    SharedCaseNumber,
+}
+
+impl RelationKind {
+    pub const ALL: [Self; 3] = [Self::Attachment, Self::InResponseTo, Self::SharedCaseNumber];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Attachment => "attachment",
+            Self::InResponseTo => "in_response_to",
+            Self::SharedCaseNumber => "shared_case_number",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    /// Human-readable label for UI display.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Attachment => "Attachment",
+            Self::InResponseTo => "In response to",
+            Self::SharedCaseNumber => "Shared case number",
+        }
+    }
+
+    /// Label seen from the target document's side.
+    pub fn inverse_label(&self) -> &'static str {
+        match self {
+            Self::Attachment => "Attached to",
+            Self::InResponseTo => "Answered by",
+            Self::SharedCaseNumber => "Shared case number",
+        }
+    }
+
+    /// Whether the relation reads the same in both directions.
+    pub fn is_symmetric(&self) -> bool {
+        matches!(self, Self::SharedCaseNumber)
+    }
+}
+
+/// A link from one document to another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DocumentRelation {
+    pub source_document_id: String,
+    pub target_document_id: String,
+    pub kind: RelationKind,
+    /// What the link was detected from, e.g. a filename or case number.
+    pub evidence: String,
+}
+
+impl DocumentRelation {
+    /// Create a relation. Symmetric relations are stored with the lower
+    /// document ID as the source so each pair is kept once.
+    pub fn new(source: &str, target: &str, kind: RelationKind, evidence: &str) -> Self {
+        let (source, target) = if kind.is_symmetric() && target < source {
+            (target, source)
+        } else {
+            (source, target)
+        };
+        Self {
+            source_document_id: source.to_string(),
+            target_document_id: target.to_string(),
+            kind,
+            evidence: evidence.to_string(),
+        }
+    }
+
+    /// The document at the other end from `doc_id`.
+    pub fn other(&self, doc_id: &str) -> &str {
+        if self.source_document_id == doc_id {
+            &self.target_document_id
+        } else {
+            &self.source_document_id
+        }
+    }
+}
+
+/// A reference to another document found in a document's text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextReference {
+    pub kind: RelationKind,
+    /// The filename or identifier the text names.
+    pub target: String,
+}
+
+static ATTACHMENT_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:attachments?|enclosures?|exhibits?|attached|enclosed)\b[^\n]{0,80}?([\w][\w.\-]*\.(?:pdf|docx?|xlsx?|pptx?|txt|rtf|csv|msg|eml|jpe?g|png|tiff?|zip))\b",
+    )
+    .expect("valid regex")
+});
+
+static RESPONSE_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\bin\s+response\s+to\b[^\n]{0,80}?\b(?:request|case|file|letter|control)\s*(?:no\.?|number|#)?\s*:?\s*([A-Z0-9]*\d[A-Z0-9\-/]{3,})",
+    )
+    .expect("valid regex")
+});
+
+/// Find references to other documents: named attachments and the
+/// requests a document answers. Each reference is returned once, in the
+/// order first seen.
+pub fn find_references(text: &str) -> Vec<TextReference> {
+    let mut seen = HashSet::new();
+    let mut refs = Vec::new();
+    let found = ATTACHMENT_PATTERN
+        .captures_iter(text)
+        .map(|c| (RelationKind::Attachment, c[1].to_string()))
+        .chain(
+            RESPONSE_PATTERN
+                .captures_iter(text)
+                .map(|c| (RelationKind::InResponseTo, c[1].to_string())),
+        );
+    for (kind, target) in found {
+        let target = target.trim_end_matches(['.', '-', '/']).to_string();
+        if seen.insert((kind, target.to_lowercase())) {
+            refs.push(TextReference { kind, target });
+        }
+    }
+    refs
+}
+
+/// A document in a relation graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationNode {
+    pub id: String,
+    pub title: String,
+    /// Hops from the document the graph was built around.
+    pub depth: u32,
+}
+
+/// The documents around one document and the relations between them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelationGraph {
+    pub nodes: Vec<RelationNode>,
+    pub edges: Vec<DocumentRelation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_references() {
+        let text = "Dear Requester,\n\
+                    This letter is in response to your request No. 2023-00451 dated May 1.\n\
+                    Enclosed please find the records (report_final.pdf).\n\
+                    Attachment 2: Field-Notes.DOCX\n\
+                    Exhibit A - report_final.PDF.\n";
+        let refs = find_references(text);
+        assert_eq!(
+            refs,
+            vec![
+                TextReference {
+                    kind: RelationKind::Attachment,
+                    target: "report_final.pdf".to_string(),
+                },
+                TextReference {
+                    kind: RelationKind::Attachment,
+                    target: "Field-Notes.DOCX".to_string(),
+                },
+                TextReference {
+                    kind: RelationKind::InResponseTo,
+                    target: "2023-00451".to_string(),
+                },
+            ]
+        );
+
+        assert!(find_references("No attachments were found.").is_empty());
+        assert!(find_references("in response to your letter of March 3").is_empty());
+    }
+
+    #[test]
+    fn test_symmetric_relations() {
+        let a = DocumentRelation::new("b", "a", RelationKind::SharedCaseNumber, "62-109060");
+        let b = DocumentRelation::new("a", "b", RelationKind::SharedCaseNumber, "62-109060");
+        assert_eq!(a, b);
+        assert_eq!(a.source_document_id, "a");
+        assert_eq!(a.other("b"), "a");
+
+        let directed = DocumentRelation::new("b", "a", RelationKind::Attachment, "x.pdf");
+        assert_eq!(directed.source_document_id, "b");
+        assert_eq!(
+            RelationKind::from_str("in_response_to"),
+            Some(RelationKind::InResponseTo)
+        );
+        assert_eq!(RelationKind::from_str("sibling"), None);
+    }
+}
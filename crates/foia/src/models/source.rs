@@ -59,6 +59,25 @@ pub struct Source {
     pub created_at: DateTime<Utc>,
     /// When the source was last scraped.
     pub last_scraped: Option<DateTime<Utc>>,
+    /// Agency in the registry this source collects from.
+    #[serde(default)]
+    pub agency_id: Option<String>,
+}
+
+/// Metadata key holding a source's latest peer-to-peer release.
+const DISTRIBUTION_KEY: &str = "distribution";
+
+/// A torrent of a source's bundle, published for peer-to-peer download.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceDistribution {
+    /// Magnet link, with any trackers and web seeds.
+    pub magnet: String,
+    /// BitTorrent v1 info hash, hex.
+    pub info_hash: String,
+    /// Total size of the torrent's files in bytes.
+    pub size: u64,
+    pub documents: u64,
+    pub published_at: DateTime<Utc>,
 }
 
 impl Source {
@@ -72,6 +91,22 @@ impl Source {
             metadata: serde_json::json!({}),
             created_at: Utc::now(),
             last_scraped: None,
+            agency_id: None,
+        }
+    }
+
+    /// The latest published torrent of this source, if any.
+    pub fn distribution(&self) -> Option<SourceDistribution> {
+        serde_json::from_value(self.metadata.get(DISTRIBUTION_KEY)?.clone()).ok()
+    }
+
+    /// Record a published torrent, replacing any earlier one.
+    pub fn set_distribution(&mut self, distribution: &SourceDistribution) {
+        if !self.metadata.is_object() {
+            self.metadata = serde_json::json!({});
+        }
+        if let Ok(value) = serde_json::to_value(distribution) {
+            self.metadata[DISTRIBUTION_KEY] = value;
         }
     }
 }
@@ -0,0 +1,70 @@
+//! Tag namespace conventions.
+//!
+//! Tags may carry a `namespace:value` prefix (`agency:fbi`, `topic:mkultra`).
+//! Unprefixed tags have no namespace.
+
+/// Namespaces shown first, in this order, when grouping tags.
+pub const KNOWN_TAG_NAMESPACES: &[&str] = &["agency", "topic", "type", "entity"];
+
+/// Split a tag into `(namespace, value)`.
+///
+/// Returns `None` for unprefixed tags and for things that merely contain a
+/// colon, such as URLs or free text ("re: budget").
+pub fn split_tag_namespace(tag: &str) -> Option<(&str, &str)> {
+    let (namespace, value) = tag.split_once(':')?;
+    if !is_valid_tag_namespace(namespace) || value.is_empty() || value.starts_with(['/', ' ']) {
+        return None;
+    }
+    Some((namespace, value))
+}
+
+/// Turn a name into a tag value: lowercase words joined by hyphens
+/// ("Abraham Lincoln" becomes `abraham-lincoln`).
+pub fn tag_slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Whether `s` is usable as a tag namespace.
+pub fn is_valid_tag_namespace(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_tag_namespace() {
+        assert_eq!(split_tag_namespace("agency:fbi"), Some(("agency", "fbi")));
+        assert_eq!(
+            split_tag_namespace("entity:john-doe"),
+            Some(("entity", "john-doe"))
+        );
+        assert_eq!(split_tag_namespace("cold-war"), None);
+        assert_eq!(split_tag_namespace("agency:"), None);
+        assert_eq!(split_tag_namespace(":fbi"), None);
+        assert_eq!(split_tag_namespace("https://example.com"), None);
+        assert_eq!(split_tag_namespace("Re: budget"), None);
+    }
+
+    #[test]
+    fn test_tag_slug() {
+        assert_eq!(tag_slug("Abraham Lincoln"), "abraham-lincoln");
+        assert_eq!(tag_slug("  Al Capone (Part 1) "), "al-capone-part-1");
+        assert_eq!(tag_slug("---"), "");
+    }
+
+    #[test]
+    fn test_is_valid_tag_namespace() {
+        assert!(is_valid_tag_namespace("agency"));
+        assert!(!is_valid_tag_namespace("Agency"));
+        assert!(!is_valid_tag_namespace(""));
+        assert!(!is_valid_tag_namespace("a:b"));
+    }
+}
@@ -2,12 +2,17 @@
 //!
 //! Virtual files represent files contained within archive formats (zip, tar, etc.)
 //! that are not extracted to disk but can be accessed and processed on-demand.
+//! Files derived from a document, such as tables extracted to CSV, are stored
+//! the same way under an underscore-prefixed path.
 
 #![allow(dead_code)]
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Archive path prefix of CSV tables extracted from a document's pages.
+pub const TABLES_PREFIX: &str = "_tables/";
+
 /// A file contained within an archive that is not stored on disk.
 ///
 /// Virtual files track their location within the parent archive and store
@@ -104,4 +109,86 @@ impl VirtualFile {
             updated_at: now,
         }
     }
+
+    /// CSV table `index` (1-based) extracted from page `page` of a document.
+    pub fn extracted_table(
+        document_id: String,
+        version_id: i64,
+        page: u32,
+        index: usize,
+        csv: String,
+    ) -> Self {
+        let mut vf = Self::new(
+            document_id,
+            version_id,
+            format!("{}page-{}-{}.csv", TABLES_PREFIX, page, index),
+            format!("page-{}-table-{}.csv", page, index),
+            "text/csv".to_string(),
+            csv.len() as u64,
+        );
+        vf.extracted_text = Some(csv);
+        vf.status = VirtualFileStatus::OcrComplete;
+        vf
+    }
+
+    /// Whether this file was derived from the document (extracted tables,
+    /// email body placeholders) rather than being an archive member.
+    pub fn is_derived(&self) -> bool {
+        self.archive_path.starts_with('_')
+    }
+
+    /// Whether this is an archive member with text but no synopsis yet.
+    pub fn needs_summary(&self) -> bool {
+        !self.is_derived()
+            && self.synopsis.is_none()
+            && self
+                .extracted_text
+                .as_deref()
+                .is_some_and(|t| !t.trim().is_empty())
+    }
+
+    /// Page an extracted table came from, or `None` for other files.
+    pub fn table_page(&self) -> Option<u32> {
+        self.archive_path
+            .strip_prefix(TABLES_PREFIX)?
+            .strip_prefix("page-")?
+            .split('-')
+            .next()?
+            .parse()
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracted_table_page() {
+        let table = VirtualFile::extracted_table("doc".into(), 1, 12, 2, "a,b\n".into());
+        assert_eq!(table.archive_path, "_tables/page-12-2.csv");
+        assert_eq!(table.filename, "page-12-table-2.csv");
+        assert_eq!(table.table_page(), Some(12));
+        assert_eq!(table.file_size, 4);
+
+        let member = VirtualFile::new(
+            "doc".into(),
+            1,
+            "page-3-1.csv".into(),
+            "page-3-1.csv".into(),
+            "text/csv".into(),
+            0,
+        );
+        assert_eq!(member.table_page(), None);
+        assert!(table.is_derived());
+        assert!(!table.needs_summary());
+        assert!(!member.is_derived());
+        assert!(!member.needs_summary());
+
+        let mut member = member;
+        member.extracted_text = Some("From: a@example.gov".into());
+        assert!(member.needs_summary());
+        member.synopsis = Some("An email.".into());
+        assert!(!member.needs_summary());
+    }
 }
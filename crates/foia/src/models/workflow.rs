@@ -0,0 +1,111 @@
+//! Document status workflow.
+//!
+//! A document advances pending → downloaded → extracted → analyzed →
+//! reviewed → published, and may fail or be sent back a stage along the
+//! way. The workflow lists which moves are allowed so every code path that
+//! changes a status agrees on them; `[workflow]` in the config can change
+//! the defaults.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::DocumentStatus;
+
+/// Allowed status transitions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusWorkflow {
+    transitions: BTreeMap<DocumentStatus, BTreeSet<DocumentStatus>>,
+}
+
+impl Default for StatusWorkflow {
+    fn default() -> Self {
+        use DocumentStatus::*;
+
+        Self::from_transitions([
+            (Pending, vec![Downloaded, Failed]),
+            // Documents needing no summary are finalized straight to analyzed.
+            (Downloaded, vec![OcrComplete, Indexed, Failed]),
+            (OcrComplete, vec![Indexed, Failed]),
+            // Back to extracted when annotations are reset.
+            (Indexed, vec![OcrComplete, Reviewed, Failed]),
+            (Reviewed, vec![Published, Indexed]),
+            (Published, vec![Reviewed]),
+            (Failed, vec![Pending, Downloaded]),
+        ])
+    }
+}
+
+impl StatusWorkflow {
+    /// A workflow allowing exactly the listed moves.
+    pub fn from_transitions(
+        transitions: impl IntoIterator<Item = (DocumentStatus, Vec<DocumentStatus>)>,
+    ) -> Self {
+        Self {
+            transitions: transitions
+                .into_iter()
+                .map(|(from, to)| (from, to.into_iter().filter(|t| *t != from).collect()))
+                .collect(),
+        }
+    }
+
+    /// Replace the moves allowed out of `from`.
+    pub fn set_transitions(&mut self, from: DocumentStatus, to: Vec<DocumentStatus>) {
+        self.transitions
+            .insert(from, to.into_iter().filter(|t| *t != from).collect());
+    }
+
+    /// Whether a document may move from `from` to `to`. Staying put is
+    /// always allowed.
+    pub fn allows(&self, from: DocumentStatus, to: DocumentStatus) -> bool {
+        from == to
+            || self
+                .transitions
+                .get(&from)
+                .is_some_and(|next| next.contains(&to))
+    }
+
+    /// Statuses a document may move to from `from`, in workflow order.
+    pub fn next(&self, from: DocumentStatus) -> Vec<DocumentStatus> {
+        self.transitions
+            .get(&from)
+            .map(|next| next.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Statuses a document may move to `to` from, including `to` itself.
+    pub fn sources(&self, to: DocumentStatus) -> Vec<DocumentStatus> {
+        DocumentStatus::ALL
+            .into_iter()
+            .filter(|from| self.allows(*from, to))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DocumentStatus::*;
+
+    #[test]
+    fn test_default_workflow() {
+        let workflow = StatusWorkflow::default();
+        assert!(workflow.allows(Downloaded, OcrComplete));
+        assert!(workflow.allows(Indexed, Indexed));
+        assert!(workflow.allows(Reviewed, Published));
+        assert!(!workflow.allows(Pending, Published));
+        assert!(!workflow.allows(Published, Indexed));
+        assert_eq!(workflow.next(Published), vec![Reviewed]);
+        assert_eq!(
+            workflow.sources(Indexed),
+            vec![Downloaded, OcrComplete, Indexed, Reviewed]
+        );
+    }
+
+    #[test]
+    fn test_set_transitions() {
+        let mut workflow = StatusWorkflow::default();
+        workflow.set_transitions(Indexed, vec![Published, Indexed]);
+        assert!(workflow.allows(Indexed, Published));
+        assert!(!workflow.allows(Indexed, Reviewed));
+        assert_eq!(workflow.next(Indexed), vec![Published]);
+    }
+}
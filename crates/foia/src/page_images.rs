@@ -0,0 +1,162 @@
+//! Rendered page images for the document viewer.
+//!
+//! PDF pages are rendered with `pdftoppm` once and kept under
+//! `documents/pages/{hash[0..2]}/{hash}/{page}.{ext}`, keyed by the
+//! version's content hash so identical files share renders. OCR fills the
+//! store as it goes; the web server renders any page still missing on
+//! first view. Changing the DPI or format applies to pages rendered after
+//! the change; delete the directory to re-render everything.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{PageImageConfig, PageImageFormat};
+
+/// Subdirectory of the documents directory holding rendered pages.
+pub const PAGES_SUBDIR: &str = "pages";
+
+/// On-disk store of rendered PDF pages.
+#[derive(Debug, Clone)]
+pub struct PageImageStore {
+    dir: PathBuf,
+    dpi: u32,
+    format: PageImageFormat,
+}
+
+impl PageImageStore {
+    /// Store under `documents_dir/pages` rendering with `config`.
+    pub fn new(documents_dir: &Path, config: &PageImageConfig) -> Self {
+        Self {
+            dir: documents_dir.join(PAGES_SUBDIR),
+            dpi: config.dpi,
+            format: config.format,
+        }
+    }
+
+    fn version_dir(&self, content_hash: &str) -> Option<PathBuf> {
+        if content_hash.len() < 2 || !content_hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(self.dir.join(&content_hash[..2]).join(content_hash))
+    }
+
+    /// Cached image for a page in any format, with its format.
+    pub fn get(&self, content_hash: &str, page: u32) -> Option<(PathBuf, PageImageFormat)> {
+        let dir = self.version_dir(content_hash)?;
+        [self.format, PageImageFormat::Png, PageImageFormat::Webp]
+            .into_iter()
+            .map(|format| (dir.join(format!("{}.{}", page, format.extension())), format))
+            .find(|(path, _)| path.is_file())
+    }
+
+    /// Cached image for a page, rendering it from `pdf_path` if missing.
+    ///
+    /// Blocks on `pdftoppm`; call from a blocking context.
+    pub fn get_or_render(
+        &self,
+        pdf_path: &Path,
+        content_hash: &str,
+        page: u32,
+    ) -> Result<(PathBuf, PageImageFormat), String> {
+        if let Some(found) = self.get(content_hash, page) {
+            return Ok(found);
+        }
+        let dir = self
+            .version_dir(content_hash)
+            .ok_or_else(|| format!("Invalid content hash: {}", content_hash))?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+        // Render beside the destination and rename, so readers never see a
+        // partial image
+        let temp_prefix = dir.join(format!(".{}-{}", page, uuid::Uuid::new_v4()));
+        let temp_png = temp_prefix.with_extension("png");
+        let dest = dir.join(format!("{}.{}", page, self.format.extension()));
+        let result = self
+            .render_png(pdf_path, page, &temp_prefix)
+            .and_then(|()| match self.format {
+                PageImageFormat::Png => std::fs::rename(&temp_png, &dest)
+                    .map_err(|e| format!("Failed to save page image: {}", e)),
+                PageImageFormat::Webp => encode_webp(&temp_png, &dest),
+            });
+        let _ = std::fs::remove_file(&temp_png);
+        result.map(|()| (dest, self.format))
+    }
+
+    fn render_png(&self, pdf_path: &Path, page: u32, output_prefix: &Path) -> Result<(), String> {
+        let page = page.to_string();
+        let status = Command::new("pdftoppm")
+            .args(["-png", "-singlefile", "-r"])
+            .arg(self.dpi.to_string())
+            .args(["-f", &page, "-l", &page])
+            .arg(pdf_path)
+            .arg(output_prefix)
+            .status()
+            .map_err(|e| format!("Failed to run pdftoppm: {}", e))?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(format!("pdftoppm failed to render page {}", page))
+        }
+    }
+}
+
+/// Re-encode a PNG as lossless WebP, written atomically to `dest`.
+fn encode_webp(png: &Path, dest: &Path) -> Result<(), String> {
+    let image = image::open(png).map_err(|e| format!("Failed to read page image: {}", e))?;
+    let temp = png.with_extension("webp");
+    let result = image
+        .save_with_format(&temp, image::ImageFormat::WebP)
+        .map_err(|e| format!("Failed to encode WebP: {}", e))
+        .and_then(|()| {
+            std::fs::rename(&temp, dest).map_err(|e| format!("Failed to save page image: {}", e))
+        });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_prefers_configured_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let hash = "ab12cd34ef";
+        let store = PageImageStore::new(
+            dir.path(),
+            &PageImageConfig {
+                format: PageImageFormat::Webp,
+                ..Default::default()
+            },
+        );
+        assert_eq!(store.get(hash, 1), None);
+
+        let version_dir = dir.path().join("pages/ab").join(hash);
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(version_dir.join("1.png"), b"png").unwrap();
+        assert_eq!(
+            store.get(hash, 1),
+            Some((version_dir.join("1.png"), PageImageFormat::Png))
+        );
+
+        std::fs::write(version_dir.join("1.webp"), b"webp").unwrap();
+        assert_eq!(
+            store.get(hash, 1),
+            Some((version_dir.join("1.webp"), PageImageFormat::Webp))
+        );
+        assert_eq!(store.get(hash, 2), None);
+    }
+
+    #[test]
+    fn test_rejects_non_hash_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PageImageStore::new(dir.path(), &PageImageConfig::default());
+        assert_eq!(store.get("../../etc", 1), None);
+        assert!(store
+            .get_or_render(Path::new("missing.pdf"), "../x", 1)
+            .is_err());
+    }
+}
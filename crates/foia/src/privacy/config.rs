@@ -122,6 +122,11 @@ pub struct SourcePrivacyConfig {
     #[serde(default)]
     #[prefer(default)]
     pub isolate: bool,
+
+    /// Route this source through its own proxies instead of Tor.
+    #[serde(default, skip_serializing_if = "ProxyConfig::is_default")]
+    #[prefer(default)]
+    pub proxy: ProxyConfig,
 }
 
 impl Default for SourcePrivacyConfig {
@@ -131,6 +136,7 @@ impl Default for SourcePrivacyConfig {
             obfuscation: true, // Default to obfuscated
             transport: None,
             isolate: false,
+            proxy: ProxyConfig::default(),
         }
     }
 }
@@ -139,10 +145,87 @@ fn default_true() -> bool {
     true
 }
 
+/// Default seconds between proxy health checks, and out of rotation after failing.
+const DEFAULT_PROXY_CHECK_SECS: u64 = 300;
+
+/// Default consecutive failures before a proxy is taken out of rotation.
+const DEFAULT_PROXY_MAX_FAILURES: u32 = 3;
+
+/// Outbound proxies for a single source.
+///
+/// One URL pins the source to that proxy; several rotate round-robin, and a
+/// proxy that keeps failing sits out of the rotation for `cooldown` seconds.
+/// Each proxy gets its own per-domain rate limiter, since each exit IP is
+/// throttled separately by the server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, prefer::FromValue)]
+pub struct ProxyConfig {
+    /// Proxy URLs (`socks5://`, `socks5h://`, `http://` or `https://`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[prefer(default)]
+    pub urls: Vec<String>,
+
+    /// URL fetched through each proxy to check it still works.
+    /// Without it, health is judged only from real request failures.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[prefer(default)]
+    pub health_check_url: Option<String>,
+
+    /// Seconds between health checks (default: 300).
+    #[serde(default = "default_proxy_check_secs")]
+    #[prefer(default = "300")]
+    pub health_check_interval: u64,
+
+    /// Consecutive failures before a proxy is taken out of rotation (default: 3).
+    #[serde(default = "default_proxy_max_failures")]
+    #[prefer(default = "3")]
+    pub max_failures: u32,
+
+    /// Seconds a failing proxy stays out of rotation (default: 300).
+    #[serde(default = "default_proxy_check_secs")]
+    #[prefer(default = "300")]
+    pub cooldown: u64,
+}
+
+fn default_proxy_check_secs() -> u64 {
+    DEFAULT_PROXY_CHECK_SECS
+}
+
+fn default_proxy_max_failures() -> u32 {
+    DEFAULT_PROXY_MAX_FAILURES
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            health_check_url: None,
+            health_check_interval: DEFAULT_PROXY_CHECK_SECS,
+            max_failures: DEFAULT_PROXY_MAX_FAILURES,
+            cooldown: DEFAULT_PROXY_CHECK_SECS,
+        }
+    }
+}
+
+impl ProxyConfig {
+    /// Check if this is the default (no proxies) config.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether any proxy is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.urls.is_empty()
+    }
+}
+
 impl SourcePrivacyConfig {
     /// Check if this is the default (empty) config.
     pub fn is_default(&self) -> bool {
-        !self.direct && self.obfuscation && self.transport.is_none() && !self.isolate
+        !self.direct
+            && self.obfuscation
+            && self.transport.is_none()
+            && !self.isolate
+            && self.proxy.is_default()
     }
 }
 
@@ -150,7 +233,12 @@ impl SourcePrivacyConfig {
     /// Resolve the effective privacy mode for this source.
     #[allow(dead_code)] // Public API for future per-source mode resolution
     pub fn resolve_mode(&self, global: &PrivacyConfig) -> PrivacyMode {
-        // Per-source direct overrides everything
+        // Per-source proxies override everything
+        if self.proxy.is_enabled() {
+            return PrivacyMode::ExternalProxy;
+        }
+
+        // Per-source direct overrides everything else
         if self.direct {
             return PrivacyMode::Direct;
         }
@@ -729,6 +817,24 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_source_proxy_overrides_direct() {
+        let global = PrivacyConfig::default();
+        let source = SourcePrivacyConfig {
+            direct: true,
+            proxy: ProxyConfig {
+                urls: vec!["http://10.0.0.6:3128".into()],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(!source.is_default());
+        assert!(matches!(
+            source.resolve_mode(&global),
+            PrivacyMode::ExternalProxy
+        ));
+    }
+
     #[test]
     fn test_apply_to_direct_override() {
         let global = PrivacyConfig::default();
@@ -26,7 +26,10 @@
 //!     "direct": true,           // Skip Tor for this source
 //!     "obfuscation": false,     // Use direct Tor (no PT)
 //!     "transport": "obfs4",     // Force specific transport
-//!     "isolate": true           // Dedicated circuit
+//!     "isolate": true,          // Dedicated circuit
+//!     "proxy": {                // Own proxies instead of Tor
+//!       "urls": ["socks5h://10.0.0.5:1080", "http://10.0.0.6:3128"]
+//!     }
 //!   }
 //! }
 //! ```
@@ -41,7 +44,7 @@ mod arti;
 #[allow(unused_imports)] // HiddenServiceSecurityLevel is public API
 pub use config::{
     socks_proxy_from_env, HiddenServiceConfig, HiddenServiceProvider, HiddenServiceSecurityLevel,
-    PrivacyConfig, PrivacyMode, SourcePrivacyConfig,
+    PrivacyConfig, PrivacyMode, ProxyConfig, SourcePrivacyConfig,
 };
 pub use ctor::CTorHiddenService;
 
@@ -115,4 +115,10 @@ pub trait RateLimitBackend: Send + Sync {
 
     /// Clean up expired 403 records (housekeeping).
     async fn cleanup_expired_403s(&self, window_ms: u64) -> RateLimitResult<u64>;
+
+    /// State of every tracked domain, for copying between backends.
+    /// Backends that cannot enumerate their domains return none.
+    async fn all_domains(&self) -> RateLimitResult<Vec<DomainRateState>> {
+        Ok(Vec::new())
+    }
 }
@@ -0,0 +1,141 @@
+//! Bandwidth caps for response bodies.
+//!
+//! A token bucket measured in bytes. Body reads take tokens and sleep once
+//! the bucket runs dry, which stalls the socket and so slows the server
+//! down too. One limiter can be shared by several clients to cap their
+//! combined rate.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket limiting bytes per second.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+    bytes_per_sec: u64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Bytes available now. Negative when readers are ahead of the cap.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    /// Create a limiter allowing `bytes_per_sec`, with up to one second of burst.
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let bytes_per_sec = bytes_per_sec.max(1);
+        Self {
+            bytes_per_sec,
+            bucket: Mutex::new(Bucket {
+                tokens: bytes_per_sec as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// The configured cap.
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    /// Account for `bytes` just read, sleeping if that put us over the cap.
+    pub async fn consume(&self, bytes: u64) {
+        let wait = self.take(bytes, Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take tokens and return how long to wait before reading more.
+    fn take(&self, bytes: u64, now: Instant) -> Duration {
+        let rate = self.bytes_per_sec as f64;
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(rate);
+        bucket.last_refill = now;
+        bucket.tokens -= bytes as f64;
+        if bucket.tokens < 0.0 {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Parse a bandwidth value into bytes per second.
+///
+/// Accepts plain byte counts (`"500000"`), byte units (`"500KB/s"`,
+/// `"2MiB/s"`, `"1.5M"`) and bit units (`"10Mbit/s"`, `"512kbps"`).
+/// `K`/`M`/`G` are decimal; `KiB`/`MiB`/`GiB` are binary.
+pub fn parse_bandwidth(value: &str) -> Result<u64, String> {
+    let lower = value.trim().to_ascii_lowercase();
+    let (unit_part, per_sec_bits) = match lower.strip_suffix("bps") {
+        Some(rest) => (rest, true),
+        None => (lower.strip_suffix("/s").unwrap_or(&lower), false),
+    };
+
+    let split = unit_part
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(unit_part.len());
+    let (number, unit) = unit_part.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid bandwidth '{}'", value))?;
+
+    let (multiplier, unit_bits) = match unit.trim() {
+        "" | "b" | "byte" | "bytes" => (1.0, false),
+        "k" | "kb" => (1e3, false),
+        "m" | "mb" => (1e6, false),
+        "g" | "gb" => (1e9, false),
+        "kib" => (1024.0, false),
+        "mib" => (1024.0 * 1024.0, false),
+        "gib" => (1024.0 * 1024.0 * 1024.0, false),
+        "kbit" => (1e3, true),
+        "mbit" => (1e6, true),
+        "gbit" => (1e9, true),
+        other => return Err(format!("Unknown bandwidth unit '{}' in '{}'", other, value)),
+    };
+
+    let bits = per_sec_bits || unit_bits;
+    let bytes = number * multiplier / if bits { 8.0 } else { 1.0 };
+    if !bytes.is_finite() || bytes < 1.0 {
+        return Err(format!("Bandwidth '{}' is too small", value));
+    }
+    Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bandwidth() {
+        assert_eq!(parse_bandwidth("500000").unwrap(), 500_000);
+        assert_eq!(parse_bandwidth("500KB/s").unwrap(), 500_000);
+        assert_eq!(parse_bandwidth("2MiB/s").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_bandwidth("1.5M").unwrap(), 1_500_000);
+        assert_eq!(parse_bandwidth("10Mbit/s").unwrap(), 1_250_000);
+        assert_eq!(parse_bandwidth("512kbps").unwrap(), 64_000);
+        assert!(parse_bandwidth("fast").is_err());
+        assert!(parse_bandwidth("10 furlongs").is_err());
+        assert!(parse_bandwidth("0").is_err());
+    }
+
+    #[test]
+    fn test_bucket_waits_once_burst_is_spent() {
+        let limiter = BandwidthLimiter::new(1000);
+        let start = Instant::now();
+
+        // The first second's worth is free
+        assert_eq!(limiter.take(1000, start), Duration::ZERO);
+        // Half a second over the cap
+        assert_eq!(limiter.take(500, start), Duration::from_millis(500));
+        // Time passing pays the debt back
+        assert_eq!(
+            limiter.take(0, start + Duration::from_millis(500)),
+            Duration::ZERO
+        );
+    }
+}
@@ -23,6 +23,8 @@ pub struct RateLimitConfig {
     pub recovery_multiplier: f64,
     /// Number of consecutive successes before reducing delay.
     pub recovery_threshold: u32,
+    /// Longest server-requested wait (`Retry-After`, quota reset) to honor.
+    pub max_server_wait: Duration,
 }
 
 impl Default for RateLimitConfig {
@@ -34,6 +36,7 @@ impl Default for RateLimitConfig {
             backoff_multiplier: 2.0,
             recovery_multiplier: 0.8,
             recovery_threshold: 5,
+            max_server_wait: Duration::from_secs(15 * 60),
         }
     }
 }
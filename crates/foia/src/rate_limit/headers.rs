@@ -0,0 +1,165 @@
+//! Server-provided rate limit hints.
+//!
+//! Parses `Retry-After` and the `X-RateLimit-*` / `RateLimit-*` header
+//! families so the limiter can wait exactly as long as the server asks
+//! instead of guessing with exponential backoff.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// Reset values above this are Unix timestamps rather than second deltas.
+const EPOCH_THRESHOLD_SECS: u64 = 1_000_000_000;
+
+/// Rate limit information extracted from response headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitHeaders {
+    /// How long the server asked us to wait (`Retry-After`).
+    pub retry_after: Option<Duration>,
+    /// Requests left in the current window.
+    pub remaining: Option<u64>,
+    /// Time until the current window resets.
+    pub reset_after: Option<Duration>,
+}
+
+impl RateLimitHeaders {
+    /// Parse from a response header map with lowercase names.
+    pub fn from_headers(headers: &HashMap<String, String>) -> Self {
+        Self::from_headers_at(headers, Utc::now())
+    }
+
+    fn from_headers_at(headers: &HashMap<String, String>, now: DateTime<Utc>) -> Self {
+        let get = |names: &[&str]| names.iter().find_map(|n| headers.get(*n)).map(|v| v.trim());
+
+        let retry_after = get(&["retry-after"]).and_then(|v| parse_retry_after_at(v, now));
+        let remaining = get(&["x-ratelimit-remaining", "ratelimit-remaining"])
+            .and_then(|v| v.parse::<u64>().ok());
+        let reset_after =
+            get(&["x-ratelimit-reset", "ratelimit-reset"]).and_then(|v| parse_reset_at(v, now));
+
+        Self {
+            retry_after,
+            remaining,
+            reset_after,
+        }
+    }
+
+    /// Whether any rate limit header was present.
+    pub fn is_empty(&self) -> bool {
+        self.retry_after.is_none() && self.remaining.is_none() && self.reset_after.is_none()
+    }
+
+    /// How long to hold off before the next request, if the server said.
+    ///
+    /// `Retry-After` wins; otherwise an exhausted quota waits for its reset.
+    pub fn wait(&self) -> Option<Duration> {
+        self.retry_after.or(match self.remaining {
+            Some(0) => self.reset_after,
+            _ => None,
+        })
+    }
+
+    /// Per-request delay that spreads the remaining quota over the window.
+    pub fn pacing_delay(&self) -> Option<Duration> {
+        match (self.remaining, self.reset_after) {
+            (Some(remaining), Some(reset)) if remaining > 0 => {
+                Some(reset / remaining.min(u32::MAX as u64) as u32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a `Retry-After` value: delay-seconds or an HTTP-date.
+pub fn parse_retry_after_value(value: &str) -> Option<Duration> {
+    parse_retry_after_at(value, Utc::now())
+}
+
+fn parse_retry_after_at(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (at.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Parse a reset value: seconds until reset, or a Unix timestamp.
+fn parse_reset_at(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let secs = value.trim().parse::<f64>().ok()?;
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    if secs as u64 >= EPOCH_THRESHOLD_SECS {
+        let at = DateTime::from_timestamp(secs as i64, 0)?;
+        return Some((at - now).to_std().unwrap_or(Duration::ZERO));
+    }
+    Some(Duration::from_secs_f64(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_retry_after_seconds_and_date() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:27:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let h = RateLimitHeaders::from_headers_at(&headers(&[("retry-after", "120")]), now);
+        assert_eq!(h.wait(), Some(Duration::from_secs(120)));
+
+        let h = RateLimitHeaders::from_headers_at(
+            &headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]),
+            now,
+        );
+        assert_eq!(h.retry_after, Some(Duration::from_secs(60)));
+
+        // Dates in the past mean "now"
+        let h = RateLimitHeaders::from_headers_at(
+            &headers(&[("retry-after", "Wed, 21 Oct 2015 07:00:00 GMT")]),
+            now,
+        );
+        assert_eq!(h.retry_after, Some(Duration::ZERO));
+
+        let h = RateLimitHeaders::from_headers_at(&headers(&[("retry-after", "soon")]), now);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn test_ratelimit_remaining_and_reset() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+
+        // Exhausted quota with an epoch reset
+        let h = RateLimitHeaders::from_headers_at(
+            &headers(&[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000030"),
+            ]),
+            now,
+        );
+        assert_eq!(h.wait(), Some(Duration::from_secs(30)));
+        assert_eq!(h.pacing_delay(), None);
+
+        // Quota left with a delta reset: spread it out
+        let h = RateLimitHeaders::from_headers_at(
+            &headers(&[("ratelimit-remaining", "10"), ("ratelimit-reset", "20")]),
+            now,
+        );
+        assert_eq!(h.wait(), None);
+        assert_eq!(h.pacing_delay(), Some(Duration::from_secs(2)));
+    }
+}
@@ -3,8 +3,9 @@
 //! Provides a high-level rate limiting API that wraps a pluggable backend.
 //! Supports in-memory, SQLite/PostgreSQL (Diesel), and Redis backends.
 
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
 use tracing::{debug, info, warn};
 use url::Url;
@@ -12,6 +13,8 @@ use url::Url;
 pub use super::config::{DomainStats, RateLimitConfig};
 
 use super::backend::RateLimitBackend;
+use super::headers::RateLimitHeaders;
+use crate::metrics;
 
 /// Type alias for a boxed rate limit backend.
 pub type BoxedRateLimitBackend = Arc<dyn RateLimitBackend>;
@@ -20,12 +23,19 @@ pub type BoxedRateLimitBackend = Arc<dyn RateLimitBackend>;
 ///
 /// Wraps a `RateLimitBackend` and provides high-level rate limiting logic:
 /// - Exponential backoff on rate limit responses (429, 503)
+/// - Exact waits when the server says when to come back (`Retry-After`,
+///   `X-RateLimit-Remaining`/`X-RateLimit-Reset`)
 /// - 403 pattern detection (multiple unique URLs getting 403)
 /// - Gradual recovery after consecutive successes
 #[derive(Clone)]
 pub struct RateLimiter {
     backend: BoxedRateLimitBackend,
-    config: RateLimitConfig,
+    /// Shared with clones so [`RateLimiter::apply_config`] reaches every
+    /// worker using this limiter.
+    config: Arc<RwLock<RateLimitConfig>>,
+    /// Server-requested "not before" times per domain. Kept in-process;
+    /// the backend only tracks the steady-state delay.
+    holds: Arc<Mutex<HashMap<String, Instant>>>,
 }
 
 impl RateLimiter {
@@ -36,7 +46,53 @@ impl RateLimiter {
 
     /// Create a new rate limiter with custom config.
     pub fn with_config(backend: BoxedRateLimitBackend, config: RateLimitConfig) -> Self {
-        Self { backend, config }
+        Self {
+            backend,
+            config: Arc::new(RwLock::new(config)),
+            holds: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The same limiter with different backoff settings. Domain state and
+    /// server holds stay shared with `self`.
+    pub fn reconfigured(&self, config: RateLimitConfig) -> Self {
+        Self {
+            backend: self.backend.clone(),
+            config: Arc::new(RwLock::new(config)),
+            holds: self.holds.clone(),
+        }
+    }
+
+    fn config(&self) -> RateLimitConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Change backoff settings while running, for this limiter and its
+    /// clones. Listed `domains` that aren't backing off move to the new base
+    /// delay right away; those backing off recover toward it.
+    pub async fn apply_config(&self, config: RateLimitConfig, domains: &[String]) {
+        let base_delay_ms = config.base_delay.as_millis() as u64;
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+
+        for domain in domains {
+            match self
+                .backend
+                .get_or_create_domain(domain, base_delay_ms)
+                .await
+            {
+                Ok(mut state) if !state.in_backoff => {
+                    state.current_delay_ms = base_delay_ms;
+                    if let Err(e) = self.backend.update_domain(&state).await {
+                        warn!("Failed to update domain state for {}: {}", domain, e);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!("Failed to get domain state for {}: {}", domain, e),
+            }
+        }
     }
 
     /// Extract domain from URL.
@@ -50,10 +106,11 @@ impl RateLimiter {
     /// Returns the domain name if successful.
     pub async fn acquire(&self, url: &str) -> Option<String> {
         let domain = Self::extract_domain(url)?;
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
 
         match self.backend.acquire(&domain, base_delay_ms).await {
             Ok(wait_time) => {
+                let wait_time = wait_time.max(self.hold_remaining(&domain));
                 if wait_time > Duration::ZERO {
                     debug!("Rate limiting {}: waiting {:?}", domain, wait_time);
                     tokio::time::sleep(wait_time).await;
@@ -62,15 +119,46 @@ impl RateLimiter {
             }
             Err(e) => {
                 warn!("Rate limit acquire failed for {}: {}", domain, e);
-                // Fall back to allowing the request
+                // Fall back to allowing the request (still honoring server holds)
+                let hold = self.hold_remaining(&domain);
+                if hold > Duration::ZERO {
+                    tokio::time::sleep(hold).await;
+                }
                 Some(domain)
             }
         }
     }
 
+    /// Time left on a server-requested hold for a domain.
+    pub fn hold_remaining(&self, domain: &str) -> Duration {
+        let mut holds = self.holds.lock().unwrap_or_else(|e| e.into_inner());
+        match holds.get(domain) {
+            Some(until) => {
+                let remaining = until.saturating_duration_since(Instant::now());
+                if remaining == Duration::ZERO {
+                    holds.remove(domain);
+                }
+                remaining
+            }
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Block a domain until `wait` has elapsed, capped at `max_server_wait`.
+    fn hold(&self, domain: &str, wait: Duration) -> Duration {
+        let wait = wait.min(self.config().max_server_wait);
+        let until = Instant::now() + wait;
+        let mut holds = self.holds.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = holds.entry(domain.to_string()).or_insert(until);
+        if *entry < until {
+            *entry = until;
+        }
+        wait
+    }
+
     /// Report a successful request - may decrease delay.
     pub async fn report_success(&self, domain: &str) {
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
 
         let state = match self
             .backend
@@ -91,10 +179,10 @@ impl RateLimiter {
         let _ = self.backend.clear_403s(domain).await;
 
         // Recover from backoff after threshold successes
-        if state.in_backoff && state.consecutive_successes >= self.config.recovery_threshold {
+        if state.in_backoff && state.consecutive_successes >= self.config().recovery_threshold {
             let new_delay_ms =
-                (state.current_delay_ms as f64 * self.config.recovery_multiplier) as u64;
-            state.current_delay_ms = new_delay_ms.max(self.config.min_delay.as_millis() as u64);
+                (state.current_delay_ms as f64 * self.config().recovery_multiplier) as u64;
+            state.current_delay_ms = new_delay_ms.max(self.config().min_delay.as_millis() as u64);
 
             if state.current_delay_ms <= base_delay_ms {
                 state.in_backoff = false;
@@ -128,7 +216,7 @@ impl RateLimiter {
     /// Report a 403 response - only backs off if we see a pattern on different URLs.
     /// Returns true if this was detected as rate limiting.
     pub async fn report_403(&self, domain: &str, url: &str, has_retry_after: bool) -> bool {
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
 
         // Record the 403
         if let Err(e) = self.backend.record_403(domain, url).await {
@@ -165,13 +253,14 @@ impl RateLimiter {
         let is_rate_limit = has_retry_after || unique_403_count >= threshold;
 
         if is_rate_limit {
+            metrics::RATE_LIMIT_BACKOFFS.inc(&[domain, "forbidden"]);
             state.rate_limit_hits += 1;
             state.in_backoff = true;
             let _ = self.backend.clear_403s(domain).await;
 
             let new_delay_ms =
-                (state.current_delay_ms as f64 * self.config.backoff_multiplier) as u64;
-            state.current_delay_ms = new_delay_ms.min(self.config.max_delay.as_millis() as u64);
+                (state.current_delay_ms as f64 * self.config().backoff_multiplier) as u64;
+            state.current_delay_ms = new_delay_ms.min(self.config().max_delay.as_millis() as u64);
 
             warn!(
                 "Rate limited by {} ({} unique URLs got 403), backing off to {}ms",
@@ -197,7 +286,7 @@ impl RateLimiter {
 
     /// Report a definite rate limit hit (429 or 503) - increases delay.
     pub async fn report_rate_limit(&self, domain: &str, status_code: u16) {
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
 
         let state = match self
             .backend
@@ -211,14 +300,16 @@ impl RateLimiter {
             }
         };
 
+        metrics::RATE_LIMIT_BACKOFFS.inc(&[domain, "status"]);
         let mut state = state;
         state.rate_limit_hits += 1;
         state.consecutive_successes = 0;
         let _ = self.backend.clear_403s(domain).await;
         state.in_backoff = true;
 
-        let new_delay_ms = (state.current_delay_ms as f64 * self.config.backoff_multiplier) as u64;
-        state.current_delay_ms = new_delay_ms.min(self.config.max_delay.as_millis() as u64);
+        let new_delay_ms =
+            (state.current_delay_ms as f64 * self.config().backoff_multiplier) as u64;
+        state.current_delay_ms = new_delay_ms.min(self.config().max_delay.as_millis() as u64);
 
         warn!(
             "Rate limited by {} (HTTP {}), backing off to {}ms",
@@ -230,9 +321,81 @@ impl RateLimiter {
         }
     }
 
+    /// Report a rate limit where the server said how long to wait.
+    ///
+    /// Holds the domain for exactly that long instead of growing the delay,
+    /// so requests resume at the normal pace once the window passes.
+    pub async fn report_server_wait(&self, domain: &str, status_code: u16, wait: Duration) {
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
+        let wait = self.hold(domain, wait);
+        metrics::RATE_LIMIT_BACKOFFS.inc(&[domain, "retry_after"]);
+
+        warn!(
+            "Rate limited by {} (HTTP {}), server asked to wait {:?}",
+            domain, status_code, wait
+        );
+
+        let mut state = match self
+            .backend
+            .get_or_create_domain(domain, base_delay_ms)
+            .await
+        {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to get domain state for {}: {}", domain, e);
+                return;
+            }
+        };
+        state.rate_limit_hits += 1;
+        state.consecutive_successes = 0;
+        let _ = self.backend.clear_403s(domain).await;
+
+        if let Err(e) = self.backend.update_domain(&state).await {
+            warn!("Failed to update domain state for {}: {}", domain, e);
+        }
+    }
+
+    /// Set the steady-state delay so the server's remaining quota lasts
+    /// until its window resets. Leaves domains in backoff alone.
+    pub async fn report_pacing(&self, domain: &str, pacing: Duration) {
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
+
+        let mut state = match self
+            .backend
+            .get_or_create_domain(domain, base_delay_ms)
+            .await
+        {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("Failed to get domain state for {}: {}", domain, e);
+                return;
+            }
+        };
+        if state.in_backoff {
+            return;
+        }
+
+        let config = self.config();
+        let target_ms = pacing
+            .clamp(config.base_delay, config.max_delay)
+            .as_millis() as u64;
+        if state.current_delay_ms == target_ms {
+            return;
+        }
+        debug!(
+            "Pacing {} to {}ms to stay within its quota",
+            domain, target_ms
+        );
+        state.current_delay_ms = target_ms;
+
+        if let Err(e) = self.backend.update_domain(&state).await {
+            warn!("Failed to update domain state for {}: {}", domain, e);
+        }
+    }
+
     /// Report a client error (4xx other than 429) - no delay change.
     pub async fn report_client_error(&self, domain: &str) {
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
         if let Ok(state) = self
             .backend
             .get_or_create_domain(domain, base_delay_ms)
@@ -247,7 +410,7 @@ impl RateLimiter {
 
     /// Report a server error (5xx other than 503) - mild backoff.
     pub async fn report_server_error(&self, domain: &str) {
-        let base_delay_ms = self.config.base_delay.as_millis() as u64;
+        let base_delay_ms = self.config().base_delay.as_millis() as u64;
 
         let state = match self
             .backend
@@ -264,7 +427,7 @@ impl RateLimiter {
         let mut state = state;
         // Mild backoff for server errors (might be overloaded)
         let new_delay_ms = (state.current_delay_ms as f64 * 1.5) as u64;
-        state.current_delay_ms = new_delay_ms.min(self.config.max_delay.as_millis() as u64);
+        state.current_delay_ms = new_delay_ms.min(self.config().max_delay.as_millis() as u64);
 
         debug!(
             "Server error for {}, delay increased to {}ms",
@@ -280,23 +443,38 @@ impl RateLimiter {
     ///
     /// Consolidates the duplicated if/else chains that were copy-pasted across
     /// every HTTP method. Handles 429/503 (rate limit), 403 (pattern detection),
-    /// 5xx (server error), and 2xx/3xx (success).
+    /// 5xx (server error), and 2xx/3xx (success). Rate limit headers, when
+    /// present, replace the generic backoff with the server's own timing.
     pub async fn report_response_status(
         &self,
         domain: &str,
         status_code: u16,
         original_url: &str,
-        response_headers: &std::collections::HashMap<String, String>,
+        response_headers: &HashMap<String, String>,
     ) {
-        let has_retry_after = response_headers.contains_key("retry-after");
+        let hints = RateLimitHeaders::from_headers(response_headers);
         if status_code == 429 || status_code == 503 {
-            self.report_rate_limit(domain, status_code).await;
+            match hints.wait() {
+                Some(wait) => self.report_server_wait(domain, status_code, wait).await,
+                None => self.report_rate_limit(domain, status_code).await,
+            }
         } else if status_code == 403 {
-            self.report_403(domain, original_url, has_retry_after).await;
+            match hints.retry_after {
+                Some(wait) => self.report_server_wait(domain, status_code, wait).await,
+                None => {
+                    self.report_403(domain, original_url, false).await;
+                }
+            }
         } else if status_code >= 500 {
             self.report_server_error(domain).await;
         } else if (200..400).contains(&status_code) {
             self.report_success(domain).await;
+            if let Some(wait) = hints.wait() {
+                // Quota exhausted: this request worked, the next one won't
+                self.hold(domain, wait);
+            } else if let Some(pacing) = hints.pacing_delay() {
+                self.report_pacing(domain, pacing).await;
+            }
         }
     }
 
@@ -311,12 +489,29 @@ impl RateLimiter {
     pub fn backend(&self) -> &BoxedRateLimitBackend {
         &self.backend
     }
+
+    /// Copy every domain's delay and backoff state into `store`. Called on
+    /// shutdown by limiters whose own backend does not persist, so the
+    /// state outlives the process. Returns the number of domains saved.
+    pub async fn save_rate_limit_state(
+        &self,
+        store: &dyn RateLimitBackend,
+    ) -> super::RateLimitResult<usize> {
+        let states = self.backend.all_domains().await?;
+        for state in &states {
+            store
+                .get_or_create_domain(&state.domain, state.current_delay_ms)
+                .await?;
+            store.update_domain(state).await?;
+        }
+        Ok(states.len())
+    }
 }
 
 impl std::fmt::Debug for RateLimiter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RateLimiter")
-            .field("config", &self.config)
+            .field("config", &self.config())
             .finish_non_exhaustive()
     }
 }
@@ -506,6 +701,102 @@ mod tests {
         assert!(!state.in_backoff);
     }
 
+    #[tokio::test]
+    async fn test_report_response_status_retry_after() {
+        let limiter = create_test_limiter();
+        limiter.acquire("https://example.com/doc").await;
+
+        let headers =
+            std::collections::HashMap::from([("retry-after".to_string(), "30".to_string())]);
+        limiter
+            .report_response_status("example.com", 429, "https://example.com/doc", &headers)
+            .await;
+
+        // Server timing replaces exponential backoff
+        let state = limiter
+            .backend
+            .get_or_create_domain("example.com", 100)
+            .await
+            .unwrap();
+        assert_eq!(state.rate_limit_hits, 1);
+        assert_eq!(state.current_delay_ms, 100);
+        let hold = limiter.hold_remaining("example.com");
+        assert!(hold > Duration::from_secs(29) && hold <= Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn test_report_response_status_quota_headers() {
+        let limiter = create_test_limiter();
+        limiter.acquire("https://example.com/doc").await;
+
+        let headers = std::collections::HashMap::from([
+            ("x-ratelimit-remaining".to_string(), "4".to_string()),
+            ("x-ratelimit-reset".to_string(), "2".to_string()),
+        ]);
+        limiter
+            .report_response_status("example.com", 200, "https://example.com/doc", &headers)
+            .await;
+
+        let state = limiter
+            .backend
+            .get_or_create_domain("example.com", 100)
+            .await
+            .unwrap();
+        assert_eq!(state.current_delay_ms, 500);
+        assert_eq!(limiter.hold_remaining("example.com"), Duration::ZERO);
+
+        let headers = std::collections::HashMap::from([
+            ("x-ratelimit-remaining".to_string(), "0".to_string()),
+            ("x-ratelimit-reset".to_string(), "5".to_string()),
+        ]);
+        limiter
+            .report_response_status("example.com", 200, "https://example.com/doc", &headers)
+            .await;
+        assert!(limiter.hold_remaining("example.com") > Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn test_save_rate_limit_state() {
+        let limiter = create_test_limiter();
+        limiter.acquire("https://example.com/doc").await;
+        limiter.report_rate_limit("example.com", 429).await;
+
+        let store = InMemoryRateLimitBackend::new(100);
+        assert_eq!(limiter.save_rate_limit_state(&store).await.unwrap(), 1);
+
+        let state = store
+            .get_or_create_domain("example.com", 100)
+            .await
+            .unwrap();
+        assert!(state.in_backoff);
+        assert_eq!(state.current_delay_ms, 200);
+    }
+
+    #[tokio::test]
+    async fn test_apply_config() {
+        let limiter = create_test_limiter();
+        let worker = limiter.clone();
+        limiter.acquire("https://example.com/doc").await;
+        limiter.acquire("https://slow.gov/doc").await;
+        limiter.report_rate_limit("slow.gov", 429).await;
+
+        let config = RateLimitConfig {
+            base_delay: Duration::from_millis(2000),
+            ..worker.config()
+        };
+        let domains = vec!["example.com".to_string(), "slow.gov".to_string()];
+        worker.apply_config(config, &domains).await;
+        assert_eq!(limiter.config().base_delay, Duration::from_millis(2000));
+
+        let state = |domain: &'static str| {
+            let backend = limiter.backend.clone();
+            async move { backend.get_or_create_domain(domain, 100).await.unwrap() }
+        };
+        assert_eq!(state("example.com").await.current_delay_ms, 2000);
+        // Backing off: left alone to recover
+        assert_eq!(state("slow.gov").await.current_delay_ms, 200);
+    }
+
     #[tokio::test]
     async fn test_is_definite_rate_limit() {
         assert!(RateLimiter::is_definite_rate_limit(429));
@@ -244,6 +244,10 @@ impl RateLimitBackend for InMemoryRateLimitBackend {
 
         Ok(removed)
     }
+
+    async fn all_domains(&self) -> RateLimitResult<Vec<DomainRateState>> {
+        Ok(self.get_all_stats().await.into_values().collect())
+    }
 }
 
 #[cfg(test)]
@@ -5,15 +5,21 @@
 //! - SQLite/PostgreSQL (persistent, single-instance)
 //! - Redis (distributed, multi-instance)
 //!
+//! Also holds the bandwidth caps and time-of-day schedules that throttle
+//! sources beyond per-request delays.
+//!
 //! Used by both scrapers and cloud API backends (Groq, Gemini, etc.).
 
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
 mod backend;
+mod bandwidth;
 mod config;
+mod headers;
 mod limiter;
 mod memory;
+mod schedule;
 mod sqlite;
 
 #[cfg(feature = "redis-backend")]
@@ -21,22 +27,21 @@ mod redis;
 
 // Re-export main types
 pub use backend::{DomainRateState, RateLimitBackend, RateLimitError, RateLimitResult};
+pub use bandwidth::{parse_bandwidth, BandwidthLimiter};
 pub use config::{DomainStats, RateLimitConfig};
+pub use headers::{parse_retry_after_value, RateLimitHeaders};
 pub use limiter::{BoxedRateLimitBackend, RateLimiter};
 pub use memory::InMemoryRateLimitBackend;
+pub use schedule::{Schedule, TimeWindow};
 pub use sqlite::DieselRateLimitBackend;
 
 #[cfg(feature = "redis-backend")]
 pub use redis::RedisRateLimitBackend;
 
-/// Parse Retry-After header value (seconds).
+/// Parse Retry-After header value (seconds or HTTP-date), capped at 60s.
 /// Returns duration to wait, or None if header is missing/invalid.
 pub fn parse_retry_after(header_value: Option<&str>) -> Option<std::time::Duration> {
-    let value = header_value?;
-    value
-        .parse::<u64>()
-        .ok()
-        .map(|secs| std::time::Duration::from_secs(secs.min(60)))
+    parse_retry_after_value(header_value?).map(|d| d.min(std::time::Duration::from_secs(60)))
 }
 
 /// Calculate exponential backoff delay for a given attempt.
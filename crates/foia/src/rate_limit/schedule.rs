@@ -0,0 +1,154 @@
+//! Time-of-day windows when a source may be fetched.
+//!
+//! Lets small servers be crawled only overnight, e.g. `"01:00-06:00"`.
+//! Times are local; a window whose end is before its start wraps past
+//! midnight (`"22:00-04:00"`).
+
+use std::time::Duration;
+
+use chrono::{Local, NaiveTime, Timelike};
+use tracing::info;
+
+const SECS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// A daily window of local time, start inclusive and end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Parse `"HH:MM-HH:MM"`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (start, end) = value
+            .split_once('-')
+            .ok_or_else(|| format!("Schedule window '{}' must look like HH:MM-HH:MM", value))?;
+        let parse_time = |s: &str| {
+            NaiveTime::parse_from_str(s.trim(), "%H:%M")
+                .map_err(|_| format!("Invalid time '{}' in schedule window '{}'", s, value))
+        };
+        Ok(Self {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+
+    /// Whether `time` falls inside the window. Equal start and end means all day.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start == self.end {
+            true
+        } else if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// Time from `time` until the window next opens.
+    fn until_start(&self, time: NaiveTime) -> Duration {
+        let now = time.num_seconds_from_midnight();
+        let start = self.start.num_seconds_from_midnight();
+        Duration::from_secs(((start + SECS_PER_DAY - now) % SECS_PER_DAY) as u64)
+    }
+}
+
+/// Set of daily windows; requests are allowed inside any of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    windows: Vec<TimeWindow>,
+}
+
+impl Schedule {
+    /// Parse a list of windows. An empty list means no restriction (`None`).
+    pub fn parse(windows: &[String]) -> Result<Option<Self>, String> {
+        if windows.is_empty() {
+            return Ok(None);
+        }
+        let windows = windows
+            .iter()
+            .map(|w| TimeWindow::parse(w))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Self { windows }))
+    }
+
+    /// Whether requests are allowed at `time`.
+    pub fn is_open_at(&self, time: NaiveTime) -> bool {
+        self.windows.iter().any(|w| w.contains(time))
+    }
+
+    /// Whether requests are allowed now.
+    pub fn is_open(&self) -> bool {
+        self.is_open_at(Local::now().time())
+    }
+
+    /// How long from `time` until a window opens (zero if one is open).
+    pub fn until_open_at(&self, time: NaiveTime) -> Duration {
+        if self.is_open_at(time) {
+            return Duration::ZERO;
+        }
+        self.windows
+            .iter()
+            .map(|w| w.until_start(time))
+            .min()
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// Sleep until a window is open.
+    pub async fn wait_until_open(&self, label: &str) {
+        let wait = self.until_open_at(Local::now().time());
+        if wait.is_zero() {
+            return;
+        }
+        info!(
+            "{} is outside its schedule; waiting {}m for the next window",
+            label,
+            wait.as_secs().div_ceil(60)
+        );
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_window_contains() {
+        let night = TimeWindow::parse("01:00-06:00").unwrap();
+        assert!(night.contains(t("01:00")));
+        assert!(night.contains(t("05:59")));
+        assert!(!night.contains(t("06:00")));
+        assert!(!night.contains(t("12:00")));
+
+        let wrapping = TimeWindow::parse("22:00 - 04:00").unwrap();
+        assert!(wrapping.contains(t("23:30")));
+        assert!(wrapping.contains(t("03:00")));
+        assert!(!wrapping.contains(t("12:00")));
+
+        assert!(TimeWindow::parse("1am-6am").is_err());
+        assert!(TimeWindow::parse("01:00").is_err());
+    }
+
+    #[test]
+    fn test_until_open() {
+        let schedule = Schedule::parse(&["01:00-06:00".into(), "13:00-14:00".into()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(schedule.until_open_at(t("02:00")), Duration::ZERO);
+        assert_eq!(
+            schedule.until_open_at(t("12:30")),
+            Duration::from_secs(30 * 60)
+        );
+        // After the last window of the day, the next is tomorrow at 01:00
+        assert_eq!(
+            schedule.until_open_at(t("23:00")),
+            Duration::from_secs(2 * 60 * 60)
+        );
+        assert!(Schedule::parse(&[]).unwrap().is_none());
+    }
+}
@@ -15,7 +15,7 @@ use super::backend::{DomainRateState, RateLimitBackend, RateLimitError, RateLimi
 use crate::repository::pool::DbPool;
 use crate::repository::{NewRateLimitState, RateLimitStateRecord};
 use crate::schema::rate_limit_state;
-use crate::with_conn_split;
+use crate::{with_conn_split, with_write_conn_split};
 
 /// Diesel-backed rate limit storage (SQLite/PostgreSQL).
 #[derive(Clone)]
@@ -63,7 +63,7 @@ impl DieselRateLimitBackend {
         let total_requests = i32::try_from(state.total_requests).unwrap_or(i32::MAX);
         let rate_limit_hits = i32::try_from(state.rate_limit_hits).unwrap_or(i32::MAX);
 
-        with_conn_split!(self.pool,
+        with_write_conn_split!(self.pool,
             sqlite: conn => {
                 diesel::replace_into(rate_limit_state::table)
                     .values(NewRateLimitState {
@@ -196,6 +196,25 @@ impl RateLimitBackend for DieselRateLimitBackend {
         // 403 tracking is handled in memory by RateLimiter
         Ok(0)
     }
+
+    async fn all_domains(&self) -> RateLimitResult<Vec<DomainRateState>> {
+        let records: Vec<RateLimitStateRecord> = with_conn_split!(self.pool,
+            sqlite: conn => {
+                rate_limit_state::table
+                    .load::<RateLimitStateRecord>(&mut conn)
+                    .await
+                    .map_err(|e| RateLimitError::Database(e.to_string()))?
+            },
+            postgres: conn => {
+                rate_limit_state::table
+                    .load::<RateLimitStateRecord>(&mut conn)
+                    .await
+                    .map_err(|e| RateLimitError::Database(e.to_string()))?
+            }
+        );
+
+        Ok(records.into_iter().map(Self::record_to_state).collect())
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,179 @@
+//! Opaque keyset pagination cursors.
+//!
+//! Listing queries order by a sort key plus a unique tie-breaker (usually the
+//! document id) and resume from the last row seen instead of using `OFFSET`,
+//! so deep pages cost the same as the first one.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Position in an ordered result set.
+///
+/// Holds the sort-key values of a boundary row, in the same order as the
+/// query's `ORDER BY`. Callers should treat it as opaque and round-trip it
+/// via [`PageCursor::encode`] / [`PageCursor::decode`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    /// Resume before the boundary row (paging backwards) instead of after it.
+    #[serde(rename = "b", default, skip_serializing_if = "std::ops::Not::not")]
+    pub before: bool,
+    /// Sort-key values of the boundary row.
+    #[serde(rename = "k")]
+    pub keys: Vec<String>,
+}
+
+impl PageCursor {
+    /// Cursor resuming after the given row.
+    pub fn after(keys: Vec<String>) -> Self {
+        Self {
+            before: false,
+            keys,
+        }
+    }
+
+    /// Cursor resuming before the given row.
+    pub fn before(keys: Vec<String>) -> Self {
+        Self { before: true, keys }
+    }
+
+    /// Sort-key value at `index`, if present.
+    pub fn key(&self, index: usize) -> Option<&str> {
+        self.keys.get(index).map(String::as_str)
+    }
+
+    /// Encode as a URL-safe opaque token.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a token produced by [`PageCursor::encode`].
+    ///
+    /// Returns `None` for malformed or tampered tokens.
+    pub fn decode(token: &str) -> Option<Self> {
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token.trim())
+            .ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+/// One page of keyset-paginated results.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the following page, if there are more rows.
+    pub next_cursor: Option<PageCursor>,
+    /// Cursor for the preceding page, if this isn't the first page.
+    pub prev_cursor: Option<PageCursor>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from rows fetched with `limit + 1` so the extra row
+    /// signals whether another page exists in the direction of travel.
+    ///
+    /// `rows` must be in the query's fetch order; for a `before` cursor
+    /// that is reversed, and the items are flipped back to display order.
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        limit: usize,
+        cursor: Option<&PageCursor>,
+        keys: impl Fn(&T) -> Vec<String>,
+    ) -> Self {
+        let has_more = rows.len() > limit;
+        rows.truncate(limit);
+
+        let backwards = cursor.is_some_and(|c| c.before);
+        if backwards {
+            rows.reverse();
+        }
+
+        let first = rows.first().map(|r| PageCursor::before(keys(r)));
+        let last = rows.last().map(|r| PageCursor::after(keys(r)));
+
+        let (has_next, has_prev) = if backwards {
+            (true, has_more)
+        } else {
+            (has_more, cursor.is_some())
+        };
+
+        Self {
+            items: rows,
+            next_cursor: if has_next { last } else { None },
+            prev_cursor: if has_prev { first } else { None },
+        }
+    }
+
+    /// Map items while keeping the cursors.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            prev_cursor: self.prev_cursor,
+        }
+    }
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            next_cursor: None,
+            prev_cursor: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = PageCursor::after(vec!["2024-01-01T00:00:00+00:00".into(), "doc-1".into()]);
+        let token = cursor.encode();
+        assert!(!token.contains('='));
+        assert_eq!(PageCursor::decode(&token), Some(cursor));
+
+        let before = PageCursor::before(vec!["x".into()]);
+        assert_eq!(PageCursor::decode(&before.encode()), Some(before));
+    }
+
+    #[test]
+    fn test_cursor_rejects_garbage() {
+        assert_eq!(PageCursor::decode("not a cursor!"), None);
+        assert_eq!(PageCursor::decode("bm9wZQ"), None);
+    }
+
+    #[test]
+    fn test_page_from_rows_forward() {
+        let key = |n: &i32| vec![n.to_string()];
+
+        let page = Page::from_rows(vec![1, 2, 3], 2, None, key);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor, Some(PageCursor::after(vec!["2".into()])));
+        assert_eq!(page.prev_cursor, None);
+
+        let cursor = page.next_cursor.unwrap();
+        let page = Page::from_rows(vec![3], 2, Some(&cursor), key);
+        assert_eq!(page.items, vec![3]);
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(page.prev_cursor, Some(PageCursor::before(vec!["3".into()])));
+    }
+
+    #[test]
+    fn test_page_from_rows_backward() {
+        let key = |n: &i32| vec![n.to_string()];
+        let cursor = PageCursor::before(vec!["5".into()]);
+
+        // Rows arrive in reverse order when paging backwards
+        let page = Page::from_rows(vec![4, 3, 2], 2, Some(&cursor), key);
+        assert_eq!(page.items, vec![3, 4]);
+        assert_eq!(page.next_cursor, Some(PageCursor::after(vec!["4".into()])));
+        assert_eq!(page.prev_cursor, Some(PageCursor::before(vec!["3".into()])));
+
+        let page = Page::from_rows(vec![2, 1], 2, Some(&cursor), key);
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.prev_cursor, None);
+    }
+}
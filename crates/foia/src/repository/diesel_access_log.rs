@@ -0,0 +1,306 @@
+//! Diesel-based document access repository.
+//!
+//! The server counts document views and downloads in `document_access`,
+//! one row per document, day, kind and referring site. Counts are all that
+//! is kept; the analytics page reads totals back from it.
+
+use chrono::{DateTime, Utc};
+use diesel_async::RunQueryDsl;
+
+use super::pool::{DbPool, DieselError};
+use crate::{with_conn, with_write_conn};
+
+/// How a document was accessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessKind {
+    /// The document page was opened.
+    View,
+    /// The file, or pages of it, were downloaded.
+    Download,
+}
+
+impl AccessKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::View => "view",
+            Self::Download => "download",
+        }
+    }
+}
+
+/// Views and downloads of one document over a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentAccessCount {
+    pub document_id: String,
+    /// The document's title, or its ID if it no longer exists.
+    pub title: String,
+    pub source_id: String,
+    pub views: u64,
+    pub downloads: u64,
+}
+
+/// Visits from one referring site over a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferrerCount {
+    /// Host name of the referring site.
+    pub referrer: String,
+    pub count: u64,
+}
+
+/// Views and downloads of all documents over a period.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessTotals {
+    pub views: u64,
+    pub downloads: u64,
+}
+
+#[derive(diesel::QueryableByName)]
+struct TotalsRow {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    views: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    downloads: i64,
+}
+
+fn day(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d").to_string()
+}
+
+/// Diesel-based document access repository.
+#[derive(Clone)]
+pub struct DieselAccessLogRepository {
+    pool: DbPool,
+}
+
+impl DieselAccessLogRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Count one access to `document_id` at `at`, from the site `referrer`
+    /// (a host name) if known.
+    pub async fn record(
+        &self,
+        document_id: &str,
+        kind: AccessKind,
+        referrer: Option<&str>,
+        at: DateTime<Utc>,
+    ) -> Result<(), DieselError> {
+        let day = day(at);
+        let referrer = referrer.unwrap_or("");
+        with_write_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"INSERT INTO document_access (document_id, day, kind, referrer, count)
+                   VALUES ($1, $2, $3, $4, 1)
+                   ON CONFLICT (document_id, day, kind, referrer) DO UPDATE
+                   SET count = document_access.count + 1"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(document_id)
+            .bind::<diesel::sql_types::Text, _>(&day)
+            .bind::<diesel::sql_types::Text, _>(kind.as_str())
+            .bind::<diesel::sql_types::Text, _>(referrer)
+            .execute(&mut conn)
+            .await
+        })?;
+        Ok(())
+    }
+
+    /// The `limit` most accessed documents since the start of `since`'s
+    /// day, by views plus downloads.
+    pub async fn most_accessed(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<DocumentAccessCount>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            document_id: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            title: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            source_id: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            views: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            downloads: i64,
+        }
+
+        let since = day(since);
+        let rows: Vec<Row> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"SELECT a.document_id,
+                          COALESCE(d.title, a.document_id) AS title,
+                          COALESCE(d.source_id, '') AS source_id,
+                          CAST(COALESCE(SUM(CASE WHEN a.kind = 'view' THEN a.count ELSE 0 END), 0) AS BIGINT) AS views,
+                          CAST(COALESCE(SUM(CASE WHEN a.kind = 'download' THEN a.count ELSE 0 END), 0) AS BIGINT) AS downloads
+                   FROM document_access a
+                   LEFT JOIN documents d ON d.id = a.document_id
+                   WHERE a.day >= $1
+                   GROUP BY a.document_id, d.title, d.source_id
+                   ORDER BY SUM(a.count) DESC, a.document_id
+                   LIMIT $2"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .bind::<diesel::sql_types::BigInt, _>(limit as i64)
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DocumentAccessCount {
+                document_id: row.document_id,
+                title: row.title,
+                source_id: row.source_id,
+                views: row.views.max(0) as u64,
+                downloads: row.downloads.max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// The `limit` sites that sent the most readers since the start of
+    /// `since`'s day.
+    pub async fn top_referrers(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ReferrerCount>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct Row {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            referrer: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            count: i64,
+        }
+
+        let since = day(since);
+        let rows: Vec<Row> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"SELECT referrer, CAST(SUM(count) AS BIGINT) AS count
+                   FROM document_access
+                   WHERE day >= $1 AND referrer != ''
+                   GROUP BY referrer
+                   ORDER BY SUM(count) DESC, referrer
+                   LIMIT $2"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .bind::<diesel::sql_types::BigInt, _>(limit as i64)
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ReferrerCount {
+                referrer: row.referrer,
+                count: row.count.max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// Views and downloads of all documents since the start of `since`'s day.
+    pub async fn totals(&self, since: DateTime<Utc>) -> Result<AccessTotals, DieselError> {
+        let since = day(since);
+        let rows: Vec<TotalsRow> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"SELECT CAST(COALESCE(SUM(CASE WHEN kind = 'view' THEN count ELSE 0 END), 0) AS BIGINT) AS views,
+                          CAST(COALESCE(SUM(CASE WHEN kind = 'download' THEN count ELSE 0 END), 0) AS BIGINT) AS downloads
+                   FROM document_access
+                   WHERE day >= $1"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .first()
+            .map(|row| AccessTotals {
+                views: row.views.max(0) as u64,
+                downloads: row.downloads.max(0) as u64,
+            })
+            .unwrap_or_default())
+    }
+
+    /// Delete counts from days before `before`'s. Returns the rows deleted.
+    pub async fn prune(&self, before: DateTime<Utc>) -> Result<usize, DieselError> {
+        let before = day(before);
+        with_write_conn!(self.pool, conn, {
+            diesel::sql_query("DELETE FROM document_access WHERE day < $1")
+                .bind::<diesel::sql_types::Text, _>(&before)
+                .execute(&mut conn)
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use chrono::{Duration, TimeZone};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_access_counts() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let repo = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .access_log();
+
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 15, 0, 0).unwrap();
+        let earlier = now - Duration::days(40);
+        repo.record("memo", AccessKind::View, Some("news.example.com"), now)
+            .await
+            .unwrap();
+        repo.record("memo", AccessKind::View, Some("news.example.com"), now)
+            .await
+            .unwrap();
+        repo.record("memo", AccessKind::Download, None, now)
+            .await
+            .unwrap();
+        repo.record("cable", AccessKind::View, Some("forum.example.org"), now)
+            .await
+            .unwrap();
+        repo.record("cable", AccessKind::View, None, earlier)
+            .await
+            .unwrap();
+
+        let since = now - Duration::days(29);
+        let top = repo.most_accessed(since, 10).await.unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].document_id, "memo");
+        assert_eq!((top[0].views, top[0].downloads), (2, 1));
+        // Unknown documents are listed by ID
+        assert_eq!(top[0].title, "memo");
+        assert_eq!(top[1].views, 1);
+
+        let referrers = repo.top_referrers(since, 10).await.unwrap();
+        assert_eq!(
+            referrers,
+            vec![
+                ReferrerCount {
+                    referrer: "news.example.com".to_string(),
+                    count: 2,
+                },
+                ReferrerCount {
+                    referrer: "forum.example.org".to_string(),
+                    count: 1,
+                },
+            ]
+        );
+
+        let totals = repo.totals(earlier).await.unwrap();
+        assert_eq!((totals.views, totals.downloads), (4, 1));
+
+        assert_eq!(repo.prune(since).await.unwrap(), 1);
+        assert_eq!(repo.totals(earlier).await.unwrap().views, 3);
+    }
+}
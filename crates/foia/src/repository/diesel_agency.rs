@@ -0,0 +1,278 @@
+//! Diesel-based agency registry repository.
+//!
+//! Agencies live in `agencies`; sources point at one through
+//! `sources.agency_id`.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::{AgencyRecord, NewAgency};
+use super::parse_datetime;
+use super::pool::{DbPool, DieselError};
+use crate::models::{Agency, AgencyLevel};
+use crate::schema::{agencies, sources};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
+
+/// Convert a database record to a domain model.
+impl From<AgencyRecord> for Agency {
+    fn from(record: AgencyRecord) -> Self {
+        Agency {
+            level: AgencyLevel::from_str(&record.level).unwrap_or(AgencyLevel::Other),
+            portal_urls: serde_json::from_str(&record.portal_urls).unwrap_or_default(),
+            created_at: parse_datetime(&record.created_at),
+            updated_at: parse_datetime(&record.updated_at),
+            id: record.id,
+            name: record.name,
+            abbreviation: record.abbreviation,
+            jurisdiction: record.jurisdiction,
+            parent_id: record.parent_id,
+            foia_email: record.foia_email,
+            foia_phone: record.foia_phone,
+            foia_address: record.foia_address,
+            website: record.website,
+        }
+    }
+}
+
+/// Diesel-based agency registry repository.
+#[derive(Clone)]
+pub struct DieselAgencyRepository {
+    pool: DbPool,
+}
+
+impl DieselAgencyRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Save an agency, replacing the entry with the same ID.
+    pub async fn save(&self, agency: &Agency) -> Result<(), DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let portal_urls =
+            serde_json::to_string(&agency.portal_urls).unwrap_or_else(|_| "[]".to_string());
+        let new = NewAgency {
+            id: &agency.id,
+            name: &agency.name,
+            abbreviation: agency.abbreviation.as_deref(),
+            jurisdiction: &agency.jurisdiction,
+            level: agency.level.as_str(),
+            parent_id: agency.parent_id.as_deref(),
+            foia_email: agency.foia_email.as_deref(),
+            foia_phone: agency.foia_phone.as_deref(),
+            foia_address: agency.foia_address.as_deref(),
+            portal_urls: portal_urls.clone(),
+            website: agency.website.as_deref(),
+            created_at: agency.created_at.to_rfc3339(),
+            updated_at: now.clone(),
+        };
+
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::replace_into(agencies::table)
+                    .values(&new)
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            },
+            postgres: conn => {
+                diesel::insert_into(agencies::table)
+                    .values(&new)
+                    .on_conflict(agencies::id)
+                    .do_update()
+                    .set((
+                        agencies::name.eq(&agency.name),
+                        agencies::abbreviation.eq(&agency.abbreviation),
+                        agencies::jurisdiction.eq(&agency.jurisdiction),
+                        agencies::level.eq(agency.level.as_str()),
+                        agencies::parent_id.eq(&agency.parent_id),
+                        agencies::foia_email.eq(&agency.foia_email),
+                        agencies::foia_phone.eq(&agency.foia_phone),
+                        agencies::foia_address.eq(&agency.foia_address),
+                        agencies::portal_urls.eq(&portal_urls),
+                        agencies::website.eq(&agency.website),
+                        agencies::updated_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            }
+        )
+    }
+
+    /// Get an agency by ID.
+    pub async fn get(&self, id: &str) -> Result<Option<Agency>, DieselError> {
+        with_conn!(self.pool, conn, {
+            agencies::table
+                .find(id)
+                .first::<AgencyRecord>(&mut conn)
+                .await
+                .optional()
+                .map(|opt| opt.map(Agency::from))
+        })
+    }
+
+    /// List agencies by name, optionally only those at `level`.
+    pub async fn list(&self, level: Option<AgencyLevel>) -> Result<Vec<Agency>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = agencies::table.order(agencies::name.asc()).into_boxed();
+            if let Some(level) = level {
+                query = query.filter(agencies::level.eq(level.as_str()));
+            }
+            query
+                .load::<AgencyRecord>(&mut conn)
+                .await
+                .map(|records| records.into_iter().map(Agency::from).collect())
+        })
+    }
+
+    /// Components of an agency, by name.
+    pub async fn children(&self, parent_id: &str) -> Result<Vec<Agency>, DieselError> {
+        with_conn!(self.pool, conn, {
+            agencies::table
+                .filter(agencies::parent_id.eq(parent_id))
+                .order(agencies::name.asc())
+                .load::<AgencyRecord>(&mut conn)
+                .await
+                .map(|records| records.into_iter().map(Agency::from).collect())
+        })
+    }
+
+    /// Delete an agency, unlinking its sources. Returns whether it existed.
+    pub async fn delete(&self, id: &str) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(sources::table.filter(sources::agency_id.eq(id)))
+                .set(sources::agency_id.eq(None::<String>))
+                .execute(&mut conn)
+                .await?;
+            diesel::delete(agencies::table.find(id))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// IDs of the sources linked to an agency.
+    pub async fn source_ids(&self, agency_id: &str) -> Result<Vec<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            sources::table
+                .filter(sources::agency_id.eq(agency_id))
+                .order(sources::id.asc())
+                .select(sources::id)
+                .load(&mut conn)
+                .await
+        })
+    }
+
+    /// Number of linked sources for every agency that has any.
+    pub async fn source_counts(&self) -> Result<HashMap<String, u64>, DieselError> {
+        use diesel::dsl::count_star;
+        let rows: Vec<(Option<String>, i64)> = with_conn!(self.pool, conn, {
+            sources::table
+                .filter(sources::agency_id.is_not_null())
+                .group_by(sources::agency_id)
+                .select((sources::agency_id, count_star()))
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, count)| Some((id?, count.max(0) as u64)))
+            .collect())
+    }
+
+    /// Link a source to an agency, or unlink it with `None`. Returns false
+    /// if the source doesn't exist.
+    pub async fn link_source(
+        &self,
+        source_id: &str,
+        agency_id: Option<&str>,
+    ) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(sources::table.find(source_id))
+                .set(sources::agency_id.eq(agency_id))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Source, SourceType};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    #[tokio::test]
+    async fn test_agency_registry() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.agencies();
+
+        let doj = Agency::new("Department of Justice".to_string(), Some("DOJ".to_string()));
+        let mut fbi = Agency::new(
+            "Federal Bureau of Investigation".to_string(),
+            Some("FBI".to_string()),
+        );
+        fbi.parent_id = Some(doj.id.clone());
+        fbi.portal_urls = vec!["https://vault.fbi.gov".to_string()];
+        repo.save(&doj).await.unwrap();
+        repo.save(&fbi).await.unwrap();
+
+        let loaded = repo.get("fbi").await.unwrap().unwrap();
+        assert_eq!(loaded.parent_id.as_deref(), Some("doj"));
+        assert_eq!(loaded.portal_urls, vec!["https://vault.fbi.gov"]);
+        assert_eq!(loaded.level, AgencyLevel::Federal);
+
+        fbi.foia_email = Some("foiparequest@fbi.gov".to_string());
+        repo.save(&fbi).await.unwrap();
+        let loaded = repo.get("fbi").await.unwrap().unwrap();
+        assert_eq!(loaded.foia_email.as_deref(), Some("foiparequest@fbi.gov"));
+
+        let all = repo.list(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, "doj", "sorted by name");
+        assert!(repo
+            .list(Some(AgencyLevel::State))
+            .await
+            .unwrap()
+            .is_empty());
+        let children = repo.children("doj").await.unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].id, "fbi");
+
+        let source = Source::new(
+            "fbi-vault".to_string(),
+            SourceType::FbiVault,
+            "FBI Vault".to_string(),
+            "https://vault.fbi.gov".to_string(),
+        );
+        ctx.sources().save(&source).await.unwrap();
+        assert!(repo.link_source("fbi-vault", Some("fbi")).await.unwrap());
+        assert!(!repo.link_source("missing", Some("fbi")).await.unwrap());
+        assert_eq!(repo.source_ids("fbi").await.unwrap(), vec!["fbi-vault"]);
+        assert_eq!(repo.source_counts().await.unwrap()["fbi"], 1);
+        let source = ctx.sources().get("fbi-vault").await.unwrap().unwrap();
+        assert_eq!(source.agency_id.as_deref(), Some("fbi"));
+
+        assert!(repo.delete("fbi").await.unwrap());
+        assert!(!repo.delete("fbi").await.unwrap());
+        let source = ctx.sources().get("fbi-vault").await.unwrap().unwrap();
+        assert_eq!(source.agency_id, None);
+    }
+}
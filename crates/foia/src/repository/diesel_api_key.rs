@@ -0,0 +1,290 @@
+//! Diesel-based API key repository.
+//!
+//! Keys for the JSON API are stored in `api_keys` as a SHA-256 hash; the key
+//! itself is shown once, when it is issued. Requests made with each key are
+//! counted per day in `api_usage`.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use sha2::{Digest, Sha256};
+
+use super::models::{ApiKeyRecord, NewApiKey};
+use super::pool::{DbPool, DieselError};
+use crate::schema::api_keys;
+use crate::{with_conn, with_write_conn};
+
+/// Start of every issued key, so leaked keys are easy to recognize.
+pub const API_KEY_PREFIX: &str = "foia_";
+
+/// `api_keys.role` of keys that may also issue, list and revoke keys.
+pub const API_KEY_ROLE_ADMIN: &str = "admin";
+
+/// `api_keys.role` of ordinary keys.
+pub const API_KEY_ROLE_CLIENT: &str = "client";
+
+/// Characters of a key kept in the clear to identify it.
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// SHA-256 of an API key, hex-encoded, as stored.
+pub fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+impl ApiKeyRecord {
+    /// Whether this key may issue, list and revoke keys.
+    pub fn is_admin(&self) -> bool {
+        self.role == API_KEY_ROLE_ADMIN
+    }
+}
+
+/// A newly issued key. `key` is not stored and can't be shown again.
+#[derive(Debug, Clone)]
+pub struct IssuedApiKey {
+    pub record: ApiKeyRecord,
+    pub key: String,
+}
+
+/// Requests made with one key over a period.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiKeyUsage {
+    pub key_id: i32,
+    /// Requests served.
+    pub requests: u64,
+    /// Requests refused for going over the key's rate limit.
+    pub rejected: u64,
+}
+
+/// Diesel-based API key repository.
+#[derive(Clone)]
+pub struct DieselApiKeyRepository {
+    pool: DbPool,
+}
+
+impl DieselApiKeyRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Issue a key named `name`, limited to `rate_limit` requests per
+    /// minute (`None` for the server default). An `admin` key may also
+    /// manage keys.
+    pub async fn issue(
+        &self,
+        name: &str,
+        rate_limit: Option<u32>,
+        admin: bool,
+    ) -> Result<IssuedApiKey, DieselError> {
+        let key = format!("{}{}", API_KEY_PREFIX, uuid::Uuid::new_v4().simple());
+        let key_hash = hash_api_key(&key);
+        let now = Utc::now().to_rfc3339();
+        let new = NewApiKey {
+            name,
+            prefix: &key[..DISPLAY_PREFIX_LEN],
+            key_hash: &key_hash,
+            rate_limit: rate_limit.map(|limit| limit.min(i32::MAX as u32) as i32),
+            created_at: &now,
+            role: if admin {
+                API_KEY_ROLE_ADMIN
+            } else {
+                API_KEY_ROLE_CLIENT
+            },
+        };
+
+        let record = with_write_conn!(self.pool, conn, {
+            diesel::insert_into(api_keys::table)
+                .values(&new)
+                .execute(&mut conn)
+                .await?;
+            api_keys::table
+                .filter(api_keys::key_hash.eq(&key_hash))
+                .first::<ApiKeyRecord>(&mut conn)
+                .await
+        })?;
+        Ok(IssuedApiKey { record, key })
+    }
+
+    /// All keys, revoked ones included, by name.
+    pub async fn list(&self) -> Result<Vec<ApiKeyRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            api_keys::table
+                .order(api_keys::name.asc())
+                .load::<ApiKeyRecord>(&mut conn)
+                .await
+        })
+    }
+
+    /// Get a key by name.
+    pub async fn get_by_name(&self, name: &str) -> Result<Option<ApiKeyRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            api_keys::table
+                .filter(api_keys::name.eq(name))
+                .first::<ApiKeyRecord>(&mut conn)
+                .await
+                .optional()
+        })
+    }
+
+    /// The unrevoked key matching `key`, if any.
+    pub async fn authenticate(&self, key: &str) -> Result<Option<ApiKeyRecord>, DieselError> {
+        let key_hash = hash_api_key(key);
+        with_conn!(self.pool, conn, {
+            api_keys::table
+                .filter(api_keys::key_hash.eq(&key_hash))
+                .filter(api_keys::revoked_at.is_null())
+                .first::<ApiKeyRecord>(&mut conn)
+                .await
+                .optional()
+        })
+    }
+
+    /// Revoke the key named `name`. Returns false if there is no such
+    /// unrevoked key.
+    pub async fn revoke(&self, name: &str) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let updated = with_write_conn!(self.pool, conn, {
+            diesel::update(
+                api_keys::table
+                    .filter(api_keys::name.eq(name))
+                    .filter(api_keys::revoked_at.is_null()),
+            )
+            .set(api_keys::revoked_at.eq(&now))
+            .execute(&mut conn)
+            .await
+        })?;
+        Ok(updated > 0)
+    }
+
+    /// Count a request made with key `key_id` at `at`, refused for going
+    /// over its rate limit if `rejected`.
+    pub async fn record_usage(
+        &self,
+        key_id: i32,
+        rejected: bool,
+        at: DateTime<Utc>,
+    ) -> Result<(), DieselError> {
+        let day = at.format("%Y-%m-%d").to_string();
+        let (served, refused) = if rejected { (0i64, 1i64) } else { (1, 0) };
+        let last_used_at = at.to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"INSERT INTO api_usage (key_id, day, requests, rejected)
+                   VALUES ($1, $2, $3, $4)
+                   ON CONFLICT (key_id, day) DO UPDATE
+                   SET requests = api_usage.requests + excluded.requests,
+                       rejected = api_usage.rejected + excluded.rejected"#,
+            )
+            .bind::<diesel::sql_types::Integer, _>(key_id)
+            .bind::<diesel::sql_types::Text, _>(&day)
+            .bind::<diesel::sql_types::BigInt, _>(served)
+            .bind::<diesel::sql_types::BigInt, _>(refused)
+            .execute(&mut conn)
+            .await?;
+            if !rejected {
+                diesel::update(api_keys::table.find(key_id))
+                    .set(api_keys::last_used_at.eq(&last_used_at))
+                    .execute(&mut conn)
+                    .await?;
+            }
+        });
+        Ok(())
+    }
+
+    /// Requests per key since the start of `since`'s day.
+    pub async fn usage_since(&self, since: DateTime<Utc>) -> Result<Vec<ApiKeyUsage>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct UsageRow {
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            key_id: i32,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            requests: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            rejected: i64,
+        }
+
+        let since = since.format("%Y-%m-%d").to_string();
+        let rows: Vec<UsageRow> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"SELECT key_id,
+                          CAST(COALESCE(SUM(requests), 0) AS BIGINT) AS requests,
+                          CAST(COALESCE(SUM(rejected), 0) AS BIGINT) AS rejected
+                   FROM api_usage
+                   WHERE day >= $1
+                   GROUP BY key_id
+                   ORDER BY key_id"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ApiKeyUsage {
+                key_id: row.key_id,
+                requests: row.requests.max(0) as u64,
+                rejected: row.rejected.max(0) as u64,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use chrono::{Duration, TimeZone};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_issue_authenticate_revoke() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let repo = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .api_keys();
+
+        let issued = repo.issue("newsroom", Some(30), false).await.unwrap();
+        assert!(issued.key.starts_with(API_KEY_PREFIX));
+        assert!(issued.key.starts_with(&issued.record.prefix));
+        assert_ne!(issued.record.key_hash, issued.key);
+        assert_eq!(issued.record.rate_limit, Some(30));
+        assert!(!issued.record.is_admin());
+        assert!(repo.issue("newsroom", None, false).await.is_err());
+        let admin = repo.issue("editor", None, true).await.unwrap();
+        assert!(admin.record.is_admin());
+
+        let found = repo.authenticate(&issued.key).await.unwrap().unwrap();
+        assert_eq!(found.id, issued.record.id);
+        assert!(repo.authenticate("foia_wrong").await.unwrap().is_none());
+
+        let day = Utc.with_ymd_and_hms(2026, 5, 4, 9, 0, 0).unwrap();
+        repo.record_usage(found.id, false, day).await.unwrap();
+        repo.record_usage(found.id, false, day).await.unwrap();
+        repo.record_usage(found.id, true, day).await.unwrap();
+        repo.record_usage(found.id, false, day - Duration::days(2))
+            .await
+            .unwrap();
+        let usage = repo.usage_since(day).await.unwrap();
+        assert_eq!(
+            usage,
+            vec![ApiKeyUsage {
+                key_id: found.id,
+                requests: 2,
+                rejected: 1,
+            }]
+        );
+        let key = repo.get_by_name("newsroom").await.unwrap().unwrap();
+        assert!(key.last_used_at.is_some());
+
+        assert!(repo.revoke("newsroom").await.unwrap());
+        assert!(!repo.revoke("newsroom").await.unwrap());
+        assert!(repo.authenticate(&issued.key).await.unwrap().is_none());
+        assert_eq!(repo.list().await.unwrap().len(), 2);
+    }
+}
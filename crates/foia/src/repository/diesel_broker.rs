@@ -0,0 +1,316 @@
+//! Diesel-based worker queue for the local-DB broker.
+//!
+//! Messages are claimed by pushing `claimed_until` past now, so a worker
+//! that dies without acking loses its claim when the timeout passes.
+
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::BrokerMessageRecord;
+use super::pool::{DbPool, DieselError};
+use crate::schema::broker_messages;
+use crate::{with_conn, with_write_conn};
+
+/// Waiting to be claimed, or claimed and not yet acked.
+const STATUS_READY: &str = "ready";
+/// Failed too often, or rejected without requeue.
+const STATUS_DEAD: &str = "dead";
+
+/// How many times a claim is retried when another worker wins the race.
+const CLAIM_RETRIES: usize = 5;
+
+/// Message counts for one queue.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    /// Messages waiting to be claimed, including those delayed for retry.
+    pub ready: u64,
+    /// Messages claimed by a worker. `None` if the broker doesn't say.
+    pub in_flight: Option<u64>,
+    /// Messages that won't be retried.
+    pub dead: u64,
+}
+
+/// Diesel-based worker queue repository.
+#[derive(Clone)]
+pub struct DieselBrokerRepository {
+    pool: DbPool,
+}
+
+impl DieselBrokerRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Add a message to `queue`. With `unique`, returns false without
+    /// adding it if the same payload is already waiting there.
+    pub async fn push(
+        &self,
+        queue: &str,
+        payload: &str,
+        unique: bool,
+    ) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            let waiting: i64 = if unique {
+                broker_messages::table
+                    .filter(broker_messages::queue.eq(queue))
+                    .filter(broker_messages::status.eq(STATUS_READY))
+                    .filter(broker_messages::claimed_until.is_null())
+                    .filter(broker_messages::payload.eq(payload))
+                    .count()
+                    .get_result(&mut conn)
+                    .await?
+            } else {
+                0
+            };
+            if waiting > 0 {
+                Ok(false)
+            } else {
+                diesel::insert_into(broker_messages::table)
+                    .values((
+                        broker_messages::queue.eq(queue),
+                        broker_messages::payload.eq(payload),
+                        broker_messages::status.eq(STATUS_READY),
+                        broker_messages::attempts.eq(0),
+                        broker_messages::available_at.eq(&now),
+                        broker_messages::created_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
+                Ok(true)
+            }
+        })
+    }
+
+    /// Claim the oldest available message in `queue` for `visibility`,
+    /// counting the attempt.
+    pub async fn claim(
+        &self,
+        queue: &str,
+        visibility: Duration,
+    ) -> Result<Option<BrokerMessageRecord>, DieselError> {
+        let now = Utc::now();
+        let now_str = now.to_rfc3339();
+        let until = (now + visibility).to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            let mut claimed = None;
+            for _ in 0..CLAIM_RETRIES {
+                let available = broker_messages::table
+                    .filter(broker_messages::queue.eq(queue))
+                    .filter(broker_messages::status.eq(STATUS_READY))
+                    .filter(broker_messages::available_at.le(&now_str))
+                    .filter(
+                        broker_messages::claimed_until
+                            .is_null()
+                            .or(broker_messages::claimed_until.le(&now_str)),
+                    );
+                let Some(mut record) = available
+                    .clone()
+                    .order(broker_messages::id.asc())
+                    .first::<BrokerMessageRecord>(&mut conn)
+                    .await
+                    .optional()?
+                else {
+                    break;
+                };
+                // Only wins if no other worker claimed it since the select
+                let rows = diesel::update(available.filter(broker_messages::id.eq(record.id)))
+                    .set((
+                        broker_messages::claimed_until.eq(&until),
+                        broker_messages::attempts.eq(broker_messages::attempts + 1),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
+                if rows == 1 {
+                    record.attempts += 1;
+                    record.claimed_until = Some(until.clone());
+                    claimed = Some(record);
+                    break;
+                }
+            }
+            Ok(claimed)
+        })
+    }
+
+    /// Delete a message once it has been processed.
+    pub async fn delete(&self, id: i64) -> Result<(), DieselError> {
+        with_write_conn!(self.pool, conn, {
+            diesel::delete(broker_messages::table.find(id as i32))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Release a claimed message to be claimed again after `delay`.
+    pub async fn release(&self, id: i64, delay: Duration, error: &str) -> Result<(), DieselError> {
+        let available_at = (Utc::now() + delay).to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(broker_messages::table.find(id as i32))
+                .set((
+                    broker_messages::available_at.eq(&available_at),
+                    broker_messages::claimed_until.eq(None::<String>),
+                    broker_messages::last_error.eq(error),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Mark a message dead so it isn't claimed again.
+    pub async fn bury(&self, id: i64, error: &str) -> Result<(), DieselError> {
+        with_write_conn!(self.pool, conn, {
+            diesel::update(broker_messages::table.find(id as i32))
+                .set((
+                    broker_messages::status.eq(STATUS_DEAD),
+                    broker_messages::claimed_until.eq(None::<String>),
+                    broker_messages::last_error.eq(error),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Make the dead messages in `queue` available again with a fresh
+    /// attempt count, returning how many.
+    pub async fn revive(&self, queue: &str) -> Result<usize, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(
+                broker_messages::table
+                    .filter(broker_messages::queue.eq(queue))
+                    .filter(broker_messages::status.eq(STATUS_DEAD)),
+            )
+            .set((
+                broker_messages::status.eq(STATUS_READY),
+                broker_messages::attempts.eq(0),
+                broker_messages::available_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await
+        })
+    }
+
+    /// Count the messages in `queue` by state.
+    pub async fn depth(&self, queue: &str) -> Result<QueueDepth, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let (ready, in_flight, dead): (i64, i64, i64) = with_conn!(self.pool, conn, {
+            let ready = broker_messages::table
+                .filter(broker_messages::queue.eq(queue))
+                .filter(broker_messages::status.eq(STATUS_READY))
+                .filter(
+                    broker_messages::claimed_until
+                        .is_null()
+                        .or(broker_messages::claimed_until.le(&now)),
+                )
+                .count()
+                .get_result(&mut conn)
+                .await?;
+            let in_flight = broker_messages::table
+                .filter(broker_messages::queue.eq(queue))
+                .filter(broker_messages::status.eq(STATUS_READY))
+                .filter(broker_messages::claimed_until.gt(&now))
+                .count()
+                .get_result(&mut conn)
+                .await?;
+            let dead = broker_messages::table
+                .filter(broker_messages::queue.eq(queue))
+                .filter(broker_messages::status.eq(STATUS_DEAD))
+                .count()
+                .get_result(&mut conn)
+                .await?;
+            Ok::<_, DieselError>((ready, in_flight, dead))
+        })?;
+        Ok(QueueDepth {
+            ready: ready as u64,
+            in_flight: Some(in_flight as u64),
+            dead: dead as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    #[tokio::test]
+    async fn test_claim_ack_and_retry() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.broker();
+        let visibility = Duration::minutes(5);
+
+        assert!(repo
+            .push("ocr", r#"{"document_id":"a"}"#, true)
+            .await
+            .unwrap());
+        assert!(!repo
+            .push("ocr", r#"{"document_id":"a"}"#, true)
+            .await
+            .unwrap());
+        assert!(repo
+            .push("ocr", r#"{"document_id":"b"}"#, true)
+            .await
+            .unwrap());
+
+        let first = repo.claim("ocr", visibility).await.unwrap().unwrap();
+        assert_eq!(first.payload, r#"{"document_id":"a"}"#);
+        assert_eq!(first.attempts, 1);
+        let second = repo.claim("ocr", visibility).await.unwrap().unwrap();
+        assert_eq!(second.payload, r#"{"document_id":"b"}"#);
+        assert!(repo.claim("ocr", visibility).await.unwrap().is_none());
+        assert!(repo.claim("download", visibility).await.unwrap().is_none());
+
+        let depth = repo.depth("ocr").await.unwrap();
+        assert_eq!((depth.ready, depth.in_flight, depth.dead), (0, Some(2), 0));
+
+        repo.delete(first.id as i64).await.unwrap();
+        repo.release(second.id as i64, Duration::zero(), "timeout")
+            .await
+            .unwrap();
+        let retried = repo.claim("ocr", visibility).await.unwrap().unwrap();
+        assert_eq!(retried.id, second.id);
+        assert_eq!(retried.attempts, 2);
+        assert_eq!(retried.last_error.as_deref(), Some("timeout"));
+    }
+
+    #[tokio::test]
+    async fn test_delayed_and_dead_messages() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.broker();
+        let visibility = Duration::minutes(5);
+
+        repo.push("summarize", "{}", false).await.unwrap();
+        repo.push("summarize", "{}", false).await.unwrap();
+        let message = repo.claim("summarize", visibility).await.unwrap().unwrap();
+        repo.release(message.id as i64, Duration::hours(1), "rate limited")
+            .await
+            .unwrap();
+        let other = repo.claim("summarize", visibility).await.unwrap().unwrap();
+        assert_ne!(other.id, message.id);
+        assert!(repo.claim("summarize", visibility).await.unwrap().is_none());
+        assert_eq!(repo.depth("summarize").await.unwrap().ready, 1);
+
+        repo.bury(message.id as i64, "gave up").await.unwrap();
+        assert_eq!(repo.depth("summarize").await.unwrap().dead, 1);
+        assert_eq!(repo.revive("summarize").await.unwrap(), 1);
+        let revived = repo.claim("summarize", visibility).await.unwrap().unwrap();
+        assert_eq!(revived.attempts, 1);
+    }
+}
@@ -10,7 +10,7 @@ use super::models::{ConfigHistoryRecord, NewConfigHistory};
 use super::parse_datetime;
 use super::pool::{DbPool, DieselError};
 use crate::schema::configuration_history;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 /// Maximum number of configuration history entries to retain.
 const MAX_HISTORY_ENTRIES: i64 = 16;
@@ -18,14 +18,11 @@ const MAX_HISTORY_ENTRIES: i64 = 16;
 /// Represents a stored configuration entry.
 #[derive(Debug, Clone)]
 pub struct DieselConfigHistoryEntry {
-    #[allow(dead_code)]
     pub uuid: String,
-    #[allow(dead_code)]
     pub created_at: DateTime<Utc>,
     pub data: String,
     #[allow(dead_code)]
     pub format: String,
-    #[allow(dead_code)]
     pub hash: String,
 }
 
@@ -89,7 +86,7 @@ impl DieselConfigHistoryRepository {
             hash,
         };
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::insert_into(configuration_history::table)
                 .values(&new_entry)
                 .execute(&mut conn)
@@ -116,7 +113,6 @@ impl DieselConfigHistoryRepository {
     }
 
     /// Get all configuration history entries (most recent first).
-    #[allow(dead_code)]
     pub async fn get_all(&self) -> Result<Vec<DieselConfigHistoryEntry>, DieselError> {
         with_conn!(self.pool, conn, {
             configuration_history::table
@@ -132,6 +128,27 @@ impl DieselConfigHistoryRepository {
         })
     }
 
+    /// Get the entries whose UUID starts with `prefix` (most recent first).
+    pub async fn find_by_uuid_prefix(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<DieselConfigHistoryEntry>, DieselError> {
+        let pattern = format!("{}%", prefix.replace(['%', '_'], ""));
+        with_conn!(self.pool, conn, {
+            configuration_history::table
+                .filter(configuration_history::uuid.like(pattern))
+                .order(configuration_history::created_at.desc())
+                .load::<ConfigHistoryRecord>(&mut conn)
+                .await
+                .map(|records| {
+                    records
+                        .into_iter()
+                        .map(DieselConfigHistoryEntry::from)
+                        .collect()
+                })
+        })
+    }
+
     /// Get just the hash of the most recent configuration entry.
     pub async fn get_latest_hash(&self) -> Result<Option<String>, DieselError> {
         with_conn!(self.pool, conn, {
@@ -146,7 +163,7 @@ impl DieselConfigHistoryRepository {
 
     /// Prune old entries to keep only the last MAX_HISTORY_ENTRIES.
     async fn prune_old_entries(&self) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             // Get UUIDs to keep (most recent MAX_HISTORY_ENTRIES)
             let uuids_to_keep: Vec<String> = configuration_history::table
                 .select(configuration_history::uuid)
@@ -239,5 +256,12 @@ mod tests {
         // Get all
         let all = repo.get_all().await.unwrap();
         assert_eq!(all.len(), 2);
+
+        // Find by UUID prefix
+        let prefix = &all[1].uuid[..8];
+        let found = repo.find_by_uuid_prefix(prefix).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].hash, "hash1");
+        assert!(repo.find_by_uuid_prefix("zz").await.unwrap().is_empty());
     }
 }
@@ -0,0 +1,119 @@
+//! Archive snapshot records, used for wayback fallback offers.
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+#[cfg(feature = "postgres")]
+use super::LastInsertId;
+use super::{DieselCrawlRepository, LastInsertRowId};
+use crate::models::{ArchiveSnapshot, FallbackState, NewArchiveSnapshot};
+use crate::repository::pool::{DbPool, DieselError};
+use crate::schema::archive_snapshots;
+use crate::{with_conn, with_write_conn};
+
+impl DieselCrawlRepository {
+    /// Record an archive snapshot, returning its ID and whether it is new.
+    ///
+    /// A snapshot already recorded under the same archive URL is reused, so
+    /// the same capture is never offered twice.
+    pub async fn insert_archive_snapshot(
+        &self,
+        snapshot: &NewArchiveSnapshot,
+    ) -> Result<(i32, bool), DieselError> {
+        let archive_url = snapshot.archive_url.clone();
+        let existing: Option<i32> = with_conn!(self.pool, conn, {
+            archive_snapshots::table
+                .filter(archive_snapshots::archive_url.eq(&archive_url))
+                .select(archive_snapshots::id)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        if let Some(id) = existing {
+            return Ok((id, false));
+        }
+
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(archive_snapshots::table)
+                .values(snapshot)
+                .execute(&mut conn)
+                .await?;
+
+            let id: i32 = match &self.pool {
+                DbPool::Sqlite(_) => {
+                    let result: LastInsertRowId = diesel::sql_query("SELECT last_insert_rowid()")
+                        .get_result(&mut conn)
+                        .await?;
+                    result.id as i32
+                }
+                #[cfg(feature = "postgres")]
+                DbPool::Postgres(_) => {
+                    let result: LastInsertId = diesel::sql_query("SELECT lastval()::integer as id")
+                        .get_result(&mut conn)
+                        .await?;
+                    result.id
+                }
+            };
+
+            Ok((id, true))
+        })
+    }
+
+    /// Get an archive snapshot by ID.
+    pub async fn get_archive_snapshot(
+        &self,
+        id: i32,
+    ) -> Result<Option<ArchiveSnapshot>, DieselError> {
+        with_conn!(self.pool, conn, {
+            archive_snapshots::table
+                .find(id)
+                .select(ArchiveSnapshot::as_select())
+                .first(&mut conn)
+                .await
+                .optional()
+        })
+    }
+
+    /// Wayback fallback captures, newest first, optionally narrowed to one
+    /// source and one state.
+    pub async fn list_fallback_snapshots(
+        &self,
+        source_id: Option<&str>,
+        state: Option<FallbackState>,
+    ) -> Result<Vec<ArchiveSnapshot>, DieselError> {
+        let snapshots: Vec<ArchiveSnapshot> = with_conn!(self.pool, conn, {
+            archive_snapshots::table
+                .filter(archive_snapshots::metadata.like("%\"fallback_source\"%"))
+                .order(archive_snapshots::id.desc())
+                .select(ArchiveSnapshot::as_select())
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(snapshots
+            .into_iter()
+            .filter(|s| source_id.is_none() || s.fallback_source().as_deref() == source_id)
+            .filter(|s| state.is_none() || s.fallback_state() == state)
+            .collect())
+    }
+
+    /// Move a fallback capture to a new state. Returns false if it does not exist.
+    pub async fn set_fallback_state(
+        &self,
+        id: i32,
+        state: FallbackState,
+    ) -> Result<bool, DieselError> {
+        let Some(snapshot) = self.get_archive_snapshot(id).await? else {
+            return Ok(false);
+        };
+        let metadata = snapshot.metadata_with_state(state).to_string();
+
+        with_write_conn!(self.pool, conn, {
+            let rows = diesel::update(archive_snapshots::table.find(id))
+                .set(archive_snapshots::metadata.eq(&metadata))
+                .execute(&mut conn)
+                .await?;
+            Ok(rows > 0)
+        })
+    }
+}
@@ -6,13 +6,13 @@ use diesel_async::RunQueryDsl;
 use super::DieselCrawlRepository;
 use crate::repository::pool::DieselError;
 use crate::schema::{crawl_config, crawl_requests, crawl_urls};
-use crate::with_conn;
+use crate::with_write_conn;
 
 impl DieselCrawlRepository {
     /// Clear pending crawl state for a source (keeps fetched URLs).
     #[allow(dead_code)]
     pub async fn clear_source(&self, source_id: &str) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 crawl_urls::table
                     .filter(crawl_urls::source_id.eq(source_id))
@@ -36,7 +36,7 @@ impl DieselCrawlRepository {
 
     /// Clear ALL crawl state for a source.
     pub async fn clear_source_all(&self, source_id: &str) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::delete(crawl_urls::table.filter(crawl_urls::source_id.eq(source_id)))
                 .execute(&mut conn)
                 .await?;
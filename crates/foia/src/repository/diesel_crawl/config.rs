@@ -7,7 +7,7 @@ use diesel_async::RunQueryDsl;
 use super::DieselCrawlRepository;
 use crate::repository::pool::DieselError;
 use crate::schema::crawl_config;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 impl DieselCrawlRepository {
     /// Check if config has changed since last crawl.
@@ -36,7 +36,7 @@ impl DieselCrawlRepository {
     ) -> Result<(), DieselError> {
         let now = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             // Try to update first
             let updated =
                 diesel::update(crawl_config::table.filter(crawl_config::source_id.eq(source_id)))
@@ -6,11 +6,13 @@
 //! - `mod.rs` (this file): Main struct, From impls, types
 //! - `urls.rs`: URL CRUD operations
 //! - `queue.rs`: Queue/claiming operations
-//! - `requests.rs`: Request logging
+//! - `requests.rs`: Request logging and log queries
 //! - `stats.rs`: Statistics and analytics
 //! - `config.rs`: Config hash management
 //! - `cleanup.rs`: Cleanup operations
+//! - `archive.rs`: Archive snapshots for wayback fallback
 
+mod archive;
 mod cleanup;
 mod config;
 mod queue;
@@ -18,6 +20,7 @@ mod requests;
 mod stats;
 mod urls;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -211,6 +214,67 @@ pub struct RequestStats {
     pub total_requests: u64,
 }
 
+/// Request outcomes for one source on one day.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyRequestHealth {
+    /// UTC date, `YYYY-MM-DD`.
+    pub day: String,
+    pub requests: u64,
+    /// Requests answered 2xx or 304 without a transport error.
+    pub successes: u64,
+    /// Responses of 429 or 503.
+    pub rate_limited: u64,
+    /// Requests with a recorded duration, and their summed duration.
+    pub timed_requests: u64,
+    pub total_duration_ms: u64,
+}
+
+/// Requests one source made over a period.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestWindow {
+    pub requests: u64,
+    /// Requests answered 2xx or 304 without a transport error.
+    pub successes: u64,
+    /// Responses of 429 or 503.
+    pub rate_limited: u64,
+    /// Conditional requests answered 304.
+    pub not_modified: u64,
+    pub first_request: Option<DateTime<Utc>>,
+    pub last_request: Option<DateTime<Utc>>,
+    /// Most requests started within one clock minute.
+    pub busiest_minute: u64,
+    /// Requests with a recorded duration, and their summed duration.
+    pub timed_requests: u64,
+    pub total_duration_ms: u64,
+}
+
+/// Filter for listing logged requests.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLogFilter {
+    pub source_id: Option<String>,
+    /// Inclusive range of response status codes, e.g. `(400, 499)`.
+    pub status: Option<(u16, u16)>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Only requests logged before this ID, for paging back through the log.
+    pub before_id: Option<i64>,
+    /// Only requests for this exact URL.
+    pub url: Option<String>,
+    /// Only failed requests: an error, or a status of 400 or more.
+    pub errors_only: bool,
+}
+
+/// Persisted rate-limit state for one domain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainRateLimit {
+    pub domain: String,
+    pub current_delay_ms: u64,
+    pub in_backoff: bool,
+    pub total_requests: u64,
+    pub rate_limit_hits: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Combined crawl statistics.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CrawlStats {
@@ -349,6 +413,7 @@ impl TryFrom<CrawlUrlRecordRaw> for CrawlUrl {
 mod tests {
     use super::super::pool::SqlitePool;
     use super::*;
+    use crate::http_client::ROBOTS_SKIP_REASON;
     use diesel_async::SimpleAsyncConnection;
     use tempfile::tempdir;
 
@@ -404,6 +469,20 @@ mod tests {
                 config_hash TEXT NOT NULL,
                 updated_at TEXT NOT NULL
             );
+
+            CREATE TABLE IF NOT EXISTS archive_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                service TEXT NOT NULL,
+                original_url TEXT NOT NULL,
+                archive_url TEXT NOT NULL,
+                captured_at TEXT NOT NULL,
+                discovered_at TEXT NOT NULL,
+                http_status INTEGER,
+                mimetype TEXT,
+                content_length INTEGER,
+                digest TEXT,
+                metadata TEXT NOT NULL DEFAULT '{}'
+            );
             "#,
         )
         .await
@@ -487,6 +566,138 @@ mod tests {
         assert!(pending.is_none());
     }
 
+    #[tokio::test]
+    async fn test_claim_pending_url_excluding() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        for source in ["night-only", "anytime"] {
+            let crawl_url = CrawlUrl::new(
+                format!("https://example.com/{}", source),
+                source.to_string(),
+                DiscoveryMethod::Seed,
+                None,
+                0,
+            );
+            repo.add_url(&crawl_url).await.unwrap();
+        }
+
+        let exclude = vec!["night-only".to_string()];
+        let claimed = repo
+            .claim_pending_url_excluding(None, &exclude)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.source_id, "anytime");
+
+        // The excluded source's URL stays in the queue
+        let pending = repo
+            .claim_pending_url_excluding(None, &exclude)
+            .await
+            .unwrap();
+        assert!(pending.is_none());
+        assert!(repo.claim_pending_url(None).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_url_queue_management() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        for (name, status) in [
+            ("stuck", UrlStatus::Exhausted),
+            ("broken", UrlStatus::Failed),
+            ("waiting", UrlStatus::Discovered),
+        ] {
+            let mut crawl_url = CrawlUrl::new(
+                format!("https://example.com/{}", name),
+                "test-source".to_string(),
+                DiscoveryMethod::Seed,
+                None,
+                0,
+            );
+            crawl_url.status = status;
+            crawl_url.retry_count = 3;
+            crawl_url.last_error = Some("HTTP 500".to_string());
+            repo.add_url(&crawl_url).await.unwrap();
+        }
+
+        let failed = UrlStatus::parse_filter("failed").unwrap();
+        let listed = repo
+            .list_urls("test-source", &failed, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(listed.items.len(), 2);
+        assert_eq!(
+            repo.list_urls("test-source", &[], None, 10)
+                .await
+                .unwrap()
+                .items
+                .len(),
+            3
+        );
+
+        // Paging two at a time reaches every URL once, then walks back
+        let first = repo.list_urls("test-source", &[], None, 2).await.unwrap();
+        let second = repo
+            .list_urls("test-source", &[], first.next_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(first.items.len(), 2);
+        assert_eq!(second.items.len(), 1);
+        assert!(second.next_cursor.is_none());
+        assert!(!first.items.iter().any(|u| u.url == second.items[0].url));
+        let back = repo
+            .list_urls("test-source", &[], second.prev_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        let urls = |p: &crate::repository::Page<CrawlUrl>| {
+            p.items.iter().map(|u| u.url.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(urls(&back), urls(&first));
+
+        // Retrying one URL clears its error state
+        assert!(repo
+            .retry_url("test-source", "https://example.com/stuck")
+            .await
+            .unwrap());
+        let retried = repo
+            .get_url("test-source", "https://example.com/stuck")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(retried.status, UrlStatus::Discovered);
+        assert_eq!(retried.retry_count, 0);
+        assert!(retried.last_error.is_none());
+
+        // Cancelling keeps the row but takes it out of the queue
+        assert!(repo
+            .cancel_url("test-source", "https://example.com/waiting")
+            .await
+            .unwrap());
+        assert!(!repo
+            .cancel_url("test-source", "https://example.com/unknown")
+            .await
+            .unwrap());
+        let skipped = repo
+            .list_urls("test-source", &[UrlStatus::Skipped], None, 10)
+            .await
+            .unwrap();
+        assert_eq!(skipped.items.len(), 1);
+
+        assert_eq!(
+            repo.reset_failed_urls(Some("test-source")).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            repo.get_pending_urls("test-source", 10)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
     #[tokio::test]
     async fn test_config_hash() {
         let (pool, _dir) = setup_test_db().await;
@@ -519,6 +730,199 @@ mod tests {
         assert!(changed);
     }
 
+    #[tokio::test]
+    async fn test_list_requests_filters() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        let now = Utc::now();
+        for (source, status, hours_ago) in [
+            ("fbi", 200, 48),
+            ("fbi", 404, 2),
+            ("fbi", 429, 1),
+            ("cia", 503, 1),
+        ] {
+            let mut request = CrawlRequest::new(
+                source.to_string(),
+                format!("https://{}.gov/{}", source, status),
+                "GET".to_string(),
+            );
+            request.request_at = now - chrono::Duration::hours(hours_ago);
+            request.response_status = Some(status);
+            repo.log_request(&request).await.unwrap();
+        }
+
+        let all = repo
+            .list_requests(&RequestLogFilter::default(), 10)
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].source_id, "cia", "newest first");
+
+        let filter = RequestLogFilter {
+            source_id: Some("fbi".to_string()),
+            status: Some((400, 499)),
+            ..Default::default()
+        };
+        let client_errors = repo.list_requests(&filter, 10).await.unwrap();
+        let statuses: Vec<_> = client_errors
+            .iter()
+            .filter_map(|r| r.response_status)
+            .collect();
+        assert_eq!(statuses, vec![429, 404]);
+
+        let filter = RequestLogFilter {
+            since: Some(now - chrono::Duration::hours(24)),
+            before_id: all[0].id,
+            ..Default::default()
+        };
+        assert_eq!(repo.list_requests(&filter, 10).await.unwrap().len(), 2);
+
+        let filter = RequestLogFilter {
+            url: Some("https://fbi.gov/404".to_string()),
+            ..Default::default()
+        };
+        let for_url = repo.list_requests(&filter, 10).await.unwrap();
+        assert_eq!(for_url.len(), 1);
+        assert_eq!(for_url[0].response_status, Some(404));
+
+        let filter = RequestLogFilter {
+            errors_only: true,
+            ..Default::default()
+        };
+        assert_eq!(repo.list_requests(&filter, 10).await.unwrap().len(), 3);
+
+        let (requests, _) = repo
+            .get_request_throughput(now - chrono::Duration::hours(3))
+            .await
+            .unwrap();
+        assert_eq!(requests, 3);
+    }
+
+    #[tokio::test]
+    async fn test_daily_request_health() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        let day1 = "2024-03-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let day2 = "2024-03-03T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        for (at, status, duration, error) in [
+            (day1, Some(200), Some(100), None),
+            (day1, Some(429), Some(300), None),
+            (day1, None, None, Some("timeout")),
+            (day2, Some(304), Some(50), None),
+            (day2, Some(503), None, None),
+        ] {
+            let mut request = CrawlRequest::new(
+                "fbi".to_string(),
+                "https://fbi.gov/doc".to_string(),
+                "GET".to_string(),
+            );
+            request.request_at = at;
+            request.response_status = status;
+            request.duration_ms = duration;
+            request.error = error.map(str::to_string);
+            repo.log_request(&request).await.unwrap();
+        }
+
+        let days = repo
+            .get_daily_request_health("fbi", day1 - chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            days[0],
+            DailyRequestHealth {
+                day: "2024-03-01".to_string(),
+                requests: 3,
+                successes: 1,
+                rate_limited: 1,
+                timed_requests: 2,
+                total_duration_ms: 400,
+            }
+        );
+        assert_eq!(days[1].day, "2024-03-03");
+        assert_eq!((days[1].successes, days[1].rate_limited), (1, 1));
+        assert!(repo
+            .get_daily_request_health("fbi", day2)
+            .await
+            .unwrap()
+            .iter()
+            .all(|d| d.day == "2024-03-03"));
+
+        assert_eq!(
+            repo.get_last_successful_request("fbi").await.unwrap(),
+            Some(day2)
+        );
+        assert_eq!(repo.get_last_successful_request("cia").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_request_window() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        let start = "2024-03-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        for (offset_secs, status) in [
+            (0, Some(200)),
+            (20, Some(304)),
+            (40, Some(429)),
+            (70, Some(200)),
+            (3600, None),
+        ] {
+            let mut request = CrawlRequest::new(
+                "fbi".to_string(),
+                "https://fbi.gov/doc".to_string(),
+                "GET".to_string(),
+            );
+            request.request_at = start + chrono::Duration::seconds(offset_secs);
+            request.response_status = status;
+            request.was_not_modified = status == Some(304);
+            request.duration_ms = Some(100);
+            repo.log_request(&request).await.unwrap();
+        }
+
+        let until = start + chrono::Duration::minutes(30);
+        let window = repo.get_request_window("fbi", start, until).await.unwrap();
+        assert_eq!(
+            window,
+            RequestWindow {
+                requests: 4,
+                successes: 3,
+                rate_limited: 1,
+                not_modified: 1,
+                first_request: Some(start),
+                last_request: Some(start + chrono::Duration::seconds(70)),
+                busiest_minute: 3,
+                timed_requests: 4,
+                total_duration_ms: 400,
+            }
+        );
+        let empty = repo.get_request_window("cia", start, until).await.unwrap();
+        assert_eq!(empty, RequestWindow::default());
+
+        let mut url = CrawlUrl::new(
+            "https://fbi.gov/private".to_string(),
+            "fbi".to_string(),
+            DiscoveryMethod::Seed,
+            None,
+            0,
+        );
+        repo.add_url(&url).await.unwrap();
+        url.mark_skipped(ROBOTS_SKIP_REASON);
+        repo.update_url(&url).await.unwrap();
+        let now = Utc::now();
+        let skipped = repo
+            .count_skipped(
+                "fbi",
+                ROBOTS_SKIP_REASON,
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(skipped, 1);
+    }
+
     async fn insert_raw_crawl(pool: &DbPool, sql: &str) {
         match pool {
             DbPool::Sqlite(ref sqlite_pool) => {
@@ -577,4 +981,50 @@ mod tests {
             Some("test"),
         );
     }
+
+    #[tokio::test]
+    async fn test_fallback_snapshots() {
+        use crate::models::{FallbackState, NewArchiveSnapshot};
+
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselCrawlRepository::new(pool);
+
+        let snapshot = NewArchiveSnapshot::new(
+            "wayback",
+            "https://agency.gov/report.pdf",
+            "https://web.archive.org/web/20240101000000id_/https://agency.gov/report.pdf",
+            Utc::now(),
+        )
+        .with_http_status(200)
+        .as_fallback("agency");
+
+        let (id, created) = repo.insert_archive_snapshot(&snapshot).await.unwrap();
+        assert!(created);
+        let (again, created) = repo.insert_archive_snapshot(&snapshot).await.unwrap();
+        assert_eq!(again, id);
+        assert!(!created);
+
+        let offered = repo
+            .list_fallback_snapshots(Some("agency"), Some(FallbackState::Offered))
+            .await
+            .unwrap();
+        assert_eq!(offered.len(), 1);
+        assert_eq!(offered[0].fallback_source().as_deref(), Some("agency"));
+        assert!(repo
+            .list_fallback_snapshots(Some("other"), None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(repo
+            .set_fallback_state(id, FallbackState::Queued)
+            .await
+            .unwrap());
+        let queued = repo.get_archive_snapshot(id).await.unwrap().unwrap();
+        assert_eq!(queued.fallback_state(), Some(FallbackState::Queued));
+        assert!(!repo
+            .set_fallback_state(id + 1, FallbackState::Queued)
+            .await
+            .unwrap());
+    }
 }
@@ -9,7 +9,7 @@ use crate::models::{CrawlUrl, UrlStatus};
 use crate::repository::models::CrawlUrlRecord;
 use crate::repository::pool::DieselError;
 use crate::schema::crawl_urls;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 impl DieselCrawlRepository {
     /// Get URLs that need to be fetched.
@@ -39,12 +39,24 @@ impl DieselCrawlRepository {
     pub async fn claim_pending_url(
         &self,
         source_id: Option<&str>,
+    ) -> Result<Option<CrawlUrl>, DieselError> {
+        self.claim_pending_url_excluding(source_id, &[]).await
+    }
+
+    /// Atomically claim a pending URL, skipping the given sources (e.g.
+    /// those outside their download schedule).
+    pub async fn claim_pending_url_excluding(
+        &self,
+        source_id: Option<&str>,
+        exclude: &[String],
     ) -> Result<Option<CrawlUrl>, DieselError> {
         let source_id = source_id.map(|s| s.to_string());
+        let exclude = exclude.to_vec();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             conn.transaction(|conn| {
                 let source_id = source_id.clone();
+                let exclude = exclude.clone();
                 Box::pin(async move {
                     let mut query = crawl_urls::table
                         .filter(crawl_urls::status.eq("discovered"))
@@ -55,6 +67,9 @@ impl DieselCrawlRepository {
                     if let Some(ref sid) = source_id {
                         query = query.filter(crawl_urls::source_id.eq(sid));
                     }
+                    if !exclude.is_empty() {
+                        query = query.filter(crawl_urls::source_id.ne_all(exclude));
+                    }
 
                     let record: Option<CrawlUrlRecord> = query.first(conn).await.optional()?;
 
@@ -89,7 +104,7 @@ impl DieselCrawlRepository {
         let limit = limit as i64;
         let now = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             // First, update any URLs whose retry time has passed to 'discovered'
             diesel::update(
                 crawl_urls::table
@@ -114,21 +129,22 @@ impl DieselCrawlRepository {
         })
     }
 
-    /// Reset all failed URLs to 'discovered' status for retry.
+    /// Reset all failed and exhausted URLs to 'discovered' status for retry.
     ///
     /// Optionally filter by source_id. Returns the number of URLs reset.
     pub async fn reset_failed_urls(&self, source_id: Option<&str>) -> Result<u64, DieselError> {
         let source_id = source_id.map(|s| s.to_string());
+        let failed = [UrlStatus::Failed.as_str(), UrlStatus::Exhausted.as_str()];
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let mut query =
-                diesel::update(crawl_urls::table.filter(crawl_urls::status.eq("failed")))
+                diesel::update(crawl_urls::table.filter(crawl_urls::status.eq_any(failed)))
                     .into_boxed();
 
             if let Some(ref sid) = source_id {
                 query = diesel::update(
                     crawl_urls::table
-                        .filter(crawl_urls::status.eq("failed"))
+                        .filter(crawl_urls::status.eq_any(failed))
                         .filter(crawl_urls::source_id.eq(sid)),
                 )
                 .into_boxed();
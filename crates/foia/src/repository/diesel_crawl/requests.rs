@@ -5,11 +5,12 @@ use diesel_async::RunQueryDsl;
 
 #[cfg(feature = "postgres")]
 use super::LastInsertId;
-use super::{DieselCrawlRepository, LastInsertRowId};
+use super::{DieselCrawlRepository, LastInsertRowId, RequestLogFilter};
 use crate::models::CrawlRequest;
+use crate::repository::models::CrawlRequestRecord;
 use crate::repository::pool::{DbPool, DieselError};
 use crate::schema::crawl_requests;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 impl DieselCrawlRepository {
     /// Log a completed request.
@@ -26,7 +27,7 @@ impl DieselCrawlRepository {
         let was_conditional = if request.was_conditional { 1i32 } else { 0 };
         let was_not_modified = if request.was_not_modified { 1i32 } else { 0 };
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::insert_into(crawl_requests::table)
                 .values((
                     crawl_requests::source_id.eq(&request.source_id),
@@ -66,4 +67,54 @@ impl DieselCrawlRepository {
             Ok(id)
         })
     }
+
+    /// List logged requests matching `filter`, newest first.
+    pub async fn list_requests(
+        &self,
+        filter: &RequestLogFilter,
+        limit: u32,
+    ) -> Result<Vec<CrawlRequest>, DieselError> {
+        let since = filter.since.map(|dt| dt.to_rfc3339());
+        let until = filter.until.map(|dt| dt.to_rfc3339());
+
+        let records: Vec<CrawlRequestRecord> = with_conn!(self.pool, conn, {
+            let mut query = crawl_requests::table.into_boxed();
+            if let Some(ref source_id) = filter.source_id {
+                query = query.filter(crawl_requests::source_id.eq(source_id));
+            }
+            if let Some(ref url) = filter.url {
+                query = query.filter(crawl_requests::url.eq(url));
+            }
+            if let Some((low, high)) = filter.status {
+                query = query.filter(
+                    crawl_requests::response_status
+                        .ge(low as i32)
+                        .and(crawl_requests::response_status.le(high as i32)),
+                );
+            }
+            if let Some(ref since) = since {
+                query = query.filter(crawl_requests::request_at.ge(since));
+            }
+            if let Some(ref until) = until {
+                query = query.filter(crawl_requests::request_at.le(until));
+            }
+            if let Some(before_id) = filter.before_id {
+                query = query.filter(crawl_requests::id.lt(before_id as i32));
+            }
+            if filter.errors_only {
+                query = query.filter(
+                    crawl_requests::error
+                        .is_not_null()
+                        .or(crawl_requests::response_status.ge(400)),
+                );
+            }
+            query
+                .order(crawl_requests::id.desc())
+                .limit(limit as i64)
+                .load(&mut conn)
+                .await
+        })?;
+
+        records.into_iter().map(CrawlRequest::try_from).collect()
+    }
 }
@@ -2,14 +2,19 @@
 
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
-use super::{CrawlState, CrawlStats, DieselCrawlRepository, RequestStats, StatusCount};
+use super::{
+    CrawlState, CrawlStats, DailyRequestHealth, DieselCrawlRepository, DomainRateLimit,
+    RequestStats, RequestWindow, StatusCount,
+};
 use crate::models::CrawlUrl;
-use crate::repository::models::CrawlUrlRecord;
+use crate::repository::models::{CrawlUrlRecord, RateLimitStateRecord};
 use crate::repository::pool::DieselError;
-use crate::schema::crawl_urls;
+use crate::repository::{parse_datetime, parse_datetime_opt};
+use crate::schema::{crawl_requests, crawl_urls, rate_limit_state};
 use crate::with_conn;
 
 impl DieselCrawlRepository {
@@ -236,4 +241,267 @@ impl DieselCrawlRepository {
 
         Ok(stats)
     }
+
+    /// Count requests logged since `since`, and the bytes they returned.
+    pub async fn get_request_throughput(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<(u64, u64), DieselError> {
+        #[derive(QueryableByName)]
+        struct ThroughputRow {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            requests: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            bytes: i64,
+        }
+
+        with_conn!(self.pool, conn, {
+            let row: ThroughputRow = diesel::sql_query(
+                r#"SELECT COUNT(*) as requests,
+                          CAST(COALESCE(SUM(response_size), 0) AS BIGINT) as bytes
+                   FROM crawl_requests
+                   WHERE request_at >= $1"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(since.to_rfc3339())
+            .get_result(&mut conn)
+            .await?;
+            Ok((row.requests as u64, row.bytes.max(0) as u64))
+        })
+    }
+
+    /// Time of the most recent logged request for each source.
+    pub async fn get_last_request_times(
+        &self,
+    ) -> Result<HashMap<String, DateTime<Utc>>, DieselError> {
+        use diesel::dsl::max;
+        let rows: Vec<(String, Option<String>)> = with_conn!(self.pool, conn, {
+            crawl_requests::table
+                .group_by(crawl_requests::source_id)
+                .select((crawl_requests::source_id, max(crawl_requests::request_at)))
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(source_id, at)| Some((source_id, parse_datetime(&at?))))
+            .collect())
+    }
+
+    /// Per-day request outcomes for a source since `since`, oldest first.
+    ///
+    /// Days without requests are omitted.
+    pub async fn get_daily_request_health(
+        &self,
+        source_id: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DailyRequestHealth>, DieselError> {
+        #[derive(QueryableByName)]
+        struct DayRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            day: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            requests: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            successes: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            rate_limited: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            timed: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            duration_ms: i64,
+        }
+
+        let rows: Vec<DayRow> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"
+                SELECT
+                    SUBSTR(request_at, 1, 10) as day,
+                    COUNT(*) as requests,
+                    COALESCE(SUM(CASE WHEN error IS NULL AND response_status >= 200
+                                       AND response_status < 400 THEN 1 ELSE 0 END), 0) as successes,
+                    COALESCE(SUM(CASE WHEN response_status IN (429, 503) THEN 1 ELSE 0 END), 0) as rate_limited,
+                    COUNT(duration_ms) as timed,
+                    CAST(COALESCE(SUM(duration_ms), 0) AS BIGINT) as duration_ms
+                FROM crawl_requests
+                WHERE source_id = $1 AND request_at >= $2
+                GROUP BY SUBSTR(request_at, 1, 10)
+                ORDER BY day
+                "#,
+            )
+            .bind::<diesel::sql_types::Text, _>(source_id)
+            .bind::<diesel::sql_types::Text, _>(since.to_rfc3339())
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| DailyRequestHealth {
+                day: r.day,
+                requests: r.requests.max(0) as u64,
+                successes: r.successes.max(0) as u64,
+                rate_limited: r.rate_limited.max(0) as u64,
+                timed_requests: r.timed.max(0) as u64,
+                total_duration_ms: r.duration_ms.max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// Time of the most recent successful (2xx or 304) request for a source.
+    pub async fn get_last_successful_request(
+        &self,
+        source_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, DieselError> {
+        use diesel::dsl::max;
+        let at: Option<String> = with_conn!(self.pool, conn, {
+            crawl_requests::table
+                .filter(crawl_requests::source_id.eq(source_id))
+                .filter(crawl_requests::error.is_null())
+                .filter(crawl_requests::response_status.ge(200))
+                .filter(crawl_requests::response_status.lt(400))
+                .select(max(crawl_requests::request_at))
+                .first(&mut conn)
+                .await
+        })?;
+        Ok(at.map(|at| parse_datetime(&at)))
+    }
+
+    /// Requests a source made between `since` and `until`.
+    pub async fn get_request_window(
+        &self,
+        source_id: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<RequestWindow, DieselError> {
+        #[derive(QueryableByName)]
+        struct WindowRow {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            requests: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            successes: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            rate_limited: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            not_modified: i64,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            first_request: Option<String>,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            last_request: Option<String>,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            timed: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            duration_ms: i64,
+        }
+
+        #[derive(QueryableByName)]
+        struct BusiestRow {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            busiest: i64,
+        }
+
+        let since = since.to_rfc3339();
+        let until = until.to_rfc3339();
+        let (row, busiest): (WindowRow, BusiestRow) = with_conn!(self.pool, conn, {
+            let row: WindowRow = diesel::sql_query(
+                r#"
+                SELECT
+                    COUNT(*) as requests,
+                    COALESCE(SUM(CASE WHEN error IS NULL AND response_status >= 200
+                                       AND response_status < 400 THEN 1 ELSE 0 END), 0) as successes,
+                    COALESCE(SUM(CASE WHEN response_status IN (429, 503) THEN 1 ELSE 0 END), 0) as rate_limited,
+                    COALESCE(SUM(CASE WHEN was_not_modified = 1 THEN 1 ELSE 0 END), 0) as not_modified,
+                    MIN(request_at) as first_request,
+                    MAX(request_at) as last_request,
+                    COUNT(duration_ms) as timed,
+                    CAST(COALESCE(SUM(duration_ms), 0) AS BIGINT) as duration_ms
+                FROM crawl_requests
+                WHERE source_id = $1 AND request_at >= $2 AND request_at <= $3
+                "#,
+            )
+            .bind::<diesel::sql_types::Text, _>(source_id)
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .bind::<diesel::sql_types::Text, _>(&until)
+            .get_result(&mut conn)
+            .await?;
+            let busiest: BusiestRow = diesel::sql_query(
+                r#"
+                SELECT CAST(COALESCE(MAX(n), 0) AS BIGINT) as busiest
+                FROM (
+                    SELECT COUNT(*) as n
+                    FROM crawl_requests
+                    WHERE source_id = $1 AND request_at >= $2 AND request_at <= $3
+                    GROUP BY SUBSTR(request_at, 1, 16)
+                ) per_minute
+                "#,
+            )
+            .bind::<diesel::sql_types::Text, _>(source_id)
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .bind::<diesel::sql_types::Text, _>(&until)
+            .get_result(&mut conn)
+            .await?;
+            Ok::<_, DieselError>((row, busiest))
+        })?;
+
+        Ok(RequestWindow {
+            requests: row.requests.max(0) as u64,
+            successes: row.successes.max(0) as u64,
+            rate_limited: row.rate_limited.max(0) as u64,
+            not_modified: row.not_modified.max(0) as u64,
+            first_request: parse_datetime_opt(row.first_request),
+            last_request: parse_datetime_opt(row.last_request),
+            busiest_minute: busiest.busiest.max(0) as u64,
+            timed_requests: row.timed.max(0) as u64,
+            total_duration_ms: row.duration_ms.max(0) as u64,
+        })
+    }
+
+    /// URLs of a source skipped for `reason` between `since` and `until`.
+    pub async fn count_skipped(
+        &self,
+        source_id: &str,
+        reason: &str,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<u64, DieselError> {
+        let count: i64 = with_conn!(self.pool, conn, {
+            crawl_urls::table
+                .filter(crawl_urls::source_id.eq(source_id))
+                .filter(crawl_urls::status.eq("skipped"))
+                .filter(crawl_urls::last_error.eq(reason))
+                .filter(crawl_urls::fetched_at.ge(since.to_rfc3339()))
+                .filter(crawl_urls::fetched_at.le(until.to_rfc3339()))
+                .count()
+                .get_result(&mut conn)
+                .await
+        })?;
+        Ok(count.max(0) as u64)
+    }
+
+    /// Persisted rate-limit state for every domain, slowest first.
+    ///
+    /// Only populated when a database rate-limit backend is in use.
+    pub async fn get_domain_rate_limits(&self) -> Result<Vec<DomainRateLimit>, DieselError> {
+        let records: Vec<RateLimitStateRecord> = with_conn!(self.pool, conn, {
+            rate_limit_state::table
+                .order((
+                    rate_limit_state::in_backoff.desc(),
+                    rate_limit_state::current_delay_ms.desc(),
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(records
+            .into_iter()
+            .map(|r| DomainRateLimit {
+                domain: r.domain,
+                current_delay_ms: r.current_delay_ms.max(0) as u64,
+                in_backoff: r.in_backoff != 0,
+                total_requests: r.total_requests.max(0) as u64,
+                rate_limit_hits: r.rate_limit_hits.max(0) as u64,
+                updated_at: parse_datetime(&r.updated_at),
+            })
+            .collect())
+    }
 }
@@ -4,11 +4,12 @@ use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
 use super::DieselCrawlRepository;
-use crate::models::CrawlUrl;
+use crate::models::{CrawlUrl, UrlStatus};
+use crate::repository::cursor::{Page, PageCursor};
 use crate::repository::models::CrawlUrlRecord;
 use crate::repository::pool::DieselError;
 use crate::schema::crawl_urls;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 impl DieselCrawlRepository {
     /// Add a discovered URL if not already known.
@@ -24,7 +25,7 @@ impl DieselCrawlRepository {
         let next_retry_at = crawl_url.next_retry_at.map(|dt| dt.to_rfc3339());
 
         use diesel::dsl::count_star;
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let exists: i64 = crawl_urls::table
                 .filter(crawl_urls::source_id.eq(&crawl_url.source_id))
                 .filter(crawl_urls::url.eq(&crawl_url.url))
@@ -101,7 +102,7 @@ impl DieselCrawlRepository {
         let next_retry_at = crawl_url.next_retry_at.map(|dt| dt.to_rfc3339());
         let retry_count = crawl_url.retry_count as i32;
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(
                 crawl_urls::table
                     .filter(crawl_urls::source_id.eq(&crawl_url.source_id))
@@ -154,7 +155,7 @@ impl DieselCrawlRepository {
         source_id: &str,
         url: &str,
     ) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(
                 crawl_urls::table
                     .filter(crawl_urls::source_id.eq(source_id))
@@ -197,6 +198,154 @@ impl DieselCrawlRepository {
         })
     }
 
+    /// Get URLs currently being fetched, across all sources.
+    pub async fn get_fetching_urls(&self, limit: u32) -> Result<Vec<CrawlUrl>, DieselError> {
+        with_conn!(self.pool, conn, {
+            crawl_urls::table
+                .filter(crawl_urls::status.eq("fetching"))
+                .order(crawl_urls::id.desc())
+                .limit(limit as i64)
+                .load::<CrawlUrlRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(CrawlUrl::try_from).collect())
+        })
+    }
+
+    /// List a source's URLs in any of `statuses`, most recently discovered
+    /// first, one keyset page at a time.
+    ///
+    /// An empty `statuses` slice lists URLs in every status.
+    pub async fn list_urls(
+        &self,
+        source_id: &str,
+        statuses: &[UrlStatus],
+        cursor: Option<&PageCursor>,
+        limit: u32,
+    ) -> Result<Page<CrawlUrl>, DieselError> {
+        let statuses: Vec<&str> = statuses.iter().map(|s| s.as_str()).collect();
+        let backwards = cursor.is_some_and(|c| c.before);
+        let cursor_at = cursor.and_then(|c| c.key(0)).map(str::to_string);
+        let cursor_id: Option<i32> = cursor.and_then(|c| c.key(1)?.parse().ok());
+
+        let records: Vec<CrawlUrlRecord> = with_conn!(self.pool, conn, {
+            let mut query = crawl_urls::table
+                .filter(crawl_urls::source_id.eq(source_id))
+                .limit(limit as i64 + 1)
+                .into_boxed();
+
+            if !statuses.is_empty() {
+                query = query.filter(crawl_urls::status.eq_any(statuses.clone()));
+            }
+
+            // Walking backwards flips the comparison and order
+            if let (Some(at), Some(id)) = (cursor_at.clone(), cursor_id) {
+                query = if backwards {
+                    query.filter(
+                        crawl_urls::discovered_at
+                            .gt(at.clone())
+                            .or(crawl_urls::discovered_at.eq(at).and(crawl_urls::id.gt(id))),
+                    )
+                } else {
+                    query.filter(
+                        crawl_urls::discovered_at
+                            .lt(at.clone())
+                            .or(crawl_urls::discovered_at.eq(at).and(crawl_urls::id.lt(id))),
+                    )
+                };
+            }
+
+            query = if backwards {
+                query.order((crawl_urls::discovered_at.asc(), crawl_urls::id.asc()))
+            } else {
+                query.order((crawl_urls::discovered_at.desc(), crawl_urls::id.desc()))
+            };
+
+            query.load::<CrawlUrlRecord>(&mut conn).await
+        })?;
+
+        let page = Page::from_rows(records, limit as usize, cursor, |r| {
+            vec![r.discovered_at.clone(), r.id.to_string()]
+        });
+        Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .map(CrawlUrl::try_from)
+                .collect::<Result<_, _>>()?,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
+    }
+
+    /// Put a single URL back in the queue with a clean retry count.
+    ///
+    /// Works for failed, exhausted, skipped and fetched URLs alike. Returns
+    /// `false` if the URL is not known for this source.
+    pub async fn retry_url(&self, source_id: &str, url: &str) -> Result<bool, DieselError> {
+        with_write_conn!(self.pool, conn, {
+            diesel::update(
+                crawl_urls::table
+                    .filter(crawl_urls::source_id.eq(source_id))
+                    .filter(crawl_urls::url.eq(url)),
+            )
+            .set((
+                crawl_urls::status.eq(UrlStatus::Discovered.as_str()),
+                crawl_urls::retry_count.eq(0),
+                crawl_urls::last_error.eq::<Option<String>>(None),
+                crawl_urls::next_retry_at.eq::<Option<String>>(None),
+            ))
+            .execute(&mut conn)
+            .await
+            .map(|n| n > 0)
+        })
+    }
+
+    /// Put several URLs of one source back in the queue; see [`Self::retry_url`].
+    ///
+    /// Returns the number of URLs re-queued.
+    pub async fn retry_urls(&self, source_id: &str, urls: &[String]) -> Result<u64, DieselError> {
+        let mut updated = 0;
+        for chunk in urls.chunks(500) {
+            updated += with_write_conn!(self.pool, conn, {
+                diesel::update(
+                    crawl_urls::table
+                        .filter(crawl_urls::source_id.eq(source_id))
+                        .filter(crawl_urls::url.eq_any(chunk)),
+                )
+                .set((
+                    crawl_urls::status.eq(UrlStatus::Discovered.as_str()),
+                    crawl_urls::retry_count.eq(0),
+                    crawl_urls::last_error.eq::<Option<String>>(None),
+                    crawl_urls::next_retry_at.eq::<Option<String>>(None),
+                ))
+                .execute(&mut conn)
+                .await
+            })? as u64;
+        }
+        Ok(updated)
+    }
+
+    /// Take a URL out of the queue by marking it skipped.
+    ///
+    /// The row is kept so discovery does not queue the URL again. Returns
+    /// `false` if the URL is not known for this source.
+    pub async fn cancel_url(&self, source_id: &str, url: &str) -> Result<bool, DieselError> {
+        with_write_conn!(self.pool, conn, {
+            diesel::update(
+                crawl_urls::table
+                    .filter(crawl_urls::source_id.eq(source_id))
+                    .filter(crawl_urls::url.eq(url)),
+            )
+            .set((
+                crawl_urls::status.eq(UrlStatus::Skipped.as_str()),
+                crawl_urls::next_retry_at.eq::<Option<String>>(None),
+            ))
+            .execute(&mut conn)
+            .await
+            .map(|n| n > 0)
+        })
+    }
+
     /// Count URLs for a source.
     pub async fn count_by_source(&self, source_id: &str) -> Result<u64, DieselError> {
         use diesel::dsl::count_star;
@@ -11,7 +11,7 @@ use super::{DieselDocumentRepository, ReturningId};
 use crate::repository::models::DocumentAnalysisResultRecord;
 use crate::repository::pool::DieselError;
 use crate::schema::document_analysis_results;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 /// Analysis result status.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -49,6 +49,7 @@ pub struct AnalysisResultEntry {
     pub version_id: i64,
     pub analysis_type: String,
     pub backend: String,
+    pub model: Option<String>,
     pub result_text: Option<String>,
     pub confidence: Option<f32>,
     pub processing_time_ms: Option<u64>,
@@ -67,6 +68,7 @@ impl From<DocumentAnalysisResultRecord> for AnalysisResultEntry {
             version_id: r.version_id as i64,
             analysis_type: r.analysis_type,
             backend: r.backend,
+            model: r.model,
             result_text: r.result_text,
             confidence: r.confidence,
             processing_time_ms: r.processing_time_ms.map(|ms| ms as u64),
@@ -100,6 +102,20 @@ impl DieselDocumentRepository {
         use crate::repository::sea_tables::DocumentAnalysisResults as Dar;
         use sea_query::{Expr, OnConflict, Query};
 
+        route_to_shard!(self, document_id, |shard| shard
+            .store_analysis_result_for_page(
+                page_id,
+                document_id,
+                version_id,
+                analysis_type,
+                backend,
+                model,
+                result_text,
+                confidence,
+                processing_time_ms,
+                error,
+                metadata,
+            ));
         let now = Utc::now().to_rfc3339();
         let status = if error.is_some() {
             AnalysisResultStatus::Failed.as_str()
@@ -166,7 +182,7 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let result: ReturningId = diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(Some(
                     page_id_i32,
@@ -210,10 +226,23 @@ impl DieselDocumentRepository {
         use crate::repository::sea_tables::DocumentAnalysisResults as Dar;
         use sea_query::{Expr, OnConflict, Query};
 
+        route_to_shard!(self, document_id, |shard| shard
+            .store_analysis_result_for_document(
+                document_id,
+                version_id,
+                analysis_type,
+                backend,
+                model,
+                result_text,
+                confidence,
+                processing_time_ms,
+                error,
+                metadata,
+            ));
         // Clean up any pending claim rows for this document/version/analysis_type.
         // Claims use backend='pending' which won't conflict with real backend names,
         // so we must explicitly delete them.
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 document_analysis_results::table
                     .filter(document_analysis_results::document_id.eq(document_id))
@@ -294,7 +323,7 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let result: ReturningId = diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(None::<i32>)
                 .bind::<diesel::sql_types::Text, _>(document_id)
@@ -335,6 +364,22 @@ impl DieselDocumentRepository {
         Ok(records.into_iter().map(AnalysisResultEntry::from).collect())
     }
 
+    /// Get analysis results for every version of a document, oldest first.
+    pub async fn get_all_analysis_results(
+        &self,
+        document_id: &str,
+    ) -> Result<Vec<AnalysisResultEntry>, DieselError> {
+        let records: Vec<DocumentAnalysisResultRecord> = with_conn!(self.pool, conn, {
+            document_analysis_results::table
+                .filter(document_analysis_results::document_id.eq(document_id))
+                .order(document_analysis_results::created_at.asc())
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(records.into_iter().map(AnalysisResultEntry::from).collect())
+    }
+
     /// Get analysis results for a specific page.
     pub async fn get_analysis_results_for_page(
         &self,
@@ -466,7 +511,9 @@ impl DieselDocumentRepository {
         document_id: &str,
         version_id: i32,
     ) -> Result<usize, DieselError> {
-        with_conn!(self.pool, conn, {
+        route_to_shard!(self, document_id, |shard| shard
+            .delete_analysis_results(document_id, version_id));
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 document_analysis_results::table
                     .filter(document_analysis_results::document_id.eq(document_id))
@@ -480,17 +527,24 @@ impl DieselDocumentRepository {
     /// Claim a document for analysis by inserting a `pending` result.
     ///
     /// Acts as a distributed lock: other workers will skip documents with a
-    /// recent pending result (within 90 minutes). The pending row is overwritten
-    /// by the completion/failure upsert when processing finishes.
+    /// pending result younger than the claim lease. Long-running work keeps
+    /// the claim with [`Self::renew_analysis_claim`]; a claim left by a
+    /// crashed worker lapses once the lease passes. The pending row is
+    /// overwritten by the completion/failure upsert when processing finishes.
     pub async fn claim_analysis(
         &self,
         document_id: &str,
         version_id: i32,
         analysis_type: &str,
     ) -> Result<(), DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.claim_analysis(
+            document_id,
+            version_id,
+            analysis_type
+        ));
         let now = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(
                 r#"INSERT INTO document_analysis_results
                    (document_id, version_id, analysis_type, backend, status, created_at)
@@ -509,6 +563,89 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Renew a pending claim so it stays held for another lease.
+    ///
+    /// Returns false if the claim is gone: finished, or released with
+    /// [`Self::release_analysis_claims`].
+    pub async fn renew_analysis_claim(
+        &self,
+        document_id: &str,
+        version_id: i32,
+        analysis_type: &str,
+    ) -> Result<bool, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.renew_analysis_claim(
+            document_id,
+            version_id,
+            analysis_type
+        ));
+        let now = Utc::now().to_rfc3339();
+
+        with_write_conn!(self.pool, conn, {
+            let updated = diesel::update(
+                document_analysis_results::table
+                    .filter(document_analysis_results::document_id.eq(document_id))
+                    .filter(document_analysis_results::version_id.eq(version_id))
+                    .filter(document_analysis_results::analysis_type.eq(analysis_type))
+                    .filter(document_analysis_results::backend.eq("pending"))
+                    .filter(document_analysis_results::status.eq("pending"))
+                    .filter(document_analysis_results::page_id.is_null()),
+            )
+            .set(document_analysis_results::created_at.eq(&now))
+            .execute(&mut conn)
+            .await?;
+            Ok(updated > 0)
+        })
+    }
+
+    /// Delete pending claims, returning how many.
+    ///
+    /// With `stale_only`, only claims older than the lease are removed;
+    /// otherwise every claim matching the filters is, including ones a live
+    /// worker still holds. `analysis_type` matches exactly, so annotation
+    /// claims need their prefixed type.
+    pub async fn release_analysis_claims(
+        &self,
+        analysis_type: Option<&str>,
+        source_id: Option<&str>,
+        stale_only: bool,
+    ) -> Result<u64, DieselError> {
+        use crate::schema::documents;
+
+        let lock_cutoff = (Utc::now() - self.claim_lease()).to_rfc3339();
+
+        let mut released = 0;
+        for shard in self.shard_repos() {
+            released +=
+                Box::pin(shard.release_analysis_claims(analysis_type, source_id, stale_only))
+                    .await?;
+        }
+        let deleted = with_write_conn!(self.pool, conn, {
+            let mut query = diesel::delete(document_analysis_results::table)
+                .filter(document_analysis_results::backend.eq("pending"))
+                .filter(document_analysis_results::status.eq("pending"))
+                .into_boxed();
+
+            if let Some(kind) = analysis_type {
+                query = query.filter(document_analysis_results::analysis_type.eq(kind));
+            }
+            if let Some(sid) = source_id {
+                query = query.filter(
+                    document_analysis_results::document_id.eq_any(
+                        documents::table
+                            .filter(documents::source_id.eq(sid))
+                            .select(documents::id),
+                    ),
+                );
+            }
+            if stale_only {
+                query = query.filter(document_analysis_results::created_at.le(&lock_cutoff));
+            }
+
+            query.execute(&mut conn).await
+        })?;
+        Ok(released + deleted as u64)
+    }
+
     /// Backfill `document_analysis_results` completion rows for documents
     /// that are already fully processed (status 'indexed' or 'ocr_complete')
     /// but don't yet have a completion row for the given analysis type.
@@ -521,7 +658,11 @@ impl DieselDocumentRepository {
     ) -> Result<u64, DieselError> {
         let now = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        let mut backfilled = 0;
+        for shard in self.shard_repos() {
+            backfilled += Box::pin(shard.backfill_analysis_completions(analysis_type)).await?;
+        }
+        with_write_conn!(self.pool, conn, {
             let count = diesel::sql_query(
                 r#"INSERT INTO document_analysis_results
                    (page_id, document_id, version_id, analysis_type, backend, status, created_at)
@@ -543,7 +684,7 @@ impl DieselDocumentRepository {
             .bind::<diesel::sql_types::Text, _>(analysis_type)
             .execute(&mut conn)
             .await?;
-            Ok(count as u64)
+            Ok(backfilled + count as u64)
         })
     }
 
@@ -559,7 +700,12 @@ impl DieselDocumentRepository {
         version_id: i32,
         analysis_type: &str,
     ) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        route_to_shard!(self, document_id, |shard| shard.delete_pending_claim(
+            document_id,
+            version_id,
+            analysis_type
+        ));
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 document_analysis_results::table
                     .filter(document_analysis_results::document_id.eq(document_id))
@@ -575,6 +721,61 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Get failed analysis results, newest first, optionally for one source.
+    pub async fn get_failed_analysis(
+        &self,
+        source_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<AnalysisResultEntry>, DieselError> {
+        use crate::schema::documents;
+
+        let limit = limit as i64;
+        let records: Vec<DocumentAnalysisResultRecord> = with_conn!(self.pool, conn, {
+            let mut query = document_analysis_results::table
+                .filter(document_analysis_results::status.eq("failed"))
+                .order(document_analysis_results::created_at.desc())
+                .limit(limit)
+                .into_boxed();
+
+            if let Some(sid) = source_id {
+                query = query.filter(
+                    document_analysis_results::document_id.eq_any(
+                        documents::table
+                            .filter(documents::source_id.eq(sid))
+                            .select(documents::id),
+                    ),
+                );
+            }
+
+            query.load(&mut conn).await
+        })?;
+
+        Ok(records.into_iter().map(AnalysisResultEntry::from).collect())
+    }
+
+    /// Delete analysis results by ID.
+    ///
+    /// Deleting a failed result makes its document eligible for analysis
+    /// again immediately instead of after the retry interval.
+    pub async fn delete_analysis_results_by_id(&self, ids: &[i64]) -> Result<u64, DieselError> {
+        let mut deleted = 0;
+        for shard in self.shard_repos() {
+            deleted += Box::pin(shard.delete_analysis_results_by_id(ids)).await?;
+        }
+        for chunk in ids.chunks(500) {
+            let chunk: Vec<i32> = chunk.iter().map(|&id| id as i32).collect();
+            deleted += with_write_conn!(self.pool, conn, {
+                diesel::delete(
+                    document_analysis_results::table
+                        .filter(document_analysis_results::id.eq_any(chunk.clone())),
+                )
+                .execute(&mut conn)
+                .await
+            })? as u64;
+        }
+        Ok(deleted)
+    }
+
     /// Count pending analysis for a specific type.
     pub async fn count_pending_analysis(&self, analysis_type: &str) -> Result<u64, DieselError> {
         use diesel::dsl::count_star;
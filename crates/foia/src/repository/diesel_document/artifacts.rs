@@ -0,0 +1,298 @@
+//! Derived artifact operations.
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::{ArtifactKind, ArtifactStatus, DerivedArtifact, NewDerivedArtifact};
+use crate::repository::models::DerivedArtifactRecord;
+use crate::repository::parse_datetime;
+use crate::repository::pool::DieselError;
+use crate::schema::{derived_artifacts, document_pages};
+use crate::{with_conn, with_write_conn};
+
+impl DieselDocumentRepository {
+    /// Record an artifact, replacing any with the same version, kind and name.
+    ///
+    /// A replaced artifact becomes ready again. Returns the artifact ID.
+    pub async fn save_artifact(&self, artifact: &NewDerivedArtifact) -> Result<i64, DieselError> {
+        route_to_shard!(self, &artifact.document_id, |shard| shard
+            .save_artifact(artifact));
+        let now = Utc::now().to_rfc3339();
+        let version_id = artifact.version_id as i32;
+        let kind = artifact.kind.as_str();
+        let metadata = artifact
+            .metadata
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+        let analysis_result_id = artifact.analysis_result_id.map(|id| id as i32);
+
+        with_write_conn!(self.pool, conn, {
+            let existing: Option<i32> = derived_artifacts::table
+                .filter(derived_artifacts::version_id.eq(version_id))
+                .filter(derived_artifacts::kind.eq(kind))
+                .filter(derived_artifacts::name.eq(&artifact.name))
+                .select(derived_artifacts::id)
+                .first(&mut conn)
+                .await
+                .optional()?;
+
+            let id = match existing {
+                Some(id) => {
+                    diesel::update(derived_artifacts::table.find(id))
+                        .set((
+                            derived_artifacts::mime_type.eq(&artifact.mime_type),
+                            derived_artifacts::file_path.eq(&artifact.file_path),
+                            derived_artifacts::file_size.eq(artifact.file_size as i64),
+                            derived_artifacts::content_hash.eq(&artifact.content_hash),
+                            derived_artifacts::analysis_result_id.eq(analysis_result_id),
+                            derived_artifacts::status.eq(ArtifactStatus::Ready.as_str()),
+                            derived_artifacts::metadata.eq(&metadata),
+                            derived_artifacts::updated_at.eq(&now),
+                        ))
+                        .execute(&mut conn)
+                        .await?;
+                    id
+                }
+                None => {
+                    diesel::insert_into(derived_artifacts::table)
+                        .values((
+                            derived_artifacts::document_id.eq(&artifact.document_id),
+                            derived_artifacts::version_id.eq(version_id),
+                            derived_artifacts::kind.eq(kind),
+                            derived_artifacts::name.eq(&artifact.name),
+                            derived_artifacts::mime_type.eq(&artifact.mime_type),
+                            derived_artifacts::file_path.eq(&artifact.file_path),
+                            derived_artifacts::file_size.eq(artifact.file_size as i64),
+                            derived_artifacts::content_hash.eq(&artifact.content_hash),
+                            derived_artifacts::analysis_result_id.eq(analysis_result_id),
+                            derived_artifacts::status.eq(ArtifactStatus::Ready.as_str()),
+                            derived_artifacts::metadata.eq(&metadata),
+                            derived_artifacts::created_at.eq(&now),
+                            derived_artifacts::updated_at.eq(&now),
+                        ))
+                        .execute(&mut conn)
+                        .await?;
+                    derived_artifacts::table
+                        .filter(derived_artifacts::version_id.eq(version_id))
+                        .filter(derived_artifacts::kind.eq(kind))
+                        .filter(derived_artifacts::name.eq(&artifact.name))
+                        .select(derived_artifacts::id)
+                        .first(&mut conn)
+                        .await?
+                }
+            };
+            Ok(id as i64)
+        })
+    }
+
+    /// Get an artifact by ID.
+    pub async fn get_artifact(&self, id: i64) -> Result<Option<DerivedArtifact>, DieselError> {
+        let record: Option<DerivedArtifactRecord> = with_conn!(self.pool, conn, {
+            derived_artifacts::table
+                .find(id as i32)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        record.map(Self::artifact_record_to_model).transpose()
+    }
+
+    /// Get an artifact of a version by kind and name.
+    pub async fn find_artifact(
+        &self,
+        version_id: i64,
+        kind: ArtifactKind,
+        name: &str,
+    ) -> Result<Option<DerivedArtifact>, DieselError> {
+        let record: Option<DerivedArtifactRecord> = with_conn!(self.pool, conn, {
+            derived_artifacts::table
+                .filter(derived_artifacts::version_id.eq(version_id as i32))
+                .filter(derived_artifacts::kind.eq(kind.as_str()))
+                .filter(derived_artifacts::name.eq(name))
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        record.map(Self::artifact_record_to_model).transpose()
+    }
+
+    /// Get all artifacts of a document version.
+    pub async fn get_artifacts(
+        &self,
+        document_id: &str,
+        version_id: i64,
+    ) -> Result<Vec<DerivedArtifact>, DieselError> {
+        let records: Vec<DerivedArtifactRecord> = with_conn!(self.pool, conn, {
+            derived_artifacts::table
+                .filter(derived_artifacts::document_id.eq(document_id))
+                .filter(derived_artifacts::version_id.eq(version_id as i32))
+                .order((derived_artifacts::kind.asc(), derived_artifacts::name.asc()))
+                .load(&mut conn)
+                .await
+        })?;
+        records
+            .into_iter()
+            .map(Self::artifact_record_to_model)
+            .collect()
+    }
+
+    /// Mark a version's artifacts of the given kinds stale.
+    pub async fn mark_artifacts_stale(
+        &self,
+        document_id: &str,
+        version_id: i64,
+        kinds: &[ArtifactKind],
+    ) -> Result<usize, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.mark_artifacts_stale(
+            document_id,
+            version_id,
+            kinds
+        ));
+        let kinds: Vec<&str> = kinds.iter().map(ArtifactKind::as_str).collect();
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(
+                derived_artifacts::table
+                    .filter(derived_artifacts::document_id.eq(document_id))
+                    .filter(derived_artifacts::version_id.eq(version_id as i32))
+                    .filter(derived_artifacts::kind.eq_any(&kinds))
+                    .filter(derived_artifacts::status.eq(ArtifactStatus::Ready.as_str())),
+            )
+            .set((
+                derived_artifacts::status.eq(ArtifactStatus::Stale.as_str()),
+                derived_artifacts::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await
+        })
+    }
+
+    /// Mark artifacts built from OCR text stale for the versions of `page_ids`.
+    ///
+    /// Shards number their pages from their own ranges, so each database
+    /// only matches the pages it holds.
+    pub(super) async fn mark_ocr_artifacts_stale(
+        &self,
+        page_ids: &[i32],
+    ) -> Result<usize, DieselError> {
+        let mut marked = 0;
+        for shard in self.shard_repos() {
+            marked += Box::pin(shard.mark_ocr_artifacts_stale(page_ids)).await?;
+        }
+        let kinds: Vec<&str> = ArtifactKind::from_ocr()
+            .iter()
+            .map(ArtifactKind::as_str)
+            .collect();
+        let now = Utc::now().to_rfc3339();
+        for chunk in page_ids.chunks(500) {
+            marked += with_write_conn!(self.pool, conn, {
+                diesel::update(
+                    derived_artifacts::table
+                        .filter(
+                            derived_artifacts::version_id.eq_any(
+                                document_pages::table
+                                    .filter(document_pages::id.eq_any(chunk))
+                                    .select(document_pages::version_id),
+                            ),
+                        )
+                        .filter(derived_artifacts::kind.eq_any(&kinds))
+                        .filter(derived_artifacts::status.eq(ArtifactStatus::Ready.as_str())),
+                )
+                .set((
+                    derived_artifacts::status.eq(ArtifactStatus::Stale.as_str()),
+                    derived_artifacts::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+            })?;
+        }
+        Ok(marked)
+    }
+
+    /// Get artifacts that have been stale since before `before`.
+    pub async fn get_stale_artifacts(
+        &self,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<DerivedArtifact>, DieselError> {
+        let before = before.to_rfc3339();
+        let records: Vec<DerivedArtifactRecord> = with_conn!(self.pool, conn, {
+            derived_artifacts::table
+                .filter(derived_artifacts::status.eq(ArtifactStatus::Stale.as_str()))
+                .filter(derived_artifacts::updated_at.lt(&before))
+                .load(&mut conn)
+                .await
+        })?;
+        records
+            .into_iter()
+            .map(Self::artifact_record_to_model)
+            .collect()
+    }
+
+    /// Delete artifact records by ID. Files on disk are left to the caller.
+    pub async fn delete_artifacts(&self, ids: &[i64]) -> Result<usize, DieselError> {
+        let mut deleted = 0;
+        for shard in self.shard_repos() {
+            deleted += Box::pin(shard.delete_artifacts(ids)).await?;
+        }
+        let ids: Vec<i32> = ids.iter().map(|&id| id as i32).collect();
+        for chunk in ids.chunks(500) {
+            deleted += with_write_conn!(self.pool, conn, {
+                diesel::delete(derived_artifacts::table.filter(derived_artifacts::id.eq_any(chunk)))
+                    .execute(&mut conn)
+                    .await
+            })?;
+        }
+        Ok(deleted)
+    }
+
+    /// File paths referenced by any artifact other than `excluding`.
+    pub async fn get_artifact_paths(
+        &self,
+        excluding: &[i64],
+    ) -> Result<HashSet<String>, DieselError> {
+        let rows: Vec<(i32, String)> = with_conn!(self.pool, conn, {
+            derived_artifacts::table
+                .select((derived_artifacts::id, derived_artifacts::file_path))
+                .load(&mut conn)
+                .await
+        })?;
+        let excluding: HashSet<i64> = excluding.iter().copied().collect();
+        Ok(rows
+            .into_iter()
+            .filter(|(id, _)| !excluding.contains(&(*id as i64)))
+            .map(|(_, path)| path)
+            .collect())
+    }
+
+    fn artifact_record_to_model(
+        record: DerivedArtifactRecord,
+    ) -> Result<DerivedArtifact, DieselError> {
+        let kind = ArtifactKind::from_str(&record.kind).ok_or_else(|| {
+            diesel::result::Error::DeserializationError(
+                format!("Unknown kind '{}' for artifact {}", record.kind, record.id).into(),
+            )
+        })?;
+        Ok(DerivedArtifact {
+            id: record.id as i64,
+            document_id: record.document_id,
+            version_id: record.version_id as i64,
+            kind,
+            name: record.name,
+            mime_type: record.mime_type,
+            file_path: record.file_path,
+            file_size: record.file_size as u64,
+            content_hash: record.content_hash,
+            analysis_result_id: record.analysis_result_id.map(|id| id as i64),
+            status: ArtifactStatus::from_str(&record.status).unwrap_or(ArtifactStatus::Stale),
+            metadata: record.metadata.and_then(|m| serde_json::from_str(&m).ok()),
+            created_at: parse_datetime(&record.created_at),
+            updated_at: parse_datetime(&record.updated_at),
+        })
+    }
+}
@@ -0,0 +1,76 @@
+//! Document rows read from portable bundles.
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::VirtualFile;
+use crate::repository::migration::PortableDocument;
+use crate::repository::models::VirtualFileRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{documents, virtual_files};
+use crate::{with_conn, with_write_conn};
+
+impl DieselDocumentRepository {
+    /// Insert a document row exactly as exported, keeping the columns
+    /// `save` derives or leaves out (category, record type, dates, case
+    /// number). Fails if the ID is already taken.
+    pub async fn insert_portable_document(&self, d: &PortableDocument) -> Result<(), DieselError> {
+        if let Some(shard) = self.shard_for_source(&d.source_id) {
+            return Box::pin(shard.insert_portable_document(d)).await;
+        }
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(documents::table)
+                .values((
+                    documents::id.eq(&d.id),
+                    documents::source_id.eq(&d.source_id),
+                    documents::title.eq(&d.title),
+                    documents::source_url.eq(&d.source_url),
+                    documents::extracted_text.eq(&d.extracted_text),
+                    documents::status.eq(&d.status),
+                    documents::metadata.eq(&d.metadata),
+                    documents::created_at.eq(&d.created_at),
+                    documents::updated_at.eq(&d.updated_at),
+                    documents::synopsis.eq(&d.synopsis),
+                    documents::tags.eq(&d.tags),
+                    documents::estimated_date.eq(&d.estimated_date),
+                    documents::date_confidence.eq(&d.date_confidence),
+                    documents::date_source.eq(&d.date_source),
+                    documents::manual_date.eq(&d.manual_date),
+                    documents::discovery_method.eq(&d.discovery_method),
+                    documents::category_id.eq(&d.category_id),
+                    documents::record_type.eq(&d.record_type),
+                    documents::record_type_confidence.eq(&d.record_type_confidence),
+                    documents::document_date.eq(&d.document_date),
+                    documents::originating_agency.eq(&d.originating_agency),
+                    documents::originating_office.eq(&d.originating_office),
+                    documents::case_number.eq(&d.case_number),
+                    documents::classification_markings.eq(&d.classification_markings),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Get the virtual files of every version of multiple documents.
+    pub async fn get_virtual_files_batch(
+        &self,
+        document_ids: &[String],
+    ) -> Result<Vec<VirtualFile>, DieselError> {
+        if document_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let records: Vec<VirtualFileRecord> = with_conn!(self.pool, conn, {
+            virtual_files::table
+                .filter(virtual_files::document_id.eq_any(document_ids))
+                .order((virtual_files::document_id, virtual_files::archive_path))
+                .load(&mut conn)
+                .await
+        })?;
+        records
+            .into_iter()
+            .map(Self::virtual_file_record_to_model)
+            .collect()
+    }
+}
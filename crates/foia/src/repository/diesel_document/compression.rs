@@ -0,0 +1,239 @@
+//! Page text compression at rest.
+//!
+//! `pdf_text` and `ocr_text` are most of an archive's database size, and
+//! search never reads them once a page has a `final_text`. Such pages store
+//! both compressed with zstd, base64-encoded behind a marker so the columns
+//! stay `TEXT` on SQLite and PostgreSQL. The repository decompresses them
+//! on read; `final_text` always stays plain for search.
+
+use base64::Engine;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use tracing::warn;
+
+use super::DieselDocumentRepository;
+use crate::repository::pool::DieselError;
+use crate::schema::document_pages;
+use crate::{with_conn, with_write_conn};
+
+/// Prefix marking a compressed value. Starts with a control character no
+/// extracted or OCR text begins with.
+const MARKER: &str = "\u{1}zstd:";
+
+/// Shorter text isn't worth compressing.
+const MIN_COMPRESS_LEN: usize = 512;
+
+/// zstd level: most of the gain of higher levels at a fraction of the cost.
+const LEVEL: i32 = 3;
+
+/// Whether `text` is stored compressed.
+pub fn is_compressed(text: &str) -> bool {
+    text.starts_with(MARKER)
+}
+
+/// `text` as stored: compressed when that makes it smaller.
+pub fn compress_page_text(text: &str) -> String {
+    if text.len() < MIN_COMPRESS_LEN || is_compressed(text) {
+        return text.to_string();
+    }
+    let compressed = match zstd::encode_all(text.as_bytes(), LEVEL) {
+        Ok(bytes) => bytes,
+        Err(_) => return text.to_string(),
+    };
+    let stored = format!(
+        "{}{}",
+        MARKER,
+        base64::engine::general_purpose::STANDARD.encode(compressed)
+    );
+    if stored.len() < text.len() {
+        stored
+    } else {
+        text.to_string()
+    }
+}
+
+/// Stored text as written: decompressed if it was compressed. A value that
+/// fails to decompress is returned as stored.
+pub fn decompress_page_text(stored: String) -> String {
+    let Some(encoded) = stored.strip_prefix(MARKER) else {
+        return stored;
+    };
+    let text = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|bytes| zstd::decode_all(bytes.as_slice()).ok())
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+    match text {
+        Some(text) => text,
+        None => {
+            warn!("Failed to decompress stored page text");
+            stored
+        }
+    }
+}
+
+/// What a page text compression pass did, or would do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageTextCompression {
+    /// Pages with a `final_text` that were checked.
+    pub pages: u64,
+    /// Pages whose text was (or would be) compressed.
+    pub compressed: u64,
+    /// Bytes of `pdf_text` and `ocr_text` in those pages before.
+    pub bytes_before: u64,
+    /// Bytes of `pdf_text` and `ocr_text` in those pages after.
+    pub bytes_after: u64,
+}
+
+impl DieselDocumentRepository {
+    /// Compress the `pdf_text` and `ocr_text` of pages stored before
+    /// compression, `batch_size` pages at a time. With `dry_run`, only
+    /// measure what compression would save.
+    pub async fn compress_page_text(
+        &self,
+        batch_size: usize,
+        dry_run: bool,
+    ) -> Result<PageTextCompression, DieselError> {
+        type Row = (i32, Option<String>, Option<String>);
+
+        let mut result = PageTextCompression::default();
+        let mut cursor: i32 = 0;
+
+        loop {
+            let batch: Vec<Row> = with_conn!(self.pool, conn, {
+                document_pages::table
+                    .filter(document_pages::final_text.is_not_null())
+                    .filter(document_pages::id.gt(cursor))
+                    .order(document_pages::id.asc())
+                    .limit(batch_size.max(1) as i64)
+                    .select((
+                        document_pages::id,
+                        document_pages::pdf_text,
+                        document_pages::ocr_text,
+                    ))
+                    .load(&mut conn)
+                    .await
+            })?;
+            let Some(&(last_id, _, _)) = batch.last() else {
+                break;
+            };
+            cursor = last_id;
+
+            let mut updates: Vec<Row> = Vec::new();
+            for (id, pdf_text, ocr_text) in batch {
+                result.pages += 1;
+                let pdf_stored = pdf_text.as_deref().map(compress_page_text);
+                let ocr_stored = ocr_text.as_deref().map(compress_page_text);
+                if pdf_stored == pdf_text && ocr_stored == ocr_text {
+                    continue;
+                }
+                let len = |text: &Option<String>| text.as_ref().map_or(0, |t| t.len() as u64);
+                result.compressed += 1;
+                result.bytes_before += len(&pdf_text) + len(&ocr_text);
+                result.bytes_after += len(&pdf_stored) + len(&ocr_stored);
+                updates.push((id, pdf_stored, ocr_stored));
+            }
+
+            if dry_run || updates.is_empty() {
+                continue;
+            }
+            // Pages are read across shards; each update lands where its row is
+            for repo in std::iter::once(self).chain(self.shard_repos()) {
+                with_write_conn!(repo.pool, conn, {
+                    for (id, pdf_text, ocr_text) in &updates {
+                        diesel::update(document_pages::table.find(*id))
+                            .set((
+                                document_pages::pdf_text.eq(pdf_text),
+                                document_pages::ocr_text.eq(ocr_text),
+                            ))
+                            .execute(&mut conn)
+                            .await?;
+                    }
+                    Ok::<_, DieselError>(())
+                })?;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentPage, DocumentVersion};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_compress_page_text_roundtrip() {
+        let short = "Page 1 of 3".to_string();
+        assert_eq!(compress_page_text(&short), short);
+
+        let long = "FEDERAL BUREAU OF INVESTIGATION. Subject reported to field office. ".repeat(40);
+        let stored = compress_page_text(&long);
+        assert!(is_compressed(&stored));
+        assert!(stored.len() < long.len() / 2);
+        assert_eq!(compress_page_text(&stored), stored);
+        assert_eq!(decompress_page_text(stored), long);
+        assert_eq!(decompress_page_text(short.clone()), short);
+
+        let damaged = format!("{}not base64!", MARKER);
+        assert_eq!(decompress_page_text(damaged.clone()), damaged);
+    }
+
+    #[tokio::test]
+    async fn test_compress_stored_pages() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let repo = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .documents();
+
+        let version = DocumentVersion::new(b"memo", "application/pdf".to_string(), None);
+        let doc = Document::new(
+            "memo".to_string(),
+            "fbi".to_string(),
+            "Memo".to_string(),
+            "https://vault.fbi.gov/memo.pdf".to_string(),
+            version,
+            serde_json::json!({}),
+        );
+        repo.save_with_versions(&doc).await.unwrap();
+        let version_id = repo.get_current_version_id("memo").await.unwrap().unwrap();
+        let text = "The informant met the subject at the usual place. ".repeat(40);
+
+        // A page stored plain, as before compression.
+        let mut page = DocumentPage::new("memo".to_string(), version_id, 1);
+        page.ocr_text = Some(text.clone());
+        repo.save_page(&page).await.unwrap();
+        rusqlite::Connection::open(&db_path)
+            .unwrap()
+            .execute("UPDATE document_pages SET final_text = ocr_text", [])
+            .unwrap();
+
+        let planned = repo.compress_page_text(10, true).await.unwrap();
+        assert_eq!(planned.pages, 1);
+        assert_eq!(planned.compressed, 1);
+        assert!(planned.bytes_after < planned.bytes_before);
+
+        let done = repo.compress_page_text(10, false).await.unwrap();
+        assert_eq!(done, planned);
+        assert_eq!(
+            repo.compress_page_text(10, false).await.unwrap().compressed,
+            0
+        );
+
+        let page = repo
+            .get_page("memo", version_id as i32, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(page.ocr_text.as_deref(), Some(text.as_str()));
+        assert_eq!(page.final_text.as_deref(), Some(text.as_str()));
+    }
+}
@@ -0,0 +1,605 @@
+//! Curator edits that restructure the document set.
+//!
+//! Merging folds documents that turned out to be the same record, acquired
+//! under different URLs, into one document with a combined version history.
+//! Every merge, split and retitle is written to `curation_log`; merges and
+//! retitles write their entry in the same transaction as the change.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::ids::MOVE_ACCESS_COUNTS;
+use super::DieselDocumentRepository;
+use crate::models::Document;
+use crate::repository::models::{CurationLogRecord, NewCurationLogEntry};
+use crate::repository::parse_datetime;
+use crate::repository::pool::DieselError;
+use crate::schema::{
+    crawl_urls, curation_log, derived_artifacts, document_access, document_analysis_results,
+    document_entities, document_exemptions, document_flags, document_id_redirects,
+    document_locations, document_pages, document_pdf_metadata, document_relations,
+    document_versions, documents, foia_request_documents, saved_view_alerts, virtual_files,
+};
+use crate::{with_conn, with_write_conn};
+
+/// `curation_log.action` for a merge.
+pub const MERGE_ACTION: &str = "merge";
+
+/// `curation_log.action` for a split.
+pub const SPLIT_ACTION: &str = "split";
+
+/// `curation_log.action` for a retitle.
+pub const RETITLE_ACTION: &str = "retitle";
+
+/// One curator edit.
+#[derive(Debug, Clone)]
+pub struct CurationLogEntry {
+    pub id: i32,
+    pub action: String,
+    /// Merge target or split original.
+    pub document_id: String,
+    /// Documents merged away, or the children of a split.
+    pub related_ids: Vec<String>,
+    pub details: serde_json::Value,
+    pub actor: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<CurationLogRecord> for CurationLogEntry {
+    fn from(record: CurationLogRecord) -> Self {
+        Self {
+            id: record.id,
+            action: record.action,
+            document_id: record.document_id,
+            related_ids: serde_json::from_str(&record.related_ids).unwrap_or_default(),
+            details: serde_json::from_str(&record.details).unwrap_or_default(),
+            actor: record.actor,
+            created_at: parse_datetime(&record.created_at),
+        }
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, DieselError> {
+    serde_json::to_string(value).map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))
+}
+
+impl DieselDocumentRepository {
+    /// Merge `sources` into `target` and delete them.
+    ///
+    /// Versions, pages, OCR and analysis results, artifacts, virtual files,
+    /// crawl URLs and FOIA request links move to the target. Tags are
+    /// combined and the merged ids are kept in the target's `merged_from`
+    /// metadata and redirect to the target. Entities, map positions and relations of the merged
+    /// documents are dropped rather than duplicated; the target's stay.
+    pub async fn merge_documents(
+        &self,
+        target: &Document,
+        sources: &[Document],
+        actor: Option<&str>,
+    ) -> Result<(), DieselError> {
+        let source_ids: Vec<String> = sources.iter().map(|doc| doc.id.clone()).collect();
+        if source_ids.is_empty() {
+            return Ok(());
+        }
+
+        // A merge is one transaction, so every document must be in one database
+        let shard = self.shard_for_source(&target.source_id);
+        if sources.iter().any(|doc| {
+            !std::ptr::eq(
+                self.shard_for_source(&doc.source_id).unwrap_or(self),
+                shard.unwrap_or(self),
+            )
+        }) {
+            return Err(diesel::result::Error::QueryBuilderError(
+                "Can't merge documents stored in different shards".into(),
+            ));
+        }
+        if let Some(shard) = shard {
+            Box::pin(shard.merge_documents(target, sources, actor)).await?;
+            // Flags and access counts live in the main database; keep them
+            // on the merged document
+            return with_write_conn!(self.pool, conn, {
+                diesel::update(
+                    document_flags::table.filter(document_flags::document_id.eq_any(&source_ids)),
+                )
+                .set(document_flags::document_id.eq(&target.id))
+                .execute(&mut conn)
+                .await?;
+                for id in &source_ids {
+                    diesel::sql_query(MOVE_ACCESS_COUNTS)
+                        .bind::<diesel::sql_types::Text, _>(&target.id)
+                        .bind::<diesel::sql_types::Text, _>(id)
+                        .execute(&mut conn)
+                        .await?;
+                }
+                diesel::delete(
+                    document_access::table.filter(document_access::document_id.eq_any(&source_ids)),
+                )
+                .execute(&mut conn)
+                .await?;
+                Ok(())
+            });
+        }
+
+        // Versions without a stored path derive it from their document's URL
+        // and title, which change with the merge; pin them first
+        let pinned: Vec<(i32, String)> = sources
+            .iter()
+            .flat_map(|doc| {
+                doc.versions
+                    .iter()
+                    .filter(|v| v.file_path.is_none())
+                    .map(|v| {
+                        let path = v.compute_storage_path(&doc.source_url, &doc.title);
+                        (v.id as i32, path.to_string_lossy().into_owned())
+                    })
+            })
+            .collect();
+
+        let mut tags = target.tags.clone();
+        for tag in sources.iter().flat_map(|doc| &doc.tags) {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        let mut metadata = target.metadata.clone();
+        if !metadata.is_object() {
+            metadata = serde_json::json!({});
+        }
+        let mut merged_from: Vec<serde_json::Value> = metadata
+            .get("merged_from")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        merged_from.extend(source_ids.iter().map(|id| id.clone().into()));
+        metadata["merged_from"] = merged_from.into();
+
+        let tags = to_json(&tags)?;
+        let metadata = to_json(&metadata)?;
+        let related_ids = to_json(&source_ids)?;
+        let details = to_json(&serde_json::json!({
+            "titles": sources.iter().map(|doc| &doc.title).collect::<Vec<_>>(),
+            "urls": sources.iter().map(|doc| &doc.source_url).collect::<Vec<_>>(),
+        }))?;
+        let now = Utc::now().to_rfc3339();
+        let target_id = target.id.as_str();
+        let ids = &source_ids;
+
+        use diesel_async::AsyncConnection;
+        with_write_conn!(self.pool, conn, {
+            conn.transaction(|conn| {
+                Box::pin(async move {
+                    for (version_id, path) in &pinned {
+                        diesel::update(document_versions::table.find(version_id))
+                            .set(document_versions::file_path.eq(path))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    diesel::update(
+                        document_versions::table.filter(document_versions::document_id.eq_any(ids)),
+                    )
+                    .set(document_versions::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_pages::table.filter(document_pages::document_id.eq_any(ids)),
+                    )
+                    .set(document_pages::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_analysis_results::table
+                            .filter(document_analysis_results::document_id.eq_any(ids)),
+                    )
+                    .set(document_analysis_results::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        derived_artifacts::table.filter(derived_artifacts::document_id.eq_any(ids)),
+                    )
+                    .set(derived_artifacts::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        virtual_files::table.filter(virtual_files::document_id.eq_any(ids)),
+                    )
+                    .set(virtual_files::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(crawl_urls::table.filter(crawl_urls::document_id.eq_any(ids)))
+                        .set(crawl_urls::document_id.eq(target_id))
+                        .execute(conn)
+                        .await?;
+
+                    // A request may already list the target; link each once
+                    let linked: Vec<(String, String)> = foia_request_documents::table
+                        .filter(
+                            foia_request_documents::document_id
+                                .eq_any(ids)
+                                .or(foia_request_documents::document_id.eq(target_id)),
+                        )
+                        .select((
+                            foia_request_documents::request_id,
+                            foia_request_documents::document_id,
+                        ))
+                        .load(conn)
+                        .await?;
+                    let mut requests: Vec<&str> = linked
+                        .iter()
+                        .filter(|(_, doc)| doc != target_id)
+                        .map(|(request, _)| request.as_str())
+                        .filter(|request| {
+                            !linked
+                                .iter()
+                                .any(|(r, doc)| r == request && doc == target_id)
+                        })
+                        .collect();
+                    requests.sort_unstable();
+                    requests.dedup();
+                    diesel::delete(
+                        foia_request_documents::table
+                            .filter(foia_request_documents::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    for request in requests {
+                        diesel::insert_into(foia_request_documents::table)
+                            .values((
+                                foia_request_documents::request_id.eq(request),
+                                foia_request_documents::document_id.eq(target_id),
+                                foia_request_documents::linked_at.eq(&now),
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    diesel::delete(
+                        document_entities::table.filter(document_entities::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        document_exemptions::table
+                            .filter(document_exemptions::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        document_locations::table
+                            .filter(document_locations::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        document_relations::table.filter(
+                            document_relations::source_document_id
+                                .eq_any(ids)
+                                .or(document_relations::target_document_id.eq_any(ids)),
+                        ),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        saved_view_alerts::table.filter(saved_view_alerts::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_flags::table.filter(document_flags::document_id.eq_any(ids)),
+                    )
+                    .set(document_flags::document_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    for id in ids {
+                        diesel::sql_query(MOVE_ACCESS_COUNTS)
+                            .bind::<diesel::sql_types::Text, _>(target_id)
+                            .bind::<diesel::sql_types::Text, _>(id)
+                            .execute(conn)
+                            .await?;
+                    }
+                    diesel::delete(
+                        document_access::table.filter(document_access::document_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(documents::table.filter(documents::id.eq_any(ids)))
+                        .execute(conn)
+                        .await?;
+
+                    diesel::update(
+                        document_id_redirects::table
+                            .filter(document_id_redirects::new_id.eq_any(ids)),
+                    )
+                    .set(document_id_redirects::new_id.eq(target_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(
+                        document_id_redirects::table
+                            .filter(document_id_redirects::old_id.eq_any(ids)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    for id in ids {
+                        diesel::insert_into(document_id_redirects::table)
+                            .values((
+                                document_id_redirects::old_id.eq(id),
+                                document_id_redirects::new_id.eq(target_id),
+                                document_id_redirects::created_at.eq(&now),
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    diesel::update(documents::table.find(target_id))
+                        .set((
+                            documents::tags.eq(&tags),
+                            documents::metadata.eq(&metadata),
+                            documents::updated_at.eq(&now),
+                        ))
+                        .execute(conn)
+                        .await?;
+
+                    diesel::insert_into(curation_log::table)
+                        .values(&NewCurationLogEntry {
+                            action: MERGE_ACTION,
+                            document_id: target_id,
+                            related_ids: &related_ids,
+                            details: &details,
+                            actor,
+                            created_at: &now,
+                        })
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .await
+        })
+    }
+
+    /// Change a document's title.
+    ///
+    /// Versions stored without a `file_path` are found by a path derived
+    /// from the title, so their current paths are pinned first. The old
+    /// title and `details` go in the log entry.
+    pub async fn retitle_document(
+        &self,
+        doc: &Document,
+        title: &str,
+        details: &serde_json::Value,
+        actor: Option<&str>,
+    ) -> Result<(), DieselError> {
+        if let Some(shard) = self.shard_for_source(&doc.source_id) {
+            return Box::pin(shard.retitle_document(doc, title, details, actor)).await;
+        }
+        let pinned: Vec<(i32, String)> = doc
+            .versions
+            .iter()
+            .filter(|v| v.file_path.is_none())
+            .map(|v| {
+                let path = v.compute_storage_path(&doc.source_url, &doc.title);
+                (v.id as i32, path.to_string_lossy().into_owned())
+            })
+            .collect();
+
+        let mut details = details.clone();
+        if !details.is_object() {
+            details = serde_json::json!({});
+        }
+        details["from"] = doc.title.clone().into();
+        details["to"] = title.into();
+        let details = to_json(&details)?;
+        let now = Utc::now().to_rfc3339();
+        let document_id = doc.id.as_str();
+
+        use diesel_async::AsyncConnection;
+        with_write_conn!(self.pool, conn, {
+            conn.transaction(|conn| {
+                Box::pin(async move {
+                    for (version_id, path) in &pinned {
+                        diesel::update(document_versions::table.find(version_id))
+                            .set(document_versions::file_path.eq(path))
+                            .execute(conn)
+                            .await?;
+                    }
+                    diesel::update(documents::table.find(document_id))
+                        .set((documents::title.eq(title), documents::updated_at.eq(&now)))
+                        .execute(conn)
+                        .await?;
+                    diesel::insert_into(curation_log::table)
+                        .values(&NewCurationLogEntry {
+                            action: RETITLE_ACTION,
+                            document_id,
+                            related_ids: "[]",
+                            details: &details,
+                            actor,
+                            created_at: &now,
+                        })
+                        .execute(conn)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .await
+        })
+    }
+
+    /// Add an entry to the curation log.
+    pub async fn record_curation(
+        &self,
+        action: &str,
+        document_id: &str,
+        related_ids: &[String],
+        details: &serde_json::Value,
+        actor: Option<&str>,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.record_curation(
+            action,
+            document_id,
+            related_ids,
+            details,
+            actor
+        ));
+        let related_ids = to_json(&related_ids)?;
+        let details = to_json(details)?;
+        let now = Utc::now().to_rfc3339();
+
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(curation_log::table)
+                .values(&NewCurationLogEntry {
+                    action,
+                    document_id,
+                    related_ids: &related_ids,
+                    details: &details,
+                    actor,
+                    created_at: &now,
+                })
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Curation log entries, newest first. With a document id, only the
+    /// entries naming it as target, original, merged or child document.
+    pub async fn get_curation_log(
+        &self,
+        document_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<CurationLogEntry>, DieselError> {
+        let limit = limit as i64;
+        let records: Vec<CurationLogRecord> = with_conn!(self.pool, conn, {
+            let mut query = curation_log::table
+                .order(curation_log::id.desc())
+                .limit(limit)
+                .into_boxed();
+            if let Some(id) = document_id {
+                query = query.filter(
+                    curation_log::document_id
+                        .eq(id)
+                        .or(curation_log::related_ids.like(format!("%\"{}\"%", id))),
+                );
+            }
+            query.load::<CurationLogRecord>(&mut conn).await
+        })?;
+        Ok(records.into_iter().map(CurationLogEntry::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DocumentVersion;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn document(id: &str, content: &[u8], tags: &[&str]) -> Document {
+        let version = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        let mut doc = Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            format!("Memo {}", id),
+            format!("https://vault.fbi.gov/{}.pdf", id),
+            version,
+            serde_json::json!({}),
+        );
+        doc.tags = tags.iter().map(|t| t.to_string()).collect();
+        doc
+    }
+
+    #[tokio::test]
+    async fn test_merge_documents() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+
+        let target = document("a", b"%PDF-1.4 first", &["fbi"]);
+        let source = document("b", b"%PDF-1.4 second", &["fbi", "hoover"]);
+        for doc in [&target, &source] {
+            repo.save_with_versions(doc).await.unwrap();
+            repo.update_synopsis_and_tags(&doc.id, None, &doc.tags)
+                .await
+                .unwrap();
+        }
+        let target = repo.get("a").await.unwrap().unwrap();
+        let source = repo.get("b").await.unwrap().unwrap();
+
+        repo.merge_documents(&target, &[source], Some("editor"))
+            .await
+            .unwrap();
+
+        assert!(!repo.exists("b").await.unwrap());
+        let merged = repo.get("a").await.unwrap().unwrap();
+        assert_eq!(merged.versions.len(), 2);
+        assert_eq!(merged.tags, vec!["fbi", "hoover"]);
+        assert_eq!(merged.metadata["merged_from"], serde_json::json!(["b"]));
+        assert_eq!(
+            repo.resolve_redirect("b").await.unwrap().as_deref(),
+            Some("a")
+        );
+
+        // The merged-away id still finds the entry
+        let log = repo.get_curation_log(Some("b"), 10).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].action, MERGE_ACTION);
+        assert_eq!(log[0].document_id, "a");
+        assert_eq!(log[0].related_ids, vec!["b"]);
+        assert_eq!(log[0].actor.as_deref(), Some("editor"));
+        assert!(repo
+            .get_curation_log(Some("c"), 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retitle_document_keeps_file_path() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+
+        let doc = document("a", b"%PDF-1.4 first", &[]);
+        repo.save_with_versions(&doc).await.unwrap();
+        let doc = repo.get("a").await.unwrap().unwrap();
+        let dir = std::path::Path::new("/docs");
+        let before = doc.versions[0].resolve_path(dir, &doc.source_url, &doc.title);
+
+        repo.retitle_document(
+            &doc,
+            "Annual FOIA Report 2023",
+            &serde_json::json!({"source": "pdf_metadata"}),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let doc = repo.get("a").await.unwrap().unwrap();
+        assert_eq!(doc.title, "Annual FOIA Report 2023");
+        assert_eq!(
+            doc.versions[0].resolve_path(dir, &doc.source_url, &doc.title),
+            before
+        );
+
+        let log = repo.get_curation_log(Some("a"), 10).await.unwrap();
+        assert_eq!(log[0].action, RETITLE_ACTION);
+        assert_eq!(log[0].details["from"], "Memo a");
+        assert_eq!(log[0].details["source"], "pdf_metadata");
+    }
+}
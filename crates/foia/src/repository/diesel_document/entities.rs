@@ -5,10 +5,11 @@ use diesel_async::RunQueryDsl;
 
 #[allow(unused_imports)]
 use super::{CountRow, DieselDocumentRepository, DocIdRow};
+use crate::repository::cursor::{Page, PageCursor};
 use crate::repository::models::{DocumentEntityRecord, NewDocumentEntity};
 use crate::repository::pool::DieselError;
 use crate::schema::document_entities;
-use crate::{with_conn, with_conn_split};
+use crate::{with_conn, with_conn_split, with_write_conn, with_write_conn_split};
 
 /// Filter for entity-based document search.
 #[derive(Debug, Clone)]
@@ -46,8 +47,19 @@ impl DieselDocumentRepository {
         if entities.is_empty() {
             return Ok(());
         }
+        if !self.shards.is_empty() {
+            // Each document's entities go to the database holding it
+            for run in entities.chunk_by(|a, b| a.document_id == b.document_id) {
+                let repo = match self.shard_for_document(run[0].document_id).await? {
+                    Some(shard) => shard.clone(),
+                    None => self.without_shards(),
+                };
+                Box::pin(repo.save_document_entities(run)).await?;
+            }
+            return Ok(());
+        }
 
-        with_conn_split!(self.pool,
+        with_write_conn_split!(self.pool,
             sqlite: conn => {
                 for entity in entities {
                     diesel::insert_or_ignore_into(document_entities::table)
@@ -74,7 +86,8 @@ impl DieselDocumentRepository {
 
     /// Delete all entities for a document (before re-extraction).
     pub async fn delete_document_entities(&self, doc_id: &str) -> Result<usize, DieselError> {
-        with_conn!(self.pool, conn, {
+        route_to_shard!(self, doc_id, |shard| shard.delete_document_entities(doc_id));
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 document_entities::table.filter(document_entities::document_id.eq(doc_id)),
             )
@@ -124,16 +137,35 @@ impl DieselDocumentRepository {
         Ok(map)
     }
 
-    /// Search for document IDs matching ALL entity filters.
+    /// Search for document IDs matching ALL entity filters, one keyset page
+    /// at a time ordered by document ID.
     pub async fn search_by_entities(
         &self,
         filters: &[EntityFilter],
         source_id: Option<&str>,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<String>, DieselError> {
+    ) -> Result<Page<String>, DieselError> {
         let all_ids = self.entity_filter_intersection(filters, source_id).await?;
-        Ok(all_ids.into_iter().skip(offset).take(limit).collect())
+        let cursor_id = cursor.and_then(|c| c.key(0));
+
+        // The intersection is already sorted, so seek past the cursor in memory
+        let rows: Vec<String> = if cursor.is_some_and(|c| c.before) {
+            all_ids
+                .into_iter()
+                .rev()
+                .filter(|id| cursor_id.is_none_or(|k| id.as_str() < k))
+                .take(limit + 1)
+                .collect()
+        } else {
+            all_ids
+                .into_iter()
+                .filter(|id| cursor_id.is_none_or(|k| id.as_str() > k))
+                .take(limit + 1)
+                .collect()
+        };
+
+        Ok(Page::from_rows(rows, limit, cursor, |id| vec![id.clone()]))
     }
 
     /// Count documents matching ALL entity filters.
@@ -218,7 +250,8 @@ impl DieselDocumentRepository {
         })
     }
 
-    /// Search for documents near a lat/lng point within a radius (km).
+    /// Search for documents near a lat/lng point within a radius (km), one
+    /// keyset page at a time ordered by document ID.
     /// Only works on PostgreSQL with PostGIS. Returns an error on SQLite.
     #[allow(unused_variables)]
     pub async fn search_near_location(
@@ -226,10 +259,13 @@ impl DieselDocumentRepository {
         lat: f64,
         lon: f64,
         radius_km: f64,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<String>, DieselError> {
+    ) -> Result<Page<String>, DieselError> {
         let radius_meters = radius_km * 1000.0;
+        let (cmp, dir) = id_seek(cursor);
+        let cursor_id = cursor.and_then(|c| c.key(0));
+        let fetch = limit + 1;
 
         with_conn_split!(self.pool,
             sqlite: _conn => {
@@ -247,13 +283,19 @@ impl DieselDocumentRepository {
                         ST_MakePoint({}, {})::geography,
                         {}
                     )
-                    ORDER BY de.document_id
-                    LIMIT {} OFFSET {}"#,
-                    lon, lat, radius_meters, limit, offset
+                    AND ($1::text IS NULL OR de.document_id {cmp} $1)
+                    ORDER BY de.document_id {dir}
+                    LIMIT {fetch}"#,
+                    lon, lat, radius_meters
                 );
-                let rows: Vec<DocIdRow> =
-                    diesel_async::RunQueryDsl::load(diesel::sql_query(&query), &mut conn).await?;
-                Ok(rows.into_iter().map(|r| r.id).collect())
+                let rows: Vec<DocIdRow> = diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(&query)
+                        .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_id),
+                    &mut conn,
+                )
+                .await?;
+                let ids = rows.into_iter().map(|r| r.id).collect();
+                Ok(Page::from_rows(ids, limit, cursor, |id: &String| vec![id.clone()]))
             }
         )
     }
@@ -301,9 +343,13 @@ impl DieselDocumentRepository {
     pub async fn search_in_region(
         &self,
         region_name: &str,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<String>, DieselError> {
+    ) -> Result<Page<String>, DieselError> {
+        let (cmp, dir) = id_seek(cursor);
+        let cursor_id = cursor.and_then(|c| c.key(0));
+        let fetch = limit + 1;
+
         with_conn_split!(self.pool,
             sqlite: _conn => {
                 Err(diesel::result::Error::QueryBuilderError(
@@ -316,17 +362,19 @@ impl DieselDocumentRepository {
                     FROM document_entities de
                     JOIN regions r ON ST_Covers(r.geom, ST_MakePoint(de.longitude, de.latitude)::geography)
                     WHERE de.latitude IS NOT NULL AND lower(r.name) = lower($1)
-                    ORDER BY de.document_id
-                    LIMIT {} OFFSET {}"#,
-                    limit, offset
+                    AND ($2::text IS NULL OR de.document_id {cmp} $2)
+                    ORDER BY de.document_id {dir}
+                    LIMIT {fetch}"#
                 );
                 let rows: Vec<DocIdRow> = diesel_async::RunQueryDsl::load(
                     diesel::sql_query(&query)
-                        .bind::<diesel::sql_types::Text, _>(region_name),
+                        .bind::<diesel::sql_types::Text, _>(region_name)
+                        .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_id),
                     &mut conn,
                 )
                 .await?;
-                Ok(rows.into_iter().map(|r| r.id).collect())
+                let ids = rows.into_iter().map(|r| r.id).collect();
+                Ok(Page::from_rows(ids, limit, cursor, |id: &String| vec![id.clone()]))
             }
         )
     }
@@ -338,10 +386,13 @@ impl DieselDocumentRepository {
         &self,
         region_name: &str,
         radius_km: f64,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<String>, DieselError> {
+    ) -> Result<Page<String>, DieselError> {
         let radius_meters = radius_km * 1000.0;
+        let (cmp, dir) = id_seek(cursor);
+        let cursor_id = cursor.and_then(|c| c.key(0));
+        let fetch = limit + 1;
 
         with_conn_split!(self.pool,
             sqlite: _conn => {
@@ -355,17 +406,20 @@ impl DieselDocumentRepository {
                     FROM document_entities de
                     JOIN regions r ON ST_DWithin(r.geom, ST_MakePoint(de.longitude, de.latitude)::geography, {})
                     WHERE de.latitude IS NOT NULL AND lower(r.name) = lower($1)
-                    ORDER BY de.document_id
-                    LIMIT {} OFFSET {}"#,
-                    radius_meters, limit, offset
+                    AND ($2::text IS NULL OR de.document_id {cmp} $2)
+                    ORDER BY de.document_id {dir}
+                    LIMIT {fetch}"#,
+                    radius_meters
                 );
                 let rows: Vec<DocIdRow> = diesel_async::RunQueryDsl::load(
                     diesel::sql_query(&query)
-                        .bind::<diesel::sql_types::Text, _>(region_name),
+                        .bind::<diesel::sql_types::Text, _>(region_name)
+                        .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_id),
                     &mut conn,
                 )
                 .await?;
-                Ok(rows.into_iter().map(|r| r.id).collect())
+                let ids = rows.into_iter().map(|r| r.id).collect();
+                Ok(Page::from_rows(ids, limit, cursor, |id: &String| vec![id.clone()]))
             }
         )
     }
@@ -410,21 +464,61 @@ impl DieselDocumentRepository {
         })
     }
 
-    /// Get all entities with coordinates (for map views).
+    /// Get entities with coordinates (for map views), one keyset page at a
+    /// time ordered by entity text.
     pub async fn get_geocoded_entities(
         &self,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<DocumentEntityRecord>, DieselError> {
-        with_conn!(self.pool, conn, {
-            document_entities::table
+    ) -> Result<Page<DocumentEntityRecord>, DieselError> {
+        let backwards = cursor.is_some_and(|c| c.before);
+        let cursor_text = cursor.and_then(|c| c.key(0)).map(str::to_string);
+        let cursor_id: Option<i32> = cursor.and_then(|c| c.key(1)?.parse().ok());
+
+        let rows: Vec<DocumentEntityRecord> = with_conn!(self.pool, conn, {
+            let mut query = document_entities::table
                 .filter(document_entities::latitude.is_not_null())
-                .order(document_entities::entity_text.asc())
-                .limit(limit as i64)
-                .offset(offset as i64)
-                .load(&mut conn)
-                .await
-        })
+                .limit(limit as i64 + 1)
+                .into_boxed();
+
+            if let (Some(text), Some(id)) = (cursor_text.clone(), cursor_id) {
+                query = if backwards {
+                    query.filter(
+                        document_entities::entity_text.lt(text.clone()).or(
+                            document_entities::entity_text
+                                .eq(text)
+                                .and(document_entities::id.lt(id)),
+                        ),
+                    )
+                } else {
+                    query.filter(
+                        document_entities::entity_text.gt(text.clone()).or(
+                            document_entities::entity_text
+                                .eq(text)
+                                .and(document_entities::id.gt(id)),
+                        ),
+                    )
+                };
+            }
+
+            query = if backwards {
+                query.order((
+                    document_entities::entity_text.desc(),
+                    document_entities::id.desc(),
+                ))
+            } else {
+                query.order((
+                    document_entities::entity_text.asc(),
+                    document_entities::id.asc(),
+                ))
+            };
+
+            query.load(&mut conn).await
+        })?;
+
+        Ok(Page::from_rows(rows, limit, cursor, |e| {
+            vec![e.entity_text.clone(), e.id.to_string()]
+        }))
     }
 
     /// Count all entities with coordinates.
@@ -441,6 +535,16 @@ impl DieselDocumentRepository {
     }
 }
 
+/// Comparison and sort direction for seeking past a document-ID cursor;
+/// walking backwards flips both.
+fn id_seek(cursor: Option<&PageCursor>) -> (&'static str, &'static str) {
+    if cursor.is_some_and(|c| c.before) {
+        ("<", "DESC")
+    } else {
+        (">", "ASC")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,7 +554,7 @@ mod tests {
 
     async fn create_entity_table(repo: &DieselDocumentRepository) -> Result<(), DieselError> {
         use diesel_async::SimpleAsyncConnection;
-        with_conn!(repo.pool, conn, {
+        with_write_conn!(repo.pool, conn, {
             conn.batch_execute(
                 r#"CREATE TABLE IF NOT EXISTS document_entities (
                     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -600,10 +704,28 @@ mod tests {
             exact: true,
         }];
         let results = repo
-            .search_by_entities(&filters, None, 100, 0)
+            .search_by_entities(&filters, None, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(results.items.len(), 2);
+
+        // One per page, resuming from the cursor
+        let first = repo
+            .search_by_entities(&filters, None, None, 1)
+            .await
+            .unwrap();
+        assert_eq!(first.items, vec!["doc-search-1"]);
+        let second = repo
+            .search_by_entities(&filters, None, first.next_cursor.as_ref(), 1)
+            .await
+            .unwrap();
+        assert_eq!(second.items, vec!["doc-search-2"]);
+        assert!(second.next_cursor.is_none());
+        let back = repo
+            .search_by_entities(&filters, None, second.prev_cursor.as_ref(), 1)
             .await
             .unwrap();
-        assert_eq!(results.len(), 2);
+        assert_eq!(back.items, vec!["doc-search-1"]);
 
         // Search for CIA + John Smith - only doc-search-1 has both
         let filters = vec![
@@ -619,11 +741,10 @@ mod tests {
             },
         ];
         let results = repo
-            .search_by_entities(&filters, None, 100, 0)
+            .search_by_entities(&filters, None, None, 100)
             .await
             .unwrap();
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0], "doc-search-1");
+        assert_eq!(results.items, vec!["doc-search-1"]);
 
         // Count
         let count = repo.count_by_entities(&filters, None).await.unwrap();
@@ -700,7 +821,9 @@ mod tests {
         let (pool, _dir) = setup_test_db().await;
         let repo = DieselDocumentRepository::new(pool);
 
-        let result = repo.search_near_location(38.9, -77.0, 100.0, 10, 0).await;
+        let result = repo
+            .search_near_location(38.9, -77.0, 100.0, None, 10)
+            .await;
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("not supported"));
@@ -717,8 +840,8 @@ mod tests {
             text: "' OR '1'='1".to_string(),
             exact: false,
         }];
-        let result = repo.search_by_entities(&filters, None, 100, 0).await;
+        let result = repo.search_by_entities(&filters, None, None, 100).await;
         assert!(result.is_ok());
-        assert!(result.unwrap().is_empty());
+        assert!(result.unwrap().items.is_empty());
     }
 }
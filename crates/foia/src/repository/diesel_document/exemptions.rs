@@ -0,0 +1,402 @@
+//! Redaction placeholders and exemption counts.
+//!
+//! When a document is finalized, redactions in its page text are replaced
+//! by placeholders (see [`mark_redactions`]) and the exemption codes they
+//! cite are counted into `document_exemptions`, which backs the "exemptions
+//! cited" browse facet and corpus exemption statistics.
+
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::queries::TIMELINE_DATE_EXPR;
+use super::DieselDocumentRepository;
+use crate::models::{count_exemptions, is_exemption_code, mark_redactions, ExemptionStats};
+use crate::repository::models::DocumentExemptionRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_exemptions, document_pages};
+use crate::{with_conn, with_write_conn};
+
+/// One document's citations of one exemption, with what the exemption
+/// analytics group them by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExemptionCitationRow {
+    pub document_id: String,
+    pub code: String,
+    pub citations: u32,
+    /// Name of the source's agency, else the originating agency extracted
+    /// from the document.
+    pub agency: Option<String>,
+    /// The document's timeline date (see `get_timeline_buckets`).
+    pub date: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// SQL restricting `documents` to those citing any of `codes`.
+///
+/// Only normalized codes are formatted into the SQL; anything else is
+/// dropped, and if nothing is left no document matches.
+pub(super) fn exemption_filter(
+    codes: &[String],
+) -> diesel::expression::SqlLiteral<diesel::sql_types::Bool> {
+    let codes: Vec<String> = codes
+        .iter()
+        .filter(|c| is_exemption_code(c))
+        .map(|c| format!("'{}'", c))
+        .collect();
+    if codes.is_empty() {
+        return diesel::dsl::sql::<diesel::sql_types::Bool>("1 = 0");
+    }
+    diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+        "EXISTS (SELECT 1 FROM document_exemptions de WHERE de.document_id = documents.id \
+         AND de.code IN ({}))",
+        codes.join(", ")
+    ))
+}
+
+impl DieselDocumentRepository {
+    /// Replace redactions in a version's page text with placeholders and
+    /// recount the exemptions the document cites. Pages a curator corrected
+    /// keep their text but still count. Returns the new counts.
+    pub async fn mark_redactions(
+        &self,
+        document_id: &str,
+        version_id: i32,
+    ) -> Result<BTreeMap<String, u32>, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard
+            .mark_redactions(document_id, version_id));
+        let now = Utc::now().to_rfc3339();
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        for page in self.get_pages(document_id, version_id).await? {
+            let text = page
+                .final_text
+                .as_deref()
+                .or(page.ocr_text.as_deref())
+                .or(page.pdf_text.as_deref())
+                .unwrap_or_default();
+            for (code, n) in count_exemptions(text) {
+                *counts.entry(code).or_insert(0) += n;
+            }
+
+            let Some(final_text) = page.final_text.as_deref() else {
+                continue;
+            };
+            if page.is_corrected() {
+                continue;
+            }
+            let marked = mark_redactions(final_text);
+            if marked == final_text {
+                continue;
+            }
+            with_write_conn!(self.pool, conn, {
+                diesel::update(document_pages::table.filter(document_pages::id.eq(page.id as i32)))
+                    .set((
+                        document_pages::final_text.eq(&marked),
+                        document_pages::updated_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await
+            })?;
+        }
+
+        self.set_document_exemptions(document_id, &counts).await?;
+        Ok(counts)
+    }
+
+    /// [`Self::mark_redactions`] for a document's current version.
+    pub async fn mark_current_redactions(
+        &self,
+        document_id: &str,
+    ) -> Result<BTreeMap<String, u32>, DieselError> {
+        match self.get_current_version_id(document_id).await? {
+            Some(version_id) => self.mark_redactions(document_id, version_id as i32).await,
+            None => {
+                let counts = BTreeMap::new();
+                self.set_document_exemptions(document_id, &counts).await?;
+                Ok(counts)
+            }
+        }
+    }
+
+    /// Replace the exemption counts recorded for a document.
+    pub async fn set_document_exemptions(
+        &self,
+        document_id: &str,
+        counts: &BTreeMap<String, u32>,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, document_id, |shard| shard
+            .set_document_exemptions(document_id, counts));
+        let records: Vec<DocumentExemptionRecord> = counts
+            .iter()
+            .map(|(code, n)| DocumentExemptionRecord {
+                document_id: document_id.to_string(),
+                code: code.clone(),
+                citations: (*n).min(i32::MAX as u32) as i32,
+            })
+            .collect();
+        with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                document_exemptions::table.filter(document_exemptions::document_id.eq(document_id)),
+            )
+            .execute(&mut conn)
+            .await
+        })?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(document_exemptions::table)
+                .values(&records)
+                .execute(&mut conn)
+                .await
+        })?;
+        Ok(())
+    }
+
+    /// Exemption codes a document cites and how many redactions cite each.
+    pub async fn get_document_exemptions(
+        &self,
+        document_id: &str,
+    ) -> Result<BTreeMap<String, u32>, DieselError> {
+        let rows: Vec<(String, i32)> = with_conn!(self.pool, conn, {
+            document_exemptions::table
+                .filter(document_exemptions::document_id.eq(document_id))
+                .select((document_exemptions::code, document_exemptions::citations))
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(rows
+            .into_iter()
+            .map(|(code, n)| (code, n.max(0) as u32))
+            .collect())
+    }
+
+    /// How many documents and redactions cite each exemption, optionally
+    /// within one source, most cited first.
+    pub async fn get_exemption_stats(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<ExemptionStats>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct ExemptionRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            code: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            documents: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            citations: i64,
+        }
+
+        let rows: Vec<ExemptionRow> = with_conn!(self.pool, conn, {
+            match source_id {
+                Some(sid) => {
+                    diesel::sql_query(
+                        "SELECT de.code, COUNT(*) AS documents, \
+                                CAST(SUM(de.citations) AS BIGINT) AS citations \
+                         FROM document_exemptions de \
+                         JOIN documents d ON d.id = de.document_id \
+                         WHERE d.source_id = $1 \
+                         GROUP BY de.code \
+                         ORDER BY citations DESC, de.code",
+                    )
+                    .bind::<diesel::sql_types::Text, _>(sid)
+                    .load(&mut conn)
+                    .await
+                }
+                None => {
+                    diesel::sql_query(
+                        "SELECT code, COUNT(*) AS documents, \
+                                CAST(SUM(citations) AS BIGINT) AS citations \
+                         FROM document_exemptions \
+                         GROUP BY code \
+                         ORDER BY citations DESC, code",
+                    )
+                    .load(&mut conn)
+                    .await
+                }
+            }
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExemptionStats {
+                code: row.code,
+                documents: row.documents.max(0) as u64,
+                citations: row.citations.max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// Every document's exemption citations, optionally within one source,
+    /// ordered by document and code.
+    pub async fn exemption_citations(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<ExemptionCitationRow>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct CitationRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            document_id: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            code: String,
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            citations: i32,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            agency: Option<String>,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            date: Option<String>,
+            #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+            tags: Option<String>,
+        }
+
+        let query = format!(
+            "SELECT de.document_id, de.code, de.citations, \
+                    COALESCE(a.name, documents.originating_agency) AS agency, \
+                    {} AS date, documents.tags \
+             FROM document_exemptions de \
+             JOIN documents ON documents.id = de.document_id \
+             LEFT JOIN sources s ON s.id = documents.source_id \
+             LEFT JOIN agencies a ON a.id = s.agency_id \
+             {} \
+             ORDER BY de.document_id, de.code",
+            TIMELINE_DATE_EXPR,
+            if source_id.is_some() {
+                "WHERE documents.source_id = $1"
+            } else {
+                ""
+            }
+        );
+        let rows: Vec<CitationRow> = with_conn!(self.pool, conn, {
+            match source_id {
+                Some(sid) => {
+                    diesel::sql_query(&query)
+                        .bind::<diesel::sql_types::Text, _>(sid)
+                        .load(&mut conn)
+                        .await
+                }
+                None => diesel::sql_query(&query).load(&mut conn).await,
+            }
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ExemptionCitationRow {
+                document_id: row.document_id,
+                code: row.code,
+                citations: row.citations.max(0) as u32,
+                agency: row.agency.filter(|a| !a.trim().is_empty()),
+                date: row.date,
+                tags: row
+                    .tags
+                    .and_then(|t| serde_json::from_str(&t).ok())
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentPage, DocumentVersion};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::diesel_document::BrowseParams;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn document(id: &str, source_id: &str) -> Document {
+        let version = DocumentVersion::new(id.as_bytes(), "application/pdf".to_string(), None);
+        Document::new(
+            id.to_string(),
+            source_id.to_string(),
+            format!("Memo {}", id),
+            format!("https://example.gov/{}.pdf", id),
+            version,
+            serde_json::json!({}),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mark_redactions_and_facet() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+
+        let mut version_ids = Vec::new();
+        for (id, source, text) in [
+            (
+                "memo",
+                "fbi",
+                "Informant ████ (b)(6), (b)(7)(C) reported on ████ b6",
+            ),
+            ("letter", "fbi", "Withheld in full (b)(1)"),
+            ("plain", "cia", "Nothing withheld here."),
+        ] {
+            let doc = document(id, source);
+            repo.save_with_versions(&doc).await.unwrap();
+            let version_id = repo.get_current_version_id(id).await.unwrap().unwrap();
+            let mut page = DocumentPage::new(id.to_string(), version_id, 1);
+            page.ocr_text = Some(text.to_string());
+            page.compute_final_text();
+            repo.save_page(&page).await.unwrap();
+            version_ids.push((id, version_id as i32));
+        }
+        for (id, version_id) in &version_ids {
+            repo.mark_redactions(id, *version_id).await.unwrap();
+        }
+
+        let page = repo
+            .get_page("memo", version_ids[0].1, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            page.final_text.as_deref(),
+            Some("Informant [REDACTED (b)(6), (b)(7)(C)] reported on [REDACTED (b)(6)]")
+        );
+        let memo = repo.get_document_exemptions("memo").await.unwrap();
+        assert_eq!(memo.get("(b)(6)"), Some(&2));
+        assert_eq!(memo.get("(b)(7)(C)"), Some(&1));
+
+        // Marking again changes nothing
+        let again = repo
+            .mark_redactions("memo", version_ids[0].1)
+            .await
+            .unwrap();
+        assert_eq!(again, memo);
+
+        let stats = repo.get_exemption_stats(None).await.unwrap();
+        assert_eq!(stats[0].code, "(b)(6)");
+        assert_eq!((stats[0].documents, stats[0].citations), (1, 2));
+        assert_eq!(stats.len(), 3);
+        assert!(repo
+            .get_exemption_stats(Some("cia"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let codes = vec!["(b)(1)".to_string(), "(b)(7)(C)".to_string()];
+        let cited = BrowseParams {
+            exemptions: &codes,
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&cited).await.unwrap(), 2);
+        let bogus = vec!["x' OR '1'='1".to_string()];
+        let bogus = BrowseParams {
+            exemptions: &bogus,
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&bogus).await.unwrap(), 0);
+    }
+}
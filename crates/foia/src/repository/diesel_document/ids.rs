@@ -0,0 +1,304 @@
+//! Stable document IDs and redirects from the IDs they replace.
+//!
+//! Documents created before stable IDs have random UUIDs. Moving one to its
+//! stable ID rewrites every row that refers to it and leaves a redirect, so
+//! links and API calls using the old ID keep resolving.
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::Document;
+use crate::repository::models::DocumentRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{
+    crawl_urls, curation_log, derived_artifacts, document_access, document_analysis_results,
+    document_entities, document_exemptions, document_flags, document_id_redirects,
+    document_locations, document_pages, document_pdf_metadata, document_relations,
+    document_versions, documents, foia_request_documents, saved_view_alerts, virtual_files,
+};
+use crate::{with_conn, with_write_conn};
+
+/// Add the access counts of document `$2` to those of `$1`, summing rows
+/// both have for the same day, kind and referrer.
+pub(super) const MOVE_ACCESS_COUNTS: &str = r#"INSERT INTO document_access (document_id, day, kind, referrer, count)
+   SELECT $1, day, kind, referrer, count FROM document_access WHERE document_id = $2
+   ON CONFLICT (document_id, day, kind, referrer) DO UPDATE
+   SET count = document_access.count + excluded.count"#;
+
+/// A document's ID and what its stable ID derives from.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct DocumentIdentity {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub id: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub source_id: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub source_url: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub created_at: String,
+}
+
+impl DocumentIdentity {
+    /// The ID this document should have.
+    pub fn stable_id(&self) -> String {
+        Document::stable_id(&self.source_id, &self.source_url)
+    }
+}
+
+impl DieselDocumentRepository {
+    /// Find the document for `url` in a source: by its stable ID, or by
+    /// URL for documents not yet moved to one.
+    pub async fn find_by_source_url(
+        &self,
+        source_id: &str,
+        url: &str,
+    ) -> Result<Option<Document>, DieselError> {
+        if let Some(doc) = self.get(&Document::stable_id(source_id, url)).await? {
+            return Ok(Some(doc));
+        }
+        Ok(self.get_by_url(url).await?.into_iter().next())
+    }
+
+    /// The ID a replaced document ID now redirects to.
+    pub async fn resolve_redirect(&self, old_id: &str) -> Result<Option<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            document_id_redirects::table
+                .find(old_id)
+                .select(document_id_redirects::new_id)
+                .first::<String>(&mut conn)
+                .await
+                .optional()
+        })
+    }
+
+    /// Every document's identity, oldest first.
+    pub async fn document_identities(&self) -> Result<Vec<DocumentIdentity>, DieselError> {
+        with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                "SELECT id, source_id, source_url, created_at FROM documents \
+                 ORDER BY created_at, id",
+            )
+            .load::<DocumentIdentity>(&mut conn)
+            .await
+        })
+    }
+
+    /// Move a document to `new_id`, along with everything referring to it,
+    /// and redirect the old ID. Returns false if there is no such document;
+    /// fails if `new_id` is taken.
+    pub async fn rename_document(&self, old_id: &str, new_id: &str) -> Result<bool, DieselError> {
+        if let Some(shard) = self.shard_for_document(old_id).await? {
+            if !Box::pin(shard.rename_document(old_id, new_id)).await? {
+                return Ok(false);
+            }
+            self.move_main_links(old_id, new_id).await?;
+            return Ok(true);
+        }
+        let now = Utc::now().to_rfc3339();
+
+        use diesel_async::AsyncConnection;
+        with_write_conn!(self.pool, conn, {
+            conn.transaction(|conn| {
+                Box::pin(async move {
+                    let Some(mut record) = documents::table
+                        .find(old_id)
+                        .select(DocumentRecord::as_select())
+                        .first::<DocumentRecord>(conn)
+                        .await
+                        .optional()?
+                    else {
+                        return Ok(false);
+                    };
+                    record.id = new_id.to_string();
+                    diesel::insert_into(documents::table)
+                        .values(&record)
+                        .execute(conn)
+                        .await?;
+
+                    diesel::update(
+                        document_versions::table.filter(document_versions::document_id.eq(old_id)),
+                    )
+                    .set(document_versions::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_pages::table.filter(document_pages::document_id.eq(old_id)),
+                    )
+                    .set(document_pages::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_analysis_results::table
+                            .filter(document_analysis_results::document_id.eq(old_id)),
+                    )
+                    .set(document_analysis_results::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        derived_artifacts::table.filter(derived_artifacts::document_id.eq(old_id)),
+                    )
+                    .set(derived_artifacts::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        virtual_files::table.filter(virtual_files::document_id.eq(old_id)),
+                    )
+                    .set(virtual_files::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(crawl_urls::table.filter(crawl_urls::document_id.eq(old_id)))
+                        .set(crawl_urls::document_id.eq(new_id))
+                        .execute(conn)
+                        .await?;
+                    diesel::update(
+                        foia_request_documents::table
+                            .filter(foia_request_documents::document_id.eq(old_id)),
+                    )
+                    .set(foia_request_documents::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_entities::table.filter(document_entities::document_id.eq(old_id)),
+                    )
+                    .set(document_entities::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_exemptions::table
+                            .filter(document_exemptions::document_id.eq(old_id)),
+                    )
+                    .set(document_exemptions::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::document_id.eq(old_id)),
+                    )
+                    .set(document_pdf_metadata::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_locations::table
+                            .filter(document_locations::document_id.eq(old_id)),
+                    )
+                    .set(document_locations::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_relations::table
+                            .filter(document_relations::source_document_id.eq(old_id)),
+                    )
+                    .set(document_relations::source_document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_relations::table
+                            .filter(document_relations::target_document_id.eq(old_id)),
+                    )
+                    .set(document_relations::target_document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        saved_view_alerts::table.filter(saved_view_alerts::document_id.eq(old_id)),
+                    )
+                    .set(saved_view_alerts::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_flags::table.filter(document_flags::document_id.eq(old_id)),
+                    )
+                    .set(document_flags::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::sql_query(MOVE_ACCESS_COUNTS)
+                        .bind::<diesel::sql_types::Text, _>(new_id)
+                        .bind::<diesel::sql_types::Text, _>(old_id)
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(
+                        document_access::table.filter(document_access::document_id.eq(old_id)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        curation_log::table.filter(curation_log::document_id.eq(old_id)),
+                    )
+                    .set(curation_log::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+
+                    diesel::delete(documents::table.find(old_id))
+                        .execute(conn)
+                        .await?;
+
+                    // Earlier redirects to the old ID skip straight to the new one
+                    diesel::update(
+                        document_id_redirects::table
+                            .filter(document_id_redirects::new_id.eq(old_id)),
+                    )
+                    .set(document_id_redirects::new_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::delete(document_id_redirects::table.find(old_id))
+                        .execute(conn)
+                        .await?;
+                    diesel::insert_into(document_id_redirects::table)
+                        .values((
+                            document_id_redirects::old_id.eq(old_id),
+                            document_id_redirects::new_id.eq(new_id),
+                            document_id_redirects::created_at.eq(&now),
+                        ))
+                        .execute(conn)
+                        .await?;
+                    Ok(true)
+                })
+            })
+            .await
+        })
+    }
+
+    /// Point rows of the main database that refer to a document moved
+    /// within its shard at the new ID.
+    async fn move_main_links(&self, old_id: &str, new_id: &str) -> Result<(), DieselError> {
+        use diesel_async::AsyncConnection;
+        with_write_conn!(self.pool, conn, {
+            conn.transaction(|conn| {
+                Box::pin(async move {
+                    diesel::update(
+                        foia_request_documents::table
+                            .filter(foia_request_documents::document_id.eq(old_id)),
+                    )
+                    .set(foia_request_documents::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        saved_view_alerts::table.filter(saved_view_alerts::document_id.eq(old_id)),
+                    )
+                    .set(saved_view_alerts::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::update(
+                        document_flags::table.filter(document_flags::document_id.eq(old_id)),
+                    )
+                    .set(document_flags::document_id.eq(new_id))
+                    .execute(conn)
+                    .await?;
+                    diesel::sql_query(MOVE_ACCESS_COUNTS)
+                        .bind::<diesel::sql_types::Text, _>(new_id)
+                        .bind::<diesel::sql_types::Text, _>(old_id)
+                        .execute(conn)
+                        .await?;
+                    diesel::delete(
+                        document_access::table.filter(document_access::document_id.eq(old_id)),
+                    )
+                    .execute(conn)
+                    .await?;
+                    Ok(())
+                })
+            })
+            .await
+        })
+    }
+}
@@ -0,0 +1,468 @@
+//! Document map positions.
+//!
+//! A document is placed at the geocoded location entity its text mentions
+//! most (see [`primary_location`]). Positions live in `document_locations`
+//! and back the map view and the `bbox` browse filter.
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::exemptions::exemption_filter;
+use super::pdf_metadata::pdf_date_filter;
+use super::processing::processing_filter;
+use super::queries::{poor_ocr_filter, timeline_range_filter};
+use super::{BrowseParams, DieselDocumentRepository};
+use crate::models::{primary_location, BoundingBox, Document, DocumentLocation};
+use crate::repository::models::DocumentLocationRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_entities, document_locations, document_pdf_metadata, documents};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
+
+impl From<DocumentLocationRecord> for DocumentLocation {
+    fn from(record: DocumentLocationRecord) -> Self {
+        DocumentLocation {
+            document_id: record.document_id,
+            place: record.place,
+            latitude: record.latitude,
+            longitude: record.longitude,
+            mentions: record.mentions.max(0) as u32,
+        }
+    }
+}
+
+/// SQL restricting `documents` to those placed within a bounding box.
+///
+/// The bounds are floats, so formatting them into the SQL is safe.
+pub(super) fn bbox_filter(
+    bbox: &BoundingBox,
+) -> diesel::expression::SqlLiteral<diesel::sql_types::Bool> {
+    diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+        "EXISTS (SELECT 1 FROM document_locations dl WHERE dl.document_id = documents.id \
+         AND dl.latitude BETWEEN {} AND {} AND dl.longitude BETWEEN {} AND {})",
+        bbox.south, bbox.north, bbox.west, bbox.east
+    ))
+}
+
+impl DieselDocumentRepository {
+    /// Get a document's map position.
+    pub async fn get_document_location(
+        &self,
+        doc_id: &str,
+    ) -> Result<Option<DocumentLocation>, DieselError> {
+        with_conn!(self.pool, conn, {
+            document_locations::table
+                .find(doc_id)
+                .first::<DocumentLocationRecord>(&mut conn)
+                .await
+                .optional()
+                .map(|opt| opt.map(DocumentLocation::from))
+        })
+    }
+
+    /// Place a document at the geocoded location its current text mentions
+    /// most, or take it off the map if none of its locations are geocoded.
+    pub async fn refresh_document_location(
+        &self,
+        doc: &Document,
+    ) -> Result<Option<DocumentLocation>, DieselError> {
+        let places: Vec<(String, Option<f64>, Option<f64>)> = with_conn!(self.pool, conn, {
+            document_entities::table
+                .filter(document_entities::document_id.eq(&doc.id))
+                .filter(document_entities::entity_type.eq("location"))
+                .filter(document_entities::latitude.is_not_null())
+                .filter(document_entities::longitude.is_not_null())
+                .select((
+                    document_entities::entity_text,
+                    document_entities::latitude,
+                    document_entities::longitude,
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+        let places: Vec<(String, f64, f64)> = places
+            .into_iter()
+            .filter_map(|(place, lat, lon)| Some((place, lat?, lon?)))
+            .collect();
+
+        let text = match doc.current_version() {
+            Some(version) if !places.is_empty() => self
+                .get_combined_page_text(&doc.id, version.id as i32)
+                .await?
+                .unwrap_or_default(),
+            _ => String::new(),
+        };
+        let location = primary_location(&doc.id, &text, &places);
+        self.set_document_location(&doc.id, location.as_ref())
+            .await?;
+        Ok(location)
+    }
+
+    /// Set a document's map position, or remove it with `None`.
+    pub async fn set_document_location(
+        &self,
+        doc_id: &str,
+        location: Option<&DocumentLocation>,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, doc_id, |shard| shard
+            .set_document_location(doc_id, location));
+        let Some(location) = location else {
+            with_write_conn!(self.pool, conn, {
+                diesel::delete(document_locations::table.find(doc_id))
+                    .execute(&mut conn)
+                    .await
+            })?;
+            return Ok(());
+        };
+
+        let record = DocumentLocationRecord {
+            document_id: doc_id.to_string(),
+            place: location.place.clone(),
+            latitude: location.latitude,
+            longitude: location.longitude,
+            mentions: location.mentions.min(i32::MAX as u32) as i32,
+            updated_at: Utc::now().to_rfc3339(),
+        };
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::replace_into(document_locations::table)
+                    .values(&record)
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            },
+            postgres: conn => {
+                diesel::insert_into(document_locations::table)
+                    .values(&record)
+                    .on_conflict(document_locations::document_id)
+                    .do_update()
+                    .set((
+                        document_locations::place.eq(&record.place),
+                        document_locations::latitude.eq(record.latitude),
+                        document_locations::longitude.eq(record.longitude),
+                        document_locations::mentions.eq(record.mentions),
+                        document_locations::updated_at.eq(&record.updated_at),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            }
+        )
+    }
+
+    /// Map positions of the documents matching the browse filters.
+    /// Sorting and paging parameters are ignored.
+    pub async fn map_locations(
+        &self,
+        params: &BrowseParams<'_>,
+    ) -> Result<Vec<DocumentLocation>, DieselError> {
+        let source_id = params.source_id;
+        let status = params.status;
+        let categories = params.categories;
+        let tags = params.tags;
+        let record_types = params.record_types;
+        let poor_ocr = params.poor_ocr;
+        let exemptions = params.exemptions;
+        let pdf_producers = params.pdf_producers;
+        let pdf_years = params.pdf_years;
+        let pdf_postdated = params.pdf_postdated;
+        let processing = params.processing;
+        let date_range = timeline_range_filter(params.date_from, params.date_to);
+        let search_query = params.search_query;
+        let bbox = params.bbox;
+
+        let records: Vec<DocumentLocationRecord> = with_conn!(self.pool, conn, {
+            let mut query = document_locations::table
+                .inner_join(documents::table)
+                .select(DocumentLocationRecord::as_select())
+                .into_boxed();
+            if let Some(sid) = source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            if let Some(st) = status {
+                query = query.filter(documents::status.eq(st));
+            }
+            if !categories.is_empty() {
+                query = query.filter(documents::category_id.eq_any(categories));
+            }
+            if !record_types.is_empty() {
+                query = query.filter(documents::record_type.eq_any(record_types));
+            }
+            if poor_ocr {
+                query = query.filter(poor_ocr_filter());
+            }
+            if !exemptions.is_empty() {
+                query = query.filter(exemption_filter(exemptions));
+            }
+            if !pdf_producers.is_empty() {
+                query = query.filter(
+                    documents::id.eq_any(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::producer.eq_any(pdf_producers))
+                            .select(document_pdf_metadata::document_id),
+                    ),
+                );
+            }
+            if let Some(pdf_dates) = pdf_date_filter(pdf_years, pdf_postdated) {
+                query = query.filter(pdf_dates);
+            }
+            if let Some(state) = processing {
+                query = query.filter(processing_filter(state));
+            }
+            if let Some(range) = date_range {
+                query = query.filter(range);
+            }
+            for tag in tags {
+                let pattern = format!("%{}%", tag);
+                query = query.filter(documents::tags.like(pattern));
+            }
+            if let Some(q) = search_query {
+                if !q.is_empty() {
+                    let pattern = format!("%{}%", q);
+                    query = query.filter(
+                        documents::title
+                            .like(pattern.clone())
+                            .or(documents::synopsis.like(pattern)),
+                    );
+                }
+            }
+            if let Some(b) = bbox {
+                query = query.filter(
+                    document_locations::latitude
+                        .between(b.south, b.north)
+                        .and(document_locations::longitude.between(b.west, b.east)),
+                );
+            }
+            query.load(&mut conn).await
+        })?;
+        Ok(records.into_iter().map(DocumentLocation::from).collect())
+    }
+
+    /// Distinct location entities (normalized text) with no coordinates.
+    pub async fn ungeocoded_location_names(&self) -> Result<Vec<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            document_entities::table
+                .filter(document_entities::entity_type.eq("location"))
+                .filter(document_entities::latitude.is_null())
+                .select(document_entities::normalized_text)
+                .distinct()
+                .order(document_entities::normalized_text.asc())
+                .load(&mut conn)
+                .await
+        })
+    }
+
+    /// Set coordinates on every location entity with this normalized text.
+    /// Returns the number of entities updated.
+    pub async fn set_location_coordinates(
+        &self,
+        normalized_text: &str,
+        latitude: f64,
+        longitude: f64,
+    ) -> Result<usize, DieselError> {
+        let mut updated = 0;
+        for shard in self.shard_repos() {
+            updated +=
+                Box::pin(shard.set_location_coordinates(normalized_text, latitude, longitude))
+                    .await?;
+        }
+        let local = with_write_conn!(self.pool, conn, {
+            diesel::update(
+                document_entities::table
+                    .filter(document_entities::entity_type.eq("location"))
+                    .filter(document_entities::normalized_text.eq(normalized_text)),
+            )
+            .set((
+                document_entities::latitude.eq(Some(latitude)),
+                document_entities::longitude.eq(Some(longitude)),
+            ))
+            .execute(&mut conn)
+            .await
+        })?;
+        Ok(updated + local)
+    }
+
+    /// IDs of documents with at least one geocoded location entity,
+    /// optionally only in one source.
+    pub async fn get_geocoded_document_ids(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = document_entities::table
+                .inner_join(documents::table)
+                .filter(document_entities::entity_type.eq("location"))
+                .filter(document_entities::latitude.is_not_null())
+                .select(document_entities::document_id)
+                .distinct()
+                .order(document_entities::document_id.asc())
+                .into_boxed();
+            if let Some(sid) = source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            query.load(&mut conn).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DocumentVersion;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use crate::repository::models::NewDocumentEntity;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn document(id: &str, source_id: &str) -> Document {
+        let version = DocumentVersion::new(id.as_bytes(), "application/pdf".to_string(), None);
+        Document::new(
+            id.to_string(),
+            source_id.to_string(),
+            format!("Memo {}", id),
+            format!("https://example.gov/{}.pdf", id),
+            version,
+            serde_json::json!({}),
+        )
+    }
+
+    fn location(id: &str, place: &str, latitude: f64, longitude: f64) -> DocumentLocation {
+        DocumentLocation {
+            document_id: id.to_string(),
+            place: place.to_string(),
+            latitude,
+            longitude,
+            mentions: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_document_locations() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+        for (id, source) in [("dc", "fbi"), ("dallas", "fbi"), ("london", "cia")] {
+            repo.save_with_versions(&document(id, source))
+                .await
+                .unwrap();
+        }
+
+        repo.set_document_location("dc", Some(&location("dc", "Washington", 38.9, -77.04)))
+            .await
+            .unwrap();
+        repo.set_document_location("dallas", Some(&location("dallas", "Dallas", 32.78, -96.8)))
+            .await
+            .unwrap();
+        repo.set_document_location("london", Some(&location("london", "London", 51.5, -0.13)))
+            .await
+            .unwrap();
+
+        let all = repo.map_locations(&BrowseParams::default()).await.unwrap();
+        assert_eq!(all.len(), 3);
+
+        let fbi = BrowseParams {
+            source_id: Some("fbi"),
+            ..Default::default()
+        };
+        assert_eq!(repo.map_locations(&fbi).await.unwrap().len(), 2);
+
+        let east_coast = BoundingBox::parse("35,-80,45,-70").unwrap();
+        let near_dc = BrowseParams {
+            bbox: Some(east_coast),
+            ..Default::default()
+        };
+        let found = repo.map_locations(&near_dc).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].place, "Washington");
+        assert_eq!(repo.browse_count(&near_dc).await.unwrap(), 1);
+
+        // Moving a document replaces its position; None removes it
+        repo.set_document_location("dc", Some(&location("dc", "Dallas", 32.78, -96.8)))
+            .await
+            .unwrap();
+        assert!(repo.map_locations(&near_dc).await.unwrap().is_empty());
+        repo.set_document_location("dc", None).await.unwrap();
+        assert_eq!(repo.get_document_location("dc").await.unwrap(), None);
+        assert_eq!(
+            repo.get_document_location("london")
+                .await
+                .unwrap()
+                .map(|l| l.place),
+            Some("London".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_geocode_and_refresh() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+        let doc = document("memo", "fbi");
+        repo.save_with_versions(&doc).await.unwrap();
+
+        let now = Utc::now().to_rfc3339();
+        repo.save_document_entities(&[
+            NewDocumentEntity {
+                document_id: "memo",
+                entity_type: "location",
+                entity_text: "Dallas",
+                normalized_text: "dallas",
+                latitude: None,
+                longitude: None,
+                created_at: &now,
+            },
+            NewDocumentEntity {
+                document_id: "memo",
+                entity_type: "location",
+                entity_text: "Atlantis",
+                normalized_text: "atlantis",
+                latitude: None,
+                longitude: None,
+                created_at: &now,
+            },
+        ])
+        .await
+        .unwrap();
+
+        assert_eq!(
+            repo.ungeocoded_location_names().await.unwrap(),
+            vec!["atlantis", "dallas"]
+        );
+        assert_eq!(repo.refresh_document_location(&doc).await.unwrap(), None);
+
+        assert_eq!(
+            repo.set_location_coordinates("dallas", 32.78, -96.8)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            repo.ungeocoded_location_names().await.unwrap(),
+            vec!["atlantis"]
+        );
+        assert_eq!(
+            repo.get_geocoded_document_ids(Some("fbi")).await.unwrap(),
+            vec!["memo"]
+        );
+        assert!(repo
+            .get_geocoded_document_ids(Some("cia"))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let placed = repo.refresh_document_location(&doc).await.unwrap().unwrap();
+        assert_eq!(placed.place, "Dallas");
+        assert_eq!(
+            repo.get_document_location("memo").await.unwrap(),
+            Some(placed)
+        );
+    }
+}
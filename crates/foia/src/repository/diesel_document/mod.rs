@@ -6,29 +6,78 @@
 //! - `mod.rs` (this file): Core CRUD, virtual files, helpers
 //! - `versions.rs`: Document version operations
 //! - `pages.rs`: Document page and OCR operations
+//! - `compression.rs`: Page text compression at rest
+//! - `search_index.rs`: Page search index upkeep
 //! - `queries.rs`: Complex queries, browsing, statistics
 //! - `analysis.rs`: Analysis result operations
+//! - `artifacts.rs`: Derived artifact operations
+//! - `curation.rs`: Document merges and the curation log
+//! - `exemptions.rs`: Redaction placeholders and exemption counts
+//! - `ids.rs`: Stable document IDs and redirects from replaced IDs
+//! - `locations.rs`: Document map positions
+//! - `relations.rs`: Links between documents
+//! - `bundle.rs`: Full document rows for portable bundles
+//! - `workflow.rs`: Status transitions checked against the workflow
+//! - `shards.rs`: Sending writes to the shard holding a document
+
+/// Return `$call` from the enclosing method if `$document_id` lives in a
+/// shard, with `$shard` bound to that shard's repository.
+macro_rules! route_to_shard {
+    ($self:ident, $document_id:expr, |$shard:ident| $call:expr) => {
+        if let Some($shard) = $self.shard_for_document($document_id).await? {
+            return Box::pin($call).await;
+        }
+    };
+}
 
 mod analysis;
+mod artifacts;
+mod bundle;
+mod compression;
+mod curation;
 pub mod entities;
+mod exemptions;
+mod ids;
+mod locations;
 mod pages;
+mod pdf_metadata;
+mod processing;
 mod queries;
+mod relations;
+mod search_index;
+mod shards;
+mod tags;
 mod versions;
-
-pub use queries::BrowseParams;
+mod workflow;
+
+pub use analysis::{AnalysisResultEntry, AnalysisResultStatus};
+pub use compression::PageTextCompression;
+pub use curation::{CurationLogEntry, MERGE_ACTION, RETITLE_ACTION, SPLIT_ACTION};
+pub use exemptions::ExemptionCitationRow;
+pub use ids::DocumentIdentity;
+pub use pages::{OcrQualitySummary, OcrRun, OcrRunSettings, PagePassageRow};
+pub use pdf_metadata::PdfFacetCount;
+pub use processing::ProcessingState;
+pub use queries::{BrowseParams, DocumentSort};
+pub use search_index::SearchIndexCheck;
+pub use tags::TagNamespaceCount;
 
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
+use super::cursor::{Page, PageCursor};
 use super::models::{DocumentRecord, DocumentVersionRecord, VirtualFileRecord};
 use super::pool::{DbPool, DieselError};
 use super::{parse_datetime, parse_datetime_opt};
-use crate::models::{Document, DocumentStatus, DocumentVersion, VirtualFile, VirtualFileStatus};
-use crate::schema::{document_versions, documents, virtual_files};
-use crate::with_conn;
+use crate::models::{
+    Document, DocumentStatus, DocumentVersion, StatusWorkflow, VirtualFile, VirtualFileStatus,
+};
+use crate::schema::{derived_artifacts, document_versions, documents, virtual_files};
+use crate::{with_conn, with_write_conn};
 
 /// OCR result for a page.
 #[derive(Debug, Clone)]
@@ -42,16 +91,51 @@ pub struct OcrResult {
     pub created_at: DateTime<Utc>,
 }
 
+/// Default lease on an analysis claim before other workers may take it.
+pub const DEFAULT_CLAIM_LEASE_MINUTES: u32 = 90;
+
 /// Diesel-based document repository with compile-time query checking.
 #[derive(Clone)]
 pub struct DieselDocumentRepository {
     pub pool: DbPool,
+    claim_lease_minutes: u32,
+    workflow: StatusWorkflow,
+    shards: Arc<[shards::ShardDocuments]>,
 }
 
 impl DieselDocumentRepository {
     /// Create a new Diesel document repository.
     pub fn new(pool: DbPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            claim_lease_minutes: DEFAULT_CLAIM_LEASE_MINUTES,
+            workflow: StatusWorkflow::default(),
+            shards: Arc::new([]),
+        }
+    }
+
+    /// Set how long an analysis claim holds a document without renewal.
+    pub fn with_claim_lease(mut self, minutes: u32) -> Self {
+        self.claim_lease_minutes = minutes.max(1);
+        self.map_shards(|repo| repo.with_claim_lease(minutes));
+        self
+    }
+
+    /// How long an analysis claim holds a document without renewal.
+    pub fn claim_lease(&self) -> chrono::Duration {
+        chrono::Duration::minutes(i64::from(self.claim_lease_minutes))
+    }
+
+    /// Set which status transitions are allowed.
+    pub fn with_workflow(mut self, workflow: StatusWorkflow) -> Self {
+        self.map_shards(|repo| repo.with_workflow(workflow.clone()));
+        self.workflow = workflow;
+        self
+    }
+
+    /// Which status transitions are allowed.
+    pub fn workflow(&self) -> &StatusWorkflow {
+        &self.workflow
     }
 
     // ========================================================================
@@ -109,17 +193,57 @@ impl DieselDocumentRepository {
         }
     }
 
-    /// Get all documents for a source.
-    pub async fn get_by_source(&self, source_id: &str) -> Result<Vec<Document>, DieselError> {
+    /// Get a source's documents, newest first, one keyset page at a time.
+    pub async fn get_by_source(
+        &self,
+        source_id: &str,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<Document>, DieselError> {
+        let backwards = cursor.is_some_and(|c| c.before);
+        let cursor_at = cursor.and_then(|c| c.key(0)).map(str::to_string);
+        let cursor_id = cursor.and_then(|c| c.key(1)).map(str::to_string);
+
         let records: Vec<DocumentRecord> = with_conn!(self.pool, conn, {
-            documents::table
+            let mut query = documents::table
                 .filter(documents::source_id.eq(source_id))
-                .order(documents::created_at.desc())
-                .load(&mut conn)
-                .await
+                .limit(limit as i64 + 1)
+                .into_boxed();
+
+            // Walking backwards flips the comparison and order
+            if let (Some(at), Some(id)) = (cursor_at.clone(), cursor_id.clone()) {
+                query = if backwards {
+                    query.filter(
+                        documents::created_at
+                            .gt(at.clone())
+                            .or(documents::created_at.eq(at).and(documents::id.gt(id))),
+                    )
+                } else {
+                    query.filter(
+                        documents::created_at
+                            .lt(at.clone())
+                            .or(documents::created_at.eq(at).and(documents::id.lt(id))),
+                    )
+                };
+            }
+
+            query = if backwards {
+                query.order((documents::created_at.asc(), documents::id.asc()))
+            } else {
+                query.order((documents::created_at.desc(), documents::id.desc()))
+            };
+
+            query.load(&mut conn).await
         })?;
 
-        self.records_to_documents(records).await
+        let page = Page::from_rows(records, limit, cursor, |r| {
+            vec![r.created_at.clone(), r.id.clone()]
+        });
+        Ok(Page {
+            items: self.records_to_documents(page.items).await?,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
     }
 
     /// Get documents by URL.
@@ -157,6 +281,10 @@ impl DieselDocumentRepository {
         use crate::repository::sea_tables::Documents;
         use sea_query::{OnConflict, Query};
 
+        if let Some(shard) = self.shard_for_source(&doc.source_id) {
+            return Box::pin(shard.save(doc)).await;
+        }
+
         let metadata = serde_json::to_string(&doc.metadata)
             .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
         let created_at = doc.created_at.to_rfc3339();
@@ -210,7 +338,7 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(&doc.id)
                 .bind::<diesel::sql_types::Text, _>(&doc.source_id)
@@ -232,16 +360,24 @@ impl DieselDocumentRepository {
     /// Use this instead of `save()` when creating a new document or adding
     /// versions, so the version rows are actually written to document_versions.
     /// Use `save()` alone when only updating document metadata/status.
+    /// The document and its versions are written in one transaction.
     pub async fn save_with_versions(&self, doc: &Document) -> Result<(), DieselError> {
-        self.save(doc).await?;
-
-        for version in &doc.versions {
-            if version.id == 0 {
-                self.add_version(&doc.id, version).await?;
-            }
+        if let Some(shard) = self.shard_for_source(&doc.source_id) {
+            return Box::pin(shard.save_with_versions(doc)).await;
         }
+        self.pool
+            .transaction(|| async {
+                self.save(doc).await?;
+
+                for version in &doc.versions {
+                    if version.id == 0 {
+                        self.add_version(&doc.id, version).await?;
+                    }
+                }
 
-        Ok(())
+                Ok(())
+            })
+            .await
     }
 
     /// Delete a document.
@@ -250,9 +386,15 @@ impl DieselDocumentRepository {
         use crate::schema::document_pages;
         use diesel_async::AsyncConnection;
 
-        with_conn!(self.pool, conn, {
+        route_to_shard!(self, id, |shard| shard.delete(id));
+        with_write_conn!(self.pool, conn, {
             conn.transaction(|conn| {
                 Box::pin(async move {
+                    diesel::delete(
+                        derived_artifacts::table.filter(derived_artifacts::document_id.eq(id)),
+                    )
+                    .execute(conn)
+                    .await?;
                     diesel::delete(
                         document_versions::table.filter(document_versions::document_id.eq(id)),
                     )
@@ -276,12 +418,14 @@ impl DieselDocumentRepository {
         })
     }
 
-    /// Update document status.
+    /// Set a document's status without checking the workflow. Services
+    /// should use [`transition_status`](Self::transition_status).
     pub async fn update_status(&self, id: &str, status: DocumentStatus) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard.update_status(id, status));
         let status_str = status.as_str().to_string();
         let updated_at = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(documents::table.find(id))
                 .set((
                     documents::status.eq(&status_str),
@@ -310,6 +454,32 @@ impl DieselDocumentRepository {
         Ok(docs)
     }
 
+    /// Get raw document rows in ID order, starting after `after_id`.
+    ///
+    /// For bulk walks over the whole table (e.g. exports) that need the
+    /// columns `Document` does not carry.
+    pub async fn get_records_after(
+        &self,
+        source_id: Option<&str>,
+        after_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<DocumentRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = documents::table.into_boxed();
+            if let Some(source_id) = source_id {
+                query = query.filter(documents::source_id.eq(source_id));
+            }
+            if let Some(after_id) = after_id {
+                query = query.filter(documents::id.gt(after_id));
+            }
+            query
+                .order(documents::id.asc())
+                .limit(limit as i64)
+                .load(&mut conn)
+                .await
+        })
+    }
+
     /// Get all document URLs as a HashSet.
     ///
     /// Only includes documents that have at least one version row, since
@@ -345,9 +515,10 @@ impl DieselDocumentRepository {
 
     /// Insert virtual file.
     pub async fn insert_virtual_file(&self, vf: &VirtualFile) -> Result<(), DieselError> {
+        route_to_shard!(self, &vf.document_id, |shard| shard.insert_virtual_file(vf));
         let now = Utc::now().to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::insert_into(virtual_files::table)
                 .values((
                     virtual_files::id.eq(&vf.id),
@@ -393,6 +564,82 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Get a virtual file by ID.
+    pub async fn get_virtual_file(&self, id: &str) -> Result<Option<VirtualFile>, DieselError> {
+        with_conn!(self.pool, conn, {
+            virtual_files::table
+                .find(id)
+                .first::<VirtualFileRecord>(&mut conn)
+                .await
+                .optional()
+                .and_then(|record| record.map(Self::virtual_file_record_to_model).transpose())
+        })
+    }
+
+    /// Save the LLM synopsis and tags of a virtual file.
+    pub async fn update_virtual_file_summary(
+        &self,
+        id: &str,
+        synopsis: &str,
+        tags: &[String],
+    ) -> Result<(), DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let tags_json = serde_json::to_string(tags)
+            .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+        for shard in self.shard_repos() {
+            Box::pin(shard.update_virtual_file_summary(id, synopsis, tags)).await?;
+        }
+        with_write_conn!(self.pool, conn, {
+            diesel::update(virtual_files::table.find(id))
+                .set((
+                    virtual_files::synopsis.eq(synopsis),
+                    virtual_files::tags.eq(&tags_json),
+                    virtual_files::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Replace a version's derived virtual files under `prefix` with `files`.
+    ///
+    /// Used for outputs that are regenerated wholesale, like extracted tables.
+    pub async fn replace_virtual_files(
+        &self,
+        document_id: &str,
+        version: i32,
+        prefix: &str,
+        files: &[VirtualFile],
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.replace_virtual_files(
+            document_id,
+            version,
+            prefix,
+            files
+        ));
+        self.pool
+            .transaction(|| async {
+                with_write_conn!(self.pool, conn, {
+                    diesel::delete(
+                        virtual_files::table
+                            .filter(virtual_files::document_id.eq(document_id))
+                            .filter(virtual_files::version_id.eq(version))
+                            .filter(virtual_files::archive_path.like(format!("{}%", prefix))),
+                    )
+                    .execute(&mut conn)
+                    .await
+                })?;
+
+                for vf in files {
+                    self.insert_virtual_file(vf).await?;
+                }
+                Ok(())
+            })
+            .await
+    }
+
     /// Count unprocessed archives.
     pub async fn count_unprocessed_archives(
         &self,
@@ -659,6 +906,11 @@ impl DieselDocumentRepository {
             archive_snapshot_id: record.archive_snapshot_id,
             earliest_archived_at: parse_datetime_opt(record.earliest_archived_at),
             dedup_index: record.dedup_index.map(|i| i as u32),
+            checksum: record
+                .checksum_verification
+                .and_then(|s| serde_json::from_str(&s).ok()),
+            declared_mime_type: record.declared_mime_type,
+            computed_sha256: None,
         }
     }
 
@@ -741,6 +993,12 @@ pub struct BrowseRow {
     #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
     pub tags: Option<String>,
     #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub record_type: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub document_date: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub updated_at: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
     pub original_filename: Option<String>,
     #[diesel(sql_type = diesel::sql_types::Text)]
     pub mime_type: String,
@@ -748,6 +1006,10 @@ pub struct BrowseRow {
     pub file_size: i32,
     #[diesel(sql_type = diesel::sql_types::Text)]
     pub acquired_at: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Integer>)]
+    pub page_count: Option<i32>,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub status: String,
 }
 
 #[derive(diesel::QueryableByName)]
@@ -760,6 +1022,7 @@ pub(crate) struct ReturningId {
 mod tests {
     use super::super::pool::SqlitePool;
     use super::*;
+    use crate::models::{DocumentPage, PageOcrStatus};
     use diesel_async::SimpleAsyncConnection;
     use tempfile::tempdir;
 
@@ -789,7 +1052,14 @@ mod tests {
                 date_source TEXT,
                 manual_date TEXT,
                 discovery_method TEXT NOT NULL DEFAULT 'import',
-                category_id TEXT
+                category_id TEXT,
+                record_type TEXT,
+                record_type_confidence REAL,
+                document_date TEXT,
+                originating_agency TEXT,
+                originating_office TEXT,
+                case_number TEXT,
+                classification_markings TEXT
             );
 
             CREATE TABLE IF NOT EXISTS document_versions (
@@ -807,7 +1077,9 @@ mod tests {
                 page_count INTEGER,
                 archive_snapshot_id INTEGER,
                 earliest_archived_at TEXT,
-                dedup_index INTEGER
+                dedup_index INTEGER,
+                checksum_verification TEXT,
+                declared_mime_type TEXT
             );
 
             CREATE TABLE IF NOT EXISTS document_pages (
@@ -821,9 +1093,32 @@ mod tests {
                 ocr_status TEXT NOT NULL DEFAULT 'pending',
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
+                ocr_quality REAL,
+                corrected_at TEXT,
                 UNIQUE(document_id, version_id, page_number)
             );
 
+            CREATE TABLE IF NOT EXISTS page_ocr_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                page_id INTEGER NOT NULL,
+                backend TEXT NOT NULL,
+                text TEXT,
+                confidence REAL,
+                quality_score REAL,
+                char_count INTEGER,
+                word_count INTEGER,
+                processing_time_ms INTEGER,
+                error_message TEXT,
+                created_at TEXT NOT NULL,
+                model TEXT,
+                image_hash TEXT,
+                tool_version TEXT,
+                language TEXT,
+                dpi INTEGER
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_page_ocr_results_unique
+                ON page_ocr_results(page_id, backend, COALESCE(model, ''));
+
             CREATE TABLE IF NOT EXISTS virtual_files (
                 id TEXT PRIMARY KEY,
                 document_id TEXT NOT NULL,
@@ -839,6 +1134,25 @@ mod tests {
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL
             );
+
+            CREATE TABLE IF NOT EXISTS derived_artifacts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                document_id TEXT NOT NULL,
+                version_id INTEGER NOT NULL,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                mime_type TEXT NOT NULL,
+                file_path TEXT NOT NULL,
+                file_size INTEGER NOT NULL,
+                content_hash TEXT NOT NULL,
+                analysis_result_id INTEGER,
+                status TEXT NOT NULL DEFAULT 'ready',
+                metadata TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_derived_artifacts_unique
+                ON derived_artifacts(version_id, kind, name);
             "#,
         )
         .await
@@ -885,6 +1199,52 @@ mod tests {
         assert!(!repo.exists("doc-1").await.unwrap());
     }
 
+    #[tokio::test]
+    async fn test_get_by_source_pages() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let now = Utc::now();
+        for i in 0..3 {
+            let doc = Document {
+                id: format!("doc-{i}"),
+                source_id: "test-source".to_string(),
+                title: format!("Document {i}"),
+                source_url: format!("https://example.com/{i}.pdf"),
+                extracted_text: None,
+                synopsis: None,
+                tags: vec![],
+                status: DocumentStatus::Pending,
+                metadata: serde_json::Value::Object(Default::default()),
+                created_at: now + chrono::Duration::seconds(i),
+                updated_at: now,
+                discovery_method: "seed".to_string(),
+                versions: vec![],
+            };
+            repo.save(&doc).await.unwrap();
+        }
+
+        let ids =
+            |page: &Page<Document>| page.items.iter().map(|d| d.id.clone()).collect::<Vec<_>>();
+
+        let first = repo.get_by_source("test-source", None, 2).await.unwrap();
+        assert_eq!(ids(&first), vec!["doc-2", "doc-1"]);
+        assert!(first.prev_cursor.is_none());
+
+        let second = repo
+            .get_by_source("test-source", first.next_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(ids(&second), vec!["doc-0"]);
+        assert!(second.next_cursor.is_none());
+
+        let back = repo
+            .get_by_source("test-source", second.prev_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(ids(&back), ids(&first));
+    }
+
     #[tokio::test]
     async fn test_document_versions() {
         let (pool, _dir) = setup_test_db().await;
@@ -922,12 +1282,320 @@ mod tests {
             archive_snapshot_id: None,
             earliest_archived_at: None,
             dedup_index: None,
+            checksum: None,
+            declared_mime_type: None,
+            computed_sha256: None,
         };
         repo.add_version("doc-2", &version).await.unwrap();
 
         let latest = repo.get_latest_version("doc-2").await.unwrap().unwrap();
         assert_eq!(latest.content_hash, "abc123");
         assert_eq!(latest.file_size, 1024);
+
+        let found = repo.find_file_by_hash_prefix("abc1").await.unwrap();
+        assert_eq!(found, Some(("abc123".to_string(), None)));
+        assert_eq!(
+            repo.find_file_by_hash_prefix("ABC").await.unwrap(),
+            Some(("abc123".to_string(), None))
+        );
+        assert_eq!(repo.find_file_by_hash_prefix("fff").await.unwrap(), None);
+        assert_eq!(repo.find_file_by_hash_prefix("ab%").await.unwrap(), None);
+        assert_eq!(repo.find_file_by_hash_prefix("").await.unwrap(), None);
+
+        // A prefix shared by two different files is ambiguous
+        let other = DocumentVersion {
+            id: 2,
+            content_hash: "abd999".to_string(),
+            ..version.clone()
+        };
+        repo.add_version("doc-2", &other).await.unwrap();
+        assert_eq!(repo.find_file_by_hash_prefix("ab").await.unwrap(), None);
+        assert!(repo
+            .find_file_by_hash_prefix("abc")
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_requeue_ocr_results() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let doc = Document {
+            id: "doc-3".to_string(),
+            source_id: "test-source".to_string(),
+            title: "Scanned Doc".to_string(),
+            source_url: "https://example.com/scan.pdf".to_string(),
+            extracted_text: None,
+            synopsis: None,
+            tags: vec![],
+            status: DocumentStatus::Pending,
+            metadata: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            discovery_method: "seed".to_string(),
+            versions: vec![],
+        };
+        repo.save(&doc).await.unwrap();
+        let page_id = repo
+            .save_page(&DocumentPage::new("doc-3".to_string(), 1, 1))
+            .await
+            .unwrap();
+
+        let tesseract = OcrRunSettings {
+            tool_version: Some("4.1.1".to_string()),
+            language: Some("eng".to_string()),
+            dpi: Some(300),
+        };
+        repo.store_page_ocr_result(
+            page_id,
+            "tesseract",
+            None,
+            Some("text"),
+            None,
+            Some(0.3),
+            None,
+            None,
+            &tesseract,
+        )
+        .await
+        .unwrap();
+        repo.store_page_ocr_result(
+            page_id,
+            "groq",
+            Some("llama"),
+            Some("text"),
+            None,
+            None,
+            None,
+            None,
+            &OcrRunSettings::default(),
+        )
+        .await
+        .unwrap();
+
+        let runs = repo.get_ocr_runs(None).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(repo.get_ocr_runs(Some("other")).await.unwrap().is_empty());
+
+        let stale: Vec<OcrRun> = runs
+            .into_iter()
+            .filter(|r| r.settings == tesseract)
+            .collect();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].quality_score, Some(0.3));
+        assert_eq!(repo.requeue_ocr_results(&stale).await.unwrap(), 1);
+
+        let remaining = repo.get_ocr_runs(Some("test-source")).await.unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].backend, "groq");
+        let pages = repo.get_pages("doc-3", 1).await.unwrap();
+        assert_eq!(pages[0].ocr_status, PageOcrStatus::TextExtracted);
+
+        // Requeueing the whole version drops the rest
+        assert_eq!(repo.requeue_version_ocr("doc-3", 1).await.unwrap(), 1);
+        assert!(repo.get_ocr_runs(None).await.unwrap().is_empty());
+        assert_eq!(repo.requeue_version_ocr("doc-3", 2).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ocr_quality() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        for id in ["clean", "smudged"] {
+            let doc = Document {
+                id: id.to_string(),
+                source_id: "test-source".to_string(),
+                title: id.to_string(),
+                source_url: format!("https://example.com/{}.pdf", id),
+                extracted_text: None,
+                synopsis: None,
+                tags: vec![],
+                status: DocumentStatus::OcrComplete,
+                metadata: serde_json::Value::Object(Default::default()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                discovery_method: "seed".to_string(),
+                versions: vec![],
+            };
+            repo.save(&doc).await.unwrap();
+        }
+        for (id, page_number, quality) in [
+            ("clean", 1, Some(0.95)),
+            ("clean", 2, None),
+            ("smudged", 1, Some(0.9)),
+            ("smudged", 2, Some(0.2)),
+        ] {
+            let mut page = DocumentPage::new(id.to_string(), 1, page_number);
+            page.ocr_quality = quality;
+            repo.save_page(&page).await.unwrap();
+        }
+
+        let clean = repo.get_ocr_quality("clean", 1).await.unwrap().unwrap();
+        assert_eq!((clean.scored_pages, clean.poor_pages), (1, 0));
+        let smudged = repo.get_ocr_quality("smudged", 1).await.unwrap().unwrap();
+        assert_eq!((smudged.scored_pages, smudged.poor_pages), (2, 1));
+        assert!((smudged.score - 0.55).abs() < 1e-6);
+        assert_eq!(repo.get_ocr_quality("clean", 2).await.unwrap(), None);
+
+        let poor = BrowseParams {
+            poor_ocr: true,
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&poor).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_page_correction() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let mut page = DocumentPage::new("doc-1".to_string(), 1, 1);
+        page.pdf_text = Some("pdf text".to_string());
+        page.ocr_text = Some("0CR text w1th err0rs".to_string());
+        page.compute_final_text();
+        repo.save_page(&page).await.unwrap();
+
+        let corrected = repo
+            .correct_page_text("doc-1", 1, 1, "OCR text with errors")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(corrected.is_corrected());
+        assert_eq!(
+            corrected.final_text.as_deref(),
+            Some("OCR text with errors")
+        );
+        assert_eq!(corrected.ocr_text.as_deref(), Some("0CR text w1th err0rs"));
+        assert!(repo
+            .correct_page_text("doc-1", 1, 2, "missing")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Re-running OCR keeps the correction
+        page.ocr_text = Some("0CR text again".to_string());
+        page.compute_final_text();
+        repo.save_page(&page).await.unwrap();
+        repo.save_pages_batch(std::slice::from_ref(&page))
+            .await
+            .unwrap();
+        let page_after = repo.get_page("doc-1", 1, 1).await.unwrap().unwrap();
+        assert_eq!(
+            page_after.final_text.as_deref(),
+            Some("OCR text with errors")
+        );
+        assert_eq!(page_after.ocr_text.as_deref(), Some("0CR text again"));
+        assert_eq!(
+            repo.get_combined_page_text("doc-1", 1).await.unwrap(),
+            Some("OCR text with errors".to_string())
+        );
+
+        let reverted = repo
+            .revert_page_correction("doc-1", 1, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!reverted.is_corrected());
+        assert_eq!(reverted.final_text.as_deref(), Some("0CR text again"));
+    }
+
+    #[tokio::test]
+    async fn test_derived_artifacts() {
+        use crate::models::{ArtifactKind, ArtifactStatus, NewDerivedArtifact};
+
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let mut artifact = NewDerivedArtifact {
+            document_id: "doc-1".to_string(),
+            version_id: 1,
+            kind: ArtifactKind::SearchablePdf,
+            name: "searchable.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            file_path: "derived/ab/abcd/searchable_pdf/searchable.pdf".to_string(),
+            file_size: 2048,
+            content_hash: "1234".to_string(),
+            analysis_result_id: None,
+            metadata: None,
+        };
+        let id = repo.save_artifact(&artifact).await.unwrap();
+        repo.mark_artifacts_stale("doc-1", 1, ArtifactKind::from_ocr())
+            .await
+            .unwrap();
+        let stale = repo.get_artifact(id).await.unwrap().unwrap();
+        assert_eq!(stale.status, ArtifactStatus::Stale);
+
+        // Regenerating replaces the record and makes it ready again
+        artifact.file_size = 4096;
+        assert_eq!(repo.save_artifact(&artifact).await.unwrap(), id);
+        let found = repo
+            .find_artifact(1, ArtifactKind::SearchablePdf, "searchable.pdf")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.file_size, 4096);
+        assert_eq!(found.status, ArtifactStatus::Ready);
+        assert_eq!(repo.get_artifacts("doc-1", 1).await.unwrap().len(), 1);
+
+        repo.mark_artifacts_stale("doc-1", 1, &[ArtifactKind::SearchablePdf])
+            .await
+            .unwrap();
+        let later = Utc::now() + chrono::Duration::seconds(1);
+        let prunable = repo.get_stale_artifacts(later).await.unwrap();
+        assert_eq!(prunable.len(), 1);
+        assert!(repo
+            .get_stale_artifacts(Utc::now() - chrono::Duration::days(1))
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(repo.delete_artifacts(&[id]).await.unwrap(), 1);
+        assert!(repo.get_artifact_paths(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_derived_virtual_files() {
+        use crate::models::TABLES_PREFIX;
+
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let member = VirtualFile::new(
+            "doc-1".to_string(),
+            1,
+            "budget/summary.pdf".to_string(),
+            "summary.pdf".to_string(),
+            "application/pdf".to_string(),
+            1024,
+        );
+        repo.insert_virtual_file(&member).await.unwrap();
+
+        let first = VirtualFile::extracted_table("doc-1".into(), 1, 2, 1, "a,b\n".into());
+        repo.replace_virtual_files("doc-1", 1, TABLES_PREFIX, &[first.clone()])
+            .await
+            .unwrap();
+        let second = VirtualFile::extracted_table("doc-1".into(), 1, 3, 1, "c,d\n".into());
+        repo.replace_virtual_files("doc-1", 1, TABLES_PREFIX, &[second.clone()])
+            .await
+            .unwrap();
+
+        let files = repo.get_virtual_files("doc-1", 1).await.unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(repo.get_virtual_file(&first.id).await.unwrap().is_none());
+        let table = repo.get_virtual_file(&second.id).await.unwrap().unwrap();
+        assert_eq!(table.extracted_text.as_deref(), Some("c,d\n"));
+        assert_eq!(table.table_page(), Some(3));
+        assert!(repo.get_virtual_file(&member.id).await.unwrap().is_some());
+
+        repo.update_virtual_file_summary(&member.id, "A budget summary.", &["budget".into()])
+            .await
+            .unwrap();
+        let member = repo.get_virtual_file(&member.id).await.unwrap().unwrap();
+        assert_eq!(member.synopsis.as_deref(), Some("A budget summary."));
+        assert_eq!(member.tags, vec!["budget".to_string()]);
     }
 
     #[tokio::test]
@@ -6,13 +6,73 @@ use chrono::Utc;
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
+use super::compression::{compress_page_text, decompress_page_text};
+use super::search_index::sqlite_text_filter;
 use super::{CountRow, DieselDocumentRepository, OcrResult, ReturningId};
-use crate::models::{DocumentPage, PageOcrStatus};
+use crate::models::{DocumentPage, PageOcrStatus, POOR_OCR_QUALITY};
+use crate::repository::cursor::{Page, PageCursor};
 use crate::repository::models::{DocumentPageRecord, PageOcrResultRecord};
 use crate::repository::parse_datetime;
 use crate::repository::pool::DieselError;
 use crate::schema::{document_pages, page_ocr_results};
-use crate::{with_conn, with_conn_split};
+use crate::{with_conn, with_conn_split, with_write_conn, with_write_conn_split};
+
+/// Upsert expression for `final_text` that leaves hand-corrected pages alone,
+/// so re-running OCR or extraction doesn't undo a curator's edits.
+const KEEP_CORRECTED_TEXT: &str = "CASE WHEN document_pages.corrected_at IS NULL \
+     THEN excluded.final_text ELSE document_pages.final_text END";
+
+/// `pdf_text` or `ocr_text` of `page` as stored: compressed when the page
+/// has a `final_text` for search to read instead.
+fn stored_text(page: &DocumentPage, text: &Option<String>) -> Option<String> {
+    match (&page.final_text, text) {
+        (Some(_), Some(text)) => Some(compress_page_text(text)),
+        _ => text.clone(),
+    }
+}
+
+/// Tool settings an OCR result was produced with.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OcrRunSettings {
+    /// OCR tool version (e.g. `5.3.0` for Tesseract).
+    pub tool_version: Option<String>,
+    /// OCR language (e.g. `eng`, `eng+fra`).
+    pub language: Option<String>,
+    /// Resolution the page was rendered at for OCR.
+    pub dpi: Option<u32>,
+}
+
+impl From<&PageOcrResultRecord> for OcrRunSettings {
+    fn from(record: &PageOcrResultRecord) -> Self {
+        Self {
+            tool_version: record.tool_version.clone(),
+            language: record.language.clone(),
+            dpi: record.dpi.map(|d| d as u32),
+        }
+    }
+}
+
+/// An OCR result's backend and settings, without its text.
+#[derive(Debug, Clone)]
+pub struct OcrRun {
+    pub result_id: i64,
+    pub page_id: i64,
+    pub backend: String,
+    pub model: Option<String>,
+    pub settings: OcrRunSettings,
+    pub quality_score: Option<f32>,
+}
+
+/// OCR quality across a document version's scored pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OcrQualitySummary {
+    /// Mean page quality score (0.0 - 1.0).
+    pub score: f32,
+    /// Pages with a quality score.
+    pub scored_pages: u32,
+    /// Pages scoring below [`POOR_OCR_QUALITY`].
+    pub poor_pages: u32,
+}
 
 #[derive(diesel::QueryableByName, Debug)]
 pub struct PageSearchRow {
@@ -36,6 +96,22 @@ pub struct PageSearchRow {
     pub dedup_index: Option<i32>,
     #[diesel(sql_type = diesel::sql_types::Text)]
     pub source_url: String,
+    /// Relevance score (Postgres only; always 0 on SQLite).
+    #[diesel(sql_type = diesel::sql_types::Float)]
+    pub rank: f32,
+}
+
+/// Text of one page retrieved as context for question answering.
+#[derive(diesel::QueryableByName, Debug, Clone)]
+pub struct PagePassageRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub document_id: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub title: String,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub page_number: i32,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub text: String,
 }
 
 impl From<DocumentPageRecord> for DocumentPage {
@@ -45,12 +121,14 @@ impl From<DocumentPageRecord> for DocumentPage {
             document_id: r.document_id,
             version_id: r.version_id as i64,
             page_number: r.page_number as u32,
-            pdf_text: r.pdf_text,
-            ocr_text: r.ocr_text,
+            pdf_text: r.pdf_text.map(decompress_page_text),
+            ocr_text: r.ocr_text.map(decompress_page_text),
             final_text: r.final_text,
             ocr_status: PageOcrStatus::from_str(&r.ocr_status).unwrap_or(PageOcrStatus::Pending),
             created_at: parse_datetime(&r.created_at),
             updated_at: parse_datetime(&r.updated_at),
+            ocr_quality: r.ocr_quality,
+            corrected_at: r.corrected_at.as_deref().map(parse_datetime),
         }
     }
 }
@@ -70,16 +148,46 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// OCR quality summary for a document version, if any page was scored.
+    pub async fn get_ocr_quality(
+        &self,
+        document_id: &str,
+        version_id: i64,
+    ) -> Result<Option<OcrQualitySummary>, DieselError> {
+        let scores: Vec<Option<f32>> = with_conn!(self.pool, conn, {
+            document_pages::table
+                .filter(document_pages::document_id.eq(document_id))
+                .filter(document_pages::version_id.eq(version_id as i32))
+                .filter(document_pages::ocr_quality.is_not_null())
+                .select(document_pages::ocr_quality)
+                .load(&mut conn)
+                .await
+        })?;
+
+        let scores: Vec<f32> = scores.into_iter().flatten().collect();
+        if scores.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(OcrQualitySummary {
+            score: scores.iter().sum::<f32>() / scores.len() as f32,
+            scored_pages: scores.len() as u32,
+            poor_pages: scores.iter().filter(|&&q| q < POOR_OCR_QUALITY).count() as u32,
+        }))
+    }
+
     /// Save a document page. Returns the page ID.
     pub async fn save_page(&self, page: &DocumentPage) -> Result<i64, DieselError> {
         use crate::repository::pool::build_sql;
         use crate::repository::sea_tables::DocumentPages;
-        use sea_query::{OnConflict, Query};
+        use sea_query::{Expr, OnConflict, Query};
 
+        route_to_shard!(self, &page.document_id, |shard| shard.save_page(page));
         let now = Utc::now().to_rfc3339();
         let version_id = page.version_id as i32;
         let page_number = page.page_number as i32;
         let ocr_status = page.ocr_status.as_str().to_string();
+        let pdf_text = stored_text(page, &page.pdf_text);
+        let ocr_text = stored_text(page, &page.ocr_text);
 
         let stmt = Query::insert()
             .into_table(DocumentPages::Table)
@@ -93,17 +201,19 @@ impl DieselDocumentRepository {
                 DocumentPages::OcrStatus,
                 DocumentPages::CreatedAt,
                 DocumentPages::UpdatedAt,
+                DocumentPages::OcrQuality,
             ])
             .values_panic([
                 page.document_id.clone().into(),
                 version_id.into(),
                 page_number.into(),
-                page.pdf_text.clone().into(),
-                page.ocr_text.clone().into(),
+                pdf_text.clone().into(),
+                ocr_text.clone().into(),
                 page.final_text.clone().into(),
                 ocr_status.clone().into(),
                 now.clone().into(),
                 now.clone().into(),
+                page.ocr_quality.into(),
             ])
             .on_conflict(
                 OnConflict::columns([
@@ -114,10 +224,11 @@ impl DieselDocumentRepository {
                 .update_columns([
                     DocumentPages::PdfText,
                     DocumentPages::OcrText,
-                    DocumentPages::FinalText,
                     DocumentPages::OcrStatus,
                     DocumentPages::UpdatedAt,
+                    DocumentPages::OcrQuality,
                 ])
+                .value(DocumentPages::FinalText, Expr::cust(KEEP_CORRECTED_TEXT))
                 .to_owned(),
             )
             .returning_col(DocumentPages::Id)
@@ -125,17 +236,18 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let result: ReturningId = diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(&page.document_id)
                 .bind::<diesel::sql_types::Integer, _>(version_id)
                 .bind::<diesel::sql_types::Integer, _>(page_number)
-                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.pdf_text)
-                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.ocr_text)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&pdf_text)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&ocr_text)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.final_text)
                 .bind::<diesel::sql_types::Text, _>(&ocr_status)
                 .bind::<diesel::sql_types::Text, _>(&now)
                 .bind::<diesel::sql_types::Text, _>(&now)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(page.ocr_quality)
                 .get_result(&mut conn)
                 .await?;
             Ok(result.id as i64)
@@ -148,28 +260,42 @@ impl DieselDocumentRepository {
         if pages.is_empty() {
             return Ok(());
         }
+        if !self.shards.is_empty() {
+            // Each document's pages go to the database holding it
+            for run in pages.chunk_by(|a, b| a.document_id == b.document_id) {
+                let repo = match self.shard_for_document(&run[0].document_id).await? {
+                    Some(shard) => shard.clone(),
+                    None => self.without_shards(),
+                };
+                Box::pin(repo.save_pages_batch(run)).await?;
+            }
+            return Ok(());
+        }
 
         let now = Utc::now().to_rfc3339();
 
-        with_conn_split!(self.pool,
+        with_write_conn_split!(self.pool,
             sqlite: conn => {
                 for page in pages {
                     let version_id = page.version_id as i32;
                     let page_number = page.page_number as i32;
                     let ocr_status = page.ocr_status.as_str().to_string();
+                    let pdf_text = stored_text(page, &page.pdf_text);
+                    let ocr_text = stored_text(page, &page.ocr_text);
 
-                    diesel::sql_query(
+                    diesel::sql_query(format!(
                         "INSERT INTO document_pages (document_id, version_id, page_number, pdf_text, ocr_text, final_text, ocr_status, created_at, updated_at) \
                          VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) \
                          ON CONFLICT (document_id, version_id, page_number) \
                          DO UPDATE SET pdf_text = excluded.pdf_text, ocr_text = excluded.ocr_text, \
-                         final_text = excluded.final_text, ocr_status = excluded.ocr_status, updated_at = excluded.updated_at"
-                    )
+                         final_text = {}, ocr_status = excluded.ocr_status, updated_at = excluded.updated_at",
+                        KEEP_CORRECTED_TEXT
+                    ))
                     .bind::<diesel::sql_types::Text, _>(&page.document_id)
                     .bind::<diesel::sql_types::Integer, _>(version_id)
                     .bind::<diesel::sql_types::Integer, _>(page_number)
-                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.pdf_text)
-                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.ocr_text)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&pdf_text)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&ocr_text)
                     .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&page.final_text)
                     .bind::<diesel::sql_types::Text, _>(&ocr_status)
                     .bind::<diesel::sql_types::Text, _>(&now)
@@ -198,8 +324,9 @@ impl DieselDocumentRepository {
                          VALUES {} \
                          ON CONFLICT (document_id, version_id, page_number) \
                          DO UPDATE SET pdf_text = EXCLUDED.pdf_text, ocr_text = EXCLUDED.ocr_text, \
-                         final_text = EXCLUDED.final_text, ocr_status = EXCLUDED.ocr_status, updated_at = EXCLUDED.updated_at",
-                        placeholders.join(", ")
+                         final_text = {}, ocr_status = EXCLUDED.ocr_status, updated_at = EXCLUDED.updated_at",
+                        placeholders.join(", "),
+                        KEEP_CORRECTED_TEXT
                     );
 
                     let mut query = diesel::sql_query(sql).into_boxed::<diesel::pg::Pg>();
@@ -212,8 +339,8 @@ impl DieselDocumentRepository {
                             .bind::<diesel::sql_types::Text, _>(page.document_id.clone())
                             .bind::<diesel::sql_types::Integer, _>(version_id)
                             .bind::<diesel::sql_types::Integer, _>(page_number)
-                            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(page.pdf_text.clone())
-                            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(page.ocr_text.clone())
+                            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(stored_text(page, &page.pdf_text))
+                            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(stored_text(page, &page.ocr_text))
                             .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(page.final_text.clone())
                             .bind::<diesel::sql_types::Text, _>(ocr_status)
                             .bind::<diesel::sql_types::Text, _>(now.clone())
@@ -247,6 +374,29 @@ impl DieselDocumentRepository {
         Ok(records.into_iter().map(DocumentPage::from).collect())
     }
 
+    /// Get pages of every version of multiple documents in a single query.
+    pub async fn get_pages_batch(
+        &self,
+        document_ids: &[String],
+    ) -> Result<Vec<DocumentPage>, DieselError> {
+        if document_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let records: Vec<DocumentPageRecord> = with_conn!(self.pool, conn, {
+            document_pages::table
+                .filter(document_pages::document_id.eq_any(document_ids))
+                .order((
+                    document_pages::document_id.asc(),
+                    document_pages::version_id.asc(),
+                    document_pages::page_number.asc(),
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(records.into_iter().map(DocumentPage::from).collect())
+    }
+
     /// Get pages needing OCR.
     #[allow(dead_code)]
     pub async fn get_pages_needing_ocr(
@@ -283,17 +433,35 @@ impl DieselDocumentRepository {
         model: Option<&str>,
         text: Option<&str>,
         confidence: Option<f32>,
+        quality_score: Option<f32>,
         processing_time_ms: Option<i32>,
         image_hash: Option<&str>,
+        settings: &OcrRunSettings,
     ) -> Result<(), DieselError> {
         use crate::repository::pool::build_sql;
         use crate::repository::sea_tables::PageOcrResults;
         use sea_query::{Expr, OnConflict, Query};
 
+        if let Some(shard) = self.shard_for_page(page_id).await? {
+            return Box::pin(shard.store_page_ocr_result(
+                page_id,
+                backend,
+                model,
+                text,
+                confidence,
+                quality_score,
+                processing_time_ms,
+                image_hash,
+                settings,
+            ))
+            .await;
+        }
+
         let now = Utc::now().to_rfc3339();
         let char_count = text.map(|t| t.chars().count() as i32);
         let word_count = text.map(|t| t.split_whitespace().count() as i32);
         let page_id_i32 = page_id as i32;
+        let dpi = settings.dpi.map(|d| d as i32);
 
         let stmt = Query::insert()
             .into_table(PageOcrResults::Table)
@@ -310,13 +478,16 @@ impl DieselDocumentRepository {
                 PageOcrResults::CreatedAt,
                 PageOcrResults::Model,
                 PageOcrResults::ImageHash,
+                PageOcrResults::ToolVersion,
+                PageOcrResults::Language,
+                PageOcrResults::Dpi,
             ])
             .values_panic([
                 page_id_i32.into(),
                 backend.to_string().into(),
                 text.map(|s| s.to_string()).into(),
                 confidence.into(),
-                Option::<i32>::None.into(),
+                quality_score.into(),
                 char_count.into(),
                 word_count.into(),
                 processing_time_ms.into(),
@@ -324,6 +495,9 @@ impl DieselDocumentRepository {
                 now.clone().into(),
                 model.map(|s| s.to_string()).into(),
                 image_hash.map(|s| s.to_string()).into(),
+                settings.tool_version.clone().into(),
+                settings.language.clone().into(),
+                dpi.into(),
             ])
             .on_conflict(
                 OnConflict::new()
@@ -333,11 +507,15 @@ impl DieselDocumentRepository {
                     .update_columns([
                         PageOcrResults::Text,
                         PageOcrResults::Confidence,
+                        PageOcrResults::QualityScore,
                         PageOcrResults::CharCount,
                         PageOcrResults::WordCount,
                         PageOcrResults::ProcessingTimeMs,
                         PageOcrResults::CreatedAt,
                         PageOcrResults::ImageHash,
+                        PageOcrResults::ToolVersion,
+                        PageOcrResults::Language,
+                        PageOcrResults::Dpi,
                     ])
                     .to_owned(),
             )
@@ -345,13 +523,13 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Integer, _>(page_id_i32)
                 .bind::<diesel::sql_types::Text, _>(backend)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(text)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(confidence)
-                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(None::<i32>)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(quality_score)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(char_count)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(word_count)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(
@@ -361,6 +539,13 @@ impl DieselDocumentRepository {
                 .bind::<diesel::sql_types::Text, _>(&now)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(model)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(image_hash)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
+                    settings.tool_version.as_deref(),
+                )
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
+                    settings.language.as_deref(),
+                )
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(dpi)
                 .execute(&mut conn)
                 .await?;
 
@@ -371,6 +556,20 @@ impl DieselDocumentRepository {
                 ))
                 .execute(&mut conn)
                 .await?;
+            // Compressed only once search has the page's final_text to read.
+            if let Some(stored) = text
+                .map(compress_page_text)
+                .filter(|s| Some(s.as_str()) != text)
+            {
+                diesel::update(
+                    document_pages::table
+                        .find(page_id_i32)
+                        .filter(document_pages::final_text.is_not_null()),
+                )
+                .set(document_pages::ocr_text.eq(stored))
+                .execute(&mut conn)
+                .await?;
+            }
             Ok(())
         })
     }
@@ -388,6 +587,11 @@ impl DieselDocumentRepository {
         use crate::repository::sea_tables::PageOcrResults;
         use sea_query::{Expr, OnConflict, Query};
 
+        if let Some(shard) = self.shard_for_page(page_id).await? {
+            return Box::pin(shard.store_page_ocr_error(page_id, backend, model, error_message))
+                .await;
+        }
+
         let now = Utc::now().to_rfc3339();
         let page_id_i32 = page_id as i32;
 
@@ -437,7 +641,7 @@ impl DieselDocumentRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Integer, _>(page_id_i32)
                 .bind::<diesel::sql_types::Text, _>(backend)
@@ -472,6 +676,32 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// All OCR results for a document's pages, oldest first, each with its
+    /// page's version ID and page number.
+    pub async fn get_document_ocr_results(
+        &self,
+        document_id: &str,
+    ) -> Result<Vec<(i64, u32, PageOcrResultRecord)>, DieselError> {
+        let rows: Vec<(i32, i32, PageOcrResultRecord)> = with_conn!(self.pool, conn, {
+            page_ocr_results::table
+                .inner_join(document_pages::table)
+                .filter(document_pages::document_id.eq(document_id))
+                .order(page_ocr_results::created_at.asc())
+                .select((
+                    document_pages::version_id,
+                    document_pages::page_number,
+                    PageOcrResultRecord::as_select(),
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(version_id, page, record)| (version_id as i64, page as u32, record))
+            .collect())
+    }
+
     /// Find an existing OCR result by image hash and backend.
     /// Used for deduplication - if we've already OCR'd this exact image, reuse the result.
     pub async fn find_ocr_result_by_image_hash(
@@ -490,13 +720,258 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Backend and settings of every OCR result, optionally limited to one
+    /// source's documents.
+    pub async fn get_ocr_runs(&self, source_id: Option<&str>) -> Result<Vec<OcrRun>, DieselError> {
+        use crate::schema::documents;
+
+        type Row = (
+            i32,
+            i32,
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            Option<i32>,
+            Option<f32>,
+        );
+        let columns = (
+            page_ocr_results::id,
+            page_ocr_results::page_id,
+            page_ocr_results::backend,
+            page_ocr_results::model,
+            page_ocr_results::tool_version,
+            page_ocr_results::language,
+            page_ocr_results::dpi,
+            page_ocr_results::quality_score,
+        );
+        let rows: Vec<Row> = with_conn!(self.pool, conn, {
+            match source_id {
+                Some(source_id) => {
+                    page_ocr_results::table
+                        .inner_join(document_pages::table.inner_join(documents::table))
+                        .filter(documents::source_id.eq(source_id))
+                        .select(columns)
+                        .load(&mut conn)
+                        .await
+                }
+                None => {
+                    page_ocr_results::table
+                        .select(columns)
+                        .load(&mut conn)
+                        .await
+                }
+            }
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(
+                |(id, page_id, backend, model, tool_version, language, dpi, quality_score)| {
+                    OcrRun {
+                        result_id: id as i64,
+                        page_id: page_id as i64,
+                        backend,
+                        model,
+                        settings: OcrRunSettings {
+                            tool_version,
+                            language,
+                            dpi: dpi.map(|d| d as u32),
+                        },
+                        quality_score,
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Delete OCR results and queue their pages for OCR again.
+    ///
+    /// Pages go back to `text_extracted`, so the next `analyze` run re-OCRs
+    /// them and re-finalizes their documents. Artifacts built from their
+    /// OCR text are marked stale. Returns the number of pages queued.
+    pub async fn requeue_ocr_results(&self, runs: &[OcrRun]) -> Result<usize, DieselError> {
+        for shard in self.shard_repos() {
+            Box::pin(shard.requeue_ocr_results(runs)).await?;
+        }
+        let mut page_ids: Vec<i32> = runs.iter().map(|r| r.page_id as i32).collect();
+        page_ids.sort_unstable();
+        page_ids.dedup();
+        let result_ids: Vec<i32> = runs.iter().map(|r| r.result_id as i32).collect();
+        let now = Utc::now().to_rfc3339();
+
+        for chunk in result_ids.chunks(500) {
+            with_write_conn!(self.pool, conn, {
+                diesel::delete(page_ocr_results::table.filter(page_ocr_results::id.eq_any(chunk)))
+                    .execute(&mut conn)
+                    .await
+            })?;
+        }
+        for chunk in page_ids.chunks(500) {
+            with_write_conn!(self.pool, conn, {
+                diesel::update(document_pages::table.filter(document_pages::id.eq_any(chunk)))
+                    .set((
+                        document_pages::ocr_status.eq(PageOcrStatus::TextExtracted.as_str()),
+                        document_pages::updated_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await
+            })?;
+        }
+        self.mark_ocr_artifacts_stale(&page_ids).await?;
+        Ok(page_ids.len())
+    }
+
+    /// Delete the OCR results of every page of a document version and queue
+    /// the pages for OCR again, failed ones included. Corrected pages keep
+    /// their text. Returns the number of pages queued.
+    pub async fn requeue_version_ocr(
+        &self,
+        document_id: &str,
+        version_id: i32,
+    ) -> Result<usize, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard
+            .requeue_version_ocr(document_id, version_id));
+        let page_ids: Vec<i32> = with_conn!(self.pool, conn, {
+            document_pages::table
+                .filter(document_pages::document_id.eq(document_id))
+                .filter(document_pages::version_id.eq(version_id))
+                .select(document_pages::id)
+                .load(&mut conn)
+                .await
+        })?;
+        if page_ids.is_empty() {
+            return Ok(0);
+        }
+        let now = Utc::now().to_rfc3339();
+
+        for chunk in page_ids.chunks(500) {
+            with_write_conn!(self.pool, conn, {
+                diesel::delete(
+                    page_ocr_results::table.filter(page_ocr_results::page_id.eq_any(chunk)),
+                )
+                .execute(&mut conn)
+                .await
+            })?;
+            with_write_conn!(self.pool, conn, {
+                diesel::update(document_pages::table.filter(document_pages::id.eq_any(chunk)))
+                    .set((
+                        document_pages::ocr_status.eq(PageOcrStatus::TextExtracted.as_str()),
+                        document_pages::updated_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await
+            })?;
+        }
+        self.mark_ocr_artifacts_stale(&page_ids).await?;
+        Ok(page_ids.len())
+    }
+
+    /// Get one page of a document version.
+    pub async fn get_page(
+        &self,
+        document_id: &str,
+        version_id: i32,
+        page_number: i32,
+    ) -> Result<Option<DocumentPage>, DieselError> {
+        let record: Option<DocumentPageRecord> = with_conn!(self.pool, conn, {
+            document_pages::table
+                .filter(document_pages::document_id.eq(document_id))
+                .filter(document_pages::version_id.eq(version_id))
+                .filter(document_pages::page_number.eq(page_number))
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+
+        Ok(record.map(DocumentPage::from))
+    }
+
+    /// Replace a page's text with a curator's correction.
+    ///
+    /// The OCR and PDF text are kept as they were. The page is marked
+    /// corrected, so later OCR runs leave `final_text` alone, artifacts
+    /// built from the version's text are marked stale and the document's
+    /// exemptions are recounted. Returns the updated page, or `None` if it
+    /// doesn't exist.
+    pub async fn correct_page_text(
+        &self,
+        document_id: &str,
+        version_id: i32,
+        page_number: i32,
+        text: &str,
+    ) -> Result<Option<DocumentPage>, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.correct_page_text(
+            document_id,
+            version_id,
+            page_number,
+            text
+        ));
+        let Some(page) = self.get_page(document_id, version_id, page_number).await? else {
+            return Ok(None);
+        };
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(document_pages::table.filter(document_pages::id.eq(page.id as i32)))
+                .set((
+                    document_pages::final_text.eq(text),
+                    document_pages::corrected_at.eq(&now),
+                    document_pages::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        self.mark_ocr_artifacts_stale(&[page.id as i32]).await?;
+        self.mark_current_redactions(document_id).await?;
+        self.get_page(document_id, version_id, page_number).await
+    }
+
+    /// Discard a page's correction, restoring the text chosen from OCR and
+    /// PDF extraction with its redactions marked. Returns the updated page,
+    /// or `None` if it doesn't exist.
+    pub async fn revert_page_correction(
+        &self,
+        document_id: &str,
+        version_id: i32,
+        page_number: i32,
+    ) -> Result<Option<DocumentPage>, DieselError> {
+        route_to_shard!(self, document_id, |shard| shard.revert_page_correction(
+            document_id,
+            version_id,
+            page_number
+        ));
+        let Some(mut page) = self.get_page(document_id, version_id, page_number).await? else {
+            return Ok(None);
+        };
+        if !page.is_corrected() {
+            return Ok(Some(page));
+        }
+        page.compute_final_text();
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(document_pages::table.filter(document_pages::id.eq(page.id as i32)))
+                .set((
+                    document_pages::final_text.eq(&page.final_text),
+                    document_pages::corrected_at.eq(None::<String>),
+                    document_pages::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        self.mark_ocr_artifacts_stale(&[page.id as i32]).await?;
+        self.mark_current_redactions(document_id).await?;
+        self.get_page(document_id, version_id, page_number).await
+    }
+
     /// Delete pages for a document version.
     pub async fn delete_pages(
         &self,
         document_id: &str,
         version_id: i32,
     ) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        route_to_shard!(self, document_id, |shard| shard
+            .delete_pages(document_id, version_id));
+        with_write_conn!(self.pool, conn, {
             diesel::delete(
                 document_pages::table
                     .filter(document_pages::document_id.eq(document_id))
@@ -509,11 +984,15 @@ impl DieselDocumentRepository {
     }
 
     /// Check if all pages are complete.
+    ///
+    /// Large documents' pages are saved in batches, so a version with a
+    /// known page count is only complete once every page has been saved.
     pub async fn are_all_pages_complete(
         &self,
         document_id: &str,
         version_id: i32,
     ) -> Result<bool, DieselError> {
+        use crate::schema::document_versions;
         use diesel::dsl::count_star;
         with_conn!(self.pool, conn, {
             let pending_count: i64 = document_pages::table
@@ -527,7 +1006,27 @@ impl DieselDocumentRepository {
                 .select(count_star())
                 .first(&mut conn)
                 .await?;
-            Ok(pending_count == 0)
+            if pending_count > 0 {
+                return Ok(false);
+            }
+
+            let expected: Option<i32> = document_versions::table
+                .filter(document_versions::id.eq(version_id))
+                .select(document_versions::page_count)
+                .first::<Option<i32>>(&mut conn)
+                .await
+                .optional()?
+                .flatten();
+            let Some(expected) = expected else {
+                return Ok(true);
+            };
+            let saved: i64 = document_pages::table
+                .filter(document_pages::document_id.eq(document_id))
+                .filter(document_pages::version_id.eq(version_id))
+                .select(count_star())
+                .first(&mut conn)
+                .await?;
+            Ok(saved >= expected as i64)
         })
     }
 
@@ -548,6 +1047,34 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Count pages needing OCR per source.
+    pub async fn get_source_pages_needing_ocr(&self) -> Result<HashMap<String, u64>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct SourceCount {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            source_id: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            count: i64,
+        }
+
+        with_conn!(self.pool, conn, {
+            let rows: Vec<SourceCount> = diesel::sql_query(
+                r#"SELECT d.source_id, COUNT(*) as count
+                   FROM document_pages p
+                   JOIN documents d ON d.id = p.document_id
+                   WHERE p.ocr_status IN ('pending', 'text_extracted')
+                   GROUP BY d.source_id"#,
+            )
+            .load(&mut conn)
+            .await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| (row.source_id, row.count as u64))
+                .collect())
+        })
+    }
+
     /// Get pages needing OCR across all documents.
     pub async fn get_all_pages_needing_ocr(
         &self,
@@ -560,6 +1087,12 @@ impl DieselDocumentRepository {
                         .eq("pending")
                         .or(document_pages::ocr_status.eq("text_extracted")),
                 )
+                // Keep each document's pages together so they can be batched
+                .order((
+                    document_pages::document_id.asc(),
+                    document_pages::version_id.asc(),
+                    document_pages::page_number.asc(),
+                ))
                 .limit(limit as i64)
                 .load(&mut conn)
                 .await
@@ -569,22 +1102,37 @@ impl DieselDocumentRepository {
     }
 
     /// Get combined page text for a document.
+    ///
+    /// Uses the OCR text of each page, or the curator's text for pages that
+    /// were corrected by hand.
     pub async fn get_combined_page_text(
         &self,
         document_id: &str,
         version: i32,
     ) -> Result<Option<String>, DieselError> {
-        let texts: Vec<Option<String>> = with_conn!(self.pool, conn, {
-            document_pages::table
-                .filter(document_pages::document_id.eq(document_id))
-                .filter(document_pages::version_id.eq(version))
-                .order(document_pages::page_number.asc())
-                .select(document_pages::ocr_text)
-                .load(&mut conn)
-                .await
-        })?;
+        let texts: Vec<(Option<String>, Option<String>, Option<String>)> =
+            with_conn!(self.pool, conn, {
+                document_pages::table
+                    .filter(document_pages::document_id.eq(document_id))
+                    .filter(document_pages::version_id.eq(version))
+                    .order(document_pages::page_number.asc())
+                    .select((
+                        document_pages::ocr_text,
+                        document_pages::final_text,
+                        document_pages::corrected_at,
+                    ))
+                    .load(&mut conn)
+                    .await
+            })?;
 
-        let combined: String = texts.into_iter().flatten().collect::<Vec<_>>().join("\n\n");
+        let combined: String = texts
+            .into_iter()
+            .filter_map(|(ocr_text, final_text, corrected_at)| match corrected_at {
+                Some(_) => final_text,
+                None => ocr_text.map(decompress_page_text),
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
 
         if combined.is_empty() {
             Ok(None)
@@ -593,70 +1141,163 @@ impl DieselDocumentRepository {
         }
     }
 
-    /// Full-text search on page content.
+    /// Full-text search on page content, one keyset page at a time.
     ///
     /// Postgres: uses `tsvector`/`tsquery` for ranked full-text search with headline snippets.
-    /// SQLite: falls back to LIKE matching (no headlines).
+    /// SQLite: matches words through the page search index, or LIKE when
+    /// that's unavailable (no headlines, no ranking).
+    ///
+    /// Results are ordered by rank, then document and page number. Only
+    /// forward paging is supported; `before` cursors are ignored.
     pub async fn search_page_content(
         &self,
         query: &str,
         source_id: Option<&str>,
         document_id: Option<&str>,
+        cursor: Option<&PageCursor>,
         limit: usize,
-        offset: usize,
-    ) -> Result<Vec<PageSearchRow>, DieselError> {
-        let like_pattern = format!("%{query}%");
+    ) -> Result<Page<PageSearchRow>, DieselError> {
+        let cursor = cursor.filter(|c| !c.before);
+        let cursor_rank: Option<f32> = cursor.and_then(|c| c.key(0)?.parse().ok());
+        let cursor_doc = cursor.and_then(|c| c.key(1));
+        let cursor_page: Option<i32> = cursor.and_then(|c| c.key(2)?.parse().ok());
+        let fetch = limit + 1;
 
-        with_conn_split!(self.pool,
+        let rows: Vec<PageSearchRow> = with_conn_split!(self.pool,
             sqlite: conn => {
+                let (text_filter, pattern) = sqlite_text_filter(&mut conn, query, "?").await?;
                 diesel::sql_query(format!(
                     r#"SELECT dp.document_id, d.title, d.source_id, dp.page_number,
                               '' AS headline,
                               dv.content_hash, dv.mime_type AS version_mime_type,
-                              dv.original_filename, dv.dedup_index, d.source_url
+                              dv.original_filename, dv.dedup_index, d.source_url,
+                              0.0 AS rank
                        FROM document_pages dp
                        JOIN documents d ON d.id = dp.document_id
                        JOIN document_versions dv ON dv.id = dp.version_id
-                       WHERE COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') LIKE ?
+                       WHERE {text_filter}
                          AND (? IS NULL OR d.source_id = ?)
                          AND (? IS NULL OR dp.document_id = ?)
+                         AND (? IS NULL OR dp.document_id > ?
+                              OR (dp.document_id = ? AND dp.page_number > ?))
                        ORDER BY dp.document_id, dp.page_number
-                       LIMIT {limit} OFFSET {offset}"#
+                       LIMIT {fetch}"#
                 ))
-                .bind::<diesel::sql_types::Text, _>(&like_pattern)
+                .bind::<diesel::sql_types::Text, _>(&pattern)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(document_id)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(document_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_doc)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_doc)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_doc)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(cursor_page)
                 .load::<PageSearchRow>(&mut conn)
-                .await
+                .await?
             },
             postgres: conn => {
+                // Rank in a subquery so the cursor can seek on it, and only
+                // build headlines for the rows actually returned.
                 diesel::sql_query(format!(
-                    r#"SELECT dp.document_id, d.title, d.source_id, dp.page_number,
-                              ts_headline('english',
-                                          COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, ''),
+                    r#"SELECT m.document_id, m.title, m.source_id, m.page_number,
+                              ts_headline('english', m.body,
                                           plainto_tsquery('english', $1),
                                           'MaxFragments=3, MaxWords=30, MinWords=10') AS headline,
-                              dv.content_hash, dv.mime_type AS version_mime_type,
-                              dv.original_filename, dv.dedup_index, d.source_url
+                              m.content_hash, m.version_mime_type,
+                              m.original_filename, m.dedup_index, m.source_url, m.rank
+                       FROM (
+                           SELECT dp.document_id, d.title, d.source_id, dp.page_number,
+                                  COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') AS body,
+                                  dv.content_hash, dv.mime_type AS version_mime_type,
+                                  dv.original_filename, dv.dedup_index, d.source_url,
+                                  ts_rank(
+                                      to_tsvector('english', COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '')),
+                                      plainto_tsquery('english', $1)) AS rank
+                           FROM document_pages dp
+                           JOIN documents d ON d.id = dp.document_id
+                           JOIN document_versions dv ON dv.id = dp.version_id
+                           WHERE to_tsvector('english', COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, ''))
+                                 @@ plainto_tsquery('english', $1)
+                             AND ($2::text IS NULL OR d.source_id = $2)
+                             AND ($3::text IS NULL OR dp.document_id = $3)
+                       ) m
+                       WHERE ($4::real IS NULL OR m.rank < $4
+                              OR (m.rank = $4 AND (m.document_id > $5
+                                  OR (m.document_id = $5 AND m.page_number > $6))))
+                       ORDER BY m.rank DESC, m.document_id, m.page_number
+                       LIMIT {fetch}"#
+                ))
+                .bind::<diesel::sql_types::Text, _>(query)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(document_id)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(cursor_rank)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_doc)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(cursor_page)
+                .load::<PageSearchRow>(&mut conn)
+                .await?
+            }
+        );
+
+        Ok(Page::from_rows(rows, limit, cursor, |r| {
+            vec![
+                r.rank.to_string(),
+                r.document_id.clone(),
+                r.page_number.to_string(),
+            ]
+        }))
+    }
+
+    /// Pages of current document versions whose text contains `term`,
+    /// with their text.
+    ///
+    /// Postgres ranks matches with `ts_rank`; SQLite uses the page search
+    /// index and returns them in document order.
+    pub async fn pages_containing(
+        &self,
+        term: &str,
+        source_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<PagePassageRow>, DieselError> {
+        with_conn_split!(self.pool,
+            sqlite: conn => {
+                let (text_filter, pattern) = sqlite_text_filter(&mut conn, term, "$1").await?;
+                diesel::sql_query(format!(
+                    r#"SELECT dp.document_id, d.title, dp.page_number,
+                              COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') AS text
+                       FROM document_pages dp
+                       JOIN documents d ON d.id = dp.document_id
+                       WHERE {text_filter}
+                         AND ($2 IS NULL OR d.source_id = $2)
+                         AND dp.version_id = (SELECT MAX(dv.id) FROM document_versions dv
+                                              WHERE dv.document_id = dp.document_id)
+                       ORDER BY dp.document_id, dp.page_number
+                       LIMIT {limit}"#
+                ))
+                .bind::<diesel::sql_types::Text, _>(&pattern)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
+                .load::<PagePassageRow>(&mut conn)
+                .await
+            },
+            postgres: conn => {
+                diesel::sql_query(format!(
+                    r#"SELECT dp.document_id, d.title, dp.page_number,
+                              COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') AS text
                        FROM document_pages dp
                        JOIN documents d ON d.id = dp.document_id
-                       JOIN document_versions dv ON dv.id = dp.version_id
                        WHERE to_tsvector('english', COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, ''))
                              @@ plainto_tsquery('english', $1)
                          AND ($2::text IS NULL OR d.source_id = $2)
-                         AND ($3::text IS NULL OR dp.document_id = $3)
+                         AND dp.version_id = (SELECT MAX(dv.id) FROM document_versions dv
+                                              WHERE dv.document_id = dp.document_id)
                        ORDER BY ts_rank(
-                                  to_tsvector('english', COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '')),
-                                  plainto_tsquery('english', $1)) DESC,
+                                    to_tsvector('english', COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '')),
+                                    plainto_tsquery('english', $1)) DESC,
                                 dp.document_id, dp.page_number
-                       LIMIT {limit} OFFSET {offset}"#
+                       LIMIT {limit}"#
                 ))
-                .bind::<diesel::sql_types::Text, _>(query)
+                .bind::<diesel::sql_types::Text, _>(term)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
-                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(document_id)
-                .load::<PageSearchRow>(&mut conn)
+                .load::<PagePassageRow>(&mut conn)
                 .await
             }
         )
@@ -669,19 +1310,18 @@ impl DieselDocumentRepository {
         source_id: Option<&str>,
         document_id: Option<&str>,
     ) -> Result<u64, DieselError> {
-        let like_pattern = format!("%{query}%");
-
         with_conn_split!(self.pool,
             sqlite: conn => {
-                let result: Vec<CountRow> = diesel::sql_query(
+                let (text_filter, pattern) = sqlite_text_filter(&mut conn, query, "?").await?;
+                let result: Vec<CountRow> = diesel::sql_query(format!(
                     r#"SELECT COUNT(*) AS count
                        FROM document_pages dp
                        JOIN documents d ON d.id = dp.document_id
-                       WHERE COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') LIKE ?
+                       WHERE {text_filter}
                          AND (? IS NULL OR d.source_id = ?)
-                         AND (? IS NULL OR dp.document_id = ?)"#,
-                )
-                .bind::<diesel::sql_types::Text, _>(&like_pattern)
+                         AND (? IS NULL OR dp.document_id = ?)"#
+                ))
+                .bind::<diesel::sql_types::Text, _>(&pattern)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(document_id)
@@ -0,0 +1,337 @@
+//! Embedded PDF metadata.
+//!
+//! Author, software and dates from each PDF's Info dictionary and XMP
+//! packet are kept in `document_pdf_metadata`, which backs the producer and
+//! creation year browse facets and the "created after its date" flag, and
+//! copied under `pdf` into the document's metadata.
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::{parse_pdf_date, PdfMetadata};
+use crate::repository::models::DocumentPdfMetadataRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_pdf_metadata, documents};
+use crate::{with_conn, with_write_conn};
+
+/// SQL restricting `documents` to PDFs created in any of `years`, or, with
+/// `postdated`, created after the date the document claims to be from.
+pub(super) fn pdf_date_filter(
+    years: &[i32],
+    postdated: bool,
+) -> Option<diesel::expression::SqlLiteral<diesel::sql_types::Bool>> {
+    let mut conditions = Vec::new();
+    if !years.is_empty() {
+        let years: Vec<String> = years.iter().map(|y| format!("'{:04}'", y)).collect();
+        conditions.push(format!(
+            "substr(pm.created_at, 1, 4) IN ({})",
+            years.join(", ")
+        ));
+    }
+    if postdated {
+        conditions.push(
+            "substr(pm.created_at, 1, 10) > \
+             substr(COALESCE(documents.manual_date, documents.document_date), 1, 10)"
+                .to_string(),
+        );
+    }
+    if conditions.is_empty() {
+        return None;
+    }
+    Some(diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+        "EXISTS (SELECT 1 FROM document_pdf_metadata pm WHERE pm.document_id = documents.id \
+         AND {})",
+        conditions.join(" AND ")
+    )))
+}
+
+/// How many documents share a PDF producer or creation year.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfFacetCount<T> {
+    pub value: T,
+    pub documents: u64,
+}
+
+impl DieselDocumentRepository {
+    /// Record a document's embedded PDF metadata, replacing any from an
+    /// earlier version, and copy it under `pdf` into the document metadata.
+    pub async fn set_pdf_metadata(
+        &self,
+        document_id: &str,
+        pdf: &PdfMetadata,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, document_id, |shard| shard
+            .set_pdf_metadata(document_id, pdf));
+        let now = Utc::now().to_rfc3339();
+        let record = DocumentPdfMetadataRecord {
+            document_id: document_id.to_string(),
+            author: pdf.author.clone(),
+            creator: pdf.creator.clone(),
+            producer: pdf.producer.clone(),
+            created_at: pdf.created.map(|d| d.to_rfc3339()),
+            modified_at: pdf.modified.map(|d| d.to_rfc3339()),
+            updated_at: now.clone(),
+        };
+        with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                document_pdf_metadata::table
+                    .filter(document_pdf_metadata::document_id.eq(document_id)),
+            )
+            .execute(&mut conn)
+            .await?;
+            diesel::insert_into(document_pdf_metadata::table)
+                .values(&record)
+                .execute(&mut conn)
+                .await?;
+            Ok::<(), DieselError>(())
+        })?;
+
+        let metadata: Option<String> = with_conn!(self.pool, conn, {
+            documents::table
+                .find(document_id)
+                .select(documents::metadata)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        let Some(metadata) = metadata else {
+            return Ok(());
+        };
+        let mut metadata: serde_json::Value =
+            serde_json::from_str(&metadata).unwrap_or(serde_json::json!({}));
+        if !metadata.is_object() {
+            metadata = serde_json::json!({});
+        }
+        metadata["pdf"] = serde_json::to_value(pdf).unwrap_or_default();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(documents::table.find(document_id))
+                .set((
+                    documents::metadata.eq(metadata.to_string()),
+                    documents::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        Ok(())
+    }
+
+    /// A document's embedded PDF metadata, if it has been read. The title
+    /// isn't kept here; see the document's own.
+    pub async fn get_pdf_metadata(
+        &self,
+        document_id: &str,
+    ) -> Result<Option<PdfMetadata>, DieselError> {
+        let record: Option<DocumentPdfMetadataRecord> = with_conn!(self.pool, conn, {
+            document_pdf_metadata::table
+                .find(document_id)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        Ok(record.map(|r| PdfMetadata {
+            title: None,
+            author: r.author,
+            creator: r.creator,
+            producer: r.producer,
+            created: r.created_at.as_deref().and_then(parse_pdf_date),
+            modified: r.modified_at.as_deref().and_then(parse_pdf_date),
+        }))
+    }
+
+    /// Most common PDF producers, optionally within one source.
+    pub async fn get_pdf_producer_counts(
+        &self,
+        source_id: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<PdfFacetCount<String>>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct ProducerRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            producer: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            documents: i64,
+        }
+
+        let query = format!(
+            "SELECT pm.producer, COUNT(*) AS documents \
+             FROM document_pdf_metadata pm \
+             JOIN documents d ON d.id = pm.document_id \
+             WHERE pm.producer IS NOT NULL {} \
+             GROUP BY pm.producer \
+             ORDER BY documents DESC, pm.producer \
+             LIMIT {}",
+            if source_id.is_some() {
+                "AND d.source_id = $1"
+            } else {
+                ""
+            },
+            limit
+        );
+        let rows: Vec<ProducerRow> = with_conn!(self.pool, conn, {
+            match source_id {
+                Some(sid) => {
+                    diesel::sql_query(&query)
+                        .bind::<diesel::sql_types::Text, _>(sid)
+                        .load(&mut conn)
+                        .await
+                }
+                None => diesel::sql_query(&query).load(&mut conn).await,
+            }
+        })?;
+        Ok(rows
+            .into_iter()
+            .map(|row| PdfFacetCount {
+                value: row.producer,
+                documents: row.documents.max(0) as u64,
+            })
+            .collect())
+    }
+
+    /// Documents per PDF creation year, newest first, optionally within one
+    /// source.
+    pub async fn get_pdf_year_counts(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<PdfFacetCount<i32>>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct YearRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            year: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            documents: i64,
+        }
+
+        let query = format!(
+            "SELECT substr(pm.created_at, 1, 4) AS year, COUNT(*) AS documents \
+             FROM document_pdf_metadata pm \
+             JOIN documents d ON d.id = pm.document_id \
+             WHERE pm.created_at IS NOT NULL {} \
+             GROUP BY substr(pm.created_at, 1, 4) \
+             ORDER BY year DESC",
+            if source_id.is_some() {
+                "AND d.source_id = $1"
+            } else {
+                ""
+            }
+        );
+        let rows: Vec<YearRow> = with_conn!(self.pool, conn, {
+            match source_id {
+                Some(sid) => {
+                    diesel::sql_query(&query)
+                        .bind::<diesel::sql_types::Text, _>(sid)
+                        .load(&mut conn)
+                        .await
+                }
+                None => diesel::sql_query(&query).load(&mut conn).await,
+            }
+        })?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                Some(PdfFacetCount {
+                    value: row.year.parse().ok()?,
+                    documents: row.documents.max(0) as u64,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentVersion, ExtractedMetadata};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::diesel_document::BrowseParams;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    #[tokio::test]
+    async fn test_pdf_metadata_facets() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+
+        for (id, date, producer, created) in [
+            (
+                "memo",
+                "2009-05-01",
+                "Adobe PDF Library 15.0",
+                "2021-06-01T09:30:00Z",
+            ),
+            ("scan", "2009-05-01", "Canon iR-ADV", "2009-05-02T00:00:00Z"),
+            (
+                "letter",
+                "2021-01-01",
+                "Adobe PDF Library 15.0",
+                "2020-12-30T00:00:00Z",
+            ),
+        ] {
+            let version = DocumentVersion::new(id.as_bytes(), "application/pdf".to_string(), None);
+            let doc = Document::new(
+                id.to_string(),
+                "fbi".to_string(),
+                format!("Memo {}", id),
+                format!("https://example.gov/{}.pdf", id),
+                version,
+                serde_json::json!({"listed": true}),
+            );
+            repo.save_with_versions(&doc).await.unwrap();
+            let extracted = ExtractedMetadata {
+                document_date: chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+                ..Default::default()
+            };
+            repo.update_extracted_metadata(id, &extracted)
+                .await
+                .unwrap();
+            let pdf = PdfMetadata {
+                producer: Some(producer.to_string()),
+                created: parse_pdf_date(created),
+                ..Default::default()
+            };
+            repo.set_pdf_metadata(id, &pdf).await.unwrap();
+        }
+
+        let memo = repo.get_pdf_metadata("memo").await.unwrap().unwrap();
+        assert_eq!(memo.producer.as_deref(), Some("Adobe PDF Library 15.0"));
+        let doc = repo.get("memo").await.unwrap().unwrap();
+        assert_eq!(doc.metadata["listed"], true);
+        assert_eq!(doc.metadata["pdf"]["producer"], "Adobe PDF Library 15.0");
+
+        let producers = repo.get_pdf_producer_counts(None, 10).await.unwrap();
+        assert_eq!(producers[0].value, "Adobe PDF Library 15.0");
+        assert_eq!(producers[0].documents, 2);
+        let years = repo.get_pdf_year_counts(Some("fbi")).await.unwrap();
+        let years: Vec<(i32, u64)> = years.iter().map(|y| (y.value, y.documents)).collect();
+        assert_eq!(years, vec![(2021, 1), (2020, 1), (2009, 1)]);
+
+        let adobe = vec!["Adobe PDF Library 15.0".to_string()];
+        let params = BrowseParams {
+            pdf_producers: &adobe,
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&params).await.unwrap(), 2);
+        let params = BrowseParams {
+            pdf_years: &[2009, 2020],
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&params).await.unwrap(), 2);
+        // The memo and the scan were made after their dates, the letter before
+        let params = BrowseParams {
+            pdf_postdated: true,
+            ..Default::default()
+        };
+        assert_eq!(repo.browse_count(&params).await.unwrap(), 2);
+    }
+}
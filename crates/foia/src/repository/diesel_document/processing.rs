@@ -0,0 +1,281 @@
+//! Processing state filters.
+//!
+//! Where a document stands in text extraction and analysis, worked out from
+//! its latest version's OCR results and pages, so the unprocessed backlog
+//! can be browsed and fixed.
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::analysis::AnalysisResultStatus;
+use super::queries::poor_ocr_filter;
+use super::DieselDocumentRepository;
+use crate::models::{DocumentStatus, PageOcrStatus};
+use crate::repository::pool::DieselError;
+use crate::schema::documents;
+use crate::utils::EXTRACTABLE_MIME_TYPES;
+use crate::with_conn;
+
+/// A document's processing state, as filtered on by browse and the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcessingState {
+    /// Text extraction hasn't run on the latest version yet.
+    NeedsOcr,
+    /// Text extraction failed on the latest version, or on some of its pages.
+    OcrFailed,
+    /// Text extracted, waiting for summarization.
+    NeedsSummary,
+    /// Some pages' OCR scored poorly.
+    PoorOcr,
+    /// The latest version's type can't be extracted.
+    Unsupported,
+}
+
+impl ProcessingState {
+    pub const ALL: [ProcessingState; 5] = [
+        Self::NeedsOcr,
+        Self::OcrFailed,
+        Self::NeedsSummary,
+        Self::PoorOcr,
+        Self::Unsupported,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NeedsOcr => "needs_ocr",
+            Self::OcrFailed => "ocr_failed",
+            Self::NeedsSummary => "needs_summary",
+            Self::PoorOcr => "poor_ocr",
+            Self::Unsupported => "unsupported",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|state| state.as_str() == s)
+    }
+
+    /// Name shown in the browse filter.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::NeedsOcr => "Needs OCR",
+            Self::OcrFailed => "OCR failed",
+            Self::NeedsSummary => "Needs summarization",
+            Self::PoorOcr => "Poor OCR quality",
+            Self::Unsupported => "Unsupported type",
+        }
+    }
+}
+
+/// SQL for the ID of a document's latest version.
+const LATEST_VERSION_EXPR: &str =
+    "(SELECT MAX(lv.id) FROM document_versions lv WHERE lv.document_id = documents.id)";
+
+/// SQL testing for an OCR result with `status` on the latest version.
+fn ocr_result_exists(status: AnalysisResultStatus) -> String {
+    format!(
+        "EXISTS (SELECT 1 FROM document_analysis_results dar \
+         WHERE dar.document_id = documents.id AND dar.version_id = {} \
+         AND dar.analysis_type = 'ocr' AND dar.status = '{}')",
+        LATEST_VERSION_EXPR,
+        status.as_str()
+    )
+}
+
+/// SQL testing whether extraction failed on the latest version, or on any
+/// of its pages.
+fn ocr_failed_condition() -> String {
+    format!(
+        "({} OR EXISTS (SELECT 1 FROM document_pages fp \
+         WHERE fp.document_id = documents.id AND fp.version_id = {} \
+         AND fp.ocr_status = '{}'))",
+        ocr_result_exists(AnalysisResultStatus::Failed),
+        LATEST_VERSION_EXPR,
+        PageOcrStatus::Failed.as_str()
+    )
+}
+
+/// SQL testing whether the latest version's type can be extracted.
+fn extractable_condition() -> String {
+    let types: Vec<String> = EXTRACTABLE_MIME_TYPES
+        .iter()
+        .map(|t| format!("'{}'", t))
+        .collect();
+    format!(
+        "(SELECT lv.mime_type FROM document_versions lv WHERE lv.document_id = documents.id \
+         ORDER BY lv.id DESC LIMIT 1) IN ({})",
+        types.join(", ")
+    )
+}
+
+/// Build a filter restricting `documents` to those in `state`. Only
+/// constants are formatted into the SQL.
+pub(super) fn processing_filter(
+    state: ProcessingState,
+) -> diesel::expression::SqlLiteral<diesel::sql_types::Bool> {
+    let condition = match state {
+        ProcessingState::NeedsOcr => format!(
+            "documents.status <> '{}' AND {} AND NOT {} AND NOT {}",
+            DocumentStatus::Failed.as_str(),
+            extractable_condition(),
+            ocr_result_exists(AnalysisResultStatus::Complete),
+            ocr_failed_condition(),
+        ),
+        ProcessingState::OcrFailed => format!(
+            "NOT {} AND {}",
+            ocr_result_exists(AnalysisResultStatus::Complete),
+            ocr_failed_condition(),
+        ),
+        ProcessingState::NeedsSummary => format!(
+            "documents.status = '{}'",
+            DocumentStatus::OcrComplete.as_str()
+        ),
+        ProcessingState::PoorOcr => return poor_ocr_filter(),
+        ProcessingState::Unsupported => format!(
+            "EXISTS (SELECT 1 FROM document_versions uv WHERE uv.document_id = documents.id) \
+             AND NOT {}",
+            extractable_condition()
+        ),
+    };
+    diesel::dsl::sql::<diesel::sql_types::Bool>(&condition)
+}
+
+impl DieselDocumentRepository {
+    /// Count documents in each processing state, optionally in one source.
+    pub async fn count_by_processing_state(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<(ProcessingState, u64)>, DieselError> {
+        use diesel::dsl::count_star;
+
+        let mut counts = Vec::with_capacity(ProcessingState::ALL.len());
+        for state in ProcessingState::ALL {
+            let count: i64 = with_conn!(self.pool, conn, {
+                let mut query = documents::table
+                    .select(count_star())
+                    .filter(processing_filter(state))
+                    .into_boxed();
+                if let Some(sid) = source_id {
+                    query = query.filter(documents::source_id.eq(sid));
+                }
+                query.first(&mut conn).await
+            })?;
+            counts.push((state, count as u64));
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentPage, DocumentVersion};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::diesel_document::BrowseParams;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    fn document(id: &str, mime_type: &str) -> Document {
+        Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            id.to_string(),
+            format!("https://vault.fbi.gov/{}", id),
+            DocumentVersion::new(id.as_bytes(), mime_type.to_string(), None),
+            serde_json::json!({}),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_processing_filters() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let docs = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .documents();
+
+        for (id, mime_type) in [
+            ("new.pdf", "application/pdf"),
+            ("done.pdf", "application/pdf"),
+            ("broken.pdf", "application/pdf"),
+            ("video.mp4", "video/mp4"),
+        ] {
+            docs.save_with_versions(&document(id, mime_type))
+                .await
+                .unwrap();
+        }
+        let version_id = |doc: &Document| doc.current_version().unwrap().id as i32;
+
+        let done = docs.get("done.pdf").await.unwrap().unwrap();
+        docs.store_analysis_result_for_document(
+            &done.id,
+            version_id(&done),
+            "ocr",
+            "pipeline",
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        docs.transition_status(&done.id, DocumentStatus::OcrComplete)
+            .await
+            .unwrap();
+
+        let broken = docs.get("broken.pdf").await.unwrap().unwrap();
+        let mut page = DocumentPage::new(broken.id.clone(), version_id(&broken) as i64, 1);
+        page.ocr_status = PageOcrStatus::Failed;
+        docs.save_page(&page).await.unwrap();
+
+        let matching = |state| {
+            let docs = docs.clone();
+            async move {
+                let filter = BrowseParams {
+                    processing: Some(state),
+                    limit: 10,
+                    ..Default::default()
+                };
+                let mut ids: Vec<String> = docs
+                    .browse_fast(&filter)
+                    .await
+                    .unwrap()
+                    .items
+                    .into_iter()
+                    .map(|row| row.id)
+                    .collect();
+                ids.sort();
+                ids
+            }
+        };
+        assert_eq!(matching(ProcessingState::NeedsOcr).await, vec!["new.pdf"]);
+        assert_eq!(
+            matching(ProcessingState::OcrFailed).await,
+            vec!["broken.pdf"]
+        );
+        assert_eq!(
+            matching(ProcessingState::NeedsSummary).await,
+            vec!["done.pdf"]
+        );
+        assert_eq!(
+            matching(ProcessingState::Unsupported).await,
+            vec!["video.mp4"]
+        );
+
+        let counts = docs.count_by_processing_state(Some("fbi")).await.unwrap();
+        assert!(counts.contains(&(ProcessingState::NeedsOcr, 1)));
+        assert!(counts.contains(&(ProcessingState::PoorOcr, 0)));
+    }
+
+    #[test]
+    fn test_processing_state_names() {
+        for state in ProcessingState::ALL {
+            assert_eq!(ProcessingState::from_str(state.as_str()), Some(state));
+        }
+        assert_eq!(ProcessingState::from_str("ocr"), None);
+    }
+}
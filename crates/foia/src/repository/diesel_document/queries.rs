@@ -2,17 +2,22 @@
 
 use std::collections::HashMap;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use diesel::prelude::*;
 use diesel_async::RunQueryDsl;
 
+use super::exemptions::exemption_filter;
+use super::locations::bbox_filter;
+use super::pdf_metadata::pdf_date_filter;
+use super::processing::{processing_filter, ProcessingState};
 use super::{CountRow, DieselDocumentRepository, DocIdRow, MimeCount, TagRow};
-use crate::models::{Document, DocumentStatus};
+use crate::models::{BoundingBox, Document, DocumentStatus, ExtractedMetadata, POOR_OCR_QUALITY};
+use crate::repository::cursor::{Page, PageCursor};
 use crate::repository::document::DocumentNavigation;
 use crate::repository::models::DocumentRecord;
 use crate::repository::pool::DieselError;
-use crate::schema::documents;
-use crate::{with_conn, with_conn_split};
+use crate::schema::{document_pdf_metadata, documents};
+use crate::{with_conn, with_conn_split, with_write_conn};
 
 /// Validate that a string only contains safe identifier characters (alphanumeric + underscore).
 ///
@@ -27,6 +32,189 @@ fn validate_identifier(s: &str) -> Result<(), DieselError> {
     Ok(())
 }
 
+/// SQL expression for a document's position on the timeline: the manual or
+/// extracted publication date when known, otherwise when its latest version
+/// was acquired.
+pub(super) const TIMELINE_DATE_EXPR: &str =
+    "COALESCE(documents.manual_date, documents.document_date, \
+     documents.estimated_date, (SELECT MAX(dv.acquired_at) FROM document_versions dv \
+     WHERE dv.document_id = documents.id))";
+
+/// Build a filter restricting documents to an inclusive timeline date range.
+///
+/// The bounds are typed dates, so formatting them into the SQL is safe.
+pub(super) fn timeline_range_filter(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+) -> Option<diesel::expression::SqlLiteral<diesel::sql_types::Bool>> {
+    let mut conditions = Vec::new();
+    if let Some(from) = from {
+        conditions.push(format!(
+            "date({}) >= '{}'",
+            TIMELINE_DATE_EXPR,
+            from.format("%Y-%m-%d")
+        ));
+    }
+    if let Some(to) = to {
+        conditions.push(format!(
+            "date({}) <= '{}'",
+            TIMELINE_DATE_EXPR,
+            to.format("%Y-%m-%d")
+        ));
+    }
+    if conditions.is_empty() {
+        None
+    } else {
+        Some(diesel::dsl::sql::<diesel::sql_types::Bool>(
+            &conditions.join(" AND "),
+        ))
+    }
+}
+
+/// Build a filter restricting documents to those with at least one page
+/// whose OCR scored below [`POOR_OCR_QUALITY`].
+pub(super) fn poor_ocr_filter() -> diesel::expression::SqlLiteral<diesel::sql_types::Bool> {
+    diesel::dsl::sql::<diesel::sql_types::Bool>(&format!(
+        "EXISTS (SELECT 1 FROM document_pages dp WHERE dp.document_id = documents.id \
+         AND dp.ocr_quality < {})",
+        POOR_OCR_QUALITY
+    ))
+}
+
+/// Order a boxed `documents` query by `$col` with `documents::id` as the
+/// tie-breaker, seeking past `$cursor` (keys: `[col value, id]`) if given.
+/// `$parse` turns the cursor key into the column's type (default: text).
+///
+/// Paging backwards flips the fetch order; `Page::from_rows` restores it.
+macro_rules! keyset_order {
+    ($query:ident, $col:expr, $desc:expr, $cursor:expr) => {
+        keyset_order!($query, $col, $desc, $cursor, |k: &str| k.to_string())
+    };
+    ($query:ident, $col:expr, $desc:expr, $cursor:expr, $parse:expr) => {{
+        let cursor: Option<&PageCursor> = $cursor;
+        let fetch_desc = $desc != cursor.is_some_and(|c| c.before);
+        if let Some(c) = cursor {
+            let key = ($parse)(c.key(0).unwrap_or_default());
+            let id = c.key(1).unwrap_or_default().to_string();
+            $query = if fetch_desc {
+                $query.filter(
+                    $col.lt(key.clone())
+                        .or($col.eq(key).and(documents::id.lt(id))),
+                )
+            } else {
+                $query.filter(
+                    $col.gt(key.clone())
+                        .or($col.eq(key).and(documents::id.gt(id))),
+                )
+            };
+        }
+        $query = if fetch_desc {
+            $query
+                .order($col.desc())
+                .then_order_by(documents::id.desc())
+        } else {
+            $query.order($col.asc()).then_order_by(documents::id.asc())
+        };
+    }};
+}
+
+/// A value of the document's latest version, for sorting listings.
+/// Served by `idx_versions_document_latest`.
+fn latest_version_expr<T>(column: &str, default: &str) -> diesel::expression::SqlLiteral<T>
+where
+    T: diesel::expression::TypedExpressionType,
+{
+    diesel::dsl::sql::<T>(&format!(
+        "COALESCE((SELECT lv.{} FROM document_versions lv WHERE lv.document_id = documents.id \
+         ORDER BY lv.id DESC LIMIT 1), {})",
+        column, default
+    ))
+}
+
+/// Document date for sorting, with undated documents first in ascending
+/// order. Served by `idx_documents_document_date_sort`.
+fn document_date_sort_expr() -> diesel::expression::SqlLiteral<diesel::sql_types::Text> {
+    diesel::dsl::sql::<diesel::sql_types::Text>("COALESCE(documents.document_date, '')")
+}
+
+/// Sort order for document listings (`BrowseParams::sort_field` in
+/// [`DieselDocumentRepository::browse_fast`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocumentSort {
+    /// Most recently updated first.
+    #[default]
+    Updated,
+    Title,
+    /// Latest version's file size.
+    Size,
+    /// When the latest version was acquired.
+    Acquired,
+    /// The document's own date, as extracted from its content.
+    DocumentDate,
+    /// Latest version's page count.
+    PageCount,
+    /// Processing status (`pending`, `downloaded`, `ocr_complete`, ...).
+    OcrStatus,
+}
+
+impl DocumentSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Updated => "updated",
+            Self::Title => "title",
+            Self::Size => "size",
+            Self::Acquired => "acquired",
+            Self::DocumentDate => "date",
+            Self::PageCount => "pages",
+            Self::OcrStatus => "status",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "updated" => Some(Self::Updated),
+            "title" => Some(Self::Title),
+            "size" => Some(Self::Size),
+            "acquired" => Some(Self::Acquired),
+            "date" => Some(Self::DocumentDate),
+            "pages" => Some(Self::PageCount),
+            "status" => Some(Self::OcrStatus),
+            _ => None,
+        }
+    }
+
+    /// Whether this sort runs descending when no order is given: dates and
+    /// sizes biggest first, names alphabetically.
+    pub fn default_desc(&self) -> bool {
+        !matches!(self, Self::Title | Self::OcrStatus)
+    }
+
+    /// Resolve `sort`/`order` parameters; unknown values fall back to the
+    /// defaults.
+    pub fn resolve(sort: Option<&str>, order: Option<&str>) -> (Self, bool) {
+        let sort = sort.and_then(Self::from_str).unwrap_or_default();
+        let desc = match order {
+            Some(o) if o.eq_ignore_ascii_case("asc") => false,
+            Some(o) if o.eq_ignore_ascii_case("desc") => true,
+            _ => sort.default_desc(),
+        };
+        (sort, desc)
+    }
+
+    /// Cursor key for a row; must match the SQL sort expression.
+    fn key(&self, row: &super::BrowseRow) -> String {
+        match self {
+            Self::Updated => row.updated_at.clone(),
+            Self::Title => row.title.clone(),
+            Self::Size => row.file_size.to_string(),
+            Self::Acquired => row.acquired_at.clone(),
+            Self::DocumentDate => row.document_date.clone().unwrap_or_default(),
+            Self::PageCount => row.page_count.unwrap_or(0).to_string(),
+            Self::OcrStatus => row.status.clone(),
+        }
+    }
+}
+
 /// Parameters for browsing/filtering documents.
 #[derive(Debug, Default, Clone)]
 pub struct BrowseParams<'a> {
@@ -34,11 +222,31 @@ pub struct BrowseParams<'a> {
     pub status: Option<&'a str>,
     pub categories: &'a [String],
     pub tags: &'a [String],
+    pub record_types: &'a [String],
+    /// Only documents with poorly recognized pages.
+    pub poor_ocr: bool,
+    /// Only documents citing any of these exemption codes, e.g. `(b)(6)`.
+    pub exemptions: &'a [String],
+    /// Only PDFs written by any of these producers.
+    pub pdf_producers: &'a [String],
+    /// Only PDFs created in any of these years.
+    pub pdf_years: &'a [i32],
+    /// Only PDFs created after the date the document claims to be from.
+    pub pdf_postdated: bool,
+    /// Only documents in this processing state.
+    pub processing: Option<ProcessingState>,
+    /// Earliest timeline date (inclusive); see `get_timeline_buckets`.
+    pub date_from: Option<NaiveDate>,
+    /// Latest timeline date (inclusive).
+    pub date_to: Option<NaiveDate>,
+    /// Only documents placed within this box on the map.
+    pub bbox: Option<BoundingBox>,
     pub search_query: Option<&'a str>,
     pub sort_field: Option<&'a str>,
     pub sort_order: Option<&'a str>,
     pub limit: u32,
-    pub offset: u32,
+    /// Resume from a cursor returned with a previous page.
+    pub cursor: Option<PageCursor>,
 }
 
 impl DieselDocumentRepository {
@@ -75,12 +283,34 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Count documents added since `since`, per source.
+    pub async fn get_source_counts_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<HashMap<String, u64>, DieselError> {
+        use diesel::dsl::count_star;
+        let since = since.to_rfc3339();
+        with_conn!(self.pool, conn, {
+            let rows: Vec<(String, i64)> = documents::table
+                .filter(documents::created_at.ge(&since))
+                .group_by(documents::source_id)
+                .select((documents::source_id, count_star()))
+                .load(&mut conn)
+                .await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|(id, count)| (id, count as u64))
+                .collect())
+        })
+    }
+
     /// Count documents needing a specific analysis type.
     ///
     /// A document needs analysis when:
     /// - No `complete` result exists in `document_analysis_results` for the type
     /// - No `failed` result exists within the retry window
-    /// - No `pending` result exists within 90 minutes (worker lock)
+    /// - No `pending` result exists within the claim lease (worker lock)
     pub async fn count_needing_analysis(
         &self,
         analysis_type: &str,
@@ -93,7 +323,7 @@ impl DieselDocumentRepository {
 
         let retry_cutoff =
             (Utc::now() - chrono::Duration::hours(i64::from(retry_interval_hours))).to_rfc3339();
-        let lock_cutoff = (Utc::now() - chrono::Duration::minutes(90)).to_rfc3339();
+        let lock_cutoff = (Utc::now() - self.claim_lease()).to_rfc3339();
 
         with_conn!(self.pool, conn, {
             let mut query = documents::table
@@ -158,14 +388,14 @@ impl DieselDocumentRepository {
     }
 
     /// Count documents needing summarization.
-    /// Documents need summarization if status is 'ocr_complete' (OCR done but not indexed).
+    /// Documents need summarization once extracted (status 'ocr_complete').
     pub async fn count_needing_summarization(
         &self,
         source_id: Option<&str>,
     ) -> Result<u64, DieselError> {
         with_conn!(self.pool, conn, {
             let mut query = documents::table
-                .filter(documents::status.eq("ocr_complete"))
+                .filter(documents::status.eq(DocumentStatus::OcrComplete.as_str()))
                 .into_boxed();
             if let Some(sid) = source_id {
                 query = query.filter(documents::source_id.eq(sid));
@@ -252,6 +482,33 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Get the stored size of every document version, summed per source.
+    pub async fn get_source_storage_bytes(&self) -> Result<HashMap<String, u64>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct SourceBytes {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            source_id: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            bytes: i64,
+        }
+
+        with_conn!(self.pool, conn, {
+            let rows: Vec<SourceBytes> = diesel::sql_query(
+                r#"SELECT d.source_id, CAST(COALESCE(SUM(dv.file_size), 0) AS BIGINT) as bytes
+                   FROM document_versions dv
+                   JOIN documents d ON d.id = dv.document_id
+                   GROUP BY d.source_id"#,
+            )
+            .load(&mut conn)
+            .await?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| (row.source_id, row.bytes.max(0) as u64))
+                .collect())
+        })
+    }
+
     /// Count documents needing date estimation.
     /// These are documents without an estimated_date in metadata.
     pub async fn count_documents_needing_date_estimation(
@@ -597,6 +854,48 @@ impl DieselDocumentRepository {
         })
     }
 
+    /// Get document counts per classified record type.
+    ///
+    /// Unclassified documents are not included.
+    pub async fn get_record_type_stats(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<HashMap<String, u64>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct RecordTypeCount {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            record_type: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            count: i64,
+        }
+
+        with_conn!(self.pool, conn, {
+            let results: Vec<RecordTypeCount> = if let Some(sid) = source_id {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        "SELECT record_type, COUNT(*) as count FROM documents WHERE record_type IS NOT NULL AND source_id = $1 GROUP BY record_type",
+                    )
+                    .bind::<diesel::sql_types::Text, _>(sid),
+                    &mut conn,
+                )
+                .await?
+            } else {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        "SELECT record_type, COUNT(*) as count FROM documents WHERE record_type IS NOT NULL GROUP BY record_type",
+                    ),
+                    &mut conn,
+                )
+                .await?
+            };
+
+            Ok(results
+                .into_iter()
+                .map(|row| (row.record_type, row.count as u64))
+                .collect())
+        })
+    }
+
     // ========================================================================
     // Browse and Search Operations
     // ========================================================================
@@ -615,17 +914,38 @@ impl DieselDocumentRepository {
         self.records_to_documents(records).await
     }
 
-    /// Browse documents.
-    pub async fn browse(&self, params: BrowseParams<'_>) -> Result<Vec<Document>, DieselError> {
-        let limit = params.limit as i64;
-        let offset = params.offset as i64;
+    /// Browse documents, one keyset page at a time.
+    ///
+    /// Ordered by the requested sort field (default `updated_at` desc) with
+    /// `id` as tie-breaker. Pass a returned page cursor back in
+    /// `params.cursor` to fetch the neighbouring page.
+    pub async fn browse(&self, params: BrowseParams<'_>) -> Result<Page<Document>, DieselError> {
+        let limit = params.limit as usize;
+        let cursor = params.cursor.as_ref();
         let source_id = params.source_id;
         let status = params.status;
         let categories = params.categories;
         let tags = params.tags;
+        let record_types = params.record_types;
+        let poor_ocr = params.poor_ocr;
+        let exemptions = params.exemptions;
+        let pdf_producers = params.pdf_producers;
+        let pdf_years = params.pdf_years;
+        let pdf_postdated = params.pdf_postdated;
+        let processing = params.processing;
+        let date_from = params.date_from;
+        let date_to = params.date_to;
+        let bbox = params.bbox;
         let search_query = params.search_query;
-        let sort_field = params.sort_field;
-        let sort_order = params.sort_order;
+        let sort_field = match params.sort_field {
+            Some("created_at") => "created_at",
+            Some("title") => "title",
+            _ => "updated_at",
+        };
+        let is_desc = params
+            .sort_order
+            .map(|o| o.eq_ignore_ascii_case("desc"))
+            .unwrap_or(true);
 
         let records: Vec<DocumentRecord> = with_conn!(self.pool, conn, {
             // Build query with filters first, then order and paginate
@@ -641,6 +961,36 @@ impl DieselDocumentRepository {
             if !categories.is_empty() {
                 query = query.filter(documents::category_id.eq_any(categories));
             }
+            if !record_types.is_empty() {
+                query = query.filter(documents::record_type.eq_any(record_types));
+            }
+            if poor_ocr {
+                query = query.filter(poor_ocr_filter());
+            }
+            if !exemptions.is_empty() {
+                query = query.filter(exemption_filter(exemptions));
+            }
+            if !pdf_producers.is_empty() {
+                query = query.filter(
+                    documents::id.eq_any(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::producer.eq_any(pdf_producers))
+                            .select(document_pdf_metadata::document_id),
+                    ),
+                );
+            }
+            if let Some(pdf_dates) = pdf_date_filter(pdf_years, pdf_postdated) {
+                query = query.filter(pdf_dates);
+            }
+            if let Some(state) = processing {
+                query = query.filter(processing_filter(state));
+            }
+            if let Some(range) = timeline_range_filter(date_from, date_to) {
+                query = query.filter(range);
+            }
+            if let Some(b) = &bbox {
+                query = query.filter(bbox_filter(b));
+            }
             // Tags are stored as comma-separated, filter docs that contain any of the requested tags
             for tag in tags {
                 let pattern = format!("%{}%", tag);
@@ -658,64 +1008,73 @@ impl DieselDocumentRepository {
                 }
             }
 
-            // Apply sorting
-            let is_desc = sort_order
-                .map(|o| o.eq_ignore_ascii_case("desc"))
-                .unwrap_or(true);
+            // Apply sorting and seek past the cursor
             match sort_field {
-                Some("created_at") => {
-                    if is_desc {
-                        query = query.order(documents::created_at.desc());
-                    } else {
-                        query = query.order(documents::created_at.asc());
-                    }
-                }
-                Some("title") => {
-                    if is_desc {
-                        query = query.order(documents::title.desc());
-                    } else {
-                        query = query.order(documents::title.asc());
-                    }
-                }
-                _ => {
-                    // Default: updated_at desc
-                    if is_desc {
-                        query = query.order(documents::updated_at.desc());
-                    } else {
-                        query = query.order(documents::updated_at.asc());
-                    }
-                }
+                "created_at" => keyset_order!(query, documents::created_at, is_desc, cursor),
+                "title" => keyset_order!(query, documents::title, is_desc, cursor),
+                _ => keyset_order!(query, documents::updated_at, is_desc, cursor),
             }
 
-            query.limit(limit).offset(offset).load(&mut conn).await
+            query.limit(limit as i64 + 1).load(&mut conn).await
         })?;
 
+        let page = Page::from_rows(records, limit, cursor, |r: &DocumentRecord| {
+            let key = match sort_field {
+                "created_at" => r.created_at.clone(),
+                "title" => r.title.clone(),
+                _ => r.updated_at.clone(),
+            };
+            vec![key, r.id.clone()]
+        });
+
         // Batch load all versions in a single query
-        let doc_ids: Vec<String> = records.iter().map(|r| r.id.clone()).collect();
+        let doc_ids: Vec<String> = page.items.iter().map(|r| r.id.clone()).collect();
         let mut versions_map = self.load_versions_batch(&doc_ids).await?;
 
-        let docs = records
+        let docs = page
+            .items
             .into_iter()
             .map(|record| {
                 let versions = versions_map.remove(&record.id).unwrap_or_default();
                 Self::record_to_document(record, versions)
             })
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(docs)
+        Ok(Page {
+            items: docs,
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
     }
 
     /// Browse count.
-    pub async fn browse_count(
-        &self,
-        source_id: Option<&str>,
-        status: Option<&str>,
-        categories: &[String],
-        tags: &[String],
-        search_query: Option<&str>,
-    ) -> Result<u64, DieselError> {
+    pub async fn browse_count(&self, params: &BrowseParams<'_>) -> Result<u64, DieselError> {
+        let source_id = params.source_id;
+        let status = params.status;
+        let categories = params.categories;
+        let tags = params.tags;
+        let record_types = params.record_types;
+        let poor_ocr = params.poor_ocr;
+        let exemptions = params.exemptions;
+        let pdf_producers = params.pdf_producers;
+        let pdf_years = params.pdf_years;
+        let pdf_postdated = params.pdf_postdated;
+        let processing = params.processing;
+        let search_query = params.search_query;
+        let date_range = timeline_range_filter(params.date_from, params.date_to);
+        let bbox = params.bbox;
+
         let has_filters = status.is_some()
             || !categories.is_empty()
             || !tags.is_empty()
+            || !record_types.is_empty()
+            || poor_ocr
+            || !exemptions.is_empty()
+            || !pdf_producers.is_empty()
+            || !pdf_years.is_empty()
+            || pdf_postdated
+            || processing.is_some()
+            || date_range.is_some()
+            || bbox.is_some()
             || search_query.is_some_and(|q| !q.is_empty());
 
         // Use pre-computed counts when no filters are active
@@ -739,6 +1098,36 @@ impl DieselDocumentRepository {
             if !categories.is_empty() {
                 query = query.filter(documents::category_id.eq_any(categories));
             }
+            if !record_types.is_empty() {
+                query = query.filter(documents::record_type.eq_any(record_types));
+            }
+            if poor_ocr {
+                query = query.filter(poor_ocr_filter());
+            }
+            if !exemptions.is_empty() {
+                query = query.filter(exemption_filter(exemptions));
+            }
+            if !pdf_producers.is_empty() {
+                query = query.filter(
+                    documents::id.eq_any(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::producer.eq_any(pdf_producers))
+                            .select(document_pdf_metadata::document_id),
+                    ),
+                );
+            }
+            if let Some(pdf_dates) = pdf_date_filter(pdf_years, pdf_postdated) {
+                query = query.filter(pdf_dates);
+            }
+            if let Some(state) = processing {
+                query = query.filter(processing_filter(state));
+            }
+            if let Some(range) = date_range {
+                query = query.filter(range);
+            }
+            if let Some(b) = &bbox {
+                query = query.filter(bbox_filter(b));
+            }
             for tag in tags {
                 let pattern = format!("%{}%", tag);
                 query = query.filter(documents::tags.like(pattern));
@@ -761,16 +1150,32 @@ impl DieselDocumentRepository {
     /// Optimized browse that only loads columns needed for listing.
     /// Avoids loading `extracted_text` which can be very large (OCR text).
     /// Two-step query: fetch document page first, then batch-load latest versions.
+    ///
+    /// `params.sort_field` is a [`DocumentSort`] name (default `updated`),
+    /// with `id` as tie-breaker.
     pub async fn browse_fast(
         &self,
-        source_id: Option<&str>,
-        _status: Option<&str>,
-        categories: &[String],
-        tags: &[String],
-        limit: u32,
-        offset: u32,
-    ) -> Result<Vec<super::BrowseRow>, DieselError> {
+        params: &BrowseParams<'_>,
+    ) -> Result<Page<super::BrowseRow>, DieselError> {
         use crate::schema::document_versions;
+        use diesel::sql_types::{Integer, Text};
+
+        let limit = params.limit as usize;
+        let cursor = params.cursor.as_ref();
+        let source_id = params.source_id;
+        let status = params.status;
+        let categories = params.categories;
+        let tags = params.tags;
+        let record_types = params.record_types;
+        let poor_ocr = params.poor_ocr;
+        let exemptions = params.exemptions;
+        let pdf_producers = params.pdf_producers;
+        let pdf_years = params.pdf_years;
+        let pdf_postdated = params.pdf_postdated;
+        let processing = params.processing;
+        let date_range = timeline_range_filter(params.date_from, params.date_to);
+        let bbox = params.bbox;
+        let (sort, is_desc) = DocumentSort::resolve(params.sort_field, params.sort_order);
 
         with_conn!(self.pool, conn, {
             // Step 1: fetch the page of documents that have at least one version
@@ -782,27 +1187,94 @@ impl DieselDocumentRepository {
                     documents::source_id,
                     documents::synopsis,
                     documents::tags,
+                    documents::record_type,
+                    documents::document_date,
+                    documents::updated_at,
+                    documents::status,
                 ))
                 .filter(diesel::dsl::exists(
                     document_versions::table
                         .filter(document_versions::document_id.eq(documents::id))
                         .select(document_versions::id),
                 ))
-                .order(documents::updated_at.desc())
-                .limit(limit as i64)
-                .offset(offset as i64)
                 .into_boxed();
 
             if let Some(sid) = source_id {
                 query = query.filter(documents::source_id.eq(sid));
             }
+            if let Some(st) = status {
+                query = query.filter(documents::status.eq(st));
+            }
             if !categories.is_empty() {
                 query = query.filter(documents::category_id.eq_any(categories));
             }
+            if !record_types.is_empty() {
+                query = query.filter(documents::record_type.eq_any(record_types));
+            }
+            if poor_ocr {
+                query = query.filter(poor_ocr_filter());
+            }
+            if !exemptions.is_empty() {
+                query = query.filter(exemption_filter(exemptions));
+            }
+            if !pdf_producers.is_empty() {
+                query = query.filter(
+                    documents::id.eq_any(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::producer.eq_any(pdf_producers))
+                            .select(document_pdf_metadata::document_id),
+                    ),
+                );
+            }
+            if let Some(pdf_dates) = pdf_date_filter(pdf_years, pdf_postdated) {
+                query = query.filter(pdf_dates);
+            }
+            if let Some(state) = processing {
+                query = query.filter(processing_filter(state));
+            }
+            if let Some(range) = date_range {
+                query = query.filter(range);
+            }
+            if let Some(b) = &bbox {
+                query = query.filter(bbox_filter(b));
+            }
             for tag in tags {
                 let pattern = format!("%{}%", tag);
                 query = query.filter(documents::tags.like(pattern));
             }
+            let parse_int = |k: &str| k.parse::<i32>().unwrap_or_default();
+            match sort {
+                DocumentSort::Updated => {
+                    keyset_order!(query, documents::updated_at, is_desc, cursor)
+                }
+                DocumentSort::Title => keyset_order!(query, documents::title, is_desc, cursor),
+                DocumentSort::Size => keyset_order!(
+                    query,
+                    latest_version_expr::<Integer>("file_size", "0"),
+                    is_desc,
+                    cursor,
+                    parse_int
+                ),
+                DocumentSort::Acquired => keyset_order!(
+                    query,
+                    latest_version_expr::<Text>("acquired_at", "''"),
+                    is_desc,
+                    cursor
+                ),
+                DocumentSort::DocumentDate => {
+                    keyset_order!(query, document_date_sort_expr(), is_desc, cursor)
+                }
+                DocumentSort::PageCount => keyset_order!(
+                    query,
+                    latest_version_expr::<Integer>("page_count", "0"),
+                    is_desc,
+                    cursor,
+                    parse_int
+                ),
+                DocumentSort::OcrStatus => {
+                    keyset_order!(query, documents::status, is_desc, cursor)
+                }
+            }
 
             #[allow(clippy::type_complexity)]
             let doc_rows: Vec<(
@@ -811,58 +1283,114 @@ impl DieselDocumentRepository {
                 String,
                 Option<String>,
                 Option<String>,
-            )> = query.load(&mut conn).await?;
-
+                Option<String>,
+                Option<String>,
+                String,
+                String,
+            )> = query.limit(limit as i64 + 1).load(&mut conn).await?;
             if doc_rows.is_empty() {
-                return Ok(Vec::new());
+                return Ok(Page::default());
             }
 
             let doc_ids: Vec<&str> = doc_rows.iter().map(|r| r.0.as_str()).collect();
 
             // Step 2: fetch all versions for these documents, ordered by id desc
-            let version_rows: Vec<(String, Option<String>, String, i32, String)> =
-                document_versions::table
-                    .filter(document_versions::document_id.eq_any(&doc_ids))
-                    .order(document_versions::id.desc())
-                    .select((
-                        document_versions::document_id,
-                        document_versions::original_filename,
-                        document_versions::mime_type,
-                        document_versions::file_size,
-                        document_versions::acquired_at,
-                    ))
-                    .load(&mut conn)
-                    .await?;
+            #[allow(clippy::type_complexity)]
+            let version_rows: Vec<(
+                String,
+                Option<String>,
+                String,
+                i32,
+                String,
+                Option<i32>,
+            )> = document_versions::table
+                .filter(document_versions::document_id.eq_any(&doc_ids))
+                .order(document_versions::id.desc())
+                .select((
+                    document_versions::document_id,
+                    document_versions::original_filename,
+                    document_versions::mime_type,
+                    document_versions::file_size,
+                    document_versions::acquired_at,
+                    document_versions::page_count,
+                ))
+                .load(&mut conn)
+                .await?;
 
             // Take only the latest version per document (first seen per document_id)
-            let mut latest_versions: HashMap<&str, (Option<String>, String, i32, String)> =
-                HashMap::new();
-            for (doc_id, filename, mime, size, acquired) in &version_rows {
+            #[allow(clippy::type_complexity)]
+            let mut latest_versions: HashMap<
+                String,
+                (Option<String>, String, i32, String, Option<i32>),
+            > = HashMap::new();
+            for (doc_id, filename, mime, size, acquired, pages) in version_rows {
                 latest_versions
-                    .entry(doc_id.as_str())
-                    .or_insert_with(|| (filename.clone(), mime.clone(), *size, acquired.clone()));
+                    .entry(doc_id)
+                    .or_insert((filename, mime, size, acquired, pages));
             }
 
-            // Combine in document order
-            let results: Vec<super::BrowseRow> = doc_rows
+            // Combine in document order (EXISTS guarantees a version per row);
+            // version-based sort keys are only known after this step
+            let rows: Vec<super::BrowseRow> = doc_rows
                 .into_iter()
-                .filter_map(|(id, title, source_id, synopsis, tags)| {
-                    let (filename, mime, size, acquired) = latest_versions.remove(id.as_str())?;
-                    Some(super::BrowseRow {
+                .map(
+                    |(
                         id,
                         title,
                         source_id,
                         synopsis,
                         tags,
-                        original_filename: filename,
-                        mime_type: mime,
-                        file_size: size,
-                        acquired_at: acquired,
-                    })
-                })
+                        record_type,
+                        document_date,
+                        updated_at,
+                        status,
+                    )| {
+                        let (filename, mime, size, acquired, pages) =
+                            latest_versions.remove(&id).unwrap_or_default();
+                        super::BrowseRow {
+                            id,
+                            title,
+                            source_id,
+                            synopsis,
+                            tags,
+                            record_type,
+                            document_date,
+                            updated_at,
+                            original_filename: filename,
+                            mime_type: mime,
+                            file_size: size,
+                            acquired_at: acquired,
+                            page_count: pages,
+                            status,
+                        }
+                    },
+                )
                 .collect();
+            Ok(Page::from_rows(rows, limit, cursor, |r| {
+                vec![sort.key(r), r.id.clone()]
+            }))
+        })
+    }
 
-            Ok(results)
+    /// Ids and titles of up to `limit` documents after `after_id`, in id
+    /// order, optionally from one source.
+    pub async fn get_titles_after(
+        &self,
+        source_id: Option<&str>,
+        after_id: &str,
+        limit: usize,
+    ) -> Result<Vec<(String, String)>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = documents::table
+                .select((documents::id, documents::title))
+                .filter(documents::id.gt(after_id))
+                .order(documents::id.asc())
+                .limit(limit as i64)
+                .into_boxed();
+            if let Some(sid) = source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            query.load(&mut conn).await
         })
     }
 
@@ -988,82 +1516,97 @@ impl DieselDocumentRepository {
         )
     }
 
-    /// Get documents by tag.
+    /// Get a keyset page of documents with a tag, newest first.
     /// Tags are stored in metadata JSON.
     pub async fn get_by_tag(
         &self,
         tag: &str,
         source_id: Option<&str>,
-    ) -> Result<Vec<Document>, DieselError> {
-        let ids: Vec<DocIdRow> = with_conn_split!(self.pool,
+        cursor: Option<&PageCursor>,
+        limit: usize,
+    ) -> Result<Page<Document>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct KeyRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            id: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            updated_at: String,
+        }
+
+        // Walking backwards flips the comparison and order
+        let (cmp, dir) = if cursor.is_some_and(|c| c.before) {
+            (">", "ASC")
+        } else {
+            ("<", "DESC")
+        };
+        let cursor_key = cursor.and_then(|c| c.key(0));
+        let cursor_id = cursor.and_then(|c| c.key(1));
+        let fetch = limit + 1;
+
+        let rows: Vec<KeyRow> = with_conn_split!(self.pool,
             sqlite: conn => {
-                if let Some(sid) = source_id {
-                    diesel_async::RunQueryDsl::load(
-                        diesel::sql_query(
-                            r#"SELECT id FROM documents
-                               WHERE source_id = $1
-                               AND EXISTS (
-                                   SELECT 1 FROM json_each(json_extract(metadata, '$.tags'))
-                                   WHERE value = $2
-                               )
-                               ORDER BY updated_at DESC"#,
-                        )
-                        .bind::<diesel::sql_types::Text, _>(sid)
-                        .bind::<diesel::sql_types::Text, _>(tag),
-                        &mut conn,
-                    )
-                    .await
-                    .unwrap_or_default()
-                } else {
-                    diesel_async::RunQueryDsl::load(
-                        diesel::sql_query(
-                            r#"SELECT id FROM documents
-                               WHERE EXISTS (
-                                   SELECT 1 FROM json_each(json_extract(metadata, '$.tags'))
-                                   WHERE value = $1
-                               )
-                               ORDER BY updated_at DESC"#,
-                        )
-                        .bind::<diesel::sql_types::Text, _>(tag),
-                        &mut conn,
-                    )
-                    .await
-                    .unwrap_or_default()
-                }
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(format!(
+                        r#"SELECT id, updated_at FROM documents
+                           WHERE ($1 IS NULL OR source_id = $1)
+                           AND EXISTS (
+                               SELECT 1 FROM json_each(json_extract(metadata, '$.tags'))
+                               WHERE value = $2
+                           )
+                           AND ($3 IS NULL OR updated_at {cmp} $3
+                                OR (updated_at = $3 AND id {cmp} $4))
+                           ORDER BY updated_at {dir}, id {dir}
+                           LIMIT {fetch}"#
+                    ))
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
+                    .bind::<diesel::sql_types::Text, _>(tag)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_key)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_id),
+                    &mut conn,
+                )
+                .await
+                .unwrap_or_default()
             },
             postgres: conn => {
-                if let Some(sid) = source_id {
-                    diesel_async::RunQueryDsl::load(
-                        diesel::sql_query(
-                            r#"SELECT id FROM documents
-                               WHERE source_id = $1
-                               AND metadata->'tags' ? $2
-                               ORDER BY updated_at DESC"#,
-                        )
-                        .bind::<diesel::sql_types::Text, _>(sid)
-                        .bind::<diesel::sql_types::Text, _>(tag),
-                        &mut conn,
-                    )
-                    .await
-                    .unwrap_or_default()
-                } else {
-                    diesel_async::RunQueryDsl::load(
-                        diesel::sql_query(
-                            r#"SELECT id FROM documents
-                               WHERE metadata->'tags' ? $1
-                               ORDER BY updated_at DESC"#,
-                        )
-                        .bind::<diesel::sql_types::Text, _>(tag),
-                        &mut conn,
-                    )
-                    .await
-                    .unwrap_or_default()
-                }
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(format!(
+                        r#"SELECT id, updated_at FROM documents
+                           WHERE ($1::text IS NULL OR source_id = $1)
+                           AND metadata->'tags' ? $2
+                           AND ($3::text IS NULL OR updated_at {cmp} $3
+                                OR (updated_at = $3 AND id {cmp} $4))
+                           ORDER BY updated_at {dir}, id {dir}
+                           LIMIT {fetch}"#
+                    ))
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(source_id)
+                    .bind::<diesel::sql_types::Text, _>(tag)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_key)
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(cursor_id),
+                    &mut conn,
+                )
+                .await
+                .unwrap_or_default()
             }
         );
 
-        let doc_ids: Vec<String> = ids.into_iter().map(|r| r.id).collect();
-        self.get_batch(&doc_ids).await
+        let page = Page::from_rows(rows, limit, cursor, |r| {
+            vec![r.updated_at.clone(), r.id.clone()]
+        });
+        let doc_ids: Vec<String> = page.items.iter().map(|r| r.id.clone()).collect();
+
+        // get_batch doesn't preserve order; restore keyset order
+        let mut by_id: HashMap<String, Document> = self
+            .get_batch(&doc_ids)
+            .await?
+            .into_iter()
+            .map(|d| (d.id.clone(), d))
+            .collect();
+
+        Ok(Page {
+            items: doc_ids.iter().filter_map(|id| by_id.remove(id)).collect(),
+            next_cursor: page.next_cursor,
+            prev_cursor: page.prev_cursor,
+        })
     }
 
     /// Get documents by MIME type category.
@@ -1126,11 +1669,11 @@ impl DieselDocumentRepository {
     // Timeline Operations
     // ========================================================================
 
-    /// Get timeline buckets (daily counts) for documents by publication date.
+    /// Get timeline buckets (daily counts) for documents by timeline date.
     ///
     /// Returns (date_string, timestamp, count) tuples grouped by day.
-    /// Uses `manual_date` if set, otherwise `estimated_date`.
-    /// Only includes documents that have a publication date.
+    /// Uses `manual_date` if set, then the extracted `document_date`, then
+    /// `estimated_date`, falling back to the latest version's `acquired_at`.
     /// Optionally filtered by source_id and date range.
     pub async fn get_timeline_buckets(
         &self,
@@ -1146,15 +1689,15 @@ impl DieselDocumentRepository {
             count: i64,
         }
 
-        // Use publication date: prefer manual_date, fall back to estimated_date
-        // Only include documents that have at least one of these dates
-        let date_expr = "COALESCE(manual_date, estimated_date)";
+        // Prefer manual_date, then the date extracted from the document content,
+        // then the heuristic estimated_date, then the acquisition date.
+        let date_expr = TIMELINE_DATE_EXPR;
         let base_query = format!(
             "SELECT date({}) as date_bucket, COUNT(*) as count FROM documents",
             date_expr
         );
 
-        // Always filter to documents with a publication date
+        // Skip documents with no date at all (no versions and no extracted date)
         let mut conditions = vec![format!("{} IS NOT NULL", date_expr)];
 
         if source_id.is_some() {
@@ -1334,6 +1877,8 @@ impl DieselDocumentRepository {
         confidence: &str,
         source: &str,
     ) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard
+            .update_estimated_date(id, date, confidence, source));
         let record: Option<DocumentRecord> = with_conn!(self.pool, conn, {
             documents::table.find(id).first(&mut conn).await.optional()
         })?;
@@ -1349,7 +1894,7 @@ impl DieselDocumentRepository {
             });
 
             let now = Utc::now().to_rfc3339();
-            with_conn!(self.pool, conn, {
+            with_write_conn!(self.pool, conn, {
                 diesel::update(documents::table.find(id))
                     .set((
                         documents::metadata.eq(metadata.to_string()),
@@ -1364,6 +1909,70 @@ impl DieselDocumentRepository {
         Ok(())
     }
 
+    /// Set the classified record type and its confidence (0.0-1.0).
+    pub async fn update_record_type(
+        &self,
+        id: &str,
+        record_type: &str,
+        confidence: f32,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard.update_record_type(
+            id,
+            record_type,
+            confidence
+        ));
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(documents::table.find(id))
+                .set((
+                    documents::record_type.eq(record_type),
+                    documents::record_type_confidence.eq(confidence),
+                    documents::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Store structured fields extracted from document content.
+    ///
+    /// Fields that were not extracted are cleared, so re-running extraction
+    /// replaces the previous result rather than merging with it.
+    pub async fn update_extracted_metadata(
+        &self,
+        id: &str,
+        extracted: &ExtractedMetadata,
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard
+            .update_extracted_metadata(id, extracted));
+        let document_date = extracted
+            .document_date
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|dt| dt.and_utc().to_rfc3339());
+        let markings = if extracted.classification_markings.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&extracted.classification_markings).ok()
+        };
+        let now = Utc::now().to_rfc3339();
+
+        with_write_conn!(self.pool, conn, {
+            diesel::update(documents::table.find(id))
+                .set((
+                    documents::document_date.eq(&document_date),
+                    documents::originating_agency.eq(&extracted.originating_agency),
+                    documents::originating_office.eq(&extracted.originating_office),
+                    documents::case_number.eq(&extracted.case_number),
+                    documents::classification_markings.eq(&markings),
+                    documents::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
     /// Record an annotation result in document metadata.
     pub async fn record_annotation(
         &self,
@@ -1373,6 +1982,13 @@ impl DieselDocumentRepository {
         data: Option<&str>,
         error: Option<&str>,
     ) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard.record_annotation(
+            id,
+            annotation_type,
+            version,
+            data,
+            error
+        ));
         let record: Option<DocumentRecord> = with_conn!(self.pool, conn, {
             documents::table.find(id).first(&mut conn).await.optional()
         })?;
@@ -1395,7 +2011,7 @@ impl DieselDocumentRepository {
             });
 
             let now = Utc::now().to_rfc3339();
-            with_conn!(self.pool, conn, {
+            with_write_conn!(self.pool, conn, {
                 diesel::update(documents::table.find(id))
                     .set((
                         documents::metadata.eq(metadata.to_string()),
@@ -1417,7 +2033,7 @@ impl DieselDocumentRepository {
     ) -> Result<Vec<Document>, DieselError> {
         let records: Vec<DocumentRecord> = with_conn!(self.pool, conn, {
             documents::table
-                .filter(documents::status.eq("ocr_complete"))
+                .filter(documents::status.eq(DocumentStatus::OcrComplete.as_str()))
                 .order(documents::updated_at.asc())
                 .limit(limit as i64)
                 .load(&mut conn)
@@ -1440,7 +2056,7 @@ impl DieselDocumentRepository {
     /// Uses cursor-based pagination: pass `after_id` to fetch the next page.
     /// Returns documents that have no `complete` result in `document_analysis_results`
     /// for the given type, no recent `failed` result within the retry window,
-    /// and no `pending` result within the lock window (90 minutes).
+    /// and no `pending` result within the claim lease.
     pub async fn get_needing_analysis(
         &self,
         analysis_type: &str,
@@ -1455,7 +2071,7 @@ impl DieselDocumentRepository {
 
         let retry_cutoff =
             (Utc::now() - chrono::Duration::hours(i64::from(retry_interval_hours))).to_rfc3339();
-        let lock_cutoff = (Utc::now() - chrono::Duration::minutes(90)).to_rfc3339();
+        let lock_cutoff = (Utc::now() - self.claim_lease()).to_rfc3339();
 
         let ids: Vec<String> = with_conn!(self.pool, conn, {
             let mut query = documents::table
@@ -1532,33 +2148,30 @@ impl DieselDocumentRepository {
             .await
     }
 
-    /// Finalize document - mark as indexed.
+    /// Finalize document - mark redactions in its text and move it to
+    /// analyzed, if the workflow allows.
     pub async fn finalize_document(&self, id: &str) -> Result<(), DieselError> {
-        self.update_status(id, DocumentStatus::Indexed).await
+        self.mark_current_redactions(id).await?;
+        self.transition_status(id, DocumentStatus::Indexed).await?;
+        Ok(())
     }
 
-    /// Finalize pending documents - mark documents with all pages complete as indexed.
+    /// Finalize pending documents - move extracted documents to analyzed.
     pub async fn finalize_pending_documents(&self) -> Result<u64, DieselError> {
-        let now = Utc::now().to_rfc3339();
-        let count: usize = with_conn!(self.pool, conn, {
-            diesel::update(documents::table.filter(documents::status.eq("ocr_complete")))
-                .set((
-                    documents::status.eq("indexed"),
-                    documents::updated_at.eq(&now),
-                ))
-                .execute(&mut conn)
-                .await
-        })?;
-
-        Ok(count as u64)
+        self.transition_all(DocumentStatus::OcrComplete, DocumentStatus::Indexed, None)
+            .await
     }
 
     /// Reset annotations for documents, allowing them to be re-annotated.
-    /// Sets status back to ocr_complete and clears synopsis/tags.
+    /// Moves analyzed documents back to extracted and clears synopsis/tags.
     pub async fn reset_annotations(&self, source_id: Option<&str>) -> Result<u64, DieselError> {
-        let count: u64 = with_conn!(self.pool, conn, {
+        let mut reset = 0;
+        for shard in self.shard_repos() {
+            reset += Box::pin(shard.reset_annotations(source_id)).await?;
+        }
+        let count: u64 = with_write_conn!(self.pool, conn, {
             let mut query = diesel::update(documents::table)
-                .filter(documents::status.eq("indexed"))
+                .filter(documents::status.eq(DocumentStatus::Indexed.as_str()))
                 .into_boxed();
 
             if let Some(sid) = source_id {
@@ -1567,7 +2180,7 @@ impl DieselDocumentRepository {
 
             query
                 .set((
-                    documents::status.eq("ocr_complete"),
+                    documents::status.eq(DocumentStatus::OcrComplete.as_str()),
                     documents::synopsis.eq(None::<String>),
                     documents::tags.eq(None::<String>),
                 ))
@@ -1575,14 +2188,33 @@ impl DieselDocumentRepository {
                 .await
         })? as u64;
 
-        Ok(count)
+        Ok(reset + count)
+    }
+
+    /// Queue one document for annotation again: move it back to extracted
+    /// and clear its synopsis, keeping its tags. Returns false if there is
+    /// no such document.
+    pub async fn reset_annotation(&self, id: &str) -> Result<bool, DieselError> {
+        route_to_shard!(self, id, |shard| shard.reset_annotation(id));
+        let now = Utc::now().to_rfc3339();
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(documents::table.filter(documents::id.eq(id)))
+                .set((
+                    documents::status.eq(DocumentStatus::OcrComplete.as_str()),
+                    documents::synopsis.eq(None::<String>),
+                    documents::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        Ok(rows > 0)
     }
 
-    /// Count documents that have been annotated (status = indexed).
+    /// Count documents that have been annotated (status analyzed).
     pub async fn count_annotated(&self, source_id: Option<&str>) -> Result<u64, DieselError> {
         with_conn!(self.pool, conn, {
             let mut query = documents::table
-                .filter(documents::status.eq("indexed"))
+                .filter(documents::status.eq(DocumentStatus::Indexed.as_str()))
                 .into_boxed();
 
             if let Some(sid) = source_id {
@@ -1597,28 +2229,30 @@ impl DieselDocumentRepository {
         })
     }
 
-    /// Update synopsis and tags for a document.
+    /// Update synopsis and tags for a document and move it to analyzed.
     pub async fn update_synopsis_and_tags(
         &self,
         id: &str,
         synopsis: Option<&str>,
         tags: &[String],
     ) -> Result<(), DieselError> {
+        route_to_shard!(self, id, |shard| shard
+            .update_synopsis_and_tags(id, synopsis, tags));
         let now = Utc::now().to_rfc3339();
         let tags_json = serde_json::to_string(tags).unwrap_or_else(|_| "[]".to_string());
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(documents::table.find(id))
                 .set((
                     documents::synopsis.eq(synopsis),
                     documents::tags.eq(&tags_json),
-                    documents::status.eq("indexed"),
                     documents::updated_at.eq(&now),
                 ))
                 .execute(&mut conn)
-                .await?;
-            Ok(())
-        })
+                .await
+        })?;
+        self.transition_status(id, DocumentStatus::Indexed).await?;
+        Ok(())
     }
 }
 
@@ -1648,9 +2282,11 @@ mod tests {
         let (pool, _dir) = setup_test_db().await;
         let repo = DieselDocumentRepository::new(pool);
 
-        let result = repo.get_by_tag("'; DROP TABLE documents; --", None).await;
+        let result = repo
+            .get_by_tag("'; DROP TABLE documents; --", None, None, 50)
+            .await;
         assert!(result.is_ok());
-        assert!(result.unwrap().is_empty());
+        assert!(result.unwrap().items.is_empty());
     }
 
     #[tokio::test]
@@ -1663,4 +2299,213 @@ mod tests {
             .await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_record_type_filter() {
+        use crate::models::DocumentVersion;
+
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        for (id, hash) in [("doc-a", "aaa"), ("doc-b", "bbb")] {
+            let doc = Document {
+                id: id.to_string(),
+                source_id: "test-source".to_string(),
+                title: id.to_string(),
+                source_url: format!("https://example.com/{}.pdf", id),
+                extracted_text: None,
+                synopsis: None,
+                tags: vec![],
+                status: DocumentStatus::OcrComplete,
+                metadata: serde_json::Value::Object(Default::default()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                discovery_method: "seed".to_string(),
+                versions: vec![],
+            };
+            repo.save(&doc).await.unwrap();
+            let version = DocumentVersion {
+                id: 0,
+                content_hash: hash.to_string(),
+                content_hash_blake3: None,
+                file_path: None,
+                file_size: 10,
+                mime_type: "application/pdf".to_string(),
+                acquired_at: Utc::now(),
+                source_url: None,
+                original_filename: None,
+                server_date: None,
+                page_count: None,
+                archive_snapshot_id: None,
+                earliest_archived_at: None,
+                dedup_index: None,
+                checksum: None,
+                declared_mime_type: None,
+                computed_sha256: None,
+            };
+            repo.add_version(id, &version).await.unwrap();
+        }
+
+        repo.update_record_type("doc-a", "contract", 0.9)
+            .await
+            .unwrap();
+
+        let contracts = vec!["contract".to_string()];
+        let rows = repo
+            .browse_fast(&BrowseParams {
+                record_types: &contracts,
+                limit: 50,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .items;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, "doc-a");
+        assert_eq!(rows[0].record_type.as_deref(), Some("contract"));
+
+        let count = repo
+            .browse_count(&BrowseParams {
+                record_types: &contracts,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let stats = repo.get_record_type_stats(None).await.unwrap();
+        assert_eq!(stats.get("contract"), Some(&1));
+        assert_eq!(stats.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_extracted_metadata_drives_timeline() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let doc = Document {
+            id: "doc-memo".to_string(),
+            source_id: "test-source".to_string(),
+            title: "memo".to_string(),
+            source_url: "https://example.com/memo.pdf".to_string(),
+            extracted_text: None,
+            synopsis: None,
+            tags: vec![],
+            status: DocumentStatus::OcrComplete,
+            metadata: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            discovery_method: "seed".to_string(),
+            versions: vec![],
+        };
+        repo.save(&doc).await.unwrap();
+
+        // With no extracted date and no versions the document is not on the timeline
+        let buckets = repo.get_timeline_buckets(None, None, None).await.unwrap();
+        assert!(buckets.is_empty());
+
+        let extracted = ExtractedMetadata {
+            document_date: chrono::NaiveDate::from_ymd_opt(1963, 11, 22),
+            originating_agency: Some("Central Intelligence Agency".to_string()),
+            originating_office: None,
+            case_number: Some("F-2017-01234".to_string()),
+            classification_markings: vec!["SECRET".to_string()],
+        };
+        repo.update_extracted_metadata("doc-memo", &extracted)
+            .await
+            .unwrap();
+
+        let buckets = repo.get_timeline_buckets(None, None, None).await.unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].0, "1963-11-22");
+        assert_eq!(buckets[0].2, 1);
+
+        let buckets = repo
+            .get_timeline_buckets(None, Some("1964-01-01"), None)
+            .await
+            .unwrap();
+        assert!(buckets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_browse_timeline_range() {
+        use crate::models::DocumentVersion;
+
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        for (id, hash) in [("doc-old", "aaa"), ("doc-new", "bbb")] {
+            let doc = Document {
+                id: id.to_string(),
+                source_id: "test-source".to_string(),
+                title: id.to_string(),
+                source_url: format!("https://example.com/{}.pdf", id),
+                extracted_text: None,
+                synopsis: None,
+                tags: vec![],
+                status: DocumentStatus::OcrComplete,
+                metadata: serde_json::Value::Object(Default::default()),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                discovery_method: "seed".to_string(),
+                versions: vec![],
+            };
+            repo.save(&doc).await.unwrap();
+            let version = DocumentVersion {
+                id: 0,
+                content_hash: hash.to_string(),
+                content_hash_blake3: None,
+                file_path: None,
+                file_size: 10,
+                mime_type: "application/pdf".to_string(),
+                acquired_at: Utc::now(),
+                source_url: None,
+                original_filename: None,
+                server_date: None,
+                page_count: None,
+                archive_snapshot_id: None,
+                earliest_archived_at: None,
+                dedup_index: None,
+                checksum: None,
+                declared_mime_type: None,
+                computed_sha256: None,
+            };
+            repo.add_version(id, &version).await.unwrap();
+        }
+
+        let extracted = ExtractedMetadata {
+            document_date: NaiveDate::from_ymd_opt(1963, 11, 22),
+            ..Default::default()
+        };
+        repo.update_extracted_metadata("doc-old", &extracted)
+            .await
+            .unwrap();
+
+        // Extracted document date
+        let cold_war = BrowseParams {
+            date_from: NaiveDate::from_ymd_opt(1960, 1, 1),
+            date_to: NaiveDate::from_ymd_opt(1969, 12, 31),
+            limit: 50,
+            ..Default::default()
+        };
+        let rows = repo.browse_fast(&cold_war).await.unwrap().items;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, "doc-old");
+        assert_eq!(repo.browse_count(&cold_war).await.unwrap(), 1);
+
+        // Fallback to acquisition date
+        let recent = BrowseParams {
+            date_from: Some(Utc::now().date_naive() - chrono::Duration::days(1)),
+            limit: 50,
+            ..Default::default()
+        };
+        let rows = repo.browse_fast(&recent).await.unwrap().items;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].id, "doc-new");
+        assert_eq!(repo.browse_count(&recent).await.unwrap(), 1);
+
+        let buckets = repo.get_timeline_buckets(None, None, None).await.unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].0, "1963-11-22");
+    }
 }
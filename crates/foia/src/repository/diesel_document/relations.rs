@@ -0,0 +1,452 @@
+//! Document relationships.
+//!
+//! Relations are detected from a document's text (named attachments and the
+//! requests it answers, see [`find_references`]) and from file number
+//! entities shared with other documents. They live in `document_relations`
+//! and back the relationship panel and graph on the document page.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text};
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::{
+    find_references, Document, DocumentRelation, RelationGraph, RelationKind, RelationNode,
+};
+use crate::repository::models::DocumentRelationRecord;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_entities, document_pages, document_relations, documents};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
+
+/// Documents a single reference may resolve to.
+const MAX_REFERENCE_MATCHES: i64 = 10;
+
+/// A file number on more documents than this is a series prefix or a
+/// boilerplate form number rather than a case, and links nothing.
+const MAX_CASE_NUMBER_DOCUMENTS: i64 = 50;
+
+impl From<DocumentRelationRecord> for DocumentRelation {
+    fn from(record: DocumentRelationRecord) -> Self {
+        DocumentRelation {
+            source_document_id: record.source_document_id,
+            target_document_id: record.target_document_id,
+            kind: RelationKind::from_str(&record.kind).unwrap_or(RelationKind::Attachment),
+            evidence: record.evidence,
+        }
+    }
+}
+
+impl DieselDocumentRepository {
+    /// Relations in either direction involving a document.
+    pub async fn get_document_relations(
+        &self,
+        doc_id: &str,
+    ) -> Result<Vec<DocumentRelation>, DieselError> {
+        let records: Vec<DocumentRelationRecord> = with_conn!(self.pool, conn, {
+            document_relations::table
+                .filter(
+                    document_relations::source_document_id
+                        .eq(doc_id)
+                        .or(document_relations::target_document_id.eq(doc_id)),
+                )
+                .order((
+                    document_relations::kind.asc(),
+                    document_relations::evidence.asc(),
+                ))
+                .select(DocumentRelationRecord::as_select())
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(records.into_iter().map(DocumentRelation::from).collect())
+    }
+
+    /// Replace the relations a document was detected to have: its outgoing
+    /// attachment and response links, and its shared case numbers.
+    pub async fn set_document_relations(
+        &self,
+        doc_id: &str,
+        relations: &[DocumentRelation],
+    ) -> Result<(), DieselError> {
+        route_to_shard!(self, doc_id, |shard| shard
+            .set_document_relations(doc_id, relations));
+        let directed: Vec<&str> = RelationKind::ALL
+            .iter()
+            .filter(|k| !k.is_symmetric())
+            .map(|k| k.as_str())
+            .collect();
+        let symmetric: Vec<&str> = RelationKind::ALL
+            .iter()
+            .filter(|k| k.is_symmetric())
+            .map(|k| k.as_str())
+            .collect();
+        let now = Utc::now().to_rfc3339();
+        let records: Vec<DocumentRelationRecord> = relations
+            .iter()
+            .map(|r| DocumentRelationRecord {
+                source_document_id: r.source_document_id.clone(),
+                target_document_id: r.target_document_id.clone(),
+                kind: r.kind.as_str().to_string(),
+                evidence: r.evidence.clone(),
+                created_at: now.clone(),
+            })
+            .collect();
+
+        with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                document_relations::table
+                    .filter(document_relations::source_document_id.eq(doc_id))
+                    .filter(document_relations::kind.eq_any(&directed)),
+            )
+            .execute(&mut conn)
+            .await?;
+            diesel::delete(
+                document_relations::table
+                    .filter(
+                        document_relations::source_document_id
+                            .eq(doc_id)
+                            .or(document_relations::target_document_id.eq(doc_id)),
+                    )
+                    .filter(document_relations::kind.eq_any(&symmetric)),
+            )
+            .execute(&mut conn)
+            .await
+        })?;
+
+        if records.is_empty() {
+            return Ok(());
+        }
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                for record in &records {
+                    diesel::insert_or_ignore_into(document_relations::table)
+                        .values(record)
+                        .execute(&mut conn)
+                        .await?;
+                }
+                Ok::<_, DieselError>(())
+            },
+            postgres: conn => {
+                for chunk in records.chunks(50) {
+                    diesel::insert_into(document_relations::table)
+                        .values(chunk)
+                        .on_conflict_do_nothing()
+                        .execute(&mut conn)
+                        .await?;
+                }
+                Ok::<_, DieselError>(())
+            }
+        )
+    }
+
+    /// Detect a document's relations from its current text and file number
+    /// entities, replacing those found before.
+    pub async fn refresh_document_relations(
+        &self,
+        doc: &Document,
+    ) -> Result<Vec<DocumentRelation>, DieselError> {
+        let page_text = match doc.current_version() {
+            Some(version) => {
+                self.get_combined_page_text(&doc.id, version.id as i32)
+                    .await?
+            }
+            None => None,
+        };
+        let text = page_text
+            .or_else(|| doc.extracted_text.clone())
+            .unwrap_or_default();
+
+        let mut relations = Vec::new();
+        for reference in find_references(&text) {
+            let targets = match reference.kind {
+                RelationKind::Attachment => self.find_by_filename(&reference.target).await?,
+                _ => self.find_by_title(&reference.target).await?,
+            };
+            relations.extend(
+                targets.iter().filter(|id| *id != &doc.id).map(|id| {
+                    DocumentRelation::new(&doc.id, id, reference.kind, &reference.target)
+                }),
+            );
+        }
+
+        let case_numbers: Vec<(String, String)> = with_conn!(self.pool, conn, {
+            document_entities::table
+                .filter(document_entities::document_id.eq(&doc.id))
+                .filter(document_entities::entity_type.eq("file_number"))
+                .select((
+                    document_entities::entity_text,
+                    document_entities::normalized_text,
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+        for (case_number, normalized) in case_numbers {
+            let others: Vec<String> = with_conn!(self.pool, conn, {
+                document_entities::table
+                    .filter(document_entities::entity_type.eq("file_number"))
+                    .filter(document_entities::normalized_text.eq(&normalized))
+                    .select(document_entities::document_id)
+                    .distinct()
+                    .limit(MAX_CASE_NUMBER_DOCUMENTS + 1)
+                    .load(&mut conn)
+                    .await
+            })?;
+            if others.len() as i64 > MAX_CASE_NUMBER_DOCUMENTS {
+                continue;
+            }
+            relations.extend(others.iter().filter(|id| *id != &doc.id).map(|id| {
+                DocumentRelation::new(&doc.id, id, RelationKind::SharedCaseNumber, &case_number)
+            }));
+        }
+
+        // One link per pair and kind, keeping the first evidence found
+        let mut seen = HashSet::new();
+        relations.retain(|r| {
+            seen.insert((
+                r.source_document_id.clone(),
+                r.target_document_id.clone(),
+                r.kind,
+            ))
+        });
+        self.set_document_relations(&doc.id, &relations).await?;
+        Ok(relations)
+    }
+
+    /// Documents whose original filename or title is `filename`, ignoring case.
+    async fn find_by_filename(&self, filename: &str) -> Result<Vec<String>, DieselError> {
+        let filename = filename.to_lowercase();
+        with_conn!(self.pool, conn, {
+            documents::table
+                .filter(
+                    diesel::dsl::sql::<Bool>("lower(documents.title) = ")
+                        .bind::<Text, _>(filename.clone())
+                        .sql(
+                            " OR EXISTS (SELECT 1 FROM document_versions dv \
+                             WHERE dv.document_id = documents.id \
+                             AND lower(dv.original_filename) = ",
+                        )
+                        .bind::<Text, _>(filename.clone())
+                        .sql(")"),
+                )
+                .select(documents::id)
+                .order(documents::id.asc())
+                .limit(MAX_REFERENCE_MATCHES)
+                .load(&mut conn)
+                .await
+        })
+    }
+
+    /// Documents whose title contains `identifier`, ignoring case.
+    async fn find_by_title(&self, identifier: &str) -> Result<Vec<String>, DieselError> {
+        let pattern = format!("%{}%", identifier.to_lowercase());
+        with_conn!(self.pool, conn, {
+            documents::table
+                .filter(
+                    diesel::dsl::sql::<Bool>("lower(documents.title) LIKE ")
+                        .bind::<Text, _>(pattern.clone()),
+                )
+                .select(documents::id)
+                .order(documents::id.asc())
+                .limit(MAX_REFERENCE_MATCHES)
+                .load(&mut conn)
+                .await
+        })
+    }
+
+    /// The documents within `depth` relation hops of a document, at most
+    /// `max_nodes` of them, with the relations between them.
+    pub async fn document_relation_graph(
+        &self,
+        doc_id: &str,
+        depth: u32,
+        max_nodes: usize,
+    ) -> Result<RelationGraph, DieselError> {
+        let mut depths: HashMap<String, u32> = HashMap::from([(doc_id.to_string(), 0)]);
+        let mut order = vec![doc_id.to_string()];
+        let mut edges: Vec<DocumentRelation> = Vec::new();
+        let mut seen_edges = HashSet::new();
+        let mut queue = VecDeque::from([(doc_id.to_string(), 0u32)]);
+
+        while let Some((id, d)) = queue.pop_front() {
+            for relation in self.get_document_relations(&id).await? {
+                let other = relation.other(&id).to_string();
+                if !depths.contains_key(&other) {
+                    if d >= depth || order.len() >= max_nodes {
+                        continue;
+                    }
+                    depths.insert(other.clone(), d + 1);
+                    order.push(other.clone());
+                    queue.push_back((other, d + 1));
+                }
+                if seen_edges.insert(relation.clone()) {
+                    edges.push(relation);
+                }
+            }
+        }
+
+        let titles: HashMap<String, String> = with_conn!(self.pool, conn, {
+            documents::table
+                .filter(documents::id.eq_any(&order))
+                .select((documents::id, documents::title))
+                .load::<(String, String)>(&mut conn)
+                .await
+        })?
+        .into_iter()
+        .collect();
+
+        let nodes = order
+            .into_iter()
+            .map(|id| RelationNode {
+                title: titles.get(&id).cloned().unwrap_or_else(|| id.clone()),
+                depth: depths[&id],
+                id,
+            })
+            .collect();
+        Ok(RelationGraph { nodes, edges })
+    }
+
+    /// IDs of documents with page text, optionally only in one source.
+    pub async fn get_document_ids_with_text(
+        &self,
+        source_id: Option<&str>,
+    ) -> Result<Vec<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = document_pages::table
+                .inner_join(documents::table)
+                .filter(document_pages::ocr_text.is_not_null())
+                .select(document_pages::document_id)
+                .distinct()
+                .order(document_pages::document_id.asc())
+                .into_boxed();
+            if let Some(sid) = source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            query.load(&mut conn).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DocumentVersion;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use crate::repository::models::NewDocumentEntity;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn document(id: &str, title: &str, filename: &str, text: &str) -> Document {
+        let version = DocumentVersion::new_with_metadata(
+            id.as_bytes(),
+            "application/pdf".to_string(),
+            None,
+            Some(filename.to_string()),
+            None,
+        );
+        let mut doc = Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            title.to_string(),
+            format!("https://example.gov/{}", filename),
+            version,
+            serde_json::json!({}),
+        );
+        doc.extracted_text = Some(text.to_string());
+        doc
+    }
+
+    fn file_number<'a>(doc_id: &'a str, number: &'a str, now: &'a str) -> NewDocumentEntity<'a> {
+        NewDocumentEntity {
+            document_id: doc_id,
+            entity_type: "file_number",
+            entity_text: number,
+            normalized_text: number,
+            latitude: None,
+            longitude: None,
+            created_at: now,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_document_relations() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.documents();
+        let letter = document(
+            "letter",
+            "Response letter",
+            "letter.pdf",
+            "This is in response to your request No. 2023-00451.\n\
+             Enclosure: Field_Report.pdf",
+        );
+        let request = document("request", "FOIA Request 2023-00451", "request.pdf", "");
+        let report = document("report", "Field report", "field_report.pdf", "");
+        let memo = document("memo", "Memo", "memo.pdf", "");
+        for doc in [&letter, &request, &report, &memo] {
+            repo.save_with_versions(doc).await.unwrap();
+        }
+        let now = Utc::now().to_rfc3339();
+        repo.save_document_entities(&[
+            file_number("letter", "62-109060", &now),
+            file_number("memo", "62-109060", &now),
+        ])
+        .await
+        .unwrap();
+
+        let relations = repo.refresh_document_relations(&letter).await.unwrap();
+        assert_eq!(relations.len(), 3);
+        assert!(relations.contains(&DocumentRelation::new(
+            "letter",
+            "report",
+            RelationKind::Attachment,
+            "Field_Report.pdf"
+        )));
+        assert!(relations.contains(&DocumentRelation::new(
+            "letter",
+            "request",
+            RelationKind::InResponseTo,
+            "2023-00451"
+        )));
+
+        // The memo sees the shared case number from its side
+        let memo_relations = repo.get_document_relations("memo").await.unwrap();
+        assert_eq!(memo_relations.len(), 1);
+        assert_eq!(memo_relations[0].kind, RelationKind::SharedCaseNumber);
+        assert_eq!(memo_relations[0].other("memo"), "letter");
+
+        // Refreshing the memo keeps the symmetric link without duplicating it
+        repo.refresh_document_relations(&memo).await.unwrap();
+        assert_eq!(
+            repo.get_document_relations("letter").await.unwrap().len(),
+            3
+        );
+
+        let graph = repo.document_relation_graph("report", 2, 10).await.unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.nodes[0].id, "report");
+        assert_eq!(graph.nodes[1].title, "Response letter");
+        assert_eq!(graph.edges.len(), 3);
+
+        let shallow = repo.document_relation_graph("report", 1, 10).await.unwrap();
+        assert_eq!(shallow.nodes.len(), 2);
+        assert_eq!(shallow.edges.len(), 1);
+
+        repo.set_document_relations("letter", &[]).await.unwrap();
+        assert!(repo
+            .get_document_relations("report")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+//! Page search index upkeep.
+//!
+//! On SQLite, page text is indexed in the `document_pages_fts` FTS5 table,
+//! which triggers on `document_pages` keep current as pages are written:
+//! new versions, OCR results, corrections and deletes each update only the
+//! pages they touch. On PostgreSQL the `idx_pages_fts` GIN expression index
+//! plays the same part. Neither needs a full reindex in normal operation;
+//! [`DieselDocumentRepository::check_search_index`] finds drift (a restored
+//! backup, an interrupted migration) and `repair_search_index` fixes it.
+
+use diesel_async::RunQueryDsl;
+
+use super::{CountRow, DieselDocumentRepository};
+use crate::repository::pool::{DieselError, SqliteConn};
+use crate::{with_conn_split, with_write_conn_split};
+
+/// Text a page is indexed by; matches the triggers and the PostgreSQL index.
+const INDEXED_TEXT: &str = "COALESCE(final_text, ocr_text, pdf_text, '')";
+
+/// FTS5 query matching text that contains every word of `query`, each as a
+/// word prefix. `None` for a query without words.
+pub(super) fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// SQLite condition on `dp` (a `document_pages` row) matching pages whose
+/// text contains `query`, with the value to bind to `param`. Uses the search
+/// index unless the connection reads a sharded archive, whose pages live in
+/// several databases; those fall back to a LIKE scan.
+pub(super) async fn sqlite_text_filter(
+    conn: &mut SqliteConn,
+    query: &str,
+    param: &str,
+) -> Result<(String, String), DieselError> {
+    let indexed: Vec<CountRow> = diesel::sql_query(
+        "SELECT COUNT(*) AS count FROM sqlite_master WHERE name = 'document_pages_fts' \
+         AND NOT EXISTS (SELECT 1 FROM sqlite_temp_master WHERE name = 'document_pages')",
+    )
+    .load(conn)
+    .await?;
+    match fts_query(query) {
+        Some(fts) if indexed.first().is_some_and(|r| r.count > 0) => Ok((
+            format!(
+                "dp.id IN (SELECT rowid FROM document_pages_fts WHERE document_pages_fts MATCH {})",
+                param
+            ),
+            fts,
+        )),
+        _ => Ok((
+            format!(
+                "COALESCE(dp.final_text, dp.ocr_text, dp.pdf_text, '') LIKE {}",
+                param
+            ),
+            format!("%{query}%"),
+        )),
+    }
+}
+
+/// How the search index compares with the pages it indexes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchIndexCheck {
+    /// Pages in the database.
+    pub pages: u64,
+    /// Pages the index doesn't have.
+    pub missing: u64,
+    /// Index entries for pages that no longer exist.
+    pub orphaned: u64,
+    /// The index is damaged, or on PostgreSQL missing or invalid, and needs
+    /// a rebuild.
+    pub damaged: bool,
+}
+
+impl SearchIndexCheck {
+    /// Whether search sees every page and nothing else.
+    pub fn is_consistent(&self) -> bool {
+        self.missing == 0 && self.orphaned == 0 && !self.damaged
+    }
+}
+
+impl DieselDocumentRepository {
+    /// Compare the search index with the pages table.
+    pub async fn check_search_index(&self) -> Result<SearchIndexCheck, DieselError> {
+        let count = |rows: Vec<CountRow>| rows.first().map_or(0, |r| r.count as u64);
+
+        let (pages, missing, orphaned) = with_conn_split!(self.pool,
+            sqlite: conn => {
+                let pages = diesel::sql_query("SELECT COUNT(*) AS count FROM document_pages")
+                    .load(&mut conn)
+                    .await?;
+                let missing = diesel::sql_query(
+                    "SELECT COUNT(*) AS count FROM document_pages \
+                     WHERE id NOT IN (SELECT rowid FROM document_pages_fts)",
+                )
+                .load(&mut conn)
+                .await?;
+                let orphaned = diesel::sql_query(
+                    "SELECT COUNT(*) AS count FROM document_pages_fts \
+                     WHERE rowid NOT IN (SELECT id FROM document_pages)",
+                )
+                .load(&mut conn)
+                .await?;
+                (count(pages), count(missing), count(orphaned))
+            },
+            postgres: conn => {
+                let pages = diesel::sql_query("SELECT COUNT(*) AS count FROM document_pages")
+                    .load(&mut conn)
+                    .await?;
+                (count(pages), 0, 0)
+            }
+        );
+
+        // FTS5's own consistency check is a write statement, so both checks
+        // of the index itself run on the writer.
+        let damaged = with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                match diesel::sql_query(
+                    "INSERT INTO document_pages_fts (document_pages_fts) VALUES ('integrity-check')",
+                )
+                .execute(&mut conn)
+                .await
+                {
+                    Ok(_) => Ok(false),
+                    Err(DieselError::DatabaseError(_, info)) => {
+                        tracing::warn!("Search index integrity check failed: {}", info.message());
+                        Ok(true)
+                    }
+                    Err(e) => Err(e),
+                }
+            },
+            postgres: conn => {
+                let valid = diesel::sql_query(
+                    "SELECT COUNT(*) AS count FROM pg_index i \
+                     JOIN pg_class c ON c.oid = i.indexrelid \
+                     WHERE c.relname = 'idx_pages_fts' AND i.indisvalid AND i.indisready",
+                )
+                .load(&mut conn)
+                .await?;
+                Ok::<_, DieselError>(count(valid) == 0)
+            }
+        )?;
+
+        Ok(SearchIndexCheck {
+            pages,
+            missing,
+            orphaned,
+            damaged,
+        })
+    }
+
+    /// Bring the search index in line with the pages table: index missing
+    /// pages and drop orphaned entries, or rebuild it when it's damaged.
+    pub async fn repair_search_index(&self, check: &SearchIndexCheck) -> Result<(), DieselError> {
+        if check.is_consistent() {
+            return Ok(());
+        }
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                if check.damaged {
+                    diesel::sql_query(
+                        "INSERT INTO document_pages_fts (document_pages_fts) VALUES ('delete-all')",
+                    )
+                    .execute(&mut conn)
+                    .await?;
+                } else {
+                    diesel::sql_query(
+                        "DELETE FROM document_pages_fts \
+                         WHERE rowid NOT IN (SELECT id FROM document_pages)",
+                    )
+                    .execute(&mut conn)
+                    .await?;
+                }
+                diesel::sql_query(format!(
+                    "INSERT INTO document_pages_fts (rowid, body) \
+                     SELECT id, {INDEXED_TEXT} FROM document_pages \
+                     WHERE id NOT IN (SELECT rowid FROM document_pages_fts)"
+                ))
+                .execute(&mut conn)
+                .await?;
+                Ok::<_, DieselError>(())
+            },
+            postgres: conn => {
+                diesel::sql_query("DROP INDEX IF EXISTS idx_pages_fts")
+                    .execute(&mut conn)
+                    .await?;
+                diesel::sql_query(format!(
+                    "CREATE INDEX idx_pages_fts ON document_pages \
+                     USING GIN (to_tsvector('english', {INDEXED_TEXT}))"
+                ))
+                .execute(&mut conn)
+                .await?;
+                Ok::<_, DieselError>(())
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentPage, DocumentVersion};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(fts_query("  "), None);
+        assert_eq!(
+            fts_query("Castro \"memo"),
+            Some("\"Castro\"* \"\"\"memo\"*".to_string())
+        );
+    }
+
+    async fn matches(repo: &DieselDocumentRepository, query: &str) -> u64 {
+        repo.count_page_content_matches(query, None, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_index_follows_page_writes() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let repo = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .documents();
+
+        let version = DocumentVersion::new(b"memo", "application/pdf".to_string(), None);
+        let doc = Document::new(
+            "memo".to_string(),
+            "cia".to_string(),
+            "Memo".to_string(),
+            "https://www.cia.gov/readingroom/memo.pdf".to_string(),
+            version,
+            serde_json::json!({}),
+        );
+        repo.save_with_versions(&doc).await.unwrap();
+        let version_id = repo.get_current_version_id("memo").await.unwrap().unwrap();
+        let mut page = DocumentPage::new("memo".to_string(), version_id, 1);
+        page.pdf_text = Some("Meeting with the informant in Havana".to_string());
+        page.compute_final_text();
+        let page_id = repo.save_page(&page).await.unwrap();
+
+        assert_eq!(matches(&repo, "havana informant").await, 1);
+        assert_eq!(matches(&repo, "inform").await, 1);
+
+        repo.store_page_ocr_result(
+            page_id,
+            "tesseract",
+            None,
+            Some("Meeting in Miami"),
+            None,
+            None,
+            None,
+            None,
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let mut page = repo
+            .get_page("memo", version_id as i32, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        page.compute_final_text();
+        repo.save_page(&page).await.unwrap();
+        assert_eq!(matches(&repo, "havana").await, 0);
+        assert_eq!(matches(&repo, "miami").await, 1);
+
+        let check = repo.check_search_index().await.unwrap();
+        assert!(check.is_consistent());
+        assert_eq!(check.pages, 1);
+
+        rusqlite::Connection::open(&db_path)
+            .unwrap()
+            .execute("DELETE FROM document_pages_fts", [])
+            .unwrap();
+        let check = repo.check_search_index().await.unwrap();
+        assert_eq!(check.missing, 1);
+        repo.repair_search_index(&check).await.unwrap();
+        assert!(repo.check_search_index().await.unwrap().is_consistent());
+        assert_eq!(matches(&repo, "miami").await, 1);
+    }
+}
@@ -0,0 +1,132 @@
+//! Writes in a sharded archive.
+//!
+//! A repository on the main database of a sharded archive reads every
+//! shard through views, but its writer only reaches the main database.
+//! Given each shard's own repository with [`DieselDocumentRepository::with_shard`],
+//! it sends writes for documents of sharded sources there instead. Writes
+//! addressed by row ID rather than document are run against every
+//! database; IDs don't collide across shards, so only the owner's row
+//! changes.
+
+use std::sync::Arc;
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_pages, document_versions, documents};
+use crate::with_conn;
+
+/// A shard's repository and the sources it holds.
+#[derive(Clone)]
+pub(super) struct ShardDocuments {
+    sources: Vec<String>,
+    repo: DieselDocumentRepository,
+}
+
+impl DieselDocumentRepository {
+    /// Send writes for documents of `sources` to `repo`, the repository
+    /// on their shard.
+    pub fn with_shard(mut self, sources: Vec<String>, repo: DieselDocumentRepository) -> Self {
+        let mut shards = self.shards.to_vec();
+        shards.push(ShardDocuments { sources, repo });
+        self.shards = shards.into();
+        self
+    }
+
+    /// Apply `f` to every shard's repository.
+    pub(super) fn map_shards(&mut self, f: impl Fn(Self) -> Self) {
+        self.shards = self
+            .shards
+            .iter()
+            .map(|shard| ShardDocuments {
+                sources: shard.sources.clone(),
+                repo: f(shard.repo.clone()),
+            })
+            .collect();
+    }
+
+    /// The repository on the shard holding `source_id`, or `None` if the
+    /// source is in this repository's database.
+    pub(super) fn shard_for_source(&self, source_id: &str) -> Option<&Self> {
+        self.shards
+            .iter()
+            .find(|shard| shard.sources.iter().any(|s| s == source_id))
+            .map(|shard| &shard.repo)
+    }
+
+    /// The repository on the shard holding `document_id`, or `None` if the
+    /// document is in this repository's database or doesn't exist.
+    pub(super) async fn shard_for_document(
+        &self,
+        document_id: &str,
+    ) -> Result<Option<&Self>, DieselError> {
+        if self.shards.is_empty() {
+            return Ok(None);
+        }
+        let source_id: Option<String> = with_conn!(self.pool, conn, {
+            documents::table
+                .find(document_id)
+                .select(documents::source_id)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        Ok(source_id.and_then(|s| self.shard_for_source(&s)))
+    }
+
+    /// The repository on the shard holding version `version_id`.
+    pub(super) async fn shard_for_version(
+        &self,
+        version_id: i64,
+    ) -> Result<Option<&Self>, DieselError> {
+        if self.shards.is_empty() {
+            return Ok(None);
+        }
+        let document_id: Option<String> = with_conn!(self.pool, conn, {
+            document_versions::table
+                .find(version_id as i32)
+                .select(document_versions::document_id)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        match document_id {
+            Some(id) => self.shard_for_document(&id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// The repository on the shard holding page `page_id`.
+    pub(super) async fn shard_for_page(&self, page_id: i64) -> Result<Option<&Self>, DieselError> {
+        if self.shards.is_empty() {
+            return Ok(None);
+        }
+        let document_id: Option<String> = with_conn!(self.pool, conn, {
+            document_pages::table
+                .find(page_id as i32)
+                .select(document_pages::document_id)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        match document_id {
+            Some(id) => self.shard_for_document(&id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// This repository without its shards, writing only its own database.
+    pub(super) fn without_shards(&self) -> Self {
+        Self {
+            shards: Arc::new([]),
+            ..self.clone()
+        }
+    }
+
+    /// Every shard's repository, for writes that apply to all databases.
+    pub(super) fn shard_repos(&self) -> impl Iterator<Item = &Self> {
+        self.shards.iter().map(|shard| &shard.repo)
+    }
+}
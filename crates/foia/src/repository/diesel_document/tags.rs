@@ -0,0 +1,566 @@
+//! Bulk tag maintenance operations.
+//!
+//! Tags live in `documents.tags` as a JSON array. These operations rewrite
+//! that array for every affected document, so near-duplicate tags produced
+//! by LLM tagging can be cleaned up in one pass.
+
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::exemptions::exemption_filter;
+use super::pdf_metadata::pdf_date_filter;
+use super::processing::processing_filter;
+use super::queries::{poor_ocr_filter, timeline_range_filter, BrowseParams};
+use super::DieselDocumentRepository;
+use crate::models::is_valid_tag_namespace;
+use crate::repository::pool::DieselError;
+use crate::schema::{document_pdf_metadata, documents};
+use crate::{with_conn, with_conn_split, with_write_conn};
+
+/// Aggregate counts for one tag namespace (the part before `:`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagNamespaceCount {
+    pub namespace: String,
+    /// Distinct tags in the namespace.
+    pub tag_count: u64,
+    /// Documents carrying at least one tag in the namespace.
+    pub document_count: u64,
+}
+
+#[derive(diesel::QueryableByName)]
+struct TagCountRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    tag: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    count: i64,
+}
+
+#[derive(diesel::QueryableByName)]
+struct NamespaceCountRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    namespace: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    tag_count: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    document_count: i64,
+}
+
+impl DieselDocumentRepository {
+    /// Document counts per tag, most used first.
+    ///
+    /// With `namespace`, only tags of the form `namespace:value` are returned.
+    pub async fn get_tag_counts(
+        &self,
+        namespace: Option<&str>,
+    ) -> Result<Vec<(String, u64)>, DieselError> {
+        let rows: Vec<TagCountRow> = with_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        r#"SELECT value AS tag, COUNT(DISTINCT documents.id) AS count
+                           FROM documents, json_each(documents.tags)
+                           WHERE documents.tags IS NOT NULL AND documents.tags != '[]'
+                           AND ($1 IS NULL OR substr(value, 1, length($1) + 1) = $1 || ':')
+                           GROUP BY value
+                           ORDER BY count DESC, value"#,
+                    )
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(namespace),
+                    &mut conn,
+                )
+                .await?
+            },
+            postgres: conn => {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        r#"SELECT tag, COUNT(DISTINCT documents.id) AS count
+                           FROM documents, jsonb_array_elements_text(documents.tags::jsonb) AS tag
+                           WHERE documents.tags IS NOT NULL AND documents.tags != '[]'
+                           AND ($1::text IS NULL OR left(tag, length($1) + 1) = $1 || ':')
+                           GROUP BY tag
+                           ORDER BY count DESC, tag"#,
+                    )
+                    .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(namespace),
+                    &mut conn,
+                )
+                .await?
+            }
+        );
+
+        Ok(rows.into_iter().map(|r| (r.tag, r.count as u64)).collect())
+    }
+
+    /// Tag and document counts per tag namespace, most used first.
+    ///
+    /// Unprefixed tags are not included.
+    pub async fn get_tag_namespaces(&self) -> Result<Vec<TagNamespaceCount>, DieselError> {
+        let rows: Vec<NamespaceCountRow> = with_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        r#"SELECT substr(value, 1, instr(value, ':') - 1) AS namespace,
+                                  COUNT(DISTINCT value) AS tag_count,
+                                  COUNT(DISTINCT documents.id) AS document_count
+                           FROM documents, json_each(documents.tags)
+                           WHERE documents.tags IS NOT NULL AND documents.tags != '[]'
+                           AND instr(value, ':') > 1
+                           AND substr(value, instr(value, ':') + 1, 1) NOT IN ('', '/', ' ')
+                           GROUP BY namespace
+                           ORDER BY document_count DESC, namespace"#,
+                    ),
+                    &mut conn,
+                )
+                .await?
+            },
+            postgres: conn => {
+                diesel_async::RunQueryDsl::load(
+                    diesel::sql_query(
+                        r#"SELECT split_part(tag, ':', 1) AS namespace,
+                                  COUNT(DISTINCT tag) AS tag_count,
+                                  COUNT(DISTINCT documents.id) AS document_count
+                           FROM documents, jsonb_array_elements_text(documents.tags::jsonb) AS tag
+                           WHERE documents.tags IS NOT NULL AND documents.tags != '[]'
+                           AND position(':' in tag) > 1
+                           AND substr(tag, position(':' in tag) + 1, 1) NOT IN ('', '/', ' ')
+                           GROUP BY 1
+                           ORDER BY document_count DESC, namespace"#,
+                    ),
+                    &mut conn,
+                )
+                .await?
+            }
+        );
+
+        // SQL can't check the namespace charset; match split_tag_namespace
+        Ok(rows
+            .into_iter()
+            .filter(|r| is_valid_tag_namespace(&r.namespace))
+            .map(|r| TagNamespaceCount {
+                namespace: r.namespace,
+                tag_count: r.tag_count as u64,
+                document_count: r.document_count as u64,
+            })
+            .collect())
+    }
+
+    /// Rename a tag on every document that has it.
+    ///
+    /// If a document already carries `to`, the two are merged. Returns the
+    /// number of documents updated.
+    pub async fn rename_tag(&self, from: &str, to: &str) -> Result<u64, DieselError> {
+        self.merge_tags(&[from.to_string()], to).await
+    }
+
+    /// Replace each of `from` with `into` across all documents.
+    pub async fn merge_tags(&self, from: &[String], into: &str) -> Result<u64, DieselError> {
+        let into = into.trim();
+        let from: Vec<&str> = from
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty() && *t != into)
+            .collect();
+        if from.is_empty() || into.is_empty() {
+            return Ok(0);
+        }
+
+        let candidates = self.documents_with_any_tag(&from).await?;
+        self.rewrite_tags(candidates, |tags| {
+            let before = tags.len();
+            tags.retain(|t| !from.contains(&t.as_str()));
+            if tags.len() == before {
+                return false;
+            }
+            if !tags.iter().any(|t| t == into) {
+                tags.push(into.to_string());
+            }
+            true
+        })
+        .await
+    }
+
+    /// Remove a tag from every document that has it.
+    pub async fn delete_tag(&self, tag: &str) -> Result<u64, DieselError> {
+        let tag = tag.trim();
+        let candidates = self.documents_with_any_tag(&[tag]).await?;
+        self.rewrite_tags(candidates, |tags| {
+            let before = tags.len();
+            tags.retain(|t| t != tag);
+            tags.len() != before
+        })
+        .await
+    }
+
+    /// Add a tag to every document matching `filter`.
+    ///
+    /// `filter.limit`, `cursor` and sort options are ignored.
+    pub async fn add_tag_matching(
+        &self,
+        tag: &str,
+        filter: &BrowseParams<'_>,
+    ) -> Result<u64, DieselError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Ok(0);
+        }
+        let candidates = self.documents_matching(filter).await?;
+        self.rewrite_tags(candidates, |tags| {
+            if tags.iter().any(|t| t == tag) {
+                return false;
+            }
+            tags.push(tag.to_string());
+            true
+        })
+        .await
+    }
+
+    /// Add tags to one document, keeping the ones it already has.
+    ///
+    /// Returns whether any tag was new.
+    pub async fn add_tags(&self, id: &str, tags: &[String]) -> Result<bool, DieselError> {
+        let tags: Vec<&str> = tags
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        if tags.is_empty() {
+            return Ok(false);
+        }
+        let raw: Option<Option<String>> = with_conn!(self.pool, conn, {
+            documents::table
+                .find(id)
+                .select(documents::tags)
+                .first(&mut conn)
+                .await
+                .optional()
+        })?;
+        let Some(raw) = raw else {
+            return Ok(false);
+        };
+        let updated = self
+            .rewrite_tags(vec![(id.to_string(), raw)], |current| {
+                let before = current.len();
+                for tag in &tags {
+                    if !current.iter().any(|t| t == tag) {
+                        current.push(tag.to_string());
+                    }
+                }
+                current.len() != before
+            })
+            .await?;
+        Ok(updated > 0)
+    }
+
+    /// Remove a tag from every document matching `filter`.
+    ///
+    /// `filter.limit`, `cursor` and sort options are ignored.
+    pub async fn remove_tag_matching(
+        &self,
+        tag: &str,
+        filter: &BrowseParams<'_>,
+    ) -> Result<u64, DieselError> {
+        let tag = tag.trim();
+        let candidates = self.documents_matching(filter).await?;
+        self.rewrite_tags(candidates, |tags| {
+            let before = tags.len();
+            tags.retain(|t| t != tag);
+            tags.len() != before
+        })
+        .await
+    }
+
+    /// Count documents carrying a tag (exact match).
+    pub async fn count_by_tag(&self, tag: &str) -> Result<u64, DieselError> {
+        let tag = tag.trim();
+        let candidates = self.documents_with_any_tag(&[tag]).await?;
+        Ok(candidates
+            .iter()
+            .filter(|(_, tags)| parse_tags(tags.as_deref()).iter().any(|t| t == tag))
+            .count() as u64)
+    }
+
+    /// Load `(id, tags)` for documents whose tag JSON might contain any of
+    /// `tags`. The LIKE prefilter can over-match; callers check exactly.
+    async fn documents_with_any_tag(
+        &self,
+        tags: &[&str],
+    ) -> Result<Vec<(String, Option<String>)>, DieselError> {
+        let mut seen = HashSet::new();
+        let mut rows: Vec<(String, Option<String>)> = Vec::new();
+        for tag in tags {
+            // Match the JSON-encoded string so quotes and escapes line up
+            let pattern = format!("%{}%", serde_json::to_string(tag).unwrap_or_default());
+            let found: Vec<(String, Option<String>)> = with_conn!(self.pool, conn, {
+                documents::table
+                    .select((documents::id, documents::tags))
+                    .filter(documents::tags.like(pattern))
+                    .load(&mut conn)
+                    .await
+            })?;
+            rows.extend(found.into_iter().filter(|(id, _)| seen.insert(id.clone())));
+        }
+        Ok(rows)
+    }
+
+    /// Load `(id, tags)` for every document matching a browse filter.
+    async fn documents_matching(
+        &self,
+        filter: &BrowseParams<'_>,
+    ) -> Result<Vec<(String, Option<String>)>, DieselError> {
+        let date_range = timeline_range_filter(filter.date_from, filter.date_to);
+
+        with_conn!(self.pool, conn, {
+            let mut query = documents::table
+                .select((documents::id, documents::tags))
+                .into_boxed();
+            if let Some(sid) = filter.source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            if let Some(st) = filter.status {
+                query = query.filter(documents::status.eq(st));
+            }
+            if !filter.categories.is_empty() {
+                query = query.filter(documents::category_id.eq_any(filter.categories));
+            }
+            if !filter.record_types.is_empty() {
+                query = query.filter(documents::record_type.eq_any(filter.record_types));
+            }
+            if filter.poor_ocr {
+                query = query.filter(poor_ocr_filter());
+            }
+            if !filter.exemptions.is_empty() {
+                query = query.filter(exemption_filter(filter.exemptions));
+            }
+            if !filter.pdf_producers.is_empty() {
+                query = query.filter(
+                    documents::id.eq_any(
+                        document_pdf_metadata::table
+                            .filter(document_pdf_metadata::producer.eq_any(filter.pdf_producers))
+                            .select(document_pdf_metadata::document_id),
+                    ),
+                );
+            }
+            if let Some(pdf_dates) = pdf_date_filter(filter.pdf_years, filter.pdf_postdated) {
+                query = query.filter(pdf_dates);
+            }
+            if let Some(state) = filter.processing {
+                query = query.filter(processing_filter(state));
+            }
+            if let Some(range) = date_range {
+                query = query.filter(range);
+            }
+            for tag in filter.tags {
+                let pattern = format!("%{}%", tag);
+                query = query.filter(documents::tags.like(pattern));
+            }
+            if let Some(q) = filter.search_query {
+                if !q.is_empty() {
+                    let pattern = format!("%{}%", q);
+                    query = query.filter(
+                        documents::title
+                            .like(pattern.clone())
+                            .or(documents::synopsis.like(pattern)),
+                    );
+                }
+            }
+            query.load(&mut conn).await
+        })
+    }
+
+    /// Apply `edit` to each document's tag list and persist the ones it
+    /// reports as changed. Returns the number of documents written.
+    async fn rewrite_tags(
+        &self,
+        rows: Vec<(String, Option<String>)>,
+        edit: impl Fn(&mut Vec<String>) -> bool,
+    ) -> Result<u64, DieselError> {
+        let updates: Vec<(String, String)> = rows
+            .into_iter()
+            .filter_map(|(id, raw)| {
+                let mut tags = parse_tags(raw.as_deref());
+                if !edit(&mut tags) {
+                    return None;
+                }
+                let json = serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string());
+                Some((id, json))
+            })
+            .collect();
+
+        if updates.is_empty() {
+            return Ok(0);
+        }
+
+        // Rows are read across shards; each update lands where its row is
+        use diesel_async::AsyncConnection;
+        for repo in std::iter::once(self).chain(self.shard_repos()) {
+            let updates = &updates;
+            with_write_conn!(repo.pool, conn, {
+                conn.transaction(|conn| {
+                    Box::pin(async move {
+                        for (id, json) in updates {
+                            diesel::update(documents::table.find(id))
+                                .set(documents::tags.eq(json))
+                                .execute(conn)
+                                .await?;
+                        }
+                        Ok::<_, DieselError>(())
+                    })
+                })
+                .await
+            })?;
+        }
+        Ok(updates.len() as u64)
+    }
+}
+
+fn parse_tags(raw: Option<&str>) -> Vec<String> {
+    raw.and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, DocumentStatus};
+    use crate::repository::diesel_document::tests::setup_test_db;
+    use chrono::Utc;
+
+    async fn insert_tagged(repo: &DieselDocumentRepository, id: &str, source: &str, tags: &[&str]) {
+        let doc = Document {
+            id: id.to_string(),
+            source_id: source.to_string(),
+            title: id.to_string(),
+            source_url: format!("https://example.com/{}.pdf", id),
+            extracted_text: None,
+            synopsis: None,
+            tags: vec![],
+            status: DocumentStatus::Pending,
+            metadata: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            discovery_method: "seed".to_string(),
+            versions: vec![],
+        };
+        repo.save(&doc).await.unwrap();
+        let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+        repo.update_synopsis_and_tags(id, None, &tags)
+            .await
+            .unwrap();
+    }
+
+    async fn tags_of(repo: &DieselDocumentRepository, id: &str) -> Vec<String> {
+        repo.get(id).await.unwrap().unwrap().tags
+    }
+
+    #[tokio::test]
+    async fn test_rename_and_merge_tags() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        insert_tagged(&repo, "a", "src", &["agency:fbi", "surveillance"]).await;
+        insert_tagged(&repo, "b", "src", &["agency:f.b.i", "agency:fbi"]).await;
+        insert_tagged(&repo, "c", "src", &["agency:fbi-field"]).await;
+
+        let updated = repo.rename_tag("agency:fbi", "agency:f.b.i").await.unwrap();
+        assert_eq!(updated, 2);
+        assert_eq!(
+            tags_of(&repo, "a").await,
+            vec!["surveillance", "agency:f.b.i"]
+        );
+        assert_eq!(tags_of(&repo, "b").await, vec!["agency:f.b.i"]);
+        // Substring matches are left alone
+        assert_eq!(tags_of(&repo, "c").await, vec!["agency:fbi-field"]);
+
+        let merged = repo
+            .merge_tags(&["agency:fbi-field".to_string()], "agency:f.b.i")
+            .await
+            .unwrap();
+        assert_eq!(merged, 1);
+        assert_eq!(repo.count_by_tag("agency:f.b.i").await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_add_tags() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        insert_tagged(&repo, "a", "src", &["agency:fbi"]).await;
+        let tags = vec!["agency:fbi".to_string(), "topic:cointelpro".to_string()];
+        assert!(repo.add_tags("a", &tags).await.unwrap());
+        assert_eq!(
+            tags_of(&repo, "a").await,
+            vec!["agency:fbi", "topic:cointelpro"]
+        );
+        assert!(!repo.add_tags("a", &tags).await.unwrap());
+        assert!(!repo.add_tags("missing", &tags).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_tag_namespace_counts() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        insert_tagged(
+            &repo,
+            "a",
+            "src",
+            &["agency:fbi", "topic:cointelpro", "memo"],
+        )
+        .await;
+        insert_tagged(&repo, "b", "src", &["agency:fbi", "agency:cia"]).await;
+        insert_tagged(&repo, "c", "src", &["https://example.com", "memo"]).await;
+
+        let namespaces = repo.get_tag_namespaces().await.unwrap();
+        assert_eq!(
+            namespaces,
+            vec![
+                TagNamespaceCount {
+                    namespace: "agency".to_string(),
+                    tag_count: 2,
+                    document_count: 2,
+                },
+                TagNamespaceCount {
+                    namespace: "topic".to_string(),
+                    tag_count: 1,
+                    document_count: 1,
+                },
+            ]
+        );
+
+        let agency = repo.get_tag_counts(Some("agency")).await.unwrap();
+        assert_eq!(
+            agency,
+            vec![("agency:fbi".to_string(), 2), ("agency:cia".to_string(), 1)]
+        );
+
+        let all = repo.get_tag_counts(None).await.unwrap();
+        assert_eq!(all.len(), 5);
+        assert!(all.contains(&("memo".to_string(), 2)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_filtered_tag_ops() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        insert_tagged(&repo, "a", "fbi", &["memo"]).await;
+        insert_tagged(&repo, "b", "fbi", &[]).await;
+        insert_tagged(&repo, "c", "cia", &["memo"]).await;
+
+        let filter = BrowseParams {
+            source_id: Some("fbi"),
+            ..Default::default()
+        };
+        assert_eq!(repo.add_tag_matching("vault", &filter).await.unwrap(), 2);
+        // Already tagged documents are not rewritten
+        assert_eq!(repo.add_tag_matching("vault", &filter).await.unwrap(), 0);
+        assert_eq!(tags_of(&repo, "b").await, vec!["vault"]);
+        assert!(tags_of(&repo, "c").await.iter().all(|t| t != "vault"));
+
+        assert_eq!(repo.remove_tag_matching("memo", &filter).await.unwrap(), 1);
+        assert_eq!(tags_of(&repo, "c").await, vec!["memo"]);
+
+        assert_eq!(repo.delete_tag("vault").await.unwrap(), 2);
+        assert_eq!(repo.count_by_tag("vault").await.unwrap(), 0);
+    }
+}
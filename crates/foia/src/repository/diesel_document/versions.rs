@@ -8,7 +8,7 @@ use crate::models::DocumentVersion;
 use crate::repository::models::DocumentVersionRecord;
 use crate::repository::pool::DieselError;
 use crate::schema::document_versions;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 impl DieselDocumentRepository {
     /// Load versions for a document.
@@ -69,6 +69,8 @@ impl DieselDocumentRepository {
         use crate::repository::sea_tables::DocumentVersions;
         use sea_query::Query;
 
+        route_to_shard!(self, document_id, |shard| shard
+            .add_version(document_id, version));
         let file_path = version
             .file_path
             .as_ref()
@@ -79,6 +81,10 @@ impl DieselDocumentRepository {
         let server_date = version.server_date.map(|d| d.to_rfc3339());
         let page_count = version.page_count.map(|c| c as i32);
         let earliest_archived_at = version.earliest_archived_at.map(|d| d.to_rfc3339());
+        let checksum_verification = version
+            .checksum
+            .as_ref()
+            .and_then(|c| serde_json::to_string(c).ok());
 
         let stmt = Query::insert()
             .into_table(DocumentVersions::Table)
@@ -97,6 +103,8 @@ impl DieselDocumentRepository {
                 DocumentVersions::ArchiveSnapshotId,
                 DocumentVersions::EarliestArchivedAt,
                 DocumentVersions::DedupIndex,
+                DocumentVersions::ChecksumVerification,
+                DocumentVersions::DeclaredMimeType,
             ])
             .values_panic([
                 document_id.to_string().into(),
@@ -113,13 +121,15 @@ impl DieselDocumentRepository {
                 version.archive_snapshot_id.into(),
                 earliest_archived_at.clone().into(),
                 dedup_index.into(),
+                checksum_verification.clone().into(),
+                version.declared_mime_type.clone().into(),
             ])
             .returning_col(DocumentVersions::Id)
             .to_owned();
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let result: ReturningId = diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(document_id)
                 .bind::<diesel::sql_types::Text, _>(&version.content_hash)
@@ -149,6 +159,12 @@ impl DieselDocumentRepository {
                     earliest_archived_at.as_deref(),
                 )
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(dedup_index)
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
+                    checksum_verification.as_deref(),
+                )
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
+                    version.declared_mime_type.as_deref(),
+                )
                 .get_result(&mut conn)
                 .await?;
             Ok(result.id as i64)
@@ -195,7 +211,10 @@ impl DieselDocumentRepository {
         version_id: i64,
         mime_type: &str,
     ) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        for shard in self.shard_repos() {
+            Box::pin(shard.update_version_mime_type(version_id, mime_type)).await?;
+        }
+        with_write_conn!(self.pool, conn, {
             diesel::update(document_versions::table.find(version_id as i32))
                 .set(document_versions::mime_type.eq(mime_type))
                 .execute(&mut conn)
@@ -216,21 +235,19 @@ impl DieselDocumentRepository {
         Ok(())
     }
 
-    /// Find an existing file by dual hash and size for deduplication.
+    /// Find an existing file by BLAKE3 hash and size for deduplication.
     ///
     /// Returns the file_path if a matching file already exists, allowing
-    /// the caller to skip writing a duplicate file to disk.
-    ///
-    /// Uses SHA-256 + BLAKE3 + file_size for collision-resistant matching.
+    /// the caller to skip writing a duplicate file to disk. Versions stored
+    /// before BLAKE3 and not yet backfilled match on `sha256`, if given.
     pub async fn find_existing_file(
         &self,
-        sha256_hash: &str,
         blake3_hash: &str,
+        sha256: Option<&str>,
         file_size: i64,
     ) -> Result<Option<String>, DieselError> {
-        with_conn!(self.pool, conn, {
+        let found = with_conn!(self.pool, conn, {
             document_versions::table
-                .filter(document_versions::content_hash.eq(sha256_hash))
                 .filter(document_versions::content_hash_blake3.eq(blake3_hash))
                 .filter(document_versions::file_size.eq(file_size as i32))
                 .select(document_versions::file_path)
@@ -238,16 +255,82 @@ impl DieselDocumentRepository {
                 .await
                 .optional()
                 .map(|opt| opt.flatten())
+        })?;
+        let Some(sha256) = sha256.filter(|_| found.is_none()) else {
+            return Ok(found);
+        };
+        with_conn!(self.pool, conn, {
+            document_versions::table
+                .filter(document_versions::content_hash.eq(sha256))
+                .filter(document_versions::content_hash_blake3.is_null())
+                .filter(document_versions::file_size.eq(file_size as i32))
+                .select(document_versions::file_path)
+                .first::<Option<String>>(&mut conn)
+                .await
+                .optional()
+                .map(|opt| opt.flatten())
         })
     }
 
+    /// Find stored content by the hash prefix in its file name.
+    ///
+    /// Returns the full content hash and an original filename recorded for
+    /// it, or `None` if nothing matches or the prefix is ambiguous.
+    ///
+    /// Matches with a range on `content_hash` rather than `LIKE` so the
+    /// index is used on both backends.
+    pub async fn find_file_by_hash_prefix(
+        &self,
+        hash_prefix: &str,
+    ) -> Result<Option<(String, Option<String>)>, DieselError> {
+        if hash_prefix.is_empty() || !hash_prefix.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Ok(None);
+        }
+        let lower = hash_prefix.to_ascii_lowercase();
+        // Every hash starting with the prefix sorts before the prefix with
+        // its last character bumped by one
+        let mut upper = lower.clone();
+        let last = upper.pop().unwrap_or('0');
+        upper.push((last as u8 + 1) as char);
+
+        let hashes: Vec<String> = with_conn!(self.pool, conn, {
+            document_versions::table
+                .filter(document_versions::content_hash.ge(&lower))
+                .filter(document_versions::content_hash.lt(&upper))
+                .select(document_versions::content_hash)
+                .distinct()
+                .order(document_versions::content_hash.asc())
+                .limit(2)
+                .load(&mut conn)
+                .await
+        })?;
+
+        let [hash] = hashes.as_slice() else {
+            return Ok(None);
+        };
+        let filename: Option<String> = with_conn!(self.pool, conn, {
+            document_versions::table
+                .filter(document_versions::content_hash.eq(hash))
+                .filter(document_versions::original_filename.is_not_null())
+                .select(document_versions::original_filename)
+                .first::<Option<String>>(&mut conn)
+                .await
+                .optional()
+        })?
+        .flatten();
+        Ok(Some((hash.clone(), filename)))
+    }
+
     /// Clear the stored file_path (migrate to deterministic) and set dedup_index.
     pub async fn clear_version_file_path(
         &self,
         version_id: i64,
         dedup_index: Option<i32>,
     ) -> Result<(), DieselError> {
-        with_conn!(self.pool, conn, {
+        for shard in self.shard_repos() {
+            Box::pin(shard.clear_version_file_path(version_id, dedup_index)).await?;
+        }
+        with_write_conn!(self.pool, conn, {
             diesel::update(document_versions::table.find(version_id as i32))
                 .set((
                     document_versions::file_path.eq(None::<String>),
@@ -280,14 +363,19 @@ impl DieselDocumentRepository {
         if version_ids.is_empty() {
             return Ok(0);
         }
-        with_conn!(self.pool, conn, {
+        let mut cleared = 0;
+        for shard in self.shard_repos() {
+            cleared += Box::pin(shard.clear_version_file_paths_batch(version_ids)).await?;
+        }
+        let local = with_write_conn!(self.pool, conn, {
             diesel::update(
                 document_versions::table.filter(document_versions::id.eq_any(version_ids)),
             )
             .set(document_versions::file_path.eq(None::<String>))
             .execute(&mut conn)
             .await
-        })
+        })?;
+        Ok(cleared + local)
     }
 
     /// Get versions with non-null file_path for legacy migration.
@@ -322,7 +410,67 @@ impl DieselDocumentRepository {
             .collect())
     }
 
-    /// Get all content hashes for duplicate detection.
+    /// Get versions stored before BLAKE3 hashing, for the hash backfill.
+    /// Returns (version, source_url, title) tuples in batches using cursor
+    /// pagination on version id.
+    pub async fn get_versions_missing_blake3(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<(DocumentVersion, String, String)>, DieselError> {
+        use crate::schema::documents;
+
+        let records: Vec<(DocumentVersionRecord, String, String)> = with_conn!(self.pool, conn, {
+            document_versions::table
+                .inner_join(documents::table)
+                .filter(document_versions::content_hash_blake3.is_null())
+                .filter(document_versions::id.gt(after_id as i32))
+                .order(document_versions::id.asc())
+                .limit(limit as i64)
+                .select((
+                    DocumentVersionRecord::as_select(),
+                    documents::source_url,
+                    documents::title,
+                ))
+                .load(&mut conn)
+                .await
+        })?;
+
+        Ok(records
+            .into_iter()
+            .map(|(rec, url, title)| (Self::version_record_to_model(rec), url, title))
+            .collect())
+    }
+
+    /// Count versions without a BLAKE3 hash.
+    pub async fn count_versions_missing_blake3(&self) -> Result<u64, DieselError> {
+        use diesel::dsl::count_star;
+        with_conn!(self.pool, conn, {
+            let n: i64 = document_versions::table
+                .filter(document_versions::content_hash_blake3.is_null())
+                .select(count_star())
+                .first(&mut conn)
+                .await?;
+            Ok(n as u64)
+        })
+    }
+
+    /// Record the BLAKE3 hash of a version's file. The SHA-256 in
+    /// `content_hash` is left alone, since stored files are named by it.
+    pub async fn set_version_blake3(&self, version_id: i64, hash: &str) -> Result<(), DieselError> {
+        for shard in self.shard_repos() {
+            Box::pin(shard.set_version_blake3(version_id, hash)).await?;
+        }
+        with_write_conn!(self.pool, conn, {
+            diesel::update(document_versions::table.find(version_id as i32))
+                .set(document_versions::content_hash_blake3.eq(hash))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Get all content hashes for duplicate detection, BLAKE3 where known.
     /// Returns (doc_id, source_id, content_hash, title) tuples
     pub async fn get_content_hashes(
         &self,
@@ -341,7 +489,8 @@ impl DieselDocumentRepository {
 
         let results: Vec<HashRow> = with_conn!(self.pool, conn, {
             diesel::sql_query(
-                r#"SELECT dv.document_id, d.source_id, dv.content_hash, d.title
+                r#"SELECT dv.document_id, d.source_id,
+                          COALESCE(dv.content_hash_blake3, dv.content_hash) AS content_hash, d.title
                    FROM document_versions dv
                    JOIN documents d ON dv.document_id = d.id
                    WHERE dv.content_hash IS NOT NULL
@@ -362,7 +511,7 @@ impl DieselDocumentRepository {
             .collect())
     }
 
-    /// Find documents by content hash.
+    /// Find documents by content hash, either BLAKE3 or a legacy SHA-256.
     /// Returns (source_id, document_id, title) tuples
     pub async fn find_sources_by_hash(
         &self,
@@ -386,7 +535,7 @@ impl DieselDocumentRepository {
                         r#"SELECT d.source_id, d.id as document_id, d.title
                            FROM documents d
                            JOIN document_versions dv ON d.id = dv.document_id
-                           WHERE dv.content_hash = $1
+                           WHERE (dv.content_hash_blake3 = $1 OR dv.content_hash = $1)
                            AND d.source_id != $2"#,
                     )
                     .bind::<diesel::sql_types::Text, _>(content_hash)
@@ -400,7 +549,7 @@ impl DieselDocumentRepository {
                         r#"SELECT d.source_id, d.id as document_id, d.title
                            FROM documents d
                            JOIN document_versions dv ON d.id = dv.document_id
-                           WHERE dv.content_hash = $1"#,
+                           WHERE (dv.content_hash_blake3 = $1 OR dv.content_hash = $1)"#,
                     )
                     .bind::<diesel::sql_types::Text, _>(content_hash),
                     &mut conn,
@@ -432,4 +581,42 @@ mod tests {
         assert!(result.is_ok());
         assert!(result.unwrap().is_empty());
     }
+
+    #[tokio::test]
+    async fn test_find_existing_file_before_backfill() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+
+        let content = b"scanned before blake3";
+        let sha256 = DocumentVersion::compute_hash_sha256(content);
+        let blake3 = DocumentVersion::compute_hash(content);
+        let mut legacy = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        legacy.content_hash = sha256.clone();
+        legacy.content_hash_blake3 = None;
+        legacy.file_path = Some("ab/legacy.pdf".into());
+        let id = repo.add_version("doc-1", &legacy).await.unwrap();
+
+        let size = content.len() as i64;
+        assert_eq!(
+            repo.find_existing_file(&blake3, None, size).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            repo.find_existing_file(&blake3, Some(&sha256), size)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("ab/legacy.pdf")
+        );
+
+        // Once backfilled, the BLAKE3 hash matches on its own
+        repo.set_version_blake3(id, &blake3).await.unwrap();
+        assert_eq!(
+            repo.find_existing_file(&blake3, None, size)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("ab/legacy.pdf")
+        );
+    }
 }
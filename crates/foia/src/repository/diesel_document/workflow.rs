@@ -0,0 +1,190 @@
+//! Status transitions checked against the workflow.
+//!
+//! Each update only touches documents whose current status may move to the
+//! new one, so a check and its update can't race with another writer.
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::DieselDocumentRepository;
+use crate::models::DocumentStatus;
+use crate::repository::pool::DieselError;
+use crate::schema::documents;
+use crate::with_write_conn;
+
+impl DieselDocumentRepository {
+    /// Stored names of the statuses allowed to move to `to`.
+    fn transition_sources(&self, to: DocumentStatus) -> Vec<&'static str> {
+        self.workflow
+            .sources(to)
+            .into_iter()
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    /// Move a document to `to` if the workflow allows it from its current
+    /// status. Returns false when it doesn't, or the document is missing.
+    pub async fn transition_status(
+        &self,
+        id: &str,
+        to: DocumentStatus,
+    ) -> Result<bool, DieselError> {
+        route_to_shard!(self, id, |shard| shard.transition_status(id, to));
+        let sources = self.transition_sources(to);
+        let now = Utc::now().to_rfc3339();
+
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(
+                documents::table
+                    .filter(documents::id.eq(id))
+                    .filter(documents::status.eq_any(&sources)),
+            )
+            .set((
+                documents::status.eq(to.as_str()),
+                documents::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await
+        })?;
+        if rows == 0 {
+            tracing::debug!("Document {} not moved to {}", id, to.label());
+        }
+        Ok(rows > 0)
+    }
+
+    /// Move the listed documents to `to`, skipping those the workflow
+    /// doesn't allow to move there. Returns how many moved.
+    pub async fn transition_documents(
+        &self,
+        ids: &[String],
+        to: DocumentStatus,
+    ) -> Result<u64, DieselError> {
+        let mut moved = 0;
+        for shard in self.shard_repos() {
+            moved += Box::pin(shard.transition_documents(ids, to)).await?;
+        }
+        let sources = self.transition_sources(to);
+        let now = Utc::now().to_rfc3339();
+
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(
+                documents::table
+                    .filter(documents::id.eq_any(ids))
+                    .filter(documents::status.eq_any(&sources))
+                    .filter(documents::status.ne(to.as_str())),
+            )
+            .set((
+                documents::status.eq(to.as_str()),
+                documents::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await
+        })?;
+        Ok(moved + rows as u64)
+    }
+
+    /// Move every document in `from`, optionally only from one source, to
+    /// `to`. Moves nothing if the workflow doesn't allow it.
+    pub async fn transition_all(
+        &self,
+        from: DocumentStatus,
+        to: DocumentStatus,
+        source_id: Option<&str>,
+    ) -> Result<u64, DieselError> {
+        if from == to || !self.workflow.allows(from, to) {
+            return Ok(0);
+        }
+        let mut moved = 0;
+        for shard in self.shard_repos() {
+            moved += Box::pin(shard.transition_all(from, to, source_id)).await?;
+        }
+        let now = Utc::now().to_rfc3339();
+
+        let rows = with_write_conn!(self.pool, conn, {
+            let mut query = diesel::update(documents::table)
+                .filter(documents::status.eq(from.as_str()))
+                .into_boxed();
+            if let Some(sid) = source_id {
+                query = query.filter(documents::source_id.eq(sid));
+            }
+            query
+                .set((
+                    documents::status.eq(to.as_str()),
+                    documents::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        Ok(moved + rows as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, StatusWorkflow};
+    use crate::repository::diesel_document::tests::setup_test_db;
+
+    fn doc(id: &str, status: DocumentStatus) -> Document {
+        Document {
+            id: id.to_string(),
+            source_id: "test-source".to_string(),
+            title: id.to_string(),
+            source_url: format!("https://example.com/{}.pdf", id),
+            extracted_text: None,
+            synopsis: None,
+            tags: vec![],
+            status,
+            metadata: serde_json::Value::Object(Default::default()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            discovery_method: "seed".to_string(),
+            versions: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transitions() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselDocumentRepository::new(pool);
+        repo.save(&doc("a", DocumentStatus::Indexed)).await.unwrap();
+        repo.save(&doc("b", DocumentStatus::Indexed)).await.unwrap();
+        repo.save(&doc("c", DocumentStatus::Pending)).await.unwrap();
+
+        assert!(repo
+            .transition_status("a", DocumentStatus::Reviewed)
+            .await
+            .unwrap());
+        assert!(!repo
+            .transition_status("c", DocumentStatus::Published)
+            .await
+            .unwrap());
+        let c = repo.get("c").await.unwrap().unwrap();
+        assert_eq!(c.status, DocumentStatus::Pending);
+
+        let ids = vec!["a".to_string(), "c".to_string()];
+        let moved = repo
+            .transition_documents(&ids, DocumentStatus::Published)
+            .await
+            .unwrap();
+        assert_eq!(moved, 1);
+
+        let moved = repo
+            .transition_all(DocumentStatus::Indexed, DocumentStatus::Published, None)
+            .await
+            .unwrap();
+        assert_eq!(moved, 0);
+
+        let mut workflow = StatusWorkflow::default();
+        workflow.set_transitions(DocumentStatus::Indexed, vec![DocumentStatus::Published]);
+        let repo = repo.with_workflow(workflow);
+        let moved = repo
+            .transition_all(DocumentStatus::Indexed, DocumentStatus::Published, None)
+            .await
+            .unwrap();
+        assert_eq!(moved, 1);
+        let b = repo.get("b").await.unwrap().unwrap();
+        assert_eq!(b.status, DocumentStatus::Published);
+    }
+}
@@ -0,0 +1,266 @@
+//! Diesel-based document flag repository.
+//!
+//! Reader reports against documents live in `document_flags`, open until a
+//! curator resolves or dismisses them.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::DocumentFlagRecord;
+use super::parse_datetime;
+use super::pool::{DbPool, DieselError};
+use crate::models::{DocumentFlag, FlagReason, FlagStatus};
+use crate::schema::document_flags;
+use crate::{with_conn, with_write_conn};
+
+/// Convert a database record to a domain model.
+impl TryFrom<DocumentFlagRecord> for DocumentFlag {
+    type Error = diesel::result::Error;
+
+    fn try_from(record: DocumentFlagRecord) -> Result<Self, Self::Error> {
+        Ok(DocumentFlag {
+            reason: FlagReason::from_str(&record.reason).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid flag reason: '{}'", record.reason).into(),
+                )
+            })?,
+            status: FlagStatus::from_str(&record.status).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid flag status: '{}'", record.status).into(),
+                )
+            })?,
+            page: record.page.and_then(|p| u32::try_from(p).ok()),
+            created_at: parse_datetime(&record.created_at),
+            resolved_at: record.resolved_at.as_deref().map(parse_datetime),
+            id: record.id,
+            document_id: record.document_id,
+            note: record.note,
+            resolution: record.resolution,
+        })
+    }
+}
+
+/// Diesel-based document flag repository.
+#[derive(Clone)]
+pub struct DieselFlagRepository {
+    pool: DbPool,
+}
+
+impl DieselFlagRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Open a flag against `document_id`.
+    pub async fn create(
+        &self,
+        document_id: &str,
+        reason: FlagReason,
+        page: Option<u32>,
+        note: &str,
+    ) -> Result<DocumentFlag, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct InsertedId {
+            #[diesel(sql_type = diesel::sql_types::Integer)]
+            id: i32,
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let page = page.map(|p| p.min(i32::MAX as u32) as i32);
+        with_write_conn!(self.pool, conn, {
+            let inserted: InsertedId = diesel::sql_query(
+                r#"INSERT INTO document_flags (document_id, reason, page, note, status, created_at)
+                   VALUES ($1, $2, $3, $4, $5, $6)
+                   RETURNING id"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(document_id)
+            .bind::<diesel::sql_types::Text, _>(reason.as_str())
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(page)
+            .bind::<diesel::sql_types::Text, _>(note)
+            .bind::<diesel::sql_types::Text, _>(FlagStatus::Open.as_str())
+            .bind::<diesel::sql_types::Text, _>(&now)
+            .get_result(&mut conn)
+            .await?;
+            document_flags::table
+                .find(inserted.id)
+                .first::<DocumentFlagRecord>(&mut conn)
+                .await
+                .and_then(DocumentFlag::try_from)
+        })
+    }
+
+    /// Get a flag by ID.
+    pub async fn get(&self, id: i32) -> Result<Option<DocumentFlag>, DieselError> {
+        with_conn!(self.pool, conn, {
+            document_flags::table
+                .find(id)
+                .first::<DocumentFlagRecord>(&mut conn)
+                .await
+                .optional()
+                .and_then(|opt| opt.map(DocumentFlag::try_from).transpose())
+        })
+    }
+
+    /// Flags newest first, optionally only those in `status`.
+    pub async fn list(
+        &self,
+        status: Option<FlagStatus>,
+        limit: usize,
+    ) -> Result<Vec<DocumentFlag>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = document_flags::table
+                .order(document_flags::id.desc())
+                .limit(limit as i64)
+                .into_boxed();
+            if let Some(status) = status {
+                query = query.filter(document_flags::status.eq(status.as_str()));
+            }
+            query
+                .load::<DocumentFlagRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(DocumentFlag::try_from).collect())
+        })
+    }
+
+    /// Every flag against `document_id`, newest first.
+    pub async fn for_document(&self, document_id: &str) -> Result<Vec<DocumentFlag>, DieselError> {
+        with_conn!(self.pool, conn, {
+            document_flags::table
+                .filter(document_flags::document_id.eq(document_id))
+                .order(document_flags::id.desc())
+                .load::<DocumentFlagRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(DocumentFlag::try_from).collect())
+        })
+    }
+
+    /// Move flag `id` to `status`, noting what was done. Reopening clears
+    /// the resolution. Returns false if there is no such flag.
+    pub async fn set_status(
+        &self,
+        id: i32,
+        status: FlagStatus,
+        resolution: Option<&str>,
+    ) -> Result<bool, DieselError> {
+        let resolved_at = (status != FlagStatus::Open).then(|| Utc::now().to_rfc3339());
+        let resolution = resolution.filter(|_| status != FlagStatus::Open);
+        let updated = with_write_conn!(self.pool, conn, {
+            diesel::update(document_flags::table.find(id))
+                .set((
+                    document_flags::status.eq(status.as_str()),
+                    document_flags::resolved_at.eq(resolved_at.as_deref()),
+                    document_flags::resolution.eq(resolution),
+                ))
+                .execute(&mut conn)
+                .await
+        })?;
+        Ok(updated > 0)
+    }
+
+    /// Number of open flags.
+    pub async fn count_open(&self) -> Result<u64, DieselError> {
+        let count: i64 = with_conn!(self.pool, conn, {
+            document_flags::table
+                .filter(document_flags::status.eq(FlagStatus::Open.as_str()))
+                .count()
+                .get_result(&mut conn)
+                .await
+        })?;
+        Ok(count.max(0) as u64)
+    }
+
+    /// Whether `document_id` has an open flag.
+    pub async fn has_open(&self, document_id: &str) -> Result<bool, DieselError> {
+        let count: i64 = with_conn!(self.pool, conn, {
+            document_flags::table
+                .filter(document_flags::document_id.eq(document_id))
+                .filter(document_flags::status.eq(FlagStatus::Open.as_str()))
+                .count()
+                .get_result(&mut conn)
+                .await
+        })?;
+        Ok(count > 0)
+    }
+
+    /// IDs of documents with an open flag.
+    pub async fn open_document_ids(&self) -> Result<HashSet<String>, DieselError> {
+        let ids: Vec<String> = with_conn!(self.pool, conn, {
+            document_flags::table
+                .filter(document_flags::status.eq(FlagStatus::Open.as_str()))
+                .select(document_flags::document_id)
+                .distinct()
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(ids.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_flag_lifecycle() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let repo = DieselDbContext::from_sqlite_path(&db_path).unwrap().flags();
+
+        let flag = repo
+            .create("memo", FlagReason::Pii, Some(3), "Home address on page 3")
+            .await
+            .unwrap();
+        assert_eq!(flag.status, FlagStatus::Open);
+        assert_eq!(flag.page, Some(3));
+        repo.create("cable", FlagReason::WrongDocument, None, "")
+            .await
+            .unwrap();
+
+        assert!(repo.has_open("memo").await.unwrap());
+        assert_eq!(repo.count_open().await.unwrap(), 2);
+        assert_eq!(repo.open_document_ids().await.unwrap().len(), 2);
+
+        assert!(repo
+            .set_status(
+                flag.id,
+                FlagStatus::Resolved,
+                Some("Redacted copy published")
+            )
+            .await
+            .unwrap());
+        assert!(!repo.has_open("memo").await.unwrap());
+        let resolved = repo.get(flag.id).await.unwrap().unwrap();
+        assert!(resolved.resolved_at.is_some());
+        assert_eq!(
+            resolved.resolution.as_deref(),
+            Some("Redacted copy published")
+        );
+
+        let open = repo.list(Some(FlagStatus::Open), 10).await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].document_id, "cable");
+        assert_eq!(repo.list(None, 10).await.unwrap().len(), 2);
+        assert_eq!(repo.for_document("memo").await.unwrap().len(), 1);
+
+        assert!(repo
+            .set_status(flag.id, FlagStatus::Open, None)
+            .await
+            .unwrap());
+        let reopened = repo.get(flag.id).await.unwrap().unwrap();
+        assert!(reopened.resolved_at.is_none());
+        assert!(!repo
+            .set_status(999, FlagStatus::Dismissed, None)
+            .await
+            .unwrap());
+    }
+}
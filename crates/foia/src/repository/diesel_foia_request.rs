@@ -0,0 +1,395 @@
+//! Diesel-based FOIA request repository.
+//!
+//! Requests live in `foia_requests`; documents received in response are
+//! linked through `foia_request_documents`.
+
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::{FoiaRequestRecord, NewFoiaRequest};
+use super::parse_datetime;
+use super::pool::{DbPool, DieselError};
+use crate::models::{FoiaRequest, FoiaRequestStatus};
+use crate::schema::{foia_request_documents, foia_requests};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+fn parse_date(s: &str) -> Result<NaiveDate, diesel::result::Error> {
+    NaiveDate::parse_from_str(s, DATE_FORMAT).map_err(|_| {
+        diesel::result::Error::DeserializationError(format!("Invalid date: '{}'", s).into())
+    })
+}
+
+fn parse_date_opt(s: Option<String>) -> Option<NaiveDate> {
+    s.and_then(|s| NaiveDate::parse_from_str(&s, DATE_FORMAT).ok())
+}
+
+fn format_date(date: Option<NaiveDate>) -> Option<String> {
+    date.map(|d| d.format(DATE_FORMAT).to_string())
+}
+
+/// Convert a database record to a domain model.
+impl TryFrom<FoiaRequestRecord> for FoiaRequest {
+    type Error = diesel::result::Error;
+
+    fn try_from(record: FoiaRequestRecord) -> Result<Self, Self::Error> {
+        Ok(FoiaRequest {
+            status: FoiaRequestStatus::from_str(&record.status).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid FOIA request status: '{}'", record.status).into(),
+                )
+            })?,
+            filed_on: parse_date(&record.filed_on)?,
+            acknowledged_on: parse_date_opt(record.acknowledged_on),
+            fulfilled_on: parse_date_opt(record.fulfilled_on),
+            appealed_on: parse_date_opt(record.appealed_on),
+            reminded_on: parse_date_opt(record.reminded_on),
+            created_at: parse_datetime(&record.created_at),
+            updated_at: parse_datetime(&record.updated_at),
+            id: record.id,
+            agency: record.agency,
+            subject: record.subject,
+            request_text: record.request_text,
+            tracking_number: record.tracking_number,
+            source_id: record.source_id,
+            jurisdiction: record.jurisdiction,
+            notes: record.notes,
+        })
+    }
+}
+
+/// Diesel-based FOIA request repository.
+#[derive(Clone)]
+pub struct DieselFoiaRequestRepository {
+    pool: DbPool,
+}
+
+impl DieselFoiaRequestRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a new request.
+    pub async fn create(&self, request: &FoiaRequest) -> Result<(), DieselError> {
+        let new = NewFoiaRequest {
+            id: &request.id,
+            agency: &request.agency,
+            subject: &request.subject,
+            request_text: &request.request_text,
+            tracking_number: request.tracking_number.as_deref(),
+            source_id: request.source_id.as_deref(),
+            status: request.status.as_str(),
+            filed_on: request.filed_on.format(DATE_FORMAT).to_string(),
+            acknowledged_on: format_date(request.acknowledged_on),
+            fulfilled_on: format_date(request.fulfilled_on),
+            appealed_on: format_date(request.appealed_on),
+            notes: request.notes.as_deref(),
+            created_at: request.created_at.to_rfc3339(),
+            updated_at: request.updated_at.to_rfc3339(),
+            jurisdiction: request.jurisdiction.as_deref(),
+            reminded_on: format_date(request.reminded_on),
+        };
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(foia_requests::table)
+                .values(&new)
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Get a request by ID.
+    pub async fn get(&self, id: &str) -> Result<Option<FoiaRequest>, DieselError> {
+        with_conn!(self.pool, conn, {
+            foia_requests::table
+                .find(id)
+                .first::<FoiaRequestRecord>(&mut conn)
+                .await
+                .optional()
+                .and_then(|opt| opt.map(FoiaRequest::try_from).transpose())
+        })
+    }
+
+    /// List requests, most recently filed first, optionally only those in
+    /// `status`.
+    pub async fn list(
+        &self,
+        status: Option<FoiaRequestStatus>,
+    ) -> Result<Vec<FoiaRequest>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = foia_requests::table
+                .order((
+                    foia_requests::filed_on.desc(),
+                    foia_requests::created_at.desc(),
+                ))
+                .into_boxed();
+            if let Some(status) = status {
+                query = query.filter(foia_requests::status.eq(status.as_str()));
+            }
+            query
+                .load::<FoiaRequestRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(FoiaRequest::try_from).collect())
+        })
+    }
+
+    /// Save every editable field of an existing request. Returns false if it
+    /// doesn't exist.
+    pub async fn update(&self, request: &FoiaRequest) -> Result<bool, DieselError> {
+        let filed_on = request.filed_on.format(DATE_FORMAT).to_string();
+        let acknowledged_on = format_date(request.acknowledged_on);
+        let fulfilled_on = format_date(request.fulfilled_on);
+        let appealed_on = format_date(request.appealed_on);
+        let now = Utc::now().to_rfc3339();
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::update(foia_requests::table.find(&request.id))
+                .set((
+                    foia_requests::agency.eq(&request.agency),
+                    foia_requests::subject.eq(&request.subject),
+                    foia_requests::request_text.eq(&request.request_text),
+                    foia_requests::tracking_number.eq(&request.tracking_number),
+                    foia_requests::source_id.eq(&request.source_id),
+                    foia_requests::jurisdiction.eq(&request.jurisdiction),
+                    foia_requests::status.eq(request.status.as_str()),
+                    foia_requests::filed_on.eq(&filed_on),
+                    foia_requests::acknowledged_on.eq(&acknowledged_on),
+                    foia_requests::fulfilled_on.eq(&fulfilled_on),
+                    foia_requests::appealed_on.eq(&appealed_on),
+                    foia_requests::notes.eq(&request.notes),
+                    foia_requests::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// Record that overdue reminders for these requests were sent on `date`.
+    pub async fn mark_reminded(&self, ids: &[String], date: NaiveDate) -> Result<(), DieselError> {
+        let date = date.format(DATE_FORMAT).to_string();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(foia_requests::table.filter(foia_requests::id.eq_any(ids)))
+                .set(foia_requests::reminded_on.eq(&date))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Delete a request and its document links. Returns whether it existed.
+    pub async fn delete(&self, id: &str) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                foia_request_documents::table.filter(foia_request_documents::request_id.eq(id)),
+            )
+            .execute(&mut conn)
+            .await?;
+            diesel::delete(foia_requests::table.find(id))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// Link a received document to a request. Returns false if it was
+    /// already linked.
+    pub async fn link_document(
+        &self,
+        request_id: &str,
+        document_id: &str,
+    ) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let values = (
+            foia_request_documents::request_id.eq(request_id),
+            foia_request_documents::document_id.eq(document_id),
+            foia_request_documents::linked_at.eq(&now),
+        );
+        let rows = with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::insert_or_ignore_into(foia_request_documents::table)
+                    .values(values)
+                    .execute(&mut conn)
+                    .await?
+            },
+            postgres: conn => {
+                diesel::insert_into(foia_request_documents::table)
+                    .values(values)
+                    .on_conflict_do_nothing()
+                    .execute(&mut conn)
+                    .await?
+            }
+        );
+        Ok(rows > 0)
+    }
+
+    /// Remove a document's link to a request. Returns whether it was linked.
+    pub async fn unlink_document(
+        &self,
+        request_id: &str,
+        document_id: &str,
+    ) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                foia_request_documents::table
+                    .filter(foia_request_documents::request_id.eq(request_id))
+                    .filter(foia_request_documents::document_id.eq(document_id)),
+            )
+            .execute(&mut conn)
+            .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// IDs of the documents linked to a request, in the order they were linked.
+    pub async fn document_ids(&self, request_id: &str) -> Result<Vec<String>, DieselError> {
+        with_conn!(self.pool, conn, {
+            foia_request_documents::table
+                .filter(foia_request_documents::request_id.eq(request_id))
+                .order(foia_request_documents::linked_at.asc())
+                .select(foia_request_documents::document_id)
+                .load(&mut conn)
+                .await
+        })
+    }
+
+    /// Requests a document was received in response to.
+    pub async fn for_document(&self, document_id: &str) -> Result<Vec<FoiaRequest>, DieselError> {
+        with_conn!(self.pool, conn, {
+            foia_requests::table
+                .filter(
+                    foia_requests::id.eq_any(
+                        foia_request_documents::table
+                            .filter(foia_request_documents::document_id.eq(document_id))
+                            .select(foia_request_documents::request_id),
+                    ),
+                )
+                .order(foia_requests::filed_on.desc())
+                .load::<FoiaRequestRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(FoiaRequest::try_from).collect())
+        })
+    }
+
+    /// Number of linked documents for every request that has any.
+    pub async fn document_counts(&self) -> Result<HashMap<String, u64>, DieselError> {
+        use diesel::dsl::count_star;
+        let rows: Vec<(String, i64)> = with_conn!(self.pool, conn, {
+            foia_request_documents::table
+                .group_by(foia_request_documents::request_id)
+                .select((foia_request_documents::request_id, count_star()))
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, count)| (id, count.max(0) as u64))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_request_lifecycle() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.foia_requests();
+
+        let mut request = FoiaRequest::new(
+            "FBI".to_string(),
+            "COINTELPRO files".to_string(),
+            date("2024-03-01"),
+        );
+        request.request_text = "All records on ...".to_string();
+        repo.create(&request).await.unwrap();
+
+        let older = FoiaRequest::new("CIA".to_string(), "MKULTRA".to_string(), date("2023-01-10"));
+        repo.create(&older).await.unwrap();
+
+        request.tracking_number = Some("1234567-000".to_string());
+        request.jurisdiction = Some("california".to_string());
+        request.set_status(FoiaRequestStatus::Acknowledged, date("2024-03-05"));
+        assert!(repo.update(&request).await.unwrap());
+
+        let loaded = repo.get(&request.id).await.unwrap().unwrap();
+        assert_eq!(loaded.status, FoiaRequestStatus::Acknowledged);
+        assert_eq!(loaded.tracking_number.as_deref(), Some("1234567-000"));
+        assert_eq!(loaded.acknowledged_on, Some(date("2024-03-05")));
+        assert_eq!(loaded.request_text, "All records on ...");
+        assert_eq!(loaded.jurisdiction.as_deref(), Some("california"));
+        assert_eq!(loaded.reminded_on, None);
+
+        repo.mark_reminded(&[request.id.clone()], date("2024-04-02"))
+            .await
+            .unwrap();
+        let loaded = repo.get(&request.id).await.unwrap().unwrap();
+        assert_eq!(loaded.reminded_on, Some(date("2024-04-02")));
+
+        let all = repo.list(None).await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].id, request.id, "most recently filed first");
+        let filed = repo.list(Some(FoiaRequestStatus::Filed)).await.unwrap();
+        assert_eq!(filed.len(), 1);
+        assert_eq!(filed[0].id, older.id);
+
+        assert!(repo.delete(&older.id).await.unwrap());
+        assert!(!repo.delete(&older.id).await.unwrap());
+        assert!(repo.get(&older.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_document_links() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.foia_requests();
+
+        let request = FoiaRequest::new(
+            "FBI".to_string(),
+            "COINTELPRO files".to_string(),
+            date("2024-03-01"),
+        );
+        repo.create(&request).await.unwrap();
+
+        assert!(repo.link_document(&request.id, "doc-1").await.unwrap());
+        assert!(repo.link_document(&request.id, "doc-2").await.unwrap());
+        assert!(!repo.link_document(&request.id, "doc-1").await.unwrap());
+        assert_eq!(
+            repo.document_ids(&request.id).await.unwrap(),
+            vec!["doc-1", "doc-2"]
+        );
+        assert_eq!(repo.document_counts().await.unwrap()[&request.id], 2);
+
+        let linked = repo.for_document("doc-2").await.unwrap();
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].id, request.id);
+
+        assert!(repo.unlink_document(&request.id, "doc-2").await.unwrap());
+        assert!(!repo.unlink_document(&request.id, "doc-2").await.unwrap());
+        assert!(repo.for_document("doc-2").await.unwrap().is_empty());
+
+        repo.delete(&request.id).await.unwrap();
+        assert!(repo.document_ids(&request.id).await.unwrap().is_empty());
+    }
+}
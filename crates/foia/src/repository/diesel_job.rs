@@ -0,0 +1,320 @@
+//! Diesel-based background job repository.
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::JobRecord;
+use super::pool::{DbPool, DieselError};
+use super::{parse_datetime, parse_datetime_opt};
+use crate::models::{Job, JobKind, JobStatus};
+use crate::schema::jobs;
+use crate::{with_conn, with_write_conn};
+
+/// Convert a database record to a domain model.
+impl TryFrom<JobRecord> for Job {
+    type Error = diesel::result::Error;
+
+    fn try_from(record: JobRecord) -> Result<Self, Self::Error> {
+        Ok(Job {
+            kind: JobKind::from_str(&record.kind).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid job kind: '{}'", record.kind).into(),
+                )
+            })?,
+            status: JobStatus::from_str(&record.status).ok_or_else(|| {
+                diesel::result::Error::DeserializationError(
+                    format!("Invalid job status: '{}'", record.status).into(),
+                )
+            })?,
+            params: serde_json::from_str(&record.params).unwrap_or_default(),
+            progress: record.progress.max(0) as u64,
+            total: record.total.map(|t| t.max(0) as u64),
+            message: record.message,
+            error: record.error,
+            result: record.result.and_then(|r| serde_json::from_str(&r).ok()),
+            cancel_requested: record.cancel_requested != 0,
+            created_at: parse_datetime(&record.created_at),
+            started_at: parse_datetime_opt(record.started_at),
+            finished_at: parse_datetime_opt(record.finished_at),
+            updated_at: parse_datetime(&record.updated_at),
+            id: record.id,
+        })
+    }
+}
+
+/// Diesel-based job repository.
+#[derive(Clone)]
+pub struct DieselJobRepository {
+    pool: DbPool,
+}
+
+impl DieselJobRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record a new job.
+    pub async fn create(&self, job: &Job) -> Result<(), DieselError> {
+        let params = job.params.to_string();
+        let created_at = job.created_at.to_rfc3339();
+        let updated_at = job.updated_at.to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(jobs::table)
+                .values((
+                    jobs::id.eq(&job.id),
+                    jobs::kind.eq(job.kind.as_str()),
+                    jobs::params.eq(&params),
+                    jobs::status.eq(job.status.as_str()),
+                    jobs::progress.eq(job.progress as i64),
+                    jobs::total.eq(job.total.map(|t| t as i64)),
+                    jobs::created_at.eq(&created_at),
+                    jobs::updated_at.eq(&updated_at),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Get a job by ID.
+    pub async fn get(&self, id: &str) -> Result<Option<Job>, DieselError> {
+        with_conn!(self.pool, conn, {
+            jobs::table
+                .find(id)
+                .first::<JobRecord>(&mut conn)
+                .await
+                .optional()
+                .and_then(|opt| opt.map(Job::try_from).transpose())
+        })
+    }
+
+    /// List the most recent jobs, optionally only those in `status`.
+    pub async fn list(
+        &self,
+        status: Option<JobStatus>,
+        limit: usize,
+    ) -> Result<Vec<Job>, DieselError> {
+        with_conn!(self.pool, conn, {
+            let mut query = jobs::table
+                .order(jobs::created_at.desc())
+                .limit(limit as i64)
+                .into_boxed();
+            if let Some(status) = status {
+                query = query.filter(jobs::status.eq(status.as_str()));
+            }
+            query
+                .load::<JobRecord>(&mut conn)
+                .await
+                .and_then(|records| records.into_iter().map(Job::try_from).collect())
+        })
+    }
+
+    /// Mark a queued job running. Returns false if it was cancelled first.
+    pub async fn start(&self, id: &str) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            let rows = diesel::update(
+                jobs::table
+                    .find(id)
+                    .filter(jobs::status.eq(JobStatus::Queued.as_str())),
+            )
+            .set((
+                jobs::status.eq(JobStatus::Running.as_str()),
+                jobs::started_at.eq(&now),
+                jobs::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await?;
+            Ok(rows > 0)
+        })
+    }
+
+    /// Record progress and return whether cancellation was requested.
+    pub async fn update_progress(
+        &self,
+        id: &str,
+        progress: u64,
+        total: Option<u64>,
+        message: Option<&str>,
+    ) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(jobs::table.find(id))
+                .set((
+                    jobs::progress.eq(progress as i64),
+                    jobs::total.eq(total.map(|t| t as i64)),
+                    jobs::message.eq(message),
+                    jobs::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            let cancel: i32 = jobs::table
+                .find(id)
+                .select(jobs::cancel_requested)
+                .first(&mut conn)
+                .await?;
+            Ok(cancel != 0)
+        })
+    }
+
+    /// Finish a job with its final status.
+    pub async fn finish(
+        &self,
+        id: &str,
+        status: JobStatus,
+        message: Option<&str>,
+        error: Option<&str>,
+        result: Option<&serde_json::Value>,
+    ) -> Result<(), DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let result = result.map(|r| r.to_string());
+        with_write_conn!(self.pool, conn, {
+            diesel::update(jobs::table.find(id))
+                .set((
+                    jobs::status.eq(status.as_str()),
+                    jobs::message.eq(message),
+                    jobs::error.eq(error),
+                    jobs::result.eq(&result),
+                    jobs::finished_at.eq(&now),
+                    jobs::updated_at.eq(&now),
+                ))
+                .execute(&mut conn)
+                .await?;
+            Ok(())
+        })
+    }
+
+    /// Ask a job to stop. Queued jobs are cancelled at once; running jobs
+    /// stop the next time they report progress. Returns false if the job
+    /// doesn't exist or has already finished.
+    pub async fn request_cancel(&self, id: &str) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            let queued = diesel::update(
+                jobs::table
+                    .find(id)
+                    .filter(jobs::status.eq(JobStatus::Queued.as_str())),
+            )
+            .set((
+                jobs::status.eq(JobStatus::Cancelled.as_str()),
+                jobs::cancel_requested.eq(1),
+                jobs::finished_at.eq(&now),
+                jobs::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await?;
+            let running = if queued > 0 {
+                0
+            } else {
+                diesel::update(
+                    jobs::table
+                        .find(id)
+                        .filter(jobs::status.eq(JobStatus::Running.as_str())),
+                )
+                .set((jobs::cancel_requested.eq(1), jobs::updated_at.eq(&now)))
+                .execute(&mut conn)
+                .await?
+            };
+            Ok(queued + running > 0)
+        })
+    }
+
+    /// Fail jobs left queued or running by a process that exited, returning
+    /// how many. Call at startup, before running any jobs.
+    pub async fn fail_interrupted(&self) -> Result<usize, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        with_write_conn!(self.pool, conn, {
+            diesel::update(jobs::table.filter(
+                jobs::status.eq_any([JobStatus::Queued.as_str(), JobStatus::Running.as_str()]),
+            ))
+            .set((
+                jobs::status.eq(JobStatus::Failed.as_str()),
+                jobs::error.eq("Interrupted by restart"),
+                jobs::finished_at.eq(&now),
+                jobs::updated_at.eq(&now),
+            ))
+            .execute(&mut conn)
+            .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    #[tokio::test]
+    async fn test_job_lifecycle() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.jobs();
+
+        let job = Job::new(JobKind::Reocr, serde_json::json!({ "source_id": "fbi" }));
+        repo.create(&job).await.unwrap();
+        assert!(repo.start(&job.id).await.unwrap());
+        assert!(!repo.start(&job.id).await.unwrap());
+
+        let cancel = repo
+            .update_progress(&job.id, 3, Some(10), Some("Working"))
+            .await
+            .unwrap();
+        assert!(!cancel);
+        let running = repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(running.status, JobStatus::Running);
+        assert_eq!((running.progress, running.total), (3, Some(10)));
+        assert_eq!(running.param_str("source_id"), Some("fbi"));
+
+        assert!(repo.request_cancel(&job.id).await.unwrap());
+        assert!(repo
+            .update_progress(&job.id, 4, Some(10), None)
+            .await
+            .unwrap());
+        repo.finish(&job.id, JobStatus::Cancelled, None, None, None)
+            .await
+            .unwrap();
+        assert!(!repo.request_cancel(&job.id).await.unwrap());
+
+        let done = repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(done.status, JobStatus::Cancelled);
+        assert!(done.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_and_fail_interrupted() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.jobs();
+
+        let queued = Job::new(JobKind::Export, serde_json::json!({}));
+        let running = Job::new(JobKind::ExtractTables, serde_json::json!({}));
+        repo.create(&queued).await.unwrap();
+        repo.create(&running).await.unwrap();
+        repo.start(&running.id).await.unwrap();
+
+        assert!(repo.request_cancel(&queued.id).await.unwrap());
+        assert!(!repo.start(&queued.id).await.unwrap());
+
+        assert_eq!(repo.fail_interrupted().await.unwrap(), 1);
+        let failed = repo.get(&running.id).await.unwrap().unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+
+        let all = repo.list(None, 10).await.unwrap();
+        assert_eq!(all.len(), 2);
+        let cancelled = repo.list(Some(JobStatus::Cancelled), 10).await.unwrap();
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].id, queued.id);
+    }
+}
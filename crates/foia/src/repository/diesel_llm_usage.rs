@@ -0,0 +1,185 @@
+//! Diesel-based LLM usage repository.
+//!
+//! Every LLM call is appended to `llm_usage`; budgets and the usage report
+//! read monthly totals back from it.
+
+use chrono::{DateTime, Utc};
+use diesel_async::RunQueryDsl;
+
+use super::models::NewLlmUsage;
+use super::pool::{DbPool, DieselError};
+use crate::models::{LlmUsageEntry, LlmUsageSummary, UsageTotals};
+use crate::schema::llm_usage;
+use crate::{with_conn, with_write_conn};
+
+/// Diesel-based LLM usage repository.
+#[derive(Clone)]
+pub struct DieselLlmUsageRepository {
+    pool: DbPool,
+}
+
+impl DieselLlmUsageRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Record one LLM call.
+    pub async fn record(&self, entry: &LlmUsageEntry) -> Result<(), DieselError> {
+        let created_at = entry.created_at.to_rfc3339();
+        let new = NewLlmUsage {
+            provider: &entry.provider,
+            model: &entry.model,
+            operation: &entry.operation,
+            prompt_tokens: entry.usage.prompt_tokens as i64,
+            completion_tokens: entry.usage.completion_tokens as i64,
+            cost_usd: entry.cost_usd,
+            created_at: &created_at,
+        };
+        with_write_conn!(self.pool, conn, {
+            diesel::insert_into(llm_usage::table)
+                .values(&new)
+                .execute(&mut conn)
+                .await?;
+        });
+        Ok(())
+    }
+
+    /// Usage per month, provider and model since `since`, newest month first.
+    pub async fn summary(&self, since: DateTime<Utc>) -> Result<Vec<LlmUsageSummary>, DieselError> {
+        #[derive(diesel::QueryableByName)]
+        struct SummaryRow {
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            month: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            provider: String,
+            #[diesel(sql_type = diesel::sql_types::Text)]
+            model: String,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            requests: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            prompt_tokens: i64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            completion_tokens: i64,
+            #[diesel(sql_type = diesel::sql_types::Double)]
+            cost_usd: f64,
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            unpriced: i64,
+        }
+
+        let since = since.to_rfc3339();
+        let rows: Vec<SummaryRow> = with_conn!(self.pool, conn, {
+            diesel::sql_query(
+                r#"SELECT SUBSTR(created_at, 1, 7) AS month, provider, model,
+                          COUNT(*) AS requests,
+                          CAST(COALESCE(SUM(prompt_tokens), 0) AS BIGINT) AS prompt_tokens,
+                          CAST(COALESCE(SUM(completion_tokens), 0) AS BIGINT) AS completion_tokens,
+                          CAST(COALESCE(SUM(cost_usd), 0) AS DOUBLE PRECISION) AS cost_usd,
+                          COUNT(*) - COUNT(cost_usd) AS unpriced
+                   FROM llm_usage
+                   WHERE created_at >= $1
+                   GROUP BY SUBSTR(created_at, 1, 7), provider, model
+                   ORDER BY month DESC, provider, model"#,
+            )
+            .bind::<diesel::sql_types::Text, _>(&since)
+            .load(&mut conn)
+            .await
+        })?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LlmUsageSummary {
+                month: row.month,
+                provider: row.provider,
+                model: row.model,
+                totals: UsageTotals {
+                    requests: row.requests.max(0) as u64,
+                    prompt_tokens: row.prompt_tokens.max(0) as u64,
+                    completion_tokens: row.completion_tokens.max(0) as u64,
+                    cost_usd: row.cost_usd,
+                    unpriced_requests: row.unpriced.max(0) as u64,
+                },
+            })
+            .collect())
+    }
+
+    /// Usage across all providers and models since `since`.
+    pub async fn totals_since(&self, since: DateTime<Utc>) -> Result<UsageTotals, DieselError> {
+        let mut totals = UsageTotals::default();
+        for row in self.summary(since).await? {
+            totals.merge(&row.totals);
+        }
+        Ok(totals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenUsage;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DieselDbContext, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let db_url = format!("sqlite:{}", db_path.display());
+        migrations::run_migrations(&db_url, false).await.unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        (ctx, dir)
+    }
+
+    fn entry(model: &str, cost_usd: Option<f64>, created_at: DateTime<Utc>) -> LlmUsageEntry {
+        LlmUsageEntry {
+            provider: "openai".to_string(),
+            model: model.to_string(),
+            operation: "synopsis".to_string(),
+            usage: TokenUsage {
+                prompt_tokens: 1000,
+                completion_tokens: 100,
+            },
+            cost_usd,
+            created_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usage_summary() {
+        let (ctx, _dir) = setup_test_db().await;
+        let repo = ctx.llm_usage();
+
+        let march = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap();
+        let april = Utc.with_ymd_and_hms(2026, 4, 2, 8, 0, 0).unwrap();
+        repo.record(&entry("gpt-4o-mini", Some(0.5), march))
+            .await
+            .unwrap();
+        repo.record(&entry("gpt-4o-mini", Some(0.25), april))
+            .await
+            .unwrap();
+        repo.record(&entry("gpt-4o-mini", Some(0.25), april))
+            .await
+            .unwrap();
+        repo.record(&entry("custom-model", None, april))
+            .await
+            .unwrap();
+
+        let rows = repo.summary(march).await.unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].month, "2026-04");
+        assert_eq!(rows[0].model, "custom-model");
+        assert_eq!(rows[0].totals.unpriced_requests, 1);
+        assert_eq!(rows[1].totals.requests, 2);
+        assert_eq!(rows[1].totals.prompt_tokens, 2000);
+        assert!((rows[1].totals.cost_usd - 0.5).abs() < 1e-9);
+        assert_eq!(rows[2].month, "2026-03");
+
+        let april_start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let totals = repo.totals_since(april_start).await.unwrap();
+        assert_eq!(totals.requests, 3);
+        assert_eq!(totals.tokens(), 3300);
+        assert_eq!(totals.unpriced_requests, 1);
+    }
+}
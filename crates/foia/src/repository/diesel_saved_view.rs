@@ -0,0 +1,333 @@
+//! Diesel-based saved view repository.
+//!
+//! Saved views are named browse filter combinations, stored as the browse
+//! page's query string in the `saved_views` table. Addresses subscribed to a
+//! view are in `saved_view_subscriptions`, and the documents each has been
+//! alerted about in `saved_view_alerts`.
+
+use std::collections::HashSet;
+
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use super::models::{NewSavedView, SavedViewRecord, SavedViewSubscriptionRecord};
+use super::pool::{DbPool, DieselError};
+use crate::schema::{saved_view_alerts, saved_view_subscriptions, saved_views};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
+
+/// Diesel-based saved view repository.
+#[derive(Clone)]
+pub struct DieselSavedViewRepository {
+    pool: DbPool,
+}
+
+impl DieselSavedViewRepository {
+    /// Create a new repository with an existing pool.
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// All saved views, by name.
+    pub async fn list(&self) -> Result<Vec<SavedViewRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            saved_views::table
+                .order(saved_views::name.asc())
+                .load::<SavedViewRecord>(&mut conn)
+                .await
+        })
+    }
+
+    /// Save a view, replacing the query of an existing view with that name.
+    pub async fn save(&self, name: &str, query: &str) -> Result<(), DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let new = NewSavedView {
+            name,
+            query,
+            created_at: &now,
+            updated_at: &now,
+        };
+
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::replace_into(saved_views::table)
+                    .values(&new)
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            },
+            postgres: conn => {
+                diesel::insert_into(saved_views::table)
+                    .values(&new)
+                    .on_conflict(saved_views::name)
+                    .do_update()
+                    .set((
+                        saved_views::query.eq(query),
+                        saved_views::updated_at.eq(&now),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            }
+        )
+    }
+
+    /// Get a saved view by name.
+    pub async fn get(&self, name: &str) -> Result<Option<SavedViewRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            saved_views::table
+                .find(name)
+                .first::<SavedViewRecord>(&mut conn)
+                .await
+                .optional()
+        })
+    }
+
+    /// Delete a saved view and its subscriptions. Returns whether it existed.
+    pub async fn delete(&self, name: &str) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::delete(saved_view_alerts::table.filter(saved_view_alerts::view_name.eq(name)))
+                .execute(&mut conn)
+                .await?;
+            diesel::delete(
+                saved_view_subscriptions::table
+                    .filter(saved_view_subscriptions::view_name.eq(name)),
+            )
+            .execute(&mut conn)
+            .await?;
+            diesel::delete(saved_views::table.find(name))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// Subscribe an address to new documents matching a view. Returns false
+    /// if it was already subscribed.
+    pub async fn subscribe(&self, view_name: &str, email: &str) -> Result<bool, DieselError> {
+        let now = Utc::now().to_rfc3339();
+        let values = (
+            saved_view_subscriptions::view_name.eq(view_name),
+            saved_view_subscriptions::email.eq(email),
+            saved_view_subscriptions::created_at.eq(&now),
+        );
+        let rows = with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::insert_or_ignore_into(saved_view_subscriptions::table)
+                    .values(values)
+                    .execute(&mut conn)
+                    .await?
+            },
+            postgres: conn => {
+                diesel::insert_into(saved_view_subscriptions::table)
+                    .values(values)
+                    .on_conflict_do_nothing()
+                    .execute(&mut conn)
+                    .await?
+            }
+        );
+        Ok(rows > 0)
+    }
+
+    /// Remove a subscription and its alert history. Returns whether it existed.
+    pub async fn unsubscribe(&self, view_name: &str, email: &str) -> Result<bool, DieselError> {
+        let rows = with_write_conn!(self.pool, conn, {
+            diesel::delete(
+                saved_view_alerts::table
+                    .filter(saved_view_alerts::view_name.eq(view_name))
+                    .filter(saved_view_alerts::email.eq(email)),
+            )
+            .execute(&mut conn)
+            .await?;
+            diesel::delete(saved_view_subscriptions::table.find((view_name, email)))
+                .execute(&mut conn)
+                .await?
+        });
+        Ok(rows > 0)
+    }
+
+    /// All subscriptions, by view then address.
+    pub async fn list_subscriptions(
+        &self,
+    ) -> Result<Vec<SavedViewSubscriptionRecord>, DieselError> {
+        with_conn!(self.pool, conn, {
+            saved_view_subscriptions::table
+                .order((
+                    saved_view_subscriptions::view_name.asc(),
+                    saved_view_subscriptions::email.asc(),
+                ))
+                .load::<SavedViewSubscriptionRecord>(&mut conn)
+                .await
+        })
+    }
+
+    /// Which of `document_ids` the subscriber was already alerted about.
+    pub async fn alerted_ids(
+        &self,
+        view_name: &str,
+        email: &str,
+        document_ids: &[String],
+    ) -> Result<HashSet<String>, DieselError> {
+        if document_ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let ids: Vec<String> = with_conn!(self.pool, conn, {
+            saved_view_alerts::table
+                .filter(saved_view_alerts::view_name.eq(view_name))
+                .filter(saved_view_alerts::email.eq(email))
+                .filter(saved_view_alerts::document_id.eq_any(document_ids))
+                .select(saved_view_alerts::document_id)
+                .load(&mut conn)
+                .await
+        })?;
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Record that the subscriber was alerted about `document_ids`.
+    pub async fn record_alerted(
+        &self,
+        view_name: &str,
+        email: &str,
+        document_ids: &[String],
+    ) -> Result<(), DieselError> {
+        if document_ids.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now().to_rfc3339();
+        let values: Vec<_> = document_ids
+            .iter()
+            .map(|id| {
+                (
+                    saved_view_alerts::view_name.eq(view_name),
+                    saved_view_alerts::email.eq(email),
+                    saved_view_alerts::document_id.eq(id),
+                    saved_view_alerts::alerted_at.eq(&now),
+                )
+            })
+            .collect();
+        with_write_conn_split!(self.pool,
+            sqlite: conn => {
+                diesel::insert_or_ignore_into(saved_view_alerts::table)
+                    .values(values)
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            },
+            postgres: conn => {
+                diesel::insert_into(saved_view_alerts::table)
+                    .values(values)
+                    .on_conflict_do_nothing()
+                    .execute(&mut conn)
+                    .await?;
+                Ok(())
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::pool::SqlitePool;
+    use diesel_async::SimpleAsyncConnection;
+    use tempfile::tempdir;
+
+    async fn setup_test_db() -> (DbPool, tempfile::TempDir) {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+
+        let sqlite_pool = SqlitePool::from_path(&db_path);
+        let mut conn = sqlite_pool.get().await.unwrap();
+
+        conn.batch_execute(
+            r#"CREATE TABLE IF NOT EXISTS saved_views (
+                name TEXT PRIMARY KEY NOT NULL,
+                query TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS saved_view_subscriptions (
+                view_name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (view_name, email)
+            );
+            CREATE TABLE IF NOT EXISTS saved_view_alerts (
+                view_name TEXT NOT NULL,
+                email TEXT NOT NULL,
+                document_id TEXT NOT NULL,
+                alerted_at TEXT NOT NULL,
+                PRIMARY KEY (view_name, email, document_id)
+            )"#,
+        )
+        .await
+        .unwrap();
+
+        (DbPool::Sqlite(sqlite_pool), dir)
+    }
+
+    #[tokio::test]
+    async fn test_saved_views() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselSavedViewRepository::new(pool);
+
+        repo.save("fbi memos", "source=fbi&record_types=memo")
+            .await
+            .unwrap();
+        repo.save("cia", "source=cia").await.unwrap();
+        let views = repo.list().await.unwrap();
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].name, "cia");
+
+        // Saving under an existing name replaces its query
+        repo.save("cia", "source=cia&poor_ocr=true").await.unwrap();
+        let views = repo.list().await.unwrap();
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].query, "source=cia&poor_ocr=true");
+
+        assert!(repo.delete("cia").await.unwrap());
+        assert!(!repo.delete("cia").await.unwrap());
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_and_alerts() {
+        let (pool, _dir) = setup_test_db().await;
+        let repo = DieselSavedViewRepository::new(pool);
+        repo.save("hoover", "q=hoover").await.unwrap();
+
+        assert!(repo.subscribe("hoover", "a@example.org").await.unwrap());
+        assert!(!repo.subscribe("hoover", "a@example.org").await.unwrap());
+        assert!(repo.subscribe("hoover", "b@example.org").await.unwrap());
+        let subs = repo.list_subscriptions().await.unwrap();
+        assert_eq!(subs.len(), 2);
+        assert_eq!(subs[0].email, "a@example.org");
+
+        let ids = vec!["doc1".to_string(), "doc2".to_string()];
+        repo.record_alerted("hoover", "a@example.org", &ids[..1])
+            .await
+            .unwrap();
+        // Recording again is harmless
+        repo.record_alerted("hoover", "a@example.org", &ids[..1])
+            .await
+            .unwrap();
+        let alerted = repo
+            .alerted_ids("hoover", "a@example.org", &ids)
+            .await
+            .unwrap();
+        assert_eq!(alerted, HashSet::from(["doc1".to_string()]));
+        // Alerts are per subscriber
+        assert!(repo
+            .alerted_ids("hoover", "b@example.org", &ids)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(repo.unsubscribe("hoover", "b@example.org").await.unwrap());
+        assert!(!repo.unsubscribe("hoover", "b@example.org").await.unwrap());
+
+        // Deleting the view drops its subscriptions
+        assert!(repo.delete("hoover").await.unwrap());
+        assert!(repo.list_subscriptions().await.unwrap().is_empty());
+    }
+}
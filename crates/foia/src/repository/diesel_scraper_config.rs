@@ -11,7 +11,7 @@ use super::models::{NewScraperConfig, ScraperConfigRecord};
 use super::pool::{DbPool, DieselError};
 use crate::config::ScraperConfig;
 use crate::schema::scraper_configs;
-use crate::{with_conn, with_conn_split};
+use crate::{with_conn, with_write_conn, with_write_conn_split};
 
 /// Diesel-based scraper config repository with compile-time query checking.
 #[derive(Clone)]
@@ -78,7 +78,7 @@ impl DieselScraperConfigRepository {
             .map_err(|e| DieselError::SerializationError(Box::new(e)))?;
         let now = Utc::now().to_rfc3339();
 
-        with_conn_split!(self.pool,
+        with_write_conn_split!(self.pool,
             sqlite: conn => {
                 let new = NewScraperConfig {
                     source_id,
@@ -116,7 +116,7 @@ impl DieselScraperConfigRepository {
 
     /// Delete a scraper config by source ID.
     pub async fn delete(&self, source_id: &str) -> Result<bool, DieselError> {
-        let rows = with_conn!(self.pool, conn, {
+        let rows = with_write_conn!(self.pool, conn, {
             diesel::delete(scraper_configs::table.find(source_id))
                 .execute(&mut conn)
                 .await?
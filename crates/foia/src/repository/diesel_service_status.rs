@@ -8,7 +8,7 @@ use super::pool::{DbPool, DieselError};
 use super::{parse_datetime, parse_datetime_opt};
 use crate::models::{ServiceState, ServiceStatus, ServiceType};
 use crate::schema::service_status;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 /// Convert a database record to a domain model.
 impl TryFrom<ServiceStatusRecord> for ServiceStatus {
@@ -161,7 +161,7 @@ impl DieselServiceStatusRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(&status.id)
                 .bind::<diesel::sql_types::Text, _>(&service_type)
@@ -199,7 +199,7 @@ impl DieselServiceStatusRepository {
 
     /// Delete a service status.
     pub async fn delete(&self, id: &str) -> Result<bool, DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let rows = diesel::delete(service_status::table.find(id))
                 .execute(&mut conn)
                 .await?;
@@ -212,7 +212,7 @@ impl DieselServiceStatusRepository {
         use chrono::Utc;
         let cutoff = (Utc::now() - chrono::Duration::seconds(threshold_secs)).to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let rows = diesel::delete(
                 service_status::table
                     .filter(service_status::last_heartbeat.lt(&cutoff))
@@ -12,7 +12,7 @@ use super::pool::{DbPool, DieselError};
 use super::{parse_datetime, parse_datetime_opt};
 use crate::models::{Source, SourceType};
 use crate::schema::sources;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 /// Convert a database record to a domain model.
 impl TryFrom<SourceRecord> for Source {
@@ -30,6 +30,7 @@ impl TryFrom<SourceRecord> for Source {
             metadata,
             created_at: parse_datetime(&record.created_at),
             last_scraped: parse_datetime_opt(record.last_scraped),
+            agency_id: record.agency_id,
         })
     }
 }
@@ -90,6 +91,7 @@ impl DieselSourceRepository {
                 Sources::Metadata,
                 Sources::CreatedAt,
                 Sources::LastScraped,
+                Sources::AgencyId,
             ])
             .values_panic([
                 source.id.clone().into(),
@@ -99,6 +101,7 @@ impl DieselSourceRepository {
                 metadata_json.clone().into(),
                 created_at.clone().into(),
                 last_scraped.clone().into(),
+                source.agency_id.clone().into(),
             ])
             .on_conflict(
                 OnConflict::column(Sources::Id)
@@ -108,6 +111,7 @@ impl DieselSourceRepository {
                         Sources::BaseUrl,
                         Sources::Metadata,
                         Sources::LastScraped,
+                        Sources::AgencyId,
                     ])
                     .to_owned(),
             )
@@ -115,7 +119,7 @@ impl DieselSourceRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(&source.id)
                 .bind::<diesel::sql_types::Text, _>(&source_type)
@@ -126,6 +130,9 @@ impl DieselSourceRepository {
                 .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
                     last_scraped.as_deref(),
                 )
+                .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(
+                    source.agency_id.as_deref(),
+                )
                 .execute(&mut conn)
                 .await?;
             Ok(())
@@ -135,7 +142,7 @@ impl DieselSourceRepository {
     /// Delete a source.
     #[allow(dead_code)]
     pub async fn delete(&self, id: &str) -> Result<bool, DieselError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let rows = diesel::delete(sources::table.find(id))
                 .execute(&mut conn)
                 .await?;
@@ -164,7 +171,7 @@ impl DieselSourceRepository {
         timestamp: DateTime<Utc>,
     ) -> Result<(), DieselError> {
         let ts = timestamp.to_rfc3339();
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(sources::table.find(id))
                 .set(sources::last_scraped.eq(Some(&ts)))
                 .execute(&mut conn)
@@ -206,7 +213,7 @@ impl DieselSourceRepository {
         let sql_crawl_config = build_sql(&self.pool, &update_crawl_config);
         let sql_sources = build_sql(&self.pool, &update_sources);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let docs_updated = diesel::sql_query(&sql_docs).execute(&mut conn).await?;
             let crawls_updated = diesel::sql_query(&sql_crawl_urls)
                 .execute(&mut conn)
@@ -243,7 +250,8 @@ mod tests {
                 base_url TEXT NOT NULL,
                 metadata TEXT NOT NULL DEFAULT '{}',
                 created_at TEXT NOT NULL,
-                last_scraped TEXT
+                last_scraped TEXT,
+                agency_id TEXT
             )"#,
         )
         .await
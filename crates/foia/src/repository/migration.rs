@@ -27,6 +27,8 @@ pub struct PortableSource {
     pub metadata: String,
     pub created_at: String,
     pub last_scraped: Option<String>,
+    #[serde(default)]
+    pub agency_id: Option<String>,
 }
 
 /// Portable document record for migration.
@@ -49,6 +51,20 @@ pub struct PortableDocument {
     pub manual_date: Option<String>,
     pub discovery_method: String,
     pub category_id: Option<String>,
+    #[serde(default)]
+    pub record_type: Option<String>,
+    #[serde(default)]
+    pub record_type_confidence: Option<f32>,
+    #[serde(default)]
+    pub document_date: Option<String>,
+    #[serde(default)]
+    pub originating_agency: Option<String>,
+    #[serde(default)]
+    pub originating_office: Option<String>,
+    #[serde(default)]
+    pub case_number: Option<String>,
+    #[serde(default)]
+    pub classification_markings: Option<String>,
 }
 
 /// Portable document version record for migration.
@@ -81,6 +97,10 @@ pub struct PortableDocumentPage {
     pub ocr_status: String,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub ocr_quality: Option<f32>,
+    #[serde(default)]
+    pub corrected_at: Option<String>,
 }
 
 /// Portable virtual file record for migration.
@@ -299,6 +319,7 @@ impl From<super::models::SourceRecord> for PortableSource {
             metadata: r.metadata,
             created_at: r.created_at,
             last_scraped: r.last_scraped,
+            agency_id: r.agency_id,
         }
     }
 }
@@ -323,6 +344,13 @@ impl From<super::models::DocumentRecord> for PortableDocument {
             manual_date: r.manual_date,
             discovery_method: r.discovery_method,
             category_id: r.category_id,
+            record_type: r.record_type,
+            record_type_confidence: r.record_type_confidence,
+            document_date: r.document_date,
+            originating_agency: r.originating_agency,
+            originating_office: r.originating_office,
+            case_number: r.case_number,
+            classification_markings: r.classification_markings,
         }
     }
 }
@@ -359,6 +387,8 @@ impl From<super::models::DocumentPageRecord> for PortableDocumentPage {
             ocr_status: r.ocr_status,
             created_at: r.created_at,
             updated_at: r.updated_at,
+            ocr_quality: r.ocr_quality,
+            corrected_at: r.corrected_at,
         }
     }
 }
@@ -76,14 +76,16 @@ impl PostgresMigrator {
         self.copy_batched(
             "COPY documents (id, source_id, title, source_url, extracted_text, status, metadata,
                 created_at, updated_at, synopsis, tags, estimated_date, date_confidence, date_source,
-                manual_date, discovery_method, category_id)
+                manual_date, discovery_method, category_id, record_type, record_type_confidence,
+                document_date, originating_agency, originating_office, case_number,
+                classification_markings)
              FROM STDIN WITH (FORMAT text)",
             documents,
             1000,
             500,
             |d| {
                 format!(
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
                     Self::escape_copy_value(Some(&d.id)),
                     Self::escape_copy_value(Some(&d.source_id)),
                     Self::escape_copy_value(Some(&d.title)),
@@ -101,6 +103,15 @@ impl PostgresMigrator {
                     Self::escape_copy_value(d.manual_date.as_deref()),
                     Self::escape_copy_value(Some(&d.discovery_method)),
                     Self::escape_copy_value(d.category_id.as_deref()),
+                    Self::escape_copy_value(d.record_type.as_deref()),
+                    d.record_type_confidence
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "\\N".to_string()),
+                    Self::escape_copy_value(d.document_date.as_deref()),
+                    Self::escape_copy_value(d.originating_agency.as_deref()),
+                    Self::escape_copy_value(d.originating_office.as_deref()),
+                    Self::escape_copy_value(d.case_number.as_deref()),
+                    Self::escape_copy_value(d.classification_markings.as_deref()),
                 )
             },
             progress,
@@ -115,14 +126,14 @@ impl PostgresMigrator {
         progress: Option<ProgressCallback>,
     ) -> Result<usize, DieselError> {
         self.copy_batched(
-            "COPY sources (id, source_type, name, base_url, metadata, created_at, last_scraped)
+            "COPY sources (id, source_type, name, base_url, metadata, created_at, last_scraped, agency_id)
              FROM STDIN WITH (FORMAT text)",
             sources,
             1000,
             200,
             |s| {
                 format!(
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
                     Self::escape_copy_value(Some(&s.id)),
                     Self::escape_copy_value(Some(&s.source_type)),
                     Self::escape_copy_value(Some(&s.name)),
@@ -130,6 +141,7 @@ impl PostgresMigrator {
                     Self::escape_copy_value(Some(&s.metadata)),
                     Self::escape_copy_value(Some(&s.created_at)),
                     Self::escape_copy_value(s.last_scraped.as_deref()),
+                    Self::escape_copy_value(s.agency_id.as_deref()),
                 )
             },
             progress,
@@ -183,14 +195,15 @@ impl PostgresMigrator {
     ) -> Result<usize, DieselError> {
         self.copy_batched(
             "COPY document_pages (id, document_id, version_id, page_number, pdf_text,
-                ocr_text, final_text, ocr_status, created_at, updated_at)
+                ocr_text, final_text, ocr_status, created_at, updated_at, ocr_quality,
+                corrected_at)
              FROM STDIN WITH (FORMAT text)",
             pages,
             1000,
             500,
             |p| {
                 format!(
-                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
                     p.id,
                     Self::escape_copy_value(Some(&p.document_id)),
                     p.version_id,
@@ -201,6 +214,10 @@ impl PostgresMigrator {
                     Self::escape_copy_value(Some(&p.ocr_status)),
                     Self::escape_copy_value(Some(&p.created_at)),
                     Self::escape_copy_value(Some(&p.updated_at)),
+                    p.ocr_quality
+                        .map(|q| q.to_string())
+                        .unwrap_or_else(|| "\\N".to_string()),
+                    Self::escape_copy_value(p.corrected_at.as_deref()),
                 )
             },
             progress,
@@ -56,15 +56,16 @@ impl DatabaseImporter for PostgresMigrator {
 
         for s in sources_data {
             diesel::sql_query(
-                "INSERT INTO sources (id, source_type, name, base_url, metadata, created_at, last_scraped)
-                 VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "INSERT INTO sources (id, source_type, name, base_url, metadata, created_at, last_scraped, agency_id)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                  ON CONFLICT (id) DO UPDATE SET
                     source_type = EXCLUDED.source_type,
                     name = EXCLUDED.name,
                     base_url = EXCLUDED.base_url,
                     metadata = EXCLUDED.metadata,
                     created_at = EXCLUDED.created_at,
-                    last_scraped = EXCLUDED.last_scraped"
+                    last_scraped = EXCLUDED.last_scraped,
+                    agency_id = EXCLUDED.agency_id"
             )
             .bind::<diesel::sql_types::Text, _>(&s.id)
             .bind::<diesel::sql_types::Text, _>(&s.source_type)
@@ -73,6 +74,7 @@ impl DatabaseImporter for PostgresMigrator {
             .bind::<diesel::sql_types::Text, _>(&s.metadata)
             .bind::<diesel::sql_types::Text, _>(&s.created_at)
             .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&s.last_scraped)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&s.agency_id)
             .execute(&mut conn)
             .await?;
             count += 1;
@@ -103,8 +105,11 @@ impl DatabaseImporter for PostgresMigrator {
             diesel::sql_query(
                 "INSERT INTO documents (id, source_id, title, source_url, extracted_text, status, metadata,
                     created_at, updated_at, synopsis, tags, estimated_date, date_confidence, date_source,
-                    manual_date, discovery_method, category_id)
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                    manual_date, discovery_method, category_id, record_type, record_type_confidence,
+                    document_date, originating_agency, originating_office, case_number,
+                    classification_markings)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19,
+                    $20, $21, $22, $23, $24)
                  ON CONFLICT (id) DO UPDATE SET
                     source_id = EXCLUDED.source_id,
                     title = EXCLUDED.title,
@@ -121,7 +126,14 @@ impl DatabaseImporter for PostgresMigrator {
                     date_source = EXCLUDED.date_source,
                     manual_date = EXCLUDED.manual_date,
                     discovery_method = EXCLUDED.discovery_method,
-                    category_id = EXCLUDED.category_id"
+                    category_id = EXCLUDED.category_id,
+                    record_type = EXCLUDED.record_type,
+                    record_type_confidence = EXCLUDED.record_type_confidence,
+                    document_date = EXCLUDED.document_date,
+                    originating_agency = EXCLUDED.originating_agency,
+                    originating_office = EXCLUDED.originating_office,
+                    case_number = EXCLUDED.case_number,
+                    classification_markings = EXCLUDED.classification_markings"
             )
             .bind::<diesel::sql_types::Text, _>(&d.id)
             .bind::<diesel::sql_types::Text, _>(&d.source_id)
@@ -140,6 +152,13 @@ impl DatabaseImporter for PostgresMigrator {
             .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.manual_date)
             .bind::<diesel::sql_types::Text, _>(&d.discovery_method)
             .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.category_id)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.record_type)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(d.record_type_confidence)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.document_date)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.originating_agency)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.originating_office)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.case_number)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&d.classification_markings)
             .execute(&mut conn)
             .await?;
             count += 1;
@@ -229,9 +248,10 @@ impl DatabaseImporter for PostgresMigrator {
         for p in pages {
             diesel::sql_query(
                 "INSERT INTO document_pages (id, document_id, version_id, page_number, pdf_text,
-                    ocr_text, final_text, ocr_status, created_at, updated_at)
+                    ocr_text, final_text, ocr_status, created_at, updated_at, ocr_quality,
+                    corrected_at)
                  OVERRIDING SYSTEM VALUE
-                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
                  ON CONFLICT (id) DO UPDATE SET
                     document_id = EXCLUDED.document_id,
                     version_id = EXCLUDED.version_id,
@@ -241,7 +261,9 @@ impl DatabaseImporter for PostgresMigrator {
                     final_text = EXCLUDED.final_text,
                     ocr_status = EXCLUDED.ocr_status,
                     created_at = EXCLUDED.created_at,
-                    updated_at = EXCLUDED.updated_at",
+                    updated_at = EXCLUDED.updated_at,
+                    ocr_quality = EXCLUDED.ocr_quality,
+                    corrected_at = EXCLUDED.corrected_at",
             )
             .bind::<diesel::sql_types::Integer, _>(p.id)
             .bind::<diesel::sql_types::Text, _>(&p.document_id)
@@ -253,6 +275,8 @@ impl DatabaseImporter for PostgresMigrator {
             .bind::<diesel::sql_types::Text, _>(&p.ocr_status)
             .bind::<diesel::sql_types::Text, _>(&p.created_at)
             .bind::<diesel::sql_types::Text, _>(&p.updated_at)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Float>, _>(p.ocr_quality)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Text>, _>(&p.corrected_at)
             .execute(&mut conn)
             .await?;
             count += 1;
@@ -239,7 +239,8 @@ impl PostgresMigrator {
                 base_url TEXT NOT NULL,
                 metadata TEXT NOT NULL DEFAULT '{}',
                 created_at TEXT NOT NULL,
-                last_scraped TEXT
+                last_scraped TEXT,
+                agency_id TEXT
             )"#,
             r#"CREATE TABLE IF NOT EXISTS documents (
                 id TEXT PRIMARY KEY,
@@ -258,7 +259,14 @@ impl PostgresMigrator {
                 date_source TEXT,
                 manual_date TEXT,
                 discovery_method TEXT NOT NULL DEFAULT 'seed',
-                category_id TEXT
+                category_id TEXT,
+                record_type TEXT,
+                record_type_confidence REAL,
+                document_date TEXT,
+                originating_agency TEXT,
+                originating_office TEXT,
+                case_number TEXT,
+                classification_markings TEXT
             )"#,
             r#"CREATE TABLE IF NOT EXISTS document_versions (
                 id SERIAL PRIMARY KEY,
@@ -284,7 +292,9 @@ impl PostgresMigrator {
                 final_text TEXT,
                 ocr_status TEXT NOT NULL DEFAULT 'pending',
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                ocr_quality REAL,
+                corrected_at TEXT
             )"#,
             r#"CREATE TABLE IF NOT EXISTS virtual_files (
                 id TEXT PRIMARY KEY,
@@ -157,6 +157,7 @@ impl DatabaseImporter for SqliteMigrator {
                     sources::metadata.eq(&s.metadata),
                     sources::created_at.eq(&s.created_at),
                     sources::last_scraped.eq(&s.last_scraped),
+                    sources::agency_id.eq(&s.agency_id),
                 ))
                 .execute(&mut conn)
                 .await?;
@@ -197,6 +198,13 @@ impl DatabaseImporter for SqliteMigrator {
                     documents::manual_date.eq(&d.manual_date),
                     documents::discovery_method.eq(&d.discovery_method),
                     documents::category_id.eq(&d.category_id),
+                    documents::record_type.eq(&d.record_type),
+                    documents::record_type_confidence.eq(&d.record_type_confidence),
+                    documents::document_date.eq(&d.document_date),
+                    documents::originating_agency.eq(&d.originating_agency),
+                    documents::originating_office.eq(&d.originating_office),
+                    documents::case_number.eq(&d.case_number),
+                    documents::classification_markings.eq(&d.classification_markings),
                 ))
                 .execute(&mut conn)
                 .await?;
@@ -265,6 +273,8 @@ impl DatabaseImporter for SqliteMigrator {
                     document_pages::ocr_status.eq(&p.ocr_status),
                     document_pages::created_at.eq(&p.created_at),
                     document_pages::updated_at.eq(&p.updated_at),
+                    document_pages::ocr_quality.eq(p.ocr_quality),
+                    document_pages::corrected_at.eq(&p.corrected_at),
                 ))
                 .execute(&mut conn)
                 .await?;
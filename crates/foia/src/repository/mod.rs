@@ -5,17 +5,28 @@
 
 // New unified modules
 pub mod context;
+pub mod cursor;
 pub mod models;
 pub mod pool;
 pub mod sea_tables;
+pub mod shards;
 
 // Repositories
 pub mod source;
 
 // Legacy diesel-prefixed modules (to be removed)
+pub mod diesel_access_log;
+pub mod diesel_agency;
+pub mod diesel_api_key;
+pub mod diesel_broker;
 pub mod diesel_config_history;
 pub mod diesel_crawl;
 pub mod diesel_document;
+pub mod diesel_flag;
+pub mod diesel_foia_request;
+pub mod diesel_job;
+pub mod diesel_llm_usage;
+pub mod diesel_saved_view;
 pub mod diesel_scraper_config;
 
 // Keep these until fully migrated
@@ -43,16 +54,26 @@ mod document;
 // Re-export main types (may be unused in main binary but are public API)
 #[allow(unused_imports)]
 pub use context::DbContext;
+pub use cursor::{Page, PageCursor};
 #[allow(unused_imports)]
 pub use pool::{DbError, DbPool};
 #[allow(unused_imports)]
 pub use source::SourceRepository;
 
 // Legacy re-exports for backwards compatibility
+pub use diesel_access_log::DieselAccessLogRepository;
+pub use diesel_agency::DieselAgencyRepository;
+pub use diesel_api_key::DieselApiKeyRepository;
+pub use diesel_broker::DieselBrokerRepository;
 #[allow(unused_imports)]
-pub use diesel_config_history::DieselConfigHistoryRepository;
+pub use diesel_config_history::{DieselConfigHistoryEntry, DieselConfigHistoryRepository};
 pub use diesel_crawl::DieselCrawlRepository;
 pub use diesel_document::DieselDocumentRepository;
+pub use diesel_flag::DieselFlagRepository;
+pub use diesel_foia_request::DieselFoiaRequestRepository;
+pub use diesel_job::DieselJobRepository;
+pub use diesel_llm_usage::DieselLlmUsageRepository;
+pub use diesel_saved_view::DieselSavedViewRepository;
 pub use diesel_scraper_config::DieselScraperConfigRepository;
 #[allow(unused_imports)]
 pub use diesel_service_status::DieselServiceStatusRepository;
@@ -67,10 +88,12 @@ pub use document::{extract_filename_parts, sanitize_filename};
 // Re-export models (public API)
 #[allow(unused_imports)]
 pub use models::{
-    ConfigHistoryRecord, CrawlConfigRecord, CrawlRequestRecord, CrawlUrlRecord, DocumentPageRecord,
-    DocumentRecord, DocumentVersionRecord, NewConfigHistory, NewCrawlRequest, NewCrawlUrl,
-    NewDocument, NewDocumentPage, NewDocumentVersion, NewRateLimitState, NewScraperConfig,
-    NewSource, NewVirtualFile, RateLimitStateRecord, ScraperConfigRecord, SourceRecord,
+    AgencyRecord, ApiKeyRecord, ConfigHistoryRecord, CrawlConfigRecord, CrawlRequestRecord,
+    CrawlUrlRecord, DocumentFlagRecord, DocumentPageRecord, DocumentRecord, DocumentVersionRecord,
+    FoiaRequestRecord, NewAgency, NewApiKey, NewConfigHistory, NewCrawlRequest, NewCrawlUrl,
+    NewDocument, NewDocumentPage, NewDocumentVersion, NewFoiaRequest, NewRateLimitState,
+    NewSavedView, NewScraperConfig, NewSource, NewVirtualFile, RateLimitStateRecord,
+    SavedViewRecord, SavedViewSubscriptionRecord, ScraperConfigRecord, SourceRecord,
     VirtualFileRecord,
 };
 
@@ -84,11 +107,20 @@ use self::diesel_context::DieselDbContext;
 /// repetitive `create_db_context()` boilerplate in CLI commands.
 pub struct Repositories {
     pub sources: DieselSourceRepository,
+    pub agencies: DieselAgencyRepository,
     pub crawl: DieselCrawlRepository,
     pub documents: DieselDocumentRepository,
     pub config_history: DieselConfigHistoryRepository,
     pub scraper_configs: DieselScraperConfigRepository,
     pub service_status: DieselServiceStatusRepository,
+    pub jobs: DieselJobRepository,
+    pub foia_requests: DieselFoiaRequestRepository,
+    pub saved_views: DieselSavedViewRepository,
+    pub llm_usage: DieselLlmUsageRepository,
+    pub api_keys: DieselApiKeyRepository,
+    pub access_log: DieselAccessLogRepository,
+    pub flags: DieselFlagRepository,
+    pub broker: DieselBrokerRepository,
     pool: DbPool,
 }
 
@@ -96,11 +128,20 @@ impl Repositories {
     pub fn new(ctx: DieselDbContext) -> Self {
         Self {
             sources: ctx.sources(),
+            agencies: ctx.agencies(),
             crawl: ctx.crawl(),
             documents: ctx.documents(),
             config_history: ctx.config_history(),
             scraper_configs: ctx.scraper_configs(),
             service_status: ctx.service_status(),
+            jobs: ctx.jobs(),
+            foia_requests: ctx.foia_requests(),
+            saved_views: ctx.saved_views(),
+            llm_usage: ctx.llm_usage(),
+            api_keys: ctx.api_keys(),
+            access_log: ctx.access_log(),
+            flags: ctx.flags(),
+            broker: ctx.broker(),
             pool: ctx.pool().clone(),
         }
     }
@@ -21,6 +21,7 @@ pub struct SourceRecord {
     pub metadata: String,
     pub created_at: String,
     pub last_scraped: Option<String>,
+    pub agency_id: Option<String>,
 }
 
 /// New source for insertion.
@@ -34,6 +35,49 @@ pub struct NewSource<'a> {
     pub metadata: &'a str,
     pub created_at: &'a str,
     pub last_scraped: Option<&'a str>,
+    pub agency_id: Option<&'a str>,
+}
+
+// =============================================================================
+// Agencies
+// =============================================================================
+
+/// Agency registry record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::agencies)]
+pub struct AgencyRecord {
+    pub id: String,
+    pub name: String,
+    pub abbreviation: Option<String>,
+    pub jurisdiction: String,
+    pub level: String,
+    pub parent_id: Option<String>,
+    pub foia_email: Option<String>,
+    pub foia_phone: Option<String>,
+    pub foia_address: Option<String>,
+    pub portal_urls: String,
+    pub website: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// New agency for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::agencies)]
+pub struct NewAgency<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    pub abbreviation: Option<&'a str>,
+    pub jurisdiction: &'a str,
+    pub level: &'a str,
+    pub parent_id: Option<&'a str>,
+    pub foia_email: Option<&'a str>,
+    pub foia_phone: Option<&'a str>,
+    pub foia_address: Option<&'a str>,
+    pub portal_urls: String,
+    pub website: Option<&'a str>,
+    pub created_at: String,
+    pub updated_at: String,
 }
 
 // =============================================================================
@@ -147,7 +191,7 @@ pub struct CrawlConfigRecord {
 // =============================================================================
 
 /// Document record from the database.
-#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
 #[diesel(table_name = schema::documents)]
 pub struct DocumentRecord {
     pub id: String,
@@ -167,6 +211,13 @@ pub struct DocumentRecord {
     pub manual_date: Option<String>,
     pub discovery_method: String,
     pub category_id: Option<String>,
+    pub record_type: Option<String>,
+    pub record_type_confidence: Option<f32>,
+    pub document_date: Option<String>,
+    pub originating_agency: Option<String>,
+    pub originating_office: Option<String>,
+    pub case_number: Option<String>,
+    pub classification_markings: Option<String>,
 }
 
 /// New document for insertion.
@@ -215,6 +266,8 @@ pub struct DocumentVersionRecord {
     pub archive_snapshot_id: Option<i32>,
     pub earliest_archived_at: Option<String>,
     pub dedup_index: Option<i32>,
+    pub checksum_verification: Option<String>,
+    pub declared_mime_type: Option<String>,
 }
 
 /// New document version for insertion.
@@ -235,6 +288,8 @@ pub struct NewDocumentVersion<'a> {
     pub archive_snapshot_id: Option<i32>,
     pub earliest_archived_at: Option<&'a str>,
     pub dedup_index: Option<i32>,
+    pub checksum_verification: Option<&'a str>,
+    pub declared_mime_type: Option<&'a str>,
 }
 
 // =============================================================================
@@ -255,6 +310,8 @@ pub struct DocumentPageRecord {
     pub ocr_status: String,
     pub created_at: String,
     pub updated_at: String,
+    pub ocr_quality: Option<f32>,
+    pub corrected_at: Option<String>,
 }
 
 /// New document page for insertion.
@@ -293,6 +350,9 @@ pub struct PageOcrResultRecord {
     pub created_at: String,
     pub model: Option<String>,
     pub image_hash: Option<String>,
+    pub tool_version: Option<String>,
+    pub language: Option<String>,
+    pub dpi: Option<i32>,
 }
 
 /// New page OCR result for insertion.
@@ -311,6 +371,9 @@ pub struct NewPageOcrResult<'a> {
     pub created_at: &'a str,
     pub model: Option<&'a str>,
     pub image_hash: Option<&'a str>,
+    pub tool_version: Option<&'a str>,
+    pub language: Option<&'a str>,
+    pub dpi: Option<i32>,
 }
 
 // =============================================================================
@@ -460,6 +523,231 @@ pub struct ServiceStatusRecord {
     pub error_count: i32,
 }
 
+// =============================================================================
+// Jobs
+// =============================================================================
+
+/// Background job record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::jobs)]
+pub struct JobRecord {
+    pub id: String,
+    pub kind: String,
+    pub params: String,
+    pub status: String,
+    pub progress: i64,
+    pub total: Option<i64>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+    pub result: Option<String>,
+    pub cancel_requested: i32,
+    pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub updated_at: String,
+}
+
+/// Worker queue message record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::broker_messages)]
+pub struct BrokerMessageRecord {
+    pub id: i32,
+    pub queue: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub available_at: String,
+    pub claimed_until: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+}
+
+// =============================================================================
+// Saved Views
+// =============================================================================
+
+/// Saved browse view (named filter bookmark) from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::saved_views)]
+#[diesel(primary_key(name))]
+pub struct SavedViewRecord {
+    pub name: String,
+    /// Browse query string, without the leading `?`.
+    pub query: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// New saved view for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::saved_views)]
+pub struct NewSavedView<'a> {
+    pub name: &'a str,
+    pub query: &'a str,
+    pub created_at: &'a str,
+    pub updated_at: &'a str,
+}
+
+/// Saved view subscription record from the database.
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = schema::saved_view_subscriptions)]
+pub struct SavedViewSubscriptionRecord {
+    pub view_name: String,
+    /// Address alerted about new matches.
+    pub email: String,
+    pub created_at: String,
+}
+
+// =============================================================================
+// Curation Log
+// =============================================================================
+
+/// Curation log entry from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::curation_log)]
+pub struct CurationLogRecord {
+    pub id: i32,
+    pub action: String,
+    pub document_id: String,
+    /// JSON array of the other documents involved.
+    pub related_ids: String,
+    /// JSON object describing the change.
+    pub details: String,
+    pub actor: Option<String>,
+    pub created_at: String,
+}
+
+/// New curation log entry for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::curation_log)]
+pub struct NewCurationLogEntry<'a> {
+    pub action: &'a str,
+    pub document_id: &'a str,
+    pub related_ids: &'a str,
+    pub details: &'a str,
+    pub actor: Option<&'a str>,
+    pub created_at: &'a str,
+}
+
+// =============================================================================
+// LLM Usage
+// =============================================================================
+
+/// New LLM usage entry for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::llm_usage)]
+pub struct NewLlmUsage<'a> {
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub operation: &'a str,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub cost_usd: Option<f64>,
+    pub created_at: &'a str,
+}
+
+// =============================================================================
+// API Keys
+// =============================================================================
+
+/// JSON API key record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::api_keys)]
+pub struct ApiKeyRecord {
+    pub id: i32,
+    pub name: String,
+    /// Start of the key, to tell keys apart in listings.
+    pub prefix: String,
+    /// SHA-256 of the key, hex-encoded.
+    pub key_hash: String,
+    /// Requests per minute; `None` uses the server default.
+    pub rate_limit: Option<i32>,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    /// `admin` keys may also manage keys; others are `client`.
+    pub role: String,
+}
+
+/// New API key for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::api_keys)]
+pub struct NewApiKey<'a> {
+    pub name: &'a str,
+    pub prefix: &'a str,
+    pub key_hash: &'a str,
+    pub rate_limit: Option<i32>,
+    pub created_at: &'a str,
+    pub role: &'a str,
+}
+
+// =============================================================================
+// Document Flags
+// =============================================================================
+
+/// Document flag record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::document_flags)]
+pub struct DocumentFlagRecord {
+    pub id: i32,
+    pub document_id: String,
+    pub reason: String,
+    pub page: Option<i32>,
+    pub note: String,
+    pub status: String,
+    pub created_at: String,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,
+}
+
+// =============================================================================
+// FOIA Requests
+// =============================================================================
+
+/// FOIA request record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::foia_requests)]
+pub struct FoiaRequestRecord {
+    pub id: String,
+    pub agency: String,
+    pub subject: String,
+    pub request_text: String,
+    pub tracking_number: Option<String>,
+    pub source_id: Option<String>,
+    pub status: String,
+    pub filed_on: String,
+    pub acknowledged_on: Option<String>,
+    pub fulfilled_on: Option<String>,
+    pub appealed_on: Option<String>,
+    pub notes: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub jurisdiction: Option<String>,
+    pub reminded_on: Option<String>,
+}
+
+/// New FOIA request for insertion.
+#[derive(Insertable, Debug)]
+#[diesel(table_name = schema::foia_requests)]
+pub struct NewFoiaRequest<'a> {
+    pub id: &'a str,
+    pub agency: &'a str,
+    pub subject: &'a str,
+    pub request_text: &'a str,
+    pub tracking_number: Option<&'a str>,
+    pub source_id: Option<&'a str>,
+    pub status: &'a str,
+    pub filed_on: String,
+    pub acknowledged_on: Option<String>,
+    pub fulfilled_on: Option<String>,
+    pub appealed_on: Option<String>,
+    pub notes: Option<&'a str>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub jurisdiction: Option<&'a str>,
+    pub reminded_on: Option<String>,
+}
+
 // =============================================================================
 // Document Entities
 // =============================================================================
@@ -491,6 +779,77 @@ pub struct NewDocumentEntity<'a> {
     pub created_at: &'a str,
 }
 
+/// Document map position record from the database.
+#[derive(Queryable, Selectable, Identifiable, Insertable, Debug, Clone)]
+#[diesel(table_name = schema::document_locations)]
+#[diesel(primary_key(document_id))]
+pub struct DocumentLocationRecord {
+    pub document_id: String,
+    pub place: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub mentions: i32,
+    pub updated_at: String,
+}
+
+/// Exemption citation count record from the database.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = schema::document_exemptions)]
+pub struct DocumentExemptionRecord {
+    pub document_id: String,
+    pub code: String,
+    pub citations: i32,
+}
+
+/// Embedded PDF metadata record from the database.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = schema::document_pdf_metadata)]
+#[diesel(primary_key(document_id))]
+pub struct DocumentPdfMetadataRecord {
+    pub document_id: String,
+    pub author: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub created_at: Option<String>,
+    pub modified_at: Option<String>,
+    pub updated_at: String,
+}
+
+/// Document relation record from the database.
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = schema::document_relations)]
+pub struct DocumentRelationRecord {
+    pub source_document_id: String,
+    pub target_document_id: String,
+    pub kind: String,
+    pub evidence: String,
+    pub created_at: String,
+}
+
+// =============================================================================
+// Derived Artifacts
+// =============================================================================
+
+/// Derived artifact record from the database.
+#[derive(Queryable, Selectable, Identifiable, Debug, Clone)]
+#[diesel(table_name = schema::derived_artifacts)]
+pub struct DerivedArtifactRecord {
+    pub id: i32,
+    pub document_id: String,
+    pub version_id: i32,
+    pub kind: String,
+    pub name: String,
+    pub mime_type: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub content_hash: String,
+    pub analysis_result_id: Option<i32>,
+    pub status: String,
+    pub metadata: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
 // =============================================================================
 // Document Analysis Results
 // =============================================================================
@@ -2,12 +2,28 @@
 //!
 //! This module provides a backend-agnostic interface for database connections.
 //! The actual backend is determined at runtime based on the database URL.
+//!
+//! SQLite connections run in WAL mode so readers never block the writer.
+//! SQLite still allows only one writer per file, so within a process all
+//! writes go through a single shared connection per database, queued on an
+//! async mutex (see [`with_write_conn!`]); reads open their own connections
+//! and stay concurrent. Separate processes (a scraper next to the web
+//! server) wait on each other via `busy_timeout` instead of failing with
+//! SQLITE_BUSY.
+//!
+//! Writes that must land together, like a document and its versions, run
+//! inside [`DbPool::transaction`]: the write macros pick up the open
+//! transaction's connection, so repository methods join it unchanged.
 
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use diesel::sqlite::SqliteConnection;
 use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
-use diesel_async::AsyncConnection;
+use diesel_async::{AsyncConnection, SimpleAsyncConnection, TransactionManager};
+use tokio::sync::OwnedMutexGuard;
 
 #[cfg(feature = "postgres")]
 use diesel_async::pooled_connection::deadpool::Pool as DeadPool;
@@ -35,10 +51,84 @@ pub type SqliteConn = SyncConnectionWrapper<SqliteConnection>;
 #[cfg(feature = "postgres")]
 pub type PgConn = deadpool::managed::Object<AsyncDieselConnectionManager<AsyncPgConnection>>;
 
+/// Settings applied to every SQLite connection. A connection waits up to
+/// 30s for another process's lock before giving up with SQLITE_BUSY.
+const SQLITE_PRAGMAS: &str = "PRAGMA busy_timeout = 30000; \
+     PRAGMA journal_mode = WAL; \
+     PRAGMA synchronous = NORMAL;";
+
+/// The shared writer connection for one database, opened on first write.
+type WriterSlot = Arc<tokio::sync::Mutex<Option<SqliteConn>>>;
+
+/// Writer slot for a database URL, shared by every pool in the process.
+fn writer_slot(database_url: &str) -> WriterSlot {
+    static SLOTS: OnceLock<Mutex<HashMap<String, WriterSlot>>> = OnceLock::new();
+    SLOTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(database_url.to_string())
+        .or_default()
+        .clone()
+}
+
+/// A connection with a transaction open on it.
+enum TxConn {
+    Sqlite(SqliteConn),
+    #[cfg(feature = "postgres")]
+    Postgres(PgConn),
+}
+
+/// Holds a transaction's connection between the writes made in it.
+type TxSlot = Arc<Mutex<Option<TxConn>>>;
+
+tokio::task_local! {
+    /// Transactions open in the current task, keyed by pool.
+    static OPEN_TRANSACTIONS: Vec<(usize, TxSlot)>;
+}
+
+/// The transaction open in the current task for the pool with `key`.
+fn open_transaction(key: usize) -> Option<TxSlot> {
+    OPEN_TRANSACTIONS
+        .try_with(|open| {
+            open.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, slot)| slot.clone())
+        })
+        .ok()
+        .flatten()
+}
+
+/// Take a transaction's connection. `None` if a write in it returned
+/// early and dropped the connection.
+fn take_tx_conn(slot: &TxSlot) -> Option<TxConn> {
+    slot.lock().unwrap().take()
+}
+
+/// Begin a transaction on `conn`.
+async fn begin_transaction<C: AsyncConnection>(conn: &mut C) -> Result<(), DbError> {
+    <C::TransactionManager as TransactionManager<C>>::begin_transaction(conn).await
+}
+
+/// Commit or roll back the transaction open on `conn`.
+async fn end_transaction<C: AsyncConnection>(conn: &mut C, commit: bool) -> Result<(), DbError> {
+    if commit {
+        return <C::TransactionManager as TransactionManager<C>>::commit_transaction(conn).await;
+    }
+    let result = <C::TransactionManager as TransactionManager<C>>::rollback_transaction(conn).await;
+    if let Err(e) = &result {
+        tracing::warn!("Failed to roll back transaction: {}", e);
+    }
+    result
+}
+
 /// SQLite connection pool (lightweight - creates connections on demand).
 #[derive(Clone)]
 pub struct SqlitePool {
     database_url: String,
+    writer: WriterSlot,
+    /// SQL run on each new read connection, e.g. to attach shards.
+    read_init: Option<Arc<str>>,
 }
 
 #[allow(dead_code)]
@@ -49,19 +139,59 @@ impl SqlitePool {
         let url = database_url.strip_prefix("sqlite:").unwrap_or(database_url);
         Self {
             database_url: url.to_string(),
+            writer: writer_slot(url),
+            read_init: None,
         }
     }
 
+    /// Run `sql` on every read connection after it opens. The writer
+    /// connection doesn't run it.
+    pub fn with_read_init(mut self, sql: String) -> Self {
+        self.read_init = (!sql.is_empty()).then(|| Arc::from(sql));
+        self
+    }
+
     /// Create pool from a file path.
     pub fn from_path(path: &Path) -> Self {
         Self::new(&path.display().to_string())
     }
 
-    /// Get a connection.
+    /// Get a connection for reading.
     pub async fn get(&self) -> Result<SqliteConn, DbError> {
-        SqliteConn::establish(&self.database_url)
+        let mut conn = self.open().await?;
+        if let Some(sql) = &self.read_init {
+            conn.batch_execute(sql).await?;
+        }
+        Ok(conn)
+    }
+
+    async fn open(&self) -> Result<SqliteConn, DbError> {
+        let mut conn = SqliteConn::establish(&self.database_url)
             .await
-            .map_err(to_diesel_error)
+            .map_err(to_diesel_error)?;
+        conn.batch_execute(SQLITE_PRAGMAS).await?;
+        Ok(conn)
+    }
+
+    /// Wait for exclusive use of the database's writer connection.
+    ///
+    /// Writers are served in the order they asked. Hold the returned
+    /// [`SqliteWriter`] only for the write itself. Inside a
+    /// [`DbPool::transaction`] scope this is the transaction's connection.
+    pub async fn writer(&self) -> SqliteWriter {
+        if let Some(tx) = open_transaction(self.tx_key()) {
+            return SqliteWriter(WriterHold::Transaction(tx));
+        }
+        SqliteWriter(WriterHold::Shared {
+            slot: self.writer.clone().lock_owned().await,
+            pool: self.clone(),
+        })
+    }
+
+    /// Identifies the database in open transactions. Pools for the same
+    /// file share a writer, so they share transactions too.
+    fn tx_key(&self) -> usize {
+        Arc::as_ptr(&self.writer) as usize
     }
 
     /// Get the database URL.
@@ -70,11 +200,50 @@ impl SqlitePool {
     }
 }
 
+/// Exclusive hold on a SQLite database's writer connection.
+pub struct SqliteWriter(WriterHold);
+
+enum WriterHold {
+    Shared {
+        slot: OwnedMutexGuard<Option<SqliteConn>>,
+        pool: SqlitePool,
+    },
+    /// The connection of a transaction open in this task.
+    Transaction(TxSlot),
+}
+
+impl SqliteWriter {
+    /// Take the writer connection, opening it if this is the first write or
+    /// the last writer didn't hand it back.
+    pub async fn take(&mut self) -> Result<SqliteConn, DbError> {
+        match &mut self.0 {
+            WriterHold::Shared { slot, pool } => match slot.take() {
+                Some(conn) => Ok(conn),
+                None => pool.open().await,
+            },
+            WriterHold::Transaction(tx) => match take_tx_conn(tx) {
+                Some(TxConn::Sqlite(conn)) => Ok(conn),
+                _ => Err(DbError::BrokenTransactionManager),
+            },
+        }
+    }
+
+    /// Hand the connection back for the next writer.
+    pub fn put_back(&mut self, conn: SqliteConn) {
+        match &mut self.0 {
+            WriterHold::Shared { slot, .. } => **slot = Some(conn),
+            WriterHold::Transaction(tx) => *tx.lock().unwrap() = Some(TxConn::Sqlite(conn)),
+        }
+    }
+}
+
 /// PostgreSQL connection pool.
 #[cfg(feature = "postgres")]
 #[derive(Clone)]
 pub struct PgPool {
     pool: DeadPool<AsyncPgConnection>,
+    /// Identifies this pool in open transactions.
+    key: Arc<()>,
 }
 
 #[cfg(feature = "postgres")]
@@ -96,7 +265,10 @@ impl PgPool {
             .max_size(max_size)
             .build()
             .map_err(to_diesel_error)?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            key: Arc::new(()),
+        })
     }
 
     /// Get a connection.
@@ -104,12 +276,54 @@ impl PgPool {
         self.pool.get().await.map_err(to_diesel_error)
     }
 
+    /// Get a connection for writing: the transaction's connection inside a
+    /// [`DbPool::transaction`] scope, otherwise one from the pool.
+    pub fn writer(&self) -> PgWriter {
+        PgWriter {
+            pool: self.clone(),
+            tx: open_transaction(self.tx_key()),
+        }
+    }
+
+    fn tx_key(&self) -> usize {
+        Arc::as_ptr(&self.key) as usize
+    }
+
     /// Get the inner deadpool pool for use with diesel_context.
     pub fn inner(&self) -> DeadPool<AsyncPgConnection> {
         self.pool.clone()
     }
 }
 
+/// A PostgreSQL connection for one write.
+#[cfg(feature = "postgres")]
+pub struct PgWriter {
+    pool: PgPool,
+    tx: Option<TxSlot>,
+}
+
+#[cfg(feature = "postgres")]
+impl PgWriter {
+    /// Take the connection to write on.
+    pub async fn take(&mut self) -> Result<PgConn, DbError> {
+        let Some(tx) = &self.tx else {
+            return self.pool.get().await;
+        };
+        match take_tx_conn(tx) {
+            Some(TxConn::Postgres(conn)) => Ok(conn),
+            _ => Err(DbError::BrokenTransactionManager),
+        }
+    }
+
+    /// Hand the connection back: to the transaction if one is open,
+    /// otherwise to the pool.
+    pub fn put_back(&mut self, conn: PgConn) {
+        if let Some(tx) = &self.tx {
+            *tx.lock().unwrap() = Some(TxConn::Postgres(conn));
+        }
+    }
+}
+
 /// Unified database pool that supports both SQLite and PostgreSQL.
 #[derive(Clone)]
 pub enum DbPool {
@@ -157,6 +371,16 @@ impl DbPool {
         DbPool::Sqlite(SqlitePool::from_path(path))
     }
 
+    /// Run `sql` on every new SQLite read connection; see
+    /// [`SqlitePool::with_read_init`]. PostgreSQL pools are unchanged.
+    pub fn with_read_init(self, sql: String) -> Self {
+        match self {
+            DbPool::Sqlite(pool) => DbPool::Sqlite(pool.with_read_init(sql)),
+            #[cfg(feature = "postgres")]
+            other => other,
+        }
+    }
+
     /// Check if this is a SQLite backend.
     pub fn is_sqlite(&self) -> bool {
         matches!(self, DbPool::Sqlite(_))
@@ -167,6 +391,82 @@ impl DbPool {
     pub fn is_postgres(&self) -> bool {
         matches!(self, DbPool::Postgres(_))
     }
+
+    fn tx_key(&self) -> usize {
+        match self {
+            DbPool::Sqlite(pool) => pool.tx_key(),
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => pool.tx_key(),
+        }
+    }
+
+    /// Run `f` in a transaction. Every write it makes through this pool,
+    /// or another pool for the same SQLite file, commits if it returns
+    /// `Ok` and is rolled back if it returns `Err`.
+    ///
+    /// Writes join the transaction when made from the task running `f`;
+    /// a transaction started inside `f` joins the outer one. Reads use
+    /// their own connections and don't see its uncommitted writes. On
+    /// SQLite other writers wait until it ends, so keep it to the writes
+    /// themselves and don't wait on writes made from spawned tasks.
+    pub async fn transaction<T, E, F, Fut>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+        E: From<DbError>,
+    {
+        let key = self.tx_key();
+        if open_transaction(key).is_some() {
+            return f().await;
+        }
+
+        match self {
+            DbPool::Sqlite(pool) => {
+                let mut writer = pool.writer().await;
+                let mut conn = writer.take().await?;
+                begin_transaction(&mut conn).await?;
+                let slot: TxSlot = Arc::new(Mutex::new(Some(TxConn::Sqlite(conn))));
+                let result = run_in_transaction(key, slot.clone(), f).await;
+
+                let Some(TxConn::Sqlite(mut conn)) = take_tx_conn(&slot) else {
+                    // A write failed and dropped the connection, which
+                    // rolled the transaction back with it.
+                    return result.and(Err(DbError::BrokenTransactionManager.into()));
+                };
+                let ended = end_transaction(&mut conn, result.is_ok()).await;
+                if ended.is_ok() {
+                    writer.put_back(conn);
+                }
+                result.and_then(|value| ended.map(|()| value).map_err(E::from))
+            }
+            #[cfg(feature = "postgres")]
+            DbPool::Postgres(pool) => {
+                let mut conn = pool.get().await?;
+                begin_transaction(&mut conn).await?;
+                let slot: TxSlot = Arc::new(Mutex::new(Some(TxConn::Postgres(conn))));
+                let result = run_in_transaction(key, slot.clone(), f).await;
+
+                let Some(TxConn::Postgres(mut conn)) = take_tx_conn(&slot) else {
+                    return result.and(Err(DbError::BrokenTransactionManager.into()));
+                };
+                let ended = end_transaction(&mut conn, result.is_ok()).await;
+                result.and_then(|value| ended.map(|()| value).map_err(E::from))
+            }
+        }
+    }
+}
+
+/// Run `f` with the transaction in `slot` open for the pool with `key`.
+async fn run_in_transaction<T, E, F, Fut>(key: usize, slot: TxSlot, f: F) -> Result<T, E>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut open = OPEN_TRANSACTIONS
+        .try_with(|open| open.clone())
+        .unwrap_or_default();
+    open.push((key, slot));
+    OPEN_TRANSACTIONS.scope(open, f()).await
 }
 
 /// Macro for running database operations on either backend.
@@ -231,10 +531,68 @@ macro_rules! with_conn_split {
     }};
 }
 
+/// Like [`with_conn!`], for operations that write.
+///
+/// On SQLite the body runs on the database's single writer connection,
+/// waiting its turn behind other writers in this process. If the body
+/// returns early the connection is dropped and the next writer opens a
+/// fresh one. Inside a [`DbPool::transaction`] scope the body runs on the
+/// transaction's connection, on either backend.
+#[macro_export]
+macro_rules! with_write_conn {
+    ($pool:expr, $conn:ident, $body:expr) => {{
+        match &$pool {
+            $crate::repository::pool::DbPool::Sqlite(pool) => {
+                let mut writer = pool.writer().await;
+                let mut $conn = writer.take().await?;
+                let result = $body;
+                writer.put_back($conn);
+                result
+            }
+            #[cfg(feature = "postgres")]
+            $crate::repository::pool::DbPool::Postgres(pool) => {
+                let mut writer = pool.writer();
+                let mut $conn = writer.take().await?;
+                let result = $body;
+                writer.put_back($conn);
+                result
+            }
+        }
+    }};
+}
+
+/// Like [`with_conn_split!`], for operations that write. See [`with_write_conn!`].
+#[macro_export]
+macro_rules! with_write_conn_split {
+    ($pool:expr, sqlite: $sqlite_conn:ident => $sqlite_body:expr, postgres: $pg_conn:ident => $pg_body:expr) => {{
+        match &$pool {
+            $crate::repository::pool::DbPool::Sqlite(pool) => {
+                let mut writer = pool.writer().await;
+                let mut $sqlite_conn = writer.take().await?;
+                let result = $sqlite_body;
+                writer.put_back($sqlite_conn);
+                result
+            }
+            #[cfg(feature = "postgres")]
+            $crate::repository::pool::DbPool::Postgres(pool) => {
+                let mut writer = pool.writer();
+                let mut $pg_conn = writer.take().await?;
+                let result = $pg_body;
+                writer.put_back($pg_conn);
+                result
+            }
+        }
+    }};
+}
+
 #[allow(unused_imports)]
 pub use with_conn;
 #[allow(unused_imports)]
 pub use with_conn_split;
+#[allow(unused_imports)]
+pub use with_write_conn;
+#[allow(unused_imports)]
+pub use with_write_conn_split;
 
 /// Build a SQL string from a sea-query statement using the correct backend.
 ///
@@ -279,4 +637,104 @@ mod tests {
                 .is_postgres());
         }
     }
+
+    async fn insert_row(pool: &DbPool, n: i32) -> Result<usize, DbError> {
+        use diesel_async::RunQueryDsl;
+        with_write_conn!(pool, conn, {
+            diesel::sql_query("INSERT INTO t (n) VALUES (?)")
+                .bind::<diesel::sql_types::Integer, _>(n)
+                .execute(&mut conn)
+                .await
+        })
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_are_queued() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = DbPool::sqlite_from_path(&dir.path().join("test.db"));
+        let DbPool::Sqlite(sqlite) = &pool else {
+            unreachable!()
+        };
+        sqlite
+            .get()
+            .await
+            .unwrap()
+            .batch_execute("CREATE TABLE t (n INTEGER NOT NULL)")
+            .await
+            .unwrap();
+
+        let tasks: Vec<_> = (0..32)
+            .map(|n| {
+                let pool = pool.clone();
+                tokio::spawn(async move { insert_row(&pool, n).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), 1);
+        }
+
+        // Any pool for the same file shares the writer connection
+        let other = SqlitePool::from_path(&dir.path().join("test.db"));
+        assert!(Arc::ptr_eq(&other.writer, &sqlite.writer));
+    }
+
+    #[tokio::test]
+    async fn test_transaction_commits_or_rolls_back() {
+        use diesel_async::RunQueryDsl;
+
+        #[derive(diesel::QueryableByName)]
+        struct Count {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            count: i64,
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let pool = DbPool::sqlite_from_path(&dir.path().join("test.db"));
+        let DbPool::Sqlite(sqlite) = &pool else {
+            unreachable!()
+        };
+        let mut reader = sqlite.get().await.unwrap();
+        reader
+            .batch_execute("CREATE TABLE t (n INTEGER NOT NULL)")
+            .await
+            .unwrap();
+
+        pool.transaction(|| async {
+            insert_row(&pool, 1).await?;
+            // Nested scopes join the outer transaction
+            pool.transaction(|| insert_row(&pool, 2)).await
+        })
+        .await
+        .unwrap();
+
+        let failed: Result<(), DbError> = pool
+            .transaction(|| async {
+                insert_row(&pool, 3).await?;
+                Err(DbError::RollbackTransaction)
+            })
+            .await;
+        assert!(failed.is_err());
+
+        // A failed statement rolls back the writes before it
+        let failed = pool
+            .transaction(|| async {
+                insert_row(&pool, 4).await?;
+                with_write_conn!(pool, conn, {
+                    diesel::sql_query("INSERT INTO missing (n) VALUES (1)")
+                        .execute(&mut conn)
+                        .await
+                })
+            })
+            .await;
+        assert!(failed.is_err());
+
+        // The writer still works afterwards
+        assert_eq!(insert_row(&pool, 5).await.unwrap(), 1);
+
+        let rows: Vec<Count> = diesel::sql_query("SELECT COUNT(*) AS count FROM t")
+            .load(&mut reader)
+            .await
+            .unwrap();
+        assert_eq!(rows[0].count, 3);
+    }
 }
@@ -33,6 +33,8 @@ pub enum DocumentPages {
     OcrStatus,
     CreatedAt,
     UpdatedAt,
+    OcrQuality,
+    CorrectedAt,
 }
 
 #[derive(Iden)]
@@ -50,6 +52,9 @@ pub enum PageOcrResults {
     CreatedAt,
     Model,
     ImageHash,
+    ToolVersion,
+    Language,
+    Dpi,
 }
 
 #[derive(Iden)]
@@ -70,6 +75,8 @@ pub enum DocumentVersions {
     ArchiveSnapshotId,
     EarliestArchivedAt,
     DedupIndex,
+    ChecksumVerification,
+    DeclaredMimeType,
 }
 
 #[derive(Iden)]
@@ -113,6 +120,7 @@ pub enum Sources {
     Metadata,
     CreatedAt,
     LastScraped,
+    AgencyId,
 }
 
 #[derive(Iden)]
@@ -0,0 +1,572 @@
+//! Routing for sharded SQLite archives.
+//!
+//! A shard is a SQLite database with the full schema that holds the
+//! documents and crawl state of some sources (see
+//! [`crate::config::ShardConfig`]). Commands working on one source open its
+//! shard directly. Everything else reads through the main database with the
+//! shards attached: each sharded table is shadowed by a temporary view that
+//! unions the main table with the shards' copies, so existing queries see
+//! the whole archive unchanged. Document writes through such a context are
+//! routed to the shard holding the document; other writes go to the main
+//! database.
+//!
+//! Integer IDs must not collide across shards, so each shard's
+//! AUTOINCREMENT counters start at its index times [`SHARD_ID_SPACING`].
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{Connection, OpenFlags};
+
+use super::pool::DieselError;
+
+/// Integer IDs reserved for each database: the main database gets the
+/// first range, shard `n` starts at `n * SHARD_ID_SPACING`.
+pub const SHARD_ID_SPACING: i64 = 100_000_000;
+
+/// A configured shard with its database file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard {
+    /// Position in the config, starting at 1.
+    pub index: usize,
+    pub name: String,
+    pub sources: Vec<String>,
+    pub path: PathBuf,
+}
+
+impl Shard {
+    /// Schema name the shard is attached under.
+    fn alias(&self) -> String {
+        format!("shard_{}", self.name)
+    }
+
+    /// Whether `source_id` lives in this shard.
+    pub fn has_source(&self, source_id: &str) -> bool {
+        self.sources.iter().any(|s| s == source_id)
+    }
+}
+
+/// How rows of a sharded table belong to a source.
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    /// A `source_id` column.
+    Source,
+    /// A column holding the document ID.
+    Document(&'static str),
+    /// A column referencing `(parent table)`, whose rows have a
+    /// `document_id`.
+    Child(&'static str, &'static str),
+    /// Rows stay in the database that wrote them, by a trigger or the
+    /// crawler; they are read across shards but never moved.
+    Unowned,
+}
+
+/// Tables that live in shards, parents before children.
+const SHARDED_TABLES: &[(&str, Owner)] = &[
+    ("crawl_config", Owner::Source),
+    ("crawl_urls", Owner::Source),
+    ("crawl_requests", Owner::Source),
+    ("documents", Owner::Source),
+    ("document_versions", Owner::Document("document_id")),
+    (
+        "archive_checks",
+        Owner::Child("document_version_id", "document_versions"),
+    ),
+    ("document_pages", Owner::Document("document_id")),
+    (
+        "page_ocr_results",
+        Owner::Child("page_id", "document_pages"),
+    ),
+    ("document_analysis_results", Owner::Document("document_id")),
+    ("document_annotations", Owner::Document("document_id")),
+    ("document_entities", Owner::Document("document_id")),
+    ("document_exemptions", Owner::Document("document_id")),
+    ("document_pdf_metadata", Owner::Document("document_id")),
+    ("document_locations", Owner::Document("document_id")),
+    ("document_relations", Owner::Document("source_document_id")),
+    ("document_id_redirects", Owner::Document("new_id")),
+    ("derived_artifacts", Owner::Document("document_id")),
+    ("virtual_files", Owner::Document("document_id")),
+    ("curation_log", Owner::Document("document_id")),
+    ("archive_snapshots", Owner::Unowned),
+    ("document_counts", Owner::Unowned),
+];
+
+fn ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn shard_error(e: impl std::fmt::Display) -> DieselError {
+    DieselError::QueryBuilderError(e.to_string().into())
+}
+
+/// File path of a SQLite database URL.
+pub fn sqlite_path(database_url: &str) -> PathBuf {
+    PathBuf::from(database_url.strip_prefix("sqlite:").unwrap_or(database_url))
+}
+
+/// Columns of `table` in `schema`, in table order; empty if there is no
+/// such table.
+fn table_columns(conn: &Connection, schema: &str, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT name FROM pragma_table_info({}, {})",
+        literal(table),
+        literal(schema)
+    ))?;
+    let columns = stmt.query_map([], |row| row.get(0))?;
+    columns.collect()
+}
+
+/// `columns` selected from `schema.table`, with NULL for any the table
+/// lacks (a shard a migration behind the main database).
+fn select_columns(
+    conn: &Connection,
+    schema: &str,
+    table: &str,
+    columns: &[String],
+) -> rusqlite::Result<String> {
+    let present = table_columns(conn, schema, table)?;
+    let list: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            if present.contains(c) {
+                ident(c)
+            } else {
+                format!("NULL AS {}", ident(c))
+            }
+        })
+        .collect();
+    Ok(format!(
+        "SELECT {} FROM {}.{}",
+        list.join(", "),
+        ident(schema),
+        ident(table)
+    ))
+}
+
+/// SQL that attaches the shards with a database file to a connection on
+/// `main` and shadows each sharded table with a view across all of them.
+/// Empty when no shard has a database yet.
+pub fn unified_read_sql(main: &Path, shards: &[Shard]) -> rusqlite::Result<String> {
+    let shards: Vec<&Shard> = shards.iter().filter(|s| s.path.exists()).collect();
+    if shards.is_empty() || !main.exists() {
+        return Ok(String::new());
+    }
+
+    let conn = Connection::open_with_flags(main, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut sql = String::new();
+    for shard in &shards {
+        let attach = format!(
+            "ATTACH DATABASE {} AS {};",
+            literal(&shard.path.display().to_string()),
+            ident(&shard.alias())
+        );
+        conn.execute_batch(&attach)?;
+        sql.push_str(&attach);
+        sql.push('\n');
+    }
+
+    for (table, _) in SHARDED_TABLES {
+        let columns = table_columns(&conn, "main", table)?;
+        if columns.is_empty() {
+            continue;
+        }
+        let mut selects = vec![select_columns(&conn, "main", table, &columns)?];
+        for shard in &shards {
+            selects.push(select_columns(&conn, &shard.alias(), table, &columns)?);
+        }
+        sql.push_str(&format!(
+            "CREATE TEMP VIEW {} AS {};\n",
+            ident(table),
+            selects.join(" UNION ALL ")
+        ));
+    }
+
+    // Category counts are kept per database by triggers; add them up
+    let schemas: Vec<String> = std::iter::once("main".to_string())
+        .chain(shards.iter().map(|s| s.alias()))
+        .collect();
+    for schema in &schemas {
+        if table_columns(&conn, schema, "file_categories")?.is_empty() {
+            return Ok(sql);
+        }
+    }
+    let categories: Vec<String> = schemas
+        .iter()
+        .map(|schema| {
+            format!(
+                "SELECT id, description, doc_count FROM {}.file_categories",
+                ident(schema)
+            )
+        })
+        .collect();
+    sql.push_str(&format!(
+        "CREATE TEMP VIEW file_categories AS \
+         SELECT id, MAX(description) AS description, SUM(doc_count) AS doc_count \
+         FROM ({}) GROUP BY id;\n",
+        categories.join(" UNION ALL ")
+    ));
+    Ok(sql)
+}
+
+/// Start the AUTOINCREMENT counters of a fresh shard in its own ID range.
+/// Counters already in use are left alone.
+fn seed_id_ranges(conn: &Connection, shard: &Shard) -> rusqlite::Result<()> {
+    let start = shard.index as i64 * SHARD_ID_SPACING;
+    for (table, _) in SHARDED_TABLES {
+        conn.execute(
+            "INSERT INTO sqlite_sequence (name, seq) SELECT ?1, ?2 \
+             WHERE EXISTS (SELECT 1 FROM sqlite_master \
+                           WHERE type = 'table' AND name = ?1 AND sql LIKE '%AUTOINCREMENT%') \
+             AND NOT EXISTS (SELECT 1 FROM sqlite_sequence WHERE name = ?1)",
+            rusqlite::params![table, start],
+        )?;
+    }
+    Ok(())
+}
+
+/// Create and migrate each shard's database.
+pub async fn prepare_shards(shards: &[Shard]) -> Result<(), DieselError> {
+    for shard in shards {
+        if let Some(parent) = shard.path.parent() {
+            std::fs::create_dir_all(parent).map_err(shard_error)?;
+        }
+        super::migrations::run_migrations(&format!("sqlite:{}", shard.path.display()), false)
+            .await?;
+
+        let shard = shard.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = Connection::open(&shard.path)?;
+            seed_id_ranges(&conn, &shard)
+        })
+        .await
+        .map_err(shard_error)?
+        .map_err(shard_error)?;
+    }
+    Ok(())
+}
+
+/// `WHERE` clause selecting the rows of `owner` that belong to `sources`,
+/// evaluated against the main database.
+fn owned_by(owner: Owner, sources: &str) -> Option<String> {
+    let documents = format!(
+        "SELECT id FROM main.documents WHERE source_id IN ({})",
+        sources
+    );
+    match owner {
+        Owner::Source => Some(format!("source_id IN ({})", sources)),
+        Owner::Document(column) => Some(format!("{} IN ({})", ident(column), documents)),
+        Owner::Child(column, parent) => Some(format!(
+            "{} IN (SELECT id FROM main.{} WHERE document_id IN ({}))",
+            ident(column),
+            ident(parent),
+            documents
+        )),
+        Owner::Unowned => None,
+    }
+}
+
+/// Move the rows of `shard`'s sources from the main database at `main`
+/// into the shard, which must already be migrated. Returns the rows moved
+/// per table, skipping tables with none.
+///
+/// Blocks; call from a blocking context. Rows are copied before they are
+/// deleted, and rows already in the shard are kept, so an interrupted
+/// split can be run again.
+pub fn split_shard(main: &Path, shard: &Shard) -> rusqlite::Result<Vec<(String, usize)>> {
+    if shard.sources.is_empty() {
+        return Ok(Vec::new());
+    }
+    let sources = shard
+        .sources
+        .iter()
+        .map(|s| literal(s))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let conn = Connection::open(main)?;
+    conn.busy_timeout(std::time::Duration::from_secs(30))?;
+    conn.execute_batch(&format!(
+        "ATTACH DATABASE {} AS shard;",
+        literal(&shard.path.display().to_string())
+    ))?;
+
+    // Each table with its filter and the columns both databases have
+    let mut owned: Vec<(&str, String, Vec<String>)> = Vec::new();
+    for (table, owner) in SHARDED_TABLES {
+        let Some(filter) = owned_by(*owner, &sources) else {
+            continue;
+        };
+        let shard_columns = table_columns(&conn, "shard", table)?;
+        let columns: Vec<String> = table_columns(&conn, "main", table)?
+            .into_iter()
+            .filter(|c| shard_columns.contains(c))
+            .map(|c| ident(&c))
+            .collect();
+        if !columns.is_empty() {
+            owned.push((*table, filter, columns));
+        }
+    }
+
+    conn.execute_batch("BEGIN IMMEDIATE")?;
+    let moved = (|| -> rusqlite::Result<Vec<(String, usize)>> {
+        for (table, filter, columns) in &owned {
+            conn.execute(
+                &format!(
+                    "INSERT OR IGNORE INTO shard.{table} ({columns}) \
+                     SELECT {columns} FROM main.{table} WHERE {filter}",
+                    table = ident(table),
+                    columns = columns.join(", "),
+                    filter = filter
+                ),
+                [],
+            )?;
+        }
+        // Children first, so their filters still find the parent rows
+        let mut moved = Vec::new();
+        for (table, filter, _) in owned.iter().rev() {
+            let deleted = conn.execute(
+                &format!("DELETE FROM main.{} WHERE {}", ident(table), filter),
+                [],
+            )?;
+            if deleted > 0 {
+                moved.push((table.to_string(), deleted));
+            }
+        }
+        moved.reverse();
+        Ok(moved)
+    })();
+
+    match moved {
+        Ok(moved) => {
+            conn.execute_batch("COMMIT")?;
+            Ok(moved)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use diesel_async::RunQueryDsl;
+
+    #[derive(diesel::QueryableByName)]
+    struct Row {
+        #[diesel(sql_type = diesel::sql_types::Text)]
+        id: String,
+        #[diesel(sql_type = diesel::sql_types::BigInt)]
+        pages: i64,
+    }
+
+    fn insert_document(conn: &Connection, id: &str, source_id: &str) {
+        conn.execute_batch(&format!(
+            "INSERT INTO documents (id, source_id, title, source_url, status, \
+                                    metadata, created_at, updated_at) \
+             VALUES ('{id}', '{source_id}', '{id}', 'https://example.gov/{id}', 'pending', \
+                     '{{}}', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z'); \
+             INSERT INTO document_pages (document_id, version_id, page_number, created_at, updated_at) \
+             VALUES ('{id}', 1, 1, '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');"
+        ))
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_split_and_unified_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("foia.db");
+        migrations::run_migrations(&format!("sqlite:{}", main.display()), false)
+            .await
+            .unwrap();
+        let shard = Shard {
+            index: 1,
+            name: "big".to_string(),
+            sources: vec!["fbi".to_string()],
+            path: dir.path().join("shards").join("big.db"),
+        };
+        prepare_shards(std::slice::from_ref(&shard)).await.unwrap();
+
+        {
+            let conn = Connection::open(&main).unwrap();
+            insert_document(&conn, "a", "cia");
+            insert_document(&conn, "b", "fbi");
+        }
+        let moved = split_shard(&main, &shard).unwrap();
+        assert!(moved.contains(&("documents".to_string(), 1)));
+        assert!(moved.contains(&("document_pages".to_string(), 1)));
+
+        // New rows in the shard take IDs from its own range
+        let conn = Connection::open(&shard.path).unwrap();
+        insert_document(&conn, "c", "fbi");
+        let page_id: i64 = conn
+            .query_row(
+                "SELECT id FROM document_pages WHERE document_id = 'c'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(page_id > SHARD_ID_SPACING);
+
+        let sql = unified_read_sql(&main, std::slice::from_ref(&shard)).unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&main).unwrap();
+        let pool = ctx.pool().clone().with_read_init(sql);
+        let crate::repository::DbPool::Sqlite(sqlite) = &pool else {
+            unreachable!()
+        };
+        let mut conn = sqlite.get().await.unwrap();
+        let rows: Vec<Row> = diesel::sql_query(
+            "SELECT d.id, COUNT(p.id) AS pages FROM documents d \
+             JOIN document_pages p ON p.document_id = d.id GROUP BY d.id ORDER BY d.id",
+        )
+        .load(&mut conn)
+        .await
+        .unwrap();
+        let rows: Vec<(String, i64)> = rows.into_iter().map(|r| (r.id, r.pages)).collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("a".to_string(), 1),
+                ("b".to_string(), 1),
+                ("c".to_string(), 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unified_writes_go_to_shard() {
+        use crate::config::{Settings, ShardConfig};
+        use crate::models::{Document, DocumentStatus, DocumentVersion};
+
+        let dir = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            data_dir: dir.path().to_path_buf(),
+            shards: vec![ShardConfig {
+                name: "big".to_string(),
+                sources: vec!["fbi".to_string()],
+                database: None,
+            }],
+            ..Default::default()
+        };
+        migrations::run_migrations(&settings.database_url(), false)
+            .await
+            .unwrap();
+        let shard = settings.shard_for("fbi").unwrap();
+        prepare_shards(std::slice::from_ref(&shard)).await.unwrap();
+
+        let repos = settings.repositories().unwrap();
+        for (id, source_id) in [("memo", "fbi"), ("cable", "cia")] {
+            let version = DocumentVersion::new(id.as_bytes(), "application/pdf".to_string(), None);
+            let doc = Document::new(
+                id.to_string(),
+                source_id.to_string(),
+                id.to_string(),
+                format!("https://example.gov/{id}.pdf"),
+                version,
+                serde_json::json!({}),
+            );
+            repos.documents.save_with_versions(&doc).await.unwrap();
+        }
+        repos
+            .documents
+            .update_status("memo", DocumentStatus::Indexed)
+            .await
+            .unwrap();
+        assert_eq!(
+            repos.documents.get("memo").await.unwrap().unwrap().status,
+            DocumentStatus::Indexed
+        );
+
+        let status = |path: &Path, id: &str| -> Option<String> {
+            Connection::open(path)
+                .unwrap()
+                .query_row("SELECT status FROM documents WHERE id = ?1", [id], |row| {
+                    row.get(0)
+                })
+                .ok()
+        };
+        let main = sqlite_path(&settings.database_url());
+        assert_eq!(status(&shard.path, "memo").as_deref(), Some("indexed"));
+        assert_eq!(status(&main, "memo"), None);
+        assert_eq!(status(&main, "cable").as_deref(), Some("downloaded"));
+        assert_eq!(status(&shard.path, "cable"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ocr_artifacts_go_stale_in_shard() {
+        use crate::config::{Settings, ShardConfig};
+        use crate::models::{
+            ArtifactKind, Document, DocumentPage, DocumentVersion, NewDerivedArtifact,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            data_dir: dir.path().to_path_buf(),
+            shards: vec![ShardConfig {
+                name: "big".to_string(),
+                sources: vec!["fbi".to_string()],
+                database: None,
+            }],
+            ..Default::default()
+        };
+        migrations::run_migrations(&settings.database_url(), false)
+            .await
+            .unwrap();
+        let shard = settings.shard_for("fbi").unwrap();
+        prepare_shards(std::slice::from_ref(&shard)).await.unwrap();
+
+        let repos = settings.repositories().unwrap();
+        let docs = &repos.documents;
+        let version = DocumentVersion::new(b"memo", "application/pdf".to_string(), None);
+        let doc = Document::new(
+            "memo".to_string(),
+            "fbi".to_string(),
+            "memo".to_string(),
+            "https://example.gov/memo.pdf".to_string(),
+            version,
+            serde_json::json!({}),
+        );
+        docs.save_with_versions(&doc).await.unwrap();
+        let version_id = docs.get_current_version_id("memo").await.unwrap().unwrap();
+        docs.save_page(&DocumentPage::new("memo".to_string(), version_id, 1))
+            .await
+            .unwrap();
+        docs.save_artifact(&NewDerivedArtifact {
+            document_id: "memo".to_string(),
+            version_id,
+            kind: ArtifactKind::SearchablePdf,
+            name: "searchable.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            file_path: "derived/searchable.pdf".to_string(),
+            file_size: 2048,
+            content_hash: "1234".to_string(),
+            analysis_result_id: None,
+            metadata: None,
+        })
+        .await
+        .unwrap();
+
+        let status = || -> String {
+            Connection::open(&shard.path)
+                .unwrap()
+                .query_row(
+                    "SELECT status FROM derived_artifacts WHERE document_id = 'memo'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(status(), "ready");
+        docs.requeue_version_ocr("memo", version_id as i32)
+            .await
+            .unwrap();
+        assert_eq!(status(), "stale");
+    }
+}
@@ -8,7 +8,7 @@ use super::models::SourceRecord;
 use super::pool::{DbError, DbPool};
 use crate::models::Source;
 use crate::schema::sources;
-use crate::with_conn;
+use crate::{with_conn, with_write_conn};
 
 /// Source repository.
 #[derive(Clone)]
@@ -93,7 +93,7 @@ impl SourceRepository {
 
         let sql = build_sql(&self.pool, &stmt);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::sql_query(&sql)
                 .bind::<diesel::sql_types::Text, _>(&source.id)
                 .bind::<diesel::sql_types::Text, _>(&source_type)
@@ -113,7 +113,7 @@ impl SourceRepository {
     /// Delete a source.
     #[allow(dead_code)]
     pub async fn delete(&self, id: &str) -> Result<bool, DbError> {
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let rows = diesel::delete(sources::table.find(id))
                 .execute(&mut conn)
                 .await?;
@@ -143,7 +143,7 @@ impl SourceRepository {
     ) -> Result<(), DbError> {
         let ts = timestamp.to_rfc3339();
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             diesel::update(sources::table.find(id))
                 .set(sources::last_scraped.eq(Some(&ts)))
                 .execute(&mut conn)
@@ -185,7 +185,7 @@ impl SourceRepository {
         let sql_crawl_config = build_sql(&self.pool, &update_crawl_config);
         let sql_sources = build_sql(&self.pool, &update_sources);
 
-        with_conn!(self.pool, conn, {
+        with_write_conn!(self.pool, conn, {
             let docs_updated = diesel::sql_query(&sql_docs).execute(&mut conn).await?;
             let crawls_updated = diesel::sql_query(&sql_crawl_urls)
                 .execute(&mut conn)
@@ -1,6 +1,61 @@
 // @generated automatically by Diesel CLI.
 // Manually corrected to match actual database schema.
 
+diesel::table! {
+    agencies (id) {
+        id -> Text,
+        name -> Text,
+        abbreviation -> Nullable<Text>,
+        jurisdiction -> Text,
+        level -> Text,
+        parent_id -> Nullable<Text>,
+        foia_email -> Nullable<Text>,
+        foia_phone -> Nullable<Text>,
+        foia_address -> Nullable<Text>,
+        portal_urls -> Text,
+        website -> Nullable<Text>,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    api_keys (id) {
+        id -> Integer,
+        name -> Text,
+        prefix -> Text,
+        key_hash -> Text,
+        rate_limit -> Nullable<Integer>,
+        created_at -> Text,
+        last_used_at -> Nullable<Text>,
+        revoked_at -> Nullable<Text>,
+        role -> Text,
+    }
+}
+
+diesel::table! {
+    api_usage (key_id, day) {
+        key_id -> Integer,
+        day -> Text,
+        requests -> BigInt,
+        rejected -> BigInt,
+    }
+}
+
+diesel::table! {
+    broker_messages (id) {
+        id -> Integer,
+        queue -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        available_at -> Text,
+        claimed_until -> Nullable<Text>,
+        last_error -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
 diesel::table! {
     configuration_history (uuid) {
         uuid -> Text,
@@ -60,6 +115,47 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    curation_log (id) {
+        id -> Integer,
+        action -> Text,
+        document_id -> Text,
+        related_ids -> Text,
+        details -> Text,
+        actor -> Nullable<Text>,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    derived_artifacts (id) {
+        id -> Integer,
+        document_id -> Text,
+        version_id -> Integer,
+        kind -> Text,
+        name -> Text,
+        mime_type -> Text,
+        file_path -> Text,
+        file_size -> BigInt,
+        content_hash -> Text,
+        analysis_result_id -> Nullable<Integer>,
+        status -> Text,
+        metadata -> Nullable<Text>,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    document_access (document_id, day, kind, referrer) {
+        document_id -> Text,
+        day -> Text,
+        kind -> Text,
+        referrer -> Text,
+        count -> BigInt,
+    }
+}
+
 diesel::table! {
     document_entities (id) {
         id -> Integer,
@@ -73,6 +169,69 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    document_exemptions (document_id, code) {
+        document_id -> Text,
+        code -> Text,
+        citations -> Integer,
+    }
+}
+
+diesel::table! {
+    document_pdf_metadata (document_id) {
+        document_id -> Text,
+        author -> Nullable<Text>,
+        creator -> Nullable<Text>,
+        producer -> Nullable<Text>,
+        created_at -> Nullable<Text>,
+        modified_at -> Nullable<Text>,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    document_flags (id) {
+        id -> Integer,
+        document_id -> Text,
+        reason -> Text,
+        page -> Nullable<Integer>,
+        note -> Text,
+        status -> Text,
+        created_at -> Text,
+        resolved_at -> Nullable<Text>,
+        resolution -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    document_id_redirects (old_id) {
+        old_id -> Text,
+        new_id -> Text,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    document_locations (document_id) {
+        document_id -> Text,
+        place -> Text,
+        latitude -> Double,
+        longitude -> Double,
+        mentions -> Integer,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    document_relations (source_document_id, target_document_id, kind) {
+        source_document_id -> Text,
+        target_document_id -> Text,
+        kind -> Text,
+        evidence -> Text,
+        created_at -> Text,
+    }
+}
+
 diesel::table! {
     document_analysis_results (id) {
         id -> Integer,
@@ -104,6 +263,69 @@ diesel::table! {
         ocr_status -> Text,
         created_at -> Text,
         updated_at -> Text,
+        ocr_quality -> Nullable<Float>,
+        corrected_at -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    foia_requests (id) {
+        id -> Text,
+        agency -> Text,
+        subject -> Text,
+        request_text -> Text,
+        tracking_number -> Nullable<Text>,
+        source_id -> Nullable<Text>,
+        status -> Text,
+        filed_on -> Text,
+        acknowledged_on -> Nullable<Text>,
+        fulfilled_on -> Nullable<Text>,
+        appealed_on -> Nullable<Text>,
+        notes -> Nullable<Text>,
+        created_at -> Text,
+        updated_at -> Text,
+        jurisdiction -> Nullable<Text>,
+        reminded_on -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    foia_request_documents (request_id, document_id) {
+        request_id -> Text,
+        document_id -> Text,
+        linked_at -> Text,
+    }
+}
+
+diesel::table! {
+    jobs (id) {
+        id -> Text,
+        kind -> Text,
+        params -> Text,
+        status -> Text,
+        progress -> BigInt,
+        total -> Nullable<BigInt>,
+        message -> Nullable<Text>,
+        error -> Nullable<Text>,
+        result -> Nullable<Text>,
+        cancel_requested -> Integer,
+        created_at -> Text,
+        started_at -> Nullable<Text>,
+        finished_at -> Nullable<Text>,
+        updated_at -> Text,
+    }
+}
+
+diesel::table! {
+    llm_usage (id) {
+        id -> Integer,
+        provider -> Text,
+        model -> Text,
+        operation -> Text,
+        prompt_tokens -> BigInt,
+        completion_tokens -> BigInt,
+        cost_usd -> Nullable<Double>,
+        created_at -> Text,
     }
 }
 
@@ -122,6 +344,9 @@ diesel::table! {
         created_at -> Text,
         model -> Nullable<Text>,
         image_hash -> Nullable<Text>,
+        tool_version -> Nullable<Text>,
+        language -> Nullable<Text>,
+        dpi -> Nullable<Integer>,
     }
 }
 
@@ -142,6 +367,8 @@ diesel::table! {
         archive_snapshot_id -> Nullable<Integer>,
         earliest_archived_at -> Nullable<Text>,
         dedup_index -> Nullable<Integer>,
+        checksum_verification -> Nullable<Text>,
+        declared_mime_type -> Nullable<Text>,
     }
 }
 
@@ -194,6 +421,13 @@ diesel::table! {
         manual_date -> Nullable<Text>,
         discovery_method -> Text,
         category_id -> Nullable<Text>,
+        record_type -> Nullable<Text>,
+        record_type_confidence -> Nullable<Float>,
+        document_date -> Nullable<Text>,
+        originating_agency -> Nullable<Text>,
+        originating_office -> Nullable<Text>,
+        case_number -> Nullable<Text>,
+        classification_markings -> Nullable<Text>,
     }
 }
 
@@ -236,6 +470,33 @@ diesel::table! {
         metadata -> Text,
         created_at -> Text,
         last_scraped -> Nullable<Text>,
+        agency_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    saved_view_alerts (view_name, email, document_id) {
+        view_name -> Text,
+        email -> Text,
+        document_id -> Text,
+        alerted_at -> Text,
+    }
+}
+
+diesel::table! {
+    saved_view_subscriptions (view_name, email) {
+        view_name -> Text,
+        email -> Text,
+        created_at -> Text,
+    }
+}
+
+diesel::table! {
+    saved_views (name) {
+        name -> Text,
+        query -> Text,
+        created_at -> Text,
+        updated_at -> Text,
     }
 }
 
@@ -266,8 +527,14 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(derived_artifacts -> documents (document_id));
+diesel::joinable!(derived_artifacts -> document_versions (version_id));
+diesel::joinable!(derived_artifacts -> document_analysis_results (analysis_result_id));
 diesel::joinable!(document_entities -> documents (document_id));
+diesel::joinable!(document_exemptions -> documents (document_id));
+diesel::joinable!(document_locations -> documents (document_id));
 diesel::joinable!(document_pages -> documents (document_id));
+diesel::joinable!(document_pdf_metadata -> documents (document_id));
 diesel::joinable!(document_versions -> documents (document_id));
 diesel::joinable!(document_versions -> archive_snapshots (archive_snapshot_id));
 diesel::joinable!(documents -> sources (source_id));
@@ -281,19 +548,39 @@ diesel::joinable!(document_analysis_results -> document_versions (version_id));
 diesel::joinable!(archive_checks -> document_versions (document_version_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    agencies,
+    api_keys,
+    api_usage,
     archive_checks,
     archive_snapshots,
+    broker_messages,
     configuration_history,
     crawl_config,
     crawl_requests,
     crawl_urls,
+    curation_log,
+    derived_artifacts,
+    document_access,
     document_analysis_results,
     document_entities,
+    document_exemptions,
+    document_flags,
+    document_id_redirects,
+    document_locations,
     document_pages,
+    document_pdf_metadata,
+    document_relations,
     document_versions,
     documents,
+    foia_request_documents,
+    foia_requests,
+    jobs,
+    llm_usage,
     page_ocr_results,
     rate_limit_state,
+    saved_view_alerts,
+    saved_view_subscriptions,
+    saved_views,
     scraper_configs,
     service_status,
     sources,
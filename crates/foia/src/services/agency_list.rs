@@ -0,0 +1,381 @@
+//! Agency lists for seeding the agency registry.
+//!
+//! A list is CSV with a header row naming a `name` column, a JSON array of
+//! objects with the same fields, or FOIA.gov's published agency components
+//! (`https://api.foia.gov/api/agency_components`, JSON:API), whose parent
+//! departments are taken from the `included` agencies. Optional fields are
+//! `id`, `abbreviation`, `jurisdiction`, `level`, `parent`, `foia_email`,
+//! `foia_phone`, `foia_address`, `portal_urls` (separated by `;` in CSV) and
+//! `website`. `parent` is a registry ID or an abbreviation.
+
+use std::path::Path;
+
+use serde_json::Value;
+use thiserror::Error;
+
+use super::url_list::split_csv;
+use crate::models::{tag_slug, Agency, AgencyLevel};
+
+#[derive(Debug, Error)]
+pub enum AgencyListError {
+    #[error("CSV has no header row")]
+    MissingHeader,
+    #[error("CSV header has no 'name' column")]
+    MissingNameColumn,
+    #[error("Invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("JSON must be an array of agencies or a FOIA.gov agency_components document")]
+    UnknownJson,
+    #[error("Entry {entry}: {message}")]
+    Invalid { entry: usize, message: String },
+}
+
+/// File layout of an agency list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgencyListFormat {
+    Csv,
+    Json,
+}
+
+impl AgencyListFormat {
+    /// JSON for `.json` files, CSV otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("json") => Self::Json,
+            _ => Self::Csv,
+        }
+    }
+}
+
+fn optional(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+/// Build an agency from its fields, looked up by name.
+fn agency<'a>(
+    entry: usize,
+    field: impl Fn(&str) -> Option<&'a str>,
+    portal_urls: Vec<String>,
+) -> Result<Agency, AgencyListError> {
+    let invalid = |message: String| AgencyListError::Invalid { entry, message };
+    let name = optional(field("name")).ok_or_else(|| invalid("missing name".to_string()))?;
+    let mut agency = Agency::new(name, optional(field("abbreviation")));
+    if let Some(id) = optional(field("id")) {
+        agency.id = tag_slug(&id);
+    }
+    if agency.id.is_empty() {
+        return Err(invalid(format!("no usable ID for '{}'", agency.name)));
+    }
+    if let Some(level) = optional(field("level")) {
+        agency.level = AgencyLevel::from_str(&level)
+            .ok_or_else(|| invalid(format!("unknown level '{}'", level)))?;
+    }
+    if let Some(jurisdiction) = optional(field("jurisdiction")) {
+        agency.jurisdiction = jurisdiction;
+    }
+    agency.parent_id = optional(field("parent")).map(|p| tag_slug(&p));
+    agency.foia_email = optional(field("foia_email"));
+    agency.foia_phone = optional(field("foia_phone"));
+    agency.foia_address = optional(field("foia_address"));
+    agency.website = optional(field("website"));
+    agency.portal_urls = portal_urls;
+    Ok(agency)
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Agency>, AgencyListError> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (_, header) = lines.next().ok_or(AgencyListError::MissingHeader)?;
+    let columns: Vec<String> = split_csv(header)
+        .into_iter()
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    if !columns.iter().any(|c| c == "name") {
+        return Err(AgencyListError::MissingNameColumn);
+    }
+
+    lines
+        .map(|(n, line)| {
+            let fields = split_csv(line);
+            let field = |name: &str| {
+                columns
+                    .iter()
+                    .position(|c| c == name)
+                    .and_then(|i| fields.get(i))
+                    .map(String::as_str)
+            };
+            let portal_urls = field("portal_urls")
+                .unwrap_or_default()
+                .split(';')
+                .filter_map(|u| optional(Some(u)))
+                .collect();
+            agency(n, field, portal_urls)
+        })
+        .collect()
+}
+
+fn parse_objects(items: &[Value]) -> Result<Vec<Agency>, AgencyListError> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let portal_urls = match item.get("portal_urls") {
+                Some(Value::Array(urls)) => {
+                    urls.iter().filter_map(|u| optional(u.as_str())).collect()
+                }
+                Some(Value::String(url)) => optional(Some(url)).into_iter().collect(),
+                _ => Vec::new(),
+            };
+            agency(i + 1, |name| item.get(name)?.as_str(), portal_urls)
+        })
+        .collect()
+}
+
+/// A FOIA.gov field that may be a string or a `{ "uri": ... }` link.
+fn link(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => optional(Some(s)),
+        Value::Object(o) => optional(o.get("uri").and_then(Value::as_str)),
+        _ => None,
+    }
+}
+
+/// First item of a FOIA.gov field that may be a value or a list of them.
+fn first(value: Option<&Value>) -> Option<&Value> {
+    match value? {
+        Value::Array(items) => items.first(),
+        v => Some(v),
+    }
+}
+
+/// One line from a FOIA.gov address, skipping empty parts.
+fn address(value: Option<&Value>) -> Option<String> {
+    let address = value?.as_object()?;
+    let part = |key: &str| optional(address.get(key).and_then(Value::as_str));
+    let locality = [
+        part("locality"),
+        part("administrative_area"),
+        part("postal_code"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" ");
+    let parts: Vec<String> = [
+        part("organization"),
+        part("address_line1"),
+        part("address_line2"),
+        optional(Some(&locality)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+fn parse_foia_gov(data: &[Value], included: &[Value]) -> Result<Vec<Agency>, AgencyListError> {
+    // Parent departments, by their FOIA.gov UUID
+    let mut departments = Vec::new();
+    for item in included
+        .iter()
+        .filter(|item| item.get("type").and_then(Value::as_str) == Some("agency"))
+    {
+        let attributes = &item["attributes"];
+        let Some(name) = optional(attributes.get("name").and_then(Value::as_str)) else {
+            continue;
+        };
+        let abbreviation = optional(attributes.get("abbreviation").and_then(Value::as_str));
+        let mut agency = Agency::new(name, abbreviation);
+        agency.website = link(attributes.get("website"));
+        departments.push((item.get("id").and_then(Value::as_str), agency));
+    }
+
+    let mut agencies = Vec::new();
+    for (i, item) in data.iter().enumerate() {
+        let attributes = &item["attributes"];
+        let name = optional(attributes.get("title").and_then(Value::as_str)).ok_or_else(|| {
+            AgencyListError::Invalid {
+                entry: i + 1,
+                message: "missing title".to_string(),
+            }
+        })?;
+        let abbreviation = optional(attributes.get("abbreviation").and_then(Value::as_str));
+        let mut agency = Agency::new(name, abbreviation);
+
+        let parent_uuid = item
+            .pointer("/relationships/agency/data/id")
+            .and_then(Value::as_str);
+        if let Some((_, parent)) = departments
+            .iter()
+            .find(|(uuid, _)| parent_uuid.is_some() && *uuid == parent_uuid)
+        {
+            if parent.id == agency.id {
+                // A department's own FOIA office shares its abbreviation;
+                // fold its contact details into the department.
+                agency.name = parent.name.clone();
+            } else {
+                agency.parent_id = Some(parent.id.clone());
+            }
+        }
+
+        agency.foia_email = first(attributes.get("email"))
+            .and_then(Value::as_str)
+            .and_then(|e| optional(Some(e)));
+        agency.foia_phone = first(attributes.get("telephone"))
+            .and_then(Value::as_str)
+            .and_then(|p| optional(Some(p)));
+        agency.foia_address = address(attributes.get("submission_address"));
+        agency.website = link(attributes.get("website"));
+        agency.portal_urls = [
+            link(attributes.get("submission_web")),
+            link(first(attributes.get("reading_rooms"))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        agencies.push(agency);
+    }
+
+    // Departments first, so components' parents exist, unless a component
+    // already stands in for the department
+    let mut all: Vec<Agency> = departments
+        .into_iter()
+        .map(|(_, department)| department)
+        .filter(|d| !agencies.iter().any(|a| a.id == d.id))
+        .collect();
+    all.extend(agencies);
+    Ok(all)
+}
+
+/// Parse an agency list. In CSV, blank lines and lines starting with `#`
+/// are skipped.
+pub fn parse(content: &str, format: AgencyListFormat) -> Result<Vec<Agency>, AgencyListError> {
+    match format {
+        AgencyListFormat::Csv => parse_csv(content),
+        AgencyListFormat::Json => {
+            let value: Value = serde_json::from_str(content)?;
+            match &value {
+                Value::Array(items) => parse_objects(items),
+                Value::Object(doc) => match doc.get("data") {
+                    Some(Value::Array(data)) => {
+                        let included = doc
+                            .get("included")
+                            .and_then(Value::as_array)
+                            .map(Vec::as_slice)
+                            .unwrap_or_default();
+                        parse_foia_gov(data, included)
+                    }
+                    _ => Err(AgencyListError::UnknownJson),
+                },
+                _ => Err(AgencyListError::UnknownJson),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "name,abbreviation,level,parent,foia_email,portal_urls\n\
+            # federal\n\
+            Department of Justice,DOJ,federal,,,\n\
+            \"Federal Bureau of Investigation, Records Division\",FBI,,DOJ,foiparequest@fbi.gov,https://efoia.fbi.gov; https://vault.fbi.gov\n\
+            Oakland Police Department,,city,,,\n";
+        let agencies = parse(content, AgencyListFormat::Csv).unwrap();
+        assert_eq!(agencies.len(), 3);
+        assert_eq!(agencies[0].id, "doj");
+        let fbi = &agencies[1];
+        assert_eq!(fbi.id, "fbi");
+        assert_eq!(
+            fbi.name,
+            "Federal Bureau of Investigation, Records Division"
+        );
+        assert_eq!(fbi.parent_id.as_deref(), Some("doj"));
+        assert_eq!(fbi.level, AgencyLevel::Federal);
+        assert_eq!(
+            fbi.portal_urls,
+            vec!["https://efoia.fbi.gov", "https://vault.fbi.gov"]
+        );
+        assert_eq!(agencies[2].id, "oakland-police-department");
+        assert_eq!(agencies[2].level, AgencyLevel::Municipal);
+
+        assert!(matches!(
+            parse("abbreviation\nFBI\n", AgencyListFormat::Csv),
+            Err(AgencyListError::MissingNameColumn)
+        ));
+        assert!(matches!(
+            parse("name,level\nFBI,galactic\n", AgencyListFormat::Csv),
+            Err(AgencyListError::Invalid { entry: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_json_array() {
+        let content = r#"[{"name": "Central Intelligence Agency", "abbreviation": "CIA",
+            "portal_urls": ["https://www.cia.gov/readingroom/"]},
+            {"id": "ca-doj", "name": "California Department of Justice",
+             "level": "state", "jurisdiction": "US-CA"}]"#;
+        let agencies = parse(content, AgencyListFormat::Json).unwrap();
+        assert_eq!(agencies[0].id, "cia");
+        assert_eq!(
+            agencies[0].portal_urls,
+            vec!["https://www.cia.gov/readingroom/"]
+        );
+        assert_eq!(agencies[1].id, "ca-doj");
+        assert_eq!(agencies[1].level, AgencyLevel::State);
+        assert_eq!(agencies[1].jurisdiction, "US-CA");
+    }
+
+    #[test]
+    fn test_parse_foia_gov() {
+        let content = r#"{
+            "data": [
+                {"type": "agency_component", "id": "c1",
+                 "attributes": {"title": "Federal Bureau of Investigation", "abbreviation": "FBI",
+                    "email": ["foiparequest@fbi.gov"], "telephone": ["540-868-1535"],
+                    "submission_address": {"organization": "Record/Information Dissemination Section",
+                        "address_line1": "170 Marcel Drive", "locality": "Winchester",
+                        "administrative_area": "VA", "postal_code": "22602"},
+                    "submission_web": {"uri": "https://efoia.fbi.gov/"},
+                    "website": {"uri": "https://www.fbi.gov/"}},
+                 "relationships": {"agency": {"data": {"type": "agency", "id": "a1"}}}}
+            ],
+            "included": [
+                {"type": "agency", "id": "a1",
+                 "attributes": {"name": "Department of Justice", "abbreviation": "DOJ"}}
+            ]
+        }"#;
+        let agencies = parse(content, AgencyListFormat::Json).unwrap();
+        assert_eq!(agencies.len(), 2);
+        assert_eq!(agencies[0].id, "doj");
+        let fbi = &agencies[1];
+        assert_eq!(fbi.parent_id.as_deref(), Some("doj"));
+        assert_eq!(fbi.foia_email.as_deref(), Some("foiparequest@fbi.gov"));
+        assert_eq!(
+            fbi.foia_address.as_deref(),
+            Some("Record/Information Dissemination Section, 170 Marcel Drive, Winchester VA 22602")
+        );
+        assert_eq!(fbi.portal_urls, vec!["https://efoia.fbi.gov/"]);
+        assert_eq!(fbi.website.as_deref(), Some("https://www.fbi.gov/"));
+
+        assert!(matches!(
+            parse(r#"{"items": []}"#, AgencyListFormat::Json),
+            Err(AgencyListError::UnknownJson)
+        ));
+    }
+}
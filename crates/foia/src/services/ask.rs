@@ -0,0 +1,301 @@
+//! Question answering over the archive.
+//!
+//! Pages likely to answer a question are retrieved with the page full-text
+//! search, one query per question term, and ranked by how many terms each
+//! page contains. The configured LLM then answers from those pages alone,
+//! citing the document and page behind each statement. Citations that don't
+//! point at a retrieved page are dropped.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::llm::{LlmClient, LlmError};
+use crate::repository::diesel_document::PagePassageRow;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+
+/// Pages given to the LLM when the caller doesn't say.
+pub const DEFAULT_CONTEXT_PAGES: usize = 8;
+
+/// Most pages given to the LLM, however many are asked for.
+pub const MAX_CONTEXT_PAGES: usize = 30;
+
+/// Pages fetched per question term before ranking.
+const PAGES_PER_TERM: usize = 50;
+
+/// Question terms searched for; the rest are ignored.
+const MAX_TERMS: usize = 8;
+
+/// Words too common in questions to help find a page.
+const STOPWORDS: &[&str] = &[
+    "about",
+    "after",
+    "also",
+    "and",
+    "any",
+    "are",
+    "before",
+    "between",
+    "but",
+    "can",
+    "could",
+    "did",
+    "does",
+    "document",
+    "documents",
+    "for",
+    "from",
+    "had",
+    "has",
+    "have",
+    "how",
+    "into",
+    "its",
+    "many",
+    "more",
+    "much",
+    "not",
+    "other",
+    "over",
+    "said",
+    "should",
+    "some",
+    "than",
+    "that",
+    "the",
+    "their",
+    "them",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "was",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+];
+
+/// A citation as written by the model: `doc:<id> p.<page>`.
+static CITATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"doc:([A-Za-z0-9_.-]+)\s+p\.?\s*(\d+)").expect("valid regex"));
+
+#[derive(Debug, Error)]
+pub enum AskError {
+    #[error("Question has no searchable terms")]
+    NoTerms,
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+    #[error(transparent)]
+    Database(#[from] DieselError),
+}
+
+/// A page an answer cites.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    pub document_id: String,
+    pub title: String,
+    pub page_number: u32,
+}
+
+/// An answer and the pages it was drawn from.
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub answer: String,
+    /// Pages cited in the answer, in order of first citation.
+    pub citations: Vec<Citation>,
+    /// Pages the LLM was given.
+    pub pages_consulted: usize,
+}
+
+/// Search terms in a question: lowercase words of three or more
+/// characters, minus stopwords, in the order they appear.
+pub fn question_terms(question: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for word in question.split(|c: char| !c.is_alphanumeric()) {
+        let word = word.to_lowercase();
+        if word.chars().count() < 3 || STOPWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        if !terms.contains(&word) {
+            terms.push(word);
+        }
+    }
+    terms.truncate(MAX_TERMS);
+    terms
+}
+
+/// Rank pages by how many terms matched them, keeping the search order
+/// among pages with the same count. `matches` holds one result list per term.
+fn rank_pages(matches: Vec<Vec<PagePassageRow>>, max: usize) -> Vec<PagePassageRow> {
+    let mut ranked: Vec<(usize, PagePassageRow)> = Vec::new();
+    let mut index: HashMap<(String, i32), usize> = HashMap::new();
+    for rows in matches {
+        for row in rows {
+            let key = (row.document_id.clone(), row.page_number);
+            match index.get(&key) {
+                Some(&i) => ranked[i].0 += 1,
+                None => {
+                    index.insert(key, ranked.len());
+                    ranked.push((1, row));
+                }
+            }
+        }
+    }
+    ranked.sort_by(|a, b| b.0.cmp(&a.0));
+    ranked.into_iter().take(max).map(|(_, row)| row).collect()
+}
+
+/// Label each page for citation, giving each an equal share of `max_chars`.
+fn format_pages(pages: &[PagePassageRow], max_chars: usize) -> String {
+    let share = max_chars / pages.len().max(1);
+    pages
+        .iter()
+        .map(|page| {
+            let mut end = share.min(page.text.len());
+            while !page.text.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!(
+                "[doc:{} p.{}] {}\n{}",
+                page.document_id,
+                page.page_number,
+                page.title,
+                page.text[..end].trim()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Citations in `answer` that point at one of `pages`.
+fn parse_citations(answer: &str, pages: &[PagePassageRow]) -> Vec<Citation> {
+    let mut citations: Vec<Citation> = Vec::new();
+    for cap in CITATION.captures_iter(answer) {
+        let Ok(page_number) = cap[2].parse::<u32>() else {
+            continue;
+        };
+        let Some(page) = pages
+            .iter()
+            .find(|p| p.document_id == cap[1] && p.page_number as u32 == page_number)
+        else {
+            continue;
+        };
+        let citation = Citation {
+            document_id: page.document_id.clone(),
+            title: page.title.clone(),
+            page_number,
+        };
+        if !citations.contains(&citation) {
+            citations.push(citation);
+        }
+    }
+    citations
+}
+
+/// Answer `question` from up to `max_pages` of the most relevant pages,
+/// optionally only from one source.
+pub async fn ask(
+    docs: &DieselDocumentRepository,
+    llm: &LlmClient,
+    question: &str,
+    source_id: Option<&str>,
+    max_pages: usize,
+) -> Result<Answer, AskError> {
+    let terms = question_terms(question);
+    if terms.is_empty() {
+        return Err(AskError::NoTerms);
+    }
+
+    let mut matches = Vec::with_capacity(terms.len());
+    for term in &terms {
+        matches.push(
+            docs.pages_containing(term, source_id, PAGES_PER_TERM)
+                .await?,
+        );
+    }
+    let pages = rank_pages(matches, max_pages.clamp(1, MAX_CONTEXT_PAGES));
+    if pages.is_empty() {
+        return Ok(Answer {
+            answer: "No pages in the archive match this question.".to_string(),
+            citations: Vec::new(),
+            pages_consulted: 0,
+        });
+    }
+
+    let context = format_pages(&pages, llm.config().max_content_chars());
+    let answer = llm.answer_question(question, &context).await?;
+    let citations = parse_citations(&answer, &pages);
+    Ok(Answer {
+        answer,
+        citations,
+        pages_consulted: pages.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(document_id: &str, page_number: i32) -> PagePassageRow {
+        PagePassageRow {
+            document_id: document_id.to_string(),
+            title: format!("{} title", document_id),
+            page_number,
+            text: "Text of the page.".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_question_terms() {
+        assert_eq!(
+            question_terms("What did the FBI say about COINTELPRO in 1971? What did the FBI do?"),
+            vec!["fbi", "say", "cointelpro", "1971"]
+        );
+        assert!(question_terms("Who was it?").is_empty());
+    }
+
+    #[test]
+    fn test_rank_pages() {
+        let ranked = rank_pages(
+            vec![
+                vec![page("a", 1), page("b", 2)],
+                vec![page("b", 2), page("c", 5)],
+                vec![page("c", 5), page("b", 2)],
+            ],
+            2,
+        );
+        let keys: Vec<_> = ranked
+            .iter()
+            .map(|p| (p.document_id.as_str(), p.page_number))
+            .collect();
+        assert_eq!(keys, vec![("b", 2), ("c", 5)]);
+    }
+
+    #[test]
+    fn test_parse_citations() {
+        let pages = vec![page("doc-1", 3), page("doc-2", 1)];
+        let answer = "The program ended in 1971 [doc:doc-1 p.3]. A memo confirms it \
+                      [doc:doc-2 p.1; doc:doc-1 p.3]. Another claim [doc:doc-9 p.4].";
+        let citations = parse_citations(answer, &pages);
+        assert_eq!(citations.len(), 2);
+        assert_eq!(citations[0].document_id, "doc-1");
+        assert_eq!(citations[0].page_number, 3);
+        assert_eq!(citations[1].title, "doc-2 title");
+    }
+}
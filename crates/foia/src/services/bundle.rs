@@ -0,0 +1,811 @@
+//! Portable archive bundles.
+//!
+//! A bundle carries selected sources from one instance to another as a
+//! single tar file, independent of either database backend:
+//!
+//! - `manifest.json`: format and schema version, counts, and the content
+//!   hash and size of every file
+//! - `sources.jsonl`: one source per line
+//! - `documents.jsonl`: one document per line with its versions, pages and
+//!   virtual files
+//! - `files/<hash>`: document content, stored once per content hash
+//!
+//! Import checks every file against the manifest before the database is
+//! touched. Documents keep their IDs unless the target already uses them;
+//! version, page and virtual file IDs are always assigned by the target. A
+//! document whose URL the target already has gains only the versions it
+//! lacks.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::migrations;
+use crate::models::{DocumentPage, DocumentVersion, Source, VirtualFile};
+use crate::repository::migration::PortableDocument;
+use crate::repository::pool::DieselError;
+use crate::repository::{DieselDocumentRepository, DieselSourceRepository};
+
+/// Bundle layout version written by this build.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+const SOURCES_PATH: &str = "sources.jsonl";
+const DOCUMENTS_PATH: &str = "documents.jsonl";
+const FILES_DIR: &str = "files/";
+
+/// Documents loaded per database round trip during export.
+const BATCH_SIZE: u32 = 200;
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("Database error: {0}")]
+    Database(#[from] DieselError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Source '{0}' not found")]
+    SourceNotFound(String),
+    #[error("Not a valid bundle: {0}")]
+    Invalid(String),
+    #[error("Bundle format {0} is newer than this build supports ({BUNDLE_FORMAT_VERSION})")]
+    UnsupportedFormat(u32),
+    #[error("Bundle schema {0} is newer than this database; upgrade foia before importing")]
+    NewerSchema(String),
+    #[error("{path}: expected hash {expected}, got {actual}")]
+    HashMismatch {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// A document file in a bundle, stored at `files/<hash>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleFile {
+    /// The versions' content hash: BLAKE3, or SHA-256 for versions stored
+    /// before BLAKE3 (and in bundles written then).
+    #[serde(alias = "sha256")]
+    pub hash: String,
+    pub size: u64,
+}
+
+/// Describes a bundle; the first entry of the tar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    /// Newest migration of the exporting database.
+    pub schema_version: String,
+    pub foia_version: String,
+    pub created_at: DateTime<Utc>,
+    pub sources: Vec<String>,
+    pub documents: u64,
+    pub versions: u64,
+    pub files: Vec<BundleFile>,
+    /// Content hashes whose file was missing from the exporting instance.
+    #[serde(default)]
+    pub missing_files: Vec<String>,
+}
+
+/// A document with everything stored under it, one line of `documents.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleDocument {
+    pub document: PortableDocument,
+    /// Oldest first.
+    pub versions: Vec<DocumentVersion>,
+    pub pages: Vec<DocumentPage>,
+    pub virtual_files: Vec<VirtualFile>,
+}
+
+/// What an import added.
+#[derive(Debug, Clone, Default)]
+pub struct BundleImport {
+    pub sources_added: usize,
+    pub documents_added: usize,
+    /// Existing documents that gained versions from the bundle.
+    pub documents_merged: usize,
+    pub versions_added: usize,
+    pub files_written: usize,
+    /// Documents whose original ID was taken and were given a new one.
+    pub ids_remapped: usize,
+}
+
+/// Export `source_ids` with their documents and files to a bundle at `output`.
+pub async fn export_bundle(
+    docs: &DieselDocumentRepository,
+    sources: &DieselSourceRepository,
+    source_ids: &[String],
+    documents_dir: &Path,
+    output: &Path,
+) -> Result<BundleManifest, BundleError> {
+    let mut source_rows = Vec::with_capacity(source_ids.len());
+    for id in source_ids {
+        match sources.get(id).await? {
+            Some(source) => source_rows.push(source),
+            None => return Err(BundleError::SourceNotFound(id.clone())),
+        }
+    }
+
+    // Documents are staged so the manifest, which needs every file hash,
+    // can go first in the tar.
+    let staged = tempfile::NamedTempFile::new()?;
+    let mut writer = BufWriter::new(staged.reopen()?);
+    let mut files: BTreeMap<String, (PathBuf, u64)> = BTreeMap::new();
+    let mut missing: HashSet<String> = HashSet::new();
+    let (mut document_count, mut version_count) = (0u64, 0u64);
+
+    for source in &source_rows {
+        let mut after: Option<String> = None;
+        loop {
+            let records = docs
+                .get_records_after(Some(&source.id), after.as_deref(), BATCH_SIZE)
+                .await?;
+            let Some(last) = records.last() else {
+                break;
+            };
+            after = Some(last.id.clone());
+
+            let ids: Vec<String> = records.iter().map(|r| r.id.clone()).collect();
+            let mut version_map = docs.load_versions_batch(&ids).await?;
+            let mut page_map: HashMap<String, Vec<DocumentPage>> = HashMap::new();
+            for page in docs.get_pages_batch(&ids).await? {
+                page_map
+                    .entry(page.document_id.clone())
+                    .or_default()
+                    .push(page);
+            }
+            let mut file_map: HashMap<String, Vec<VirtualFile>> = HashMap::new();
+            for vf in docs.get_virtual_files_batch(&ids).await? {
+                file_map.entry(vf.document_id.clone()).or_default().push(vf);
+            }
+
+            for record in records {
+                let mut versions = version_map.remove(&record.id).unwrap_or_default();
+                versions.sort_by_key(|v| v.id);
+                for version in &versions {
+                    if files.contains_key(&version.content_hash) {
+                        continue;
+                    }
+                    let path =
+                        version.resolve_path(documents_dir, &record.source_url, &record.title);
+                    match std::fs::metadata(&path) {
+                        Ok(meta) if meta.is_file() => {
+                            files.insert(version.content_hash.clone(), (path, meta.len()));
+                        }
+                        _ => {
+                            missing.insert(version.content_hash.clone());
+                        }
+                    }
+                }
+                document_count += 1;
+                version_count += versions.len() as u64;
+
+                let doc = BundleDocument {
+                    pages: page_map.remove(&record.id).unwrap_or_default(),
+                    virtual_files: file_map.remove(&record.id).unwrap_or_default(),
+                    document: PortableDocument::from(record),
+                    versions,
+                };
+                serde_json::to_writer(&mut writer, &doc)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+
+    let mut missing_files: Vec<String> = missing
+        .into_iter()
+        .filter(|hash| !files.contains_key(hash))
+        .collect();
+    missing_files.sort();
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        schema_version: migrations::schema_version(),
+        foia_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        sources: source_rows.iter().map(|s| s.id.clone()).collect(),
+        documents: document_count,
+        versions: version_count,
+        files: files
+            .iter()
+            .map(|(hash, (_, size))| BundleFile {
+                hash: hash.clone(),
+                size: *size,
+            })
+            .collect(),
+        missing_files,
+    };
+
+    let mut sources_jsonl = Vec::new();
+    for source in &source_rows {
+        serde_json::to_writer(&mut sources_jsonl, source)?;
+        sources_jsonl.push(b'\n');
+    }
+
+    let mut tar = tar::Builder::new(BufWriter::new(File::create(output)?));
+    append_bytes(
+        &mut tar,
+        MANIFEST_PATH,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+    append_bytes(&mut tar, SOURCES_PATH, &sources_jsonl)?;
+    tar.append_path_with_name(staged.path(), DOCUMENTS_PATH)?;
+    for (hash, (path, _)) in &files {
+        tar.append_path_with_name(path, format!("{}{}", FILES_DIR, hash))?;
+    }
+    tar.into_inner()?.flush()?;
+
+    Ok(manifest)
+}
+
+fn append_bytes<W: Write>(
+    tar: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), BundleError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// Read a bundle's manifest without unpacking the rest.
+pub fn read_manifest(bundle: &Path) -> Result<BundleManifest, BundleError> {
+    let mut archive = tar::Archive::new(BufReader::new(File::open(bundle)?));
+    let mut entries = archive.entries()?;
+    let Some(entry) = entries.next() else {
+        return Err(BundleError::Invalid("empty archive".to_string()));
+    };
+    let mut entry = entry?;
+    if entry.path()?.to_string_lossy() != MANIFEST_PATH {
+        return Err(BundleError::Invalid(format!(
+            "first entry is not {}",
+            MANIFEST_PATH
+        )));
+    }
+    let mut data = Vec::new();
+    entry.read_to_end(&mut data)?;
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Refuse bundles this build cannot read or whose rows may carry columns
+/// this database does not have yet.
+fn check_compatible(manifest: &BundleManifest) -> Result<(), BundleError> {
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(BundleError::UnsupportedFormat(manifest.format_version));
+    }
+    if !migrations::is_known_migration(&manifest.schema_version) {
+        return Err(BundleError::NewerSchema(manifest.schema_version.clone()));
+    }
+    Ok(())
+}
+
+/// Whether `hash` looks like a content hash: 64 lowercase hex digits.
+/// Hashes name files on disk, so anything else is refused.
+fn is_content_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// What was written by [`write_hashed`].
+struct Written {
+    sha256: String,
+    blake3: String,
+    size: u64,
+}
+
+impl Written {
+    /// Whether the content has this hash, in either algorithm.
+    fn has_hash(&self, hash: &str) -> bool {
+        self.blake3 == hash || self.sha256 == hash
+    }
+}
+
+/// Copy `reader` to `path`, hashing what was written.
+fn write_hashed(reader: &mut impl Read, path: &Path) -> Result<Written, BundleError> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let mut hasher = Sha256::new();
+    let mut blake3 = blake3::Hasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        blake3.update(&buf[..n]);
+        out.write_all(&buf[..n])?;
+        size += n as u64;
+    }
+    out.flush()?;
+    Ok(Written {
+        sha256: hex::encode(hasher.finalize()),
+        blake3: hex::encode(blake3.finalize().as_bytes()),
+        size,
+    })
+}
+
+/// Import a bundle into this instance, placing its files under `documents_dir`.
+pub async fn import_bundle(
+    docs: &DieselDocumentRepository,
+    sources: &DieselSourceRepository,
+    documents_dir: &Path,
+    bundle: &Path,
+) -> Result<BundleImport, BundleError> {
+    std::fs::create_dir_all(documents_dir)?;
+    // Staged next to the documents so placing a file is usually a rename.
+    let staging = tempfile::Builder::new()
+        .prefix(".bundle-")
+        .tempdir_in(documents_dir)?;
+    let manifest = unpack_verified(bundle, staging.path())?;
+
+    let mut result = BundleImport::default();
+    let sources_file = BufReader::new(File::open(staging.path().join(SOURCES_PATH))?);
+    for line in sources_file.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let source: Source = serde_json::from_str(&line)?;
+        if !manifest.sources.contains(&source.id) {
+            return Err(BundleError::Invalid(format!(
+                "source '{}' is not listed in the manifest",
+                source.id
+            )));
+        }
+        if !sources.exists(&source.id).await? {
+            sources.save(&source).await?;
+            result.sources_added += 1;
+        }
+    }
+
+    let files_dir = staging.path().join(FILES_DIR);
+    let documents_file = BufReader::new(File::open(staging.path().join(DOCUMENTS_PATH))?);
+    for line in documents_file.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let doc: BundleDocument = serde_json::from_str(&line)?;
+        if let Some(version) = doc
+            .versions
+            .iter()
+            .find(|v| !is_content_hash(&v.content_hash))
+        {
+            return Err(BundleError::Invalid(format!(
+                "document {} has a version with invalid hash {:?}",
+                doc.document.id, version.content_hash
+            )));
+        }
+        import_document(docs, doc, documents_dir, &files_dir, &mut result).await?;
+    }
+
+    Ok(result)
+}
+
+/// Unpack a bundle into `dir`, checking each file's hash and size against
+/// the manifest, and that every listed file is present.
+fn unpack_verified(bundle: &Path, dir: &Path) -> Result<BundleManifest, BundleError> {
+    std::fs::create_dir_all(dir.join(FILES_DIR))?;
+    let mut archive = tar::Archive::new(BufReader::new(File::open(bundle)?));
+    let mut manifest: Option<BundleManifest> = None;
+    let mut expected: HashMap<String, u64> = HashMap::new();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(BundleError::Invalid(format!(
+                "unsafe entry path {}",
+                path.display()
+            )));
+        }
+        let name = path.to_string_lossy().to_string();
+        let Some(m) = manifest.as_ref() else {
+            if name != MANIFEST_PATH {
+                return Err(BundleError::Invalid(format!(
+                    "first entry is not {}",
+                    MANIFEST_PATH
+                )));
+            }
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            let m: BundleManifest = serde_json::from_slice(&data)?;
+            check_compatible(&m)?;
+            if let Some(file) = m.files.iter().find(|f| !is_content_hash(&f.hash)) {
+                return Err(BundleError::Invalid(format!(
+                    "manifest lists invalid hash {:?}",
+                    file.hash
+                )));
+            }
+            expected = m.files.iter().map(|f| (f.hash.clone(), f.size)).collect();
+            manifest = Some(m);
+            continue;
+        };
+
+        if name == SOURCES_PATH || name == DOCUMENTS_PATH {
+            write_hashed(&mut entry, &dir.join(&name))?;
+            seen.insert(name);
+        } else if let Some(hash) = name.strip_prefix(FILES_DIR) {
+            let Some(&size) = expected.get(hash) else {
+                return Err(BundleError::Invalid(format!(
+                    "{} is not listed in the manifest",
+                    name
+                )));
+            };
+            // Listed names are checked hex digests, so they are safe file names
+            let dest = dir.join(FILES_DIR).join(hash);
+            let written = write_hashed(&mut entry, &dest)?;
+            if !written.has_hash(hash) || written.size != size {
+                let _ = std::fs::remove_file(&dest);
+                return Err(BundleError::HashMismatch {
+                    path: name,
+                    expected: hash.to_string(),
+                    actual: written.blake3,
+                });
+            }
+            seen.insert(name);
+        } else {
+            return Err(BundleError::Invalid(format!(
+                "unexpected entry {} (manifest lists {} files)",
+                name,
+                m.files.len()
+            )));
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| BundleError::Invalid("empty archive".to_string()))?;
+    for required in [SOURCES_PATH, DOCUMENTS_PATH] {
+        if !seen.contains(required) {
+            return Err(BundleError::Invalid(format!("missing {}", required)));
+        }
+    }
+    if let Some(file) = manifest
+        .files
+        .iter()
+        .find(|f| !seen.contains(&format!("{}{}", FILES_DIR, f.hash)))
+    {
+        return Err(BundleError::Invalid(format!(
+            "missing {}{}",
+            FILES_DIR, file.hash
+        )));
+    }
+    Ok(manifest)
+}
+
+async fn import_document(
+    docs: &DieselDocumentRepository,
+    bundled: BundleDocument,
+    documents_dir: &Path,
+    files_dir: &Path,
+    result: &mut BundleImport,
+) -> Result<(), BundleError> {
+    let BundleDocument {
+        mut document,
+        versions,
+        pages,
+        virtual_files,
+    } = bundled;
+
+    let existing = docs
+        .get_by_url(&document.source_url)
+        .await?
+        .into_iter()
+        .find(|d| d.source_id == document.source_id);
+    let known_hashes: HashSet<String> = existing
+        .as_ref()
+        .map(|d| d.versions.iter().map(|v| v.content_hash.clone()).collect())
+        .unwrap_or_default();
+    let new_versions: Vec<DocumentVersion> = versions
+        .into_iter()
+        .filter(|v| !known_hashes.contains(&v.content_hash))
+        .collect();
+
+    let merged = match existing {
+        Some(existing) => {
+            if new_versions.is_empty() {
+                return Ok(());
+            }
+            document.id = existing.id;
+            true
+        }
+        None => {
+            if docs.exists(&document.id).await? {
+                document.id = uuid::Uuid::new_v4().to_string();
+                result.ids_remapped += 1;
+            }
+            false
+        }
+    };
+
+    // Written in one transaction so a failed import leaves no document
+    // without its versions.
+    docs.pool
+        .transaction(move || async move {
+            if merged {
+                result.documents_merged += 1;
+            } else {
+                docs.insert_portable_document(&document).await?;
+                result.documents_added += 1;
+            }
+
+            let mut version_ids: HashMap<i64, i64> = HashMap::new();
+            for mut version in new_versions {
+                let old_id = version.id;
+                version.file_path = None;
+                version.archive_snapshot_id = None;
+                let new_id = docs.add_version(&document.id, &version).await?;
+                version_ids.insert(old_id, new_id);
+                result.versions_added += 1;
+
+                let staged = files_dir.join(&version.content_hash);
+                let dest =
+                    version.resolve_path(documents_dir, &document.source_url, &document.title);
+                if staged.exists() && !dest.exists() {
+                    if let Some(parent) = dest.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    // Several versions can share a hash, so copy rather than move
+                    std::fs::copy(&staged, &dest)?;
+                    result.files_written += 1;
+                }
+            }
+
+            let pages: Vec<DocumentPage> = pages
+                .into_iter()
+                .filter_map(|mut page| {
+                    page.version_id = *version_ids.get(&page.version_id)?;
+                    page.document_id = document.id.clone();
+                    Some(page)
+                })
+                .collect();
+            docs.save_pages_batch(&pages).await?;
+
+            for mut vf in virtual_files {
+                let Some(&version_id) = version_ids.get(&vf.version_id) else {
+                    continue;
+                };
+                vf.id = uuid::Uuid::new_v4().to_string();
+                vf.document_id = document.id.clone();
+                vf.version_id = version_id;
+                docs.insert_virtual_file(&vf).await?;
+            }
+
+            Ok(())
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Document, SourceType};
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations as db_migrations;
+    use tempfile::tempdir;
+
+    async fn setup_instance(dir: &Path) -> DieselDbContext {
+        let db_path = dir.join("foia.db");
+        let db_url = format!("sqlite:{}", db_path.display());
+        db_migrations::run_migrations(&db_url, false).await.unwrap();
+        DieselDbContext::from_sqlite_path(&db_path).unwrap()
+    }
+
+    fn document(id: &str, url: &str, content: &[u8]) -> Document {
+        let version = DocumentVersion::new_with_metadata(
+            content,
+            "application/pdf".to_string(),
+            Some(url.to_string()),
+            Some(format!("{}.pdf", id)),
+            None,
+        );
+        Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            format!("Document {}", id),
+            url.to_string(),
+            version,
+            serde_json::json!({}),
+        )
+    }
+
+    async fn store(ctx: &DieselDbContext, documents_dir: &Path, doc: &Document, content: &[u8]) {
+        ctx.documents().save_with_versions(doc).await.unwrap();
+        let version = doc.current_version().unwrap();
+        let path = version.resolve_path(documents_dir, &doc.source_url, &doc.title);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bundle_round_trip() {
+        let from_dir = tempdir().unwrap();
+        let from = setup_instance(from_dir.path()).await;
+        let from_docs = from_dir.path().join("documents");
+        let source = Source::new(
+            "fbi".to_string(),
+            SourceType::Custom,
+            "FBI Vault".to_string(),
+            "https://vault.fbi.gov".to_string(),
+        );
+        from.sources().save(&source).await.unwrap();
+        let a = document("a", "https://vault.fbi.gov/a.pdf", b"first");
+        let b = document("b", "https://vault.fbi.gov/b.pdf", b"second");
+        store(&from, &from_docs, &a, b"first").await;
+        store(&from, &from_docs, &b, b"second").await;
+
+        let bundle = from_dir.path().join("fbi.tar");
+        let manifest = export_bundle(
+            &from.documents(),
+            &from.sources(),
+            &["fbi".to_string()],
+            &from_docs,
+            &bundle,
+        )
+        .await
+        .unwrap();
+        assert_eq!(manifest.documents, 2);
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.missing_files.is_empty());
+        assert_eq!(
+            read_manifest(&bundle).unwrap().schema_version,
+            manifest.schema_version
+        );
+
+        // The target already has "a" under its ID with different content
+        let to_dir = tempdir().unwrap();
+        let to = setup_instance(to_dir.path()).await;
+        let to_docs = to_dir.path().join("documents");
+        to.sources().save(&source).await.unwrap();
+        let taken = document("a", "https://vault.fbi.gov/other.pdf", b"other");
+        store(&to, &to_docs, &taken, b"other").await;
+
+        let imported = import_bundle(&to.documents(), &to.sources(), &to_docs, &bundle)
+            .await
+            .unwrap();
+        assert_eq!(imported.documents_added, 2);
+        assert_eq!(imported.ids_remapped, 1);
+        assert_eq!(imported.files_written, 2);
+
+        let remapped = to
+            .documents()
+            .get_by_url("https://vault.fbi.gov/a.pdf")
+            .await
+            .unwrap()
+            .remove(0);
+        assert_ne!(remapped.id, "a");
+        let version = remapped.current_version().unwrap();
+        let path = version.resolve_path(&to_docs, &remapped.source_url, &remapped.title);
+        assert_eq!(std::fs::read(path).unwrap(), b"first");
+
+        // Importing again adds nothing
+        let again = import_bundle(&to.documents(), &to.sources(), &to_docs, &bundle)
+            .await
+            .unwrap();
+        assert_eq!(again.documents_added + again.versions_added, 0);
+    }
+
+    /// Write a tar of `entries` after `manifest`, without the checks the
+    /// tar builder makes on entry names.
+    fn raw_bundle(path: &Path, manifest: &BundleManifest, entries: &[(&str, &[u8])]) {
+        let mut tar = tar::Builder::new(File::create(path).unwrap());
+        let manifest = serde_json::to_vec(manifest).unwrap();
+        let manifest_entry: (&str, &[u8]) = (MANIFEST_PATH, &manifest);
+        for (name, data) in std::iter::once(&manifest_entry).chain(entries) {
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append(&header, *data).unwrap();
+        }
+        tar.finish().unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsafe_paths() {
+        let dir = tempdir().unwrap();
+        let content: &[u8] = b"payload";
+        let hash = blake3::hash(content).to_hex().to_string();
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            schema_version: migrations::schema_version(),
+            foia_version: "0.0.0".to_string(),
+            created_at: Utc::now(),
+            sources: Vec::new(),
+            documents: 0,
+            versions: 0,
+            files: vec![BundleFile {
+                hash: hash.clone(),
+                size: content.len() as u64,
+            }],
+            missing_files: Vec::new(),
+        };
+        let staging = dir.path().join("staging");
+
+        // An entry climbing out of the staging directory
+        let bundle = dir.path().join("escape.tar");
+        raw_bundle(&bundle, &manifest, &[("../escape", content)]);
+        assert!(matches!(
+            unpack_verified(&bundle, &staging),
+            Err(BundleError::Invalid(_))
+        ));
+        assert!(!dir.path().join("escape").exists());
+
+        // A manifest hash that would name a file outside files/
+        let bad = BundleManifest {
+            files: vec![BundleFile {
+                hash: "../../escape".to_string(),
+                size: content.len() as u64,
+            }],
+            ..manifest.clone()
+        };
+        let bundle = dir.path().join("hash.tar");
+        raw_bundle(&bundle, &bad, &[("files/../../escape", content)]);
+        assert!(matches!(
+            unpack_verified(&bundle, &staging),
+            Err(BundleError::Invalid(_))
+        ));
+        assert!(!dir.path().join("escape").exists());
+
+        // A file that doesn't match its hash is not left behind
+        let bundle = dir.path().join("mismatch.tar");
+        raw_bundle(
+            &bundle,
+            &manifest,
+            &[(
+                format!("{}{}", FILES_DIR, hash).as_str(),
+                b"tampered".as_slice(),
+            )],
+        );
+        assert!(matches!(
+            unpack_verified(&bundle, &staging),
+            Err(BundleError::HashMismatch { .. })
+        ));
+        assert!(!staging.join(FILES_DIR).join(&hash).exists());
+    }
+
+    #[test]
+    fn test_rejects_newer_schema() {
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            schema_version: "9999_from_the_future".to_string(),
+            foia_version: "99.0.0".to_string(),
+            created_at: Utc::now(),
+            sources: Vec::new(),
+            documents: 0,
+            versions: 0,
+            files: Vec::new(),
+            missing_files: Vec::new(),
+        };
+        assert!(matches!(
+            check_compatible(&manifest),
+            Err(BundleError::NewerSchema(_))
+        ));
+        let current = BundleManifest {
+            schema_version: migrations::schema_version(),
+            ..manifest.clone()
+        };
+        assert!(check_compatible(&current).is_ok());
+        let future = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION + 1,
+            ..current
+        };
+        assert!(matches!(
+            check_compatible(&future),
+            Err(BundleError::UnsupportedFormat(_))
+        ));
+    }
+}
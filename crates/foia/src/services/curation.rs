@@ -0,0 +1,225 @@
+//! Curator operations: merging duplicate documents and splitting bundles.
+//!
+//! The same record is often acquired twice under different URLs, and
+//! agencies often release unrelated records bundled into one giant PDF.
+//! Merging folds duplicates into one document with a combined version
+//! history; splitting cuts a PDF at page boundaries into child documents
+//! that go through OCR and analysis on their own. The original of a split
+//! is kept. Both are recorded in the curation log.
+
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::models::Document;
+use crate::repository::diesel_document::SPLIT_ACTION;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+use crate::storage::{save_document_async, DocumentInput};
+use crate::utils::{extract_pdf_pages, pdf_page_count};
+
+#[derive(Debug, Error)]
+pub enum CurationError {
+    #[error("Document not found: {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Only PDFs can be split; {0} is {1}")]
+    NotPdf(String, String),
+    #[error("{0}")]
+    Tool(String),
+    #[error("Failed to save split document: {0}")]
+    Storage(String),
+    #[error(transparent)]
+    Database(#[from] DieselError),
+}
+
+async fn load(repo: &DieselDocumentRepository, id: &str) -> Result<Document, CurationError> {
+    repo.get(id)
+        .await?
+        .ok_or_else(|| CurationError::NotFound(id.to_string()))
+}
+
+/// Merge `source_ids` into `target_id`, returning the merged document. The
+/// merged documents are deleted; see
+/// [`DieselDocumentRepository::merge_documents`] for what moves.
+pub async fn merge_documents(
+    repo: &DieselDocumentRepository,
+    target_id: &str,
+    source_ids: &[String],
+    actor: Option<&str>,
+) -> Result<Document, CurationError> {
+    let mut ids: Vec<&str> = Vec::new();
+    for id in source_ids {
+        if id == target_id {
+            return Err(CurationError::Invalid(format!(
+                "Cannot merge {} into itself",
+                id
+            )));
+        }
+        if !ids.contains(&id.as_str()) {
+            ids.push(id);
+        }
+    }
+    if ids.is_empty() {
+        return Err(CurationError::Invalid("No documents to merge".to_string()));
+    }
+
+    let target = load(repo, target_id).await?;
+    let mut sources = Vec::with_capacity(ids.len());
+    for id in ids {
+        sources.push(load(repo, id).await?);
+    }
+    repo.merge_documents(&target, &sources, actor).await?;
+    load(repo, target_id).await
+}
+
+/// Inclusive page ranges for a PDF of `page_count` pages split so that a new
+/// part starts at each page in `starts`.
+pub fn page_ranges(starts: &[u32], page_count: u32) -> Result<Vec<(u32, u32)>, CurationError> {
+    let mut starts = starts.to_vec();
+    starts.sort_unstable();
+    starts.dedup();
+    if starts.is_empty() {
+        return Err(CurationError::Invalid("No split pages given".to_string()));
+    }
+    if let Some(bad) = starts.iter().find(|&&p| p < 2 || p > page_count) {
+        return Err(CurationError::Invalid(format!(
+            "Cannot start a part at page {}; the document has {} pages",
+            bad, page_count
+        )));
+    }
+
+    let mut ranges = Vec::with_capacity(starts.len() + 1);
+    let mut first = 1;
+    for start in starts {
+        ranges.push((first, start - 1));
+        first = start;
+    }
+    ranges.push((first, page_count));
+    Ok(ranges)
+}
+
+fn pages_label((first, last): (u32, u32)) -> String {
+    if first == last {
+        format!("page {}", first)
+    } else {
+        format!("pages {}-{}", first, last)
+    }
+}
+
+/// Split a document's current PDF version into child documents, one per
+/// page range, starting a new child at each page in `starts`. Children are
+/// new documents in the same source, linked to the original through
+/// `split_from`/`split_into` metadata. Splitting again at the same pages
+/// reuses the existing children.
+pub async fn split_document(
+    repo: &DieselDocumentRepository,
+    documents_dir: &Path,
+    doc_id: &str,
+    starts: &[u32],
+    actor: Option<&str>,
+) -> Result<Vec<Document>, CurationError> {
+    let mut doc = load(repo, doc_id).await?;
+    let version = doc
+        .current_version()
+        .cloned()
+        .ok_or_else(|| CurationError::Invalid(format!("{} has no content", doc.id)))?;
+    if version.mime_type != "application/pdf" {
+        return Err(CurationError::NotPdf(doc.id, version.mime_type));
+    }
+    let pdf = version.resolve_path(documents_dir, &doc.source_url, &doc.title);
+
+    let page_count = match version.page_count {
+        Some(count) => count,
+        None => {
+            let pdf = pdf.clone();
+            tokio::task::spawn_blocking(move || pdf_page_count(&pdf))
+                .await
+                .map_err(|e| CurationError::Tool(e.to_string()))?
+                .map_err(CurationError::Tool)?
+        }
+    };
+    let ranges = page_ranges(starts, page_count)?;
+
+    let mut children = Vec::with_capacity(ranges.len());
+    for range in ranges.iter().copied() {
+        let content = {
+            let pdf = pdf.clone();
+            tokio::task::spawn_blocking(move || extract_pdf_pages(&pdf, range))
+                .await
+                .map_err(|e| CurationError::Tool(e.to_string()))?
+                .map_err(CurationError::Tool)?
+        };
+        let url = format!("{}#pages={}-{}", doc.source_url, range.0, range.1);
+        let input = DocumentInput {
+            url: url.clone(),
+            title: format!("{} ({})", doc.title, pages_label(range)),
+            mime_type: version.mime_type.clone(),
+            metadata: serde_json::json!({
+                "split_from": doc.id,
+                "pages": [range.0, range.1],
+            }),
+            original_filename: None,
+            server_date: version.server_date,
+            declared_mime_type: None,
+            tags: Vec::new(),
+        };
+        save_document_async(repo, &content, &input, &doc.source_id, documents_dir)
+            .await
+            .map_err(|e| CurationError::Storage(e.to_string()))?;
+        let child = repo
+            .get_by_url(&url)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| CurationError::Storage(format!("{} was not saved", url)))?;
+        children.push(child);
+    }
+
+    let child_ids: Vec<String> = children.iter().map(|child| child.id.clone()).collect();
+    if !doc.metadata.is_object() {
+        doc.metadata = serde_json::json!({});
+    }
+    doc.metadata["split_into"] = serde_json::json!(child_ids);
+    repo.save(&doc).await?;
+    repo.record_curation(
+        SPLIT_ACTION,
+        &doc.id,
+        &child_ids,
+        &serde_json::json!({ "version_id": version.id, "pages": ranges }),
+        actor,
+    )
+    .await?;
+
+    Ok(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_ranges() {
+        assert_eq!(
+            page_ranges(&[12, 5], 20).unwrap(),
+            vec![(1, 4), (5, 11), (12, 20)]
+        );
+        assert_eq!(page_ranges(&[20, 20], 20).unwrap(), vec![(1, 19), (20, 20)]);
+        assert!(matches!(
+            page_ranges(&[], 20),
+            Err(CurationError::Invalid(_))
+        ));
+        assert!(matches!(
+            page_ranges(&[1], 20),
+            Err(CurationError::Invalid(_))
+        ));
+        assert!(matches!(
+            page_ranges(&[21], 20),
+            Err(CurationError::Invalid(_))
+        ));
+
+        assert_eq!(pages_label((3, 3)), "page 3");
+        assert_eq!(pages_label((1, 4)), "pages 1-4");
+    }
+}
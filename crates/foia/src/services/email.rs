@@ -0,0 +1,194 @@
+//! Sending plain-text email over SMTP or sendmail.
+//!
+//! Webhooks suit chat rooms and automation; email reaches collaborators
+//! who only read their inbox. Everything that notifies people by email
+//! (request reminders, crawl summaries, scraper alerts, digests) goes
+//! through [`Mailer`].
+//!
+//! SMTP connections don't go through Tor. With Tor enabled, only a server
+//! on this machine is used unless `smtp_direct` allows a remote one.
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSendmailTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use thiserror::Error;
+
+use crate::config::{EmailConfig, SmtpSecurity};
+use crate::privacy::PrivacyConfig;
+
+/// Seconds to wait on the SMTP server.
+const SMTP_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("Invalid address '{0}'")]
+    Address(String),
+    #[error("No recipients")]
+    NoRecipients,
+    #[error(
+        "SMTP server {0} would be reached without Tor; use a local relay, \
+         set email.smtp_direct = true or run with --direct"
+    )]
+    NotPrivate(String),
+    #[error("Failed to build message: {0}")]
+    Message(#[from] lettre::error::Error),
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("sendmail error: {0}")]
+    Sendmail(#[from] lettre::transport::sendmail::Error),
+}
+
+/// A plain-text email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub subject: String,
+    pub body: String,
+}
+
+impl Email {
+    pub fn new(subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            body: body.into(),
+        }
+    }
+}
+
+enum Transport {
+    Smtp(AsyncSmtpTransport<Tokio1Executor>),
+    Sendmail(AsyncSendmailTransport<Tokio1Executor>),
+}
+
+/// Sends email as configured under `email`.
+pub struct Mailer {
+    transport: Transport,
+    from: Mailbox,
+}
+
+/// Whether `host` is this machine, so connecting to it reveals nothing.
+fn is_local(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, EmailError> {
+    address
+        .trim()
+        .parse()
+        .map_err(|_| EmailError::Address(address.to_string()))
+}
+
+impl Mailer {
+    /// Build a mailer: SMTP when `smtp_host` is set, sendmail otherwise.
+    ///
+    /// Refuses a remote SMTP server when `privacy` uses Tor, unless the
+    /// config sets `smtp_direct`.
+    pub fn from_config(config: &EmailConfig, privacy: &PrivacyConfig) -> Result<Self, EmailError> {
+        let from = mailbox(config.from())?;
+        let transport = match &config.smtp_host {
+            Some(host) => {
+                if privacy.uses_tor() && !is_local(host) && !config.smtp_direct() {
+                    return Err(EmailError::NotPrivate(host.clone()));
+                }
+                let builder = match config.security() {
+                    SmtpSecurity::StartTls => {
+                        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+                    }
+                    SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+                    SmtpSecurity::None => {
+                        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+                    }
+                };
+                let mut builder = builder
+                    .port(config.smtp_port())
+                    .timeout(Some(Duration::from_secs(SMTP_TIMEOUT_SECS)));
+                if let Some(username) = &config.smtp_username {
+                    builder = builder.credentials(Credentials::new(
+                        username.clone(),
+                        config.smtp_password().unwrap_or_default(),
+                    ));
+                }
+                Transport::Smtp(builder.build())
+            }
+            None => Transport::Sendmail(
+                AsyncSendmailTransport::<Tokio1Executor>::new_with_command(config.sendmail()),
+            ),
+        };
+        Ok(Self { transport, from })
+    }
+
+    /// Send `email` to each of `to` in one message.
+    pub async fn send(&self, to: &[String], email: &Email) -> Result<(), EmailError> {
+        if to.is_empty() {
+            return Err(EmailError::NoRecipients);
+        }
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(email.subject.as_str())
+            .header(ContentType::TEXT_PLAIN);
+        for address in to {
+            builder = builder.to(mailbox(address)?);
+        }
+        let message = builder.body(email.body.clone())?;
+
+        match &self.transport {
+            Transport::Smtp(smtp) => {
+                smtp.send(message).await?;
+            }
+            Transport::Sendmail(sendmail) => {
+                sendmail.send(message).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smtp(host: &str) -> EmailConfig {
+        EmailConfig {
+            smtp_host: Some(host.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_local() {
+        assert!(is_local("localhost"));
+        assert!(is_local("127.0.0.1"));
+        assert!(is_local("[::1]"));
+        assert!(!is_local("smtp.example.org"));
+        assert!(!is_local("10.0.0.5"));
+    }
+
+    #[tokio::test]
+    async fn test_remote_smtp_needs_opt_in_with_tor() {
+        let tor = PrivacyConfig::default();
+        assert!(matches!(
+            Mailer::from_config(&smtp("smtp.example.org"), &tor),
+            Err(EmailError::NotPrivate(_))
+        ));
+        assert!(Mailer::from_config(&smtp("localhost"), &tor).is_ok());
+        assert!(Mailer::from_config(&EmailConfig::default(), &tor).is_ok());
+
+        let mut opted_in = smtp("smtp.example.org");
+        opted_in.smtp_direct = Some(true);
+        assert!(Mailer::from_config(&opted_in, &tor).is_ok());
+
+        let direct = PrivacyConfig {
+            direct: true,
+            ..Default::default()
+        };
+        assert!(Mailer::from_config(&smtp("smtp.example.org"), &direct).is_ok());
+    }
+}
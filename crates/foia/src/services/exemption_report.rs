@@ -0,0 +1,253 @@
+//! FOIA exemption analytics.
+//!
+//! Summarizes the exemption codes cited in redactions across the archive:
+//! how often each is cited, by which agencies, in which years, and alongside
+//! which `topic:` tags. Built from the per-document counts kept in
+//! `document_exemptions` (see [`crate::models::count_exemptions`]).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{split_tag_namespace, ExemptionStats};
+use crate::repository::diesel_document::ExemptionCitationRow;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+
+/// Group for documents whose agency is unknown.
+pub const UNKNOWN_AGENCY: &str = "Unknown";
+
+/// How often one exemption is cited within one group of documents: an
+/// agency, a year or a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExemptionBreakdown {
+    pub group: String,
+    pub code: String,
+    pub documents: u64,
+    pub citations: u64,
+}
+
+/// Exemption citations across a set of documents.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExemptionReport {
+    /// Per code, most cited first.
+    pub totals: Vec<ExemptionStats>,
+    /// Ordered by agency, then code.
+    pub by_agency: Vec<ExemptionBreakdown>,
+    /// Ordered by year of the document date, then code. Undated documents
+    /// are left out.
+    pub by_year: Vec<ExemptionBreakdown>,
+    /// Ordered by topic, then code.
+    pub by_topic: Vec<ExemptionBreakdown>,
+    /// Documents citing any exemption.
+    pub documents: u64,
+}
+
+#[derive(Default)]
+struct Tally {
+    documents: BTreeSet<String>,
+    citations: u64,
+}
+
+fn add(tallies: &mut BTreeMap<(String, String), Tally>, group: &str, row: &ExemptionCitationRow) {
+    let tally = tallies
+        .entry((group.to_string(), row.code.clone()))
+        .or_default();
+    tally.documents.insert(row.document_id.clone());
+    tally.citations += row.citations as u64;
+}
+
+fn breakdown(tallies: BTreeMap<(String, String), Tally>) -> Vec<ExemptionBreakdown> {
+    tallies
+        .into_iter()
+        .map(|((group, code), tally)| ExemptionBreakdown {
+            group,
+            code,
+            documents: tally.documents.len() as u64,
+            citations: tally.citations,
+        })
+        .collect()
+}
+
+/// The year a timeline date falls in, if it starts with one.
+fn year(date: &str) -> Option<&str> {
+    let year = date.get(..4)?;
+    year.chars().all(|c| c.is_ascii_digit()).then_some(year)
+}
+
+/// Quote a CSV field if it needs it.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+impl ExemptionReport {
+    /// Build a report from per-document citation rows.
+    pub fn from_rows(rows: &[ExemptionCitationRow]) -> Self {
+        let mut totals = BTreeMap::new();
+        let mut by_agency = BTreeMap::new();
+        let mut by_year = BTreeMap::new();
+        let mut by_topic = BTreeMap::new();
+        let mut documents = BTreeSet::new();
+
+        for row in rows {
+            documents.insert(row.document_id.as_str());
+            add(&mut totals, "", row);
+            add(
+                &mut by_agency,
+                row.agency.as_deref().unwrap_or(UNKNOWN_AGENCY),
+                row,
+            );
+            if let Some(year) = row.date.as_deref().and_then(year) {
+                add(&mut by_year, year, row);
+            }
+            let topics: BTreeSet<&str> = row
+                .tags
+                .iter()
+                .filter_map(|t| split_tag_namespace(t))
+                .filter(|(namespace, _)| *namespace == "topic")
+                .map(|(_, topic)| topic)
+                .collect();
+            for topic in topics {
+                add(&mut by_topic, topic, row);
+            }
+        }
+
+        let mut totals: Vec<ExemptionStats> = breakdown(totals)
+            .into_iter()
+            .map(|b| ExemptionStats {
+                code: b.code,
+                documents: b.documents,
+                citations: b.citations,
+            })
+            .collect();
+        totals.sort_by(|a, b| b.citations.cmp(&a.citations).then(a.code.cmp(&b.code)));
+
+        Self {
+            totals,
+            by_agency: breakdown(by_agency),
+            by_year: breakdown(by_year),
+            by_topic: breakdown(by_topic),
+            documents: documents.len() as u64,
+        }
+    }
+
+    /// The report as CSV, one row per code and group, with a `dimension`
+    /// column of `total`, `agency`, `year` or `topic`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("dimension,group,code,documents,citations\n");
+        for t in &self.totals {
+            csv.push_str(&format!(
+                "total,,{},{},{}\n",
+                csv_field(&t.code),
+                t.documents,
+                t.citations
+            ));
+        }
+        for (dimension, rows) in [
+            ("agency", &self.by_agency),
+            ("year", &self.by_year),
+            ("topic", &self.by_topic),
+        ] {
+            for b in rows {
+                csv.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    dimension,
+                    csv_field(&b.group),
+                    csv_field(&b.code),
+                    b.documents,
+                    b.citations
+                ));
+            }
+        }
+        csv
+    }
+}
+
+/// Exemption analytics for the archive, or for one source.
+pub async fn exemption_report(
+    docs: &DieselDocumentRepository,
+    source_id: Option<&str>,
+) -> Result<ExemptionReport, DieselError> {
+    let rows = docs.exemption_citations(source_id).await?;
+    Ok(ExemptionReport::from_rows(&rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(
+        document_id: &str,
+        code: &str,
+        citations: u32,
+        agency: Option<&str>,
+        date: Option<&str>,
+        tags: &[&str],
+    ) -> ExemptionCitationRow {
+        ExemptionCitationRow {
+            document_id: document_id.to_string(),
+            code: code.to_string(),
+            citations,
+            agency: agency.map(str::to_string),
+            date: date.map(str::to_string),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_exemption_report() {
+        let rows = vec![
+            row(
+                "a",
+                "(b)(6)",
+                3,
+                Some("FBI"),
+                Some("1971-03-08"),
+                &["topic:cointelpro", "agency:fbi"],
+            ),
+            row(
+                "a",
+                "(b)(7)(C)",
+                1,
+                Some("FBI"),
+                Some("1971-03-08"),
+                &["topic:cointelpro", "agency:fbi"],
+            ),
+            row("b", "(b)(6)", 2, Some("FBI"), Some("1972-01-02"), &[]),
+            row("c", "(b)(1)", 1, None, None, &["topic:cuba, \"bay\""]),
+        ];
+        let report = ExemptionReport::from_rows(&rows);
+
+        assert_eq!(report.documents, 3);
+        assert_eq!(report.totals[0].code, "(b)(6)");
+        assert_eq!(
+            (report.totals[0].documents, report.totals[0].citations),
+            (2, 5)
+        );
+        assert_eq!(report.by_agency.len(), 3);
+        assert_eq!(report.by_agency[0].group, "FBI");
+        assert_eq!(report.by_agency[2].group, UNKNOWN_AGENCY);
+        let years: Vec<_> = report
+            .by_year
+            .iter()
+            .map(|b| (b.group.as_str(), b.code.as_str()))
+            .collect();
+        assert_eq!(
+            years,
+            vec![
+                ("1971", "(b)(6)"),
+                ("1971", "(b)(7)(C)"),
+                ("1972", "(b)(6)")
+            ]
+        );
+        assert_eq!(report.by_topic.len(), 3);
+        assert_eq!(report.by_topic[0].group, "cointelpro");
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("dimension,group,code,documents,citations\ntotal,,(b)(6),2,5\n"));
+        assert!(csv.contains("agency,FBI,(b)(6),2,5\n"));
+        assert!(csv.contains("topic,\"cuba, \"\"bay\"\"\",(b)(1),1,1\n"));
+    }
+}
@@ -0,0 +1,418 @@
+//! Failure triage: group failed crawl URLs and analysis results by cause.
+//!
+//! Stored errors are free-form strings from many layers (reqwest, HTTP status
+//! checks, external tools). Classifying them makes systemic problems, such as
+//! a DNS outage or a missing OCR tool, stand out from the long tail, and lets
+//! a whole class be retried at once.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::quarantine::QUARANTINE_ERROR_PREFIX;
+use crate::repository::pool::DieselError;
+use crate::repository::{parse_datetime_opt, DieselCrawlRepository, DieselDocumentRepository};
+
+/// Days of history shown in each group's trend.
+pub const TREND_DAYS: usize = 14;
+
+/// Most failures of each kind loaded for triage.
+const MAX_FAILURES: u32 = 100_000;
+
+/// Broad cause of a failure, derived from its error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorClass {
+    Dns,
+    Timeout,
+    Connection,
+    Tls,
+    Forbidden,
+    NotFound,
+    RateLimited,
+    ServerError,
+    HttpError,
+    Parse,
+    ToolMissing,
+    /// An error page was downloaded in place of the document.
+    Quarantined,
+    Other,
+}
+
+impl ErrorClass {
+    pub const ALL: [ErrorClass; 13] = [
+        Self::Dns,
+        Self::Timeout,
+        Self::Connection,
+        Self::Tls,
+        Self::Forbidden,
+        Self::NotFound,
+        Self::RateLimited,
+        Self::ServerError,
+        Self::HttpError,
+        Self::Parse,
+        Self::ToolMissing,
+        Self::Quarantined,
+        Self::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dns => "dns",
+            Self::Timeout => "timeout",
+            Self::Connection => "connection",
+            Self::Tls => "tls",
+            Self::Forbidden => "forbidden",
+            Self::NotFound => "not_found",
+            Self::RateLimited => "rate_limited",
+            Self::ServerError => "server_error",
+            Self::HttpError => "http_error",
+            Self::Parse => "parse",
+            Self::ToolMissing => "tool_missing",
+            Self::Quarantined => "quarantined",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == s)
+    }
+
+    /// Human-readable label.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Dns => "DNS lookup",
+            Self::Timeout => "Timeout",
+            Self::Connection => "Connection",
+            Self::Tls => "TLS/certificate",
+            Self::Forbidden => "403 Forbidden",
+            Self::NotFound => "404 Not Found",
+            Self::RateLimited => "429 Rate limited",
+            Self::ServerError => "5xx Server error",
+            Self::HttpError => "Other HTTP error",
+            Self::Parse => "Parse",
+            Self::ToolMissing => "Tool missing",
+            Self::Quarantined => "Error page quarantined",
+            Self::Other => "Other",
+        }
+    }
+
+    /// Classify an error message.
+    pub fn classify(error: &str) -> Self {
+        let e = error.to_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|n| e.contains(n));
+
+        // The page's own wording ("not found page") mustn't be read as a cause
+        if e.starts_with(QUARANTINE_ERROR_PREFIX) {
+            return Self::Quarantined;
+        }
+        match http_status(&e) {
+            Some(401 | 403) => return Self::Forbidden,
+            Some(404 | 410) => return Self::NotFound,
+            Some(429) => return Self::RateLimited,
+            Some(500..=599) => return Self::ServerError,
+            Some(_) => return Self::HttpError,
+            None => {}
+        }
+
+        if has(&["rate limited", "too many requests"]) {
+            Self::RateLimited
+        } else if has(&[
+            "dns error",
+            "failed to lookup address",
+            "name or service not known",
+            "no such host",
+            "nodename nor servname",
+        ]) {
+            Self::Dns
+        } else if has(&["timed out", "timeout", "deadline has elapsed"]) {
+            Self::Timeout
+        } else if has(&["certificate", "tls", "ssl", "handshake"]) {
+            Self::Tls
+        } else if has(&[
+            "connection refused",
+            "connection reset",
+            "connection closed",
+            "connect error",
+            "error sending request",
+            "broken pipe",
+            "unreachable",
+        ]) {
+            Self::Connection
+        } else if has(&[
+            "tool not found",
+            "not found. install",
+            "not found (install",
+            "not found at '",
+            "not installed",
+            "backend not available",
+            "model not found",
+            "no such file or directory",
+        ]) {
+            Self::ToolMissing
+        } else if has(&[
+            "parse",
+            "invalid",
+            "malformed",
+            "decode",
+            "unexpected",
+            "utf-8",
+            "json",
+        ]) {
+            Self::Parse
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Find an HTTP error status (400-599) in a lowercased message, e.g.
+/// `"HTTP 404"`, `"status: 503"` or `"client error (403 Forbidden)"`.
+fn http_status(e: &str) -> Option<u16> {
+    let bytes = e.as_bytes();
+    let near = |before: &[u8], needle: &[u8]| before.windows(needle.len()).any(|w| w == needle);
+
+    for i in 0..bytes.len().saturating_sub(2) {
+        let digits = &bytes[i..i + 3];
+        let standalone = digits.iter().all(u8::is_ascii_digit)
+            && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric())
+            && !bytes.get(i + 3).is_some_and(u8::is_ascii_alphanumeric);
+        if !standalone {
+            continue;
+        }
+        let code = digits
+            .iter()
+            .fold(0u16, |n, d| n * 10 + u16::from(d - b'0'));
+        let before = &bytes[i.saturating_sub(14)..i];
+        if (400..=599).contains(&code)
+            && (near(before, b"http") || near(before, b"status") || before.ends_with(b"("))
+        {
+            return Some(code);
+        }
+    }
+    None
+}
+
+/// Which queue a failure belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Failed or exhausted crawl URLs.
+    Crawl,
+    /// Failed rows in `document_analysis_results`.
+    Analysis,
+}
+
+impl FailureKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Crawl => "crawl",
+            Self::Analysis => "analysis",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "crawl" => Some(Self::Crawl),
+            "analysis" => Some(Self::Analysis),
+            _ => None,
+        }
+    }
+}
+
+/// Failures of one class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureGroup {
+    pub class: ErrorClass,
+    pub count: u64,
+    /// Failures per day over the last [`TREND_DAYS`] days, oldest first.
+    pub daily: Vec<u64>,
+    /// Most recent error message in this class.
+    pub sample: String,
+}
+
+/// Failed crawl URLs and analysis results, grouped by class.
+#[derive(Debug, Clone, Default)]
+pub struct FailureReport {
+    pub crawl: Vec<FailureGroup>,
+    pub analysis: Vec<FailureGroup>,
+}
+
+/// Group `(error, failed_at)` pairs, newest first, by class; largest group first.
+fn group<'a>(
+    failures: impl IntoIterator<Item = (&'a str, Option<DateTime<Utc>>)>,
+    now: DateTime<Utc>,
+) -> Vec<FailureGroup> {
+    let today = now.date_naive();
+    let mut groups: HashMap<ErrorClass, FailureGroup> = HashMap::new();
+
+    for (error, failed_at) in failures {
+        let class = ErrorClass::classify(error);
+        let group = groups.entry(class).or_insert_with(|| FailureGroup {
+            class,
+            count: 0,
+            daily: vec![0; TREND_DAYS],
+            sample: error.to_string(),
+        });
+        group.count += 1;
+        if let Some(at) = failed_at {
+            let age = (today - at.date_naive()).num_days();
+            if (0..TREND_DAYS as i64).contains(&age) {
+                group.daily[TREND_DAYS - 1 - age as usize] += 1;
+            }
+        }
+    }
+
+    let mut groups: Vec<FailureGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then(a.class.cmp(&b.class)));
+    groups
+}
+
+/// Build the failure report, optionally for one source.
+pub async fn failure_report(
+    crawl_repo: &DieselCrawlRepository,
+    doc_repo: &DieselDocumentRepository,
+    source_id: Option<&str>,
+) -> Result<FailureReport, DieselError> {
+    let urls = crawl_repo.get_failed_urls(source_id, MAX_FAILURES).await?;
+    let results = doc_repo
+        .get_failed_analysis(source_id, MAX_FAILURES)
+        .await?;
+    let now = Utc::now();
+
+    Ok(FailureReport {
+        crawl: group(
+            urls.iter()
+                .map(|u| (u.last_error.as_deref().unwrap_or(""), u.failed_at())),
+            now,
+        ),
+        analysis: group(
+            results.iter().map(|r| {
+                (
+                    r.error.as_deref().unwrap_or(""),
+                    parse_datetime_opt(Some(r.created_at.clone())),
+                )
+            }),
+            now,
+        ),
+    })
+}
+
+/// Re-queue every failure of `class`, for one kind or both.
+///
+/// Crawl URLs go back to `discovered` with a clean retry count; failed
+/// analysis results are deleted so the documents are picked up again.
+/// Returns the number of failures retried.
+pub async fn retry_class(
+    crawl_repo: &DieselCrawlRepository,
+    doc_repo: &DieselDocumentRepository,
+    class: ErrorClass,
+    kind: Option<FailureKind>,
+    source_id: Option<&str>,
+) -> Result<u64, DieselError> {
+    let mut retried = 0;
+
+    if kind != Some(FailureKind::Analysis) {
+        let mut by_source: HashMap<String, Vec<String>> = HashMap::new();
+        for u in crawl_repo.get_failed_urls(source_id, MAX_FAILURES).await? {
+            if ErrorClass::classify(u.last_error.as_deref().unwrap_or("")) == class {
+                by_source.entry(u.source_id).or_default().push(u.url);
+            }
+        }
+        for (source, urls) in &by_source {
+            retried += crawl_repo.retry_urls(source, urls).await?;
+        }
+    }
+
+    if kind != Some(FailureKind::Crawl) {
+        let ids: Vec<i64> = doc_repo
+            .get_failed_analysis(source_id, MAX_FAILURES)
+            .await?
+            .into_iter()
+            .filter(|r| ErrorClass::classify(r.error.as_deref().unwrap_or("")) == class)
+            .map(|r| r.id)
+            .collect();
+        retried += doc_repo.delete_analysis_results_by_id(&ids).await?;
+    }
+
+    Ok(retried)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            ("HTTP 404", ErrorClass::NotFound),
+            ("HTTP 403", ErrorClass::Forbidden),
+            ("Rate limited (HTTP 429)", ErrorClass::RateLimited),
+            ("HTTP 503", ErrorClass::ServerError),
+            ("HTTP 418", ErrorClass::HttpError),
+            (
+                "HTTP status client error (404 Not Found) for url (https://a.gov/x)",
+                ErrorClass::NotFound,
+            ),
+            (
+                "error sending request for url (https://a.gov/): client error (Connect): dns error: failed to lookup address information",
+                ErrorClass::Dns,
+            ),
+            ("operation timed out", ErrorClass::Timeout),
+            (
+                "error sending request for url (https://a.gov/): connection refused",
+                ErrorClass::Connection,
+            ),
+            ("invalid peer certificate: Expired", ErrorClass::Tls),
+            (
+                "Backend not available: tesseract not found (install tesseract-ocr)",
+                ErrorClass::ToolMissing,
+            ),
+            ("External tool not found: pdftotext", ErrorClass::ToolMissing),
+            ("Failed to parse PDF: unexpected EOF", ErrorClass::Parse),
+            ("quarantined: not found page", ErrorClass::Quarantined),
+            ("something odd", ErrorClass::Other),
+            ("", ErrorClass::Other),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(ErrorClass::classify(error), expected, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_http_status_needs_context() {
+        // A bare number in a URL or size is not a status code
+        assert_eq!(http_status("failed to fetch /files/404.pdf"), None);
+        assert_eq!(http_status("read 500 bytes"), None);
+        assert_eq!(http_status("status: 502 bad gateway"), Some(502));
+    }
+
+    #[test]
+    fn test_group_counts_and_trend() {
+        let now = Utc::now();
+        let groups = group(
+            [
+                ("HTTP 404", Some(now)),
+                ("HTTP 404", Some(now - Duration::days(1))),
+                ("HTTP 404", Some(now - Duration::days(60))),
+                ("operation timed out", None),
+            ],
+            now,
+        );
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].class, ErrorClass::NotFound);
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].daily[TREND_DAYS - 1], 1);
+        assert_eq!(groups[0].daily[TREND_DAYS - 2], 1);
+        assert_eq!(groups[0].daily.iter().sum::<u64>(), 2);
+        assert_eq!(groups[1].class, ErrorClass::Timeout);
+        assert_eq!(groups[1].sample, "operation timed out");
+    }
+
+    #[test]
+    fn test_class_roundtrip() {
+        for class in ErrorClass::ALL {
+            assert_eq!(ErrorClass::from_str(class.as_str()), Some(class));
+        }
+    }
+}
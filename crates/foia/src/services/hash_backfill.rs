@@ -0,0 +1,172 @@
+//! BLAKE3 hashes for versions stored before BLAKE3.
+//!
+//! Older versions carry only a SHA-256, which stays their `content_hash`
+//! because stored files are named by it. Deduplication matches on BLAKE3,
+//! so the backfill reads each such file once and records its BLAKE3.
+
+use std::path::Path;
+
+use tracing::warn;
+
+use crate::models::DocumentVersion;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+
+/// What a hash backfill did, or would do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HashBackfill {
+    /// Versions without a BLAKE3 hash.
+    pub versions: u64,
+    /// Versions given one.
+    pub hashed: u64,
+    /// Versions whose file is missing or unreadable; they keep only SHA-256.
+    pub missing: u64,
+}
+
+/// Hash every version's file that lacks a BLAKE3 hash, `batch_size`
+/// versions at a time. With `dry_run`, only check which files are there.
+pub async fn backfill_hashes(
+    docs: &DieselDocumentRepository,
+    documents_dir: &Path,
+    batch_size: usize,
+    dry_run: bool,
+) -> Result<HashBackfill, DieselError> {
+    let mut result = HashBackfill::default();
+    let mut cursor: i64 = 0;
+
+    loop {
+        let batch = docs
+            .get_versions_missing_blake3(cursor, batch_size.max(1))
+            .await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        for (version, source_url, title) in &batch {
+            cursor = version.id;
+            result.versions += 1;
+
+            let path = version.resolve_path(documents_dir, source_url, title);
+            if dry_run {
+                if path.is_file() {
+                    result.hashed += 1;
+                } else {
+                    result.missing += 1;
+                }
+                continue;
+            }
+
+            let hashed = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || std::fs::File::open(path).and_then(DocumentVersion::compute_hash_reader)
+            })
+            .await;
+            match hashed {
+                Ok(Ok(hash)) => {
+                    docs.set_version_blake3(version.id, &hash).await?;
+                    result.hashed += 1;
+                }
+                Ok(Err(e)) => {
+                    warn!("Cannot hash {}: {}", path.display(), e);
+                    result.missing += 1;
+                }
+                Err(e) => {
+                    warn!("Hash task for {} failed: {}", path.display(), e);
+                    result.missing += 1;
+                }
+            }
+        }
+
+        if batch.len() < batch_size {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Document;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    /// A document whose version predates BLAKE3.
+    fn legacy_document(id: &str, content: &[u8]) -> Document {
+        let mut version = DocumentVersion::new(content, "application/pdf".to_string(), None);
+        version.content_hash = DocumentVersion::compute_hash_sha256(content);
+        version.content_hash_blake3 = None;
+        Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            id.to_string(),
+            format!("https://vault.fbi.gov/{}.pdf", id),
+            version,
+            serde_json::json!({}),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_backfill_hashes() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let docs = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .documents();
+        let documents_dir = dir.path().join("documents");
+
+        let stored = legacy_document("stored", b"stored content");
+        let version = stored.current_version().unwrap();
+        let path = version.resolve_path(&documents_dir, &stored.source_url, &stored.title);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, b"stored content").unwrap();
+        docs.save_with_versions(&stored).await.unwrap();
+        docs.save_with_versions(&legacy_document("lost", b"lost content"))
+            .await
+            .unwrap();
+
+        let planned = backfill_hashes(&docs, &documents_dir, 1, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            planned,
+            HashBackfill {
+                versions: 2,
+                hashed: 1,
+                missing: 1,
+            }
+        );
+        assert_eq!(docs.count_versions_missing_blake3().await.unwrap(), 2);
+
+        let done = backfill_hashes(&docs, &documents_dir, 1, false)
+            .await
+            .unwrap();
+        assert_eq!(done, planned);
+        let version = docs
+            .get("stored")
+            .await
+            .unwrap()
+            .unwrap()
+            .current_version()
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            version.content_hash,
+            DocumentVersion::compute_hash_sha256(b"stored content")
+        );
+        assert_eq!(
+            version.content_hash_blake3.as_deref(),
+            Some(DocumentVersion::compute_hash(b"stored content").as_str())
+        );
+        assert_eq!(
+            Some(version.dedup_hash()),
+            version.content_hash_blake3.as_deref()
+        );
+        assert_eq!(docs.count_versions_missing_blake3().await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,184 @@
+//! Known-file hash lists.
+//!
+//! Reading rooms repeat the same boilerplate across thousands of releases:
+//! the agency seal, a blank separator page, the blank request form. Hash
+//! lists in the style of NIST's NSRL name such files so downloads matching
+//! them are dropped before they're stored. A keep list overrides the skip
+//! lists for files that must be stored anyway.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::config::KnownFilesConfig;
+use crate::models::ChecksumAlgorithm;
+
+/// Hex digests read from a hash list: the fields of each line that are
+/// MD5, SHA-1, SHA-256/BLAKE3 or SHA-512 digests. Blank lines, `#`
+/// comments, CSV headers and other fields (filenames, CRC32s, sizes) are
+/// ignored, so `sha256sum` output and NSRL RDS CSV files both parse.
+pub fn parse_hash_list(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| {
+            line.split(|c: char| c == ',' || c.is_whitespace())
+                .map(|field| field.trim_matches('"').trim_start_matches('*'))
+                .filter(|field| {
+                    ChecksumAlgorithm::from_hex_len(field.len()).is_some()
+                        && field.chars().all(|c| c.is_ascii_hexdigit())
+                })
+                .map(str::to_ascii_lowercase)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Skip and keep lists, indexed by digest.
+#[derive(Debug, Default)]
+pub struct KnownFiles {
+    /// Digest to the name of the skip list it came from.
+    skip: HashMap<String, String>,
+    keep: HashSet<String>,
+}
+
+impl KnownFiles {
+    /// Load the lists `config` names. Relative paths must already be
+    /// resolved.
+    pub fn load(config: &KnownFilesConfig) -> Result<Self, String> {
+        let mut known = Self::default();
+        let read = |path: &str| {
+            std::fs::read_to_string(path)
+                .map(|text| parse_hash_list(&text))
+                .map_err(|e| format!("Failed to read hash list {}: {}", path, e))
+        };
+        for path in &config.skip {
+            let name = Path::new(path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.clone());
+            known.add_skip(&name, read(path)?);
+        }
+        for path in &config.keep {
+            known.add_keep(read(path)?);
+        }
+        Ok(known)
+    }
+
+    /// Add digests of files not to store, under a list name for reports.
+    pub fn add_skip(&mut self, list: &str, digests: impl IntoIterator<Item = String>) {
+        for digest in digests {
+            self.skip
+                .entry(digest.to_ascii_lowercase())
+                .or_insert_with(|| list.to_string());
+        }
+    }
+
+    /// Add digests of files always stored.
+    pub fn add_keep(&mut self, digests: impl IntoIterator<Item = String>) {
+        self.keep
+            .extend(digests.into_iter().map(|d| d.to_ascii_lowercase()));
+    }
+
+    /// Whether there are no skip lists to check.
+    pub fn is_empty(&self) -> bool {
+        self.skip.is_empty()
+    }
+
+    /// Algorithms the lists use, other than the BLAKE3 hash every
+    /// download already has. 64-digit digests may be either BLAKE3 or
+    /// SHA-256, so both are checked.
+    fn algorithms(&self) -> Vec<ChecksumAlgorithm> {
+        let mut algorithms: Vec<ChecksumAlgorithm> = self
+            .skip
+            .keys()
+            .chain(&self.keep)
+            .filter_map(|d| ChecksumAlgorithm::from_hex_len(d.len()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        algorithms.sort_by_key(|a| a.as_str());
+        algorithms
+    }
+
+    /// The skip list naming a file with these digests, unless a keep list
+    /// has it.
+    fn check(&self, digests: &[String]) -> Option<&str> {
+        if digests.iter().any(|d| self.keep.contains(d)) {
+            return None;
+        }
+        digests
+            .iter()
+            .find_map(|d| self.skip.get(d))
+            .map(String::as_str)
+    }
+
+    /// The skip list naming `content`, whose BLAKE3 hash is `blake3`.
+    pub fn match_content(&self, content: &[u8], blake3: &str) -> Option<&str> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut digests = vec![blake3.to_ascii_lowercase()];
+        digests.extend(self.algorithms().iter().map(|a| a.hash_bytes(content)));
+        self.check(&digests)
+    }
+
+    /// [`Self::match_content`] for a file on disk. Blocks; call from a
+    /// blocking context.
+    pub fn match_file(&self, path: &Path, blake3: &str) -> std::io::Result<Option<&str>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut digests = vec![blake3.to_ascii_lowercase()];
+        for algorithm in self.algorithms() {
+            digests.push(algorithm.hash_reader(std::fs::File::open(path)?)?);
+        }
+        Ok(self.check(&digests))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DocumentVersion;
+
+    const LOGO: &[u8] = b"agency seal";
+    const MEMO: &[u8] = b"memorandum";
+
+    fn matches<'a>(known: &'a KnownFiles, content: &[u8]) -> Option<&'a str> {
+        known.match_content(content, &DocumentVersion::compute_hash(content))
+    }
+
+    #[test]
+    fn test_parse_hash_list() {
+        let md5 = ChecksumAlgorithm::Md5.hash_bytes(LOGO);
+        let sha1 = ChecksumAlgorithm::Sha1.hash_bytes(LOGO).to_uppercase();
+        let text = format!(
+            "# agency logos\n{}  seal.png\n\n\"SHA-1\",\"MD5\",\"CRC32\",\"FileName\"\n\"{}\",\"{}\",\"0BADF00D\",\"seal.png\"\nnot a digest\n",
+            md5, sha1, md5
+        );
+        let digests = parse_hash_list(&text);
+        assert_eq!(digests.len(), 3);
+        assert_eq!(digests[1], sha1.to_lowercase());
+    }
+
+    #[test]
+    fn test_skip_and_keep() {
+        let mut known = KnownFiles::default();
+        assert_eq!(matches(&known, LOGO), None);
+
+        known.add_skip("logos", vec![ChecksumAlgorithm::Sha256.hash_bytes(LOGO)]);
+        known.add_skip("blank", vec![DocumentVersion::compute_hash(MEMO)]);
+        assert_eq!(matches(&known, LOGO), Some("logos"));
+        assert_eq!(matches(&known, MEMO), Some("blank"));
+        assert_eq!(matches(&known, b"other"), None);
+
+        known.add_keep(vec![ChecksumAlgorithm::Md5.hash_bytes(MEMO)]);
+        assert_eq!(matches(&known, MEMO), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seal.png");
+        std::fs::write(&path, LOGO).unwrap();
+        let blake3 = DocumentVersion::compute_hash(LOGO);
+        assert_eq!(known.match_file(&path, &blake3).unwrap(), Some("logos"));
+    }
+}
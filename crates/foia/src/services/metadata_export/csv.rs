@@ -0,0 +1,75 @@
+//! CSV table writer (RFC 4180).
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::SecondsFormat;
+
+use super::{MetadataExportError, TableSchema, TableWriter, Value};
+
+pub(super) struct CsvWriter {
+    out: BufWriter<File>,
+}
+
+impl CsvWriter {
+    pub(super) fn create(table: &TableSchema, path: &Path) -> Result<Self, MetadataExportError> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let header: Vec<&str> = table.columns.iter().map(|c| c.name).collect();
+        writeln!(out, "{}", header.join(","))?;
+        Ok(Self { out })
+    }
+}
+
+impl TableWriter for CsvWriter {
+    fn write_row(&mut self, row: Vec<Value>) -> Result<(), MetadataExportError> {
+        let line: Vec<String> = row.iter().map(format_value).collect();
+        writeln!(self.out, "{}", line.join(","))?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), MetadataExportError> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Format a cell, leaving NULL as an empty field.
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Text(s) => escape(s),
+        Value::Integer(n) => n.to_string(),
+        Value::Float(f) => f.to_string(),
+        Value::Timestamp(t) => t.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+/// Quote a field if it contains a delimiter, quote or line break.
+fn escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(&Value::Null), "");
+        assert_eq!(format_value(&Value::Text("plain".into())), "plain");
+        assert_eq!(
+            format_value(&Value::Text("a, \"b\"\nc".into())),
+            "\"a, \"\"b\"\"\nc\""
+        );
+        assert_eq!(format_value(&Value::Float(0.25)), "0.25");
+        let t = chrono::Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(format_value(&Value::Timestamp(t)), "2024-01-02T03:04:05Z");
+    }
+}
@@ -0,0 +1,446 @@
+//! Metadata export for data analysis.
+//!
+//! Writes the corpus as flat tables (documents, versions, pages, tags,
+//! entities) that load directly into pandas, DuckDB or R. The database
+//! schema is internal and changes between releases; the tables here are
+//! the stable interface, so columns are only ever added, never renamed or
+//! removed.
+//!
+//! Each table is written as `<dir>/<table>.csv` or `<dir>/<table>.parquet`.
+//! Parquet output requires the `parquet-export` feature.
+
+mod csv;
+#[cfg(feature = "parquet-export")]
+mod parquet;
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::models::DocumentPage;
+use crate::repository::models::DocumentRecord;
+use crate::repository::pool::DieselError;
+use crate::repository::{parse_datetime, DieselDocumentRepository};
+use ColumnKind::{Float, Integer, Text, Timestamp};
+
+/// Documents loaded per database round trip.
+const BATCH_SIZE: u32 = 500;
+
+#[derive(Debug, Error)]
+pub enum MetadataExportError {
+    #[error("Database error: {0}")]
+    Database(#[from] DieselError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Format(String),
+}
+
+/// Output file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Type of a column, mapped to the closest native type of each format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    Text,
+    Integer,
+    Float,
+    /// UTC timestamp; RFC 3339 in CSV.
+    Timestamp,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnKind,
+}
+
+const fn col(name: &'static str, kind: ColumnKind) -> Column {
+    Column { name, kind }
+}
+
+/// An export table and its columns, in output order.
+#[derive(Debug, Clone, Copy)]
+pub struct TableSchema {
+    pub name: &'static str,
+    pub columns: &'static [Column],
+}
+
+pub const DOCUMENTS: TableSchema = TableSchema {
+    name: "documents",
+    columns: &[
+        col("document_id", Text),
+        col("source_id", Text),
+        col("title", Text),
+        col("source_url", Text),
+        col("status", Text),
+        col("discovery_method", Text),
+        col("created_at", Timestamp),
+        col("updated_at", Timestamp),
+        col("synopsis", Text),
+        col("category", Text),
+        col("record_type", Text),
+        col("record_type_confidence", Float),
+        col("document_date", Text),
+        col("estimated_date", Text),
+        col("date_confidence", Text),
+        col("manual_date", Text),
+        col("originating_agency", Text),
+        col("originating_office", Text),
+        col("case_number", Text),
+        col("classification_markings", Text),
+        col("version_count", Integer),
+        col("text_chars", Integer),
+    ],
+};
+
+pub const VERSIONS: TableSchema = TableSchema {
+    name: "versions",
+    columns: &[
+        col("version_id", Integer),
+        col("document_id", Text),
+        col("sha256", Text),
+        col("blake3", Text),
+        col("file_size", Integer),
+        col("mime_type", Text),
+        col("acquired_at", Timestamp),
+        col("source_url", Text),
+        col("original_filename", Text),
+        col("server_date", Timestamp),
+        col("page_count", Integer),
+        col("earliest_archived_at", Timestamp),
+    ],
+};
+
+pub const PAGES: TableSchema = TableSchema {
+    name: "pages",
+    columns: &[
+        col("document_id", Text),
+        col("version_id", Integer),
+        col("page_number", Integer),
+        col("ocr_status", Text),
+        col("ocr_quality", Float),
+        col("pdf_text_chars", Integer),
+        col("ocr_text_chars", Integer),
+        col("text_chars", Integer),
+    ],
+};
+
+pub const TAGS: TableSchema = TableSchema {
+    name: "tags",
+    columns: &[
+        col("document_id", Text),
+        col("tag", Text),
+        col("namespace", Text),
+    ],
+};
+
+pub const ENTITIES: TableSchema = TableSchema {
+    name: "entities",
+    columns: &[
+        col("document_id", Text),
+        col("entity_type", Text),
+        col("entity_text", Text),
+        col("normalized_text", Text),
+        col("latitude", Float),
+        col("longitude", Float),
+    ],
+};
+
+/// Every export table, in the order they are written.
+pub const TABLES: [TableSchema; 5] = [DOCUMENTS, VERSIONS, PAGES, TAGS, ENTITIES];
+
+/// One cell of an export row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Timestamp(DateTime<Utc>),
+}
+
+impl From<Option<String>> for Value {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(Value::Null, Value::Text)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+fn chars(text: Option<&str>) -> Value {
+    text.map_or(Value::Null, |t| Value::Integer(t.chars().count() as i64))
+}
+
+/// Sink for the rows of one table.
+trait TableWriter: Send {
+    fn write_row(&mut self, row: Vec<Value>) -> Result<(), MetadataExportError>;
+    fn finish(self: Box<Self>) -> Result<(), MetadataExportError>;
+}
+
+fn open_writer(
+    table: &TableSchema,
+    path: &Path,
+    format: ExportFormat,
+) -> Result<Box<dyn TableWriter>, MetadataExportError> {
+    match format {
+        ExportFormat::Csv => Ok(Box::new(csv::CsvWriter::create(table, path)?)),
+        #[cfg(feature = "parquet-export")]
+        ExportFormat::Parquet => Ok(Box::new(parquet::ParquetWriter::create(table, path)?)),
+        #[cfg(not(feature = "parquet-export"))]
+        ExportFormat::Parquet => Err(MetadataExportError::Format(
+            "Parquet export requires the parquet-export feature".to_string(),
+        )),
+    }
+}
+
+/// A written table.
+#[derive(Debug, Clone)]
+pub struct ExportedTable {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub rows: u64,
+}
+
+struct Output {
+    writer: Box<dyn TableWriter>,
+    table: ExportedTable,
+}
+
+impl Output {
+    fn write(&mut self, row: Vec<Value>) -> Result<(), MetadataExportError> {
+        self.table.rows += 1;
+        self.writer.write_row(row)
+    }
+}
+
+/// Export document metadata, optionally for one source, into `dir`.
+///
+/// Walks the documents table in ID order so memory stays flat however
+/// large the corpus is.
+pub async fn export_metadata(
+    repo: &DieselDocumentRepository,
+    source_id: Option<&str>,
+    dir: &Path,
+    format: ExportFormat,
+) -> Result<Vec<ExportedTable>, MetadataExportError> {
+    std::fs::create_dir_all(dir)?;
+    let mut outputs = Vec::with_capacity(TABLES.len());
+    for table in &TABLES {
+        let path = dir.join(format!("{}.{}", table.name, format.extension()));
+        outputs.push(Output {
+            writer: open_writer(table, &path, format)?,
+            table: ExportedTable {
+                name: table.name,
+                path,
+                rows: 0,
+            },
+        });
+    }
+    let [documents, versions, pages, tags, entities] = &mut outputs[..] else {
+        unreachable!("one output per table");
+    };
+
+    let mut after: Option<String> = None;
+    loop {
+        let records = repo
+            .get_records_after(source_id, after.as_deref(), BATCH_SIZE)
+            .await?;
+        let Some(last) = records.last() else {
+            break;
+        };
+        after = Some(last.id.clone());
+
+        let ids: Vec<String> = records.iter().map(|r| r.id.clone()).collect();
+        let mut version_map = repo.load_versions_batch(&ids).await?;
+        let mut entity_map = repo.get_entities_batch(&ids).await?;
+
+        for record in records {
+            let doc_versions = version_map.remove(&record.id).unwrap_or_default();
+            let doc_id = record.id.clone();
+
+            for tag in parse_tags(&record) {
+                let namespace = tag.split_once(':').map(|(ns, _)| ns.to_string());
+                tags.write(vec![doc_id.clone().into(), tag.into(), namespace.into()])?;
+            }
+            for version in &doc_versions {
+                versions.write(vec![
+                    Value::Integer(version.id),
+                    doc_id.clone().into(),
+                    version.sha256().map(str::to_string).into(),
+                    version.content_hash_blake3.clone().into(),
+                    Value::Integer(version.file_size as i64),
+                    version.mime_type.clone().into(),
+                    Value::Timestamp(version.acquired_at),
+                    version.source_url.clone().into(),
+                    version.original_filename.clone().into(),
+                    version.server_date.map_or(Value::Null, Value::Timestamp),
+                    version
+                        .page_count
+                        .map_or(Value::Null, |n| Value::Integer(n as i64)),
+                    version
+                        .earliest_archived_at
+                        .map_or(Value::Null, Value::Timestamp),
+                ])?;
+            }
+            for entity in entity_map.remove(&doc_id).unwrap_or_default() {
+                entities.write(vec![
+                    doc_id.clone().into(),
+                    entity.entity_type.into(),
+                    entity.entity_text.into(),
+                    entity.normalized_text.into(),
+                    entity.latitude.map_or(Value::Null, Value::Float),
+                    entity.longitude.map_or(Value::Null, Value::Float),
+                ])?;
+            }
+            documents.write(document_row(record, doc_versions.len()))?;
+        }
+
+        for page in repo.get_pages_batch(&ids).await? {
+            pages.write(page_row(page))?;
+        }
+    }
+
+    let mut tables = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        output.writer.finish()?;
+        tables.push(output.table);
+    }
+    Ok(tables)
+}
+
+/// Tags are a JSON array; a malformed value exports as no tags rather
+/// than failing the whole export.
+fn parse_tags(record: &DocumentRecord) -> Vec<String> {
+    record
+        .tags
+        .as_deref()
+        .and_then(|s| serde_json::from_str(s).ok())
+        .unwrap_or_default()
+}
+
+fn document_row(record: DocumentRecord, version_count: usize) -> Vec<Value> {
+    let text_chars = chars(record.extracted_text.as_deref());
+    vec![
+        record.id.into(),
+        record.source_id.into(),
+        record.title.into(),
+        record.source_url.into(),
+        record.status.into(),
+        record.discovery_method.into(),
+        Value::Timestamp(parse_datetime(&record.created_at)),
+        Value::Timestamp(parse_datetime(&record.updated_at)),
+        record.synopsis.into(),
+        record.category_id.into(),
+        record.record_type.into(),
+        record
+            .record_type_confidence
+            .map_or(Value::Null, |c| Value::Float(c as f64)),
+        record.document_date.into(),
+        record.estimated_date.into(),
+        record.date_confidence.into(),
+        record.manual_date.into(),
+        record.originating_agency.into(),
+        record.originating_office.into(),
+        record.case_number.into(),
+        record.classification_markings.into(),
+        Value::Integer(version_count as i64),
+        text_chars,
+    ]
+}
+
+fn page_row(page: DocumentPage) -> Vec<Value> {
+    vec![
+        page.document_id.into(),
+        Value::Integer(page.version_id),
+        Value::Integer(page.page_number as i64),
+        page.ocr_status.as_str().to_string().into(),
+        page.ocr_quality
+            .map_or(Value::Null, |q| Value::Float(q as f64)),
+        chars(page.pdf_text.as_deref()),
+        chars(page.ocr_text.as_deref()),
+        chars(page.final_text.as_deref()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_match_schema() {
+        let record = DocumentRecord {
+            id: "doc-1".to_string(),
+            source_id: "fbi".to_string(),
+            title: "Report".to_string(),
+            source_url: "https://example.gov/report.pdf".to_string(),
+            extracted_text: Some("héllo".to_string()),
+            status: "indexed".to_string(),
+            metadata: "{}".to_string(),
+            created_at: "2024-01-02T03:04:05+00:00".to_string(),
+            updated_at: "2024-01-02T03:04:05+00:00".to_string(),
+            synopsis: None,
+            tags: Some(r#"["topic:cia","memo"]"#.to_string()),
+            estimated_date: None,
+            date_confidence: None,
+            date_source: None,
+            manual_date: None,
+            discovery_method: "crawl".to_string(),
+            category_id: Some("documents".to_string()),
+            record_type: None,
+            record_type_confidence: Some(0.5),
+            document_date: None,
+            originating_agency: None,
+            originating_office: None,
+            case_number: None,
+            classification_markings: None,
+        };
+        assert_eq!(parse_tags(&record), vec!["topic:cia", "memo"]);
+
+        let row = document_row(record, 2);
+        assert_eq!(row.len(), DOCUMENTS.columns.len());
+        assert_eq!(row[0], Value::Text("doc-1".to_string()));
+        assert_eq!(row[8], Value::Null);
+        assert_eq!(row[20], Value::Integer(2));
+        assert_eq!(row[21], Value::Integer(5));
+
+        let page = DocumentPage::new("doc-1".to_string(), 1, 3);
+        assert_eq!(page_row(page).len(), PAGES.columns.len());
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!(ExportFormat::from_str("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(
+            ExportFormat::from_str("parquet"),
+            Some(ExportFormat::Parquet)
+        );
+        assert_eq!(ExportFormat::from_str("xlsx"), None);
+    }
+}
@@ -0,0 +1,122 @@
+//! Parquet table writer.
+
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{
+    ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use super::{ColumnKind, MetadataExportError, TableSchema, TableWriter, Value};
+
+/// Rows buffered per row group.
+const ROW_GROUP_SIZE: usize = 8192;
+
+pub(super) struct ParquetWriter {
+    table: TableSchema,
+    schema: SchemaRef,
+    writer: ArrowWriter<File>,
+    rows: Vec<Vec<Value>>,
+}
+
+fn format_error(e: impl std::fmt::Display) -> MetadataExportError {
+    MetadataExportError::Format(format!("Parquet error: {}", e))
+}
+
+impl ParquetWriter {
+    pub(super) fn create(table: &TableSchema, path: &Path) -> Result<Self, MetadataExportError> {
+        let fields: Vec<Field> = table
+            .columns
+            .iter()
+            .map(|c| {
+                let data_type = match c.kind {
+                    ColumnKind::Text => DataType::Utf8,
+                    ColumnKind::Integer => DataType::Int64,
+                    ColumnKind::Float => DataType::Float64,
+                    ColumnKind::Timestamp => {
+                        DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+                    }
+                };
+                Field::new(c.name, data_type, true)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))
+            .map_err(format_error)?;
+        Ok(Self {
+            table: *table,
+            schema,
+            writer,
+            rows: Vec::with_capacity(ROW_GROUP_SIZE),
+        })
+    }
+
+    fn flush_rows(&mut self) -> Result<(), MetadataExportError> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(&mut self.rows);
+        let columns: Vec<ArrayRef> = self
+            .table
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| -> ArrayRef {
+                let cells = rows.iter().map(|row| &row[i]);
+                match c.kind {
+                    ColumnKind::Text => Arc::new(StringArray::from_iter(cells.map(|v| match v {
+                        Value::Text(s) => Some(s.as_str()),
+                        _ => None,
+                    }))),
+                    ColumnKind::Integer => {
+                        Arc::new(Int64Array::from_iter(cells.map(|v| match v {
+                            Value::Integer(n) => Some(*n),
+                            _ => None,
+                        })))
+                    }
+                    ColumnKind::Float => {
+                        Arc::new(Float64Array::from_iter(cells.map(|v| match v {
+                            Value::Float(f) => Some(*f),
+                            _ => None,
+                        })))
+                    }
+                    ColumnKind::Timestamp => Arc::new(
+                        TimestampMicrosecondArray::from_iter(cells.map(|v| match v {
+                            Value::Timestamp(t) => Some(t.timestamp_micros()),
+                            _ => None,
+                        }))
+                        .with_timezone("UTC"),
+                    ),
+                }
+            })
+            .collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(format_error)?;
+        self.writer.write(&batch).map_err(format_error)?;
+        self.rows = Vec::with_capacity(ROW_GROUP_SIZE);
+        Ok(())
+    }
+}
+
+impl TableWriter for ParquetWriter {
+    fn write_row(&mut self, row: Vec<Value>) -> Result<(), MetadataExportError> {
+        self.rows.push(row);
+        if self.rows.len() >= ROW_GROUP_SIZE {
+            self.flush_rows()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), MetadataExportError> {
+        self.flush_rows()?;
+        self.writer.close().map_err(format_error)?;
+        Ok(())
+    }
+}
@@ -3,5 +3,28 @@
 //! This module contains domain logic separated from UI concerns.
 //! Services can be used by CLI, web server, or other interfaces.
 
+pub mod agency_list;
+pub mod ask;
+pub mod bundle;
+pub mod curation;
+pub mod email;
+pub mod exemption_report;
+pub mod failures;
 #[cfg(feature = "gis")]
 pub mod geolookup;
+pub mod hash_backfill;
+pub mod known_files;
+pub mod metadata_export;
+pub mod ocr_reprocess;
+pub mod politeness_report;
+pub mod provenance;
+pub mod quarantine;
+pub mod reports;
+pub mod request_reminders;
+pub mod saved_search;
+pub mod saved_search_alerts;
+pub mod source_health;
+pub mod stable_ids;
+pub mod title_inference;
+pub mod torrent;
+pub mod url_list;
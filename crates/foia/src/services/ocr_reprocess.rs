@@ -0,0 +1,286 @@
+//! Selecting OCR results to reprocess by the settings they were made with.
+//!
+//! Filters are `AND`-joined comparisons on an OCR result's recorded
+//! backend, model, tool version, language, DPI and quality score, e.g.
+//! `backend=tesseract AND version<5` or `quality<0.5`. Versions compare
+//! numerically by component, so `4.1.1 < 5 < 5.3.0`. Results recorded
+//! before settings were tracked have no version, language or DPI; match
+//! them with `version=none`.
+
+use std::cmp::Ordering;
+
+use regex::Regex;
+
+use crate::repository::diesel_document::OcrRun;
+
+/// Field of an OCR result a filter can compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Backend,
+    Model,
+    Version,
+    Language,
+    Dpi,
+    Quality,
+}
+
+impl Field {
+    fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "backend" => Some(Self::Backend),
+            "model" => Some(Self::Model),
+            "version" => Some(Self::Version),
+            "language" | "lang" => Some(Self::Language),
+            "dpi" => Some(Self::Dpi),
+            "quality" => Some(Self::Quality),
+            _ => None,
+        }
+    }
+
+    fn is_ordered(&self) -> bool {
+        matches!(self, Self::Version | Self::Dpi | Self::Quality)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "=" | "==" => Some(Self::Eq),
+            "!=" => Some(Self::Ne),
+            "<" => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">" => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            _ => None,
+        }
+    }
+
+    fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    field: Field,
+    op: Op,
+    /// `None` for the literal `none`, matching results without the field.
+    value: Option<String>,
+}
+
+impl Condition {
+    fn matches(&self, run: &OcrRun) -> bool {
+        let actual = match self.field {
+            Field::Backend => Some(run.backend.clone()),
+            Field::Model => run.model.clone(),
+            Field::Version => run.settings.tool_version.clone(),
+            Field::Language => run.settings.language.clone(),
+            Field::Dpi => run.settings.dpi.map(|d| d.to_string()),
+            Field::Quality => run.quality_score.map(|q| q.to_string()),
+        };
+
+        let (actual, expected) = match (actual, &self.value) {
+            (None, None) => return self.op == Op::Eq,
+            (Some(_), None) => return self.op == Op::Ne,
+            // A missing value is unequal to everything and unordered
+            (None, Some(_)) => return self.op == Op::Ne,
+            (Some(actual), Some(expected)) => (actual, expected),
+        };
+
+        let ordering = match self.field {
+            Field::Version => compare_versions(&actual, expected),
+            Field::Dpi => match (actual.parse::<u32>(), expected.parse::<u32>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => return false,
+            },
+            Field::Quality => match (actual.parse::<f32>(), expected.parse::<f32>()) {
+                (Ok(a), Ok(b)) => a.total_cmp(&b),
+                _ => return false,
+            },
+            _ if actual.eq_ignore_ascii_case(expected) => Ordering::Equal,
+            _ => Ordering::Less,
+        };
+        self.op.holds(ordering)
+    }
+}
+
+/// Parsed `--where` filter over OCR results.
+#[derive(Debug, Clone)]
+pub struct OcrRunFilter {
+    conditions: Vec<Condition>,
+}
+
+impl OcrRunFilter {
+    /// Parse a filter like `backend=tesseract AND version<5`.
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let and = Regex::new(r"(?i)\s+and\s+").expect("valid regex");
+        let term =
+            Regex::new(r"^\s*([A-Za-z_]+)\s*(!=|<=|>=|==|=|<|>)\s*(\S+)\s*$").expect("valid regex");
+
+        let mut conditions = Vec::new();
+        for part in and.split(expr.trim()) {
+            let caps = term
+                .captures(part)
+                .ok_or_else(|| format!("Invalid condition '{}' (expected FIELD OP VALUE)", part))?;
+            let field = Field::from_str(&caps[1]).ok_or_else(|| {
+                format!(
+                    "Unknown field '{}' (expected backend, model, version, language, dpi or quality)",
+                    &caps[1]
+                )
+            })?;
+            let op = Op::from_str(&caps[2]).expect("operator matched by regex");
+            let value = caps[3].trim_matches(|c| c == '\'' || c == '"');
+            let value = (!value.eq_ignore_ascii_case("none")).then(|| value.to_string());
+
+            if !matches!(op, Op::Eq | Op::Ne) && (value.is_none() || !field.is_ordered()) {
+                return Err(format!("'{}' only supports = and !=", part.trim()));
+            }
+            if let (Field::Dpi, Some(v)) = (field, &value) {
+                v.parse::<u32>()
+                    .map_err(|_| format!("Invalid DPI '{}'", v))?;
+            }
+            if let (Field::Quality, Some(v)) = (field, &value) {
+                v.parse::<f32>()
+                    .map_err(|_| format!("Invalid quality '{}'", v))?;
+            }
+            conditions.push(Condition { field, op, value });
+        }
+        Ok(Self { conditions })
+    }
+
+    /// Whether an OCR result satisfies every condition.
+    pub fn matches(&self, run: &OcrRun) -> bool {
+        self.conditions.iter().all(|c| c.matches(run))
+    }
+}
+
+/// Compare version strings numerically by dot-separated component.
+///
+/// Non-numeric suffixes are ignored (`5.3.0-1ubuntu` is `5.3.0`) and
+/// missing components count as zero (`5` equals `5.0`).
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn components(v: &str) -> Vec<u64> {
+        v.trim_start_matches(['v', 'V'])
+            .split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    }
+
+    let (a, b) = (components(a), components(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::diesel_document::OcrRunSettings;
+
+    fn run(
+        backend: &str,
+        version: Option<&str>,
+        language: Option<&str>,
+        dpi: Option<u32>,
+    ) -> OcrRun {
+        OcrRun {
+            result_id: 1,
+            page_id: 1,
+            backend: backend.to_string(),
+            model: None,
+            settings: OcrRunSettings {
+                tool_version: version.map(String::from),
+                language: language.map(String::from),
+                dpi,
+            },
+            quality_score: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("4.1.1", "5"), Ordering::Less);
+        assert_eq!(compare_versions("5.3.0", "5"), Ordering::Greater);
+        assert_eq!(compare_versions("5.0", "5"), Ordering::Equal);
+        assert_eq!(compare_versions("5.3.0-1ubuntu", "5.3.0"), Ordering::Equal);
+        assert_eq!(compare_versions("10.0", "9.9"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_backend_and_version() {
+        let filter = OcrRunFilter::parse("backend=tesseract AND version<5").unwrap();
+        assert!(filter.matches(&run("tesseract", Some("4.1.1"), None, None)));
+        assert!(!filter.matches(&run("tesseract", Some("5.3.0"), None, None)));
+        assert!(!filter.matches(&run("groq", Some("4.0"), None, None)));
+        // Unknown versions aren't older than anything
+        assert!(!filter.matches(&run("tesseract", None, None, None)));
+    }
+
+    #[test]
+    fn test_none_matches_unrecorded_settings() {
+        let filter = OcrRunFilter::parse("backend = tesseract and version = none").unwrap();
+        assert!(filter.matches(&run("tesseract", None, None, None)));
+        assert!(!filter.matches(&run("tesseract", Some("5.3.0"), None, None)));
+
+        let filter = OcrRunFilter::parse("language!=eng").unwrap();
+        assert!(filter.matches(&run("tesseract", None, Some("deu"), None)));
+        assert!(filter.matches(&run("tesseract", None, None, None)));
+        assert!(!filter.matches(&run("tesseract", None, Some("ENG"), None)));
+    }
+
+    #[test]
+    fn test_dpi() {
+        let filter = OcrRunFilter::parse("dpi<300").unwrap();
+        assert!(filter.matches(&run("tesseract", None, None, Some(150))));
+        assert!(!filter.matches(&run("tesseract", None, None, Some(300))));
+        assert!(!filter.matches(&run("tesseract", None, None, None)));
+    }
+
+    #[test]
+    fn test_quality() {
+        let filter = OcrRunFilter::parse("quality<0.5").unwrap();
+        let mut poor = run("tesseract", None, None, Some(300));
+        poor.quality_score = Some(0.3);
+        assert!(filter.matches(&poor));
+        poor.quality_score = Some(0.8);
+        assert!(!filter.matches(&poor));
+        poor.quality_score = None;
+        assert!(!filter.matches(&poor));
+        assert!(OcrRunFilter::parse("quality<low").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(OcrRunFilter::parse("").is_err());
+        assert!(OcrRunFilter::parse("engine=tesseract").is_err());
+        assert!(OcrRunFilter::parse("backend<tesseract").is_err());
+        assert!(OcrRunFilter::parse("version<none").is_err());
+        assert!(OcrRunFilter::parse("dpi=high").is_err());
+        assert!(OcrRunFilter::parse("backend tesseract").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Crawl politeness reports.
+//!
+//! A per-source account of how foia accessed a site over a period: how many
+//! requests it made and how fast, how the site answered, whether robots.txt
+//! was honored and which user agent was sent. Counts come from the request
+//! log; pacing, robots and user agent are the source's current settings.
+//! Rendered as Markdown, which `foia report politeness` can also write as a
+//! PDF to hand to an agency that asks.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::{Config, Politeness};
+use crate::http_client::{ROBOTS_SKIP_REASON, USER_AGENT};
+use crate::repository::diesel_crawl::RequestWindow;
+use crate::repository::pool::DieselError;
+use crate::repository::Repositories;
+
+/// How one source was crawled over the report period.
+#[derive(Debug, Clone)]
+pub struct SourcePoliteness {
+    pub source_id: String,
+    pub name: String,
+    pub base_url: String,
+    pub requests: RequestWindow,
+    /// URLs left unfetched because robots.txt disallows them.
+    pub robots_skipped: u64,
+    pub politeness: Politeness,
+}
+
+impl SourcePoliteness {
+    /// Requests per minute between the first and last request.
+    pub fn average_rate(&self) -> Option<f64> {
+        let (first, last) = (self.requests.first_request?, self.requests.last_request?);
+        let minutes = ((last - first).num_seconds() as f64 / 60.0).max(1.0);
+        Some(self.requests.requests as f64 / minutes)
+    }
+
+    /// The user agent sent, as a reader would want it described.
+    pub fn user_agent(&self) -> String {
+        match self.politeness.user_agent.as_deref() {
+            None => USER_AGENT.to_string(),
+            Some("impersonate") => "a current desktop browser's, rotated (impersonate)".to_string(),
+            Some(custom) => custom.to_string(),
+        }
+    }
+}
+
+/// Politeness report over a period.
+#[derive(Debug, Clone)]
+pub struct PolitenessReport {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    /// Sources with requests in the period, by name.
+    pub sources: Vec<SourcePoliteness>,
+}
+
+fn minute(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M").to_string()
+}
+
+fn seconds(delay: Duration) -> String {
+    format!("{:.1} s", delay.as_secs_f64())
+}
+
+impl PolitenessReport {
+    /// The report as Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("# Crawl politeness report\n\n");
+        md.push_str(&format!(
+            "Requests from {} to {} UTC, generated {} UTC.\n\n",
+            minute(self.since),
+            minute(self.until),
+            minute(self.generated_at)
+        ));
+        md.push_str(
+            "Every request foia makes is logged with its time and the response it got; \
+             the counts below come from that log. Pacing, robots.txt and user agent are \
+             the settings in effect when the report was generated.\n",
+        );
+        if self.sources.is_empty() {
+            md.push_str("\nNo requests were made in this period.\n");
+        }
+
+        for source in &self.sources {
+            let requests = &source.requests;
+            md.push_str(&format!("\n## {} ({})\n\n", source.name, source.source_id));
+            if !source.base_url.is_empty() {
+                md.push_str(&format!("- Site: {}\n", source.base_url));
+            }
+            md.push_str(&format!(
+                "- Requests: {}, {} succeeded ({} unchanged, 304), {} rate limited (429/503)\n",
+                requests.requests, requests.successes, requests.not_modified, requests.rate_limited
+            ));
+            if let (Some(first), Some(last)) = (requests.first_request, requests.last_request) {
+                md.push_str(&format!(
+                    "- Active: {} to {} UTC\n",
+                    minute(first),
+                    minute(last)
+                ));
+            }
+            if let Some(rate) = source.average_rate() {
+                md.push_str(&format!(
+                    "- Average rate: {:.1} requests/minute while active; busiest minute: {}\n",
+                    rate, requests.busiest_minute
+                ));
+            }
+            if requests.timed_requests > 0 {
+                md.push_str(&format!(
+                    "- Average response time: {} ms\n",
+                    requests.total_duration_ms / requests.timed_requests
+                ));
+            }
+
+            let politeness = &source.politeness;
+            let concurrency = match politeness.concurrency {
+                Some(1) => "one request at a time".to_string(),
+                Some(n) => format!("at most {} requests at a time", n),
+                None => "concurrency set by the number of workers".to_string(),
+            };
+            md.push_str(&format!(
+                "- Pacing: \"{}\" profile, {} between requests, {}, backing off on 429/503\n",
+                politeness.profile.as_str(),
+                seconds(politeness.request_delay),
+                concurrency
+            ));
+            if politeness.respect_robots {
+                md.push_str(&format!(
+                    "- robots.txt: honored; {} disallowed URLs not fetched\n",
+                    source.robots_skipped
+                ));
+            } else {
+                md.push_str("- robots.txt: not consulted\n");
+            }
+            md.push_str(&format!("- User agent: {}\n", source.user_agent()));
+        }
+        md
+    }
+}
+
+/// Politeness report for requests between `since` and `until`, for every
+/// source or just `source_id`. `global_delay` is the global
+/// `request_delay_ms`.
+pub async fn politeness_report(
+    repos: &Repositories,
+    config: &Config,
+    global_delay: Duration,
+    source_id: Option<&str>,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<PolitenessReport, DieselError> {
+    let mut sources = Vec::new();
+    for source in repos.sources.get_all().await? {
+        if source_id.is_some_and(|id| id != source.id) {
+            continue;
+        }
+        let requests = repos
+            .crawl
+            .get_request_window(&source.id, since, until)
+            .await?;
+        if requests.requests == 0 && source_id.is_none() {
+            continue;
+        }
+
+        let mut scraper = config.scrapers.get(&source.id).cloned().unwrap_or_default();
+        config.apply_politeness_defaults(&mut scraper);
+        let politeness = Politeness::resolve(&scraper, global_delay);
+        let robots_skipped = if politeness.respect_robots {
+            repos
+                .crawl
+                .count_skipped(&source.id, ROBOTS_SKIP_REASON, since, until)
+                .await?
+        } else {
+            0
+        };
+        sources.push(SourcePoliteness {
+            source_id: source.id,
+            name: source.name,
+            base_url: source.base_url,
+            requests,
+            robots_skipped,
+            politeness,
+        });
+    }
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(PolitenessReport {
+        since,
+        until,
+        generated_at: Utc::now(),
+        sources,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{PolitenessProfile, ScraperConfig};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_politeness_markdown() {
+        let scraper = ScraperConfig {
+            politeness: Some(PolitenessProfile::Gentle),
+            ..Default::default()
+        };
+        let source = SourcePoliteness {
+            source_id: "county".to_string(),
+            name: "County Clerk".to_string(),
+            base_url: "https://clerk.county.gov".to_string(),
+            requests: RequestWindow {
+                requests: 120,
+                successes: 118,
+                rate_limited: 1,
+                not_modified: 30,
+                first_request: Some(at("2024-03-02T10:00:00Z")),
+                last_request: Some(at("2024-03-02T11:00:00Z")),
+                busiest_minute: 3,
+                timed_requests: 100,
+                total_duration_ms: 25_000,
+            },
+            robots_skipped: 4,
+            politeness: Politeness::resolve(&scraper, Duration::from_millis(500)),
+        };
+        assert_eq!(source.average_rate(), Some(2.0));
+
+        let report = PolitenessReport {
+            since: at("2024-03-01T00:00:00Z"),
+            until: at("2024-04-01T00:00:00Z"),
+            generated_at: at("2024-04-01T09:30:00Z"),
+            sources: vec![source],
+        };
+        let md = report.to_markdown();
+        assert!(md.contains("Requests from 2024-03-01 00:00 to 2024-04-01 00:00 UTC"));
+        assert!(md.contains("## County Clerk (county)\n"));
+        assert!(md.contains("- Requests: 120, 118 succeeded (30 unchanged, 304), 1 rate limited"));
+        assert!(
+            md.contains("- Average rate: 2.0 requests/minute while active; busiest minute: 3\n")
+        );
+        assert!(md.contains("- Average response time: 250 ms\n"));
+        assert!(md.contains("\"gentle\" profile, 5.0 s between requests, one request at a time"));
+        assert!(md.contains("- robots.txt: honored; 4 disallowed URLs not fetched\n"));
+        assert!(md.contains(&format!("- User agent: {}\n", USER_AGENT)));
+
+        let empty = PolitenessReport {
+            sources: Vec::new(),
+            ..report
+        };
+        assert!(empty
+            .to_markdown()
+            .contains("No requests were made in this period."));
+    }
+}
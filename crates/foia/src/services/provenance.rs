@@ -0,0 +1,259 @@
+//! Document provenance: how a document was found, fetched and processed.
+//!
+//! Reconstructs a chain-of-custody record from what the crawler and the
+//! analysis pipeline already store: the discovery chain from the seed URL
+//! down to the document, every logged HTTP request for the document's URLs,
+//! each version's hashes, and every OCR and analysis run over it.
+
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::{CrawlRequest, DocumentVersion};
+use crate::repository::diesel_crawl::RequestLogFilter;
+use crate::repository::pool::DieselError;
+use crate::repository::{parse_datetime, DieselCrawlRepository, DieselDocumentRepository};
+
+/// Parent links followed before giving up on a discovery chain.
+const MAX_CHAIN_DEPTH: usize = 64;
+
+/// Most logged requests loaded per URL.
+const MAX_REQUESTS_PER_URL: u32 = 1000;
+
+/// Everything recorded about where a document came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceRecord {
+    pub generated_at: DateTime<Utc>,
+    pub document: ProvenanceDocument,
+    /// URL discovery started from, the first link of `discovery_chain`.
+    pub seed: Option<String>,
+    /// Seed first, ending at the document's URL. Empty when the document
+    /// was imported rather than crawled.
+    pub discovery_chain: Vec<ChainLink>,
+    /// Logged HTTP requests for the document's URLs, oldest first.
+    pub requests: Vec<CrawlRequest>,
+    pub versions: Vec<DocumentVersion>,
+    pub analysis: Vec<AnalysisRun>,
+}
+
+/// The document the record describes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProvenanceDocument {
+    pub id: String,
+    pub source_id: String,
+    pub title: String,
+    pub source_url: String,
+    pub discovery_method: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One URL in a discovery chain.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainLink {
+    pub url: String,
+    pub discovery_method: String,
+    pub depth: u32,
+    pub discovered_at: DateTime<Utc>,
+    pub fetched_at: Option<DateTime<Utc>>,
+    pub status: String,
+    /// Extra context from discovery, e.g. the link text or API page.
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub context: serde_json::Map<String, serde_json::Value>,
+}
+
+/// One tool's pass over a document version.
+///
+/// Page-level results from the same tool and model are folded into one
+/// run spanning the first to the last page processed.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisRun {
+    pub version_id: i64,
+    /// `ocr` for page OCR, otherwise the analysis type.
+    pub kind: String,
+    /// Backend that did the work, e.g. `tesseract` or `llm`.
+    pub tool: String,
+    /// Model or tool version, when recorded.
+    pub model: Option<String>,
+    /// Pages processed; 0 for a document-level analysis.
+    pub pages: u32,
+    pub failed: u32,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Build the provenance record for a document, or `None` if it doesn't exist.
+pub async fn provenance_record(
+    doc_repo: &DieselDocumentRepository,
+    crawl_repo: &DieselCrawlRepository,
+    doc_id: &str,
+) -> Result<Option<ProvenanceRecord>, DieselError> {
+    let Some(doc) = doc_repo.get(doc_id).await? else {
+        return Ok(None);
+    };
+
+    // Walk parent links up from the document's URL to the seed
+    let mut discovery_chain = Vec::new();
+    let mut seen = HashSet::new();
+    let mut next = Some(doc.source_url.clone());
+    while let Some(url) = next.take() {
+        if discovery_chain.len() >= MAX_CHAIN_DEPTH || !seen.insert(url.clone()) {
+            break;
+        }
+        let Some(crawl_url) = crawl_repo.get_url(&doc.source_id, &url).await? else {
+            break;
+        };
+        next = crawl_url.parent_url.clone();
+        discovery_chain.push(ChainLink {
+            url: crawl_url.url,
+            discovery_method: crawl_url.discovery_method.as_str().to_string(),
+            depth: crawl_url.depth,
+            discovered_at: crawl_url.discovered_at,
+            fetched_at: crawl_url.fetched_at,
+            status: crawl_url.status.as_str().to_string(),
+            context: crawl_url.discovery_context.into_iter().collect(),
+        });
+    }
+    discovery_chain.reverse();
+
+    // Requests for the document's URL and any other URL a version came from
+    let mut urls = vec![doc.source_url.clone()];
+    for url in doc.versions.iter().filter_map(|v| v.source_url.clone()) {
+        if !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    let mut requests = Vec::new();
+    for url in urls {
+        let filter = RequestLogFilter {
+            url: Some(url),
+            ..Default::default()
+        };
+        requests.extend(
+            crawl_repo
+                .list_requests(&filter, MAX_REQUESTS_PER_URL)
+                .await?,
+        );
+    }
+    requests.sort_by_key(|r| (r.request_at, r.id));
+
+    let mut analysis = RunBuilder::default();
+    for (version_id, _page, ocr) in doc_repo.get_document_ocr_results(&doc.id).await? {
+        analysis.add(
+            version_id,
+            "ocr",
+            &ocr.backend,
+            ocr.model.as_deref(),
+            true,
+            ocr.error_message.is_some(),
+            parse_datetime(&ocr.created_at),
+        );
+    }
+    for result in doc_repo.get_all_analysis_results(&doc.id).await? {
+        analysis.add(
+            result.version_id,
+            &result.analysis_type,
+            &result.backend,
+            result.model.as_deref(),
+            result.page_id.is_some(),
+            result.error.is_some(),
+            parse_datetime(&result.created_at),
+        );
+    }
+
+    Ok(Some(ProvenanceRecord {
+        generated_at: Utc::now(),
+        document: ProvenanceDocument {
+            id: doc.id,
+            source_id: doc.source_id,
+            title: doc.title,
+            source_url: doc.source_url,
+            discovery_method: doc.discovery_method,
+            created_at: doc.created_at,
+        },
+        seed: discovery_chain.first().map(|link| link.url.clone()),
+        discovery_chain,
+        requests,
+        versions: doc.versions,
+        analysis: analysis.finish(),
+    }))
+}
+
+/// Folds individual results into runs keyed by version, kind, tool and model.
+#[derive(Default)]
+struct RunBuilder {
+    runs: BTreeMap<(i64, String, String, Option<String>), AnalysisRun>,
+}
+
+impl RunBuilder {
+    #[allow(clippy::too_many_arguments)]
+    fn add(
+        &mut self,
+        version_id: i64,
+        kind: &str,
+        tool: &str,
+        model: Option<&str>,
+        per_page: bool,
+        failed: bool,
+        at: DateTime<Utc>,
+    ) {
+        let key = (
+            version_id,
+            kind.to_string(),
+            tool.to_string(),
+            model.map(str::to_string),
+        );
+        let run = self.runs.entry(key).or_insert_with(|| AnalysisRun {
+            version_id,
+            kind: kind.to_string(),
+            tool: tool.to_string(),
+            model: model.map(str::to_string),
+            pages: 0,
+            failed: 0,
+            started_at: at,
+            finished_at: at,
+        });
+        run.pages += u32::from(per_page);
+        run.failed += u32::from(failed);
+        run.started_at = run.started_at.min(at);
+        run.finished_at = run.finished_at.max(at);
+    }
+
+    /// Runs in the order they started.
+    fn finish(self) -> Vec<AnalysisRun> {
+        let mut runs: Vec<AnalysisRun> = self.runs.into_values().collect();
+        runs.sort_by_key(|r| r.started_at);
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_fold_pages_by_tool_and_model() {
+        let at = |minute: u32| {
+            chrono::TimeZone::with_ymd_and_hms(&Utc, 2026, 3, 1, 12, minute, 0).unwrap()
+        };
+        let mut builder = RunBuilder::default();
+        builder.add(1, "ocr", "tesseract", None, true, false, at(5));
+        builder.add(1, "ocr", "tesseract", None, true, true, at(1));
+        builder.add(1, "ocr", "tesseract", None, true, false, at(3));
+        builder.add(1, "summarize", "llm", Some("llama3"), false, false, at(10));
+        builder.add(2, "ocr", "tesseract", None, true, false, at(0));
+
+        let runs = builder.finish();
+        assert_eq!(runs.len(), 3);
+
+        assert_eq!(runs[0].version_id, 2);
+        let ocr = &runs[1];
+        assert_eq!((ocr.version_id, ocr.kind.as_str()), (1, "ocr"));
+        assert_eq!((ocr.pages, ocr.failed), (3, 1));
+        assert_eq!((ocr.started_at, ocr.finished_at), (at(1), at(5)));
+
+        let summary = &runs[2];
+        assert_eq!(summary.pages, 0);
+        assert_eq!(summary.model.as_deref(), Some("llama3"));
+    }
+}
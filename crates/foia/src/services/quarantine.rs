@@ -0,0 +1,200 @@
+//! Spotting error pages downloaded in place of documents.
+//!
+//! Sites answer document URLs with "Access Denied" pages, login forms and
+//! bot challenges, often with a 200 status. Saved as documents they pile up
+//! as hundreds of identical HTML files. These checks recognise such pages so
+//! the download can be quarantined and retried instead of stored.
+
+use scraper::{Html, Selector};
+
+use crate::utils::has_document_extension;
+
+/// Larger HTML responses are assumed to be real pages.
+pub const MAX_ERROR_PAGE_BYTES: usize = 64 * 1024;
+
+/// Prefix of the crawl error recorded for a quarantined download.
+pub const QUARANTINE_ERROR_PREFIX: &str = "quarantined";
+
+/// Attempts before a URL that keeps returning error pages is exhausted.
+pub const MAX_RETRIES: u32 = 3;
+
+/// Why a response looks like an error page rather than a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPage {
+    AccessDenied,
+    NotFound,
+    Login,
+    Challenge,
+    ServerError,
+    /// HTML served for a URL naming a document file (e.g. `.pdf`).
+    NotADocument,
+}
+
+impl ErrorPage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::AccessDenied => "access denied page",
+            Self::NotFound => "not found page",
+            Self::Login => "login page",
+            Self::Challenge => "bot challenge page",
+            Self::ServerError => "server error page",
+            Self::NotADocument => "HTML page instead of document",
+        }
+    }
+
+    /// Crawl error recorded for a download quarantined for this reason.
+    pub fn error_message(&self) -> String {
+        format!("{}: {}", QUARANTINE_ERROR_PREFIX, self.as_str())
+    }
+}
+
+/// Phrases in an error page's title or heading.
+const HEADING_PATTERNS: &[(&str, ErrorPage)] = &[
+    ("access denied", ErrorPage::AccessDenied),
+    ("forbidden", ErrorPage::AccessDenied),
+    ("request rejected", ErrorPage::AccessDenied),
+    ("unauthorized", ErrorPage::AccessDenied),
+    ("not found", ErrorPage::NotFound),
+    ("no longer available", ErrorPage::NotFound),
+    ("sign in", ErrorPage::Login),
+    ("log in", ErrorPage::Login),
+    ("login", ErrorPage::Login),
+    ("session expired", ErrorPage::Login),
+    ("just a moment", ErrorPage::Challenge),
+    ("attention required", ErrorPage::Challenge),
+    ("are you a robot", ErrorPage::Challenge),
+    ("verify you are human", ErrorPage::Challenge),
+    ("service unavailable", ErrorPage::ServerError),
+    ("internal server error", ErrorPage::ServerError),
+    ("bad gateway", ErrorPage::ServerError),
+    ("temporarily unavailable", ErrorPage::ServerError),
+];
+
+/// Markup only bot challenges carry, anywhere in the page.
+const CHALLENGE_MARKERS: &[&str] = &[
+    "cf-browser-verification",
+    "cf-challenge",
+    "challenge-platform",
+    "g-recaptcha",
+    "h-captcha",
+    "_incapsula_resource",
+];
+
+fn is_html(content: &[u8], mime_type: &str) -> bool {
+    if mime_type.to_lowercase().contains("html") {
+        return true;
+    }
+    let head = String::from_utf8_lossy(&content[..content.len().min(512)]).to_lowercase();
+    let head = head.trim_start();
+    head.starts_with("<!doctype html") || head.starts_with("<html")
+}
+
+fn heading_text(document: &Html) -> String {
+    let mut text = String::new();
+    for tag in ["title", "h1", "h2"] {
+        if let Ok(selector) = Selector::parse(tag) {
+            for el in document.select(&selector).take(2) {
+                text.push_str(&el.text().collect::<String>());
+                text.push('\n');
+            }
+        }
+    }
+    text.to_lowercase()
+}
+
+/// Check a downloaded response for signs of an error page. `url` is the
+/// document URL; HTML served for a `.pdf` or Office file is never the
+/// document itself.
+pub fn detect_error_page(content: &[u8], mime_type: &str, url: &str) -> Option<ErrorPage> {
+    if !is_html(content, mime_type) {
+        return None;
+    }
+    if has_document_extension(url) {
+        return Some(ErrorPage::NotADocument);
+    }
+    if content.len() > MAX_ERROR_PAGE_BYTES {
+        return None;
+    }
+
+    let html = String::from_utf8_lossy(content);
+    let lower = html.to_lowercase();
+    if CHALLENGE_MARKERS.iter().any(|m| lower.contains(m)) {
+        return Some(ErrorPage::Challenge);
+    }
+
+    let document = Html::parse_document(&html);
+    let headings = heading_text(&document);
+    if let Some((_, page)) = HEADING_PATTERNS
+        .iter()
+        .find(|(pattern, _)| headings.contains(pattern))
+    {
+        return Some(*page);
+    }
+
+    // A short page whose main job is a password field is a login wall
+    if let Ok(selector) = Selector::parse("input[type=password]") {
+        if document.select(&selector).next().is_some() {
+            return Some(ErrorPage::Login);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_error_page() {
+        let denied = b"<html><head><title>Access Denied</title></head>\
+            <body><h1>Access Denied</h1>You don't have permission.</body></html>";
+        assert_eq!(
+            detect_error_page(denied, "text/html", "https://agency.gov/records/123"),
+            Some(ErrorPage::AccessDenied)
+        );
+
+        // Any HTML is wrong for a URL naming a PDF
+        let page = b"<!DOCTYPE html><html><body>Welcome</body></html>";
+        assert_eq!(
+            detect_error_page(page, "text/html", "https://agency.gov/files/memo.pdf"),
+            Some(ErrorPage::NotADocument)
+        );
+
+        let login = b"<html><body><form><input name=user>\
+            <input type=\"password\" name=pw></form></body></html>";
+        assert_eq!(
+            detect_error_page(
+                login,
+                "text/html; charset=utf-8",
+                "https://agency.gov/doc/9"
+            ),
+            Some(ErrorPage::Login)
+        );
+
+        let challenge = b"<html><body><div class=\"cf-browser-verification\"></div></body></html>";
+        assert_eq!(
+            detect_error_page(
+                challenge,
+                "application/octet-stream",
+                "https://agency.gov/d"
+            ),
+            Some(ErrorPage::Challenge)
+        );
+
+        // Ordinary HTML documents and non-HTML files are kept
+        let memo = b"<html><head><title>Memo on budget</title></head><body>Text</body></html>";
+        assert_eq!(
+            detect_error_page(memo, "text/html", "https://agency.gov/doc/1"),
+            None
+        );
+        assert_eq!(
+            detect_error_page(b"%PDF-1.4", "application/pdf", "https://agency.gov/a.pdf"),
+            None
+        );
+
+        assert_eq!(
+            ErrorPage::AccessDenied.error_message(),
+            "quarantined: access denied page"
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Emailed reports: crawl summaries, broken-scraper alerts and saved-view
+//! digests.
+//!
+//! Each report is built from the database as an [`Email`] and sent with
+//! [`Mailer`](super::email::Mailer). They are meant to run from cron, so a
+//! report with nothing worth saying returns `None` instead of an empty email
+//! (except the crawl summary, where silence is itself news).
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use super::email::Email;
+use super::saved_search::SavedSearch;
+use super::source_health;
+use crate::config::EmailConfig;
+use crate::models::Document;
+use crate::repository::pool::DieselError;
+use crate::repository::Repositories;
+
+/// Documents listed per saved view in a digest.
+pub const DIGEST_LIMIT: u32 = 20;
+
+/// What one source did over the report period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceActivity {
+    pub source_id: String,
+    pub name: String,
+    pub requests: u64,
+    pub successes: u64,
+    pub new_documents: u64,
+}
+
+/// A source whose scraper stopped succeeding.
+#[derive(Debug, Clone)]
+pub struct BrokenSource {
+    pub source_id: String,
+    pub name: String,
+    pub last_success: Option<DateTime<Utc>>,
+    pub success_rate: Option<f64>,
+}
+
+/// New documents matching one saved view.
+#[derive(Debug, Clone)]
+pub struct ViewMatches {
+    pub name: String,
+    pub query: String,
+    pub documents: Vec<Document>,
+}
+
+fn plural(n: u64, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+fn period(since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let hours = (now - since).num_hours();
+    if hours > 0 && hours % 24 == 0 {
+        plural(hours as u64 / 24, "day", "days")
+    } else {
+        plural(hours.max(0) as u64, "hour", "hours")
+    }
+}
+
+/// Per-source activity since `since`. Sources with no requests and no new
+/// documents are left out.
+pub async fn crawl_activity(
+    repos: &Repositories,
+    since: DateTime<Utc>,
+) -> Result<Vec<SourceActivity>, DieselError> {
+    let sources = repos.sources.get_all().await?;
+    let new_documents: HashMap<String, u64> =
+        repos.documents.get_source_counts_since(since).await?;
+
+    let mut activity = Vec::new();
+    for source in sources {
+        let days = repos
+            .crawl
+            .get_daily_request_health(&source.id, since)
+            .await?;
+        let item = SourceActivity {
+            requests: days.iter().map(|d| d.requests).sum(),
+            successes: days.iter().map(|d| d.successes).sum(),
+            new_documents: new_documents.get(&source.id).copied().unwrap_or(0),
+            source_id: source.id,
+            name: source.name,
+        };
+        if item.requests > 0 || item.new_documents > 0 {
+            activity.push(item);
+        }
+    }
+    activity.sort_by(|a, b| b.new_documents.cmp(&a.new_documents));
+    Ok(activity)
+}
+
+/// Summary of crawling since `since`.
+pub fn crawl_summary_email(
+    activity: &[SourceActivity],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Email {
+    let total: u64 = activity.iter().map(|a| a.new_documents).sum();
+    let period = period(since, now);
+    let subject = format!(
+        "Crawl summary: {} in the last {}",
+        plural(total, "new document", "new documents"),
+        period
+    );
+    if activity.is_empty() {
+        return Email::new(
+            subject,
+            format!("No source was crawled in the last {}.\n", period),
+        );
+    }
+
+    let mut body = format!("Crawl activity in the last {}:\n\n", period);
+    for a in activity {
+        let failed = a.requests.saturating_sub(a.successes);
+        body.push_str(&format!(
+            "- {} ({}): {}, {}",
+            a.name,
+            a.source_id,
+            plural(a.new_documents, "new document", "new documents"),
+            plural(a.requests, "request", "requests")
+        ));
+        if failed > 0 {
+            body.push_str(&format!(", {} failed", failed));
+        }
+        body.push('\n');
+    }
+    Email::new(subject, body)
+}
+
+/// Sources being crawled without success for
+/// [`STALE_DAYS`](source_health::STALE_DAYS) days.
+pub async fn broken_sources(
+    repos: &Repositories,
+    now: DateTime<Utc>,
+) -> Result<Vec<BrokenSource>, DieselError> {
+    let mut broken = Vec::new();
+    for source in repos.sources.get_all().await? {
+        let health = source_health::source_health(&repos.crawl, &source.id).await?;
+        if health.is_broken(now) {
+            broken.push(BrokenSource {
+                success_rate: health.success_rate(),
+                last_success: health.last_success,
+                source_id: source.id,
+                name: source.name,
+            });
+        }
+    }
+    Ok(broken)
+}
+
+/// Alert about broken scrapers, or `None` when all are healthy.
+pub fn scraper_alert_email(broken: &[BrokenSource], config: &EmailConfig) -> Option<Email> {
+    let subject = match broken {
+        [] => return None,
+        [one] => format!("Scraper alert: {} has stopped working", one.name),
+        many => format!("Scraper alert: {} sources have stopped working", many.len()),
+    };
+
+    let mut body = format!(
+        "These sources are still being crawled but no request has succeeded in {} days:\n\n",
+        source_health::STALE_DAYS
+    );
+    for b in broken {
+        let last = b
+            .last_success
+            .map(|at| at.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "never".to_string());
+        let rate = b
+            .success_rate
+            .map(|r| format!("{:.0}%", r))
+            .unwrap_or_else(|| "-".to_string());
+        body.push_str(&format!(
+            "- {} ({})\n  Last success: {}, 30-day success rate: {}\n",
+            b.name, b.source_id, last, rate
+        ));
+        if let Some(link) = config.link(&format!("/sources/{}/health", b.source_id)) {
+            body.push_str(&format!("  {}\n", link));
+        }
+        body.push('\n');
+    }
+    Some(Email::new(subject, body))
+}
+
+/// New documents since `since` for every saved view with any.
+pub async fn saved_view_matches(
+    repos: &Repositories,
+    since: DateTime<Utc>,
+) -> Result<Vec<ViewMatches>, DieselError> {
+    let mut matches = Vec::new();
+    for view in repos.saved_views.list().await? {
+        let documents = SavedSearch::parse(&view.query)
+            .new_matches(&repos.documents, since, DIGEST_LIMIT)
+            .await?;
+        if !documents.is_empty() {
+            matches.push(ViewMatches {
+                name: view.name,
+                query: view.query,
+                documents,
+            });
+        }
+    }
+    Ok(matches)
+}
+
+/// Digest of new documents matching saved views, or `None` when there are
+/// none.
+pub fn digest_email(
+    matches: &[ViewMatches],
+    since: DateTime<Utc>,
+    now: DateTime<Utc>,
+    config: &EmailConfig,
+) -> Option<Email> {
+    if matches.is_empty() {
+        return None;
+    }
+    let period = period(since, now);
+    let subject = match matches {
+        [one] => format!(
+            "New documents matching \"{}\" in the last {}",
+            one.name, period
+        ),
+        many => format!(
+            "New documents matching {} saved searches in the last {}",
+            many.len(),
+            period
+        ),
+    };
+
+    let body = list_matches(matches, DIGEST_LIMIT, config);
+    Some(Email::new(subject, body))
+}
+
+/// Each view's documents, linked to the web UI when `base_url` is set.
+/// `limit` is how many documents were fetched per view.
+pub(super) fn list_matches(matches: &[ViewMatches], limit: u32, config: &EmailConfig) -> String {
+    let mut body = String::new();
+    for view in matches {
+        let count = view.documents.len() as u64;
+        let more = if count >= limit as u64 { "+" } else { "" };
+        body.push_str(&format!(
+            "{} ({}{})\n",
+            view.name,
+            plural(count, "new document", "new documents"),
+            more
+        ));
+        if let Some(link) = config.link(&format!("/?{}", view.query)) {
+            body.push_str(&format!("{}\n", link));
+        }
+        body.push('\n');
+        for doc in &view.documents {
+            body.push_str(&format!("- {} [{}]\n", doc.title, doc.source_id));
+            match config.link(&format!("/documents/{}", doc.id)) {
+                Some(link) => body.push_str(&format!("  {}\n", link)),
+                None => body.push_str(&format!("  {}\n", doc.source_url)),
+            }
+        }
+        body.push('\n');
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_crawl_summary_email() {
+        let activity = vec![
+            SourceActivity {
+                source_id: "fbi".to_string(),
+                name: "FBI Vault".to_string(),
+                requests: 120,
+                successes: 117,
+                new_documents: 14,
+            },
+            SourceActivity {
+                source_id: "cia".to_string(),
+                name: "CIA Reading Room".to_string(),
+                requests: 1,
+                successes: 1,
+                new_documents: 0,
+            },
+        ];
+        let now = at("2024-03-10T06:00:00Z");
+        let email = crawl_summary_email(&activity, at("2024-03-09T06:00:00Z"), now);
+        assert_eq!(
+            email.subject,
+            "Crawl summary: 14 new documents in the last 1 day"
+        );
+        assert!(email
+            .body
+            .contains("- FBI Vault (fbi): 14 new documents, 120 requests, 3 failed\n"));
+        assert!(email
+            .body
+            .contains("- CIA Reading Room (cia): 0 new documents, 1 request\n"));
+
+        let empty = crawl_summary_email(&[], at("2024-03-10T00:00:00Z"), now);
+        assert_eq!(empty.body, "No source was crawled in the last 6 hours.\n");
+    }
+
+    #[test]
+    fn test_scraper_alert_email() {
+        let config = EmailConfig {
+            base_url: Some("https://foia.example.org/".to_string()),
+            ..Default::default()
+        };
+        assert!(scraper_alert_email(&[], &config).is_none());
+
+        let broken = vec![BrokenSource {
+            source_id: "fbi".to_string(),
+            name: "FBI Vault".to_string(),
+            last_success: Some(at("2024-03-01T08:00:00Z")),
+            success_rate: Some(12.4),
+        }];
+        let email = scraper_alert_email(&broken, &config).unwrap();
+        assert_eq!(
+            email.subject,
+            "Scraper alert: FBI Vault has stopped working"
+        );
+        assert!(email
+            .body
+            .contains("Last success: 2024-03-01, 30-day success rate: 12%"));
+        assert!(email
+            .body
+            .contains("https://foia.example.org/sources/fbi/health"));
+    }
+}
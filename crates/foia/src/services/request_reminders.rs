@@ -0,0 +1,250 @@
+//! Reminders for FOIA requests past their response deadline.
+//!
+//! Agencies miss deadlines routinely and nothing happens unless the
+//! requester follows up. Overdue requests are reported to a webhook and/or
+//! by email, and again every few days while they stay overdue.
+
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde_json::json;
+
+use super::email::{Email, Mailer};
+use crate::config::{EmailConfig, RemindersConfig, RequestsConfig};
+use crate::http_client::HttpClient;
+use crate::models::FoiaRequest;
+use crate::privacy::PrivacyConfig;
+
+/// A request the agency has not answered in time.
+#[derive(Debug, Clone)]
+pub struct OverdueRequest {
+    pub request: FoiaRequest,
+    pub due_on: NaiveDate,
+    pub days_overdue: i64,
+}
+
+/// Overdue requests to remind about today: never reminded, last reminded
+/// before their current deadline passed, or reminded at least
+/// `remind_every_days` ago.
+pub fn reminders_due(
+    requests: Vec<FoiaRequest>,
+    config: &RequestsConfig,
+    today: NaiveDate,
+) -> Vec<OverdueRequest> {
+    let every = config.reminders.remind_every_days() as i64;
+    requests
+        .into_iter()
+        .filter_map(|request| {
+            let rule = config.rule(request.jurisdiction.as_deref());
+            let due_on = request.due_on(&rule)?;
+            let days_overdue = (today - due_on).num_days();
+            if days_overdue <= 0 {
+                return None;
+            }
+            let remind = match request.reminded_on {
+                Some(last) => last <= due_on || (today - last).num_days() >= every,
+                None => true,
+            };
+            remind.then_some(OverdueRequest {
+                request,
+                due_on,
+                days_overdue,
+            })
+        })
+        .collect()
+}
+
+fn subject_line(overdue: &[OverdueRequest]) -> String {
+    match overdue {
+        [one] => format!(
+            "FOIA request overdue: {} ({})",
+            one.request.subject, one.request.agency
+        ),
+        many => format!("{} FOIA requests overdue", many.len()),
+    }
+}
+
+fn describe(item: &OverdueRequest) -> String {
+    let r = &item.request;
+    let mut line = format!(
+        "- {}: {}\n  Filed {}, {}, due {} ({} days overdue)",
+        r.agency,
+        r.subject,
+        r.filed_on,
+        r.status.as_str(),
+        item.due_on,
+        item.days_overdue
+    );
+    if let Some(tracking) = &r.tracking_number {
+        line.push_str(&format!("\n  Tracking #: {}", tracking));
+    }
+    line.push_str(&format!("\n  ID: {}", r.id));
+    line
+}
+
+fn reminder_email(overdue: &[OverdueRequest]) -> Email {
+    let mut body = String::from("These requests are past their response deadline:\n\n");
+    for item in overdue {
+        body.push_str(&describe(item));
+        body.push_str("\n\n");
+    }
+    Email::new(subject_line(overdue), body)
+}
+
+fn webhook_payload(overdue: &[OverdueRequest]) -> serde_json::Value {
+    let requests: Vec<_> = overdue
+        .iter()
+        .map(|item| {
+            let r = &item.request;
+            json!({
+                "id": r.id,
+                "agency": r.agency,
+                "subject": r.subject,
+                "tracking_number": r.tracking_number,
+                "jurisdiction": r.jurisdiction,
+                "status": r.status.as_str(),
+                "filed_on": r.filed_on.to_string(),
+                "due_on": item.due_on.to_string(),
+                "days_overdue": item.days_overdue,
+            })
+        })
+        .collect();
+    json!({
+        "event": "foia_requests_overdue",
+        "text": subject_line(overdue),
+        "requests": requests,
+    })
+}
+
+async fn send_webhook(url: &str, overdue: &[OverdueRequest]) -> Result<(), String> {
+    let client =
+        HttpClient::builder("reminders", Duration::from_secs(30), Duration::ZERO).build()?;
+    let response = client
+        .post_json(url, &webhook_payload(overdue))
+        .await
+        .map_err(|e| format!("webhook {}: {}", url, e))?;
+    if !response.is_success() {
+        return Err(format!("webhook {} returned {}", url, response.status));
+    }
+    Ok(())
+}
+
+async fn send_email(
+    email: &EmailConfig,
+    privacy: &PrivacyConfig,
+    to: &str,
+    overdue: &[OverdueRequest],
+) -> Result<(), String> {
+    let mailer = Mailer::from_config(email, privacy).map_err(|e| e.to_string())?;
+    mailer
+        .send(&[to.to_string()], &reminder_email(overdue))
+        .await
+        .map_err(|e| format!("email to {}: {}", to, e))
+}
+
+/// Send one reminder listing `overdue` to every configured channel. Errors
+/// from each channel are collected; the others are still tried.
+pub async fn send_reminders(
+    config: &RemindersConfig,
+    email: &EmailConfig,
+    privacy: &PrivacyConfig,
+    overdue: &[OverdueRequest],
+) -> Result<(), Vec<String>> {
+    if overdue.is_empty() {
+        return Ok(());
+    }
+    let mut errors = Vec::new();
+    if let Some(url) = &config.webhook {
+        if let Err(e) = send_webhook(url, overdue).await {
+            errors.push(e);
+        }
+    }
+    if let Some(to) = &config.email {
+        if let Err(e) = send_email(email, privacy, to, overdue).await {
+            errors.push(e);
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FoiaRequestStatus;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn request(subject: &str, filed_on: &str) -> FoiaRequest {
+        FoiaRequest::new("FBI".to_string(), subject.to_string(), date(filed_on))
+    }
+
+    #[test]
+    fn test_reminders_due() {
+        let config = RequestsConfig::default();
+        let today = date("2024-04-10");
+
+        // Due 2024-03-29
+        let overdue = request("overdue", "2024-03-01");
+        let on_time = request("on time", "2024-04-01");
+        let mut fulfilled = request("fulfilled", "2024-03-01");
+        fulfilled.set_status(FoiaRequestStatus::Fulfilled, date("2024-04-02"));
+        let mut reminded = request("reminded", "2024-03-01");
+        reminded.reminded_on = Some(date("2024-04-08"));
+        let mut reminded_long_ago = request("reminded long ago", "2024-03-01");
+        reminded_long_ago.reminded_on = Some(date("2024-04-01"));
+
+        let due = reminders_due(
+            vec![overdue, on_time, fulfilled, reminded, reminded_long_ago],
+            &config,
+            today,
+        );
+        let subjects: Vec<_> = due.iter().map(|o| o.request.subject.as_str()).collect();
+        assert_eq!(subjects, vec!["overdue", "reminded long ago"]);
+        assert_eq!(due[0].due_on, date("2024-03-29"));
+        assert_eq!(due[0].days_overdue, 12);
+    }
+
+    #[test]
+    fn test_reminder_before_new_deadline() {
+        // Reminded about the original deadline, then appealed; the appeal
+        // deadline passing is news even within the repeat interval
+        let mut appealed = request("appealed", "2024-01-02");
+        appealed.set_status(FoiaRequestStatus::Appealed, date("2024-02-01"));
+        appealed.reminded_on = Some(date("2024-02-28"));
+        let due = reminders_due(
+            vec![appealed],
+            &RequestsConfig::default(),
+            date("2024-03-04"),
+        );
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].due_on, date("2024-03-01"));
+    }
+
+    #[test]
+    fn test_email_message() {
+        let mut r = request("COINTELPRO files", "2024-03-01");
+        r.tracking_number = Some("1234567-000".to_string());
+        let overdue = vec![OverdueRequest {
+            request: r,
+            due_on: date("2024-03-29"),
+            days_overdue: 12,
+        }];
+        let email = reminder_email(&overdue);
+        assert_eq!(
+            email.subject,
+            "FOIA request overdue: COINTELPRO files (FBI)"
+        );
+        assert!(email.body.contains("due 2024-03-29 (12 days overdue)"));
+        assert!(email.body.contains("Tracking #: 1234567-000"));
+
+        let payload = webhook_payload(&overdue);
+        assert_eq!(payload["requests"][0]["days_overdue"], 12);
+        assert_eq!(payload["requests"][0]["tracking_number"], "1234567-000");
+    }
+}
@@ -0,0 +1,173 @@
+//! Running saved browse views as searches.
+//!
+//! A saved view stores the browse page's query string. Parsing it back into
+//! browse filters lets the same view be re-run later, e.g. to find what
+//! matched since the last digest.
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::{BoundingBox, Document, DocumentStatus};
+use crate::repository::diesel_document::{BrowseParams, ProcessingState};
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+
+/// Browse filters from a saved view's query string. Sorting and paging
+/// parameters are ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SavedSearch {
+    pub source_id: Option<String>,
+    /// Stored status name.
+    pub status: Option<String>,
+    pub categories: Vec<String>,
+    pub tags: Vec<String>,
+    pub record_types: Vec<String>,
+    pub poor_ocr: bool,
+    pub exemptions: Vec<String>,
+    pub pdf_producers: Vec<String>,
+    pub pdf_years: Vec<i32>,
+    pub pdf_postdated: bool,
+    pub processing: Option<ProcessingState>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    pub bbox: Option<BoundingBox>,
+    pub search_query: Option<String>,
+}
+
+fn csv(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl SavedSearch {
+    /// Parse a browse query string such as `source=fbi&tags=cointelpro`.
+    pub fn parse(query: &str) -> Self {
+        let mut search = Self::default();
+        for (key, value) in url::form_urlencoded::parse(query.trim_start_matches('?').as_bytes()) {
+            match key.as_ref() {
+                "source" => search.source_id = non_empty(&value),
+                "status" => {
+                    search.status =
+                        DocumentStatus::from_str(value.trim()).map(|s| s.as_str().to_string())
+                }
+                "types" => search.categories = csv(&value),
+                "tags" => search.tags = csv(&value),
+                "record_types" => search.record_types = csv(&value),
+                "poor_ocr" => search.poor_ocr = value == "true",
+                "exemptions" => search.exemptions = csv(&value),
+                "pdf_producer" => search.pdf_producers = non_empty(&value).into_iter().collect(),
+                "pdf_years" => {
+                    search.pdf_years = csv(&value).iter().filter_map(|y| y.parse().ok()).collect()
+                }
+                "pdf_postdated" => search.pdf_postdated = value == "true",
+                "processing" => search.processing = ProcessingState::from_str(value.trim()),
+                "start" => search.date_from = value.trim().parse().ok(),
+                "end" => search.date_to = value.trim().parse().ok(),
+                "bbox" => search.bbox = BoundingBox::parse(&value),
+                "q" => search.search_query = non_empty(&value),
+                _ => {}
+            }
+        }
+        search
+    }
+
+    /// Browse parameters for these filters, newest documents first.
+    pub fn browse_params(&self, limit: u32) -> BrowseParams<'_> {
+        BrowseParams {
+            source_id: self.source_id.as_deref(),
+            status: self.status.as_deref(),
+            categories: &self.categories,
+            tags: &self.tags,
+            record_types: &self.record_types,
+            poor_ocr: self.poor_ocr,
+            exemptions: &self.exemptions,
+            pdf_producers: &self.pdf_producers,
+            pdf_years: &self.pdf_years,
+            pdf_postdated: self.pdf_postdated,
+            processing: self.processing,
+            date_from: self.date_from,
+            date_to: self.date_to,
+            bbox: self.bbox,
+            search_query: self.search_query.as_deref(),
+            sort_field: Some("created_at"),
+            sort_order: Some("desc"),
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Up to `limit` matching documents first seen at or after `since`,
+    /// newest first.
+    pub async fn new_matches(
+        &self,
+        docs: &DieselDocumentRepository,
+        since: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<Document>, DieselError> {
+        let page = docs.browse(self.browse_params(limit)).await?;
+        Ok(page
+            .items
+            .into_iter()
+            .take_while(|doc| doc.created_at >= since)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_view_query() {
+        let search = SavedSearch::parse(
+            "source=fbi&types=pdf,email&tags=cointelpro&start=1960-01-01&q=hoover+memo&sort=title&per_page=50",
+        );
+        assert_eq!(search.source_id.as_deref(), Some("fbi"));
+        assert_eq!(search.categories, vec!["pdf", "email"]);
+        assert_eq!(search.tags, vec!["cointelpro"]);
+        assert_eq!(search.date_from, "1960-01-01".parse().ok());
+        assert_eq!(search.date_to, None);
+        assert_eq!(search.search_query.as_deref(), Some("hoover memo"));
+        assert!(!search.poor_ocr);
+
+        let params = search.browse_params(10);
+        assert_eq!(params.sort_field, Some("created_at"));
+        assert_eq!(params.limit, 10);
+
+        assert_eq!(SavedSearch::parse(""), SavedSearch::default());
+        assert_eq!(
+            SavedSearch::parse("?q=%22grand+jury%22")
+                .search_query
+                .as_deref(),
+            Some("\"grand jury\"")
+        );
+        assert_eq!(
+            SavedSearch::parse("bbox=35,-80,45,-70").bbox,
+            BoundingBox::parse("35,-80,45,-70")
+        );
+        assert_eq!(SavedSearch::parse("bbox=north").bbox, None);
+        assert_eq!(
+            SavedSearch::parse("exemptions=(b)(6),(b)(7)(C)").exemptions,
+            vec!["(b)(6)", "(b)(7)(C)"]
+        );
+        let pdf = SavedSearch::parse("pdf_producer=Adobe+PDF+Library&pdf_years=2019,x,2021");
+        assert_eq!(pdf.pdf_producers, vec!["Adobe PDF Library"]);
+        assert_eq!(pdf.pdf_years, vec![2019, 2021]);
+        assert_eq!(
+            SavedSearch::parse("status=analyzed").status.as_deref(),
+            Some("indexed")
+        );
+        assert_eq!(
+            SavedSearch::parse("processing=ocr_failed").processing,
+            Some(ProcessingState::OcrFailed)
+        );
+    }
+}
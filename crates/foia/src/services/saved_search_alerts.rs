@@ -0,0 +1,206 @@
+//! Alerts for new documents matching saved views.
+//!
+//! Reporters subscribe to a saved view to hear when anything matching it
+//! arrives. After each crawl the subscribed views are re-run and every
+//! subscriber gets one email listing the documents they haven't been told
+//! about yet; documents already alerted are remembered per subscriber.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::email::{Email, Mailer};
+use super::reports::{list_matches, ViewMatches};
+use super::saved_search::SavedSearch;
+use crate::config::EmailConfig;
+use crate::models::Document;
+use crate::privacy::PrivacyConfig;
+use crate::repository::pool::DieselError;
+use crate::repository::{parse_datetime, Repositories};
+
+/// Newest matching documents checked per view on each run.
+pub const ALERT_LIMIT: u32 = 50;
+
+/// Only documents added this many days ago or later are alerted about, so
+/// the first run after a long pause doesn't dig up old matches.
+pub const LOOKBACK_DAYS: i64 = 7;
+
+/// Everything one address should be told about.
+#[derive(Debug, Clone)]
+pub struct SubscriberAlert {
+    pub email: String,
+    pub matches: Vec<ViewMatches>,
+}
+
+/// New matches per subscriber that have not been alerted yet. Documents
+/// added before the address subscribed are skipped.
+pub async fn pending_alerts(
+    repos: &Repositories,
+    now: DateTime<Utc>,
+) -> Result<Vec<SubscriberAlert>, DieselError> {
+    let subscriptions = repos.saved_views.list_subscriptions().await?;
+    if subscriptions.is_empty() {
+        return Ok(Vec::new());
+    }
+    let queries: HashMap<String, String> = repos
+        .saved_views
+        .list()
+        .await?
+        .into_iter()
+        .map(|view| (view.name, view.query))
+        .collect();
+    let since = now - Duration::days(LOOKBACK_DAYS);
+
+    // Each view is run once however many addresses follow it
+    let mut results: HashMap<String, Vec<Document>> = HashMap::new();
+    let mut alerts: BTreeMap<String, Vec<ViewMatches>> = BTreeMap::new();
+    for sub in subscriptions {
+        let Some(query) = queries.get(&sub.view_name) else {
+            continue;
+        };
+        if !results.contains_key(&sub.view_name) {
+            let documents = SavedSearch::parse(query)
+                .new_matches(&repos.documents, since, ALERT_LIMIT)
+                .await?;
+            results.insert(sub.view_name.clone(), documents);
+        }
+
+        let subscribed_at = parse_datetime(&sub.created_at);
+        let candidates: Vec<&Document> = results[&sub.view_name]
+            .iter()
+            .filter(|doc| doc.created_at >= subscribed_at)
+            .collect();
+        let ids: Vec<String> = candidates.iter().map(|doc| doc.id.clone()).collect();
+        let alerted = repos
+            .saved_views
+            .alerted_ids(&sub.view_name, &sub.email, &ids)
+            .await?;
+        let documents: Vec<Document> = candidates
+            .into_iter()
+            .filter(|doc| !alerted.contains(&doc.id))
+            .cloned()
+            .collect();
+
+        if !documents.is_empty() {
+            alerts.entry(sub.email).or_default().push(ViewMatches {
+                name: sub.view_name,
+                query: query.clone(),
+                documents,
+            });
+        }
+    }
+
+    Ok(alerts
+        .into_iter()
+        .map(|(email, matches)| SubscriberAlert { email, matches })
+        .collect())
+}
+
+/// The email for one subscriber.
+pub fn alert_email(alert: &SubscriberAlert, config: &EmailConfig) -> Email {
+    let subject = match alert.matches.as_slice() {
+        [one] => format!("New documents matching \"{}\"", one.name),
+        many => format!("New documents matching {} saved searches", many.len()),
+    };
+    let mut body = String::from("New documents match your saved searches.\n\n");
+    body.push_str(&list_matches(&alert.matches, ALERT_LIMIT, config));
+    body.push_str(&format!(
+        "You receive these alerts because {} is subscribed to these saved views.\n",
+        alert.email
+    ));
+    Email::new(subject, body)
+}
+
+/// Email every subscriber their pending alerts and remember what was sent.
+/// Returns the number of emails sent; failures for one subscriber don't
+/// stop the others, and their documents are alerted on the next run.
+pub async fn send_alerts(
+    repos: &Repositories,
+    config: &EmailConfig,
+    privacy: &PrivacyConfig,
+) -> Result<usize, Vec<String>> {
+    let alerts = pending_alerts(repos, Utc::now())
+        .await
+        .map_err(|e| vec![e.to_string()])?;
+    if alerts.is_empty() {
+        return Ok(0);
+    }
+    let mailer = Mailer::from_config(config, privacy).map_err(|e| vec![e.to_string()])?;
+
+    let mut sent = 0;
+    let mut errors = Vec::new();
+    for alert in &alerts {
+        if let Err(e) = mailer
+            .send(&[alert.email.clone()], &alert_email(alert, config))
+            .await
+        {
+            errors.push(format!("{}: {}", alert.email, e));
+            continue;
+        }
+        sent += 1;
+        for view in &alert.matches {
+            let ids: Vec<String> = view.documents.iter().map(|doc| doc.id.clone()).collect();
+            if let Err(e) = repos
+                .saved_views
+                .record_alerted(&view.name, &alert.email, &ids)
+                .await
+            {
+                errors.push(format!("{}: {}", alert.email, e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(sent)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::DocumentVersion;
+
+    fn document(id: &str, title: &str) -> Document {
+        let version = DocumentVersion::new(b"%PDF", "application/pdf".to_string(), None);
+        Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            title.to_string(),
+            format!("https://vault.fbi.gov/{}", id),
+            version,
+            serde_json::json!({}),
+        )
+    }
+
+    #[test]
+    fn test_alert_email() {
+        let alert = SubscriberAlert {
+            email: "reporter@example.org".to_string(),
+            matches: vec![ViewMatches {
+                name: "hoover".to_string(),
+                query: "q=hoover".to_string(),
+                documents: vec![document("doc1", "Hoover memo")],
+            }],
+        };
+
+        let email = alert_email(&alert, &EmailConfig::default());
+        assert_eq!(email.subject, "New documents matching \"hoover\"");
+        assert!(email.body.contains("hoover (1 new document)\n"));
+        assert!(email
+            .body
+            .contains("- Hoover memo [fbi]\n  https://vault.fbi.gov/doc1\n"));
+        assert!(email.body.contains("reporter@example.org is subscribed"));
+
+        let config = EmailConfig {
+            base_url: Some("https://foia.example.org".to_string()),
+            ..Default::default()
+        };
+        let email = alert_email(&alert, &config);
+        assert!(email.body.contains("https://foia.example.org/?q=hoover\n"));
+        assert!(email
+            .body
+            .contains("https://foia.example.org/documents/doc1\n"));
+    }
+}
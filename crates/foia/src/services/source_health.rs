@@ -0,0 +1,218 @@
+//! Per-source crawl health from the request log.
+//!
+//! A scraper that breaks rarely fails loudly: the site changes layout, starts
+//! rate limiting, or goes away, and requests quietly stop succeeding. Daily
+//! success rates, latency and rate-limit incidents make that visible.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::repository::diesel_crawl::DailyRequestHealth;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselCrawlRepository;
+
+/// Days of history in a health report.
+pub const HEALTH_DAYS: usize = 30;
+
+/// A source with no successful request for this many days is stale.
+pub const STALE_DAYS: i64 = 7;
+
+/// Request outcomes for one day; all zero when nothing was requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayHealth {
+    pub date: NaiveDate,
+    pub requests: u64,
+    pub successes: u64,
+    pub rate_limited: u64,
+    /// Average duration of timed requests.
+    pub avg_latency_ms: Option<u64>,
+}
+
+impl DayHealth {
+    /// Percentage of requests that succeeded, if any were made.
+    pub fn success_rate(&self) -> Option<f64> {
+        rate(self.successes, self.requests)
+    }
+}
+
+/// Crawl health of one source over the last [`HEALTH_DAYS`] days.
+#[derive(Debug, Clone)]
+pub struct SourceHealth {
+    /// One entry per day, oldest first.
+    pub days: Vec<DayHealth>,
+    pub requests: u64,
+    pub successes: u64,
+    pub rate_limited: u64,
+    pub avg_latency_ms: Option<u64>,
+    /// Most recent successful request, at any time.
+    pub last_success: Option<DateTime<Utc>>,
+}
+
+impl SourceHealth {
+    /// Percentage of requests in the window that succeeded.
+    pub fn success_rate(&self) -> Option<f64> {
+        rate(self.successes, self.requests)
+    }
+
+    /// Days in the window with rate-limited responses.
+    pub fn rate_limited_days(&self) -> usize {
+        self.days.iter().filter(|d| d.rate_limited > 0).count()
+    }
+
+    /// No successful request in the last [`STALE_DAYS`] days.
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        self.last_success
+            .is_none_or(|at| (now - at).num_days() >= STALE_DAYS)
+    }
+
+    /// Stale while still being crawled: requests are going out but none
+    /// succeed, which usually means the scraper or the site broke.
+    pub fn is_broken(&self, now: DateTime<Utc>) -> bool {
+        let recent = self.days.iter().rev().take(STALE_DAYS as usize);
+        recent.map(|d| d.requests).sum::<u64>() > 0 && self.is_stale(now)
+    }
+}
+
+fn rate(part: u64, total: u64) -> Option<f64> {
+    (total > 0).then(|| part as f64 * 100.0 / total as f64)
+}
+
+fn average(total_ms: u64, count: u64) -> Option<u64> {
+    (count > 0).then(|| total_ms / count)
+}
+
+/// Spread per-day rows over a window of `days` days ending today, filling
+/// days without requests, and total them.
+fn summarize(
+    rows: &[DailyRequestHealth],
+    days: usize,
+    now: DateTime<Utc>,
+    last_success: Option<DateTime<Utc>>,
+) -> SourceHealth {
+    let today = now.date_naive();
+    let mut health = SourceHealth {
+        days: Vec::with_capacity(days),
+        requests: 0,
+        successes: 0,
+        rate_limited: 0,
+        avg_latency_ms: None,
+        last_success,
+    };
+    let (mut timed, mut duration_ms) = (0, 0);
+
+    for offset in (0..days as i64).rev() {
+        let date = today - Duration::days(offset);
+        let key = date.format("%Y-%m-%d").to_string();
+        let day = match rows.iter().find(|r| r.day == key) {
+            Some(r) => {
+                health.requests += r.requests;
+                health.successes += r.successes;
+                health.rate_limited += r.rate_limited;
+                timed += r.timed_requests;
+                duration_ms += r.total_duration_ms;
+                DayHealth {
+                    date,
+                    requests: r.requests,
+                    successes: r.successes,
+                    rate_limited: r.rate_limited,
+                    avg_latency_ms: average(r.total_duration_ms, r.timed_requests),
+                }
+            }
+            None => DayHealth {
+                date,
+                requests: 0,
+                successes: 0,
+                rate_limited: 0,
+                avg_latency_ms: None,
+            },
+        };
+        health.days.push(day);
+    }
+    health.avg_latency_ms = average(duration_ms, timed);
+    health
+}
+
+/// Build the health report for a source.
+pub async fn source_health(
+    crawl_repo: &DieselCrawlRepository,
+    source_id: &str,
+) -> Result<SourceHealth, DieselError> {
+    let now = Utc::now();
+    let start = (now - Duration::days(HEALTH_DAYS as i64 - 1))
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .unwrap_or_default()
+        .and_utc();
+
+    let rows = crawl_repo
+        .get_daily_request_health(source_id, start)
+        .await?;
+    let last_success = crawl_repo.get_last_successful_request(source_id).await?;
+    Ok(summarize(&rows, HEALTH_DAYS, now, last_success))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(day: &str, requests: u64, successes: u64, rate_limited: u64) -> DailyRequestHealth {
+        DailyRequestHealth {
+            day: day.to_string(),
+            requests,
+            successes,
+            rate_limited,
+            timed_requests: requests,
+            total_duration_ms: requests * 200,
+        }
+    }
+
+    #[test]
+    fn test_summarize_fills_missing_days() {
+        let now = "2024-03-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let rows = vec![
+            row("2024-03-01", 5, 5, 0),
+            row("2024-03-08", 10, 4, 3),
+            row("2024-03-10", 5, 1, 0),
+        ];
+        let health = summarize(&rows, 7, now, None);
+
+        assert_eq!(health.days.len(), 7);
+        assert_eq!(health.days[0].date.to_string(), "2024-03-04");
+        assert_eq!(health.days[6].date.to_string(), "2024-03-10");
+        assert_eq!(health.days[0].success_rate(), None);
+        assert_eq!(health.days[4].success_rate(), Some(40.0));
+        assert_eq!(health.days[4].avg_latency_ms, Some(200));
+
+        // The row before the window is left out of the totals
+        assert_eq!(health.requests, 15);
+        assert_eq!(health.successes, 5);
+        assert_eq!(health.rate_limited, 3);
+        assert_eq!(health.rate_limited_days(), 1);
+        assert_eq!(health.avg_latency_ms, Some(200));
+    }
+
+    #[test]
+    fn test_summarize_empty() {
+        let health = summarize(&[], HEALTH_DAYS, Utc::now(), None);
+        assert_eq!(health.days.len(), HEALTH_DAYS);
+        assert_eq!(health.success_rate(), None);
+        assert_eq!(health.avg_latency_ms, None);
+    }
+
+    #[test]
+    fn test_is_broken() {
+        let now = "2024-03-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let last_success = "2024-03-01T08:00:00Z".parse::<DateTime<Utc>>().ok();
+
+        let failing = summarize(&[row("2024-03-09", 10, 0, 0)], 30, now, last_success);
+        assert!(failing.is_stale(now));
+        assert!(failing.is_broken(now));
+
+        // Not crawled lately: stale, but nothing to alert about
+        let idle = summarize(&[row("2024-03-01", 10, 10, 0)], 30, now, last_success);
+        assert!(idle.is_stale(now));
+        assert!(!idle.is_broken(now));
+
+        let healthy = summarize(&[row("2024-03-09", 10, 9, 0)], 30, now, Some(now));
+        assert!(!healthy.is_broken(now));
+    }
+}
@@ -0,0 +1,207 @@
+//! Moving documents to stable IDs.
+//!
+//! Documents acquired before stable IDs have random IDs, and a document
+//! whose URL was linked with tracking parameters may have been acquired
+//! twice. The migration moves every document to its stable ID (see
+//! [`Document::stable_id`]); documents sharing one are merged into the
+//! oldest, and every replaced ID redirects to the one that took its place.
+
+use std::collections::HashMap;
+
+use crate::models::Document;
+use crate::repository::diesel_document::DocumentIdentity;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+
+/// Curation log actor for merges made by the migration.
+pub const MIGRATION_ACTOR: &str = "stable-ids";
+
+/// What a migration to stable IDs did, or would do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StableIdMigration {
+    /// Documents checked.
+    pub documents: u64,
+    /// Documents moved to their stable ID.
+    pub renamed: u64,
+    /// Duplicates merged into the document keeping the stable ID.
+    pub merged: u64,
+}
+
+impl StableIdMigration {
+    /// Whether every document already has its stable ID.
+    pub fn is_noop(&self) -> bool {
+        self.renamed == 0 && self.merged == 0
+    }
+}
+
+/// Documents grouped by stable ID, in order of their oldest document.
+fn group_by_stable_id(identities: Vec<DocumentIdentity>) -> Vec<(String, Vec<DocumentIdentity>)> {
+    let mut groups: Vec<(String, Vec<DocumentIdentity>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for identity in identities {
+        let stable_id = identity.stable_id();
+        match index.get(&stable_id) {
+            Some(&i) => groups[i].1.push(identity),
+            None => {
+                index.insert(stable_id.clone(), groups.len());
+                groups.push((stable_id, vec![identity]));
+            }
+        }
+    }
+    groups
+}
+
+/// Move every document to its stable ID, merging duplicates. With
+/// `dry_run`, only count what would change.
+pub async fn migrate_to_stable_ids(
+    docs: &DieselDocumentRepository,
+    dry_run: bool,
+) -> Result<StableIdMigration, DieselError> {
+    let identities = docs.document_identities().await?;
+    let mut result = StableIdMigration {
+        documents: identities.len() as u64,
+        ..Default::default()
+    };
+
+    for (stable_id, mut group) in group_by_stable_id(identities) {
+        // A document already at the stable ID keeps it, otherwise the oldest
+        let keeper = group.iter().position(|d| d.id == stable_id).unwrap_or(0);
+        let keeper = group.remove(keeper);
+        let rename = keeper.id != stable_id;
+        if !rename && group.is_empty() {
+            continue;
+        }
+
+        if !dry_run {
+            if !group.is_empty() {
+                let Some(target) = docs.get(&keeper.id).await? else {
+                    continue;
+                };
+                let mut duplicates = Vec::with_capacity(group.len());
+                for duplicate in &group {
+                    duplicates.extend(docs.get(&duplicate.id).await?);
+                }
+                docs.merge_documents(&target, &duplicates, Some(MIGRATION_ACTOR))
+                    .await?;
+            }
+            if rename {
+                docs.rename_document(&keeper.id, &stable_id).await?;
+            }
+        }
+        result.merged += group.len() as u64;
+        result.renamed += rename as u64;
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DocumentVersion, FlagReason};
+    use crate::repository::diesel_access_log::AccessKind;
+    use crate::repository::diesel_context::DieselDbContext;
+    use crate::repository::migrations;
+    use tempfile::tempdir;
+
+    fn document(id: &str, url: &str, content: &[u8]) -> Document {
+        Document::new(
+            id.to_string(),
+            "fbi".to_string(),
+            id.to_string(),
+            url.to_string(),
+            DocumentVersion::new(content, "application/pdf".to_string(), None),
+            serde_json::json!({}),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_migrate_to_stable_ids() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let ctx = DieselDbContext::from_sqlite_path(&db_path).unwrap();
+        let docs = ctx.documents();
+        let flags = ctx.flags();
+        let access = ctx.access_log();
+
+        let url = "https://vault.fbi.gov/a.pdf";
+        let stable_a = Document::stable_id("fbi", url);
+        let stable_b = Document::stable_id("fbi", "https://vault.fbi.gov/b.pdf");
+        docs.save_with_versions(&document("old-a", url, b"a"))
+            .await
+            .unwrap();
+        docs.save_with_versions(&document(
+            "dup-a",
+            "https://vault.fbi.gov/a.pdf?utm_source=feed",
+            b"a2",
+        ))
+        .await
+        .unwrap();
+        docs.save_with_versions(&document(&stable_b, "https://vault.fbi.gov/b.pdf", b"b"))
+            .await
+            .unwrap();
+        flags
+            .create("old-a", FlagReason::Pii, Some(1), "Phone number")
+            .await
+            .unwrap();
+        let now = chrono::Utc::now();
+        for id in ["old-a", "dup-a", "dup-a"] {
+            access
+                .record(id, AccessKind::View, None, now)
+                .await
+                .unwrap();
+        }
+
+        let planned = migrate_to_stable_ids(&docs, true).await.unwrap();
+        assert_eq!(
+            planned,
+            StableIdMigration {
+                documents: 3,
+                renamed: 1,
+                merged: 1,
+            }
+        );
+        assert!(docs.exists("old-a").await.unwrap());
+
+        let done = migrate_to_stable_ids(&docs, false).await.unwrap();
+        assert_eq!(done, planned);
+        assert!(!docs.exists("old-a").await.unwrap());
+        assert!(!docs.exists("dup-a").await.unwrap());
+        let merged = docs.get(&stable_a).await.unwrap().unwrap();
+        assert_eq!(merged.versions.len(), 2);
+        for old in ["old-a", "dup-a"] {
+            assert_eq!(
+                docs.resolve_redirect(old).await.unwrap().as_deref(),
+                Some(stable_a.as_str())
+            );
+        }
+
+        // Flags follow the document, so it stays withheld
+        assert!(!flags.has_open("old-a").await.unwrap());
+        assert!(flags.has_open(&stable_a).await.unwrap());
+
+        // So do access counts, summed across the merged documents
+        let top = access
+            .most_accessed(now - chrono::Duration::days(1), 10)
+            .await
+            .unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(
+            (top[0].document_id.as_str(), top[0].views),
+            (stable_a.as_str(), 3)
+        );
+
+        // Re-crawling the URL finds the migrated document
+        let found = docs
+            .find_by_source_url("fbi", "https://vault.fbi.gov/a.pdf?fbclid=x")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, stable_a);
+
+        assert!(migrate_to_stable_ids(&docs, false).await.unwrap().is_noop());
+    }
+}
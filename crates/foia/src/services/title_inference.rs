@@ -0,0 +1,633 @@
+//! Better titles for documents listed under meaningless filenames.
+//!
+//! Scrapers title a document from its link text or URL, which for many
+//! reading rooms gives "document.pdf (143)", "Scan0001" or a bare ID.
+//! [`is_junk_title`] recognises those. A better title is looked for, in
+//! order, in the Content-Disposition filename, the PDF's own metadata
+//! (Info dictionary, then XMP), the first heading on page one and,
+//! optionally, an LLM reading the text.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use tracing::{debug, warn};
+
+use crate::llm::LlmClient;
+use crate::models::Document;
+use crate::repository::pool::DieselError;
+use crate::repository::DieselDocumentRepository;
+use crate::utils::extract_title_from_url;
+
+/// Names that say nothing about a document once numbers are removed.
+const GENERIC_NAMES: &[&str] = &[
+    "attachment",
+    "blob",
+    "content",
+    "default",
+    "doc",
+    "document",
+    "download",
+    "file",
+    "fulltext",
+    "getattachment",
+    "getdoc",
+    "getdocument",
+    "getfile",
+    "image",
+    "img",
+    "index",
+    "new document",
+    "page",
+    "pdf",
+    "print",
+    "scan",
+    "scanned document",
+    "showdocument",
+    "slide",
+    "untitled",
+    "unknown",
+    "view",
+    "viewdocument",
+    "viewfile",
+];
+
+/// File extensions that turn up in titles as words.
+const EXTENSIONS: &[&str] = &[
+    "aspx", "doc", "docx", "htm", "html", "jsp", "pdf", "php", "txt",
+];
+
+/// Longest title kept, in characters.
+const MAX_TITLE_CHARS: usize = 200;
+
+/// Prefixes authoring tools put in front of a PDF's Info title.
+static TOOL_PREFIX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?i)(?:microsoft\s+(?:word|excel|powerpoint|outlook)|wordperfect)\s*-\s*")
+        .expect("valid regex")
+});
+
+/// `dc:title` in an XMP packet.
+static XMP_TITLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<dc:title>.*?<rdf:li[^>]*>(.*?)</rdf:li>").expect("valid regex")
+});
+
+/// A memo's subject line.
+static SUBJECT_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?i)(?:subject|subj|re)\s*:\s*(.+)$").expect("valid regex"));
+
+/// Classification banners, page numbers and other lines that are never a
+/// heading.
+static BOILERPLATE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?i)(?:(?:top\s+)?secret|confidential|unclassified|classified|(?:for\s+)?official\s+use\s+only|fouo|noforn|[a-z/ ]*//[a-z/ ]*|page\s+\d+(?:\s+of\s+\d+)?|\d+(?:\s+of\s+\d+)?|-\s*\d+\s*-|date\s*:.*|to\s*:.*|from\s*:.*|cc\s*:.*|b\d(?:\s*\([a-z]\))?(?:\s*,\s*b\d(?:\s*\([a-z]\))?)*)$",
+    )
+    .expect("valid regex")
+});
+
+/// Where a title came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleSource {
+    /// The Content-Disposition filename.
+    Filename,
+    /// The PDF's Info dictionary or XMP metadata.
+    PdfMetadata,
+    /// The first heading on page one.
+    Heading,
+    /// An LLM's reading of the text.
+    Llm,
+}
+
+impl TitleSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Filename => "filename",
+            Self::PdfMetadata => "pdf_metadata",
+            Self::Heading => "heading",
+            Self::Llm => "llm",
+        }
+    }
+}
+
+/// Whether `title` is a filename or ID rather than a title: a generic name
+/// like "document", "download" or "Scan0001", with or without a file
+/// extension and a "(143)" counter, or with no words at all.
+pub fn is_junk_title(title: &str) -> bool {
+    let name = strip_extension(title.trim()).to_lowercase();
+    let words: String = name
+        .chars()
+        .map(|c| if c.is_alphabetic() { c } else { ' ' })
+        .collect();
+    let words = words
+        .split_whitespace()
+        .filter(|word| !EXTENSIONS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if words.chars().count() < 3 {
+        return true;
+    }
+    if GENERIC_NAMES.contains(&words.as_str()) {
+        return true;
+    }
+    // Hashes and UUIDs
+    let compact: String = name.chars().filter(|c| !matches!(c, '-' | '_')).collect();
+    compact.len() >= 8 && compact.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// A title made from a filename: extension dropped, underscores and
+/// hyphens read as spaces. `None` if what's left is junk.
+pub fn title_from_filename(filename: &str) -> Option<String> {
+    let name = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let name = strip_extension(name).replace(['_', '-'], " ");
+    clean_title(&name)
+}
+
+/// `title` with whitespace collapsed, authoring-tool prefixes (and the
+/// filename extension after them) dropped and length capped. `None` if it
+/// is junk.
+pub fn clean_title(title: &str) -> Option<String> {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let title = match TOOL_PREFIX.find(&title) {
+        Some(prefix) => strip_extension(&title[prefix.end()..]),
+        None => title.as_str(),
+    };
+    let title = title.trim_matches(|c: char| c.is_whitespace() || c == '"' || c == '\'');
+    if is_junk_title(title) {
+        return None;
+    }
+    Some(truncate_words(title, MAX_TITLE_CHARS))
+}
+
+/// The `Title:` line of `pdfinfo` output.
+pub fn parse_pdfinfo_title(pdfinfo: &str) -> Option<String> {
+    pdfinfo
+        .lines()
+        .find_map(|line| line.strip_prefix("Title:"))
+        .and_then(clean_title)
+}
+
+/// The `dc:title` of an XMP packet.
+pub fn parse_xmp_title(xmp: &str) -> Option<String> {
+    let caps = XMP_TITLE.captures(xmp)?;
+    let title = caps[1]
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'");
+    clean_title(&title)
+}
+
+/// A PDF's own title from its Info dictionary, or failing that its XMP
+/// metadata, read with `pdfinfo`. Blocks; call from a blocking context.
+pub fn pdf_metadata_title(pdf: &Path) -> Option<String> {
+    let pdfinfo = |args: &[&str]| {
+        let output = Command::new("pdfinfo")
+            .args(args)
+            .arg(pdf)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    pdfinfo(&["-enc", "UTF-8"])
+        .as_deref()
+        .and_then(parse_pdfinfo_title)
+        .or_else(|| pdfinfo(&["-meta"]).as_deref().and_then(parse_xmp_title))
+}
+
+/// The heading of a first page: its subject line if it has one, otherwise
+/// the first line of words that isn't a marking, page number or memo
+/// header, joined with the next line when both are in capitals.
+pub fn first_page_heading(text: &str) -> Option<String> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .take(30)
+        .collect();
+
+    if let Some(subject) = lines
+        .iter()
+        .find_map(|line| SUBJECT_LINE.captures(line))
+        .and_then(|caps| clean_title(&caps[1]))
+    {
+        return Some(subject);
+    }
+
+    let is_heading = |line: &str| {
+        let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+        line.split_whitespace().count() >= 2
+            && line.chars().count() <= 120
+            && letters * 2 >= line.chars().count()
+            && !BOILERPLATE.is_match(line)
+    };
+    let is_caps = |line: &str| !line.chars().any(|c| c.is_lowercase());
+
+    let (i, &first) = lines
+        .iter()
+        .enumerate()
+        .take(15)
+        .find(|&(_, &line)| is_heading(line))?;
+    let mut heading = first.to_string();
+    if let Some(&next) = lines.get(i + 1) {
+        if is_caps(first) && is_caps(next) && is_heading(next) {
+            heading = format!("{} {}", heading, next);
+        }
+    }
+    clean_title(&heading)
+}
+
+/// What is known about a document when looking for its title.
+#[derive(Debug, Default)]
+pub struct TitleEvidence<'a> {
+    /// Filename from the Content-Disposition header.
+    pub filename: Option<&'a str>,
+    /// Stored file, if it is a PDF.
+    pub pdf: Option<&'a Path>,
+    /// Text of the first page.
+    pub first_page: Option<&'a str>,
+}
+
+/// The first title found in `evidence`, without asking an LLM. Reads the
+/// PDF with `pdfinfo`, so blocks; call from a blocking context.
+pub fn infer_title(evidence: &TitleEvidence) -> Option<(String, TitleSource)> {
+    evidence
+        .filename
+        .and_then(title_from_filename)
+        .map(|t| (t, TitleSource::Filename))
+        .or_else(|| {
+            evidence
+                .pdf
+                .and_then(pdf_metadata_title)
+                .map(|t| (t, TitleSource::PdfMetadata))
+        })
+        .or_else(|| {
+            evidence
+                .first_page
+                .and_then(first_page_heading)
+                .map(|t| (t, TitleSource::Heading))
+        })
+}
+
+/// Title for a newly downloaded document: its listed title, the
+/// Content-Disposition filename or the URL, whichever first isn't junk.
+/// If all are, the first of them that exists, as before.
+pub fn download_title(listed: Option<String>, filename: Option<&str>, url: &str) -> String {
+    if let Some(listed) = listed.as_deref().and_then(clean_title) {
+        return listed;
+    }
+    if let Some(title) = filename.and_then(title_from_filename) {
+        return title;
+    }
+    let from_url = extract_title_from_url(url);
+    if let Some(title) = clean_title(&from_url) {
+        return title;
+    }
+    listed
+        .or_else(|| filename.map(str::to_string))
+        .unwrap_or(from_url)
+}
+
+/// A title changed, or that would be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Retitled {
+    pub document_id: String,
+    pub from: String,
+    pub to: String,
+    pub source: TitleSource,
+}
+
+/// What a retitle run found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Retitle {
+    /// Documents looked at.
+    pub checked: u64,
+    /// Documents whose title is junk.
+    pub junk: u64,
+    /// Junk titles with nothing better found.
+    pub unresolved: u64,
+    pub retitled: Vec<Retitled>,
+}
+
+/// Look for a better title for every document with a junk title,
+/// optionally only from `source_id`, `batch_size` documents at a time.
+/// `llm` is asked when nothing else gives a title. Nothing is changed;
+/// pass the result to [`apply_retitles`].
+pub async fn find_retitles(
+    docs: &DieselDocumentRepository,
+    documents_dir: &Path,
+    source_id: Option<&str>,
+    llm: Option<&LlmClient>,
+    batch_size: usize,
+) -> Result<Retitle, DieselError> {
+    let batch_size = batch_size.max(1);
+    let mut result = Retitle::default();
+    let mut cursor = String::new();
+
+    loop {
+        let batch = docs
+            .get_titles_after(source_id, &cursor, batch_size)
+            .await?;
+        let Some((last, _)) = batch.last() else {
+            break;
+        };
+        cursor = last.clone();
+
+        for (id, title) in &batch {
+            result.checked += 1;
+            if !is_junk_title(title) {
+                continue;
+            }
+            result.junk += 1;
+            let Some(doc) = docs.get(id).await? else {
+                continue;
+            };
+
+            let Some((to, source)) = find_title(docs, documents_dir, &doc, llm).await? else {
+                debug!("No better title for {} ({})", doc.id, doc.title);
+                result.unresolved += 1;
+                continue;
+            };
+            result.retitled.push(Retitled {
+                document_id: doc.id.clone(),
+                from: doc.title.clone(),
+                to,
+                source,
+            });
+        }
+
+        if batch.len() < batch_size {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Give each document its new title, logging each change with its
+/// source. Documents deleted or retitled by hand since are skipped.
+/// Returns how many were changed.
+pub async fn apply_retitles(
+    docs: &DieselDocumentRepository,
+    retitles: &[Retitled],
+    actor: Option<&str>,
+) -> Result<usize, DieselError> {
+    let mut changed = 0;
+    for retitle in retitles {
+        let Some(doc) = docs.get(&retitle.document_id).await? else {
+            continue;
+        };
+        if doc.title != retitle.from {
+            continue;
+        }
+        docs.retitle_document(
+            &doc,
+            &retitle.to,
+            &serde_json::json!({ "source": retitle.source.as_str() }),
+            actor,
+        )
+        .await?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// Look through a stored document's evidence for a title.
+async fn find_title(
+    docs: &DieselDocumentRepository,
+    documents_dir: &Path,
+    doc: &Document,
+    llm: Option<&LlmClient>,
+) -> Result<Option<(String, TitleSource)>, DieselError> {
+    let Some(version) = doc.current_version() else {
+        return Ok(None);
+    };
+    let first_page = docs
+        .get_page(&doc.id, version.id as i32, 1)
+        .await?
+        .and_then(|page| page.final_text.or(page.ocr_text).or(page.pdf_text));
+    let pdf = (version.mime_type == "application/pdf")
+        .then(|| version.resolve_path(documents_dir, &doc.source_url, &doc.title))
+        .filter(|path| path.is_file());
+
+    let filename = version.original_filename.clone();
+    let found = tokio::task::spawn_blocking(move || {
+        infer_title(&TitleEvidence {
+            filename: filename.as_deref(),
+            pdf: pdf.as_deref(),
+            first_page: first_page.as_deref(),
+        })
+    })
+    .await
+    .unwrap_or_default();
+    if found.is_some() {
+        return Ok(found);
+    }
+
+    let Some(llm) = llm else {
+        return Ok(None);
+    };
+    let Some(text) = docs
+        .get_combined_page_text(&doc.id, version.id as i32)
+        .await?
+        .filter(|text| !text.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    match llm.suggest_title(&text, &doc.title).await {
+        Ok(suggested) => Ok(clean_title(&suggested).map(|t| (t, TitleSource::Llm))),
+        Err(e) => {
+            warn!("LLM title for {} failed: {}", doc.id, e);
+            Ok(None)
+        }
+    }
+}
+
+/// `name` without a file extension, if it has one.
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((base, ext))
+            if !base.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && ext.chars().any(|c| c.is_ascii_alphabetic()) =>
+        {
+            base
+        }
+        _ => name,
+    }
+}
+
+/// `text` cut to at most `max` characters at a word boundary.
+fn truncate_words(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        if out.chars().count() + word.chars().count() + 1 > max {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_junk_title() {
+        for junk in [
+            "document.pdf (143)",
+            "Document (2)",
+            "Scan0001",
+            "download",
+            "12345.pdf",
+            "3f2504e0-4f89-11d3-9a0c-0305e82c3301",
+            "getFile.aspx",
+            "  ",
+        ] {
+            assert!(is_junk_title(junk), "{junk}");
+        }
+        for title in [
+            "Annual FOIA Report 2023",
+            "FOIA-2023-00123",
+            "memo on surveillance",
+            "CIA CREST index",
+        ] {
+            assert!(!is_junk_title(title), "{title}");
+        }
+    }
+
+    #[test]
+    fn test_title_from_filename() {
+        assert_eq!(
+            title_from_filename("Annual_FOIA-Report_2023.pdf").as_deref(),
+            Some("Annual FOIA Report 2023")
+        );
+        assert_eq!(title_from_filename("document(3).pdf"), None);
+    }
+
+    #[test]
+    fn test_pdf_metadata_titles() {
+        let pdfinfo =
+            "Title:           Microsoft Word - Hoover memo re COINTELPRO.docx\nPages: 3\n";
+        assert_eq!(
+            parse_pdfinfo_title(pdfinfo).as_deref(),
+            Some("Hoover memo re COINTELPRO")
+        );
+        assert_eq!(parse_pdfinfo_title("Title:           untitled\n"), None);
+
+        let xmp = r#"<dc:title><rdf:Alt><rdf:li xml:lang="x-default">Audit of the FBI&apos;s Records</rdf:li></rdf:Alt></dc:title>"#;
+        assert_eq!(
+            parse_xmp_title(xmp).as_deref(),
+            Some("Audit of the FBI's Records")
+        );
+    }
+
+    #[test]
+    fn test_first_page_heading() {
+        let memo = "UNCLASSIFIED\nDATE: March 4, 1964\nTO: Director\nSUBJECT: Surveillance of the Fair Play Committee\n\nBody text.";
+        assert_eq!(
+            first_page_heading(memo).as_deref(),
+            Some("Surveillance of the Fair Play Committee")
+        );
+
+        let report = "Page 1 of 40\n\nOFFICE OF THE INSPECTOR GENERAL\nREVIEW OF FOIA BACKLOG REDUCTION\nMarch 2021\nThe office reviewed...";
+        assert_eq!(
+            first_page_heading(report).as_deref(),
+            Some("OFFICE OF THE INSPECTOR GENERAL REVIEW OF FOIA BACKLOG REDUCTION")
+        );
+        assert_eq!(first_page_heading("SECRET\n12\n"), None);
+    }
+
+    #[test]
+    fn test_download_title() {
+        let url = "https://agency.gov/files/document(143).pdf";
+        assert_eq!(
+            download_title(
+                Some("document.pdf (143)".into()),
+                Some("Budget_2024.pdf"),
+                url
+            ),
+            "Budget 2024"
+        );
+        assert_eq!(
+            download_title(None, None, "https://agency.gov/files/budget-request.pdf"),
+            "budget request"
+        );
+        // Nothing better: keep what there is
+        assert_eq!(download_title(None, Some("file.pdf"), url), "file.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_find_and_apply_retitles() {
+        use crate::models::{DocumentPage, DocumentVersion};
+        use crate::repository::diesel_context::DieselDbContext;
+        use crate::repository::migrations;
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        migrations::run_migrations(&format!("sqlite:{}", db_path.display()), false)
+            .await
+            .unwrap();
+        let docs = DieselDbContext::from_sqlite_path(&db_path)
+            .unwrap()
+            .documents();
+
+        for (id, title) in [("junk", "document (143)"), ("good", "Annual Report")] {
+            let version = DocumentVersion::new(id.as_bytes(), "text/plain".to_string(), None);
+            let doc = Document::new(
+                id.to_string(),
+                "agency".to_string(),
+                title.to_string(),
+                format!("https://agency.gov/{}", id),
+                version,
+                serde_json::json!({}),
+            );
+            docs.save_with_versions(&doc).await.unwrap();
+            let doc = docs.get(id).await.unwrap().unwrap();
+            let mut page = DocumentPage::new(doc.id.clone(), doc.versions[0].id, 1);
+            page.final_text = Some("SECRET\nSUBJECT: Border crossings, 1962\n".to_string());
+            docs.save_page(&page).await.unwrap();
+        }
+
+        let found = find_retitles(&docs, &dir.path().join("documents"), None, None, 1)
+            .await
+            .unwrap();
+        assert_eq!(found.checked, 2);
+        assert_eq!(found.junk, 1);
+        assert_eq!(
+            found.retitled,
+            vec![Retitled {
+                document_id: "junk".to_string(),
+                from: "document (143)".to_string(),
+                to: "Border crossings, 1962".to_string(),
+                source: TitleSource::Heading,
+            }]
+        );
+        assert_eq!(
+            docs.get("junk").await.unwrap().unwrap().title,
+            "document (143)"
+        );
+
+        assert_eq!(
+            apply_retitles(&docs, &found.retitled, None).await.unwrap(),
+            1
+        );
+        assert_eq!(
+            docs.get("junk").await.unwrap().unwrap().title,
+            "Border crossings, 1962"
+        );
+        assert_eq!(
+            apply_retitles(&docs, &found.retitled, None).await.unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,413 @@
+//! Peer-to-peer distribution of sources.
+//!
+//! Large public-interest releases are cheapest to serve when downloaders
+//! share the load. Publishing a source writes its bundle (see
+//! [`super::bundle`]) and the bundle manifest into a directory, creates a
+//! BitTorrent v1 torrent of that directory, and records the magnet link on
+//! the source so the browse page and static site can offer it.
+//!
+//! ```text
+//! <out>/<source>/manifest.json
+//! <out>/<source>/<source>.tar
+//! <out>/<source>.torrent
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use super::bundle::{self, BundleError};
+use crate::models::SourceDistribution;
+use crate::repository::pool::DieselError;
+use crate::repository::{DieselDocumentRepository, DieselSourceRepository};
+
+const MIN_PIECE_LENGTH: u64 = 256 * 1024;
+const MAX_PIECE_LENGTH: u64 = 16 * 1024 * 1024;
+/// Pieces a torrent aims for; fewer, larger pieces keep the file small.
+const TARGET_PIECES: u64 = 1500;
+
+#[derive(Debug, Error)]
+pub enum TorrentError {
+    #[error("Database error: {0}")]
+    Database(#[from] DieselError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+    #[error("Source '{0}' not found")]
+    SourceNotFound(String),
+    #[error("Nothing to share in {0}")]
+    Empty(PathBuf),
+}
+
+/// Where a torrent can be fetched from besides the DHT.
+#[derive(Debug, Clone, Default)]
+pub struct TorrentOptions {
+    /// Tracker announce URLs, tried in order.
+    pub trackers: Vec<String>,
+    /// HTTP(S) URLs serving the same files (BEP 19).
+    pub web_seeds: Vec<String>,
+    pub comment: Option<String>,
+}
+
+/// A created torrent.
+#[derive(Debug, Clone)]
+pub struct Torrent {
+    pub name: String,
+    /// BitTorrent v1 info hash, hex.
+    pub info_hash: String,
+    /// Total size of the shared files in bytes.
+    pub size: u64,
+    pub files: usize,
+    /// Bencoded `.torrent` contents.
+    pub metainfo: Vec<u8>,
+    trackers: Vec<String>,
+    web_seeds: Vec<String>,
+}
+
+impl Torrent {
+    /// Magnet link naming the torrent's trackers and web seeds.
+    pub fn magnet(&self) -> String {
+        let mut magnet = format!(
+            "magnet:?xt=urn:btih:{}&dn={}",
+            self.info_hash,
+            urlencoding::encode(&self.name)
+        );
+        for tracker in &self.trackers {
+            magnet.push_str("&tr=");
+            magnet.push_str(&urlencoding::encode(tracker));
+        }
+        for seed in &self.web_seeds {
+            magnet.push_str("&ws=");
+            magnet.push_str(&urlencoding::encode(seed));
+        }
+        magnet
+    }
+}
+
+/// A bencoded value.
+enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Bencode>),
+    /// Keys are kept sorted, as the format requires.
+    Dict(BTreeMap<&'static str, Bencode>),
+}
+
+impl Bencode {
+    fn str(s: &str) -> Self {
+        Self::Bytes(s.as_bytes().to_vec())
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Int(n) => out.extend_from_slice(format!("i{}e", n).as_bytes()),
+            Self::Bytes(b) => {
+                out.extend_from_slice(format!("{}:", b.len()).as_bytes());
+                out.extend_from_slice(b);
+            }
+            Self::List(items) => {
+                out.push(b'l');
+                for item in items {
+                    item.encode(out);
+                }
+                out.push(b'e');
+            }
+            Self::Dict(entries) => {
+                out.push(b'd');
+                for (key, value) in entries {
+                    Self::str(key).encode(out);
+                    value.encode(out);
+                }
+                out.push(b'e');
+            }
+        }
+    }
+}
+
+/// Files under `dir`, by path relative to it, in a stable order.
+fn list_files(dir: &Path) -> Result<Vec<(Vec<String>, PathBuf, u64)>, TorrentError> {
+    let mut files = Vec::new();
+    let mut pending = vec![(Vec::new(), dir.to_path_buf())];
+    while let Some((prefix, path)) = pending.pop() {
+        for entry in std::fs::read_dir(&path)? {
+            let entry = entry?;
+            let mut relative = prefix.clone();
+            relative.push(entry.file_name().to_string_lossy().to_string());
+            let meta = entry.metadata()?;
+            if meta.is_dir() {
+                pending.push((relative, entry.path()));
+            } else if meta.is_file() {
+                files.push((relative, entry.path(), meta.len()));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
+
+/// Smallest power of two giving at most [`TARGET_PIECES`] pieces.
+fn piece_length(total: u64) -> u64 {
+    let mut length = MIN_PIECE_LENGTH;
+    while length < MAX_PIECE_LENGTH && total.div_ceil(length) > TARGET_PIECES {
+        length *= 2;
+    }
+    length
+}
+
+/// Create a torrent sharing the file or directory at `root`.
+pub fn create_torrent(root: &Path, options: &TorrentOptions) -> Result<Torrent, TorrentError> {
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "foia".to_string());
+    let single = std::fs::metadata(root)?.is_file();
+    let files = if single {
+        vec![(
+            Vec::new(),
+            root.to_path_buf(),
+            std::fs::metadata(root)?.len(),
+        )]
+    } else {
+        list_files(root)?
+    };
+    let size: u64 = files.iter().map(|f| f.2).sum();
+    if size == 0 {
+        return Err(TorrentError::Empty(root.to_path_buf()));
+    }
+
+    // Pieces run across file boundaries, as if the files were concatenated
+    let piece_len = piece_length(size);
+    let mut pieces = Vec::new();
+    let mut hasher = Sha1::new();
+    let mut filled = 0u64;
+    let mut buf = vec![0u8; 64 * 1024];
+    for (_, path, _) in &files {
+        let mut file = File::open(path)?;
+        loop {
+            let want = buf.len().min((piece_len - filled) as usize);
+            let n = file.read(&mut buf[..want])?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            filled += n as u64;
+            if filled == piece_len {
+                pieces.extend_from_slice(&hasher.finalize_reset());
+                filled = 0;
+            }
+        }
+    }
+    if filled > 0 {
+        pieces.extend_from_slice(&hasher.finalize());
+    }
+
+    let mut info = BTreeMap::new();
+    info.insert("name", Bencode::str(&name));
+    info.insert("piece length", Bencode::Int(piece_len as i64));
+    info.insert("pieces", Bencode::Bytes(pieces));
+    if single {
+        info.insert("length", Bencode::Int(size as i64));
+    } else {
+        let entries = files
+            .iter()
+            .map(|(relative, _, len)| {
+                let mut file = BTreeMap::new();
+                file.insert("length", Bencode::Int(*len as i64));
+                file.insert(
+                    "path",
+                    Bencode::List(relative.iter().map(|p| Bencode::str(p)).collect()),
+                );
+                Bencode::Dict(file)
+            })
+            .collect();
+        info.insert("files", Bencode::List(entries));
+    }
+    let info = Bencode::Dict(info);
+    let mut info_bytes = Vec::new();
+    info.encode(&mut info_bytes);
+    let info_hash = hex::encode(Sha1::digest(&info_bytes));
+
+    let mut meta = BTreeMap::new();
+    meta.insert("info", info);
+    meta.insert(
+        "created by",
+        Bencode::str(concat!("foia ", env!("CARGO_PKG_VERSION"))),
+    );
+    meta.insert("creation date", Bencode::Int(Utc::now().timestamp()));
+    if let Some(first) = options.trackers.first() {
+        meta.insert("announce", Bencode::str(first));
+        meta.insert(
+            "announce-list",
+            Bencode::List(
+                options
+                    .trackers
+                    .iter()
+                    .map(|t| Bencode::List(vec![Bencode::str(t)]))
+                    .collect(),
+            ),
+        );
+    }
+    if !options.web_seeds.is_empty() {
+        meta.insert(
+            "url-list",
+            Bencode::List(options.web_seeds.iter().map(|s| Bencode::str(s)).collect()),
+        );
+    }
+    if let Some(comment) = &options.comment {
+        meta.insert("comment", Bencode::str(comment));
+    }
+    let mut metainfo = Vec::new();
+    Bencode::Dict(meta).encode(&mut metainfo);
+
+    Ok(Torrent {
+        name,
+        info_hash,
+        size,
+        files: files.len(),
+        metainfo,
+        trackers: options.trackers.clone(),
+        web_seeds: options.web_seeds.clone(),
+    })
+}
+
+/// A published source.
+#[derive(Debug, Clone)]
+pub struct PublishedTorrent {
+    pub torrent: Torrent,
+    /// The directory the torrent shares.
+    pub content_dir: PathBuf,
+    pub torrent_path: PathBuf,
+    pub distribution: SourceDistribution,
+}
+
+/// Bundle a source into `out`, create a torrent of the bundle and its
+/// manifest, and record the magnet link on the source.
+pub async fn publish_torrent(
+    docs: &DieselDocumentRepository,
+    sources: &DieselSourceRepository,
+    source_id: &str,
+    documents_dir: &Path,
+    out: &Path,
+    options: &TorrentOptions,
+) -> Result<PublishedTorrent, TorrentError> {
+    let mut source = sources
+        .get(source_id)
+        .await?
+        .ok_or_else(|| TorrentError::SourceNotFound(source_id.to_string()))?;
+
+    let content_dir = out.join(&source.id);
+    std::fs::create_dir_all(&content_dir)?;
+    let manifest = bundle::export_bundle(
+        docs,
+        sources,
+        std::slice::from_ref(&source.id),
+        documents_dir,
+        &content_dir.join(format!("{}.tar", source.id)),
+    )
+    .await?;
+    // Alongside the bundle so peers can see what it holds before fetching it
+    std::fs::write(
+        content_dir.join("manifest.json"),
+        serde_json::to_vec_pretty(&manifest)?,
+    )?;
+
+    let mut options = options.clone();
+    if options.comment.is_none() {
+        options.comment = Some(format!(
+            "{}: {} documents, exported {}",
+            source.name,
+            manifest.documents,
+            manifest.created_at.format("%Y-%m-%d")
+        ));
+    }
+    let torrent = create_torrent(&content_dir, &options)?;
+    let torrent_path = out.join(format!("{}.torrent", source.id));
+    std::fs::write(&torrent_path, &torrent.metainfo)?;
+
+    let distribution = SourceDistribution {
+        magnet: torrent.magnet(),
+        info_hash: torrent.info_hash.clone(),
+        size: torrent.size,
+        documents: manifest.documents,
+        published_at: Utc::now(),
+    };
+    source.set_distribution(&distribution);
+    sources.save(&source).await?;
+
+    Ok(PublishedTorrent {
+        torrent,
+        content_dir,
+        torrent_path,
+        distribution,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bencode() {
+        let mut dict = BTreeMap::new();
+        dict.insert(
+            "spam",
+            Bencode::List(vec![Bencode::str("a"), Bencode::Int(-3)]),
+        );
+        dict.insert("cow", Bencode::str("moo"));
+        let mut out = Vec::new();
+        Bencode::Dict(dict).encode(&mut out);
+        assert_eq!(out, b"d3:cow3:moo4:spamll:ai-3eee".to_vec());
+    }
+
+    #[test]
+    fn test_create_torrent() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("fbi");
+        std::fs::create_dir_all(root.join("files")).unwrap();
+        std::fs::write(root.join("manifest.json"), b"{}").unwrap();
+        std::fs::write(root.join("files").join("a.pdf"), vec![7u8; 300_000]).unwrap();
+
+        let options = TorrentOptions {
+            trackers: vec!["udp://tracker.example:1337/announce".to_string()],
+            web_seeds: vec!["https://example.org/releases/".to_string()],
+            comment: None,
+        };
+        let torrent = create_torrent(&root, &options).unwrap();
+        assert_eq!(torrent.files, 2);
+        assert_eq!(torrent.size, 300_002);
+        assert_eq!(torrent.info_hash.len(), 40);
+        // 300,002 bytes in 256 KiB pieces is two piece hashes
+        let meta = &torrent.metainfo;
+        assert!(meta.windows(11).any(|w| w == b"6:pieces40:"));
+        assert!(meta.windows(18).any(|w| w == b"5:filesld6:lengthi"));
+
+        let magnet = torrent.magnet();
+        assert!(magnet.starts_with(&format!("magnet:?xt=urn:btih:{}&dn=fbi", torrent.info_hash)));
+        assert!(magnet.contains("&tr=udp%3A%2F%2Ftracker.example%3A1337%2Fannounce"));
+        assert!(magnet.contains("&ws=https%3A%2F%2Fexample.org%2Freleases%2F"));
+
+        // The info hash depends only on the content
+        let again = create_torrent(&root, &TorrentOptions::default()).unwrap();
+        assert_eq!(again.info_hash, torrent.info_hash);
+    }
+
+    #[test]
+    fn test_piece_length() {
+        assert_eq!(piece_length(1), MIN_PIECE_LENGTH);
+        assert_eq!(piece_length(1500 * MIN_PIECE_LENGTH), MIN_PIECE_LENGTH);
+        assert_eq!(
+            piece_length(1500 * MIN_PIECE_LENGTH + 1),
+            2 * MIN_PIECE_LENGTH
+        );
+        assert_eq!(piece_length(u64::MAX / 2), MAX_PIECE_LENGTH);
+    }
+}
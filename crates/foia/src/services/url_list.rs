@@ -0,0 +1,301 @@
+//! Lists of known document URLs with optional metadata.
+//!
+//! Investigations often start from a spreadsheet of links rather than a
+//! site to crawl. A list is CSV with a header row naming a `url` column, or
+//! JSONL with one object per line; `title`, `date` and `source` are optional.
+//! The title and date travel with the queued URL in its discovery context and
+//! are applied to the document when it is downloaded.
+
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use thiserror::Error;
+
+use crate::models::{CrawlUrl, DiscoveryMethod};
+
+/// Discovery context key holding the listed title.
+pub const TITLE_KEY: &str = "list_title";
+
+/// Discovery context key holding the listed date (`YYYY-MM-DD`).
+pub const DATE_KEY: &str = "list_date";
+
+#[derive(Debug, Error)]
+pub enum UrlListError {
+    #[error("CSV has no header row")]
+    MissingHeader,
+    #[error("CSV header has no 'url' column")]
+    MissingUrlColumn,
+    #[error("Line {line}: {message}")]
+    Invalid { line: usize, message: String },
+}
+
+/// File layout of a URL list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlListFormat {
+    Csv,
+    Jsonl,
+}
+
+impl UrlListFormat {
+    /// JSONL for `.jsonl`/`.ndjson` files, CSV otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("jsonl" | "ndjson") => Self::Jsonl,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// One row of a URL list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlListEntry {
+    /// Line the entry came from, for error messages.
+    pub line: usize,
+    pub url: String,
+    pub title: Option<String>,
+    pub date: Option<NaiveDate>,
+    pub source_id: Option<String>,
+}
+
+impl UrlListEntry {
+    /// The crawl queue entry for this row, under its own source or
+    /// `default_source`.
+    pub fn to_crawl_url(&self, default_source: &str) -> CrawlUrl {
+        let mut crawl_url = CrawlUrl::new(
+            self.url.clone(),
+            self.source_id
+                .clone()
+                .unwrap_or_else(|| default_source.to_string()),
+            DiscoveryMethod::Manual,
+            None,
+            0,
+        );
+        if let Some(title) = &self.title {
+            crawl_url
+                .discovery_context
+                .insert(TITLE_KEY.to_string(), title.clone().into());
+        }
+        if let Some(date) = self.date {
+            crawl_url
+                .discovery_context
+                .insert(DATE_KEY.to_string(), date.to_string().into());
+        }
+        crawl_url
+    }
+}
+
+/// Title given for a queued URL in a URL list, if any.
+pub fn listed_title(crawl_url: &CrawlUrl) -> Option<String> {
+    crawl_url
+        .discovery_context
+        .get(TITLE_KEY)?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Date given for a queued URL in a URL list, if any, as midnight UTC.
+pub fn listed_date(crawl_url: &CrawlUrl) -> Option<DateTime<Utc>> {
+    let date = crawl_url.discovery_context.get(DATE_KEY)?.as_str()?;
+    let midnight = date.parse::<NaiveDate>().ok()?.and_hms_opt(0, 0, 0)?;
+    Some(Utc.from_utc_datetime(&midnight))
+}
+
+fn optional(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    value.parse::<NaiveDate>().ok().or_else(|| {
+        DateTime::parse_from_rfc3339(value)
+            .ok()
+            .map(|d| d.date_naive())
+    })
+}
+
+fn entry(
+    line: usize,
+    url: Option<&str>,
+    title: Option<&str>,
+    date: Option<&str>,
+    source: Option<&str>,
+) -> Result<UrlListEntry, UrlListError> {
+    let invalid = |message: String| UrlListError::Invalid { line, message };
+    let url = optional(url).ok_or_else(|| invalid("missing url".to_string()))?;
+    if url::Url::parse(&url).is_err() {
+        return Err(invalid(format!("invalid URL '{}'", url)));
+    }
+    let date = match optional(date) {
+        Some(d) => Some(parse_date(&d).ok_or_else(|| invalid(format!("invalid date '{}'", d)))?),
+        None => None,
+    };
+    Ok(UrlListEntry {
+        line,
+        url,
+        title: optional(title),
+        date,
+        source_id: optional(source),
+    })
+}
+
+/// Split one CSV record, honouring double-quoted fields with `""` escapes.
+pub(crate) fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parse a URL list. Blank lines and lines starting with `#` are skipped.
+/// Each row is parsed independently so callers can skip bad rows.
+pub fn parse(
+    content: &str,
+    format: UrlListFormat,
+) -> Result<Vec<Result<UrlListEntry, UrlListError>>, UrlListError> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    match format {
+        UrlListFormat::Jsonl => Ok(lines
+            .map(|(n, line)| {
+                let value: serde_json::Value =
+                    serde_json::from_str(line).map_err(|e| UrlListError::Invalid {
+                        line: n,
+                        message: e.to_string(),
+                    })?;
+                let field = |key: &str| value.get(key).and_then(|v| v.as_str());
+                entry(
+                    n,
+                    field("url"),
+                    field("title"),
+                    field("date"),
+                    field("source"),
+                )
+            })
+            .collect()),
+        UrlListFormat::Csv => {
+            let (_, header) = lines.next().ok_or(UrlListError::MissingHeader)?;
+            let columns: Vec<String> = split_csv(header)
+                .into_iter()
+                .map(|c| c.trim().to_ascii_lowercase())
+                .collect();
+            let column = |name: &str| columns.iter().position(|c| c == name);
+            let url_col = column("url").ok_or(UrlListError::MissingUrlColumn)?;
+            let (title_col, date_col, source_col) =
+                (column("title"), column("date"), column("source"));
+
+            Ok(lines
+                .map(|(n, line)| {
+                    let fields = split_csv(line);
+                    let field =
+                        |col: Option<usize>| col.and_then(|i| fields.get(i)).map(String::as_str);
+                    entry(
+                        n,
+                        field(Some(url_col)),
+                        field(title_col),
+                        field(date_col),
+                        field(source_col),
+                    )
+                })
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "URL,Title,Date\n\
+            # from the agency's reading room\n\
+            https://vault.fbi.gov/a.pdf,\"Hoover memo, 1962\",1962-03-01\n\
+            https://vault.fbi.gov/b.pdf,,\n\
+            not a url,Broken,\n\
+            https://vault.fbi.gov/c.pdf,\"Say \"\"hi\"\"\",March\n";
+        let rows = parse(content, UrlListFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 4);
+
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.line, 3);
+        assert_eq!(first.title.as_deref(), Some("Hoover memo, 1962"));
+        assert_eq!(first.date, "1962-03-01".parse().ok());
+        assert_eq!(first.source_id, None);
+
+        let second = rows[1].as_ref().unwrap();
+        assert_eq!(second.title, None);
+        assert_eq!(second.date, None);
+
+        assert!(matches!(
+            rows[2],
+            Err(UrlListError::Invalid { line: 5, .. })
+        ));
+        assert!(matches!(
+            rows[3],
+            Err(UrlListError::Invalid { line: 6, .. })
+        ));
+
+        assert!(matches!(
+            parse("title\nfoo\n", UrlListFormat::Csv),
+            Err(UrlListError::MissingUrlColumn)
+        ));
+    }
+
+    #[test]
+    fn test_parse_jsonl_and_context() {
+        let content = r#"{"url": "https://example.gov/a.pdf", "title": "Budget", "date": "2020-05-01T12:00:00Z", "source": "cia"}
+{"url": "https://example.gov/b.pdf"}
+"#;
+        let rows = parse(content, UrlListFormat::Jsonl).unwrap();
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.source_id.as_deref(), Some("cia"));
+        assert_eq!(first.date, "2020-05-01".parse().ok());
+
+        let crawl_url = first.to_crawl_url("default");
+        assert_eq!(crawl_url.source_id, "cia");
+        assert_eq!(listed_title(&crawl_url).as_deref(), Some("Budget"));
+        assert_eq!(
+            listed_date(&crawl_url).map(|d| d.to_rfc3339()),
+            Some("2020-05-01T00:00:00+00:00".to_string())
+        );
+
+        let crawl_url = rows[1].as_ref().unwrap().to_crawl_url("default");
+        assert_eq!(crawl_url.source_id, "default");
+        assert_eq!(listed_title(&crawl_url), None);
+        assert_eq!(listed_date(&crawl_url), None);
+
+        assert_eq!(
+            UrlListFormat::from_path(Path::new("links.JSONL")),
+            UrlListFormat::Jsonl
+        );
+        assert_eq!(
+            UrlListFormat::from_path(Path::new("links.csv")),
+            UrlListFormat::Csv
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Coordinated shutdown on SIGINT/SIGTERM.
+//!
+//! Long-running commands call [`install`] and then check [`is_requested`]
+//! between items (or await [`requested`]): on the first signal they stop
+//! taking new work, finish what is in flight, and exit with a summary. A
+//! second signal exits at once, as Ctrl-C did before.
+
+use std::sync::OnceLock;
+
+use tokio::sync::watch;
+
+/// Exit status for a process stopped by a second signal (128 + SIGINT).
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// A one-way flag that can be awaited.
+struct Signal {
+    tx: watch::Sender<bool>,
+}
+
+impl Signal {
+    fn new() -> Self {
+        Self {
+            tx: watch::channel(false).0,
+        }
+    }
+
+    fn request(&self) {
+        self.tx.send_replace(true);
+    }
+
+    fn is_requested(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    async fn requested(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender outlives every receiver, so this only returns once set
+        let _ = rx.wait_for(|requested| *requested).await;
+    }
+}
+
+fn signal() -> &'static Signal {
+    static SIGNAL: OnceLock<Signal> = OnceLock::new();
+    SIGNAL.get_or_init(Signal::new)
+}
+
+/// Listen for SIGINT and SIGTERM, turning the first into a shutdown
+/// request. Safe to call more than once; only the first call listens.
+pub fn install() {
+    static INSTALLED: OnceLock<()> = OnceLock::new();
+    if INSTALLED.set(()).is_err() {
+        return;
+    }
+    tokio::spawn(async {
+        loop {
+            if !wait_for_signal().await {
+                return;
+            }
+            if is_requested() {
+                eprintln!("Forced shutdown");
+                std::process::exit(FORCED_EXIT_CODE);
+            }
+            eprintln!("Shutting down after in-flight work (press Ctrl-C again to exit now)");
+            request();
+        }
+    });
+}
+
+/// Wait for the next SIGINT or SIGTERM. Returns false if signals cannot
+/// be received.
+#[cfg(unix)]
+async fn wait_for_signal() -> bool {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (Ok(mut interrupt), Ok(mut terminate)) = (
+        signal(SignalKind::interrupt()),
+        signal(SignalKind::terminate()),
+    ) else {
+        return tokio::signal::ctrl_c().await.is_ok();
+    };
+    tokio::select! {
+        _ = interrupt.recv() => true,
+        _ = terminate.recv() => true,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> bool {
+    tokio::signal::ctrl_c().await.is_ok()
+}
+
+/// Ask everything watching for shutdown to stop.
+pub fn request() {
+    signal().request();
+}
+
+/// Whether shutdown has been requested.
+pub fn is_requested() -> bool {
+    signal().is_requested()
+}
+
+/// Resolve once shutdown is requested.
+pub async fn requested() {
+    signal().requested().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    // A local signal: requesting the global one would stop every other
+    // test in this process
+    #[tokio::test]
+    async fn test_request_wakes_waiters() {
+        let signal = Arc::new(Signal::new());
+        let waiter = {
+            let signal = signal.clone();
+            tokio::spawn(async move { signal.requested().await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        assert!(!signal.is_requested());
+
+        signal.request();
+        assert!(signal.is_requested());
+        tokio::time::timeout(std::time::Duration::from_secs(1), waiter)
+            .await
+            .expect("waiter woke")
+            .unwrap();
+        // Later waiters see the request without another signal
+        signal.requested().await;
+    }
+}
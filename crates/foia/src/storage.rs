@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc};
 
 use crate::models::{Document, DocumentVersion};
 use crate::repository::{extract_filename_parts, sanitize_filename, DieselDocumentRepository};
+use crate::utils::content_fingerprint;
 
 /// Metadata needed to save a document to disk and database.
 ///
@@ -17,6 +18,10 @@ pub struct DocumentInput {
     pub metadata: serde_json::Value,
     pub original_filename: Option<String>,
     pub server_date: Option<DateTime<Utc>>,
+    /// Content-Type the server declared, if `mime_type` was corrected.
+    pub declared_mime_type: Option<String>,
+    /// Tags to add to the document.
+    pub tags: Vec<String>,
 }
 
 /// Minimum length required for a content hash used in storage paths.
@@ -123,6 +128,28 @@ pub fn compute_storage_path_with_dedup(
     (relative, Some(content_hash.len() as u32 - 2))
 }
 
+/// Whether `content` matches the current version of `doc` once per-request
+/// noise such as render times and CSRF tokens is ignored. Always false for
+/// types that aren't fingerprinted, or when the stored file can't be read.
+pub async fn fingerprint_matches(
+    doc: &Document,
+    documents_dir: &Path,
+    content: &[u8],
+    mime_type: &str,
+) -> bool {
+    let Some(fingerprint) = content_fingerprint(content, mime_type) else {
+        return false;
+    };
+    let Some(current) = doc.current_version() else {
+        return false;
+    };
+    let path = current.resolve_path(documents_dir, &doc.source_url, &doc.title);
+    match tokio::fs::read(&path).await {
+        Ok(stored) => content_fingerprint(&stored, &current.mime_type) == Some(fingerprint),
+        Err(_) => false,
+    }
+}
+
 /// Save document content to disk and database.
 ///
 /// Uses `DocumentInput` so callers don't need to depend on `ScraperResult`.
@@ -160,18 +187,20 @@ pub async fn save_document_async(
         input.server_date,
     );
     version.dedup_index = dedup_index;
+    version.declared_mime_type = input.declared_mime_type.clone();
 
     // Check existing document
-    let existing = doc_repo.get_by_url(&input.url).await?;
+    let existing = doc_repo.find_by_source_url(source_id, &input.url).await?;
 
-    if let Some(mut doc) = existing.into_iter().next() {
+    if let Some(mut doc) = existing {
         if doc.add_version(version) {
             doc_repo.save_with_versions(&doc).await?;
         }
+        doc_repo.add_tags(&doc.id, &input.tags).await?;
         Ok(false) // Updated existing
     } else {
         let doc = Document::new(
-            uuid::Uuid::new_v4().to_string(),
+            Document::stable_id(source_id, &input.url),
             source_id.to_string(),
             input.title.clone(),
             input.url.clone(),
@@ -179,6 +208,7 @@ pub async fn save_document_async(
             input.metadata.clone(),
         );
         doc_repo.save_with_versions(&doc).await?;
+        doc_repo.add_tags(&doc.id, &input.tags).await?;
         Ok(true) // Created new
     }
 }
@@ -0,0 +1,71 @@
+//! URL canonicalization for document identity.
+//!
+//! The same document is often linked with tracking parameters appended or
+//! its query parameters in a different order. Canonicalizing before
+//! deriving a document ID maps all of those links to one document.
+
+use url::Url;
+
+/// Query parameters added by analytics and ad platforms, never by the
+/// servers we fetch from.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid",
+    "mc_eid", "_ga", "_gl", "_hsenc", "_hsmi", "mkt_tok", "ref_src",
+];
+
+fn is_tracking_param(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str())
+}
+
+/// The canonical form of a URL: lowercase scheme and host, no default
+/// port, tracking parameters removed and the rest sorted by name. Paths
+/// and fragments are kept, since both can name different documents.
+///
+/// Returns the URL trimmed if it doesn't parse.
+pub fn canonical_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url.trim()) else {
+        return url.trim().to_string();
+    };
+
+    if let Some(query) = parsed.query() {
+        // Work on the raw pairs so their encoding is left as found
+        let mut pairs: Vec<&str> = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| !is_tracking_param(pair.split('=').next().unwrap_or(pair)))
+            .collect();
+        pairs.sort_by_key(|pair| pair.split('=').next().unwrap_or(pair));
+        let query = pairs.join("&");
+        parsed.set_query((!query.is_empty()).then_some(query.as_str()));
+    }
+
+    parsed.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_url() {
+        assert_eq!(
+            canonical_url("HTTPS://Vault.FBI.gov:443/a.pdf?utm_source=x&b=2&a=1&fbclid=y"),
+            "https://vault.fbi.gov/a.pdf?a=1&b=2"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/doc.pdf?utm_medium=email"),
+            "https://example.com/doc.pdf"
+        );
+        assert_eq!(
+            canonical_url("https://example.com/doc.pdf#pages=1-3"),
+            "https://example.com/doc.pdf#pages=1-3"
+        );
+        // Repeated keys keep their order
+        assert_eq!(
+            canonical_url("https://example.com/?id=2&id=1&q=a%20b"),
+            "https://example.com/?id=2&id=1&q=a%20b"
+        );
+        assert_eq!(canonical_url(" not a url "), "not a url");
+    }
+}
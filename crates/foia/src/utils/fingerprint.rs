@@ -0,0 +1,115 @@
+//! Content fingerprints for pages that change on every request.
+//!
+//! Dynamic pages embed the render time, CSP nonces, CSRF tokens and the
+//! like, so their bytes differ on every fetch even when nothing a reader
+//! would notice has changed. Hashing the page with those stripped tells a
+//! real change from a re-render.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+/// Scripts and styles: inline ones carry nonces, build IDs and tracking
+/// state, and none of it is document content.
+static SCRIPT_OR_STYLE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<script\b.*?</script\s*>|<style\b.*?</style\s*>|<!--.*?-->").unwrap()
+});
+
+/// Attributes whose values are minted per request.
+static VOLATILE_ATTR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\s(?:nonce|integrity|data-[\w-]*(?:token|nonce|csrf|timestamp|time|request-id)[\w-]*)\s*=\s*(?:"[^"]*"|'[^']*'|[^\s>]+)"#,
+    )
+    .unwrap()
+});
+
+/// Hidden form fields and meta tags holding CSRF tokens and view state.
+static TOKEN_FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)<(?:input|meta)\b[^>]*(?:csrf|xsrf|token|__viewstate|__eventvalidation|authenticity)[^>]*>"#,
+    )
+    .unwrap()
+});
+
+/// Dates and times as pages print them: ISO 8601, RFC 2822 and clock times.
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b\d{4}-\d{2}-\d{2}[t ]\d{2}:\d{2}(?::\d{2}(?:\.\d+)?)?(?:z|[+-]\d{2}:?\d{2})?|\b(?:mon|tue|wed|thu|fri|sat|sun),? \d{1,2} \w{3} \d{4} \d{2}:\d{2}(?::\d{2})?(?: \w+)?|\b\d{1,2}:\d{2}(?::\d{2})?(?:\s?[ap]\.?m\.?)?\b",
+    )
+    .unwrap()
+});
+
+/// Long hex or base64 runs: session IDs, cache busters and request IDs.
+static OPAQUE_TOKEN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Za-z0-9+/_-]{32,}={0,2}").unwrap());
+
+static WHITESPACE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+").unwrap());
+
+/// Whether pages of this type are fingerprinted. Binary formats change only
+/// when their content does, so their plain hash already serves.
+fn is_fingerprinted(mime_type: &str) -> bool {
+    let mime_type = mime_type.split(';').next().unwrap_or("").trim();
+    mime_type.starts_with("text/")
+        || mime_type == "application/xhtml+xml"
+        || mime_type == "application/xml"
+        || mime_type == "application/json"
+}
+
+/// Page text with the parts that change on every request removed.
+pub fn normalize_page(content: &str) -> String {
+    let text = SCRIPT_OR_STYLE.replace_all(content, "");
+    let text = TOKEN_FIELD.replace_all(&text, "");
+    let text = VOLATILE_ATTR.replace_all(&text, "");
+    let text = TIMESTAMP.replace_all(&text, "");
+    let text = OPAQUE_TOKEN.replace_all(&text, "");
+    WHITESPACE.replace_all(&text, " ").trim().to_string()
+}
+
+/// SHA-256 of the normalized page, or None for types that aren't
+/// fingerprinted.
+pub fn content_fingerprint(content: &[u8], mime_type: &str) -> Option<String> {
+    if !is_fingerprinted(mime_type) {
+        return None;
+    }
+    let normalized = normalize_page(&String::from_utf8_lossy(content));
+    Some(hex::encode(Sha256::digest(normalized.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rerender_keeps_fingerprint() {
+        let first = br#"<html><head><script nonce="a1b2">var t=1718000000;</script>
+            <meta name="csrf-token" content="Zm9vYmFyYmF6cXV4Zm9vYmFyYmF6cXV4Zm9vYmFy"></head>
+            <body><p>FOIA log for June</p><footer>Generated 2024-06-10T14:03:22Z</footer></body></html>"#;
+        let second = br#"<html><head><script nonce="c3d4">var t=1718000999;</script>
+            <meta name="csrf-token" content="cXV4YmF6YmFyZm9vcXV4YmF6YmFyZm9vcXV4YmF6"></head>
+            <body><p>FOIA log   for June</p><footer>Generated 2024-06-10T15:41:07Z</footer></body></html>"#;
+        assert_eq!(
+            content_fingerprint(first, "text/html; charset=utf-8"),
+            content_fingerprint(second, "text/html")
+        );
+
+        let changed = br#"<html><body><p>FOIA log for July</p></body></html>"#;
+        assert_ne!(
+            content_fingerprint(first, "text/html"),
+            content_fingerprint(changed, "text/html")
+        );
+    }
+
+    #[test]
+    fn test_binary_not_fingerprinted() {
+        assert_eq!(content_fingerprint(b"%PDF-1.7", "application/pdf"), None);
+    }
+
+    #[test]
+    fn test_normalize_page() {
+        assert_eq!(
+            normalize_page("<p>Updated at 3:15 PM</p>\n\n<p>Item</p>"),
+            "<p>Updated at </p> <p>Item</p>"
+        );
+    }
+}
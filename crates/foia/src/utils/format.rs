@@ -13,6 +13,24 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Render counts as a one-line bar chart, e.g. `"▁▁▃█"`.
+///
+/// Zero is a blank, so days with nothing stand out.
+pub fn sparkline(values: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| {
+            if v == 0 {
+                ' '
+            } else {
+                BARS[((v * (BARS.len() as u64 - 1)) / max.max(1)) as usize]
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -25,4 +43,10 @@ mod tests {
         assert_eq!(format_size(1_500_000), "1.5 MB");
         assert_eq!(format_size(1_500_000_000), "1.5 GB");
     }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), " ▁▄█");
+        assert_eq!(sparkline(&[]), "");
+    }
 }
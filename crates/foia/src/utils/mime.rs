@@ -68,19 +68,46 @@ pub fn has_file_extension(url: &str) -> bool {
     FILE_EXTENSIONS.contains(&ext.as_str())
 }
 
+/// MIME types supported for text extraction (OCR/parsing).
+pub const EXTRACTABLE_MIME_TYPES: &[&str] = &[
+    "application/pdf",
+    "image/png",
+    "image/jpeg",
+    "image/tiff",
+    "image/gif",
+    "image/bmp",
+    "text/plain",
+    "text/html",
+];
+
 /// Check if a MIME type is supported for text extraction (OCR/parsing).
 pub fn is_extractable_mimetype(mime_type: &str) -> bool {
-    matches!(
-        mime_type,
-        "application/pdf"
-            | "image/png"
-            | "image/jpeg"
-            | "image/tiff"
-            | "image/gif"
-            | "image/bmp"
-            | "text/plain"
-            | "text/html"
-    )
+    EXTRACTABLE_MIME_TYPES.contains(&mime_type)
+}
+
+/// Detect the real type of `content` when it contradicts the declared one.
+///
+/// Returns the type found from the content's magic bytes if the declared
+/// type is generic (`application/octet-stream`, missing) or a different
+/// top-level type, e.g. a PDF served as `text/html`. Declared types in the
+/// same family, such as a DOCX that sniffs as a ZIP, are left alone.
+pub fn sniff_mime_type(content: &[u8], declared: &str) -> Option<&'static str> {
+    let detected = infer::get(content)?.mime_type();
+    let declared = declared
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if detected == declared {
+        return None;
+    }
+    let generic = matches!(
+        declared.as_str(),
+        "" | "application/octet-stream" | "binary/octet-stream" | "application/unknown"
+    );
+    let base = |mime: &str| mime.split('/').next().unwrap_or_default().to_string();
+    (generic || base(&declared) != base(detected)).then_some(detected)
 }
 
 /// Check if a MIME type represents a FOIA-relevant document format.
@@ -358,6 +385,33 @@ mod tests {
         assert!(mime_type_sql_condition("invalid").is_none());
     }
 
+    #[test]
+    fn test_sniff_mime_type() {
+        let pdf = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n";
+        assert_eq!(
+            sniff_mime_type(pdf, "text/html; charset=utf-8"),
+            Some("application/pdf")
+        );
+        assert_eq!(
+            sniff_mime_type(pdf, "application/octet-stream"),
+            Some("application/pdf")
+        );
+        assert_eq!(sniff_mime_type(pdf, "application/pdf"), None);
+
+        // Same family: a ZIP-based Office file stays as declared
+        let zip = b"PK\x03\x04\x14\x00\x00\x00";
+        assert_eq!(
+            sniff_mime_type(
+                zip,
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            ),
+            None
+        );
+
+        // Nothing recognisable: trust the server
+        assert_eq!(sniff_mime_type(b"<html></html>", "text/html"), None);
+    }
+
     #[test]
     fn guess_mime_from_filename_common_types() {
         assert_eq!(guess_mime_from_filename("report.pdf"), "application/pdf");
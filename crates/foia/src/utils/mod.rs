@@ -4,17 +4,27 @@
 //! - `html`: HTML escaping for safe rendering
 //! - `format`: Human-readable formatting (sizes, etc.)
 //! - `mime`: MIME type categorization and icons
+//! - `pdf`: Page counts, page-range extraction and metadata for PDFs
+//! - `canonical`: URL canonicalization for document identity
+//! - `fingerprint`: Content fingerprints that ignore per-request noise
 
+mod canonical;
+mod fingerprint;
 mod format;
 mod mime;
+mod pdf;
 pub mod url_finder;
 
-pub use format::format_size;
+pub use canonical::canonical_url;
+pub use fingerprint::{content_fingerprint, normalize_page};
+pub use format::{format_size, sparkline};
 pub use mime::{
     category_to_mime_patterns, guess_mime_from_filename, guess_mime_from_url,
     has_document_extension, has_file_extension, is_document_mimetype, is_extractable_mimetype,
-    mime_icon, mime_to_category, mime_type_category, MimeCategory,
+    mime_icon, mime_to_category, mime_type_category, sniff_mime_type, MimeCategory,
+    EXTRACTABLE_MIME_TYPES,
 };
+pub use pdf::{extract_pdf_pages, pdf_page_count, read_pdf_metadata};
 pub use url_finder::UrlFinder;
 
 /// Extract document title from URL.
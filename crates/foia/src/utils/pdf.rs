@@ -0,0 +1,69 @@
+//! Page-level PDF operations and metadata with poppler-utils.
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::models::PdfMetadata;
+
+/// Page count from `pdfinfo`. Blocks; call from a blocking context.
+pub fn pdf_page_count(pdf: &Path) -> Result<u32, String> {
+    let output = Command::new("pdfinfo")
+        .arg(pdf)
+        .output()
+        .map_err(|e| format!("Failed to run pdfinfo: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pdfinfo failed on {}", pdf.display()));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("Pages:"))
+        .and_then(|count| count.trim().parse().ok())
+        .ok_or_else(|| format!("pdfinfo reported no page count for {}", pdf.display()))
+}
+
+/// Pages `first..=last` of `pdf` as a new PDF, cut with `pdfseparate` and
+/// joined with `pdfunite`. Blocks; call from a blocking context.
+pub fn extract_pdf_pages(pdf: &Path, (first, last): (u32, u32)) -> Result<Vec<u8>, String> {
+    let dir = tempfile::tempdir().map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    let status = Command::new("pdfseparate")
+        .args(["-f", &first.to_string(), "-l", &last.to_string()])
+        .arg(pdf)
+        .arg(dir.path().join("page-%d.pdf"))
+        .status()
+        .map_err(|e| format!("Failed to run pdfseparate: {}", e))?;
+    if !status.success() {
+        return Err(format!("pdfseparate failed on {}", pdf.display()));
+    }
+
+    let output = dir.path().join("part.pdf");
+    let status = Command::new("pdfunite")
+        .args((first..=last).map(|page| dir.path().join(format!("page-{}.pdf", page))))
+        .arg(&output)
+        .status()
+        .map_err(|e| format!("Failed to run pdfunite: {}", e))?;
+    if !status.success() {
+        return Err(format!("pdfunite failed on {}", pdf.display()));
+    }
+    std::fs::read(&output).map_err(|e| format!("Failed to read extracted PDF: {}", e))
+}
+
+/// Info dictionary and XMP metadata from `pdfinfo`. Blocks; call from a
+/// blocking context.
+pub fn read_pdf_metadata(pdf: &Path) -> Result<PdfMetadata, String> {
+    let pdfinfo = |args: &[&str]| {
+        let output = Command::new("pdfinfo")
+            .args(args)
+            .arg(pdf)
+            .output()
+            .map_err(|e| format!("Failed to run pdfinfo: {}", e))?;
+        if !output.status.success() {
+            return Err(format!("pdfinfo failed on {}", pdf.display()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    };
+    let mut metadata = PdfMetadata::from_pdfinfo(&pdfinfo(&["-enc", "UTF-8", "-isodates"])?);
+    if let Ok(xmp) = pdfinfo(&["-meta"]) {
+        metadata.merge_xmp(&xmp);
+    }
+    Ok(metadata)
+}
@@ -0,0 +1,309 @@
+//! RabbitMQ broker.
+//!
+//! Each task kind has three durable queues on the default exchange:
+//! `foia.<kind>` holds tasks, `foia.<kind>.retry` holds nacked tasks until
+//! their per-message TTL expires and dead-letters them back, and
+//! `foia.<kind>.dead` holds tasks that used up their attempts. The
+//! delivery count travels in an `x-attempts` header.
+
+use async_trait::async_trait;
+use lapin::options::{
+    BasicAckOptions, BasicGetOptions, BasicNackOptions, BasicPublishOptions, ConfirmSelectOptions,
+    QueueDeclareOptions,
+};
+use lapin::types::{AMQPValue, FieldTable, LongString, ShortString};
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties};
+
+use super::{retry_delay, Broker, QueueDepth, Task, TaskKind, MAX_ATTEMPTS};
+use crate::work_queue::handle::ClaimId;
+use crate::work_queue::{WorkHandle, WorkQueueError};
+
+/// Header carrying how many times a task has been delivered.
+const ATTEMPTS_HEADER: &str = "x-attempts";
+
+/// Broker publishing to and receiving from RabbitMQ.
+pub struct AmqpBroker {
+    // Dropping the connection closes the channel
+    _connection: Connection,
+    channel: Channel,
+}
+
+fn amqp_error(e: lapin::Error) -> WorkQueueError {
+    WorkQueueError::Connection(e.to_string())
+}
+
+fn retry_queue(kind: TaskKind) -> String {
+    format!("{}.retry", kind.queue())
+}
+
+fn dead_queue(kind: TaskKind) -> String {
+    format!("{}.dead", kind.queue())
+}
+
+/// Arguments routing expired or rejected messages to `target`.
+fn dead_letter_to(target: &str) -> FieldTable {
+    let mut args = FieldTable::default();
+    args.insert(
+        "x-dead-letter-exchange".into(),
+        AMQPValue::LongString(LongString::from("")),
+    );
+    args.insert(
+        "x-dead-letter-routing-key".into(),
+        AMQPValue::LongString(LongString::from(target)),
+    );
+    args
+}
+
+fn attempts_of(properties: &BasicProperties) -> u32 {
+    let value = properties.headers().as_ref().and_then(|headers| {
+        headers
+            .inner()
+            .get(&ShortString::from(ATTEMPTS_HEADER))
+            .cloned()
+    });
+    match value {
+        Some(AMQPValue::LongLongInt(n)) => n.max(0) as u32,
+        Some(AMQPValue::LongInt(n)) => n.max(0) as u32,
+        Some(AMQPValue::LongUInt(n)) => n,
+        _ => 0,
+    }
+}
+
+impl AmqpBroker {
+    /// Connect and declare the queues for every task kind.
+    pub async fn connect(url: &str) -> Result<Self, WorkQueueError> {
+        let connection = Connection::connect(url, ConnectionProperties::default())
+            .await
+            .map_err(amqp_error)?;
+        let channel = connection.create_channel().await.map_err(amqp_error)?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(amqp_error)?;
+
+        let durable = QueueDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+        for kind in TaskKind::ALL {
+            channel
+                .queue_declare(&dead_queue(kind), durable, FieldTable::default())
+                .await
+                .map_err(amqp_error)?;
+            channel
+                .queue_declare(kind.queue(), durable, dead_letter_to(&dead_queue(kind)))
+                .await
+                .map_err(amqp_error)?;
+            channel
+                .queue_declare(&retry_queue(kind), durable, dead_letter_to(kind.queue()))
+                .await
+                .map_err(amqp_error)?;
+        }
+
+        Ok(Self {
+            _connection: connection,
+            channel,
+        })
+    }
+
+    /// Publish `payload` to `queue` and wait for the broker to confirm it.
+    async fn send(
+        &self,
+        queue: &str,
+        payload: &[u8],
+        attempts: u32,
+        expiration_ms: Option<u64>,
+    ) -> Result<(), WorkQueueError> {
+        let mut headers = FieldTable::default();
+        headers.insert(
+            ATTEMPTS_HEADER.into(),
+            AMQPValue::LongLongInt(attempts as i64),
+        );
+        let mut properties = BasicProperties::default()
+            .with_delivery_mode(2)
+            .with_content_type(ShortString::from("application/json"))
+            .with_headers(headers);
+        if let Some(ms) = expiration_ms {
+            properties = properties.with_expiration(ShortString::from(ms.to_string()));
+        }
+
+        let confirm = self
+            .channel
+            .basic_publish(
+                "",
+                queue,
+                BasicPublishOptions::default(),
+                payload,
+                properties,
+            )
+            .await
+            .map_err(amqp_error)?
+            .await
+            .map_err(amqp_error)?;
+        if confirm.is_nack() {
+            return Err(WorkQueueError::Connection(format!(
+                "RabbitMQ rejected a message for {}",
+                queue
+            )));
+        }
+        Ok(())
+    }
+
+    async fn ack_tag(&self, tag: u64) -> Result<(), WorkQueueError> {
+        self.channel
+            .basic_ack(tag, BasicAckOptions::default())
+            .await
+            .map_err(amqp_error)
+    }
+
+    async fn message_count(
+        &self,
+        queue: &str,
+        arguments: FieldTable,
+    ) -> Result<u64, WorkQueueError> {
+        let options = QueueDeclareOptions {
+            durable: true,
+            passive: true,
+            ..Default::default()
+        };
+        let queue = self
+            .channel
+            .queue_declare(queue, options, arguments)
+            .await
+            .map_err(amqp_error)?;
+        Ok(queue.message_count() as u64)
+    }
+}
+
+fn delivery_tag(claim_id: ClaimId) -> Result<u64, WorkQueueError> {
+    match claim_id {
+        ClaimId::DeliveryTag(tag) => Ok(tag),
+        other => Err(WorkQueueError::Other(format!(
+            "Not an AMQP delivery: {:?}",
+            other
+        ))),
+    }
+}
+
+#[async_trait]
+impl Broker for AmqpBroker {
+    fn name(&self) -> &'static str {
+        "RabbitMQ"
+    }
+
+    async fn publish(&self, task: &Task) -> Result<bool, WorkQueueError> {
+        let payload = task.to_payload()?;
+        self.send(task.kind.queue(), payload.as_bytes(), 0, None)
+            .await?;
+        Ok(true)
+    }
+
+    async fn receive(&self, kind: TaskKind) -> Result<Option<WorkHandle<Task>>, WorkQueueError> {
+        let message = self
+            .channel
+            .basic_get(kind.queue(), BasicGetOptions { no_ack: false })
+            .await
+            .map_err(amqp_error)?;
+        let Some(message) = message else {
+            return Ok(None);
+        };
+        let tag = message.delivery.delivery_tag;
+        let attempts = attempts_of(&message.delivery.properties) + 1;
+        match Task::from_payload(&message.delivery.data, attempts) {
+            Ok(task) => Ok(Some(WorkHandle::new(task, ClaimId::DeliveryTag(tag)))),
+            Err(e) => {
+                // Rejecting without requeue dead-letters it
+                self.channel
+                    .basic_nack(
+                        tag,
+                        BasicNackOptions {
+                            multiple: false,
+                            requeue: false,
+                        },
+                    )
+                    .await
+                    .map_err(amqp_error)?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn ack(&self, handle: WorkHandle<Task>) -> Result<(), WorkQueueError> {
+        let (_, claim_id) = handle.consume();
+        self.ack_tag(delivery_tag(claim_id)?).await
+    }
+
+    async fn nack(
+        &self,
+        handle: WorkHandle<Task>,
+        error: &str,
+        requeue: bool,
+    ) -> Result<(), WorkQueueError> {
+        let (task, claim_id) = handle.consume();
+        let tag = delivery_tag(claim_id)?;
+        if requeue && task.attempts < MAX_ATTEMPTS {
+            // RabbitMQ redelivers requeued messages at once and doesn't
+            // count them, so park a copy in the retry queue instead
+            let payload = task.to_payload()?;
+            let delay = retry_delay(task.attempts).as_millis() as u64;
+            self.send(
+                &retry_queue(task.kind),
+                payload.as_bytes(),
+                task.attempts,
+                Some(delay),
+            )
+            .await?;
+            self.ack_tag(tag).await
+        } else {
+            tracing::warn!(
+                "Task {} dead after {} attempts: {}",
+                task.kind.as_str(),
+                task.attempts,
+                error
+            );
+            self.channel
+                .basic_nack(
+                    tag,
+                    BasicNackOptions {
+                        multiple: false,
+                        requeue: false,
+                    },
+                )
+                .await
+                .map_err(amqp_error)
+        }
+    }
+
+    async fn depth(&self, kind: TaskKind) -> Result<QueueDepth, WorkQueueError> {
+        let ready = self
+            .message_count(kind.queue(), dead_letter_to(&dead_queue(kind)))
+            .await?;
+        let retrying = self
+            .message_count(&retry_queue(kind), dead_letter_to(kind.queue()))
+            .await?;
+        let dead = self
+            .message_count(&dead_queue(kind), FieldTable::default())
+            .await?;
+        Ok(QueueDepth {
+            ready: ready + retrying,
+            in_flight: None,
+            dead,
+        })
+    }
+
+    async fn retry_dead(&self, kind: TaskKind) -> Result<u64, WorkQueueError> {
+        let mut moved = 0;
+        while let Some(message) = self
+            .channel
+            .basic_get(&dead_queue(kind), BasicGetOptions { no_ack: false })
+            .await
+            .map_err(amqp_error)?
+        {
+            self.send(kind.queue(), &message.delivery.data, 0, None)
+                .await?;
+            self.ack_tag(message.delivery.delivery_tag).await?;
+            moved += 1;
+        }
+        Ok(moved)
+    }
+}
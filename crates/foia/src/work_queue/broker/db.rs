@@ -0,0 +1,99 @@
+//! Local-DB broker backed by the `broker_messages` table.
+//!
+//! Needs no extra service, and works across processes sharing a database.
+//! A received task is claimed for [`VISIBILITY_TIMEOUT`]; if its worker
+//! dies without acking, it becomes available again once that passes.
+
+use async_trait::async_trait;
+
+use super::{retry_delay, Broker, QueueDepth, Task, TaskKind, MAX_ATTEMPTS, VISIBILITY_TIMEOUT};
+use crate::repository::DieselBrokerRepository;
+use crate::work_queue::handle::ClaimId;
+use crate::work_queue::{WorkHandle, WorkQueueError};
+
+/// Broker storing tasks in the database.
+pub struct DbBroker {
+    repo: DieselBrokerRepository,
+}
+
+impl DbBroker {
+    pub fn new(repo: DieselBrokerRepository) -> Self {
+        Self { repo }
+    }
+}
+
+fn message_id(claim_id: ClaimId) -> Result<i64, WorkQueueError> {
+    match claim_id {
+        ClaimId::DbRow(id) => Ok(id),
+        other => Err(WorkQueueError::Other(format!(
+            "Not a local queue claim: {:?}",
+            other
+        ))),
+    }
+}
+
+fn chrono_duration(duration: std::time::Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX)
+}
+
+#[async_trait]
+impl Broker for DbBroker {
+    fn name(&self) -> &'static str {
+        "local database"
+    }
+
+    async fn publish(&self, task: &Task) -> Result<bool, WorkQueueError> {
+        let payload = task.to_payload()?;
+        // Two workers OCRing or summarizing one document is wasted work;
+        // several download tasks for one source are not
+        let unique = task.document_id.is_some();
+        Ok(self.repo.push(task.kind.queue(), &payload, unique).await?)
+    }
+
+    async fn receive(&self, kind: TaskKind) -> Result<Option<WorkHandle<Task>>, WorkQueueError> {
+        let visibility = chrono_duration(VISIBILITY_TIMEOUT);
+        let Some(message) = self.repo.claim(kind.queue(), visibility).await? else {
+            return Ok(None);
+        };
+        let id = message.id as i64;
+        match Task::from_payload(message.payload.as_bytes(), message.attempts as u32) {
+            Ok(task) => Ok(Some(WorkHandle::new(task, ClaimId::DbRow(id)))),
+            Err(e) => {
+                // A payload no worker can read would otherwise be retried forever
+                self.repo.bury(id, &e.to_string()).await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn ack(&self, handle: WorkHandle<Task>) -> Result<(), WorkQueueError> {
+        let (_, claim_id) = handle.consume();
+        self.repo.delete(message_id(claim_id)?).await?;
+        Ok(())
+    }
+
+    async fn nack(
+        &self,
+        handle: WorkHandle<Task>,
+        error: &str,
+        requeue: bool,
+    ) -> Result<(), WorkQueueError> {
+        let (task, claim_id) = handle.consume();
+        let id = message_id(claim_id)?;
+        if requeue && task.attempts < MAX_ATTEMPTS {
+            let delay = chrono_duration(retry_delay(task.attempts));
+            self.repo.release(id, delay, error).await?;
+        } else {
+            self.repo.bury(id, error).await?;
+        }
+        Ok(())
+    }
+
+    async fn depth(&self, kind: TaskKind) -> Result<QueueDepth, WorkQueueError> {
+        Ok(self.repo.depth(kind.queue()).await?)
+    }
+
+    async fn retry_dead(&self, kind: TaskKind) -> Result<u64, WorkQueueError> {
+        Ok(self.repo.revive(kind.queue()).await? as u64)
+    }
+}
@@ -0,0 +1,236 @@
+//! Worker queue broker for distributing tasks across processes.
+//!
+//! Producers publish [`Task`]s (download a source's pending URLs, OCR a
+//! document, summarize a document) and `foia worker` processes receive
+//! them. A received task is held by its worker until acked; a nacked task
+//! is redelivered with backoff until it has been tried [`MAX_ATTEMPTS`]
+//! times, then set aside as dead.
+//!
+//! `broker_url` selects the backend: unset for the local-DB queue, or
+//! `amqp://...` for RabbitMQ (requires the `amqp-broker` feature).
+
+mod db;
+
+#[cfg(feature = "amqp-broker")]
+mod amqp;
+
+pub use crate::repository::diesel_broker::QueueDepth;
+pub use db::DbBroker;
+
+#[cfg(feature = "amqp-broker")]
+pub use amqp::AmqpBroker;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{WorkHandle, WorkQueueError};
+use crate::repository::DieselBrokerRepository;
+
+/// Deliveries before a failing task is set aside as dead.
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// How long a received task is held before another worker may take it.
+pub const VISIBILITY_TIMEOUT: Duration = Duration::from_secs(90 * 60);
+
+/// What a task does, and the queue it goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    /// Download pending URLs from the crawl queue.
+    Download,
+    /// Extract text and OCR one document.
+    Ocr,
+    /// Summarize one document with the LLM.
+    Summarize,
+}
+
+impl TaskKind {
+    pub const ALL: [Self; 3] = [Self::Download, Self::Ocr, Self::Summarize];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Download => "download",
+            Self::Ocr => "ocr",
+            Self::Summarize => "summarize",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "download" => Some(Self::Download),
+            "ocr" => Some(Self::Ocr),
+            "summarize" => Some(Self::Summarize),
+            _ => None,
+        }
+    }
+
+    /// Queue name, shared by every backend.
+    pub fn queue(&self) -> &'static str {
+        match self {
+            Self::Download => "foia.download",
+            Self::Ocr => "foia.ocr",
+            Self::Summarize => "foia.summarize",
+        }
+    }
+}
+
+/// A unit of work handed to a worker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub kind: TaskKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<String>,
+    /// Most items a download task handles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Deliveries so far, counting the current one.
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl Task {
+    /// Download up to `limit` pending URLs, from one source or all.
+    pub fn download(source_id: Option<&str>, limit: usize) -> Self {
+        Self {
+            kind: TaskKind::Download,
+            source_id: source_id.map(String::from),
+            document_id: None,
+            limit: Some(limit),
+            attempts: 0,
+        }
+    }
+
+    /// Extract text from and OCR a document.
+    pub fn ocr(document_id: &str) -> Self {
+        Self::for_document(TaskKind::Ocr, document_id)
+    }
+
+    /// Summarize a document.
+    pub fn summarize(document_id: &str) -> Self {
+        Self::for_document(TaskKind::Summarize, document_id)
+    }
+
+    fn for_document(kind: TaskKind, document_id: &str) -> Self {
+        Self {
+            kind,
+            source_id: None,
+            document_id: Some(document_id.to_string()),
+            limit: None,
+            attempts: 0,
+        }
+    }
+
+    /// Serialize for publishing, without the delivery count.
+    pub(crate) fn to_payload(&self) -> Result<String, WorkQueueError> {
+        let task = Self {
+            attempts: 0,
+            ..self.clone()
+        };
+        serde_json::to_string(&task).map_err(|e| WorkQueueError::Other(e.to_string()))
+    }
+
+    pub(crate) fn from_payload(payload: &[u8], attempts: u32) -> Result<Self, WorkQueueError> {
+        let mut task: Self = serde_json::from_slice(payload)
+            .map_err(|e| WorkQueueError::Other(format!("Malformed task: {}", e)))?;
+        task.attempts = attempts;
+        Ok(task)
+    }
+}
+
+/// Delay before redelivering a task that failed on attempt `attempts`.
+pub fn retry_delay(attempts: u32) -> Duration {
+    let secs = 30u64.saturating_mul(1 << attempts.saturating_sub(1).min(7));
+    Duration::from_secs(secs.min(3600))
+}
+
+/// A queue of tasks shared by producers and worker processes.
+#[async_trait]
+pub trait Broker: Send + Sync {
+    /// Backend name for display.
+    fn name(&self) -> &'static str;
+
+    /// Publish a task. Returns false if the backend dropped it as a
+    /// duplicate of a task for the same document already waiting.
+    async fn publish(&self, task: &Task) -> Result<bool, WorkQueueError>;
+
+    /// Take the next task of `kind`, if any is available.
+    async fn receive(&self, kind: TaskKind) -> Result<Option<WorkHandle<Task>>, WorkQueueError>;
+
+    /// Acknowledge a task as done, removing it from the queue.
+    async fn ack(&self, handle: WorkHandle<Task>) -> Result<(), WorkQueueError>;
+
+    /// Reject a task. With `requeue` it is redelivered after
+    /// [`retry_delay`] unless it has used up [`MAX_ATTEMPTS`]; otherwise
+    /// it is set aside as dead.
+    async fn nack(
+        &self,
+        handle: WorkHandle<Task>,
+        error: &str,
+        requeue: bool,
+    ) -> Result<(), WorkQueueError>;
+
+    /// Count the tasks of `kind` by state.
+    async fn depth(&self, kind: TaskKind) -> Result<QueueDepth, WorkQueueError>;
+
+    /// Requeue dead tasks of `kind`, returning how many.
+    async fn retry_dead(&self, kind: TaskKind) -> Result<u64, WorkQueueError>;
+}
+
+/// Connect to the broker named by `broker_url`: the local DB when unset,
+/// or RabbitMQ for `amqp://` and `amqps://` URLs.
+pub async fn connect(
+    broker_url: Option<&str>,
+    repo: DieselBrokerRepository,
+) -> Result<Box<dyn Broker>, WorkQueueError> {
+    match broker_url.filter(|url| !url.is_empty()) {
+        None => Ok(Box::new(DbBroker::new(repo))),
+        Some(url) if url.starts_with("amqp://") || url.starts_with("amqps://") => {
+            #[cfg(feature = "amqp-broker")]
+            {
+                Ok(Box::new(AmqpBroker::connect(url).await?))
+            }
+            #[cfg(not(feature = "amqp-broker"))]
+            {
+                Err(WorkQueueError::Connection(
+                    "broker_url is an AMQP URL but this build lacks the amqp-broker feature"
+                        .to_string(),
+                ))
+            }
+        }
+        Some(url) => Err(WorkQueueError::Connection(format!(
+            "Unsupported broker_url scheme: {}",
+            url.split("://").next().unwrap_or(url)
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_drops_attempts() {
+        let mut task = Task::ocr("doc-1");
+        task.attempts = 3;
+        let payload = task.to_payload().unwrap();
+        assert_eq!(
+            payload,
+            r#"{"kind":"ocr","document_id":"doc-1","attempts":0}"#
+        );
+        let received = Task::from_payload(payload.as_bytes(), 4).unwrap();
+        assert_eq!(received.document_id.as_deref(), Some("doc-1"));
+        assert_eq!(received.attempts, 4);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), Duration::from_secs(30));
+        assert_eq!(retry_delay(2), Duration::from_secs(60));
+        assert_eq!(retry_delay(4), Duration::from_secs(240));
+        assert_eq!(retry_delay(20), Duration::from_secs(3600));
+    }
+}
@@ -87,8 +87,8 @@ impl WorkQueue for DbAnalysisQueue {
         Ok(())
     }
 
-    /// No-op: the pending claim row expires after 90 minutes. The analysis
-    /// pipeline records the error via `store_analysis_result_for_document`.
+    /// No-op: the pending claim row expires once its lease passes. The
+    /// analysis pipeline records the error via `store_analysis_result_for_document`.
     async fn fail(
         &self,
         handle: WorkHandle<Document>,
@@ -108,7 +108,7 @@ impl WorkQueue for DbAnnotationQueue {
         Ok(())
     }
 
-    /// No-op: the pending claim row expires after 90 minutes.
+    /// No-op: the pending claim row expires once its lease passes.
     async fn fail(
         &self,
         handle: WorkHandle<Document>,
@@ -3,8 +3,8 @@
 /// Opaque claim identifier used by queue backends to track ownership.
 #[derive(Debug)]
 pub(crate) enum ClaimId {
-    /// Row ID in `document_analysis_results` (pending claim row).
-    #[allow(dead_code)]
+    /// Row ID in `document_analysis_results` (pending claim row), or in
+    /// `broker_messages` for the local-DB broker.
     DbRow(i64),
     /// Pending claim that needs explicit cleanup.
     /// Stores (document_id, version_id, analysis_type) for `delete_pending_claim`.
@@ -13,8 +13,8 @@ pub(crate) enum ClaimId {
         version_id: i32,
         analysis_type: String,
     },
-    /// Message delivery tag (for future AMQP backends).
-    #[allow(dead_code)]
+    /// AMQP message delivery tag.
+    #[cfg_attr(not(feature = "amqp-broker"), allow(dead_code))]
     DeliveryTag(u64),
     /// No external identifier (claim uses upsert with no returned ID).
     None,
@@ -22,8 +22,8 @@ pub(crate) enum ClaimId {
 
 /// A claimed work item. Move semantics: consumed by `complete()` or `fail()`.
 ///
-/// If dropped without being consumed, logs a warning. The DB's 90-minute
-/// claim expiry is the real safety net — the warning is for debugging only.
+/// If dropped without being consumed, logs a warning. The DB's claim lease
+/// is the real safety net — the warning is for debugging only.
 pub struct WorkHandle<T: Send + Sync> {
     pub item: T,
     pub(crate) claim_id: ClaimId,
@@ -58,7 +58,7 @@ impl<T: Send + Sync> Drop for WorkHandle<T> {
         if !self.consumed {
             tracing::warn!(
                 "WorkHandle dropped without being completed or failed — \
-                 claim will expire after 90 minutes"
+                 claim will expire once its lease passes"
             );
         }
     }
@@ -0,0 +1,56 @@
+//! Claim heartbeat — keeps an analysis claim alive during long work.
+
+use tokio::task::JoinHandle;
+
+use crate::repository::DieselDocumentRepository;
+
+/// Renews a pending analysis claim until dropped.
+///
+/// Renewals happen three times per lease, so a slow database or a missed
+/// tick does not let the claim lapse while its worker is alive. Once the
+/// worker dies the renewals stop and the claim expires a lease later.
+pub struct ClaimHeartbeat {
+    task: JoinHandle<()>,
+}
+
+impl ClaimHeartbeat {
+    /// Start renewing the claim on `document_id`/`version_id` for
+    /// `analysis_type`. Must be called inside a Tokio runtime.
+    pub fn start(
+        repo: DieselDocumentRepository,
+        document_id: &str,
+        version_id: i32,
+        analysis_type: &str,
+    ) -> Self {
+        let period = (repo.claim_lease() / 3)
+            .to_std()
+            .unwrap_or(std::time::Duration::from_secs(60));
+        let (document_id, analysis_type) = (document_id.to_string(), analysis_type.to_string());
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(period).await;
+                match repo
+                    .renew_analysis_claim(&document_id, version_id, &analysis_type)
+                    .await
+                {
+                    Ok(true) => {}
+                    // Finished or released; nothing left to hold
+                    Ok(false) => return,
+                    Err(e) => tracing::warn!(
+                        "Failed to renew {} claim on {}: {}",
+                        analysis_type,
+                        document_id,
+                        e
+                    ),
+                }
+            }
+        });
+        Self { task }
+    }
+}
+
+impl Drop for ClaimHeartbeat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -2,11 +2,14 @@
 //!
 //! Both analysis and annotation pipelines follow the same pattern:
 //! discover work, fetch a batch, claim each item, process, record outcome.
-//! This module provides a shared trait so pipelines can be backend-agnostic
-//! (DB polling today, message queues in the future).
+//! This module provides a shared trait so pipelines can be backend-agnostic.
+//! The [`broker`] hands discrete tasks (download, OCR, summarize) to worker
+//! processes through the local DB or RabbitMQ.
 
+pub mod broker;
 mod error;
 mod handle;
+mod heartbeat;
 pub mod pipeline;
 pub mod runner;
 
@@ -15,6 +18,7 @@ pub mod db_annotation;
 
 pub use error::WorkQueueError;
 pub use handle::WorkHandle;
+pub use heartbeat::ClaimHeartbeat;
 pub use pipeline::{ChunkResult, ExecutionStrategy, PipelineError, PipelineEvent, PipelineStage};
 pub use runner::PipelineRunner;
 
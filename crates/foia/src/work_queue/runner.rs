@@ -2,12 +2,16 @@
 //!
 //! **Wide mode**: all chunks through stage N, then stage N+1 (sequential phases).
 //! **Deep mode**: interleave stages per chunk; deferred stages run as concurrent consumers.
+//!
+//! On a shutdown request no further chunks are started; stages stop taking
+//! items within a chunk, so completion events still report what was done.
 
 use std::time::Duration;
 
 use tokio::sync::{mpsc, watch};
 
 use super::pipeline::{ExecutionStrategy, PipelineError, PipelineEvent, PipelineStage};
+use crate::shutdown;
 
 /// Drives pipeline stages through their work using a configurable execution strategy.
 pub struct PipelineRunner {
@@ -73,6 +77,9 @@ impl PipelineRunner {
         let mut processed = 0usize;
 
         loop {
+            if shutdown::is_requested() {
+                break;
+            }
             let remaining_limit = if self.limit > 0 {
                 let left = self.limit.saturating_sub(processed);
                 if left == 0 {
@@ -169,6 +176,9 @@ impl PipelineRunner {
         let mut s2_skipped = 0usize;
 
         loop {
+            if shutdown::is_requested() {
+                break;
+            }
             let remaining_limit = if self.limit > 0 {
                 let left = self.limit.saturating_sub(processed);
                 if left == 0 {
@@ -227,6 +237,9 @@ impl PipelineRunner {
         }
 
         loop {
+            if shutdown::is_requested() {
+                break;
+            }
             let count = stage2.count().await?;
             if count == 0 {
                 break;
@@ -288,6 +301,9 @@ impl PipelineRunner {
         let mut s2_skipped = 0usize;
 
         loop {
+            if shutdown::is_requested() {
+                break;
+            }
             let remaining_limit = if self.limit > 0 {
                 let left = self.limit.saturating_sub(processed);
                 if left == 0 {
@@ -351,6 +367,9 @@ impl PipelineRunner {
         }
 
         loop {
+            if shutdown::is_requested() {
+                break;
+            }
             let count = stage2.count().await?;
             if count == 0 {
                 // Small sleep to allow deferred API calls to complete
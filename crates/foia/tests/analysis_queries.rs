@@ -444,3 +444,93 @@ async fn completion_overwrites_pending_claim() {
         .unwrap();
     assert_eq!(count, 0, "Completed document should not need analysis");
 }
+
+#[tokio::test]
+async fn renew_analysis_claim_only_renews_pending_claims() {
+    let (repo, _dir) = setup_test_db().await;
+    create_test_doc(&repo, "doc-001", "test", "application/pdf").await;
+
+    let doc = repo.get("doc-001").await.unwrap().unwrap();
+    let version_id = doc.current_version().unwrap().id as i32;
+
+    // Nothing to renew before the claim exists
+    assert!(!repo
+        .renew_analysis_claim("doc-001", version_id, "ocr")
+        .await
+        .unwrap());
+
+    repo.claim_analysis("doc-001", version_id, "ocr")
+        .await
+        .unwrap();
+    assert!(repo
+        .renew_analysis_claim("doc-001", version_id, "ocr")
+        .await
+        .unwrap());
+
+    repo.store_analysis_result_for_document(
+        "doc-001",
+        version_id,
+        "ocr",
+        "tesseract",
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    // A finished claim must not come back as pending
+    assert!(!repo
+        .renew_analysis_claim("doc-001", version_id, "ocr")
+        .await
+        .unwrap());
+    assert_eq!(repo.count_pending_analysis("ocr").await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn release_analysis_claims_returns_documents_to_pool() {
+    let (repo, _dir) = setup_test_db().await;
+    create_test_doc(&repo, "doc-001", "source-a", "application/pdf").await;
+    create_test_doc(&repo, "doc-002", "source-b", "application/pdf").await;
+
+    for id in ["doc-001", "doc-002"] {
+        let doc = repo.get(id).await.unwrap().unwrap();
+        let version_id = doc.current_version().unwrap().id as i32;
+        repo.claim_analysis(id, version_id, "ocr").await.unwrap();
+    }
+    assert_eq!(
+        repo.count_needing_analysis("ocr", None, None, 12)
+            .await
+            .unwrap(),
+        0
+    );
+
+    // Fresh claims are within their lease
+    let released = repo
+        .release_analysis_claims(None, None, true)
+        .await
+        .unwrap();
+    assert_eq!(released, 0);
+
+    let released = repo
+        .release_analysis_claims(Some("tables"), None, false)
+        .await
+        .unwrap();
+    assert_eq!(released, 0, "Other analysis types are untouched");
+
+    let released = repo
+        .release_analysis_claims(Some("ocr"), Some("source-a"), false)
+        .await
+        .unwrap();
+    assert_eq!(released, 1);
+
+    let docs = repo
+        .get_needing_analysis("ocr", 10, None, None, None, 12)
+        .await
+        .unwrap();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].id, "doc-001");
+}
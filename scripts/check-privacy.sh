@@ -129,7 +129,7 @@ echo ""
 # 7. Run clippy to catch disallowed methods
 if [ "$SKIP_CLIPPY" = false ]; then
     echo "7. Running clippy for disallowed methods..."
-    if cargo clippy --features "${SAFE_FEATURES:-browser,postgres,redis-backend,amqp-broker,ocr-ocrs,ocr-paddle,gis}" -- -D clippy::disallowed-methods 2>&1 | grep "error.*disallowed" > /tmp/clippy_violations.txt; then
+    if cargo clippy --features "${SAFE_FEATURES:-browser,postgres,redis-backend,amqp-broker,parquet-export,ocr-ocrs,ocr-paddle,gis}" -- -D clippy::disallowed-methods 2>&1 | grep "error.*disallowed" > /tmp/clippy_violations.txt; then
         echo -e "${RED}❌ Clippy found disallowed method usage:${NC}"
         cat /tmp/clippy_violations.txt
         VIOLATIONS=$((VIOLATIONS + 1))